@@ -10,6 +10,23 @@ struct timeval {
     suseconds_t tv_usec;    /* microseconds */
 };
 
+struct statfs {
+    long f_type;                    /* type of file system */
+    long f_bsize;                   /* optimal transfer block size */
+    unsigned long f_blocks;         /* total data blocks in file system */
+    unsigned long f_bfree;          /* free blocks in fs */
+    unsigned long f_bavail;         /* free blocks available to unprivileged user */
+    unsigned long f_files;          /* total file nodes in file system */
+    unsigned long f_ffree;          /* free file nodes in fs */
+    struct {
+        int __val[2];
+    } f_fsid;                       /* file system id */
+    long f_namelen;                 /* maximum length of filenames */
+    long f_frsize;                  /* fragment size */
+    long f_flags;                   /* mount flags of file system */
+    long f_spare[4];
+};
+
 struct occlum_stdio_fds {
     int stdin_fd;
     int stdout_fd;
@@ -710,6 +710,7 @@
 #define __NR_userfaultfd 323
 #define __NR_membarrier 324
 #define __NR_mlock2 325
+#define __NR_copy_file_range 326
 
 #define __NR_spawn 360
 
@@ -4,8 +4,10 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::ffi::CString;
 use std::io::Read;
+use std::net::Ipv4Addr;
 use std::path::{Path, PathBuf};
 use std::sgxfs::SgxFile;
+use std::time::Duration;
 
 lazy_static! {
     pub static ref LIBOS_CONFIG: Config = {
@@ -88,11 +90,17 @@ pub struct Config {
     pub env: ConfigEnv,
     pub entry_points: Vec<PathBuf>,
     pub mount: Vec<ConfigMount>,
+    pub network: ConfigNetwork,
+    pub fs: ConfigFs,
 }
 
 #[derive(Debug)]
 pub struct ConfigResourceLimits {
     pub user_space_size: usize,
+    pub cpu_set: Option<Vec<usize>>,
+    pub pin_cpu_affinity: bool,
+    pub io_threads: usize,
+    pub page_cache_size: usize,
 }
 
 #[derive(Debug)]
@@ -100,6 +108,9 @@ pub struct ConfigProcess {
     pub default_stack_size: usize,
     pub default_heap_size: usize,
     pub default_mmap_size: usize,
+    pub default_max_open_files: u64,
+    pub default_max_processes: u64,
+    pub enable_aslr: bool,
 }
 
 #[derive(Debug)]
@@ -108,6 +119,97 @@ pub struct ConfigEnv {
     pub untrusted: HashSet<String>,
 }
 
+#[derive(Debug)]
+pub struct ConfigFs {
+    // The interval to write back the dirty data of the file systems, or None
+    // if the dirty data is only written back on memory pressure or on sync
+    pub writeback_interval: Option<Duration>,
+    // The file systems that can be mounted by LibOS processes at runtime
+    pub runtime_mount: ConfigRuntimeMount,
+}
+
+#[derive(Debug)]
+pub struct ConfigRuntimeMount {
+    // The types of the file systems that can be mounted, i.e., "ramfs",
+    // "tmpfs", "hostfs" and "bind" for bind mounts. Runtime mounts are
+    // disabled if it is empty.
+    pub types: Vec<String>,
+    // The host directories that can be mounted as HostFS, including their
+    // subdirectories
+    pub hostfs_sources: Vec<PathBuf>,
+}
+
+#[derive(Debug)]
+pub struct ConfigNetwork {
+    // The names of the host interfaces visible to LibOS processes, or None if
+    // all of them are visible
+    pub interfaces: Option<Vec<String>>,
+    // Whether raw sockets and packet sockets can be created
+    pub raw_sockets: bool,
+    // Whether ICMP datagram sockets, i.e., ping sockets, can be created
+    pub ping_sockets: bool,
+    // The interfaces of the in-enclave TCP/IP stack
+    pub enclave_interfaces: Vec<ConfigEnclaveInterface>,
+    // The TCP ports whose connections are wrapped in TLS by LibOS
+    pub tls: Vec<ConfigTls>,
+    // The resolver of LibOS, which answers the DNS queries inside the enclave
+    pub dns: Option<ConfigDns>,
+    // The policies of the socket options by their names, e.g., TCP_NODELAY.
+    // The options not given are allowed.
+    pub socket_options: HashMap<String, ConfigSockOptPolicy>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigSockOptPolicy {
+    Allow,
+    Deny,
+}
+
+#[derive(Debug)]
+pub struct ConfigDns {
+    // The upstream server that the queries are forwarded to
+    pub upstream: Ipv4Addr,
+    // The port of the upstream server, or the default port of DNS (53) or
+    // DNS over TLS (853) if None
+    pub port: Option<u16>,
+    // DNS over TLS to the upstream server, or plain UDP if None
+    pub tls: Option<ConfigDnsTls>,
+    // The search domains in resolv.conf
+    pub search: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ConfigDnsTls {
+    pub server_name: String,
+    // The path of the PEM file of the CA in the file system of LibOS
+    pub ca: String,
+}
+
+#[derive(Debug)]
+pub struct ConfigTls {
+    // The remote port of the outgoing connections or the local port of the
+    // incoming connections
+    pub port: u16,
+    pub role: ConfigTlsRole,
+}
+
+// The paths of the PEM files are in the file system of LibOS
+#[derive(Debug)]
+pub enum ConfigTlsRole {
+    Client { ca: String, server_name: String },
+    Server { cert: String, key: String },
+}
+
+#[derive(Debug)]
+pub struct ConfigEnclaveInterface {
+    // The TAP device of the host, over which the frames are sent and received
+    pub tap: String,
+    pub mac: [u8; 6],
+    // The IPv4 addresses and their prefix lengths
+    pub addrs: Vec<(Ipv4Addr, u8)>,
+    pub gateway: Option<Ipv4Addr>,
+}
+
 #[derive(Debug)]
 pub struct ConfigMount {
     pub type_: ConfigMountFsType,
@@ -125,6 +227,7 @@ pub enum ConfigMountFsType {
     TYPE_UNIONFS,
     TYPE_DEVFS,
     TYPE_PROCFS,
+    TYPE_SYSFS,
 }
 
 #[derive(Debug)]
@@ -132,6 +235,17 @@ pub struct ConfigMountOptions {
     pub mac: Option<sgx_aes_gcm_128bit_tag_t>,
     pub layers: Option<Vec<ConfigMount>>,
     pub temporary: bool,
+    pub integrity_only: bool,
+    pub role: Option<ConfigLayerRole>,
+}
+
+// The role of a layer of the root UnionFS
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ConfigLayerRole {
+    // A read-only image SEFS, which is given the MAC
+    Image,
+    // The writable container SEFS
+    Container,
 }
 
 impl Config {
@@ -157,12 +271,16 @@ impl Config {
             }
             mount
         };
+        let network = ConfigNetwork::from_input(&input.network)?;
+        let fs = ConfigFs::from_input(&input.fs)?;
         Ok(Config {
             resource_limits,
             process,
             env,
             entry_points,
             mount,
+            network,
+            fs,
         })
     }
 }
@@ -170,7 +288,21 @@ impl Config {
 impl ConfigResourceLimits {
     fn from_input(input: &InputConfigResourceLimits) -> Result<ConfigResourceLimits> {
         let user_space_size = parse_memory_size(&input.user_space_size)?;
-        Ok(ConfigResourceLimits { user_space_size })
+        let cpu_set = match input.cpu_set.as_ref() {
+            Some(cpu_list) => Some(parse_cpu_list(cpu_list)?),
+            None => None,
+        };
+        let page_cache_size = match input.page_cache_size.as_ref() {
+            Some(page_cache_size) => parse_memory_size(page_cache_size)?,
+            None => 0,
+        };
+        Ok(ConfigResourceLimits {
+            user_space_size,
+            cpu_set,
+            pin_cpu_affinity: input.pin_cpu_affinity,
+            io_threads: input.io_threads,
+            page_cache_size,
+        })
     }
 }
 
@@ -183,6 +315,9 @@ impl ConfigProcess {
             default_stack_size,
             default_heap_size,
             default_mmap_size,
+            default_max_open_files: input.default_max_open_files,
+            default_max_processes: input.default_max_processes,
+            enable_aslr: input.enable_aslr,
         })
     }
 }
@@ -196,9 +331,188 @@ impl ConfigEnv {
     }
 }
 
+impl ConfigFs {
+    fn from_input(input: &InputConfigFs) -> Result<ConfigFs> {
+        let writeback_interval = match input.writeback_interval {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        };
+        let runtime_mount = ConfigRuntimeMount::from_input(&input.runtime_mount)?;
+        Ok(ConfigFs {
+            writeback_interval,
+            runtime_mount,
+        })
+    }
+}
+
+impl ConfigRuntimeMount {
+    fn from_input(input: &InputConfigRuntimeMount) -> Result<ConfigRuntimeMount> {
+        const ALL_RUNTIME_FS_TYPES: [&str; 4] = ["ramfs", "tmpfs", "hostfs", "bind"];
+
+        for type_ in &input.types {
+            if !ALL_RUNTIME_FS_TYPES.contains(&type_.as_str()) {
+                return_errno!(EINVAL, "Unsupported file system type for runtime mounts");
+            }
+        }
+        let hostfs_sources = input
+            .hostfs_sources
+            .iter()
+            .map(|source| {
+                let path = unsafe { PathBuf::from(&INSTANCE_DIR) }.join(source);
+                path.canonicalize().unwrap_or(path)
+            })
+            .collect();
+        Ok(ConfigRuntimeMount {
+            types: input.types.clone(),
+            hostfs_sources,
+        })
+    }
+}
+
+impl ConfigNetwork {
+    fn from_input(input: &InputConfigNetwork) -> Result<ConfigNetwork> {
+        Ok(ConfigNetwork {
+            interfaces: input.interfaces.clone(),
+            raw_sockets: input.raw_sockets,
+            ping_sockets: input.ping_sockets,
+            enclave_interfaces: input
+                .enclave_interfaces
+                .iter()
+                .map(|interface| ConfigEnclaveInterface::from_input(interface))
+                .collect::<Result<Vec<_>>>()?,
+            tls: input
+                .tls
+                .iter()
+                .map(|tls| ConfigTls::from_input(tls))
+                .collect::<Result<Vec<_>>>()?,
+            dns: match &input.dns {
+                Some(dns) => Some(ConfigDns::from_input(dns)?),
+                None => None,
+            },
+            socket_options: input
+                .socket_options
+                .iter()
+                .map(|(name, policy)| {
+                    if !crate::net::SockOpt::is_known_name(name) {
+                        return_errno!(EINVAL, "Unknown socket option");
+                    }
+                    let policy = match policy.as_str() {
+                        "allow" => ConfigSockOptPolicy::Allow,
+                        "deny" => ConfigSockOptPolicy::Deny,
+                        _ => return_errno!(EINVAL, "Unsupported policy of socket option"),
+                    };
+                    Ok((name.clone(), policy))
+                })
+                .collect::<Result<HashMap<_, _>>>()?,
+        })
+    }
+}
+
+impl ConfigEnclaveInterface {
+    fn from_input(input: &InputConfigEnclaveInterface) -> Result<ConfigEnclaveInterface> {
+        let mac = {
+            let bytes: Vec<&str> = input.mac.split(':').collect();
+            if bytes.len() != 6 {
+                return_errno!(EINVAL, "The format of MAC address is invalid");
+            }
+            let mut mac = [0; 6];
+            for (byte, byte_str) in mac.iter_mut().zip(bytes.iter()) {
+                *byte = u8::from_str_radix(byte_str, 16).map_err(|e| errno!(e))?;
+            }
+            mac
+        };
+        let addrs = input
+            .addrs
+            .iter()
+            .map(|addr| {
+                let mut parts = addr.splitn(2, '/');
+                let ip = parse_ipv4_addr(parts.next().unwrap())?;
+                let prefixlen = match parts.next() {
+                    Some(prefixlen) => prefixlen.parse::<u8>().map_err(|e| errno!(e))?,
+                    None => 32,
+                };
+                if prefixlen > 32 {
+                    return_errno!(EINVAL, "The prefix length is invalid");
+                }
+                Ok((ip, prefixlen))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if addrs.is_empty() {
+            return_errno!(EINVAL, "The interface has no address");
+        }
+        let gateway = match &input.gateway {
+            Some(gateway) => Some(parse_ipv4_addr(gateway)?),
+            None => None,
+        };
+        Ok(ConfigEnclaveInterface {
+            tap: input.tap.clone(),
+            mac,
+            addrs,
+            gateway,
+        })
+    }
+}
+
+impl ConfigTls {
+    fn from_input(input: &InputConfigTls) -> Result<ConfigTls> {
+        let role = match input.role.as_str() {
+            "client" => ConfigTlsRole::Client {
+                ca: input
+                    .ca
+                    .clone()
+                    .ok_or_else(|| errno!(EINVAL, "The CA of TLS client is not given"))?,
+                server_name: input
+                    .server_name
+                    .clone()
+                    .ok_or_else(|| errno!(EINVAL, "The server name of TLS client is not given"))?,
+            },
+            "server" => ConfigTlsRole::Server {
+                cert: input
+                    .cert
+                    .clone()
+                    .ok_or_else(|| errno!(EINVAL, "The cert of TLS server is not given"))?,
+                key: input
+                    .key
+                    .clone()
+                    .ok_or_else(|| errno!(EINVAL, "The key of TLS server is not given"))?,
+            },
+            _ => return_errno!(EINVAL, "Unsupported TLS role"),
+        };
+        if input.port == 0 {
+            return_errno!(EINVAL, "The port of TLS is invalid");
+        }
+        Ok(ConfigTls {
+            port: input.port,
+            role,
+        })
+    }
+}
+
+impl ConfigDns {
+    fn from_input(input: &InputConfigDns) -> Result<ConfigDns> {
+        let tls = input.tls.as_ref().map(|tls| ConfigDnsTls {
+            server_name: tls.server_name.clone(),
+            ca: tls.ca.clone(),
+        });
+        Ok(ConfigDns {
+            upstream: parse_ipv4_addr(&input.upstream)?,
+            port: input.port,
+            tls,
+            search: input.search.clone(),
+        })
+    }
+}
+
+fn parse_ipv4_addr(addr: &str) -> Result<Ipv4Addr> {
+    addr.parse::<Ipv4Addr>()
+        .map_err(|_| errno!(EINVAL, "The format of IPv4 address is invalid"))
+}
+
 impl ConfigMount {
     fn from_input(input: &InputConfigMount) -> Result<ConfigMount> {
-        const ALL_FS_TYPES: [&str; 6] = ["sefs", "hostfs", "ramfs", "unionfs", "devfs", "procfs"];
+        const ALL_FS_TYPES: [&str; 7] = [
+            "sefs", "hostfs", "ramfs", "unionfs", "devfs", "procfs", "sysfs",
+        ];
 
         let type_ = match input.type_.as_str() {
             "sefs" => ConfigMountFsType::TYPE_SEFS,
@@ -207,6 +521,7 @@ impl ConfigMount {
             "unionfs" => ConfigMountFsType::TYPE_UNIONFS,
             "devfs" => ConfigMountFsType::TYPE_DEVFS,
             "procfs" => ConfigMountFsType::TYPE_PROCFS,
+            "sysfs" => ConfigMountFsType::TYPE_SYSFS,
             _ => {
                 return_errno!(EINVAL, "Unsupported file system type");
             }
@@ -245,16 +560,24 @@ impl ConfigMountOptions {
         let layers = if let Some(layers) = &input.layers {
             let layers = layers
                 .iter()
-                .map(|config| ConfigMount::from_input(config).expect("invalid mount config"))
-                .collect();
+                .map(|config| ConfigMount::from_input(config))
+                .collect::<Result<Vec<ConfigMount>>>()?;
             Some(layers)
         } else {
             None
         };
+        let role = match input.role.as_deref() {
+            Some("image") => Some(ConfigLayerRole::Image),
+            Some("container") => Some(ConfigLayerRole::Container),
+            Some(_) => return_errno!(EINVAL, "Unsupported layer role"),
+            None => None,
+        };
         Ok(ConfigMountOptions {
             mac,
             layers,
             temporary: input.temporary,
+            integrity_only: input.integrity_only,
+            role,
         })
     }
 }
@@ -284,6 +607,31 @@ fn parse_memory_size(mem_str: &str) -> Result<usize> {
     Ok(number * factor)
 }
 
+// Parse a list of CPU cores in the format of, e.g., "0-3,6"
+fn parse_cpu_list(cpu_list: &str) -> Result<Vec<usize>> {
+    let parse_cpu = |cpu_str: &str| -> Result<usize> {
+        cpu_str
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| errno!(EINVAL, "invalid CPU in the CPU list"))
+    };
+
+    let mut cpus = Vec::new();
+    for range_str in cpu_list.split(',') {
+        let mut range_iter = range_str.splitn(2, '-');
+        let first = parse_cpu(range_iter.next().unwrap())?;
+        let last = match range_iter.next() {
+            Some(last_str) => parse_cpu(last_str)?,
+            None => first,
+        };
+        if first > last {
+            return_errno!(EINVAL, "invalid CPU range in the CPU list");
+        }
+        cpus.extend(first..=last);
+    }
+    Ok(cpus)
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(deny_unknown_fields)]
 struct InputConfig {
@@ -297,6 +645,10 @@ struct InputConfig {
     pub entry_points: Vec<String>,
     #[serde(default)]
     pub mount: Vec<InputConfigMount>,
+    #[serde(default)]
+    pub network: InputConfigNetwork,
+    #[serde(default)]
+    pub fs: InputConfigFs,
 }
 
 #[derive(Deserialize, Debug)]
@@ -304,18 +656,34 @@ struct InputConfig {
 struct InputConfigResourceLimits {
     #[serde(default = "InputConfigResourceLimits::get_user_space_size")]
     pub user_space_size: String,
+    #[serde(default)]
+    pub cpu_set: Option<String>,
+    #[serde(default = "InputConfigResourceLimits::get_pin_cpu_affinity")]
+    pub pin_cpu_affinity: bool,
+    #[serde(default)]
+    pub io_threads: usize,
+    #[serde(default)]
+    pub page_cache_size: Option<String>,
 }
 
 impl InputConfigResourceLimits {
     fn get_user_space_size() -> String {
         "128MB".to_string()
     }
+
+    fn get_pin_cpu_affinity() -> bool {
+        true
+    }
 }
 
 impl Default for InputConfigResourceLimits {
     fn default() -> InputConfigResourceLimits {
         InputConfigResourceLimits {
             user_space_size: InputConfigResourceLimits::get_user_space_size(),
+            cpu_set: None,
+            pin_cpu_affinity: InputConfigResourceLimits::get_pin_cpu_affinity(),
+            io_threads: 0,
+            page_cache_size: None,
         }
     }
 }
@@ -328,6 +696,12 @@ struct InputConfigProcess {
     pub default_heap_size: String,
     #[serde(default = "InputConfigProcess::get_default_mmap_size")]
     pub default_mmap_size: String,
+    #[serde(default = "InputConfigProcess::get_default_max_open_files")]
+    pub default_max_open_files: u64,
+    #[serde(default = "InputConfigProcess::get_default_max_processes")]
+    pub default_max_processes: u64,
+    #[serde(default = "InputConfigProcess::get_enable_aslr")]
+    pub enable_aslr: bool,
 }
 
 impl InputConfigProcess {
@@ -342,6 +716,18 @@ impl InputConfigProcess {
     fn get_default_mmap_size() -> String {
         "32MB".to_string()
     }
+
+    fn get_default_max_open_files() -> u64 {
+        1024
+    }
+
+    fn get_default_max_processes() -> u64 {
+        u64::max_value()
+    }
+
+    fn get_enable_aslr() -> bool {
+        true
+    }
 }
 
 impl Default for InputConfigProcess {
@@ -350,6 +736,9 @@ impl Default for InputConfigProcess {
             default_stack_size: InputConfigProcess::get_default_stack_size(),
             default_heap_size: InputConfigProcess::get_default_heap_size(),
             default_mmap_size: InputConfigProcess::get_default_mmap_size(),
+            default_max_open_files: InputConfigProcess::get_default_max_open_files(),
+            default_max_processes: InputConfigProcess::get_default_max_processes(),
+            enable_aslr: InputConfigProcess::get_enable_aslr(),
         }
     }
 }
@@ -370,6 +759,102 @@ impl Default for InputConfigEnv {
     }
 }
 
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct InputConfigFs {
+    #[serde(default = "InputConfigFs::get_writeback_interval")]
+    pub writeback_interval: u64,
+    #[serde(default)]
+    pub runtime_mount: InputConfigRuntimeMount,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct InputConfigRuntimeMount {
+    #[serde(default)]
+    pub types: Vec<String>,
+    #[serde(default)]
+    pub hostfs_sources: Vec<String>,
+}
+
+impl InputConfigFs {
+    fn get_writeback_interval() -> u64 {
+        5
+    }
+}
+
+impl Default for InputConfigFs {
+    fn default() -> InputConfigFs {
+        InputConfigFs {
+            writeback_interval: InputConfigFs::get_writeback_interval(),
+            runtime_mount: Default::default(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct InputConfigNetwork {
+    #[serde(default)]
+    pub interfaces: Option<Vec<String>>,
+    #[serde(default)]
+    pub raw_sockets: bool,
+    #[serde(default)]
+    pub ping_sockets: bool,
+    #[serde(default)]
+    pub enclave_interfaces: Vec<InputConfigEnclaveInterface>,
+    #[serde(default)]
+    pub tls: Vec<InputConfigTls>,
+    #[serde(default)]
+    pub dns: Option<InputConfigDns>,
+    #[serde(default)]
+    pub socket_options: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct InputConfigDns {
+    pub upstream: String,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub tls: Option<InputConfigDnsTls>,
+    #[serde(default)]
+    pub search: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct InputConfigDnsTls {
+    pub server_name: String,
+    pub ca: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct InputConfigTls {
+    pub port: u16,
+    pub role: String,
+    #[serde(default)]
+    pub ca: Option<String>,
+    #[serde(default)]
+    pub server_name: Option<String>,
+    #[serde(default)]
+    pub cert: Option<String>,
+    #[serde(default)]
+    pub key: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct InputConfigEnclaveInterface {
+    pub tap: String,
+    pub mac: String,
+    pub addrs: Vec<String>,
+    #[serde(default)]
+    pub gateway: Option<String>,
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(deny_unknown_fields)]
 struct InputConfigMount {
@@ -391,4 +876,8 @@ struct InputConfigMountOptions {
     pub layers: Option<Vec<InputConfigMount>>,
     #[serde(default)]
     pub temporary: bool,
+    #[serde(default)]
+    pub integrity_only: bool,
+    #[serde(default)]
+    pub role: Option<String>,
 }
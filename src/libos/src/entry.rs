@@ -180,6 +180,43 @@ pub extern "C" fn occlum_ecall_broadcast_interrupts() -> i32 {
     .unwrap_or(ecall_errno!(EFAULT))
 }
 
+#[no_mangle]
+pub extern "C" fn occlum_ecall_writeback() -> i32 {
+    if HAS_INIT.load(Ordering::SeqCst) == false {
+        return ecall_errno!(EAGAIN);
+    }
+
+    panic::catch_unwind(|| {
+        backtrace::__rust_begin_short_backtrace(|| match fs::do_writeback() {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("failed to write back: {}", e.backtrace());
+                ecall_errno!(e.errno())
+            }
+        })
+    })
+    .unwrap_or(ecall_errno!(EFAULT))
+}
+
+#[no_mangle]
+pub extern "C" fn occlum_ecall_poll_net() -> i32 {
+    if HAS_INIT.load(Ordering::SeqCst) == false {
+        return ecall_errno!(EAGAIN);
+    }
+
+    panic::catch_unwind(|| {
+        backtrace::__rust_begin_short_backtrace(|| match net::do_poll_net() {
+            Ok(delay) => delay.as_millis() as i32,
+            Err(e) if e.errno() == ENOENT => ecall_errno!(ENOENT),
+            Err(e) => {
+                eprintln!("failed to poll the network: {}", e.backtrace());
+                ecall_errno!(e.errno())
+            }
+        })
+    })
+    .unwrap_or(ecall_errno!(EFAULT))
+}
+
 fn parse_log_level(level_chars: *const c_char) -> Result<LevelFilter> {
     const DEFAULT_LEVEL: LevelFilter = LevelFilter::Off;
 
@@ -82,7 +82,7 @@ impl ToErrno for rcore_fs::vfs::FsError {
             FsError::EntryExist => EEXIST,
             FsError::NotSameFs => EXDEV,
             FsError::InvalidParam => EINVAL,
-            FsError::NoDeviceSpace => ENOMEM,
+            FsError::NoDeviceSpace => ENOSPC,
             FsError::DirRemoved => ENOENT,
             FsError::DirNotEmpty => ENOTEMPTY,
             FsError::WrongFs => EINVAL,
@@ -31,13 +31,9 @@ impl HostEventFd {
     }
 
     pub fn write_u64(&self, val: u64) {
-        unsafe {
-            libc::ocall::write(
-                self.host_fd as c_int,
-                &val as *const _ as *const c_void,
-                std::mem::size_of::<u64>(),
-            );
-        }
+        // Waking up a thread is on the hot path, so the switchless OCall for
+        // batch writing is used even for a single eventfd
+        ocall_eventfd_write_batch(&[self.host_fd], val);
     }
 
     pub fn poll(&self, timeout: Option<&Duration>) -> Result<()> {
@@ -15,6 +15,7 @@ struct Subscriber<E: Event, F: EventFilter<E>> {
     observer: Weak<dyn Observer<E>>,
     filter: Option<F>,
     metadata: Option<Weak<dyn Any + Send + Sync>>,
+    is_exclusive: bool,
 }
 
 impl<E: Event, F: EventFilter<E>> Notifier<E, F> {
@@ -30,12 +31,37 @@ impl<E: Event, F: EventFilter<E>> Notifier<E, F> {
         observer: Weak<dyn Observer<E>>,
         filter: Option<F>,
         metadata: Option<Weak<dyn Any + Send + Sync>>,
+    ) {
+        self.do_register(observer, filter, metadata, false);
+    }
+
+    /// Register an exclusive observer with its interesting events and metadata.
+    ///
+    /// An event is delivered to the exclusive observers one by one until one of
+    /// them takes it (see `Observer::on_exclusive_event`), while the
+    /// non-exclusive observers always receive it.
+    pub fn register_exclusive(
+        &self,
+        observer: Weak<dyn Observer<E>>,
+        filter: Option<F>,
+        metadata: Option<Weak<dyn Any + Send + Sync>>,
+    ) {
+        self.do_register(observer, filter, metadata, true);
+    }
+
+    fn do_register(
+        &self,
+        observer: Weak<dyn Observer<E>>,
+        filter: Option<F>,
+        metadata: Option<Weak<dyn Any + Send + Sync>>,
+        is_exclusive: bool,
     ) {
         let mut subscribers = self.subscribers.lock().unwrap();
         subscribers.push_back(Subscriber {
             observer,
             filter,
             metadata,
+            is_exclusive,
         });
     }
 
@@ -46,22 +72,36 @@ impl<E: Event, F: EventFilter<E>> Notifier<E, F> {
     }
 
     /// Broadcast an event to all registered observers.
+    ///
+    /// Once an exclusive observer takes the event, the remaining exclusive
+    /// observers are skipped, like the exclusive waiters of Linux.
+    ///
+    /// The subscribers whose observers have been freed are removed along the way.
     pub fn broadcast(&self, event: &E) {
-        let subscribers = self.subscribers.lock().unwrap();
-        for subscriber in subscribers.iter() {
-            if let Some(filter) = subscriber.filter.as_ref() {
-                if !filter.filter(event) {
-                    continue;
-                }
+        let mut has_been_taken = false;
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|subscriber| {
+            if subscriber.is_exclusive && has_been_taken {
+                return true;
             }
+
             let observer = match subscriber.observer.upgrade() {
-                // TODO: should remove subscribers whose observers have been freed
-                None => return,
+                None => return false,
                 Some(observer) => observer,
             };
+            if let Some(filter) = subscriber.filter.as_ref() {
+                if !filter.filter(event) {
+                    return true;
+                }
+            }
 
-            observer.on_event(event, &subscriber.metadata);
-        }
+            if subscriber.is_exclusive {
+                has_been_taken = observer.on_exclusive_event(event, &subscriber.metadata);
+            } else {
+                observer.on_event(event, &subscriber.metadata);
+            }
+            true
+        });
     }
 }
 
@@ -17,4 +17,18 @@ pub trait Observer<E: Event>: Send + Sync {
     /// the odds of deadlocks, the `on_event` method should be written short
     /// and sweet.
     fn on_event(&self, event: &E, metadata: &Option<Weak<dyn Any + Send + Sync>>) -> ();
+
+    /// The callback that will be executed when an event is delivered by a
+    /// notifier to this observer, which has registered exclusively.
+    ///
+    /// The return value tells whether the event has been taken by this observer.
+    /// If so, the event is not delivered to the other exclusive observers.
+    fn on_exclusive_event(
+        &self,
+        event: &E,
+        metadata: &Option<Weak<dyn Any + Send + Sync>>,
+    ) -> bool {
+        self.on_event(event, metadata);
+        true
+    }
 }
@@ -4,6 +4,7 @@ use std::time::Duration;
 
 use super::host_event_fd::HostEventFd;
 use crate::prelude::*;
+use crate::signal::has_interrupting_signals;
 
 /// A waiter enables a thread to sleep.
 pub struct Waiter {
@@ -22,6 +23,18 @@ impl Waiter {
         }
     }
 
+    /// Create a waiter for the current thread that sleeps on the given host
+    /// eventfd, instead of the host eventfd of the thread.
+    ///
+    /// Besides wakers, the host eventfd may be written by the host I/O threads
+    /// on the completions of the requests submitted by `IoEngine::submit`,
+    /// which are waited for by the `wait_host_event_mut` method.
+    pub fn with_host_eventfd(host_eventfd: Arc<HostEventFd>) -> Self {
+        Self {
+            inner: Arc::new(Inner::with_host_eventfd(host_eventfd)),
+        }
+    }
+
     /// Return whether a waiter has been waken up.
     ///
     /// Once a waiter is waken up, the `wait` or `wait_mut` method becomes
@@ -60,6 +73,17 @@ impl Waiter {
         self.inner.wait_mut(timeout)
     }
 
+    /// Put the current thread to sleep until being waken up by a waker, or
+    /// until the host eventfd is written by others, e.g., the host I/O threads.
+    ///
+    /// Unlike `wait_mut`, the method may return `Ok(())` without being waken
+    /// up, so the caller must check its condition again. The signals interrupt
+    /// the sleep by interrupting the host syscall of polling, since only the
+    /// host eventfd of the thread is written on signals.
+    pub fn wait_host_event_mut(&self, timeout: Option<&mut Duration>) -> Result<()> {
+        self.inner.wait_host_event_mut(timeout)
+    }
+
     /// Create a waker that can wake up this waiter.
     ///
     /// `WaiterQueue` maintains a list of `Waker` internally to wake up the
@@ -108,8 +132,11 @@ struct Inner {
 
 impl Inner {
     pub fn new() -> Self {
+        Self::with_host_eventfd(current!().host_eventfd().clone())
+    }
+
+    pub fn with_host_eventfd(host_eventfd: Arc<HostEventFd>) -> Self {
         let is_woken = AtomicBool::new(false);
-        let host_eventfd = current!().host_eventfd().clone();
         Self {
             is_woken,
             host_eventfd,
@@ -125,8 +152,11 @@ impl Inner {
     }
 
     pub fn wait(&self, timeout: Option<&Duration>) -> Result<()> {
+        // The signals that are pending before the wait do not wake the host eventfd
+        self.check_interrupted(Ok(()))?;
         while !self.is_woken() {
-            self.host_eventfd.poll(timeout)?;
+            let res = self.host_eventfd.poll(timeout);
+            self.check_interrupted(res)?;
         }
         Ok(())
     }
@@ -145,8 +175,40 @@ impl Inner {
     }
 
     fn do_wait_mut(&self, remain: &mut Option<Duration>) -> Result<()> {
+        self.check_interrupted(Ok(()))?;
         while !self.is_woken() {
-            self.host_eventfd.poll_mut(remain.as_mut())?;
+            let res = self.host_eventfd.poll_mut(remain.as_mut());
+            self.check_interrupted(res)?;
+        }
+        Ok(())
+    }
+
+    pub fn wait_host_event_mut(&self, timeout: Option<&mut Duration>) -> Result<()> {
+        self.check_interrupted(Ok(()))?;
+        if !self.is_woken() {
+            let res = self.host_eventfd.poll_mut(timeout);
+            self.check_interrupted(res)?;
+        }
+        Ok(())
+    }
+
+    /// Check the result of polling the host eventfd.
+    ///
+    /// Besides wakers, the polling returns when the thread is interrupted by
+    /// signals: the host eventfd is written or the host syscall is interrupted.
+    /// The waiter returns EINTR only if there are signals that interrupt the
+    /// thread. Otherwise, the wakeup is spurious and the waiter keeps waiting.
+    fn check_interrupted(&self, poll_result: Result<()>) -> Result<()> {
+        if self.is_woken() {
+            return Ok(());
+        }
+        if let Err(e) = poll_result {
+            if e.errno() != EINTR {
+                return Err(e);
+            }
+        }
+        if has_interrupting_signals(&current!()) {
+            return_errno!(EINTR, "interrupted by signals");
         }
         Ok(())
     }
@@ -262,6 +262,18 @@ pub fn setup_cpuid_info() {
     let max_basic_leaf = CPUID.get_max_basic_leaf();
 }
 
+/// Get the cached CPUID info of the leaf and the subleaf, in the order of
+/// EAX, EBX, ECX and EDX.
+pub fn get_cpuid_info(leaf: u32, subleaf: u32) -> [u32; 4] {
+    let cpuid_result = CPUID.get_cpuid_info(leaf, subleaf);
+    [
+        cpuid_result.eax,
+        cpuid_result.ebx,
+        cpuid_result.ecx,
+        cpuid_result.edx,
+    ]
+}
+
 pub fn handle_cpuid_exception(user_context: &mut CpuContext) -> Result<isize> {
     debug!("handle CPUID exception");
     let leaf = user_context.rax as u32;
@@ -11,6 +11,8 @@ use aligned::{Aligned, A16};
 use core::arch::x86_64::_fxsave;
 use sgx_types::*;
 
+pub use self::cpuid::get_cpuid_info;
+
 // Modules for instruction simulation
 mod cpuid;
 mod rdtsc;
@@ -72,14 +74,34 @@ pub fn do_handle_exception(
     // > If SIGBUS, SIGFPE, SIGILL, or SIGSEGV are generated while they are blocked, the result is
     // undefined, unless the signal was generated by kill(2), sigqueue(3), or raise(3).
     //
-    // As the thread cannot proceed without handling the exception, we choose to force
-    // delivering the signal regardless of the current signal mask.
-    let signal = Box::new(FaultSignal::new(info));
+    // As the thread cannot proceed without handling the exception, we choose to do as Linux:
+    // the signal is delivered to the user-registered handler if there is one and it is not
+    // blocked; otherwise, the process is killed by the signal.
+    if info.exception_vector == sgx_exception_vector_t::SGX_EXCEPTION_VECTOR_PF {
+        report_stack_overflow(info.exinfo.maddr as usize, user_context);
+    }
+    let signal = Box::new(FaultSignal::new(info, unsafe { &*fpregs }));
     crate::signal::force_signal(signal, user_context);
 
     Ok(0)
 }
 
+// A page fault in the guard region below a stack means that the stack
+// overflows, which is reported here since it is hard to diagnose otherwise.
+fn report_stack_overflow(fault_addr: usize, user_context: &CpuContext) {
+    let thread = current!();
+    if let Some(guard_range) = thread.vm().find_stack_guard(fault_addr) {
+        error!(
+            "Stack overflow: tid = {}, rip = {:#x}, rsp = {:#x}, fault address = {:#x} in the stack guard {:?}",
+            thread.tid(),
+            user_context.rip,
+            user_context.rsp,
+            fault_addr,
+            guard_range
+        );
+    }
+}
+
 // Notes about #PF and #GP exception simulation for SGX 1.
 //
 // SGX 1 cannot capture #PF and #GP exceptions inside enclaves. This leaves us
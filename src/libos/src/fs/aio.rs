@@ -0,0 +1,503 @@
+//! The Linux AIO, i.e., io_setup, io_submit, io_getevents, io_cancel and
+//! io_destroy, which is used by libaio.
+//!
+//! The reads and writes of the host files (e.g., host sockets) are submitted
+//! to the host I/O threads of the LibOS async I/O engine, and completed
+//! asynchronously. Their completions are collected when the events are got.
+//! The other requests, e.g., the ones of the LibOS files, are done by the
+//! LibOS at the time they are submitted, as well as the requests that notify
+//! an eventfd, which must be signaled without the events being got. The
+//! results are queued in the context as events until they are got.
+//!
+//! Same as Linux, the ID of a context is the address of a ring in the user
+//! space. The ring is never filled by the LibOS, so its header does not have
+//! the magic and libaio always gets the events by the syscall.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use super::async_host_io::{AsyncHostIo, HostIoOp};
+use super::event_file::AsEvent;
+use super::file_ops;
+use super::*;
+use crate::events::{HostEventFd, Waiter, WaiterQueue};
+use crate::vm::{MMapFlags, VMPerms, PAGE_SIZE};
+use util::mem_util::from_user;
+
+#[allow(non_camel_case_types)]
+pub type aio_context_t = u64;
+
+/// The `struct iocb` of a request.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+#[allow(non_camel_case_types)]
+pub struct iocb_t {
+    pub aio_data: u64,
+    pub aio_key: u32,
+    pub aio_rw_flags: i32,
+    pub aio_lio_opcode: u16,
+    pub aio_reqprio: i16,
+    pub aio_fildes: u32,
+    pub aio_buf: u64,
+    pub aio_nbytes: u64,
+    pub aio_offset: i64,
+    pub aio_reserved2: u64,
+    pub aio_flags: u32,
+    pub aio_resfd: u32,
+}
+
+/// The `struct io_event` of a completed request.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+#[allow(non_camel_case_types)]
+pub struct io_event_t {
+    pub data: u64,
+    pub obj: u64,
+    pub res: i64,
+    pub res2: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(u16)]
+enum IocbCmd {
+    Pread = 0,
+    Pwrite = 1,
+    Fsync = 2,
+    Fdsync = 3,
+    Preadv = 7,
+    Pwritev = 8,
+}
+
+impl IocbCmd {
+    fn from_u16(opcode: u16) -> Result<Self> {
+        let cmd = match opcode {
+            0 => IocbCmd::Pread,
+            1 => IocbCmd::Pwrite,
+            2 => IocbCmd::Fsync,
+            3 => IocbCmd::Fdsync,
+            7 => IocbCmd::Preadv,
+            8 => IocbCmd::Pwritev,
+            _ => return_errno!(EINVAL, "the opcode of the request is not supported"),
+        };
+        Ok(cmd)
+    }
+}
+
+bitflags! {
+    struct IocbFlags: u32 {
+        /// Notify the completion by the eventfd of aio_resfd
+        const IOCB_FLAG_RESFD = 1 << 0;
+        /// The priority of the request is set by aio_reqprio
+        const IOCB_FLAG_IOPRIO = 1 << 1;
+    }
+}
+
+bitflags! {
+    struct RwFlags: i32 {
+        const RWF_HIPRI = 0x01;
+        const RWF_DSYNC = 0x02;
+        const RWF_SYNC = 0x04;
+        const RWF_NOWAIT = 0x08;
+    }
+}
+
+/// The same limit as the default of /proc/sys/fs/aio-max-nr.
+const AIO_MAX_NR: u32 = 65536;
+
+/// An AIO context, which queues the events of the completed requests.
+pub struct AioContext {
+    max_nr: usize,
+    // The requests in progress in the host I/O threads
+    in_flight: SgxMutex<Vec<InFlightRequest>>,
+    events: SgxMutex<VecDeque<io_event_t>>,
+    is_destroyed: AtomicBool,
+    // All threads that are blocked on getting the events, which sleep on the
+    // host eventfd that is written on the completions of the requests in flight
+    waiters: WaiterQueue,
+    host_eventfd: Arc<HostEventFd>,
+}
+
+struct InFlightRequest {
+    io: AsyncHostIo,
+    data: u64,
+    obj: u64,
+}
+
+impl AioContext {
+    fn new(max_nr: usize) -> Result<Self> {
+        Ok(Self {
+            max_nr,
+            in_flight: SgxMutex::new(Vec::new()),
+            events: SgxMutex::new(VecDeque::new()),
+            is_destroyed: AtomicBool::new(false),
+            waiters: WaiterQueue::new(),
+            host_eventfd: Arc::new(HostEventFd::new()?),
+        })
+    }
+
+    fn submit(&self, iocb_ptr: *const iocb_t) -> Result<()> {
+        from_user::check_ptr(iocb_ptr)?;
+        let iocb = unsafe { *iocb_ptr };
+        if iocb.aio_reserved2 != 0 {
+            return_errno!(EINVAL, "the reserved field is not zero");
+        }
+        let flags = IocbFlags::from_bits(iocb.aio_flags)
+            .ok_or_else(|| errno!(EINVAL, "unknown flags of the request"))?;
+        if iocb.aio_reqprio != 0 && !flags.contains(IocbFlags::IOCB_FLAG_IOPRIO) {
+            return_errno!(EINVAL, "the priority is set without IOCB_FLAG_IOPRIO");
+        }
+        let rw_flags = RwFlags::from_bits(iocb.aio_rw_flags)
+            .ok_or_else(|| errno!(EOPNOTSUPP, "unsupported flags of the read or write"))?;
+        let cmd = IocbCmd::from_u16(iocb.aio_lio_opcode)?;
+        let fd = iocb.aio_fildes as FileDesc;
+        let file = current!().file(fd)?;
+        let eventfd = if flags.contains(IocbFlags::IOCB_FLAG_RESFD) {
+            let file_ref = current!().file(iocb.aio_resfd as FileDesc)?;
+            file_ref.as_event()?;
+            Some(file_ref)
+        } else {
+            None
+        };
+
+        // Same as Linux, a request is not taken if there is no room for its event
+        let mut in_flight = self.in_flight.lock().unwrap();
+        let mut events = self.events.lock().unwrap();
+        if in_flight.len() + events.len() >= self.max_nr {
+            return_errno!(EAGAIN, "the events of the context are full");
+        }
+        // The requests that notify an eventfd are done synchronously, since
+        // nobody collects their completions to signal the eventfd
+        let host_io = match (file.host_fd(), &eventfd) {
+            (Some(host_fd), None) => self.start_host_io(host_fd.to_raw(), cmd, &iocb),
+            _ => None,
+        };
+        if let Some(res) = host_io {
+            match res {
+                Ok(io) => in_flight.push(InFlightRequest {
+                    io,
+                    data: iocb.aio_data,
+                    obj: iocb_ptr as u64,
+                }),
+                Err(e) => events.push_back(io_event_t {
+                    data: iocb.aio_data,
+                    obj: iocb_ptr as u64,
+                    res: -(e.errno() as i64),
+                    res2: 0,
+                }),
+            }
+            drop(events);
+            drop(in_flight);
+            self.waiters.dequeue_and_wake_all();
+            return Ok(());
+        }
+        drop(in_flight);
+
+        let res = match do_request(cmd, fd, &iocb, rw_flags) {
+            Ok(len) => len as i64,
+            Err(e) => -(e.errno() as i64),
+        };
+        events.push_back(io_event_t {
+            data: iocb.aio_data,
+            obj: iocb_ptr as u64,
+            res,
+            res2: 0,
+        });
+        drop(events);
+
+        if let Some(eventfd) = eventfd {
+            eventfd.as_event().unwrap().signal();
+        }
+        self.waiters.dequeue_and_wake_all();
+        Ok(())
+    }
+
+    /// Start to read or write the host file with the host I/O threads, or
+    /// None if the request is not done asynchronously.
+    fn start_host_io(
+        &self,
+        host_fd: FileDesc,
+        cmd: IocbCmd,
+        iocb: &iocb_t,
+    ) -> Option<Result<AsyncHostIo>> {
+        // The offset is ignored, since the host files have no position
+        let (op, bufs) = match cmd {
+            IocbCmd::Pread | IocbCmd::Pwrite => {
+                (cmd, vec![(iocb.aio_buf as usize, iocb.aio_nbytes as usize)])
+            }
+            IocbCmd::Preadv | IocbCmd::Pwritev => {
+                match iovecs_from_user(iocb.aio_buf as usize, iocb.aio_nbytes as usize) {
+                    Ok(iovecs) => (cmd, iovecs),
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+            IocbCmd::Fsync | IocbCmd::Fdsync => return None,
+        };
+        let op = match op {
+            IocbCmd::Pread | IocbCmd::Preadv => HostIoOp::Read,
+            _ => HostIoOp::Write,
+        };
+        AsyncHostIo::start(host_fd, op, &bufs, &self.host_eventfd)
+    }
+
+    /// Queue the events of the requests that are completed by the host I/O
+    /// threads.
+    fn collect_completions(&self) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        let mut events = self.events.lock().unwrap();
+        let nr_events = events.len();
+        let mut idx = 0;
+        while idx < in_flight.len() {
+            let res = match in_flight[idx].io.result() {
+                Some(Ok(len)) => len as i64,
+                Some(Err(e)) => -(e.errno() as i64),
+                None => {
+                    idx += 1;
+                    continue;
+                }
+            };
+            let request = in_flight.remove(idx);
+            events.push_back(io_event_t {
+                data: request.data,
+                obj: request.obj,
+                res,
+                res2: 0,
+            });
+        }
+        let has_new_events = events.len() > nr_events;
+        drop(events);
+        drop(in_flight);
+        if has_new_events {
+            self.waiters.dequeue_and_wake_all();
+        }
+    }
+
+    fn get_events(
+        &self,
+        min_nr: usize,
+        events: &mut [io_event_t],
+        mut timeout: Option<Duration>,
+    ) -> Result<usize> {
+        let mut count = 0;
+        let waiter = Waiter::with_host_eventfd(self.host_eventfd.clone());
+        loop {
+            self.waiters.reset_and_enqueue(&waiter);
+            self.collect_completions();
+            {
+                let mut queued = self.events.lock().unwrap();
+                while count < events.len() {
+                    match queued.pop_front() {
+                        Some(event) => {
+                            events[count] = event;
+                            count += 1;
+                        }
+                        None => break,
+                    }
+                }
+            }
+            if count >= min_nr || self.is_destroyed.load(Ordering::Acquire) {
+                return Ok(count);
+            }
+
+            match waiter.wait_host_event_mut(timeout.as_mut()) {
+                Ok(()) => {}
+                Err(e) if e.errno() == ETIMEDOUT => return Ok(count),
+                // Same as Linux, the events got before the signal are returned
+                Err(e) if count > 0 => return Ok(count),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+fn do_request(cmd: IocbCmd, fd: FileDesc, iocb: &iocb_t, rw_flags: RwFlags) -> Result<usize> {
+    let buf = iocb.aio_buf as usize;
+    let nbytes = iocb.aio_nbytes as usize;
+    let offset = iocb.aio_offset;
+    let len = match cmd {
+        IocbCmd::Pread => {
+            let buf = user_mut_slice(buf, nbytes)?;
+            file_ops::do_pread(fd, buf, offset)?
+        }
+        IocbCmd::Pwrite => {
+            let buf = user_slice(buf, nbytes)?;
+            file_ops::do_pwrite(fd, buf, offset)?
+        }
+        IocbCmd::Preadv => {
+            let mut total = 0;
+            for (base, len) in iovecs_from_user(buf, nbytes)? {
+                let buf = user_mut_slice(base, len)?;
+                let read_len = file_ops::do_pread(fd, buf, offset + total as off_t)?;
+                total += read_len;
+                if read_len < len {
+                    break;
+                }
+            }
+            total
+        }
+        IocbCmd::Pwritev => {
+            let mut total = 0;
+            for (base, len) in iovecs_from_user(buf, nbytes)? {
+                let buf = user_slice(base, len)?;
+                let write_len = file_ops::do_pwrite(fd, buf, offset + total as off_t)?;
+                total += write_len;
+                if write_len < len {
+                    break;
+                }
+            }
+            total
+        }
+        IocbCmd::Fsync => {
+            file_ops::do_fsync(fd)?;
+            0
+        }
+        IocbCmd::Fdsync => {
+            file_ops::do_fdatasync(fd)?;
+            0
+        }
+    };
+
+    if cmd == IocbCmd::Pwrite || cmd == IocbCmd::Pwritev {
+        if rw_flags.contains(RwFlags::RWF_SYNC) {
+            file_ops::do_fsync(fd)?;
+        } else if rw_flags.contains(RwFlags::RWF_DSYNC) {
+            file_ops::do_fdatasync(fd)?;
+        }
+    }
+    Ok(len)
+}
+
+// The base and length of each `struct iovec` of PREADV and PWRITEV
+fn iovecs_from_user(iov: usize, count: usize) -> Result<Vec<(usize, usize)>> {
+    if count > UIO_MAXIOV {
+        return_errno!(EINVAL, "too many iovecs");
+    }
+    let iov = iov as *const [usize; 2];
+    from_user::check_array(iov, count)?;
+    let iovecs = unsafe { std::slice::from_raw_parts(iov, count) };
+    Ok(iovecs.iter().map(|iovec| (iovec[0], iovec[1])).collect())
+}
+
+const UIO_MAXIOV: usize = 1024;
+
+fn user_slice<'a>(buf: usize, len: usize) -> Result<&'a [u8]> {
+    from_user::check_array(buf as *const u8, len)?;
+    Ok(unsafe { std::slice::from_raw_parts(buf as *const u8, len) })
+}
+
+fn user_mut_slice<'a>(buf: usize, len: usize) -> Result<&'a mut [u8]> {
+    from_user::check_mut_array(buf as *mut u8, len)?;
+    Ok(unsafe { std::slice::from_raw_parts_mut(buf as *mut u8, len) })
+}
+
+/// The AIO contexts of a process.
+#[derive(Default)]
+pub struct AioContexts {
+    contexts: HashMap<aio_context_t, Arc<AioContext>>,
+    nr_events: u32,
+}
+
+impl AioContexts {
+    fn get(&self, ctx_id: aio_context_t) -> Result<Arc<AioContext>> {
+        self.contexts
+            .get(&ctx_id)
+            .cloned()
+            .ok_or_else(|| errno!(EINVAL, "invalid AIO context"))
+    }
+}
+
+/// Create an AIO context that is able to hold the events of nr_events requests.
+pub fn do_io_setup(nr_events: u32) -> Result<aio_context_t> {
+    debug!("io_setup: nr_events: {}", nr_events);
+    if nr_events == 0 {
+        return_errno!(EINVAL, "nr_events is zero");
+    }
+
+    let current = current!();
+    let mut contexts = current.process().aio_contexts();
+    if nr_events > AIO_MAX_NR - contexts.nr_events {
+        return_errno!(EAGAIN, "nr_events exceeds the limit");
+    }
+    let ctx_id = current.vm().mmap(
+        0,
+        PAGE_SIZE,
+        VMPerms::READ,
+        MMapFlags::MAP_ANONYMOUS | MMapFlags::MAP_PRIVATE,
+        0,
+        0,
+    )? as aio_context_t;
+    contexts.nr_events += nr_events;
+    let ctx = match AioContext::new(nr_events as usize) {
+        Ok(ctx) => ctx,
+        Err(e) => {
+            current.vm().munmap(ctx_id as usize, PAGE_SIZE)?;
+            return Err(e);
+        }
+    };
+    contexts.contexts.insert(ctx_id, Arc::new(ctx));
+    Ok(ctx_id)
+}
+
+/// Destroy an AIO context. The threads that are blocked on getting the events
+/// of the context are woken up, and the requests in flight are cancelled.
+pub fn do_io_destroy(ctx_id: aio_context_t) -> Result<()> {
+    debug!("io_destroy: ctx_id: {:#x}", ctx_id);
+    let current = current!();
+    let ctx = {
+        let mut contexts = current.process().aio_contexts();
+        let ctx = contexts
+            .contexts
+            .remove(&ctx_id)
+            .ok_or_else(|| errno!(EINVAL, "invalid AIO context"))?;
+        contexts.nr_events -= ctx.max_nr as u32;
+        ctx
+    };
+    ctx.is_destroyed.store(true, Ordering::Release);
+    ctx.waiters.dequeue_and_wake_all();
+    // Same as Linux, wait for the requests in flight, which are cancelled
+    ctx.in_flight.lock().unwrap().clear();
+    current.vm().munmap(ctx_id as usize, PAGE_SIZE)
+}
+
+/// Submit the requests, returning the number of the submitted ones. Same as
+/// Linux, an error is returned only if the first request is not submitted.
+pub fn do_io_submit(ctx_id: aio_context_t, iocbs: &[*const iocb_t]) -> Result<usize> {
+    debug!("io_submit: ctx_id: {:#x}, nr: {}", ctx_id, iocbs.len());
+    let ctx = current!().process().aio_contexts().get(ctx_id)?;
+    for (i, &iocb) in iocbs.iter().enumerate() {
+        if let Err(e) = ctx.submit(iocb) {
+            if i == 0 {
+                return Err(e);
+            }
+            return Ok(i);
+        }
+    }
+    Ok(iocbs.len())
+}
+
+/// Get the events of at least min_nr and at most events.len() completed
+/// requests, or fewer if the timeout expires.
+pub fn do_io_getevents(
+    ctx_id: aio_context_t,
+    min_nr: usize,
+    events: &mut [io_event_t],
+    timeout: Option<Duration>,
+) -> Result<usize> {
+    debug!(
+        "io_getevents: ctx_id: {:#x}, min_nr: {}, nr: {}, timeout: {:?}",
+        ctx_id,
+        min_nr,
+        events.len(),
+        timeout
+    );
+    let ctx = current!().process().aio_contexts().get(ctx_id)?;
+    ctx.get_events(min_nr, events, timeout)
+}
+
+/// Cancel a request. Same as Linux, the reads and writes in progress can not be
+/// cancelled, and the other requests are done at the time they are submitted.
+pub fn do_io_cancel(ctx_id: aio_context_t, iocb: *const iocb_t) -> Result<io_event_t> {
+    debug!("io_cancel: ctx_id: {:#x}, iocb: {:?}", ctx_id, iocb);
+    current!().process().aio_contexts().get(ctx_id)?;
+    from_user::check_ptr(iocb)?;
+    return_errno!(EINVAL, "the request is completed")
+}
@@ -0,0 +1,150 @@
+//! The reads and writes of the host files (e.g., host sockets) that are done
+//! by the host I/O threads asynchronously, which back the requests of the
+//! Linux AIO and io_uring.
+//!
+//! The data is transferred through a buffer in the untrusted memory, which is
+//! copied from the user buffers on submission, or copied to the user buffers
+//! on completion.
+
+use super::*;
+use crate::events::HostEventFd;
+use crate::syscall::SyscallNum;
+use crate::untrusted::{io_engine, IoHandle, UntrustedSliceAlloc};
+use util::mem_util::from_user;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HostIoOp {
+    Read,
+    Write,
+    Recv(i32),
+    Send(i32),
+}
+
+impl HostIoOp {
+    fn is_read(&self) -> bool {
+        match self {
+            HostIoOp::Read | HostIoOp::Recv(_) => true,
+            HostIoOp::Write | HostIoOp::Send(_) => false,
+        }
+    }
+}
+
+/// A read or write of a host file in progress.
+pub struct AsyncHostIo {
+    // Dropped before the buffer, so that the request is done or cancelled
+    // before the buffer is freed
+    handle: IoHandle,
+    u_buf: *mut u8,
+    u_buf_len: usize,
+    _u_allocator: UntrustedSliceAlloc,
+    // The user buffers to copy the data read to
+    read_bufs: Option<Vec<(usize, usize)>>,
+}
+
+// Safety. The untrusted buffer is owned by the request.
+unsafe impl Send for AsyncHostIo {}
+unsafe impl Sync for AsyncHostIo {}
+
+impl Debug for AsyncHostIo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncHostIo")
+            .field("u_buf_len", &self.u_buf_len)
+            .field("is_done", &self.is_done())
+            .finish()
+    }
+}
+
+impl AsyncHostIo {
+    /// Start to read or write the host file with the user buffers, which are
+    /// given as the pairs of their addresses and lengths.
+    ///
+    /// The host eventfd is written once the I/O is done. None is returned if
+    /// the I/O engine is not enabled.
+    pub fn start(
+        host_fd: FileDesc,
+        op: HostIoOp,
+        bufs: &[(usize, usize)],
+        host_eventfd: &Arc<HostEventFd>,
+    ) -> Option<Result<Self>> {
+        let engine = io_engine()?;
+        let res = || -> Result<Self> {
+            let total_len = bufs.iter().map(|&(_, len)| len).sum();
+            let u_allocator = UntrustedSliceAlloc::new(total_len)?;
+            let u_buf = u_allocator.new_slice_mut(total_len)?;
+            let u_buf_ptr = u_buf.as_mut_ptr();
+            if !op.is_read() {
+                let mut pos = 0;
+                for &(base, len) in bufs {
+                    from_user::check_array(base as *const u8, len)?;
+                    let buf = unsafe { std::slice::from_raw_parts(base as *const u8, len) };
+                    u_buf[pos..pos + len].copy_from_slice(buf);
+                    pos += len;
+                }
+            }
+
+            let (num, flags) = match op {
+                HostIoOp::Read => (SyscallNum::Read, 0),
+                HostIoOp::Write => (SyscallNum::Write, 0),
+                HostIoOp::Recv(flags) => (SyscallNum::Recvfrom, flags),
+                HostIoOp::Send(flags) => (SyscallNum::Sendto, flags),
+            };
+            let args = [
+                host_fd as i64,
+                u_buf_ptr as i64,
+                total_len as i64,
+                flags as i64,
+                0,
+                0,
+            ];
+            let handle = unsafe { engine.submit(num, args, host_eventfd)? };
+            Ok(Self {
+                handle,
+                u_buf: u_buf_ptr,
+                u_buf_len: total_len,
+                _u_allocator: u_allocator,
+                read_bufs: if op.is_read() {
+                    Some(bufs.to_vec())
+                } else {
+                    None
+                },
+            })
+        };
+        Some(res())
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.handle.is_done()
+    }
+
+    /// Get the result of the I/O, or None if it is not done.
+    ///
+    /// The data read is copied to the user buffers.
+    pub fn result(&self) -> Option<Result<usize>> {
+        // Check the length returned from outside the enclave
+        let len = match self.handle.result()? {
+            Ok(retval) => (retval as usize).min(self.u_buf_len),
+            Err(e) => return Some(Err(e)),
+        };
+        let bufs = match &self.read_bufs {
+            Some(bufs) => bufs,
+            None => return Some(Ok(len)),
+        };
+
+        let u_buf = unsafe { std::slice::from_raw_parts(self.u_buf, len) };
+        let mut pos = 0;
+        for &(base, buf_len) in bufs {
+            if pos == len {
+                break;
+            }
+            let copy_len = buf_len.min(len - pos);
+            // The user buffers may have been unmapped since the submission
+            if let Err(e) = from_user::check_mut_array(base as *mut u8, copy_len) {
+                return Some(Err(e));
+            }
+            let buf = unsafe { std::slice::from_raw_parts_mut(base as *mut u8, copy_len) };
+            buf.copy_from_slice(&u_buf[pos..pos + copy_len]);
+            pos += copy_len;
+        }
+        Some(Ok(len))
+    }
+}
@@ -0,0 +1,179 @@
+//! A file system that makes a directory visible at another path, i.e., the
+//! file system of a bind mount.
+//!
+//! The inodes of BindFS wrap the inodes under the source directory. A
+//! read-only BindFS rejects all the updates through it, while the source
+//! directory is still writable at its own path.
+
+use super::*;
+use rcore_fs::vfs::{self, FsInfo, PollStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Weak;
+
+pub struct BindFS {
+    root: Arc<dyn INode>,
+    read_only: Arc<AtomicBool>,
+    self_ref: Weak<BindFS>,
+}
+
+impl BindFS {
+    /// Create a BindFS of the source directory.
+    ///
+    /// Whether it is read-only is shared with the mount table, so that it can
+    /// be changed by remounting.
+    pub fn new(root: Arc<dyn INode>, read_only: Arc<AtomicBool>) -> Arc<Self> {
+        let fs = Arc::new(Self {
+            root,
+            read_only,
+            self_ref: Weak::default(),
+        });
+        let weak = Arc::downgrade(&fs);
+        let ptr = Arc::into_raw(fs) as *mut Self;
+        unsafe {
+            (*ptr).self_ref = weak;
+            Arc::from_raw(ptr)
+        }
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::Relaxed)
+    }
+
+    fn wrap_inode(&self, inode: Arc<dyn INode>) -> Arc<dyn INode> {
+        Arc::new(BindINode {
+            inner: inode,
+            fs: self.self_ref.upgrade().unwrap(),
+        })
+    }
+}
+
+impl FileSystem for BindFS {
+    fn sync(&self) -> vfs::Result<()> {
+        self.root.fs().sync()
+    }
+
+    fn root_inode(&self) -> Arc<dyn INode> {
+        self.wrap_inode(self.root.clone())
+    }
+
+    fn info(&self) -> FsInfo {
+        self.root.fs().info()
+    }
+}
+
+/// The inode of BindFS, which wraps an inode under the source directory.
+pub struct BindINode {
+    inner: Arc<dyn INode>,
+    fs: Arc<BindFS>,
+}
+
+impl BindINode {
+    /// Fail with EROFS if the BindFS is read-only.
+    fn check_writable(&self) -> vfs::Result<()> {
+        if self.fs.is_read_only() {
+            return Err(FsError::WrProtected);
+        }
+        Ok(())
+    }
+
+    /// Get the inode that holds the extended attributes, failing with EROFS
+    /// if the attributes are to be updated through a read-only BindFS.
+    pub fn xattr_inode(&self, for_update: bool) -> vfs::Result<&Arc<dyn INode>> {
+        if for_update {
+            self.check_writable()?;
+        }
+        Ok(&self.inner)
+    }
+
+    fn inner_of(inode: &Arc<dyn INode>) -> vfs::Result<&Arc<dyn INode>> {
+        inode
+            .downcast_ref::<BindINode>()
+            .map(|inode| &inode.inner)
+            .ok_or(FsError::NotSameFs)
+    }
+}
+
+impl INode for BindINode {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> vfs::Result<usize> {
+        self.inner.read_at(offset, buf)
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> vfs::Result<usize> {
+        self.check_writable()?;
+        self.inner.write_at(offset, buf)
+    }
+
+    fn poll(&self) -> vfs::Result<PollStatus> {
+        self.inner.poll()
+    }
+
+    fn metadata(&self) -> vfs::Result<Metadata> {
+        self.inner.metadata()
+    }
+
+    fn set_metadata(&self, metadata: &Metadata) -> vfs::Result<()> {
+        self.check_writable()?;
+        self.inner.set_metadata(metadata)
+    }
+
+    fn sync_all(&self) -> vfs::Result<()> {
+        self.inner.sync_all()
+    }
+
+    fn sync_data(&self) -> vfs::Result<()> {
+        self.inner.sync_data()
+    }
+
+    fn resize(&self, len: usize) -> vfs::Result<()> {
+        self.check_writable()?;
+        self.inner.resize(len)
+    }
+
+    fn fallocate(&self, mode: u32, offset: u64, len: u64) -> vfs::Result<()> {
+        self.check_writable()?;
+        self.inner.fallocate(mode, offset, len)
+    }
+
+    fn create(&self, name: &str, type_: FileType, mode: u32) -> vfs::Result<Arc<dyn INode>> {
+        self.check_writable()?;
+        let inode = self.inner.create(name, type_, mode)?;
+        Ok(self.fs.wrap_inode(inode))
+    }
+
+    fn link(&self, name: &str, other: &Arc<dyn INode>) -> vfs::Result<()> {
+        self.check_writable()?;
+        self.inner.link(name, Self::inner_of(other)?)
+    }
+
+    fn unlink(&self, name: &str) -> vfs::Result<()> {
+        self.check_writable()?;
+        self.inner.unlink(name)
+    }
+
+    fn move_(&self, old_name: &str, target: &Arc<dyn INode>, new_name: &str) -> vfs::Result<()> {
+        self.check_writable()?;
+        self.inner
+            .move_(old_name, Self::inner_of(target)?, new_name)
+    }
+
+    fn find(&self, name: &str) -> vfs::Result<Arc<dyn INode>> {
+        let inode = self.inner.find(name)?;
+        Ok(self.fs.wrap_inode(inode))
+    }
+
+    fn get_entry(&self, id: usize) -> vfs::Result<String> {
+        self.inner.get_entry(id)
+    }
+
+    fn io_control(&self, cmd: u32, data: usize) -> vfs::Result<()> {
+        self.inner.io_control(cmd, data)
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.clone()
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
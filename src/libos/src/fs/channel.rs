@@ -1,5 +1,6 @@
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Weak;
+use std::time::Duration;
 
 use ringbuf::{Consumer as RbConsumer, Producer as RbProducer, RingBuffer};
 
@@ -24,6 +25,11 @@ impl<I> Channel<I> {
         let mut producer = Producer::new(rb_producer, state.clone());
         let mut consumer = Consumer::new(rb_consumer, state.clone());
 
+        // The ring buffer is shared by the two endpoints, so resizing it on
+        // either endpoint needs to replace the buffers of both endpoints
+        producer.peer_inner = Arc::downgrade(&consumer.inner);
+        consumer.peer_inner = Arc::downgrade(&producer.inner);
+
         // The events on an endpoint is not triggered by itself, but its peer.
         // For example, a producer becomes writable (IoEvents::OUT) only if
         // its peer consumer gets read. So an endpoint needs to hold a
@@ -121,31 +127,38 @@ impl<I: Copy> Channel<I> {
 macro_rules! impl_end_point_type {
     ($(#[$attr:meta])* $vis:vis struct $end_point:ident<$i:ident> {
         inner: $inner:ident<$_:ident>,
+        peer_inner: $peer_inner:ident<$__:ident>,
     }) => (
         /// An endpoint is either the producer or consumer of a channel.
         $(#[$attr])* $vis struct $end_point<$i> {
-            inner: SgxMutex<$inner<$i>>,
+            inner: Arc<SgxMutex<$inner<$i>>>,
+            peer_inner: Weak<SgxMutex<$peer_inner<$i>>>,
             state: Arc<State>,
             observer: Arc<WaiterQueueObserver<IoEvents>>,
             notifier: Arc<IoNotifier>,
             peer_notifier: Weak<IoNotifier>,
             is_nonblocking: AtomicBool,
+            timeout: SgxMutex<Option<Duration>>,
         }
 
         impl<$i> $end_point<$i> {
             fn new(inner: $inner<$i>, state: Arc<State>) -> Self {
-                let inner = SgxMutex::new(inner);
+                let inner = Arc::new(SgxMutex::new(inner));
+                let peer_inner = Weak::new();
                 let observer = WaiterQueueObserver::new();
                 let notifier = Arc::new(IoNotifier::new());
                 let peer_notifier = Default::default();
                 let is_nonblocking = AtomicBool::new(false);
+                let timeout = SgxMutex::new(None);
                 Self {
                     inner,
+                    peer_inner,
                     state,
                     observer,
                     notifier,
                     peer_notifier,
                     is_nonblocking,
+                    timeout,
                 }
             }
 
@@ -174,6 +187,24 @@ macro_rules! impl_end_point_type {
                 }
             }
 
+            /// Returns the timeout of a blocking push or pop, which fails with
+            /// EAGAIN on timeout.
+            ///
+            /// By default, a channel blocks indefinitely.
+            pub fn timeout(&self) -> Option<Duration> {
+                *self.timeout.lock().unwrap()
+            }
+
+            /// Set the timeout of a blocking push or pop.
+            pub fn set_timeout(&self, timeout: Option<Duration>) {
+                *self.timeout.lock().unwrap() = timeout;
+            }
+
+            /// Returns the max number of items in the channel.
+            pub fn capacity(&self) -> usize {
+                self.inner.lock().unwrap().capacity()
+            }
+
             fn trigger_peer_events(&self, events: &IoEvents) {
                 if let Some(peer_notifier) = self.peer_notifier.upgrade() {
                     peer_notifier.broadcast(events);
@@ -185,9 +216,9 @@ macro_rules! impl_end_point_type {
 
 // Just like a normal loop, except that a waiter queue (as well as a waiter)
 // is used to avoid busy loop. This macro is used in the push/pop implementation
-// below.
+// below. The loop fails with EAGAIN if it is not done within the timeout.
 macro_rules! waiter_loop {
-    ($loop_body: block, $waiter_queue: expr) => {
+    ($loop_body: block, $waiter_queue: expr, $timeout: expr) => {
         // Try without creating a waiter. This saves some CPU cycles if the
         // first attempt succeeds.
         {
@@ -197,6 +228,7 @@ macro_rules! waiter_loop {
         // The main loop
         let waiter = Waiter::new();
         let waiter_queue = $waiter_queue;
+        let mut timeout = $timeout;
         loop {
             waiter_queue.reset_and_enqueue(&waiter);
 
@@ -204,7 +236,12 @@ macro_rules! waiter_loop {
                 $loop_body
             }
 
-            waiter.wait(None)?;
+            if let Err(e) = waiter.wait_mut(timeout.as_mut()) {
+                if e.errno() == ETIMEDOUT {
+                    return_errno!(EAGAIN, "timed out");
+                }
+                return Err(e);
+            }
         }
     };
 }
@@ -213,6 +250,7 @@ impl_end_point_type! {
     /// Producer is the writable endpoint of a channel.
     pub struct Producer<I> {
         inner: RbProducer<I>,
+        peer_inner: RbConsumer<I>,
     }
 }
 
@@ -238,7 +276,8 @@ impl<I> Producer<I> {
                     return_errno!(EAGAIN, "try again later");
                 }
             },
-            self.observer.waiter_queue()
+            self.observer.waiter_queue(),
+            self.timeout()
         );
     }
 
@@ -276,6 +315,27 @@ impl<I> Producer<I> {
         self.observer.waiter_queue().dequeue_and_wake_all();
     }
 
+    /// Resize the channel to hold at most `capacity` items, keeping the
+    /// items in the channel.
+    pub fn resize(&self, capacity: usize) -> Result<()> {
+        // Lock the consumer before the producer, same as `Consumer::resize`
+        match self.peer_inner.upgrade() {
+            Some(rb_consumer) => {
+                let mut rb_consumer = rb_consumer.lock().unwrap();
+                let mut rb_producer = self.inner.lock().unwrap();
+                resize_ring_buffer(Some(&mut rb_consumer), &mut rb_producer, capacity)?;
+            }
+            None => {
+                let mut rb_producer = self.inner.lock().unwrap();
+                resize_ring_buffer(None, &mut rb_producer, capacity)?;
+            }
+        }
+
+        // There may be more space for the blocked producers
+        self.notifier.broadcast(&IoEvents::OUT);
+        Ok(())
+    }
+
     pub fn is_self_shutdown(&self) -> bool {
         self.state.is_producer_shutdown()
     }
@@ -291,6 +351,12 @@ impl<I: Copy> Producer<I> {
     }
 
     pub fn push_slices(&self, item_slices: &[&[I]]) -> Result<usize> {
+        self.push_slices_dontwait(item_slices, false)
+    }
+
+    /// Same as `push_slices`, except that it never blocks given `dontwait`,
+    /// as MSG_DONTWAIT does for a socket.
+    pub fn push_slices_dontwait(&self, item_slices: &[&[I]], dontwait: bool) -> Result<usize> {
         let len: usize = item_slices.iter().map(|slice| slice.len()).sum();
         if len == 0 {
             return Ok(0);
@@ -320,13 +386,19 @@ impl<I: Copy> Producer<I> {
                     return Ok(total_count);
                 }
 
-                if self.is_nonblocking() {
+                if dontwait || self.is_nonblocking() {
                     return_errno!(EAGAIN, "try again later");
                 }
             },
-            self.observer.waiter_queue()
+            self.observer.waiter_queue(),
+            self.timeout()
         );
     }
+
+    /// Returns the number of items that can be pushed without blocking.
+    pub fn free_space(&self) -> usize {
+        self.inner.lock().unwrap().remaining()
+    }
 }
 
 impl<I> Drop for Producer<I> {
@@ -339,6 +411,7 @@ impl_end_point_type! {
     /// Consumer is the readable endpoint of a channel.
     pub struct Consumer<I> {
         inner: RbConsumer<I>,
+        peer_inner: RbProducer<I>,
     }
 }
 
@@ -347,24 +420,23 @@ impl<I> Consumer<I> {
         waiter_loop!(
             {
                 let mut rb_consumer = self.inner.lock().unwrap();
-                if self.is_self_shutdown() {
-                    return_errno!(EPIPE, "this endpoint has been shutdown");
-                }
-
                 if let Some(item) = rb_consumer.pop() {
                     drop(rb_consumer);
                     self.trigger_peer_events(&IoEvents::OUT);
                     return Ok(Some(item));
                 }
 
-                if self.is_peer_shutdown() {
+                // The items left can still be consumed after the endpoint is
+                // shutdown, as a half-closed socket does
+                if self.is_peer_shutdown() || self.is_self_shutdown() {
                     return Ok(None);
                 }
                 if self.is_nonblocking() {
                     return_errno!(EAGAIN, "try again later");
                 }
             },
-            self.observer.waiter_queue()
+            self.observer.waiter_queue(),
+            self.timeout()
         );
     }
 
@@ -402,6 +474,29 @@ impl<I> Consumer<I> {
         self.observer.waiter_queue().dequeue_and_wake_all();
     }
 
+    /// Resize the channel to hold at most `capacity` items, keeping the
+    /// items in the channel.
+    pub fn resize(&self, capacity: usize) -> Result<()> {
+        let mut rb_consumer = self.inner.lock().unwrap();
+        let rb_producer = self.peer_inner.upgrade();
+        match rb_producer {
+            Some(rb_producer) => {
+                let mut rb_producer = rb_producer.lock().unwrap();
+                resize_ring_buffer(Some(&mut rb_consumer), &mut rb_producer, capacity)?;
+            }
+            None => {
+                // The producer is gone, so a new one is used to fill the buffer
+                let mut rb_producer = RingBuffer::new(0).split().0;
+                resize_ring_buffer(Some(&mut rb_consumer), &mut rb_producer, capacity)?;
+            }
+        }
+        drop(rb_consumer);
+
+        // There may be more space for the blocked producers
+        self.trigger_peer_events(&IoEvents::OUT);
+        Ok(())
+    }
+
     pub fn is_self_shutdown(&self) -> bool {
         self.state.is_consumer_shutdown()
     }
@@ -411,16 +506,24 @@ impl<I> Consumer<I> {
     }
 
     pub fn items_to_consume(&self) -> usize {
-        if self.is_self_shutdown() {
-            0
-        } else {
-            self.inner.lock().unwrap().len()
-        }
+        self.inner.lock().unwrap().len()
     }
 
-    pub fn capacity(&self) -> usize {
-        let rb_consumer = self.inner.lock().unwrap();
-        rb_consumer.capacity()
+    /// Returns whether the consumer and the producer belong to the same channel.
+    pub fn is_peer_of(&self, producer: &Producer<I>) -> bool {
+        Arc::ptr_eq(&self.state, &producer.state)
+    }
+
+    /// Discard at most `count` items, returning the number of discarded items.
+    pub fn skip(&self, count: usize) -> usize {
+        let mut rb_consumer = self.inner.lock().unwrap();
+        let count = rb_consumer.discard(count);
+        drop(rb_consumer);
+
+        if count > 0 {
+            self.trigger_peer_events(&IoEvents::OUT);
+        }
+        count
     }
 }
 
@@ -430,6 +533,16 @@ impl<I: Copy> Consumer<I> {
     }
 
     pub fn pop_slices(&self, item_slices: &mut [&mut [I]]) -> Result<usize> {
+        self.pop_slices_dontwait(item_slices, false)
+    }
+
+    /// Same as `pop_slices`, except that it never blocks given `dontwait`,
+    /// as MSG_DONTWAIT does for a socket.
+    pub fn pop_slices_dontwait(
+        &self,
+        item_slices: &mut [&mut [I]],
+        dontwait: bool,
+    ) -> Result<usize> {
         let len: usize = item_slices.iter().map(|slice| slice.len()).sum();
         if len == 0 {
             return Ok(0);
@@ -438,10 +551,6 @@ impl<I: Copy> Consumer<I> {
         waiter_loop!(
             {
                 let mut rb_consumer = self.inner.lock().unwrap();
-                if self.is_self_shutdown() {
-                    return_errno!(EPIPE, "this endpoint has been shutdown");
-                }
-
                 let mut total_count = 0;
                 for items in item_slices.iter_mut() {
                     let count = rb_consumer.pop_slice(items);
@@ -459,14 +568,58 @@ impl<I: Copy> Consumer<I> {
                     return Ok(total_count);
                 };
 
-                if self.is_peer_shutdown() {
+                if self.is_peer_shutdown() || self.is_self_shutdown() {
                     return Ok(0);
                 }
-                if self.is_nonblocking() {
+                if dontwait || self.is_nonblocking() {
                     return_errno!(EAGAIN, "try again later");
                 }
             },
-            self.observer.waiter_queue()
+            self.observer.waiter_queue(),
+            self.timeout()
+        );
+    }
+
+    /// Copy a slice of items from the channel without consuming them.
+    ///
+    /// Just like `pop_slice`, this method blocks until there are items to
+    /// copy, unless the endpoint is non-blocking or the peer has been shutdown.
+    pub fn peek_slice(&self, items: &mut [I]) -> Result<usize> {
+        self.peek_slice_dontwait(items, false)
+    }
+
+    /// Same as `peek_slice`, except that it never blocks given `dontwait`.
+    pub fn peek_slice_dontwait(&self, items: &mut [I], dontwait: bool) -> Result<usize> {
+        if items.len() == 0 {
+            return Ok(0);
+        }
+
+        waiter_loop!(
+            {
+                let rb_consumer = self.inner.lock().unwrap();
+                let mut total_count = 0;
+                rb_consumer.access(|first_items, second_items| {
+                    for src_items in [first_items, second_items].iter() {
+                        let count = src_items.len().min(items.len() - total_count);
+                        items[total_count..total_count + count]
+                            .copy_from_slice(&src_items[..count]);
+                        total_count += count;
+                    }
+                });
+
+                if total_count > 0 {
+                    return Ok(total_count);
+                }
+
+                if self.is_peer_shutdown() || self.is_self_shutdown() {
+                    return Ok(0);
+                }
+                if dontwait || self.is_nonblocking() {
+                    return_errno!(EAGAIN, "try again later");
+                }
+            },
+            self.observer.waiter_queue(),
+            self.timeout()
         );
     }
 }
@@ -477,6 +630,30 @@ impl<I> Drop for Consumer<I> {
     }
 }
 
+// Replace the ring buffer of the two endpoints by a new one of the capacity,
+// moving the items in the old buffer to the new one. The consumer is `None`
+// if it has been dropped, in which case the items are discarded.
+fn resize_ring_buffer<I>(
+    rb_consumer: Option<&mut RbConsumer<I>>,
+    rb_producer: &mut RbProducer<I>,
+    capacity: usize,
+) -> Result<()> {
+    let (mut new_producer, new_consumer) = RingBuffer::new(capacity).split();
+    if let Some(rb_consumer) = rb_consumer {
+        if rb_consumer.len() > capacity {
+            return_errno!(EBUSY, "too many items in the channel");
+        }
+        while let Some(item) = rb_consumer.pop() {
+            if new_producer.push(item).is_err() {
+                unreachable!();
+            }
+        }
+        *rb_consumer = new_consumer;
+    }
+    *rb_producer = new_producer;
+    Ok(())
+}
+
 /// The state of a channel shared by the two endpoints of a channel.
 struct State {
     is_producer_shutdown: AtomicBool,
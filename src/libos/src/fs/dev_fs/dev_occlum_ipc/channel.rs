@@ -0,0 +1,190 @@
+use super::session::{self, SessionKeys, INITIATOR, MAC_SIZE, RESPONDER};
+use super::shm::{IpcShm, Ring, RECORD_MAX_SIZE};
+use super::*;
+use fs::{AccessMode, IoEvents, StatusFlags};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The max size of a message, which fits in a ring after being encrypted
+pub const MSG_MAX_SIZE: usize = RECORD_MAX_SIZE - MAC_SIZE;
+
+/// One side of an established channel, which sends and receives messages.
+/// The boundaries of the messages are preserved.
+pub struct IpcChannel {
+    shm: IpcShm,
+    side: usize,
+    sender: SgxMutex<Endpoint>,
+    receiver: SgxMutex<Endpoint>,
+    nonblocking: AtomicBool,
+}
+
+// The key and the sequence number of a direction
+struct Endpoint {
+    key: sgx_aes_gcm_128bit_key_t,
+    seq: u64,
+}
+
+impl IpcChannel {
+    /// Connect to the channel of the name, which blocks until the peer
+    /// connects and the handshake is done.
+    pub fn connect(name: &str, nonblocking: bool) -> Result<Self> {
+        let shm = IpcShm::open(name)?;
+        let side = if shm.is_creator() {
+            INITIATOR
+        } else {
+            RESPONDER
+        };
+        let result = session::handshake(&shm, side);
+        if side == INITIATOR && result.is_err() {
+            // The name is not unlinked by a responder
+            shm.unlink();
+        }
+        let SessionKeys { send_key, recv_key } = result?;
+        Ok(Self {
+            shm,
+            side,
+            sender: SgxMutex::new(Endpoint {
+                key: send_key,
+                seq: 0,
+            }),
+            receiver: SgxMutex::new(Endpoint {
+                key: recv_key,
+                seq: 0,
+            }),
+            nonblocking: AtomicBool::new(nonblocking),
+        })
+    }
+
+    fn send_ring(&self) -> &Ring {
+        &self.shm.layout().rings[self.side]
+    }
+
+    fn recv_ring(&self) -> &Ring {
+        &self.shm.layout().rings[1 - self.side]
+    }
+
+    fn nonblocking(&self) -> bool {
+        self.nonblocking.load(Ordering::Relaxed)
+    }
+
+    /// Send a message as a whole.
+    pub fn send(&self, msg: &[u8]) -> Result<usize> {
+        if msg.len() > MSG_MAX_SIZE {
+            return_errno!(EMSGSIZE, "the message is too large");
+        }
+        let ring = self.send_ring();
+        let mut sender = self.sender.lock().unwrap();
+        let record = session::seal(&sender.key, sender.seq, msg)?;
+        loop {
+            if ring.is_closed() {
+                return_errno!(EPIPE, "the channel is closed by the peer");
+            }
+            let head = ring.head();
+            if ring.try_push(&record)? {
+                break;
+            }
+            if self.nonblocking() {
+                return_errno!(EAGAIN, "no space in the channel");
+            }
+            ring.wait_for_space(head);
+        }
+        sender.seq += 1;
+        ring.wake_reader();
+        Ok(msg.len())
+    }
+
+    /// Receive a message, whose extra bytes are discarded if the buffer is
+    /// too small. Zero is returned if the channel is closed by the peer.
+    pub fn recv(&self, buf: &mut [u8]) -> Result<usize> {
+        let ring = self.recv_ring();
+        let mut receiver = self.receiver.lock().unwrap();
+        let record = loop {
+            let tail = ring.tail();
+            if let Some(record) = ring.try_pop()? {
+                break record;
+            }
+            if ring.is_closed() {
+                return Ok(0);
+            }
+            if self.nonblocking() {
+                return_errno!(EAGAIN, "no messages in the channel");
+            }
+            ring.wait_for_record(tail);
+        };
+        ring.wake_writer();
+
+        let msg = session::open(&receiver.key, receiver.seq, &record)?;
+        receiver.seq += 1;
+        let len = msg.len().min(buf.len());
+        buf[..len].copy_from_slice(&msg[..len]);
+        Ok(len)
+    }
+}
+
+impl Drop for IpcChannel {
+    fn drop(&mut self) {
+        // The peer gets EOF or EPIPE afterwards
+        self.send_ring().close();
+        self.recv_ring().close();
+    }
+}
+
+impl Debug for IpcChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("IpcChannel")
+            .field("side", &self.side)
+            .field("nonblocking", &self.nonblocking())
+            .finish()
+    }
+}
+
+impl File for IpcChannel {
+    fn read(&self, buf: &mut [u8]) -> Result<usize> {
+        self.recv(buf)
+    }
+
+    fn write(&self, buf: &[u8]) -> Result<usize> {
+        self.send(buf)
+    }
+
+    fn access_mode(&self) -> Result<AccessMode> {
+        Ok(AccessMode::O_RDWR)
+    }
+
+    fn status_flags(&self) -> Result<StatusFlags> {
+        if self.nonblocking() {
+            Ok(StatusFlags::O_NONBLOCK)
+        } else {
+            Ok(StatusFlags::empty())
+        }
+    }
+
+    fn set_status_flags(&self, new_status_flags: StatusFlags) -> Result<()> {
+        // Only O_NONBLOCK is supported
+        let nonblocking = new_status_flags.contains(StatusFlags::O_NONBLOCK);
+        self.nonblocking.store(nonblocking, Ordering::Relaxed);
+        Ok(())
+    }
+
+    // There is no notifier since the peer is outside the enclave, so the
+    // channel can only be polled by busy polling
+    fn poll_new(&self) -> IoEvents {
+        let mut events = IoEvents::empty();
+        let recv_ring = self.recv_ring();
+        if recv_ring.has_record() {
+            events |= IoEvents::IN;
+        } else if recv_ring.is_closed() {
+            events |= IoEvents::IN | IoEvents::RDHUP;
+        }
+        let send_ring = self.send_ring();
+        if send_ring.is_closed() {
+            events |= IoEvents::ERR;
+        } else if send_ring.has_space() {
+            events |= IoEvents::OUT;
+        }
+        events
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
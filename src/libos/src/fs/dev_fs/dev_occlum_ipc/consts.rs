@@ -0,0 +1,24 @@
+use super::*;
+
+/// Ioctl to connect to a channel of the given name, which returns a new file
+/// descriptor of the channel
+pub const OCCLUM_IPC_CMD_NUM_CONNECT: u32 = StructuredIoctlNum::new::<IoctlConnectArg>(
+    0,
+    OCCLUM_IPC_MAGIC_CHAR,
+    StructuredIoctlArgType::InputOutput,
+)
+.as_u32();
+
+/// A magical number that distinguishes the ioctls of /dev/occlum_ipc from other ioctls
+const OCCLUM_IPC_MAGIC_CHAR: u8 = 'i' as u8;
+
+/// The max length of the name of a channel
+pub const CHANNEL_NAME_MAX: usize = 64;
+
+bitflags! {
+    /// The flags of a new channel, which are the same as those of open.
+    pub struct ChannelFlags : i32 {
+        const NONBLOCK = 0o4000;
+        const CLOEXEC  = 0o2000000;
+    }
+}
@@ -0,0 +1,122 @@
+//! Cross-enclave IPC device (/dev/occlum_ipc).
+//!
+//! Two Occlum instances on the same host connect to a channel of the same
+//! name, which is backed by a shared memory of the host. The two enclaves
+//! attest each other by local attestation and agree on the session keys by
+//! ECDH, so the messages are encrypted and authenticated before they leave
+//! the enclave. Only the enclaves signed by the same key as the current one
+//! are trusted.
+//!
+//! The first enclave that connects to a channel creates the shared memory
+//! and waits for its peer; the second one removes the name from the host, so
+//! a name can be used again once a channel is established.
+
+use super::*;
+
+mod channel;
+mod consts;
+mod session;
+mod shm;
+
+use self::channel::IpcChannel;
+use self::consts::*;
+use std::os::raw::c_char;
+use util::mem_util::from_user::*;
+
+#[derive(Debug)]
+pub struct DevOcclumIpc;
+
+impl INode for DevOcclumIpc {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> vfs::Result<usize> {
+        Err(FsError::PermError)
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> vfs::Result<usize> {
+        Err(FsError::PermError)
+    }
+
+    fn poll(&self) -> vfs::Result<vfs::PollStatus> {
+        Err(FsError::PermError)
+    }
+
+    fn metadata(&self) -> vfs::Result<Metadata> {
+        Ok(Metadata {
+            dev: 1,
+            inode: 0,
+            size: 0,
+            blk_size: 0,
+            blocks: 0,
+            atime: Timespec { sec: 0, nsec: 0 },
+            mtime: Timespec { sec: 0, nsec: 0 },
+            ctime: Timespec { sec: 0, nsec: 0 },
+            type_: vfs::FileType::CharDevice,
+            mode: 0o666,
+            nlinks: 1,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+        })
+    }
+
+    fn io_control(&self, cmd: u32, data: usize) -> vfs::Result<()> {
+        let mut ioctl_cmd =
+            unsafe { IoctlCmd::new(cmd, data as *mut u8).map_err(|_| FsError::InvalidParam)? };
+        self.ioctl(&mut ioctl_cmd).map_err(|e| {
+            error!("{}", e.backtrace());
+            FsError::IOCTLError
+        })?;
+        Ok(())
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl DevOcclumIpc {
+    fn ioctl(&self, cmd: &mut IoctlCmd) -> Result<i32> {
+        let nonbuiltin_cmd = match cmd {
+            IoctlCmd::NonBuiltin(nonbuiltin_cmd) => nonbuiltin_cmd,
+            _ => return_errno!(EINVAL, "unknown ioctl cmd for /dev/occlum_ipc"),
+        };
+        let cmd_num = nonbuiltin_cmd.cmd_num().as_u32();
+        match cmd_num {
+            OCCLUM_IPC_CMD_NUM_CONNECT => {
+                let arg = nonbuiltin_cmd.arg_mut::<IoctlConnectArg>()?;
+                let name = clone_cstring_safely(arg.name)?
+                    .into_string()
+                    .map_err(|_| errno!(EINVAL, "the name is not valid UTF-8"))?;
+                check_channel_name(&name)?;
+                let flags = ChannelFlags::from_bits(arg.flags)
+                    .ok_or_else(|| errno!(EINVAL, "unknown flags"))?;
+
+                let channel = IpcChannel::connect(&name, flags.contains(ChannelFlags::NONBLOCK))?;
+                let close_on_spawn = flags.contains(ChannelFlags::CLOEXEC);
+                arg.fd = current!().add_file(Arc::new(channel), close_on_spawn)? as i32;
+            }
+            _ => return_errno!(EINVAL, "unknown ioctl cmd for /dev/occlum_ipc"),
+        }
+        Ok(0)
+    }
+}
+
+// The name is a part of the file name of the shared memory on the host
+fn check_channel_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.len() > CHANNEL_NAME_MAX {
+        return_errno!(EINVAL, "the length of the name is invalid");
+    }
+    if !name
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+    {
+        return_errno!(EINVAL, "the name contains invalid characters");
+    }
+    Ok(())
+}
+
+#[repr(C)]
+struct IoctlConnectArg {
+    name: *const c_char, // Input
+    flags: i32,          // Input
+    fd: i32,             // Output
+}
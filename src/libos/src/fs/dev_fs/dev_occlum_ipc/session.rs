@@ -0,0 +1,280 @@
+//! The handshake of a channel and the encryption of its records.
+//!
+//! The two sides exchange their ECDH public keys along with the reports
+//! targeted at each other, whose report data binds the keys of both sides.
+//! The peer must be the same product as the current enclave, signed by the
+//! same key, in the same debug mode and of a version no older than the
+//! current one. Each direction has its own key derived from the shared secret
+//! with HKDF-SHA256, and the
+//! nonce of a record is its sequence number, so the records cannot be
+//! replayed, reordered or reflected.
+
+use super::shm::{Hello, IpcShm, ShmLayout};
+use super::*;
+use sgx_tcrypto::{
+    rsgx_rijndael128GCM_decrypt, rsgx_rijndael128GCM_encrypt, rsgx_sha256_slice, SgxEccHandle,
+};
+use std::sync::atomic::Ordering;
+use util::sgx::{create_report, get_self_target, verify_report};
+
+// The states of the handshake
+const STATE_INITIATOR_HELLO: u32 = 1;
+const STATE_RESPONDER_HELLO: u32 = 2;
+const STATE_INITIATOR_REPORT: u32 = 3;
+const STATE_ESTABLISHED: u32 = 4;
+const STATE_FAILED: u32 = u32::max_value();
+
+/// The initiator and the responder, which are also the indexes of their
+/// hellos and the rings they write.
+pub const INITIATOR: usize = 0;
+pub const RESPONDER: usize = 1;
+
+/// The size of the MAC that precedes the ciphertext in a record
+pub const MAC_SIZE: usize = SGX_AESGCM_MAC_SIZE;
+
+/// The keys of the two directions of a channel.
+pub struct SessionKeys {
+    pub send_key: sgx_aes_gcm_128bit_key_t,
+    pub recv_key: sgx_aes_gcm_128bit_key_t,
+}
+
+/// Do the handshake as the given side, which blocks until the peer finishes
+/// its part.
+pub fn handshake(shm: &IpcShm, side: usize) -> Result<SessionKeys> {
+    let layout = shm.layout();
+    let result = if side == INITIATOR {
+        handshake_as_initiator(layout)
+    } else {
+        handshake_as_responder(shm, layout)
+    };
+    if result.is_err() {
+        layout.state.store(STATE_FAILED, Ordering::Release);
+        shm::futex_wake(&layout.state);
+    }
+    result
+}
+
+fn handshake_as_initiator(layout: &ShmLayout) -> Result<SessionKeys> {
+    let ecc = open_ecc()?;
+    let (priv_key, pub_key) = ecc
+        .create_key_pair()
+        .map_err(|_| errno!(EIO, "failed to create the key pair"))?;
+    let mut hello = Hello {
+        target_info: get_self_target()?,
+        pub_key,
+        ..Default::default()
+    };
+    layout.write_hello(INITIATOR, &hello);
+    set_state(layout, STATE_INITIATOR_HELLO);
+
+    wait_for_state(layout, STATE_RESPONDER_HELLO)?;
+    let peer_hello = layout.read_hello(RESPONDER);
+    let peer_pub_key = peer_hello.pub_key;
+    verify_peer(&peer_hello.report, &bind_keys(&peer_pub_key, &pub_key))?;
+
+    hello.report = create_report(
+        Some(&peer_hello.target_info),
+        Some(&bind_keys(&pub_key, &peer_pub_key)),
+    )?;
+    layout.write_hello(INITIATOR, &hello);
+    set_state(layout, STATE_INITIATOR_REPORT);
+
+    wait_for_state(layout, STATE_ESTABLISHED)?;
+    let shared_key = ecc
+        .compute_shared_dhkey(&priv_key, &peer_pub_key)
+        .map_err(|_| errno!(EIO, "failed to compute the shared key"))?;
+    let salt = bind_keys(&pub_key, &peer_pub_key);
+    Ok(SessionKeys {
+        send_key: derive_key(&shared_key, &salt, INITIATOR),
+        recv_key: derive_key(&shared_key, &salt, RESPONDER),
+    })
+}
+
+fn handshake_as_responder(shm: &IpcShm, layout: &ShmLayout) -> Result<SessionKeys> {
+    if layout
+        .responder_claimed
+        .compare_exchange(0, 1, Ordering::AcqRel, Ordering::Acquire)
+        .is_err()
+    {
+        return_errno!(EADDRINUSE, "the channel is connected by another responder");
+    }
+    // The name can be used by a new channel from now on
+    shm.unlink();
+
+    wait_for_state(layout, STATE_INITIATOR_HELLO)?;
+    let peer_hello = layout.read_hello(INITIATOR);
+    let peer_pub_key = peer_hello.pub_key;
+
+    let ecc = open_ecc()?;
+    let (priv_key, pub_key) = ecc
+        .create_key_pair()
+        .map_err(|_| errno!(EIO, "failed to create the key pair"))?;
+    let hello = Hello {
+        target_info: get_self_target()?,
+        pub_key,
+        report: create_report(
+            Some(&peer_hello.target_info),
+            Some(&bind_keys(&pub_key, &peer_pub_key)),
+        )?,
+    };
+    layout.write_hello(RESPONDER, &hello);
+    set_state(layout, STATE_RESPONDER_HELLO);
+
+    wait_for_state(layout, STATE_INITIATOR_REPORT)?;
+    let peer_report = layout.read_hello(INITIATOR).report;
+    verify_peer(&peer_report, &bind_keys(&peer_pub_key, &pub_key))?;
+    set_state(layout, STATE_ESTABLISHED);
+
+    let shared_key = ecc
+        .compute_shared_dhkey(&priv_key, &peer_pub_key)
+        .map_err(|_| errno!(EIO, "failed to compute the shared key"))?;
+    let salt = bind_keys(&peer_pub_key, &pub_key);
+    Ok(SessionKeys {
+        send_key: derive_key(&shared_key, &salt, RESPONDER),
+        recv_key: derive_key(&shared_key, &salt, INITIATOR),
+    })
+}
+
+fn open_ecc() -> Result<SgxEccHandle> {
+    let ecc = SgxEccHandle::new();
+    ecc.open()
+        .map_err(|_| errno!(EIO, "failed to open the ECC context"))?;
+    Ok(ecc)
+}
+
+fn set_state(layout: &ShmLayout, state: u32) {
+    layout.state.store(state, Ordering::Release);
+    shm::futex_wake(&layout.state);
+}
+
+// The state could be set to any value by the host, which is harmless as the
+// hellos are verified anyway
+fn wait_for_state(layout: &ShmLayout, state: u32) -> Result<()> {
+    loop {
+        let current_state = layout.state.load(Ordering::Acquire);
+        if current_state == STATE_FAILED {
+            return_errno!(ECONNREFUSED, "the handshake is failed by the peer");
+        }
+        if current_state >= state {
+            return Ok(());
+        }
+        shm::futex_wait(&layout.state, current_state);
+    }
+}
+
+// The report data of a side, which binds its own key and its peer's
+fn bind_keys(
+    own_pub_key: &sgx_ec256_public_t,
+    peer_pub_key: &sgx_ec256_public_t,
+) -> sgx_report_data_t {
+    let mut keys = Vec::with_capacity(SGX_ECP256_KEY_SIZE * 4);
+    for pub_key in &[own_pub_key, peer_pub_key] {
+        keys.extend_from_slice(&pub_key.gx);
+        keys.extend_from_slice(&pub_key.gy);
+    }
+    let hash = rsgx_sha256_slice(&keys).unwrap();
+    let mut report_data = sgx_report_data_t::default();
+    report_data.d[..hash.len()].copy_from_slice(&hash);
+    report_data
+}
+
+// The peer must be an enclave on the same platform that is the same product
+// as the current one, signed by the same key, in the same debug mode and of a
+// version no older than the current one, and its report must bind the keys
+fn verify_peer(report: &sgx_report_t, expected_data: &sgx_report_data_t) -> Result<()> {
+    verify_report(report).map_err(|_| errno!(EACCES, "the report of the peer is invalid"))?;
+    if report.body.report_data.d[..] != expected_data.d[..] {
+        return_errno!(EACCES, "the report of the peer does not match its key");
+    }
+    let self_report = create_report(None, None)?;
+    if report.body.mr_signer.m != self_report.body.mr_signer.m {
+        return_errno!(EACCES, "the peer is not signed by the same key");
+    }
+    if report.body.isv_prod_id != self_report.body.isv_prod_id {
+        return_errno!(EACCES, "the peer is not the same product");
+    }
+    // A debug enclave can be inspected by the host, so the secrets must not be
+    // shared between debug and production enclaves
+    let is_debug = |report: &sgx_report_t| report.body.attributes.flags & SGX_FLAGS_DEBUG != 0;
+    if is_debug(report) != is_debug(&self_report) {
+        return_errno!(EACCES, "the peer is not in the same debug mode");
+    }
+    // The older versions may have known vulnerabilities
+    if report.body.isv_svn < self_report.body.isv_svn {
+        return_errno!(EACCES, "the version of the peer is too old");
+    }
+    Ok(())
+}
+
+// The key of the direction in which the given side sends, which is derived
+// with HKDF-SHA256 (RFC 5869) from the shared secret, salted by the public
+// keys of both sides
+fn derive_key(
+    shared_key: &sgx_ec256_dh_shared_t,
+    salt: &sgx_report_data_t,
+    sender: usize,
+) -> sgx_aes_gcm_128bit_key_t {
+    let info: &[u8] = if sender == INITIATOR {
+        b"occlum_ipc initiator"
+    } else {
+        b"occlum_ipc responder"
+    };
+    // Extract
+    let prk = hmac_sha256(&salt.d[..SGX_SHA256_HASH_SIZE], &shared_key.s);
+    // Expand, for which one block is enough for the key
+    let mut info_and_counter = info.to_vec();
+    info_and_counter.push(1);
+    let okm = hmac_sha256(&prk, &info_and_counter);
+    let mut key = sgx_aes_gcm_128bit_key_t::default();
+    key.copy_from_slice(&okm[..key.len()]);
+    key
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> sgx_sha256_hash_t {
+    const BLOCK_SIZE: usize = 64;
+    let mut block_key = [0; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block_key[..SGX_SHA256_HASH_SIZE].copy_from_slice(&rsgx_sha256_slice(key).unwrap());
+    } else {
+        block_key[..key.len()].copy_from_slice(key);
+    }
+    let padded_key = |pad: u8| block_key.iter().map(|b| b ^ pad).collect::<Vec<u8>>();
+
+    let mut inner = padded_key(0x36);
+    inner.extend_from_slice(data);
+    let mut outer = padded_key(0x5c);
+    outer.extend_from_slice(&rsgx_sha256_slice(&inner).unwrap());
+    rsgx_sha256_slice(&outer).unwrap()
+}
+
+/// Encrypt a message into a record, which is the MAC followed by the
+/// ciphertext.
+pub fn seal(key: &sgx_aes_gcm_128bit_key_t, seq: u64, msg: &[u8]) -> Result<Vec<u8>> {
+    let mut mac = sgx_aes_gcm_128bit_tag_t::default();
+    let mut ciphertext = vec![0; msg.len()];
+    rsgx_rijndael128GCM_encrypt(key, msg, &nonce(seq), &[], &mut ciphertext, &mut mac)
+        .map_err(|_| errno!(EIO, "failed to encrypt the message"))?;
+    let mut record = mac.to_vec();
+    record.extend_from_slice(&ciphertext);
+    Ok(record)
+}
+
+/// Decrypt a record into a message.
+pub fn open(key: &sgx_aes_gcm_128bit_key_t, seq: u64, record: &[u8]) -> Result<Vec<u8>> {
+    if record.len() < MAC_SIZE {
+        return_errno!(EIO, "the record is too short");
+    }
+    let (mac_bytes, ciphertext) = record.split_at(MAC_SIZE);
+    let mut mac = sgx_aes_gcm_128bit_tag_t::default();
+    mac.copy_from_slice(mac_bytes);
+    let mut msg = vec![0; ciphertext.len()];
+    rsgx_rijndael128GCM_decrypt(key, ciphertext, &nonce(seq), &[], &mac, &mut msg)
+        .map_err(|_| errno!(EIO, "failed to authenticate the record"))?;
+    Ok(msg)
+}
+
+fn nonce(seq: u64) -> [u8; SGX_AESGCM_IV_SIZE] {
+    let mut nonce = [0; SGX_AESGCM_IV_SIZE];
+    nonce[..8].copy_from_slice(&seq.to_le_bytes());
+    nonce
+}
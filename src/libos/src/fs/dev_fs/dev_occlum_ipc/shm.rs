@@ -0,0 +1,313 @@
+//! The shared memory of a channel, which is outside the enclave.
+//!
+//! Everything in the shared memory can be modified by the host at any time,
+//! so the values read from it are validated before use and the data is only
+//! trusted after it is authenticated.
+
+use super::*;
+use std::cell::UnsafeCell;
+use std::ffi::CString;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use crate::time::timespec_t;
+
+/// The size of the ring buffer of each direction
+pub const RING_SIZE: usize = 64 * 1024;
+
+// The size of the length that precedes each record in a ring
+const RECORD_LEN_SIZE: usize = std::mem::size_of::<u32>();
+
+/// The max size of a record in a ring
+pub const RECORD_MAX_SIZE: usize = RING_SIZE - RECORD_LEN_SIZE;
+
+// The interval to check the state of a channel while waiting, in case a
+// wakeup is dropped by the host
+const WAIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The layout of the shared memory.
+#[repr(C)]
+pub struct ShmLayout {
+    /// The state of the handshake, which is also used as a futex
+    pub state: AtomicU32,
+    /// Whether a responder has connected to the channel
+    pub responder_claimed: AtomicU32,
+    // The hellos of the initiator and the responder
+    hellos: [UnsafeCell<Hello>; 2],
+    /// The rings from the initiator to the responder and the reverse
+    pub rings: [Ring; 2],
+}
+
+impl ShmLayout {
+    pub fn read_hello(&self, side: usize) -> Hello {
+        unsafe { std::ptr::read_volatile(self.hellos[side].get()) }
+    }
+
+    pub fn write_hello(&self, side: usize, hello: &Hello) {
+        unsafe { std::ptr::write_volatile(self.hellos[side].get(), *hello) }
+    }
+}
+
+/// The message of the handshake by one side of a channel.
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+pub struct Hello {
+    pub target_info: sgx_target_info_t,
+    pub pub_key: sgx_ec256_public_t,
+    pub report: sgx_report_t,
+}
+
+/// A ring buffer of records, each of which is a length of u32 followed by
+/// the bytes of the record.
+#[repr(C)]
+pub struct Ring {
+    // The total numbers of the bytes written to and read from the ring,
+    // which wrap around. They are also used as futexes.
+    tail: AtomicU32,
+    head: AtomicU32,
+    is_closed: AtomicU32,
+    data: UnsafeCell<[u8; RING_SIZE]>,
+}
+
+impl Ring {
+    /// Push a record if there is enough space. Only the writer of the ring
+    /// pushes records.
+    pub fn try_push(&self, record: &[u8]) -> Result<bool> {
+        debug_assert!(record.len() <= RECORD_MAX_SIZE);
+        let tail = self.tail.load(Ordering::Relaxed);
+        let used = self.used(tail)?;
+        if RING_SIZE - used < RECORD_LEN_SIZE + record.len() {
+            return Ok(false);
+        }
+
+        self.copy_in(tail, &(record.len() as u32).to_le_bytes());
+        self.copy_in(tail.wrapping_add(RECORD_LEN_SIZE as u32), record);
+        let new_tail = tail.wrapping_add((RECORD_LEN_SIZE + record.len()) as u32);
+        self.tail.store(new_tail, Ordering::Release);
+        Ok(true)
+    }
+
+    /// Pop a record if there is any. Only the reader of the ring pops records.
+    pub fn try_pop(&self) -> Result<Option<Vec<u8>>> {
+        let tail = self.tail.load(Ordering::Acquire);
+        let head = self.head.load(Ordering::Relaxed);
+        let used = self.used(tail)?;
+        if used == 0 {
+            return Ok(None);
+        }
+        if used < RECORD_LEN_SIZE {
+            return_errno!(EIO, "the ring is corrupted");
+        }
+
+        let mut len_bytes = [0; RECORD_LEN_SIZE];
+        self.copy_out(head, &mut len_bytes);
+        let len = u32::from_le_bytes(len_bytes) as usize;
+        if len > used - RECORD_LEN_SIZE {
+            return_errno!(EIO, "the ring is corrupted");
+        }
+        let mut record = vec![0; len];
+        self.copy_out(head.wrapping_add(RECORD_LEN_SIZE as u32), &mut record);
+        let new_head = head.wrapping_add((RECORD_LEN_SIZE + len) as u32);
+        self.head.store(new_head, Ordering::Release);
+        Ok(Some(record))
+    }
+
+    /// Whether a nonempty record can be pushed.
+    pub fn has_space(&self) -> bool {
+        let tail = self.tail.load(Ordering::Relaxed);
+        self.used(tail)
+            .map(|used| used < RING_SIZE - RECORD_LEN_SIZE)
+            .unwrap_or(true)
+    }
+
+    /// Whether there is any record to pop.
+    pub fn has_record(&self) -> bool {
+        let tail = self.tail.load(Ordering::Acquire);
+        self.used(tail).map(|used| used > 0).unwrap_or(true)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.is_closed.load(Ordering::Acquire) != 0
+    }
+
+    pub fn close(&self) {
+        self.is_closed.store(1, Ordering::Release);
+        futex_wake(&self.tail);
+        futex_wake(&self.head);
+    }
+
+    /// Wait for a record until the ring is changed, or the wait interval
+    /// passes.
+    pub fn wait_for_record(&self, tail: u32) {
+        futex_wait(&self.tail, tail);
+    }
+
+    /// Wait for the space until the ring is changed, or the wait interval
+    /// passes.
+    pub fn wait_for_space(&self, head: u32) {
+        futex_wait(&self.head, head);
+    }
+
+    pub fn tail(&self) -> u32 {
+        self.tail.load(Ordering::Acquire)
+    }
+
+    pub fn head(&self) -> u32 {
+        self.head.load(Ordering::Acquire)
+    }
+
+    pub fn wake_reader(&self) {
+        futex_wake(&self.tail);
+    }
+
+    pub fn wake_writer(&self) {
+        futex_wake(&self.head);
+    }
+
+    // The number of the used bytes, given the tail
+    fn used(&self, tail: u32) -> Result<usize> {
+        let used = tail.wrapping_sub(self.head.load(Ordering::Acquire)) as usize;
+        if used > RING_SIZE {
+            return_errno!(EIO, "the ring is corrupted");
+        }
+        Ok(used)
+    }
+
+    fn copy_in(&self, pos: u32, src: &[u8]) {
+        let data = self.data.get() as *mut u8;
+        let offset = pos as usize % RING_SIZE;
+        let first_len = src.len().min(RING_SIZE - offset);
+        unsafe {
+            std::ptr::copy_nonoverlapping(src.as_ptr(), data.add(offset), first_len);
+            std::ptr::copy_nonoverlapping(src.as_ptr().add(first_len), data, src.len() - first_len);
+        }
+    }
+
+    fn copy_out(&self, pos: u32, dst: &mut [u8]) {
+        let data = self.data.get() as *const u8;
+        let offset = pos as usize % RING_SIZE;
+        let first_len = dst.len().min(RING_SIZE - offset);
+        unsafe {
+            std::ptr::copy_nonoverlapping(data.add(offset), dst.as_mut_ptr(), first_len);
+            std::ptr::copy_nonoverlapping(
+                data,
+                dst.as_mut_ptr().add(first_len),
+                dst.len() - first_len,
+            );
+        }
+    }
+}
+
+/// The shared memory of a channel mapped by the host.
+pub struct IpcShm {
+    name: CString,
+    layout: *mut ShmLayout,
+    is_creator: bool,
+}
+
+// The shared memory is only accessed by atomics or copies
+unsafe impl Send for IpcShm {}
+unsafe impl Sync for IpcShm {}
+
+impl IpcShm {
+    /// Open the shared memory of the name, which is created if it does not
+    /// exist.
+    pub fn open(name: &str) -> Result<Self> {
+        let name = CString::new(name).map_err(|_| errno!(EINVAL, "invalid name"))?;
+        let size = std::mem::size_of::<ShmLayout>();
+        let mut is_creator = 0;
+        let layout = unsafe {
+            let mut addr: *mut c_void = std::ptr::null_mut();
+            let status = occlum_ocall_ipc_shm_open(&mut addr, name.as_ptr(), size, &mut is_creator);
+            assert!(status == sgx_status_t::SGX_SUCCESS);
+            if addr.is_null() {
+                let errno = Errno::from(libc::errno() as u32);
+                return_errno!(errno, "failed to open the shared memory");
+            }
+            addr as *mut ShmLayout
+        };
+        // The host could give an address inside the enclave
+        if !sgx_trts::trts::rsgx_raw_is_outside_enclave(layout as *const u8, size) {
+            panic!("the shared memory must be outside the enclave");
+        }
+        Ok(Self {
+            name,
+            layout,
+            is_creator: is_creator != 0,
+        })
+    }
+
+    pub fn is_creator(&self) -> bool {
+        self.is_creator
+    }
+
+    pub fn layout(&self) -> &ShmLayout {
+        unsafe { &*self.layout }
+    }
+
+    /// Remove the name of the shared memory from the host, so that the name
+    /// can be used by a new channel.
+    pub fn unlink(&self) {
+        let mut retval = 0;
+        let status = unsafe { occlum_ocall_ipc_shm_unlink(&mut retval, self.name.as_ptr()) };
+        assert!(status == sgx_status_t::SGX_SUCCESS);
+        if retval < 0 {
+            warn!("failed to unlink the shared memory: errno = {}", unsafe {
+                libc::errno()
+            });
+        }
+    }
+}
+
+impl Drop for IpcShm {
+    fn drop(&mut self) {
+        let size = std::mem::size_of::<ShmLayout>();
+        let mut retval = 0;
+        let status =
+            unsafe { occlum_ocall_ipc_shm_close(&mut retval, self.layout as *mut c_void, size) };
+        assert!(status == sgx_status_t::SGX_SUCCESS);
+    }
+}
+
+/// Wait on the futex in the shared memory until its value is changed or the
+/// wait interval passes.
+pub fn futex_wait(futex: &AtomicU32, val: u32) {
+    let timeout = timespec_t::from(WAIT_INTERVAL);
+    let mut retval = 0;
+    let status = unsafe {
+        occlum_ocall_ipc_futex_wait(
+            &mut retval,
+            futex as *const AtomicU32 as *mut i32,
+            val as i32,
+            &timeout,
+        )
+    };
+    assert!(status == sgx_status_t::SGX_SUCCESS);
+}
+
+/// Wake up all the waiters on the futex in the shared memory.
+pub fn futex_wake(futex: &AtomicU32) {
+    let mut retval = 0;
+    let status =
+        unsafe { occlum_ocall_ipc_futex_wake(&mut retval, futex as *const AtomicU32 as *mut i32) };
+    assert!(status == sgx_status_t::SGX_SUCCESS);
+}
+
+extern "C" {
+    fn occlum_ocall_ipc_shm_open(
+        retval: *mut *mut c_void,
+        name: *const c_char,
+        size: usize,
+        is_creator: *mut i32,
+    ) -> sgx_status_t;
+    fn occlum_ocall_ipc_shm_unlink(retval: *mut i32, name: *const c_char) -> sgx_status_t;
+    fn occlum_ocall_ipc_shm_close(retval: *mut i32, addr: *mut c_void, size: usize)
+        -> sgx_status_t;
+    fn occlum_ocall_ipc_futex_wait(
+        retval: *mut i32,
+        addr: *mut i32,
+        val: i32,
+        timeout: *const timespec_t,
+    ) -> sgx_status_t;
+    fn occlum_ocall_ipc_futex_wake(retval: *mut i32, addr: *mut i32) -> sgx_status_t;
+}
@@ -0,0 +1,115 @@
+//! SEFS device (/dev/sefs).
+//!
+//! The device re-encrypts the writable layer of the root file system with a
+//! new key, i.e., key rotation, and reports the progress of the re-encryption.
+
+use super::*;
+use crate::fs::sefs::{rekey_progress, rekey_writable_layer};
+
+/// Ioctl to re-encrypt the writable layer with a new key, which blocks until
+/// the re-encryption is done
+const SEFS_CMD_NUM_REKEY: u32 =
+    StructuredIoctlNum::new::<IoctlRekeyArg>(0, SEFS_MAGIC_CHAR, StructuredIoctlArgType::Input)
+        .as_u32();
+
+/// Ioctl to get the progress of the re-encryption
+const SEFS_CMD_NUM_GET_REKEY_PROGRESS: u32 = StructuredIoctlNum::new::<IoctlRekeyProgressArg>(
+    1,
+    SEFS_MAGIC_CHAR,
+    StructuredIoctlArgType::Output,
+)
+.as_u32();
+
+/// A magical number that distinguishes SEFS ioctls for other ioctls
+const SEFS_MAGIC_CHAR: u8 = 'f' as u8;
+
+#[derive(Debug)]
+pub struct DevSefs;
+
+impl INode for DevSefs {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> vfs::Result<usize> {
+        Err(FsError::PermError)
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> vfs::Result<usize> {
+        Err(FsError::PermError)
+    }
+
+    fn poll(&self) -> vfs::Result<vfs::PollStatus> {
+        Err(FsError::PermError)
+    }
+
+    fn metadata(&self) -> vfs::Result<Metadata> {
+        Ok(Metadata {
+            dev: 1,
+            inode: 0,
+            size: 0,
+            blk_size: 0,
+            blocks: 0,
+            atime: Timespec { sec: 0, nsec: 0 },
+            mtime: Timespec { sec: 0, nsec: 0 },
+            ctime: Timespec { sec: 0, nsec: 0 },
+            type_: vfs::FileType::CharDevice,
+            mode: 0o600,
+            nlinks: 1,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+        })
+    }
+
+    fn io_control(&self, cmd: u32, data: usize) -> vfs::Result<()> {
+        let mut ioctl_cmd =
+            unsafe { IoctlCmd::new(cmd, data as *mut u8).map_err(|_| FsError::InvalidParam)? };
+        self.ioctl(&mut ioctl_cmd).map_err(|e| {
+            error!("{}", e.backtrace());
+            FsError::IOCTLError
+        })?;
+        Ok(())
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl DevSefs {
+    fn ioctl(&self, cmd: &mut IoctlCmd) -> Result<i32> {
+        let nonbuiltin_cmd = match cmd {
+            IoctlCmd::NonBuiltin(nonbuiltin_cmd) => nonbuiltin_cmd,
+            _ => return_errno!(EINVAL, "unknown ioctl cmd for /dev/sefs"),
+        };
+        let cmd_num = nonbuiltin_cmd.cmd_num().as_u32();
+        match cmd_num {
+            SEFS_CMD_NUM_REKEY => {
+                let arg = nonbuiltin_cmd.arg::<IoctlRekeyArg>()?;
+                // Write back all the dirty data with the old key first
+                ROOT_INODE.read().unwrap().fs().sync()?;
+                rekey_writable_layer(&arg.new_key)?;
+            }
+            SEFS_CMD_NUM_GET_REKEY_PROGRESS => {
+                let arg = nonbuiltin_cmd.arg_mut::<IoctlRekeyProgressArg>()?;
+                let progress = rekey_progress();
+                arg.is_running = progress.is_running() as u32;
+                arg.total_files = progress.total_files() as u32;
+                arg.done_files = progress.done_files() as u32;
+            }
+            _ => {
+                return_errno!(ENOSYS, "unknown ioctl cmd for /dev/sefs");
+            }
+        }
+        Ok(0)
+    }
+}
+
+#[repr(C)]
+struct IoctlRekeyArg {
+    new_key: sgx_key_128bit_t, // Input
+}
+
+#[repr(C)]
+struct IoctlRekeyProgressArg {
+    is_running: u32,  // Output
+    total_files: u32, // Output
+    done_files: u32,  // Output
+}
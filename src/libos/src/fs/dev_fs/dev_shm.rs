@@ -0,0 +1,218 @@
+use super::*;
+use rcore_fs::vfs::{FsInfo, PollStatus};
+use rcore_fs_ramfs::RamFS;
+use std::sync::Weak;
+
+/// The maximum total size of the files in `/dev/shm`.
+///
+/// The files are kept in the kernel heap of the enclave, so the limit is much
+/// smaller than the default of tmpfs on Linux, which is half of the RAM.
+const SHM_FS_SIZE_LIMIT: usize = 16 * 1024 * 1024;
+
+/// The device ID in the metadata of the inodes of ShmFS, which tells the
+/// POSIX shared memory objects from the other files.
+const SHM_FS_DEV_ID: usize = 0x5348_4d46;
+
+lazy_static! {
+    /// The tmpfs at `/dev/shm`, on which the POSIX shared memory objects are
+    /// created by `shm_open`.
+    pub static ref SHM_FS: Arc<ShmFS> = ShmFS::new(SHM_FS_SIZE_LIMIT);
+}
+
+/// A size-limited RamFS, i.e., a tmpfs.
+pub struct ShmFS {
+    ramfs: Arc<RamFS>,
+    size_limit: usize,
+    // Serialize the operations that grow files, so that the total size is
+    // checked and updated atomically
+    grow_lock: SgxMutex<()>,
+    self_ref: Weak<ShmFS>,
+}
+
+impl ShmFS {
+    fn new(size_limit: usize) -> Arc<Self> {
+        let fs = Arc::new(Self {
+            ramfs: RamFS::new(),
+            size_limit,
+            grow_lock: SgxMutex::new(()),
+            self_ref: Weak::default(),
+        });
+        let weak = Arc::downgrade(&fs);
+        let ptr = Arc::into_raw(fs) as *mut Self;
+        unsafe {
+            (*ptr).self_ref = weak;
+            Arc::from_raw(ptr)
+        }
+    }
+
+    pub fn size_limit(&self) -> usize {
+        self.size_limit
+    }
+
+    /// The total size of the files that are reachable from the root.
+    pub fn used_size(&self) -> usize {
+        fn dir_size(dir: &Arc<dyn INode>) -> usize {
+            let mut size = 0;
+            let names = (0..)
+                .map(|id| dir.get_entry(id))
+                .take_while(|name| name.is_ok());
+            for name in names.map(|name| name.unwrap()) {
+                if name == "." || name == ".." {
+                    continue;
+                }
+                let inode = match dir.find(&name) {
+                    Ok(inode) => inode,
+                    Err(_) => continue,
+                };
+                match inode.metadata() {
+                    Ok(metadata) if metadata.type_ == FileType::Dir => size += dir_size(&inode),
+                    Ok(metadata) => size += metadata.size,
+                    Err(_) => {}
+                }
+            }
+            size
+        }
+        dir_size(&self.ramfs.root_inode())
+    }
+
+    /// Check whether the inode is in ShmFS, i.e., a POSIX shared memory object.
+    pub fn contains(&self, inode: &Arc<dyn INode>) -> bool {
+        inode
+            .metadata()
+            .map(|metadata| metadata.dev == SHM_FS_DEV_ID)
+            .unwrap_or(false)
+    }
+}
+
+impl FileSystem for ShmFS {
+    fn sync(&self) -> vfs::Result<()> {
+        Ok(())
+    }
+
+    fn root_inode(&self) -> Arc<dyn INode> {
+        self.wrap_inode(self.ramfs.root_inode())
+    }
+
+    fn info(&self) -> FsInfo {
+        self.ramfs.info()
+    }
+}
+
+impl ShmFS {
+    fn wrap_inode(&self, inode: Arc<dyn INode>) -> Arc<dyn INode> {
+        Arc::new(ShmINode {
+            inner: inode,
+            fs: self.self_ref.upgrade().unwrap(),
+        })
+    }
+}
+
+/// The inode of ShmFS, which wraps an inode of RamFS and limits the growth of
+/// the files.
+pub struct ShmINode {
+    inner: Arc<dyn INode>,
+    fs: Arc<ShmFS>,
+}
+
+impl ShmINode {
+    /// Run the operation that may grow the file to the new size, failing with
+    /// ENOSPC if the total size of the files would exceed the limit.
+    fn grow_with<T, F>(&self, new_size: usize, f: F) -> vfs::Result<T>
+    where
+        F: FnOnce() -> vfs::Result<T>,
+    {
+        let _grow_guard = self.fs.grow_lock.lock().unwrap();
+        let old_size = self.inner.metadata()?.size;
+        if new_size > old_size && self.fs.used_size() + (new_size - old_size) > self.fs.size_limit {
+            return Err(FsError::NoDeviceSpace);
+        }
+        f()
+    }
+
+    fn inner_of(inode: &Arc<dyn INode>) -> vfs::Result<&Arc<dyn INode>> {
+        inode
+            .downcast_ref::<ShmINode>()
+            .map(|inode| &inode.inner)
+            .ok_or(FsError::NotSameFs)
+    }
+}
+
+impl INode for ShmINode {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> vfs::Result<usize> {
+        self.inner.read_at(offset, buf)
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> vfs::Result<usize> {
+        self.grow_with(offset + buf.len(), || self.inner.write_at(offset, buf))
+    }
+
+    fn poll(&self) -> vfs::Result<PollStatus> {
+        self.inner.poll()
+    }
+
+    fn metadata(&self) -> vfs::Result<Metadata> {
+        let mut metadata = self.inner.metadata()?;
+        metadata.dev = SHM_FS_DEV_ID;
+        Ok(metadata)
+    }
+
+    fn set_metadata(&self, metadata: &Metadata) -> vfs::Result<()> {
+        self.inner.set_metadata(metadata)
+    }
+
+    fn sync_all(&self) -> vfs::Result<()> {
+        self.inner.sync_all()
+    }
+
+    fn sync_data(&self) -> vfs::Result<()> {
+        self.inner.sync_data()
+    }
+
+    fn resize(&self, len: usize) -> vfs::Result<()> {
+        self.grow_with(len, || self.inner.resize(len))
+    }
+
+    fn fallocate(&self, mode: u32, offset: u64, len: u64) -> vfs::Result<()> {
+        let new_size = (offset + len) as usize;
+        self.grow_with(new_size, || self.inner.fallocate(mode, offset, len))
+    }
+
+    fn create(&self, name: &str, type_: FileType, mode: u32) -> vfs::Result<Arc<dyn INode>> {
+        let inode = self.inner.create(name, type_, mode)?;
+        Ok(self.fs.wrap_inode(inode))
+    }
+
+    fn link(&self, name: &str, other: &Arc<dyn INode>) -> vfs::Result<()> {
+        self.inner.link(name, Self::inner_of(other)?)
+    }
+
+    fn unlink(&self, name: &str) -> vfs::Result<()> {
+        self.inner.unlink(name)
+    }
+
+    fn move_(&self, old_name: &str, target: &Arc<dyn INode>, new_name: &str) -> vfs::Result<()> {
+        self.inner
+            .move_(old_name, Self::inner_of(target)?, new_name)
+    }
+
+    fn find(&self, name: &str) -> vfs::Result<Arc<dyn INode>> {
+        let inode = self.inner.find(name)?;
+        Ok(self.fs.wrap_inode(inode))
+    }
+
+    fn get_entry(&self, id: usize) -> vfs::Result<String> {
+        self.inner.get_entry(id)
+    }
+
+    fn io_control(&self, cmd: u32, data: usize) -> vfs::Result<()> {
+        self.inner.io_control(cmd, data)
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.clone()
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
@@ -0,0 +1,55 @@
+use super::*;
+
+/// A symbolic link in DevFS, e.g., `/dev/stdin` which links to `/proc/self/fd/0`.
+#[derive(Debug)]
+pub struct DevSymLink {
+    target: &'static str,
+}
+
+impl DevSymLink {
+    pub fn new(target: &'static str) -> Self {
+        Self { target }
+    }
+}
+
+impl INode for DevSymLink {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> vfs::Result<usize> {
+        let data = self.target.as_bytes();
+        let start = data.len().min(offset);
+        let end = data.len().min(offset + buf.len());
+        let len = end - start;
+        buf[0..len].copy_from_slice(&data[start..end]);
+        Ok(len)
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> vfs::Result<usize> {
+        Err(FsError::PermError)
+    }
+
+    fn poll(&self) -> vfs::Result<vfs::PollStatus> {
+        Err(FsError::NotFile)
+    }
+
+    fn metadata(&self) -> vfs::Result<Metadata> {
+        Ok(Metadata {
+            dev: 1,
+            inode: 0,
+            size: self.target.len(),
+            blk_size: 0,
+            blocks: 0,
+            atime: Timespec { sec: 0, nsec: 0 },
+            mtime: Timespec { sec: 0, nsec: 0 },
+            ctime: Timespec { sec: 0, nsec: 0 },
+            type_: vfs::FileType::SymLink,
+            mode: 0o777,
+            nlinks: 1,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+        })
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
@@ -0,0 +1,61 @@
+use super::*;
+
+/// The controlling terminal of the processes, which is the terminal of the
+/// host process that runs the enclave.
+///
+/// Unlike the stdio of a process, the terminal is not affected by the
+/// redirection of the file descriptors.
+#[derive(Debug)]
+pub struct DevTty;
+
+impl INode for DevTty {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> vfs::Result<usize> {
+        let (buf_ptr, buf_len) = buf.as_mut().as_mut_ptr_and_len();
+        let ret = unsafe { libc::ocall::read(libc::STDIN_FILENO, buf_ptr as *mut c_void, buf_len) };
+        if ret < 0 {
+            return Err(FsError::DeviceError);
+        }
+        Ok(ret as usize)
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> vfs::Result<usize> {
+        let (buf_ptr, buf_len) = buf.as_ptr_and_len();
+        let ret =
+            unsafe { libc::ocall::write(libc::STDOUT_FILENO, buf_ptr as *const c_void, buf_len) };
+        if ret < 0 {
+            return Err(FsError::DeviceError);
+        }
+        Ok(ret as usize)
+    }
+
+    fn poll(&self) -> vfs::Result<vfs::PollStatus> {
+        Ok(vfs::PollStatus {
+            read: true,
+            write: true,
+            error: false,
+        })
+    }
+
+    fn metadata(&self) -> vfs::Result<Metadata> {
+        Ok(Metadata {
+            dev: 1,
+            inode: 0,
+            size: 0,
+            blk_size: 0,
+            blocks: 0,
+            atime: Timespec { sec: 0, nsec: 0 },
+            mtime: Timespec { sec: 0, nsec: 0 },
+            ctime: Timespec { sec: 0, nsec: 0 },
+            type_: vfs::FileType::CharDevice,
+            mode: 0o666,
+            nlinks: 1,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+        })
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
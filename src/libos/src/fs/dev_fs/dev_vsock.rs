@@ -0,0 +1,84 @@
+//! Vsock device (/dev/vsock).
+//!
+//! Same as Linux, the device only provides the context ID (CID) of the local
+//! machine, which is got from the /dev/vsock of the host.
+
+use super::*;
+
+#[derive(Debug)]
+pub struct DevVsock;
+
+impl INode for DevVsock {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> vfs::Result<usize> {
+        Err(FsError::PermError)
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> vfs::Result<usize> {
+        Err(FsError::PermError)
+    }
+
+    fn poll(&self) -> vfs::Result<vfs::PollStatus> {
+        Err(FsError::PermError)
+    }
+
+    fn metadata(&self) -> vfs::Result<Metadata> {
+        Ok(Metadata {
+            dev: 1,
+            inode: 0,
+            size: 0,
+            blk_size: 0,
+            blocks: 0,
+            atime: Timespec { sec: 0, nsec: 0 },
+            mtime: Timespec { sec: 0, nsec: 0 },
+            ctime: Timespec { sec: 0, nsec: 0 },
+            type_: vfs::FileType::CharDevice,
+            mode: 0o444,
+            nlinks: 1,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+        })
+    }
+
+    fn io_control(&self, cmd: u32, data: usize) -> vfs::Result<()> {
+        let mut ioctl_cmd =
+            unsafe { IoctlCmd::new(cmd, data as *mut u8).map_err(|_| FsError::InvalidParam)? };
+        self.ioctl(&mut ioctl_cmd).map_err(|e| {
+            error!("{}", e.backtrace());
+            FsError::IOCTLError
+        })?;
+        Ok(())
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl DevVsock {
+    fn ioctl(&self, cmd: &mut IoctlCmd) -> Result<i32> {
+        match cmd {
+            IoctlCmd::IOCTL_VM_SOCKETS_GET_LOCAL_CID(cid) => {
+                **cid = get_local_cid()?;
+                Ok(0)
+            }
+            _ => return_errno!(EINVAL, "unknown ioctl cmd for /dev/vsock"),
+        }
+    }
+}
+
+// The CID is not cached, as it may change when the VM is migrated
+fn get_local_cid() -> Result<u32> {
+    let mut cid = 0;
+    try_libc!({
+        let mut retval = 0;
+        let status = occlum_ocall_vsock_get_local_cid(&mut retval, &mut cid);
+        assert!(status == sgx_status_t::SGX_SUCCESS);
+        retval
+    });
+    Ok(cid)
+}
+
+extern "C" {
+    fn occlum_ocall_vsock_get_local_cid(retval: *mut i32, cid: *mut u32) -> sgx_status_t;
+}
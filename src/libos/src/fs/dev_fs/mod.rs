@@ -3,13 +3,26 @@ use rcore_fs::vfs;
 use rcore_fs_devfs::DevFS;
 
 use self::dev_null::DevNull;
+use self::dev_occlum_ipc::DevOcclumIpc;
 use self::dev_random::DevRandom;
+use self::dev_sefs::DevSefs;
 use self::dev_sgx::DevSgx;
+use self::dev_symlink::DevSymLink;
+use self::dev_tty::DevTty;
+use self::dev_vsock::DevVsock;
 use self::dev_zero::DevZero;
 
+pub use self::dev_shm::{ShmFS, SHM_FS};
+
 mod dev_null;
+mod dev_occlum_ipc;
 mod dev_random;
+mod dev_sefs;
 mod dev_sgx;
+mod dev_shm;
+mod dev_symlink;
+mod dev_tty;
+mod dev_vsock;
 mod dev_zero;
 
 /// API to initialize the DevFS
@@ -25,6 +38,26 @@ pub fn init_devfs() -> Result<Arc<DevFS>> {
     devfs.add("arandom", Arc::clone(&dev_random))?;
     let dev_sgx = Arc::new(DevSgx) as _;
     devfs.add("sgx", dev_sgx)?;
-    // TODO: Add stdio(stdin, stdout, stderr) into DevFS
+    let dev_sefs = Arc::new(DevSefs) as _;
+    devfs.add("sefs", dev_sefs)?;
+    let dev_occlum_ipc = Arc::new(DevOcclumIpc) as _;
+    devfs.add("occlum_ipc", dev_occlum_ipc)?;
+    let dev_tty = Arc::new(DevTty) as _;
+    devfs.add("tty", dev_tty)?;
+    let dev_vsock = Arc::new(DevVsock) as _;
+    devfs.add("vsock", dev_vsock)?;
+    // The file descriptors of the current process, same as Linux
+    let dev_fd = Arc::new(DevSymLink::new("/proc/self/fd")) as _;
+    devfs.add("fd", dev_fd)?;
+    let dev_stdin = Arc::new(DevSymLink::new("/proc/self/fd/0")) as _;
+    devfs.add("stdin", dev_stdin)?;
+    let dev_stdout = Arc::new(DevSymLink::new("/proc/self/fd/1")) as _;
+    devfs.add("stdout", dev_stdout)?;
+    let dev_stderr = Arc::new(DevSymLink::new("/proc/self/fd/2")) as _;
+    devfs.add("stderr", dev_stderr)?;
+    // The tmpfs for the POSIX shared memory objects
+    devfs.add("shm", SHM_FS.root_inode())?;
+    // The directory of the POSIX message queues
+    devfs.add("mqueue", crate::ipc::MQUEUE_FS.root_inode())?;
     Ok(devfs)
 }
@@ -1,36 +1,82 @@
 use super::*;
 
-use atomic::{Atomic, Ordering};
+use std::sync::atomic::{AtomicBool, Ordering};
 
-/// Native Linux eventfd
-// TODO: move the implementaion of eventfd into libos to defend against Iago attacks from OCalls
-#[derive(Debug)]
+use crate::events::{Waiter, WaiterQueue};
+
+/// An eventfd implemented inside LibOS.
+///
+/// An eventfd maintains an unsigned 64-bit counter. A write adds the given value
+/// to the counter; a read returns the value of the counter and resets it to
+/// zero, or, in semaphore mode, decreases the counter by one and returns one.
+/// Implementing eventfd inside LibOS defends against Iago attacks from the host
+/// and saves OCalls.
 pub struct EventFile {
-    host_fd: HostFd,
-    host_events: Atomic<IoEvents>,
+    counter: SgxMutex<u64>,
+    is_semaphore: bool,
+    is_nonblocking: AtomicBool,
+    // All threads that are blocked on reading or writing this file.
+    waiters: WaiterQueue,
     notifier: IoNotifier,
 }
 
 impl EventFile {
+    /// The maximum value of the counter.
+    const MAX_COUNTER: u64 = u64::MAX - 1;
+
     pub fn new(init_val: u32, flags: EventCreationFlags) -> Result<Self> {
-        let raw_host_fd = try_libc!({
-            let mut ret: i32 = 0;
-            let status = occlum_ocall_eventfd(&mut ret, init_val, flags.bits());
-            assert!(status == sgx_status_t::SGX_SUCCESS);
-            ret
-        }) as FileDesc;
-        let host_fd = HostFd::new(raw_host_fd);
-        let host_events = Atomic::new(IoEvents::empty());
+        let counter = SgxMutex::new(init_val as u64);
+        let is_semaphore = flags.contains(EventCreationFlags::EFD_SEMAPHORE);
+        let is_nonblocking = AtomicBool::new(flags.contains(EventCreationFlags::EFD_NONBLOCK));
+        let waiters = WaiterQueue::new();
         let notifier = IoNotifier::new();
         Ok(Self {
-            host_fd,
-            host_events,
+            counter,
+            is_semaphore,
+            is_nonblocking,
+            waiters,
             notifier,
         })
     }
 
-    pub fn host_fd(&self) -> c_int {
-        self.host_fd.to_raw() as c_int
+    /// Add one to the counter without blocking, which notifies the completion
+    /// of an AIO request. Same as Linux, the counter saturates if it is full.
+    pub fn signal(&self) {
+        {
+            let mut counter = self.counter.lock().unwrap();
+            if *counter < Self::MAX_COUNTER {
+                *counter += 1;
+            }
+        }
+        self.after_counter_changed();
+    }
+
+    fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Acquire)
+    }
+
+    fn try_read(&self) -> Option<u64> {
+        let mut counter = self.counter.lock().unwrap();
+        if *counter == 0 {
+            return None;
+        }
+        let val = if self.is_semaphore { 1 } else { *counter };
+        *counter -= val;
+        Some(val)
+    }
+
+    fn try_write(&self, val: u64) -> bool {
+        let mut counter = self.counter.lock().unwrap();
+        if val > Self::MAX_COUNTER - *counter {
+            return false;
+        }
+        *counter += val;
+        true
+    }
+
+    fn after_counter_changed(&self) {
+        self.notifier.broadcast(&self.poll_new());
+        self.waiters.dequeue_and_wake_all();
     }
 }
 
@@ -45,32 +91,65 @@ bitflags! {
     }
 }
 
-extern "C" {
-    fn occlum_ocall_eventfd(ret: *mut i32, init_val: u32, flags: i32) -> sgx_status_t;
-}
-
 impl File for EventFile {
     fn read(&self, buf: &mut [u8]) -> Result<usize> {
-        let (buf_ptr, buf_len) = buf.as_mut().as_mut_ptr_and_len();
+        const VAL_LEN: usize = std::mem::size_of::<u64>();
+        if buf.len() < VAL_LEN {
+            return_errno!(EINVAL, "the buffer is too small");
+        }
+
+        let waiter = Waiter::new();
+        let val = loop {
+            self.waiters.reset_and_enqueue(&waiter);
+
+            if let Some(val) = self.try_read() {
+                break val;
+            }
+            if self.is_nonblocking() {
+                return_errno!(EAGAIN, "the counter is zero");
+            }
 
-        let ret = try_libc!(libc::ocall::read(
-            self.host_fd.to_raw() as i32,
-            buf_ptr as *mut c_void,
-            buf_len
-        )) as usize;
-        assert!(ret <= buf.len());
-        Ok(ret)
+            waiter.wait(None)?;
+        };
+        self.after_counter_changed();
+
+        buf[..VAL_LEN].copy_from_slice(&val.to_ne_bytes());
+        Ok(VAL_LEN)
     }
 
     fn write(&self, buf: &[u8]) -> Result<usize> {
-        let (buf_ptr, buf_len) = buf.as_ptr_and_len();
-        let ret = try_libc!(libc::ocall::write(
-            self.host_fd.to_raw() as i32,
-            buf_ptr as *const c_void,
-            buf_len
-        )) as usize;
-        assert!(ret <= buf.len());
-        Ok(ret)
+        const VAL_LEN: usize = std::mem::size_of::<u64>();
+        if buf.len() < VAL_LEN {
+            return_errno!(EINVAL, "the buffer is too small");
+        }
+        let val = {
+            let mut bytes = [0u8; VAL_LEN];
+            bytes.copy_from_slice(&buf[..VAL_LEN]);
+            u64::from_ne_bytes(bytes)
+        };
+        if val == u64::MAX {
+            return_errno!(EINVAL, "the value is too large");
+        }
+        if val == 0 {
+            return Ok(VAL_LEN);
+        }
+
+        let waiter = Waiter::new();
+        loop {
+            self.waiters.reset_and_enqueue(&waiter);
+
+            if self.try_write(val) {
+                break;
+            }
+            if self.is_nonblocking() {
+                return_errno!(EAGAIN, "the counter would overflow");
+            }
+
+            waiter.wait(None)?;
+        }
+        self.after_counter_changed();
+
+        Ok(VAL_LEN)
     }
 
     fn access_mode(&self) -> Result<AccessMode> {
@@ -78,50 +157,54 @@ impl File for EventFile {
     }
 
     fn status_flags(&self) -> Result<StatusFlags> {
-        let ret = try_libc!(libc::ocall::fcntl_arg0(self.host_fd(), libc::F_GETFL));
-        Ok(StatusFlags::from_bits_truncate(ret as u32))
+        if self.is_nonblocking() {
+            Ok(StatusFlags::O_NONBLOCK)
+        } else {
+            Ok(StatusFlags::empty())
+        }
     }
 
     fn set_status_flags(&self, new_status_flags: StatusFlags) -> Result<()> {
-        let valid_flags_mask = StatusFlags::O_APPEND
-            | StatusFlags::O_ASYNC
-            | StatusFlags::O_DIRECT
-            | StatusFlags::O_NOATIME
-            | StatusFlags::O_NONBLOCK;
-        let raw_status_flags = (new_status_flags & valid_flags_mask).bits();
-        try_libc!(libc::ocall::fcntl_arg1(
-            self.host_fd(),
-            libc::F_SETFL,
-            raw_status_flags as c_int
-        ));
+        let nonblocking = new_status_flags.contains(StatusFlags::O_NONBLOCK);
+        self.is_nonblocking.store(nonblocking, Ordering::Release);
+        if nonblocking {
+            // Wake all threads that are blocked on reading/writing this file
+            self.waiters.dequeue_and_wake_all();
+        }
         Ok(())
     }
 
     fn poll_new(&self) -> IoEvents {
-        self.host_events.load(Ordering::Acquire)
+        let counter = *self.counter.lock().unwrap();
+        let mut events = IoEvents::empty();
+        if counter > 0 {
+            events |= IoEvents::IN;
+        }
+        if counter < Self::MAX_COUNTER {
+            events |= IoEvents::OUT;
+        }
+        events
     }
 
     fn notifier(&self) -> Option<&IoNotifier> {
         Some(&self.notifier)
     }
 
-    fn host_fd(&self) -> Option<&HostFd> {
-        Some(&self.host_fd)
-    }
-
-    fn update_host_events(&self, ready: &IoEvents, mask: &IoEvents, trigger_notifier: bool) {
-        self.host_events.update(ready, mask, Ordering::Release);
-
-        if trigger_notifier {
-            self.notifier.broadcast(ready);
-        }
-    }
-
     fn as_any(&self) -> &dyn Any {
         self
     }
 }
 
+impl fmt::Debug for EventFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventFile")
+            .field("counter", &*self.counter.lock().unwrap())
+            .field("is_semaphore", &self.is_semaphore)
+            .field("is_nonblocking", &self.is_nonblocking())
+            .finish()
+    }
+}
+
 pub trait AsEvent {
     fn as_event(&self) -> Result<&EventFile>;
 }
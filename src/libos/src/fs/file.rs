@@ -87,10 +87,19 @@ pub trait File: Debug + Sync + Send + Any {
         return_op_unsupported_error!("test_advisory_lock")
     }
 
-    fn set_advisory_lock(&self, lock: &Flock) -> Result<()> {
+    fn set_advisory_lock(&self, lock: &Flock, is_nonblocking: bool) -> Result<()> {
         return_op_unsupported_error!("set_advisory_lock")
     }
 
+    fn flock(&self, ops: FlockOps) -> Result<()> {
+        return_op_unsupported_error!("flock")
+    }
+
+    /// Release the POSIX record locks of the current process on this file.
+    ///
+    /// This is called when any file descriptor of the file is closed.
+    fn release_advisory_locks(&self) {}
+
     fn fallocate(&self, _mode: u32, _offset: u64, _len: u64) -> Result<()> {
         return_op_unsupported_error!("fallocate")
     }
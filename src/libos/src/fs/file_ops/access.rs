@@ -60,6 +60,18 @@ pub fn do_faccessat(
     if mode.test_for_exist() {
         return Ok(());
     }
+    check_inode_access(inode.as_ref(), mode)
+}
+
+/// Check whether the current process is granted the requested permissions on
+/// the inode, by its access ACL if any, or by its permission bits.
+pub fn check_inode_access(inode: &dyn INode, mode: AccessibilityCheckMode) -> Result<()> {
+    if let Some(allowed) = PosixAcl::check_access(inode, mode)? {
+        if !allowed {
+            return_errno!(EACCES, "the requested access is denied");
+        }
+        return Ok(());
+    }
     // Check the permissions of file owner
     let owner_file_mode = {
         let metadata = inode.metadata()?;
@@ -67,6 +67,7 @@ pub fn do_fchmodat(fs_path: &FsPath, mode: FileMode) -> Result<()> {
     let mut info = inode.metadata()?;
     info.mode = mode.bits();
     inode.set_metadata(&info)?;
+    PosixAcl::chmod_inode(inode.as_ref(), mode.bits())?;
     Ok(())
 }
 
@@ -77,5 +78,8 @@ pub fn do_fchmod(fd: FileDesc, mode: FileMode) -> Result<()> {
     let mut info = file_ref.metadata()?;
     info.mode = mode.bits();
     file_ref.set_metadata(&info)?;
+    if let Ok(inode_file) = file_ref.as_inode_file() {
+        PosixAcl::chmod_inode(inode_file.inode().as_ref(), mode.bits())?;
+    }
     Ok(())
 }
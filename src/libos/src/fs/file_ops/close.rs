@@ -1,5 +1,14 @@
 use super::*;
 
+bitflags! {
+    pub struct CloseRangeFlags: u32 {
+        /// Unshare the file table before closing the file descriptors
+        const CLOSE_RANGE_UNSHARE = 1 << 1;
+        /// Set the file descriptors to be close-on-exec instead of closing them
+        const CLOSE_RANGE_CLOEXEC = 1 << 2;
+    }
+}
+
 pub fn do_close(fd: FileDesc) -> Result<()> {
     debug!("close: fd: {}", fd);
     let current = current!();
@@ -10,6 +19,39 @@ pub fn do_close(fd: FileDesc) -> Result<()> {
     // a deadlock.
     // TODO: make FileTable a struct of internal mutability to avoid deadlock.
     drop(files);
+    file.release_advisory_locks();
     drop(file);
     Ok(())
 }
+
+pub fn do_close_range(first: FileDesc, last: FileDesc, flags: CloseRangeFlags) -> Result<()> {
+    debug!(
+        "close_range: first: {}, last: {}, flags: {:?}",
+        first, last, flags
+    );
+    if first > last {
+        return_errno!(EINVAL, "first must not be greater than last");
+    }
+    let current = current!();
+    if flags.contains(CloseRangeFlags::CLOSE_RANGE_UNSHARE)
+        && Arc::strong_count(current.files()) > 1
+    {
+        // The file table of a thread cannot be replaced, so it can be
+        // "unshared" only if no other thread is sharing it
+        return_errno!(EINVAL, "unsharing a shared file table is not supported");
+    }
+
+    let mut files = current.files().lock().unwrap();
+    if flags.contains(CloseRangeFlags::CLOSE_RANGE_CLOEXEC) {
+        files.set_close_on_spawn_range(first, last);
+        return Ok(());
+    }
+    let deleted_files = files.del_range(first, last);
+    // Deadlock note: same as do_close, the files must be dropped after
+    // releasing the lock of the file table.
+    drop(files);
+    for file in deleted_files {
+        file.release_advisory_locks();
+    }
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use super::*;
+use crate::fs::sefs::PAGE_SIZE;
+
+/// The maximum number of bytes that are copied in one round, which is a
+/// multiple of the page size of the SEFS page cache.
+const COPY_CHUNK_SIZE: usize = 16 * PAGE_SIZE;
+
+/// Copy a range of data from one file to another inside LibOS.
+///
+/// Compared to a loop of read and write in the user space, this avoids
+/// copying data between the LibOS and the user space, as well as the
+/// overhead of syscalls.
+///
+/// The data of SEFS files is copied in plaintext, too. Each SEFS file is an
+/// SGX protected file, whose blocks are encrypted with the keys of the file
+/// and whose tree of MACs binds the blocks to their positions and the name of
+/// the file. So the encrypted blocks of one file are not valid in another, or
+/// at another offset, and can not be copied without decryption. The copy is
+/// done in chunks of whole pages of the SEFS page cache, so the copied pages
+/// are served from and buffered in the cache without the user buffers.
+pub fn do_copy_file_range(
+    fd_in: FileDesc,
+    off_in: Option<&mut off_t>,
+    fd_out: FileDesc,
+    off_out: Option<&mut off_t>,
+    len: usize,
+    flags: u32,
+) -> Result<usize> {
+    debug!(
+        "copy_file_range: fd_in: {}, off_in: {:?}, fd_out: {}, off_out: {:?}, len: {}, flags: {:#x}",
+        fd_in, off_in, fd_out, off_out, len, flags
+    );
+
+    if flags != 0 {
+        return_errno!(EINVAL, "flags must be zero");
+    }
+    let current = current!();
+    let in_file = current.file(fd_in)?;
+    let out_file = current.file(fd_out)?;
+    // Files that do not support metadata (e.g., sockets) are not regular files
+    let in_metadata = in_file
+        .metadata()
+        .map_err(|_| errno!(EINVAL, "not a regular file"))?;
+    let out_metadata = out_file
+        .metadata()
+        .map_err(|_| errno!(EINVAL, "not a regular file"))?;
+    for metadata in [&in_metadata, &out_metadata].iter() {
+        match metadata.type_ {
+            FileType::File => {}
+            FileType::Dir => return_errno!(EISDIR, "cannot copy a directory"),
+            _ => return_errno!(EINVAL, "not a regular file"),
+        }
+    }
+    if !in_file.access_mode()?.readable() {
+        return_errno!(EBADF, "fd_in is not opened for reading");
+    }
+    if !out_file.access_mode()?.writable() {
+        return_errno!(EBADF, "fd_out is not opened for writing");
+    }
+    if out_file.status_flags()?.always_append() {
+        return_errno!(EBADF, "fd_out is opened with O_APPEND");
+    }
+
+    let in_offset = match off_in.as_ref() {
+        Some(offset) => **offset,
+        None => in_file.seek(SeekFrom::Current(0))?,
+    };
+    let out_offset = match off_out.as_ref() {
+        Some(offset) => **offset,
+        None => out_file.seek(SeekFrom::Current(0))?,
+    };
+    if in_offset < 0 || out_offset < 0 {
+        return_errno!(EINVAL, "offset is negative");
+    }
+    let (in_offset, out_offset) = (in_offset as usize, out_offset as usize);
+    let max_offset = off_t::max_value() as usize;
+    if len > max_offset - in_offset || len > max_offset - out_offset {
+        return_errno!(EOVERFLOW, "the range is too large");
+    }
+
+    let is_same_file =
+        in_metadata.dev == out_metadata.dev && in_metadata.inode == out_metadata.inode;
+    if is_same_file && in_offset < out_offset + len && out_offset < in_offset + len {
+        return_errno!(EINVAL, "the ranges overlap in the same file");
+    }
+
+    let mut copied = 0;
+    let result = copy_range(&in_file, in_offset, &out_file, out_offset, len, &mut copied);
+    // An error after some bytes have been copied is not reported to the user
+    if copied == 0 {
+        result?;
+    }
+
+    match off_in {
+        Some(offset) => *offset += copied as off_t,
+        None => {
+            in_file.seek(SeekFrom::Start((in_offset + copied) as u64))?;
+        }
+    }
+    match off_out {
+        Some(offset) => *offset += copied as off_t,
+        None => {
+            out_file.seek(SeekFrom::Start((out_offset + copied) as u64))?;
+        }
+    }
+    Ok(copied)
+}
+
+fn copy_range(
+    in_file: &FileRef,
+    mut in_offset: usize,
+    out_file: &FileRef,
+    mut out_offset: usize,
+    len: usize,
+    copied: &mut usize,
+) -> Result<()> {
+    let mut buf = vec![0; min(len, COPY_CHUNK_SIZE)];
+    while *copied < len {
+        // The first chunk ends at a chunk boundary, so the others are aligned
+        let chunk_len = min(
+            COPY_CHUNK_SIZE - in_offset % COPY_CHUNK_SIZE,
+            min(buf.len(), len - *copied),
+        );
+        let read_len = in_file.read_at(in_offset, &mut buf[..chunk_len])?;
+        if read_len == 0 {
+            break;
+        }
+
+        let mut bytes_written = 0;
+        while bytes_written < read_len {
+            let write_len =
+                out_file.write_at(out_offset + bytes_written, &buf[bytes_written..read_len])?;
+            if write_len == 0 {
+                return_errno!(EIO, "failed to write the file");
+            }
+            bytes_written += write_len;
+            *copied += write_len;
+        }
+        in_offset += read_len;
+        out_offset += read_len;
+    }
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use super::*;
+
+/// The alignment of the user buffers, file offsets and lengths of direct I/O.
+///
+/// This is the logical block size of most block devices.
+const DIRECT_IO_ALIGNMENT: usize = 512;
+
+/// Check whether a read or write on the file satisfies the alignment
+/// requirements of direct I/O.
+///
+/// This check has no effect unless the file is an inode file opened with
+/// O_DIRECT (for pipes, O_DIRECT means the packet mode instead). If no
+/// offset is given, the current file offset is checked. Each buffer is given
+/// as a pair of its address and length.
+pub fn check_direct_io<I>(file_ref: &FileRef, offset: Option<usize>, bufs: I) -> Result<()>
+where
+    I: IntoIterator<Item = (usize, usize)>,
+{
+    let is_direct_io = match file_ref.as_inode_file() {
+        Ok(inode_file) => inode_file.status_flags()?.is_direct_io(),
+        Err(_) => false,
+    };
+    if !is_direct_io {
+        return Ok(());
+    }
+
+    let is_aligned = |val: usize| val % DIRECT_IO_ALIGNMENT == 0;
+    let offset = match offset {
+        Some(offset) => offset,
+        None => file_ref.seek(SeekFrom::Current(0))? as usize,
+    };
+    if !is_aligned(offset) {
+        return_errno!(EINVAL, "the offset of direct I/O is not aligned");
+    }
+    for (addr, len) in bufs {
+        if !is_aligned(addr) || !is_aligned(len) {
+            return_errno!(EINVAL, "the buffer of direct I/O is not aligned");
+        }
+    }
+    Ok(())
+}
@@ -3,16 +3,21 @@ use super::*;
 pub fn do_dup(old_fd: FileDesc) -> Result<FileDesc> {
     let current = current!();
     let file = current.file(old_fd)?;
-    let new_fd = current.add_file(file, false);
+    let new_fd = current.add_file(file, false)?;
     Ok(new_fd)
 }
 
 pub fn do_dup2(old_fd: FileDesc, new_fd: FileDesc) -> Result<FileDesc> {
     let current = current!();
+    if new_fd >= current.max_fds() {
+        return_errno!(EBADF, "new_fd exceeds the limit of open files");
+    }
     let mut files = current.files().lock().unwrap();
     let file = files.get(old_fd)?;
     if old_fd != new_fd {
-        files.put_at(new_fd, file, false);
+        let replaced_file = files.put_at(new_fd, file, false);
+        drop(files);
+        release_replaced_file(replaced_file);
     }
     Ok(new_fd)
 }
@@ -20,11 +25,24 @@ pub fn do_dup2(old_fd: FileDesc, new_fd: FileDesc) -> Result<FileDesc> {
 pub fn do_dup3(old_fd: FileDesc, new_fd: FileDesc, flags: u32) -> Result<FileDesc> {
     let creation_flags = CreationFlags::from_bits_truncate(flags);
     let current = current!();
+    if new_fd >= current.max_fds() {
+        return_errno!(EBADF, "new_fd exceeds the limit of open files");
+    }
     let mut files = current.files().lock().unwrap();
     let file = files.get(old_fd)?;
     if old_fd == new_fd {
         return_errno!(EINVAL, "old_fd must not be equal to new_fd");
     }
-    files.put_at(new_fd, file, creation_flags.must_close_on_spawn());
+    let replaced_file = files.put_at(new_fd, file, creation_flags.must_close_on_spawn());
+    drop(files);
+    release_replaced_file(replaced_file);
     Ok(new_fd)
 }
+
+/// The file replaced by dup2 or dup3 is closed, which releases the locks of
+/// the process on it, same as do_close.
+fn release_replaced_file(replaced_file: Option<FileRef>) {
+    if let Some(file) = replaced_file {
+        file.release_advisory_locks();
+    }
+}
@@ -1,11 +1,111 @@
 use super::*;
 
+bitflags! {
+    pub struct FallocateFlags: u32 {
+        /// Do not change the file size
+        const FALLOC_FL_KEEP_SIZE       = 0x01;
+        /// Deallocate the range, which reads as zeros afterwards
+        const FALLOC_FL_PUNCH_HOLE      = 0x02;
+        /// Reserved, no longer supported by Linux
+        const FALLOC_FL_NO_HIDE_STALE   = 0x04;
+        /// Remove the range without leaving a hole
+        const FALLOC_FL_COLLAPSE_RANGE  = 0x08;
+        /// Zero the range
+        const FALLOC_FL_ZERO_RANGE      = 0x10;
+        /// Insert a hole at the range without overwriting existing data
+        const FALLOC_FL_INSERT_RANGE    = 0x20;
+        /// Unshare the shared blocks of the range
+        const FALLOC_FL_UNSHARE_RANGE   = 0x40;
+    }
+}
+
+impl FallocateFlags {
+    fn from_u32(mode: u32) -> Result<Self> {
+        let flags =
+            Self::from_bits(mode).ok_or_else(|| errno!(EOPNOTSUPP, "unknown fallocate mode"))?;
+        let unsupported_flags = Self::FALLOC_FL_NO_HIDE_STALE
+            | Self::FALLOC_FL_COLLAPSE_RANGE
+            | Self::FALLOC_FL_INSERT_RANGE
+            | Self::FALLOC_FL_UNSHARE_RANGE;
+        if flags.intersects(unsupported_flags) {
+            return_errno!(EOPNOTSUPP, "unsupported fallocate mode");
+        }
+        if flags.contains(Self::FALLOC_FL_PUNCH_HOLE | Self::FALLOC_FL_ZERO_RANGE) {
+            return_errno!(EINVAL, "punch hole and zero range cannot be used together");
+        }
+        if flags.contains(Self::FALLOC_FL_PUNCH_HOLE) && !flags.contains(Self::FALLOC_FL_KEEP_SIZE)
+        {
+            return_errno!(EOPNOTSUPP, "punch hole must be used with keep size");
+        }
+        Ok(flags)
+    }
+}
+
 pub fn do_fallocate(fd: FileDesc, mode: u32, offset: u64, len: u64) -> Result<()> {
     debug!(
         "fallocate: fd: {}, mode: {}, offset: {}, len: {}",
         fd, mode, offset, len
     );
+    let flags = FallocateFlags::from_u32(mode)?;
+    let end = offset
+        .checked_add(len)
+        .filter(|end| *end <= off_t::max_value() as u64)
+        .ok_or_else(|| errno!(EFBIG, "the range is too large"))?;
+
     let file_ref = current!().file(fd)?;
-    file_ref.fallocate(mode, offset, len)?;
+    let metadata = file_ref
+        .metadata()
+        .map_err(|_| errno!(ENODEV, "not a regular file"))?;
+    match metadata.type_ {
+        FileType::File => {}
+        FileType::Dir => return_errno!(EISDIR, "fd refers to a directory"),
+        _ => return_errno!(ENODEV, "not a regular file"),
+    }
+    if !file_ref.access_mode()?.writable() {
+        return_errno!(EBADF, "fd is not opened for writing");
+    }
+    let file_size = metadata.size as u64;
+    let extends_file = !flags.contains(FallocateFlags::FALLOC_FL_KEEP_SIZE) && end > file_size;
+
+    if flags.intersects(FallocateFlags::FALLOC_FL_PUNCH_HOLE | FallocateFlags::FALLOC_FL_ZERO_RANGE)
+    {
+        // Holes are not supported by the file systems, so both punching a hole
+        // and zeroing a range are done by overwriting the range with zeros
+        zero_range(&file_ref, offset, min(end, file_size))?;
+        if extends_file {
+            file_ref.set_len(end)?;
+        }
+    } else if flags.contains(FallocateFlags::FALLOC_FL_KEEP_SIZE) {
+        // The file systems allocate space on demand, so preallocating space
+        // without changing the file size is only a hint
+    } else {
+        match file_ref.fallocate(mode, offset, len) {
+            Ok(()) => {}
+            // Fallback to extending the file if the file system cannot preallocate space
+            Err(e) if e.errno() == ENOSYS => {
+                if extends_file {
+                    file_ref.set_len(end)?;
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Overwrite the range [start, end) of the file with zeros.
+fn zero_range(file_ref: &FileRef, start: u64, end: u64) -> Result<()> {
+    const ZEROS_LEN: usize = 64 * 1024;
+    let zeros = vec![0; min(end.saturating_sub(start) as usize, ZEROS_LEN)];
+
+    let mut offset = start;
+    while offset < end {
+        let len = min(zeros.len() as u64, end - offset) as usize;
+        let write_len = file_ref.write_at(offset as usize, &zeros[..len])?;
+        if write_len == 0 {
+            return_errno!(EIO, "failed to write zeros");
+        }
+        offset += write_len as u64;
+    }
     Ok(())
 }
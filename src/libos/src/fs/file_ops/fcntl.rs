@@ -2,6 +2,13 @@ use super::flock::flock;
 use super::*;
 use util::mem_util::from_user;
 
+// The commands of file sealing, which are missing in the libc crate
+const F_ADD_SEALS: c_int = 1033;
+const F_GET_SEALS: c_int = 1034;
+// The commands of the capacity of pipes
+const F_SETPIPE_SZ: c_int = 1031;
+const F_GETPIPE_SZ: c_int = 1032;
+
 #[derive(Debug)]
 pub enum FcntlCmd<'a> {
     /// Duplicate the file descriptor fd using the lowest-numbered available
@@ -22,6 +29,16 @@ pub enum FcntlCmd<'a> {
     GetLk(&'a mut flock),
     /// Acquire or release a file lock
     SetLk(&'a flock),
+    /// As for `SetLk`, but wait for the conflicting lock to be released
+    SetLkWait(&'a flock),
+    /// Add seals to a memfd
+    AddSeals(u32),
+    /// Get the seals of a memfd
+    GetSeals(),
+    /// Set the capacity of a pipe
+    SetPipeSz(i32),
+    /// Get the capacity of a pipe
+    GetPipeSz(),
 }
 
 impl<'a> FcntlCmd<'a> {
@@ -46,6 +63,16 @@ impl<'a> FcntlCmd<'a> {
                 let flock_c = unsafe { &*flock_ptr };
                 FcntlCmd::SetLk(flock_c)
             }
+            libc::F_SETLKW => {
+                let flock_ptr = arg as *const flock;
+                from_user::check_ptr(flock_ptr)?;
+                let flock_c = unsafe { &*flock_ptr };
+                FcntlCmd::SetLkWait(flock_c)
+            }
+            F_ADD_SEALS => FcntlCmd::AddSeals(arg as u32),
+            F_GET_SEALS => FcntlCmd::GetSeals(),
+            F_SETPIPE_SZ => FcntlCmd::SetPipeSz(arg as i32),
+            F_GETPIPE_SZ => FcntlCmd::GetPipeSz(),
             _ => return_errno!(EINVAL, "unsupported command"),
         })
     }
@@ -55,15 +82,16 @@ pub fn do_fcntl(fd: FileDesc, cmd: &mut FcntlCmd) -> Result<isize> {
     debug!("fcntl: fd: {:?}, cmd: {:?}", &fd, cmd);
 
     let current = current!();
+    let max_fd = current.max_fds();
     let mut file_table = current.files().lock().unwrap();
 
     let ret = match cmd {
         FcntlCmd::DupFd(min_fd) => {
-            let dup_fd = file_table.dup(fd, *min_fd, false)?;
+            let dup_fd = file_table.dup(fd, *min_fd, max_fd, false)?;
             dup_fd as isize
         }
         FcntlCmd::DupFdCloexec(min_fd) => {
-            let dup_fd = file_table.dup(fd, *min_fd, true)?;
+            let dup_fd = file_table.dup(fd, *min_fd, max_fd, true)?;
             dup_fd as isize
         }
         FcntlCmd::GetFd() => {
@@ -105,9 +133,64 @@ pub fn do_fcntl(fd: FileDesc, cmd: &mut FcntlCmd) -> Result<isize> {
         FcntlCmd::SetLk(flock_c) => {
             let file = file_table.get(fd)?;
             let lock = Flock::from_c(*flock_c)?;
-            file.set_advisory_lock(&lock)?;
+            file.set_advisory_lock(&lock, true)?;
+            0
+        }
+        FcntlCmd::SetLkWait(flock_c) => {
+            let file = file_table.get(fd)?;
+            // Do not hold the file table while waiting for the lock
+            drop(file_table);
+            let lock = Flock::from_c(*flock_c)?;
+            file.set_advisory_lock(&lock, false)?;
+            0
+        }
+        FcntlCmd::AddSeals(seals) => {
+            let file = file_table.get(fd)?;
+            let mem_file = file.as_mem_file()?;
+            if !mem_file.access_mode()?.writable() {
+                return_errno!(EPERM, "the file is not writable");
+            }
+            let seals =
+                FileSeals::from_bits(*seals).ok_or_else(|| errno!(EINVAL, "invalid seals"))?;
+            // The file may be mapped by any process that shares it
+            let has_shared_writable_mapping = || {
+                crate::process::table::get_all_processes()
+                    .iter()
+                    .filter_map(|process| process.main_thread())
+                    .any(|thread| thread.vm().has_shared_writable_mapping(&file))
+            };
+            if seals.contains(FileSeals::F_SEAL_WRITE) && has_shared_writable_mapping() {
+                return_errno!(EBUSY, "the file has shared, writable memory mappings");
+            }
+            mem_file.add_seals(seals)?;
             0
         }
+        FcntlCmd::GetSeals() => {
+            let file = file_table.get(fd)?;
+            let mem_file = file.as_mem_file()?;
+            mem_file.seals().bits() as isize
+        }
+        FcntlCmd::SetPipeSz(size) => {
+            let file = file_table.get(fd)?;
+            drop(file_table);
+            if *size < 0 {
+                return_errno!(EINVAL, "the size is negative");
+            }
+            let size = *size as usize;
+            let capacity = match file.as_pipe_reader() {
+                Ok(pipe_reader) => pipe_reader.set_capacity(size)?,
+                Err(_) => file.as_pipe_writer()?.set_capacity(size)?,
+            };
+            capacity as isize
+        }
+        FcntlCmd::GetPipeSz() => {
+            let file = file_table.get(fd)?;
+            let capacity = match file.as_pipe_reader() {
+                Ok(pipe_reader) => pipe_reader.capacity(),
+                Err(_) => file.as_pipe_writer()?.capacity(),
+            };
+            capacity as isize
+        }
     };
     Ok(ret)
 }
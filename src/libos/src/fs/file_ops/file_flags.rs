@@ -78,12 +78,12 @@ impl CreationFlags {
     }
 
     pub fn must_be_directory(&self) -> bool {
-        if self.contains(CreationFlags::_O_TMPFILE) {
-            warn!("O_TMPFILE is not supported, handle it as O_DIRECTORY");
-            return true;
-        }
         self.contains(CreationFlags::O_DIRECTORY)
     }
+
+    pub fn is_tmpfile(&self) -> bool {
+        self.contains(CreationFlags::_O_TMPFILE)
+    }
 }
 
 bitflags! {
@@ -113,6 +113,10 @@ impl StatusFlags {
         self.contains(StatusFlags::O_APPEND)
     }
 
+    pub fn is_direct_io(&self) -> bool {
+        self.contains(StatusFlags::O_DIRECT)
+    }
+
     pub fn is_fast_open(&self) -> bool {
         self.contains(StatusFlags::O_PATH)
     }
@@ -2,6 +2,13 @@
 use super::*;
 use process::pid_t;
 
+pub fn do_flock(fd: FileDesc, ops: i32) -> Result<()> {
+    debug!("flock: fd: {}, ops: {:#x}", fd, ops);
+    let ops = FlockOps::from_i32(ops)?;
+    let file_ref = current!().file(fd)?;
+    file_ref.flock(ops)
+}
+
 /// C struct for a lock
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
@@ -48,7 +55,7 @@ impl Flock {
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(u16)]
 pub enum FlockType {
     F_RDLCK = 0,
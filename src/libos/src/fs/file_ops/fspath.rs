@@ -59,6 +59,14 @@ impl<'a> FsPath<'a> {
         })
     }
 
+    /// Get the file descriptor if the path refers to the file itself
+    pub fn as_fd(&self) -> Option<FileDesc> {
+        match self.inner {
+            FsPathInner::Fd(fd) => Some(fd),
+            _ => None,
+        }
+    }
+
     /// Convert to absolute path
     pub fn to_abs_path(&self) -> Result<String> {
         let abs_path = match &self.inner {
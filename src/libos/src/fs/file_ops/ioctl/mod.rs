@@ -26,6 +26,10 @@ impl_ioctl_nums_and_cmds! {
     // Format:
     // ioctl_name => (ioctl_num, ioctl_type_arg)
 
+    // Get the foreground process group of the terminal
+    TIOCGPGRP => (0x540F, mut i32),
+    // Set the foreground process group of the terminal
+    TIOCSPGRP => (0x5410, i32),
     // Get window size
     TIOCGWINSZ => (0x5413, mut WinSize),
     // Set window size
@@ -37,6 +41,8 @@ impl_ioctl_nums_and_cmds! {
     // the foreground process group and all processes in the current session lose their controlling
     // terminal
     TIOCNOTTY => (0x5422, ()),
+    // Get the session ID of the terminal
+    TIOCGSID => (0x5429, mut i32),
     // Get the number of bytes in the input buffer
     FIONREAD => (0x541B, mut i32),
     // Low-level access to Linux network devices on man7/netdevice.7
@@ -54,6 +60,8 @@ impl_ioctl_nums_and_cmds! {
     SIOCGIFPFLAGS => (0x8935, mut IfReq),
     SIOCGIFTXQLEN => (0x8942, mut IfReq),
     SIOCGIFMAP => (0x8970, mut IfReq),
+    // Get the context ID of the local machine for vsock on /dev/vsock
+    IOCTL_VM_SOCKETS_GET_LOCAL_CID => (0x7B9, mut u32),
 }
 
 /// This is the centralized place to add sanity checks for the argument values
@@ -92,6 +100,13 @@ impl<'a> IoctlCmd<'a> {
 pub fn do_ioctl(fd: FileDesc, cmd: &mut IoctlCmd) -> Result<i32> {
     debug!("ioctl: fd: {}, cmd: {:?}", fd, cmd);
     let file_ref = current!().file(fd)?;
+    // Same as Linux, FIONBIO applies to all files by setting O_NONBLOCK
+    if let IoctlCmd::FIONBIO(nonblocking) = cmd {
+        let mut status_flags = file_ref.status_flags()?;
+        status_flags.set(StatusFlags::O_NONBLOCK, **nonblocking != 0);
+        file_ref.set_status_flags(status_flags)?;
+        return Ok(0);
+    }
     file_ref.ioctl(cmd)
 }
 
@@ -15,18 +15,38 @@ pub fn do_linkat(old_fs_path: &FsPath, new_fs_path: &FsPath, flags: LinkFlags) -
 
     let newpath = new_fs_path.to_abs_path()?;
     let (new_dir_path, new_file_name) = split_path(&newpath);
-    let (inode, new_dir_inode) = {
+    let inode = if let Some(fd) = old_fs_path.as_fd() {
+        // Use the inode of the open file, which may have no name at all
+        let file_ref = current!().file(fd)?;
+        let inode_file = file_ref
+            .as_inode_file()
+            .map_err(|_| errno!(EBADF, "not an inode file"))?;
+        if inode_file.inode().metadata()?.nlinks == 0 && !inode_file.is_linkable_tmpfile() {
+            return_errno!(ENOENT, "the file has been removed");
+        }
+        inode_file.inode().clone()
+    } else {
         let oldpath = old_fs_path.to_abs_path()?;
         let current = current!();
         let fs = current.fs().lock().unwrap();
-        let inode = if flags.contains(LinkFlags::AT_SYMLINK_FOLLOW) {
+        if flags.contains(LinkFlags::AT_SYMLINK_FOLLOW) {
             fs.lookup_inode(&oldpath)?
         } else {
             fs.lookup_inode_no_follow(&oldpath)?
-        };
-        let new_dir_inode = fs.lookup_inode(new_dir_path)?;
-        (inode, new_dir_inode)
+        }
+    };
+    let new_dir_inode = {
+        let current = current!();
+        let fs = current.fs().lock().unwrap();
+        fs.lookup_inode(new_dir_path)?
     };
     new_dir_inode.link(new_file_name, &inode)?;
+    inotify::notify_inode(inode.as_ref(), InotifyMask::IN_ATTRIB);
+    inotify::notify_dir_entry(
+        new_dir_inode.as_ref(),
+        new_file_name,
+        InotifyMask::IN_CREATE,
+        0,
+    );
     Ok(())
 }
@@ -5,10 +5,10 @@ pub fn do_mkdirat(fs_path: &FsPath, mode: usize) -> Result<()> {
 
     let path = fs_path.to_abs_path()?;
     let (dir_path, file_name) = split_path(&path);
-    let inode = {
+    let (inode, umask) = {
         let current = current!();
         let fs = current.fs().lock().unwrap();
-        fs.lookup_inode(dir_path)?
+        (fs.lookup_inode(dir_path)?, fs.umask())
     };
     if inode.find(file_name).is_ok() {
         return_errno!(EEXIST, "");
@@ -16,6 +16,12 @@ pub fn do_mkdirat(fs_path: &FsPath, mode: usize) -> Result<()> {
     if !inode.allow_write()? {
         return_errno!(EPERM, "dir cannot be written");
     }
-    inode.create(file_name, FileType::Dir, mode as u32)?;
+    create_inode_with_acl(inode.as_ref(), file_name, FileType::Dir, mode as u32, umask)?;
+    inotify::notify_dir_entry(
+        inode.as_ref(),
+        file_name,
+        InotifyMask::IN_CREATE | InotifyMask::IN_ISDIR,
+        0,
+    );
     Ok(())
 }
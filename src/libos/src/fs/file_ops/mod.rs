@@ -1,40 +1,48 @@
 use super::*;
 use process::Process;
 
-pub use self::access::{do_faccessat, AccessibilityCheckFlags, AccessibilityCheckMode};
+pub use self::access::{
+    check_inode_access, do_faccessat, AccessibilityCheckFlags, AccessibilityCheckMode,
+};
 pub use self::chmod::{do_fchmod, do_fchmodat, FileMode};
 pub use self::chown::{do_fchown, do_fchownat, ChownFlags};
-pub use self::close::do_close;
+pub use self::close::{do_close, do_close_range, CloseRangeFlags};
+pub use self::copy_file_range::do_copy_file_range;
 pub use self::dirent::{do_getdents, do_getdents64};
 pub use self::dup::{do_dup, do_dup2, do_dup3};
 pub use self::fallocate::do_fallocate;
 pub use self::fcntl::{do_fcntl, FcntlCmd};
 pub use self::file_flags::{AccessMode, CreationFlags, StatusFlags};
-pub use self::flock::{Flock, FlockType};
+pub use self::flock::{do_flock, Flock, FlockType, FlockWhence};
 pub use self::fspath::{FsPath, AT_FDCWD};
 pub use self::fsync::{do_fdatasync, do_fsync};
 pub use self::ioctl::{
-    do_ioctl, occlum_ocall_ioctl, BuiltinIoctlNum, IfConf, IoctlCmd, StructuredIoctlArgType,
+    do_ioctl, occlum_ocall_ioctl, BuiltinIoctlNum, IfConf, IfReq, IoctlCmd, StructuredIoctlArgType,
     StructuredIoctlNum,
 };
 pub use self::link::{do_linkat, LinkFlags};
 pub use self::lseek::do_lseek;
 pub use self::mkdir::do_mkdirat;
-pub use self::open::do_openat;
+pub use self::open::{do_openat, do_openat2, OpenHow, ResolveFlags};
 pub use self::read::{do_pread, do_read, do_readv};
-pub use self::rename::do_renameat;
+pub use self::rename::{do_renameat, RenameFlags};
 pub use self::rmdir::do_rmdir;
 pub use self::sendfile::do_sendfile;
+pub use self::splice::{do_splice, do_tee, do_vmsplice};
 pub use self::stat::{do_fstat, do_fstatat, Stat, StatFlags};
 pub use self::symlink::{do_readlinkat, do_symlinkat};
 pub use self::truncate::{do_ftruncate, do_truncate};
 pub use self::unlink::{do_unlinkat, UnlinkFlags};
+pub use self::utimes::{do_futimens, do_utimensat, Utime, UtimeFlags};
 pub use self::write::{do_pwrite, do_write, do_writev};
+pub use self::xattr::{do_getxattr, do_listxattr, do_removexattr, do_setxattr, XattrTarget};
 
 mod access;
 mod chmod;
 mod chown;
 mod close;
+mod copy_file_range;
+mod direct_io;
 mod dirent;
 mod dup;
 mod fallocate;
@@ -52,8 +60,11 @@ mod read;
 mod rename;
 mod rmdir;
 mod sendfile;
+mod splice;
 mod stat;
 mod symlink;
 mod truncate;
 mod unlink;
+mod utimes;
 mod write;
+mod xattr;
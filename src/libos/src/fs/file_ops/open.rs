@@ -14,7 +14,82 @@ pub fn do_openat(fs_path: &FsPath, flags: u32, mode: u32) -> Result<FileDesc> {
 
     let fd = {
         let creation_flags = CreationFlags::from_bits_truncate(flags);
-        current.add_file(file_ref, creation_flags.must_close_on_spawn())
+        current.add_file(file_ref, creation_flags.must_close_on_spawn())?
     };
     Ok(fd)
 }
+
+bitflags! {
+    pub struct ResolveFlags: u64 {
+        /// Block mount-point crossings
+        const RESOLVE_NO_XDEV = 0x01;
+        /// Block traversal through procfs-style magic links
+        const RESOLVE_NO_MAGICLINKS = 0x02;
+        /// Block traversal through all symlinks
+        const RESOLVE_NO_SYMLINKS = 0x04;
+        /// Block "lexical" trickery like "..", symlinks, and absolute paths
+        /// which escape the dirfd
+        const RESOLVE_BENEATH = 0x08;
+        /// Make all jumps to "/" and ".." be scoped inside the dirfd
+        const RESOLVE_IN_ROOT = 0x10;
+        /// Only complete if resolution can be completed through cached lookup
+        const RESOLVE_CACHED = 0x20;
+    }
+}
+
+/// The arguments of openat2.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct OpenHow {
+    pub flags: u64,
+    pub mode: u64,
+    pub resolve: u64,
+}
+
+pub fn do_openat2(dirfd: i32, path: &str, how: &OpenHow) -> Result<FileDesc> {
+    debug!(
+        "openat2: dirfd: {}, path: {:?}, how: {:?}",
+        dirfd, path, how
+    );
+
+    // Unlike openat, unknown flags and modes are rejected
+    let valid_flags = CreationFlags::all().bits() | StatusFlags::all().bits() | 0b11;
+    if how.flags & !(valid_flags as u64) != 0 {
+        return_errno!(EINVAL, "invalid flags");
+    }
+    let flags = how.flags as u32;
+    let creation_flags = CreationFlags::from_bits_truncate(flags);
+    let needs_mode = creation_flags.can_create() || creation_flags.is_tmpfile();
+    if how.mode & !0o7777 != 0 || (how.mode != 0 && !needs_mode) {
+        return_errno!(EINVAL, "invalid mode");
+    }
+    let resolve =
+        ResolveFlags::from_bits(how.resolve).ok_or_else(|| errno!(EINVAL, "invalid resolve"))?;
+    if resolve.contains(ResolveFlags::RESOLVE_BENEATH | ResolveFlags::RESOLVE_IN_ROOT) {
+        return_errno!(
+            EINVAL,
+            "RESOLVE_BENEATH and RESOLVE_IN_ROOT are mutually exclusive"
+        );
+    }
+    if resolve.contains(ResolveFlags::RESOLVE_CACHED) {
+        // There is no cache of lookups, so let the caller retry without it
+        return_errno!(EAGAIN, "RESOLVE_CACHED is not supported");
+    }
+
+    // The dirfd is only used by relative paths, unless the resolution is scoped
+    let is_scoped =
+        resolve.intersects(ResolveFlags::RESOLVE_BENEATH | ResolveFlags::RESOLVE_IN_ROOT);
+    let dir_path = if Path::new(path).is_absolute() && !is_scoped {
+        String::from("/")
+    } else {
+        FsPath::new("", dirfd, true)?.to_abs_path()?
+    };
+    let current = current!();
+    let fs = current.fs().lock().unwrap();
+
+    let file_ref: Arc<dyn File> =
+        fs.open_file_with_resolve(&dir_path, path, flags, how.mode as u32, resolve)?;
+
+    let fd = current.add_file(file_ref, creation_flags.must_close_on_spawn())?;
+    Ok(fd)
+}
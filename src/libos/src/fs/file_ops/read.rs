@@ -1,14 +1,18 @@
+use super::direct_io::check_direct_io;
 use super::*;
 
 pub fn do_read(fd: FileDesc, buf: &mut [u8]) -> Result<usize> {
     debug!("read: fd: {}", fd);
     let file_ref = current!().file(fd)?;
+    check_direct_io(&file_ref, None, Some((buf.as_ptr() as usize, buf.len())))?;
     file_ref.read(buf)
 }
 
 pub fn do_readv(fd: FileDesc, bufs: &mut [&mut [u8]]) -> Result<usize> {
     debug!("readv: fd: {}", fd);
     let file_ref = current!().file(fd)?;
+    let buf_addrs_and_lens = bufs.iter().map(|buf| (buf.as_ptr() as usize, buf.len()));
+    check_direct_io(&file_ref, None, buf_addrs_and_lens)?;
     file_ref.readv(bufs)
 }
 
@@ -18,5 +22,10 @@ pub fn do_pread(fd: FileDesc, buf: &mut [u8], offset: off_t) -> Result<usize> {
         return_errno!(EINVAL, "the offset is negative");
     }
     let file_ref = current!().file(fd)?;
+    check_direct_io(
+        &file_ref,
+        Some(offset as usize),
+        Some((buf.as_ptr() as usize, buf.len())),
+    )?;
     file_ref.read_at(offset as usize, buf)
 }
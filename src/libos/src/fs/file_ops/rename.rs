@@ -1,9 +1,43 @@
 use super::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-pub fn do_renameat(old_fs_path: &FsPath, new_fs_path: &FsPath) -> Result<()> {
+bitflags! {
+    pub struct RenameFlags: u32 {
+        /// Do not overwrite the new path
+        const RENAME_NOREPLACE = 1 << 0;
+        /// Exchange the old and new paths atomically
+        const RENAME_EXCHANGE = 1 << 1;
+        /// Create a whiteout object at the old path
+        const RENAME_WHITEOUT = 1 << 2;
+    }
+}
+
+impl RenameFlags {
+    pub fn from_u32(flags: u32) -> Result<Self> {
+        let flags = Self::from_bits(flags).ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+        if flags.contains(Self::RENAME_NOREPLACE | Self::RENAME_EXCHANGE) {
+            return_errno!(
+                EINVAL,
+                "RENAME_NOREPLACE and RENAME_EXCHANGE are mutually exclusive"
+            );
+        }
+        if flags.contains(Self::RENAME_WHITEOUT) {
+            return_errno!(EINVAL, "RENAME_WHITEOUT is not supported");
+        }
+        Ok(flags)
+    }
+}
+
+lazy_static! {
+    /// Serialize renames, so that checking the new path and moving the entries
+    /// are atomic with respect to other renames.
+    static ref RENAME_LOCK: SgxMutex<()> = SgxMutex::new(());
+}
+
+pub fn do_renameat(old_fs_path: &FsPath, new_fs_path: &FsPath, flags: RenameFlags) -> Result<()> {
     debug!(
-        "renameat: old_fs_path: {:?}, new_fs_path: {:?}",
-        old_fs_path, new_fs_path
+        "renameat: old_fs_path: {:?}, new_fs_path: {:?}, flags: {:?}",
+        old_fs_path, new_fs_path, flags
     );
 
     let oldpath = old_fs_path.to_abs_path()?;
@@ -15,15 +49,142 @@ pub fn do_renameat(old_fs_path: &FsPath, new_fs_path: &FsPath) -> Result<()> {
     let (new_dir_path, new_file_name) = split_path(&newpath);
     let old_dir_inode = fs.lookup_inode(old_dir_path)?;
     let new_dir_inode = fs.lookup_inode(new_dir_path)?;
-    let old_file_mode = {
-        let old_file_inode = old_dir_inode.find(old_file_name)?;
+
+    let _rename_guard = RENAME_LOCK.lock().unwrap();
+    let old_file_inode = old_dir_inode.find(old_file_name)?;
+    let (old_file_mode, is_dir) = {
         let metadata = old_file_inode.metadata()?;
-        FileMode::from_bits_truncate(metadata.mode)
+        (
+            FileMode::from_bits_truncate(metadata.mode),
+            metadata.type_ == FileType::Dir,
+        )
     };
     if old_file_mode.has_sticky_bit() {
         warn!("ignoring the sticky bit");
     }
-    // TODO: support to modify file's absolute path
-    old_dir_inode.move_(old_file_name, &new_dir_inode, new_file_name)?;
+    if flags.contains(RenameFlags::RENAME_EXCHANGE) {
+        let new_file_inode = new_dir_inode.find(new_file_name)?;
+        let new_metadata = new_file_inode.metadata()?;
+        let old_metadata = old_file_inode.metadata()?;
+        if (old_metadata.dev, old_metadata.inode) == (new_metadata.dev, new_metadata.inode) {
+            // Exchanging a file with itself does nothing
+            return Ok(());
+        }
+        exchange_entries(&old_dir_inode, old_file_name, &new_dir_inode, new_file_name)?;
+        let new_is_dir = new_metadata.type_ == FileType::Dir;
+        notify_move(
+            &new_dir_inode,
+            new_file_name,
+            &old_dir_inode,
+            old_file_name,
+            &new_file_inode,
+            new_is_dir,
+        );
+    } else {
+        let replaced_inode = new_dir_inode.find(new_file_name).ok();
+        if flags.contains(RenameFlags::RENAME_NOREPLACE) && replaced_inode.is_some() {
+            return_errno!(EEXIST, "the new path exists");
+        }
+        let replaced_metadata = match &replaced_inode {
+            Some(replaced_inode) => Some(replaced_inode.metadata()?),
+            None => None,
+        };
+        // TODO: support to modify file's absolute path
+        old_dir_inode.move_(old_file_name, &new_dir_inode, new_file_name)?;
+        // The last link to the replaced inode is removed
+        if let (Some(replaced_inode), Some(replaced_metadata)) = (replaced_inode, replaced_metadata)
+        {
+            let old_metadata = old_file_inode.metadata()?;
+            let is_same_inode = (old_metadata.dev, old_metadata.inode)
+                == (replaced_metadata.dev, replaced_metadata.inode);
+            if !is_same_inode
+                && (replaced_metadata.nlinks <= 1 || replaced_metadata.type_ == FileType::Dir)
+            {
+                XattrTable::unlink(&replaced_inode);
+            }
+        }
+    }
+    notify_move(
+        &old_dir_inode,
+        old_file_name,
+        &new_dir_inode,
+        new_file_name,
+        &old_file_inode,
+        is_dir,
+    );
+    Ok(())
+}
+
+/// Exchange two directory entries.
+///
+/// The underlying file systems only support moving an entry, so one of the
+/// entries is moved aside temporarily. The entries are restored on failure.
+fn exchange_entries(
+    old_dir_inode: &Arc<dyn INode>,
+    old_file_name: &str,
+    new_dir_inode: &Arc<dyn INode>,
+    new_file_name: &str,
+) -> Result<()> {
+    static EXCHANGE_ID: AtomicUsize = AtomicUsize::new(0);
+
+    let tmp_file_name = loop {
+        let id = EXCHANGE_ID.fetch_add(1, Ordering::Relaxed);
+        let tmp_file_name = format!(".rename_exchange.{}", id);
+        if old_dir_inode.find(&tmp_file_name).is_err() {
+            break tmp_file_name;
+        }
+    };
+
+    old_dir_inode.move_(old_file_name, old_dir_inode, &tmp_file_name)?;
+    if let Err(e) = new_dir_inode.move_(new_file_name, old_dir_inode, old_file_name) {
+        if old_dir_inode
+            .move_(&tmp_file_name, old_dir_inode, old_file_name)
+            .is_err()
+        {
+            error!("failed to restore {:?} after exchange", old_file_name);
+        }
+        return Err(e.into());
+    }
+    if let Err(e) = old_dir_inode.move_(&tmp_file_name, new_dir_inode, new_file_name) {
+        let restore_result = old_dir_inode
+            .move_(old_file_name, new_dir_inode, new_file_name)
+            .and_then(|_| old_dir_inode.move_(&tmp_file_name, old_dir_inode, old_file_name));
+        if restore_result.is_err() {
+            error!(
+                "failed to restore {:?} and {:?} after exchange",
+                old_file_name, new_file_name
+            );
+        }
+        return Err(e.into());
+    }
     Ok(())
 }
+
+fn notify_move(
+    from_dir_inode: &Arc<dyn INode>,
+    from_file_name: &str,
+    to_dir_inode: &Arc<dyn INode>,
+    to_file_name: &str,
+    file_inode: &Arc<dyn INode>,
+    is_dir: bool,
+) {
+    let isdir_mask = if is_dir {
+        InotifyMask::IN_ISDIR
+    } else {
+        InotifyMask::empty()
+    };
+    let cookie = inotify::alloc_cookie();
+    inotify::notify_dir_entry(
+        from_dir_inode.as_ref(),
+        from_file_name,
+        InotifyMask::IN_MOVED_FROM | isdir_mask,
+        cookie,
+    );
+    inotify::notify_dir_entry(
+        to_dir_inode.as_ref(),
+        to_file_name,
+        InotifyMask::IN_MOVED_TO | isdir_mask,
+        cookie,
+    );
+    inotify::notify_inode(file_inode.as_ref(), InotifyMask::IN_MOVE_SELF);
+}
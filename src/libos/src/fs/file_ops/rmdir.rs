@@ -14,5 +14,13 @@ pub fn do_rmdir(path: &str) -> Result<()> {
         return_errno!(ENOTDIR, "rmdir on not directory");
     }
     dir_inode.unlink(file_name)?;
+    inotify::notify_inode(file_inode.as_ref(), InotifyMask::IN_DELETE_SELF);
+    XattrTable::unlink(&file_inode);
+    inotify::notify_dir_entry(
+        dir_inode.as_ref(),
+        file_name,
+        InotifyMask::IN_DELETE | InotifyMask::IN_ISDIR,
+        0,
+    );
     Ok(())
 }
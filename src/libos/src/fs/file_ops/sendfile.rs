@@ -1,5 +1,8 @@
 use super::*;
 
+/// The maximum number of bytes that can be transferred by a single sendfile, same as Linux.
+const MAX_RW_COUNT: usize = 0x7fff_f000;
+
 pub fn do_sendfile(
     out_fd: FileDesc,
     in_fd: FileDesc,
@@ -15,23 +18,76 @@ pub fn do_sendfile(
     let current = current!();
     let in_file = current.file(in_fd)?;
     let out_file = current.file(out_fd)?;
-    let mut buffer: [u8; 1024 * 11] = unsafe { MaybeUninit::uninit().assume_init() };
+    // Not all types of files support querying the access mode or status flags
+    if let Ok(access_mode) = in_file.access_mode() {
+        if !access_mode.readable() {
+            return_errno!(EBADF, "in_fd is not opened for reading");
+        }
+    }
+    if let Ok(access_mode) = out_file.access_mode() {
+        if !access_mode.writable() {
+            return_errno!(EBADF, "out_fd is not opened for writing");
+        }
+    }
+    if let Ok(status_flags) = out_file.status_flags() {
+        if status_flags.always_append() {
+            return_errno!(EINVAL, "out_fd is opened with O_APPEND");
+        }
+    }
+    if let Some(offset) = offset {
+        if offset < 0 {
+            return_errno!(EINVAL, "offset is negative");
+        }
+    }
+    let count = min(count, MAX_RW_COUNT);
 
-    let mut read_offset = match offset {
+    let start_offset = match offset {
         Some(offset) => offset,
         None => in_file.seek(SeekFrom::Current(0))?,
     } as usize;
 
-    // read from specified offset and write new offset back
-    let mut bytes_read = 0;
-    while bytes_read < count {
-        let len = min(buffer.len(), count - bytes_read);
+    let mut buffer = SendfileBuffer::new();
+    let mut bytes_sent = 0;
+    let result = transfer(
+        &in_file,
+        &out_file,
+        start_offset,
+        count,
+        &mut buffer,
+        &mut bytes_sent,
+    );
+    // Any error after some bytes have been sent is not reported to the user,
+    // so that the offset can be updated correctly
+    if bytes_sent == 0 {
+        result?;
+    }
+
+    let end_offset = start_offset + bytes_sent;
+    if offset.is_none() {
+        in_file.seek(SeekFrom::Start(end_offset as u64))?;
+    }
+    Ok((bytes_sent, end_offset))
+}
+
+/// Copy data from the in file to the out file chunk by chunk, counting the
+/// bytes that have been written to the out file in `bytes_sent`.
+fn transfer(
+    in_file: &FileRef,
+    out_file: &FileRef,
+    mut read_offset: usize,
+    count: usize,
+    buffer: &mut SendfileBuffer,
+    bytes_sent: &mut usize,
+) -> Result<()> {
+    let buffer = buffer.as_mut_slice();
+    while *bytes_sent < count {
+        let len = min(buffer.len(), count - *bytes_sent);
         let read_len = in_file.read_at(read_offset, &mut buffer[..len])?;
         if read_len == 0 {
             break;
         }
-        bytes_read += read_len;
         read_offset += read_len;
+
         let mut bytes_written = 0;
         while bytes_written < read_len {
             let write_len = out_file.write(&buffer[bytes_written..read_len])?;
@@ -39,11 +95,47 @@ pub fn do_sendfile(
                 return_errno!(EBADF, "sendfile write return 0");
             }
             bytes_written += write_len;
+            *bytes_sent += write_len;
         }
     }
+    Ok(())
+}
 
-    if offset.is_none() {
-        in_file.seek(SeekFrom::Current(bytes_read as i64))?;
+lazy_static! {
+    /// A pool of buffers that are reused by sendfile.
+    ///
+    /// Using large, reusable buffers reduces both the cost of memory allocation
+    /// and the number of OCalls when the out file is backed by the host
+    /// (e.g., a socket).
+    static ref SENDFILE_BUFFER_POOL: SgxMutex<Vec<Vec<u8>>> = SgxMutex::new(Vec::new());
+}
+
+/// A buffer borrowed from the pool, which is returned to the pool on drop.
+struct SendfileBuffer(Vec<u8>);
+
+impl SendfileBuffer {
+    const BUF_SIZE: usize = 64 * 1024;
+    const MAX_POOLED_BUFS: usize = 8;
+
+    fn new() -> Self {
+        let buf = SENDFILE_BUFFER_POOL
+            .lock()
+            .unwrap()
+            .pop()
+            .unwrap_or_else(|| vec![0; Self::BUF_SIZE]);
+        Self(buf)
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        self.0.as_mut_slice()
+    }
+}
+
+impl Drop for SendfileBuffer {
+    fn drop(&mut self) {
+        let mut pool = SENDFILE_BUFFER_POOL.lock().unwrap();
+        if pool.len() < Self::MAX_POOLED_BUFS {
+            pool.push(std::mem::replace(&mut self.0, Vec::new()));
+        }
     }
-    Ok((bytes_read, read_offset))
 }
@@ -0,0 +1,313 @@
+use super::*;
+use crate::fs::pipe::{PipeReader, PipeWriter};
+
+bitflags! {
+    pub struct SpliceFlags: u32 {
+        /// Attempt to move pages instead of copying (only a hint)
+        const SPLICE_F_MOVE     = 1;
+        /// Do not block on I/O
+        const SPLICE_F_NONBLOCK = 2;
+        /// More data will be coming in a subsequent splice (only a hint)
+        const SPLICE_F_MORE     = 4;
+        /// Gift the user pages to the pipe (only a hint)
+        const SPLICE_F_GIFT     = 8;
+    }
+}
+
+/// The maximum number of bytes that are transferred in one round.
+const SPLICE_CHUNK_SIZE: usize = 64 * 1024;
+
+pub fn do_splice(
+    fd_in: FileDesc,
+    mut off_in: Option<&mut off_t>,
+    fd_out: FileDesc,
+    mut off_out: Option<&mut off_t>,
+    len: usize,
+    flags: u32,
+) -> Result<usize> {
+    debug!(
+        "splice: fd_in: {}, off_in: {:?}, fd_out: {}, off_out: {:?}, len: {}, flags: {:#x}",
+        fd_in, off_in, fd_out, off_out, len, flags
+    );
+
+    let flags = SpliceFlags::from_bits(flags).ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+    let nonblocking = flags.contains(SpliceFlags::SPLICE_F_NONBLOCK);
+    let current = current!();
+    let in_file = current.file(fd_in)?;
+    let out_file = current.file(fd_out)?;
+    check_files(&in_file, &out_file)?;
+    for offset in [&off_in, &off_out].iter() {
+        if let Some(offset) = offset {
+            if **offset < 0 {
+                return_errno!(EINVAL, "offset is negative");
+            }
+        }
+    }
+    if len == 0 {
+        return Ok(0);
+    }
+
+    let mut transferred = 0;
+    let result = match (in_file.as_pipe_reader(), out_file.as_pipe_writer()) {
+        (Ok(reader), out_pipe) => {
+            if off_in.is_some() || (out_pipe.is_ok() && off_out.is_some()) {
+                return_errno!(ESPIPE, "offset is given for a pipe");
+            }
+            if let Ok(writer) = out_pipe {
+                if reader.is_reader_of(writer) {
+                    return_errno!(EINVAL, "the two ends refer to the same pipe");
+                }
+            }
+            splice_from_pipe(
+                reader,
+                &out_file,
+                off_out.as_deref_mut(),
+                len,
+                nonblocking,
+                &mut transferred,
+            )
+        }
+        (Err(_), Ok(writer)) => {
+            if off_out.is_some() {
+                return_errno!(ESPIPE, "offset is given for a pipe");
+            }
+            splice_to_pipe(
+                &in_file,
+                off_in.as_deref_mut(),
+                writer,
+                len,
+                nonblocking,
+                &mut transferred,
+            )
+        }
+        (Err(_), Err(_)) => return_errno!(EINVAL, "neither of the files is a pipe"),
+    };
+    // An error after some bytes have been transferred is not reported to the user
+    if transferred == 0 {
+        result?;
+    }
+    Ok(transferred)
+}
+
+pub fn do_tee(fd_in: FileDesc, fd_out: FileDesc, len: usize, flags: u32) -> Result<usize> {
+    debug!(
+        "tee: fd_in: {}, fd_out: {}, len: {}, flags: {:#x}",
+        fd_in, fd_out, len, flags
+    );
+
+    let flags = SpliceFlags::from_bits(flags).ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+    let nonblocking = flags.contains(SpliceFlags::SPLICE_F_NONBLOCK);
+    let current = current!();
+    let in_file = current.file(fd_in)?;
+    let out_file = current.file(fd_out)?;
+    check_files(&in_file, &out_file)?;
+    let (reader, writer) = match (in_file.as_pipe_reader(), out_file.as_pipe_writer()) {
+        (Ok(reader), Ok(writer)) => (reader, writer),
+        _ => return_errno!(EINVAL, "both files must be pipes"),
+    };
+    if reader.is_reader_of(writer) {
+        return_errno!(EINVAL, "the two ends refer to the same pipe");
+    }
+    if len == 0 {
+        return Ok(0);
+    }
+
+    if nonblocking {
+        if !check_pipe_data(reader, true)? {
+            return Ok(0);
+        }
+        if writer.free_space() == 0 {
+            return_errno!(EAGAIN, "the output pipe is full");
+        }
+    }
+
+    // The data in the input pipe is duplicated, but not consumed. Since the data
+    // is always copied from the beginning of the input pipe, at most one chunk of
+    // data is duplicated.
+    let mut buf = vec![0; min(len, SPLICE_CHUNK_SIZE)];
+    let free_space = writer.free_space();
+    let chunk_len = if free_space > 0 {
+        min(buf.len(), free_space)
+    } else {
+        buf.len()
+    };
+    let peek_len = reader.peek(&mut buf[..chunk_len])?;
+    let mut bytes_written = 0;
+    while bytes_written < peek_len {
+        match writer.write(&buf[bytes_written..peek_len]) {
+            Ok(write_len) => bytes_written += write_len,
+            Err(_) if bytes_written > 0 => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(bytes_written)
+}
+
+pub fn do_vmsplice(fd: FileDesc, bufs: &mut [&mut [u8]], flags: u32) -> Result<usize> {
+    debug!(
+        "vmsplice: fd: {}, nr_segs: {}, flags: {:#x}",
+        fd,
+        bufs.len(),
+        flags
+    );
+
+    let flags = SpliceFlags::from_bits(flags).ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+    let nonblocking = flags.contains(SpliceFlags::SPLICE_F_NONBLOCK);
+    let file_ref = current!().file(fd)?;
+    if let Ok(writer) = file_ref.as_pipe_writer() {
+        if nonblocking && writer.free_space() == 0 {
+            return_errno!(EAGAIN, "the pipe is full");
+        }
+        let bufs: Vec<&[u8]> = bufs.iter().map(|buf| &**buf).collect();
+        writer.writev(&bufs)
+    } else if let Ok(reader) = file_ref.as_pipe_reader() {
+        if nonblocking && !check_pipe_data(reader, true)? {
+            return Ok(0);
+        }
+        reader.readv(bufs)
+    } else {
+        return_errno!(EBADF, "not a pipe");
+    }
+}
+
+fn check_files(in_file: &FileRef, out_file: &FileRef) -> Result<()> {
+    // Not all types of files support querying the access mode or status flags
+    if let Ok(access_mode) = in_file.access_mode() {
+        if !access_mode.readable() {
+            return_errno!(EBADF, "fd_in is not opened for reading");
+        }
+    }
+    if let Ok(access_mode) = out_file.access_mode() {
+        if !access_mode.writable() {
+            return_errno!(EBADF, "fd_out is not opened for writing");
+        }
+    }
+    if let Ok(status_flags) = out_file.status_flags() {
+        if status_flags.always_append() {
+            return_errno!(EINVAL, "fd_out is opened with O_APPEND");
+        }
+    }
+    Ok(())
+}
+
+/// Check whether there is data to read in the pipe, returning false on EOF.
+///
+/// If the pipe is empty, `EAGAIN` is returned when `nonblocking` is true,
+/// otherwise, this function returns true, letting the following read block.
+fn check_pipe_data(reader: &PipeReader, nonblocking: bool) -> Result<bool> {
+    let events = reader.poll_new();
+    if events.contains(IoEvents::IN) {
+        return Ok(true);
+    }
+    if events.contains(IoEvents::HUP) {
+        return Ok(false);
+    }
+    if nonblocking {
+        return_errno!(EAGAIN, "the pipe is empty");
+    }
+    Ok(true)
+}
+
+/// Move data from a pipe to a file, which may be another pipe.
+///
+/// The data is consumed from the pipe only after it is written to the file,
+/// so no data is lost if the file cannot accept all the data.
+fn splice_from_pipe(
+    reader: &PipeReader,
+    out_file: &FileRef,
+    mut off_out: Option<&mut off_t>,
+    len: usize,
+    nonblocking: bool,
+    transferred: &mut usize,
+) -> Result<()> {
+    if !check_pipe_data(reader, nonblocking)? {
+        return Ok(());
+    }
+    if let Ok(writer) = out_file.as_pipe_writer() {
+        if nonblocking && writer.free_space() == 0 {
+            return_errno!(EAGAIN, "the output pipe is full");
+        }
+    }
+
+    let mut buf = vec![0; min(len, SPLICE_CHUNK_SIZE)];
+    while *transferred < len {
+        // Only block before any data is transferred
+        if *transferred > 0 && !reader.poll_new().contains(IoEvents::IN) {
+            break;
+        }
+
+        let chunk_len = min(buf.len(), len - *transferred);
+        let peek_len = reader.peek(&mut buf[..chunk_len])?;
+        if peek_len == 0 {
+            break;
+        }
+        let write_len = match off_out.as_mut() {
+            Some(offset) => out_file.write_at(**offset as usize, &buf[..peek_len])?,
+            None => out_file.write(&buf[..peek_len])?,
+        };
+        reader.consume(write_len);
+        if let Some(offset) = off_out.as_mut() {
+            **offset += write_len as off_t;
+        }
+        *transferred += write_len;
+
+        if write_len < peek_len {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Move data from a file (not a pipe) to a pipe.
+///
+/// To avoid losing data that has been read from the file, data is only read
+/// when there is enough free space in the pipe, unless the pipe is allowed to
+/// block before any data is transferred.
+fn splice_to_pipe(
+    in_file: &FileRef,
+    mut off_in: Option<&mut off_t>,
+    writer: &PipeWriter,
+    len: usize,
+    nonblocking: bool,
+    transferred: &mut usize,
+) -> Result<()> {
+    let nonblocking = nonblocking || writer.status_flags()?.contains(StatusFlags::O_NONBLOCK);
+
+    let mut buf = vec![0; min(len, SPLICE_CHUNK_SIZE)];
+    while *transferred < len {
+        let mut chunk_len = min(buf.len(), len - *transferred);
+        if nonblocking || *transferred > 0 {
+            let free_space = writer.free_space();
+            if free_space == 0 {
+                if *transferred > 0 {
+                    break;
+                }
+                return_errno!(EAGAIN, "the pipe is full");
+            }
+            chunk_len = min(chunk_len, free_space);
+        }
+
+        let read_len = match off_in.as_mut() {
+            Some(offset) => in_file.read_at(**offset as usize, &mut buf[..chunk_len])?,
+            None => in_file.read(&mut buf[..chunk_len])?,
+        };
+        if read_len == 0 {
+            break;
+        }
+        if let Some(offset) = off_in.as_mut() {
+            **offset += read_len as off_t;
+        }
+
+        let mut bytes_written = 0;
+        while bytes_written < read_len {
+            bytes_written += writer.write(&buf[bytes_written..read_len])?;
+        }
+        *transferred += read_len;
+
+        // A short read indicates that there is no more data available for now
+        if read_len < chunk_len {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -45,6 +45,7 @@ pub fn do_symlinkat(target: &str, link_path: &FsPath) -> Result<usize> {
         return_errno!(EPERM, "symlink cannot be created");
     }
     let link_inode = dir_inode.create(link_name, FileType::SymLink, 0o0777)?;
+    inotify::notify_dir_entry(dir_inode.as_ref(), link_name, InotifyMask::IN_CREATE, 0);
     let data = target.as_bytes();
     link_inode.resize(data.len())?;
     link_inode.write_at(0, data)?;
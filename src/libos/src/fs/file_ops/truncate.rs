@@ -8,6 +8,7 @@ pub fn do_truncate(path: &str, len: usize) -> Result<()> {
         fs.lookup_inode(&path)?
     };
     inode.resize(len)?;
+    inotify::notify_modify(inode.as_ref(), &path);
     Ok(())
 }
 
@@ -23,6 +23,13 @@ fn do_unlink(path: &str) -> Result<()> {
         warn!("ignoring the sticky bit");
     }
     dir_inode.unlink(file_name)?;
+    if metadata.nlinks > 1 {
+        inotify::notify_inode(file_inode.as_ref(), InotifyMask::IN_ATTRIB);
+    } else {
+        inotify::notify_inode(file_inode.as_ref(), InotifyMask::IN_DELETE_SELF);
+        XattrTable::unlink(&file_inode);
+    }
+    inotify::notify_dir_entry(dir_inode.as_ref(), file_name, InotifyMask::IN_DELETE, 0);
     Ok(())
 }
 
@@ -0,0 +1,112 @@
+use super::*;
+use crate::time::{do_clock_gettime, timespec_t, timeval_t, ClockID};
+
+/// Set the timestamp to the current time
+const UTIME_NOW: i64 = (1 << 30) - 1;
+/// Leave the timestamp unchanged
+const UTIME_OMIT: i64 = (1 << 30) - 2;
+
+bitflags! {
+    pub struct UtimeFlags: i32 {
+        const AT_SYMLINK_NOFOLLOW = 0x100;
+        const AT_EMPTY_PATH = 0x1000;
+    }
+}
+
+/// The new value of a timestamp of a file.
+#[derive(Debug, Clone, Copy)]
+pub enum Utime {
+    Now,
+    Omit,
+    Time(Timespec),
+}
+
+impl Utime {
+    pub fn from_timespec(time: &timespec_t) -> Result<Self> {
+        let utime = match time.nsec() {
+            UTIME_NOW => Utime::Now,
+            UTIME_OMIT => Utime::Omit,
+            _ => {
+                time.validate()?;
+                Utime::Time(Timespec {
+                    sec: time.sec(),
+                    nsec: time.nsec() as i32,
+                })
+            }
+        };
+        Ok(utime)
+    }
+
+    pub fn from_timeval(time: &timeval_t) -> Result<Self> {
+        time.validate()?;
+        let duration = time.as_duration();
+        Ok(Utime::Time(Timespec {
+            sec: duration.as_secs() as i64,
+            nsec: duration.subsec_nanos() as i32,
+        }))
+    }
+
+    fn apply(&self, time: &mut Timespec, now: &Timespec) {
+        match self {
+            Utime::Now => *time = *now,
+            Utime::Omit => {}
+            Utime::Time(new_time) => *time = *new_time,
+        }
+    }
+}
+
+pub fn do_utimensat(fs_path: &FsPath, atime: Utime, mtime: Utime, flags: UtimeFlags) -> Result<()> {
+    debug!(
+        "utimensat: fs_path: {:?}, atime: {:?}, mtime: {:?}, flags: {:?}",
+        fs_path, atime, mtime, flags
+    );
+
+    if let Some(fd) = fs_path.as_fd() {
+        return do_futimens(fd, atime, mtime);
+    }
+    let inode = {
+        let path = fs_path.to_abs_path()?;
+        let current = current!();
+        let fs = current.fs().lock().unwrap();
+        if flags.contains(UtimeFlags::AT_SYMLINK_NOFOLLOW) {
+            fs.lookup_inode_no_follow(&path)?
+        } else {
+            fs.lookup_inode(&path)?
+        }
+    };
+    let mut info = inode.metadata()?;
+    update_times(&mut info, atime, mtime)?;
+    inode.set_metadata(&info)?;
+    Ok(())
+}
+
+pub fn do_futimens(fd: FileDesc, atime: Utime, mtime: Utime) -> Result<()> {
+    debug!(
+        "futimens: fd: {}, atime: {:?}, mtime: {:?}",
+        fd, atime, mtime
+    );
+
+    let file_ref = current!().file(fd)?;
+    let mut info = file_ref.metadata()?;
+    update_times(&mut info, atime, mtime)?;
+    file_ref.set_metadata(&info)?;
+    Ok(())
+}
+
+fn update_times(info: &mut Metadata, atime: Utime, mtime: Utime) -> Result<()> {
+    if let (Utime::Omit, Utime::Omit) = (atime, mtime) {
+        return Ok(());
+    }
+    let now = {
+        let now = do_clock_gettime(ClockID::CLOCK_REALTIME)?;
+        Timespec {
+            sec: now.sec(),
+            nsec: now.nsec() as i32,
+        }
+    };
+    atime.apply(&mut info.atime, &now);
+    mtime.apply(&mut info.mtime, &now);
+    // The status change time is always set to the current time
+    info.ctime = now;
+    Ok(())
+}
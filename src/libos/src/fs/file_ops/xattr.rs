@@ -0,0 +1,108 @@
+use super::*;
+
+/// The file whose extended attributes are accessed.
+#[derive(Debug)]
+pub enum XattrTarget<'a> {
+    /// A path, dereferenced if it is a symlink
+    Path(&'a str),
+    /// A path, not dereferenced if it is a symlink
+    PathNoFollow(&'a str),
+    /// An open file
+    File(FileDesc),
+}
+
+impl<'a> XattrTarget<'a> {
+    fn lookup_inode(&self) -> Result<Arc<dyn INode>> {
+        let current = current!();
+        match self {
+            XattrTarget::Path(path) => current.fs().lock().unwrap().lookup_inode(path),
+            XattrTarget::PathNoFollow(path) => {
+                current.fs().lock().unwrap().lookup_inode_no_follow(path)
+            }
+            XattrTarget::File(fd) => {
+                let file_ref = current.file(*fd)?;
+                if let Ok(inode_file) = file_ref.as_inode_file() {
+                    Ok(inode_file.inode().clone())
+                } else if let Ok(mem_file) = file_ref.as_mem_file() {
+                    Ok(mem_file.inode().clone())
+                } else {
+                    return_errno!(
+                        EOPNOTSUPP,
+                        "extended attributes are not supported by the file"
+                    )
+                }
+            }
+        }
+    }
+}
+
+pub fn do_setxattr(
+    target: &XattrTarget,
+    name: &str,
+    value: &[u8],
+    flags: XattrFlags,
+) -> Result<()> {
+    debug!(
+        "setxattr: target: {:?}, name: {:?}, size: {}, flags: {:?}",
+        target,
+        name,
+        value.len(),
+        flags
+    );
+    let inode = target.lookup_inode()?;
+    match PosixAclType::from_xattr_name(name) {
+        Some(acl_type) => PosixAcl::set(inode.as_ref(), acl_type, value, flags),
+        None => XattrTable::set(inode.as_ref(), name, value, flags),
+    }
+}
+
+/// Get the value of an attribute, returning the size of the value.
+///
+/// If the buffer is empty, only the size of the value is returned.
+pub fn do_getxattr(target: &XattrTarget, name: &str, buf: &mut [u8]) -> Result<usize> {
+    debug!(
+        "getxattr: target: {:?}, name: {:?}, size: {}",
+        target,
+        name,
+        buf.len()
+    );
+    let inode = target.lookup_inode()?;
+    let value = XattrTable::get(inode.as_ref(), name)?;
+    if buf.is_empty() {
+        return Ok(value.len());
+    }
+    if buf.len() < value.len() {
+        return_errno!(ERANGE, "the buffer is too small");
+    }
+    buf[..value.len()].copy_from_slice(&value);
+    Ok(value.len())
+}
+
+/// Get the null-terminated names of all the attributes, returning the size of the names.
+///
+/// If the buffer is empty, only the size of the names is returned.
+pub fn do_listxattr(target: &XattrTarget, buf: &mut [u8]) -> Result<usize> {
+    debug!("listxattr: target: {:?}, size: {}", target, buf.len());
+    let inode = target.lookup_inode()?;
+    let names = XattrTable::list(inode.as_ref())?;
+    let list_len = names.iter().map(|name| name.len() + 1).sum();
+    if buf.is_empty() {
+        return Ok(list_len);
+    }
+    if buf.len() < list_len {
+        return_errno!(ERANGE, "the buffer is too small");
+    }
+    let mut offset = 0;
+    for name in names.iter() {
+        buf[offset..offset + name.len()].copy_from_slice(name.as_bytes());
+        buf[offset + name.len()] = 0;
+        offset += name.len() + 1;
+    }
+    Ok(list_len)
+}
+
+pub fn do_removexattr(target: &XattrTarget, name: &str) -> Result<()> {
+    debug!("removexattr: target: {:?}, name: {:?}", target, name);
+    let inode = target.lookup_inode()?;
+    XattrTable::remove(inode.as_ref(), name)
+}
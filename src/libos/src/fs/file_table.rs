@@ -21,13 +21,20 @@ impl FileTable {
         }
     }
 
+    /// Duplicate a file to the lowest free fd that is not less than min_fd.
+    ///
+    /// The new fd must be less than max_fd, i.e., the limit of RLIMIT_NOFILE.
     pub fn dup(
         &mut self,
         fd: FileDesc,
         min_fd: FileDesc,
+        max_fd: FileDesc,
         close_on_spawn: bool,
     ) -> Result<FileDesc> {
         let file_ref = self.get(fd)?;
+        if min_fd >= max_fd {
+            return_errno!(EINVAL, "min_fd exceeds the limit of open files");
+        }
 
         let min_fd = min_fd as usize;
         let min_free_fd = {
@@ -55,6 +62,9 @@ impl FileTable {
                 table.len() - 1
             }
         } as FileDesc;
+        if min_free_fd >= max_fd {
+            return_errno!(EMFILE, "too many open files");
+        }
 
         self.put_at(min_free_fd, file_ref, close_on_spawn);
 
@@ -62,27 +72,36 @@ impl FileTable {
     }
 
     pub fn put(&mut self, file: FileRef, close_on_spawn: bool) -> FileDesc {
+        let min_free_fd = self.lowest_free_fd() as usize;
         let mut table = &mut self.table;
+        if min_free_fd == table.len() {
+            table.push(None);
+        }
+
+        table[min_free_fd] = Some(FileTableEntry::new(file, close_on_spawn));
+        self.num_fds += 1;
+
+        min_free_fd as FileDesc
+    }
 
-        let min_free_fd = if self.num_fds < table.len() {
-            table
+    /// Get the file descriptor that the next file put into the table gets.
+    pub fn lowest_free_fd(&self) -> FileDesc {
+        let min_free_fd = if self.num_fds < self.table.len() {
+            self.table
                 .iter()
                 .enumerate()
                 .find(|&(idx, opt)| opt.is_none())
                 .unwrap()
                 .0
         } else {
-            table.push(None);
-            table.len() - 1
+            self.table.len()
         };
-
-        table[min_free_fd as usize] = Some(FileTableEntry::new(file, close_on_spawn));
-        self.num_fds += 1;
-
         min_free_fd as FileDesc
     }
 
-    pub fn put_at(&mut self, fd: FileDesc, file: FileRef, close_on_spawn: bool) {
+    /// Put the file at the file descriptor, returning the file that the file
+    /// descriptor referred to, if any.
+    pub fn put_at(&mut self, fd: FileDesc, file: FileRef, close_on_spawn: bool) -> Option<FileRef> {
         let mut table = &mut self.table;
         let mut table_entry = Some(FileTableEntry::new(file, close_on_spawn));
         if fd as usize >= table.len() {
@@ -92,6 +111,7 @@ impl FileTable {
         if table_entry.is_none() {
             self.num_fds += 1;
         }
+        table_entry.map(|entry| entry.file)
     }
 
     pub fn get(&self, fd: FileDesc) -> Result<FileRef> {
@@ -99,6 +119,19 @@ impl FileTable {
         Ok(entry.file.clone())
     }
 
+    /// Get the file descriptors in use, together with the files they refer to
+    pub fn fds_and_files(&self) -> Vec<(FileDesc, FileRef)> {
+        self.table
+            .iter()
+            .enumerate()
+            .filter_map(|(fd, entry)| {
+                entry
+                    .as_ref()
+                    .map(|entry| (fd as FileDesc, Arc::clone(entry.get_file())))
+            })
+            .collect()
+    }
+
     pub fn get_entry(&self, fd: FileDesc) -> Result<&FileTableEntry> {
         if fd as usize >= self.table.len() {
             return_errno!(EBADF, "Invalid file descriptor");
@@ -141,6 +174,45 @@ impl FileTable {
         }
     }
 
+    /// Remove the file descriptors in the range of [first, last]
+    pub fn del_range(&mut self, first: FileDesc, last: FileDesc) -> Vec<FileRef> {
+        let mut deleted_files = Vec::new();
+        let mut deleted_fds = Vec::new();
+        let end = self.table.len().min(last as usize + 1);
+        for fd in (first as usize)..end {
+            if let Some(entry) = self.table[fd].take() {
+                deleted_files.push(entry.file);
+                deleted_fds.push(fd as FileDesc);
+                self.num_fds -= 1;
+            }
+        }
+
+        for fd in deleted_fds {
+            self.broadcast_del(fd);
+        }
+        deleted_files
+    }
+
+    /// Set the file descriptors in the range of [first, last] to be close-on-spawn
+    pub fn set_close_on_spawn_range(&mut self, first: FileDesc, last: FileDesc) {
+        let end = self.table.len().min(last as usize + 1);
+        for entry in self.table[(first as usize).min(end)..end].iter_mut() {
+            if let Some(entry) = entry {
+                entry.set_close_on_spawn(true);
+            }
+        }
+    }
+
+    /// Get the files referred to by the file descriptors that are close-on-spawn
+    pub fn close_on_spawn_files(&self) -> Vec<FileRef> {
+        self.table
+            .iter()
+            .filter_map(|entry| entry.as_ref())
+            .filter(|entry| entry.is_close_on_spawn())
+            .map(|entry| Arc::clone(entry.get_file()))
+            .collect()
+    }
+
     /// Remove file descriptors that are close-on-spawn
     pub fn close_on_spawn(&mut self) {
         let mut deleted_fds = Vec::new();
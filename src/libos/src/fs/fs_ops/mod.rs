@@ -2,10 +2,16 @@ use super::*;
 
 pub use self::chdir::do_chdir;
 pub use self::getcwd::do_getcwd;
-pub use self::mount::do_mount_rootfs;
+pub use self::mount::{
+    do_mount, do_mount_rootfs, do_umount, is_on_read_only_mount, MountFlags, UmountFlags,
+};
+pub use self::statfs::{do_fstatfs, do_statfs, Statfs};
 pub use self::sync::do_sync;
+pub use self::umask::do_umask;
 
 mod chdir;
 mod getcwd;
 mod mount;
+mod statfs;
 mod sync;
+mod umask;
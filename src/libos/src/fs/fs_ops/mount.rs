@@ -1,10 +1,99 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Once;
+use std::untrusted::path::PathEx;
 
+use super::bindfs::BindFS;
+use super::hostfs::HostFS;
 use super::rootfs::{mount_nonroot_fs_according_to, open_root_fs_according_to};
+use super::statfs::normalize_path;
 use super::*;
+use rcore_fs_mountfs::MNode;
+use rcore_fs_ramfs::RamFS;
 
 lazy_static! {
     static ref MOUNT_ONCE: Once = Once::new();
+    /// The file systems mounted by LibOS processes at runtime
+    static ref RUNTIME_MOUNTS: SgxMutex<Vec<RuntimeMount>> = SgxMutex::new(Vec::new());
+}
+
+bitflags! {
+    pub struct MountFlags: u32 {
+        const MS_RDONLY = 1;
+        const MS_NOSUID = 2;
+        const MS_NODEV = 4;
+        const MS_NOEXEC = 8;
+        const MS_SYNCHRONOUS = 16;
+        const MS_REMOUNT = 32;
+        const MS_MANDLOCK = 64;
+        const MS_DIRSYNC = 128;
+        const MS_NOSYMFOLLOW = 256;
+        const MS_NOATIME = 1024;
+        const MS_NODIRATIME = 2048;
+        const MS_BIND = 4096;
+        const MS_MOVE = 8192;
+        const MS_REC = 16384;
+        const MS_SILENT = 32768;
+        const MS_POSIXACL = 1 << 16;
+        const MS_UNBINDABLE = 1 << 17;
+        const MS_PRIVATE = 1 << 18;
+        const MS_SLAVE = 1 << 19;
+        const MS_SHARED = 1 << 20;
+        const MS_RELATIME = 1 << 21;
+        const MS_I_VERSION = 1 << 23;
+        const MS_STRICTATIME = 1 << 24;
+        const MS_LAZYTIME = 1 << 25;
+    }
+}
+
+bitflags! {
+    pub struct UmountFlags: u32 {
+        const MNT_FORCE = 1;
+        const MNT_DETACH = 2;
+        const MNT_EXPIRE = 4;
+        const UMOUNT_NOFOLLOW = 8;
+    }
+}
+
+/// A file system mounted at runtime.
+#[derive(Debug, Clone)]
+pub struct RuntimeMount {
+    pub fs_type: RuntimeMountFsType,
+    pub target: PathBuf,
+    /// The host dir of HostFS, or the source dir of a bind mount
+    pub source: Option<PathBuf>,
+    /// Whether a bind mount is read-only, which is shared with its BindFS
+    pub read_only: Arc<AtomicBool>,
+}
+
+/// The types of the file systems that can be mounted at runtime.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RuntimeMountFsType {
+    RamFS,
+    TmpFS,
+    HostFS,
+    Bind,
+}
+
+impl RuntimeMountFsType {
+    fn from_str(fs_type: &str) -> Result<Self> {
+        Ok(match fs_type {
+            "ramfs" => Self::RamFS,
+            "tmpfs" => Self::TmpFS,
+            "hostfs" => Self::HostFS,
+            _ => return_errno!(ENODEV, "the file system type is not supported"),
+        })
+    }
+
+    /// The name of the type in the `fs.runtime_mount` policy.
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::RamFS => "ramfs",
+            Self::TmpFS => "tmpfs",
+            Self::HostFS => "hostfs",
+            Self::Bind => "bind",
+        }
+    }
 }
 
 pub fn do_mount_rootfs(
@@ -29,3 +118,229 @@ pub fn do_mount_rootfs(
     });
     Ok(())
 }
+
+/// Mount a file system at runtime, which is allowed by the `fs.runtime_mount`
+/// policy in the config.
+pub fn do_mount(
+    source: Option<&str>,
+    target: &str,
+    fs_type: Option<&str>,
+    flags: MountFlags,
+) -> Result<()> {
+    debug!(
+        "mount: source: {:?}, target: {:?}, fs_type: {:?}, flags: {:?}",
+        source, target, fs_type, flags
+    );
+
+    // There is only one mount namespace, so the propagation types make no difference
+    if flags.intersects(
+        MountFlags::MS_SHARED
+            | MountFlags::MS_PRIVATE
+            | MountFlags::MS_SLAVE
+            | MountFlags::MS_UNBINDABLE,
+    ) {
+        return Ok(());
+    }
+    if flags.contains(MountFlags::MS_MOVE) {
+        return_errno!(EINVAL, "move mounts are not supported");
+    }
+    if flags.contains(MountFlags::MS_REMOUNT) {
+        if !flags.contains(MountFlags::MS_BIND) {
+            return_errno!(EINVAL, "only bind mounts can be remounted");
+        }
+        return remount_bind(target, flags);
+    }
+
+    let policy = &config::LIBOS_CONFIG.fs.runtime_mount;
+    // The file system type is ignored by bind mounts
+    let fs_type = if flags.contains(MountFlags::MS_BIND) {
+        RuntimeMountFsType::Bind
+    } else {
+        let fs_type = fs_type.ok_or_else(|| errno!(EINVAL, "the file system type is expected"))?;
+        RuntimeMountFsType::from_str(fs_type)?
+    };
+    if !policy.types.iter().any(|type_| type_ == fs_type.as_str()) {
+        return_errno!(
+            EPERM,
+            "the file system type is not allowed to mount at runtime"
+        );
+    }
+    if flags.contains(MountFlags::MS_RDONLY) && fs_type != RuntimeMountFsType::Bind {
+        return_errno!(EINVAL, "only bind mounts can be read-only");
+    }
+    let read_only = Arc::new(AtomicBool::new(flags.contains(MountFlags::MS_RDONLY)));
+
+    let current = current!();
+    let fs_view = current.fs().lock().unwrap();
+    let target_inode = if flags.contains(MountFlags::MS_NOSYMFOLLOW) {
+        fs_view.lookup_inode_no_follow(target)?
+    } else {
+        fs_view.lookup_inode(target)?
+    };
+    if target_inode.metadata()?.type_ != FileType::Dir {
+        return_errno!(ENOTDIR, "the target is not a directory");
+    }
+    let abs_target =
+        normalize_path(&fs_view.convert_to_abs_path(&fs_view.lookup_real_path(target)?));
+
+    let (mount_fs, source): (Arc<dyn FileSystem>, Option<PathBuf>) = match fs_type {
+        RuntimeMountFsType::RamFS | RuntimeMountFsType::TmpFS => (RamFS::new() as _, None),
+        RuntimeMountFsType::HostFS => {
+            let source = source.ok_or_else(|| errno!(EINVAL, "source is expected for HostFS"))?;
+            // A relative host path is relative to the instance dir, same as the config
+            let source_path = unsafe { PathBuf::from(&INSTANCE_DIR) }
+                .join(source)
+                .canonicalize()
+                .map_err(|_| errno!(ENOENT, "the source of HostFS does not exist"))?;
+            if !source_path.is_dir() {
+                return_errno!(ENOTDIR, "the source of HostFS is not a directory");
+            }
+            if !policy
+                .hostfs_sources
+                .iter()
+                .any(|allowed_source| source_path.starts_with(allowed_source))
+            {
+                return_errno!(EPERM, "the source is not allowed to mount as HostFS");
+            }
+            (HostFS::new(&source_path) as _, Some(source_path))
+        }
+        RuntimeMountFsType::Bind => {
+            let source =
+                source.ok_or_else(|| errno!(EINVAL, "source is expected for bind mounts"))?;
+            let source_inode = fs_view.lookup_inode(source)?;
+            if source_inode.metadata()?.type_ != FileType::Dir {
+                return_errno!(ENOTDIR, "only dirs can be bind mounted");
+            }
+            let abs_source =
+                normalize_path(&fs_view.convert_to_abs_path(&fs_view.lookup_real_path(source)?));
+            (
+                BindFS::new(source_inode, read_only.clone()) as _,
+                Some(abs_source),
+            )
+        }
+    };
+
+    let mut runtime_mounts = RUNTIME_MOUNTS.lock().unwrap();
+    // Mounting on top of a mount point is not supported, which would hide the
+    // file system mounted there
+    if config::LIBOS_CONFIG
+        .mount
+        .iter()
+        .map(|mc| &mc.target)
+        .chain(runtime_mounts.iter().map(|mount| &mount.target))
+        .any(|mount_target| mount_target == &abs_target)
+    {
+        return_errno!(EBUSY, "the target is already a mount point");
+    }
+    let mount_dir = target_inode
+        .as_any_ref()
+        .downcast_ref::<MNode>()
+        .ok_or_else(|| errno!(EINVAL, "the target is not in a mountable file system"))?;
+    mount_dir.mount(mount_fs)?;
+    runtime_mounts.push(RuntimeMount {
+        fs_type,
+        target: abs_target,
+        source,
+        read_only,
+    });
+    Ok(())
+}
+
+/// Change whether a bind mount is read-only.
+fn remount_bind(target: &str, flags: MountFlags) -> Result<()> {
+    let abs_target = {
+        let current = current!();
+        let fs_view = current.fs().lock().unwrap();
+        normalize_path(&fs_view.convert_to_abs_path(&fs_view.lookup_real_path(target)?))
+    };
+    let runtime_mounts = RUNTIME_MOUNTS.lock().unwrap();
+    let bind_mount = runtime_mounts
+        .iter()
+        .find(|mount| mount.target == abs_target && mount.fs_type == RuntimeMountFsType::Bind)
+        .ok_or_else(|| errno!(EINVAL, "the target is not a bind mount"))?;
+    bind_mount
+        .read_only
+        .store(flags.contains(MountFlags::MS_RDONLY), Ordering::Relaxed);
+    Ok(())
+}
+
+/// Unmount a file system mounted at runtime.
+///
+/// The file system is always detached lazily, i.e., the opened files in it
+/// keep working until they are closed.
+pub fn do_umount(target: &str, flags: UmountFlags) -> Result<()> {
+    debug!("umount: target: {:?}, flags: {:?}", target, flags);
+
+    if flags.contains(UmountFlags::MNT_EXPIRE) {
+        return_errno!(EINVAL, "MNT_EXPIRE is not supported");
+    }
+
+    let current = current!();
+    let fs_view = current.fs().lock().unwrap();
+    let (target_inode, abs_target) = if flags.contains(UmountFlags::UMOUNT_NOFOLLOW) {
+        let target_inode = fs_view.lookup_inode_no_follow(target)?;
+        (target_inode, fs_view.convert_to_abs_path(target))
+    } else {
+        let target_inode = fs_view.lookup_inode(target)?;
+        let real_target = fs_view.lookup_real_path(target)?;
+        (target_inode, fs_view.convert_to_abs_path(&real_target))
+    };
+    let abs_target = normalize_path(&abs_target);
+
+    let mut runtime_mounts = RUNTIME_MOUNTS.lock().unwrap();
+    let idx = match runtime_mounts
+        .iter()
+        .position(|mount| mount.target == abs_target)
+    {
+        Some(idx) => idx,
+        None => {
+            if config::LIBOS_CONFIG
+                .mount
+                .iter()
+                .any(|mc| mc.target == abs_target)
+            {
+                return_errno!(EPERM, "the file systems in the config cannot be unmounted");
+            }
+            return_errno!(EINVAL, "the target is not a mount point");
+        }
+    };
+    if runtime_mounts
+        .iter()
+        .any(|mount| mount.target != abs_target && mount.target.starts_with(&abs_target))
+    {
+        return_errno!(EBUSY, "other file systems are mounted under the target");
+    }
+
+    target_inode.fs().sync()?;
+    let mount_root = target_inode
+        .as_any_ref()
+        .downcast_ref::<MNode>()
+        .ok_or_else(|| errno!(EINVAL, "the target is not a mount point"))?;
+    mount_root.umount()?;
+    runtime_mounts.remove(idx);
+    Ok(())
+}
+
+/// Get the file system mounted at runtime at the longest prefix of the path,
+/// along with its index in the mount order.
+///
+/// Only the first `num_mounts` file systems mounted are looked at, e.g., the
+/// source dir of a bind mount is resolved with the mounts before it.
+pub fn runtime_mount_of_path(abs_path: &Path, num_mounts: usize) -> Option<(usize, RuntimeMount)> {
+    RUNTIME_MOUNTS
+        .lock()
+        .unwrap()
+        .iter()
+        .take(num_mounts)
+        .enumerate()
+        .filter(|(_, mount)| abs_path.starts_with(&mount.target))
+        .max_by_key(|(_, mount)| mount.target.components().count())
+        .map(|(idx, mount)| (idx, mount.clone()))
+}
+
+/// Whether the file is under a read-only bind mount.
+pub fn is_on_read_only_mount(abs_path: &str) -> bool {
+    runtime_mount_of_path(&normalize_path(abs_path), usize::max_value())
+        .map(|(_, mount)| mount.read_only.load(Ordering::Relaxed))
+        .unwrap_or(false)
+}
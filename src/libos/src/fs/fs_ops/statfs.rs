@@ -0,0 +1,246 @@
+use super::mount::{runtime_mount_of_path, RuntimeMountFsType};
+use super::*;
+use crate::vm::{PAGE_SIZE, USER_SPACE_VM_MANAGER};
+use config::ConfigMountFsType;
+use std::ffi::CString;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::Ordering;
+
+// The magic numbers of file systems reported in `Statfs::f_type`.
+//
+// SEFS and UnionFS have no Linux counterparts, so they use their own magic
+// numbers. DevFS is reported as tmpfs, same as the devtmpfs of Linux.
+const SEFS_MAGIC: i64 = 0x5345_4653;
+const UNIONFS_MAGIC: i64 = 0x554e_494f;
+const RAMFS_MAGIC: i64 = 0x8584_58f6;
+const TMPFS_MAGIC: i64 = 0x0102_1994;
+const PROC_SUPER_MAGIC: i64 = 0x9fa0;
+const SYSFS_MAGIC: i64 = 0x6265_6572;
+const ANON_INODE_FS_MAGIC: i64 = 0x0904_1934;
+
+const NAME_MAX: i64 = 255;
+
+/// The flag of read-only file systems in `Statfs::f_flags`
+const ST_RDONLY: i64 = 1;
+
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C)]
+pub struct Statfs {
+    /// type of file system
+    f_type: i64,
+    /// optimal transfer block size
+    f_bsize: i64,
+    /// total data blocks in file system
+    f_blocks: u64,
+    /// free blocks in fs
+    f_bfree: u64,
+    /// free blocks available to unprivileged user
+    f_bavail: u64,
+    /// total file nodes in file system
+    f_files: u64,
+    /// free file nodes in fs
+    f_ffree: u64,
+    /// file system id
+    f_fsid: [i32; 2],
+    /// maximum length of filenames
+    f_namelen: i64,
+    /// fragment size
+    f_frsize: i64,
+    /// mount flags of file system
+    f_flags: i64,
+    /// padding
+    f_spare: [i64; 4],
+}
+
+impl Statfs {
+    /// The statistics of a file system backed by the memory of the enclave.
+    fn from_enclave_memory(f_type: i64) -> Self {
+        let bsize = PAGE_SIZE as u64;
+        let total_size = USER_SPACE_VM_MANAGER.get_total_size() as u64;
+        let free_size = USER_SPACE_VM_MANAGER.get_free_size() as u64;
+        Self {
+            f_type,
+            f_bsize: bsize as i64,
+            f_blocks: total_size / bsize,
+            f_bfree: free_size / bsize,
+            f_bavail: free_size / bsize,
+            f_namelen: NAME_MAX,
+            f_frsize: bsize as i64,
+            ..Default::default()
+        }
+    }
+
+    /// The statistics of the tmpfs at `/dev/shm`, whose size is limited.
+    fn from_shm_fs() -> Self {
+        let bsize = PAGE_SIZE as u64;
+        let total_size = SHM_FS.size_limit() as u64;
+        let free_size = total_size.saturating_sub(SHM_FS.used_size() as u64);
+        Self {
+            f_type: TMPFS_MAGIC,
+            f_bsize: bsize as i64,
+            f_blocks: total_size / bsize,
+            f_bfree: free_size / bsize,
+            f_bavail: free_size / bsize,
+            f_namelen: NAME_MAX,
+            f_frsize: bsize as i64,
+            ..Default::default()
+        }
+    }
+
+    /// The statistics of a pseudo file system, which occupies no space.
+    fn from_pseudo_fs(f_type: i64) -> Self {
+        Self {
+            f_type,
+            f_bsize: PAGE_SIZE as i64,
+            f_namelen: NAME_MAX,
+            f_frsize: PAGE_SIZE as i64,
+            ..Default::default()
+        }
+    }
+}
+
+pub fn do_statfs(path: &str) -> Result<Statfs> {
+    debug!("statfs: path: {:?}", path);
+
+    let abs_path = {
+        let current = current!();
+        let fs = current.fs().lock().unwrap();
+        // Make sure that the file exists
+        fs.lookup_inode(path)?;
+        fs.convert_to_abs_path(&fs.lookup_real_path(path)?)
+    };
+    statfs_of_path(&abs_path)
+}
+
+pub fn do_fstatfs(fd: FileDesc) -> Result<Statfs> {
+    debug!("fstatfs: fd: {}", fd);
+
+    let file_ref = current!().file(fd)?;
+    if let Ok(inode_file) = file_ref.as_inode_file() {
+        statfs_of_path(inode_file.abs_path())
+    } else if file_ref.as_mem_file().is_ok() {
+        Ok(Statfs::from_enclave_memory(TMPFS_MAGIC))
+    } else {
+        // Pipes, sockets, eventfds, etc. are not in any mounted file system
+        Ok(Statfs::from_pseudo_fs(ANON_INODE_FS_MAGIC))
+    }
+}
+
+/// Get the statistics of the file system mounted at the longest prefix of the path.
+fn statfs_of_path(abs_path: &str) -> Result<Statfs> {
+    statfs_of_path_with_mounts(abs_path, usize::max_value())
+}
+
+/// Get the statistics of the file system at the path, looking at only the
+/// first `num_runtime_mounts` file systems mounted at runtime.
+fn statfs_of_path_with_mounts(abs_path: &str, num_runtime_mounts: usize) -> Result<Statfs> {
+    let abs_path = normalize_path(abs_path);
+    // The tmpfs at /dev/shm is a part of DevFS instead of a mount in the config
+    if abs_path.starts_with("/dev/shm") {
+        return Ok(Statfs::from_shm_fs());
+    }
+    let mount_config = config::LIBOS_CONFIG
+        .mount
+        .iter()
+        .filter(|mc| abs_path.starts_with(&mc.target))
+        .max_by_key(|mc| mc.target.components().count())
+        .ok_or_else(|| errno!(ENOENT, "no file system is mounted at the path"))?;
+
+    // A file system mounted at runtime may be mounted under the ones in the config
+    if let Some((idx, runtime_mount)) = runtime_mount_of_path(&abs_path, num_runtime_mounts) {
+        if runtime_mount.target.components().count() > mount_config.target.components().count() {
+            let statfs = match runtime_mount.fs_type {
+                RuntimeMountFsType::RamFS => Statfs::from_enclave_memory(RAMFS_MAGIC),
+                RuntimeMountFsType::TmpFS => Statfs::from_enclave_memory(TMPFS_MAGIC),
+                RuntimeMountFsType::HostFS => {
+                    fetch_host_statfs(runtime_mount.source.as_ref().unwrap())?
+                }
+                // A bind mount reports the file system of its source dir, which
+                // is resolved with the mounts before it
+                RuntimeMountFsType::Bind => {
+                    let source = runtime_mount.source.as_ref().unwrap();
+                    let mut statfs = statfs_of_path_with_mounts(source.to_str().unwrap(), idx)?;
+                    if runtime_mount.read_only.load(Ordering::Relaxed) {
+                        statfs.f_flags |= ST_RDONLY;
+                    }
+                    statfs
+                }
+            };
+            return Ok(statfs);
+        }
+    }
+
+    use self::ConfigMountFsType::*;
+    let statfs = match mount_config.type_ {
+        TYPE_SEFS => {
+            // SEFS stores each inode as a file on the host, so the space and
+            // the inodes available to SEFS are limited by the host file system
+            let source_path = mount_config.source.as_ref().unwrap();
+            let host_statfs = fetch_host_statfs(source_path)?;
+            Statfs {
+                f_type: SEFS_MAGIC,
+                f_namelen: NAME_MAX,
+                f_fsid: [0; 2],
+                ..host_statfs
+            }
+        }
+        TYPE_UNIONFS => {
+            // The writable container layer decides the available space
+            let container_config = mount_config
+                .options
+                .layers
+                .as_ref()
+                .and_then(|layers| layers.iter().find(|mc| mc.options.mac.is_none()))
+                .ok_or_else(|| errno!(EINVAL, "the container layer of UnionFS is not valid"))?;
+            let source_path = container_config.source.as_ref().unwrap();
+            let host_statfs = fetch_host_statfs(source_path)?;
+            Statfs {
+                f_type: UNIONFS_MAGIC,
+                f_namelen: NAME_MAX,
+                f_fsid: [0; 2],
+                ..host_statfs
+            }
+        }
+        TYPE_HOSTFS => {
+            let source_path = mount_config.source.as_ref().unwrap();
+            fetch_host_statfs(source_path)?
+        }
+        TYPE_RAMFS => Statfs::from_enclave_memory(RAMFS_MAGIC),
+        TYPE_DEVFS => Statfs::from_pseudo_fs(TMPFS_MAGIC),
+        TYPE_PROCFS => Statfs::from_pseudo_fs(PROC_SUPER_MAGIC),
+        TYPE_SYSFS => Statfs::from_pseudo_fs(SYSFS_MAGIC),
+    };
+    Ok(statfs)
+}
+
+/// Remove the "." and ".." components of an absolute path.
+pub(super) fn normalize_path(abs_path: &str) -> PathBuf {
+    let mut normalized_path = PathBuf::from("/");
+    for component in Path::new(abs_path).components() {
+        match component {
+            Component::Normal(name) => normalized_path.push(name),
+            Component::ParentDir => {
+                normalized_path.pop();
+            }
+            Component::RootDir | Component::CurDir | Component::Prefix(_) => {}
+        }
+    }
+    normalized_path
+}
+
+fn fetch_host_statfs(path: &Path) -> Result<Statfs> {
+    let path = CString::new(path.to_str().unwrap())
+        .map_err(|_| errno!(EINVAL, "the path of the host file system is invalid"))?;
+    let mut statfs: Statfs = Default::default();
+    try_libc!({
+        let mut retval: i32 = 0;
+        let status = occlum_ocall_statfs(&mut retval, path.as_ptr(), &mut statfs);
+        assert!(status == sgx_status_t::SGX_SUCCESS);
+        retval
+    });
+    Ok(statfs)
+}
+
+extern "C" {
+    fn occlum_ocall_statfs(ret: *mut i32, path: *const c_char, buf: *mut Statfs) -> sgx_status_t;
+}
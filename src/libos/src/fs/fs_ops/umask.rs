@@ -0,0 +1,10 @@
+use super::*;
+
+/// Set the file mode creation mask, returning the previous mask.
+pub fn do_umask(mask: u16) -> Result<u16> {
+    debug!("umask: mask: {:o}", mask);
+
+    let current = current!();
+    let mut fs = current.fs().lock().unwrap();
+    Ok(fs.set_umask(mask))
+}
@@ -1,18 +1,31 @@
 /// Present a per-process view of FS.
 use super::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 #[derive(Debug, Clone)]
 pub struct FsView {
     cwd: String,
+    umask: u16,
 }
 
 impl FsView {
     pub fn new() -> FsView {
         Self {
             cwd: "/".to_owned(),
+            umask: 0,
         }
     }
 
+    /// Get the file mode creation mask.
+    pub fn umask(&self) -> u16 {
+        self.umask
+    }
+
+    /// Set the file mode creation mask, returning the previous mask.
+    pub fn set_umask(&mut self, umask: u16) -> u16 {
+        std::mem::replace(&mut self.umask, umask & 0o777)
+    }
+
     /// Get the current working directory.
     pub fn cwd(&self) -> &str {
         &self.cwd
@@ -40,6 +53,11 @@ impl FsView {
     /// Open a file on the process. But DO NOT add it to file table.
     pub fn open_file(&self, path: &str, flags: u32, mode: u32) -> Result<Arc<dyn File>> {
         let creation_flags = CreationFlags::from_bits_truncate(flags);
+        if creation_flags.is_tmpfile() {
+            let dir_inode = self.lookup_inode(path)?;
+            let abs_dir_path = self.convert_to_abs_path(path);
+            return self.open_tmpfile(dir_inode, &abs_dir_path, flags, mode);
+        }
         let inode = if creation_flags.no_follow_symlink() {
             match self.lookup_inode_no_follow(path) {
                 Ok(inode) => {
@@ -64,10 +82,7 @@ impl FsView {
                 Err(e) if e.errno() == ENOENT && creation_flags.can_create() => {
                     let (dir_path, file_name) = split_path(&path);
                     let dir_inode = self.lookup_inode(dir_path)?;
-                    if !dir_inode.allow_write()? {
-                        return_errno!(EPERM, "file cannot be created");
-                    }
-                    dir_inode.create(file_name, FileType::File, mode)?
+                    self.create_file(&dir_inode, file_name, mode)?
                 }
                 Err(e) => return Err(e),
             }
@@ -87,22 +102,318 @@ impl FsView {
                     }
                     inode
                 }
-                Err(e) if e.errno() == ENOENT && creation_flags.can_create() => {
+                Err(e) => {
+                    // The path may refer to a file that has no path to be
+                    // reopened with, e.g., `/proc/self/fd/N` of a pipe
+                    if let Some(file_ref) = self.lookup_fd_magic_link(path) {
+                        return Ok(file_ref);
+                    }
+                    if e.errno() != ENOENT || !creation_flags.can_create() {
+                        return Err(e);
+                    }
                     let real_path = self.lookup_real_path(&path)?;
                     let (dir_path, file_name) = split_path(&real_path);
                     let dir_inode = self.lookup_inode(dir_path)?;
-                    if !dir_inode.allow_write()? {
-                        return_errno!(EPERM, "file cannot be created");
-                    }
-                    dir_inode.create(file_name, FileType::File, mode)?
+                    self.create_file(&dir_inode, file_name, mode)?
                 }
-                Err(e) => return Err(e),
             }
         };
         let abs_path = self.convert_to_abs_path(&path);
         Ok(Arc::new(INodeFile::open(inode, &abs_path, flags)?))
     }
 
+    /// Create a regular file in the directory.
+    fn create_file(
+        &self,
+        dir_inode: &Arc<dyn INode>,
+        file_name: &str,
+        mode: u32,
+    ) -> Result<Arc<dyn INode>> {
+        if !dir_inode.allow_write()? {
+            return_errno!(EPERM, "file cannot be created");
+        }
+        let inode = create_inode_with_acl(
+            dir_inode.as_ref(),
+            file_name,
+            FileType::File,
+            mode,
+            self.umask,
+        )?;
+        inotify::notify_dir_entry(dir_inode.as_ref(), file_name, InotifyMask::IN_CREATE, 0);
+        Ok(inode)
+    }
+
+    /// Create an unnamed temporary file in the directory and open it.
+    ///
+    /// The file is created with a hidden name and unlinked immediately, so it
+    /// is removed when the last open file is closed unless linkat is used to
+    /// give it a name.
+    fn open_tmpfile(
+        &self,
+        dir_inode: Arc<dyn INode>,
+        abs_dir_path: &str,
+        flags: u32,
+        mode: u32,
+    ) -> Result<Arc<dyn File>> {
+        static TMPFILE_ID: AtomicUsize = AtomicUsize::new(0);
+
+        let access_mode = AccessMode::from_u32(flags)?;
+        if !access_mode.writable() {
+            return_errno!(EINVAL, "O_TMPFILE requires write access");
+        }
+        if dir_inode.metadata()?.type_ != FileType::Dir {
+            return_errno!(ENOTDIR, "O_TMPFILE requires a directory");
+        }
+        if !dir_inode.allow_write()? {
+            return_errno!(EPERM, "file cannot be created");
+        }
+        let (file_name, inode) = loop {
+            let id = TMPFILE_ID.fetch_add(1, Ordering::Relaxed);
+            let file_name = format!(".tmpfile.{}", id);
+            match create_inode_with_acl(
+                dir_inode.as_ref(),
+                &file_name,
+                FileType::File,
+                mode,
+                self.umask,
+            ) {
+                Ok(inode) => break (file_name, inode),
+                Err(e) if e.errno() == EEXIST => continue,
+                Err(e) => return Err(e),
+            }
+        };
+        dir_inode.unlink(&file_name)?;
+
+        let abs_path = format!("{}/{}", abs_dir_path.trim_end_matches('/'), file_name);
+        Ok(Arc::new(INodeFile::open(inode, &abs_path, flags)?))
+    }
+
+    /// Open a file with the restrictions on path resolution given by openat2.
+    ///
+    /// Unlike `open_file`, the path is resolved component by component from
+    /// `dir_path`, so that every symlink and ".." can be checked against the
+    /// resolve flags. The file is opened from the resolved inode, thus the
+    /// checks cannot be bypassed by changing the path concurrently.
+    pub fn open_file_with_resolve(
+        &self,
+        dir_path: &str,
+        path: &str,
+        flags: u32,
+        mode: u32,
+        resolve: ResolveFlags,
+    ) -> Result<Arc<dyn File>> {
+        let creation_flags = CreationFlags::from_bits_truncate(flags);
+        if creation_flags.is_tmpfile() {
+            // The directory must be resolved with the restrictions, too
+            let resolved = self.lookup_with_resolve(dir_path, path, resolve, true)?;
+            let dir_inode = resolved
+                .inode
+                .ok_or_else(|| errno!(ENOENT, "directory does not exist"))?;
+            return self.open_tmpfile(dir_inode, &resolved.abs_path, flags, mode);
+        }
+        let follow_last = !creation_flags.no_follow_symlink();
+        let resolved = self.lookup_with_resolve(dir_path, path, resolve, follow_last)?;
+        let inode = match resolved.inode {
+            Some(inode) => {
+                let file_type = inode.metadata()?.type_;
+                let status_flags = StatusFlags::from_bits_truncate(flags);
+                if file_type == FileType::SymLink && !status_flags.is_fast_open() {
+                    return_errno!(ELOOP, "file is a symlink");
+                }
+                if creation_flags.can_create() && creation_flags.is_exclusive() {
+                    return_errno!(EEXIST, "file exists");
+                }
+                if creation_flags.must_be_directory() && file_type != FileType::Dir {
+                    return_errno!(
+                        ENOTDIR,
+                        "O_DIRECTORY is specified but file is not a directory"
+                    );
+                }
+                inode
+            }
+            None if creation_flags.can_create() => {
+                self.create_file(&resolved.dir_inode, &resolved.file_name, mode)?
+            }
+            None => return_errno!(ENOENT, "file does not exist"),
+        };
+        Ok(Arc::new(INodeFile::open(inode, &resolved.abs_path, flags)?))
+    }
+
+    /// Resolve the path relative to `dir_path` with the resolve flags.
+    ///
+    /// If the last component of the path does not exist, the inode of its
+    /// parent directory is returned, so that the file can be created.
+    fn lookup_with_resolve(
+        &self,
+        dir_path: &str,
+        path: &str,
+        resolve: ResolveFlags,
+        follow_last: bool,
+    ) -> Result<ResolvedPath> {
+        // Linux uses 40 as the upper limit for resolving symbolic links,
+        // so Occlum use it as a reasonable value
+        const MAX_SYMLINKS: usize = 40;
+        debug!(
+            "lookup_with_resolve: dir_path: {:?}, path: {:?}, resolve: {:?}",
+            dir_path, path, resolve
+        );
+        if path.is_empty() {
+            return_errno!(ENOENT, "path is an empty string");
+        }
+
+        let dir_path = self.convert_to_abs_path(dir_path);
+        let base_inode = self.lookup_inode(&dir_path)?;
+        if base_inode.metadata()?.type_ != FileType::Dir {
+            return_errno!(ENOTDIR, "not a directory");
+        }
+        let base_components: Vec<String> = dir_path
+            .split('/')
+            .filter(|name| !name.is_empty() && *name != ".")
+            .fold(Vec::new(), |mut components, name| {
+                if name == ".." {
+                    components.pop();
+                } else {
+                    components.push(name.to_owned());
+                }
+                components
+            });
+        let is_scoped =
+            resolve.intersects(ResolveFlags::RESOLVE_BENEATH | ResolveFlags::RESOLVE_IN_ROOT);
+        let is_same_inode = |a: &Arc<dyn INode>, b: &Arc<dyn INode>| -> Result<bool> {
+            let (a, b) = (a.metadata()?, b.metadata()?);
+            Ok(a.dev == b.dev && a.inode == b.inode)
+        };
+
+        let mut dir_inode = base_inode.clone();
+        let mut components = base_components.clone();
+        let mut pending_names = VecDeque::new();
+        let mut num_symlinks = 0;
+
+        // Restart the resolution from the root if the path is absolute
+        let restart_from_root = |path: &str,
+                                 dir_inode: &mut Arc<dyn INode>,
+                                 components: &mut Vec<String>,
+                                 pending_names: &mut VecDeque<String>|
+         -> Result<()> {
+            if path.starts_with('/') {
+                if resolve.contains(ResolveFlags::RESOLVE_BENEATH) {
+                    return_errno!(EXDEV, "absolute path escapes from the directory");
+                }
+                let root_inode = if resolve.contains(ResolveFlags::RESOLVE_IN_ROOT) {
+                    *components = base_components.clone();
+                    base_inode.clone()
+                } else {
+                    components.clear();
+                    ROOT_INODE.read().unwrap().clone()
+                };
+                if resolve.contains(ResolveFlags::RESOLVE_NO_XDEV)
+                    && root_inode.metadata()?.dev != dir_inode.metadata()?.dev
+                {
+                    return_errno!(EXDEV, "path crosses a mount point");
+                }
+                *dir_inode = root_inode;
+            }
+            for name in path.split('/').rev().filter(|name| !name.is_empty()) {
+                pending_names.push_front(name.to_owned());
+            }
+            Ok(())
+        };
+        restart_from_root(path, &mut dir_inode, &mut components, &mut pending_names)?;
+
+        while let Some(name) = pending_names.pop_front() {
+            let is_last = pending_names.is_empty();
+            match name.as_str() {
+                "." => {}
+                ".." => {
+                    if is_scoped && is_same_inode(&dir_inode, &base_inode)? {
+                        if resolve.contains(ResolveFlags::RESOLVE_BENEATH) {
+                            return_errno!(EXDEV, "\"..\" escapes from the directory");
+                        }
+                        // ".." of the root stays at the root
+                        continue;
+                    }
+                    let parent_inode = dir_inode.find("..")?;
+                    if resolve.contains(ResolveFlags::RESOLVE_NO_XDEV)
+                        && parent_inode.metadata()?.dev != dir_inode.metadata()?.dev
+                    {
+                        return_errno!(EXDEV, "path crosses a mount point");
+                    }
+                    dir_inode = parent_inode;
+                    components.pop();
+                }
+                _ => {
+                    let inode = match dir_inode.find(&name) {
+                        Ok(inode) => inode,
+                        Err(FsError::EntryNotFound) if is_last => {
+                            components.push(name.clone());
+                            return Ok(ResolvedPath {
+                                dir_inode,
+                                file_name: name,
+                                inode: None,
+                                abs_path: format!("/{}", components.join("/")),
+                            });
+                        }
+                        Err(e) => return Err(Error::from(e)),
+                    };
+                    let metadata = inode.metadata()?;
+                    if resolve.contains(ResolveFlags::RESOLVE_NO_XDEV)
+                        && metadata.dev != dir_inode.metadata()?.dev
+                    {
+                        return_errno!(EXDEV, "path crosses a mount point");
+                    }
+                    if metadata.type_ == FileType::SymLink && (!is_last || follow_last) {
+                        if resolve.contains(ResolveFlags::RESOLVE_NO_SYMLINKS) {
+                            return_errno!(ELOOP, "path contains a symlink");
+                        }
+                        if resolve.contains(ResolveFlags::RESOLVE_NO_MAGICLINKS)
+                            && is_magic_link(&components)
+                        {
+                            return_errno!(ELOOP, "path contains a magic link");
+                        }
+                        num_symlinks += 1;
+                        if num_symlinks > MAX_SYMLINKS {
+                            return_errno!(ELOOP, "too many symlinks");
+                        }
+                        let target = {
+                            let mut content = vec![0u8; PATH_MAX];
+                            let len = inode.read_at(0, &mut content)?;
+                            String::from_utf8(content[..len].to_vec())
+                                .map_err(|_| errno!(ENOENT, "invalid symlink content"))?
+                        };
+                        restart_from_root(
+                            &target,
+                            &mut dir_inode,
+                            &mut components,
+                            &mut pending_names,
+                        )?;
+                        continue;
+                    }
+                    components.push(name.clone());
+                    if is_last {
+                        return Ok(ResolvedPath {
+                            dir_inode,
+                            file_name: name,
+                            inode: Some(inode),
+                            abs_path: format!("/{}", components.join("/")),
+                        });
+                    }
+                    if metadata.type_ != FileType::Dir {
+                        return_errno!(ENOTDIR, "not a directory");
+                    }
+                    dir_inode = inode;
+                }
+            }
+        }
+
+        // The path ends with "." or "..", which refers to the directory itself
+        Ok(ResolvedPath {
+            inode: Some(dir_inode.clone()),
+            dir_inode,
+            file_name: String::from("."),
+            abs_path: format!("/{}", components.join("/")),
+        })
+    }
+
     /// Recursively lookup the real path of giving path, dereference symlinks
     pub fn lookup_real_path(&self, path: &str) -> Result<String> {
         let (dir_path, file_name) = split_path(&path);
@@ -179,6 +490,40 @@ impl FsView {
         }
     }
 
+    /// Get the file referred to by the magic link of `/proc/[pid]/fd/N`, or a
+    /// symlink to it, e.g., `/dev/stdin`.
+    ///
+    /// Same as Linux, the file is shared with the file descriptor, instead of
+    /// being opened again, since the file may have no path to be reopened
+    /// with, e.g., pipes and sockets.
+    pub fn lookup_fd_magic_link(&self, path: &str) -> Option<FileRef> {
+        const MAX_SYMLINKS: usize = 40;
+        let mut abs_path = self.convert_to_abs_path(path);
+        for _ in 0..MAX_SYMLINKS {
+            let (dir_path, file_name) = split_path(&abs_path);
+            let real_dir_path = self.lookup_real_path(dir_path).ok()?;
+            if let Some(file_ref) = get_file_of_fd_magic_link(&real_dir_path, file_name) {
+                return Some(file_ref);
+            }
+            let inode = self
+                .lookup_inode(&real_dir_path)
+                .and_then(|dir_inode| Ok(dir_inode.find(file_name)?))
+                .ok()?;
+            if inode.metadata().ok()?.type_ != FileType::SymLink {
+                return None;
+            }
+            let mut content = vec![0u8; PATH_MAX];
+            let len = inode.read_at(0, &mut content).ok()?;
+            let target = std::str::from_utf8(&content[..len]).ok()?;
+            abs_path = if target.starts_with('/') {
+                String::from(target)
+            } else {
+                format!("{}/{}", real_dir_path.trim_end_matches('/'), target)
+            };
+        }
+        None
+    }
+
     /// Convert the path to be absolute
     pub fn convert_to_abs_path(&self, path: &str) -> String {
         debug!(
@@ -208,3 +553,42 @@ impl Default for FsView {
         }
     }
 }
+
+/// The result of resolving a path with `FsView::lookup_with_resolve`.
+struct ResolvedPath {
+    /// The directory that contains the file
+    dir_inode: Arc<dyn INode>,
+    file_name: String,
+    /// The file, or None if it does not exist
+    inode: Option<Arc<dyn INode>>,
+    /// The absolute path of the file without symlinks, "." and ".."
+    abs_path: String,
+}
+
+/// Get the file referred to by `/proc/[pid]/fd/N` in the directory.
+fn get_file_of_fd_magic_link(dir_path: &str, file_name: &str) -> Option<FileRef> {
+    let components: Vec<&str> = dir_path.split('/').filter(|c| !c.is_empty()).collect();
+    let (pid, fd) = match components.as_slice() {
+        ["proc", pid, "fd"] => (*pid, file_name.parse::<FileDesc>().ok()?),
+        _ => return None,
+    };
+    let process = if pid == "self" {
+        current!().process().clone()
+    } else {
+        process::table::get_process(pid.parse().ok()?).ok()?
+    };
+    process.main_thread()?.file(fd).ok()
+}
+
+/// Whether the symlink in the directory is a magic link of procfs, i.e.,
+/// `/proc/[pid]/{cwd,exe,root}` or `/proc/[pid]/fd/*`.
+///
+/// The magic links refer to files instead of paths, thus they can be used to
+/// escape from any directory.
+fn is_magic_link(dir_components: &[String]) -> bool {
+    match dir_components {
+        [proc_dir, _pid] => proc_dir == "proc",
+        [proc_dir, _pid, fd_dir] => proc_dir == "proc" && fd_dir == "fd",
+        _ => false,
+    }
+}
@@ -2,6 +2,8 @@ use alloc::string::String;
 use alloc::sync::{Arc, Weak};
 use core::any::Any;
 use rcore_fs::vfs::*;
+use sgx_trts::libc;
+use sgx_types::sgx_status_t;
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 use std::sync::{SgxMutex as Mutex, SgxMutexGuard as MutexGuard};
@@ -107,8 +109,9 @@ impl INode for HNode {
     }
 
     fn set_metadata(&self, metadata: &Metadata) -> Result<()> {
-        warn!("HostFS: set_metadata() is unimplemented");
-        Ok(())
+        // Only the timestamps are synchronized to the host, the other fields
+        // such as mode and owner are decided by the host file system
+        self.set_times(&metadata.atime, &metadata.mtime)
     }
 
     fn sync_all(&self) -> Result<()> {
@@ -244,6 +247,32 @@ impl HNode {
         }
         Ok(maybe_file)
     }
+
+    /// Set the access and modification times of the file at host.
+    fn set_times(&self, atime: &Timespec, mtime: &Timespec) -> Result<()> {
+        extern "C" {
+            fn occlum_ocall_utimensat(
+                ret: *mut i32,
+                path: *const i8,
+                times: *const libc::timespec,
+            ) -> sgx_status_t;
+        }
+
+        let path = std::ffi::CString::new(self.path.to_str().unwrap())
+            .map_err(|_| FsError::InvalidParam)?;
+        let to_host_timespec = |time: &Timespec| libc::timespec {
+            tv_sec: time.sec,
+            tv_nsec: time.nsec as i64,
+        };
+        let times = [to_host_timespec(atime), to_host_timespec(mtime)];
+        let mut retval: i32 = 0;
+        let status = unsafe { occlum_ocall_utimensat(&mut retval, path.as_ptr(), times.as_ptr()) };
+        assert!(status == sgx_status_t::SGX_SUCCESS);
+        if retval < 0 {
+            return Err(std::io::Error::last_os_error().into_fs_error());
+        }
+        Ok(())
+    }
 }
 
 trait IntoFsError {
@@ -270,7 +299,6 @@ trait IntoFsMetadata {
 
 impl IntoFsMetadata for fs::Metadata {
     fn into_fs_metadata(self) -> Metadata {
-        use sgx_trts::libc;
         use std::os::fs::MetadataExt;
         Metadata {
             dev: self.st_dev() as usize,
@@ -1,5 +1,6 @@
 use super::*;
 use crate::net::PollEventFlags;
+use crate::vm::ShmSegment;
 use rcore_fs_sefs::dev::SefsMac;
 
 pub struct INodeFile {
@@ -8,6 +9,9 @@ pub struct INodeFile {
     offset: SgxMutex<usize>,
     access_mode: AccessMode,
     status_flags: RwLock<StatusFlags>,
+    /// Whether the file is an unnamed temporary file that can be linked into
+    /// the file system, i.e., opened with O_TMPFILE but without O_EXCL
+    is_linkable_tmpfile: bool,
 }
 
 impl File for INodeFile {
@@ -16,7 +20,7 @@ impl File for INodeFile {
             return_errno!(EACCES, "File not readable");
         }
         let mut offset = self.offset.lock().unwrap();
-        let len = self.inode.read_at(*offset, buf).map_err(|e| errno!(e))?;
+        let len = self.read_inode(*offset, buf)?;
         *offset += len;
         Ok(len)
     }
@@ -30,8 +34,11 @@ impl File for INodeFile {
             let info = self.inode.metadata()?;
             *offset = info.size;
         }
-        let len = self.inode.write_at(*offset, buf)?;
+        let len = self.write_inode(*offset, buf)?;
         *offset += len;
+        drop(offset);
+        self.flush_direct_io()?;
+        self.notify_modify();
         Ok(len)
     }
 
@@ -39,7 +46,7 @@ impl File for INodeFile {
         if !self.access_mode.readable() {
             return_errno!(EACCES, "File not readable");
         }
-        let len = self.inode.read_at(offset, buf)?;
+        let len = self.read_inode(offset, buf)?;
         Ok(len)
     }
 
@@ -47,7 +54,9 @@ impl File for INodeFile {
         if !self.access_mode.writable() {
             return_errno!(EACCES, "File not writable");
         }
-        let len = self.inode.write_at(offset, buf)?;
+        let len = self.write_inode(offset, buf)?;
+        self.flush_direct_io()?;
+        self.notify_modify();
         Ok(len)
     }
 
@@ -58,13 +67,13 @@ impl File for INodeFile {
         let mut offset = self.offset.lock().unwrap();
         let mut total_len = 0;
         for buf in bufs {
-            match self.inode.read_at(*offset, buf) {
+            match self.read_inode(*offset, buf) {
                 Ok(len) => {
                     total_len += len;
                     *offset += len;
                 }
                 Err(_) if total_len != 0 => break,
-                Err(e) => return Err(e.into()),
+                Err(e) => return Err(e),
             }
         }
         Ok(total_len)
@@ -81,15 +90,18 @@ impl File for INodeFile {
         }
         let mut total_len = 0;
         for buf in bufs {
-            match self.inode.write_at(*offset, buf) {
+            match self.write_inode(*offset, buf) {
                 Ok(len) => {
                     total_len += len;
                     *offset += len;
                 }
                 Err(_) if total_len != 0 => break,
-                Err(e) => return Err(e.into()),
+                Err(e) => return Err(e),
             }
         }
+        drop(offset);
+        self.flush_direct_io()?;
+        self.notify_modify();
         Ok(total_len)
     }
 
@@ -130,12 +142,18 @@ impl File for INodeFile {
         if !self.access_mode.writable() {
             return_errno!(EACCES, "File not writable. Can't set len.");
         }
+        let old_len = self.inode.metadata()?.size;
         self.inode.resize(len as usize)?;
+        ShmSegment::resize_shared_pages(&self.inode, old_len, len as usize);
+        self.notify_modify();
         Ok(())
     }
 
     fn sync_all(&self) -> Result<()> {
+        // The metadata of the inode is persisted by flushing the metadata files
+        // of SEFS, rather than by syncing the whole file system
         self.inode.sync_all()?;
+        super::sefs::sync_metadata_files()?;
         Ok(())
     }
 
@@ -165,10 +183,11 @@ impl File for INodeFile {
 
     fn set_status_flags(&self, new_status_flags: StatusFlags) -> Result<()> {
         let mut status_flags = self.status_flags.write().unwrap();
-        // Currently, F_SETFL can change only the O_APPEND,
-        // O_ASYNC, O_NOATIME, and O_NONBLOCK flags
+        // Currently, F_SETFL can change only the O_APPEND, O_ASYNC,
+        // O_DIRECT, O_NOATIME, and O_NONBLOCK flags
         let valid_flags_mask = StatusFlags::O_APPEND
             | StatusFlags::O_ASYNC
+            | StatusFlags::O_DIRECT
             | StatusFlags::O_NOATIME
             | StatusFlags::O_NONBLOCK;
         status_flags.remove(valid_flags_mask);
@@ -177,29 +196,47 @@ impl File for INodeFile {
     }
 
     fn test_advisory_lock(&self, lock: &mut Flock) -> Result<()> {
-        // Let the advisory lock could be placed
-        // TODO: Implement the real advisory lock
-        lock.l_type = FlockType::F_UNLCK;
+        let range = self.file_range_of(lock)?;
+        let owner = current!().process().pid();
+        let mut range_lock = RangeLock::new(lock.l_type, range, owner);
+        RangeLockTable::test_lock(self.inode.as_ref(), &mut range_lock)?;
+        lock.l_type = range_lock.type_();
+        if lock.l_type != FlockType::F_UNLCK {
+            lock.l_whence = FlockWhence::SEEK_SET;
+            lock.l_start = range_lock.range().start();
+            lock.l_len = range_lock.range().len();
+            lock.l_pid = range_lock.owner();
+        }
         Ok(())
     }
 
-    fn set_advisory_lock(&self, lock: &Flock) -> Result<()> {
+    fn set_advisory_lock(&self, lock: &Flock, is_nonblocking: bool) -> Result<()> {
         match lock.l_type {
             FlockType::F_RDLCK => {
                 if !self.access_mode.readable() {
-                    return_errno!(EACCES, "File not readable");
+                    return_errno!(EBADF, "File not readable");
                 }
             }
             FlockType::F_WRLCK => {
                 if !self.access_mode.writable() {
-                    return_errno!(EACCES, "File not writable");
+                    return_errno!(EBADF, "File not writable");
                 }
             }
             _ => (),
         }
-        // Let the advisory lock could be acquired or released
-        // TODO: Implement the real advisory lock
-        Ok(())
+        let range = self.file_range_of(lock)?;
+        let owner = current!().process().pid();
+        let range_lock = RangeLock::new(lock.l_type, range, owner);
+        RangeLockTable::set_lock(self.inode.as_ref(), &range_lock, is_nonblocking)
+    }
+
+    fn flock(&self, ops: FlockOps) -> Result<()> {
+        FlockTable::set_lock(self.inode.as_ref(), self.flock_owner(), ops)
+    }
+
+    fn release_advisory_locks(&self) {
+        let owner = current!().process().pid();
+        RangeLockTable::release_locks(self.inode.as_ref(), owner);
     }
 
     fn ioctl(&self, cmd: &mut IoctlCmd) -> Result<i32> {
@@ -233,19 +270,94 @@ impl INodeFile {
         if access_mode.writable() && inode.metadata()?.type_ == FileType::Dir {
             return_errno!(EISDIR, "Directory cannot be open to write");
         }
+        if access_mode.writable() && super::fs_ops::is_on_read_only_mount(abs_path) {
+            return_errno!(EROFS, "File is on a read-only mount");
+        }
         let status_flags = StatusFlags::from_bits_truncate(flags);
+        let creation_flags = CreationFlags::from_bits_truncate(flags);
         Ok(INodeFile {
             inode,
             abs_path: abs_path.to_owned(),
             offset: SgxMutex::new(0),
             access_mode,
             status_flags: RwLock::new(status_flags),
+            is_linkable_tmpfile: creation_flags.is_tmpfile() && !creation_flags.is_exclusive(),
         })
     }
 
     pub fn abs_path(&self) -> &str {
         &self.abs_path
     }
+
+    pub fn is_linkable_tmpfile(&self) -> bool {
+        self.is_linkable_tmpfile
+    }
+
+    pub fn inode(&self) -> &Arc<dyn INode> {
+        &self.inode
+    }
+
+    /// Read the inode, which is coherent with the shared mappings of the file.
+    ///
+    /// Direct I/O bypasses the page cache of SEFS.
+    fn read_inode(&self, offset: usize, buf: &mut [u8]) -> Result<usize> {
+        let len = if self.is_direct_io() {
+            sefs::without_page_cache(|| self.inode.read_at(offset, buf))?
+        } else {
+            self.inode.read_at(offset, buf)?
+        };
+        ShmSegment::read_shared_pages(&self.inode, offset, &mut buf[..len]);
+        Ok(len)
+    }
+
+    /// Write the inode, which is coherent with the shared mappings of the file.
+    fn write_inode(&self, offset: usize, buf: &[u8]) -> Result<usize> {
+        let len = if self.is_direct_io() {
+            sefs::without_page_cache(|| self.inode.write_at(offset, buf))?
+        } else {
+            self.inode.write_at(offset, buf)?
+        };
+        ShmSegment::write_shared_pages(&self.inode, offset, &buf[..len]);
+        Ok(len)
+    }
+
+    fn is_direct_io(&self) -> bool {
+        self.status_flags.read().unwrap().is_direct_io()
+    }
+
+    /// Direct I/O bypasses the caches of LibOS, so the written data is flushed
+    /// to the underlying storage immediately.
+    fn flush_direct_io(&self) -> Result<()> {
+        if self.is_direct_io() {
+            self.inode.sync_data()?;
+        }
+        Ok(())
+    }
+
+    /// Get the absolute byte range of a POSIX record lock.
+    fn file_range_of(&self, lock: &Flock) -> Result<FileRange> {
+        let base = match lock.l_whence {
+            FlockWhence::SEEK_SET => 0,
+            FlockWhence::SEEK_CUR => *self.offset.lock().unwrap() as off_t,
+            FlockWhence::SEEK_END => self.inode.metadata()?.size as off_t,
+        };
+        FileRange::from_start_and_len(base, lock.l_start, lock.l_len)
+    }
+
+    /// The owner of BSD locks placed through this open file.
+    fn flock_owner(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    fn notify_modify(&self) {
+        inotify::notify_modify(self.inode.as_ref(), &self.abs_path);
+    }
+}
+
+impl Drop for INodeFile {
+    fn drop(&mut self) {
+        FlockTable::release_lock(self.inode.as_ref(), self.flock_owner());
+    }
 }
 
 impl Debug for INodeFile {
@@ -265,6 +377,7 @@ pub trait INodeExt {
     fn read_as_vec(&self) -> Result<Vec<u8>>;
     fn allow_write(&self) -> Result<bool>;
     fn allow_read(&self) -> Result<bool>;
+    fn allow_exec(&self) -> Result<bool>;
 }
 
 impl INodeExt for dyn INode {
@@ -279,16 +392,31 @@ impl INodeExt for dyn INode {
     }
 
     fn allow_write(&self) -> Result<bool> {
+        if let Some(allowed) = PosixAcl::check_access(self, AccessibilityCheckMode::W_OK)? {
+            return Ok(allowed);
+        }
         let info = self.metadata()?;
         let file_mode = FileMode::from_bits_truncate(info.mode);
         Ok(file_mode.is_writable())
     }
 
     fn allow_read(&self) -> Result<bool> {
+        if let Some(allowed) = PosixAcl::check_access(self, AccessibilityCheckMode::R_OK)? {
+            return Ok(allowed);
+        }
         let info = self.metadata()?;
         let file_mode = FileMode::from_bits_truncate(info.mode);
         Ok(file_mode.is_readable())
     }
+
+    fn allow_exec(&self) -> Result<bool> {
+        if let Some(allowed) = PosixAcl::check_access(self, AccessibilityCheckMode::X_OK)? {
+            return Ok(allowed);
+        }
+        let info = self.metadata()?;
+        let file_mode = FileMode::from_bits_truncate(info.mode);
+        Ok(file_mode.is_executable())
+    }
 }
 
 pub trait AsINodeFile {
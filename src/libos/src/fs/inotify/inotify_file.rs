@@ -0,0 +1,402 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::*;
+use crate::events::{Waiter, WaiterQueue};
+
+bitflags! {
+    /// The events (and some options) of an inotify watch.
+    pub struct InotifyMask: u32 {
+        const IN_ACCESS         = 0x0000_0001;
+        const IN_MODIFY         = 0x0000_0002;
+        const IN_ATTRIB         = 0x0000_0004;
+        const IN_CLOSE_WRITE    = 0x0000_0008;
+        const IN_CLOSE_NOWRITE  = 0x0000_0010;
+        const IN_OPEN           = 0x0000_0020;
+        const IN_MOVED_FROM     = 0x0000_0040;
+        const IN_MOVED_TO       = 0x0000_0080;
+        const IN_CREATE         = 0x0000_0100;
+        const IN_DELETE         = 0x0000_0200;
+        const IN_DELETE_SELF    = 0x0000_0400;
+        const IN_MOVE_SELF      = 0x0000_0800;
+        // Events sent by the kernel
+        const IN_UNMOUNT        = 0x0000_2000;
+        const IN_Q_OVERFLOW     = 0x0000_4000;
+        const IN_IGNORED        = 0x0000_8000;
+        // Options of a watch
+        const IN_ONLYDIR        = 0x0100_0000;
+        const IN_DONT_FOLLOW    = 0x0200_0000;
+        const IN_EXCL_UNLINK    = 0x0400_0000;
+        const IN_MASK_CREATE    = 0x1000_0000;
+        const IN_MASK_ADD       = 0x2000_0000;
+        const IN_ISDIR          = 0x4000_0000;
+        const IN_ONESHOT        = 0x8000_0000;
+    }
+}
+
+impl InotifyMask {
+    /// All events that can be watched.
+    pub fn all_events() -> Self {
+        Self::IN_ACCESS
+            | Self::IN_MODIFY
+            | Self::IN_ATTRIB
+            | Self::IN_CLOSE_WRITE
+            | Self::IN_CLOSE_NOWRITE
+            | Self::IN_OPEN
+            | Self::IN_MOVED_FROM
+            | Self::IN_MOVED_TO
+            | Self::IN_CREATE
+            | Self::IN_DELETE
+            | Self::IN_DELETE_SELF
+            | Self::IN_MOVE_SELF
+    }
+}
+
+bitflags! {
+    pub struct InotifyFlags: i32 {
+        /// Non-blocking
+        const IN_NONBLOCK   = 1 << 11;
+        /// Close on exec
+        const IN_CLOEXEC    = 1 << 19;
+    }
+}
+
+/// The maximum number of queued events of an inotify file.
+const MAX_QUEUED_EVENTS: usize = 16384;
+
+/// An inotify file that delivers file system events to the user.
+pub struct InotifyFile {
+    inner: SgxMutex<InotifyInner>,
+    is_nonblocking: AtomicBool,
+    // All threads that are blocked on reading this file.
+    waiters: WaiterQueue,
+    notifier: IoNotifier,
+}
+
+struct InotifyInner {
+    watches: HashMap<i32, Watch>,
+    next_wd: i32,
+    events: VecDeque<InotifyEvent>,
+}
+
+#[derive(Debug)]
+struct Watch {
+    key: WatchKey,
+    mask: InotifyMask,
+}
+
+#[derive(Debug, PartialEq)]
+struct InotifyEvent {
+    wd: i32,
+    mask: InotifyMask,
+    cookie: u32,
+    name: Option<String>,
+}
+
+/// The header of C's struct inotify_event.
+#[repr(C)]
+struct inotify_event_t {
+    wd: i32,
+    mask: u32,
+    cookie: u32,
+    len: u32,
+}
+
+impl InotifyEvent {
+    const HEADER_LEN: usize = std::mem::size_of::<inotify_event_t>();
+
+    /// The length of the name, including the padding null bytes.
+    fn name_len(&self) -> usize {
+        match self.name.as_ref() {
+            None => 0,
+            Some(name) => align_up(name.len() + 1, Self::HEADER_LEN),
+        }
+    }
+
+    fn len(&self) -> usize {
+        Self::HEADER_LEN + self.name_len()
+    }
+
+    fn copy_to(&self, buf: &mut [u8]) {
+        let header = inotify_event_t {
+            wd: self.wd,
+            mask: self.mask.bits(),
+            cookie: self.cookie,
+            len: self.name_len() as u32,
+        };
+        let header_bytes = unsafe {
+            std::slice::from_raw_parts(&header as *const _ as *const u8, Self::HEADER_LEN)
+        };
+        buf[..Self::HEADER_LEN].copy_from_slice(header_bytes);
+
+        let name_buf = &mut buf[Self::HEADER_LEN..self.len()];
+        for byte in name_buf.iter_mut() {
+            *byte = 0;
+        }
+        if let Some(name) = self.name.as_ref() {
+            name_buf[..name.len()].copy_from_slice(name.as_bytes());
+        }
+    }
+}
+
+impl InotifyFile {
+    pub fn new(flags: InotifyFlags) -> Arc<Self> {
+        let inner = SgxMutex::new(InotifyInner {
+            watches: HashMap::new(),
+            next_wd: 1,
+            events: VecDeque::new(),
+        });
+        let is_nonblocking = AtomicBool::new(flags.contains(InotifyFlags::IN_NONBLOCK));
+        let new_self = Arc::new(Self {
+            inner,
+            is_nonblocking,
+            waiters: WaiterQueue::new(),
+            notifier: IoNotifier::new(),
+        });
+        register_inotify_file(&new_self);
+        new_self
+    }
+
+    /// Add a new watch or modify an existing watch for the inode, returning the watch descriptor.
+    pub fn add_watch(&self, inode: &dyn INode, mask: InotifyMask) -> Result<i32> {
+        if !mask.intersects(InotifyMask::all_events()) {
+            return_errno!(EINVAL, "no events to watch");
+        }
+        if mask.contains(InotifyMask::IN_MASK_ADD | InotifyMask::IN_MASK_CREATE) {
+            return_errno!(
+                EINVAL,
+                "IN_MASK_ADD and IN_MASK_CREATE cannot be used together"
+            );
+        }
+        if mask.contains(InotifyMask::IN_ONLYDIR) && inode.metadata()?.type_ != FileType::Dir {
+            return_errno!(ENOTDIR, "not a directory");
+        }
+
+        let key = WatchKey::from_inode(inode)?;
+        let watch_mask = mask
+            & (InotifyMask::all_events() | InotifyMask::IN_ONESHOT | InotifyMask::IN_EXCL_UNLINK);
+
+        let mut inner = self.inner.lock().unwrap();
+        let existing = inner.watches.iter_mut().find(|(_, watch)| watch.key == key);
+        if let Some((&wd, watch)) = existing {
+            if mask.contains(InotifyMask::IN_MASK_CREATE) {
+                return_errno!(EEXIST, "the inode is already being watched");
+            }
+            if mask.contains(InotifyMask::IN_MASK_ADD) {
+                watch.mask |= watch_mask;
+            } else {
+                watch.mask = watch_mask;
+            }
+            return Ok(wd);
+        }
+
+        let wd = inner.next_wd;
+        inner.next_wd += 1;
+        inner.watches.insert(
+            wd,
+            Watch {
+                key,
+                mask: watch_mask,
+            },
+        );
+        NUM_WATCHES.fetch_add(1, Ordering::Relaxed);
+        Ok(wd)
+    }
+
+    /// Remove a watch.
+    pub fn rm_watch(&self, wd: i32) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.watches.remove(&wd).is_none() {
+            return_errno!(EINVAL, "invalid watch descriptor");
+        }
+        NUM_WATCHES.fetch_sub(1, Ordering::Relaxed);
+        self.push_event(&mut inner, wd, InotifyMask::IN_IGNORED, 0, None);
+        drop(inner);
+
+        self.after_events_queued();
+        Ok(())
+    }
+
+    /// Queue an event for all the watches on the inode identified by the key.
+    pub(super) fn queue_event(
+        &self,
+        key: &WatchKey,
+        mask: InotifyMask,
+        cookie: u32,
+        name: Option<&str>,
+    ) {
+        let mut inner = self.inner.lock().unwrap();
+        let matched: Vec<(i32, InotifyMask)> = inner
+            .watches
+            .iter()
+            .filter(|(_, watch)| watch.key == *key && watch.mask.intersects(mask))
+            .map(|(&wd, watch)| (wd, watch.mask))
+            .collect();
+        if matched.is_empty() {
+            return;
+        }
+
+        for (wd, watch_mask) in matched {
+            let event_mask = (mask & watch_mask) | (mask & InotifyMask::IN_ISDIR);
+            self.push_event(&mut inner, wd, event_mask, cookie, name);
+
+            // A watch is removed once its inode is deleted or it is one-shot
+            if watch_mask.contains(InotifyMask::IN_ONESHOT)
+                || (name.is_none() && mask.contains(InotifyMask::IN_DELETE_SELF))
+            {
+                inner.watches.remove(&wd);
+                NUM_WATCHES.fetch_sub(1, Ordering::Relaxed);
+                self.push_event(&mut inner, wd, InotifyMask::IN_IGNORED, 0, None);
+            }
+        }
+        drop(inner);
+
+        self.after_events_queued();
+    }
+
+    fn push_event(
+        &self,
+        inner: &mut InotifyInner,
+        wd: i32,
+        mask: InotifyMask,
+        cookie: u32,
+        name: Option<&str>,
+    ) {
+        let event = InotifyEvent {
+            wd,
+            mask,
+            cookie,
+            name: name.map(|name| name.to_owned()),
+        };
+        // Coalesce identical events, like Linux does
+        if inner.events.back() == Some(&event) {
+            return;
+        }
+
+        if inner.events.len() >= MAX_QUEUED_EVENTS {
+            let overflow_event = InotifyEvent {
+                wd: -1,
+                mask: InotifyMask::IN_Q_OVERFLOW,
+                cookie: 0,
+                name: None,
+            };
+            if inner.events.back() != Some(&overflow_event) {
+                inner.events.push_back(overflow_event);
+            }
+            return;
+        }
+        inner.events.push_back(event);
+    }
+
+    fn after_events_queued(&self) {
+        self.notifier.broadcast(&IoEvents::IN);
+        self.waiters.dequeue_and_wake_all();
+    }
+
+    fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Acquire)
+    }
+
+    fn try_read(&self, buf: &mut [u8]) -> Result<usize> {
+        let mut inner = self.inner.lock().unwrap();
+        let mut total_len = 0;
+        while let Some(event) = inner.events.front() {
+            let event_len = event.len();
+            if total_len + event_len > buf.len() {
+                if total_len == 0 {
+                    return_errno!(EINVAL, "the buffer is too small");
+                }
+                break;
+            }
+            event.copy_to(&mut buf[total_len..]);
+            total_len += event_len;
+            inner.events.pop_front();
+        }
+        Ok(total_len)
+    }
+}
+
+impl File for InotifyFile {
+    fn read(&self, buf: &mut [u8]) -> Result<usize> {
+        let waiter = Waiter::new();
+        loop {
+            self.waiters.reset_and_enqueue(&waiter);
+
+            let len = self.try_read(buf)?;
+            if len > 0 {
+                return Ok(len);
+            }
+            if self.is_nonblocking() {
+                return_errno!(EAGAIN, "no events");
+            }
+
+            waiter.wait(None)?;
+        }
+    }
+
+    fn access_mode(&self) -> Result<AccessMode> {
+        Ok(AccessMode::O_RDONLY)
+    }
+
+    fn status_flags(&self) -> Result<StatusFlags> {
+        if self.is_nonblocking() {
+            Ok(StatusFlags::O_NONBLOCK)
+        } else {
+            Ok(StatusFlags::empty())
+        }
+    }
+
+    fn set_status_flags(&self, new_status_flags: StatusFlags) -> Result<()> {
+        let nonblocking = new_status_flags.contains(StatusFlags::O_NONBLOCK);
+        self.is_nonblocking.store(nonblocking, Ordering::Release);
+        if nonblocking {
+            // Wake all threads that are blocked on reading this file
+            self.waiters.dequeue_and_wake_all();
+        }
+        Ok(())
+    }
+
+    fn poll_new(&self) -> IoEvents {
+        if self.inner.lock().unwrap().events.is_empty() {
+            IoEvents::empty()
+        } else {
+            IoEvents::IN
+        }
+    }
+
+    fn notifier(&self) -> Option<&IoNotifier> {
+        Some(&self.notifier)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Drop for InotifyFile {
+    fn drop(&mut self) {
+        let num_watches = self.inner.lock().unwrap().watches.len();
+        NUM_WATCHES.fetch_sub(num_watches, Ordering::Relaxed);
+    }
+}
+
+impl Debug for InotifyFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let inner = self.inner.lock().unwrap();
+        f.debug_struct("InotifyFile")
+            .field("watches", &inner.watches)
+            .field("num_events", &inner.events.len())
+            .finish()
+    }
+}
+
+pub trait AsInotifyFile {
+    fn as_inotify_file(&self) -> Result<&InotifyFile>;
+}
+
+impl AsInotifyFile for FileRef {
+    fn as_inotify_file(&self) -> Result<&InotifyFile> {
+        self.as_any()
+            .downcast_ref::<InotifyFile>()
+            .ok_or_else(|| errno!(EINVAL, "not an inotify file"))
+    }
+}
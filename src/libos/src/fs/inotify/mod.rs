@@ -0,0 +1,128 @@
+//! The inotify API for monitoring file system events.
+//!
+//! File system operations in LibOS (e.g., creating, writing, or deleting a file)
+//! report events through the `notify_*` functions of this module. The events
+//! are then dispatched to all `InotifyFile`s that have a watch on the affected
+//! inode or its parent directory. Since the events are generated in the
+//! VFS layer of LibOS, all in-enclave file systems (e.g., SEFS, UnionFS, and
+//! RamFS) are supported.
+//!
+//! An inode is identified by its device and inode numbers, so an inode can be
+//! watched regardless of the path through which it is accessed.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Weak;
+
+use super::*;
+
+pub use self::inotify_file::{AsInotifyFile, InotifyFile, InotifyFlags, InotifyMask};
+
+mod inotify_file;
+
+lazy_static! {
+    /// All alive inotify files.
+    static ref INOTIFY_FILES: SgxMutex<Vec<Weak<InotifyFile>>> = SgxMutex::new(Vec::new());
+}
+
+/// The total number of watches, used to skip event dispatching in the common case.
+static NUM_WATCHES: AtomicUsize = AtomicUsize::new(0);
+
+/// The identity of an inode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WatchKey {
+    dev: usize,
+    ino: usize,
+}
+
+impl WatchKey {
+    pub fn from_inode(inode: &dyn INode) -> Result<Self> {
+        let metadata = inode.metadata()?;
+        Ok(Self {
+            dev: metadata.dev,
+            ino: metadata.inode,
+        })
+    }
+}
+
+fn register_inotify_file(file: &Arc<InotifyFile>) {
+    let mut files = INOTIFY_FILES.lock().unwrap();
+    files.retain(|file| file.upgrade().is_some());
+    files.push(Arc::downgrade(file));
+}
+
+fn has_watches() -> bool {
+    NUM_WATCHES.load(Ordering::Relaxed) > 0
+}
+
+fn for_each_inotify_file<F: FnMut(&InotifyFile)>(mut f: F) {
+    let files: Vec<Arc<InotifyFile>> = INOTIFY_FILES
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|file| file.upgrade())
+        .collect();
+    for file in files.iter() {
+        f(file);
+    }
+}
+
+/// Report an event that happens on the inode itself.
+pub fn notify_inode(inode: &dyn INode, mask: InotifyMask) {
+    if !has_watches() {
+        return;
+    }
+    let key = match WatchKey::from_inode(inode) {
+        Ok(key) => key,
+        Err(_) => return,
+    };
+    for_each_inotify_file(|file| file.queue_event(&key, mask, 0, None));
+}
+
+/// Report an event that happens on the entry `name` of the directory `dir_inode`.
+pub fn notify_dir_entry(dir_inode: &dyn INode, name: &str, mask: InotifyMask, cookie: u32) {
+    if !has_watches() {
+        return;
+    }
+    let key = match WatchKey::from_inode(dir_inode) {
+        Ok(key) => key,
+        Err(_) => return,
+    };
+    for_each_inotify_file(|file| file.queue_event(&key, mask, cookie, Some(name)));
+}
+
+/// Report that the content of a file has been modified.
+///
+/// The event is reported to the watches of the file itself, as well as the
+/// watches of the parent directory of the file.
+pub fn notify_modify(inode: &dyn INode, abs_path: &str) {
+    if !has_watches() {
+        return;
+    }
+    notify_inode(inode, InotifyMask::IN_MODIFY);
+
+    let (dir_path, file_name) = split_path(abs_path);
+    let dir_inode = {
+        let current = current!();
+        let fs = current.fs().lock().unwrap();
+        match fs.lookup_inode(dir_path) {
+            Ok(dir_inode) => dir_inode,
+            Err(_) => return,
+        }
+    };
+    // The path may be stale (e.g., the file has been unlinked or renamed)
+    let is_same_inode = dir_inode
+        .find(file_name)
+        .ok()
+        .and_then(|inode| WatchKey::from_inode(inode.as_ref()).ok())
+        == WatchKey::from_inode(inode).ok();
+    if !is_same_inode {
+        return;
+    }
+    notify_dir_entry(dir_inode.as_ref(), file_name, InotifyMask::IN_MODIFY, 0);
+}
+
+/// Allocate a cookie to associate the IN_MOVED_FROM and IN_MOVED_TO events of a rename.
+pub fn alloc_cookie() -> u32 {
+    static NEXT_COOKIE: AtomicUsize = AtomicUsize::new(1);
+    NEXT_COOKIE.fetch_add(1, Ordering::Relaxed) as u32
+}
@@ -0,0 +1,272 @@
+//! The structures and constants of the io_uring ABI, which are the same as
+//! those of Linux.
+
+use super::*;
+
+/// The offsets to mmap the rings and the SQEs.
+pub const IORING_OFF_SQ_RING: usize = 0;
+pub const IORING_OFF_CQ_RING: usize = 0x8000000;
+pub const IORING_OFF_SQES: usize = 0x10000000;
+
+/// The maximum number of the SQ entries.
+pub const IORING_MAX_ENTRIES: u32 = 4096;
+/// The maximum number of the CQ entries.
+pub const IORING_MAX_CQ_ENTRIES: u32 = 2 * IORING_MAX_ENTRIES;
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+#[allow(non_camel_case_types)]
+pub struct io_sqring_offsets_t {
+    pub head: u32,
+    pub tail: u32,
+    pub ring_mask: u32,
+    pub ring_entries: u32,
+    pub flags: u32,
+    pub dropped: u32,
+    pub array: u32,
+    pub resv1: u32,
+    pub resv2: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+#[allow(non_camel_case_types)]
+pub struct io_cqring_offsets_t {
+    pub head: u32,
+    pub tail: u32,
+    pub ring_mask: u32,
+    pub ring_entries: u32,
+    pub overflow: u32,
+    pub cqes: u32,
+    pub flags: u32,
+    pub resv1: u32,
+    pub resv2: u64,
+}
+
+/// The `struct io_uring_params` of io_uring_setup.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+#[allow(non_camel_case_types)]
+pub struct io_uring_params_t {
+    pub sq_entries: u32,
+    pub cq_entries: u32,
+    pub flags: u32,
+    pub sq_thread_cpu: u32,
+    pub sq_thread_idle: u32,
+    pub features: u32,
+    pub wq_fd: u32,
+    pub resv: [u32; 3],
+    pub sq_off: io_sqring_offsets_t,
+    pub cq_off: io_cqring_offsets_t,
+}
+
+/// The `struct io_uring_sqe` of a submission queue entry.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+#[allow(non_camel_case_types)]
+pub struct io_uring_sqe_t {
+    pub opcode: u8,
+    pub flags: u8,
+    pub ioprio: u16,
+    pub fd: i32,
+    pub off: u64,
+    pub addr: u64,
+    pub len: u32,
+    // The union of rw_flags, fsync_flags, poll_events, msg_flags, etc.
+    pub op_flags: u32,
+    pub user_data: u64,
+    // The union of buf_index and buf_group
+    pub buf_index: u16,
+    pub personality: u16,
+    pub splice_fd_in: i32,
+    pub pad2: [u64; 2],
+}
+
+/// The `struct io_uring_cqe` of a completion queue entry.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+#[allow(non_camel_case_types)]
+pub struct io_uring_cqe_t {
+    pub user_data: u64,
+    pub res: i32,
+    pub flags: u32,
+}
+
+/// The `struct io_uring_getevents_arg` of io_uring_enter with
+/// IORING_ENTER_EXT_ARG.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+#[allow(non_camel_case_types)]
+pub struct io_uring_getevents_arg_t {
+    pub sigmask: u64,
+    pub sigmask_sz: u32,
+    pub pad: u32,
+    pub ts: u64,
+}
+
+/// The `struct io_uring_files_update` of IORING_REGISTER_FILES_UPDATE.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+#[allow(non_camel_case_types)]
+pub struct io_uring_files_update_t {
+    pub offset: u32,
+    pub resv: u32,
+    pub fds: u64,
+}
+
+/// The header of `struct io_uring_probe` of IORING_REGISTER_PROBE, which is
+/// followed by an array of `struct io_uring_probe_op`.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+#[allow(non_camel_case_types)]
+pub struct io_uring_probe_t {
+    pub last_op: u8,
+    pub ops_len: u8,
+    pub resv: u16,
+    pub resv2: [u32; 3],
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+#[allow(non_camel_case_types)]
+pub struct io_uring_probe_op_t {
+    pub op: u8,
+    pub resv: u8,
+    pub flags: u16,
+    pub resv2: u32,
+}
+
+pub const IO_URING_OP_SUPPORTED: u16 = 1 << 0;
+
+bitflags! {
+    pub struct IoUringSetupFlags: u32 {
+        const IORING_SETUP_IOPOLL = 1 << 0;
+        const IORING_SETUP_SQPOLL = 1 << 1;
+        const IORING_SETUP_SQ_AFF = 1 << 2;
+        const IORING_SETUP_CQSIZE = 1 << 3;
+        const IORING_SETUP_CLAMP = 1 << 4;
+        const IORING_SETUP_ATTACH_WQ = 1 << 5;
+        const IORING_SETUP_R_DISABLED = 1 << 6;
+    }
+}
+
+bitflags! {
+    pub struct IoUringFeatures: u32 {
+        const IORING_FEAT_SINGLE_MMAP = 1 << 0;
+        const IORING_FEAT_NODROP = 1 << 1;
+        const IORING_FEAT_SUBMIT_STABLE = 1 << 2;
+        const IORING_FEAT_RW_CUR_POS = 1 << 3;
+        const IORING_FEAT_EXT_ARG = 1 << 8;
+    }
+}
+
+bitflags! {
+    pub struct IoUringEnterFlags: u32 {
+        const IORING_ENTER_GETEVENTS = 1 << 0;
+        const IORING_ENTER_SQ_WAKEUP = 1 << 1;
+        const IORING_ENTER_SQ_WAIT = 1 << 2;
+        const IORING_ENTER_EXT_ARG = 1 << 3;
+    }
+}
+
+bitflags! {
+    pub struct SqeFlags: u8 {
+        const IOSQE_FIXED_FILE = 1 << 0;
+        const IOSQE_IO_DRAIN = 1 << 1;
+        const IOSQE_IO_LINK = 1 << 2;
+        const IOSQE_IO_HARDLINK = 1 << 3;
+        const IOSQE_ASYNC = 1 << 4;
+    }
+}
+
+/// The flags of the SQ ring.
+pub const IORING_SQ_CQ_OVERFLOW: u32 = 1 << 1;
+
+/// The flags of IORING_OP_FSYNC.
+pub const IORING_FSYNC_DATASYNC: u32 = 1 << 0;
+
+/// The flags of IORING_OP_TIMEOUT.
+pub const IORING_TIMEOUT_ABS: u32 = 1 << 0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(u8)]
+pub enum IoUringOp {
+    Nop = 0,
+    Readv = 1,
+    Writev = 2,
+    Fsync = 3,
+    ReadFixed = 4,
+    WriteFixed = 5,
+    PollAdd = 6,
+    Sendmsg = 9,
+    Recvmsg = 10,
+    Timeout = 11,
+    Accept = 13,
+    Connect = 16,
+    Close = 19,
+    Read = 22,
+    Write = 23,
+    Send = 26,
+    Recv = 27,
+}
+
+impl IoUringOp {
+    /// The last opcode of Linux that is known.
+    pub const LAST_OP: u8 = 33;
+
+    pub fn from_u8(opcode: u8) -> Option<Self> {
+        let op = match opcode {
+            0 => IoUringOp::Nop,
+            1 => IoUringOp::Readv,
+            2 => IoUringOp::Writev,
+            3 => IoUringOp::Fsync,
+            4 => IoUringOp::ReadFixed,
+            5 => IoUringOp::WriteFixed,
+            6 => IoUringOp::PollAdd,
+            9 => IoUringOp::Sendmsg,
+            10 => IoUringOp::Recvmsg,
+            11 => IoUringOp::Timeout,
+            13 => IoUringOp::Accept,
+            16 => IoUringOp::Connect,
+            19 => IoUringOp::Close,
+            22 => IoUringOp::Read,
+            23 => IoUringOp::Write,
+            26 => IoUringOp::Send,
+            27 => IoUringOp::Recv,
+            _ => return None,
+        };
+        Some(op)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(u32)]
+pub enum IoUringRegisterOp {
+    RegisterBuffers = 0,
+    UnregisterBuffers = 1,
+    RegisterFiles = 2,
+    UnregisterFiles = 3,
+    RegisterEventfd = 4,
+    UnregisterEventfd = 5,
+    RegisterFilesUpdate = 6,
+    RegisterEventfdAsync = 7,
+    RegisterProbe = 8,
+}
+
+impl IoUringRegisterOp {
+    pub fn from_u32(opcode: u32) -> Result<Self> {
+        let op = match opcode {
+            0 => IoUringRegisterOp::RegisterBuffers,
+            1 => IoUringRegisterOp::UnregisterBuffers,
+            2 => IoUringRegisterOp::RegisterFiles,
+            3 => IoUringRegisterOp::UnregisterFiles,
+            4 => IoUringRegisterOp::RegisterEventfd,
+            5 => IoUringRegisterOp::UnregisterEventfd,
+            6 => IoUringRegisterOp::RegisterFilesUpdate,
+            7 => IoUringRegisterOp::RegisterEventfdAsync,
+            8 => IoUringRegisterOp::RegisterProbe,
+            _ => return_errno!(EINVAL, "the opcode of io_uring_register is not supported"),
+        };
+        Ok(op)
+    }
+}
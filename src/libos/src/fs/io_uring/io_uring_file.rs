@@ -0,0 +1,614 @@
+use std::sync::Weak;
+use std::time::Duration;
+
+use super::abi::*;
+use super::request::{PrepareContext, Request};
+use super::ring::Rings;
+use super::*;
+use crate::events::{HostEventFd, Observer, Waiter, WaiterQueue, WaiterQueueObserver};
+use crate::fs::event_file::AsEvent;
+use crate::time::{do_clock_gettime, ClockID};
+use crate::vm::ShmSegment;
+use util::mem_util::from_user;
+
+/// An io_uring, whose rings are mapped by the user through its fd.
+pub struct IoUringFile {
+    rings: Rings,
+    inner: SgxMutex<Inner>,
+    // All threads that are blocked on waiting for the CQEs, which sleep on the
+    // host eventfd that is written on the completions of the requests done by
+    // the host I/O threads
+    waiters: WaiterQueue,
+    host_eventfd: Arc<HostEventFd>,
+    notifier: IoNotifier,
+}
+
+struct Inner {
+    // The chains of the linked requests that are not done, in the order of
+    // submission
+    pending: VecDeque<Chain>,
+    // The CQEs that wait for room in the CQ ring, i.e., IORING_FEAT_NODROP
+    overflow: VecDeque<io_uring_cqe_t>,
+    // The number of the CQEs ever posted, which completes the timeouts
+    nr_posted: u64,
+    files: Vec<Option<FileRef>>,
+    buffers: Vec<(usize, usize)>,
+    eventfd: Option<RegisteredEventfd>,
+}
+
+// A chain of the requests linked by IOSQE_IO_LINK or IOSQE_IO_HARDLINK, which
+// are done one after another. A request that is not linked is a chain of
+// itself.
+struct Chain {
+    requests: VecDeque<Request>,
+    is_drain: bool,
+    // Whether the chain has been pending in an io_uring_enter
+    is_deferred: bool,
+}
+
+struct RegisteredEventfd {
+    file: FileRef,
+    // Only notify the completions of the pending requests, i.e.,
+    // IORING_REGISTER_EVENTFD_ASYNC
+    is_async_only: bool,
+}
+
+// The limit of the CQEs waiting for room in the CQ ring, beyond which the
+// CQEs are dropped and counted as overflow
+const MAX_OVERFLOW_CQES: usize = 4 * IORING_MAX_CQ_ENTRIES as usize;
+// The limits of the registered files and buffers
+const MAX_FIXED_FILES: u32 = 32768;
+const MAX_FIXED_BUFFERS: u32 = 16384;
+const MAX_FIXED_BUFFER_SIZE: usize = 1 << 30;
+
+impl IoUringFile {
+    /// Create an io_uring, filling the sizes and the offsets of the rings in
+    /// the params.
+    pub fn new(entries: u32, params: &mut io_uring_params_t) -> Result<Self> {
+        if params.resv.iter().any(|&resv| resv != 0) {
+            return_errno!(EINVAL, "the reserved fields are not zero");
+        }
+        let flags = IoUringSetupFlags::from_bits(params.flags)
+            .ok_or_else(|| errno!(EINVAL, "unknown flags of io_uring_setup"))?;
+        // There is no kernel thread to poll the SQ or the devices
+        if flags.intersects(
+            IoUringSetupFlags::IORING_SETUP_IOPOLL
+                | IoUringSetupFlags::IORING_SETUP_SQPOLL
+                | IoUringSetupFlags::IORING_SETUP_SQ_AFF
+                | IoUringSetupFlags::IORING_SETUP_R_DISABLED,
+        ) {
+            return_errno!(EINVAL, "the flags of io_uring_setup are not supported");
+        }
+        let is_clamp = flags.contains(IoUringSetupFlags::IORING_SETUP_CLAMP);
+
+        let sq_entries = match entries {
+            0 => return_errno!(EINVAL, "the number of entries is zero"),
+            n if n > IORING_MAX_ENTRIES && !is_clamp => return_errno!(EINVAL, "too many entries"),
+            n => n.min(IORING_MAX_ENTRIES).next_power_of_two(),
+        };
+        let cq_entries = if flags.contains(IoUringSetupFlags::IORING_SETUP_CQSIZE) {
+            let cq_entries = match params.cq_entries {
+                0 => return_errno!(EINVAL, "the number of CQ entries is zero"),
+                n if n > IORING_MAX_CQ_ENTRIES && !is_clamp => {
+                    return_errno!(EINVAL, "too many CQ entries")
+                }
+                n => n.min(IORING_MAX_CQ_ENTRIES).next_power_of_two(),
+            };
+            if cq_entries < sq_entries {
+                return_errno!(EINVAL, "the CQ is smaller than the SQ");
+            }
+            cq_entries
+        } else {
+            2 * sq_entries
+        };
+
+        let rings = Rings::new(sq_entries, cq_entries)?;
+        params.sq_entries = sq_entries;
+        params.cq_entries = cq_entries;
+        params.features = (IoUringFeatures::IORING_FEAT_SINGLE_MMAP
+            | IoUringFeatures::IORING_FEAT_NODROP
+            | IoUringFeatures::IORING_FEAT_SUBMIT_STABLE
+            | IoUringFeatures::IORING_FEAT_RW_CUR_POS
+            | IoUringFeatures::IORING_FEAT_EXT_ARG)
+            .bits();
+        rings.fill_offsets(params);
+
+        let inner = Inner {
+            pending: VecDeque::new(),
+            overflow: VecDeque::new(),
+            nr_posted: 0,
+            files: Vec::new(),
+            buffers: Vec::new(),
+            eventfd: None,
+        };
+        Ok(Self {
+            rings,
+            inner: SgxMutex::new(inner),
+            waiters: WaiterQueue::new(),
+            host_eventfd: Arc::new(HostEventFd::new()?),
+            notifier: IoNotifier::new(),
+        })
+    }
+
+    /// Get the shared memory to be mapped at the offset of mmap.
+    pub fn mmap_segment(&self, offset: usize, size: usize) -> Result<Arc<ShmSegment>> {
+        self.rings.segment(offset, size)
+    }
+
+    /// Submit the SQEs and wait for the CQEs, returning the number of the
+    /// submitted SQEs.
+    pub fn enter(
+        &self,
+        to_submit: u32,
+        min_complete: u32,
+        flags: IoUringEnterFlags,
+        timeout: Option<Duration>,
+    ) -> Result<usize> {
+        let submitted = if to_submit > 0 {
+            self.submit(to_submit)?
+        } else {
+            0
+        };
+        if flags.contains(IoUringEnterFlags::IORING_ENTER_GETEVENTS) {
+            // Same as Linux, the error of waiting is returned only if no SQE
+            // is submitted
+            if let Err(e) = self.wait_cqes(min_complete, timeout) {
+                if submitted == 0 {
+                    return Err(e);
+                }
+            }
+        }
+        Ok(submitted)
+    }
+
+    fn submit(&self, to_submit: u32) -> Result<usize> {
+        let mut inner = self.inner.lock().unwrap();
+        self.flush_overflow(&mut inner);
+        if !inner.overflow.is_empty() {
+            return_errno!(EBUSY, "the CQ ring overflows");
+        }
+
+        let now = monotonic_now()?;
+        let mut submitted = 0;
+        let mut chain: Option<Chain> = None;
+        while submitted < to_submit as usize {
+            let sqe = match self.rings.pop_sqe() {
+                Some(sqe) => sqe,
+                None => break,
+            };
+            submitted += 1;
+
+            let request = {
+                let ctx = PrepareContext {
+                    now,
+                    nr_posted: inner.nr_posted,
+                    files: &inner.files,
+                    buffers: &inner.buffers,
+                };
+                Request::prepare(&sqe, &ctx)
+            };
+            let is_linked = request
+                .flags()
+                .intersects(SqeFlags::IOSQE_IO_LINK | SqeFlags::IOSQE_IO_HARDLINK);
+            let is_drain = request.flags().contains(SqeFlags::IOSQE_IO_DRAIN);
+            chain
+                .get_or_insert_with(|| Chain::new(is_drain))
+                .requests
+                .push_back(request);
+            if !is_linked {
+                inner.pending.push_back(chain.take().unwrap());
+            }
+        }
+        // Same as Linux, the link that is not ended in the submission is cut
+        if let Some(chain) = chain.take() {
+            inner.pending.push_back(chain);
+        }
+
+        self.process(&mut inner, now);
+        Ok(submitted)
+    }
+
+    fn wait_cqes(&self, min_complete: u32, timeout: Option<Duration>) -> Result<()> {
+        let min_complete = min_complete.min(self.rings.cq_entries());
+        let deadline = match timeout {
+            Some(timeout) => Some(monotonic_now()? + timeout),
+            None => None,
+        };
+
+        // Wait for the CQEs posted by others and the files awaited by the
+        // pending requests
+        let waiter = Waiter::with_host_eventfd(self.host_eventfd.clone());
+        let observer = WaiterQueueObserver::<IoEvents>::new();
+        let weak_observer = Arc::downgrade(&observer) as Weak<dyn Observer<_>>;
+        let mut awaited_files: Vec<FileRef> = Vec::new();
+        let res = loop {
+            self.waiters.reset_and_enqueue(&waiter);
+            observer.waiter_queue().reset_and_enqueue(&waiter);
+
+            let now = monotonic_now()?;
+            let mut wait_timeout = {
+                let mut inner = self.inner.lock().unwrap();
+                self.flush_overflow(&mut inner);
+                self.process(&mut inner, now);
+                if self.rings.cq_ready() >= min_complete {
+                    break Ok(());
+                }
+
+                let mut request_deadline: Option<Duration> = None;
+                for request in inner
+                    .pending
+                    .iter()
+                    .filter_map(|chain| chain.requests.front())
+                {
+                    if let Some((file, events)) = request.awaited_file() {
+                        if !awaited_files
+                            .iter()
+                            .any(|awaited| Arc::ptr_eq(awaited, file))
+                        {
+                            let notifier = file.notifier().unwrap();
+                            notifier.register(weak_observer.clone(), Some(events), None);
+                            awaited_files.push(file.clone());
+                        }
+                    }
+                    if let Some(deadline) = request.deadline() {
+                        request_deadline =
+                            Some(request_deadline.map_or(deadline, |d| d.min(deadline)));
+                    }
+                }
+                let wait_deadline = match (deadline, request_deadline) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (a, b) => a.or(b),
+                };
+                wait_deadline.map(|deadline| deadline.checked_sub(now).unwrap_or_default())
+            };
+
+            if let Some(deadline) = deadline {
+                if now >= deadline {
+                    break Err(errno!(ETIME, "the time is up"));
+                }
+            }
+            match waiter.wait_host_event_mut(wait_timeout.as_mut()) {
+                Ok(()) => {}
+                Err(e) if e.errno() == ETIMEDOUT => {}
+                Err(e) => break Err(e),
+            }
+        };
+
+        for file in awaited_files {
+            file.notifier().unwrap().unregister(&weak_observer);
+        }
+        res
+    }
+
+    // Do the requests that are ready, and keep the others pending
+    fn process(&self, inner: &mut Inner, now: Duration) {
+        let nr_posted = inner.nr_posted;
+        let mut idx = 0;
+        while idx < inner.pending.len() {
+            // A drained chain starts after all the chains before it are done,
+            // and the chains after it wait for it
+            if inner.pending[idx].is_drain && idx > 0 {
+                break;
+            }
+            if self.process_chain(inner, idx, now) {
+                inner.pending.remove(idx);
+            } else {
+                inner.pending[idx].is_deferred = true;
+                if inner.pending[idx].is_drain {
+                    break;
+                }
+                idx += 1;
+            }
+        }
+
+        if inner.nr_posted != nr_posted {
+            self.notifier.broadcast(&IoEvents::IN);
+            self.waiters.dequeue_and_wake_all();
+        }
+    }
+
+    // Do the requests of the chain one after another, returning whether the
+    // chain is done
+    fn process_chain(&self, inner: &mut Inner, idx: usize, now: Duration) -> bool {
+        let is_async = inner.pending[idx].is_deferred;
+        // The requests that notify an eventfd are done synchronously, since
+        // nobody completes the requests done by the host I/O threads to signal
+        // the eventfd
+        let is_host_io_allowed = inner.eventfd.is_none();
+        loop {
+            let request = {
+                let nr_posted = inner.nr_posted;
+                let requests = &mut inner.pending[idx].requests;
+                match requests.front_mut() {
+                    Some(request) => {
+                        if is_host_io_allowed {
+                            request.start_host_io(&self.host_eventfd);
+                        }
+                        if !request.is_ready(now, nr_posted) {
+                            return false;
+                        }
+                    }
+                    None => return true,
+                }
+                requests.pop_front().unwrap()
+            };
+            let res = request.execute(now);
+            self.post_cqe(inner, request.user_data(), res, is_async);
+
+            // Same as Linux, a failed request or a short read or write breaks
+            // the link, unless the link is a hard one
+            let flags = request.flags();
+            let is_failed = res < 0 || request.is_short(res);
+            if is_failed
+                && flags.contains(SqeFlags::IOSQE_IO_LINK)
+                && !flags.contains(SqeFlags::IOSQE_IO_HARDLINK)
+            {
+                let cancelled: Vec<Request> = inner.pending[idx].requests.drain(..).collect();
+                for request in cancelled {
+                    self.post_cqe(inner, request.user_data(), -(ECANCELED as i32), is_async);
+                }
+                return true;
+            }
+        }
+    }
+
+    fn post_cqe(&self, inner: &mut Inner, user_data: u64, res: i32, is_async: bool) {
+        let cqe = io_uring_cqe_t {
+            user_data,
+            res,
+            flags: 0,
+        };
+        inner.nr_posted += 1;
+        if !inner.overflow.is_empty() || !self.rings.push_cqe(&cqe) {
+            if inner.overflow.len() < MAX_OVERFLOW_CQES {
+                inner.overflow.push_back(cqe);
+                self.rings.set_cq_overflow_flag(true);
+            } else {
+                self.rings.add_cq_overflow();
+            }
+        }
+
+        if let Some(eventfd) = inner.eventfd.as_ref() {
+            if is_async || !eventfd.is_async_only {
+                eventfd.file.as_event().unwrap().signal();
+            }
+        }
+    }
+
+    fn flush_overflow(&self, inner: &mut Inner) {
+        if inner.overflow.is_empty() {
+            return;
+        }
+        while let Some(cqe) = inner.overflow.front() {
+            if !self.rings.push_cqe(cqe) {
+                break;
+            }
+            inner.overflow.pop_front();
+        }
+        self.rings.set_cq_overflow_flag(!inner.overflow.is_empty());
+    }
+
+    /// Register or unregister the resources of the io_uring.
+    pub fn register(&self, op: IoUringRegisterOp, arg: usize, nr_args: u32) -> Result<usize> {
+        let mut inner = self.inner.lock().unwrap();
+        match op {
+            IoUringRegisterOp::RegisterBuffers => {
+                if !inner.buffers.is_empty() {
+                    return_errno!(EBUSY, "the buffers are registered");
+                }
+                if nr_args == 0 || nr_args > MAX_FIXED_BUFFERS {
+                    return_errno!(EINVAL, "invalid number of buffers");
+                }
+                let iov = arg as *const [usize; 2];
+                from_user::check_array(iov, nr_args as usize)?;
+                let iovecs = unsafe { std::slice::from_raw_parts(iov, nr_args as usize) };
+                let mut buffers = Vec::with_capacity(iovecs.len());
+                for &[base, len] in iovecs {
+                    if base == 0 || len == 0 || len > MAX_FIXED_BUFFER_SIZE {
+                        return_errno!(EFAULT, "invalid buffer");
+                    }
+                    from_user::check_mut_array(base as *mut u8, len)?;
+                    buffers.push((base, len));
+                }
+                inner.buffers = buffers;
+            }
+            IoUringRegisterOp::UnregisterBuffers => {
+                check_no_args(arg, nr_args)?;
+                if inner.buffers.is_empty() {
+                    return_errno!(ENXIO, "no buffer is registered");
+                }
+                inner.buffers.clear();
+            }
+            IoUringRegisterOp::RegisterFiles => {
+                if !inner.files.is_empty() {
+                    return_errno!(EBUSY, "the files are registered");
+                }
+                if nr_args == 0 || nr_args > MAX_FIXED_FILES {
+                    return_errno!(EINVAL, "invalid number of files");
+                }
+                let fds = fds_from_user(arg, nr_args)?;
+                inner.files = fds
+                    .iter()
+                    .map(|&fd| file_to_register(fd))
+                    .collect::<Result<_>>()?;
+            }
+            IoUringRegisterOp::UnregisterFiles => {
+                check_no_args(arg, nr_args)?;
+                if inner.files.is_empty() {
+                    return_errno!(ENXIO, "no file is registered");
+                }
+                inner.files.clear();
+            }
+            IoUringRegisterOp::RegisterFilesUpdate => {
+                if inner.files.is_empty() {
+                    return_errno!(ENXIO, "no file is registered");
+                }
+                let update_ptr = arg as *const io_uring_files_update_t;
+                from_user::check_ptr(update_ptr)?;
+                let update = unsafe { *update_ptr };
+                if update.resv != 0 {
+                    return_errno!(EINVAL, "the reserved field is not zero");
+                }
+                let offset = update.offset as usize;
+                if offset + nr_args as usize > inner.files.len() {
+                    return_errno!(EINVAL, "the update is beyond the registered files");
+                }
+                let fds = fds_from_user(update.fds as usize, nr_args)?;
+                let files = fds
+                    .iter()
+                    .map(|&fd| file_to_register(fd))
+                    .collect::<Result<Vec<_>>>()?;
+                for (i, file) in files.into_iter().enumerate() {
+                    inner.files[offset + i] = file;
+                }
+                return Ok(nr_args as usize);
+            }
+            IoUringRegisterOp::RegisterEventfd | IoUringRegisterOp::RegisterEventfdAsync => {
+                if nr_args != 1 {
+                    return_errno!(EINVAL, "one eventfd is expected");
+                }
+                if inner.eventfd.is_some() {
+                    return_errno!(EBUSY, "an eventfd is registered");
+                }
+                let fd_ptr = arg as *const i32;
+                from_user::check_ptr(fd_ptr)?;
+                let file = current!().file(unsafe { *fd_ptr } as FileDesc)?;
+                file.as_event()?;
+                inner.eventfd = Some(RegisteredEventfd {
+                    file,
+                    is_async_only: op == IoUringRegisterOp::RegisterEventfdAsync,
+                });
+            }
+            IoUringRegisterOp::UnregisterEventfd => {
+                check_no_args(arg, nr_args)?;
+                if inner.eventfd.take().is_none() {
+                    return_errno!(ENXIO, "no eventfd is registered");
+                }
+            }
+            IoUringRegisterOp::RegisterProbe => {
+                do_probe(arg, nr_args)?;
+            }
+        }
+        Ok(0)
+    }
+}
+
+impl File for IoUringFile {
+    fn poll_new(&self) -> IoEvents {
+        let mut events = IoEvents::empty();
+        let has_overflow = {
+            // Post the CQEs of the requests done by the host I/O threads
+            let mut inner = self.inner.lock().unwrap();
+            if let Ok(now) = monotonic_now() {
+                self.process(&mut inner, now);
+            }
+            !inner.overflow.is_empty()
+        };
+        if self.rings.cq_ready() > 0 || has_overflow {
+            events |= IoEvents::IN;
+        }
+        if self.rings.sq_pending() < self.rings.sq_entries() {
+            events |= IoEvents::OUT;
+        }
+        events
+    }
+
+    fn notifier(&self) -> Option<&IoNotifier> {
+        Some(&self.notifier)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Debug for IoUringFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.inner.lock().unwrap();
+        f.debug_struct("IoUringFile")
+            .field("rings", &self.rings)
+            .field("nr_pending", &inner.pending.len())
+            .field("nr_overflow", &inner.overflow.len())
+            .finish()
+    }
+}
+
+impl Chain {
+    fn new(is_drain: bool) -> Self {
+        Self {
+            requests: VecDeque::new(),
+            is_drain,
+            is_deferred: false,
+        }
+    }
+}
+
+pub trait AsIoUring {
+    fn as_io_uring(&self) -> Result<&IoUringFile>;
+}
+
+impl AsIoUring for FileRef {
+    fn as_io_uring(&self) -> Result<&IoUringFile> {
+        self.as_any()
+            .downcast_ref::<IoUringFile>()
+            .ok_or_else(|| errno!(EOPNOTSUPP, "not an io_uring"))
+    }
+}
+
+fn monotonic_now() -> Result<Duration> {
+    Ok(do_clock_gettime(ClockID::CLOCK_MONOTONIC)?.as_duration())
+}
+
+fn check_no_args(arg: usize, nr_args: u32) -> Result<()> {
+    if arg != 0 || nr_args != 0 {
+        return_errno!(EINVAL, "no argument is expected");
+    }
+    Ok(())
+}
+
+fn fds_from_user(fds: usize, nr: u32) -> Result<Vec<i32>> {
+    let fds = fds as *const i32;
+    from_user::check_array(fds, nr as usize)?;
+    Ok(unsafe { std::slice::from_raw_parts(fds, nr as usize) }.to_vec())
+}
+
+// A fd of -1 leaves a hole in the registered files
+fn file_to_register(fd: i32) -> Result<Option<FileRef>> {
+    if fd == -1 {
+        return Ok(None);
+    }
+    let file = current!().file(fd as FileDesc)?;
+    // Same as Linux, an io_uring cannot be registered to avoid the cycles
+    if file.as_io_uring().is_ok() {
+        return_errno!(EBADF, "an io_uring cannot be registered");
+    }
+    Ok(Some(file))
+}
+
+fn do_probe(arg: usize, nr_args: u32) -> Result<()> {
+    let nr_ops = (nr_args as usize).min(IoUringOp::LAST_OP as usize + 1);
+    let size = std::mem::size_of::<io_uring_probe_t>()
+        + nr_ops * std::mem::size_of::<io_uring_probe_op_t>();
+    from_user::check_mut_array(arg as *mut u8, size)?;
+    let buf = unsafe { std::slice::from_raw_parts_mut(arg as *mut u8, size) };
+    if buf.iter().any(|&b| b != 0) {
+        return_errno!(EINVAL, "the probe is not zeroed");
+    }
+
+    let probe = unsafe { &mut *(arg as *mut io_uring_probe_t) };
+    probe.last_op = IoUringOp::LAST_OP;
+    probe.ops_len = nr_ops as u8;
+    let ops = unsafe {
+        std::slice::from_raw_parts_mut(
+            (arg + std::mem::size_of::<io_uring_probe_t>()) as *mut io_uring_probe_op_t,
+            nr_ops,
+        )
+    };
+    for (opcode, op) in ops.iter_mut().enumerate() {
+        op.op = opcode as u8;
+        if IoUringOp::from_u8(opcode as u8).is_some() {
+            op.flags = IO_URING_OP_SUPPORTED;
+        }
+    }
+    Ok(())
+}
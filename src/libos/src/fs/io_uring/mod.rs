@@ -0,0 +1,31 @@
+//! An io_uring-compatible interface, i.e., io_uring_setup, io_uring_enter and
+//! io_uring_register.
+//!
+//! The SQ ring, the CQ ring and the SQEs live in the enclave memory shared by
+//! the LibOS and the user, which the user maps through the fd of the io_uring,
+//! so that a batch of requests is submitted by one syscall without copying.
+//!
+//! The reads and writes of the host files, e.g., the sends and receives of host
+//! sockets, are submitted to the host I/O threads of the LibOS async I/O
+//! engine in io_uring_enter, and the other requests are done by the LibOS in
+//! io_uring_enter. A request on a LibOS file that is not ready, e.g., a read of
+//! an empty pipe, is kept pending until the file is ready, so that the requests
+//! after it are not blocked.
+//!
+//! The CQEs of the pending requests and the requests done by the host I/O
+//! threads are posted when io_uring_enter is called again, e.g., to wait for
+//! the CQEs, which is how liburing waits, or when the io_uring is polled. As
+//! nobody posts the CQEs to signal the registered eventfd, the requests are
+//! done synchronously if an eventfd is registered.
+
+use super::*;
+
+pub use self::abi::{
+    io_uring_getevents_arg_t, io_uring_params_t, IoUringEnterFlags, IoUringRegisterOp,
+};
+pub use self::io_uring_file::{AsIoUring, IoUringFile};
+
+mod abi;
+mod io_uring_file;
+mod request;
+mod ring;
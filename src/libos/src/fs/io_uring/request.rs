@@ -0,0 +1,533 @@
+use std::time::Duration;
+
+use super::abi::*;
+use super::*;
+use crate::events::HostEventFd;
+use crate::fs::async_host_io::{AsyncHostIo, HostIoOp};
+use crate::fs::file_ops;
+use crate::net::{do_accept4, do_connect, do_poll_new, do_recvfrom, do_recvmsg, do_sendmsg};
+use crate::net::{do_sendto, msghdr, msghdr_mut, PollFd};
+use crate::time::timespec_t;
+use util::mem_util::from_user;
+
+/// A request prepared from an SQE.
+///
+/// Same as Linux, the file of a request is looked up when the request is
+/// submitted, and the arguments are read from the SQE, so that the user may
+/// reuse the SQE right after the submission (IORING_FEAT_SUBMIT_STABLE).
+#[derive(Debug)]
+pub struct Request {
+    user_data: u64,
+    flags: SqeFlags,
+    op: Op,
+    // The I/O in progress in the host I/O threads
+    host_io: Option<AsyncHostIo>,
+}
+
+#[derive(Debug)]
+enum Op {
+    Nop,
+    Rw {
+        file: FileRef,
+        is_write: bool,
+        buf: usize,
+        len: usize,
+        offset: u64,
+    },
+    Rwv {
+        file: FileRef,
+        is_write: bool,
+        iovecs: Vec<(usize, usize)>,
+        offset: u64,
+    },
+    Fsync {
+        file: FileRef,
+        is_datasync: bool,
+    },
+    PollAdd {
+        file: FileRef,
+        fd: FileDesc,
+        events: IoEvents,
+    },
+    // The requests of sockets, which are done by the syscalls of the same
+    // arguments on the fd
+    Socket {
+        file: FileRef,
+        fd: FileDesc,
+        op: IoUringOp,
+        sqe: io_uring_sqe_t,
+    },
+    Close {
+        fd: FileDesc,
+    },
+    Timeout {
+        deadline: Duration,
+        // The number of the posted CQEs that completes the timeout
+        target_nr_posted: Option<u64>,
+    },
+    // A request that fails when it is prepared
+    Failed(Errno),
+}
+
+// The offset of READ and WRITE to use the current position of the file,
+// i.e., IORING_FEAT_RW_CUR_POS
+const CUR_POS: u64 = u64::MAX;
+
+impl Request {
+    /// Prepare a request from the SQE. The failure of the preparation is
+    /// reported by the CQE of the request.
+    pub fn prepare(sqe: &io_uring_sqe_t, ctx: &PrepareContext) -> Self {
+        let flags = SqeFlags::from_bits_truncate(sqe.flags);
+        let op = match Self::prepare_op(sqe, ctx) {
+            Ok(op) => op,
+            Err(e) => Op::Failed(e.errno()),
+        };
+        Self {
+            user_data: sqe.user_data,
+            flags,
+            op,
+            host_io: None,
+        }
+    }
+
+    fn prepare_op(sqe: &io_uring_sqe_t, ctx: &PrepareContext) -> Result<Op> {
+        if SqeFlags::from_bits(sqe.flags).is_none() {
+            return_errno!(EINVAL, "unknown flags of the SQE");
+        }
+        let op = IoUringOp::from_u8(sqe.opcode)
+            .ok_or_else(|| errno!(EINVAL, "the opcode is not supported"))?;
+        let is_fixed_file =
+            SqeFlags::from_bits_truncate(sqe.flags).contains(SqeFlags::IOSQE_FIXED_FILE);
+        let file = || -> Result<FileRef> {
+            if is_fixed_file {
+                ctx.fixed_file(sqe.fd)
+            } else {
+                current!().file(sqe.fd as FileDesc)
+            }
+        };
+
+        let op = match op {
+            IoUringOp::Nop => Op::Nop,
+            IoUringOp::Read | IoUringOp::Write => Op::Rw {
+                file: file()?,
+                is_write: op == IoUringOp::Write,
+                buf: sqe.addr as usize,
+                len: sqe.len as usize,
+                offset: sqe.off,
+            },
+            IoUringOp::ReadFixed | IoUringOp::WriteFixed => {
+                let (buf, len) = (sqe.addr as usize, sqe.len as usize);
+                ctx.check_fixed_buffer(sqe.buf_index, buf, len)?;
+                Op::Rw {
+                    file: file()?,
+                    is_write: op == IoUringOp::WriteFixed,
+                    buf,
+                    len,
+                    offset: sqe.off,
+                }
+            }
+            IoUringOp::Readv | IoUringOp::Writev => Op::Rwv {
+                file: file()?,
+                is_write: op == IoUringOp::Writev,
+                iovecs: iovecs_from_user(sqe.addr as usize, sqe.len as usize)?,
+                offset: sqe.off,
+            },
+            IoUringOp::Fsync => Op::Fsync {
+                file: file()?,
+                is_datasync: sqe.op_flags & IORING_FSYNC_DATASYNC != 0,
+            },
+            IoUringOp::PollAdd => {
+                if is_fixed_file {
+                    return_errno!(EINVAL, "fixed files are not supported by POLL_ADD");
+                }
+                Op::PollAdd {
+                    file: file()?,
+                    fd: sqe.fd as FileDesc,
+                    events: IoEvents::from_raw(sqe.op_flags) | IoEvents::ERR | IoEvents::HUP,
+                }
+            }
+            IoUringOp::Send
+            | IoUringOp::Recv
+            | IoUringOp::Sendmsg
+            | IoUringOp::Recvmsg
+            | IoUringOp::Accept
+            | IoUringOp::Connect => {
+                if is_fixed_file {
+                    return_errno!(EINVAL, "fixed files are not supported by socket requests");
+                }
+                Op::Socket {
+                    file: file()?,
+                    fd: sqe.fd as FileDesc,
+                    op,
+                    sqe: *sqe,
+                }
+            }
+            IoUringOp::Close => {
+                if is_fixed_file {
+                    return_errno!(EBADF, "fixed files cannot be closed");
+                }
+                Op::Close {
+                    fd: sqe.fd as FileDesc,
+                }
+            }
+            IoUringOp::Timeout => {
+                if sqe.len != 1 {
+                    return_errno!(EINVAL, "invalid length of the timeout");
+                }
+                let ts_ptr = sqe.addr as *const timespec_t;
+                from_user::check_ptr(ts_ptr)?;
+                let ts = timespec_t::from_raw_ptr(ts_ptr)?.as_duration();
+                let deadline = if sqe.op_flags & IORING_TIMEOUT_ABS != 0 {
+                    ts
+                } else {
+                    ctx.now + ts
+                };
+                let target_nr_posted = if sqe.off != 0 {
+                    Some(ctx.nr_posted + sqe.off)
+                } else {
+                    None
+                };
+                Op::Timeout {
+                    deadline,
+                    target_nr_posted,
+                }
+            }
+        };
+        Ok(op)
+    }
+
+    pub fn user_data(&self) -> u64 {
+        self.user_data
+    }
+
+    pub fn flags(&self) -> SqeFlags {
+        self.flags
+    }
+
+    /// The file that notifies the events that the request waits for, if the
+    /// request is kept pending until the file is ready.
+    ///
+    /// Only the LibOS files that are able to notify the events are waited
+    /// for. The requests of the other files, e.g., regular files, are done at
+    /// once, which may block, unless they are done by the host I/O threads.
+    pub fn awaited_file(&self) -> Option<(&FileRef, IoEvents)> {
+        let (file, events) = match &self.op {
+            Op::Rw { file, is_write, .. } | Op::Rwv { file, is_write, .. } => {
+                let events = if *is_write {
+                    IoEvents::OUT
+                } else {
+                    IoEvents::IN
+                };
+                (file, events)
+            }
+            Op::PollAdd { file, events, .. } => (file, *events),
+            Op::Socket { file, op, .. } => {
+                let events = match op {
+                    IoUringOp::Send | IoUringOp::Sendmsg | IoUringOp::Connect => IoEvents::OUT,
+                    _ => IoEvents::IN,
+                };
+                (file, events)
+            }
+            _ => return None,
+        };
+        if file.host_fd().is_some() || file.notifier().is_none() {
+            return None;
+        }
+        Some((file, events | IoEvents::ERR | IoEvents::HUP))
+    }
+
+    /// Start to do the request with the host I/O threads, if it is a read or
+    /// write of a host file, e.g., a host socket. The host eventfd is written
+    /// once the request is done.
+    ///
+    /// The request is done synchronously by `execute` if it is not started,
+    /// e.g., the I/O engine is not enabled.
+    pub fn start_host_io(&mut self, host_eventfd: &Arc<HostEventFd>) {
+        if self.host_io.is_some() {
+            return;
+        }
+        // The host files have no position, so the offset is ignored
+        let (file, op, bufs) = match &self.op {
+            Op::Rw {
+                file,
+                is_write,
+                buf,
+                len,
+                ..
+            } => (file, rw_host_io_op(*is_write), vec![(*buf, *len)]),
+            Op::Rwv {
+                file,
+                is_write,
+                iovecs,
+                ..
+            } => (file, rw_host_io_op(*is_write), iovecs.clone()),
+            Op::Socket { file, op, sqe, .. } => {
+                let flags = sqe.op_flags as i32;
+                let op = match op {
+                    IoUringOp::Send => HostIoOp::Send(flags),
+                    IoUringOp::Recv => HostIoOp::Recv(flags),
+                    _ => return,
+                };
+                (file, op, vec![(sqe.addr as usize, sqe.len as usize)])
+            }
+            _ => return,
+        };
+        let host_fd = match file.host_fd() {
+            Some(host_fd) => host_fd.to_raw(),
+            None => return,
+        };
+        match AsyncHostIo::start(host_fd, op, &bufs, host_eventfd) {
+            Some(Ok(host_io)) => self.host_io = Some(host_io),
+            Some(Err(e)) => self.op = Op::Failed(e.errno()),
+            None => {}
+        }
+    }
+
+    /// The time when the request expires, if it is a timeout.
+    pub fn deadline(&self) -> Option<Duration> {
+        match &self.op {
+            Op::Timeout { deadline, .. } => Some(*deadline),
+            _ => None,
+        }
+    }
+
+    /// Whether the request is able to be done without blocking.
+    pub fn is_ready(&self, now: Duration, nr_posted: u64) -> bool {
+        if let Some(host_io) = &self.host_io {
+            return host_io.is_done();
+        }
+        if let Op::Timeout {
+            deadline,
+            target_nr_posted,
+        } = &self.op
+        {
+            return now >= *deadline || target_nr_posted.map_or(false, |nr| nr_posted >= nr);
+        }
+        match self.awaited_file() {
+            Some((file, events)) => !(file.poll_new() & events).is_empty(),
+            None => true,
+        }
+    }
+
+    /// Do the request, returning the result of the CQE.
+    pub fn execute(&self, now: Duration) -> i32 {
+        if let Some(host_io) = &self.host_io {
+            return match host_io.result().unwrap() {
+                Ok(len) => len as i32,
+                Err(e) => -(e.errno() as i32),
+            };
+        }
+        let res = match &self.op {
+            Op::Nop => Ok(0),
+            Op::Rw {
+                file,
+                is_write,
+                buf,
+                len,
+                offset,
+            } => do_rw(file, *is_write, *buf, *len, *offset),
+            Op::Rwv {
+                file,
+                is_write,
+                iovecs,
+                offset,
+            } => do_rwv(file, *is_write, iovecs, *offset),
+            Op::Fsync { file, is_datasync } => do_fsync(file, *is_datasync),
+            Op::PollAdd { file, fd, events } => do_poll_add(file, *fd, *events),
+            Op::Socket { fd, op, sqe, .. } => do_socket_op(*fd, *op, sqe),
+            Op::Close { fd } => file_ops::do_close(*fd).map(|_| 0),
+            Op::Timeout { deadline, .. } => {
+                if now >= *deadline {
+                    Err(errno!(ETIME, "the timeout expires"))
+                } else {
+                    Ok(0)
+                }
+            }
+            Op::Failed(errno) => return -(*errno as i32),
+        };
+        match res {
+            Ok(len) => len as i32,
+            Err(e) => -(e.errno() as i32),
+        }
+    }
+
+    /// Whether a successful result breaks the link of the request, i.e., a
+    /// short read or write, which is the same as Linux.
+    pub fn is_short(&self, res: i32) -> bool {
+        match &self.op {
+            Op::Rw { len, .. } => (res as usize) < *len,
+            Op::Rwv { iovecs, .. } => {
+                (res as usize) < iovecs.iter().map(|(_, len)| len).sum::<usize>()
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The state of the io_uring used to prepare the requests.
+pub struct PrepareContext<'a> {
+    pub now: Duration,
+    pub nr_posted: u64,
+    pub files: &'a [Option<FileRef>],
+    pub buffers: &'a [(usize, usize)],
+}
+
+impl<'a> PrepareContext<'a> {
+    fn fixed_file(&self, idx: i32) -> Result<FileRef> {
+        self.files
+            .get(idx as usize)
+            .and_then(|file| file.clone())
+            .ok_or_else(|| errno!(EBADF, "invalid index of the fixed files"))
+    }
+
+    fn check_fixed_buffer(&self, idx: u16, buf: usize, len: usize) -> Result<()> {
+        let (start, size) = *self
+            .buffers
+            .get(idx as usize)
+            .ok_or_else(|| errno!(EFAULT, "invalid index of the fixed buffers"))?;
+        if buf < start || buf.saturating_add(len) > start + size {
+            return_errno!(EFAULT, "the buffer is beyond the fixed buffer");
+        }
+        Ok(())
+    }
+}
+
+// The files that have no position, e.g., pipes and sockets, ignore the offset
+fn is_stream(file: &FileRef) -> bool {
+    file.as_inode_file().is_err()
+}
+
+fn do_rw(file: &FileRef, is_write: bool, buf: usize, len: usize, offset: u64) -> Result<usize> {
+    if is_write {
+        from_user::check_array(buf as *const u8, len)?;
+        let buf = unsafe { std::slice::from_raw_parts(buf as *const u8, len) };
+        if offset == CUR_POS || is_stream(file) {
+            file.write(buf)
+        } else {
+            file.write_at(checked_offset(offset)?, buf)
+        }
+    } else {
+        from_user::check_mut_array(buf as *mut u8, len)?;
+        let buf = unsafe { std::slice::from_raw_parts_mut(buf as *mut u8, len) };
+        if offset == CUR_POS || is_stream(file) {
+            file.read(buf)
+        } else {
+            file.read_at(checked_offset(offset)?, buf)
+        }
+    }
+}
+
+fn do_rwv(file: &FileRef, is_write: bool, iovecs: &[(usize, usize)], offset: u64) -> Result<usize> {
+    if offset == CUR_POS || is_stream(file) {
+        return if is_write {
+            let bufs = iovecs
+                .iter()
+                .map(|&(base, len)| unsafe { std::slice::from_raw_parts(base as *const u8, len) })
+                .collect::<Vec<_>>();
+            file.writev(&bufs)
+        } else {
+            let mut bufs = iovecs
+                .iter()
+                .map(|&(base, len)| unsafe { std::slice::from_raw_parts_mut(base as *mut u8, len) })
+                .collect::<Vec<_>>();
+            file.readv(&mut bufs)
+        };
+    }
+
+    let mut offset = checked_offset(offset)?;
+    let mut total = 0;
+    for &(base, len) in iovecs {
+        let done = match do_rw(file, is_write, base, len, offset as u64) {
+            Ok(done) => done,
+            Err(e) if total > 0 => break,
+            Err(e) => return Err(e),
+        };
+        total += done;
+        offset += done;
+        if done < len {
+            break;
+        }
+    }
+    Ok(total)
+}
+
+fn rw_host_io_op(is_write: bool) -> HostIoOp {
+    if is_write {
+        HostIoOp::Write
+    } else {
+        HostIoOp::Read
+    }
+}
+
+fn checked_offset(offset: u64) -> Result<usize> {
+    if offset > i64::max_value() as u64 {
+        return_errno!(EINVAL, "the offset is negative");
+    }
+    Ok(offset as usize)
+}
+
+fn do_fsync(file: &FileRef, is_datasync: bool) -> Result<usize> {
+    current!().vm().msync_by_file(file);
+    if is_datasync {
+        file.sync_data()?;
+    } else {
+        file.sync_all()?;
+    }
+    Ok(0)
+}
+
+fn do_poll_add(file: &FileRef, fd: FileDesc, events: IoEvents) -> Result<usize> {
+    let ready = file.poll_new() & events;
+    if !ready.is_empty() {
+        return Ok(ready.to_raw() as usize);
+    }
+    // The file is not waited for by the io_uring, e.g., a host socket
+    let poll_fds = [PollFd::new(fd, events)];
+    do_poll_new(&poll_fds, None)?;
+    Ok(poll_fds[0].revents().get().to_raw() as usize)
+}
+
+fn do_socket_op(fd: FileDesc, op: IoUringOp, sqe: &io_uring_sqe_t) -> Result<usize> {
+    let fd = fd as i32;
+    let flags = sqe.op_flags as i32;
+    let res = match op {
+        IoUringOp::Send => do_sendto(
+            fd,
+            sqe.addr as *const _,
+            sqe.len as usize,
+            flags,
+            std::ptr::null(),
+            0,
+        )?,
+        IoUringOp::Recv => do_recvfrom(
+            fd,
+            sqe.addr as *mut _,
+            sqe.len as usize,
+            flags,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )?,
+        IoUringOp::Sendmsg => do_sendmsg(fd, sqe.addr as *const msghdr, flags)?,
+        IoUringOp::Recvmsg => do_recvmsg(fd, sqe.addr as *mut msghdr_mut, flags)?,
+        // The address and its length are in addr and addr2, i.e., off
+        IoUringOp::Accept => do_accept4(fd, sqe.addr as *mut _, sqe.off as *mut _, flags)?,
+        IoUringOp::Connect => do_connect(fd, sqe.addr as *const _, sqe.off as u32)?,
+        _ => unreachable!(),
+    };
+    Ok(res as usize)
+}
+
+// The base and length of each `struct iovec` of READV and WRITEV
+fn iovecs_from_user(iov: usize, count: usize) -> Result<Vec<(usize, usize)>> {
+    const UIO_MAXIOV: usize = 1024;
+    if count > UIO_MAXIOV {
+        return_errno!(EINVAL, "too many iovecs");
+    }
+    let iov = iov as *const [usize; 2];
+    from_user::check_array(iov, count)?;
+    let iovecs = unsafe { std::slice::from_raw_parts(iov, count) };
+    for iovec in iovecs {
+        from_user::check_array(iovec[0] as *const u8, iovec[1])?;
+    }
+    Ok(iovecs.iter().map(|iovec| (iovec[0], iovec[1])).collect())
+}
@@ -0,0 +1,196 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use super::abi::*;
+use super::*;
+use crate::vm::ShmSegment;
+
+// The layout of the rings, where the SQ ring and the CQ ring share one
+// region of memory, i.e., IORING_FEAT_SINGLE_MMAP
+const SQ_HEAD: usize = 0;
+const SQ_TAIL: usize = 4;
+const SQ_RING_MASK: usize = 8;
+const SQ_RING_ENTRIES: usize = 12;
+const SQ_FLAGS: usize = 16;
+const SQ_DROPPED: usize = 20;
+const CQ_HEAD: usize = 24;
+const CQ_TAIL: usize = 28;
+const CQ_RING_MASK: usize = 32;
+const CQ_RING_ENTRIES: usize = 36;
+const CQ_OVERFLOW: usize = 40;
+const CQ_FLAGS: usize = 44;
+const CQES: usize = 64;
+
+/// The SQ ring, the CQ ring and the array of SQEs, which live in the memory
+/// shared by the LibOS and the user.
+///
+/// The user is trusted for nothing: the indexes read from the shared memory
+/// are masked or checked before they are used.
+pub struct Rings {
+    rings: Arc<ShmSegment>,
+    sqes: Arc<ShmSegment>,
+    sq_entries: u32,
+    cq_entries: u32,
+}
+
+impl Rings {
+    pub fn new(sq_entries: u32, cq_entries: u32) -> Result<Self> {
+        let rings_size = Self::sq_array_offset(cq_entries) + sq_entries as usize * 4;
+        let rings = ShmSegment::new_anonymous(rings_size)?;
+        let sqes_size = sq_entries as usize * std::mem::size_of::<io_uring_sqe_t>();
+        let sqes = ShmSegment::new_anonymous(sqes_size)?;
+        let new_self = Self {
+            rings,
+            sqes,
+            sq_entries,
+            cq_entries,
+        };
+        new_self
+            .word(SQ_RING_MASK)
+            .store(sq_entries - 1, Ordering::Relaxed);
+        new_self
+            .word(SQ_RING_ENTRIES)
+            .store(sq_entries, Ordering::Relaxed);
+        new_self
+            .word(CQ_RING_MASK)
+            .store(cq_entries - 1, Ordering::Relaxed);
+        new_self
+            .word(CQ_RING_ENTRIES)
+            .store(cq_entries, Ordering::Relaxed);
+        Ok(new_self)
+    }
+
+    /// Fill the offsets of the rings in the params of io_uring_setup.
+    pub fn fill_offsets(&self, params: &mut io_uring_params_t) {
+        params.sq_off = io_sqring_offsets_t {
+            head: SQ_HEAD as u32,
+            tail: SQ_TAIL as u32,
+            ring_mask: SQ_RING_MASK as u32,
+            ring_entries: SQ_RING_ENTRIES as u32,
+            flags: SQ_FLAGS as u32,
+            dropped: SQ_DROPPED as u32,
+            array: Self::sq_array_offset(self.cq_entries) as u32,
+            ..Default::default()
+        };
+        params.cq_off = io_cqring_offsets_t {
+            head: CQ_HEAD as u32,
+            tail: CQ_TAIL as u32,
+            ring_mask: CQ_RING_MASK as u32,
+            ring_entries: CQ_RING_ENTRIES as u32,
+            overflow: CQ_OVERFLOW as u32,
+            cqes: CQES as u32,
+            flags: CQ_FLAGS as u32,
+            ..Default::default()
+        };
+    }
+
+    /// Get the segment to be mapped at the offset of mmap.
+    pub fn segment(&self, offset: usize, size: usize) -> Result<Arc<ShmSegment>> {
+        let segment = match offset {
+            IORING_OFF_SQ_RING | IORING_OFF_CQ_RING => &self.rings,
+            IORING_OFF_SQES => &self.sqes,
+            _ => return_errno!(EINVAL, "invalid offset to mmap the io_uring"),
+        };
+        if size > segment.range().size() {
+            return_errno!(EINVAL, "the size to mmap is beyond the rings");
+        }
+        Ok(segment.clone())
+    }
+
+    pub fn sq_entries(&self) -> u32 {
+        self.sq_entries
+    }
+
+    pub fn cq_entries(&self) -> u32 {
+        self.cq_entries
+    }
+
+    /// Pop an SQE, skipping the invalid indexes as Linux does.
+    pub fn pop_sqe(&self) -> Option<io_uring_sqe_t> {
+        loop {
+            let head = self.word(SQ_HEAD).load(Ordering::Relaxed);
+            let tail = self.word(SQ_TAIL).load(Ordering::Acquire);
+            if head == tail {
+                return None;
+            }
+            let idx = self.sq_array()[(head & (self.sq_entries - 1)) as usize];
+            self.word(SQ_HEAD)
+                .store(head.wrapping_add(1), Ordering::Release);
+            if idx >= self.sq_entries {
+                self.word(SQ_DROPPED).fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+            let sqes = self.sqes.range().start() as *const io_uring_sqe_t;
+            return Some(unsafe { std::ptr::read_volatile(sqes.add(idx as usize)) });
+        }
+    }
+
+    /// The number of the SQEs that are submitted by the user.
+    pub fn sq_pending(&self) -> u32 {
+        let head = self.word(SQ_HEAD).load(Ordering::Relaxed);
+        let tail = self.word(SQ_TAIL).load(Ordering::Acquire);
+        tail.wrapping_sub(head)
+    }
+
+    /// Push a CQE, returning false if the CQ ring is full.
+    pub fn push_cqe(&self, cqe: &io_uring_cqe_t) -> bool {
+        let head = self.word(CQ_HEAD).load(Ordering::Acquire);
+        let tail = self.word(CQ_TAIL).load(Ordering::Relaxed);
+        if tail.wrapping_sub(head) >= self.cq_entries {
+            return false;
+        }
+        let cqes = (self.rings.range().start() + CQES) as *mut io_uring_cqe_t;
+        let idx = (tail & (self.cq_entries - 1)) as usize;
+        unsafe { std::ptr::write_volatile(cqes.add(idx), *cqe) };
+        self.word(CQ_TAIL)
+            .store(tail.wrapping_add(1), Ordering::Release);
+        true
+    }
+
+    /// The number of the CQEs that are not consumed by the user.
+    pub fn cq_ready(&self) -> u32 {
+        let head = self.word(CQ_HEAD).load(Ordering::Acquire);
+        let tail = self.word(CQ_TAIL).load(Ordering::Relaxed);
+        tail.wrapping_sub(head).min(self.cq_entries)
+    }
+
+    /// Count the CQEs that are dropped when the CQ ring overflows.
+    pub fn add_cq_overflow(&self) {
+        self.word(CQ_OVERFLOW).fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Tell the user that there are CQEs waiting for room in the CQ ring, so
+    /// that io_uring_enter is called to flush them.
+    pub fn set_cq_overflow_flag(&self, is_overflow: bool) {
+        if is_overflow {
+            self.word(SQ_FLAGS)
+                .fetch_or(IORING_SQ_CQ_OVERFLOW, Ordering::Release);
+        } else {
+            self.word(SQ_FLAGS)
+                .fetch_and(!IORING_SQ_CQ_OVERFLOW, Ordering::Release);
+        }
+    }
+
+    fn sq_array_offset(cq_entries: u32) -> usize {
+        CQES + cq_entries as usize * std::mem::size_of::<io_uring_cqe_t>()
+    }
+
+    fn sq_array(&self) -> &[u32] {
+        let array = self.rings.range().start() + Self::sq_array_offset(self.cq_entries);
+        unsafe { std::slice::from_raw_parts(array as *const u32, self.sq_entries as usize) }
+    }
+
+    fn word(&self, offset: usize) -> &AtomicU32 {
+        unsafe { &*((self.rings.range().start() + offset) as *const AtomicU32) }
+    }
+}
+
+impl Debug for Rings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Rings")
+            .field("sq_entries", &self.sq_entries)
+            .field("cq_entries", &self.cq_entries)
+            .field("sq_pending", &self.sq_pending())
+            .field("cq_ready", &self.cq_ready())
+            .finish()
+    }
+}
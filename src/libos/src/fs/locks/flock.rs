@@ -0,0 +1,144 @@
+use super::*;
+
+bitflags! {
+    pub struct FlockOps: i32 {
+        /// Place a shared lock
+        const LOCK_SH = 1;
+        /// Place an exclusive lock
+        const LOCK_EX = 2;
+        /// Do not block when locking
+        const LOCK_NB = 4;
+        /// Remove an existing lock
+        const LOCK_UN = 8;
+    }
+}
+
+impl FlockOps {
+    pub fn from_i32(ops: i32) -> Result<Self> {
+        let ops = Self::from_bits(ops).ok_or_else(|| errno!(EINVAL, "invalid operation"))?;
+        let num_lock_ops = [Self::LOCK_SH, Self::LOCK_EX, Self::LOCK_UN]
+            .iter()
+            .filter(|op| ops.contains(**op))
+            .count();
+        if num_lock_ops != 1 {
+            return_errno!(
+                EINVAL,
+                "exactly one of LOCK_SH, LOCK_EX and LOCK_UN is required"
+            );
+        }
+        Ok(ops)
+    }
+}
+
+lazy_static! {
+    static ref FLOCK_TABLE: SgxMutex<FlockTable> = SgxMutex::new(FlockTable::new());
+    /// The threads that wait for conflicting locks to be released.
+    static ref FLOCK_WAITERS: WaiterQueue = WaiterQueue::new();
+}
+
+/// A BSD lock, which is owned by an open file.
+#[derive(Debug, Copy, Clone)]
+struct BsdLock {
+    is_exclusive: bool,
+    /// The address of the open file
+    owner: usize,
+}
+
+impl BsdLock {
+    fn conflicts_with(&self, other: &BsdLock) -> bool {
+        self.owner != other.owner && (self.is_exclusive || other.is_exclusive)
+    }
+}
+
+/// The BSD locks of all files.
+pub struct FlockTable {
+    locks: HashMap<LockKey, Vec<BsdLock>>,
+}
+
+impl FlockTable {
+    fn new() -> Self {
+        Self {
+            locks: HashMap::new(),
+        }
+    }
+
+    /// Apply the lock operation to the file on behalf of the open file `owner`.
+    ///
+    /// Converting an existing lock is not atomic: the existing lock is removed
+    /// first and then the new lock is acquired, same as Linux.
+    pub fn set_lock(inode: &dyn INode, owner: usize, ops: FlockOps) -> Result<()> {
+        let key = LockKey::from_inode(inode)?;
+        if ops.contains(FlockOps::LOCK_UN) {
+            Self::release_lock(inode, owner);
+            return Ok(());
+        }
+
+        let new_lock = BsdLock {
+            is_exclusive: ops.contains(FlockOps::LOCK_EX),
+            owner,
+        };
+        let mut waiter = None;
+        let mut table = FLOCK_TABLE.lock().unwrap();
+        if table.remove_lock(&key, owner) {
+            FLOCK_WAITERS.dequeue_and_wake_all();
+        }
+        loop {
+            let has_conflict = table
+                .locks
+                .get(&key)
+                .map(|locks| locks.iter().any(|lock| lock.conflicts_with(&new_lock)))
+                .unwrap_or(false);
+            if !has_conflict {
+                table
+                    .locks
+                    .entry(key)
+                    .or_insert_with(Vec::new)
+                    .push(new_lock);
+                return Ok(());
+            }
+            if ops.contains(FlockOps::LOCK_NB) {
+                return_errno!(EAGAIN, "the file is locked by another open file");
+            }
+
+            // Enqueue the waiter before releasing the table, so that a wakeup
+            // by the open file holding the conflicting lock cannot be missed
+            let waiter = waiter.get_or_insert_with(Waiter::new);
+            FLOCK_WAITERS.reset_and_enqueue(waiter);
+            drop(table);
+            waiter.wait(None)?;
+            table = FLOCK_TABLE.lock().unwrap();
+        }
+    }
+
+    /// Release the lock of the open file `owner` on the file, if any.
+    ///
+    /// This should be called when the open file is closed.
+    pub fn release_lock(inode: &dyn INode, owner: usize) {
+        let mut table = FLOCK_TABLE.lock().unwrap();
+        if table.locks.is_empty() {
+            return;
+        }
+        let key = match LockKey::from_inode(inode) {
+            Ok(key) => key,
+            Err(_) => return,
+        };
+        if table.remove_lock(&key, owner) {
+            drop(table);
+            FLOCK_WAITERS.dequeue_and_wake_all();
+        }
+    }
+
+    fn remove_lock(&mut self, key: &LockKey, owner: usize) -> bool {
+        let locks = match self.locks.get_mut(key) {
+            Some(locks) => locks,
+            None => return false,
+        };
+        let num_locks = locks.len();
+        locks.retain(|lock| lock.owner != owner);
+        let is_removed = locks.len() < num_locks;
+        if locks.is_empty() {
+            self.locks.remove(key);
+        }
+        is_removed
+    }
+}
@@ -0,0 +1,38 @@
+//! Advisory file locks.
+//!
+//! Two kinds of advisory locks are supported:
+//!
+//! * POSIX record locks (`fcntl` with `F_GETLK`, `F_SETLK`, or `F_SETLKW`),
+//! which lock byte ranges of a file and are owned by processes;
+//! * BSD locks (`flock`), which lock whole files and are owned by open files.
+//!
+//! As on Linux, the two kinds of locks are independent of each other. The
+//! locks are shared by all processes in LibOS. A file is identified by its
+//! device and inode numbers, so the locks placed through different open files
+//! of the same file interact with each other.
+
+use super::*;
+use crate::events::{Waiter, WaiterQueue};
+
+pub use self::flock::{FlockOps, FlockTable};
+pub use self::range_lock::{FileRange, RangeLock, RangeLockTable, OFFSET_MAX};
+
+mod flock;
+mod range_lock;
+
+/// The identity of a locked file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct LockKey {
+    dev: usize,
+    ino: usize,
+}
+
+impl LockKey {
+    fn from_inode(inode: &dyn INode) -> Result<Self> {
+        let metadata = inode.metadata()?;
+        Ok(Self {
+            dev: metadata.dev,
+            ino: metadata.inode,
+        })
+    }
+}
@@ -0,0 +1,326 @@
+use super::*;
+
+/// The end of a range that extends to the end of the file, however large the
+/// file grows.
+pub const OFFSET_MAX: off_t = off_t::max_value();
+
+/// The maximum length of the wait-for chain to be checked for deadlocks, same as Linux.
+const MAX_DEADLOCK_ITERATIONS: usize = 10;
+
+lazy_static! {
+    static ref RANGE_LOCK_TABLE: SgxMutex<RangeLockTable> = SgxMutex::new(RangeLockTable::new());
+    /// The threads that wait for conflicting locks to be released.
+    static ref RANGE_LOCK_WAITERS: WaiterQueue = WaiterQueue::new();
+}
+
+/// A byte range `[start, end)` of a file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FileRange {
+    start: off_t,
+    end: off_t,
+}
+
+impl FileRange {
+    pub fn new(start: off_t, end: off_t) -> Result<Self> {
+        if start < 0 || start >= end {
+            return_errno!(EINVAL, "invalid file range");
+        }
+        Ok(Self { start, end })
+    }
+
+    /// Build a range from the `l_start` and `l_len` fields of a lock, where
+    /// `base` is the offset that `l_start` is relative to.
+    pub fn from_start_and_len(base: off_t, l_start: off_t, l_len: off_t) -> Result<Self> {
+        let start = base
+            .checked_add(l_start)
+            .ok_or_else(|| errno!(EOVERFLOW, "the start of the range overflows"))?;
+        if start < 0 {
+            return_errno!(EINVAL, "the start of the range is negative");
+        }
+        if l_len == 0 {
+            return Self::new(start, OFFSET_MAX);
+        }
+        let end = start
+            .checked_add(l_len)
+            .ok_or_else(|| errno!(EOVERFLOW, "the end of the range overflows"))?;
+        // A negative length means the range ends at `start`
+        if l_len < 0 {
+            Self::new(end, start)
+        } else {
+            Self::new(start, end)
+        }
+    }
+
+    pub fn start(&self) -> off_t {
+        self.start
+    }
+
+    /// The length of the range, which is zero if the range extends to the end
+    /// of the file.
+    pub fn len(&self) -> off_t {
+        if self.end == OFFSET_MAX {
+            0
+        } else {
+            self.end - self.start
+        }
+    }
+
+    fn overlaps(&self, other: &FileRange) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+
+    fn overlaps_or_adjoins(&self, other: &FileRange) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    fn merge(&mut self, other: &FileRange) {
+        self.start = min(self.start, other.start);
+        self.end = max(self.end, other.end);
+    }
+}
+
+/// A POSIX record lock, which is owned by a process.
+#[derive(Debug, Copy, Clone)]
+pub struct RangeLock {
+    type_: FlockType,
+    range: FileRange,
+    owner: pid_t,
+}
+
+impl RangeLock {
+    pub fn new(type_: FlockType, range: FileRange, owner: pid_t) -> Self {
+        Self {
+            type_,
+            range,
+            owner,
+        }
+    }
+
+    pub fn type_(&self) -> FlockType {
+        self.type_
+    }
+
+    pub fn range(&self) -> &FileRange {
+        &self.range
+    }
+
+    pub fn owner(&self) -> pid_t {
+        self.owner
+    }
+
+    fn conflicts_with(&self, other: &RangeLock) -> bool {
+        if self.owner == other.owner || !self.range.overlaps(&other.range) {
+            return false;
+        }
+        self.type_ == FlockType::F_WRLCK || other.type_ == FlockType::F_WRLCK
+    }
+}
+
+/// The POSIX record locks of all files.
+pub struct RangeLockTable {
+    locks: HashMap<LockKey, Vec<RangeLock>>,
+    /// The threads that are blocked by conflicting locks, as a map from the tid
+    /// of a blocked thread to its process and the process owning the conflicting lock.
+    blocked_threads: HashMap<pid_t, (pid_t, pid_t)>,
+}
+
+impl RangeLockTable {
+    fn new() -> Self {
+        Self {
+            locks: HashMap::new(),
+            blocked_threads: HashMap::new(),
+        }
+    }
+
+    /// Test whether the lock could be placed on the file.
+    ///
+    /// If there is a conflicting lock, the lock is overwritten by it. Otherwise,
+    /// the type of the lock is set to `F_UNLCK`.
+    pub fn test_lock(inode: &dyn INode, lock: &mut RangeLock) -> Result<()> {
+        let key = LockKey::from_inode(inode)?;
+        let table = RANGE_LOCK_TABLE.lock().unwrap();
+        match table.find_conflicting_lock(&key, lock) {
+            Some(conflicting_lock) => *lock = conflicting_lock,
+            None => lock.type_ = FlockType::F_UNLCK,
+        }
+        Ok(())
+    }
+
+    /// Acquire or release a lock on the file.
+    ///
+    /// If a conflicting lock is held by another process, `EAGAIN` is returned
+    /// when `is_nonblocking` is true. Otherwise, the current thread blocks until
+    /// the conflicting lock is released, unless waiting for it would cause a
+    /// deadlock, in which case `EDEADLK` is returned.
+    pub fn set_lock(inode: &dyn INode, lock: &RangeLock, is_nonblocking: bool) -> Result<()> {
+        let key = LockKey::from_inode(inode)?;
+        let mut waiter = None;
+        loop {
+            let mut table = RANGE_LOCK_TABLE.lock().unwrap();
+            let blocker = match table.find_conflicting_lock(&key, lock) {
+                Some(conflicting_lock) => conflicting_lock.owner,
+                None => {
+                    table.insert_lock(key, lock);
+                    drop(table);
+                    // Unlocking or downgrading a lock may unblock others
+                    RANGE_LOCK_WAITERS.dequeue_and_wake_all();
+                    return Ok(());
+                }
+            };
+            if is_nonblocking {
+                return_errno!(EAGAIN, "the lock is held by another process");
+            }
+            if table.would_deadlock(lock.owner, blocker) {
+                return_errno!(EDEADLK, "waiting for the lock would cause a deadlock");
+            }
+
+            // Enqueue the waiter before releasing the table, so that a wakeup
+            // by the process holding the conflicting lock cannot be missed
+            let waiter = waiter.get_or_insert_with(Waiter::new);
+            RANGE_LOCK_WAITERS.reset_and_enqueue(waiter);
+            let tid = current!().tid();
+            table.blocked_threads.insert(tid, (lock.owner, blocker));
+            drop(table);
+
+            let result = waiter.wait(None);
+            RANGE_LOCK_TABLE
+                .lock()
+                .unwrap()
+                .blocked_threads
+                .remove(&tid);
+            result?;
+        }
+    }
+
+    /// Release all the locks of the process on the file.
+    ///
+    /// This should be called when the process closes any of its file
+    /// descriptors that refer to the file.
+    pub fn release_locks(inode: &dyn INode, owner: pid_t) {
+        let mut table = RANGE_LOCK_TABLE.lock().unwrap();
+        if table.locks.is_empty() {
+            return;
+        }
+        let key = match LockKey::from_inode(inode) {
+            Ok(key) => key,
+            Err(_) => return,
+        };
+        if table.remove_locks(&key, owner) {
+            drop(table);
+            RANGE_LOCK_WAITERS.dequeue_and_wake_all();
+        }
+    }
+
+    /// Release all the locks of the process on all files.
+    ///
+    /// This should be called when the process exits.
+    pub fn release_all_locks(owner: pid_t) {
+        let mut table = RANGE_LOCK_TABLE.lock().unwrap();
+        let keys: Vec<LockKey> = table.locks.keys().cloned().collect();
+        let mut is_released = false;
+        for key in keys.iter() {
+            is_released |= table.remove_locks(key, owner);
+        }
+        if is_released {
+            drop(table);
+            RANGE_LOCK_WAITERS.dequeue_and_wake_all();
+        }
+    }
+
+    /// Transfer all the locks of the process to another one.
+    ///
+    /// This should be called when the process is replaced by a new process on
+    /// exec, which keeps the locks of the process.
+    pub fn transfer_all_locks(owner: pid_t, new_owner: pid_t) {
+        let mut table = RANGE_LOCK_TABLE.lock().unwrap();
+        for lock in table.locks.values_mut().flat_map(|locks| locks.iter_mut()) {
+            if lock.owner == owner {
+                lock.owner = new_owner;
+            }
+        }
+    }
+
+    fn find_conflicting_lock(&self, key: &LockKey, lock: &RangeLock) -> Option<RangeLock> {
+        self.locks
+            .get(key)?
+            .iter()
+            .find(|existing_lock| existing_lock.conflicts_with(lock))
+            .cloned()
+    }
+
+    /// Replace the locks of the owner in the range of the new lock with the new
+    /// lock, merging it with the adjoining locks of the same type.
+    fn insert_lock(&mut self, key: LockKey, lock: &RangeLock) {
+        let locks = self.locks.entry(key).or_insert_with(Vec::new);
+        let mut new_lock = *lock;
+        let mut new_locks = Vec::with_capacity(locks.len() + 2);
+        for existing_lock in locks.drain(..) {
+            if existing_lock.owner != lock.owner
+                || !existing_lock.range.overlaps_or_adjoins(&lock.range)
+            {
+                new_locks.push(existing_lock);
+                continue;
+            }
+            if existing_lock.type_ == lock.type_ {
+                new_lock.range.merge(&existing_lock.range);
+                continue;
+            }
+            // Keep the parts of the existing lock outside the range of the new lock
+            if existing_lock.range.start < lock.range.start {
+                let mut head = existing_lock;
+                head.range.end = lock.range.start;
+                new_locks.push(head);
+            }
+            if existing_lock.range.end > lock.range.end {
+                let mut tail = existing_lock;
+                tail.range.start = lock.range.end;
+                new_locks.push(tail);
+            }
+        }
+        if new_lock.type_ != FlockType::F_UNLCK {
+            new_locks.push(new_lock);
+        }
+
+        if new_locks.is_empty() {
+            self.locks.remove(&key);
+        } else {
+            *locks = new_locks;
+        }
+    }
+
+    /// Remove all the locks of the owner on the file, returning whether any
+    /// lock is removed.
+    fn remove_locks(&mut self, key: &LockKey, owner: pid_t) -> bool {
+        let locks = match self.locks.get_mut(key) {
+            Some(locks) => locks,
+            None => return false,
+        };
+        let num_locks = locks.len();
+        locks.retain(|lock| lock.owner != owner);
+        let is_removed = locks.len() < num_locks;
+        if locks.is_empty() {
+            self.locks.remove(key);
+        }
+        is_removed
+    }
+
+    /// Check whether the owner waiting for the blocker would cause a deadlock,
+    /// i.e., whether the blocker is (transitively) waiting for the owner.
+    fn would_deadlock(&self, owner: pid_t, blocker: pid_t) -> bool {
+        let mut blocker = blocker;
+        for _ in 0..MAX_DEADLOCK_ITERATIONS {
+            if blocker == owner {
+                return true;
+            }
+            blocker = match self
+                .blocked_threads
+                .values()
+                .find(|(blocked_process, _)| *blocked_process == blocker)
+            {
+                Some((_, next_blocker)) => *next_blocker,
+                None => return false,
+            };
+        }
+        false
+    }
+}
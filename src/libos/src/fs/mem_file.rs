@@ -0,0 +1,261 @@
+use super::*;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rcore_fs_ramfs::RamFS;
+
+lazy_static! {
+    /// An internal, unmounted RamFS that holds the inodes of all memfds.
+    static ref MEMFD_FS: Arc<RamFS> = RamFS::new();
+}
+
+/// The maximum length of the name of a memfd, excluding the terminating null byte.
+const MEMFD_NAME_MAX: usize = 249;
+
+/// An anonymous file that lives in memory, i.e., a memfd.
+///
+/// A memfd is backed by an inode of an internal RamFS that is not reachable
+/// through any path. Besides the usual file operations, a memfd supports
+/// file seals, which restrict the operations that are allowed on the file.
+pub struct MemFile {
+    inode_file: INodeFile,
+    seals: SgxMutex<FileSeals>,
+}
+
+impl MemFile {
+    pub fn new(name: &str, flags: MemFileFlags) -> Result<Self> {
+        if name.len() > MEMFD_NAME_MAX {
+            return_errno!(EINVAL, "the name is too long");
+        }
+        if flags.contains(MemFileFlags::MFD_HUGETLB) {
+            return_errno!(EINVAL, "huge pages are not supported");
+        }
+
+        let inode = {
+            // Create the inode with a unique name and then unlink it, so that
+            // the inode is only reachable through the memfd
+            static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+            let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+            let entry_name = format!("memfd-{}", id);
+            let root_inode = MEMFD_FS.root_inode();
+            let inode = root_inode.create(&entry_name, FileType::File, 0o777)?;
+            root_inode.unlink(&entry_name)?;
+            inode
+        };
+        let abs_path = format!("/memfd:{} (deleted)", name);
+        let inode_file = INodeFile::open(inode, &abs_path, AccessMode::O_RDWR as u32)?;
+
+        // A memfd created without MFD_ALLOW_SEALING cannot be sealed
+        let seals = if flags.contains(MemFileFlags::MFD_ALLOW_SEALING) {
+            FileSeals::empty()
+        } else {
+            FileSeals::F_SEAL_SEAL
+        };
+        Ok(Self {
+            inode_file,
+            seals: SgxMutex::new(seals),
+        })
+    }
+
+    pub fn abs_path(&self) -> &str {
+        self.inode_file.abs_path()
+    }
+
+    pub fn inode(&self) -> &Arc<dyn INode> {
+        self.inode_file.inode()
+    }
+
+    pub fn seals(&self) -> FileSeals {
+        *self.seals.lock().unwrap()
+    }
+
+    /// Add new seals to the file.
+    ///
+    /// The caller is responsible for checking that there are no shared,
+    /// writable memory mappings of the file when adding F_SEAL_WRITE.
+    pub fn add_seals(&self, new_seals: FileSeals) -> Result<()> {
+        let mut seals = self.seals.lock().unwrap();
+        if seals.contains(FileSeals::F_SEAL_SEAL) {
+            return_errno!(EPERM, "the file is sealed against adding seals");
+        }
+        *seals |= new_seals;
+        Ok(())
+    }
+
+    /// Check whether the given seals prohibit writing `len` bytes at `offset`.
+    fn check_write(&self, seals: FileSeals, offset: usize, len: usize) -> Result<()> {
+        if seals.intersects(FileSeals::F_SEAL_WRITE | FileSeals::F_SEAL_FUTURE_WRITE) {
+            return_errno!(EPERM, "the file is sealed against writing");
+        }
+        if seals.contains(FileSeals::F_SEAL_GROW) {
+            let file_size = self.inode_file.metadata()?.size;
+            if offset + len > file_size {
+                return_errno!(EPERM, "the file is sealed against growing");
+            }
+        }
+        Ok(())
+    }
+
+    fn current_offset(&self) -> Result<usize> {
+        let offset = if self.inode_file.status_flags()?.always_append() {
+            self.inode_file.metadata()?.size
+        } else {
+            self.inode_file.seek(SeekFrom::Current(0))? as usize
+        };
+        Ok(offset)
+    }
+}
+
+bitflags! {
+    pub struct MemFileFlags: u32 {
+        /// Close on exec
+        const MFD_CLOEXEC       = 0x0001;
+        /// Allow sealing operations on the file
+        const MFD_ALLOW_SEALING = 0x0002;
+        /// Create the file in the hugetlbfs
+        const MFD_HUGETLB       = 0x0004;
+    }
+}
+
+bitflags! {
+    pub struct FileSeals: u32 {
+        /// Prevent further seals from being set
+        const F_SEAL_SEAL           = 0x0001;
+        /// Prevent the file from shrinking
+        const F_SEAL_SHRINK         = 0x0002;
+        /// Prevent the file from growing
+        const F_SEAL_GROW           = 0x0004;
+        /// Prevent writes
+        const F_SEAL_WRITE          = 0x0008;
+        /// Prevent future writes while mapped
+        const F_SEAL_FUTURE_WRITE   = 0x0010;
+    }
+}
+
+impl File for MemFile {
+    fn read(&self, buf: &mut [u8]) -> Result<usize> {
+        self.inode_file.read(buf)
+    }
+
+    fn write(&self, buf: &[u8]) -> Result<usize> {
+        let seals = self.seals.lock().unwrap();
+        self.check_write(*seals, self.current_offset()?, buf.len())?;
+        self.inode_file.write(buf)
+    }
+
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize> {
+        self.inode_file.read_at(offset, buf)
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> Result<usize> {
+        let seals = self.seals.lock().unwrap();
+        self.check_write(*seals, offset, buf.len())?;
+        self.inode_file.write_at(offset, buf)
+    }
+
+    fn readv(&self, bufs: &mut [&mut [u8]]) -> Result<usize> {
+        self.inode_file.readv(bufs)
+    }
+
+    fn writev(&self, bufs: &[&[u8]]) -> Result<usize> {
+        let seals = self.seals.lock().unwrap();
+        let total_len = bufs.iter().map(|buf| buf.len()).sum();
+        self.check_write(*seals, self.current_offset()?, total_len)?;
+        self.inode_file.writev(bufs)
+    }
+
+    fn seek(&self, pos: SeekFrom) -> Result<off_t> {
+        self.inode_file.seek(pos)
+    }
+
+    fn metadata(&self) -> Result<Metadata> {
+        self.inode_file.metadata()
+    }
+
+    fn set_metadata(&self, metadata: &Metadata) -> Result<()> {
+        self.inode_file.set_metadata(metadata)
+    }
+
+    fn set_len(&self, len: u64) -> Result<()> {
+        let seals = self.seals.lock().unwrap();
+        let file_size = self.inode_file.metadata()?.size as u64;
+        if len < file_size && seals.contains(FileSeals::F_SEAL_SHRINK) {
+            return_errno!(EPERM, "the file is sealed against shrinking");
+        }
+        if len > file_size && seals.contains(FileSeals::F_SEAL_GROW) {
+            return_errno!(EPERM, "the file is sealed against growing");
+        }
+        self.inode_file.set_len(len)
+    }
+
+    fn fallocate(&self, mode: u32, offset: u64, len: u64) -> Result<()> {
+        let seals = self.seals.lock().unwrap();
+        if seals.intersects(FileSeals::F_SEAL_WRITE | FileSeals::F_SEAL_FUTURE_WRITE) {
+            return_errno!(EPERM, "the file is sealed against writing");
+        }
+        if seals.contains(FileSeals::F_SEAL_GROW) {
+            let file_size = self.inode_file.metadata()?.size as u64;
+            if offset + len > file_size {
+                return_errno!(EPERM, "the file is sealed against growing");
+            }
+        }
+        self.inode_file.fallocate(mode, offset, len)
+    }
+
+    fn access_mode(&self) -> Result<AccessMode> {
+        self.inode_file.access_mode()
+    }
+
+    fn status_flags(&self) -> Result<StatusFlags> {
+        self.inode_file.status_flags()
+    }
+
+    fn set_status_flags(&self, new_status_flags: StatusFlags) -> Result<()> {
+        self.inode_file.set_status_flags(new_status_flags)
+    }
+
+    fn test_advisory_lock(&self, lock: &mut Flock) -> Result<()> {
+        self.inode_file.test_advisory_lock(lock)
+    }
+
+    fn set_advisory_lock(&self, lock: &Flock, is_nonblocking: bool) -> Result<()> {
+        self.inode_file.set_advisory_lock(lock, is_nonblocking)
+    }
+
+    fn flock(&self, ops: FlockOps) -> Result<()> {
+        self.inode_file.flock(ops)
+    }
+
+    fn release_advisory_locks(&self) {
+        self.inode_file.release_advisory_locks()
+    }
+
+    fn poll_new(&self) -> IoEvents {
+        self.inode_file.poll_new()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Debug for MemFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MemFile")
+            .field("abs_path", &self.abs_path())
+            .field("seals", &self.seals())
+            .finish()
+    }
+}
+
+pub trait AsMemFile {
+    fn as_mem_file(&self) -> Result<&MemFile>;
+}
+
+impl AsMemFile for FileRef {
+    fn as_mem_file(&self) -> Result<&MemFile> {
+        self.as_any()
+            .downcast_ref::<MemFile>()
+            .ok_or_else(|| errno!(EINVAL, "not a memfd"))
+    }
+}
@@ -12,22 +12,37 @@ use untrusted::{SliceAsMutPtrAndLen, SliceAsPtrAndLen};
 
 use crate::config::ConfigMount;
 
+pub use self::aio::{aio_context_t, io_event_t, iocb_t, AioContexts};
+pub use self::dev_fs::{ShmFS, SHM_FS};
 pub use self::event_file::{AsEvent, EventCreationFlags, EventFile};
 pub use self::events::{AtomicIoEvents, IoEvents, IoNotifier};
 pub use self::file::{File, FileRef};
 pub use self::file_ops::{
-    occlum_ocall_ioctl, AccessMode, BuiltinIoctlNum, CreationFlags, FileMode, Flock, FlockType,
-    IfConf, IoctlCmd, Stat, StatusFlags, StructuredIoctlArgType, StructuredIoctlNum,
+    occlum_ocall_ioctl, AccessMode, AccessibilityCheckMode, BuiltinIoctlNum, CreationFlags,
+    FileMode, Flock, FlockType, FlockWhence, FsPath, IfConf, IfReq, IoctlCmd, OpenHow,
+    ResolveFlags, Stat, StatusFlags, StructuredIoctlArgType, StructuredIoctlNum,
 };
 pub use self::file_table::{FileDesc, FileTable, FileTableEvent, FileTableNotifier};
+pub use self::fs_ops::Statfs;
 pub use self::fs_view::FsView;
 pub use self::host_fd::HostFd;
 pub use self::inode_file::{AsINodeFile, INodeExt, INodeFile};
+pub use self::inotify::{AsInotifyFile, InotifyFile, InotifyFlags, InotifyMask};
+pub use self::io_uring::{io_uring_params_t, AsIoUring, IoUringFile};
+pub use self::locks::{FileRange, FlockOps, FlockTable, RangeLock, RangeLockTable};
+pub use self::mem_file::{AsMemFile, FileSeals, MemFile, MemFileFlags};
 pub use self::pipe::PipeType;
+pub use self::posix_acl::{PosixAcl, PosixAclType};
 pub use self::rootfs::ROOT_INODE;
-pub use self::stdio::{HostStdioFds, StdinFile, StdoutFile};
+pub use self::stdio::{HostStdioFds, StdinFile, StdoutFile, Terminal};
 pub use self::syscalls::*;
+pub use self::timer_file::{AsTimer, TimerCreationFlags, TimerFile, TimerSetFlags};
+pub use self::writeback::do_writeback;
+pub use self::xattr::{XattrFlags, XattrNamespace, XattrTable};
 
+mod aio;
+mod async_host_io;
+mod bindfs;
 pub mod channel;
 mod dev_fs;
 mod event_file;
@@ -40,12 +55,22 @@ mod fs_view;
 mod host_fd;
 mod hostfs;
 mod inode_file;
+mod inotify;
+mod io_uring;
+mod locks;
+mod mem_file;
 mod pipe;
+mod posix_acl;
 mod procfs;
 mod rootfs;
 mod sefs;
 mod stdio;
 mod syscalls;
+mod sysfs;
+mod timer_file;
+mod unionfs;
+mod writeback;
+mod xattr;
 
 /// Split a `path` str to `(base_path, file_name)`
 fn split_path(path: &str) -> (&str, &str) {
@@ -1,15 +1,22 @@
 use atomic::{Atomic, Ordering};
+use std::sync::atomic::AtomicUsize;
 
 use super::channel::{Channel, Consumer, Producer};
 use super::*;
+use crate::vm::PAGE_SIZE;
 use net::PollEventFlags;
 
-// TODO: Add F_SETPIPE_SZ in fcntl to dynamically change the size of pipe
-// to improve memory efficiency. This value is got from /proc/sys/fs/pipe-max-size on linux.
-pub const PIPE_BUF_SIZE: usize = 1024 * 1024;
+/// The default capacity of a pipe, which is the default value of
+/// /proc/sys/fs/pipe-max-size on Linux. A pipe can be resized by F_SETPIPE_SZ.
+pub const PIPE_DEFAULT_SIZE: usize = 1024 * 1024;
+
+/// The max capacity of a pipe set by F_SETPIPE_SZ. Unlike Linux, it cannot be
+/// exceeded even by root, as the buffer of a pipe is in the enclave memory.
+pub const PIPE_MAX_SIZE: usize = 16 * 1024 * 1024;
 
 pub fn pipe(flags: StatusFlags) -> Result<(PipeReader, PipeWriter)> {
-    let (producer, consumer) = Channel::new(PIPE_BUF_SIZE)?.split();
+    let (producer, consumer) = Channel::new(PIPE_DEFAULT_SIZE)?.split();
+    let ino = alloc_pipe_ino();
 
     // Only O_NONBLOCK and O_DIRECT can be applied during pipe creation
     let valid_flags = flags & (StatusFlags::O_NONBLOCK | StatusFlags::O_DIRECT);
@@ -22,17 +29,68 @@ pub fn pipe(flags: StatusFlags) -> Result<(PipeReader, PipeWriter)> {
         PipeReader {
             consumer: consumer,
             status_flags: Atomic::new(valid_flags),
+            ino,
         },
         PipeWriter {
             producer: producer,
             status_flags: Atomic::new(valid_flags),
+            ino,
         },
     ))
 }
 
+/// Round up the capacity given by F_SETPIPE_SZ, same as Linux.
+fn round_pipe_size(size: usize) -> Result<usize> {
+    if size > PIPE_MAX_SIZE {
+        return_errno!(EPERM, "the size exceeds the max pipe size");
+    }
+    Ok(size.max(PAGE_SIZE).next_power_of_two())
+}
+
+/// Allocate an inode number for a new pipe, which is shared by both ends of
+/// the pipe and shown in procfs, e.g., `/proc/[pid]/fd`.
+fn alloc_pipe_ino() -> usize {
+    static NEXT_PIPE_INO: AtomicUsize = AtomicUsize::new(1);
+    NEXT_PIPE_INO.fetch_add(1, Ordering::Relaxed)
+}
+
 pub struct PipeReader {
     consumer: Consumer<u8>,
     status_flags: Atomic<StatusFlags>,
+    ino: usize,
+}
+
+impl PipeReader {
+    /// Copy data from the pipe without consuming it.
+    pub fn peek(&self, buf: &mut [u8]) -> Result<usize> {
+        self.consumer.peek_slice(buf)
+    }
+
+    /// Consume at most `count` bytes of data, returning the number of consumed bytes.
+    pub fn consume(&self, count: usize) -> usize {
+        self.consumer.skip(count)
+    }
+
+    pub fn ino(&self) -> usize {
+        self.ino
+    }
+
+    /// Returns the capacity of the pipe.
+    pub fn capacity(&self) -> usize {
+        self.consumer.capacity()
+    }
+
+    /// Set the capacity of the pipe, returning the actual capacity.
+    pub fn set_capacity(&self, size: usize) -> Result<usize> {
+        let capacity = round_pipe_size(size)?;
+        self.consumer.resize(capacity)?;
+        Ok(capacity)
+    }
+
+    /// Returns whether the reader and the writer belong to the same pipe.
+    pub fn is_reader_of(&self, writer: &PipeWriter) -> bool {
+        self.consumer.is_peer_of(&writer.producer)
+    }
 }
 
 impl File for PipeReader {
@@ -44,6 +102,16 @@ impl File for PipeReader {
         self.consumer.pop_slices(bufs)
     }
 
+    fn ioctl(&self, cmd: &mut IoctlCmd) -> Result<i32> {
+        match cmd {
+            IoctlCmd::FIONREAD(arg) => {
+                **arg = self.consumer.items_to_consume().min(std::i32::MAX as usize) as i32;
+                Ok(0)
+            }
+            _ => return_errno!(EINVAL, "unknown ioctl cmd for pipe"),
+        }
+    }
+
     fn access_mode(&self) -> Result<AccessMode> {
         Ok(AccessMode::O_RDONLY)
     }
@@ -92,6 +160,30 @@ impl File for PipeReader {
 pub struct PipeWriter {
     producer: Producer<u8>,
     status_flags: Atomic<StatusFlags>,
+    ino: usize,
+}
+
+impl PipeWriter {
+    pub fn ino(&self) -> usize {
+        self.ino
+    }
+
+    /// Returns the capacity of the pipe.
+    pub fn capacity(&self) -> usize {
+        self.producer.capacity()
+    }
+
+    /// Set the capacity of the pipe, returning the actual capacity.
+    pub fn set_capacity(&self, size: usize) -> Result<usize> {
+        let capacity = round_pipe_size(size)?;
+        self.producer.resize(capacity)?;
+        Ok(capacity)
+    }
+
+    /// Returns the number of bytes that can be written without blocking.
+    pub fn free_space(&self) -> usize {
+        self.producer.free_space()
+    }
 }
 
 impl File for PipeWriter {
@@ -180,8 +272,14 @@ pub fn do_pipe2(flags: u32) -> Result<[FileDesc; 2]> {
     let close_on_spawn = creation_flags.must_close_on_spawn();
 
     let current = current!();
-    let reader_fd = current.add_file(Arc::new(pipe_reader), close_on_spawn);
-    let writer_fd = current.add_file(Arc::new(pipe_writer), close_on_spawn);
+    let reader_fd = current.add_file(Arc::new(pipe_reader), close_on_spawn)?;
+    let writer_fd = match current.add_file(Arc::new(pipe_writer), close_on_spawn) {
+        Ok(writer_fd) => writer_fd,
+        Err(e) => {
+            current.files().lock().unwrap().del(reader_fd)?;
+            return Err(e);
+        }
+    };
     trace!("pipe2: reader_fd: {}, writer_fd: {}", reader_fd, writer_fd);
     Ok([reader_fd, writer_fd])
 }
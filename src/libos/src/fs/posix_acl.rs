@@ -0,0 +1,375 @@
+//! POSIX access control lists (ACLs).
+//!
+//! An ACL grants permissions to specific users and groups in addition to the
+//! owner, the owning group, and others described by the permission bits. ACLs
+//! are stored as extended attributes in the same binary format as Linux:
+//!
+//! * `system.posix_acl_access` is the access ACL, which is enforced by the
+//! permission checks of the inode;
+//! * `system.posix_acl_default` is the default ACL of a directory, which is
+//! inherited by the files and directories created in the directory.
+//!
+//! An access ACL is kept in sync with the permission bits: setting the ACL
+//! updates the permission bits and vice versa (e.g., by chmod). An access ACL
+//! that is equivalent to the permission bits is not stored at all.
+
+use super::*;
+
+const ACL_ACCESS_XATTR: &str = "system.posix_acl_access";
+const ACL_DEFAULT_XATTR: &str = "system.posix_acl_default";
+
+const ACL_XATTR_VERSION: u32 = 0x0002;
+const ACL_UNDEFINED_ID: u32 = u32::max_value();
+const ACL_HEADER_SIZE: usize = 4;
+const ACL_ENTRY_SIZE: usize = 8;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PosixAclType {
+    Access,
+    Default,
+}
+
+impl PosixAclType {
+    pub fn from_xattr_name(name: &str) -> Option<Self> {
+        match name {
+            ACL_ACCESS_XATTR => Some(PosixAclType::Access),
+            ACL_DEFAULT_XATTR => Some(PosixAclType::Default),
+            _ => None,
+        }
+    }
+
+    fn xattr_name(&self) -> &'static str {
+        match self {
+            PosixAclType::Access => ACL_ACCESS_XATTR,
+            PosixAclType::Default => ACL_DEFAULT_XATTR,
+        }
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u16)]
+enum AclTag {
+    ACL_USER_OBJ = 0x01,
+    ACL_USER = 0x02,
+    ACL_GROUP_OBJ = 0x04,
+    ACL_GROUP = 0x08,
+    ACL_MASK = 0x10,
+    ACL_OTHER = 0x20,
+}
+
+impl AclTag {
+    fn from_u16(tag: u16) -> Result<Self> {
+        Ok(match tag {
+            0x01 => AclTag::ACL_USER_OBJ,
+            0x02 => AclTag::ACL_USER,
+            0x04 => AclTag::ACL_GROUP_OBJ,
+            0x08 => AclTag::ACL_GROUP,
+            0x10 => AclTag::ACL_MASK,
+            0x20 => AclTag::ACL_OTHER,
+            _ => return_errno!(EINVAL, "invalid ACL tag"),
+        })
+    }
+
+    fn has_id(&self) -> bool {
+        *self == AclTag::ACL_USER || *self == AclTag::ACL_GROUP
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct AclEntry {
+    tag: AclTag,
+    /// The permission bits, i.e., a combination of R_OK, W_OK and X_OK
+    perm: u16,
+    id: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct PosixAcl {
+    entries: Vec<AclEntry>,
+}
+
+impl PosixAcl {
+    /// Parse an ACL from the value of its extended attribute.
+    ///
+    /// Returns `None` if the ACL has no entries.
+    fn from_xattr(value: &[u8]) -> Result<Option<Self>> {
+        if value.len() < ACL_HEADER_SIZE || (value.len() - ACL_HEADER_SIZE) % ACL_ENTRY_SIZE != 0 {
+            return_errno!(EINVAL, "invalid size of ACL");
+        }
+        let read_u16 = |offset: usize| u16::from_le_bytes([value[offset], value[offset + 1]]);
+        let read_u32 = |offset: usize| {
+            let mut bytes = [0; 4];
+            bytes.copy_from_slice(&value[offset..offset + 4]);
+            u32::from_le_bytes(bytes)
+        };
+        if read_u32(0) != ACL_XATTR_VERSION {
+            return_errno!(EOPNOTSUPP, "unsupported version of ACL");
+        }
+
+        let mut entries = Vec::new();
+        for offset in (ACL_HEADER_SIZE..value.len()).step_by(ACL_ENTRY_SIZE) {
+            let tag = AclTag::from_u16(read_u16(offset))?;
+            let perm = read_u16(offset + 2);
+            if perm & !0o7 != 0 {
+                return_errno!(EINVAL, "invalid ACL permissions");
+            }
+            let id = if tag.has_id() {
+                read_u32(offset + 4)
+            } else {
+                ACL_UNDEFINED_ID
+            };
+            entries.push(AclEntry { tag, perm, id });
+        }
+        if entries.is_empty() {
+            return Ok(None);
+        }
+        let acl = Self { entries };
+        acl.validate()?;
+        Ok(Some(acl))
+    }
+
+    fn to_xattr(&self) -> Vec<u8> {
+        let mut value = Vec::with_capacity(ACL_HEADER_SIZE + self.entries.len() * ACL_ENTRY_SIZE);
+        value.extend_from_slice(&ACL_XATTR_VERSION.to_le_bytes());
+        for entry in self.entries.iter() {
+            value.extend_from_slice(&(entry.tag as u16).to_le_bytes());
+            value.extend_from_slice(&entry.perm.to_le_bytes());
+            value.extend_from_slice(&entry.id.to_le_bytes());
+        }
+        value
+    }
+
+    /// Check that the entries are sorted and that the required entries exist,
+    /// same as Linux.
+    fn validate(&self) -> Result<()> {
+        let count = |tag: AclTag| self.entries.iter().filter(|e| e.tag == tag).count();
+        if count(AclTag::ACL_USER_OBJ) != 1
+            || count(AclTag::ACL_GROUP_OBJ) != 1
+            || count(AclTag::ACL_OTHER) != 1
+            || count(AclTag::ACL_MASK) > 1
+        {
+            return_errno!(EINVAL, "invalid ACL entries");
+        }
+        let has_named_entries = self.entries.iter().any(|e| e.tag.has_id());
+        if has_named_entries && count(AclTag::ACL_MASK) == 0 {
+            return_errno!(EINVAL, "ACL with named entries must have a mask");
+        }
+        let is_sorted = self
+            .entries
+            .windows(2)
+            .all(|pair| (pair[0].tag, pair[0].id) < (pair[1].tag, pair[1].id));
+        if !is_sorted {
+            return_errno!(EINVAL, "ACL entries are not sorted or not unique");
+        }
+        Ok(())
+    }
+
+    fn entry_mut(&mut self, tag: AclTag) -> Option<&mut AclEntry> {
+        self.entries.iter_mut().find(|e| e.tag == tag)
+    }
+
+    fn perm_of(&self, tag: AclTag) -> Option<u16> {
+        self.entries.iter().find(|e| e.tag == tag).map(|e| e.perm)
+    }
+
+    /// Whether the ACL can be represented by the permission bits only.
+    fn is_equiv_mode(&self) -> bool {
+        self.entries.len() == 3
+    }
+
+    /// The permission bits that are equivalent to the ACL.
+    ///
+    /// The group class bits reflect the mask entry if there is one.
+    fn mode(&self) -> u16 {
+        let user_perm = self.perm_of(AclTag::ACL_USER_OBJ).unwrap();
+        let group_perm = self
+            .perm_of(AclTag::ACL_MASK)
+            .or_else(|| self.perm_of(AclTag::ACL_GROUP_OBJ))
+            .unwrap();
+        let other_perm = self.perm_of(AclTag::ACL_OTHER).unwrap();
+        (user_perm << 6) | (group_perm << 3) | other_perm
+    }
+
+    /// Update the ACL according to the new permission bits.
+    fn chmod(&mut self, mode: u16) {
+        self.entry_mut(AclTag::ACL_USER_OBJ).unwrap().perm = (mode >> 6) & 0o7;
+        let group_entry = if self.perm_of(AclTag::ACL_MASK).is_some() {
+            self.entry_mut(AclTag::ACL_MASK)
+        } else {
+            self.entry_mut(AclTag::ACL_GROUP_OBJ)
+        };
+        group_entry.unwrap().perm = (mode >> 3) & 0o7;
+        self.entry_mut(AclTag::ACL_OTHER).unwrap().perm = mode & 0o7;
+    }
+
+    /// Restrict the ACL inherited by a new file with the permission bits given
+    /// at creation, returning the resulting permission bits.
+    fn apply_create_mode(&mut self, mode: u16) -> u16 {
+        let has_mask = self.perm_of(AclTag::ACL_MASK).is_some();
+        let mut new_mode = mode & !0o777;
+        let mut mask_perm = |tag: AclTag, shift: u16| {
+            let perm = &mut self.entry_mut(tag).unwrap().perm;
+            *perm &= (mode >> shift) & 0o7;
+            new_mode |= *perm << shift;
+        };
+        mask_perm(AclTag::ACL_USER_OBJ, 6);
+        mask_perm(AclTag::ACL_OTHER, 0);
+        if has_mask {
+            mask_perm(AclTag::ACL_MASK, 3);
+        } else {
+            mask_perm(AclTag::ACL_GROUP_OBJ, 3);
+        }
+        new_mode
+    }
+
+    /// Check whether the ACL grants the requested permissions to the user,
+    /// following the access check algorithm of POSIX ACLs.
+    fn permits(&self, metadata: &Metadata, uid: uid_t, gid: uid_t, want: u16) -> bool {
+        let mask = self.perm_of(AclTag::ACL_MASK).unwrap_or(0o7);
+        let mut is_group_matched = false;
+        for entry in self.entries.iter() {
+            match entry.tag {
+                AclTag::ACL_USER_OBJ => {
+                    if metadata.uid == uid as usize {
+                        return entry.perm & want == want;
+                    }
+                }
+                AclTag::ACL_USER => {
+                    if entry.id == uid {
+                        return entry.perm & mask & want == want;
+                    }
+                }
+                AclTag::ACL_GROUP_OBJ | AclTag::ACL_GROUP => {
+                    let group_id = if entry.tag == AclTag::ACL_GROUP_OBJ {
+                        metadata.gid as u32
+                    } else {
+                        entry.id
+                    };
+                    if group_id == gid {
+                        is_group_matched = true;
+                        if entry.perm & want == want {
+                            return mask & want == want;
+                        }
+                    }
+                }
+                AclTag::ACL_MASK => {}
+                AclTag::ACL_OTHER => {
+                    return !is_group_matched && entry.perm & want == want;
+                }
+            }
+        }
+        false
+    }
+
+    /// Get the ACL of the inode, if any.
+    pub fn get(inode: &dyn INode, acl_type: PosixAclType) -> Result<Option<Self>> {
+        match XattrTable::get(inode, acl_type.xattr_name()) {
+            Ok(value) => Self::from_xattr(&value),
+            Err(e) if e.errno() == ENODATA => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Set the ACL of the inode from the value of its extended attribute.
+    pub fn set(
+        inode: &dyn INode,
+        acl_type: PosixAclType,
+        value: &[u8],
+        flags: XattrFlags,
+    ) -> Result<()> {
+        let mut metadata = inode.metadata()?;
+        if acl_type == PosixAclType::Default && metadata.type_ != FileType::Dir {
+            return_errno!(EACCES, "only directories have default ACLs");
+        }
+        let acl = match Self::from_xattr(value)? {
+            Some(acl) => acl,
+            None => return Self::remove(inode, acl_type),
+        };
+        if acl_type == PosixAclType::Access {
+            metadata.mode = (metadata.mode & !0o777) | acl.mode();
+            inode.set_metadata(&metadata)?;
+            if acl.is_equiv_mode() {
+                return Self::remove(inode, acl_type);
+            }
+        }
+        XattrTable::set(inode, acl_type.xattr_name(), &acl.to_xattr(), flags)
+    }
+
+    pub fn remove(inode: &dyn INode, acl_type: PosixAclType) -> Result<()> {
+        match XattrTable::remove(inode, acl_type.xattr_name()) {
+            Err(e) if e.errno() != ENODATA => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Update the access ACL of the inode after its permission bits are changed.
+    pub fn chmod_inode(inode: &dyn INode, mode: u16) -> Result<()> {
+        if let Some(mut acl) = Self::get(inode, PosixAclType::Access)? {
+            acl.chmod(mode);
+            XattrTable::set(
+                inode,
+                ACL_ACCESS_XATTR,
+                &acl.to_xattr(),
+                XattrFlags::empty(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Check whether the current process is granted the requested permissions
+    /// by the access ACL of the inode.
+    ///
+    /// Returns `None` if the inode has no access ACL, in which case the
+    /// permission bits should be checked instead.
+    pub fn check_access(inode: &dyn INode, want: AccessibilityCheckMode) -> Result<Option<bool>> {
+        let acl = match Self::get(inode, PosixAclType::Access)? {
+            Some(acl) => acl,
+            None => return Ok(None),
+        };
+        let metadata = inode.metadata()?;
+        // The processes in LibOS always run as root, see getuid and getgid
+        let (uid, gid) = (0, 0);
+        Ok(Some(acl.permits(&metadata, uid, gid, want.bits() as u16)))
+    }
+}
+
+/// Create an inode in the directory.
+///
+/// If the directory has a default ACL, the new inode inherits it. Otherwise,
+/// the umask is applied to the permission bits of the new inode.
+pub fn create_inode_with_acl(
+    dir_inode: &dyn INode,
+    name: &str,
+    type_: FileType,
+    mode: u32,
+    umask: u16,
+) -> Result<Arc<dyn INode>> {
+    let default_acl = match PosixAcl::get(dir_inode, PosixAclType::Default)? {
+        Some(default_acl) => default_acl,
+        None => return Ok(dir_inode.create(name, type_, mode & !(umask as u32))?),
+    };
+
+    let inode = dir_inode.create(name, type_, mode)?;
+    let mut access_acl = default_acl.clone();
+    let mut metadata = inode.metadata()?;
+    metadata.mode = access_acl.apply_create_mode(metadata.mode);
+    inode.set_metadata(&metadata)?;
+    if !access_acl.is_equiv_mode() {
+        XattrTable::set(
+            inode.as_ref(),
+            ACL_ACCESS_XATTR,
+            &access_acl.to_xattr(),
+            XattrFlags::empty(),
+        )?;
+    }
+    if type_ == FileType::Dir {
+        XattrTable::set(
+            inode.as_ref(),
+            ACL_DEFAULT_XATTR,
+            &default_acl.to_xattr(),
+            XattrFlags::empty(),
+        )?;
+    }
+    Ok(inode)
+}
@@ -1,4 +1,5 @@
 use super::*;
+use crate::sched::{AVAIL_CPUSET, NCORES};
 use std::untrusted::fs;
 
 pub struct CpuInfoINode;
@@ -16,11 +17,58 @@ impl ProcINode for CpuInfoINode {
 }
 
 lazy_static! {
-    static ref CPUINFO: Vec<u8> = get_untrusted_cpuinfo().unwrap();
+    static ref CPUINFO: Vec<u8> = {
+        let cpus = AVAIL_CPUSET.cpus();
+        let cpuinfo = get_untrusted_cpuinfo()
+            .and_then(|untrusted_cpuinfo| sanitize_cpuinfo(&untrusted_cpuinfo, &cpus))
+            .unwrap_or_else(|e| {
+                warn!("failed to get the cpuinfo of host: {:?}", e);
+                cpus.iter()
+                    .map(|processor| format!("processor\t: {}\n\n", processor))
+                    .collect()
+            });
+        cpuinfo.into_bytes()
+    };
 }
 
-fn get_untrusted_cpuinfo() -> Result<Vec<u8>> {
-    let cpuinfo = fs::read_to_string("/proc/cpuinfo")?.into_bytes();
-    // TODO: do sanity check ?
+fn get_untrusted_cpuinfo() -> Result<String> {
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo")?;
+    Ok(cpuinfo)
+}
+
+/// Keep the info of the CPU cores available to the enclave, i.e., the cores
+/// of the host limited by `resource_limits.cpu_set` in Occlum.json, which is
+/// consistent with the CPU cores reported by sched_getaffinity.
+///
+/// The info of each CPU core is a block of "key : value" lines, and the
+/// blocks are separated by empty lines.
+fn sanitize_cpuinfo(untrusted_cpuinfo: &str, cpus: &[usize]) -> Result<String> {
+    let blocks: Vec<&str> = untrusted_cpuinfo
+        .split("\n\n")
+        .filter(|block| !block.trim().is_empty())
+        .collect();
+    if blocks.len() < *NCORES {
+        return_errno!(
+            EINVAL,
+            "the cpuinfo of host does not match the number of cores"
+        );
+    }
+
+    let mut cpuinfo = String::new();
+    for &processor in cpus {
+        for line in blocks[processor].lines() {
+            let key = line.split(':').next().unwrap_or("").trim();
+            match key {
+                "processor" => cpuinfo.push_str(&format!("processor\t: {}\n", processor)),
+                "siblings" => cpuinfo.push_str(&format!("siblings\t: {}\n", cpus.len())),
+                "cpu cores" => cpuinfo.push_str(&format!("cpu cores\t: {}\n", cpus.len())),
+                _ => {
+                    cpuinfo.push_str(line);
+                    cpuinfo.push('\n');
+                }
+            }
+        }
+        cpuinfo.push('\n');
+    }
     Ok(cpuinfo)
 }
@@ -1,4 +1,5 @@
 use super::*;
+use crate::fs::sefs::{PAGE_CACHE, PAGE_SIZE};
 use crate::vm::USER_SPACE_VM_MANAGER;
 
 pub struct MemInfoINode;
@@ -13,15 +14,46 @@ impl MemInfoINode {
 
 impl ProcINode for MemInfoINode {
     fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        // The memory available to the user space is the user_space_size
+        // configured in Occlum.json. There is no swap or huge pages in the
+        // enclave. The page cache of SEFS is in the kernel space.
         let total_ram = USER_SPACE_VM_MANAGER.get_total_size();
         let free_ram = USER_SPACE_VM_MANAGER.get_free_size();
+        let used_ram = total_ram - free_ram;
+        let cached = PAGE_CACHE.num_pages() * PAGE_SIZE;
         Ok(format!(
-            "MemTotal:       {} kB\n\
-             MemFree:        {} kB\n\
-             MemAvailable:   {} kB\n",
+            "MemTotal:       {:8} kB\n\
+             MemFree:        {:8} kB\n\
+             MemAvailable:   {:8} kB\n\
+             Buffers:        {:8} kB\n\
+             Cached:         {:8} kB\n\
+             SwapCached:     {:8} kB\n\
+             Active:         {:8} kB\n\
+             Inactive:       {:8} kB\n\
+             SwapTotal:      {:8} kB\n\
+             SwapFree:       {:8} kB\n\
+             Shmem:          {:8} kB\n\
+             CommitLimit:    {:8} kB\n\
+             Committed_AS:   {:8} kB\n\
+             HugePages_Total:   {:5}\n\
+             HugePages_Free:    {:5}\n\
+             Hugepagesize:   {:8} kB\n",
             total_ram / KB,
             free_ram / KB,
             free_ram / KB,
+            0,
+            cached / KB,
+            0,
+            used_ram / KB,
+            0,
+            0,
+            0,
+            0,
+            total_ram / KB,
+            used_ram / KB,
+            0,
+            0,
+            2048,
         )
         .into_bytes())
     }
@@ -6,15 +6,21 @@ use crate::process::pid_t;
 
 use self::cpuinfo_inode::CpuInfoINode;
 use self::meminfo_inode::MemInfoINode;
+use self::net_inode::LockedNetDirINode;
 use self::pid_inode::LockedPidDirINode;
-use self::proc_inode::{Dir, DirProcINode, File, ProcINode, SymLink};
+pub(super) use self::proc_inode::{Dir, DirProcINode, File, ProcINode, SymLink};
+use self::sefs_page_cache_inode::SefsPageCacheINode;
 use self::self_inode::SelfSymINode;
+use self::sgx_inode::LockedSgxDirINode;
 
 mod cpuinfo_inode;
 mod meminfo_inode;
+mod net_inode;
 mod pid_inode;
 mod proc_inode;
+mod sefs_page_cache_inode;
 mod self_inode;
+mod sgx_inode;
 
 /// Proc file system
 pub struct ProcFS {
@@ -90,7 +96,8 @@ impl LockedProcRootINode {
         let mut file = self.0.write().unwrap();
         file.this = Arc::downgrade(&fs.root);
         file.parent = Arc::downgrade(&fs.root);
-        // Currently, we only init the 'cpuinfo', 'meminfo' and 'self' entry.
+        // Currently, we only init the 'cpuinfo', 'meminfo', 'net', 'self', 'sgx' and
+        // 'sefs_page_cache' entry.
         // TODO: Add more entries for root.
         // All [pid] entries are lazy-initialized at the find() step.
         let cpuinfo_inode = CpuInfoINode::new();
@@ -99,9 +106,19 @@ impl LockedProcRootINode {
         let meminfo_inode = MemInfoINode::new();
         file.non_volatile_entries
             .insert(String::from("meminfo"), meminfo_inode);
+        let root_inode = Arc::clone(&fs.root) as Arc<dyn INode>;
+        let net_inode = LockedNetDirINode::new(&root_inode);
+        file.non_volatile_entries
+            .insert(String::from("net"), net_inode);
         let self_inode = SelfSymINode::new();
         file.non_volatile_entries
             .insert(String::from("self"), self_inode);
+        let sgx_inode = LockedSgxDirINode::new(&root_inode);
+        file.non_volatile_entries
+            .insert(String::from("sgx"), sgx_inode);
+        let sefs_page_cache_inode = SefsPageCacheINode::new();
+        file.non_volatile_entries
+            .insert(String::from("sefs_page_cache"), sefs_page_cache_inode);
     }
 }
 
@@ -0,0 +1,282 @@
+use super::*;
+use crate::net::{
+    AddressFamily, AsUnixDatagram, AsUnixSocket, HostSocketType, SockAddr, SocketType, UnixAddr,
+};
+use crate::process::table::get_all_processes;
+use std::collections::HashSet;
+
+// The TCP states in /proc/net/tcp, same as Linux
+const TCP_ESTABLISHED: u8 = 1;
+const TCP_CLOSE: u8 = 7;
+
+// The flags and the states in /proc/net/unix, same as Linux
+const SO_ACCEPTCON: u32 = 1 << 16;
+const SS_UNCONNECTED: u8 = 1;
+const SS_CONNECTED: u8 = 3;
+
+pub struct LockedNetDirINode(RwLock<NetDirINode>);
+
+struct NetDirINode {
+    this: Weak<Dir<LockedNetDirINode>>,
+    parent: Weak<dyn INode>,
+    entries: HashMap<String, Arc<dyn INode>>,
+}
+
+impl LockedNetDirINode {
+    pub fn new(parent: &Arc<dyn INode>) -> Arc<dyn INode> {
+        let inode = Arc::new(Dir::new(Self(RwLock::new(NetDirINode {
+            this: Weak::default(),
+            parent: Arc::downgrade(parent),
+            entries: HashMap::new(),
+        }))));
+        inode.inner().0.write().unwrap().this = Arc::downgrade(&inode);
+        inode.inner().init_entries();
+        inode
+    }
+
+    fn init_entries(&self) {
+        let mut file = self.0.write().unwrap();
+        let inet_tables = [
+            ("tcp", AddressFamily::INET, SocketType::STREAM),
+            ("tcp6", AddressFamily::INET6, SocketType::STREAM),
+            ("udp", AddressFamily::INET, SocketType::DGRAM),
+            ("udp6", AddressFamily::INET6, SocketType::DGRAM),
+        ];
+        for (name, domain, socket_type) in inet_tables.iter() {
+            let inet_inode = ProcNetInetINode::new(*domain, *socket_type);
+            file.entries.insert(String::from(*name), inet_inode);
+        }
+        let unix_inode = ProcNetUnixINode::new();
+        file.entries.insert(String::from("unix"), unix_inode);
+    }
+}
+
+impl DirProcINode for LockedNetDirINode {
+    fn find(&self, name: &str) -> vfs::Result<Arc<dyn INode>> {
+        let file = self.0.read().unwrap();
+        if name == "." {
+            return Ok(file.this.upgrade().unwrap());
+        }
+        if name == ".." {
+            return Ok(file.parent.upgrade().unwrap());
+        }
+        if let Some(inode) = file.entries.get(name) {
+            Ok(Arc::clone(inode))
+        } else {
+            Err(FsError::EntryNotFound)
+        }
+    }
+
+    fn get_entry(&self, id: usize) -> vfs::Result<String> {
+        match id {
+            0 => Ok(String::from(".")),
+            1 => Ok(String::from("..")),
+            i => {
+                let file = self.0.read().unwrap();
+                if let Some(s) = file.entries.keys().nth(i - 2) {
+                    Ok(s.to_string())
+                } else {
+                    Err(FsError::EntryNotFound)
+                }
+            }
+        }
+    }
+}
+
+/// The table of the TCP or UDP sockets, e.g., `/proc/net/tcp`.
+pub struct ProcNetInetINode {
+    domain: AddressFamily,
+    socket_type: SocketType,
+}
+
+impl ProcNetInetINode {
+    pub fn new(domain: AddressFamily, socket_type: SocketType) -> Arc<dyn INode> {
+        Arc::new(File::new(Self {
+            domain,
+            socket_type,
+        }))
+    }
+}
+
+impl ProcINode for ProcNetInetINode {
+    fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        let is_tcp = self.socket_type == SocketType::STREAM;
+        let mut table = if is_tcp {
+            String::from(
+                "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n",
+            )
+        } else {
+            String::from(
+                "   sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops\n",
+            )
+        };
+
+        let mut sl = 0;
+        for file_ref in get_all_sockets() {
+            let socket = match file_ref.as_host_socket() {
+                Ok(socket) => socket,
+                Err(_) => continue,
+            };
+            // The socket may be closed by the host, e.g., on failed connections
+            match (socket.domain(), socket.socket_type()) {
+                (Ok(domain), Ok(socket_type))
+                    if domain == self.domain && socket_type == self.socket_type => {}
+                _ => continue,
+            }
+
+            let local_addr = socket.addr().ok();
+            let remote_addr = socket.peer_addr().ok();
+            let state = if is_tcp {
+                socket.tcp_state().unwrap_or(TCP_CLOSE)
+            } else if remote_addr.is_some() {
+                TCP_ESTABLISHED
+            } else {
+                TCP_CLOSE
+            };
+            let sl_width = if is_tcp { 4 } else { 5 };
+            table.push_str(&format!(
+                "{:>width$}: {} {} {:02X} {:08X}:{:08X} {:02X}:{:08X} {:08X} {:>5} {:>8} {}",
+                sl,
+                format_inet_addr(self.domain, local_addr.as_ref()),
+                format_inet_addr(self.domain, remote_addr.as_ref()),
+                state,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                socket.ino(),
+                width = sl_width,
+            ));
+            if !is_tcp {
+                table.push_str(" 2 0000000000000000 0");
+            }
+            table.push('\n');
+            sl += 1;
+        }
+        Ok(table.into_bytes())
+    }
+}
+
+/// The table of the UNIX domain sockets, i.e., `/proc/net/unix`.
+pub struct ProcNetUnixINode;
+
+impl ProcNetUnixINode {
+    pub fn new() -> Arc<dyn INode> {
+        Arc::new(File::new(Self))
+    }
+}
+
+impl ProcINode for ProcNetUnixINode {
+    fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        let mut table = String::from("Num       RefCount Protocol Flags    Type St Inode Path\n");
+        for file_ref in get_all_sockets() {
+            let (flags, socket_type, state, ino, addr) =
+                if let Ok(socket) = file_ref.as_unix_socket() {
+                    let (flags, state) = if socket.is_listening() {
+                        (SO_ACCEPTCON, SS_UNCONNECTED)
+                    } else if socket.is_connected() {
+                        (0, SS_CONNECTED)
+                    } else {
+                        (0, SS_UNCONNECTED)
+                    };
+                    (
+                        flags,
+                        socket.socket_type(),
+                        state,
+                        socket.ino(),
+                        socket.addr(),
+                    )
+                } else if let Ok(socket) = file_ref.as_unix_datagram() {
+                    let state = if socket.is_connected() {
+                        SS_CONNECTED
+                    } else {
+                        SS_UNCONNECTED
+                    };
+                    (0, SocketType::DGRAM, state, socket.ino(), socket.addr())
+                } else {
+                    continue;
+                };
+            // The kernel addresses of sockets are hidden, same as Linux does
+            // for unprivileged users
+            table.push_str(&format!(
+                "{:016x}: {:08X} {:08X} {:08X} {:04X} {:02X} {:>5}",
+                0, 2, 0, flags, socket_type as i32, state, ino,
+            ));
+            match addr {
+                Some(UnixAddr::File(path)) => {
+                    table.push_str(&format!(" {}", path.path_str()));
+                }
+                // Same as Linux, the null bytes of an abstract name are shown as '@'
+                Some(UnixAddr::Abstract(name)) => {
+                    let name = String::from_utf8_lossy(&name).replace('\0', "@");
+                    table.push_str(&format!(" @{}", name));
+                }
+                None => {}
+            }
+            table.push('\n');
+        }
+        Ok(table.into_bytes())
+    }
+}
+
+/// Collect the sockets opened by all the processes. A socket shared by
+/// multiple file descriptors or processes is collected only once.
+fn get_all_sockets() -> Vec<FileRef> {
+    let mut inos = HashSet::new();
+    let mut sockets = Vec::new();
+    for process in get_all_processes() {
+        let main_thread = match process.main_thread() {
+            Some(main_thread) => main_thread,
+            None => continue,
+        };
+        let files = main_thread.files().lock().unwrap().fds_and_files();
+        for (_, file_ref) in files {
+            let ino = if let Ok(socket) = file_ref.as_host_socket() {
+                socket.ino()
+            } else if let Ok(socket) = file_ref.as_unix_socket() {
+                socket.ino()
+            } else if let Ok(socket) = file_ref.as_unix_datagram() {
+                socket.ino()
+            } else {
+                continue;
+            };
+            if inos.insert(ino) {
+                sockets.push(file_ref);
+            }
+        }
+    }
+    sockets
+}
+
+/// Format the address in the same way as Linux, i.e., the IP address in the
+/// network byte order printed as native integers, followed by the port.
+fn format_inet_addr(domain: AddressFamily, addr: Option<&SockAddr>) -> String {
+    let bytes = addr.map(|addr| addr.as_slice()).unwrap_or(&[]);
+    let word_at = |offset: usize| -> u32 {
+        bytes
+            .get(offset..offset + 4)
+            .map(|word| u32::from_ne_bytes([word[0], word[1], word[2], word[3]]))
+            .unwrap_or(0)
+    };
+    let port = bytes
+        .get(2..4)
+        .map(|port| u16::from_be_bytes([port[0], port[1]]))
+        .unwrap_or(0);
+    if domain == AddressFamily::INET6 {
+        // struct sockaddr_in6: family, port, flowinfo and then the address
+        format!(
+            "{:08X}{:08X}{:08X}{:08X}:{:04X}",
+            word_at(8),
+            word_at(12),
+            word_at(16),
+            word_at(20),
+            port
+        )
+    } else {
+        // struct sockaddr_in: family, port and then the address
+        format!("{:08X}:{:04X}", word_at(4), port)
+    }
+}
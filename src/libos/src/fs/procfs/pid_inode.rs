@@ -1,6 +1,14 @@
 use super::*;
+use crate::net::{
+    AsDnsSocket, AsEnclaveSocket, AsEpollFile, AsNetlinkSocket, AsUnixDatagram, AsUnixSocket,
+    HostSocketType,
+};
 use crate::process::table::get_process;
-use crate::process::ProcessRef;
+use crate::process::{ProcessRef, ProcessStatus, ThreadRef};
+use crate::signal::AsSignalFile;
+use crate::vm::{VMPerms, VMRange, VMUsage, PAGE_SIZE};
+use std::ffi::CString;
+use std::fmt;
 
 pub struct LockedPidDirINode(RwLock<PidDirINode>);
 
@@ -29,15 +37,38 @@ impl LockedPidDirINode {
         // cmdline
         let cmdline_inode = ProcCmdlineINode::new(&file.process_ref);
         file.entries.insert(String::from("cmdline"), cmdline_inode);
+        // environ
+        let environ_inode = ProcEnvironINode::new(&file.process_ref);
+        file.entries.insert(String::from("environ"), environ_inode);
+        // stat
+        let stat_inode = ProcStatINode::new(&file.process_ref);
+        file.entries.insert(String::from("stat"), stat_inode);
+        // status
+        let status_inode = ProcStatusINode::new(&file.process_ref);
+        file.entries.insert(String::from("status"), status_inode);
         // cwd
         let cwd_inode = ProcCwdSymINode::new(&file.process_ref);
         file.entries.insert(String::from("cwd"), cwd_inode);
         // exe
         let exe_inode = ProcExeSymINode::new(&file.process_ref);
         file.entries.insert(String::from("exe"), exe_inode);
+        // maps
+        let maps_inode = ProcMapsINode::new(&file.process_ref);
+        file.entries.insert(String::from("maps"), maps_inode);
+        // statm
+        let statm_inode = ProcStatmINode::new(&file.process_ref);
+        file.entries.insert(String::from("statm"), statm_inode);
+        // smaps_rollup
+        let smaps_rollup_inode = ProcSmapsRollupINode::new(&file.process_ref);
+        file.entries
+            .insert(String::from("smaps_rollup"), smaps_rollup_inode);
         // fd
         let fd_inode = LockedProcFdDirINode::new(&file.process_ref, file.this.upgrade().unwrap());
         file.entries.insert(String::from("fd"), fd_inode);
+        // task
+        let task_inode =
+            LockedProcTaskDirINode::new(&file.process_ref, file.this.upgrade().unwrap());
+        file.entries.insert(String::from("task"), task_inode);
         Ok(())
     }
 }
@@ -115,13 +146,205 @@ impl DirProcINode for LockedProcFdDirINode {
             0 => Ok(String::from(".")),
             1 => Ok(String::from("..")),
             i => {
-                // TODO: When to iterate the file table ?
-                Err(FsError::EntryNotFound)
+                let file = self.0.read().unwrap();
+                let main_thread = file
+                    .process_ref
+                    .main_thread()
+                    .ok_or(FsError::EntryNotFound)?;
+                let fds_and_files = main_thread.files().lock().unwrap().fds_and_files();
+                fds_and_files
+                    .get(i - 2)
+                    .map(|(fd, _)| fd.to_string())
+                    .ok_or(FsError::EntryNotFound)
+            }
+        }
+    }
+}
+
+struct LockedProcTaskDirINode(RwLock<ProcTaskDirINode>);
+
+struct ProcTaskDirINode {
+    process_ref: ProcessRef,
+    this: Weak<Dir<LockedProcTaskDirINode>>,
+    parent: Arc<dyn INode>,
+}
+
+impl LockedProcTaskDirINode {
+    pub fn new(process_ref: &ProcessRef, parent: Arc<dyn INode>) -> Arc<dyn INode> {
+        let inode = Arc::new(Dir::new(Self(RwLock::new(ProcTaskDirINode {
+            process_ref: Arc::clone(process_ref),
+            this: Weak::default(),
+            parent: Arc::clone(&parent),
+        }))));
+        inode.inner().0.write().unwrap().this = Arc::downgrade(&inode);
+        inode
+    }
+}
+
+impl DirProcINode for LockedProcTaskDirINode {
+    fn find(&self, name: &str) -> vfs::Result<Arc<dyn INode>> {
+        let file = self.0.read().unwrap();
+        if name == "." {
+            return Ok(file.this.upgrade().unwrap());
+        }
+        if name == ".." {
+            return Ok(Arc::clone(&file.parent));
+        }
+        let tid = name.parse::<pid_t>().map_err(|_| FsError::EntryNotFound)?;
+        let thread_ref = file
+            .process_ref
+            .threads()
+            .into_iter()
+            .find(|thread| thread.tid() == tid)
+            .ok_or(FsError::EntryNotFound)?;
+        let tid_inode =
+            LockedTidDirINode::new(&file.process_ref, &thread_ref, file.this.upgrade().unwrap());
+        Ok(tid_inode)
+    }
+
+    fn get_entry(&self, id: usize) -> vfs::Result<String> {
+        match id {
+            0 => Ok(String::from(".")),
+            1 => Ok(String::from("..")),
+            i => {
+                let file = self.0.read().unwrap();
+                let threads = file.process_ref.threads();
+                threads
+                    .get(i - 2)
+                    .map(|thread| thread.tid().to_string())
+                    .ok_or(FsError::EntryNotFound)
+            }
+        }
+    }
+}
+
+struct LockedTidDirINode(RwLock<TidDirINode>);
+
+struct TidDirINode {
+    this: Weak<Dir<LockedTidDirINode>>,
+    parent: Arc<dyn INode>,
+    entries: HashMap<String, Arc<dyn INode>>,
+}
+
+impl LockedTidDirINode {
+    pub fn new(
+        process_ref: &ProcessRef,
+        thread_ref: &ThreadRef,
+        parent: Arc<dyn INode>,
+    ) -> Arc<dyn INode> {
+        let inode = Arc::new(Dir::new(Self(RwLock::new(TidDirINode {
+            this: Weak::default(),
+            parent: Arc::clone(&parent),
+            entries: HashMap::new(),
+        }))));
+        inode.inner().0.write().unwrap().this = Arc::downgrade(&inode);
+        inode.inner().init_entries(process_ref, thread_ref);
+        inode
+    }
+
+    fn init_entries(&self, process_ref: &ProcessRef, thread_ref: &ThreadRef) {
+        let mut file = self.0.write().unwrap();
+        // comm
+        let comm_inode = ProcTaskCommINode::new(thread_ref);
+        file.entries.insert(String::from("comm"), comm_inode);
+        // stat
+        let stat_inode = ProcTaskStatINode::new(process_ref, thread_ref);
+        file.entries.insert(String::from("stat"), stat_inode);
+        // status
+        let status_inode = ProcTaskStatusINode::new(process_ref, thread_ref);
+        file.entries.insert(String::from("status"), status_inode);
+    }
+}
+
+impl DirProcINode for LockedTidDirINode {
+    fn find(&self, name: &str) -> vfs::Result<Arc<dyn INode>> {
+        let file = self.0.read().unwrap();
+        if name == "." {
+            return Ok(file.this.upgrade().unwrap());
+        }
+        if name == ".." {
+            return Ok(Arc::clone(&file.parent));
+        }
+        if let Some(inode) = file.entries.get(name) {
+            Ok(Arc::clone(inode))
+        } else {
+            Err(FsError::EntryNotFound)
+        }
+    }
+
+    fn get_entry(&self, id: usize) -> vfs::Result<String> {
+        match id {
+            0 => Ok(String::from(".")),
+            1 => Ok(String::from("..")),
+            i => {
+                let file = self.0.read().unwrap();
+                if let Some(s) = file.entries.keys().nth(i - 2) {
+                    Ok(s.to_string())
+                } else {
+                    Err(FsError::EntryNotFound)
+                }
             }
         }
     }
 }
 
+pub struct ProcTaskCommINode(ThreadRef);
+
+impl ProcTaskCommINode {
+    pub fn new(thread_ref: &ThreadRef) -> Arc<dyn INode> {
+        Arc::new(File::new(Self(Arc::clone(thread_ref))))
+    }
+}
+
+impl ProcINode for ProcTaskCommINode {
+    fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        let name = self.0.name();
+        let mut comm = name.as_c_str().to_bytes().to_vec();
+        comm.push(b'\n');
+        Ok(comm)
+    }
+}
+
+pub struct ProcTaskStatusINode {
+    process_ref: ProcessRef,
+    thread_ref: ThreadRef,
+}
+
+impl ProcTaskStatusINode {
+    pub fn new(process_ref: &ProcessRef, thread_ref: &ThreadRef) -> Arc<dyn INode> {
+        Arc::new(File::new(Self {
+            process_ref: Arc::clone(process_ref),
+            thread_ref: Arc::clone(thread_ref),
+        }))
+    }
+}
+
+impl ProcINode for ProcTaskStatusINode {
+    fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        Ok(generate_status(&self.process_ref, &self.thread_ref).into_bytes())
+    }
+}
+
+pub struct ProcTaskStatINode {
+    process_ref: ProcessRef,
+    thread_ref: ThreadRef,
+}
+
+impl ProcTaskStatINode {
+    pub fn new(process_ref: &ProcessRef, thread_ref: &ThreadRef) -> Arc<dyn INode> {
+        Arc::new(File::new(Self {
+            process_ref: Arc::clone(process_ref),
+            thread_ref: Arc::clone(thread_ref),
+        }))
+    }
+}
+
+impl ProcINode for ProcTaskStatINode {
+    fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        Ok(generate_stat(&self.process_ref, &self.thread_ref).into_bytes())
+    }
+}
+
 pub struct ProcCmdlineINode(ProcessRef);
 
 impl ProcCmdlineINode {
@@ -132,7 +355,438 @@ impl ProcCmdlineINode {
 
 impl ProcINode for ProcCmdlineINode {
     fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
-        Ok(self.0.exec_path().to_owned().into_bytes())
+        Ok(join_with_nul(self.0.argv()))
+    }
+}
+
+pub struct ProcEnvironINode(ProcessRef);
+
+impl ProcEnvironINode {
+    pub fn new(process_ref: &ProcessRef) -> Arc<dyn INode> {
+        Arc::new(File::new(Self(Arc::clone(process_ref))))
+    }
+}
+
+impl ProcINode for ProcEnvironINode {
+    fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        Ok(join_with_nul(self.0.envp()))
+    }
+}
+
+/// Concatenate the strings, each of which is terminated by a null byte.
+fn join_with_nul(strings: &[CString]) -> Vec<u8> {
+    strings
+        .iter()
+        .flat_map(|string| string.as_bytes_with_nul())
+        .cloned()
+        .collect()
+}
+
+pub struct ProcStatusINode(ProcessRef);
+
+impl ProcStatusINode {
+    pub fn new(process_ref: &ProcessRef) -> Arc<dyn INode> {
+        Arc::new(File::new(Self(Arc::clone(process_ref))))
+    }
+}
+
+impl ProcINode for ProcStatusINode {
+    fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        let thread = self.0.main_thread().ok_or(FsError::EntryNotFound)?;
+        Ok(generate_status(&self.0, &thread).into_bytes())
+    }
+}
+
+pub struct ProcStatINode(ProcessRef);
+
+impl ProcStatINode {
+    pub fn new(process_ref: &ProcessRef) -> Arc<dyn INode> {
+        Arc::new(File::new(Self(Arc::clone(process_ref))))
+    }
+}
+
+impl ProcINode for ProcStatINode {
+    fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        let thread = self.0.main_thread().ok_or(FsError::EntryNotFound)?;
+        Ok(generate_stat(&self.0, &thread).into_bytes())
+    }
+}
+
+/// The number of clock ticks per second, which is the unit of times in the stat file
+const CLOCK_TICKS_PER_SEC: u64 = 100;
+
+/// The info of a thread shown by the status and stat files.
+struct ThreadInfo {
+    name: String,
+    state: char,
+    pid: pid_t,
+    tid: pid_t,
+    ppid: pid_t,
+    pgid: pid_t,
+    sid: pid_t,
+    num_threads: usize,
+    start_time: u64,
+    vm_usage: VMUsage,
+    max_rss: usize,
+    code_range: (usize, usize),
+    stack_start: usize,
+    brk_start: usize,
+    umask: u16,
+    sig_pending: u64,
+    shared_sig_pending: u64,
+    sig_blocked: u64,
+    sig_ignored: u64,
+    sig_caught: u64,
+}
+
+impl ThreadInfo {
+    fn new(process_ref: &ProcessRef, thread_ref: &ThreadRef) -> Self {
+        let state = match process_ref.status() {
+            ProcessStatus::Running => 'R',
+            ProcessStatus::Stopped => 'T',
+            ProcessStatus::Zombie => 'Z',
+        };
+        let ppid = if process_ref.pid() > 0 {
+            process_ref.parent().pid()
+        } else {
+            0
+        };
+        let process_vm = thread_ref.vm();
+        let code_range = process_vm
+            .get_elf_ranges()
+            .first()
+            .map(|range| (range.start(), range.end()))
+            .unwrap_or((0, 0));
+        let sig_dispositions = process_ref.sig_dispositions().read().unwrap();
+        Self {
+            name: thread_ref.name().as_c_str().to_string_lossy().into_owned(),
+            state,
+            pid: process_ref.pid(),
+            tid: thread_ref.tid(),
+            ppid,
+            pgid: process_ref.pgid(),
+            sid: process_ref.sid(),
+            num_threads: process_ref.threads().len(),
+            start_time: process_ref.start_time().as_millis() as u64 * CLOCK_TICKS_PER_SEC / 1000,
+            vm_usage: process_vm.get_usage(),
+            max_rss: process_vm.get_max_rss(),
+            code_range,
+            stack_start: process_vm.get_stack_base(),
+            brk_start: process_vm.get_heap_range().start(),
+            umask: thread_ref.fs().lock().unwrap().umask(),
+            sig_pending: thread_ref.sig_queues().read().unwrap().pending().as_u64(),
+            shared_sig_pending: process_ref.sig_queues().read().unwrap().pending().as_u64(),
+            sig_blocked: thread_ref.sig_mask().read().unwrap().as_u64(),
+            sig_ignored: sig_dispositions.ignored().as_u64(),
+            sig_caught: sig_dispositions.caught().as_u64(),
+        }
+    }
+}
+
+fn generate_status(process_ref: &ProcessRef, thread_ref: &ThreadRef) -> String {
+    let info = ThreadInfo::new(process_ref, thread_ref);
+    let state_name = match info.state {
+        'R' => "R (running)",
+        'T' => "T (stopped)",
+        _ => "Z (zombie)",
+    };
+    let usage = &info.vm_usage;
+    format!(
+        "Name:\t{}\n\
+         Umask:\t{:04o}\n\
+         State:\t{}\n\
+         Tgid:\t{}\n\
+         Ngid:\t0\n\
+         Pid:\t{}\n\
+         PPid:\t{}\n\
+         TracerPid:\t0\n\
+         Uid:\t0\t0\t0\t0\n\
+         Gid:\t0\t0\t0\t0\n\
+         VmPeak:\t{:8} kB\n\
+         VmSize:\t{:8} kB\n\
+         VmLck:\t{:8} kB\n\
+         VmHWM:\t{:8} kB\n\
+         VmRSS:\t{:8} kB\n\
+         RssAnon:\t{:8} kB\n\
+         RssFile:\t{:8} kB\n\
+         RssShmem:\t{:8} kB\n\
+         VmStk:\t{:8} kB\n\
+         VmExe:\t{:8} kB\n\
+         Threads:\t{}\n\
+         SigPnd:\t{:016x}\n\
+         ShdPnd:\t{:016x}\n\
+         SigBlk:\t{:016x}\n\
+         SigIgn:\t{:016x}\n\
+         SigCgt:\t{:016x}\n",
+        info.name,
+        info.umask,
+        state_name,
+        info.pid,
+        info.tid,
+        info.ppid,
+        usage.size / 1024,
+        usage.size / 1024,
+        usage.locked_size / 1024,
+        info.max_rss / 1024,
+        usage.rss / 1024,
+        usage.anon_rss / 1024,
+        usage.file_rss / 1024,
+        usage.shared_rss / 1024,
+        usage.stack_size / 1024,
+        usage.elf_size / 1024,
+        info.num_threads,
+        info.sig_pending,
+        info.shared_sig_pending,
+        info.sig_blocked,
+        info.sig_ignored,
+        info.sig_caught,
+    )
+}
+
+fn generate_stat(process_ref: &ProcessRef, thread_ref: &ThreadRef) -> String {
+    let info = ThreadInfo::new(process_ref, thread_ref);
+    // The fields are the same as Linux, see proc(5) for the details.
+    // The unsupported fields are zeros.
+    format!(
+        "{} ({}) {} {} {} {} 0 -1 0 0 0 0 0 0 0 0 0 20 0 {} 0 {} {} {} {} {} {} {} 0 0 \
+         {} {} {} {} 0 0 0 17 0 0 0 0 0 0 0 0 {} 0 0 0 0 0\n",
+        info.tid,
+        info.name,
+        info.state,
+        info.ppid,
+        info.pgid,
+        info.sid,
+        info.num_threads,
+        info.start_time,
+        info.vm_usage.size,
+        info.vm_usage.rss / PAGE_SIZE,
+        std::u64::MAX,
+        info.code_range.0,
+        info.code_range.1,
+        info.stack_start,
+        info.sig_pending | info.shared_sig_pending,
+        info.sig_blocked,
+        info.sig_ignored,
+        info.sig_caught,
+        info.brk_start,
+    )
+}
+
+pub struct ProcMapsINode(ProcessRef);
+
+impl ProcMapsINode {
+    pub fn new(process_ref: &ProcessRef) -> Arc<dyn INode> {
+        Arc::new(File::new(Self(Arc::clone(process_ref))))
+    }
+}
+
+impl ProcINode for ProcMapsINode {
+    fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        let main_thread = self.0.main_thread().ok_or(FsError::EntryNotFound)?;
+        let process_vm = main_thread.vm();
+        // The regions of the process that are named after what they are
+        let mut named_ranges = vec![
+            (*process_vm.get_heap_range(), String::from("[heap]")),
+            (*process_vm.get_stack_range(), String::from("[stack]")),
+        ];
+        if let Some(exec_range) = process_vm.get_elf_ranges().first() {
+            named_ranges.push((*exec_range, self.0.exec_path().to_owned()));
+        }
+
+        let mut entries = Vec::new();
+        for vma in process_vm.get_vmas() {
+            let perms = vma.perms();
+            match vma.file() {
+                Some((file_ref, offset)) => {
+                    let (dev, inode) = file_ref
+                        .metadata()
+                        .map(|metadata| (metadata.dev, metadata.inode))
+                        .unwrap_or((0, 0));
+                    let path = if let Ok(inode_file) = file_ref.as_inode_file() {
+                        inode_file.abs_path().to_owned()
+                    } else if let Ok(mem_file) = file_ref.as_mem_file() {
+                        mem_file.abs_path().to_owned()
+                    } else {
+                        String::new()
+                    };
+                    entries.push(MapsEntry {
+                        range: *vma.range(),
+                        perms,
+                        is_shared: vma.is_shared(),
+                        offset: *offset,
+                        dev,
+                        inode,
+                        path,
+                    });
+                }
+                None => {
+                    // Adjacent VMAs of the same permissions are merged, so a
+                    // VMA is split at the bounds of the named regions
+                    let mut bounds = vec![vma.start(), vma.end()];
+                    for (range, _) in &named_ranges {
+                        bounds.extend_from_slice(&[range.start(), range.end()]);
+                    }
+                    bounds.retain(|&bound| bound >= vma.start() && bound <= vma.end());
+                    bounds.sort();
+                    bounds.dedup();
+                    for part in bounds.windows(2) {
+                        let range = unsafe { VMRange::from_unchecked(part[0], part[1]) };
+                        let path = named_ranges
+                            .iter()
+                            .find(|(named_range, _)| named_range.is_superset_of(&range))
+                            .map(|(_, name)| name.clone())
+                            .unwrap_or_default();
+                        entries.push(MapsEntry {
+                            range,
+                            perms,
+                            is_shared: false,
+                            offset: 0,
+                            dev: 0,
+                            inode: 0,
+                            path,
+                        });
+                    }
+                }
+            }
+        }
+        // The shared mappings of files and the shared memory segments, which
+        // are outside the process range
+        for segment in process_vm.get_shm_segments() {
+            let (path, dev, inode, offset) = segment.file_info().unwrap_or(("", 0, 0, 0));
+            entries.push(MapsEntry {
+                range: *segment.range(),
+                perms: VMPerms::READ | VMPerms::WRITE,
+                is_shared: true,
+                offset,
+                dev,
+                inode,
+                path: path.to_owned(),
+            });
+        }
+        entries.sort_by_key(|entry| entry.range.start());
+
+        let mut maps = String::new();
+        for entry in entries {
+            maps.push_str(&entry.to_string());
+            maps.push('\n');
+        }
+        Ok(maps.into_bytes())
+    }
+}
+
+/// A line of the maps file.
+struct MapsEntry {
+    range: VMRange,
+    perms: VMPerms,
+    is_shared: bool,
+    offset: usize,
+    dev: usize,
+    inode: usize,
+    path: String,
+}
+
+impl fmt::Display for MapsEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The format is the same as Linux, e.g.,
+        // "00400000-00452000 r-xp 00000000 08:02 173521      /usr/bin/dbus-daemon"
+        let line = format!(
+            "{:08x}-{:08x} {}{}{}{} {:08x} {:02x}:{:02x} {} ",
+            self.range.start(),
+            self.range.end(),
+            if self.perms.can_read() { 'r' } else { '-' },
+            if self.perms.can_write() { 'w' } else { '-' },
+            if self.perms.can_execute() { 'x' } else { '-' },
+            if self.is_shared { 's' } else { 'p' },
+            self.offset,
+            (self.dev >> 8) & 0xfff,
+            self.dev & 0xff,
+            self.inode,
+        );
+        if self.path.is_empty() {
+            write!(f, "{}", line.trim_end())
+        } else {
+            write!(f, "{:<73}{}", line, self.path)
+        }
+    }
+}
+
+pub struct ProcStatmINode(ProcessRef);
+
+impl ProcStatmINode {
+    pub fn new(process_ref: &ProcessRef) -> Arc<dyn INode> {
+        Arc::new(File::new(Self(Arc::clone(process_ref))))
+    }
+}
+
+impl ProcINode for ProcStatmINode {
+    fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        let main_thread = self.0.main_thread().ok_or(FsError::EntryNotFound)?;
+        let usage = main_thread.vm().get_usage();
+        // The fields are the same as Linux, see proc(5) for the details, all
+        // of which are measured in pages
+        let size = usage.size / PAGE_SIZE;
+        let resident = usage.rss / PAGE_SIZE;
+        let shared = (usage.file_rss + usage.shared_rss) / PAGE_SIZE;
+        let text = usage.elf_size / PAGE_SIZE;
+        let data = size - text;
+        Ok(format!("{} {} {} {} 0 {} 0\n", size, resident, shared, text, data).into_bytes())
+    }
+}
+
+pub struct ProcSmapsRollupINode(ProcessRef);
+
+impl ProcSmapsRollupINode {
+    pub fn new(process_ref: &ProcessRef) -> Arc<dyn INode> {
+        Arc::new(File::new(Self(Arc::clone(process_ref))))
+    }
+}
+
+impl ProcINode for ProcSmapsRollupINode {
+    fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        let main_thread = self.0.main_thread().ok_or(FsError::EntryNotFound)?;
+        let process_vm = main_thread.vm();
+        let usage = process_vm.get_usage();
+        let range = process_vm.get_process_range();
+        // The memory of the enclave is never swapped out, and the private pages
+        // are counted as dirty since they are never reloaded from files
+        let header = format!(
+            "{:08x}-{:08x} ---p 00000000 00:00 0 ",
+            range.start(),
+            range.end()
+        );
+        Ok(format!(
+            "{:<73}[rollup]\n\
+             Rss:            {:8} kB\n\
+             Pss:            {:8} kB\n\
+             Pss_Anon:       {:8} kB\n\
+             Pss_File:       {:8} kB\n\
+             Pss_Shmem:      {:8} kB\n\
+             Shared_Clean:   {:8} kB\n\
+             Shared_Dirty:   {:8} kB\n\
+             Private_Clean:  {:8} kB\n\
+             Private_Dirty:  {:8} kB\n\
+             Referenced:     {:8} kB\n\
+             Anonymous:      {:8} kB\n\
+             Swap:           {:8} kB\n\
+             SwapPss:        {:8} kB\n\
+             Locked:         {:8} kB\n",
+            header,
+            usage.rss / 1024,
+            usage.pss / 1024,
+            usage.anon_rss / 1024,
+            usage.file_rss / 1024,
+            (usage.pss - usage.anon_rss - usage.file_rss) / 1024,
+            0,
+            usage.shared_rss / 1024,
+            0,
+            (usage.anon_rss + usage.file_rss) / 1024,
+            usage.rss / 1024,
+            usage.anon_rss / 1024,
+            0,
+            0,
+            usage.locked_size / 1024,
+        )
+        .into_bytes())
     }
 }
 
@@ -178,15 +832,43 @@ impl FdSymINode {
 
 impl ProcINode for FdSymINode {
     fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
-        let path = if let Ok(inode_file) = self.0.as_inode_file() {
+        // For file descriptors for pipes and sockets, the content is:
+        // type:[inode]. For file descriptors that have no corresponding
+        // inode, the content is: anon_inode:[file-type].
+        let file_ref = &self.0;
+        let path = if let Ok(inode_file) = file_ref.as_inode_file() {
             inode_file.abs_path().to_owned()
+        } else if let Ok(mem_file) = file_ref.as_mem_file() {
+            mem_file.abs_path().to_owned()
+        } else if let Ok(pipe_reader) = file_ref.as_pipe_reader() {
+            format!("pipe:[{}]", pipe_reader.ino())
+        } else if let Ok(pipe_writer) = file_ref.as_pipe_writer() {
+            format!("pipe:[{}]", pipe_writer.ino())
+        } else if let Ok(socket) = file_ref.as_host_socket() {
+            format!("socket:[{}]", socket.ino())
+        } else if let Ok(socket) = file_ref.as_unix_socket() {
+            format!("socket:[{}]", socket.ino())
+        } else if let Ok(socket) = file_ref.as_unix_datagram() {
+            format!("socket:[{}]", socket.ino())
+        } else if let Ok(socket) = file_ref.as_netlink_socket() {
+            format!("socket:[{}]", socket.ino())
+        } else if let Ok(socket) = file_ref.as_enclave_socket() {
+            format!("socket:[{}]", socket.ino())
+        } else if let Ok(socket) = file_ref.as_dns_socket() {
+            format!("socket:[{}]", socket.ino())
+        } else if file_ref.as_event().is_ok() {
+            String::from("anon_inode:[eventfd]")
+        } else if file_ref.as_timer().is_ok() {
+            String::from("anon_inode:[timerfd]")
+        } else if file_ref.as_epoll_file().is_ok() {
+            String::from("anon_inode:[eventpoll]")
+        } else if file_ref.as_signal_file().is_ok() {
+            String::from("anon_inode:[signalfd]")
+        } else if file_ref.as_inotify_file().is_ok() {
+            String::from("anon_inode:inotify")
         } else {
-            // TODO: Support other file types
-            // For file descriptors for pipes and sockets,
-            // the content is: type:[inode].
-            // For file descriptors that have no corresponding inode,
-            // the content is: anon_inode:[file-type]
-            return Err(FsError::EntryNotFound);
+            // The stdio of the host
+            String::from("anon_inode:[stdio]")
         };
         Ok(path.into_bytes())
     }
@@ -0,0 +1,34 @@
+use super::*;
+use crate::fs::sefs::PAGE_CACHE;
+
+/// The statistics of the page cache of SEFS, i.e., `/proc/sefs_page_cache`.
+pub struct SefsPageCacheINode;
+
+impl SefsPageCacheINode {
+    pub fn new() -> Arc<dyn INode> {
+        Arc::new(File::new(Self))
+    }
+}
+
+impl ProcINode for SefsPageCacheINode {
+    fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        let stats = PAGE_CACHE.stats();
+        Ok(format!(
+            "capacity_pages: {}\n\
+             cached_pages: {}\n\
+             dirty_pages: {}\n\
+             hits: {}\n\
+             misses: {}\n\
+             evictions: {}\n\
+             writebacks: {}\n",
+            PAGE_CACHE.capacity(),
+            PAGE_CACHE.num_pages(),
+            PAGE_CACHE.num_dirty_pages(),
+            stats.hits(),
+            stats.misses(),
+            stats.evictions(),
+            stats.writebacks(),
+        )
+        .into_bytes())
+    }
+}
@@ -0,0 +1,106 @@
+use super::*;
+use crate::util::sgx::create_report;
+use sgx_types::SGX_FLAGS_DEBUG;
+
+/// The directory of the identity of the enclave, i.e., `/proc/sgx`.
+///
+/// The identity is taken from a report of the enclave targeting itself, so
+/// that applications can embed it in their own protocols without doing the
+/// attestation themselves.
+pub struct LockedSgxDirINode(RwLock<SgxDirINode>);
+
+struct SgxDirINode {
+    this: Weak<Dir<LockedSgxDirINode>>,
+    parent: Weak<dyn INode>,
+    entries: HashMap<String, Arc<dyn INode>>,
+}
+
+impl LockedSgxDirINode {
+    pub fn new(parent: &Arc<dyn INode>) -> Arc<dyn INode> {
+        let inode = Arc::new(Dir::new(Self(RwLock::new(SgxDirINode {
+            this: Weak::default(),
+            parent: Arc::downgrade(parent),
+            entries: HashMap::new(),
+        }))));
+        inode.inner().0.write().unwrap().this = Arc::downgrade(&inode);
+        inode.inner().init_entries();
+        inode
+    }
+
+    fn init_entries(&self) {
+        let mut file = self.0.write().unwrap();
+        let report = match create_report(None, None) {
+            Ok(report) => report,
+            Err(e) => {
+                warn!("failed to create the report of the enclave: {:?}", e);
+                return;
+            }
+        };
+        let body = &report.body;
+        let is_debug = (body.attributes.flags & SGX_FLAGS_DEBUG) == SGX_FLAGS_DEBUG;
+        let identities = [
+            ("mr_enclave", to_hex_string(&body.mr_enclave.m)),
+            ("mr_signer", to_hex_string(&body.mr_signer.m)),
+            ("isv_prod_id", body.isv_prod_id.to_string()),
+            ("isv_svn", body.isv_svn.to_string()),
+            ("debug", (is_debug as u8).to_string()),
+        ];
+        for (name, value) in identities.iter() {
+            let inode = ProcSgxIdentityINode::new(format!("{}\n", value));
+            file.entries.insert(String::from(*name), inode);
+        }
+    }
+}
+
+impl DirProcINode for LockedSgxDirINode {
+    fn find(&self, name: &str) -> vfs::Result<Arc<dyn INode>> {
+        let file = self.0.read().unwrap();
+        if name == "." {
+            return Ok(file.this.upgrade().unwrap());
+        }
+        if name == ".." {
+            return Ok(file.parent.upgrade().unwrap());
+        }
+        if let Some(inode) = file.entries.get(name) {
+            Ok(Arc::clone(inode))
+        } else {
+            Err(FsError::EntryNotFound)
+        }
+    }
+
+    fn get_entry(&self, id: usize) -> vfs::Result<String> {
+        match id {
+            0 => Ok(String::from(".")),
+            1 => Ok(String::from("..")),
+            i => {
+                let file = self.0.read().unwrap();
+                if let Some(s) = file.entries.keys().nth(i - 2) {
+                    Ok(s.to_string())
+                } else {
+                    Err(FsError::EntryNotFound)
+                }
+            }
+        }
+    }
+}
+
+/// A field of the identity of the enclave, e.g., `/proc/sgx/mr_enclave`.
+pub struct ProcSgxIdentityINode {
+    value: String,
+}
+
+impl ProcSgxIdentityINode {
+    pub fn new(value: String) -> Arc<dyn INode> {
+        Arc::new(File::new(Self { value }))
+    }
+}
+
+impl ProcINode for ProcSgxIdentityINode {
+    fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        Ok(self.value.clone().into_bytes())
+    }
+}
+
+fn to_hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -1,17 +1,21 @@
+use super::bindfs::BindFS;
 use super::dev_fs;
 use super::hostfs::HostFS;
 use super::procfs::ProcFS;
-use super::sefs::{SgxStorage, SgxUuidProvider};
+use super::sefs::{self, SgxStorage, SgxUuidProvider, SharedSgxStorage};
+use super::sysfs::SysFS;
+use super::unionfs::UnionFS;
+use super::xattr;
 use super::*;
-use config::ConfigMountFsType;
+use config::{ConfigLayerRole, ConfigMountFsType};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
 use std::untrusted::path::PathEx;
 
 use rcore_fs_mountfs::{MNode, MountFS};
 use rcore_fs_ramfs::RamFS;
 use rcore_fs_sefs::dev::*;
 use rcore_fs_sefs::SEFS;
-use rcore_fs_unionfs::UnionFS;
 
 lazy_static! {
     /// The root of file system
@@ -23,6 +27,7 @@ lazy_static! {
                 rootfs.root_inode()
             };
             mount_nonroot_fs_according_to(&root_inode, mount_config, &None)?;
+            crate::net::write_resolv_conf(&*root_inode)?;
             Ok(root_inode)
         }
 
@@ -46,31 +51,55 @@ pub fn open_root_fs_according_to(
         return_errno!(EINVAL, "the root UnionFS must be given the layers");
     }
     let layer_mount_configs = root_mount_config.options.layers.as_ref().unwrap();
-    // image SEFS in layers
-    let root_image_sefs_mount_config = layer_mount_configs
+    // The layers are given from the bottom to the top. The image SEFS layers,
+    // which are given the MACs, are read-only, while the only container SEFS
+    // layer is writable and always put on the top.
+    if layer_mount_configs
         .iter()
-        .find(|m| {
-            m.target == Path::new("/")
-                && m.type_ == ConfigMountFsType::TYPE_SEFS
-                && m.options.mac.is_some()
-        })
-        .ok_or_else(|| errno!(Errno::ENOENT, "the image SEFS in layers is not valid"))?;
-    let root_image_sefs =
-        open_or_create_sefs_according_to(&root_image_sefs_mount_config, user_key)?;
-    // container SEFS in layers
-    let root_container_sefs_mount_config = layer_mount_configs
-        .iter()
-        .find(|m| {
-            m.target == Path::new("/")
-                && m.type_ == ConfigMountFsType::TYPE_SEFS
-                && m.options.mac.is_none()
-        })
-        .ok_or_else(|| errno!(Errno::ENOENT, "the container SEFS in layers is not valid"))?;
-    let root_container_sefs =
-        open_or_create_sefs_according_to(&root_container_sefs_mount_config, user_key)?;
+        .any(|m| m.target != Path::new("/") || m.type_ != ConfigMountFsType::TYPE_SEFS)
+    {
+        return_errno!(EINVAL, "the layers of the root UnionFS must be SEFS");
+    }
+    if layer_mount_configs.iter().any(|m| m.options.role.is_none()) {
+        return_errno!(
+            EINVAL,
+            "the layers of the root UnionFS must be given the roles"
+        );
+    }
+    let (container_mount_configs, image_mount_configs): (Vec<&ConfigMount>, Vec<&ConfigMount>) =
+        layer_mount_configs
+            .iter()
+            .partition(|m| m.options.role == Some(ConfigLayerRole::Container));
+    if container_mount_configs.len() != 1 {
+        return_errno!(
+            EINVAL,
+            "the root UnionFS must have only one container layer"
+        );
+    }
+    if container_mount_configs[0].options.mac.is_some() {
+        return_errno!(EINVAL, "the container layer cannot be given the MAC");
+    }
+    if image_mount_configs.is_empty() {
+        return_errno!(
+            EINVAL,
+            "the root UnionFS must have at least one image layer"
+        );
+    }
+    if image_mount_configs.iter().any(|m| m.options.mac.is_none()) {
+        return_errno!(EINVAL, "the image layers must be given the MACs");
+    }
+    let (root_container_sefs, root_container_storage) =
+        open_or_create_sefs_and_storage(container_mount_configs[0], user_key)?;
+    let mut layers: Vec<Arc<dyn FileSystem>> = vec![root_container_sefs];
+    for image_mount_config in image_mount_configs.iter().rev() {
+        let image_sefs = open_or_create_sefs_according_to(image_mount_config, user_key)?;
+        layers.push(image_sefs);
+    }
     // create UnionFS
-    let root_unionfs = UnionFS::new(vec![root_container_sefs, root_image_sefs])?;
+    let root_unionfs = UnionFS::new(layers)?;
     let root_mountable_unionfs = MountFS::new(root_unionfs);
+    // The container SEFS is the writable layer, which can be re-encrypted
+    sefs::set_writable_layer(root_container_storage);
     Ok(root_mountable_unionfs)
 }
 
@@ -92,7 +121,15 @@ pub fn mount_nonroot_fs_according_to(
         match mc.type_ {
             TYPE_SEFS => {
                 let sefs = open_or_create_sefs_according_to(&mc, user_key)?;
-                mount_fs_at(sefs, root, &mc.target)?;
+                if mc.options.integrity_only {
+                    // Any update changes the MAC, with which the SEFS could
+                    // not be mounted again, thus reject all the updates
+                    let read_only = Arc::new(AtomicBool::new(true));
+                    let read_only_sefs = BindFS::new(sefs.root_inode(), read_only);
+                    mount_fs_at(read_only_sefs, root, &mc.target)?;
+                } else {
+                    mount_fs_at(sefs, root, &mc.target)?;
+                }
             }
             TYPE_HOSTFS => {
                 if mc.source.is_none() {
@@ -115,6 +152,10 @@ pub fn mount_nonroot_fs_according_to(
                 let procfs = ProcFS::new();
                 mount_fs_at(procfs, root, &mc.target)?;
             }
+            TYPE_SYSFS => {
+                let sysfs = SysFS::new();
+                mount_fs_at(sysfs, root, &mc.target)?;
+            }
             TYPE_UNIONFS => {
                 return_errno!(EINVAL, "Cannot mount UnionFS at non-root path");
             }
@@ -145,6 +186,14 @@ fn open_or_create_sefs_according_to(
     mc: &ConfigMount,
     user_key: &Option<sgx_key_128bit_t>,
 ) -> Result<Arc<SEFS>> {
+    let (sefs, _) = open_or_create_sefs_and_storage(mc, user_key)?;
+    Ok(sefs)
+}
+
+fn open_or_create_sefs_and_storage(
+    mc: &ConfigMount,
+    user_key: &Option<sgx_key_128bit_t>,
+) -> Result<(Arc<SEFS>, Arc<SgxStorage>)> {
     assert!(mc.type_ == ConfigMountFsType::TYPE_SEFS);
 
     if mc.source.is_none() {
@@ -153,34 +202,32 @@ fn open_or_create_sefs_according_to(
     if mc.options.temporary && mc.options.mac.is_some() {
         return_errno!(EINVAL, "Integrity protected SEFS cannot be temporary");
     }
+    // The files of integrity-only SEFS are not encrypted, thus its integrity
+    // depends on the MAC of its root, which is trusted
+    if mc.options.integrity_only && mc.options.mac.is_none() {
+        return_errno!(EINVAL, "MAC is expected for integrity-only SEFS");
+    }
     let source_path = mc.source.as_ref().unwrap();
     let root_mac = mc.options.mac;
-    let sefs = if !mc.options.temporary {
-        if root_mac.is_some() {
-            SEFS::open(
-                Box::new(SgxStorage::new(source_path, user_key, &root_mac)),
-                &time::OcclumTimeProvider,
-                &SgxUuidProvider,
-            )?
-        } else if source_path.join("metadata").exists() {
-            SEFS::open(
-                Box::new(SgxStorage::new(source_path, user_key, &root_mac)),
-                &time::OcclumTimeProvider,
-                &SgxUuidProvider,
-            )?
-        } else {
-            SEFS::create(
-                Box::new(SgxStorage::new(source_path, user_key, &root_mac)),
-                &time::OcclumTimeProvider,
-                &SgxUuidProvider,
-            )?
-        }
+    if root_mac.is_none() {
+        sefs::recover_interrupted_rekey(source_path)?;
+    }
+    // The user key is for the encrypted SEFS, not the integrity-only SEFS,
+    // which can be mounted together with the former
+    let key = if mc.options.integrity_only {
+        &None
     } else {
-        SEFS::create(
-            Box::new(SgxStorage::new(source_path, user_key, &root_mac)),
-            &time::OcclumTimeProvider,
-            &SgxUuidProvider,
-        )?
+        user_key
     };
-    Ok(sefs)
+    let storage = Arc::new(SgxStorage::new(source_path, key, &root_mac));
+    let shared_storage = Box::new(SharedSgxStorage(storage.clone()));
+    let is_new =
+        mc.options.temporary || (root_mac.is_none() && !source_path.join("metadata").exists());
+    let sefs = if is_new {
+        SEFS::create(shared_storage, &time::OcclumTimeProvider, &SgxUuidProvider)?
+    } else {
+        SEFS::open(shared_storage, &time::OcclumTimeProvider, &SgxUuidProvider)?
+    };
+    xattr::register_sefs(&sefs, &storage, is_new)?;
+    Ok((sefs, storage))
 }
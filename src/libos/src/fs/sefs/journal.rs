@@ -0,0 +1,481 @@
+//! A write-ahead journal for the metadata file of SEFS.
+//!
+//! The metadata file of SEFS holds the inodes, the free map and the super
+//! block, which are updated in place. A crash in the middle of a sync may leave
+//! the metadata partially updated, after which the whole SEFS cannot be
+//! mounted. To make the updates atomic, the writes to the metadata file are
+//! buffered in memory until the file is flushed. On a flush, the buffered pages
+//! are written to the journal file, followed by a commit record, before they
+//! are applied to the metadata file. When SEFS is mounted, a committed journal
+//! is replayed, while an incomplete one is dropped.
+//!
+//! The removals of the data files are deferred until the metadata that no
+//! longer refers to them is committed, so they are recorded in the journal too.
+//! So are the truncations of the metadata file. As a protected file cannot be
+//! shrunk, the data truncated away is zeroed when the journal is applied.
+
+use super::page_cache::PAGE_SIZE;
+use super::sgx_storage::{register_metadata_file, EncryptMode, LockedFile, MetadataFile};
+use crate::prelude::*;
+use crate::std::untrusted::path::PathEx;
+use rcore_fs::dev::DevResult;
+use rcore_fs_sefs::dev::{File, SefsMac};
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sgxfs::{remove, OpenOptions};
+use std::sync::{Arc, SgxMutex as Mutex, SgxMutexGuard as MutexGuard, SgxRwLock as RwLock, Weak};
+
+const JOURNAL_FILE_ID: &str = "metadata.journal";
+
+/// The magic number at the beginning of a journal, i.e., "SEFSJRNL"
+const JOURNAL_MAGIC: u64 = 0x4c4e_524a_5346_4553;
+/// The magic number of the commit record at the end of a journal, i.e., "SEFSCMIT"
+const COMMIT_MAGIC: u64 = 0x5449_4d43_5346_4553;
+
+/// The metadata file of SEFS, whose updates are journaled.
+#[derive(Clone)]
+pub struct JournaledFile(Arc<JournaledFileInner>);
+
+struct JournaledFileInner {
+    file: LockedFile,
+    /// The directory of the files of SEFS
+    dir: PathBuf,
+    encrypt_mode: RwLock<EncryptMode>,
+    pending: Mutex<PendingUpdates>,
+}
+
+/// The updates that are not committed yet.
+struct PendingUpdates {
+    pages: BTreeMap<usize, Box<[u8]>>,
+    /// The size of the file, including the pending pages
+    size: usize,
+    /// The size of the committed data that is not truncated away
+    kept_size: usize,
+    /// Whether the file has been truncated or extended by set_len
+    is_resized: bool,
+    /// The ids of the data files to remove after the commit
+    removals: Vec<String>,
+}
+
+/// A lock that prevents the metadata from being updated.
+pub struct JournalLock<'a>(MutexGuard<'a, PendingUpdates>);
+
+/// The updates decoded from a committed journal.
+struct JournalRecord<'a> {
+    size: usize,
+    kept_size: usize,
+    removals: Vec<String>,
+    pages: Vec<(usize, &'a [u8])>,
+}
+
+impl JournaledFile {
+    /// Wrap the metadata file with the journal.
+    ///
+    /// A committed journal left by a crash is replayed if the file is opened,
+    /// or dropped if the file is newly created.
+    pub fn new(
+        file: LockedFile,
+        dir: PathBuf,
+        encrypt_mode: EncryptMode,
+        is_new: bool,
+    ) -> Result<Self> {
+        let inner = JournaledFileInner {
+            file,
+            dir,
+            encrypt_mode: RwLock::new(encrypt_mode),
+            pending: Mutex::new(PendingUpdates {
+                pages: BTreeMap::new(),
+                size: 0,
+                kept_size: 0,
+                is_resized: false,
+                removals: Vec::new(),
+            }),
+        };
+        if is_new {
+            inner.remove_journal()?;
+        } else {
+            inner.recover()?;
+        }
+        {
+            let mut pending = inner.pending.lock().unwrap();
+            pending.size = inner.file.size();
+            pending.kept_size = pending.size;
+        }
+
+        let journaled_file = Self(Arc::new(inner));
+        register_metadata_file(
+            journaled_file.0.file.id(),
+            Arc::downgrade(&journaled_file.0) as Weak<dyn MetadataFile>,
+        );
+        Ok(journaled_file)
+    }
+
+    /// Commit the pending updates and prevent further updates until the
+    /// returned lock is dropped.
+    pub fn commit_and_lock(&self) -> Result<JournalLock> {
+        let pending = self.0.commit_locked(self.0.pending.lock().unwrap())?;
+        Ok(JournalLock(pending))
+    }
+
+    /// Change the encryption mode of the journal after the metadata file is
+    /// re-encrypted.
+    pub fn set_encrypt_mode(&self, _lock: &JournalLock, encrypt_mode: EncryptMode) {
+        *self.0.encrypt_mode.write().unwrap() = encrypt_mode;
+    }
+
+    /// Remove the data file after the next commit.
+    pub fn defer_removal(&self, file_id: &str) {
+        let mut pending = self.0.pending.lock().unwrap();
+        pending.removals.push(file_id.to_string());
+    }
+}
+
+impl File for JournaledFile {
+    fn read_at(&self, buf: &mut [u8], offset: usize) -> DevResult<usize> {
+        Ok(self.0.read_at(buf, offset)?)
+    }
+
+    fn write_at(&self, buf: &[u8], offset: usize) -> DevResult<usize> {
+        Ok(self.0.write_at(buf, offset)?)
+    }
+
+    fn set_len(&self, len: usize) -> DevResult<()> {
+        Ok(self.0.set_len(len)?)
+    }
+
+    fn flush(&self) -> DevResult<()> {
+        Ok(self.0.commit()?)
+    }
+
+    fn get_file_mac(&self) -> DevResult<SefsMac> {
+        self.0.commit()?;
+        self.0.file.get_file_mac()
+    }
+}
+
+impl MetadataFile for JournaledFileInner {
+    fn sync(&self) -> Result<()> {
+        self.commit()
+    }
+}
+
+impl JournaledFileInner {
+    fn read_at(&self, buf: &mut [u8], offset: usize) -> Result<usize> {
+        let pending = self.pending.lock().unwrap();
+        if offset >= pending.size {
+            return Ok(0);
+        }
+        let end = pending.size.min(offset + buf.len());
+
+        let mut pos = offset;
+        while pos < end {
+            let page_idx = pos / PAGE_SIZE;
+            let page_offset = pos % PAGE_SIZE;
+            let len = (PAGE_SIZE - page_offset).min(end - pos);
+            let dst = &mut buf[pos - offset..pos - offset + len];
+            match pending.pages.get(&page_idx) {
+                Some(page) => dst.copy_from_slice(&page[page_offset..page_offset + len]),
+                None => self.read_committed(dst, pos, pending.kept_size)?,
+            }
+            pos += len;
+        }
+        Ok(end - offset)
+    }
+
+    fn write_at(&self, buf: &[u8], offset: usize) -> Result<usize> {
+        let mut pending = self.pending.lock().unwrap();
+        let end = offset + buf.len();
+
+        let mut pos = offset;
+        while pos < end {
+            let page_idx = pos / PAGE_SIZE;
+            let page_offset = pos % PAGE_SIZE;
+            let len = (PAGE_SIZE - page_offset).min(end - pos);
+            let page = self.pending_page(&mut pending, page_idx)?;
+            page[page_offset..page_offset + len]
+                .copy_from_slice(&buf[pos - offset..pos - offset + len]);
+            pos += len;
+        }
+        pending.size = pending.size.max(end);
+        Ok(buf.len())
+    }
+
+    fn set_len(&self, len: usize) -> Result<()> {
+        let mut pending = self.pending.lock().unwrap();
+        if len < pending.size {
+            // Drop the pages beyond the end and zero the tail of the last page,
+            // so that the data truncated away is not seen once extended again
+            let nr_pages = (len + PAGE_SIZE - 1) / PAGE_SIZE;
+            pending.pages.split_off(&nr_pages);
+            if len % PAGE_SIZE != 0 {
+                let page = self.pending_page(&mut pending, len / PAGE_SIZE)?;
+                for byte in &mut page[len % PAGE_SIZE..] {
+                    *byte = 0;
+                }
+            }
+            pending.kept_size = pending.kept_size.min(len);
+        }
+        pending.size = len;
+        pending.is_resized = true;
+        Ok(())
+    }
+
+    /// Get the pending page, which is read from the committed data if absent.
+    fn pending_page<'a>(
+        &self,
+        pending: &'a mut PendingUpdates,
+        page_idx: usize,
+    ) -> Result<&'a mut Box<[u8]>> {
+        if !pending.pages.contains_key(&page_idx) {
+            let mut page = vec![0u8; PAGE_SIZE].into_boxed_slice();
+            self.read_committed(&mut page, page_idx * PAGE_SIZE, pending.kept_size)?;
+            pending.pages.insert(page_idx, page);
+        }
+        Ok(pending.pages.get_mut(&page_idx).unwrap())
+    }
+
+    /// Read the committed data, where the part beyond the end or truncated
+    /// away is zeroed.
+    fn read_committed(&self, buf: &mut [u8], offset: usize, kept_size: usize) -> Result<()> {
+        let len = if offset < kept_size {
+            let kept_len = (kept_size - offset).min(buf.len());
+            self.file.read(&mut buf[..kept_len], offset)?
+        } else {
+            0
+        };
+        for byte in &mut buf[len..] {
+            *byte = 0;
+        }
+        Ok(())
+    }
+
+    /// Write the pending updates to the journal and then apply them.
+    fn commit(&self) -> Result<()> {
+        self.commit_locked(self.pending.lock().unwrap())?;
+        Ok(())
+    }
+
+    fn commit_locked<'a>(
+        &self,
+        mut pending: MutexGuard<'a, PendingUpdates>,
+    ) -> Result<MutexGuard<'a, PendingUpdates>> {
+        if pending.pages.is_empty() && pending.removals.is_empty() && !pending.is_resized {
+            self.file.flush_file()?;
+            return Ok(pending);
+        }
+
+        // Once the journal is written, the updates survive crashes. If any of
+        // the following steps fails, the updates are kept pending and
+        // committed again later.
+        self.write_journal(&pending.encode())?;
+        let record = JournalRecord {
+            size: pending.size,
+            kept_size: pending.kept_size,
+            removals: pending.removals.clone(),
+            pages: pending
+                .pages
+                .iter()
+                .map(|(page_idx, page)| (*page_idx, &page[..]))
+                .collect(),
+        };
+        self.apply(&record)?;
+        self.remove_journal()?;
+
+        pending.pages.clear();
+        pending.removals.clear();
+        pending.kept_size = pending.size;
+        pending.is_resized = false;
+        Ok(pending)
+    }
+
+    /// Replay the journal left by a crash, if any.
+    fn recover(&self) -> Result<()> {
+        let journal_path = self.journal_path();
+        if !journal_path.exists() {
+            return Ok(());
+        }
+
+        match self.read_journal() {
+            Ok(data) => match JournalRecord::decode(&data) {
+                Some(record) => {
+                    info!("replay the journal of the SEFS at {:?}", self.dir);
+                    self.apply(&record)?;
+                }
+                None => warn!("drop the incomplete journal of the SEFS at {:?}", self.dir),
+            },
+            // The journal is created, but not flushed for the first time
+            Err(e) => warn!(
+                "drop the broken journal of the SEFS at {:?}: {}",
+                self.dir, e
+            ),
+        }
+        self.remove_journal()
+    }
+
+    /// Apply the updates to the metadata file and remove the data files.
+    ///
+    /// Applying the same updates more than once is harmless, so a crash in the
+    /// middle of it is recovered by replaying the journal.
+    fn apply(&self, record: &JournalRecord) -> Result<()> {
+        self.zero_range(record.kept_size, self.file.size())?;
+        for (page_idx, page) in &record.pages {
+            let offset = page_idx * PAGE_SIZE;
+            let len = PAGE_SIZE.min(record.size.saturating_sub(offset));
+            self.file.write(&page[..len], offset)?;
+        }
+        // The file extended by set_len is filled with zeros
+        self.zero_range(self.file.size(), record.size)?;
+        self.file.flush_file()?;
+
+        for file_id in &record.removals {
+            let path = self.dir.join(file_id);
+            if path.exists() {
+                remove(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn zero_range(&self, start: usize, end: usize) -> Result<()> {
+        let zeros = [0u8; PAGE_SIZE];
+        let mut offset = start;
+        while offset < end {
+            let len = (PAGE_SIZE - offset % PAGE_SIZE).min(end - offset);
+            self.file.write(&zeros[..len], offset)?;
+            offset += len;
+        }
+        Ok(())
+    }
+
+    fn write_journal(&self, data: &[u8]) -> Result<()> {
+        let options = {
+            let mut options = OpenOptions::new();
+            options.write(true);
+            options
+        };
+        let mut journal = self
+            .encrypt_mode
+            .read()
+            .unwrap()
+            .open_file(&self.journal_path(), &options)?;
+        journal.write_all(data)?;
+        journal.flush()?;
+        Ok(())
+    }
+
+    fn read_journal(&self) -> Result<Vec<u8>> {
+        let options = {
+            let mut options = OpenOptions::new();
+            options.read(true);
+            options
+        };
+        let mut journal = self
+            .encrypt_mode
+            .read()
+            .unwrap()
+            .open_file(&self.journal_path(), &options)?;
+        let mut data = Vec::new();
+        journal.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    fn remove_journal(&self) -> Result<()> {
+        let journal_path = self.journal_path();
+        if journal_path.exists() {
+            remove(&journal_path)?;
+        }
+        Ok(())
+    }
+
+    fn journal_path(&self) -> PathBuf {
+        self.dir.join(JOURNAL_FILE_ID)
+    }
+}
+
+impl PendingUpdates {
+    /// Encode the updates as a journal.
+    ///
+    /// The layout is the header (the magic, the size of the file, the size of
+    /// the committed data that is kept, the number of the removals and the
+    /// number of the pages), the removals (each is the
+    /// length of the id and the id), the pages (each is the index of the page
+    /// and the data) and the commit record. All the integers are u64 in little
+    /// endian.
+    fn encode(&self) -> Vec<u8> {
+        fn put_u64(data: &mut Vec<u8>, val: u64) {
+            data.extend_from_slice(&val.to_le_bytes());
+        }
+
+        let mut data = Vec::new();
+        put_u64(&mut data, JOURNAL_MAGIC);
+        put_u64(&mut data, self.size as u64);
+        put_u64(&mut data, self.kept_size as u64);
+        put_u64(&mut data, self.removals.len() as u64);
+        put_u64(&mut data, self.pages.len() as u64);
+        for file_id in &self.removals {
+            put_u64(&mut data, file_id.len() as u64);
+            data.extend_from_slice(file_id.as_bytes());
+        }
+        for (page_idx, page) in &self.pages {
+            put_u64(&mut data, *page_idx as u64);
+            data.extend_from_slice(page);
+        }
+        put_u64(&mut data, COMMIT_MAGIC);
+        data
+    }
+}
+
+impl<'a> JournalRecord<'a> {
+    /// Decode a journal, or return None if it is incomplete or corrupted.
+    fn decode(data: &'a [u8]) -> Option<Self> {
+        let mut reader = JournalReader { data, pos: 0 };
+        if reader.take_u64()? != JOURNAL_MAGIC {
+            return None;
+        }
+        let size = reader.take_u64()? as usize;
+        let kept_size = reader.take_u64()? as usize;
+        let num_removals = reader.take_u64()? as usize;
+        let num_pages = reader.take_u64()? as usize;
+        let mut removals = Vec::new();
+        for _ in 0..num_removals {
+            let len = reader.take_u64()? as usize;
+            let file_id = std::str::from_utf8(reader.take(len)?).ok()?;
+            removals.push(file_id.to_string());
+        }
+        let mut pages = Vec::new();
+        for _ in 0..num_pages {
+            let page_idx = reader.take_u64()? as usize;
+            pages.push((page_idx, reader.take(PAGE_SIZE)?));
+        }
+        // Only a journal ending with the commit record is complete
+        if reader.take_u64()? != COMMIT_MAGIC || reader.pos != data.len() {
+            return None;
+        }
+        Some(Self {
+            size,
+            kept_size,
+            removals,
+            pages,
+        })
+    }
+}
+
+struct JournalReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> JournalReader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn take_u64(&mut self) -> Option<u64> {
+        let bytes = self.take(8)?;
+        Some(u64::from_le_bytes(bytes.try_into().ok()?))
+    }
+}
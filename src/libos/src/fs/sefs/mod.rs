@@ -1,7 +1,17 @@
 use super::{sgx_aes_gcm_128bit_tag_t, sgx_key_128bit_t};
 
-pub use self::sgx_storage::SgxStorage;
+pub use self::page_cache::{without_page_cache, PageCache, PAGE_CACHE, PAGE_SIZE};
+pub use self::rekey::{
+    recover_interrupted_rekey, rekey_progress, rekey_writable_layer, set_writable_layer,
+    RekeyProgress,
+};
+pub use self::sgx_storage::{sync_metadata_files, SgxStorage, SharedSgxStorage};
 pub use self::sgx_uuid_provider::SgxUuidProvider;
+pub use self::xattr_file::XattrFile;
 
+mod journal;
+mod page_cache;
+mod rekey;
 mod sgx_storage;
 mod sgx_uuid_provider;
+mod xattr_file;
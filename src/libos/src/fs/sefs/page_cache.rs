@@ -0,0 +1,440 @@
+//! An in-enclave cache of the decrypted pages of SEFS files.
+//!
+//! Reading a page of an SGX protected file decrypts and verifies the page, and
+//! may also issue OCalls to fetch it from the host. The page cache keeps the
+//! plaintext pages of the hot files inside the enclave, so repeated reads and
+//! writes are served from the enclave memory. Writes are buffered in dirty
+//! pages, which are written back when the file is flushed or when the pages are
+//! evicted. When the cache is full, the least recently used pages are evicted.
+//!
+//! The size of the cache is given by `resource_limits.page_cache_size` in the
+//! config. A zero size disables the cache.
+//!
+//! Direct I/O (i.e., O_DIRECT) bypasses the cache, see `without_page_cache`.
+
+use super::sgx_storage::CachedSgxFile;
+use crate::config::LIBOS_CONFIG;
+use crate::prelude::*;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, SgxMutex as Mutex, Weak};
+
+pub const PAGE_SIZE: usize = 4096;
+
+/// The max number of the least recently used pages to look at for a victim
+const MAX_EVICTION_CANDIDATES: usize = 16;
+
+lazy_static! {
+    pub static ref PAGE_CACHE: PageCache = {
+        let capacity = LIBOS_CONFIG.resource_limits.page_cache_size / PAGE_SIZE;
+        PageCache::new(capacity)
+    };
+}
+
+thread_local! {
+    // Whether the SEFS I/O issued by this thread bypasses the page cache
+    static BYPASS_PAGE_CACHE: Cell<bool> = Cell::new(false);
+}
+
+/// Do the SEFS I/O in the closure without the page cache, e.g., for direct I/O.
+///
+/// The I/O reads and writes the protected files directly. To be coherent with
+/// the cache, the cached pages in the range of the I/O are written back (if
+/// dirty) and dropped before the I/O.
+pub fn without_page_cache<R>(f: impl FnOnce() -> R) -> R {
+    let old_bypass = BYPASS_PAGE_CACHE.with(|bypass| bypass.replace(true));
+    let ret = f();
+    BYPASS_PAGE_CACHE.with(|bypass| bypass.set(old_bypass));
+    ret
+}
+
+/// A page is identified by the id of its file and its index in the file.
+type PageKey = (u64, usize);
+
+pub struct PageCache {
+    /// The max number of the cached pages
+    capacity: usize,
+    inner: Mutex<PageCacheInner>,
+    stats: PageCacheStats,
+}
+
+struct PageCacheInner {
+    pages: BTreeMap<PageKey, CachedPage>,
+    /// The keys of the pages ordered by the time of their last accesses
+    lru: BTreeMap<u64, PageKey>,
+    clock: u64,
+    num_dirty: usize,
+}
+
+struct CachedPage {
+    data: Box<[u8]>,
+    is_dirty: bool,
+    last_access: u64,
+    /// The file to write back the page when it is evicted
+    file: Weak<Mutex<CachedSgxFile>>,
+}
+
+/// The statistics of the page cache.
+#[derive(Default)]
+pub struct PageCacheStats {
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    evictions: AtomicUsize,
+    writebacks: AtomicUsize,
+}
+
+impl PageCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(PageCacheInner {
+                pages: BTreeMap::new(),
+                lru: BTreeMap::new(),
+                clock: 0,
+                num_dirty: 0,
+            }),
+            stats: Default::default(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Whether the I/O of the current thread goes through the cache.
+    pub fn is_used(&self) -> bool {
+        self.is_enabled() && !BYPASS_PAGE_CACHE.with(|bypass| bypass.get())
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Read the file through the cache.
+    ///
+    /// The caller must hold the lock of the file, i.e., `file` is locked from
+    /// `file_ref`.
+    pub fn read_at(
+        &self,
+        file_ref: &Weak<Mutex<CachedSgxFile>>,
+        file: &mut CachedSgxFile,
+        buf: &mut [u8],
+        offset: usize,
+    ) -> Result<usize> {
+        let file_size = file.size();
+        if offset >= file_size {
+            return Ok(0);
+        }
+        let end = file_size.min(offset + buf.len());
+
+        let mut pos = offset;
+        while pos < end {
+            let page_idx = pos / PAGE_SIZE;
+            let page_offset = pos % PAGE_SIZE;
+            let len = (PAGE_SIZE - page_offset).min(end - pos);
+            let buf_offset = pos - offset;
+            self.access_page(file_ref, file, page_idx, true, |data| {
+                buf[buf_offset..buf_offset + len]
+                    .copy_from_slice(&data[page_offset..page_offset + len]);
+                false
+            })?;
+            pos += len;
+        }
+        Ok(end - offset)
+    }
+
+    /// Write the file through the cache, which only updates the cached pages.
+    ///
+    /// The caller must hold the lock of the file, i.e., `file` is locked from
+    /// `file_ref`.
+    pub fn write_at(
+        &self,
+        file_ref: &Weak<Mutex<CachedSgxFile>>,
+        file: &mut CachedSgxFile,
+        buf: &[u8],
+        offset: usize,
+    ) -> Result<usize> {
+        let end = offset + buf.len();
+        let mut pos = offset;
+        while pos < end {
+            let page_idx = pos / PAGE_SIZE;
+            let page_offset = pos % PAGE_SIZE;
+            let len = (PAGE_SIZE - page_offset).min(end - pos);
+            let buf_offset = pos - offset;
+            // A page that is fully overwritten needs not to be loaded
+            let need_load = len < PAGE_SIZE;
+            self.access_page(file_ref, file, page_idx, need_load, |data| {
+                data[page_offset..page_offset + len]
+                    .copy_from_slice(&buf[buf_offset..buf_offset + len]);
+                true
+            })?;
+            pos += len;
+            file.set_size(file.size().max(pos));
+        }
+        Ok(buf.len())
+    }
+
+    /// Write back the dirty pages of the file.
+    pub fn flush_file(&self, file: &mut CachedSgxFile) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        let file_id = file.id();
+        let dirty_pages: Vec<usize> = inner
+            .pages
+            .range((file_id, 0)..=(file_id, usize::max_value()))
+            .filter(|(_, page)| page.is_dirty)
+            .map(|((_, page_idx), _)| *page_idx)
+            .collect();
+        // Write back in the order of the offsets to avoid padding holes
+        for page_idx in dirty_pages {
+            let page = inner.pages.get_mut(&(file_id, page_idx)).unwrap();
+            Self::write_back(file, page_idx, &page.data)?;
+            page.is_dirty = false;
+            inner.num_dirty -= 1;
+            self.stats.writebacks.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Write back the dirty pages of all the files.
+    ///
+    /// The files that are being dropped flush their own pages, thus are skipped.
+    pub fn flush_all(&self) -> Result<()> {
+        // Collect the files first, since the lock of a file must be acquired
+        // before the lock of the cache
+        let files: Vec<Arc<Mutex<CachedSgxFile>>> = {
+            let inner = self.inner.lock().unwrap();
+            let mut last_file_id = None;
+            inner
+                .pages
+                .iter()
+                .filter(|(_, page)| page.is_dirty)
+                .filter(|((file_id, _), _)| {
+                    let is_new_file = last_file_id != Some(*file_id);
+                    last_file_id = Some(*file_id);
+                    is_new_file
+                })
+                .filter_map(|(_, page)| page.file.upgrade())
+                .collect()
+        };
+        for file in files {
+            let mut file = file.lock().unwrap();
+            self.flush_file(&mut file)?;
+        }
+        Ok(())
+    }
+
+    /// Write back the dirty pages of the file in the range and drop the pages
+    /// in the range.
+    pub fn invalidate_range(
+        &self,
+        file: &mut CachedSgxFile,
+        offset: usize,
+        len: usize,
+    ) -> Result<()> {
+        if len == 0 {
+            return Ok(());
+        }
+        let mut inner = self.inner.lock().unwrap();
+        let file_id = file.id();
+        let first_page = offset / PAGE_SIZE;
+        let last_page = (offset + len - 1) / PAGE_SIZE;
+        let keys: Vec<PageKey> = inner
+            .pages
+            .range((file_id, first_page)..=(file_id, last_page))
+            .map(|(key, _)| *key)
+            .collect();
+        for key in keys {
+            let page = inner.pages.get(&key).unwrap();
+            if page.is_dirty {
+                Self::write_back(file, key.1, &page.data)?;
+                self.stats.writebacks.fetch_add(1, Ordering::Relaxed);
+            }
+            inner.remove_page(&key);
+        }
+        Ok(())
+    }
+
+    /// Drop all the pages of the file without writing them back.
+    pub fn discard_file(&self, file_id: u64) {
+        let mut inner = self.inner.lock().unwrap();
+        let keys: Vec<PageKey> = inner
+            .pages
+            .range((file_id, 0)..=(file_id, usize::max_value()))
+            .map(|(key, _)| *key)
+            .collect();
+        for key in keys {
+            inner.remove_page(&key);
+        }
+    }
+
+    pub fn stats(&self) -> &PageCacheStats {
+        &self.stats
+    }
+
+    pub fn num_pages(&self) -> usize {
+        self.inner.lock().unwrap().pages.len()
+    }
+
+    pub fn num_dirty_pages(&self) -> usize {
+        self.inner.lock().unwrap().num_dirty
+    }
+
+    /// Access a page of the file, which is loaded into the cache on a miss.
+    ///
+    /// The closure returns whether the page is modified.
+    fn access_page(
+        &self,
+        file_ref: &Weak<Mutex<CachedSgxFile>>,
+        file: &mut CachedSgxFile,
+        page_idx: usize,
+        need_load: bool,
+        f: impl FnOnce(&mut [u8]) -> bool,
+    ) -> Result<()> {
+        let key = (file.id(), page_idx);
+        {
+            let mut inner = self.inner.lock().unwrap();
+            if let Some(page) = inner.pages.get_mut(&key) {
+                let is_modified = f(&mut page.data);
+                if is_modified && !page.is_dirty {
+                    page.is_dirty = true;
+                    inner.num_dirty += 1;
+                }
+                inner.touch(&key);
+                self.stats.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(());
+            }
+        }
+        self.stats.misses.fetch_add(1, Ordering::Relaxed);
+
+        // Decrypt the page without holding the lock of the cache. No one else
+        // can insert the page since the lock of the file is held.
+        let mut data = vec![0u8; PAGE_SIZE].into_boxed_slice();
+        if need_load && page_idx * PAGE_SIZE < file.size() {
+            file.read_page(page_idx, &mut data)?;
+        }
+        let is_dirty = f(&mut data);
+
+        // The upgraded files of the victims must be dropped after the lock of
+        // the cache is released
+        let mut victim_files = Vec::new();
+        let mut inner = self.inner.lock().unwrap();
+        while inner.pages.len() >= self.capacity {
+            match self.evict_one(&mut inner, file, &mut victim_files)? {
+                true => self.stats.evictions.fetch_add(1, Ordering::Relaxed),
+                // All the candidates are in use, so let the cache grow slightly
+                false => break,
+            };
+        }
+        let last_access = inner.tick();
+        inner.pages.insert(
+            key,
+            CachedPage {
+                data,
+                is_dirty,
+                last_access,
+                file: file_ref.clone(),
+            },
+        );
+        inner.lru.insert(last_access, key);
+        if is_dirty {
+            inner.num_dirty += 1;
+        }
+        drop(inner);
+        Ok(())
+    }
+
+    /// Evict one of the least recently used pages, writing it back if dirty.
+    ///
+    /// The page of another file is only evicted if the lock of the file is free,
+    /// which avoids deadlocks.
+    fn evict_one(
+        &self,
+        inner: &mut PageCacheInner,
+        current: &mut CachedSgxFile,
+        victim_files: &mut Vec<Arc<Mutex<CachedSgxFile>>>,
+    ) -> Result<bool> {
+        let candidates: Vec<PageKey> = inner
+            .lru
+            .values()
+            .take(MAX_EVICTION_CANDIDATES)
+            .cloned()
+            .collect();
+        for key in candidates {
+            let page = inner.pages.get(&key).unwrap();
+            if page.is_dirty {
+                let (file_id, page_idx) = key;
+                if file_id == current.id() {
+                    Self::write_back(current, page_idx, &page.data)?;
+                } else {
+                    // A file being dropped flushes its own pages
+                    let file = match page.file.upgrade() {
+                        Some(file) => file,
+                        None => continue,
+                    };
+                    {
+                        let mut file = match file.try_lock() {
+                            Ok(file) => file,
+                            Err(_) => continue,
+                        };
+                        Self::write_back(&mut file, page_idx, &page.data)?;
+                    }
+                    victim_files.push(file);
+                }
+                self.stats.writebacks.fetch_add(1, Ordering::Relaxed);
+            }
+            inner.remove_page(&key);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn write_back(file: &mut CachedSgxFile, page_idx: usize, data: &[u8]) -> Result<()> {
+        // Do not extend the file with the padding of the last page
+        let offset = page_idx * PAGE_SIZE;
+        let len = PAGE_SIZE.min(file.size().saturating_sub(offset));
+        file.write_page(page_idx, &data[..len])
+    }
+}
+
+impl PageCacheInner {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn touch(&mut self, key: &PageKey) {
+        let last_access = self.tick();
+        let page = self.pages.get_mut(key).unwrap();
+        let old_access = std::mem::replace(&mut page.last_access, last_access);
+        self.lru.remove(&old_access);
+        self.lru.insert(last_access, *key);
+    }
+
+    fn remove_page(&mut self, key: &PageKey) {
+        if let Some(page) = self.pages.remove(key) {
+            self.lru.remove(&page.last_access);
+            if page.is_dirty {
+                self.num_dirty -= 1;
+            }
+        }
+    }
+}
+
+impl PageCacheStats {
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    pub fn evictions(&self) -> usize {
+        self.evictions.load(Ordering::Relaxed)
+    }
+
+    pub fn writebacks(&self) -> usize {
+        self.writebacks.load(Ordering::Relaxed)
+    }
+}
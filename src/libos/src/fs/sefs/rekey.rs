@@ -0,0 +1,272 @@
+//! The re-encryption of the writable SEFS with a new key, i.e., key rotation.
+//!
+//! The files of SEFS are re-encrypted into a staging directory next to the
+//! directory of SEFS. Since an SGX protected file is bound to its name, but not
+//! to its directory, the staging directory replaces the directory of SEFS once
+//! all the files are re-encrypted. The names of the re-encrypted files are
+//! recorded in a progress file in the staging directory, which is encrypted
+//! with the new key.
+//!
+//! A re-encryption interrupted by a crash is resumed by re-encrypting with the
+//! same new key again, which skips the files recorded in the progress file.
+//! Until the directories are swapped, SEFS is still encrypted with the old key.
+//! After that, SEFS must be mounted with the new key.
+
+use super::sgx_storage::{EncryptMode, SgxStorage};
+use crate::prelude::*;
+use crate::std::untrusted::path::PathEx;
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sgxfs::{OpenOptions, SgxFile};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::untrusted::fs;
+
+const PROGRESS_FILE_ID: &str = "rekey.progress";
+
+/// The size of the chunks in which the files are copied
+const CHUNK_SIZE: usize = 64 * 1024;
+
+lazy_static! {
+    /// The storage of the writable layer of the root file system
+    static ref WRITABLE_LAYER: SgxMutex<Option<Arc<SgxStorage>>> = SgxMutex::new(None);
+    static ref REKEY_PROGRESS: RekeyProgress = RekeyProgress::default();
+}
+
+/// The progress of the re-encryption.
+#[derive(Default)]
+pub struct RekeyProgress {
+    is_running: AtomicBool,
+    total_files: AtomicUsize,
+    done_files: AtomicUsize,
+}
+
+impl RekeyProgress {
+    pub fn is_running(&self) -> bool {
+        self.is_running.load(Ordering::SeqCst)
+    }
+
+    pub fn total_files(&self) -> usize {
+        self.total_files.load(Ordering::SeqCst)
+    }
+
+    pub fn done_files(&self) -> usize {
+        self.done_files.load(Ordering::SeqCst)
+    }
+}
+
+/// Set the storage of the writable layer of the root file system.
+pub fn set_writable_layer(storage: Arc<SgxStorage>) {
+    *WRITABLE_LAYER.lock().unwrap() = Some(storage);
+}
+
+/// Re-encrypt the writable layer of the root file system with the new key.
+pub fn rekey_writable_layer(new_key: &sgx_key_128bit_t) -> Result<()> {
+    let storage = WRITABLE_LAYER
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| errno!(ENOENT, "no writable layer to re-encrypt"))?;
+    if REKEY_PROGRESS
+        .is_running
+        .compare_and_swap(false, true, Ordering::SeqCst)
+    {
+        return_errno!(EBUSY, "the re-encryption is in progress");
+    }
+
+    info!("re-encrypt the writable layer of the root file system");
+    let res = storage.rekey(new_key, &REKEY_PROGRESS);
+    REKEY_PROGRESS.is_running.store(false, Ordering::SeqCst);
+    res
+}
+
+pub fn rekey_progress() -> &'static RekeyProgress {
+    &REKEY_PROGRESS
+}
+
+/// Finish or clean up a re-encryption interrupted by a crash when swapping the
+/// directories.
+///
+/// An incomplete re-encryption is kept to be resumed later.
+pub fn recover_interrupted_rekey(dir: &Path) -> Result<()> {
+    let staging_dir = sibling_dir(dir, "rekey");
+    let old_dir = sibling_dir(dir, "old");
+    if !dir.exists() && staging_dir.exists() {
+        warn!("finish the interrupted re-encryption of {:?}", dir);
+        fs::rename(&staging_dir, dir)?;
+    }
+    if old_dir.exists() {
+        fs::remove_dir_all(&old_dir)?;
+    }
+    Ok(())
+}
+
+/// The re-encryption of the files in a directory.
+pub struct Rekey<'a> {
+    dir: PathBuf,
+    staging_dir: PathBuf,
+    old_mode: EncryptMode,
+    new_mode: EncryptMode,
+    /// The ids of all the files in the directory
+    file_ids: Vec<String>,
+    /// The ids of the files that have been re-encrypted
+    done_file_ids: HashSet<String>,
+    progress_file: SgxFile,
+    progress: &'a RekeyProgress,
+}
+
+impl<'a> Rekey<'a> {
+    /// Start or resume the re-encryption of the directory.
+    pub fn new(
+        dir: &Path,
+        old_mode: EncryptMode,
+        new_mode: EncryptMode,
+        progress: &'a RekeyProgress,
+    ) -> Result<Self> {
+        let staging_dir = sibling_dir(dir, "rekey");
+        fs::create_dir_all(&staging_dir)?;
+
+        let progress_path = staging_dir.join(PROGRESS_FILE_ID);
+        let done_file_ids = match Self::read_progress(&progress_path, new_mode) {
+            Some(done_file_ids) => {
+                info!("resume the re-encryption of {:?}", dir);
+                done_file_ids
+            }
+            // There is no progress to resume, or the new key is different
+            None => {
+                fs::remove_dir_all(&staging_dir)?;
+                fs::create_dir_all(&staging_dir)?;
+                HashSet::new()
+            }
+        };
+        let progress_file = {
+            let mut options = OpenOptions::new();
+            options.append(true);
+            new_mode.open_file(&progress_path, &options)?
+        };
+
+        let mut file_ids = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let file_id = entry?
+                .file_name()
+                .into_string()
+                .map_err(|_| errno!(EINVAL))?;
+            file_ids.push(file_id);
+        }
+        file_ids.sort();
+
+        progress.total_files.store(file_ids.len(), Ordering::SeqCst);
+        progress
+            .done_files
+            .store(done_file_ids.len(), Ordering::SeqCst);
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            staging_dir,
+            old_mode,
+            new_mode,
+            file_ids,
+            done_file_ids,
+            progress_file,
+            progress,
+        })
+    }
+
+    fn read_progress(progress_path: &Path, new_mode: EncryptMode) -> Option<HashSet<String>> {
+        if !progress_path.exists() {
+            return None;
+        }
+        let options = {
+            let mut options = OpenOptions::new();
+            options.read(true);
+            options
+        };
+        let mut progress_file = new_mode.open_file(progress_path, &options).ok()?;
+        let mut content = String::new();
+        progress_file.read_to_string(&mut content).ok()?;
+        Some(content.lines().map(|line| line.to_string()).collect())
+    }
+
+    pub fn all_file_ids(&self) -> &[String] {
+        &self.file_ids
+    }
+
+    /// The ids of the files that are not re-encrypted yet.
+    pub fn file_ids(&self) -> Vec<String> {
+        self.file_ids
+            .iter()
+            .filter(|file_id| !self.done_file_ids.contains(*file_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Re-encrypt the file, which is opened with the old key.
+    pub fn reencrypt(&mut self, file_id: &str) -> Result<()> {
+        let options = {
+            let mut options = OpenOptions::new();
+            options.read(true);
+            options
+        };
+        let mut old_file = self.old_mode.open_file(&self.dir.join(file_id), &options)?;
+        self.reencrypt_with(file_id, |buf, _| Ok(old_file.read(buf)?))
+    }
+
+    /// Re-encrypt the file, whose content is read by the given function.
+    pub fn reencrypt_with(
+        &mut self,
+        file_id: &str,
+        mut read_at: impl FnMut(&mut [u8], usize) -> Result<usize>,
+    ) -> Result<()> {
+        let mut new_file = {
+            let mut options = OpenOptions::new();
+            options.write(true);
+            self.new_mode
+                .open_file(&self.staging_dir.join(file_id), &options)?
+        };
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        let mut offset = 0;
+        loop {
+            let len = read_at(&mut buf, offset)?;
+            if len == 0 {
+                break;
+            }
+            new_file.write_all(&buf[..len])?;
+            offset += len;
+        }
+        new_file.flush()?;
+        drop(new_file);
+
+        // Record the progress after the file is persistent
+        writeln!(self.progress_file, "{}", file_id)?;
+        self.progress_file.flush()?;
+        self.done_file_ids.insert(file_id.to_string());
+        self.progress.done_files.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Replace the directory with the staging directory.
+    pub fn commit(&mut self) -> Result<()> {
+        fs::remove_file(self.staging_dir.join(PROGRESS_FILE_ID))?;
+        let old_dir = sibling_dir(&self.dir, "old");
+        fs::rename(&self.dir, &old_dir)?;
+        fs::rename(&self.staging_dir, &self.dir)?;
+        Ok(())
+    }
+
+    /// Remove the old directory after the files are reopened.
+    pub fn finish(self) -> Result<()> {
+        fs::remove_dir_all(sibling_dir(&self.dir, "old"))?;
+        info!("the re-encryption of {:?} is done", self.dir);
+        Ok(())
+    }
+}
+
+/// Get the path of `<dir>.<suffix>`.
+fn sibling_dir(dir: &Path, suffix: &str) -> PathBuf {
+    let mut name = dir
+        .file_name()
+        .map(|name| name.to_os_string())
+        .unwrap_or_default();
+    name.push(".");
+    name.push(suffix);
+    dir.with_file_name(name)
+}
@@ -1,3 +1,6 @@
+use super::journal::JournaledFile;
+use super::page_cache::{PAGE_CACHE, PAGE_SIZE};
+use super::rekey::{Rekey, RekeyProgress};
 use super::*;
 use crate::error::*;
 use rcore_fs::dev::{DevError, DevResult};
@@ -9,9 +12,48 @@ use std::hash::{Hash, Hasher};
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 use std::sgxfs::{remove, OpenOptions, SgxFile};
-use std::sync::{Arc, SgxMutex as Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, SgxMutex as Mutex, SgxMutexGuard as MutexGuard, SgxRwLock as RwLock, Weak};
 use std::untrusted::fs;
 
+/// The id of the metadata file of SEFS
+pub const METADATA_FILE_ID: &str = "metadata";
+
+lazy_static! {
+    /// The metadata files of all the SEFS instances, indexed by the ids of the files
+    static ref METADATA_FILES: Mutex<BTreeMap<u64, Weak<dyn MetadataFile>>> =
+        Mutex::new(BTreeMap::new());
+}
+
+/// The metadata file of a SEFS instance, which is flushed by `sync_metadata_files`.
+pub trait MetadataFile: Send + Sync {
+    fn sync(&self) -> Result<()>;
+}
+
+pub fn register_metadata_file(id: u64, file: Weak<dyn MetadataFile>) {
+    METADATA_FILES.lock().unwrap().insert(id, file);
+}
+
+/// Flush the metadata files of all the SEFS instances that have been modified.
+///
+/// The metadata of an inode, e.g., its size, is stored in the metadata file of
+/// its SEFS, thus syncing an inode flushes both its data file and the metadata
+/// file, without touching the other data files.
+pub fn sync_metadata_files() -> Result<()> {
+    let files: Vec<Arc<dyn MetadataFile>> = {
+        let mut metadata_files = METADATA_FILES.lock().unwrap();
+        metadata_files.retain(|_, file| file.upgrade().is_some());
+        metadata_files
+            .values()
+            .filter_map(|file| file.upgrade())
+            .collect()
+    };
+    for file in files {
+        file.sync()?;
+    }
+    Ok(())
+}
+
 /// A helper macro to automatically convert a block of code that returns `std::result::Result<T, E1>`
 /// to one that returns `std::result::Result<T, E2>`, where `E1` satisfies `impl From<E1> for Error`
 /// and `E2` satisfies `impl From<Error> for E2`.
@@ -28,8 +70,10 @@ macro_rules! convert_result {
 
 pub struct SgxStorage {
     path: PathBuf,
-    encrypt_mode: EncryptMode,
+    encrypt_mode: RwLock<EncryptMode>,
     file_cache: Mutex<BTreeMap<u64, LockedFile>>,
+    /// The metadata file with the journal, which is shared by all the opens
+    journaled_metadata: Mutex<Option<JournaledFile>>,
 }
 
 impl SgxStorage {
@@ -41,8 +85,9 @@ impl SgxStorage {
         // assert!(path.as_ref().is_dir());
         SgxStorage {
             path: path.as_ref().to_path_buf(),
-            encrypt_mode: EncryptMode::new(key, root_mac),
+            encrypt_mode: RwLock::new(EncryptMode::new(key, root_mac)),
             file_cache: Mutex::new(BTreeMap::new()),
+            journaled_metadata: Mutex::new(None),
         }
     }
     /// Get file by `file_id`.
@@ -82,6 +127,124 @@ impl SgxStorage {
     ) -> Result<LockedFile> {
         open_fn(self)
     }
+
+    fn encrypt_mode(&self) -> EncryptMode {
+        *self.encrypt_mode.read().unwrap()
+    }
+
+    /// Re-encrypt all the files of the storage with the new key.
+    ///
+    /// All the updates to the storage are blocked until the re-encryption is
+    /// done. The opened files are reopened with the new key afterwards, so the
+    /// file system keeps working without being remounted.
+    #[cfg(feature = "sgx_file_cache")]
+    pub fn rekey(&self, new_key: &sgx_key_128bit_t, progress: &RekeyProgress) -> Result<()> {
+        let old_mode = self.encrypt_mode();
+        let new_mode = match old_mode {
+            EncryptMode::Encrypt(_) | EncryptMode::EncryptAutoKey => EncryptMode::Encrypt(*new_key),
+            _ => return_errno!(EINVAL, "integrity protected SEFS cannot be re-encrypted"),
+        };
+
+        // Block the updates to the metadata, the opens and the I/O of the
+        // opened files, in the same order as the other paths
+        let journaled_metadata = self.journaled_metadata.lock().unwrap().clone();
+        let journal_lock = match journaled_metadata.as_ref() {
+            Some(journaled_file) => Some(journaled_file.commit_and_lock()?),
+            None => None,
+        };
+        let caches = self.file_cache.lock().unwrap();
+        let mut opened_files: BTreeMap<u64, MutexGuard<CachedSgxFile>> = caches
+            .iter()
+            .map(|(key, locked_file)| (*key, locked_file.0.lock().unwrap()))
+            .collect();
+        for file in opened_files.values_mut() {
+            file.flush()?;
+        }
+
+        let mut rekey = Rekey::new(&self.path, old_mode, new_mode, progress)?;
+        for file_id in rekey.file_ids() {
+            match opened_files.get_mut(&self.calculate_hash(&file_id)) {
+                Some(file) => rekey
+                    .reencrypt_with(&file_id, |buf, offset| file.read_at_uncached(buf, offset))?,
+                None => rekey.reencrypt(&file_id)?,
+            }
+        }
+        rekey.commit()?;
+
+        // The files are replaced by the re-encrypted ones, so switch to the new key
+        *self.encrypt_mode.write().unwrap() = new_mode;
+        if let (Some(journaled_file), Some(journal_lock)) = (&journaled_metadata, &journal_lock) {
+            journaled_file.set_encrypt_mode(journal_lock, new_mode);
+        }
+        for file_id in rekey.all_file_ids() {
+            if let Some(file) = opened_files.get_mut(&self.calculate_hash(file_id)) {
+                let options = {
+                    let mut options = OpenOptions::new();
+                    options.read(true).update(true);
+                    options
+                };
+                file.file = new_mode.open_file(&self.path.join(file_id), &options)?;
+            }
+        }
+        rekey.finish()
+    }
+
+    #[cfg(not(feature = "sgx_file_cache"))]
+    pub fn rekey(&self, new_key: &sgx_key_128bit_t, progress: &RekeyProgress) -> Result<()> {
+        return_errno!(
+            ENOSYS,
+            "re-encryption requires the cache of the opened files"
+        );
+    }
+
+    /// Whether the updates to the metadata are journaled.
+    ///
+    /// The integrity protected SEFS is read-only, whose MAC of the metadata
+    /// file is fixed, so it needs no journal.
+    fn is_journaled(&self) -> bool {
+        !self.protect_integrity()
+    }
+
+    fn wrap_metadata_file(&self, file: LockedFile, is_new: bool) -> Result<Box<dyn File>> {
+        if !self.is_journaled() {
+            file.register_metadata_file();
+            return Ok(Box::new(file));
+        }
+
+        let mut journaled_metadata = self.journaled_metadata.lock().unwrap();
+        if journaled_metadata.is_none() {
+            let journaled_file =
+                JournaledFile::new(file, self.path.clone(), self.encrypt_mode(), is_new)?;
+            *journaled_metadata = Some(journaled_file);
+        }
+        Ok(Box::new(journaled_metadata.as_ref().unwrap().clone()))
+    }
+}
+
+/// A storage that is shared, so that it can be re-encrypted while being used
+/// by SEFS.
+pub struct SharedSgxStorage(pub Arc<SgxStorage>);
+
+impl Storage for SharedSgxStorage {
+    fn open(&self, file_id: &str) -> DevResult<Box<dyn File>> {
+        self.0.open(file_id)
+    }
+
+    fn create(&self, file_id: &str) -> DevResult<Box<dyn File>> {
+        self.0.create(file_id)
+    }
+
+    fn remove(&self, file_id: &str) -> DevResult<()> {
+        self.0.remove(file_id)
+    }
+
+    fn protect_integrity(&self) -> bool {
+        self.0.protect_integrity()
+    }
+
+    fn clear(&self) -> DevResult<()> {
+        self.0.clear()
+    }
 }
 
 impl Storage for SgxStorage {
@@ -94,29 +257,26 @@ impl Storage for SgxStorage {
                 options.read(true).update(true);
                 options
             };
-            let file = match self.encrypt_mode {
-                EncryptMode::IntegrityOnly(_) => options.open_integrity_only(path)?,
-                EncryptMode::EncryptWithIntegrity(key, _) | EncryptMode::Encrypt(key) => {
-                    options.open_ex(path, &key)?
-                }
-                EncryptMode::EncryptAutoKey => options.open(path)?,
-            };
+            let file = this.encrypt_mode().open_file(&path, &options)?;
 
             // Check the MAC of the root file against the given root MAC of the storage
-            if file_id == "metadata" && self.protect_integrity() {
+            if file_id == METADATA_FILE_ID && self.protect_integrity() {
                 let root_file_mac = file.get_mac().expect("Failed to get mac");
-                if root_file_mac != self.encrypt_mode.root_mac().unwrap() {
+                if root_file_mac != self.encrypt_mode().root_mac().unwrap() {
                     error!(
                         "MAC validation for metadata file failed: expected = {:#?}, found = {:?}",
-                        self.encrypt_mode.root_mac().unwrap(),
+                        self.encrypt_mode().root_mac().unwrap(),
                         root_file_mac
                     );
                     return_errno!(EACCES);
                 }
             }
 
-            Ok(LockedFile(Arc::new(Mutex::new(file))))
+            LockedFile::new(file)
         })?;
+        if file_id == METADATA_FILE_ID {
+            return convert_result!({ self.wrap_metadata_file(locked_file, false) });
+        }
         Ok(Box::new(locked_file))
     }
 
@@ -129,33 +289,40 @@ impl Storage for SgxStorage {
                 options.write(true).update(true);
                 options
             };
-            let file = match self.encrypt_mode {
-                EncryptMode::IntegrityOnly(_) => options.open_integrity_only(path)?,
-                EncryptMode::EncryptWithIntegrity(key, _) | EncryptMode::Encrypt(key) => {
-                    options.open_ex(path, &key)?
-                }
-                EncryptMode::EncryptAutoKey => options.open(path)?,
-            };
-            Ok(LockedFile(Arc::new(Mutex::new(file))))
+            let file = this.encrypt_mode().open_file(&path, &options)?;
+            LockedFile::new(file)
         })?;
+        if file_id == METADATA_FILE_ID {
+            return convert_result!({ self.wrap_metadata_file(locked_file, true) });
+        }
         Ok(Box::new(locked_file))
     }
 
     fn remove(&self, file_id: &str) -> DevResult<()> {
         convert_result!({
-            let mut path = self.path.to_path_buf();
-            path.push(file_id);
-            remove(path)?;
             // remove from cache
             let key = self.calculate_hash(file_id);
             let mut caches = self.file_cache.lock().unwrap();
-            caches.remove(&key);
+            if let Some(locked_file) = caches.remove(&key) {
+                locked_file.discard_cached_pages();
+            }
+            drop(caches);
+
+            // The file is still referred to by the metadata on the disk until
+            // the metadata is committed
+            if let Some(journaled_file) = self.journaled_metadata.lock().unwrap().as_ref() {
+                journaled_file.defer_removal(file_id);
+                return Ok(());
+            }
+            let mut path = self.path.to_path_buf();
+            path.push(file_id);
+            remove(path)?;
             Ok(())
         })
     }
 
     fn protect_integrity(&self) -> bool {
-        match self.encrypt_mode {
+        match self.encrypt_mode() {
             EncryptMode::IntegrityOnly(_) | EncryptMode::EncryptWithIntegrity(_, _) => true,
             _ => false,
         }
@@ -169,13 +336,18 @@ impl Storage for SgxStorage {
             }
             // clear cache
             let mut caches = self.file_cache.lock().unwrap();
+            for locked_file in caches.values() {
+                locked_file.discard_cached_pages();
+            }
             caches.clear();
+            *self.journaled_metadata.lock().unwrap() = None;
             Ok(())
         })
     }
 }
 
-enum EncryptMode {
+#[derive(Clone, Copy)]
+pub enum EncryptMode {
     IntegrityOnly(sgx_aes_gcm_128bit_tag_t),
     EncryptWithIntegrity(sgx_key_128bit_t, sgx_aes_gcm_128bit_tag_t),
     Encrypt(sgx_key_128bit_t),
@@ -195,6 +367,17 @@ impl EncryptMode {
         }
     }
 
+    pub fn open_file(&self, path: &Path, options: &OpenOptions) -> Result<SgxFile> {
+        let file = match self {
+            EncryptMode::IntegrityOnly(_) => options.open_integrity_only(path)?,
+            EncryptMode::EncryptWithIntegrity(key, _) | EncryptMode::Encrypt(key) => {
+                options.open_ex(path, key)?
+            }
+            EncryptMode::EncryptAutoKey => options.open(path)?,
+        };
+        Ok(file)
+    }
+
     pub fn root_mac(&self) -> Option<sgx_aes_gcm_128bit_tag_t> {
         match self {
             Self::IntegrityOnly(root_mac) | Self::EncryptWithIntegrity(_, root_mac) => {
@@ -206,59 +389,77 @@ impl EncryptMode {
 }
 
 #[derive(Clone)]
-pub struct LockedFile(Arc<Mutex<SgxFile>>);
+pub struct LockedFile(Arc<Mutex<CachedSgxFile>>);
 
 // `sgx_tstd::sgxfs::SgxFile` not impl Send ...
 unsafe impl Send for LockedFile {}
 unsafe impl Sync for LockedFile {}
 
-impl File for LockedFile {
-    fn read_at(&self, buf: &mut [u8], offset: usize) -> DevResult<usize> {
-        convert_result!({
-            if buf.len() == 0 {
-                return Ok(0);
-            }
-            let mut file = self.0.lock().unwrap();
+impl LockedFile {
+    fn new(file: SgxFile) -> Result<Self> {
+        Ok(LockedFile(Arc::new(Mutex::new(CachedSgxFile::new(file)?))))
+    }
 
-            // SgxFile does not support to seek a position beyond the end.
-            // So check if file_size < offset and return zero(indicates end of file).
-            let file_size = file.seek(SeekFrom::End(0))? as usize;
-            if file_size < offset {
-                return Ok(0);
-            }
+    /// Drop the cached pages of the removed file.
+    fn discard_cached_pages(&self) {
+        let file = self.0.lock().unwrap();
+        PAGE_CACHE.discard_file(file.id());
+    }
 
-            let offset = offset as u64;
-            file.seek(SeekFrom::Start(offset))?;
-            let len = file.read(buf)?;
-            Ok(len)
-        })
+    fn register_metadata_file(&self) {
+        let id = self.id();
+        register_metadata_file(id, Arc::downgrade(&self.0) as Weak<dyn MetadataFile>);
     }
 
-    fn write_at(&self, buf: &[u8], offset: usize) -> DevResult<usize> {
-        convert_result!({
-            if buf.len() == 0 {
-                return Ok(0);
-            }
-            let mut file = self.0.lock().unwrap();
-
-            // SgxFile does not support to seek a position beyond the end.
-            // So check if file_size < offset and padding null bytes.
-            let file_size = file.seek(SeekFrom::End(0))? as usize;
-            if file_size < offset {
-                static ZEROS: [u8; 0x1000] = [0; 0x1000];
-                let mut rest_len = offset - file_size;
-                while rest_len != 0 {
-                    let l = rest_len.min(0x1000);
-                    let len = file.write(&ZEROS[..l])?;
-                    rest_len -= len;
-                }
-            }
+    pub fn id(&self) -> u64 {
+        self.0.lock().unwrap().id()
+    }
 
-            let offset = offset as u64;
-            file.seek(SeekFrom::Start(offset))?;
-            let len = file.write(buf)?;
-            Ok(len)
-        })
+    pub fn size(&self) -> usize {
+        self.0.lock().unwrap().size()
+    }
+
+    pub fn read(&self, buf: &mut [u8], offset: usize) -> Result<usize> {
+        if buf.len() == 0 {
+            return Ok(0);
+        }
+        let mut file = self.0.lock().unwrap();
+        if PAGE_CACHE.is_used() {
+            return PAGE_CACHE.read_at(&Arc::downgrade(&self.0), &mut file, buf, offset);
+        }
+        if PAGE_CACHE.is_enabled() {
+            PAGE_CACHE.invalidate_range(&mut file, offset, buf.len())?;
+        }
+        file.read_at_uncached(buf, offset)
+    }
+
+    pub fn write(&self, buf: &[u8], offset: usize) -> Result<usize> {
+        if buf.len() == 0 {
+            return Ok(0);
+        }
+        let mut file = self.0.lock().unwrap();
+        file.is_dirty = true;
+        if PAGE_CACHE.is_used() {
+            return PAGE_CACHE.write_at(&Arc::downgrade(&self.0), &mut file, buf, offset);
+        }
+        if PAGE_CACHE.is_enabled() {
+            PAGE_CACHE.invalidate_range(&mut file, offset, buf.len())?;
+        }
+        file.write_at_uncached(buf, offset)
+    }
+
+    pub fn flush_file(&self) -> Result<()> {
+        self.0.lock().unwrap().flush()
+    }
+}
+
+impl File for LockedFile {
+    fn read_at(&self, buf: &mut [u8], offset: usize) -> DevResult<usize> {
+        convert_result!({ self.read(buf, offset) })
+    }
+
+    fn write_at(&self, buf: &[u8], offset: usize) -> DevResult<usize> {
+        convert_result!({ self.write(buf, offset) })
     }
 
     fn set_len(&self, len: usize) -> DevResult<()> {
@@ -267,16 +468,128 @@ impl File for LockedFile {
     }
 
     fn flush(&self) -> DevResult<()> {
-        convert_result!({
-            let mut file = self.0.lock().unwrap();
-            file.flush()?;
-            Ok(())
-        })
+        convert_result!({ self.flush_file() })
     }
 
     fn get_file_mac(&self) -> DevResult<SefsMac> {
-        let file = self.0.lock().unwrap();
-        Ok(SefsMac(file.get_mac().unwrap()))
+        let mut file = self.0.lock().unwrap();
+        // The MAC covers the dirty pages only after they are written back
+        PAGE_CACHE.flush_file(&mut file)?;
+        Ok(SefsMac(file.file.get_mac().unwrap()))
+    }
+}
+
+/// An SGX protected file, whose pages may be cached in the page cache.
+pub struct CachedSgxFile {
+    /// The unique id of the file in the page cache
+    id: u64,
+    file: SgxFile,
+    /// The size of the file, including the data in the dirty pages
+    size: usize,
+    /// Whether the file has been written since the last flush
+    is_dirty: bool,
+}
+
+// `sgx_tstd::sgxfs::SgxFile` not impl Send ...
+unsafe impl Send for CachedSgxFile {}
+
+impl CachedSgxFile {
+    fn new(mut file: SgxFile) -> Result<Self> {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let size = file.seek(SeekFrom::End(0))? as usize;
+        Ok(Self {
+            id,
+            file,
+            size,
+            is_dirty: false,
+        })
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn set_size(&mut self, size: usize) {
+        self.size = size;
+    }
+
+    /// Write back the dirty pages and flush the protected file.
+    ///
+    /// A file that has not been written since the last flush is skipped.
+    fn flush(&mut self) -> Result<()> {
+        if !self.is_dirty {
+            return Ok(());
+        }
+        PAGE_CACHE.flush_file(self)?;
+        self.file.flush()?;
+        self.is_dirty = false;
+        Ok(())
+    }
+
+    /// Read a page from the protected file, which decrypts the page.
+    pub fn read_page(&mut self, page_idx: usize, buf: &mut [u8]) -> Result<usize> {
+        self.read_at_uncached(buf, page_idx * PAGE_SIZE)
+    }
+
+    /// Write a page to the protected file, which encrypts the page.
+    pub fn write_page(&mut self, page_idx: usize, buf: &[u8]) -> Result<()> {
+        self.write_at_uncached(buf, page_idx * PAGE_SIZE)?;
+        Ok(())
+    }
+
+    fn read_at_uncached(&mut self, buf: &mut [u8], offset: usize) -> Result<usize> {
+        // SgxFile does not support to seek a position beyond the end.
+        // So check if file_size < offset and return zero(indicates end of file).
+        let file_size = self.file.seek(SeekFrom::End(0))? as usize;
+        if file_size < offset {
+            return Ok(0);
+        }
+
+        let offset = offset as u64;
+        self.file.seek(SeekFrom::Start(offset))?;
+        let len = self.file.read(buf)?;
+        Ok(len)
+    }
+
+    fn write_at_uncached(&mut self, buf: &[u8], offset: usize) -> Result<usize> {
+        // SgxFile does not support to seek a position beyond the end.
+        // So check if file_size < offset and padding null bytes.
+        let file_size = self.file.seek(SeekFrom::End(0))? as usize;
+        if file_size < offset {
+            static ZEROS: [u8; 0x1000] = [0; 0x1000];
+            let mut rest_len = offset - file_size;
+            while rest_len != 0 {
+                let l = rest_len.min(0x1000);
+                let len = self.file.write(&ZEROS[..l])?;
+                rest_len -= len;
+            }
+        }
+
+        let offset = offset as u64;
+        self.file.seek(SeekFrom::Start(offset))?;
+        let len = self.file.write(buf)?;
+        self.size = self.size.max(offset as usize + len);
+        Ok(len)
+    }
+}
+
+impl MetadataFile for Mutex<CachedSgxFile> {
+    fn sync(&self) -> Result<()> {
+        self.lock().unwrap().flush()
+    }
+}
+
+impl Drop for CachedSgxFile {
+    fn drop(&mut self) {
+        if let Err(e) = PAGE_CACHE.flush_file(self) {
+            error!("failed to write back the cached pages: {:?}", e);
+        }
+        PAGE_CACHE.discard_file(self.id);
     }
 }
 
@@ -0,0 +1,110 @@
+//! The files of the extended attributes of SEFS.
+//!
+//! The on-disk format of the inodes of SEFS has no place for extended
+//! attributes, so they are stored in the files along with the metadata file
+//! of SEFS, which are protected by the same key. Each save rewrites all the
+//! attributes into one of the two files alternately, so that a crash in the
+//! middle of a save leaves the content of the last save in the other file.
+//! The content with the newer generation wins when the files are loaded.
+
+use super::sgx_storage::SgxStorage;
+use crate::prelude::*;
+use rcore_fs_sefs::dev::{File, Storage};
+use std::convert::TryInto;
+
+const XATTR_FILE_IDS: [&str; 2] = ["xattrs.0", "xattrs.1"];
+
+/// The magic number at the beginning of a file, i.e., "SEFSXATR"
+const XATTR_MAGIC: u64 = 0x5254_4158_5346_4553;
+/// The magic number at the end of a complete file, i.e., "SEFSXEND"
+const END_MAGIC: u64 = 0x444e_4558_5346_4553;
+/// The size of the magic number, the generation and the size of the content
+const HEADER_SIZE: usize = 24;
+
+pub struct XattrFile {
+    files: Vec<Box<dyn File>>,
+    /// The generation of the last save
+    generation: u64,
+}
+
+impl XattrFile {
+    /// Open the files of the storage, returning the content of the last save.
+    ///
+    /// The files are created if SEFS is newly created, or has no attributes.
+    pub fn open_or_create(storage: &SgxStorage, is_new: bool) -> Result<(Self, Vec<u8>)> {
+        let mut files = Vec::new();
+        for file_id in XATTR_FILE_IDS.iter() {
+            let file = match storage.open(file_id) {
+                Ok(file) if !is_new => file,
+                _ => storage
+                    .create(file_id)
+                    .map_err(|_| errno!(EIO, "failed to create the file of attributes"))?,
+            };
+            files.push(file);
+        }
+
+        // A file that is incomplete or corrupted, e.g., by a crash, is ignored
+        let (generation, content) = files
+            .iter()
+            .filter_map(|file| Self::load(file.as_ref()).ok())
+            .max_by_key(|(generation, _)| *generation)
+            .unwrap_or((0, Vec::new()));
+        Ok((Self { files, generation }, content))
+    }
+
+    /// Save the content, which replaces the content of the last save.
+    pub fn save(&mut self, content: &[u8]) -> Result<()> {
+        let generation = self.generation + 1;
+        let mut buf = Vec::with_capacity(HEADER_SIZE + content.len() + 8);
+        buf.extend_from_slice(&XATTR_MAGIC.to_le_bytes());
+        buf.extend_from_slice(&generation.to_le_bytes());
+        buf.extend_from_slice(&(content.len() as u64).to_le_bytes());
+        buf.extend_from_slice(content);
+        buf.extend_from_slice(&END_MAGIC.to_le_bytes());
+
+        // Overwrite the older file, keeping the last save intact
+        let file = &self.files[(generation % 2) as usize];
+        let mut offset = 0;
+        while offset < buf.len() {
+            offset += file
+                .write_at(&buf[offset..], offset)
+                .map_err(|_| errno!(EIO, "failed to write the file of attributes"))?;
+        }
+        file.flush()
+            .map_err(|_| errno!(EIO, "failed to flush the file of attributes"))?;
+        self.generation = generation;
+        Ok(())
+    }
+
+    fn load(file: &dyn File) -> Result<(u64, Vec<u8>)> {
+        let header = Self::read_exact(file, 0, HEADER_SIZE)?;
+        let read_u64 = |buf: &[u8], offset: usize| {
+            u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+        };
+        if read_u64(&header, 0) != XATTR_MAGIC {
+            return_errno!(EINVAL, "not a file of attributes");
+        }
+        let generation = read_u64(&header, 8);
+        let size = read_u64(&header, 16) as usize;
+        let buf = Self::read_exact(file, HEADER_SIZE, size + 8)?;
+        if read_u64(&buf, size) != END_MAGIC {
+            return_errno!(EINVAL, "the file of attributes is incomplete");
+        }
+        Ok((generation, buf[..size].to_vec()))
+    }
+
+    fn read_exact(file: &dyn File, offset: usize, len: usize) -> Result<Vec<u8>> {
+        let mut buf = vec![0; len];
+        let mut bytes_read = 0;
+        while bytes_read < len {
+            let n = file
+                .read_at(&mut buf[bytes_read..], offset + bytes_read)
+                .map_err(|_| errno!(EIO, "failed to read the file of attributes"))?;
+            if n == 0 {
+                return_errno!(EINVAL, "the file of attributes is incomplete");
+            }
+            bytes_read += n;
+        }
+        Ok(buf)
+    }
+}
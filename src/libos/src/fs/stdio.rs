@@ -4,6 +4,10 @@ use core::cmp;
 use std::io::{BufReader, LineWriter};
 use std::sync::SgxMutex;
 
+use crate::signal::{kill_process_group, SigAction, SigNum, SIGTTIN, SIGTTOU};
+use crate::syscall::SyscallNum;
+use crate::untrusted::{io_engine, IoEngine, UntrustedSliceAlloc};
+
 macro_rules! try_libc_stdio {
     ($ret: expr) => {{
         let ret = unsafe { $ret };
@@ -48,6 +52,124 @@ impl HostStdioFds {
     }
 }
 
+/// The host terminal of a session, which is the controlling terminal of the
+/// processes in the session.
+///
+/// The terminal is shared by the stdio files of the first process of the
+/// session and inherited by its descendants. Only the processes in the
+/// foreground process group of the session can read from the terminal, while
+/// the processes in the background process groups are stopped by SIGTTIN.
+/// Writes are always allowed, same as the default terminal mode of Linux, where
+/// TOSTOP is not set.
+#[derive(Debug, Default)]
+pub struct Terminal {
+    session: SgxMutex<Option<TerminalSession>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TerminalSession {
+    sid: pid_t,
+    foreground_pgid: pid_t,
+}
+
+impl Terminal {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Make the terminal the controlling terminal of the session, whose leader
+    /// is in the foreground.
+    pub fn set_session(&self, sid: pid_t) {
+        *self.session.lock().unwrap() = Some(TerminalSession {
+            sid,
+            foreground_pgid: sid,
+        });
+    }
+
+    /// Check whether the current process can read from the terminal.
+    ///
+    /// A process in a background process group is stopped by SIGTTIN, after
+    /// which the read is restarted. If the process ignores or blocks SIGTTIN,
+    /// the read fails with EIO instead.
+    fn check_read(&self) -> Result<()> {
+        self.check_background_access(SIGTTIN, true)
+    }
+
+    /// Check whether the current process can change the settings of the
+    /// terminal.
+    ///
+    /// A process in a background process group is stopped by SIGTTOU, unless
+    /// it ignores or blocks the signal, in which case the change is allowed.
+    fn check_change(&self) -> Result<()> {
+        self.check_background_access(SIGTTOU, false)
+    }
+
+    fn check_background_access(&self, signum: SigNum, fails_if_not_stopped: bool) -> Result<()> {
+        let thread = current!();
+        let process = thread.process();
+        let pgid = process.pgid();
+        match *self.session.lock().unwrap() {
+            Some(session) if session.sid == process.sid() && session.foreground_pgid != pgid => {}
+            _ => return Ok(()),
+        }
+
+        let is_ignored = process.sig_dispositions().read().unwrap().get(signum) == SigAction::Ign;
+        let is_blocked = thread.sig_mask().read().unwrap().contains(signum);
+        if is_ignored || is_blocked {
+            if fails_if_not_stopped {
+                return_errno!(EIO, "a background process cannot access the terminal");
+            }
+            return Ok(());
+        }
+        kill_process_group(pgid, signum)?;
+        return_errno!(EINTR, "a background process is stopped by the terminal");
+    }
+
+    /// Get the session of the current process if the terminal controls it.
+    fn session(&self) -> Result<TerminalSession> {
+        let sid = current!().process().sid();
+        match *self.session.lock().unwrap() {
+            Some(session) if session.sid == sid => Ok(session),
+            _ => return_errno!(ENOTTY, "not the controlling terminal of the process"),
+        }
+    }
+
+    fn set_foreground_pgid(&self, pgid: pid_t) -> Result<()> {
+        if pgid < 0 {
+            return_errno!(EINVAL, "invalid process group ID");
+        }
+        let session = self.session()?;
+        self.check_change()?;
+        let is_group_in_session = crate::process::table::get_all_processes()
+            .iter()
+            .any(|process| process.pgid() == pgid && process.sid() == session.sid);
+        if !is_group_in_session {
+            return_errno!(EPERM, "no such process group in the session");
+        }
+        if let Some(session) = self.session.lock().unwrap().as_mut() {
+            session.foreground_pgid = pgid;
+        }
+        Ok(())
+    }
+
+    /// Handle the ioctls of job control, returning None for other ioctls.
+    fn ioctl(&self, cmd: &mut IoctlCmd) -> Option<Result<i32>> {
+        let res = match cmd {
+            IoctlCmd::TIOCGPGRP(pgid) => self.session().map(|session| {
+                **pgid = session.foreground_pgid;
+                0
+            }),
+            IoctlCmd::TIOCSPGRP(pgid) => self.set_foreground_pgid(**pgid).map(|_| 0),
+            IoctlCmd::TIOCGSID(sid) => self.session().map(|session| {
+                **sid = session.sid;
+                0
+            }),
+            _ => return None,
+        };
+        Some(res)
+    }
+}
+
 struct StdoutRaw {
     host_fd: i32,
 }
@@ -86,13 +208,15 @@ impl std::io::Write for StdoutRaw {
 pub struct StdoutFile {
     inner: SgxMutex<LineWriter<StdoutRaw>>,
     host_fd: FileDesc,
+    terminal: Arc<Terminal>,
 }
 
 impl StdoutFile {
-    pub fn new(host_fd: FileDesc) -> Self {
+    pub fn new(host_fd: FileDesc, terminal: Arc<Terminal>) -> Self {
         StdoutFile {
             inner: SgxMutex::new(LineWriter::new(StdoutRaw::new(host_fd))),
             host_fd,
+            terminal,
         }
     }
 
@@ -170,6 +294,9 @@ impl File for StdoutFile {
     }
 
     fn ioctl(&self, cmd: &mut IoctlCmd) -> Result<i32> {
+        if let Some(res) = self.terminal.ioctl(cmd) {
+            return res;
+        }
         let can_delegate_to_host = match cmd {
             IoctlCmd::TIOCGWINSZ(_) => true,
             IoctlCmd::TIOCSWINSZ(_) => true,
@@ -226,19 +353,56 @@ impl StdinRaw {
     }
 }
 
+impl StdinRaw {
+    /// Read the host stdin with the host I/O threads, so that the reading
+    /// thread waits inside the enclave.
+    fn read_with_io_engine(&self, engine: &IoEngine, buf: &mut [u8]) -> Result<isize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let u_allocator = UntrustedSliceAlloc::new(buf.len())?;
+        let u_buf = u_allocator.new_slice_mut(buf.len())?;
+        let (u_buf_ptr, u_buf_len) = u_buf.as_mut_ptr_and_len();
+        let args = [
+            self.host_fd as i64,
+            u_buf_ptr as i64,
+            u_buf_len as i64,
+            0,
+            0,
+            0,
+        ];
+        let ret = unsafe { engine.execute(SyscallNum::Read, args)? };
+        let len = (ret as usize).min(buf.len());
+        buf[..len].copy_from_slice(&u_buf[..len]);
+        Ok(ret)
+    }
+}
+
 impl std::io::Read for StdinRaw {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         let reading_len = cmp::min(buf.len(), size_t::max_value() as usize);
-        let (buf_ptr, _) = buf.as_mut().as_mut_ptr_and_len();
-        let ret = try_libc_stdio!(libc::ocall::read(
-            self.host_fd,
-            buf_ptr as *mut c_void,
-            reading_len,
-        ))
-        .unwrap_or_else(|err| {
-            warn!("tolerate the read error: {:?}", err.errno());
-            0
-        });
+        let res = match io_engine() {
+            Some(engine) => self.read_with_io_engine(engine, &mut buf[..reading_len]),
+            None => {
+                let (buf_ptr, _) = buf.as_mut().as_mut_ptr_and_len();
+                try_libc_stdio!(libc::ocall::read(
+                    self.host_fd,
+                    buf_ptr as *mut c_void,
+                    reading_len,
+                ))
+            }
+        };
+        let ret = match res {
+            Ok(ret) => ret,
+            // The read is interrupted by signals, e.g., to stop the process
+            Err(err) if err.errno() == EINTR => {
+                return Err(std::io::ErrorKind::Interrupted.into());
+            }
+            Err(err) => {
+                warn!("tolerate the read error: {:?}", err.errno());
+                0
+            }
+        };
         // sanity check
         assert!(ret <= reading_len as isize);
         Ok(ret as usize)
@@ -248,13 +412,15 @@ impl std::io::Read for StdinRaw {
 pub struct StdinFile {
     inner: SgxMutex<BufReader<StdinRaw>>,
     host_fd: FileDesc,
+    terminal: Arc<Terminal>,
 }
 
 impl StdinFile {
-    pub fn new(host_fd: FileDesc) -> Self {
+    pub fn new(host_fd: FileDesc, terminal: Arc<Terminal>) -> Self {
         StdinFile {
             inner: SgxMutex::new(BufReader::new(StdinRaw::new(host_fd))),
             host_fd,
+            terminal,
         }
     }
 
@@ -265,6 +431,7 @@ impl StdinFile {
 
 impl File for StdinFile {
     fn read(&self, buf: &mut [u8]) -> Result<usize> {
+        self.terminal.check_read()?;
         let read_len = {
             self.inner
                 .lock()
@@ -276,6 +443,7 @@ impl File for StdinFile {
     }
 
     fn readv(&self, bufs: &mut [&mut [u8]]) -> Result<usize> {
+        self.terminal.check_read()?;
         let mut guard = self.inner.lock().unwrap();
         let mut total_bytes = 0;
         for buf in bufs {
@@ -319,6 +487,9 @@ impl File for StdinFile {
     }
 
     fn ioctl(&self, cmd: &mut IoctlCmd) -> Result<i32> {
+        if let Some(res) = self.terminal.ioctl(cmd) {
+            return res;
+        }
         let can_delegate_to_host = match cmd {
             IoctlCmd::TIOCGWINSZ(_) => true,
             IoctlCmd::TIOCSWINSZ(_) => true,
@@ -1,11 +1,16 @@
 use super::event_file::EventCreationFlags;
 use super::file_ops;
 use super::file_ops::{
-    AccessibilityCheckFlags, AccessibilityCheckMode, ChownFlags, FcntlCmd, FsPath, LinkFlags,
-    StatFlags, UnlinkFlags, AT_FDCWD,
+    AccessibilityCheckFlags, AccessibilityCheckMode, ChownFlags, CloseRangeFlags, FcntlCmd, FsPath,
+    LinkFlags, OpenHow, RenameFlags, StatFlags, UnlinkFlags, XattrTarget, AT_FDCWD,
 };
 use super::fs_ops;
+use super::io_uring::{
+    io_uring_getevents_arg_t, io_uring_params_t, IoUringEnterFlags, IoUringRegisterOp,
+};
 use super::*;
+use crate::signal::{sigset_t, wait_with_sigmask, SigSet};
+use crate::time::{clockid_t, itimerspec_t, time_t, timespec_t, timeval_t, ClockID};
 use util::mem_util::from_user;
 
 #[allow(non_camel_case_types)]
@@ -31,10 +36,130 @@ pub fn do_eventfd2(init_val: u32, flags: i32) -> Result<isize> {
     let fd = current!().add_file(
         file_ref,
         inner_flags.contains(EventCreationFlags::EFD_CLOEXEC),
-    );
+    )?;
     Ok(fd as isize)
 }
 
+pub fn do_timerfd_create(clockid: clockid_t, flags: i32) -> Result<isize> {
+    debug!("timerfd: clockid {}, flags {} ", clockid, flags);
+
+    let clockid = ClockID::from_raw(clockid)?;
+    match clockid {
+        ClockID::CLOCK_REALTIME | ClockID::CLOCK_MONOTONIC | ClockID::CLOCK_BOOTTIME => {}
+        _ => return_errno!(EINVAL, "invalid clockid"),
+    }
+    let inner_flags =
+        TimerCreationFlags::from_bits(flags).ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+    let file_ref: Arc<dyn File> = {
+        let timer = TimerFile::new(clockid, inner_flags)?;
+        Arc::new(timer)
+    };
+
+    let fd = current!().add_file(
+        file_ref,
+        inner_flags.contains(TimerCreationFlags::TFD_CLOEXEC),
+    )?;
+    Ok(fd as isize)
+}
+
+pub fn do_timerfd_settime(
+    fd: FileDesc,
+    flags: i32,
+    new_value_ptr: *const itimerspec_t,
+    old_value_ptr: *mut itimerspec_t,
+) -> Result<isize> {
+    from_user::check_ptr(new_value_ptr)?;
+    let new_value = itimerspec_t::from_raw_ptr(new_value_ptr)?;
+    let flags = TimerSetFlags::from_bits(flags).ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+    // The old value is checked before the timer is changed
+    if !old_value_ptr.is_null() {
+        from_user::check_mut_ptr(old_value_ptr)?;
+    }
+
+    let file_ref = current!().file(fd)?;
+    let timer_file = file_ref.as_timer()?;
+    let old_value = timer_file.set_time(flags, &new_value)?;
+    if !old_value_ptr.is_null() {
+        unsafe {
+            old_value_ptr.write(old_value);
+        }
+    }
+    Ok(0)
+}
+
+pub fn do_timerfd_gettime(fd: FileDesc, curr_value_ptr: *mut itimerspec_t) -> Result<isize> {
+    from_user::check_mut_ptr(curr_value_ptr)?;
+    let file_ref = current!().file(fd)?;
+    let timer_file = file_ref.as_timer()?;
+    let curr_value = timer_file.time()?;
+    unsafe {
+        curr_value_ptr.write(curr_value);
+    }
+    Ok(0)
+}
+
+pub fn do_memfd_create(name: *const i8, flags: u32) -> Result<isize> {
+    let name = from_user::clone_cstring_safely(name)?
+        .to_string_lossy()
+        .into_owned();
+    debug!("memfd_create: name: {:?}, flags: {:#x}", name, flags);
+
+    let flags = MemFileFlags::from_bits(flags).ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+    let file_ref: Arc<dyn File> = Arc::new(MemFile::new(&name, flags)?);
+
+    let fd = current!().add_file(file_ref, flags.contains(MemFileFlags::MFD_CLOEXEC))?;
+    Ok(fd as isize)
+}
+
+pub fn do_inotify_init() -> Result<isize> {
+    do_inotify_init1(0)
+}
+
+pub fn do_inotify_init1(flags: i32) -> Result<isize> {
+    debug!("inotify_init1: flags {}", flags);
+
+    let inner_flags =
+        InotifyFlags::from_bits(flags).ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+    let file_ref: Arc<dyn File> = InotifyFile::new(inner_flags);
+
+    let fd = current!().add_file(file_ref, inner_flags.contains(InotifyFlags::IN_CLOEXEC))?;
+    Ok(fd as isize)
+}
+
+pub fn do_inotify_add_watch(fd: FileDesc, path: *const i8, mask: u32) -> Result<isize> {
+    let path = from_user::clone_cstring_safely(path)?
+        .to_string_lossy()
+        .into_owned();
+    let mask = InotifyMask::from_bits_truncate(mask);
+    debug!(
+        "inotify_add_watch: fd: {}, path: {:?}, mask: {:?}",
+        fd, path, mask
+    );
+
+    let file_ref = current!().file(fd)?;
+    let inotify_file = file_ref.as_inotify_file()?;
+    let inode = {
+        let current = current!();
+        let fs = current.fs().lock().unwrap();
+        if mask.contains(InotifyMask::IN_DONT_FOLLOW) {
+            fs.lookup_inode_no_follow(&path)?
+        } else {
+            fs.lookup_inode(&path)?
+        }
+    };
+    let wd = inotify_file.add_watch(inode.as_ref(), mask)?;
+    Ok(wd as isize)
+}
+
+pub fn do_inotify_rm_watch(fd: FileDesc, wd: i32) -> Result<isize> {
+    debug!("inotify_rm_watch: fd: {}, wd: {}", fd, wd);
+
+    let file_ref = current!().file(fd)?;
+    let inotify_file = file_ref.as_inotify_file()?;
+    inotify_file.rm_watch(wd)?;
+    Ok(0)
+}
+
 pub fn do_open(path: *const i8, flags: u32, mode: u32) -> Result<isize> {
     self::do_openat(AT_FDCWD, path, flags, mode)
 }
@@ -48,11 +173,40 @@ pub fn do_openat(dirfd: i32, path: *const i8, flags: u32, mode: u32) -> Result<i
     Ok(fd as isize)
 }
 
+pub fn do_openat2(dirfd: i32, path: *const i8, how: *const OpenHow, size: usize) -> Result<isize> {
+    let path = from_user::clone_cstring_safely(path)?
+        .to_string_lossy()
+        .into_owned();
+    // Same as Linux, a larger struct is accepted if the extra bytes are zero
+    if size < std::mem::size_of::<OpenHow>() {
+        return_errno!(EINVAL, "the size of open_how is too small");
+    }
+    from_user::check_array(how as *const u8, size)?;
+    let extra_bytes = unsafe {
+        std::slice::from_raw_parts(
+            (how as *const u8).add(std::mem::size_of::<OpenHow>()),
+            size - std::mem::size_of::<OpenHow>(),
+        )
+    };
+    if extra_bytes.iter().any(|byte| *byte != 0) {
+        return_errno!(E2BIG, "unknown fields of open_how are not zero");
+    }
+    let how = unsafe { how.read() };
+    let fd = file_ops::do_openat2(dirfd, &path, &how)?;
+    Ok(fd as isize)
+}
+
 pub fn do_close(fd: FileDesc) -> Result<isize> {
     file_ops::do_close(fd)?;
     Ok(0)
 }
 
+pub fn do_close_range(first: FileDesc, last: FileDesc, flags: u32) -> Result<isize> {
+    let flags = CloseRangeFlags::from_bits(flags).ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+    file_ops::do_close_range(first, last, flags)?;
+    Ok(0)
+}
+
 pub fn do_read(fd: FileDesc, buf: *mut u8, size: usize) -> Result<isize> {
     let safe_buf = {
         from_user::check_mut_array(buf, size)?;
@@ -216,6 +370,11 @@ pub fn do_lseek(fd: FileDesc, offset: off_t, whence: i32) -> Result<isize> {
     Ok(offset as isize)
 }
 
+pub fn do_flock(fd: FileDesc, operation: i32) -> Result<isize> {
+    file_ops::do_flock(fd, operation)?;
+    Ok(0)
+}
+
 pub fn do_fsync(fd: FileDesc) -> Result<isize> {
     file_ops::do_fsync(fd)?;
     Ok(0)
@@ -292,6 +451,34 @@ pub fn do_dup3(old_fd: FileDesc, new_fd: FileDesc, flags: u32) -> Result<isize>
     Ok(new_fd as isize)
 }
 
+pub fn do_statfs(path: *const i8, statfs_buf: *mut Statfs) -> Result<isize> {
+    let path = from_user::clone_cstring_safely(path)?
+        .to_string_lossy()
+        .into_owned();
+    from_user::check_mut_ptr(statfs_buf)?;
+
+    let statfs = fs_ops::do_statfs(&path)?;
+    unsafe {
+        statfs_buf.write(statfs);
+    }
+    Ok(0)
+}
+
+pub fn do_fstatfs(fd: FileDesc, statfs_buf: *mut Statfs) -> Result<isize> {
+    from_user::check_mut_ptr(statfs_buf)?;
+
+    let statfs = fs_ops::do_fstatfs(fd)?;
+    unsafe {
+        statfs_buf.write(statfs);
+    }
+    Ok(0)
+}
+
+pub fn do_umask(mask: u16) -> Result<isize> {
+    let old_mask = fs_ops::do_umask(mask)?;
+    Ok(old_mask as isize)
+}
+
 pub fn do_chdir(path: *const i8) -> Result<isize> {
     let path = from_user::clone_cstring_safely(path)?
         .to_string_lossy()
@@ -326,6 +513,16 @@ pub fn do_renameat(
     oldpath: *const i8,
     newdirfd: i32,
     newpath: *const i8,
+) -> Result<isize> {
+    self::do_renameat2(olddirfd, oldpath, newdirfd, newpath, 0)
+}
+
+pub fn do_renameat2(
+    olddirfd: i32,
+    oldpath: *const i8,
+    newdirfd: i32,
+    newpath: *const i8,
+    flags: u32,
 ) -> Result<isize> {
     let oldpath = from_user::clone_cstring_safely(oldpath)?
         .to_string_lossy()
@@ -335,7 +532,8 @@ pub fn do_renameat(
         .into_owned();
     let old_fs_path = FsPath::new(&oldpath, olddirfd, false)?;
     let new_fs_path = FsPath::new(&newpath, newdirfd, false)?;
-    file_ops::do_renameat(&old_fs_path, &new_fs_path)?;
+    let flags = RenameFlags::from_u32(flags)?;
+    file_ops::do_renameat(&old_fs_path, &new_fs_path, flags)?;
     Ok(0)
 }
 
@@ -481,6 +679,92 @@ pub fn do_lchown(path: *const i8, uid: u32, gid: u32) -> Result<isize> {
     )
 }
 
+pub fn do_utimensat(
+    dirfd: i32,
+    path: *const i8,
+    times: *const timespec_t,
+    flags: i32,
+) -> Result<isize> {
+    let flags = UtimeFlags::from_bits(flags).ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+    let (atime, mtime) = if times.is_null() {
+        (Utime::Now, Utime::Now)
+    } else {
+        from_user::check_array(times, 2)?;
+        let times = unsafe { std::slice::from_raw_parts(times, 2) };
+        (
+            Utime::from_timespec(&times[0])?,
+            Utime::from_timespec(&times[1])?,
+        )
+    };
+    if path.is_null() {
+        // Same as futimens, which is implemented with a null path by libc
+        if dirfd < 0 {
+            return_errno!(EBADF, "invalid fd");
+        }
+        file_ops::do_futimens(dirfd as FileDesc, atime, mtime)?;
+        return Ok(0);
+    }
+    let path = from_user::clone_cstring_safely(path)?
+        .to_string_lossy()
+        .into_owned();
+    let fs_path = FsPath::new(&path, dirfd, flags.contains(UtimeFlags::AT_EMPTY_PATH))?;
+    file_ops::do_utimensat(&fs_path, atime, mtime, flags)?;
+    Ok(0)
+}
+
+pub fn do_futimesat(dirfd: i32, path: *const i8, times: *const timeval_t) -> Result<isize> {
+    let (atime, mtime) = if times.is_null() {
+        (Utime::Now, Utime::Now)
+    } else {
+        from_user::check_array(times, 2)?;
+        let times = unsafe { std::slice::from_raw_parts(times, 2) };
+        (
+            Utime::from_timeval(&times[0])?,
+            Utime::from_timeval(&times[1])?,
+        )
+    };
+    let path = from_user::clone_cstring_safely(path)?
+        .to_string_lossy()
+        .into_owned();
+    let fs_path = FsPath::new(&path, dirfd, false)?;
+    file_ops::do_utimensat(&fs_path, atime, mtime, UtimeFlags::empty())?;
+    Ok(0)
+}
+
+pub fn do_utimes(path: *const i8, times: *const timeval_t) -> Result<isize> {
+    self::do_futimesat(AT_FDCWD, path, times)
+}
+
+pub fn do_utime(path: *const i8, times: *const utimbuf_t) -> Result<isize> {
+    let (atime, mtime) = if times.is_null() {
+        (Utime::Now, Utime::Now)
+    } else {
+        from_user::check_ptr(times)?;
+        let times = unsafe { &*times };
+        let to_utime = |sec: time_t| {
+            if sec < 0 {
+                return_errno!(EINVAL, "invalid time");
+            }
+            Ok(Utime::Time(Timespec { sec, nsec: 0 }))
+        };
+        (to_utime(times.actime)?, to_utime(times.modtime)?)
+    };
+    let path = from_user::clone_cstring_safely(path)?
+        .to_string_lossy()
+        .into_owned();
+    let fs_path = FsPath::new(&path, AT_FDCWD, false)?;
+    file_ops::do_utimensat(&fs_path, atime, mtime, UtimeFlags::empty())?;
+    Ok(0)
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct utimbuf_t {
+    actime: time_t,
+    modtime: time_t,
+}
+
 pub fn do_sendfile(
     out_fd: FileDesc,
     in_fd: FileDesc,
@@ -503,6 +787,79 @@ pub fn do_sendfile(
     Ok(len as isize)
 }
 
+pub fn do_splice(
+    fd_in: FileDesc,
+    off_in_ptr: *mut off_t,
+    fd_out: FileDesc,
+    off_out_ptr: *mut off_t,
+    len: usize,
+    flags: u32,
+) -> Result<isize> {
+    let off_in = if off_in_ptr.is_null() {
+        None
+    } else {
+        from_user::check_mut_ptr(off_in_ptr)?;
+        Some(unsafe { &mut *off_in_ptr })
+    };
+    let off_out = if off_out_ptr.is_null() {
+        None
+    } else {
+        from_user::check_mut_ptr(off_out_ptr)?;
+        Some(unsafe { &mut *off_out_ptr })
+    };
+
+    let len = file_ops::do_splice(fd_in, off_in, fd_out, off_out, len, flags)?;
+    Ok(len as isize)
+}
+
+pub fn do_copy_file_range(
+    fd_in: FileDesc,
+    off_in_ptr: *mut off_t,
+    fd_out: FileDesc,
+    off_out_ptr: *mut off_t,
+    len: usize,
+    flags: u32,
+) -> Result<isize> {
+    let off_in = if off_in_ptr.is_null() {
+        None
+    } else {
+        from_user::check_mut_ptr(off_in_ptr)?;
+        Some(unsafe { &mut *off_in_ptr })
+    };
+    let off_out = if off_out_ptr.is_null() {
+        None
+    } else {
+        from_user::check_mut_ptr(off_out_ptr)?;
+        Some(unsafe { &mut *off_out_ptr })
+    };
+
+    let len = file_ops::do_copy_file_range(fd_in, off_in, fd_out, off_out, len, flags)?;
+    Ok(len as isize)
+}
+
+pub fn do_tee(fd_in: FileDesc, fd_out: FileDesc, len: usize, flags: u32) -> Result<isize> {
+    let len = file_ops::do_tee(fd_in, fd_out, len, flags)?;
+    Ok(len as isize)
+}
+
+pub fn do_vmsplice(fd: FileDesc, iov: *mut iovec_t, count: usize, flags: u32) -> Result<isize> {
+    from_user::check_array(iov, count)?;
+    let mut bufs_vec = {
+        let mut bufs_vec = Vec::with_capacity(count);
+        for iov_i in 0..count {
+            let iov_ptr = unsafe { iov.offset(iov_i as isize) };
+            let iov = unsafe { &*iov_ptr };
+            let buf = unsafe { std::slice::from_raw_parts_mut(iov.base as *mut u8, iov.len) };
+            bufs_vec.push(buf);
+        }
+        bufs_vec
+    };
+    let bufs = &mut bufs_vec[..];
+
+    let len = file_ops::do_vmsplice(fd, bufs, flags)?;
+    Ok(len as isize)
+}
+
 pub fn do_fcntl(fd: FileDesc, cmd: u32, arg: u64) -> Result<isize> {
     let mut cmd = FcntlCmd::from_raw(cmd, arg)?;
     file_ops::do_fcntl(fd, &mut cmd)
@@ -538,6 +895,61 @@ pub fn do_mount_rootfs(
     Ok(0)
 }
 
+pub fn do_mount(
+    source: *const i8,
+    target: *const i8,
+    fs_type: *const i8,
+    flags: u32,
+    data: *const u8,
+) -> Result<isize> {
+    // The magic number in the upper 16 bits of the flags is required before Linux 2.4
+    const MS_MGC_MSK: u32 = 0xffff_0000;
+    const MS_MGC_VAL: u32 = 0xc0ed_0000;
+
+    let source = if source.is_null() {
+        None
+    } else {
+        Some(
+            from_user::clone_cstring_safely(source)?
+                .to_string_lossy()
+                .into_owned(),
+        )
+    };
+    let target = from_user::clone_cstring_safely(target)?
+        .to_string_lossy()
+        .into_owned();
+    let fs_type = if fs_type.is_null() {
+        None
+    } else {
+        Some(
+            from_user::clone_cstring_safely(fs_type)?
+                .to_string_lossy()
+                .into_owned(),
+        )
+    };
+    let flags = if flags & MS_MGC_MSK == MS_MGC_VAL {
+        flags & !MS_MGC_MSK
+    } else {
+        flags
+    };
+    let flags =
+        fs_ops::MountFlags::from_bits(flags).ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+    // The data are the options specific to the file systems, which are ignored
+    // since none of the supported file systems takes any option
+    fs_ops::do_mount(source.as_deref(), &target, fs_type.as_deref(), flags)?;
+    Ok(0)
+}
+
+pub fn do_umount(target: *const i8, flags: u32) -> Result<isize> {
+    let target = from_user::clone_cstring_safely(target)?
+        .to_string_lossy()
+        .into_owned();
+    let flags =
+        fs_ops::UmountFlags::from_bits(flags).ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+    fs_ops::do_umount(&target, flags)?;
+    Ok(0)
+}
+
 pub fn do_fallocate(fd: FileDesc, mode: u32, offset: off_t, len: off_t) -> Result<isize> {
     if offset < 0 || len <= 0 {
         return_errno!(
@@ -545,11 +957,309 @@ pub fn do_fallocate(fd: FileDesc, mode: u32, offset: off_t, len: off_t) -> Resul
             "offset was less than 0, or len was less than or equal to 0"
         );
     }
-    // Current implementation is just the posix_fallocate
-    // TODO: Support more modes in fallocate
-    if mode != 0 {
-        return_errno!(ENOSYS, "unsupported mode");
-    }
     file_ops::do_fallocate(fd, mode, offset as u64, len as u64)?;
     Ok(0)
 }
+
+pub fn do_setxattr(
+    path: *const i8,
+    name: *const i8,
+    value: *const u8,
+    size: usize,
+    flags: i32,
+) -> Result<isize> {
+    let path = from_user::clone_cstring_safely(path)?
+        .to_string_lossy()
+        .into_owned();
+    self::setxattr_on(&XattrTarget::Path(&path), name, value, size, flags)
+}
+
+pub fn do_lsetxattr(
+    path: *const i8,
+    name: *const i8,
+    value: *const u8,
+    size: usize,
+    flags: i32,
+) -> Result<isize> {
+    let path = from_user::clone_cstring_safely(path)?
+        .to_string_lossy()
+        .into_owned();
+    self::setxattr_on(&XattrTarget::PathNoFollow(&path), name, value, size, flags)
+}
+
+pub fn do_fsetxattr(
+    fd: FileDesc,
+    name: *const i8,
+    value: *const u8,
+    size: usize,
+    flags: i32,
+) -> Result<isize> {
+    self::setxattr_on(&XattrTarget::File(fd), name, value, size, flags)
+}
+
+fn setxattr_on(
+    target: &XattrTarget,
+    name: *const i8,
+    value: *const u8,
+    size: usize,
+    flags: i32,
+) -> Result<isize> {
+    let name = from_user::clone_cstring_safely(name)?
+        .to_string_lossy()
+        .into_owned();
+    let value: &[u8] = if size == 0 {
+        &[]
+    } else {
+        from_user::check_array(value, size)?;
+        unsafe { std::slice::from_raw_parts(value, size) }
+    };
+    let flags = XattrFlags::from_bits(flags).ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+    file_ops::do_setxattr(target, &name, value, flags)?;
+    Ok(0)
+}
+
+pub fn do_getxattr(path: *const i8, name: *const i8, value: *mut u8, size: usize) -> Result<isize> {
+    let path = from_user::clone_cstring_safely(path)?
+        .to_string_lossy()
+        .into_owned();
+    self::getxattr_on(&XattrTarget::Path(&path), name, value, size)
+}
+
+pub fn do_lgetxattr(
+    path: *const i8,
+    name: *const i8,
+    value: *mut u8,
+    size: usize,
+) -> Result<isize> {
+    let path = from_user::clone_cstring_safely(path)?
+        .to_string_lossy()
+        .into_owned();
+    self::getxattr_on(&XattrTarget::PathNoFollow(&path), name, value, size)
+}
+
+pub fn do_fgetxattr(fd: FileDesc, name: *const i8, value: *mut u8, size: usize) -> Result<isize> {
+    self::getxattr_on(&XattrTarget::File(fd), name, value, size)
+}
+
+fn getxattr_on(
+    target: &XattrTarget,
+    name: *const i8,
+    value: *mut u8,
+    size: usize,
+) -> Result<isize> {
+    let name = from_user::clone_cstring_safely(name)?
+        .to_string_lossy()
+        .into_owned();
+    let value: &mut [u8] = if size == 0 {
+        &mut []
+    } else {
+        from_user::check_mut_array(value, size)?;
+        unsafe { std::slice::from_raw_parts_mut(value, size) }
+    };
+    let len = file_ops::do_getxattr(target, &name, value)?;
+    Ok(len as isize)
+}
+
+pub fn do_listxattr(path: *const i8, list: *mut u8, size: usize) -> Result<isize> {
+    let path = from_user::clone_cstring_safely(path)?
+        .to_string_lossy()
+        .into_owned();
+    self::listxattr_on(&XattrTarget::Path(&path), list, size)
+}
+
+pub fn do_llistxattr(path: *const i8, list: *mut u8, size: usize) -> Result<isize> {
+    let path = from_user::clone_cstring_safely(path)?
+        .to_string_lossy()
+        .into_owned();
+    self::listxattr_on(&XattrTarget::PathNoFollow(&path), list, size)
+}
+
+pub fn do_flistxattr(fd: FileDesc, list: *mut u8, size: usize) -> Result<isize> {
+    self::listxattr_on(&XattrTarget::File(fd), list, size)
+}
+
+fn listxattr_on(target: &XattrTarget, list: *mut u8, size: usize) -> Result<isize> {
+    let list: &mut [u8] = if size == 0 {
+        &mut []
+    } else {
+        from_user::check_mut_array(list, size)?;
+        unsafe { std::slice::from_raw_parts_mut(list, size) }
+    };
+    let len = file_ops::do_listxattr(target, list)?;
+    Ok(len as isize)
+}
+
+pub fn do_removexattr(path: *const i8, name: *const i8) -> Result<isize> {
+    let path = from_user::clone_cstring_safely(path)?
+        .to_string_lossy()
+        .into_owned();
+    self::removexattr_on(&XattrTarget::Path(&path), name)
+}
+
+pub fn do_lremovexattr(path: *const i8, name: *const i8) -> Result<isize> {
+    let path = from_user::clone_cstring_safely(path)?
+        .to_string_lossy()
+        .into_owned();
+    self::removexattr_on(&XattrTarget::PathNoFollow(&path), name)
+}
+
+pub fn do_fremovexattr(fd: FileDesc, name: *const i8) -> Result<isize> {
+    self::removexattr_on(&XattrTarget::File(fd), name)
+}
+
+fn removexattr_on(target: &XattrTarget, name: *const i8) -> Result<isize> {
+    let name = from_user::clone_cstring_safely(name)?
+        .to_string_lossy()
+        .into_owned();
+    file_ops::do_removexattr(target, &name)?;
+    Ok(0)
+}
+
+pub fn do_io_setup(nr_events: u32, ctx_idp: *mut aio_context_t) -> Result<isize> {
+    from_user::check_mut_ptr(ctx_idp)?;
+    // Same as Linux, the ID must be initialized to zero
+    if unsafe { *ctx_idp } != 0 {
+        return_errno!(EINVAL, "the context ID is not zero");
+    }
+    let ctx_id = aio::do_io_setup(nr_events)?;
+    unsafe {
+        *ctx_idp = ctx_id;
+    }
+    Ok(0)
+}
+
+pub fn do_io_destroy(ctx_id: aio_context_t) -> Result<isize> {
+    aio::do_io_destroy(ctx_id)?;
+    Ok(0)
+}
+
+pub fn do_io_submit(ctx_id: aio_context_t, nr: i64, iocbpp: *const *const iocb_t) -> Result<isize> {
+    if nr < 0 {
+        return_errno!(EINVAL, "the number of requests is negative");
+    }
+    if nr == 0 {
+        return Ok(0);
+    }
+    from_user::check_array(iocbpp, nr as usize)?;
+    let iocbs = unsafe { std::slice::from_raw_parts(iocbpp, nr as usize) };
+    let count = aio::do_io_submit(ctx_id, iocbs)?;
+    Ok(count as isize)
+}
+
+pub fn do_io_getevents(
+    ctx_id: aio_context_t,
+    min_nr: i64,
+    nr: i64,
+    events: *mut io_event_t,
+    timeout: *const timespec_t,
+) -> Result<isize> {
+    if min_nr < 0 || nr < 0 || min_nr > nr {
+        return_errno!(EINVAL, "invalid number of events");
+    }
+    from_user::check_mut_array(events, nr as usize)?;
+    let events = unsafe { std::slice::from_raw_parts_mut(events, nr as usize) };
+    let timeout = if timeout.is_null() {
+        None
+    } else {
+        from_user::check_ptr(timeout)?;
+        Some(timespec_t::from_raw_ptr(timeout)?.as_duration())
+    };
+    let count = aio::do_io_getevents(ctx_id, min_nr as usize, events, timeout)?;
+    Ok(count as isize)
+}
+
+pub fn do_io_cancel(
+    ctx_id: aio_context_t,
+    iocb: *const iocb_t,
+    result: *mut io_event_t,
+) -> Result<isize> {
+    from_user::check_mut_ptr(result)?;
+    let event = aio::do_io_cancel(ctx_id, iocb)?;
+    unsafe {
+        *result = event;
+    }
+    Ok(0)
+}
+
+pub fn do_io_uring_setup(entries: u32, params: *mut io_uring_params_t) -> Result<isize> {
+    from_user::check_mut_ptr(params)?;
+    let params = unsafe { &mut *params };
+    debug!("io_uring_setup: entries: {}, params: {:?}", entries, params);
+    let file_ref: Arc<dyn File> = Arc::new(IoUringFile::new(entries, params)?);
+    // Same as Linux, the fd of an io_uring is close-on-exec
+    let fd = current!().add_file(file_ref, true)?;
+    Ok(fd as isize)
+}
+
+pub fn do_io_uring_enter(
+    fd: FileDesc,
+    to_submit: u32,
+    min_complete: u32,
+    flags: u32,
+    arg: *const c_void,
+    argsz: usize,
+) -> Result<isize> {
+    debug!(
+        "io_uring_enter: fd: {}, to_submit: {}, min_complete: {}, flags: {:#x}",
+        fd, to_submit, min_complete, flags
+    );
+    let flags = IoUringEnterFlags::from_bits(flags)
+        .ok_or_else(|| errno!(EINVAL, "unknown flags of io_uring_enter"))?;
+    let file_ref = current!().file(fd)?;
+    let io_uring = file_ref.as_io_uring()?;
+
+    let (sigmask, sigset_size, timeout) = if flags.contains(IoUringEnterFlags::IORING_ENTER_EXT_ARG)
+    {
+        let arg = arg as *const io_uring_getevents_arg_t;
+        if argsz != std::mem::size_of::<io_uring_getevents_arg_t>() {
+            return_errno!(EINVAL, "invalid size of the argument");
+        }
+        from_user::check_ptr(arg)?;
+        let arg = unsafe { *arg };
+        let timeout = if arg.ts != 0 {
+            let ts = arg.ts as *const timespec_t;
+            from_user::check_ptr(ts)?;
+            Some(timespec_t::from_raw_ptr(ts)?.as_duration())
+        } else {
+            None
+        };
+        (
+            arg.sigmask as *const sigset_t,
+            arg.sigmask_sz as usize,
+            timeout,
+        )
+    } else {
+        (arg as *const sigset_t, argsz, None)
+    };
+    let sigmask = if !sigmask.is_null() {
+        if sigset_size != std::mem::size_of::<sigset_t>() {
+            return_errno!(EINVAL, "the size of the signal mask is not correct");
+        }
+        from_user::check_ptr(sigmask)?;
+        Some(SigSet::from_c(unsafe { *sigmask }))
+    } else {
+        None
+    };
+
+    let submitted = wait_with_sigmask(sigmask, || {
+        io_uring.enter(to_submit, min_complete, flags, timeout)
+    })?;
+    Ok(submitted as isize)
+}
+
+pub fn do_io_uring_register(
+    fd: FileDesc,
+    opcode: u32,
+    arg: *const c_void,
+    nr_args: u32,
+) -> Result<isize> {
+    debug!(
+        "io_uring_register: fd: {}, opcode: {}, arg: {:?}, nr_args: {}",
+        fd, opcode, arg, nr_args
+    );
+    let op = IoUringRegisterOp::from_u32(opcode)?;
+    let file_ref = current!().file(fd)?;
+    let io_uring = file_ref.as_io_uring()?;
+    let ret = io_uring.register(op, arg as usize, nr_args)?;
+    Ok(ret as isize)
+}
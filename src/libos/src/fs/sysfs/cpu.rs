@@ -0,0 +1,193 @@
+use super::*;
+use crate::exception::get_cpuid_info;
+use crate::sched::{AVAIL_CPUSET, NCORES};
+
+/// The leaf of CPUID to enumerate the deterministic cache parameters.
+const CPUID_CACHE_PARAMS_LEAF: u32 = 0x4;
+/// The max number of cache levels and types to enumerate.
+const MAX_CACHE_INDEXES: u32 = 8;
+
+/// Populate `/sys/devices/system/cpu`.
+///
+/// Each CPU core available to the enclave, i.e., the cores of the host limited
+/// by `resource_limits.cpu_set` in Occlum.json, is presented as a core of its
+/// own in a single package, since the host topology of the cores that Occlum
+/// runs on is not trusted and may change between runs. The cores keep their
+/// indexes on the host, same as the ones reported by sched_getaffinity.
+pub(super) fn init_cpu_dir(cpu_dir: &Arc<Dir<LockedSysDirINode>>) {
+    let ncores = *NCORES;
+    let cpus = AVAIL_CPUSET.cpus();
+    let all_cpus_list = cpu_list(&cpus);
+    let all_cpus_map = cpu_map(&cpus, ncores);
+    cpu_dir.inner().add_attr("online", all_cpus_list.clone());
+    cpu_dir.inner().add_attr("possible", all_cpus_list.clone());
+    cpu_dir.inner().add_attr("present", all_cpus_list.clone());
+    cpu_dir.inner().add_attr("offline", String::from("\n"));
+    cpu_dir
+        .inner()
+        .add_attr("kernel_max", format!("{}\n", ncores - 1));
+
+    let caches = CACHES.as_slice();
+    for &cpu in &cpus {
+        let cpun_dir = LockedSysDirINode::new_child(cpu_dir, &format!("cpu{}", cpu));
+        cpun_dir.inner().add_attr("online", String::from("1\n"));
+
+        let topology_dir = LockedSysDirINode::new_child(&cpun_dir, "topology");
+        let topology = topology_dir.inner();
+        topology.add_attr("physical_package_id", String::from("0\n"));
+        topology.add_attr("die_id", String::from("0\n"));
+        topology.add_attr("core_id", format!("{}\n", cpu));
+        topology.add_attr("thread_siblings", cpu_map(&[cpu], ncores));
+        topology.add_attr("thread_siblings_list", cpu_list(&[cpu]));
+        topology.add_attr("core_siblings", all_cpus_map.clone());
+        topology.add_attr("core_siblings_list", all_cpus_list.clone());
+        topology.add_attr("package_cpus", all_cpus_map.clone());
+        topology.add_attr("package_cpus_list", all_cpus_list.clone());
+
+        let cache_dir = LockedSysDirINode::new_child(&cpun_dir, "cache");
+        for (index, cache) in caches.iter().enumerate() {
+            let index_dir = LockedSysDirINode::new_child(&cache_dir, &format!("index{}", index));
+            let shared_cpus = if cache.is_shared() {
+                cpus.as_slice()
+            } else {
+                std::slice::from_ref(&cpu)
+            };
+            let attrs = index_dir.inner();
+            attrs.add_attr(
+                "id",
+                format!("{}\n", if cache.is_shared() { 0 } else { cpu }),
+            );
+            attrs.add_attr("level", format!("{}\n", cache.level));
+            attrs.add_attr("type", format!("{}\n", cache.type_.as_str()));
+            attrs.add_attr("size", format!("{}K\n", cache.size() / 1024));
+            attrs.add_attr(
+                "coherency_line_size",
+                format!("{}\n", cache.coherency_line_size),
+            );
+            attrs.add_attr(
+                "physical_line_partition",
+                format!("{}\n", cache.physical_line_partition),
+            );
+            attrs.add_attr(
+                "ways_of_associativity",
+                format!("{}\n", cache.ways_of_associativity),
+            );
+            attrs.add_attr("number_of_sets", format!("{}\n", cache.number_of_sets));
+            attrs.add_attr("shared_cpu_map", cpu_map(shared_cpus, ncores));
+            attrs.add_attr("shared_cpu_list", cpu_list(shared_cpus));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CacheType {
+    Data,
+    Instruction,
+    Unified,
+}
+
+impl CacheType {
+    fn from_cpuid(cache_type: u32) -> Option<Self> {
+        match cache_type {
+            1 => Some(CacheType::Data),
+            2 => Some(CacheType::Instruction),
+            3 => Some(CacheType::Unified),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            CacheType::Data => "Data",
+            CacheType::Instruction => "Instruction",
+            CacheType::Unified => "Unified",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CacheInfo {
+    level: u32,
+    type_: CacheType,
+    coherency_line_size: u32,
+    physical_line_partition: u32,
+    ways_of_associativity: u32,
+    number_of_sets: u32,
+}
+
+impl CacheInfo {
+    fn size(&self) -> u32 {
+        self.coherency_line_size
+            * self.physical_line_partition
+            * self.ways_of_associativity
+            * self.number_of_sets
+    }
+
+    /// The L1 and L2 caches are private to a core, while the caches of the
+    /// higher levels are shared by all the cores.
+    fn is_shared(&self) -> bool {
+        self.level > 2
+    }
+}
+
+lazy_static! {
+    static ref CACHES: Vec<CacheInfo> = {
+        let mut caches = Vec::new();
+        for subleaf in 0..MAX_CACHE_INDEXES {
+            let [eax, ebx, ecx, _] = get_cpuid_info(CPUID_CACHE_PARAMS_LEAF, subleaf);
+            // No more caches if the cache type is null
+            let type_ = match CacheType::from_cpuid(eax & 0x1F) {
+                Some(type_) => type_,
+                None => break,
+            };
+            caches.push(CacheInfo {
+                level: (eax >> 5) & 0x7,
+                type_,
+                coherency_line_size: (ebx & 0xFFF) + 1,
+                physical_line_partition: ((ebx >> 12) & 0x3FF) + 1,
+                ways_of_associativity: ((ebx >> 22) & 0x3FF) + 1,
+                number_of_sets: ecx + 1,
+            });
+        }
+        caches
+    };
+}
+
+/// The list format of the sorted CPUs, e.g., "0-3,6".
+fn cpu_list(cpus: &[usize]) -> String {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &cpu in cpus {
+        match ranges.last_mut() {
+            Some((_, last)) if *last + 1 == cpu => *last = cpu,
+            _ => ranges.push((cpu, cpu)),
+        }
+    }
+    let ranges: Vec<String> = ranges
+        .iter()
+        .map(|&(first, last)| {
+            if first == last {
+                format!("{}", first)
+            } else {
+                format!("{}-{}", first, last)
+            }
+        })
+        .collect();
+    format!("{}\n", ranges.join(","))
+}
+
+/// The bitmap format of the CPUs, e.g., "0000000f", which is grouped by 32
+/// bits and separated by commas.
+fn cpu_map(cpus: &[usize], ncores: usize) -> String {
+    let nwords = (ncores + 31) / 32;
+    let words: Vec<String> = (0..nwords)
+        .rev()
+        .map(|word_idx| {
+            let word = cpus
+                .iter()
+                .filter(|&&cpu| cpu / 32 == word_idx)
+                .fold(0u32, |word, cpu| word | (1 << (cpu % 32)));
+            format!("{:08x}", word)
+        })
+        .collect();
+    format!("{}\n", words.join(","))
+}
@@ -0,0 +1,145 @@
+use super::*;
+use alloc::sync::{Arc, Weak};
+use rcore_fs::vfs;
+
+use super::procfs::{Dir, DirProcINode, File, ProcINode};
+
+use self::cpu::init_cpu_dir;
+
+mod cpu;
+
+/// Sys file system
+///
+/// Only the CPU topology under `/sys/devices/system/cpu` is provided, which is
+/// consistent with the CPU cores available to the enclave.
+pub struct SysFS {
+    root: Arc<Dir<LockedSysDirINode>>,
+}
+
+impl FileSystem for SysFS {
+    fn sync(&self) -> vfs::Result<()> {
+        Ok(())
+    }
+
+    fn root_inode(&self) -> Arc<dyn INode> {
+        Arc::clone(&self.root) as _
+    }
+
+    fn info(&self) -> vfs::FsInfo {
+        vfs::FsInfo {
+            bsize: 0,
+            frsize: 0,
+            blocks: 0,
+            bfree: 0,
+            bavail: 0,
+            files: 0,
+            ffree: 0,
+            namemax: 0,
+        }
+    }
+}
+
+impl SysFS {
+    /// Create a new `SysFS`
+    pub fn new() -> Arc<SysFS> {
+        let root = LockedSysDirINode::new_root();
+        let devices = LockedSysDirINode::new_child(&root, "devices");
+        let system = LockedSysDirINode::new_child(&devices, "system");
+        let cpu = LockedSysDirINode::new_child(&system, "cpu");
+        init_cpu_dir(&cpu);
+        Arc::new(SysFS { root })
+    }
+}
+
+/// A directory of SysFS, whose entries never change after initialization.
+struct LockedSysDirINode(RwLock<SysDirINode>);
+
+struct SysDirINode {
+    entries: Vec<(String, Arc<dyn INode>)>,
+    this: Weak<Dir<LockedSysDirINode>>,
+    parent: Weak<Dir<LockedSysDirINode>>,
+}
+
+impl LockedSysDirINode {
+    fn new_root() -> Arc<Dir<LockedSysDirINode>> {
+        let root = Arc::new(Dir::new(LockedSysDirINode(RwLock::new(SysDirINode {
+            entries: Vec::new(),
+            this: Weak::default(),
+            parent: Weak::default(),
+        }))));
+        {
+            let mut dir = root.inner().0.write().unwrap();
+            dir.this = Arc::downgrade(&root);
+            dir.parent = Arc::downgrade(&root);
+        }
+        root
+    }
+
+    fn new_child(parent: &Arc<Dir<LockedSysDirINode>>, name: &str) -> Arc<Dir<LockedSysDirINode>> {
+        let child = Arc::new(Dir::new(LockedSysDirINode(RwLock::new(SysDirINode {
+            entries: Vec::new(),
+            this: Weak::default(),
+            parent: Arc::downgrade(parent),
+        }))));
+        child.inner().0.write().unwrap().this = Arc::downgrade(&child);
+        parent.inner().add_entry(name, Arc::clone(&child) as _);
+        child
+    }
+
+    fn add_entry(&self, name: &str, inode: Arc<dyn INode>) {
+        let mut dir = self.0.write().unwrap();
+        dir.entries.push((String::from(name), inode));
+    }
+
+    fn add_attr(&self, name: &str, value: String) {
+        self.add_entry(name, SysAttrINode::new(value));
+    }
+}
+
+impl DirProcINode for LockedSysDirINode {
+    fn find(&self, name: &str) -> vfs::Result<Arc<dyn INode>> {
+        let dir = self.0.read().unwrap();
+        if name == "." {
+            return Ok(dir.this.upgrade().unwrap());
+        }
+        if name == ".." {
+            return Ok(dir.parent.upgrade().unwrap());
+        }
+        dir.entries
+            .iter()
+            .find(|(entry_name, _)| entry_name == name)
+            .map(|(_, inode)| Arc::clone(inode))
+            .ok_or(FsError::EntryNotFound)
+    }
+
+    fn get_entry(&self, id: usize) -> vfs::Result<String> {
+        match id {
+            0 => Ok(String::from(".")),
+            1 => Ok(String::from("..")),
+            i => {
+                let dir = self.0.read().unwrap();
+                dir.entries
+                    .get(i - 2)
+                    .map(|(name, _)| name.clone())
+                    .ok_or(FsError::EntryNotFound)
+            }
+        }
+    }
+}
+
+/// A read-only attribute file of SysFS, whose content is fixed.
+struct SysAttrINode {
+    value: String,
+}
+
+impl SysAttrINode {
+    fn new(value: String) -> Arc<dyn INode> {
+        Arc::new(File::new(Self { value }))
+    }
+}
+
+impl ProcINode for SysAttrINode {
+    fn generate_data_in_bytes(&self) -> vfs::Result<Vec<u8>> {
+        Ok(self.value.clone().into_bytes())
+    }
+}
@@ -0,0 +1,204 @@
+use super::*;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Weak;
+
+use crate::events::{Waiter, WaiterQueue};
+use crate::time::{itimerspec_t, ClockID, Timer};
+
+/// A timerfd implemented inside LibOS.
+///
+/// A timerfd counts the expirations of its timer, which is driven by the
+/// timer wheel of LibOS. A read returns the number of expirations since the
+/// last read or setting of the timer and resets it to zero. Implementing
+/// timerfd inside LibOS defends against Iago attacks from the host and saves
+/// OCalls.
+pub struct TimerFile {
+    timer: Timer,
+    expirations: Arc<TimerExpirations>,
+    is_nonblocking: AtomicBool,
+}
+
+/// The expirations of a timer, which are shared with the callback of the timer.
+struct TimerExpirations {
+    count: SgxMutex<u64>,
+    // All threads that are blocked on reading the file.
+    waiters: WaiterQueue,
+    notifier: IoNotifier,
+}
+
+impl TimerExpirations {
+    fn add(&self, num_expirations: u64) {
+        {
+            let mut count = self.count.lock().unwrap();
+            *count = count.saturating_add(num_expirations);
+        }
+        self.notifier.broadcast(&IoEvents::IN);
+        self.waiters.dequeue_and_wake_all();
+    }
+}
+
+impl TimerFile {
+    pub fn new(clockid: ClockID, flags: TimerCreationFlags) -> Result<Self> {
+        let expirations = Arc::new(TimerExpirations {
+            count: SgxMutex::new(0),
+            waiters: WaiterQueue::new(),
+            notifier: IoNotifier::new(),
+        });
+        let timer = {
+            let weak_expirations = Arc::downgrade(&expirations);
+            Timer::new(
+                clockid,
+                Arc::new(move |num_expirations| {
+                    if let Some(expirations) = Weak::upgrade(&weak_expirations) {
+                        expirations.add(num_expirations);
+                    }
+                }),
+            )?
+        };
+        let is_nonblocking = AtomicBool::new(flags.contains(TimerCreationFlags::TFD_NONBLOCK));
+        Ok(Self {
+            timer,
+            expirations,
+            is_nonblocking,
+        })
+    }
+
+    /// Arm or disarm the timer, returning the old setting of the timer.
+    pub fn set_time(&self, flags: TimerSetFlags, new_value: &itimerspec_t) -> Result<itimerspec_t> {
+        // LibOS cannot learn the discontinuous changes of the realtime clock,
+        // which are made by the host
+        if flags.contains(TimerSetFlags::TFD_TIMER_CANCEL_ON_SET) {
+            return_errno!(EINVAL, "TFD_TIMER_CANCEL_ON_SET is not supported");
+        }
+        let is_abs = flags.contains(TimerSetFlags::TFD_TIMER_ABSTIME);
+
+        // The expirations of the old setting are discarded before the timer is
+        // armed. The lock is held until then so that an expiration of the new
+        // setting, which may come right away, is counted after the reset.
+        let mut count = self.expirations.count.lock().unwrap();
+        *count = 0;
+        self.timer.set(new_value, is_abs)
+    }
+
+    /// Get the current setting of the timer.
+    pub fn time(&self) -> Result<itimerspec_t> {
+        Ok(self.timer.get())
+    }
+
+    fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Acquire)
+    }
+
+    fn try_read(&self) -> Option<u64> {
+        let mut count = self.expirations.count.lock().unwrap();
+        if *count == 0 {
+            return None;
+        }
+        Some(std::mem::replace(&mut *count, 0))
+    }
+}
+
+bitflags! {
+    pub struct TimerCreationFlags: i32 {
+        /// Non-blocking
+        const TFD_NONBLOCK  = 1 << 11;
+        /// Close on exec
+        const TFD_CLOEXEC   = 1 << 19;
+    }
+}
+
+bitflags! {
+    pub struct TimerSetFlags: i32 {
+        /// The new value is an absolute time
+        const TFD_TIMER_ABSTIME = 1 << 0;
+        /// Cancel the timer if the realtime clock undergoes a discontinuous change
+        const TFD_TIMER_CANCEL_ON_SET = 1 << 1;
+    }
+}
+
+impl File for TimerFile {
+    fn read(&self, buf: &mut [u8]) -> Result<usize> {
+        const VAL_LEN: usize = std::mem::size_of::<u64>();
+        if buf.len() < VAL_LEN {
+            return_errno!(EINVAL, "the buffer is too small");
+        }
+
+        let waiter = Waiter::new();
+        let val = loop {
+            self.expirations.waiters.reset_and_enqueue(&waiter);
+
+            if let Some(val) = self.try_read() {
+                break val;
+            }
+            if self.is_nonblocking() {
+                return_errno!(EAGAIN, "the timer has not expired");
+            }
+
+            waiter.wait(None)?;
+        };
+
+        buf[..VAL_LEN].copy_from_slice(&val.to_ne_bytes());
+        Ok(VAL_LEN)
+    }
+
+    fn access_mode(&self) -> Result<AccessMode> {
+        Ok(AccessMode::O_RDONLY)
+    }
+
+    fn status_flags(&self) -> Result<StatusFlags> {
+        if self.is_nonblocking() {
+            Ok(StatusFlags::O_NONBLOCK)
+        } else {
+            Ok(StatusFlags::empty())
+        }
+    }
+
+    fn set_status_flags(&self, new_status_flags: StatusFlags) -> Result<()> {
+        let nonblocking = new_status_flags.contains(StatusFlags::O_NONBLOCK);
+        self.is_nonblocking.store(nonblocking, Ordering::Release);
+        if nonblocking {
+            // Wake all threads that are blocked on reading this file
+            self.expirations.waiters.dequeue_and_wake_all();
+        }
+        Ok(())
+    }
+
+    fn poll_new(&self) -> IoEvents {
+        if *self.expirations.count.lock().unwrap() > 0 {
+            IoEvents::IN
+        } else {
+            IoEvents::empty()
+        }
+    }
+
+    fn notifier(&self) -> Option<&IoNotifier> {
+        Some(&self.expirations.notifier)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl fmt::Debug for TimerFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimerFile")
+            .field("timer", &self.timer)
+            .field("expirations", &*self.expirations.count.lock().unwrap())
+            .field("is_nonblocking", &self.is_nonblocking())
+            .finish()
+    }
+}
+
+pub trait AsTimer {
+    fn as_timer(&self) -> Result<&TimerFile>;
+}
+
+impl AsTimer for FileRef {
+    fn as_timer(&self) -> Result<&TimerFile> {
+        self.as_any()
+            .downcast_ref::<TimerFile>()
+            .ok_or_else(|| errno!(EINVAL, "not a timer file"))
+    }
+}
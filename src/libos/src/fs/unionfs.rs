@@ -0,0 +1,580 @@
+//! A union file system of a writable layer on top of read-only layers, which
+//! behaves like overlayfs.
+//!
+//! A file of the read-only layers is copied up to the writable layer before it
+//! is updated. Removing an entry that exists in the read-only layers leaves a
+//! whiteout, i.e., an empty file named `.wh.<name>` in the writable layer,
+//! which hides the entries of the lower layers. A directory that is created in
+//! place of a removed one is opaque, i.e., it contains the `.wh..wh..opq` file,
+//! so that the entries of the lower layers are not merged into it. Whiteouts
+//! are ordinary files of the writable layer, so the removed entries do not
+//! resurrect after remounting.
+//!
+//! Renaming a directory that is merged from the read-only layers fails with
+//! EXDEV, as overlayfs does without redirect_dir. Callers like `mv` fall back
+//! to copying.
+
+use super::*;
+use rcore_fs::vfs::{self, FsInfo, PollStatus};
+use std::collections::BTreeSet;
+use std::sync::Weak;
+
+/// The prefix of the names of whiteouts, which are hidden from the users
+const WHITEOUT_PREFIX: &str = ".wh.";
+/// The file that marks a directory of the writable layer as opaque
+const OPAQUE_MARKER: &str = ".wh..wh..opq";
+/// The size of the chunks in which the files are copied up
+const COPY_UP_CHUNK_SIZE: usize = 64 * 1024;
+/// The inode numbers of the layers are told apart by the bits above it
+const LAYER_SHIFT: usize = 48;
+
+pub struct UnionFS {
+    /// The layers from the top to the bottom, of which the top one is writable
+    layers: Vec<Arc<dyn FileSystem>>,
+    self_ref: Weak<UnionFS>,
+}
+
+impl UnionFS {
+    /// Create a UnionFS of the layers, which are given from the top to the
+    /// bottom. Only the top layer is updated.
+    pub fn new(layers: Vec<Arc<dyn FileSystem>>) -> Result<Arc<Self>> {
+        if layers.is_empty() {
+            return_errno!(EINVAL, "no layers for UnionFS");
+        }
+        let fs = Arc::new(Self {
+            layers,
+            self_ref: Weak::default(),
+        });
+        let weak = Arc::downgrade(&fs);
+        let ptr = Arc::into_raw(fs) as *mut Self;
+        unsafe {
+            (*ptr).self_ref = weak;
+            Ok(Arc::from_raw(ptr))
+        }
+    }
+}
+
+impl FileSystem for UnionFS {
+    fn sync(&self) -> vfs::Result<()> {
+        // The read-only layers are never updated
+        self.layers[0].sync()
+    }
+
+    fn root_inode(&self) -> Arc<dyn INode> {
+        let mut inodes = Vec::new();
+        for (layer, fs) in self.layers.iter().enumerate() {
+            let root = fs.root_inode();
+            let is_opaque = is_opaque(&root);
+            inodes.push((layer, root));
+            if is_opaque {
+                break;
+            }
+        }
+        UnionINode::new(self.self_ref.upgrade().unwrap(), None, inodes)
+            .expect("the root inodes of the layers must be valid")
+    }
+
+    fn info(&self) -> FsInfo {
+        self.layers[0].info()
+    }
+}
+
+/// The inode of UnionFS.
+pub struct UnionINode {
+    /// The parent directory and the name in it, which are needed to copy up
+    parent: Option<(Arc<UnionINode>, String)>,
+    /// The inodes of the layers that make up this inode, from the top to the
+    /// bottom, together with the indexes of their layers. A directory is
+    /// merged from the directories of the layers until an opaque one, while a
+    /// non-directory is its top-most inode only.
+    inodes: SgxRwLock<Vec<(usize, Arc<dyn INode>)>>,
+    /// The inode number, which is kept even if the inode is copied up
+    id: usize,
+    /// The merged entries of the directory, which are cached for `get_entry`
+    entries: SgxMutex<Vec<String>>,
+    fs: Arc<UnionFS>,
+    self_ref: Weak<UnionINode>,
+}
+
+impl UnionINode {
+    fn new(
+        fs: Arc<UnionFS>,
+        parent: Option<(Arc<UnionINode>, String)>,
+        inodes: Vec<(usize, Arc<dyn INode>)>,
+    ) -> vfs::Result<Arc<Self>> {
+        // The inode number of a directory is decided by its bottom-most layer,
+        // which is not changed by copying up
+        let id = {
+            let (layer, inode) = if inodes[0].1.metadata()?.type_ == FileType::Dir {
+                inodes.last().unwrap()
+            } else {
+                &inodes[0]
+            };
+            inode.metadata()?.inode | (layer << LAYER_SHIFT)
+        };
+        let inode = Arc::new(Self {
+            parent,
+            inodes: SgxRwLock::new(inodes),
+            id,
+            entries: SgxMutex::new(Vec::new()),
+            fs,
+            self_ref: Weak::default(),
+        });
+        let weak = Arc::downgrade(&inode);
+        let ptr = Arc::into_raw(inode) as *mut Self;
+        unsafe {
+            (*ptr).self_ref = weak;
+            Ok(Arc::from_raw(ptr))
+        }
+    }
+
+    fn top(&self) -> Arc<dyn INode> {
+        self.inodes.read().unwrap()[0].1.clone()
+    }
+
+    fn is_dir(&self) -> vfs::Result<bool> {
+        Ok(self.top().metadata()?.type_ == FileType::Dir)
+    }
+
+    fn has_lower(&self) -> bool {
+        self.inodes
+            .read()
+            .unwrap()
+            .iter()
+            .any(|(layer, _)| *layer > 0)
+    }
+
+    /// Whether the entry exists in the lower layers of the directory, which has
+    /// to be hidden by a whiteout once it is removed from the writable layer.
+    fn lower_has_entry(&self, name: &str) -> vfs::Result<bool> {
+        let whiteout = whiteout_name(name);
+        let dirs = self.inodes.read().unwrap().clone();
+        for (_, dir) in dirs.iter().filter(|(layer, _)| *layer > 0) {
+            if dir.find(name).is_ok() {
+                return Ok(true);
+            }
+            if dir.find(&whiteout).is_ok() {
+                return Ok(false);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Get the inode of the writable layer if there is one.
+    ///
+    /// The inode may have been copied up through another `UnionINode` of the
+    /// same path, so it is looked up again if it is not known yet.
+    fn upper(&self) -> vfs::Result<Option<Arc<dyn INode>>> {
+        if let Some((0, upper)) = self.inodes.read().unwrap().first() {
+            return Ok(Some(upper.clone()));
+        }
+        let (parent, name) = match &self.parent {
+            Some(parent) => parent,
+            None => return Ok(None),
+        };
+        let upper = match parent.upper()? {
+            Some(upper_dir) => match upper_dir.find(name) {
+                Ok(upper) => upper,
+                Err(FsError::EntryNotFound) => return Ok(None),
+                Err(e) => return Err(e),
+            },
+            None => return Ok(None),
+        };
+        let mut inodes = self.inodes.write().unwrap();
+        if inodes[0].0 != 0 {
+            if upper.metadata()?.type_ != FileType::Dir || is_opaque(&upper) {
+                inodes.clear();
+            }
+            inodes.insert(0, (0, upper.clone()));
+        }
+        Ok(Some(upper))
+    }
+
+    /// Copy up the inode to the writable layer if it is not there yet.
+    ///
+    /// The parent directories are copied up first. A directory is copied up
+    /// without its entries, which are still merged from the lower layers.
+    fn copy_up(&self) -> vfs::Result<Arc<dyn INode>> {
+        if let Some(upper) = self.upper()? {
+            return Ok(upper);
+        }
+        // The root of the writable layer always exists
+        let (parent, name) = self.parent.as_ref().unwrap();
+        let upper_dir = parent.copy_up()?;
+        if upper_dir.find(&whiteout_name(name)).is_ok() {
+            // The inode has been removed through another `UnionINode`
+            return Err(FsError::EntryNotFound);
+        }
+
+        let lower = self.top();
+        let metadata = lower.metadata()?;
+        let upper = upper_dir.create(name, metadata.type_, metadata.mode as u32)?;
+        if metadata.type_ == FileType::File || metadata.type_ == FileType::SymLink {
+            if let Err(e) = copy_data(&lower, &upper) {
+                let _ = upper_dir.unlink(name);
+                return Err(e);
+            }
+        }
+        let mut upper_metadata = upper.metadata()?;
+        upper_metadata.mode = metadata.mode;
+        upper_metadata.uid = metadata.uid;
+        upper_metadata.gid = metadata.gid;
+        upper_metadata.atime = metadata.atime;
+        upper_metadata.mtime = metadata.mtime;
+        upper_metadata.ctime = metadata.ctime;
+        upper.set_metadata(&upper_metadata)?;
+
+        let mut inodes = self.inodes.write().unwrap();
+        if metadata.type_ != FileType::Dir {
+            inodes.clear();
+        }
+        inodes.insert(0, (0, upper.clone()));
+        Ok(upper)
+    }
+
+    /// Get the inode that holds the extended attributes, which is copied up
+    /// if the attributes are to be updated.
+    pub fn xattr_inode(&self, for_update: bool) -> vfs::Result<Arc<dyn INode>> {
+        if for_update {
+            return self.copy_up();
+        }
+        Ok(self.upper()?.unwrap_or_else(|| self.top()))
+    }
+
+    /// Get the merged entries of the directory, including "." and "..".
+    fn merged_entries(&self) -> vfs::Result<Vec<String>> {
+        self.upper()?;
+        let dirs = self.inodes.read().unwrap().clone();
+        let mut entries = Vec::new();
+        let mut seen = BTreeSet::new();
+        // The whiteouts of a layer hide the entries of the layers below it
+        let mut whiteouts = BTreeSet::new();
+        for (_, dir) in dirs.iter() {
+            let mut layer_whiteouts = Vec::new();
+            for name in dir_entries(dir)? {
+                if let Some(removed_name) = name.strip_prefix(WHITEOUT_PREFIX) {
+                    layer_whiteouts.push(removed_name.to_string());
+                    continue;
+                }
+                if whiteouts.contains(&name) || !seen.insert(name.clone()) {
+                    continue;
+                }
+                entries.push(name);
+            }
+            whiteouts.extend(layer_whiteouts);
+        }
+        Ok(entries)
+    }
+
+    fn is_empty_dir(&self) -> vfs::Result<bool> {
+        Ok(self
+            .merged_entries()?
+            .iter()
+            .all(|name| name == "." || name == ".."))
+    }
+
+    /// Remove the whiteouts in the directory of the writable layer, which must
+    /// be empty after merging.
+    fn remove_whiteouts(dir: &Arc<dyn INode>) -> vfs::Result<()> {
+        for name in dir_entries(dir)? {
+            if name.starts_with(WHITEOUT_PREFIX) {
+                dir.unlink(&name)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn find_child(&self, name: &str) -> vfs::Result<Arc<UnionINode>> {
+        let child = self.find(name)?;
+        Ok(child.downcast_ref::<UnionINode>().unwrap().to_arc())
+    }
+
+    fn to_arc(&self) -> Arc<UnionINode> {
+        self.self_ref.upgrade().unwrap()
+    }
+
+    fn wrap_child(
+        &self,
+        name: &str,
+        inodes: Vec<(usize, Arc<dyn INode>)>,
+    ) -> vfs::Result<Arc<dyn INode>> {
+        let child = UnionINode::new(
+            self.fs.clone(),
+            Some((self.to_arc(), name.to_string())),
+            inodes,
+        )?;
+        Ok(child)
+    }
+}
+
+impl INode for UnionINode {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> vfs::Result<usize> {
+        self.top().read_at(offset, buf)
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> vfs::Result<usize> {
+        self.copy_up()?.write_at(offset, buf)
+    }
+
+    fn poll(&self) -> vfs::Result<PollStatus> {
+        self.top().poll()
+    }
+
+    fn metadata(&self) -> vfs::Result<Metadata> {
+        let mut metadata = self.top().metadata()?;
+        metadata.inode = self.id;
+        Ok(metadata)
+    }
+
+    fn set_metadata(&self, metadata: &Metadata) -> vfs::Result<()> {
+        self.copy_up()?.set_metadata(metadata)
+    }
+
+    fn sync_all(&self) -> vfs::Result<()> {
+        self.top().sync_all()
+    }
+
+    fn sync_data(&self) -> vfs::Result<()> {
+        self.top().sync_data()
+    }
+
+    fn resize(&self, len: usize) -> vfs::Result<()> {
+        self.copy_up()?.resize(len)
+    }
+
+    fn fallocate(&self, mode: u32, offset: u64, len: u64) -> vfs::Result<()> {
+        self.copy_up()?.fallocate(mode, offset, len)
+    }
+
+    fn create(&self, name: &str, type_: FileType, mode: u32) -> vfs::Result<Arc<dyn INode>> {
+        if name.starts_with(WHITEOUT_PREFIX) {
+            return Err(FsError::InvalidParam);
+        }
+        if self.find(name).is_ok() {
+            return Err(FsError::EntryExist);
+        }
+        let upper_dir = self.copy_up()?;
+        let whiteout = whiteout_name(name);
+        let replaces_removed = upper_dir.find(&whiteout).is_ok();
+        let inode = upper_dir.create(name, type_, mode)?;
+        if replaces_removed {
+            // Do not merge the removed directory of the lower layers
+            if type_ == FileType::Dir {
+                inode.create(OPAQUE_MARKER, FileType::File, 0o600)?;
+            }
+            upper_dir.unlink(&whiteout)?;
+        }
+        self.wrap_child(name, vec![(0, inode)])
+    }
+
+    fn link(&self, name: &str, other: &Arc<dyn INode>) -> vfs::Result<()> {
+        let other = other
+            .downcast_ref::<UnionINode>()
+            .ok_or(FsError::NotSameFs)?;
+        if other.is_dir()? {
+            return Err(FsError::IsDir);
+        }
+        if self.find(name).is_ok() {
+            return Err(FsError::EntryExist);
+        }
+        let other_upper = other.copy_up()?;
+        let upper_dir = self.copy_up()?;
+        upper_dir.link(name, &other_upper)?;
+        let whiteout = whiteout_name(name);
+        if upper_dir.find(&whiteout).is_ok() {
+            upper_dir.unlink(&whiteout)?;
+        }
+        Ok(())
+    }
+
+    fn unlink(&self, name: &str) -> vfs::Result<()> {
+        if name == "." || name == ".." {
+            return Err(FsError::IsDir);
+        }
+        let child = self.find_child(name)?;
+        let is_dir = child.is_dir()?;
+        if is_dir && !child.is_empty_dir()? {
+            return Err(FsError::DirNotEmpty);
+        }
+        let upper_dir = self.copy_up()?;
+        // The whiteout is created first, so that the entry of the lower layers
+        // never shows up even if the removal is interrupted
+        if self.lower_has_entry(name)? {
+            upper_dir.create(&whiteout_name(name), FileType::File, 0o600)?;
+        }
+        if let Some(upper) = child.upper()? {
+            if is_dir {
+                Self::remove_whiteouts(&upper)?;
+            }
+            upper_dir.unlink(name)?;
+        }
+        Ok(())
+    }
+
+    fn move_(&self, old_name: &str, target: &Arc<dyn INode>, new_name: &str) -> vfs::Result<()> {
+        let target = target
+            .downcast_ref::<UnionINode>()
+            .ok_or(FsError::NotSameFs)?;
+        let child = self.find_child(old_name)?;
+        let is_dir = child.is_dir()?;
+        if is_dir && child.has_lower() {
+            return Err(FsError::NotSameFs);
+        }
+        let replaced = match target.find_child(new_name) {
+            Ok(replaced) => Some(replaced),
+            Err(FsError::EntryNotFound) => None,
+            Err(e) => return Err(e),
+        };
+        if let Some(replaced) = replaced.as_ref() {
+            if replaced.id == child.id {
+                return Ok(());
+            }
+            if replaced.is_dir()? {
+                if !is_dir {
+                    return Err(FsError::IsDir);
+                }
+                if !replaced.is_empty_dir()? {
+                    return Err(FsError::DirNotEmpty);
+                }
+            } else if is_dir {
+                return Err(FsError::NotDir);
+            }
+        }
+
+        let child_upper = child.copy_up()?;
+        let target_upper = target.copy_up()?;
+        let upper_dir = self.copy_up()?;
+        let new_whiteout = whiteout_name(new_name);
+        let replaces_removed = target_upper.find(&new_whiteout).is_ok();
+        if let Some(replaced) = replaced.as_ref() {
+            if let Some(replaced_upper) = replaced.upper()? {
+                if is_dir {
+                    Self::remove_whiteouts(&replaced_upper)?;
+                }
+            }
+        }
+        // The moved directory must not be merged with the removed or the
+        // replaced directory of the lower layers
+        let hides_lower = replaces_removed || target.lower_has_entry(new_name)?;
+        if is_dir && hides_lower && child_upper.find(OPAQUE_MARKER).is_err() {
+            child_upper.create(OPAQUE_MARKER, FileType::File, 0o600)?;
+        }
+        if self.lower_has_entry(old_name)? {
+            upper_dir.create(&whiteout_name(old_name), FileType::File, 0o600)?;
+        }
+        upper_dir.move_(old_name, &target_upper, new_name)?;
+        if replaces_removed {
+            target_upper.unlink(&new_whiteout)?;
+        }
+        Ok(())
+    }
+
+    fn find(&self, name: &str) -> vfs::Result<Arc<dyn INode>> {
+        match name {
+            "." => return Ok(self.to_arc()),
+            ".." => {
+                return Ok(match &self.parent {
+                    Some((parent, _)) => parent.clone(),
+                    None => self.to_arc(),
+                })
+            }
+            _ => {}
+        }
+        if name.starts_with(WHITEOUT_PREFIX) {
+            return Err(FsError::EntryNotFound);
+        }
+        if !self.is_dir()? {
+            return Err(FsError::NotDir);
+        }
+        self.upper()?;
+        let dirs = self.inodes.read().unwrap().clone();
+        let whiteout = whiteout_name(name);
+        let mut inodes = Vec::new();
+        for (layer, dir) in dirs {
+            let inode = match dir.find(name) {
+                Ok(inode) => inode,
+                Err(FsError::EntryNotFound) => {
+                    if dir.find(&whiteout).is_ok() {
+                        break;
+                    }
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let is_dir = inode.metadata()?.type_ == FileType::Dir;
+            // A non-directory of the lower layers is hidden by a directory
+            if !inodes.is_empty() && !is_dir {
+                break;
+            }
+            let is_opaque = is_dir && is_opaque(&inode);
+            inodes.push((layer, inode));
+            if !is_dir || is_opaque {
+                break;
+            }
+        }
+        if inodes.is_empty() {
+            return Err(FsError::EntryNotFound);
+        }
+        self.wrap_child(name, inodes)
+    }
+
+    fn get_entry(&self, id: usize) -> vfs::Result<String> {
+        if !self.is_dir()? {
+            return Err(FsError::NotDir);
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if id == 0 || entries.is_empty() {
+            *entries = self.merged_entries()?;
+        }
+        entries.get(id).cloned().ok_or(FsError::EntryNotFound)
+    }
+
+    fn io_control(&self, cmd: u32, data: usize) -> vfs::Result<()> {
+        self.top().io_control(cmd, data)
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.clone()
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
+
+fn whiteout_name(name: &str) -> String {
+    format!("{}{}", WHITEOUT_PREFIX, name)
+}
+
+fn is_opaque(dir: &Arc<dyn INode>) -> bool {
+    dir.find(OPAQUE_MARKER).is_ok()
+}
+
+fn dir_entries(dir: &Arc<dyn INode>) -> vfs::Result<Vec<String>> {
+    let mut entries = Vec::new();
+    for id in 0.. {
+        match dir.get_entry(id) {
+            Ok(name) => entries.push(name),
+            Err(FsError::EntryNotFound) => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(entries)
+}
+
+fn copy_data(from: &Arc<dyn INode>, to: &Arc<dyn INode>) -> vfs::Result<()> {
+    let mut buf = vec![0u8; COPY_UP_CHUNK_SIZE];
+    let mut offset = 0;
+    loop {
+        let len = from.read_at(offset, &mut buf)?;
+        if len == 0 {
+            break;
+        }
+        let mut written = 0;
+        while written < len {
+            written += to.write_at(offset + written, &buf[written..len])?;
+        }
+        offset += len;
+    }
+    Ok(())
+}
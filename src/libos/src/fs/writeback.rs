@@ -0,0 +1,42 @@
+//! The writeback of the dirty data of the file systems.
+//!
+//! The dirty data of SEFS, including the dirty pages in the page cache, is
+//! written back by a background thread of PAL, which calls `do_writeback`
+//! periodically through an ECall. The data is written back when the writeback
+//! interval in the config has elapsed, or when the dirty pages take more than
+//! half of the page cache.
+
+use super::sefs::PAGE_CACHE;
+use super::*;
+use crate::config::LIBOS_CONFIG;
+use crate::time::up_time;
+use std::time::Duration;
+
+lazy_static! {
+    /// The up time of the last writeback
+    static ref LAST_WRITEBACK: SgxMutex<Duration> = SgxMutex::new(Duration::from_secs(0));
+}
+
+pub fn do_writeback() -> Result<()> {
+    let now = up_time::get().unwrap();
+    let mut last_writeback = LAST_WRITEBACK.lock().unwrap();
+
+    let is_due = match LIBOS_CONFIG.fs.writeback_interval {
+        Some(interval) => now >= *last_writeback + interval,
+        None => false,
+    };
+    if is_due {
+        debug!("write back the dirty data of the file systems");
+        PAGE_CACHE.flush_all()?;
+        ROOT_INODE.read().unwrap().fs().sync()?;
+        *last_writeback = now;
+    } else if has_memory_pressure() {
+        debug!("write back the dirty pages on memory pressure");
+        PAGE_CACHE.flush_all()?;
+    }
+    Ok(())
+}
+
+fn has_memory_pressure() -> bool {
+    PAGE_CACHE.is_enabled() && PAGE_CACHE.num_dirty_pages() * 2 > PAGE_CACHE.capacity()
+}
@@ -0,0 +1,470 @@
+//! Extended attributes of inodes.
+//!
+//! The extended attributes are managed by LibOS instead of the underlying
+//! file systems, so that they are supported by all the file systems (e.g.,
+//! SEFS, UnionFS, RamFS, and HostFS) in the same way. The attributes belong to
+//! the inodes of the underlying file systems, rather than those of MountFS and
+//! BindFS that wrap them, and an inode of UnionFS is copied up before its
+//! attributes are updated.
+//!
+//! The attributes of SEFS are persisted in the files along with its metadata
+//! file, see `XattrFile`, while those of the other file systems are kept in the
+//! memory of the enclave, same as their inodes.
+//!
+//! The attributes of an inode whose last link is removed are dropped once the
+//! inode is released, i.e., after it is closed by all the processes, so that
+//! they are not inherited by a new inode with the same number.
+//!
+//! POSIX ACLs are stored as system attributes, see the `posix_acl` module.
+
+use super::bindfs::BindINode;
+use super::file_ops::check_inode_access;
+use super::sefs::{SgxStorage, XattrFile};
+use super::unionfs::UnionINode;
+use super::*;
+use rcore_fs_mountfs::MNode;
+use rcore_fs_sefs::dev::Storage;
+use rcore_fs_sefs::SEFS;
+use std::convert::TryInto;
+use std::sync::Weak;
+
+/// The maximum length of the name of an attribute.
+pub const XATTR_NAME_MAX: usize = 255;
+/// The maximum size of the value of an attribute.
+pub const XATTR_SIZE_MAX: usize = 65536;
+/// The maximum size of the list of attribute names.
+pub const XATTR_LIST_MAX: usize = 65536;
+
+lazy_static! {
+    /// The attributes of the file systems that are in use
+    static ref XATTR_STORES: SgxMutex<Vec<(Weak<dyn FileSystem>, Arc<XattrStore>)>> =
+        SgxMutex::new(Vec::new());
+}
+
+bitflags! {
+    pub struct XattrFlags: i32 {
+        /// Fail if the attribute already exists
+        const XATTR_CREATE  = 0x1;
+        /// Fail if the attribute does not exist
+        const XATTR_REPLACE = 0x2;
+    }
+}
+
+/// The namespaces of extended attributes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum XattrNamespace {
+    Security,
+    System,
+    Trusted,
+    User,
+}
+
+impl XattrNamespace {
+    /// Parse the namespace from the prefix of the name of an attribute.
+    pub fn from_name(name: &str) -> Result<Self> {
+        let (prefix, suffix) = match name.find('.') {
+            Some(idx) => (&name[..idx], &name[idx + 1..]),
+            None => return_errno!(EOPNOTSUPP, "the name has no namespace"),
+        };
+        if suffix.is_empty() {
+            return_errno!(EINVAL, "the name has only a namespace");
+        }
+        Ok(match prefix {
+            "security" => XattrNamespace::Security,
+            "system" => XattrNamespace::System,
+            "trusted" => XattrNamespace::Trusted,
+            "user" => XattrNamespace::User,
+            _ => return_errno!(EOPNOTSUPP, "unknown namespace"),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Xattr {
+    name: String,
+    value: Vec<u8>,
+}
+
+/// The attributes of an inode.
+#[derive(Debug, Default)]
+struct InodeXattrs {
+    xattrs: Vec<Xattr>,
+    /// Whether the last link to the inode is removed
+    is_unlinked: bool,
+}
+
+/// The attributes of the inodes of a file system, indexed by inode numbers.
+struct XattrStore {
+    inner: SgxMutex<XattrStoreInner>,
+}
+
+struct XattrStoreInner {
+    inodes: HashMap<usize, InodeXattrs>,
+    /// The unlinked inodes, whose attributes are dropped once they are released
+    unlinked: Vec<(usize, Weak<dyn INode>)>,
+    /// The file where the attributes are persisted, if any
+    file: Option<XattrFile>,
+    is_read_only: bool,
+}
+
+/// Persist the attributes of SEFS in its storage.
+///
+/// The attributes of an integrity protected SEFS, which is read-only, are
+/// always empty.
+pub fn register_sefs(sefs: &Arc<SEFS>, storage: &SgxStorage, is_new: bool) -> Result<()> {
+    let inner = if storage.protect_integrity() {
+        XattrStoreInner::new(None, true)
+    } else {
+        let (file, content) = XattrFile::open_or_create(storage, is_new)?;
+        let mut inner = XattrStoreInner::new(Some(file), false);
+        inner.inodes = decode_xattrs(&content)?;
+        // No inode can be open since SEFS is mounted
+        inner
+            .inodes
+            .retain(|_, inode_xattrs| !inode_xattrs.is_unlinked);
+        inner
+    };
+    let fs = sefs.clone() as Arc<dyn FileSystem>;
+    XATTR_STORES.lock().unwrap().push((
+        Arc::downgrade(&fs),
+        Arc::new(XattrStore {
+            inner: SgxMutex::new(inner),
+        }),
+    ));
+    Ok(())
+}
+
+impl XattrStore {
+    /// Get the store of the file system of the inode. The attributes of a file
+    /// system that is not registered are kept in memory.
+    fn of(inode: &dyn INode) -> Arc<Self> {
+        let fs = inode.fs();
+        let fs_ptr = &*fs as *const dyn FileSystem as *const u8;
+        let is_same_fs = |weak_fs: &Weak<dyn FileSystem>| match weak_fs.upgrade() {
+            Some(other_fs) => &*other_fs as *const dyn FileSystem as *const u8 == fs_ptr,
+            None => false,
+        };
+        let mut stores = XATTR_STORES.lock().unwrap();
+        stores.retain(|(weak_fs, _)| weak_fs.strong_count() > 0);
+        if let Some((_, store)) = stores.iter().find(|(weak_fs, _)| is_same_fs(weak_fs)) {
+            return store.clone();
+        }
+        let store = Arc::new(XattrStore {
+            inner: SgxMutex::new(XattrStoreInner::new(None, false)),
+        });
+        stores.push((Arc::downgrade(&fs), store.clone()));
+        store
+    }
+
+    /// Lock the store, dropping the attributes of the released inodes first.
+    fn lock(&self) -> Result<SgxMutexGuard<XattrStoreInner>> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.drop_released()?;
+        Ok(inner)
+    }
+}
+
+impl XattrStoreInner {
+    fn new(file: Option<XattrFile>, is_read_only: bool) -> Self {
+        Self {
+            inodes: HashMap::new(),
+            unlinked: Vec::new(),
+            file,
+            is_read_only,
+        }
+    }
+
+    fn check_writable(&self) -> Result<()> {
+        if self.is_read_only {
+            return_errno!(EROFS, "the file system is read-only");
+        }
+        Ok(())
+    }
+
+    fn drop_released(&mut self) -> Result<()> {
+        let mut is_changed = false;
+        let inodes = &mut self.inodes;
+        self.unlinked.retain(|(ino, inode)| {
+            if inode.strong_count() > 0 {
+                return true;
+            }
+            is_changed |= inodes.remove(ino).is_some();
+            false
+        });
+        if is_changed {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    fn save(&mut self) -> Result<()> {
+        if let Some(file) = self.file.as_mut() {
+            file.save(&encode_xattrs(&self.inodes))?;
+        }
+        Ok(())
+    }
+}
+
+// The persisted attributes are the attributes of the inodes one after another.
+// The attributes of an inode are its number, whether it is unlinked and the
+// number of its attributes, followed by the names and the values of them.
+fn encode_xattrs(inodes: &HashMap<usize, InodeXattrs>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for (ino, inode_xattrs) in inodes {
+        buf.extend_from_slice(&(*ino as u64).to_le_bytes());
+        buf.push(inode_xattrs.is_unlinked as u8);
+        buf.extend_from_slice(&(inode_xattrs.xattrs.len() as u32).to_le_bytes());
+        for xattr in &inode_xattrs.xattrs {
+            buf.extend_from_slice(&(xattr.name.len() as u32).to_le_bytes());
+            buf.extend_from_slice(xattr.name.as_bytes());
+            buf.extend_from_slice(&(xattr.value.len() as u32).to_le_bytes());
+            buf.extend_from_slice(&xattr.value);
+        }
+    }
+    buf
+}
+
+fn decode_xattrs(mut buf: &[u8]) -> Result<HashMap<usize, InodeXattrs>> {
+    fn take<'a>(buf: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+        if buf.len() < len {
+            return_errno!(EINVAL, "the persisted attributes are corrupted");
+        }
+        let (bytes, rest) = buf.split_at(len);
+        *buf = rest;
+        Ok(bytes)
+    }
+    fn take_u32(buf: &mut &[u8]) -> Result<usize> {
+        Ok(u32::from_le_bytes(take(buf, 4)?.try_into().unwrap()) as usize)
+    }
+
+    let mut inodes = HashMap::new();
+    while !buf.is_empty() {
+        let ino = u64::from_le_bytes(take(&mut buf, 8)?.try_into().unwrap()) as usize;
+        let is_unlinked = take(&mut buf, 1)?[0] != 0;
+        let num_xattrs = take_u32(&mut buf)?;
+        let mut xattrs = Vec::new();
+        for _ in 0..num_xattrs {
+            let name_len = take_u32(&mut buf)?;
+            let name = String::from_utf8(take(&mut buf, name_len)?.to_vec())
+                .map_err(|_| errno!(EINVAL, "the persisted attributes are corrupted"))?;
+            let value_len = take_u32(&mut buf)?;
+            let value = take(&mut buf, value_len)?.to_vec();
+            xattrs.push(Xattr { name, value });
+        }
+        inodes.insert(
+            ino,
+            InodeXattrs {
+                xattrs,
+                is_unlinked,
+            },
+        );
+    }
+    Ok(inodes)
+}
+
+/// Call the function with the inode of the underlying file system that holds
+/// the attributes of the given inode.
+fn with_real_inode<R, F>(inode: &dyn INode, for_update: bool, f: F) -> Result<R>
+where
+    F: FnOnce(&dyn INode) -> Result<R>,
+{
+    let any = inode.as_any_ref();
+    if let Some(mnode) = any.downcast_ref::<MNode>() {
+        return with_real_inode(mnode.inode.as_ref(), for_update, f);
+    }
+    if let Some(bind_inode) = any.downcast_ref::<BindINode>() {
+        return with_real_inode(bind_inode.xattr_inode(for_update)?.as_ref(), for_update, f);
+    }
+    if let Some(union_inode) = any.downcast_ref::<UnionINode>() {
+        return with_real_inode(union_inode.xattr_inode(for_update)?.as_ref(), for_update, f);
+    }
+    f(inode)
+}
+
+/// Same as `with_real_inode`, but the inode is not updated.
+fn real_inode(inode: &Arc<dyn INode>) -> Result<Arc<dyn INode>> {
+    let any = inode.as_any_ref();
+    let inner = if let Some(mnode) = any.downcast_ref::<MNode>() {
+        mnode.inode.clone()
+    } else if let Some(bind_inode) = any.downcast_ref::<BindINode>() {
+        bind_inode.xattr_inode(false)?.clone()
+    } else if let Some(union_inode) = any.downcast_ref::<UnionINode>() {
+        union_inode.xattr_inode(false)?
+    } else {
+        return Ok(inode.clone());
+    };
+    real_inode(&inner)
+}
+
+/// Check whether the attribute can be accessed on the inode.
+fn check_xattr(
+    inode: &dyn INode,
+    metadata: &Metadata,
+    name: &str,
+    access_mode: AccessibilityCheckMode,
+) -> Result<()> {
+    if name.is_empty() || name.len() > XATTR_NAME_MAX {
+        return_errno!(ERANGE, "the length of the name is invalid");
+    }
+    match XattrNamespace::from_name(name)? {
+        XattrNamespace::User => {
+            // Same as Linux, user attributes are only allowed for regular files
+            // and directories, whose permissions control the access
+            if metadata.type_ != FileType::File && metadata.type_ != FileType::Dir {
+                return_errno!(EPERM, "user attributes are not allowed on this file type");
+            }
+            check_inode_access(inode, access_mode)?;
+        }
+        XattrNamespace::System => {
+            // The only supported system attributes are POSIX ACLs
+            if PosixAclType::from_xattr_name(name).is_none() {
+                return_errno!(EOPNOTSUPP, "system attributes are not supported");
+            }
+        }
+        XattrNamespace::Security | XattrNamespace::Trusted => {}
+    }
+    Ok(())
+}
+
+/// The extended attributes of all inodes.
+pub struct XattrTable;
+
+impl XattrTable {
+    pub fn get(inode: &dyn INode, name: &str) -> Result<Vec<u8>> {
+        let metadata = inode.metadata()?;
+        check_xattr(inode, &metadata, name, AccessibilityCheckMode::R_OK)?;
+        with_real_inode(inode, false, |inode| {
+            let ino = inode.metadata()?.inode;
+            let store = XattrStore::of(inode);
+            let inner = store.lock()?;
+            inner
+                .inodes
+                .get(&ino)
+                .and_then(|inode_xattrs| {
+                    inode_xattrs.xattrs.iter().find(|xattr| xattr.name == name)
+                })
+                .map(|xattr| xattr.value.clone())
+                .ok_or_else(|| errno!(ENODATA, "the attribute does not exist"))
+        })
+    }
+
+    pub fn set(inode: &dyn INode, name: &str, value: &[u8], flags: XattrFlags) -> Result<()> {
+        let metadata = inode.metadata()?;
+        check_xattr(inode, &metadata, name, AccessibilityCheckMode::W_OK)?;
+        if value.len() > XATTR_SIZE_MAX {
+            return_errno!(E2BIG, "the value is too large");
+        }
+
+        with_real_inode(inode, true, |inode| {
+            let ino = inode.metadata()?.inode;
+            let store = XattrStore::of(inode);
+            let mut inner = store.lock()?;
+            inner.check_writable()?;
+            let xattrs = &mut inner.inodes.entry(ino).or_default().xattrs;
+            match xattrs.iter_mut().find(|xattr| xattr.name == name) {
+                Some(xattr) => {
+                    if flags.contains(XattrFlags::XATTR_CREATE) {
+                        return_errno!(EEXIST, "the attribute already exists");
+                    }
+                    xattr.value = value.to_vec();
+                }
+                None => {
+                    if flags.contains(XattrFlags::XATTR_REPLACE) {
+                        return_errno!(ENODATA, "the attribute does not exist");
+                    }
+                    let list_len = xattrs
+                        .iter()
+                        .map(|xattr| xattr.name.len() + 1)
+                        .sum::<usize>();
+                    if list_len + name.len() + 1 > XATTR_LIST_MAX {
+                        return_errno!(ENOSPC, "too many attributes");
+                    }
+                    xattrs.push(Xattr {
+                        name: name.to_owned(),
+                        value: value.to_vec(),
+                    });
+                }
+            }
+            inner.save()
+        })?;
+        inotify::notify_inode(inode, InotifyMask::IN_ATTRIB);
+        Ok(())
+    }
+
+    /// Get the names of all the attributes of the inode.
+    pub fn list(inode: &dyn INode) -> Result<Vec<String>> {
+        with_real_inode(inode, false, |inode| {
+            let ino = inode.metadata()?.inode;
+            let store = XattrStore::of(inode);
+            let inner = store.lock()?;
+            let names = inner
+                .inodes
+                .get(&ino)
+                .map(|inode_xattrs| {
+                    inode_xattrs
+                        .xattrs
+                        .iter()
+                        .map(|xattr| xattr.name.clone())
+                        .collect()
+                })
+                .unwrap_or_default();
+            Ok(names)
+        })
+    }
+
+    pub fn remove(inode: &dyn INode, name: &str) -> Result<()> {
+        let metadata = inode.metadata()?;
+        check_xattr(inode, &metadata, name, AccessibilityCheckMode::W_OK)?;
+        with_real_inode(inode, true, |inode| {
+            let ino = inode.metadata()?.inode;
+            let store = XattrStore::of(inode);
+            let mut inner = store.lock()?;
+            inner.check_writable()?;
+            let inode_xattrs = inner
+                .inodes
+                .get_mut(&ino)
+                .ok_or_else(|| errno!(ENODATA, "the attribute does not exist"))?;
+            let idx = inode_xattrs
+                .xattrs
+                .iter()
+                .position(|xattr| xattr.name == name)
+                .ok_or_else(|| errno!(ENODATA, "the attribute does not exist"))?;
+            inode_xattrs.xattrs.remove(idx);
+            if inode_xattrs.xattrs.is_empty() {
+                inner.inodes.remove(&ino);
+            }
+            inner.save()
+        })?;
+        inotify::notify_inode(inode, InotifyMask::IN_ATTRIB);
+        Ok(())
+    }
+
+    /// Drop the attributes of the inode once it is released.
+    ///
+    /// This should be called when the last link to the inode is removed. The
+    /// attributes are still accessible through the open files of the inode.
+    pub fn unlink(inode: &Arc<dyn INode>) {
+        if let Err(e) = Self::do_unlink(inode) {
+            warn!(
+                "failed to drop the attributes of the unlinked inode: {:?}",
+                e
+            );
+        }
+    }
+
+    fn do_unlink(inode: &Arc<dyn INode>) -> Result<()> {
+        let inode = real_inode(inode)?;
+        let ino = inode.metadata()?.inode;
+        let store = XattrStore::of(inode.as_ref());
+        let mut inner = store.lock()?;
+        let inode_xattrs = match inner.inodes.get_mut(&ino) {
+            Some(inode_xattrs) => inode_xattrs,
+            None => return Ok(()),
+        };
+        inode_xattrs.is_unlinked = true;
+        inner.unlinked.push((ino, Arc::downgrade(&inode)));
+        // The attributes are dropped when SEFS is mounted next time, in case
+        // the inode is not released before the enclave exits
+        inner.save()
+    }
+}
@@ -42,18 +42,33 @@ pub fn do_handle_interrupt(
 }
 
 /// Broadcast interrupts to threads by sending POSIX signals.
+///
+/// Before broadcasting, the timer wheel of LibOS is advanced so that the
+/// signals sent by the expired timers are delivered in time.
 pub fn broadcast_interrupts() -> Result<usize> {
+    crate::time::timer_wheel::tick();
+
     let should_interrupt_thread = |thread: &&ThreadRef| -> bool {
         // TODO: check Thread::sig_mask to reduce false positives
         thread.process().is_forced_to_exit()
             || !thread.sig_queues().read().unwrap().empty()
             || !thread.process().sig_queues().read().unwrap().empty()
+            // The threads need to check the CPU timers by themselves
+            || crate::process::has_cpu_itimers(thread.process())
+            // The threads of a stopped process need to stop by themselves
+            || thread.process().is_stopped()
     };
 
     let num_signaled_threads = crate::process::table::get_all_threads()
         .iter()
         .filter(should_interrupt_thread)
         .map(|thread| {
+            // Wake up the thread if it is blocked by a waiter, which polls the
+            // host eventfd of the thread
+            if crate::signal::has_interrupting_signals(thread) {
+                thread.host_eventfd().write_u64(1);
+            }
+
             let host_tid = {
                 let sched = thread.sched().lock().unwrap();
                 match sched.host_tid() {
@@ -0,0 +1,21 @@
+//! Inter-process communication facilities of System V and POSIX, e.g., System V
+//! shared memory, semaphores and message queues, and POSIX message queues.
+
+use super::*;
+
+mod mqueue;
+mod mqueue_file;
+mod mqueue_fs;
+mod msg;
+mod sem;
+mod shm;
+mod syscalls;
+mod sysv_ipc;
+
+pub use self::mqueue::mq_attr_t;
+pub use self::mqueue_fs::MQUEUE_FS;
+pub use self::msg::msqid_ds_t;
+pub use self::sem::{exit_sem, sembuf_t};
+pub use self::shm::shmid_ds_t;
+pub use self::syscalls::*;
+pub use self::sysv_ipc::key_t;
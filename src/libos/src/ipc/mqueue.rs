@@ -0,0 +1,438 @@
+//! POSIX message queues.
+//!
+//! A queue is a file in `/dev/mqueue`, which shows the status of the queue
+//! when read, and is opened as a message queue descriptor by mq_open.
+
+use super::mqueue_file::{AsMqueueFile, MqueueFile};
+use super::mqueue_fs::MQUEUE_FS;
+use super::*;
+
+use crate::events::{Waiter, WaiterQueue};
+use crate::fs::{AccessMode, CreationFlags, IoEvents, IoNotifier, StatusFlags};
+use crate::misc::resource_t;
+use crate::process::{sigevent_t, SigEvent};
+use crate::signal::{do_sigqueue, SI_MESGQ};
+use crate::time::{do_clock_gettime, timespec_t, ClockID};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+// The default attributes of a new queue
+const DFLT_MSGMAX: usize = 10;
+const DFLT_MSGSIZEMAX: usize = 8192;
+// The maximum attributes of a queue, whose messages are kept in the kernel
+// heap of the enclave
+const HARD_MSGMAX: usize = 65536;
+const HARD_MSGSIZEMAX: usize = 16 * 1024 * 1024;
+// The priority of a message must be less than the value
+const MQ_PRIO_MAX: u32 = 32768;
+const NAME_MAX: usize = 255;
+
+/// A POSIX message queue.
+pub struct Mqueue {
+    max_msgs: usize,
+    max_msg_size: usize,
+    inner: SgxMutex<MqueueInner>,
+    // The threads blocked in sending or receiving messages
+    waiters: WaiterQueue,
+    // The observers of the events of the descriptors of the queue
+    notifier: IoNotifier,
+}
+
+struct MqueueInner {
+    // Sorted by the priorities from high to low, and in FIFO order for the
+    // same priority
+    msgs: VecDeque<MqMessage>,
+    total_size: usize,
+    mode: u16,
+    notification: Option<MqNotification>,
+    nr_blocked_receivers: usize,
+}
+
+struct MqMessage {
+    prio: u32,
+    data: Vec<u8>,
+}
+
+/// The process registered by mq_notify to be notified of the arrival of a
+/// message at the empty queue.
+struct MqNotification {
+    pid: pid_t,
+    sigevent: SigEvent,
+}
+
+impl Mqueue {
+    fn new(attr: Option<&mq_attr_t>, mode: u16) -> Result<Self> {
+        let (max_msgs, max_msg_size) = match attr {
+            Some(attr) => {
+                if attr.mq_maxmsg <= 0 || attr.mq_maxmsg as usize > HARD_MSGMAX {
+                    return_errno!(EINVAL, "invalid maximum number of messages");
+                }
+                if attr.mq_msgsize <= 0 || attr.mq_msgsize as usize > HARD_MSGSIZEMAX {
+                    return_errno!(EINVAL, "invalid maximum size of messages");
+                }
+                (attr.mq_maxmsg as usize, attr.mq_msgsize as usize)
+            }
+            None => (DFLT_MSGMAX, DFLT_MSGSIZEMAX),
+        };
+        // Same as Linux, the size of the queue is limited by RLIMIT_MSGQUEUE
+        let max_queue_size = current!()
+            .rlimits()
+            .lock()
+            .unwrap()
+            .get(resource_t::RLIMIT_MSGQUEUE)
+            .get_cur();
+        if (max_msgs * max_msg_size) as u64 > max_queue_size {
+            return_errno!(EMFILE, "the queue exceeds RLIMIT_MSGQUEUE");
+        }
+
+        let inner = MqueueInner {
+            msgs: VecDeque::new(),
+            total_size: 0,
+            mode: mode & 0o777,
+            notification: None,
+            nr_blocked_receivers: 0,
+        };
+        Ok(Self {
+            max_msgs,
+            max_msg_size,
+            inner: SgxMutex::new(inner),
+            waiters: WaiterQueue::new(),
+            notifier: IoNotifier::new(),
+        })
+    }
+
+    pub fn mode(&self) -> u16 {
+        self.inner.lock().unwrap().mode
+    }
+
+    /// The total size of the messages in the queue.
+    pub fn total_size(&self) -> usize {
+        self.inner.lock().unwrap().total_size
+    }
+
+    pub fn attr(&self) -> mq_attr_t {
+        mq_attr_t {
+            mq_flags: 0,
+            mq_maxmsg: self.max_msgs as i64,
+            mq_msgsize: self.max_msg_size as i64,
+            mq_curmsgs: self.inner.lock().unwrap().msgs.len() as i64,
+            ..Default::default()
+        }
+    }
+
+    /// The status of the queue shown by its file in `/dev/mqueue`.
+    pub fn status(&self) -> String {
+        let inner = self.inner.lock().unwrap();
+        let (notify, signo, notify_pid) = match &inner.notification {
+            Some(notification) => match notification.sigevent {
+                SigEvent::None => (1, 0, notification.pid),
+                SigEvent::Signal { signum, .. } => (0, signum.as_u8(), notification.pid),
+            },
+            None => (0, 0, 0),
+        };
+        format!(
+            "QSIZE:{:<10} NOTIFY:{:<5} SIGNO:{:<5} NOTIFY_PID:{:<6}\n",
+            inner.total_size, notify, signo, notify_pid
+        )
+    }
+
+    pub fn send(
+        &self,
+        data: &[u8],
+        prio: u32,
+        is_nonblocking: bool,
+        timeout: Option<&mut Duration>,
+    ) -> Result<()> {
+        if data.len() > self.max_msg_size {
+            return_errno!(EMSGSIZE, "the message is too large");
+        }
+        if prio >= MQ_PRIO_MAX {
+            return_errno!(EINVAL, "invalid priority");
+        }
+
+        let mut timeout = timeout;
+        let waiter = Waiter::new();
+        loop {
+            let mut inner = self.inner.lock().unwrap();
+            if inner.msgs.len() < self.max_msgs {
+                let was_empty = inner.msgs.is_empty();
+                let idx = inner
+                    .msgs
+                    .iter()
+                    .position(|msg| msg.prio < prio)
+                    .unwrap_or(inner.msgs.len());
+                inner.msgs.insert(
+                    idx,
+                    MqMessage {
+                        prio,
+                        data: data.to_vec(),
+                    },
+                );
+                inner.total_size += data.len();
+
+                // The registered process is notified only if no thread is
+                // waiting to receive the message
+                let notification = if was_empty && inner.nr_blocked_receivers == 0 {
+                    inner.notification.take()
+                } else {
+                    None
+                };
+                drop(inner);
+
+                if let Some(notification) = notification {
+                    notification.notify();
+                }
+                self.after_msgs_changed();
+                return Ok(());
+            }
+            if is_nonblocking {
+                return_errno!(EAGAIN, "the queue is full");
+            }
+
+            self.waiters.reset_and_enqueue(&waiter);
+            drop(inner);
+            waiter.wait_mut(timeout.as_deref_mut())?;
+        }
+    }
+
+    pub fn receive(
+        &self,
+        buf: &mut [u8],
+        is_nonblocking: bool,
+        timeout: Option<&mut Duration>,
+    ) -> Result<(usize, u32)> {
+        if buf.len() < self.max_msg_size {
+            return_errno!(
+                EMSGSIZE,
+                "the buffer is smaller than the maximum message size"
+            );
+        }
+
+        let mut timeout = timeout;
+        let waiter = Waiter::new();
+        loop {
+            let mut inner = self.inner.lock().unwrap();
+            if let Some(msg) = inner.msgs.pop_front() {
+                inner.total_size -= msg.data.len();
+                drop(inner);
+
+                buf[..msg.data.len()].copy_from_slice(&msg.data);
+                self.after_msgs_changed();
+                return Ok((msg.data.len(), msg.prio));
+            }
+            if is_nonblocking {
+                return_errno!(EAGAIN, "the queue is empty");
+            }
+
+            self.waiters.reset_and_enqueue(&waiter);
+            inner.nr_blocked_receivers += 1;
+            drop(inner);
+            let wait_res = waiter.wait_mut(timeout.as_deref_mut());
+            self.inner.lock().unwrap().nr_blocked_receivers -= 1;
+            wait_res?;
+        }
+    }
+
+    /// Register or unregister the current process to be notified.
+    pub fn set_notification(&self, sigevent: Option<SigEvent>) -> Result<()> {
+        let pid = current!().process().pid();
+        let mut inner = self.inner.lock().unwrap();
+        match sigevent {
+            Some(sigevent) => {
+                if inner.notification.is_some() {
+                    return_errno!(EBUSY, "another process is registered");
+                }
+                inner.notification = Some(MqNotification { pid, sigevent });
+            }
+            None => {
+                // Only the registered process can unregister itself
+                let is_registered = inner
+                    .notification
+                    .as_ref()
+                    .map(|notification| notification.pid == pid)
+                    .unwrap_or(false);
+                if is_registered {
+                    inner.notification = None;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn poll(&self) -> IoEvents {
+        let inner = self.inner.lock().unwrap();
+        let mut events = IoEvents::empty();
+        if !inner.msgs.is_empty() {
+            events |= IoEvents::IN;
+        }
+        if inner.msgs.len() < self.max_msgs {
+            events |= IoEvents::OUT;
+        }
+        events
+    }
+
+    pub fn notifier(&self) -> &IoNotifier {
+        &self.notifier
+    }
+
+    fn after_msgs_changed(&self) {
+        self.notifier.broadcast(&self.poll());
+        self.waiters.dequeue_and_wake_all();
+    }
+}
+
+impl Debug for Mqueue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Mqueue")
+            .field("max_msgs", &self.max_msgs)
+            .field("max_msg_size", &self.max_msg_size)
+            .finish()
+    }
+}
+
+impl MqNotification {
+    fn notify(&self) {
+        if let SigEvent::Signal { signum, value, .. } = self.sigevent {
+            if let Err(e) = do_sigqueue(self.pid, None, Some(signum), SI_MESGQ, value) {
+                warn!("failed to notify the process of the message: {:?}", e);
+            }
+        }
+    }
+}
+
+pub fn do_mq_open(name: &str, flags: u32, mode: u16, attr: Option<&mq_attr_t>) -> Result<FileDesc> {
+    debug!(
+        "mq_open: name: {:?}, flags: {:#o}, mode: {:#o}, attr: {:?}",
+        name, flags, mode, attr
+    );
+    check_name(name)?;
+    let access_mode = AccessMode::from_u32(flags)?;
+    let creation_flags = CreationFlags::from_bits_truncate(flags);
+    let status_flags = StatusFlags::from_bits_truncate(flags);
+
+    let mqueue = MQUEUE_FS.open_mqueue(name, creation_flags, || Mqueue::new(attr, mode))?;
+
+    let file = MqueueFile::new(mqueue, access_mode, status_flags);
+    current!().add_file(Arc::new(file), creation_flags.must_close_on_spawn())
+}
+
+pub fn do_mq_unlink(name: &str) -> Result<()> {
+    debug!("mq_unlink: name: {:?}", name);
+    check_name(name)?;
+    // The queue is destroyed once it is closed by all the processes
+    MQUEUE_FS.remove_mqueue(name)
+}
+
+pub fn do_mq_timedsend(
+    mqdes: FileDesc,
+    data: &[u8],
+    prio: u32,
+    abs_timeout: Option<&timespec_t>,
+) -> Result<()> {
+    debug!(
+        "mq_timedsend: mqdes: {}, len: {}, prio: {}, abs_timeout: {:?}",
+        mqdes,
+        data.len(),
+        prio,
+        abs_timeout
+    );
+    let file_ref = current!().file(mqdes)?;
+    let mqueue_file = file_ref.as_mqueue_file()?;
+    if !mqueue_file.access_mode()?.writable() {
+        return_errno!(EBADF, "the queue is not opened for writing");
+    }
+    let mut timeout = abs_timeout.map(to_relative_timeout).transpose()?;
+    mqueue_file
+        .mqueue()
+        .send(data, prio, mqueue_file.is_nonblocking(), timeout.as_mut())
+}
+
+pub fn do_mq_timedreceive(
+    mqdes: FileDesc,
+    buf: &mut [u8],
+    abs_timeout: Option<&timespec_t>,
+) -> Result<(usize, u32)> {
+    debug!(
+        "mq_timedreceive: mqdes: {}, len: {}, abs_timeout: {:?}",
+        mqdes,
+        buf.len(),
+        abs_timeout
+    );
+    let file_ref = current!().file(mqdes)?;
+    let mqueue_file = file_ref.as_mqueue_file()?;
+    if !mqueue_file.access_mode()?.readable() {
+        return_errno!(EBADF, "the queue is not opened for reading");
+    }
+    let mut timeout = abs_timeout.map(to_relative_timeout).transpose()?;
+    mqueue_file
+        .mqueue()
+        .receive(buf, mqueue_file.is_nonblocking(), timeout.as_mut())
+}
+
+pub fn do_mq_notify(mqdes: FileDesc, sigevent: Option<&sigevent_t>) -> Result<()> {
+    debug!("mq_notify: mqdes: {}, sigevent: {:?}", mqdes, sigevent);
+    let file_ref = current!().file(mqdes)?;
+    let mqueue_file = file_ref.as_mqueue_file()?;
+    let sigevent = match sigevent {
+        // SIGEV_THREAD is implemented by libc with a netlink socket, to which
+        // the notification is sent, but netlink sockets are not supported
+        Some(sigevent) => match SigEvent::from_c(sigevent)? {
+            SigEvent::Signal { tid: Some(_), .. } => {
+                return_errno!(EINVAL, "SIGEV_THREAD_ID is not supported by mq_notify")
+            }
+            sigevent => Some(sigevent),
+        },
+        None => None,
+    };
+    mqueue_file.mqueue().set_notification(sigevent)
+}
+
+/// Get and set the attributes of the queue. Only O_NONBLOCK can be set.
+pub fn do_mq_getsetattr(mqdes: FileDesc, new_attr: Option<&mq_attr_t>) -> Result<mq_attr_t> {
+    debug!("mq_getsetattr: mqdes: {}, new_attr: {:?}", mqdes, new_attr);
+    let file_ref = current!().file(mqdes)?;
+    let mqueue_file = file_ref.as_mqueue_file()?;
+    let mut old_attr = mqueue_file.mqueue().attr();
+    old_attr.mq_flags = mqueue_file.status_flags()?.bits() as i64;
+    if let Some(new_attr) = new_attr {
+        let status_flags = StatusFlags::from_bits_truncate(new_attr.mq_flags as u32);
+        mqueue_file.set_status_flags(status_flags & StatusFlags::O_NONBLOCK)?;
+    }
+    Ok(old_attr)
+}
+
+fn check_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        return_errno!(ENOENT, "empty name");
+    }
+    if name.len() > NAME_MAX {
+        return_errno!(ENAMETOOLONG, "the name is too long");
+    }
+    if name.contains('/') || name == "." || name == ".." {
+        return_errno!(EACCES, "invalid name");
+    }
+    Ok(())
+}
+
+// The timeouts of mq_timedsend and mq_timedreceive are absolute times of
+// CLOCK_REALTIME
+fn to_relative_timeout(abs_timeout: &timespec_t) -> Result<Duration> {
+    abs_timeout.validate()?;
+    let now = do_clock_gettime(ClockID::CLOCK_REALTIME)?.as_duration();
+    Ok(abs_timeout
+        .as_duration()
+        .checked_sub(now)
+        .unwrap_or_default())
+}
+
+/// The `struct mq_attr` of Linux.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct mq_attr_t {
+    pub mq_flags: i64,
+    pub mq_maxmsg: i64,
+    pub mq_msgsize: i64,
+    pub mq_curmsgs: i64,
+    __reserved: [i64; 4],
+}
@@ -0,0 +1,94 @@
+use super::mqueue::Mqueue;
+use super::*;
+
+use crate::fs::{AccessMode, IoEvents, IoNotifier, StatusFlags};
+use std::any::Any;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// A message queue descriptor opened by mq_open.
+pub struct MqueueFile {
+    mqueue: Arc<Mqueue>,
+    access_mode: AccessMode,
+    is_nonblocking: AtomicBool,
+}
+
+impl MqueueFile {
+    pub fn new(mqueue: Arc<Mqueue>, access_mode: AccessMode, status_flags: StatusFlags) -> Self {
+        let is_nonblocking = AtomicBool::new(status_flags.contains(StatusFlags::O_NONBLOCK));
+        Self {
+            mqueue,
+            access_mode,
+            is_nonblocking,
+        }
+    }
+
+    pub fn mqueue(&self) -> &Arc<Mqueue> {
+        &self.mqueue
+    }
+
+    pub fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Acquire)
+    }
+}
+
+impl File for MqueueFile {
+    // Same as Linux, reading a descriptor gets the status of the queue
+    fn read(&self, buf: &mut [u8]) -> Result<usize> {
+        let status = self.mqueue.status();
+        let len = buf.len().min(status.len());
+        buf[..len].copy_from_slice(&status.as_bytes()[..len]);
+        Ok(len)
+    }
+
+    fn access_mode(&self) -> Result<AccessMode> {
+        Ok(self.access_mode.clone())
+    }
+
+    fn status_flags(&self) -> Result<StatusFlags> {
+        if self.is_nonblocking() {
+            Ok(StatusFlags::O_NONBLOCK)
+        } else {
+            Ok(StatusFlags::empty())
+        }
+    }
+
+    fn set_status_flags(&self, new_status_flags: StatusFlags) -> Result<()> {
+        let nonblocking = new_status_flags.contains(StatusFlags::O_NONBLOCK);
+        self.is_nonblocking.store(nonblocking, Ordering::Release);
+        Ok(())
+    }
+
+    fn poll_new(&self) -> IoEvents {
+        self.mqueue.poll()
+    }
+
+    fn notifier(&self) -> Option<&IoNotifier> {
+        Some(self.mqueue.notifier())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Debug for MqueueFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MqueueFile")
+            .field("mqueue", &self.mqueue)
+            .field("access_mode", &self.access_mode)
+            .field("is_nonblocking", &self.is_nonblocking())
+            .finish()
+    }
+}
+
+pub trait AsMqueueFile {
+    fn as_mqueue_file(&self) -> Result<&MqueueFile>;
+}
+
+impl AsMqueueFile for FileRef {
+    fn as_mqueue_file(&self) -> Result<&MqueueFile> {
+        self.as_any()
+            .downcast_ref::<MqueueFile>()
+            .ok_or_else(|| errno!(EBADF, "not a message queue descriptor"))
+    }
+}
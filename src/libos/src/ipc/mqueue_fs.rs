@@ -0,0 +1,203 @@
+use super::mqueue::Mqueue;
+use super::*;
+
+use crate::fs::CreationFlags;
+use rcore_fs::vfs::{self, FileType, FsError, INode, Metadata, PollStatus, Timespec};
+use std::any::Any;
+use std::collections::BTreeMap;
+
+/// The device ID in the metadata of the inodes in `/dev/mqueue`.
+const MQUEUE_FS_DEV_ID: usize = 0x4d51_5546;
+
+lazy_static! {
+    /// The directory of the POSIX message queues, which is `/dev/mqueue`.
+    pub static ref MQUEUE_FS: MqueueFS = MqueueFS::new();
+}
+
+pub struct MqueueFS {
+    root: Arc<MqueueDirINode>,
+}
+
+impl MqueueFS {
+    fn new() -> Self {
+        let root = Arc::new(MqueueDirINode {
+            queues: SgxMutex::new(BTreeMap::new()),
+        });
+        Self { root }
+    }
+
+    pub fn root_inode(&self) -> Arc<dyn INode> {
+        self.root.clone()
+    }
+
+    /// Open the queue of the name, or create one by `new_mqueue` following
+    /// the semantics of O_CREAT and O_EXCL.
+    pub fn open_mqueue<F>(
+        &self,
+        name: &str,
+        creation_flags: CreationFlags,
+        new_mqueue: F,
+    ) -> Result<Arc<Mqueue>>
+    where
+        F: FnOnce() -> Result<Mqueue>,
+    {
+        let mut queues = self.root.queues.lock().unwrap();
+        if let Some(inode) = queues.get(name) {
+            if creation_flags.can_create() && creation_flags.is_exclusive() {
+                return_errno!(EEXIST, "the queue exists");
+            }
+            return Ok(inode.mqueue.clone());
+        }
+        if !creation_flags.can_create() {
+            return_errno!(ENOENT, "no such queue");
+        }
+        let mqueue = Arc::new(new_mqueue()?);
+        let inode = Arc::new(MqueueINode {
+            mqueue: mqueue.clone(),
+        });
+        queues.insert(name.to_string(), inode);
+        Ok(mqueue)
+    }
+
+    /// Remove the queue of the name, which is destroyed once all the
+    /// descriptors of it are closed.
+    pub fn remove_mqueue(&self, name: &str) -> Result<()> {
+        self.root
+            .queues
+            .lock()
+            .unwrap()
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| errno!(ENOENT, "no such queue"))
+    }
+}
+
+/// The inode of `/dev/mqueue`, in which the files are the queues.
+struct MqueueDirINode {
+    queues: SgxMutex<BTreeMap<String, Arc<MqueueINode>>>,
+}
+
+impl INode for MqueueDirINode {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> vfs::Result<usize> {
+        Err(FsError::NotFile)
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> vfs::Result<usize> {
+        Err(FsError::NotFile)
+    }
+
+    fn poll(&self) -> vfs::Result<PollStatus> {
+        Err(FsError::NotFile)
+    }
+
+    fn metadata(&self) -> vfs::Result<Metadata> {
+        Ok(Metadata {
+            dev: MQUEUE_FS_DEV_ID,
+            inode: 0,
+            size: 0,
+            blk_size: 0,
+            blocks: 0,
+            atime: Timespec { sec: 0, nsec: 0 },
+            mtime: Timespec { sec: 0, nsec: 0 },
+            ctime: Timespec { sec: 0, nsec: 0 },
+            type_: FileType::Dir,
+            mode: 0o1777,
+            nlinks: 2,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+        })
+    }
+
+    fn unlink(&self, name: &str) -> vfs::Result<()> {
+        self.queues
+            .lock()
+            .unwrap()
+            .remove(name)
+            .map(|_| ())
+            .ok_or(FsError::EntryNotFound)
+    }
+
+    fn find(&self, name: &str) -> vfs::Result<Arc<dyn INode>> {
+        if name == "." || name == ".." {
+            return Ok(MQUEUE_FS.root_inode());
+        }
+        self.queues
+            .lock()
+            .unwrap()
+            .get(name)
+            .map(|inode| inode.clone() as Arc<dyn INode>)
+            .ok_or(FsError::EntryNotFound)
+    }
+
+    fn get_entry(&self, id: usize) -> vfs::Result<String> {
+        match id {
+            0 => Ok(String::from(".")),
+            1 => Ok(String::from("..")),
+            i => self
+                .queues
+                .lock()
+                .unwrap()
+                .keys()
+                .nth(i - 2)
+                .cloned()
+                .ok_or(FsError::EntryNotFound),
+        }
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// The inode of a queue, whose content is the status of the queue.
+struct MqueueINode {
+    mqueue: Arc<Mqueue>,
+}
+
+impl INode for MqueueINode {
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> vfs::Result<usize> {
+        let status = self.mqueue.status();
+        let data = status.as_bytes();
+        let start = data.len().min(offset);
+        let end = data.len().min(offset + buf.len());
+        let len = end - start;
+        buf[0..len].copy_from_slice(&data[start..end]);
+        Ok(len)
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> vfs::Result<usize> {
+        Err(FsError::PermError)
+    }
+
+    fn poll(&self) -> vfs::Result<PollStatus> {
+        Ok(PollStatus {
+            read: true,
+            write: false,
+            error: false,
+        })
+    }
+
+    fn metadata(&self) -> vfs::Result<Metadata> {
+        Ok(Metadata {
+            dev: MQUEUE_FS_DEV_ID,
+            inode: Arc::as_ptr(&self.mqueue) as usize,
+            size: self.mqueue.total_size(),
+            blk_size: 0,
+            blocks: 0,
+            atime: Timespec { sec: 0, nsec: 0 },
+            mtime: Timespec { sec: 0, nsec: 0 },
+            ctime: Timespec { sec: 0, nsec: 0 },
+            type_: FileType::File,
+            mode: self.mqueue.mode(),
+            nlinks: 1,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+        })
+    }
+
+    fn as_any_ref(&self) -> &dyn Any {
+        self
+    }
+}
@@ -0,0 +1,295 @@
+//! System V message queues.
+//!
+//! A message is received by its type, which is selected in the same way as
+//! Linux given the type and the flags of msgrcv.
+
+use super::sysv_ipc::{ipc_perm_t, ipc_time_now, key_t, IpcFlags, IpcIds, IpcPerm};
+use super::*;
+
+use crate::events::{Waiter, WaiterQueue};
+use crate::time::time_t;
+
+// The maximum size of a message
+const MSGMAX: usize = 8192;
+// The default maximum number of bytes in a queue
+const MSGMNB: usize = 16384;
+
+lazy_static! {
+    static ref MSG_IDS: SgxMutex<IpcIds<MsgQueue>> = SgxMutex::new(IpcIds::new());
+}
+
+/// A message queue.
+struct MsgQueue {
+    id: i32,
+    inner: SgxMutex<MsgQueueInner>,
+    // The threads blocked in msgsnd or msgrcv, which are woken up to retry
+    // whenever the messages change or the queue is removed
+    waiters: WaiterQueue,
+}
+
+struct MsgQueueInner {
+    perm: IpcPerm,
+    msgs: VecDeque<Msg>,
+    // The total size of the messages and its maximum
+    cbytes: usize,
+    qbytes: usize,
+    is_removed: bool,
+    stime: time_t,
+    rtime: time_t,
+    ctime: time_t,
+    lspid: pid_t,
+    lrpid: pid_t,
+}
+
+struct Msg {
+    mtype: i64,
+    data: Vec<u8>,
+}
+
+impl MsgQueue {
+    fn new(id: i32, key: key_t, mode: u16) -> Self {
+        let inner = MsgQueueInner {
+            perm: IpcPerm::new(key, mode),
+            msgs: VecDeque::new(),
+            cbytes: 0,
+            qbytes: MSGMNB,
+            is_removed: false,
+            stime: 0,
+            rtime: 0,
+            ctime: ipc_time_now(),
+            lspid: 0,
+            lrpid: 0,
+        };
+        Self {
+            id,
+            inner: SgxMutex::new(inner),
+            waiters: WaiterQueue::new(),
+        }
+    }
+}
+
+impl MsgQueueInner {
+    // Same as Linux, the number of messages is also limited by the maximum
+    // number of bytes, so that a queue cannot be filled up by empty messages
+    fn can_send(&self, len: usize) -> bool {
+        self.cbytes + len <= self.qbytes && self.msgs.len() + 1 <= self.qbytes
+    }
+
+    // Find the message to receive given the type:
+    // * zero: the first message;
+    // * positive: the first message of the type, or of any other type with
+    //   MSG_EXCEPT;
+    // * negative: the first message of the lowest type that is less than or
+    //   equal to the absolute value of the type.
+    fn find_msg(&self, mtype: i64, flags: MsgFlags) -> Option<usize> {
+        if mtype == 0 {
+            if self.msgs.is_empty() {
+                None
+            } else {
+                Some(0)
+            }
+        } else if mtype > 0 {
+            let except = flags.contains(MsgFlags::MSG_EXCEPT);
+            self.msgs
+                .iter()
+                .position(|msg| (msg.mtype == mtype) != except)
+        } else {
+            self.msgs
+                .iter()
+                .enumerate()
+                .filter(|(_, msg)| msg.mtype <= -mtype)
+                .min_by_key(|(idx, msg)| (msg.mtype, *idx))
+                .map(|(idx, _)| idx)
+        }
+    }
+}
+
+pub fn do_msgget(key: key_t, flags: IpcFlags, mode: u16) -> Result<i32> {
+    debug!("msgget: key: {}, flags: {:?}, mode: {:o}", key, flags, mode);
+    MSG_IDS.lock().unwrap().get_or_create(
+        key,
+        flags,
+        |id| Ok(MsgQueue::new(id, key, mode)),
+        |_| Ok(()),
+    )
+}
+
+pub fn do_msgsnd(msqid: i32, mtype: i64, data: &[u8], flags: MsgFlags) -> Result<()> {
+    debug!(
+        "msgsnd: msqid: {}, mtype: {}, len: {}, flags: {:?}",
+        msqid,
+        mtype,
+        data.len(),
+        flags
+    );
+    if mtype <= 0 {
+        return_errno!(EINVAL, "the type of a message must be positive");
+    }
+    if data.len() > MSGMAX {
+        return_errno!(EINVAL, "the message is too large");
+    }
+    let queue = MSG_IDS.lock().unwrap().get(msqid)?;
+    let pid = current!().process().pid();
+
+    let waiter = Waiter::new();
+    loop {
+        let mut inner = queue.inner.lock().unwrap();
+        if inner.is_removed {
+            return_errno!(EIDRM, "the queue is removed");
+        }
+        if inner.can_send(data.len()) {
+            inner.msgs.push_back(Msg {
+                mtype,
+                data: data.to_vec(),
+            });
+            inner.cbytes += data.len();
+            inner.stime = ipc_time_now();
+            inner.lspid = pid;
+            drop(inner);
+            queue.waiters.dequeue_and_wake_all();
+            return Ok(());
+        }
+        if flags.contains(MsgFlags::IPC_NOWAIT) {
+            return_errno!(EAGAIN, "the queue is full");
+        }
+
+        // Enqueue before unlocking the queue, so that no wakeup is missed
+        queue.waiters.reset_and_enqueue(&waiter);
+        drop(inner);
+        waiter.wait_mut(None)?;
+    }
+}
+
+/// Receive a message into the buffer, returning the type and the length of
+/// the message.
+pub fn do_msgrcv(msqid: i32, buf: &mut [u8], mtype: i64, flags: MsgFlags) -> Result<(i64, usize)> {
+    debug!(
+        "msgrcv: msqid: {}, len: {}, mtype: {}, flags: {:?}",
+        msqid,
+        buf.len(),
+        mtype,
+        flags
+    );
+    if flags.contains(MsgFlags::MSG_COPY) {
+        return_errno!(ENOSYS, "MSG_COPY is not supported");
+    }
+    let queue = MSG_IDS.lock().unwrap().get(msqid)?;
+    let pid = current!().process().pid();
+
+    let waiter = Waiter::new();
+    loop {
+        let mut inner = queue.inner.lock().unwrap();
+        if inner.is_removed {
+            return_errno!(EIDRM, "the queue is removed");
+        }
+        if let Some(idx) = inner.find_msg(mtype, flags) {
+            let msg_len = inner.msgs[idx].data.len();
+            if msg_len > buf.len() && !flags.contains(MsgFlags::MSG_NOERROR) {
+                return_errno!(E2BIG, "the message is larger than the buffer");
+            }
+            let msg = inner.msgs.remove(idx).unwrap();
+            inner.cbytes -= msg_len;
+            inner.rtime = ipc_time_now();
+            inner.lrpid = pid;
+            drop(inner);
+            queue.waiters.dequeue_and_wake_all();
+
+            // The message is truncated with MSG_NOERROR
+            let len = msg_len.min(buf.len());
+            buf[..len].copy_from_slice(&msg.data[..len]);
+            return Ok((msg.mtype, len));
+        }
+        if flags.contains(MsgFlags::IPC_NOWAIT) {
+            return_errno!(ENOMSG, "no message of the type");
+        }
+
+        queue.waiters.reset_and_enqueue(&waiter);
+        drop(inner);
+        waiter.wait_mut(None)?;
+    }
+}
+
+pub fn do_msgctl(msqid: i32, cmd: MsgCtlCmd) -> Result<()> {
+    debug!("msgctl: msqid: {}, cmd: {:?}", msqid, cmd);
+    let mut msg_ids = MSG_IDS.lock().unwrap();
+    let queue = msg_ids.get(msqid)?;
+    let mut inner = queue.inner.lock().unwrap();
+    match cmd {
+        MsgCtlCmd::IPC_RMID => {
+            inner.is_removed = true;
+            drop(inner);
+            msg_ids.remove(msqid);
+            // The blocked threads fail with EIDRM
+            queue.waiters.dequeue_and_wake_all();
+        }
+        MsgCtlCmd::IPC_SET(buf) => {
+            if buf.msg_qbytes as usize > MSGMNB {
+                // Only privileged processes can raise the limit on Linux,
+                // while all the processes in an enclave run as root
+                warn!("msg_qbytes is larger than the default limit");
+            }
+            inner.perm.set(&buf.msg_perm);
+            inner.qbytes = buf.msg_qbytes as usize;
+            inner.ctime = ipc_time_now();
+            drop(inner);
+            // The senders may proceed with the new limit
+            queue.waiters.dequeue_and_wake_all();
+        }
+        MsgCtlCmd::IPC_STAT(buf) => {
+            *buf = msqid_ds_t {
+                msg_perm: inner.perm.to_c(queue.id, 0),
+                msg_stime: inner.stime,
+                msg_rtime: inner.rtime,
+                msg_ctime: inner.ctime,
+                msg_cbytes: inner.cbytes as u64,
+                msg_qnum: inner.msgs.len() as u64,
+                msg_qbytes: inner.qbytes as u64,
+                msg_lspid: inner.lspid,
+                msg_lrpid: inner.lrpid,
+                ..Default::default()
+            };
+        }
+    }
+    Ok(())
+}
+
+bitflags! {
+    pub struct MsgFlags : u32 {
+        const IPC_NOWAIT  = 0o4000;
+        const MSG_NOERROR = 0o10000;
+        const MSG_EXCEPT  = 0o20000;
+        const MSG_COPY    = 0o40000;
+    }
+}
+
+impl MsgFlags {
+    pub fn from_i32(bits: i32) -> Result<Self> {
+        MsgFlags::from_bits(bits as u32).ok_or_else(|| errno!(EINVAL, "containing unknown bits"))
+    }
+}
+
+#[derive(Debug)]
+#[allow(non_camel_case_types)]
+pub enum MsgCtlCmd<'a> {
+    IPC_RMID,
+    IPC_SET(&'a msqid_ds_t),
+    IPC_STAT(&'a mut msqid_ds_t),
+}
+
+/// The `struct msqid64_ds` of Linux.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct msqid_ds_t {
+    pub msg_perm: ipc_perm_t,
+    pub msg_stime: time_t,
+    pub msg_rtime: time_t,
+    pub msg_ctime: time_t,
+    pub msg_cbytes: u64,
+    pub msg_qnum: u64,
+    pub msg_qbytes: u64,
+    pub msg_lspid: pid_t,
+    pub msg_lrpid: pid_t,
+    __unused4: u64,
+    __unused5: u64,
+}
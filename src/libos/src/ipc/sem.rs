@@ -0,0 +1,394 @@
+//! System V semaphores.
+//!
+//! The operations of SEM_UNDO are recorded in the semaphore set for each
+//! process, and are reverted when the process exits, see `exit_sem`.
+
+use super::sysv_ipc::{ipc_perm_t, ipc_time_now, key_t, IpcFlags, IpcIds, IpcPerm};
+use super::*;
+
+use crate::events::{Waiter, WaiterQueue};
+use crate::time::time_t;
+use crate::util::mem_util::from_user::*;
+use std::time::Duration;
+
+// The maximum number of semaphores in a set
+const SEMMSL: usize = 32000;
+// The maximum number of operations in a semop call
+const SEMOPM: usize = 500;
+// The maximum value of a semaphore
+const SEMVMX: i32 = 32767;
+// The maximum adjustment of a semaphore recorded for SEM_UNDO
+const SEMAEM: i32 = SEMVMX;
+
+lazy_static! {
+    static ref SEM_IDS: SgxMutex<IpcIds<SemSet>> = SgxMutex::new(IpcIds::new());
+}
+
+/// A set of semaphores.
+struct SemSet {
+    id: i32,
+    inner: SgxMutex<SemSetInner>,
+    // The threads blocked in semop, which are woken up to retry whenever the
+    // values change or the set is removed
+    waiters: WaiterQueue,
+}
+
+struct SemSetInner {
+    perm: IpcPerm,
+    sems: Vec<Semaphore>,
+    // The adjustments to revert the operations of SEM_UNDO for each process
+    undos: HashMap<pid_t, Vec<i32>>,
+    is_removed: bool,
+    otime: time_t,
+    ctime: time_t,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Semaphore {
+    val: i32,
+    // The pid of the process that performs the last operation
+    pid: pid_t,
+    // The numbers of the threads waiting for the value to increase or to
+    // become zero
+    ncnt: usize,
+    zcnt: usize,
+}
+
+// The result of trying to perform the operations
+enum SemOpResult {
+    Done,
+    // Blocked by the operation of the index
+    Blocked(usize),
+}
+
+impl SemSet {
+    fn new(id: i32, key: key_t, nsems: usize, mode: u16) -> Self {
+        let inner = SemSetInner {
+            perm: IpcPerm::new(key, mode),
+            sems: vec![Semaphore::default(); nsems],
+            undos: HashMap::new(),
+            is_removed: false,
+            otime: 0,
+            ctime: ipc_time_now(),
+        };
+        Self {
+            id,
+            inner: SgxMutex::new(inner),
+            waiters: WaiterQueue::new(),
+        }
+    }
+
+    fn nsems(&self) -> usize {
+        self.inner.lock().unwrap().sems.len()
+    }
+}
+
+impl SemSetInner {
+    // Perform all the operations or none of them
+    fn try_semop(&mut self, sops: &[sembuf_t], pid: pid_t) -> Result<SemOpResult> {
+        let mut vals: Vec<i32> = self.sems.iter().map(|sem| sem.val).collect();
+        for (idx, sop) in sops.iter().enumerate() {
+            let sem_op = sop.sem_op as i32;
+            let val = &mut vals[sop.sem_num as usize];
+            if (sem_op == 0 && *val != 0) || *val + sem_op < 0 {
+                return Ok(SemOpResult::Blocked(idx));
+            } else if *val + sem_op > SEMVMX {
+                return_errno!(ERANGE, "the value of the semaphore is too large");
+            }
+            *val += sem_op;
+        }
+
+        for sop in sops {
+            let flags = SemFlags::from_bits_truncate(sop.sem_flg as u16);
+            if flags.contains(SemFlags::SEM_UNDO) && sop.sem_op != 0 {
+                let nsems = self.sems.len();
+                let adjs = self.undos.entry(pid).or_insert_with(|| vec![0; nsems]);
+                let adj = &mut adjs[sop.sem_num as usize];
+                *adj = (*adj - sop.sem_op as i32).max(-SEMAEM).min(SEMAEM);
+            }
+        }
+        for (sem, val) in self.sems.iter_mut().zip(vals) {
+            sem.val = val;
+        }
+        for sop in sops {
+            self.sems[sop.sem_num as usize].pid = pid;
+        }
+        self.otime = ipc_time_now();
+        Ok(SemOpResult::Done)
+    }
+
+    fn set_val(&mut self, sem_num: usize, val: i32, pid: pid_t) -> Result<()> {
+        if val < 0 || val > SEMVMX {
+            return_errno!(ERANGE, "invalid value of the semaphore");
+        }
+        self.sems[sem_num].val = val;
+        self.sems[sem_num].pid = pid;
+        // The adjustments of the semaphore are cleared in all the processes
+        for adjs in self.undos.values_mut() {
+            adjs[sem_num] = 0;
+        }
+        self.ctime = ipc_time_now();
+        Ok(())
+    }
+
+    fn update_wait_count(&mut self, sem_num: usize, wait_zero: bool, is_waiting: bool) {
+        let sem = &mut self.sems[sem_num];
+        let count = if wait_zero {
+            &mut sem.zcnt
+        } else {
+            &mut sem.ncnt
+        };
+        if is_waiting {
+            *count += 1;
+        } else {
+            *count -= 1;
+        }
+    }
+
+    fn check_sem_num(&self, sem_num: usize) -> Result<()> {
+        if sem_num >= self.sems.len() {
+            return_errno!(EINVAL, "invalid semaphore number");
+        }
+        Ok(())
+    }
+}
+
+pub fn do_semget(key: key_t, nsems: usize, flags: IpcFlags, mode: u16) -> Result<i32> {
+    debug!(
+        "semget: key: {}, nsems: {}, flags: {:?}, mode: {:o}",
+        key, nsems, flags, mode
+    );
+    if nsems > SEMMSL {
+        return_errno!(EINVAL, "too many semaphores");
+    }
+    SEM_IDS.lock().unwrap().get_or_create(
+        key,
+        flags,
+        |id| {
+            if nsems == 0 {
+                return_errno!(EINVAL, "no semaphore in the new set");
+            }
+            Ok(SemSet::new(id, key, nsems, mode))
+        },
+        |set| {
+            if nsems > set.nsems() {
+                return_errno!(EINVAL, "more semaphores than the set has");
+            }
+            Ok(())
+        },
+    )
+}
+
+pub fn do_semtimedop(semid: i32, sops: &[sembuf_t], timeout: Option<&Duration>) -> Result<()> {
+    debug!(
+        "semtimedop: semid: {}, sops: {:?}, timeout: {:?}",
+        semid, sops, timeout
+    );
+    if sops.is_empty() || sops.len() > SEMOPM {
+        return_errno!(E2BIG, "invalid number of operations");
+    }
+    let set = SEM_IDS.lock().unwrap().get(semid)?;
+    let pid = current!().process().pid();
+    let mut timeout = timeout.cloned();
+
+    let waiter = Waiter::new();
+    loop {
+        let mut inner = set.inner.lock().unwrap();
+        if inner.is_removed {
+            return_errno!(EIDRM, "the semaphore set is removed");
+        }
+        if sops
+            .iter()
+            .any(|sop| inner.check_sem_num(sop.sem_num as usize).is_err())
+        {
+            return_errno!(EFBIG, "invalid semaphore number");
+        }
+
+        let blocking_sop = match inner.try_semop(sops, pid)? {
+            SemOpResult::Done => {
+                drop(inner);
+                set.waiters.dequeue_and_wake_all();
+                return Ok(());
+            }
+            SemOpResult::Blocked(idx) => &sops[idx],
+        };
+        let flags = SemFlags::from_bits_truncate(blocking_sop.sem_flg as u16);
+        if flags.contains(SemFlags::IPC_NOWAIT) {
+            return_errno!(EAGAIN, "the operations would block");
+        }
+        let sem_num = blocking_sop.sem_num as usize;
+        let wait_zero = blocking_sop.sem_op == 0;
+
+        // Enqueue before unlocking the set, so that no wakeup is missed
+        set.waiters.reset_and_enqueue(&waiter);
+        inner.update_wait_count(sem_num, wait_zero, true);
+        drop(inner);
+
+        let wait_res = waiter.wait_mut(timeout.as_mut());
+        set.inner
+            .lock()
+            .unwrap()
+            .update_wait_count(sem_num, wait_zero, false);
+        if let Err(e) = wait_res {
+            if e.errno() == ETIMEDOUT {
+                return_errno!(EAGAIN, "the operations timed out");
+            }
+            return Err(e);
+        }
+    }
+}
+
+pub fn do_semctl(semid: i32, sem_num: usize, cmd: SemCtlCmd) -> Result<isize> {
+    debug!(
+        "semctl: semid: {}, sem_num: {}, cmd: {:?}",
+        semid, sem_num, cmd
+    );
+    let mut sem_ids = SEM_IDS.lock().unwrap();
+    let set = sem_ids.get(semid)?;
+    let mut inner = set.inner.lock().unwrap();
+    let pid = current!().process().pid();
+    let ret = match cmd {
+        SemCtlCmd::IPC_RMID => {
+            inner.is_removed = true;
+            drop(inner);
+            sem_ids.remove(semid);
+            // The blocked threads fail with EIDRM
+            set.waiters.dequeue_and_wake_all();
+            return Ok(0);
+        }
+        SemCtlCmd::IPC_SET(buf) => {
+            inner.perm.set(&buf.sem_perm);
+            inner.ctime = ipc_time_now();
+            0
+        }
+        SemCtlCmd::IPC_STAT(buf) => {
+            *buf = semid_ds_t {
+                sem_perm: inner.perm.to_c(set.id, 0),
+                sem_otime: inner.otime,
+                sem_ctime: inner.ctime,
+                sem_nsems: inner.sems.len() as u64,
+                ..Default::default()
+            };
+            0
+        }
+        SemCtlCmd::GETVAL => {
+            inner.check_sem_num(sem_num)?;
+            inner.sems[sem_num].val as isize
+        }
+        SemCtlCmd::GETPID => {
+            inner.check_sem_num(sem_num)?;
+            inner.sems[sem_num].pid as isize
+        }
+        SemCtlCmd::GETNCNT => {
+            inner.check_sem_num(sem_num)?;
+            inner.sems[sem_num].ncnt as isize
+        }
+        SemCtlCmd::GETZCNT => {
+            inner.check_sem_num(sem_num)?;
+            inner.sems[sem_num].zcnt as isize
+        }
+        SemCtlCmd::GETALL(vals_ptr) => {
+            check_mut_array(vals_ptr, inner.sems.len())?;
+            let vals = unsafe { std::slice::from_raw_parts_mut(vals_ptr, inner.sems.len()) };
+            for (val, sem) in vals.iter_mut().zip(inner.sems.iter()) {
+                *val = sem.val as u16;
+            }
+            0
+        }
+        SemCtlCmd::SETVAL(val) => {
+            inner.check_sem_num(sem_num)?;
+            inner.set_val(sem_num, val, pid)?;
+            drop(inner);
+            set.waiters.dequeue_and_wake_all();
+            return Ok(0);
+        }
+        SemCtlCmd::SETALL(vals_ptr) => {
+            check_array(vals_ptr, inner.sems.len())?;
+            let vals = unsafe { std::slice::from_raw_parts(vals_ptr, inner.sems.len()) };
+            if vals.iter().any(|val| *val as i32 > SEMVMX) {
+                return_errno!(ERANGE, "invalid value of the semaphore");
+            }
+            for (sem_num, val) in vals.iter().enumerate() {
+                inner.set_val(sem_num, *val as i32, pid)?;
+            }
+            drop(inner);
+            set.waiters.dequeue_and_wake_all();
+            return Ok(0);
+        }
+    };
+    Ok(ret)
+}
+
+/// Revert the operations of SEM_UNDO performed by the process, which exits.
+pub fn exit_sem(pid: pid_t) {
+    let sets: Vec<Arc<SemSet>> = {
+        let sem_ids = SEM_IDS.lock().unwrap();
+        sem_ids.objects().cloned().collect()
+    };
+    for set in sets {
+        let mut inner = set.inner.lock().unwrap();
+        let adjs = match inner.undos.remove(&pid) {
+            Some(adjs) => adjs,
+            None => continue,
+        };
+        // Same as Linux, a value that would become negative is set to zero
+        // instead of blocking the exiting process
+        for (sem, adj) in inner.sems.iter_mut().zip(adjs) {
+            if adj != 0 {
+                sem.val = (sem.val + adj).max(0).min(SEMVMX);
+                sem.pid = pid;
+            }
+        }
+        inner.otime = ipc_time_now();
+        drop(inner);
+        set.waiters.dequeue_and_wake_all();
+    }
+}
+
+bitflags! {
+    pub struct SemFlags : u16 {
+        const IPC_NOWAIT = 0o4000;
+        const SEM_UNDO   = 0x1000;
+    }
+}
+
+#[derive(Debug)]
+#[allow(non_camel_case_types)]
+pub enum SemCtlCmd<'a> {
+    IPC_RMID,
+    IPC_SET(&'a semid_ds_t),
+    IPC_STAT(&'a mut semid_ds_t),
+    GETVAL,
+    GETPID,
+    GETNCNT,
+    GETZCNT,
+    // The arrays of the values are checked given the number of semaphores
+    GETALL(*mut u16),
+    SETVAL(i32),
+    SETALL(*const u16),
+}
+
+/// The `struct sembuf` of Linux.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct sembuf_t {
+    pub sem_num: u16,
+    pub sem_op: i16,
+    pub sem_flg: i16,
+}
+
+/// The `struct semid64_ds` of Linux.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct semid_ds_t {
+    pub sem_perm: ipc_perm_t,
+    pub sem_otime: time_t,
+    __unused1: u64,
+    pub sem_ctime: time_t,
+    __unused2: u64,
+    pub sem_nsems: u64,
+    __unused3: u64,
+    __unused4: u64,
+}
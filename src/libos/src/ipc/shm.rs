@@ -0,0 +1,269 @@
+//! System V shared memory.
+//!
+//! A segment is allocated out of the range of any process, like the shared
+//! mappings of files. As all the processes in an enclave share one address
+//! space, a segment is attached at the same address in all the processes, so
+//! shmat cannot attach a segment at an address given by the user.
+//!
+//! A segment removed by IPC_RMID is destroyed when it is detached by the last
+//! process, including the processes that exit without detaching it.
+
+use super::sysv_ipc::{ipc_perm_t, ipc_time_now, key_t, IpcFlags, IpcIds, IpcPerm};
+use super::*;
+
+use crate::time::time_t;
+use crate::vm::{ShmSegment, PAGE_SIZE};
+use std::sync::Weak;
+
+// The minimum size of a segment
+const SHMMIN: usize = 1;
+// The alignment of the addresses of segments
+const SHMLBA: usize = PAGE_SIZE;
+
+// The extra bits of the mode of a segment
+const SHM_DEST: u16 = 0o1000;
+const SHM_LOCKED: u16 = 0o2000;
+
+lazy_static! {
+    static ref SHM_IDS: SgxMutex<IpcIds<ShmIdentifier>> = SgxMutex::new(IpcIds::new());
+}
+
+/// A segment and its attributes.
+struct ShmIdentifier {
+    id: i32,
+    // The size given by shmget, which is not rounded up to pages
+    size: usize,
+    // The address of the segment in all the processes
+    addr: usize,
+    segment: Weak<ShmSegment>,
+    inner: SgxMutex<ShmIdentifierInner>,
+}
+
+struct ShmIdentifierInner {
+    perm: IpcPerm,
+    // The segment is kept alive by the identifier until it is removed
+    owned_segment: Option<Arc<ShmSegment>>,
+    is_locked: bool,
+    atime: time_t,
+    dtime: time_t,
+    ctime: time_t,
+    cpid: pid_t,
+    lpid: pid_t,
+}
+
+impl ShmIdentifier {
+    fn new(id: i32, key: key_t, size: usize, mode: u16) -> Result<Self> {
+        let segment = ShmSegment::new_anonymous(size)?;
+        let inner = ShmIdentifierInner {
+            perm: IpcPerm::new(key, mode),
+            owned_segment: Some(segment.clone()),
+            is_locked: false,
+            atime: 0,
+            dtime: 0,
+            ctime: ipc_time_now(),
+            cpid: current!().process().pid(),
+            lpid: 0,
+        };
+        Ok(Self {
+            id,
+            size,
+            addr: segment.range().start(),
+            segment: Arc::downgrade(&segment),
+            inner: SgxMutex::new(inner),
+        })
+    }
+
+    fn is_removed(&self) -> bool {
+        self.inner.lock().unwrap().owned_segment.is_none()
+    }
+
+    // The number of the attachments of the segment
+    fn nattch(&self) -> usize {
+        let nr_refs = Weak::strong_count(&self.segment);
+        if self.is_removed() {
+            nr_refs
+        } else {
+            nr_refs - 1
+        }
+    }
+
+    // A removed segment is forgotten once it is detached by all the processes
+    fn is_alive(&self) -> bool {
+        !self.is_removed() || self.nattch() > 0
+    }
+
+    fn stat(&self) -> shmid_ds_t {
+        let nattch = self.nattch();
+        let inner = self.inner.lock().unwrap();
+        let mut extra_mode = 0;
+        if inner.owned_segment.is_none() {
+            extra_mode |= SHM_DEST;
+        }
+        if inner.is_locked {
+            extra_mode |= SHM_LOCKED;
+        }
+        shmid_ds_t {
+            shm_perm: inner.perm.to_c(self.id, extra_mode),
+            shm_segsz: self.size,
+            shm_atime: inner.atime,
+            shm_dtime: inner.dtime,
+            shm_ctime: inner.ctime,
+            shm_cpid: inner.cpid,
+            shm_lpid: inner.lpid,
+            shm_nattch: nattch as u64,
+            ..Default::default()
+        }
+    }
+}
+
+pub fn do_shmget(key: key_t, size: usize, flags: IpcFlags, mode: u16) -> Result<i32> {
+    debug!(
+        "shmget: key: {}, size: {:#x}, flags: {:?}, mode: {:o}",
+        key, size, flags, mode
+    );
+    let mut shm_ids = SHM_IDS.lock().unwrap();
+    shm_ids.retain(|identifier| identifier.is_alive());
+    shm_ids.get_or_create(
+        key,
+        flags,
+        |id| {
+            if size < SHMMIN {
+                return_errno!(EINVAL, "the size is too small");
+            }
+            ShmIdentifier::new(id, key, size, mode)
+        },
+        |identifier| {
+            if size > identifier.size {
+                return_errno!(EINVAL, "the size is larger than the segment");
+            }
+            Ok(())
+        },
+    )
+}
+
+pub fn do_shmat(shmid: i32, shmaddr: usize, flags: ShmAtFlags) -> Result<usize> {
+    debug!(
+        "shmat: shmid: {}, shmaddr: {:#x}, flags: {:?}",
+        shmid, shmaddr, flags
+    );
+    let identifier = SHM_IDS.lock().unwrap().get(shmid)?;
+    if shmaddr != 0 {
+        let shmaddr = if flags.contains(ShmAtFlags::SHM_RND) {
+            align_down(shmaddr, SHMLBA)
+        } else {
+            shmaddr
+        };
+        if shmaddr != identifier.addr {
+            return_errno!(EINVAL, "cannot attach the segment at the given address");
+        }
+    }
+    if flags.contains(ShmAtFlags::SHM_RDONLY) {
+        // The segment is shared by the processes, so its permissions are
+        // kept as readable and writable
+        warn!("Do not support read-only shared memory yet");
+    }
+
+    let segment = identifier
+        .segment
+        .upgrade()
+        .ok_or_else(|| errno!(EINVAL, "the segment is removed"))?;
+    let current = current!();
+    let addr = current.vm().attach_shm(segment)?;
+
+    let mut inner = identifier.inner.lock().unwrap();
+    inner.atime = ipc_time_now();
+    inner.lpid = current.process().pid();
+    Ok(addr)
+}
+
+pub fn do_shmdt(shmaddr: usize) -> Result<()> {
+    debug!("shmdt: shmaddr: {:#x}", shmaddr);
+    let current = current!();
+    let segment = current.vm().detach_shm(shmaddr)?;
+
+    let mut shm_ids = SHM_IDS.lock().unwrap();
+    if let Some(identifier) = shm_ids.find(|identifier| identifier.addr == shmaddr) {
+        let mut inner = identifier.inner.lock().unwrap();
+        inner.dtime = ipc_time_now();
+        inner.lpid = current.process().pid();
+    }
+    // Destroy the segment if it is removed and detached by all the processes
+    drop(segment);
+    shm_ids.retain(|identifier| identifier.is_alive());
+    Ok(())
+}
+
+pub fn do_shmctl(shmid: i32, cmd: ShmCtlCmd) -> Result<()> {
+    debug!("shmctl: shmid: {}, cmd: {:?}", shmid, cmd);
+    let mut shm_ids = SHM_IDS.lock().unwrap();
+    let identifier = shm_ids.get(shmid)?;
+    match cmd {
+        ShmCtlCmd::IPC_STAT(buf) => {
+            *buf = identifier.stat();
+        }
+        ShmCtlCmd::IPC_SET(buf) => {
+            let mut inner = identifier.inner.lock().unwrap();
+            inner.perm.set(&buf.shm_perm);
+            inner.ctime = ipc_time_now();
+        }
+        ShmCtlCmd::IPC_RMID => {
+            let segment = identifier.inner.lock().unwrap().owned_segment.take();
+            // The key refers to a new segment afterwards
+            shm_ids.remove_key(shmid);
+            drop(segment);
+            if !identifier.is_alive() {
+                shm_ids.remove(shmid);
+            }
+        }
+        ShmCtlCmd::SHM_LOCK => {
+            // The memory of an enclave is never swapped out by LibOS
+            identifier.inner.lock().unwrap().is_locked = true;
+        }
+        ShmCtlCmd::SHM_UNLOCK => {
+            identifier.inner.lock().unwrap().is_locked = false;
+        }
+    }
+    Ok(())
+}
+
+bitflags! {
+    pub struct ShmAtFlags : u32 {
+        const SHM_RDONLY = 0o10000;
+        const SHM_RND    = 0o20000;
+        const SHM_REMAP  = 0o40000;
+        const SHM_EXEC   = 0o100000;
+    }
+}
+
+impl ShmAtFlags {
+    pub fn from_i32(bits: i32) -> Result<Self> {
+        ShmAtFlags::from_bits(bits as u32).ok_or_else(|| errno!(EINVAL, "containing unknown bits"))
+    }
+}
+
+#[derive(Debug)]
+#[allow(non_camel_case_types)]
+pub enum ShmCtlCmd<'a> {
+    IPC_RMID,
+    IPC_SET(&'a shmid_ds_t),
+    IPC_STAT(&'a mut shmid_ds_t),
+    SHM_LOCK,
+    SHM_UNLOCK,
+}
+
+/// The `struct shmid64_ds` of Linux.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct shmid_ds_t {
+    pub shm_perm: ipc_perm_t,
+    pub shm_segsz: usize,
+    pub shm_atime: time_t,
+    pub shm_dtime: time_t,
+    pub shm_ctime: time_t,
+    pub shm_cpid: pid_t,
+    pub shm_lpid: pid_t,
+    pub shm_nattch: u64,
+    __unused4: u64,
+    __unused5: u64,
+}
@@ -0,0 +1,289 @@
+use super::mqueue::{self, mq_attr_t};
+use super::msg::{self, msqid_ds_t, MsgCtlCmd, MsgFlags};
+use super::sem::{self, sembuf_t, semid_ds_t, SemCtlCmd};
+use super::shm::{self, shmid_ds_t, ShmAtFlags, ShmCtlCmd};
+use super::sysv_ipc::{key_t, strip_ipc_64, IpcFlags};
+use super::*;
+
+use crate::process::sigevent_t;
+use crate::time::timespec_t;
+use crate::util::mem_util::from_user::*;
+
+pub fn do_shmget(key: key_t, size: usize, shmflg: i32) -> Result<isize> {
+    let (flags, mode) = IpcFlags::from_i32(shmflg);
+    let shmid = shm::do_shmget(key, size, flags, mode)?;
+    Ok(shmid as isize)
+}
+
+pub fn do_shmat(shmid: i32, shmaddr: usize, shmflg: i32) -> Result<isize> {
+    let flags = ShmAtFlags::from_i32(shmflg)?;
+    let addr = shm::do_shmat(shmid, shmaddr, flags)?;
+    Ok(addr as isize)
+}
+
+pub fn do_shmdt(shmaddr: usize) -> Result<isize> {
+    shm::do_shmdt(shmaddr)?;
+    Ok(0)
+}
+
+pub fn do_shmctl(shmid: i32, cmd: i32, buf: *mut shmid_ds_t) -> Result<isize> {
+    const IPC_RMID: i32 = 0;
+    const IPC_SET: i32 = 1;
+    const IPC_STAT: i32 = 2;
+    const SHM_LOCK: i32 = 11;
+    const SHM_UNLOCK: i32 = 12;
+
+    let cmd = match strip_ipc_64(cmd) {
+        IPC_RMID => ShmCtlCmd::IPC_RMID,
+        IPC_SET => {
+            check_ptr(buf)?;
+            ShmCtlCmd::IPC_SET(unsafe { &*buf })
+        }
+        IPC_STAT => {
+            check_mut_ptr(buf)?;
+            ShmCtlCmd::IPC_STAT(unsafe { &mut *buf })
+        }
+        SHM_LOCK => ShmCtlCmd::SHM_LOCK,
+        SHM_UNLOCK => ShmCtlCmd::SHM_UNLOCK,
+        _ => return_errno!(EINVAL, "unsupported command"),
+    };
+    shm::do_shmctl(shmid, cmd)?;
+    Ok(0)
+}
+
+pub fn do_semget(key: key_t, nsems: i32, semflg: i32) -> Result<isize> {
+    if nsems < 0 {
+        return_errno!(EINVAL, "invalid number of semaphores");
+    }
+    let (flags, mode) = IpcFlags::from_i32(semflg);
+    let semid = sem::do_semget(key, nsems as usize, flags, mode)?;
+    Ok(semid as isize)
+}
+
+pub fn do_semop(semid: i32, sops: *const sembuf_t, nsops: usize) -> Result<isize> {
+    do_semtimedop(semid, sops, nsops, std::ptr::null())
+}
+
+pub fn do_semtimedop(
+    semid: i32,
+    sops: *const sembuf_t,
+    nsops: usize,
+    timeout: *const timespec_t,
+) -> Result<isize> {
+    let sops = {
+        check_array(sops, nsops)?;
+        unsafe { std::slice::from_raw_parts(sops, nsops) }
+    };
+    let timeout = if timeout.is_null() {
+        None
+    } else {
+        check_ptr(timeout)?;
+        Some(timespec_t::from_raw_ptr(timeout)?.as_duration())
+    };
+    sem::do_semtimedop(semid, sops, timeout.as_ref())?;
+    Ok(0)
+}
+
+pub fn do_semctl(semid: i32, semnum: i32, cmd: i32, arg: usize) -> Result<isize> {
+    const IPC_RMID: i32 = 0;
+    const IPC_SET: i32 = 1;
+    const IPC_STAT: i32 = 2;
+    const GETPID: i32 = 11;
+    const GETVAL: i32 = 12;
+    const GETALL: i32 = 13;
+    const GETNCNT: i32 = 14;
+    const GETZCNT: i32 = 15;
+    const SETVAL: i32 = 16;
+    const SETALL: i32 = 17;
+
+    // The argument is `union semun`, which is either a value or a pointer
+    let cmd = match strip_ipc_64(cmd) {
+        IPC_RMID => SemCtlCmd::IPC_RMID,
+        IPC_SET => {
+            let buf = arg as *const semid_ds_t;
+            check_ptr(buf)?;
+            SemCtlCmd::IPC_SET(unsafe { &*buf })
+        }
+        IPC_STAT => {
+            let buf = arg as *mut semid_ds_t;
+            check_mut_ptr(buf)?;
+            SemCtlCmd::IPC_STAT(unsafe { &mut *buf })
+        }
+        GETPID => SemCtlCmd::GETPID,
+        GETVAL => SemCtlCmd::GETVAL,
+        GETALL => SemCtlCmd::GETALL(arg as *mut u16),
+        GETNCNT => SemCtlCmd::GETNCNT,
+        GETZCNT => SemCtlCmd::GETZCNT,
+        SETVAL => SemCtlCmd::SETVAL(arg as i32),
+        SETALL => SemCtlCmd::SETALL(arg as *const u16),
+        _ => return_errno!(EINVAL, "unsupported command"),
+    };
+    if semnum < 0 {
+        return_errno!(EINVAL, "invalid semaphore number");
+    }
+    sem::do_semctl(semid, semnum as usize, cmd)
+}
+
+pub fn do_mq_open(name: *const i8, oflag: i32, mode: u32, attr: *const mq_attr_t) -> Result<isize> {
+    let name = clone_cstring_safely(name)?.to_string_lossy().into_owned();
+    let attr = if attr.is_null() {
+        None
+    } else {
+        check_ptr(attr)?;
+        Some(unsafe { &*attr })
+    };
+    let fd = mqueue::do_mq_open(&name, oflag as u32, (mode & 0o777) as u16, attr)?;
+    Ok(fd as isize)
+}
+
+pub fn do_mq_unlink(name: *const i8) -> Result<isize> {
+    let name = clone_cstring_safely(name)?.to_string_lossy().into_owned();
+    mqueue::do_mq_unlink(&name)?;
+    Ok(0)
+}
+
+pub fn do_mq_timedsend(
+    mqdes: FileDesc,
+    msg_ptr: *const u8,
+    msg_len: usize,
+    msg_prio: u32,
+    abs_timeout: *const timespec_t,
+) -> Result<isize> {
+    let data = {
+        check_array(msg_ptr, msg_len)?;
+        unsafe { std::slice::from_raw_parts(msg_ptr, msg_len) }
+    };
+    let abs_timeout = if abs_timeout.is_null() {
+        None
+    } else {
+        check_ptr(abs_timeout)?;
+        Some(unsafe { &*abs_timeout })
+    };
+    mqueue::do_mq_timedsend(mqdes, data, msg_prio, abs_timeout)?;
+    Ok(0)
+}
+
+pub fn do_mq_timedreceive(
+    mqdes: FileDesc,
+    msg_ptr: *mut u8,
+    msg_len: usize,
+    msg_prio: *mut u32,
+    abs_timeout: *const timespec_t,
+) -> Result<isize> {
+    let buf = {
+        check_mut_array(msg_ptr, msg_len)?;
+        unsafe { std::slice::from_raw_parts_mut(msg_ptr, msg_len) }
+    };
+    if !msg_prio.is_null() {
+        check_mut_ptr(msg_prio)?;
+    }
+    let abs_timeout = if abs_timeout.is_null() {
+        None
+    } else {
+        check_ptr(abs_timeout)?;
+        Some(unsafe { &*abs_timeout })
+    };
+    let (len, prio) = mqueue::do_mq_timedreceive(mqdes, buf, abs_timeout)?;
+    if !msg_prio.is_null() {
+        unsafe {
+            *msg_prio = prio;
+        }
+    }
+    Ok(len as isize)
+}
+
+pub fn do_mq_notify(mqdes: FileDesc, sevp: *const sigevent_t) -> Result<isize> {
+    let sigevent = if sevp.is_null() {
+        None
+    } else {
+        check_ptr(sevp)?;
+        Some(unsafe { &*sevp })
+    };
+    mqueue::do_mq_notify(mqdes, sigevent)?;
+    Ok(0)
+}
+
+pub fn do_mq_getsetattr(
+    mqdes: FileDesc,
+    newattr: *const mq_attr_t,
+    oldattr: *mut mq_attr_t,
+) -> Result<isize> {
+    let new_attr = if newattr.is_null() {
+        None
+    } else {
+        check_ptr(newattr)?;
+        Some(unsafe { &*newattr })
+    };
+    if !oldattr.is_null() {
+        check_mut_ptr(oldattr)?;
+    }
+    let old_attr = mqueue::do_mq_getsetattr(mqdes, new_attr)?;
+    if !oldattr.is_null() {
+        unsafe {
+            *oldattr = old_attr;
+        }
+    }
+    Ok(0)
+}
+
+pub fn do_msgget(key: key_t, msgflg: i32) -> Result<isize> {
+    let (flags, mode) = IpcFlags::from_i32(msgflg);
+    let msqid = msg::do_msgget(key, flags, mode)?;
+    Ok(msqid as isize)
+}
+
+// The buffer of a message is `struct msgbuf`, which is the type of the message
+// followed by the data of the message
+pub fn do_msgsnd(msqid: i32, msgp: *const u8, msgsz: usize, msgflg: i32) -> Result<isize> {
+    let flags = MsgFlags::from_i32(msgflg)?;
+    let msgbuf_len = msgsz
+        .checked_add(std::mem::size_of::<i64>())
+        .ok_or_else(|| errno!(EINVAL, "invalid message size"))?;
+    check_array(msgp, msgbuf_len)?;
+    let mtype = unsafe { (msgp as *const i64).read_unaligned() };
+    let data = unsafe { std::slice::from_raw_parts(msgp.add(std::mem::size_of::<i64>()), msgsz) };
+    msg::do_msgsnd(msqid, mtype, data, flags)?;
+    Ok(0)
+}
+
+pub fn do_msgrcv(
+    msqid: i32,
+    msgp: *mut u8,
+    msgsz: usize,
+    msgtyp: i64,
+    msgflg: i32,
+) -> Result<isize> {
+    if (msgsz as isize) < 0 {
+        return_errno!(EINVAL, "invalid message size");
+    }
+    let flags = MsgFlags::from_i32(msgflg)?;
+    check_mut_array(msgp, msgsz + std::mem::size_of::<i64>())?;
+    let buf =
+        unsafe { std::slice::from_raw_parts_mut(msgp.add(std::mem::size_of::<i64>()), msgsz) };
+    let (mtype, len) = msg::do_msgrcv(msqid, buf, msgtyp, flags)?;
+    unsafe {
+        (msgp as *mut i64).write_unaligned(mtype);
+    }
+    Ok(len as isize)
+}
+
+pub fn do_msgctl(msqid: i32, cmd: i32, buf: *mut msqid_ds_t) -> Result<isize> {
+    const IPC_RMID: i32 = 0;
+    const IPC_SET: i32 = 1;
+    const IPC_STAT: i32 = 2;
+
+    let cmd = match strip_ipc_64(cmd) {
+        IPC_RMID => MsgCtlCmd::IPC_RMID,
+        IPC_SET => {
+            check_ptr(buf)?;
+            MsgCtlCmd::IPC_SET(unsafe { &*buf })
+        }
+        IPC_STAT => {
+            check_mut_ptr(buf)?;
+            MsgCtlCmd::IPC_STAT(unsafe { &mut *buf })
+        }
+        _ => return_errno!(EINVAL, "unsupported command"),
+    };
+    msg::do_msgctl(msqid, cmd)?;
+    Ok(0)
+}
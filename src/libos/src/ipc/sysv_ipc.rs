@@ -0,0 +1,231 @@
+use super::*;
+
+use crate::time::{do_clock_gettime, time_t, ClockID};
+
+#[allow(non_camel_case_types)]
+pub type key_t = i32;
+
+/// The key that always refers to a new IPC object.
+pub const IPC_PRIVATE: key_t = 0;
+
+/// The flag of the commands of the ctl syscalls set by musl, which requests
+/// the 64-bit version of the structures, the only version supported.
+const IPC_64: i32 = 0x100;
+
+bitflags! {
+    /// The flags of the get syscalls, e.g., shmget, whose lowest 9 bits are
+    /// the permission of the new IPC object.
+    pub struct IpcFlags : u32 {
+        const IPC_CREAT  = 0o1000;
+        const IPC_EXCL   = 0o2000;
+        const IPC_NOWAIT = 0o4000;
+    }
+}
+
+impl IpcFlags {
+    /// Get the flags and the permission from the flags of the get syscalls.
+    /// The unknown bits, e.g., SHM_HUGETLB, are ignored.
+    pub fn from_i32(bits: i32) -> (Self, u16) {
+        let flags = IpcFlags::from_bits_truncate(bits as u32);
+        let mode = (bits & 0o777) as u16;
+        (flags, mode)
+    }
+}
+
+/// Strip the IPC_64 flag from the command of a ctl syscall.
+pub fn strip_ipc_64(cmd: i32) -> i32 {
+    cmd & !IPC_64
+}
+
+/// The `struct ipc64_perm` of Linux.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct ipc_perm_t {
+    pub key: key_t,
+    pub uid: uid_t,
+    pub gid: u32,
+    pub cuid: uid_t,
+    pub cgid: u32,
+    pub mode: u16,
+    __pad1: u16,
+    pub seq: u16,
+    __pad2: u16,
+    __unused1: u64,
+    __unused2: u64,
+}
+
+/// The key, the ownership and the permission of an IPC object.
+///
+/// All the processes in an enclave run as root, which can access any IPC
+/// object regardless of the permission, so the permission is only recorded.
+#[derive(Debug, Clone, Copy)]
+pub struct IpcPerm {
+    key: key_t,
+    uid: uid_t,
+    gid: u32,
+    cuid: uid_t,
+    cgid: u32,
+    mode: u16,
+}
+
+impl IpcPerm {
+    pub fn new(key: key_t, mode: u16) -> Self {
+        Self {
+            key,
+            uid: 0,
+            gid: 0,
+            cuid: 0,
+            cgid: 0,
+            mode: mode & 0o777,
+        }
+    }
+
+    pub fn key(&self) -> key_t {
+        self.key
+    }
+
+    /// Change the owner and the permission, as IPC_SET does.
+    pub fn set(&mut self, perm: &ipc_perm_t) {
+        self.uid = perm.uid;
+        self.gid = perm.gid;
+        self.mode = (self.mode & !0o777) | (perm.mode & 0o777);
+    }
+
+    /// Convert to the C structure, with the extra bits of the mode, e.g.,
+    /// SHM_DEST, which are not part of the permission.
+    pub fn to_c(&self, id: i32, extra_mode: u16) -> ipc_perm_t {
+        ipc_perm_t {
+            key: self.key,
+            uid: self.uid,
+            gid: self.gid,
+            cuid: self.cuid,
+            cgid: self.cgid,
+            mode: self.mode | extra_mode,
+            seq: (id as u32 / IPC_ID_SEQ_MULTIPLIER) as u16,
+            ..Default::default()
+        }
+    }
+}
+
+// Same as Linux, an ID consists of the index of the object and a sequence
+// number, so that an ID is not reused soon after its object is removed
+const IPC_ID_SEQ_MULTIPLIER: u32 = 32768;
+
+/// The IDs of the IPC objects of one kind, e.g., the shared memory segments,
+/// and the keys that refer to them.
+pub struct IpcIds<T> {
+    objects: HashMap<i32, Arc<T>>,
+    keys: HashMap<key_t, i32>,
+    seq: u32,
+}
+
+impl<T> IpcIds<T> {
+    pub fn new() -> Self {
+        Self {
+            objects: HashMap::new(),
+            keys: HashMap::new(),
+            seq: 0,
+        }
+    }
+
+    /// Get the ID of the object referred to by the key, or create an object
+    /// by `new_object` given the new ID, following the semantics of IPC_CREAT
+    /// and IPC_EXCL. The existing object is checked by `check_object`.
+    pub fn get_or_create<F, C>(
+        &mut self,
+        key: key_t,
+        flags: IpcFlags,
+        new_object: F,
+        check_object: C,
+    ) -> Result<i32>
+    where
+        F: FnOnce(i32) -> Result<T>,
+        C: FnOnce(&T) -> Result<()>,
+    {
+        if key != IPC_PRIVATE {
+            if let Some(&id) = self.keys.get(&key) {
+                if flags.contains(IpcFlags::IPC_CREAT | IpcFlags::IPC_EXCL) {
+                    return_errno!(EEXIST, "the IPC object of the key exists");
+                }
+                check_object(&self.objects[&id])?;
+                return Ok(id);
+            }
+            if !flags.contains(IpcFlags::IPC_CREAT) {
+                return_errno!(ENOENT, "no IPC object of the key");
+            }
+        }
+
+        let id = self.alloc_id()?;
+        let object = new_object(id)?;
+        self.objects.insert(id, Arc::new(object));
+        if key != IPC_PRIVATE {
+            self.keys.insert(key, id);
+        }
+        Ok(id)
+    }
+
+    fn alloc_id(&mut self) -> Result<i32> {
+        let idx = (0..IPC_ID_SEQ_MULTIPLIER)
+            .find(|idx| {
+                self.objects
+                    .keys()
+                    .all(|id| *id as u32 % IPC_ID_SEQ_MULTIPLIER != *idx)
+            })
+            .ok_or_else(|| errno!(ENOSPC, "too many IPC objects"))?;
+        self.seq = (self.seq + 1) % (i32::max_value() as u32 / IPC_ID_SEQ_MULTIPLIER);
+        Ok((self.seq * IPC_ID_SEQ_MULTIPLIER + idx) as i32)
+    }
+
+    pub fn get(&self, id: i32) -> Result<Arc<T>> {
+        self.objects
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| errno!(EINVAL, "invalid IPC object ID"))
+    }
+
+    pub fn objects(&self) -> impl Iterator<Item = &Arc<T>> {
+        self.objects.values()
+    }
+
+    /// Find the object that satisfies the predicate.
+    pub fn find<P: Fn(&T) -> bool>(&self, predicate: P) -> Option<Arc<T>> {
+        self.objects
+            .values()
+            .find(|object| predicate(object))
+            .cloned()
+    }
+
+    /// Unlink the key from the object, so that the key refers to a new object
+    /// afterwards while the object can still be accessed by its ID.
+    pub fn remove_key(&mut self, id: i32) {
+        self.keys.retain(|_, key_id| *key_id != id);
+    }
+
+    /// Remove the object and its key.
+    pub fn remove(&mut self, id: i32) -> Option<Arc<T>> {
+        self.remove_key(id);
+        self.objects.remove(&id)
+    }
+
+    /// Remove the objects that do not satisfy the predicate, along with their keys.
+    pub fn retain<P: Fn(&T) -> bool>(&mut self, predicate: P) {
+        let removed_ids: Vec<i32> = self
+            .objects
+            .iter()
+            .filter(|(_, object)| !predicate(object))
+            .map(|(id, _)| *id)
+            .collect();
+        for id in removed_ids {
+            self.remove(id);
+        }
+    }
+}
+
+/// Get the current time in seconds, which is used as the time of the
+/// operations on IPC objects.
+pub fn ipc_time_now() -> time_t {
+    do_clock_gettime(ClockID::CLOCK_REALTIME)
+        .map(|now| now.sec())
+        .unwrap_or(0)
+}
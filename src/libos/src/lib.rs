@@ -30,10 +30,13 @@ extern crate sgx_types;
 #[macro_use]
 extern crate sgx_tstd as std;
 extern crate goblin;
+extern crate rustls;
 extern crate scroll;
 extern crate sgx_tcrypto;
 extern crate sgx_trts;
 extern crate sgx_tse;
+extern crate smoltcp;
+extern crate webpki;
 #[macro_use]
 extern crate lazy_static;
 #[macro_use]
@@ -43,7 +46,6 @@ extern crate rcore_fs_devfs;
 extern crate rcore_fs_mountfs;
 extern crate rcore_fs_ramfs;
 extern crate rcore_fs_sefs;
-extern crate rcore_fs_unionfs;
 #[macro_use]
 extern crate derive_builder;
 extern crate ringbuf;
@@ -72,6 +74,7 @@ mod events;
 mod exception;
 mod fs;
 mod interrupt;
+mod ipc;
 mod misc;
 mod net;
 mod process;
@@ -6,6 +6,8 @@ mod rlimit;
 mod sysinfo;
 mod uname;
 
-pub use self::rlimit::{do_prlimit, resource_t, rlimit_t, ResourceLimits};
+pub use self::rlimit::{
+    check_nproc_limit, do_getrlimit, do_prlimit, do_setrlimit, resource_t, rlimit_t, ResourceLimits,
+};
 pub use self::sysinfo::{do_sysinfo, sysinfo_t};
 pub use self::uname::{do_uname, utsname_t};
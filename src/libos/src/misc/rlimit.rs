@@ -1,5 +1,5 @@
 use super::*;
-use process::pid_t;
+use process::{pid_t, ThreadRef};
 
 #[derive(Debug, Copy, Clone)]
 pub struct ResourceLimits {
@@ -32,18 +32,24 @@ impl Default for ResourceLimits {
         // heap, stack and mmap size.
         let address_space = rlimit_t::new(cfg_heap_size + cfg_stack_size + cfg_mmap_size);
 
+        let open_files = rlimit_t::new(config::LIBOS_CONFIG.process.default_max_open_files);
+        let processes = rlimit_t::new(config::LIBOS_CONFIG.process.default_max_processes);
+
         let mut rlimits = ResourceLimits {
             rlimits: [Default::default(); RLIMIT_COUNT],
         };
         *rlimits.get_mut(resource_t::RLIMIT_DATA) = data_size;
         *rlimits.get_mut(resource_t::RLIMIT_STACK) = stack_size;
         *rlimits.get_mut(resource_t::RLIMIT_AS) = address_space;
+        *rlimits.get_mut(resource_t::RLIMIT_NOFILE) = open_files;
+        *rlimits.get_mut(resource_t::RLIMIT_NPROC) = processes;
 
         rlimits
     }
 }
 
 #[derive(Debug, Copy, Clone)]
+#[repr(C)]
 #[allow(non_camel_case_types)]
 pub struct rlimit_t {
     cur: u64,
@@ -128,7 +134,9 @@ impl resource_t {
 /// since the rlimits object is shared between threads in a process, the
 /// semantic of limiting resource usage on a per-process basisi is preserved.
 ///
-/// Limitation: Current implementation only takes effect on child processes.
+/// The limits of RLIMIT_NOFILE, RLIMIT_NPROC and RLIMIT_AS are enforced on
+/// the process immediately, while the limits of RLIMIT_DATA and RLIMIT_STACK
+/// only take effect on the child processes, whose memory is allocated on spawn.
 pub fn do_prlimit(
     pid: pid_t,
     resource: resource_t,
@@ -145,9 +153,9 @@ pub fn do_prlimit(
         *old_limit = *rlimits.get(resource)
     }
     if let Some(new_limit) = new_limit {
-        // Privilege is not granted for setting hard limit
-        if new_limit.get_max() != u64::max_value() {
-            return_errno!(EPERM, "setting hard limit is not permitted")
+        // Privilege is not granted for raising hard limit
+        if new_limit.get_max() > rlimits.get(resource).get_max() {
+            return_errno!(EPERM, "raising hard limit is not permitted")
         }
         if new_limit.get_cur() > new_limit.get_max() {
             return_errno!(EINVAL, "soft limit is greater than hard limit");
@@ -166,6 +174,7 @@ pub fn do_prlimit(
             resource_t::RLIMIT_AS => {
                 soft_rlimit_address_space_size = new_limit.get_cur();
             }
+            resource_t::RLIMIT_NOFILE | resource_t::RLIMIT_NPROC => {}
             _ => warn!("resource type not supported"),
         }
 
@@ -190,3 +199,22 @@ pub fn do_getrlimit(resource: resource_t, old_limit: &mut rlimit_t) -> Result<()
 pub fn do_setrlimit(resource: resource_t, new_limit: &rlimit_t) -> Result<()> {
     do_prlimit(0 as pid_t, resource, Some(new_limit), None)
 }
+
+/// Check whether a new thread or process can be created by a thread under the
+/// soft limit of its RLIMIT_NPROC.
+///
+/// As all LibOS processes belong to the same user, the limit is on the total
+/// number of threads in LibOS, like Linux does for a user.
+pub fn check_nproc_limit(thread: &ThreadRef) -> Result<()> {
+    let max_threads = thread
+        .rlimits()
+        .lock()
+        .unwrap()
+        .get(resource_t::RLIMIT_NPROC)
+        .get_cur();
+    let num_threads = process::table::get_all_threads().len() as u64;
+    if num_threads >= max_threads {
+        return_errno!(EAGAIN, "the number of threads reaches RLIMIT_NPROC");
+    }
+    Ok(())
+}
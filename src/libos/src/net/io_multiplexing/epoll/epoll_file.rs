@@ -193,8 +193,11 @@ impl EpollFile {
 
                     // Behave differently according the epoll flags
 
+                    // A one-shot entry is disabled after reporting an event. It will
+                    // not be reported again until it is re-armed with EPOLL_CTL_MOD.
                     if inner.flags.contains(EpollFlags::ONE_SHOT) {
                         inner.event.mask = IoEvents::empty();
+                        ep_entry.mask.store(IoEvents::empty(), Ordering::Release);
                     }
 
                     if !inner
@@ -242,7 +245,17 @@ impl EpollFile {
             return_errno!(EINVAL, "a epoll file cannot epoll itself");
         }
 
-        self.check_flags(&flags);
+        self.check_flags(&flags)?;
+        if flags.contains(EpollFlags::EXCLUSIVE) {
+            if file.as_epoll_file().is_ok() {
+                return_errno!(EINVAL, "an epoll file cannot be added exclusively");
+            }
+            if !(IoEvents::IN | IoEvents::OUT | IoEvents::ERR | IoEvents::HUP)
+                .contains(event.mask())
+            {
+                return_errno!(EINVAL, "invalid events for an exclusive entry");
+            }
+        }
         self.prepare_event(&mut event);
 
         let ep_entry = Arc::new(EpollEntry::new(fd, file, event, flags));
@@ -263,7 +276,17 @@ impl EpollFile {
             // Start observing events on the target file.
             let weak_observer = self.weak_self.clone() as Weak<dyn Observer<_>>;
             let weak_ep_entry = Arc::downgrade(&ep_entry);
-            notifier.register(weak_observer, Some(IoEvents::all()), Some(weak_ep_entry));
+            // An exclusive entry stops the event from waking up the other epoll
+            // files that have added the target file exclusively.
+            if ep_entry.is_exclusive {
+                notifier.register_exclusive(
+                    weak_observer,
+                    Some(IoEvents::all()),
+                    Some(weak_ep_entry),
+                );
+            } else {
+                notifier.register(weak_observer, Some(IoEvents::all()), Some(weak_ep_entry));
+            }
 
             // Handle host file
             if ep_entry.file.host_fd().is_some() {
@@ -299,7 +322,10 @@ impl EpollFile {
     }
 
     fn mod_interest(&self, fd: FileDesc, mut event: EpollEvent, flags: EpollFlags) -> Result<()> {
-        self.check_flags(&flags);
+        self.check_flags(&flags)?;
+        if flags.contains(EpollFlags::EXCLUSIVE) {
+            return_errno!(EINVAL, "EPOLLEXCLUSIVE is not allowed for EPOLL_CTL_MOD");
+        }
         self.prepare_event(&mut event);
 
         // A critical section protected by the lock of self.interest
@@ -309,6 +335,9 @@ impl EpollFile {
                 .get(&fd)
                 .ok_or_else(|| errno!(ENOENT, "fd is not added"))?
                 .clone();
+            if ep_entry.is_exclusive {
+                return_errno!(EINVAL, "an exclusive entry cannot be modified");
+            }
 
             let new_ep_inner = EpollEntryInner { event, flags };
             let mut old_ep_inner = ep_entry.inner.lock().unwrap();
@@ -316,6 +345,8 @@ impl EpollFile {
                 return Ok(());
             }
             *old_ep_inner = new_ep_inner;
+            // This also re-arms the entry in case it has been disabled by EPOLLONESHOT
+            ep_entry.mask.store(event.mask(), Ordering::Release);
             drop(old_ep_inner);
 
             if ep_entry.file.host_fd().is_some() {
@@ -351,6 +382,7 @@ impl EpollFile {
 
     fn push_ready_iter<I: Iterator<Item = Arc<EpollEntry>>>(&self, ep_entries: I) {
         let mut has_pushed_any = false;
+        let mut has_pushed_exclusive_only = true;
 
         // A critical section protected by self.ready.lock()
         {
@@ -361,6 +393,9 @@ impl EpollFile {
                 }
 
                 ep_entry.is_ready.store(true, Ordering::Relaxed);
+                if !ep_entry.is_exclusive {
+                    has_pushed_exclusive_only = false;
+                }
                 ready_entries.push_back(ep_entry);
 
                 has_pushed_any = true;
@@ -368,7 +403,7 @@ impl EpollFile {
         }
 
         if has_pushed_any {
-            self.mark_ready();
+            self.mark_ready(has_pushed_exclusive_only);
         }
     }
 
@@ -387,15 +422,26 @@ impl EpollFile {
         }
     }
 
-    fn mark_ready(&self) {
+    fn mark_ready(&self, exclusive: bool) {
         self.notifier.broadcast(&IoEvents::IN);
-        self.waiters.dequeue_and_wake_all();
+        // If all the newly-ready entries are exclusive, then waking up one waiter
+        // is enough to avoid the thundering herd problem.
+        if exclusive {
+            self.waiters.dequeue_and_wake_one();
+        } else {
+            self.waiters.dequeue_and_wake_all();
+        }
     }
 
-    fn check_flags(&self, flags: &EpollFlags) {
-        if flags.intersects(EpollFlags::EXCLUSIVE | EpollFlags::WAKE_UP) {
-            warn!("{:?} contains unsupported flags", flags);
+    fn check_flags(&self, flags: &EpollFlags) -> Result<()> {
+        if flags.contains(EpollFlags::EXCLUSIVE) && flags.contains(EpollFlags::ONE_SHOT) {
+            return_errno!(EINVAL, "EPOLLEXCLUSIVE cannot be used with EPOLLONESHOT");
+        }
+        if flags.contains(EpollFlags::WAKE_UP) {
+            // There is no system suspend in LibOS, so EPOLLWAKEUP is meaningless
+            debug!("EPOLLWAKEUP is ignored");
         }
+        Ok(())
     }
 
     fn prepare_event(&self, event: &mut EpollEvent) {
@@ -462,17 +508,57 @@ impl Drop for EpollFile {
 }
 
 impl Observer<IoEvents> for EpollFile {
-    fn on_event(&self, _events: &IoEvents, metadata: &Option<Weak<dyn Any + Send + Sync>>) {
+    fn on_event(&self, events: &IoEvents, metadata: &Option<Weak<dyn Any + Send + Sync>>) {
+        self.handle_event(events, metadata);
+    }
+
+    fn on_exclusive_event(
+        &self,
+        events: &IoEvents,
+        metadata: &Option<Weak<dyn Any + Send + Sync>>,
+    ) -> bool {
+        // Like Linux, the event is taken only if it is interesting and there
+        // are threads waiting on this epoll file to handle it.
+        let has_waiters = !self.waiters.is_empty();
+        self.handle_event(events, metadata) && has_waiters
+    }
+}
+
+impl EpollFile {
+    // Push the entry in the metadata to the ready list if the events are
+    // interesting to it. Return whether they are interesting.
+    fn handle_event(
+        &self,
+        events: &IoEvents,
+        metadata: &Option<Weak<dyn Any + Send + Sync>>,
+    ) -> bool {
         let ep_entry_opt = metadata
             .as_ref()
             .and_then(|weak_any| weak_any.upgrade())
             .and_then(|strong_any| strong_any.downcast().ok());
         let ep_entry: Arc<EpollEntry> = match ep_entry_opt {
-            None => return,
+            None => return false,
             Some(ep_entry) => ep_entry,
         };
 
+        // Only the entries with interesting events are pushed to the ready list.
+        // Otherwise, the events that no one waits for, e.g., the writability of
+        // a socket that is only polled for reading, would wake up the waiters
+        // and make them poll the files in vain. Note that a disabled one-shot
+        // entry is not interested in any events.
+        let events = if events.contains(IoEvents::RDHUP) {
+            // The shutdown of the peer makes the file readable
+            *events | IoEvents::IN
+        } else {
+            *events
+        };
+        let mask = ep_entry.mask.load(Ordering::Acquire);
+        if !events.intersects(mask) {
+            return false;
+        }
+
         self.push_ready(ep_entry);
+        true
     }
 }
 
@@ -513,12 +599,19 @@ struct EpollEntry {
     is_ready: AtomicBool,
     // Whether the entry has been deleted from the interest list
     is_deleted: AtomicBool,
+    // The interesting events, which are checked without the lock of `inner` when
+    // an event happens. It is empty after an event is reported with EPOLLONESHOT.
+    mask: Atomic<IoEvents>,
+    // Whether the entry is added with EPOLLEXCLUSIVE, which cannot be modified later
+    is_exclusive: bool,
 }
 
 impl EpollEntry {
     pub fn new(fd: FileDesc, file: FileRef, event: EpollEvent, flags: EpollFlags) -> Self {
         let is_ready = Default::default();
         let is_deleted = Default::default();
+        let mask = Atomic::new(event.mask());
+        let is_exclusive = flags.contains(EpollFlags::EXCLUSIVE);
         let inner = SgxMutex::new(EpollEntryInner { event, flags });
         Self {
             fd,
@@ -526,6 +619,8 @@ impl EpollEntry {
             inner,
             is_ready,
             is_deleted,
+            mask,
+            is_exclusive,
         }
     }
 }
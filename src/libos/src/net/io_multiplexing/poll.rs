@@ -115,10 +115,6 @@ pub fn do_poll(pollfds: &mut [PollEvent], timeout: *mut timeval_t) -> Result<usi
             let fd = socket.host_fd().unwrap().to_raw();
             index_host_pollfds.push(i);
             host_pollfds.push(PollEvent::new(fd, pollfd.events()));
-        } else if let Ok(eventfd) = file_ref.as_event() {
-            let fd = eventfd.host_fd() as FileDesc;
-            index_host_pollfds.push(i);
-            host_pollfds.push(PollEvent::new(fd, pollfd.events()));
         } else {
             return_errno!(EBADF, "not a supported file type");
         }
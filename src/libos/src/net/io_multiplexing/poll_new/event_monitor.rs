@@ -6,6 +6,7 @@ use std::time::Duration;
 use crate::events::{Observer, Waiter, WaiterQueueObserver};
 use crate::fs::{AtomicIoEvents, IoEvents};
 use crate::prelude::*;
+use crate::signal::has_interrupting_signals;
 use crate::time::{timespec_t, TIMERSLACK};
 
 /// Monitor events that happen on a set of interesting files.
@@ -73,15 +74,36 @@ impl EventMonitor {
             }
         }
 
+        // A signal that is pending before the wait, e.g., unblocked by the
+        // signal mask given to ppoll, does not interrupt the host syscall
+        if has_interrupting_signals(&current!()) {
+            return_errno!(EINTR, "interrupted by signals");
+        }
+
         // The do_ocall method returns when one of the following conditions is satisfied:
         // 1. self.waiter is waken, indicating some interesting events happen on the LibOS files;
         // 2. some interesting events happen on the host files;
         // 3. a signal arrives;
         // 4. the time is up.
-        let num_events = self.do_poll_ocall(&mut timeout)?;
+        let num_events = match self.do_poll_ocall(&mut timeout) {
+            Ok(num_events) => num_events,
+            // The host syscall is interrupted by a host signal
+            Err(e) if e.errno() == EINTR => 0,
+            Err(e) => return Err(e),
+        };
 
         self.update_host_file_events(num_events);
 
+        // A signal arrives if the thread has interrupting signals. Otherwise,
+        // the interruption of the host syscall or the wakeup is spurious.
+        if has_interrupting_signals(&current!()) {
+            return_errno!(EINTR, "interrupted by signals");
+        }
+        if num_events == 0 && timeout.as_ref().map_or(true, |timeout| **timeout != ZERO) {
+            // Interrupted before the time is up, so let the caller try again
+            return Ok(timeout);
+        }
+
         // Poll syscall does not treat timeout as error. So we need
         // to distinguish the case by ourselves.
         if let Some(timeout) = timeout.as_mut() {
@@ -3,13 +3,17 @@ use std;
 use untrusted::{SliceAsMutPtrAndLen, SliceAsPtrAndLen, UntrustedSliceAlloc};
 
 pub use self::io_multiplexing::{
-    clear_notifier_status, notify_thread, wait_for_notification, EpollEvent, IoEvent, PollEvent,
-    PollEventFlags, PollFd, THREAD_NOTIFIERS,
+    clear_notifier_status, do_poll_new, notify_thread, wait_for_notification, AsEpollFile,
+    EpollEvent, IoEvent, PollEvent, PollEventFlags, PollFd, THREAD_NOTIFIERS,
 };
 pub use self::socket::{
-    msghdr, msghdr_mut, socketpair, unix_socket, AddressFamily, AsUnixSocket, FileFlags,
-    HostSocket, HostSocketType, HowToShut, Iovs, IovsMut, MsgHdr, MsgHdrFlags, MsgHdrMut,
-    RecvFlags, SendFlags, SliceAsLibcIovec, SockAddr, SocketType, UnixAddr,
+    do_poll_net, enclave_socket, is_enclave_addr, is_enclave_stack_enabled, is_resolver_addr,
+    linger_t, mmsghdr, mmsghdr_mut, msghdr, msghdr_mut, netlink_socket, socketpair, unix_socket,
+    write_resolv_conf, AddressFamily, AsDnsSocket, AsEnclaveSocket, AsNetlinkSocket,
+    AsUnixDatagram, AsUnixSocket, DnsSocket, EnclaveSocket, FileFlags, HostSocket, HostSocketType,
+    HowToShut, Iovs, IovsMut, MsgHdr, MsgHdrFlags, MsgHdrMut, NetlinkAddr, RecvFlags, SendFlags,
+    SliceAsLibcIovec, SockAddr, SockOpt, SockOpts, SockOptsExt, SockTimeouts, SockTimeoutsExt,
+    SocketType, UnixAddr, NETLINK_ROUTE,
 };
 pub use self::syscalls::*;
 
@@ -0,0 +1,81 @@
+use super::*;
+use fs::{AccessMode, IoEvents, IoNotifier, IoctlCmd, StatusFlags};
+use std::any::Any;
+
+impl File for DnsSocket {
+    fn read(&self, buf: &mut [u8]) -> Result<usize> {
+        self.readv(&mut [buf])
+    }
+
+    fn write(&self, buf: &[u8]) -> Result<usize> {
+        self.writev(&[buf])
+    }
+
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize> {
+        if offset != 0 {
+            return_errno!(ESPIPE, "a nonzero position is not supported");
+        }
+        self.read(buf)
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> Result<usize> {
+        if offset != 0 {
+            return_errno!(ESPIPE, "a nonzero position is not supported");
+        }
+        self.write(buf)
+    }
+
+    fn readv(&self, bufs: &mut [&mut [u8]]) -> Result<usize> {
+        let (bytes_recvd, _) = self.recv(bufs, RecvFlags::empty())?;
+        Ok(bytes_recvd)
+    }
+
+    fn writev(&self, bufs: &[&[u8]]) -> Result<usize> {
+        self.send(bufs, None)
+    }
+
+    fn ioctl(&self, cmd: &mut IoctlCmd) -> Result<i32> {
+        match cmd {
+            // Same as Linux, the length of the next reply is returned
+            IoctlCmd::FIONREAD(arg) => {
+                **arg = self.bytes_to_read().min(std::i32::MAX as usize) as i32;
+                Ok(0)
+            }
+            IoctlCmd::SIOCGIFCONF(_) | IoctlCmd::SIOCGIFADDR(_) | IoctlCmd::SIOCGIFHWADDR(_) => {
+                do_iface_ioctl(cmd)
+            }
+            _ => return_errno!(EINVAL, "unknown ioctl cmd for DNS socket"),
+        }
+    }
+
+    fn access_mode(&self) -> Result<AccessMode> {
+        Ok(AccessMode::O_RDWR)
+    }
+
+    fn status_flags(&self) -> Result<StatusFlags> {
+        if self.nonblocking() {
+            Ok(StatusFlags::O_NONBLOCK)
+        } else {
+            Ok(StatusFlags::empty())
+        }
+    }
+
+    fn set_status_flags(&self, new_status_flags: StatusFlags) -> Result<()> {
+        // Only O_NONBLOCK is supported
+        let nonblocking = new_status_flags.contains(StatusFlags::O_NONBLOCK);
+        self.set_nonblocking(nonblocking);
+        Ok(())
+    }
+
+    fn poll_new(&self) -> IoEvents {
+        self.poll()
+    }
+
+    fn notifier(&self) -> Option<&IoNotifier> {
+        Some(self.notifier())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
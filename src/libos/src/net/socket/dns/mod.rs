@@ -0,0 +1,102 @@
+//! The resolver of LibOS.
+//!
+//! When the resolver is enabled in the config, `/etc/resolv.conf` is
+//! generated from the config at startup, whose nameserver is a virtual
+//! address. A UDP or TCP socket that talks to the address is replaced by a
+//! DNS socket in all the fds that refer to it, e.g., the dup'd ones, and the
+//! queries of the DNS socket are answered inside the enclave from the cache, or
+//! by forwarding them to the upstream server over TLS, so that the answers
+//! cannot be spoofed by the host. The queries can also be forwarded over
+//! plain UDP, which only defends against off-path spoofing, as the queries
+//! have random IDs and the answers must match the questions.
+
+use super::*;
+use crate::config::LIBOS_CONFIG;
+use fs::File;
+use rcore_fs::vfs::{FileType, INode};
+use std::net::Ipv4Addr;
+
+mod file;
+mod resolver;
+mod socket;
+
+pub use self::socket::DnsSocket;
+
+/// The virtual address of the resolver, the same as that of systemd-resolved.
+const RESOLVER_ADDR: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 53);
+const DNS_PORT: u16 = 53;
+
+pub fn is_resolver_enabled() -> bool {
+    LIBOS_CONFIG.network.dns.is_some()
+}
+
+/// Whether the address is the virtual address of the resolver.
+pub fn is_resolver_addr(addr: &SockAddr) -> bool {
+    is_resolver_enabled() && ipv4_of_sockaddr(addr) == Some((RESOLVER_ADDR, DNS_PORT))
+}
+
+/// Generate `/etc/resolv.conf` from the config, whose nameserver is the
+/// resolver of LibOS.
+pub fn write_resolv_conf(root: &dyn INode) -> Result<()> {
+    let config = match &LIBOS_CONFIG.network.dns {
+        Some(config) => config,
+        None => return Ok(()),
+    };
+    let mut content = format!("nameserver {}\n", RESOLVER_ADDR);
+    if !config.search.is_empty() {
+        content += &format!("search {}\n", config.search.join(" "));
+    }
+
+    let etc = match root.find("etc") {
+        Ok(etc) => etc,
+        Err(_) => root.create("etc", FileType::Dir, 0o755)?,
+    };
+    let file = match etc.find("resolv.conf") {
+        Ok(file) => file,
+        Err(_) => etc.create("resolv.conf", FileType::File, 0o644)?,
+    };
+    file.resize(0)?;
+    file.write_at(0, content.as_bytes())?;
+    Ok(())
+}
+
+fn ipv4_of_sockaddr(addr: &SockAddr) -> Option<(Ipv4Addr, u16)> {
+    let raw = addr.as_slice();
+    if raw.len() < std::mem::size_of::<libc::sockaddr_in>() {
+        return None;
+    }
+    let addr_in = unsafe { &*(raw.as_ptr() as *const libc::sockaddr_in) };
+    if addr_in.sin_family != AddressFamily::INET as libc::sa_family_t {
+        return None;
+    }
+    Some((
+        Ipv4Addr::from(u32::from_be(addr_in.sin_addr.s_addr)),
+        u16::from_be(addr_in.sin_port),
+    ))
+}
+
+fn sockaddr_of_ipv4(ip: Ipv4Addr, port: u16) -> SockAddr {
+    let mut addr_in: libc::sockaddr_in = unsafe { std::mem::zeroed() };
+    addr_in.sin_family = AddressFamily::INET as libc::sa_family_t;
+    addr_in.sin_port = port.to_be();
+    addr_in.sin_addr.s_addr = u32::from(ip).to_be();
+    unsafe {
+        SockAddr::try_from_raw(
+            &addr_in as *const _ as *const libc::sockaddr,
+            std::mem::size_of::<libc::sockaddr_in>() as u32,
+        )
+        .unwrap()
+    }
+}
+
+pub trait AsDnsSocket {
+    fn as_dns_socket(&self) -> Result<&DnsSocket>;
+}
+
+impl AsDnsSocket for FileRef {
+    fn as_dns_socket(&self) -> Result<&DnsSocket> {
+        self.as_any()
+            .downcast_ref::<DnsSocket>()
+            .ok_or_else(|| errno!(EBADF, "not a DNS socket"))
+    }
+}
@@ -0,0 +1,399 @@
+use super::*;
+use crate::config::ConfigDns;
+use crate::time::{do_clock_gettime, time_t, timeval_t, ClockID};
+use crate::util::random::get_random;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::super::host::TlsStream;
+
+const HEADER_SIZE: usize = 12;
+const FLAG_QR: u16 = 0x8000;
+const FLAG_TC: u16 = 0x0200;
+const FLAG_RA: u16 = 0x0080;
+const RCODE_MASK: u16 = 0x000f;
+const RCODE_NOERROR: u16 = 0;
+const RCODE_FORMERR: u16 = 1;
+const RCODE_SERVFAIL: u16 = 2;
+const RCODE_NXDOMAIN: u16 = 3;
+const TYPE_OPT: u16 = 41;
+
+const SOL_SOCKET: i32 = 1;
+const SO_RCVTIMEO: i32 = 20;
+const SO_SNDTIMEO: i32 = 21;
+
+// The max size of a UDP message without EDNS
+const MAX_UDP_SIZE: usize = 512;
+const DOT_PORT: u16 = 853;
+// The timeout of sending to and receiving from the upstream server
+const UPSTREAM_TIMEOUT: Duration = Duration::from_secs(5);
+const UPSTREAM_RETRIES: usize = 3;
+const MAX_IGNORED_REPLIES: usize = 16;
+const MAX_CACHE_ENTRIES: usize = 1024;
+// The TTL of the negative answers without records
+const NEGATIVE_TTL: u32 = 60;
+
+lazy_static! {
+    static ref CACHE: SgxMutex<HashMap<Question, CacheEntry>> = SgxMutex::new(HashMap::new());
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Question {
+    // The name in the wire format, which is lowercased
+    name: Vec<u8>,
+    qtype: u16,
+    qclass: u16,
+}
+
+struct CacheEntry {
+    reply: Vec<u8>,
+    cached_at: Duration,
+    expires_at: Duration,
+}
+
+/// Answer the query, returning the reply, or None if the query is too
+/// malformed to be replied. The reply is truncated if it is too large for
+/// UDP, but never over TCP.
+pub fn resolve(query: &[u8], over_tcp: bool) -> Option<Vec<u8>> {
+    if query.len() < HEADER_SIZE || read_u16(query, 2) & FLAG_QR != 0 {
+        return None;
+    }
+    let (question, question_end) = match parse_question(query) {
+        Ok(question) => question,
+        Err(_) => return Some(error_reply(query, HEADER_SIZE, RCODE_FORMERR)),
+    };
+    let max_size = if over_tcp {
+        u16::max_value() as usize
+    } else {
+        max_reply_size(query, question_end)
+    };
+
+    let reply = match lookup_cache(&question) {
+        Some(reply) => reply,
+        None => match forward(query, &question) {
+            Ok(reply) => {
+                insert_cache(question, &reply, question_end);
+                reply
+            }
+            Err(e) => {
+                warn!("failed to resolve the query: {}", e.backtrace());
+                return Some(error_reply(query, question_end, RCODE_SERVFAIL));
+            }
+        },
+    };
+    let mut reply = if reply.len() > max_size {
+        // Same as a DNS server, the truncated reply only has the question
+        let mut truncated = reply[..question_end].to_vec();
+        let flags = read_u16(&truncated, 2) | FLAG_TC;
+        write_u16(&mut truncated, 2, flags);
+        for offset in [6, 8, 10].iter() {
+            write_u16(&mut truncated, *offset, 0);
+        }
+        truncated
+    } else {
+        reply
+    };
+    // The ID of the query is restored
+    reply[..2].copy_from_slice(&query[..2]);
+    Some(reply)
+}
+
+// Forward the query to the upstream server with a random ID
+fn forward(query: &[u8], question: &Question) -> Result<Vec<u8>> {
+    let config = LIBOS_CONFIG.network.dns.as_ref().unwrap();
+    let mut query = query.to_vec();
+    let mut id = [0; 2];
+    get_random(&mut id)?;
+    query[..2].copy_from_slice(&id);
+
+    let mut last_error = errno!(EIO, "no reply from the upstream server");
+    for _ in 0..UPSTREAM_RETRIES {
+        let result = match &config.tls {
+            Some(_) => forward_over_tls(config, &query),
+            None => forward_over_udp(config, &query),
+        };
+        match result {
+            Ok(reply) => {
+                check_reply(&reply, &query[..2], question)?;
+                return Ok(reply);
+            }
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+fn forward_over_udp(config: &ConfigDns, query: &[u8]) -> Result<Vec<u8>> {
+    let port = config.port.unwrap_or(DNS_PORT);
+    let socket = upstream_socket(SocketType::DGRAM, config.upstream, port)?;
+    socket.sendto(query, SendFlags::MSG_NOSIGNAL, &None)?;
+    // The replies of other IDs, e.g., the spoofed ones, are ignored
+    for _ in 0..MAX_IGNORED_REPLIES {
+        let mut buf = vec![0; u16::max_value() as usize];
+        let (len, _) = socket.recvfrom(&mut buf, RecvFlags::empty())?;
+        if len >= HEADER_SIZE && buf[..2] == query[..2] {
+            buf.truncate(len);
+            return Ok(buf);
+        }
+    }
+    return_errno!(EBADMSG, "too many replies of other IDs");
+}
+
+fn forward_over_tls(config: &ConfigDns, query: &[u8]) -> Result<Vec<u8>> {
+    let tls_config = config.tls.as_ref().unwrap();
+    let port = config.port.unwrap_or(DOT_PORT);
+    let socket = upstream_socket(SocketType::STREAM, config.upstream, port)?;
+    let mut tls = TlsStream::new_client_with(&tls_config.ca, &tls_config.server_name)?;
+
+    // Over TCP, a message is prefixed by its length
+    let mut msg = (query.len() as u16).to_be_bytes().to_vec();
+    msg.extend_from_slice(query);
+    let mut bytes_sent = 0;
+    while bytes_sent < msg.len() {
+        bytes_sent += tls.send(&socket, &[&msg[bytes_sent..]], SendFlags::MSG_NOSIGNAL)?;
+    }
+
+    let mut len_buf = [0; 2];
+    recv_exact(&mut tls, &socket, &mut len_buf)?;
+    let mut reply = vec![0; u16::from_be_bytes(len_buf) as usize];
+    recv_exact(&mut tls, &socket, &mut reply)?;
+    let _ = tls.close(&socket);
+    Ok(reply)
+}
+
+fn recv_exact(tls: &mut TlsStream, socket: &HostSocket, buf: &mut [u8]) -> Result<()> {
+    let mut bytes_recvd = 0;
+    while bytes_recvd < buf.len() {
+        let len = tls.recv(socket, &mut [&mut buf[bytes_recvd..]], RecvFlags::empty())?;
+        if len == 0 {
+            return_errno!(
+                ECONNRESET,
+                "the connection is closed by the upstream server"
+            );
+        }
+        bytes_recvd += len;
+    }
+    Ok(())
+}
+
+fn upstream_socket(socket_type: SocketType, ip: Ipv4Addr, port: u16) -> Result<HostSocket> {
+    let socket = HostSocket::new(AddressFamily::INET, socket_type, FileFlags::empty(), 0)?;
+    let timeout = timeval_t::new(UPSTREAM_TIMEOUT.as_secs() as time_t, 0);
+    let timeout = unsafe {
+        std::slice::from_raw_parts(
+            &timeout as *const _ as *const u8,
+            std::mem::size_of::<timeval_t>(),
+        )
+    };
+    socket.setsockopt(SOL_SOCKET, SO_RCVTIMEO, timeout)?;
+    socket.setsockopt(SOL_SOCKET, SO_SNDTIMEO, timeout)?;
+    socket.connect(&Some(sockaddr_of_ipv4(ip, port)))?;
+    Ok(socket)
+}
+
+// The reply must answer the question of the query
+fn check_reply(reply: &[u8], id: &[u8], question: &Question) -> Result<()> {
+    if reply.len() < HEADER_SIZE || &reply[..2] != id || read_u16(reply, 2) & FLAG_QR == 0 {
+        return_errno!(EBADMSG, "the reply does not match the query");
+    }
+    let (reply_question, question_end) = parse_question(reply)?;
+    if &reply_question != question {
+        return_errno!(EBADMSG, "the reply does not match the question");
+    }
+    // Records are walked to make sure that the reply is well-formed
+    resource_records(reply, question_end)?;
+    Ok(())
+}
+
+fn lookup_cache(question: &Question) -> Option<Vec<u8>> {
+    let now = now();
+    let mut cache = CACHE.lock().unwrap();
+    let entry = cache.get(question)?;
+    if entry.expires_at <= now {
+        cache.remove(question);
+        return None;
+    }
+
+    // The TTLs are decreased by the time elapsed since the reply is cached
+    let elapsed = (now - entry.cached_at).as_secs() as u32;
+    let mut reply = entry.reply.clone();
+    let (_, question_end) = parse_question(&reply).ok()?;
+    for (ttl_offset, rtype) in resource_records(&reply, question_end).ok()? {
+        if rtype != TYPE_OPT {
+            let ttl = read_u32(&reply, ttl_offset).saturating_sub(elapsed);
+            write_u32(&mut reply, ttl_offset, ttl);
+        }
+    }
+    Some(reply)
+}
+
+fn insert_cache(question: Question, reply: &[u8], question_end: usize) {
+    let flags = read_u16(reply, 2);
+    let rcode = flags & RCODE_MASK;
+    if flags & FLAG_TC != 0 || (rcode != RCODE_NOERROR && rcode != RCODE_NXDOMAIN) {
+        return;
+    }
+    let ttl = match resource_records(reply, question_end) {
+        Ok(records) => records
+            .iter()
+            .filter(|(_, rtype)| *rtype != TYPE_OPT)
+            .map(|(ttl_offset, _)| read_u32(reply, *ttl_offset))
+            .min()
+            .unwrap_or(NEGATIVE_TTL),
+        Err(_) => return,
+    };
+    if ttl == 0 {
+        return;
+    }
+
+    let now = now();
+    let mut cache = CACHE.lock().unwrap();
+    if cache.len() >= MAX_CACHE_ENTRIES {
+        cache.retain(|_, entry| entry.expires_at > now);
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            let victim = cache.keys().next().unwrap().clone();
+            cache.remove(&victim);
+        }
+    }
+    cache.insert(
+        question,
+        CacheEntry {
+            reply: reply.to_vec(),
+            cached_at: now,
+            expires_at: now + Duration::from_secs(ttl as u64),
+        },
+    );
+}
+
+// The reply of the error with the question of the query
+fn error_reply(query: &[u8], question_end: usize, rcode: u16) -> Vec<u8> {
+    let mut reply = query[..question_end].to_vec();
+    let flags = (read_u16(query, 2) & !RCODE_MASK & !FLAG_TC) | FLAG_QR | FLAG_RA | rcode;
+    write_u16(&mut reply, 2, flags);
+    let qdcount = if question_end > HEADER_SIZE { 1 } else { 0 };
+    write_u16(&mut reply, 4, qdcount);
+    for offset in [6, 8, 10].iter() {
+        write_u16(&mut reply, *offset, 0);
+    }
+    reply
+}
+
+// The max size of the reply to the query, which is given by the OPT record
+// of EDNS
+fn max_reply_size(query: &[u8], question_end: usize) -> usize {
+    resource_records(query, question_end)
+        .ok()
+        .and_then(|records| {
+            records
+                .iter()
+                .find(|(_, rtype)| *rtype == TYPE_OPT)
+                .map(|(ttl_offset, _)| read_u16(query, ttl_offset - 2) as usize)
+        })
+        .map(|size| size.max(MAX_UDP_SIZE))
+        .unwrap_or(MAX_UDP_SIZE)
+}
+
+// Parse the only question of a message, returning the question and the
+// offset of its end
+fn parse_question(msg: &[u8]) -> Result<(Question, usize)> {
+    if msg.len() < HEADER_SIZE || read_u16(msg, 4) != 1 {
+        return_errno!(EBADMSG, "not a message of one question");
+    }
+    let mut name = Vec::new();
+    let mut offset = HEADER_SIZE;
+    loop {
+        let len = *msg
+            .get(offset)
+            .ok_or_else(|| errno!(EBADMSG, "the name is truncated"))? as usize;
+        // The name of the question is never compressed
+        if len & 0xc0 != 0 {
+            return_errno!(EBADMSG, "invalid label of the name");
+        }
+        if offset + 1 + len > msg.len() {
+            return_errno!(EBADMSG, "the name is truncated");
+        }
+        name.extend(
+            msg[offset..offset + 1 + len]
+                .iter()
+                .map(|c| c.to_ascii_lowercase()),
+        );
+        offset += 1 + len;
+        if len == 0 {
+            break;
+        }
+    }
+    if offset + 4 > msg.len() {
+        return_errno!(EBADMSG, "the question is truncated");
+    }
+    let question = Question {
+        name,
+        qtype: read_u16(msg, offset),
+        qclass: read_u16(msg, offset + 2),
+    };
+    Ok((question, offset + 4))
+}
+
+// Walk the records of all the sections after the question, returning the
+// offsets of their TTLs and their types
+fn resource_records(msg: &[u8], question_end: usize) -> Result<Vec<(usize, u16)>> {
+    let nr_records =
+        read_u16(msg, 6) as usize + read_u16(msg, 8) as usize + read_u16(msg, 10) as usize;
+    let mut records = Vec::with_capacity(nr_records);
+    let mut offset = question_end;
+    for _ in 0..nr_records {
+        offset = skip_name(msg, offset)?;
+        if offset + 10 > msg.len() {
+            return_errno!(EBADMSG, "the record is truncated");
+        }
+        let rtype = read_u16(msg, offset);
+        let rdlength = read_u16(msg, offset + 8) as usize;
+        records.push((offset + 4, rtype));
+        offset += 10 + rdlength;
+        if offset > msg.len() {
+            return_errno!(EBADMSG, "the record is truncated");
+        }
+    }
+    Ok(records)
+}
+
+// Skip a name, which may end with a pointer to compress it
+fn skip_name(msg: &[u8], mut offset: usize) -> Result<usize> {
+    loop {
+        let len = *msg
+            .get(offset)
+            .ok_or_else(|| errno!(EBADMSG, "the name is truncated"))? as usize;
+        if len & 0xc0 == 0xc0 {
+            return Ok(offset + 2);
+        }
+        if len & 0xc0 != 0 {
+            return_errno!(EBADMSG, "invalid label of the name");
+        }
+        offset += 1 + len;
+        if len == 0 {
+            return Ok(offset);
+        }
+    }
+}
+
+fn read_u16(msg: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([msg[offset], msg[offset + 1]])
+}
+
+fn write_u16(msg: &mut [u8], offset: usize, val: u16) {
+    msg[offset..offset + 2].copy_from_slice(&val.to_be_bytes());
+}
+
+fn read_u32(msg: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&msg[offset..offset + 4]);
+    u32::from_be_bytes(bytes)
+}
+
+fn write_u32(msg: &mut [u8], offset: usize, val: u32) {
+    msg[offset..offset + 4].copy_from_slice(&val.to_be_bytes());
+}
+
+fn now() -> Duration {
+    let now = do_clock_gettime(ClockID::CLOCK_MONOTONIC).unwrap();
+    Duration::new(now.sec() as u64, now.nsec() as u32)
+}
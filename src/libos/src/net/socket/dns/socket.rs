@@ -0,0 +1,379 @@
+use super::resolver::resolve;
+use super::*;
+use events::{Waiter, WaiterQueue};
+use fs::{IoEvents, IoNotifier};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// The max number of the replies queued in a socket
+const MAX_QUEUED_REPLIES: usize = 64;
+
+/// A UDP or TCP socket that talks to the resolver of LibOS, whose queries
+/// are answered as soon as they are sent.
+///
+/// Over TCP, which is used by the clients to retry the queries whose replies
+/// are truncated over UDP, a message is prefixed by its length, and the
+/// replies are never truncated.
+pub struct DnsSocket {
+    inner: SgxMutex<Inner>,
+    socket_type: SocketType,
+    // The address of the replaced socket
+    addr: SockAddr,
+    waiters: WaiterQueue,
+    notifier: IoNotifier,
+    nonblocking: AtomicBool,
+    ino: usize,
+}
+
+struct Inner {
+    // The replies, which are prefixed by their lengths over TCP
+    replies: VecDeque<Vec<u8>>,
+    // The bytes of the incomplete query sent over TCP
+    stream_buf: Vec<u8>,
+    is_connected: bool,
+    is_read_shutdown: bool,
+    is_write_shutdown: bool,
+    timeouts: SockTimeouts,
+}
+
+impl DnsSocket {
+    pub fn new(addr: SockAddr, socket_type: SocketType, nonblocking: bool) -> Self {
+        let inner = Inner {
+            replies: VecDeque::new(),
+            stream_buf: Vec::new(),
+            is_connected: false,
+            is_read_shutdown: false,
+            is_write_shutdown: false,
+            timeouts: SockTimeouts::default(),
+        };
+        Self {
+            inner: SgxMutex::new(inner),
+            socket_type,
+            addr,
+            waiters: WaiterQueue::new(),
+            notifier: IoNotifier::new(),
+            nonblocking: AtomicBool::new(nonblocking),
+            ino: alloc_socket_ino(),
+        }
+    }
+
+    pub fn ino(&self) -> usize {
+        self.ino
+    }
+
+    pub fn addr(&self) -> SockAddr {
+        self.addr
+    }
+
+    /// Only the resolver can be connected.
+    pub fn connect(&self, addr: &Option<SockAddr>) -> Result<()> {
+        match addr {
+            Some(addr) if is_resolver_addr(addr) => {
+                let mut inner = self.inner();
+                if self.socket_type == SocketType::STREAM && inner.is_connected {
+                    return_errno!(EISCONN, "the socket is already connected");
+                }
+                inner.is_connected = true;
+                Ok(())
+            }
+            _ => return_errno!(EINVAL, "the DNS socket can only talk to the resolver"),
+        }
+    }
+
+    pub fn peer_addr(&self) -> Result<SockAddr> {
+        if !self.inner().is_connected {
+            return_errno!(ENOTCONN, "the socket is not connected");
+        }
+        Ok(resolver_sockaddr())
+    }
+
+    pub fn sendto(&self, buf: &[u8], _flags: SendFlags, addr: &Option<SockAddr>) -> Result<usize> {
+        self.send(&[buf], addr.as_ref())
+    }
+
+    pub fn recvfrom(&self, buf: &mut [u8], flags: RecvFlags) -> Result<(usize, Option<SockAddr>)> {
+        let (bytes_recvd, reply_len) = self.recv(&mut [buf], flags)?;
+        let src = if reply_len > 0 && self.socket_type == SocketType::DGRAM {
+            Some(resolver_sockaddr())
+        } else {
+            None
+        };
+        Ok((recvd_len(bytes_recvd, reply_len, flags), src))
+    }
+
+    pub fn sendmsg(&self, msg: &MsgHdr, _flags: SendFlags) -> Result<usize> {
+        let addr = match msg.get_name() {
+            Some(name) if !name.is_empty() => Some(unsafe {
+                SockAddr::try_from_raw(name.as_ptr() as *const libc::sockaddr, name.len() as u32)?
+            }),
+            _ => None,
+        };
+        self.send(msg.get_iovs().as_slices(), addr.as_ref())
+    }
+
+    pub fn recvmsg(&self, msg: &mut MsgHdrMut, flags: RecvFlags) -> Result<usize> {
+        let (bytes_recvd, reply_len) = self.recv(msg.get_iovs_mut().as_slices_mut(), flags)?;
+        let name_len = match msg.get_name_mut() {
+            Some(name) if reply_len > 0 && self.socket_type == SocketType::DGRAM => {
+                resolver_sockaddr().copy_to_slice(name)
+            }
+            _ => 0,
+        };
+        let mut msg_flags = MsgHdrFlags::empty();
+        if reply_len > bytes_recvd {
+            msg_flags |= MsgHdrFlags::MSG_TRUNC;
+        }
+        msg.set_name_len(name_len)?;
+        msg.set_control_len(0)?;
+        msg.set_flags(msg_flags);
+        Ok(recvd_len(bytes_recvd, reply_len, flags))
+    }
+
+    pub fn shutdown(&self, how: HowToShut) -> Result<()> {
+        let mut inner = self.inner();
+        if !inner.is_connected {
+            return_errno!(ENOTCONN, "the socket is not connected");
+        }
+        if how.to_shut_read() {
+            inner.is_read_shutdown = true;
+        }
+        if how.to_shut_write() {
+            inner.is_write_shutdown = true;
+        }
+        drop(inner);
+        self.waiters.dequeue_and_wake_all();
+        self.notifier.broadcast(&self.poll());
+        Ok(())
+    }
+
+    pub fn getsockopt(&self, level: i32, optname: i32) -> Result<i32> {
+        match (level, optname) {
+            (libc::SOL_SOCKET, libc::SO_TYPE) => Ok(self.socket_type as i32),
+            // The queries are answered at once, so no errors are pending
+            (libc::SOL_SOCKET, libc::SO_ERROR) => Ok(0),
+            _ => return_errno!(ENOPROTOOPT, "the option is not supported"),
+        }
+    }
+
+    /// Answer the queries, whose replies are queued in the socket. A query may
+    /// take a while if it is forwarded to the upstream server.
+    pub(super) fn send(&self, bufs: &[&[u8]], addr: Option<&SockAddr>) -> Result<usize> {
+        let is_connected = self.inner().is_connected;
+        if self.socket_type == SocketType::STREAM {
+            // Same as TCP, the address is ignored
+            if !is_connected {
+                return_errno!(ENOTCONN, "the socket is not connected");
+            }
+        } else {
+            match addr {
+                Some(addr) if !is_resolver_addr(addr) => {
+                    return_errno!(EINVAL, "the DNS socket can only talk to the resolver")
+                }
+                None if !is_connected => return_errno!(EDESTADDRREQ, "no destination address"),
+                _ => {}
+            }
+        }
+        if self.inner().is_write_shutdown {
+            return_errno!(EPIPE, "the socket has been shutdown for writing");
+        }
+
+        let data = bufs.concat();
+        let data_len = data.len();
+        let queries = if self.socket_type == SocketType::STREAM {
+            self.take_stream_queries(data)?
+        } else {
+            vec![data]
+        };
+        for query in queries {
+            if let Some(reply) = resolve(&query, self.socket_type == SocketType::STREAM) {
+                self.queue_reply(reply);
+            }
+        }
+        Ok(data_len)
+    }
+
+    // Append the data sent over TCP to the incomplete query, returning the
+    // queries that are complete
+    fn take_stream_queries(&self, data: Vec<u8>) -> Result<Vec<Vec<u8>>> {
+        let mut inner = self.inner();
+        // Unlike UDP, the replies cannot be dropped, so the sender has to
+        // receive them before sending more queries
+        if inner.replies.len() >= MAX_QUEUED_REPLIES {
+            return_errno!(ENOBUFS, "too many replies are not received");
+        }
+        let stream_buf = &mut inner.stream_buf;
+        stream_buf.extend_from_slice(&data);
+        let mut queries = Vec::new();
+        while stream_buf.len() >= 2 {
+            let query_len = u16::from_be_bytes([stream_buf[0], stream_buf[1]]) as usize;
+            if stream_buf.len() < 2 + query_len {
+                break;
+            }
+            queries.push(stream_buf[2..2 + query_len].to_vec());
+            stream_buf.drain(..2 + query_len);
+        }
+        Ok(queries)
+    }
+
+    fn queue_reply(&self, reply: Vec<u8>) {
+        let mut inner = self.inner();
+        if self.socket_type == SocketType::STREAM {
+            let mut msg = (reply.len() as u16).to_be_bytes().to_vec();
+            msg.extend_from_slice(&reply);
+            inner.replies.push_back(msg);
+        } else if inner.replies.len() < MAX_QUEUED_REPLIES {
+            // Same as UDP, the reply is dropped if the socket is full
+            inner.replies.push_back(reply);
+        }
+        drop(inner);
+        self.waiters.dequeue_and_wake_all();
+        self.notifier.broadcast(&IoEvents::IN);
+    }
+
+    /// Receive a reply into the buffers, returning the number of the bytes
+    /// received and the length of the reply, which is zero if the socket has
+    /// been shutdown for reading. Over TCP, the bytes of the replies are
+    /// received as a stream, and both numbers are the same.
+    pub(super) fn recv(&self, bufs: &mut [&mut [u8]], flags: RecvFlags) -> Result<(usize, usize)> {
+        let nonblocking = self.nonblocking() || flags.contains(RecvFlags::MSG_DONTWAIT);
+        let is_peek = flags.contains(RecvFlags::MSG_PEEK);
+        let mut timeout = self.inner().timeouts.recv;
+        let waiter = Waiter::new();
+        let reply = loop {
+            let mut inner = self.inner();
+            if !inner.replies.is_empty() && self.socket_type == SocketType::STREAM {
+                let bytes_recvd = recv_stream(&mut inner.replies, bufs, is_peek);
+                return Ok((bytes_recvd, bytes_recvd));
+            }
+            let reply = if is_peek {
+                inner.replies.front().cloned()
+            } else {
+                inner.replies.pop_front()
+            };
+            if let Some(reply) = reply {
+                break reply;
+            }
+            if inner.is_read_shutdown {
+                return Ok((0, 0));
+            }
+            if nonblocking {
+                return_errno!(EAGAIN, "no replies are received");
+            }
+
+            // Enqueue before unlocking the socket, so that no wakeup is missed
+            self.waiters.reset_and_enqueue(&waiter);
+            drop(inner);
+            sock_wait(&waiter, timeout.as_mut())?;
+        };
+
+        // Same as UDP, the bytes that do not fit in the buffers are discarded
+        let mut bytes_recvd = 0;
+        for buf in bufs.iter_mut() {
+            let len = buf.len().min(reply.len() - bytes_recvd);
+            buf[..len].copy_from_slice(&reply[bytes_recvd..bytes_recvd + len]);
+            bytes_recvd += len;
+        }
+        Ok((bytes_recvd, reply.len()))
+    }
+
+    pub(super) fn bytes_to_read(&self) -> usize {
+        let inner = self.inner();
+        if self.socket_type == SocketType::STREAM {
+            inner.replies.iter().map(|reply| reply.len()).sum()
+        } else {
+            inner.replies.front().map(|reply| reply.len()).unwrap_or(0)
+        }
+    }
+
+    pub(super) fn poll(&self) -> IoEvents {
+        let inner = self.inner();
+        let mut events = IoEvents::empty();
+        if !inner.replies.is_empty() || inner.is_read_shutdown {
+            events |= IoEvents::IN;
+        }
+        if !inner.is_write_shutdown {
+            events |= IoEvents::OUT;
+        }
+        events
+    }
+
+    pub(super) fn notifier(&self) -> &IoNotifier {
+        &self.notifier
+    }
+
+    pub(super) fn nonblocking(&self) -> bool {
+        self.nonblocking.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn set_nonblocking(&self, nonblocking: bool) {
+        self.nonblocking.store(nonblocking, Ordering::Relaxed);
+    }
+
+    fn inner(&self) -> SgxMutexGuard<'_, Inner> {
+        self.inner.lock().unwrap()
+    }
+}
+
+// Receive the bytes of the replies over TCP into the buffers, which are
+// consumed unless peeked
+fn recv_stream(replies: &mut VecDeque<Vec<u8>>, bufs: &mut [&mut [u8]], is_peek: bool) -> usize {
+    let mut bytes = replies.iter().flatten();
+    let mut bytes_recvd = 0;
+    for buf in bufs.iter_mut() {
+        for (dst, src) in buf.iter_mut().zip(&mut bytes) {
+            *dst = *src;
+            bytes_recvd += 1;
+        }
+    }
+    if is_peek {
+        return bytes_recvd;
+    }
+
+    let mut bytes_to_consume = bytes_recvd;
+    while bytes_to_consume > 0 {
+        let front_len = replies[0].len();
+        if front_len <= bytes_to_consume {
+            replies.pop_front();
+            bytes_to_consume -= front_len;
+        } else {
+            replies[0].drain(..bytes_to_consume);
+            bytes_to_consume = 0;
+        }
+    }
+    bytes_recvd
+}
+
+fn resolver_sockaddr() -> SockAddr {
+    sockaddr_of_ipv4(RESOLVER_ADDR, DNS_PORT)
+}
+
+// Same as UDP, the real length of a truncated reply is returned given
+// MSG_TRUNC
+fn recvd_len(bytes_recvd: usize, reply_len: usize, flags: RecvFlags) -> usize {
+    if flags.contains(RecvFlags::MSG_TRUNC) {
+        reply_len
+    } else {
+        bytes_recvd
+    }
+}
+
+// Sending never blocks, so only the receiving timeout takes effect
+impl SockTimeoutsExt for DnsSocket {
+    fn timeouts(&self) -> SockTimeouts {
+        self.inner().timeouts
+    }
+
+    fn set_timeouts(&self, timeouts: SockTimeouts) {
+        self.inner().timeouts = timeouts;
+    }
+}
+
+impl Debug for DnsSocket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DnsSocket")
+            .field("socket_type", &self.socket_type)
+            .field("addr", &self.addr)
+            .field("nonblocking", &self.nonblocking())
+            .finish()
+    }
+}
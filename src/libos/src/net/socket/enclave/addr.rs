@@ -0,0 +1,38 @@
+use super::*;
+use smoltcp::wire::{IpAddress, IpEndpoint, Ipv4Address};
+
+/// Convert an IPv4 socket address to the endpoint of the stack.
+pub fn endpoint_from_sockaddr(addr: &SockAddr) -> Result<IpEndpoint> {
+    let raw = addr.as_slice();
+    if raw.len() < std::mem::size_of::<libc::sockaddr_in>() {
+        return_errno!(EINVAL, "the address is too short");
+    }
+    let addr_in = unsafe { &*(raw.as_ptr() as *const libc::sockaddr_in) };
+    if addr_in.sin_family != AddressFamily::INET as libc::sa_family_t {
+        return_errno!(EAFNOSUPPORT, "not an IPv4 address");
+    }
+    let ip = Ipv4Address::from_bytes(&addr_in.sin_addr.s_addr.to_ne_bytes());
+    Ok(IpEndpoint::new(
+        IpAddress::Ipv4(ip),
+        u16::from_be(addr_in.sin_port),
+    ))
+}
+
+/// Convert the endpoint of the stack to an IPv4 socket address.
+pub fn endpoint_to_sockaddr(endpoint: &IpEndpoint) -> SockAddr {
+    let ip = match endpoint.addr {
+        IpAddress::Ipv4(ip) => ip,
+        _ => Ipv4Address::UNSPECIFIED,
+    };
+    let mut addr_in: libc::sockaddr_in = unsafe { std::mem::zeroed() };
+    addr_in.sin_family = AddressFamily::INET as libc::sa_family_t;
+    addr_in.sin_port = endpoint.port.to_be();
+    addr_in.sin_addr.s_addr = u32::from_ne_bytes(ip.0);
+    unsafe {
+        SockAddr::try_from_raw(
+            &addr_in as *const _ as *const libc::sockaddr,
+            std::mem::size_of::<libc::sockaddr_in>() as u32,
+        )
+        .unwrap()
+    }
+}
@@ -0,0 +1,113 @@
+//! The TAP devices of the host, over which the stack sends and receives the
+//! Ethernet frames.
+
+use super::*;
+use fs::HostFd;
+use smoltcp::phy::{self, DeviceCapabilities};
+use smoltcp::time::Instant;
+use std::ffi::CString;
+use std::os::raw::c_char;
+
+// The MTU of the interfaces plus the size of the Ethernet header
+const MAX_FRAME_SIZE: usize = 1514;
+
+pub struct TapDevice {
+    host_fd: HostFd,
+}
+
+impl TapDevice {
+    /// Open the TAP device of the host, which is nonblocking.
+    pub fn open(name: &str) -> Result<Self> {
+        let name = CString::new(name).map_err(|_| errno!(EINVAL, "invalid TAP name"))?;
+        let raw_host_fd = try_libc!({
+            let mut retval = 0;
+            let status = occlum_ocall_tap_open(&mut retval, name.as_ptr());
+            assert!(status == sgx_status_t::SGX_SUCCESS);
+            retval
+        }) as FileDesc;
+        Ok(Self {
+            host_fd: HostFd::new(raw_host_fd),
+        })
+    }
+
+    // Receive a frame, or None if there is no frame
+    fn recv_frame(&self) -> Option<Vec<u8>> {
+        let mut frame = vec![0; MAX_FRAME_SIZE];
+        let len = unsafe {
+            libc::ocall::read(
+                self.host_fd.to_raw() as i32,
+                frame.as_mut_ptr() as *mut c_void,
+                frame.len(),
+            )
+        };
+        if len <= 0 {
+            return None;
+        }
+        frame.truncate((len as usize).min(MAX_FRAME_SIZE));
+        Some(frame)
+    }
+
+    fn send_frame(&self, frame: &[u8]) -> Result<()> {
+        try_libc!(libc::ocall::write(
+            self.host_fd.to_raw() as i32,
+            frame.as_ptr() as *const c_void,
+            frame.len()
+        ));
+        Ok(())
+    }
+}
+
+impl<'a> phy::Device<'a> for TapDevice {
+    type RxToken = RxToken;
+    type TxToken = TxToken<'a>;
+
+    fn receive(&'a mut self) -> Option<(Self::RxToken, Self::TxToken)> {
+        let frame = self.recv_frame()?;
+        Some((RxToken(frame), TxToken(self)))
+    }
+
+    fn transmit(&'a mut self) -> Option<Self::TxToken> {
+        Some(TxToken(self))
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut caps = DeviceCapabilities::default();
+        caps.max_transmission_unit = MAX_FRAME_SIZE;
+        caps.max_burst_size = Some(1);
+        caps
+    }
+}
+
+pub struct RxToken(Vec<u8>);
+
+impl phy::RxToken for RxToken {
+    fn consume<R, F>(mut self, _timestamp: Instant, f: F) -> smoltcp::Result<R>
+    where
+        F: FnOnce(&mut [u8]) -> smoltcp::Result<R>,
+    {
+        f(&mut self.0)
+    }
+}
+
+pub struct TxToken<'a>(&'a TapDevice);
+
+impl<'a> phy::TxToken for TxToken<'a> {
+    fn consume<R, F>(self, _timestamp: Instant, len: usize, f: F) -> smoltcp::Result<R>
+    where
+        F: FnOnce(&mut [u8]) -> smoltcp::Result<R>,
+    {
+        let mut frame = vec![0; len];
+        let res = f(&mut frame)?;
+        // The frame is dropped if the host fails to send it, which is
+        // recovered by the retransmission of TCP
+        if let Err(e) = self.0.send_frame(&frame) {
+            debug!("failed to send the frame: {}", e);
+            return Err(smoltcp::Error::Exhausted);
+        }
+        Ok(res)
+    }
+}
+
+extern "C" {
+    fn occlum_ocall_tap_open(retval: *mut i32, name: *const c_char) -> sgx_status_t;
+}
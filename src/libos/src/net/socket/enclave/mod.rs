@@ -0,0 +1,103 @@
+//! Sockets of the in-enclave TCP/IP stack.
+//!
+//! Instead of the sockets of the host, the IPv4 TCP and UDP sockets are
+//! provided by a TCP/IP stack running inside the enclave (smoltcp) when some
+//! interfaces are assigned to the stack in the config. The stack sends and
+//! receives Ethernet frames over the TAP devices of the host, so the payload
+//! never stays in the socket buffers of the host in plaintext.
+//!
+//! A socket is served by the stack unless it is bound to an address that is
+//! not on the interfaces of the stack, e.g., the loopback, or connected to an
+//! address that is not reachable through them, in which case it is diverted
+//! to a socket of the host before it is bound or connected.
+//!
+//! Besides the threads that operate on its sockets, the stack is driven by a
+//! background thread of PAL, which waits for the frames from the TAP devices
+//! and the timers of the stack, and then notifies the sockets whose events
+//! change.
+
+use self::addr::endpoint_from_sockaddr;
+use self::stack::net_stack;
+use super::*;
+use crate::config::LIBOS_CONFIG;
+use fs::File;
+use smoltcp::wire::IpAddress;
+
+mod addr;
+mod device;
+mod file;
+mod socket;
+mod stack;
+
+pub use self::socket::EnclaveSocket;
+pub use self::stack::do_poll_net;
+
+const IPPROTO_TCP: i32 = 6;
+const IPPROTO_UDP: i32 = 17;
+
+/// Whether the IPv4 TCP and UDP sockets are provided by the in-enclave stack.
+pub fn is_enclave_stack_enabled() -> bool {
+    !LIBOS_CONFIG.network.enclave_interfaces.is_empty()
+}
+
+/// Whether the socket bound to the local address, or connected to the remote
+/// address, is served by the stack, i.e., the local address is unspecified or
+/// on one of the interfaces, or the remote address is reachable through them.
+/// Neither the loopback nor the resolver of LibOS is served by the stack.
+pub fn is_enclave_addr(addr: &SockAddr, is_local: bool) -> bool {
+    let ip = match endpoint_from_sockaddr(addr) {
+        Ok(endpoint) => match endpoint.addr {
+            IpAddress::Ipv4(ip) => ip,
+            _ => return true,
+        },
+        // The invalid address is reported by the socket of the stack
+        Err(_) => return true,
+    };
+    if ip.is_unspecified() {
+        return true;
+    }
+    if ip.is_loopback() || is_resolver_addr(addr) {
+        return false;
+    }
+    let stack = match net_stack() {
+        Ok(stack) => stack,
+        Err(_) => return true,
+    };
+    let stack = stack.inner();
+    if is_local {
+        stack.ifaces_of_addr(ip).is_ok()
+    } else {
+        stack.route(ip).is_ok()
+    }
+}
+
+pub fn enclave_socket(
+    socket_type: SocketType,
+    flags: FileFlags,
+    protocol: i32,
+) -> Result<Arc<dyn File>> {
+    match (socket_type, protocol) {
+        (SocketType::STREAM, 0) | (SocketType::STREAM, IPPROTO_TCP) => {
+            Ok(Arc::new(EnclaveSocket::new_tcp(flags)?))
+        }
+        (SocketType::DGRAM, 0) | (SocketType::DGRAM, IPPROTO_UDP) => {
+            Ok(Arc::new(EnclaveSocket::new_udp(flags)?))
+        }
+        (SocketType::STREAM, _) | (SocketType::DGRAM, _) => {
+            return_errno!(EPROTONOSUPPORT, "protocol is not supported")
+        }
+        _ => return_errno!(ESOCKTNOSUPPORT, "the socket type is not supported"),
+    }
+}
+
+pub trait AsEnclaveSocket {
+    fn as_enclave_socket(&self) -> Result<&EnclaveSocket>;
+}
+
+impl AsEnclaveSocket for FileRef {
+    fn as_enclave_socket(&self) -> Result<&EnclaveSocket> {
+        self.as_any()
+            .downcast_ref::<EnclaveSocket>()
+            .ok_or_else(|| errno!(EBADF, "not an enclave socket"))
+    }
+}
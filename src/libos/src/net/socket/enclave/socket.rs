@@ -0,0 +1,1047 @@
+use super::addr::{endpoint_from_sockaddr, endpoint_to_sockaddr};
+use super::stack::{net_stack, Handle, NetStack, Protocol, StackInner, WatchedSocket};
+use super::*;
+use fs::{IoEvents, IoNotifier};
+use smoltcp::socket::{
+    TcpSocket, TcpSocketBuffer, TcpState, UdpPacketMetadata, UdpSocket, UdpSocketBuffer,
+};
+use smoltcp::wire::{IpAddress, IpEndpoint, Ipv4Address};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+const TCP_BUF_SIZE: usize = 64 * 1024;
+const UDP_BUF_SIZE: usize = 64 * 1024;
+const UDP_BUF_PACKETS: usize = 64;
+// The max payload of a UDP datagram that fits in an Ethernet frame, as IP
+// fragmentation is not supported
+const UDP_MAX_PAYLOAD: usize = 1500 - 20 - 8;
+// The listening sockets of the stack on each interface, i.e., the number of
+// the connections that can be pending on a listening socket
+const MAX_BACKLOG: usize = 8;
+// Same as the default of Linux, which is given up after several retries
+const CONNECT_TIMEOUT_MS: u64 = 60 * 1000;
+
+const SOL_SOCKET: i32 = 1;
+const SO_TYPE: i32 = 3;
+const SO_ERROR: i32 = 4;
+
+/// An IPv4 TCP or UDP socket of the in-enclave stack.
+pub struct EnclaveSocket {
+    state: Arc<SocketState>,
+    stack: Arc<NetStack>,
+    nonblocking: AtomicBool,
+    timeouts: SgxMutex<SockTimeouts>,
+    sock_opts: SgxMutex<SockOpts>,
+    ino: usize,
+}
+
+// The state of the socket shared with the stack, which broadcasts the events
+// of the socket in the background
+struct SocketState {
+    inner: SgxMutex<Inner>,
+    notifier: IoNotifier,
+}
+
+enum Inner {
+    Tcp(TcpInner),
+    Udp(UdpInner),
+}
+
+struct TcpInner {
+    // The port owned by the socket, which is shared by the accepted sockets
+    port: Option<u16>,
+    local_addr: Ipv4Address,
+    state: TcpSocketState,
+    // Whether a connection is being established
+    is_connecting: bool,
+    // The failure of the connection, which is reported by SO_ERROR or the
+    // next connect
+    error: Option<Errno>,
+    is_read_shutdown: bool,
+}
+
+enum TcpSocketState {
+    Init,
+    Listening(Vec<Handle>),
+    Connected(Handle),
+}
+
+struct UdpInner {
+    port: Option<u16>,
+    local_addr: Ipv4Address,
+    // The sockets of the stack on the interfaces of the local address
+    handles: Vec<Handle>,
+    peer: Option<IpEndpoint>,
+    is_read_shutdown: bool,
+    is_write_shutdown: bool,
+}
+
+impl EnclaveSocket {
+    pub fn new_tcp(flags: FileFlags) -> Result<Self> {
+        let inner = Inner::Tcp(TcpInner {
+            port: None,
+            local_addr: Ipv4Address::UNSPECIFIED,
+            state: TcpSocketState::Init,
+            is_connecting: false,
+            error: None,
+            is_read_shutdown: false,
+        });
+        Self::new(inner, flags)
+    }
+
+    pub fn new_udp(flags: FileFlags) -> Result<Self> {
+        let inner = Inner::Udp(UdpInner {
+            port: None,
+            local_addr: Ipv4Address::UNSPECIFIED,
+            handles: Vec::new(),
+            peer: None,
+            is_read_shutdown: false,
+            is_write_shutdown: false,
+        });
+        Self::new(inner, flags)
+    }
+
+    fn new(inner: Inner, flags: FileFlags) -> Result<Self> {
+        let socket_type = match inner {
+            Inner::Tcp(_) => SocketType::STREAM,
+            Inner::Udp(_) => SocketType::DGRAM,
+        };
+        let stack = net_stack()?;
+        Ok(Self {
+            state: SocketState::new(inner, &stack),
+            stack,
+            nonblocking: AtomicBool::new(flags.contains(FileFlags::SOCK_NONBLOCK)),
+            timeouts: SgxMutex::new(SockTimeouts::default()),
+            sock_opts: SgxMutex::new(SockOpts::new(AddressFamily::INET, socket_type)),
+            ino: alloc_socket_ino(),
+        })
+    }
+
+    pub fn ino(&self) -> usize {
+        self.ino
+    }
+
+    /// Whether the socket is neither bound nor connected, which can still be
+    /// diverted to a socket of the host.
+    pub fn is_unbound(&self) -> bool {
+        match &*self.inner() {
+            Inner::Tcp(tcp) => tcp.port.is_none(),
+            Inner::Udp(udp) => udp.port.is_none(),
+        }
+    }
+
+    pub fn socket_type(&self) -> SocketType {
+        match *self.inner() {
+            Inner::Tcp(_) => SocketType::STREAM,
+            Inner::Udp(_) => SocketType::DGRAM,
+        }
+    }
+
+    pub fn bind(&self, addr: &SockAddr) -> Result<()> {
+        let endpoint = endpoint_from_sockaddr(addr)?;
+        let local_addr = ipv4_of(&endpoint);
+        let mut stack = self.stack.inner();
+        let mut inner = self.inner();
+        let (protocol, port) = match &mut *inner {
+            Inner::Tcp(tcp) => (Protocol::Tcp, &mut tcp.port),
+            Inner::Udp(udp) => (Protocol::Udp, &mut udp.port),
+        };
+        if port.is_some() {
+            return_errno!(EINVAL, "the socket is already bound");
+        }
+        let ifaces = stack.ifaces_of_addr(local_addr)?;
+        *port = Some(stack.alloc_port(protocol, endpoint.port)?);
+        match &mut *inner {
+            Inner::Tcp(tcp) => tcp.local_addr = local_addr,
+            Inner::Udp(udp) => {
+                udp.local_addr = local_addr;
+                udp.bind(&mut stack, &ifaces)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn listen(&self, _backlog: i32) -> Result<()> {
+        let mut stack = self.stack.inner();
+        let mut inner = self.inner();
+        let tcp = match &mut *inner {
+            Inner::Tcp(tcp) => tcp,
+            Inner::Udp(_) => return_errno!(EOPNOTSUPP, "the socket does not support listen"),
+        };
+        match tcp.state {
+            TcpSocketState::Init => {}
+            TcpSocketState::Listening(_) => return Ok(()),
+            TcpSocketState::Connected(_) => return_errno!(EINVAL, "the socket is connected"),
+        }
+        // Same as Linux, an unbound socket is bound to an ephemeral port
+        let port = match tcp.port {
+            Some(port) => port,
+            None => {
+                let port = stack.alloc_port(Protocol::Tcp, 0)?;
+                tcp.port = Some(port);
+                port
+            }
+        };
+
+        let endpoint = IpEndpoint::new(IpAddress::Ipv4(tcp.local_addr), port);
+        let mut handles = Vec::new();
+        for iface in stack.ifaces_of_addr(tcp.local_addr)? {
+            for _ in 0..MAX_BACKLOG {
+                handles.push(new_listener(&mut stack, iface, endpoint)?);
+            }
+        }
+        tcp.state = TcpSocketState::Listening(handles);
+        Ok(())
+    }
+
+    pub fn connect(&self, addr: &Option<SockAddr>) -> Result<()> {
+        let remote = match addr {
+            Some(addr) => endpoint_from_sockaddr(addr)?,
+            None => return_errno!(EINVAL, "invalid address"),
+        };
+        if remote.port == 0 || ipv4_of(&remote).is_unspecified() {
+            return_errno!(EINVAL, "invalid address");
+        }
+        if self.socket_type() == SocketType::DGRAM {
+            return self.connect_udp(remote);
+        }
+
+        let nonblocking = self.nonblocking();
+        {
+            let mut stack = self.stack.inner();
+            let mut inner = self.inner();
+            let tcp = inner.as_tcp();
+            stack.poll();
+            tcp.update_connecting(&mut stack);
+            // Same as Linux, the failure of the last connection is reported
+            // before a new one is started
+            if let Some(error) = tcp.error.take() {
+                return_errno!(error, "the connection failed");
+            }
+            match tcp.state {
+                TcpSocketState::Init => {
+                    tcp.start_connect(&mut stack, remote)?;
+                    if nonblocking {
+                        return_errno!(EINPROGRESS, "the connection is in progress");
+                    }
+                }
+                TcpSocketState::Listening(_) => return_errno!(EINVAL, "the socket is listening"),
+                TcpSocketState::Connected(_) if !tcp.is_connecting => {
+                    return_errno!(EISCONN, "the socket is connected")
+                }
+                TcpSocketState::Connected(_) if nonblocking => {
+                    return_errno!(EALREADY, "the connection is in progress")
+                }
+                TcpSocketState::Connected(_) => {}
+            }
+        }
+
+        let error = self.stack.poll_until(false, None, |stack| {
+            let mut inner = self.inner();
+            let tcp = inner.as_tcp();
+            tcp.update_connecting(stack);
+            if tcp.is_connecting {
+                Ok(None)
+            } else {
+                Ok(Some(tcp.error.take()))
+            }
+        })?;
+        if let Some(error) = error {
+            return_errno!(error, "the connection failed");
+        }
+        Ok(())
+    }
+
+    fn connect_udp(&self, remote: IpEndpoint) -> Result<()> {
+        self.autobind_udp()?;
+        self.inner().as_udp().peer = Some(remote);
+        Ok(())
+    }
+
+    pub fn accept(&self, flags: FileFlags) -> Result<(Self, Option<SockAddr>)> {
+        let nonblocking = self.nonblocking();
+        let timeout = self.timeouts().recv;
+        let handle = self.stack.poll_until(nonblocking, timeout, |stack| {
+            let mut inner = self.inner();
+            let tcp = match &mut *inner {
+                Inner::Tcp(tcp) => tcp,
+                Inner::Udp(_) => return_errno!(EOPNOTSUPP, "the socket does not support accept"),
+            };
+            let handles = match &mut tcp.state {
+                TcpSocketState::Listening(handles) => handles,
+                _ => return_errno!(EINVAL, "the socket is not listening"),
+            };
+            let idx = match handles
+                .iter()
+                .position(|handle| is_established(&stack.tcp(*handle).state()))
+            {
+                Some(idx) => idx,
+                None => return Ok(None),
+            };
+            // Replace the connected socket of the stack with a new listener
+            let handle = handles[idx];
+            let endpoint = IpEndpoint::new(IpAddress::Ipv4(tcp.local_addr), tcp.port.unwrap());
+            handles[idx] = new_listener(stack, handle.iface, endpoint)?;
+            Ok(Some(handle))
+        })?;
+
+        let mut stack = self.stack.inner();
+        let socket = stack.tcp(handle);
+        let local_addr = ipv4_of(&socket.local_endpoint());
+        let remote = socket.remote_endpoint();
+        drop(socket);
+        drop(stack);
+
+        let inner = Inner::Tcp(TcpInner {
+            port: None,
+            local_addr,
+            state: TcpSocketState::Connected(handle),
+            is_connecting: false,
+            error: None,
+            is_read_shutdown: false,
+        });
+        let new_socket = Self {
+            state: SocketState::new(inner, &self.stack),
+            stack: self.stack.clone(),
+            nonblocking: AtomicBool::new(flags.contains(FileFlags::SOCK_NONBLOCK)),
+            // Same as Linux, the accepted socket inherits the timeouts and the options
+            timeouts: SgxMutex::new(self.timeouts()),
+            sock_opts: SgxMutex::new(self.sock_opts.lock().unwrap().clone()),
+            ino: alloc_socket_ino(),
+        };
+        Ok((new_socket, Some(endpoint_to_sockaddr(&remote))))
+    }
+
+    pub fn addr(&self) -> SockAddr {
+        let mut stack = self.stack.inner();
+        let inner = self.inner();
+        let endpoint = match &*inner {
+            // The accepted sockets share the port of the listening socket
+            Inner::Tcp(TcpInner {
+                state: TcpSocketState::Connected(handle),
+                ..
+            }) => stack.tcp(*handle).local_endpoint(),
+            Inner::Tcp(tcp) => {
+                IpEndpoint::new(IpAddress::Ipv4(tcp.local_addr), tcp.port.unwrap_or(0))
+            }
+            Inner::Udp(udp) => {
+                // A socket on all the interfaces uses the address of the
+                // interface of its peer
+                let local_addr = match udp.peer {
+                    Some(peer) if udp.local_addr.is_unspecified() => stack
+                        .route(ipv4_of(&peer))
+                        .map(|(_, addr)| addr)
+                        .unwrap_or(udp.local_addr),
+                    _ => udp.local_addr,
+                };
+                IpEndpoint::new(IpAddress::Ipv4(local_addr), udp.port.unwrap_or(0))
+            }
+        };
+        endpoint_to_sockaddr(&endpoint)
+    }
+
+    pub fn peer_addr(&self) -> Result<SockAddr> {
+        let mut stack = self.stack.inner();
+        let mut inner = self.inner();
+        if let Inner::Tcp(tcp) = &mut *inner {
+            tcp.update_connecting(&mut stack);
+        }
+        let peer = match &mut *inner {
+            Inner::Tcp(tcp) => match tcp.state {
+                TcpSocketState::Connected(handle) if !tcp.is_connecting => {
+                    stack.tcp(handle).remote_endpoint()
+                }
+                _ => return_errno!(ENOTCONN, "the socket is not connected"),
+            },
+            Inner::Udp(udp) => udp
+                .peer
+                .ok_or_else(|| errno!(ENOTCONN, "the socket is not connected"))?,
+        };
+        Ok(endpoint_to_sockaddr(&peer))
+    }
+
+    pub fn sendto(&self, buf: &[u8], flags: SendFlags, addr: &Option<SockAddr>) -> Result<usize> {
+        let addr = match addr {
+            Some(addr) => Some(endpoint_from_sockaddr(addr)?),
+            None => None,
+        };
+        self.send(&[buf], flags, addr)
+    }
+
+    pub fn recvfrom(&self, buf: &mut [u8], flags: RecvFlags) -> Result<(usize, Option<SockAddr>)> {
+        let (bytes_recvd, datagram_len, src) = self.recv(&mut [buf], flags)?;
+        Ok((
+            recvd_len(bytes_recvd, datagram_len, flags),
+            src.map(|src| endpoint_to_sockaddr(&src)),
+        ))
+    }
+
+    pub fn sendmsg(&self, msg: &MsgHdr, flags: SendFlags) -> Result<usize> {
+        let addr = match msg.get_name() {
+            Some(name) if !name.is_empty() => Some(endpoint_from_sockaddr(&unsafe {
+                SockAddr::try_from_raw(name.as_ptr() as *const libc::sockaddr, name.len() as u32)?
+            })?),
+            _ => None,
+        };
+        self.send(msg.get_iovs().as_slices(), flags, addr)
+    }
+
+    pub fn recvmsg(&self, msg: &mut MsgHdrMut, flags: RecvFlags) -> Result<usize> {
+        let (bytes_recvd, datagram_len, src) =
+            self.recv(msg.get_iovs_mut().as_slices_mut(), flags)?;
+        let name_len = match (msg.get_name_mut(), src) {
+            (Some(name), Some(src)) => endpoint_to_sockaddr(&src).copy_to_slice(name),
+            _ => 0,
+        };
+        let mut msg_flags = MsgHdrFlags::empty();
+        if datagram_len > bytes_recvd {
+            msg_flags |= MsgHdrFlags::MSG_TRUNC;
+        }
+        msg.set_name_len(name_len)?;
+        msg.set_control_len(0)?;
+        msg.set_flags(msg_flags);
+        Ok(recvd_len(bytes_recvd, datagram_len, flags))
+    }
+
+    pub fn shutdown(&self, how: HowToShut) -> Result<()> {
+        let mut stack = self.stack.inner();
+        let mut inner = self.inner();
+        match &mut *inner {
+            Inner::Tcp(tcp) => {
+                let handle = match tcp.state {
+                    TcpSocketState::Connected(handle) => handle,
+                    _ => return_errno!(ENOTCONN, "the socket is not connected"),
+                };
+                if how.to_shut_read() {
+                    tcp.is_read_shutdown = true;
+                }
+                if how.to_shut_write() {
+                    // Send a FIN
+                    stack.tcp(handle).close();
+                    stack.poll();
+                }
+            }
+            Inner::Udp(udp) => {
+                if udp.peer.is_none() {
+                    return_errno!(ENOTCONN, "the socket is not connected");
+                }
+                if how.to_shut_read() {
+                    udp.is_read_shutdown = true;
+                }
+                if how.to_shut_write() {
+                    udp.is_write_shutdown = true;
+                }
+            }
+        }
+        // Wake up the threads blocked on the socket
+        drop(inner);
+        self.stack.notify(stack);
+        Ok(())
+    }
+
+    pub fn getsockopt(&self, level: i32, optname: i32) -> Result<i32> {
+        match (level, optname) {
+            (SOL_SOCKET, SO_TYPE) => Ok(self.socket_type() as i32),
+            (SOL_SOCKET, SO_ERROR) => {
+                let mut stack = self.stack.inner();
+                stack.poll();
+                let mut inner = self.inner();
+                if let Inner::Tcp(tcp) = &mut *inner {
+                    tcp.update_connecting(&mut stack);
+                    // Same as Linux, the error is cleared once it is got
+                    if let Some(error) = tcp.error.take() {
+                        return Ok(error as i32);
+                    }
+                }
+                Ok(0)
+            }
+            _ => return_errno!(ENOPROTOOPT, "the option is not supported"),
+        }
+    }
+
+    pub(super) fn send(
+        &self,
+        bufs: &[&[u8]],
+        flags: SendFlags,
+        addr: Option<IpEndpoint>,
+    ) -> Result<usize> {
+        let nonblocking = self.nonblocking() || flags.contains(SendFlags::MSG_DONTWAIT);
+        let buf = bufs.concat();
+        let res = match self.socket_type() {
+            SocketType::STREAM => self.send_tcp(&buf, nonblocking),
+            _ => self.send_udp(&buf, nonblocking, addr),
+        };
+        flags.check_epipe(res)
+    }
+
+    fn send_tcp(&self, buf: &[u8], nonblocking: bool) -> Result<usize> {
+        let handle = self.connected_handle()?;
+        let timeout = self.timeouts().send;
+        self.stack.poll_until(nonblocking, timeout, |stack| {
+            let mut socket = stack.tcp(handle);
+            match socket.state() {
+                TcpState::SynSent | TcpState::SynReceived => return Ok(None),
+                _ if !socket.may_send() => {
+                    return_errno!(EPIPE, "the socket has been shutdown for writing")
+                }
+                _ => {}
+            }
+            if buf.is_empty() {
+                return Ok(Some(0));
+            }
+            match socket.send_slice(buf) {
+                Ok(0) => Ok(None),
+                Ok(len) => Ok(Some(len)),
+                Err(_) => return_errno!(EPIPE, "the connection is closed"),
+            }
+        })
+    }
+
+    fn send_udp(&self, buf: &[u8], nonblocking: bool, addr: Option<IpEndpoint>) -> Result<usize> {
+        if buf.len() > UDP_MAX_PAYLOAD {
+            return_errno!(EMSGSIZE, "the datagram is too large");
+        }
+        let remote = {
+            let mut inner = self.inner();
+            let udp = inner.as_udp();
+            if udp.is_write_shutdown {
+                return_errno!(EPIPE, "the socket has been shutdown for writing");
+            }
+            match addr.or(udp.peer) {
+                Some(remote) => remote,
+                None => return_errno!(EDESTADDRREQ, "no destination address"),
+            }
+        };
+        if remote.port == 0 {
+            return_errno!(EINVAL, "invalid port");
+        }
+        self.autobind_udp()?;
+
+        let handle = {
+            let stack = self.stack.inner();
+            let mut inner = self.inner();
+            let udp = inner.as_udp();
+            let (iface, _) = stack.route(ipv4_of(&remote))?;
+            *udp.handles
+                .iter()
+                .find(|handle| handle.iface == iface)
+                .ok_or_else(|| errno!(ENETUNREACH, "the network is unreachable"))?
+        };
+        let timeout = self.timeouts().send;
+        self.stack.poll_until(nonblocking, timeout, |stack| {
+            let mut socket = stack.udp(handle);
+            if !socket.can_send() {
+                return Ok(None);
+            }
+            match socket.send_slice(buf, remote) {
+                Ok(()) => Ok(Some(buf.len())),
+                Err(smoltcp::Error::Exhausted) => Ok(None),
+                Err(_) => return_errno!(EINVAL, "failed to send the datagram"),
+            }
+        })
+    }
+
+    /// Receive into the buffers, returning the number of the bytes received,
+    /// the length of the datagram, which is the same as the former for TCP,
+    /// and the source of the datagram
+    pub(super) fn recv(
+        &self,
+        bufs: &mut [&mut [u8]],
+        flags: RecvFlags,
+    ) -> Result<(usize, usize, Option<IpEndpoint>)> {
+        let nonblocking = self.nonblocking() || flags.contains(RecvFlags::MSG_DONTWAIT);
+        let is_peek = flags.contains(RecvFlags::MSG_PEEK);
+        let timeout = self.timeouts().recv;
+        let capacity = bufs.iter().map(|buf| buf.len()).sum();
+        let (data, datagram_len, src) = match self.socket_type() {
+            SocketType::STREAM => {
+                let handle = self.connected_handle()?;
+                // Same as Linux, the data is received until the buffers are
+                // full given MSG_WAITALL, unless it reaches the end or an error
+                let waitall = flags.contains(RecvFlags::MSG_WAITALL) && !is_peek;
+                let mut data = Vec::with_capacity(capacity);
+                let mut src = None;
+                let res = self.stack.poll_until(nonblocking, timeout, |stack| {
+                    let mut socket = stack.tcp(handle);
+                    while socket.can_recv() && data.len() < capacity {
+                        let offset = data.len();
+                        data.resize(capacity, 0);
+                        let len = if is_peek {
+                            socket.peek_slice(&mut data[offset..])
+                        } else {
+                            socket.recv_slice(&mut data[offset..])
+                        }
+                        .map_err(|_| errno!(ECONNRESET, "the connection is reset"))?;
+                        data.truncate(offset + len);
+                        src = Some(socket.remote_endpoint());
+                        if !waitall || len == 0 {
+                            break;
+                        }
+                    }
+                    let is_done = match data.len() {
+                        0 => capacity == 0 && socket.can_recv(),
+                        len => !waitall || len == capacity,
+                    };
+                    if is_done {
+                        return Ok(Some(()));
+                    }
+                    // Same as Linux, the data received before the socket is
+                    // shutdown for reading can still be received
+                    if self.inner().as_tcp().is_read_shutdown {
+                        return Ok(Some(()));
+                    }
+                    match socket.state() {
+                        TcpState::SynSent | TcpState::SynReceived => Ok(None),
+                        // Reach the end of the stream
+                        _ if !socket.may_recv() => Ok(Some(())),
+                        _ => Ok(None),
+                    }
+                });
+                match res {
+                    Ok(()) => {}
+                    // The data received so far is returned on errors, e.g.,
+                    // on timeout
+                    Err(_) if !data.is_empty() => {}
+                    Err(e) => return Err(e),
+                }
+                let len = data.len();
+                (data, len, src)
+            }
+            _ => {
+                self.autobind_udp()?;
+                let (data, src) = self.stack.poll_until(nonblocking, timeout, |stack| {
+                    let mut inner = self.inner();
+                    let udp = inner.as_udp();
+                    if udp.is_read_shutdown {
+                        return Ok(Some((Vec::new(), None)));
+                    }
+                    for handle in udp.handles.iter() {
+                        let mut socket = stack.udp(*handle);
+                        while socket.can_recv() {
+                            let (data, src) = if is_peek {
+                                let (data, src) = socket.peek().unwrap();
+                                (data.to_vec(), *src)
+                            } else {
+                                let (data, src) = socket.recv().unwrap();
+                                (data.to_vec(), src)
+                            };
+                            // Same as Linux, the datagrams not from the peer
+                            // of a connected socket are dropped
+                            match udp.peer {
+                                Some(peer) if peer != src && !is_peek => continue,
+                                Some(peer) if peer != src => {
+                                    socket.recv().unwrap();
+                                    continue;
+                                }
+                                _ => return Ok(Some((data, Some(src)))),
+                            }
+                        }
+                    }
+                    Ok(None)
+                })?;
+                let len = data.len();
+                (data, len, src)
+            }
+        };
+
+        // Same as Linux, the data received by TCP is discarded given MSG_TRUNC
+        if self.socket_type() == SocketType::STREAM && flags.contains(RecvFlags::MSG_TRUNC) {
+            return Ok((datagram_len, datagram_len, src));
+        }
+        // Same as Linux, the bytes of a datagram that do not fit in the
+        // buffers are discarded
+        let mut bytes_recvd = 0;
+        for buf in bufs.iter_mut() {
+            let len = buf.len().min(data.len() - bytes_recvd);
+            buf[..len].copy_from_slice(&data[bytes_recvd..bytes_recvd + len]);
+            bytes_recvd += len;
+        }
+        Ok((bytes_recvd, datagram_len, src))
+    }
+
+    // Same as Linux, an unbound UDP socket is bound to an ephemeral port on
+    // sending or receiving
+    fn autobind_udp(&self) -> Result<()> {
+        let mut stack = self.stack.inner();
+        let mut inner = self.inner();
+        let udp = inner.as_udp();
+        if udp.port.is_some() {
+            return Ok(());
+        }
+        let ifaces = stack.ifaces_of_addr(Ipv4Address::UNSPECIFIED)?;
+        udp.port = Some(stack.alloc_port(Protocol::Udp, 0)?);
+        udp.bind(&mut stack, &ifaces)
+    }
+
+    fn connected_handle(&self) -> Result<Handle> {
+        self.inner().as_tcp().connected_handle()
+    }
+
+    pub(super) fn bytes_to_read(&self) -> usize {
+        let mut stack = self.stack.inner();
+        stack.poll();
+        let mut inner = self.inner();
+        match &mut *inner {
+            Inner::Tcp(tcp) => match tcp.state {
+                TcpSocketState::Connected(handle) => stack.tcp(handle).recv_queue(),
+                _ => 0,
+            },
+            Inner::Udp(udp) => udp
+                .handles
+                .iter()
+                .find_map(|handle| stack.udp(*handle).peek().ok().map(|(data, _)| data.len()))
+                .unwrap_or(0),
+        }
+    }
+
+    pub(super) fn poll(&self) -> IoEvents {
+        let mut stack = self.stack.inner();
+        stack.poll();
+        self.state.poll_events(&mut stack)
+    }
+
+    pub(super) fn notifier(&self) -> &IoNotifier {
+        &self.state.notifier
+    }
+
+    pub fn nonblocking(&self) -> bool {
+        self.nonblocking.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn set_nonblocking(&self, nonblocking: bool) {
+        self.nonblocking.store(nonblocking, Ordering::Relaxed);
+    }
+
+    fn inner(&self) -> SgxMutexGuard<'_, Inner> {
+        self.state.inner.lock().unwrap()
+    }
+
+    // Close the connection with SO_LINGER on, which waits until the data sent
+    // are acknowledged or the timeout expires, or resets the connection at
+    // once if the timeout is zero.
+    fn close_with_linger(&self, handle: Handle, timeout: Duration) {
+        if timeout == Duration::from_secs(0) {
+            self.stack.inner().tcp(handle).abort();
+            return;
+        }
+        self.stack.inner().tcp(handle).close();
+        // The FIN is acknowledged after all the data sent
+        let _ = self.stack.poll_until(false, Some(timeout), |stack| {
+            match stack.tcp(handle).state() {
+                TcpState::FinWait1 | TcpState::Closing | TcpState::LastAck => Ok(None),
+                _ => Ok(Some(())),
+            }
+        });
+    }
+}
+
+impl SockOptsExt for EnclaveSocket {
+    fn sock_opts(&self) -> &SgxMutex<SockOpts> {
+        &self.sock_opts
+    }
+}
+
+impl SockTimeoutsExt for EnclaveSocket {
+    fn timeouts(&self) -> SockTimeouts {
+        *self.timeouts.lock().unwrap()
+    }
+
+    fn set_timeouts(&self, timeouts: SockTimeouts) {
+        *self.timeouts.lock().unwrap() = timeouts;
+    }
+}
+
+impl SocketState {
+    fn new(inner: Inner, stack: &NetStack) -> Arc<Self> {
+        let state = Arc::new(Self {
+            inner: SgxMutex::new(inner),
+            notifier: IoNotifier::new(),
+        });
+        let watched: Arc<dyn WatchedSocket> = state.clone();
+        stack.inner().watch(Arc::downgrade(&watched));
+        state
+    }
+}
+
+impl WatchedSocket for SocketState {
+    fn poll_events(&self, stack: &mut StackInner) -> IoEvents {
+        self.inner.lock().unwrap().poll_events(stack)
+    }
+
+    fn notifier(&self) -> &IoNotifier {
+        &self.notifier
+    }
+}
+
+impl Inner {
+    fn poll_events(&mut self, stack: &mut StackInner) -> IoEvents {
+        let mut events = IoEvents::empty();
+        if let Inner::Tcp(tcp) = self {
+            tcp.update_connecting(stack);
+        }
+        match self {
+            Inner::Tcp(tcp) => match &tcp.state {
+                // Same as Linux, a socket that is not connected is hung up,
+                // whose failed connection is reported as an error
+                TcpSocketState::Init if tcp.error.is_some() => {
+                    events |= IoEvents::OUT | IoEvents::HUP | IoEvents::ERR
+                }
+                TcpSocketState::Init => events |= IoEvents::OUT | IoEvents::HUP,
+                TcpSocketState::Listening(handles) => {
+                    if handles
+                        .iter()
+                        .any(|handle| is_established(&stack.tcp(*handle).state()))
+                    {
+                        events |= IoEvents::IN;
+                    }
+                }
+                TcpSocketState::Connected(handle) => {
+                    let socket = stack.tcp(*handle);
+                    match socket.state() {
+                        TcpState::SynSent | TcpState::SynReceived => {}
+                        _ => {
+                            if socket.can_recv() || !socket.may_recv() || tcp.is_read_shutdown {
+                                events |= IoEvents::IN;
+                            }
+                            if !socket.may_recv() {
+                                events |= IoEvents::RDHUP;
+                            }
+                            if socket.can_send() {
+                                events |= IoEvents::OUT;
+                            }
+                            if !socket.is_open() {
+                                events |= IoEvents::HUP;
+                            }
+                        }
+                    }
+                }
+            },
+            Inner::Udp(udp) => {
+                if udp.is_read_shutdown
+                    || udp
+                        .handles
+                        .iter()
+                        .any(|handle| stack.udp(*handle).can_recv())
+                {
+                    events |= IoEvents::IN;
+                }
+                if !udp.is_write_shutdown {
+                    events |= IoEvents::OUT;
+                }
+            }
+        }
+        events
+    }
+
+    fn as_tcp(&mut self) -> &mut TcpInner {
+        match self {
+            Inner::Tcp(tcp) => tcp,
+            Inner::Udp(_) => unreachable!(),
+        }
+    }
+
+    fn as_udp(&mut self) -> &mut UdpInner {
+        match self {
+            Inner::Udp(udp) => udp,
+            Inner::Tcp(_) => unreachable!(),
+        }
+    }
+}
+
+impl TcpInner {
+    // Start to connect to the remote endpoint on the interface to reach it
+    fn start_connect(&mut self, stack: &mut StackInner, remote: IpEndpoint) -> Result<()> {
+        let (iface, local_addr) = if self.local_addr.is_unspecified() {
+            stack.route(ipv4_of(&remote))?
+        } else {
+            (stack.ifaces_of_addr(self.local_addr)?[0], self.local_addr)
+        };
+        let port = match self.port {
+            Some(port) => port,
+            None => {
+                let port = stack.alloc_port(Protocol::Tcp, 0)?;
+                self.port = Some(port);
+                port
+            }
+        };
+
+        let handle = stack.add_socket(iface, new_tcp_socket());
+        let mut socket = stack.tcp(handle);
+        socket.set_timeout(Some(smoltcp::time::Duration::from_millis(
+            CONNECT_TIMEOUT_MS,
+        )));
+        let result = socket.connect(remote, (IpAddress::Ipv4(local_addr), port));
+        drop(socket);
+        if result.is_err() {
+            stack.release_socket(handle);
+            return_errno!(EINVAL, "invalid address");
+        }
+        self.local_addr = local_addr;
+        self.state = TcpSocketState::Connected(handle);
+        self.is_connecting = true;
+        // Send the SYN at once
+        stack.poll();
+        Ok(())
+    }
+
+    // Check whether the connection being established succeeds or fails. The
+    // socket can connect again after the failure, which is kept as the error.
+    fn update_connecting(&mut self, stack: &mut StackInner) {
+        let handle = match self.state {
+            TcpSocketState::Connected(handle) if self.is_connecting => handle,
+            _ => return,
+        };
+        match stack.tcp(handle).state() {
+            TcpState::SynSent | TcpState::SynReceived => {}
+            TcpState::Closed => {
+                stack.release_socket(handle);
+                self.state = TcpSocketState::Init;
+                self.is_connecting = false;
+                self.error = Some(ECONNREFUSED);
+            }
+            _ => {
+                self.is_connecting = false;
+                // No timeout after the connection is established
+                stack.tcp(handle).set_timeout(None);
+            }
+        }
+    }
+
+    fn connected_handle(&self) -> Result<Handle> {
+        match self.state {
+            TcpSocketState::Connected(handle) => Ok(handle),
+            _ => return_errno!(ENOTCONN, "the socket is not connected"),
+        }
+    }
+}
+
+impl UdpInner {
+    // Create the sockets of the stack on the interfaces, given the port
+    fn bind(&mut self, stack: &mut StackInner, ifaces: &[usize]) -> Result<()> {
+        let endpoint = IpEndpoint::new(IpAddress::Ipv4(self.local_addr), self.port.unwrap());
+        for iface in ifaces {
+            let handle = stack.add_socket(*iface, new_udp_socket());
+            self.handles.push(handle);
+            if stack.udp(handle).bind(endpoint).is_err() {
+                return_errno!(EINVAL, "invalid address");
+            }
+        }
+        Ok(())
+    }
+}
+
+fn new_tcp_socket() -> TcpSocket<'static> {
+    TcpSocket::new(
+        TcpSocketBuffer::new(vec![0; TCP_BUF_SIZE]),
+        TcpSocketBuffer::new(vec![0; TCP_BUF_SIZE]),
+    )
+}
+
+fn new_udp_socket() -> UdpSocket<'static, 'static> {
+    let new_buffer = || {
+        UdpSocketBuffer::new(
+            vec![UdpPacketMetadata::EMPTY; UDP_BUF_PACKETS],
+            vec![0; UDP_BUF_SIZE],
+        )
+    };
+    UdpSocket::new(new_buffer(), new_buffer())
+}
+
+fn new_listener(stack: &mut StackInner, iface: usize, endpoint: IpEndpoint) -> Result<Handle> {
+    let handle = stack.add_socket(iface, new_tcp_socket());
+    if stack.tcp(handle).listen(endpoint).is_err() {
+        stack.release_socket(handle);
+        return_errno!(EINVAL, "invalid address");
+    }
+    Ok(handle)
+}
+
+// Whether a connection is established, including the ones closed by the peer
+fn is_established(state: &TcpState) -> bool {
+    match state {
+        TcpState::Closed | TcpState::Listen | TcpState::SynSent | TcpState::SynReceived => false,
+        _ => true,
+    }
+}
+
+fn ipv4_of(endpoint: &IpEndpoint) -> Ipv4Address {
+    match endpoint.addr {
+        IpAddress::Ipv4(addr) => addr,
+        _ => Ipv4Address::UNSPECIFIED,
+    }
+}
+
+// Same as Linux, the real length of a truncated datagram is returned given
+// MSG_TRUNC
+fn recvd_len(bytes_recvd: usize, datagram_len: usize, flags: RecvFlags) -> usize {
+    if flags.contains(RecvFlags::MSG_TRUNC) {
+        datagram_len
+    } else {
+        bytes_recvd
+    }
+}
+
+impl Debug for EnclaveSocket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EnclaveSocket")
+            .field("type", &self.socket_type())
+            .field("nonblocking", &self.nonblocking())
+            .finish()
+    }
+}
+
+impl Drop for EnclaveSocket {
+    fn drop(&mut self) {
+        // Same as Linux, the connection is closed gracefully in the background
+        // unless SO_LINGER is on
+        let linger = self.sock_opts.get_mut().unwrap().linger();
+        let connected_handle = match &*self.inner() {
+            Inner::Tcp(TcpInner {
+                state: TcpSocketState::Connected(handle),
+                ..
+            }) => Some(*handle),
+            _ => None,
+        };
+        match connected_handle {
+            Some(handle) if linger.l_onoff != 0 => {
+                self.close_with_linger(handle, Duration::from_secs(linger.l_linger as u64))
+            }
+            _ => {}
+        }
+
+        let mut stack = self.stack.inner();
+        let inner = self.inner();
+        match &*inner {
+            Inner::Tcp(tcp) => {
+                match &tcp.state {
+                    TcpSocketState::Init => {}
+                    TcpSocketState::Listening(handles) => {
+                        for handle in handles {
+                            stack.tcp(*handle).abort();
+                            stack.release_socket(*handle);
+                        }
+                    }
+                    TcpSocketState::Connected(handle) => {
+                        // The connection is closed gracefully by the stack
+                        stack.tcp(*handle).close();
+                        stack.release_socket(*handle);
+                    }
+                }
+                if let Some(port) = tcp.port {
+                    stack.free_port(Protocol::Tcp, port);
+                }
+            }
+            Inner::Udp(udp) => {
+                for handle in udp.handles.iter() {
+                    stack.udp(*handle).close();
+                    stack.release_socket(*handle);
+                }
+                if let Some(port) = udp.port {
+                    stack.free_port(Protocol::Udp, port);
+                }
+            }
+        }
+        stack.poll();
+    }
+}
@@ -0,0 +1,381 @@
+//! The TCP/IP stack, which has an interface on each TAP device in the config.
+
+use super::device::TapDevice;
+use super::*;
+use crate::config::ConfigEnclaveInterface;
+use crate::time::{do_clock_gettime, ClockID};
+use events::{Waiter, WaiterQueue};
+use fs::{IoEvents, IoNotifier};
+use smoltcp::iface::{EthernetInterface, EthernetInterfaceBuilder, NeighborCache, Routes};
+use smoltcp::socket::{Socket, SocketHandle, SocketRef, SocketSet, TcpSocket, UdpSocket};
+use smoltcp::time::Instant;
+use smoltcp::wire::{EthernetAddress, IpAddress, IpCidr, Ipv4Address};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Weak;
+use std::time::Duration;
+
+// The max time that the background thread waits for the frames before the
+// stack is polled again, which bounds the delay of the timers armed by the
+// sockets after the thread starts to wait
+const MAX_POLL_DELAY: Duration = Duration::from_millis(100);
+// The max rounds to poll an interface, each of which may be interrupted by
+// an invalid frame
+const MAX_POLL_ROUNDS: usize = 64;
+// Same as Linux, the ports allocated to the sockets not bound to any port
+const EPHEMERAL_PORT_MIN: u16 = 32768;
+const EPHEMERAL_PORT_MAX: u16 = 60999;
+
+lazy_static! {
+    static ref NET_STACK: SgxMutex<Option<Arc<NetStack>>> = SgxMutex::new(None);
+}
+
+/// Get the stack, which is created on its first use.
+pub fn net_stack() -> Result<Arc<NetStack>> {
+    let mut net_stack = NET_STACK.lock().unwrap();
+    if net_stack.is_none() {
+        *net_stack = Some(Arc::new(NetStack::new()?));
+    }
+    Ok(net_stack.as_ref().unwrap().clone())
+}
+
+/// Drive the stack in the background, which is called repeatedly by a thread
+/// of PAL through an ECall. The received frames and the expired timers, e.g.,
+/// of retransmission, are processed, and the sockets whose events change are
+/// notified. Return how long the thread waits for the frames from the TAP
+/// devices before the next call.
+pub fn do_poll_net() -> Result<Duration> {
+    let net_stack = match NET_STACK.lock().unwrap().as_ref() {
+        Some(net_stack) => net_stack.clone(),
+        None => return_errno!(ENOENT, "the in-enclave stack is not running"),
+    };
+    let mut inner = net_stack.inner();
+    inner.poll();
+    let delay = inner
+        .poll_delay()
+        .map_or(MAX_POLL_DELAY, |delay| delay.min(MAX_POLL_DELAY));
+    net_stack.notify(inner);
+    Ok(delay)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
+/// A socket of the stack, which belongs to one of the interfaces.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Handle {
+    pub iface: usize,
+    pub socket: SocketHandle,
+}
+
+/// A socket whose events are broadcast to its notifier by the stack once they
+/// change.
+pub trait WatchedSocket: Send + Sync {
+    fn poll_events(&self, stack: &mut StackInner) -> IoEvents;
+    fn notifier(&self) -> &IoNotifier;
+}
+
+/// The stack, which must be locked before any socket of it.
+pub struct NetStack {
+    inner: SgxMutex<StackInner>,
+    // The threads blocked on the sockets of the stack
+    waiters: WaiterQueue,
+}
+
+pub struct StackInner {
+    ifaces: Vec<Interface>,
+    used_ports: HashSet<(Protocol, u16)>,
+    next_port: u16,
+    // The sockets with the events last broadcast
+    watches: Vec<(Weak<dyn WatchedSocket>, IoEvents)>,
+    // Whether any packet is processed or sent since the last notification
+    is_active: bool,
+}
+
+struct Interface {
+    iface: EthernetInterface<'static, 'static, 'static, TapDevice>,
+    sockets: SocketSet<'static, 'static, 'static>,
+    has_gateway: bool,
+}
+
+impl NetStack {
+    fn new() -> Result<Self> {
+        let ifaces = LIBOS_CONFIG
+            .network
+            .enclave_interfaces
+            .iter()
+            .map(|config| Interface::new(config))
+            .collect::<Result<Vec<_>>>()?;
+        let inner = StackInner {
+            ifaces,
+            used_ports: HashSet::new(),
+            next_port: EPHEMERAL_PORT_MIN,
+            watches: Vec::new(),
+            is_active: false,
+        };
+        Ok(Self {
+            inner: SgxMutex::new(inner),
+            waiters: WaiterQueue::new(),
+        })
+    }
+
+    pub fn inner(&self) -> SgxMutexGuard<'_, StackInner> {
+        self.inner.lock().unwrap()
+    }
+
+    /// Poll the stack until `cond` returns a value, or return EAGAIN if the
+    /// value is not available and `nonblocking` is true, or not available
+    /// before the timeout, e.g., SO_RCVTIMEO, expires.
+    pub fn poll_until<F, R>(
+        &self,
+        nonblocking: bool,
+        timeout: Option<Duration>,
+        mut cond: F,
+    ) -> Result<R>
+    where
+        F: FnMut(&mut StackInner) -> Result<Option<R>>,
+    {
+        let mut timeout = timeout;
+        let waiter = Waiter::new();
+        loop {
+            let mut inner = self.inner();
+            inner.poll();
+            if let Some(res) = cond(&mut inner)? {
+                // Send the packets resulted from the operation at once
+                inner.poll();
+                self.notify(inner);
+                return Ok(res);
+            }
+            if nonblocking {
+                return_errno!(EAGAIN, "try again later");
+            }
+
+            // Enqueue before unlocking the stack, so that no wakeup is missed
+            self.waiters.reset_and_enqueue(&waiter);
+            drop(inner);
+            sock_wait(&waiter, timeout.as_mut())?;
+        }
+    }
+
+    /// Wake up the blocked threads and broadcast the events of the sockets
+    /// that change after the stack is polled or the sockets are operated on.
+    pub fn notify(&self, mut inner: SgxMutexGuard<'_, StackInner>) {
+        let is_active = std::mem::replace(&mut inner.is_active, false);
+        let mut watches = std::mem::replace(&mut inner.watches, Vec::new());
+        watches.retain(|(socket, _)| socket.strong_count() > 0);
+        let mut changed = Vec::new();
+        for (socket, events) in watches.iter_mut() {
+            let socket = match socket.upgrade() {
+                Some(socket) => socket,
+                None => continue,
+            };
+            // New data may arrive when the socket is already readable, which
+            // is notified as well for the edge-triggered epoll
+            let new_events = socket.poll_events(&mut inner);
+            if new_events != *events || (is_active && !new_events.is_empty()) {
+                *events = new_events;
+                changed.push((socket, new_events));
+            }
+        }
+        inner.watches = watches;
+        drop(inner);
+
+        if is_active || !changed.is_empty() {
+            self.waiters.dequeue_and_wake_all();
+        }
+        for (socket, events) in changed {
+            socket.notifier().broadcast(&events);
+        }
+    }
+}
+
+impl StackInner {
+    /// Process the received frames and send the pending packets.
+    pub fn poll(&mut self) {
+        let timestamp = now();
+        for iface in self.ifaces.iter_mut() {
+            if iface.poll(timestamp) {
+                self.is_active = true;
+            }
+        }
+    }
+
+    /// How long the stack can wait before it is polled again, i.e., until the
+    /// earliest timer of the sockets expires, or None if there is no timer.
+    pub fn poll_delay(&self) -> Option<Duration> {
+        let timestamp = now();
+        self.ifaces
+            .iter()
+            .filter_map(|iface| iface.iface.poll_delay(&iface.sockets, timestamp))
+            .min()
+            .map(Duration::from)
+    }
+
+    /// Watch the socket, whose events are broadcast once they change.
+    pub fn watch(&mut self, socket: Weak<dyn WatchedSocket>) {
+        self.watches.push((socket, IoEvents::empty()));
+    }
+
+    pub fn add_socket<T>(&mut self, iface: usize, socket: T) -> Handle
+    where
+        T: Into<Socket<'static, 'static>>,
+    {
+        let socket = self.ifaces[iface].sockets.add(socket);
+        Handle { iface, socket }
+    }
+
+    /// Release the socket, which is removed once it is closed. A TCP socket
+    /// is closed gracefully.
+    pub fn release_socket(&mut self, handle: Handle) {
+        self.ifaces[handle.iface].sockets.release(handle.socket);
+    }
+
+    pub fn tcp(&mut self, handle: Handle) -> SocketRef<'_, TcpSocket<'static>> {
+        self.ifaces[handle.iface]
+            .sockets
+            .get::<TcpSocket>(handle.socket)
+    }
+
+    pub fn udp(&mut self, handle: Handle) -> SocketRef<'_, UdpSocket<'static, 'static>> {
+        self.ifaces[handle.iface]
+            .sockets
+            .get::<UdpSocket>(handle.socket)
+    }
+
+    /// Reserve the port, or an ephemeral port if it is zero.
+    pub fn alloc_port(&mut self, protocol: Protocol, port: u16) -> Result<u16> {
+        if port != 0 {
+            if !self.used_ports.insert((protocol, port)) {
+                return_errno!(EADDRINUSE, "the port is in use");
+            }
+            return Ok(port);
+        }
+
+        for _ in EPHEMERAL_PORT_MIN..=EPHEMERAL_PORT_MAX {
+            let port = self.next_port;
+            self.next_port = if port == EPHEMERAL_PORT_MAX {
+                EPHEMERAL_PORT_MIN
+            } else {
+                port + 1
+            };
+            if self.used_ports.insert((protocol, port)) {
+                return Ok(port);
+            }
+        }
+        return_errno!(EADDRNOTAVAIL, "no ephemeral port is available");
+    }
+
+    pub fn free_port(&mut self, protocol: Protocol, port: u16) {
+        self.used_ports.remove(&(protocol, port));
+    }
+
+    /// The interfaces that a socket bound to the address works on, i.e., all
+    /// the interfaces for the unspecified address.
+    pub fn ifaces_of_addr(&self, addr: Ipv4Address) -> Result<Vec<usize>> {
+        if addr.is_unspecified() {
+            return Ok((0..self.ifaces.len()).collect());
+        }
+        match self
+            .ifaces
+            .iter()
+            .position(|iface| iface.iface.has_ip_addr(addr))
+        {
+            Some(iface) => Ok(vec![iface]),
+            None => return_errno!(EADDRNOTAVAIL, "the address is not local"),
+        }
+    }
+
+    /// Get the interface to reach the remote address, and the local address
+    /// on the interface.
+    pub fn route(&self, remote: Ipv4Address) -> Result<(usize, Ipv4Address)> {
+        // The interfaces on the same subnet go first
+        for (idx, iface) in self.ifaces.iter().enumerate() {
+            for cidr in iface.iface.ip_addrs() {
+                if let IpCidr::Ipv4(cidr) = cidr {
+                    if cidr.contains_addr(&remote) {
+                        return Ok((idx, cidr.address()));
+                    }
+                }
+            }
+        }
+        for (idx, iface) in self.ifaces.iter().enumerate() {
+            if iface.has_gateway {
+                return Ok((idx, iface.first_addr()));
+            }
+        }
+        return_errno!(ENETUNREACH, "the network is unreachable");
+    }
+}
+
+impl Interface {
+    fn new(config: &ConfigEnclaveInterface) -> Result<Self> {
+        let device = TapDevice::open(&config.tap)?;
+        let ip_addrs: Vec<IpCidr> = config
+            .addrs
+            .iter()
+            .map(|(addr, prefixlen)| {
+                let octets = addr.octets();
+                IpCidr::new(
+                    IpAddress::v4(octets[0], octets[1], octets[2], octets[3]),
+                    *prefixlen,
+                )
+            })
+            .collect();
+        let mut routes = Routes::new(BTreeMap::new());
+        if let Some(gateway) = config.gateway {
+            let octets = gateway.octets();
+            routes
+                .add_default_ipv4_route(Ipv4Address::new(
+                    octets[0], octets[1], octets[2], octets[3],
+                ))
+                .map_err(|_| errno!(EINVAL, "invalid gateway"))?;
+        }
+        let iface = EthernetInterfaceBuilder::new(device)
+            .ethernet_addr(EthernetAddress(config.mac))
+            .neighbor_cache(NeighborCache::new(BTreeMap::new()))
+            .ip_addrs(ip_addrs)
+            .routes(routes)
+            .finalize();
+        Ok(Self {
+            iface,
+            sockets: SocketSet::new(Vec::new()),
+            has_gateway: config.gateway.is_some(),
+        })
+    }
+
+    // Return whether any packet is processed or sent
+    fn poll(&mut self, timestamp: Instant) -> bool {
+        let mut is_active = false;
+        for _ in 0..MAX_POLL_ROUNDS {
+            match self.iface.poll(&mut self.sockets, timestamp) {
+                Ok(processed) => {
+                    is_active |= processed;
+                    break;
+                }
+                // The invalid frame is dropped
+                Err(e) => {
+                    is_active = true;
+                    trace!("failed to process a frame: {}", e);
+                }
+            }
+        }
+        // Remove the released sockets that have been closed
+        self.sockets.prune();
+        is_active
+    }
+
+    // The config ensures that an interface has at least one address
+    fn first_addr(&self) -> Ipv4Address {
+        match self.iface.ip_addrs()[0] {
+            IpCidr::Ipv4(cidr) => cidr.address(),
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn now() -> Instant {
+    let now = do_clock_gettime(ClockID::CLOCK_MONOTONIC).unwrap();
+    Instant::from_millis(now.sec() as i64 * 1000 + now.nsec() / 1_000_000)
+}
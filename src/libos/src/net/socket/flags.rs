@@ -19,6 +19,7 @@ bitflags! {
         const MSG_TRUNC        = 0x20;
         const MSG_DONTWAIT     = 0x40;       // Nonblocking io
         const MSG_WAITALL      = 0x0100;     // Wait for a full request
+        const MSG_WAITFORONE   = 0x10000;    // Wait for at least one message of recvmmsg
         const MSG_ERRQUEUE     = 0x2000;     // Fetch message from error queue
         const MSG_CMSG_CLOEXEC = 0x40000000; // Set close_on_exec for file descriptor received through M_RIGHTS
     }
@@ -41,3 +42,16 @@ bitflags! {
         const SOCK_CLOEXEC  = 0x80000;
     }
 }
+
+impl SendFlags {
+    /// Raise SIGPIPE if sending fails with EPIPE, unless MSG_NOSIGNAL is
+    /// given, the same as the sockets of the host do.
+    pub fn check_epipe<T>(self, result: Result<T>) -> Result<T> {
+        if let Err(e) = &result {
+            if e.errno() == EPIPE && !self.contains(SendFlags::MSG_NOSIGNAL) {
+                crate::signal::do_tkill(current!().tid(), crate::signal::SIGPIPE.as_u8() as i32);
+            }
+        }
+        result
+    }
+}
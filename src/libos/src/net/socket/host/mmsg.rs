@@ -0,0 +1,263 @@
+use super::*;
+use crate::time::timespec_t;
+use crate::untrusted::{SliceAsMutPtrAndLen, SliceAsPtrAndLen, UntrustedSliceAlloc};
+
+/// The `struct occlum_mmsghdr` of a message of a batch, whose buffers are in
+/// the untrusted memory.
+#[repr(C)]
+#[allow(non_camel_case_types)]
+struct occlum_mmsghdr {
+    msg_name: *mut c_void,
+    msg_namelen: u32,
+    msg_iovlen: size_t,
+    msg_control: *mut c_void,
+    msg_controllen: size_t,
+    msg_flags: c_int,
+    msg_len: u32,
+}
+
+impl HostSocket {
+    /// Send the messages by one OCall, returning the number of the bytes sent
+    /// of each message that is sent.
+    pub fn sendmmsg(&self, msgs: &[MsgHdr], flags: SendFlags) -> Result<Vec<usize>> {
+        // The messages of a TLS connection are encrypted one by one
+        if self.tls.lock().unwrap().is_some() {
+            let mut bytes_sent = Vec::new();
+            for msg in msgs {
+                match self.sendmsg(msg, flags) {
+                    Ok(len) => bytes_sent.push(len),
+                    Err(e) if bytes_sent.is_empty() => return Err(e),
+                    Err(_) => break,
+                }
+            }
+            return Ok(bytes_sent);
+        }
+
+        let total_len = msgs
+            .iter()
+            .map(|msg| {
+                msg.get_iovs().total_bytes()
+                    + msg.get_name().map(|name| name.len()).unwrap_or(0)
+                    + msg.get_control().map(|control| control.len()).unwrap_or(0)
+            })
+            .sum();
+        let u_allocator = UntrustedSliceAlloc::new(total_len)?;
+        let mut u_msgs = Vec::with_capacity(msgs.len());
+        let mut u_iovs = Vec::new();
+        for msg in msgs {
+            let (msg_name, msg_namelen) = match msg.get_name() {
+                Some(name) => u_allocator.new_slice(name)?.as_ptr_and_len(),
+                None => (std::ptr::null(), 0),
+            };
+            let (msg_control, msg_controllen) = match msg.get_control() {
+                Some(control) => u_allocator.new_slice(control)?.as_ptr_and_len(),
+                None => (std::ptr::null(), 0),
+            };
+            let data = msg.get_iovs().as_slices();
+            for buf in data {
+                u_iovs.push(u_allocator.new_slice(buf)?.as_libc_iovec());
+            }
+            u_msgs.push(occlum_mmsghdr {
+                msg_name: msg_name as *mut c_void,
+                msg_namelen: msg_namelen as u32,
+                msg_iovlen: data.len(),
+                msg_control: msg_control as *mut c_void,
+                msg_controllen,
+                msg_flags: 0,
+                msg_len: 0,
+            });
+        }
+
+        // Do OCall
+        let mut retval: i32 = 0;
+        unsafe {
+            let status = occlum_ocall_sendmmsg(
+                &mut retval as *mut i32,
+                self.raw_host_fd() as i32,
+                u_msgs.as_mut_ptr(),
+                u_msgs.len() as u32,
+                u_iovs.as_ptr(),
+                u_iovs.len(),
+                flags.bits(),
+            );
+            assert!(status == sgx_status_t::SGX_SUCCESS);
+        }
+        let nr_msgs_sent = if flags.contains(SendFlags::MSG_NOSIGNAL) {
+            try_libc!(retval)
+        } else {
+            try_libc_may_epipe!(retval)
+        } as usize;
+
+        // Check values returned from outside the enclave
+        assert!(nr_msgs_sent <= msgs.len());
+        let bytes_sent = msgs
+            .iter()
+            .zip(u_msgs.iter())
+            .take(nr_msgs_sent)
+            .map(|(msg, u_msg)| {
+                let bytes_sent = u_msg.msg_len as usize;
+                assert!(bytes_sent <= msg.get_iovs().total_bytes());
+                bytes_sent
+            })
+            .collect();
+        Ok(bytes_sent)
+    }
+
+    /// Receive the messages by one OCall, returning the number of the bytes
+    /// received of each message that is received. Same as Linux, the timeout
+    /// is checked only after a message is received.
+    pub fn recvmmsg(
+        &self,
+        msgs: &mut [MsgHdrMut],
+        flags: RecvFlags,
+        timeout: Option<&timespec_t>,
+    ) -> Result<Vec<usize>> {
+        // The messages of a TLS connection are decrypted one by one
+        if self.tls.lock().unwrap().is_some() {
+            let mut bytes_recvd = Vec::new();
+            let mut flags = flags - RecvFlags::MSG_WAITFORONE;
+            for msg in msgs.iter_mut() {
+                match self.recvmsg(msg, flags) {
+                    Ok(len) => bytes_recvd.push(len),
+                    Err(e) if bytes_recvd.is_empty() => return Err(e),
+                    Err(_) => break,
+                }
+                // The data of a stream is never split into more messages than
+                // those that can be received without blocking
+                flags |= RecvFlags::MSG_DONTWAIT;
+            }
+            return Ok(bytes_recvd);
+        }
+
+        let total_len = msgs
+            .iter()
+            .map(|msg| {
+                msg.get_iovs().total_bytes() + msg.get_name_max_len() + msg.get_control_max_len()
+            })
+            .sum();
+        let u_allocator = UntrustedSliceAlloc::new(total_len)?;
+        let mut u_names = Vec::with_capacity(msgs.len());
+        let mut u_controls = Vec::with_capacity(msgs.len());
+        let mut u_data = Vec::with_capacity(msgs.len());
+        let mut u_msgs = Vec::with_capacity(msgs.len());
+        let mut u_iovs = Vec::new();
+        for msg in msgs.iter() {
+            let mut name = if msg.get_name_max_len() > 0 {
+                Some(u_allocator.new_slice_mut(msg.get_name_max_len())?)
+            } else {
+                None
+            };
+            let mut control = if msg.get_control_max_len() > 0 {
+                Some(u_allocator.new_slice_mut(msg.get_control_max_len())?)
+            } else {
+                None
+            };
+            let mut data = Vec::new();
+            for buf in msg.get_iovs().as_slices() {
+                data.push(u_allocator.new_slice_mut(buf.len())?);
+            }
+
+            let (msg_name, msg_namelen) = name.as_mut_ptr_and_len();
+            let (msg_control, msg_controllen) = control.as_mut_ptr_and_len();
+            u_iovs.extend(data.iter().map(|slice| slice.as_libc_iovec()));
+            u_msgs.push(occlum_mmsghdr {
+                msg_name: msg_name as *mut c_void,
+                msg_namelen: msg_namelen as u32,
+                msg_iovlen: data.len(),
+                msg_control: msg_control as *mut c_void,
+                msg_controllen,
+                msg_flags: 0,
+                msg_len: 0,
+            });
+            u_names.push(name);
+            u_controls.push(control);
+            u_data.push(data);
+        }
+        let timeout_ptr = timeout
+            .map(|timeout| timeout as *const timespec_t)
+            .unwrap_or(std::ptr::null());
+
+        // Do OCall
+        let nr_msgs_recvd = try_libc!({
+            let mut retval: i32 = 0;
+            let status = occlum_ocall_recvmmsg(
+                &mut retval as *mut i32,
+                self.raw_host_fd() as i32,
+                u_msgs.as_mut_ptr(),
+                u_msgs.len() as u32,
+                u_iovs.as_mut_ptr(),
+                u_iovs.len(),
+                flags.bits(),
+                timeout_ptr,
+            );
+            assert!(status == sgx_status_t::SGX_SUCCESS);
+            retval
+        }) as usize;
+
+        // Check values returned from outside the enclave and copy the
+        // messages received into the enclave
+        assert!(nr_msgs_recvd <= msgs.len());
+        let mut bytes_recvd = Vec::with_capacity(nr_msgs_recvd);
+        for (i, msg) in msgs.iter_mut().enumerate().take(nr_msgs_recvd) {
+            let u_msg = &u_msgs[i];
+            let flags_recvd = MsgHdrFlags::from_bits_truncate(u_msg.msg_flags);
+            let namelen_recvd = u_msg.msg_namelen as usize;
+            let controllen_recvd = u_msg.msg_controllen;
+            assert!(namelen_recvd <= msg.get_name_max_len());
+            assert!(controllen_recvd <= msg.get_control_max_len());
+
+            let len = u_msg.msg_len as usize;
+            let (iovs, name, control) = msg.get_iovs_name_and_control_mut();
+            let max_bytes_recvd = iovs.total_bytes();
+            // For MSG_TRUNC the real length of the datagram is returned, even
+            // when it was longer than the passed buffer.
+            if flags.contains(RecvFlags::MSG_TRUNC) && len > max_bytes_recvd {
+                assert!(flags_recvd.contains(MsgHdrFlags::MSG_TRUNC));
+            } else {
+                assert!(len <= max_bytes_recvd);
+            }
+
+            let mut remain = len.min(max_bytes_recvd);
+            for (buf, u_buf) in iovs.as_slices_mut().iter_mut().zip(u_data[i].iter()) {
+                let copy_len = remain.min(buf.len());
+                buf[..copy_len].copy_from_slice(&u_buf[..copy_len]);
+                remain -= copy_len;
+            }
+            if let (Some(name), Some(u_name)) = (name, u_names[i].as_ref()) {
+                name[..namelen_recvd].copy_from_slice(&u_name[..namelen_recvd]);
+            }
+            if let (Some(control), Some(u_control)) = (control, u_controls[i].as_ref()) {
+                control[..controllen_recvd].copy_from_slice(&u_control[..controllen_recvd]);
+            }
+
+            msg.set_name_len(namelen_recvd)?;
+            msg.set_control_len(controllen_recvd)?;
+            msg.set_flags(flags_recvd);
+            bytes_recvd.push(len);
+        }
+        Ok(bytes_recvd)
+    }
+}
+
+extern "C" {
+    fn occlum_ocall_sendmmsg(
+        ret: *mut c_int,
+        fd: c_int,
+        msgvec: *mut occlum_mmsghdr,
+        vlen: u32,
+        iovs: *const libc::iovec,
+        iovlen: size_t,
+        flags: c_int,
+    ) -> sgx_status_t;
+
+    fn occlum_ocall_recvmmsg(
+        ret: *mut c_int,
+        fd: c_int,
+        msgvec: *mut occlum_mmsghdr,
+        vlen: u32,
+        iovs: *mut libc::iovec,
+        iovlen: size_t,
+        flags: c_int,
+        timeout: *const timespec_t,
+    ) -> sgx_status_t;
+}
@@ -5,22 +5,42 @@ use std::mem;
 use atomic::Atomic;
 
 use super::*;
+use crate::config::LIBOS_CONFIG;
 use crate::fs::{
     occlum_ocall_ioctl, AccessMode, CreationFlags, File, FileRef, HostFd, IoEvents, IoNotifier,
     IoctlCmd, StatusFlags,
 };
+use crate::syscall::SyscallNum;
+use crate::untrusted::{io_engine, IoEngine, UntrustedSliceAlloc};
 
 mod ioctl_impl;
+mod mmsg;
+mod multicast;
 mod recv;
 mod send;
 mod socket_file;
+mod tls;
+
+const SO_DOMAIN: i32 = 39;
+const TCP_INFO: i32 = 11;
+const IPPROTO_ICMP: i32 = 1;
+const IPPROTO_ICMPV6: i32 = 58;
+
+use self::multicast::{check_interface, MulticastInterface, MulticastOption};
+pub use self::multicast::{MulticastGroup, MulticastMembership};
+use self::tls::port_of_addr;
+pub use self::tls::TlsStream;
 
 /// Native linux socket
 #[derive(Debug)]
 pub struct HostSocket {
     host_fd: HostFd,
+    ino: usize,
     host_events: Atomic<IoEvents>,
     notifier: IoNotifier,
+    multicast_memberships: SgxMutex<Vec<MulticastMembership>>,
+    // The TLS session if the connection is wrapped in TLS by LibOS
+    tls: SgxMutex<Option<Arc<TlsStream>>>,
 }
 
 impl HostSocket {
@@ -30,6 +50,7 @@ impl HostSocket {
         file_flags: FileFlags,
         protocol: i32,
     ) -> Result<Self> {
+        Self::check_permission(domain, socket_type, protocol)?;
         let raw_host_fd = try_libc!(libc::ocall::socket(
             domain as i32,
             socket_type as i32 | file_flags.bits(),
@@ -39,13 +60,43 @@ impl HostSocket {
         Ok(HostSocket::from_host_fd(host_fd))
     }
 
+    // The sockets that require privileges on Linux, i.e., raw sockets, packet
+    // sockets and ping sockets, are denied unless they are permitted by the
+    // config, as the errors of Linux
+    fn check_permission(
+        domain: AddressFamily,
+        socket_type: SocketType,
+        protocol: i32,
+    ) -> Result<()> {
+        let network = &LIBOS_CONFIG.network;
+        if (socket_type == SocketType::RAW || domain == AddressFamily::PACKET)
+            && !network.raw_sockets
+        {
+            return_errno!(EPERM, "raw sockets are not permitted by the config");
+        }
+
+        let is_ping = socket_type == SocketType::DGRAM
+            && match domain {
+                AddressFamily::INET => protocol == IPPROTO_ICMP,
+                AddressFamily::INET6 => protocol == IPPROTO_ICMPV6,
+                _ => false,
+            };
+        if is_ping && !network.ping_sockets {
+            return_errno!(EACCES, "ping sockets are not permitted by the config");
+        }
+        Ok(())
+    }
+
     fn from_host_fd(host_fd: HostFd) -> HostSocket {
         let host_events = Atomic::new(IoEvents::empty());
         let notifier = IoNotifier::new();
         Self {
             host_fd,
+            ino: alloc_socket_ino(),
             host_events,
             notifier,
+            multicast_memberships: SgxMutex::new(Vec::new()),
+            tls: SgxMutex::new(None),
         }
     }
 
@@ -69,12 +120,17 @@ impl HostSocket {
         let mut sockaddr = SockAddr::default();
         let mut addr_len = sockaddr.len();
 
-        let raw_host_fd = try_libc!(libc::ocall::accept4(
-            self.raw_host_fd() as i32,
-            sockaddr.as_mut_ptr() as *mut _,
-            &mut addr_len as *mut _ as *mut _,
-            flags.bits()
-        )) as FileDesc;
+        let raw_host_fd = match io_engine() {
+            Some(engine) => {
+                self.accept_with_io_engine(engine, sockaddr.as_mut_slice(), &mut addr_len, flags)?
+            }
+            None => try_libc!(libc::ocall::accept4(
+                self.raw_host_fd() as i32,
+                sockaddr.as_mut_ptr() as *mut _,
+                &mut addr_len as *mut _ as *mut _,
+                flags.bits()
+            )),
+        } as FileDesc;
         let host_fd = HostFd::new(raw_host_fd);
 
         let addr_option = if addr_len != 0 {
@@ -83,7 +139,48 @@ impl HostSocket {
         } else {
             None
         };
-        Ok((HostSocket::from_host_fd(host_fd), addr_option))
+        let new_socket = HostSocket::from_host_fd(host_fd);
+        if !LIBOS_CONFIG.network.tls.is_empty() {
+            if let Some(port) = port_of_addr(&self.addr()?) {
+                *new_socket.tls.lock().unwrap() = TlsStream::new_server(port)?.map(Arc::new);
+            }
+        }
+        Ok((new_socket, addr_option))
+    }
+
+    /// Do accept4 with the host I/O threads, which requires the address buffer
+    /// to be in the untrusted memory.
+    fn accept_with_io_engine(
+        &self,
+        engine: &IoEngine,
+        addr: &mut [u8],
+        addr_len: &mut usize,
+        flags: FileFlags,
+    ) -> Result<i32> {
+        let u_allocator = UntrustedSliceAlloc::new(addr.len() + mem::size_of::<u32>())?;
+        let u_addr_len = u_allocator.new_slice_mut(mem::size_of::<u32>())?;
+        u_addr_len.copy_from_slice(&(addr.len() as u32).to_ne_bytes());
+        let u_addr = u_allocator.new_slice_mut(addr.len())?;
+
+        let args = [
+            self.raw_host_fd() as i64,
+            u_addr.as_mut_ptr() as i64,
+            u_addr_len.as_mut_ptr() as i64,
+            flags.bits() as i64,
+            0,
+            0,
+        ];
+        let raw_host_fd = unsafe { engine.execute(SyscallNum::Accept4, args)? } as i32;
+
+        let len = {
+            let mut bytes = [0u8; 4];
+            bytes.copy_from_slice(u_addr_len);
+            u32::from_ne_bytes(bytes) as usize
+        };
+        let copied_len = len.min(addr.len());
+        addr[..copied_len].copy_from_slice(&u_addr[..copied_len]);
+        *addr_len = len;
+        Ok(raw_host_fd)
     }
 
     pub fn connect(&self, addr: &Option<SockAddr>) -> Result<()> {
@@ -95,7 +192,24 @@ impl HostSocket {
             (std::ptr::null(), 0)
         };
 
-        let ret = try_libc!(libc::ocall::connect(
+        let result = self.do_connect(addr_ptr, addr_len);
+        // A nonblocking connection is wrapped in TLS before it is established
+        let is_connecting = match &result {
+            Ok(()) => true,
+            Err(e) => e.errno() == EINPROGRESS,
+        };
+        if is_connecting && !LIBOS_CONFIG.network.tls.is_empty() {
+            if let Some(port) = addr.as_ref().and_then(|addr| port_of_addr(addr)) {
+                if self.socket_type()? == SocketType::STREAM {
+                    *self.tls.lock().unwrap() = TlsStream::new_client(port)?.map(Arc::new);
+                }
+            }
+        }
+        result
+    }
+
+    fn do_connect(&self, addr_ptr: *const libc::sockaddr, addr_len: usize) -> Result<()> {
+        try_libc!(libc::ocall::connect(
             self.raw_host_fd() as i32,
             addr_ptr,
             addr_len as u32
@@ -133,7 +247,124 @@ impl HostSocket {
         self.host_fd.to_raw()
     }
 
+    // The TLS session, which is not locked by the operations of the connection
+    fn tls(&self) -> Option<Arc<TlsStream>> {
+        self.tls.lock().unwrap().clone()
+    }
+
+    pub fn ino(&self) -> usize {
+        self.ino
+    }
+
+    pub fn addr(&self) -> Result<SockAddr> {
+        let mut sockaddr = SockAddr::default();
+        let mut addr_len = sockaddr.len() as libc::socklen_t;
+        try_libc!(libc::ocall::getsockname(
+            self.raw_host_fd() as i32,
+            sockaddr.as_mut_ptr(),
+            &mut addr_len
+        ));
+        sockaddr.set_len(addr_len as usize)?;
+        Ok(sockaddr)
+    }
+
+    pub fn peer_addr(&self) -> Result<SockAddr> {
+        let mut sockaddr = SockAddr::default();
+        let mut addr_len = sockaddr.len() as libc::socklen_t;
+        try_libc!(libc::ocall::getpeername(
+            self.raw_host_fd() as i32,
+            sockaddr.as_mut_ptr(),
+            &mut addr_len
+        ));
+        sockaddr.set_len(addr_len as usize)?;
+        Ok(sockaddr)
+    }
+
+    pub fn domain(&self) -> Result<AddressFamily> {
+        let domain = self.getsockopt_int(libc::SOL_SOCKET, SO_DOMAIN)?;
+        AddressFamily::try_from(domain as u16)
+    }
+
+    pub fn socket_type(&self) -> Result<SocketType> {
+        let socket_type = self.getsockopt_int(libc::SOL_SOCKET, libc::SO_TYPE)?;
+        SocketType::try_from(socket_type)
+    }
+
+    /// Get the state of a TCP socket, which is defined the same as Linux,
+    /// e.g., 1 for TCP_ESTABLISHED and 10 for TCP_LISTEN.
+    pub fn tcp_state(&self) -> Result<u8> {
+        // The state is the first field of struct tcp_info
+        let mut state: u8 = 0;
+        let mut optlen = std::mem::size_of::<u8>() as libc::socklen_t;
+        try_libc!(libc::ocall::getsockopt(
+            self.raw_host_fd() as i32,
+            libc::IPPROTO_TCP,
+            TCP_INFO,
+            &mut state as *mut _ as *mut c_void,
+            &mut optlen
+        ));
+        Ok(state)
+    }
+
+    pub fn setsockopt(&self, level: i32, optname: i32, optval: &[u8]) -> Result<()> {
+        let multicast_option = MulticastOption::from_raw(level, optname, optval)?;
+        if let Some(option) = &multicast_option {
+            check_interface(option.interface())?;
+        }
+
+        try_libc!(libc::ocall::setsockopt(
+            self.raw_host_fd() as i32,
+            level,
+            optname,
+            optval.as_ptr() as *const c_void,
+            optval.len() as libc::socklen_t
+        ));
+
+        let mut memberships = self.multicast_memberships.lock().unwrap();
+        match multicast_option {
+            Some(MulticastOption::AddMembership(membership)) => memberships.push(membership),
+            Some(MulticastOption::DropMembership(membership)) => {
+                // The host has dropped the membership, whose interface may be
+                // chosen by the host if it is not given
+                if let Some(idx) = memberships.iter().position(|joined| {
+                    joined.group == membership.group
+                        && (joined.interface == membership.interface
+                            || membership.interface == MulticastInterface::Any)
+                }) {
+                    memberships.remove(idx);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// The multicast groups joined by the socket.
+    pub fn multicast_memberships(&self) -> Vec<MulticastMembership> {
+        self.multicast_memberships.lock().unwrap().clone()
+    }
+
+    fn getsockopt_int(&self, level: i32, optname: i32) -> Result<i32> {
+        let mut optval: i32 = 0;
+        let mut optlen = std::mem::size_of::<i32>() as libc::socklen_t;
+        try_libc!(libc::ocall::getsockopt(
+            self.raw_host_fd() as i32,
+            level,
+            optname,
+            &mut optval as *mut _ as *mut c_void,
+            &mut optlen
+        ));
+        Ok(optval)
+    }
+
     pub fn shutdown(&self, how: HowToShut) -> Result<()> {
+        if how.to_shut_write() {
+            if let Some(tls) = self.tls() {
+                if let Err(e) = tls.close(self) {
+                    warn!("failed to close the TLS connection: {:?}", e);
+                }
+            }
+        }
         try_libc!(libc::ocall::shutdown(self.raw_host_fd() as i32, how.bits()));
         Ok(())
     }
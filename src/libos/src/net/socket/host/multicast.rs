@@ -0,0 +1,177 @@
+//! The multicast options of host sockets.
+//!
+//! The options are passed through to the host sockets, after they are checked
+//! against the interfaces visible to LibOS. The groups joined by a socket are
+//! tracked, so that the memberships of the socket are known to LibOS.
+
+use super::super::netlink::host_interfaces;
+use super::*;
+
+const IPPROTO_IP: i32 = 0;
+const IPPROTO_IPV6: i32 = 41;
+
+const IP_MULTICAST_IF: i32 = 32;
+const IP_ADD_MEMBERSHIP: i32 = 35;
+const IP_DROP_MEMBERSHIP: i32 = 36;
+const IPV6_MULTICAST_IF: i32 = 17;
+const IPV6_ADD_MEMBERSHIP: i32 = 20;
+const IPV6_DROP_MEMBERSHIP: i32 = 21;
+
+/// A multicast group joined by a socket on an interface.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MulticastMembership {
+    pub group: MulticastGroup,
+    pub interface: MulticastInterface,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MulticastGroup {
+    V4([u8; 4]),
+    V6([u8; 16]),
+}
+
+/// The interface of a multicast option. The interface is chosen by the host
+/// if it is `Any`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MulticastInterface {
+    Any,
+    Index(u32),
+    Addr([u8; 4]),
+}
+
+/// A multicast option that LibOS needs to check or track.
+#[derive(Debug)]
+pub enum MulticastOption {
+    AddMembership(MulticastMembership),
+    DropMembership(MulticastMembership),
+    SetInterface(MulticastInterface),
+}
+
+impl MulticastOption {
+    /// Parse the option given to setsockopt, or return None if it is not a
+    /// multicast option to check or track, e.g., IP_MULTICAST_TTL and
+    /// IP_MULTICAST_LOOP, which are simply passed through.
+    pub fn from_raw(level: i32, optname: i32, optval: &[u8]) -> Result<Option<Self>> {
+        let option = match (level, optname) {
+            (IPPROTO_IP, IP_ADD_MEMBERSHIP) => Self::AddMembership(parse_ip_mreq(optval)?),
+            (IPPROTO_IP, IP_DROP_MEMBERSHIP) => Self::DropMembership(parse_ip_mreq(optval)?),
+            (IPPROTO_IPV6, IPV6_ADD_MEMBERSHIP) => Self::AddMembership(parse_ipv6_mreq(optval)?),
+            (IPPROTO_IPV6, IPV6_DROP_MEMBERSHIP) => Self::DropMembership(parse_ipv6_mreq(optval)?),
+            (IPPROTO_IP, IP_MULTICAST_IF) => Self::SetInterface(parse_ip_multicast_if(optval)?),
+            (IPPROTO_IPV6, IPV6_MULTICAST_IF) => {
+                let ifindex = read_u32(optval, 0)?;
+                Self::SetInterface(to_interface(ifindex, [0; 4]))
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(option))
+    }
+
+    pub fn interface(&self) -> MulticastInterface {
+        match self {
+            Self::AddMembership(membership) | Self::DropMembership(membership) => {
+                membership.interface
+            }
+            Self::SetInterface(interface) => *interface,
+        }
+    }
+}
+
+/// Check that the interface is visible to LibOS according to the config.
+pub fn check_interface(interface: MulticastInterface) -> Result<()> {
+    if LIBOS_CONFIG.network.interfaces.is_none() {
+        return Ok(());
+    }
+    let (links, addrs) = host_interfaces()?;
+    let is_visible = match interface {
+        MulticastInterface::Any => true,
+        MulticastInterface::Index(index) => links.iter().any(|link| link.index == index),
+        MulticastInterface::Addr(addr) => addrs.iter().any(|ifaddr| ifaddr.addr == addr),
+    };
+    if !is_visible {
+        return_errno!(ENODEV, "the interface is not visible");
+    }
+    Ok(())
+}
+
+// struct ip_mreq, or struct ip_mreqn which has the index of the interface
+fn parse_ip_mreq(optval: &[u8]) -> Result<MulticastMembership> {
+    if optval.len() < 8 {
+        return_errno!(EINVAL, "the option is too short");
+    }
+    let group = read_ipv4_addr(optval, 0);
+    // Same as Linux, only the multicast addresses, i.e., 224.0.0.0/4, can be
+    // joined
+    if group[0] & 0xf0 != 0xe0 {
+        return_errno!(EINVAL, "not a multicast address");
+    }
+    let addr = read_ipv4_addr(optval, 4);
+    let ifindex = if optval.len() >= 12 {
+        read_u32(optval, 8)?
+    } else {
+        0
+    };
+    Ok(MulticastMembership {
+        group: MulticastGroup::V4(group),
+        interface: to_interface(ifindex, addr),
+    })
+}
+
+// struct ipv6_mreq
+fn parse_ipv6_mreq(optval: &[u8]) -> Result<MulticastMembership> {
+    if optval.len() < 20 {
+        return_errno!(EINVAL, "the option is too short");
+    }
+    let mut group = [0; 16];
+    group.copy_from_slice(&optval[..16]);
+    // Same as Linux, only the multicast addresses, i.e., ff00::/8, can be
+    // joined
+    if group[0] != 0xff {
+        return_errno!(EINVAL, "not a multicast address");
+    }
+    let ifindex = read_u32(optval, 16)?;
+    Ok(MulticastMembership {
+        group: MulticastGroup::V6(group),
+        interface: to_interface(ifindex, [0; 4]),
+    })
+}
+
+// Same as Linux, the option is struct ip_mreqn, struct ip_mreq, or struct in_addr
+fn parse_ip_multicast_if(optval: &[u8]) -> Result<MulticastInterface> {
+    if optval.len() >= 12 {
+        let addr = read_ipv4_addr(optval, 4);
+        let ifindex = read_u32(optval, 8)?;
+        Ok(to_interface(ifindex, addr))
+    } else if optval.len() >= 8 {
+        Ok(to_interface(0, read_ipv4_addr(optval, 4)))
+    } else if optval.len() >= 4 {
+        Ok(to_interface(0, read_ipv4_addr(optval, 0)))
+    } else {
+        return_errno!(EINVAL, "the option is too short");
+    }
+}
+
+// Same as Linux, the index of the interface takes precedence over its address
+fn to_interface(ifindex: u32, addr: [u8; 4]) -> MulticastInterface {
+    if ifindex != 0 {
+        MulticastInterface::Index(ifindex)
+    } else if addr != [0; 4] {
+        MulticastInterface::Addr(addr)
+    } else {
+        MulticastInterface::Any
+    }
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> Result<u32> {
+    if buf.len() < offset + 4 {
+        return_errno!(EINVAL, "the option is too short");
+    }
+    Ok(u32::from_ne_bytes(read_ipv4_addr(buf, offset)))
+}
+
+// The caller ensures that the buffer is long enough
+fn read_ipv4_addr(buf: &[u8], offset: usize) -> [u8; 4] {
+    let mut addr = [0; 4];
+    addr.copy_from_slice(&buf[offset..offset + 4]);
+    addr
+}
@@ -1,5 +1,8 @@
 use super::*;
-use crate::untrusted::{SliceAsMutPtrAndLen, SliceAsPtrAndLen, UntrustedSliceAlloc};
+use crate::syscall::SyscallNum;
+use crate::untrusted::{
+    io_engine, IoEngine, SliceAsMutPtrAndLen, SliceAsPtrAndLen, UntrustedSliceAlloc,
+};
 
 impl HostSocket {
     pub fn recv(&self, buf: &mut [u8], flags: RecvFlags) -> Result<usize> {
@@ -25,6 +28,22 @@ impl HostSocket {
     }
 
     pub(super) fn do_recvmsg(
+        &self,
+        data: &mut [&mut [u8]],
+        flags: RecvFlags,
+        name: Option<&mut [u8]>,
+        control: Option<&mut [u8]>,
+    ) -> Result<(usize, usize, usize, MsgHdrFlags)> {
+        // The data of a TLS connection is decrypted, without the name and the
+        // control messages
+        if let Some(tls) = self.tls() {
+            let bytes_recvd = tls.recv(self, data, flags)?;
+            return Ok((bytes_recvd, 0, 0, MsgHdrFlags::empty()));
+        }
+        self.do_host_recvmsg(data, flags, name, control)
+    }
+
+    pub(super) fn do_host_recvmsg(
         &self,
         data: &mut [&mut [u8]],
         flags: RecvFlags,
@@ -81,31 +100,42 @@ impl HostSocket {
         let raw_flags = flags.bits();
         let mut msg_flags_recvd = 0;
 
-        // Do OCall
-        let retval = try_libc!({
-            let mut retval = 0_isize;
-            let status = occlum_ocall_recvmsg(
-                &mut retval as *mut isize,
-                host_fd,
-                msg_name,
-                msg_namelen as u32,
-                &mut msg_namelen_recvd as *mut u32,
-                msg_iov,
-                msg_iovlen,
-                msg_control,
-                msg_controllen,
-                &mut msg_controllen_recvd as *mut usize,
-                &mut msg_flags_recvd as *mut i32,
-                raw_flags,
-            );
-            assert!(status == sgx_status_t::SGX_SUCCESS);
+        let retval = match io_engine() {
+            // A nonblocking recvmsg is not worth offloading
+            Some(engine) if !flags.contains(RecvFlags::MSG_DONTWAIT) => {
+                let (retval, namelen_recvd, controllen_recvd, flags_recvd) =
+                    self.do_recvmsg_with_io_engine(engine, &raw_iovs, flags, name, control)?;
+                msg_namelen_recvd = namelen_recvd;
+                msg_controllen_recvd = controllen_recvd;
+                msg_flags_recvd = flags_recvd;
+                retval
+            }
+            // Do OCall
+            _ => try_libc!({
+                let mut retval = 0_isize;
+                let status = occlum_ocall_recvmsg(
+                    &mut retval as *mut isize,
+                    host_fd,
+                    msg_name,
+                    msg_namelen as u32,
+                    &mut msg_namelen_recvd as *mut u32,
+                    msg_iov,
+                    msg_iovlen,
+                    msg_control,
+                    msg_controllen,
+                    &mut msg_controllen_recvd as *mut usize,
+                    &mut msg_flags_recvd as *mut i32,
+                    raw_flags,
+                );
+                assert!(status == sgx_status_t::SGX_SUCCESS);
 
-            // TODO: what if retval < 0 but buffers are modified by the
-            // untrusted OCall? We reset the potentially tampered buffers.
-            retval
-        });
+                // TODO: what if retval < 0 but buffers are modified by the
+                // untrusted OCall? We reset the potentially tampered buffers.
+                retval
+            }),
+        };
 
-        let flags_recvd = MsgHdrFlags::from_bits(msg_flags_recvd).unwrap();
+        let flags_recvd = MsgHdrFlags::from_bits_truncate(msg_flags_recvd);
 
         // Check values returned from outside the enclave
         let bytes_recvd = {
@@ -135,6 +165,71 @@ impl HostSocket {
             flags_recvd,
         ))
     }
+
+    /// Do recvmsg with the host I/O threads, which requires the message header,
+    /// the name and the control buffers to be in the untrusted memory as well.
+    fn do_recvmsg_with_io_engine(
+        &self,
+        engine: &IoEngine,
+        iovs: &[libc::iovec],
+        flags: RecvFlags,
+        name: Option<&mut [u8]>,
+        control: Option<&mut [u8]>,
+    ) -> Result<(isize, u32, usize, i32)> {
+        let msghdr_len = std::mem::size_of::<libc::msghdr>();
+        let iovs_len = iovs.len() * std::mem::size_of::<libc::iovec>();
+        let name_len = name.as_ref().map_or(0, |name| name.len());
+        let control_len = control.as_ref().map_or(0, |control| control.len());
+        // The control buffer follows the iovecs so that it is aligned
+        let u_allocator = UntrustedSliceAlloc::new(msghdr_len + iovs_len + control_len + name_len)?;
+        let u_msghdr = u_allocator.new_slice_mut(msghdr_len)?.as_mut_ptr() as *mut libc::msghdr;
+        let u_iovs = u_allocator.new_slice(unsafe {
+            std::slice::from_raw_parts(iovs.as_ptr() as *const u8, iovs_len)
+        })?;
+        let u_control = u_allocator.new_slice_mut(control_len)?;
+        let u_name = u_allocator.new_slice_mut(name_len)?;
+
+        let (msg_name, msg_namelen) = u_name.as_mut_ptr_and_len();
+        let (msg_control, msg_controllen) = u_control.as_mut_ptr_and_len();
+        unsafe {
+            std::ptr::write(
+                u_msghdr,
+                libc::msghdr {
+                    msg_name: msg_name as *mut c_void,
+                    msg_namelen: msg_namelen as u32,
+                    msg_iov: u_iovs.as_ptr() as *mut libc::iovec,
+                    msg_iovlen: iovs.len(),
+                    msg_control: msg_control as *mut c_void,
+                    msg_controllen,
+                    msg_flags: 0,
+                },
+            );
+        }
+
+        let args = [
+            self.raw_host_fd() as i64,
+            u_msghdr as i64,
+            flags.bits() as i64,
+            0,
+            0,
+            0,
+        ];
+        let retval = unsafe { engine.execute(SyscallNum::Recvmsg, args)? };
+
+        // Read the outputs only once since the untrusted memory may be changed
+        let u_msghdr = unsafe { std::ptr::read_volatile(u_msghdr) };
+        let namelen_recvd = u_msghdr.msg_namelen;
+        let controllen_recvd = u_msghdr.msg_controllen;
+        if let Some(name) = name {
+            let len = name_len.min(namelen_recvd as usize);
+            name[..len].copy_from_slice(&u_name[..len]);
+        }
+        if let Some(control) = control {
+            let len = control_len.min(controllen_recvd);
+            control[..len].copy_from_slice(&u_control[..len]);
+        }
+        Ok((retval, namelen_recvd, controllen_recvd, u_msghdr.msg_flags))
+    }
 }
 
 extern "C" {
@@ -1,5 +1,8 @@
 use super::*;
 
+/// The max length of the messages that are sent with the switchless OCall
+const SWITCHLESS_SEND_MAX_LEN: usize = 4096;
+
 impl HostSocket {
     pub fn send(&self, buf: &[u8], flags: SendFlags) -> Result<usize> {
         self.sendto(buf, flags, &None)
@@ -22,6 +25,21 @@ impl HostSocket {
         flags: SendFlags,
         name: Option<&[u8]>,
         control: Option<&[u8]>,
+    ) -> Result<usize> {
+        // The data of a TLS connection is encrypted, while the name and the
+        // control messages are ignored as for any TCP socket
+        if let Some(tls) = self.tls() {
+            return tls.send(self, data, flags);
+        }
+        self.do_host_sendmsg(data, flags, name, control)
+    }
+
+    pub(super) fn do_host_sendmsg(
+        &self,
+        data: &[&[u8]],
+        flags: SendFlags,
+        name: Option<&[u8]>,
+        control: Option<&[u8]>,
     ) -> Result<usize> {
         let data_length = data.iter().map(|s| s.len()).sum();
         let u_allocator = UntrustedSliceAlloc::new(data_length)?;
@@ -59,9 +77,16 @@ impl HostSocket {
         // Flags
         let raw_flags = flags.bits();
 
-        // Do OCall
+        // Do OCall. Small messages are sent with the switchless OCall, which
+        // saves the enclave transitions on the hot path.
+        let data_length: usize = u_data.iter().map(|s| s.len()).sum();
+        let ocall_sendmsg = if data_length <= SWITCHLESS_SEND_MAX_LEN {
+            occlum_ocall_sendmsg_switchless
+        } else {
+            occlum_ocall_sendmsg
+        };
         unsafe {
-            let status = occlum_ocall_sendmsg(
+            let status = ocall_sendmsg(
                 &mut retval as *mut isize,
                 host_fd,
                 msg_name,
@@ -97,4 +122,15 @@ extern "C" {
         msg_controllen: size_t,
         flags: c_int,
     ) -> sgx_status_t;
+    fn occlum_ocall_sendmsg_switchless(
+        ret: *mut ssize_t,
+        fd: c_int,
+        msg_name: *const c_void,
+        msg_namelen: libc::socklen_t,
+        msg_data: *const libc::iovec,
+        msg_datalen: size_t,
+        msg_control: *const c_void,
+        msg_controllen: size_t,
+        flags: c_int,
+    ) -> sgx_status_t;
 }
@@ -78,7 +78,11 @@ impl File for HostSocket {
     }
 
     fn poll_new(&self) -> IoEvents {
-        self.host_events.load(Ordering::Acquire)
+        let host_events = self.host_events.load(Ordering::Acquire);
+        match self.tls() {
+            Some(tls) => tls.poll(self, host_events),
+            None => host_events,
+        }
     }
 
     fn host_fd(&self) -> Option<&HostFd> {
@@ -93,7 +97,11 @@ impl File for HostSocket {
         self.host_events.update(ready, mask, Ordering::Release);
 
         if trigger_notifier {
-            self.notifier.broadcast(ready);
+            // The readiness of a TLS connection depends on the decrypted data
+            match self.tls() {
+                Some(tls) => self.notifier.broadcast(&tls.poll(self, *ready)),
+                None => self.notifier.broadcast(ready),
+            }
         }
     }
 
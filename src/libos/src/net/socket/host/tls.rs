@@ -0,0 +1,425 @@
+//! Transparent TLS of host TCP sockets.
+//!
+//! The connections to the remote ports and from the local ports given in the
+//! config are wrapped in TLS terminated inside the enclave, so that the
+//! plaintext never reaches the host even if the application is not aware of
+//! TLS. The handshake is done on the first send, receive or poll of a
+//! connection. The session is only locked when the records are processed, so
+//! a blocking receive on the host does not delay the sends.
+//!
+//! The certificates and the keys are read from the file system of LibOS when
+//! they are first used, so they can be provisioned at runtime, e.g., by an
+//! init process after remote attestation, into the encrypted file system.
+
+use super::*;
+use crate::config::{ConfigTls, ConfigTlsRole};
+use crate::fs::INodeExt;
+use rustls::internal::pemfile;
+use rustls::{ClientConfig, ClientSession, NoClientAuth, ServerConfig, ServerSession, Session};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+// The max length of the records received at once, which is the max length of
+// a TLS record
+const RECV_BUF_SIZE: usize = 16 * 1024 + 2048;
+
+lazy_static! {
+    // The configs of TLS loaded from the files, indexed by the paths of the
+    // CAs for the clients and by the ports for the servers
+    static ref CLIENT_CONFIGS: SgxMutex<HashMap<String, Arc<ClientConfig>>> =
+        SgxMutex::new(HashMap::new());
+    static ref SERVER_CONFIGS: SgxMutex<HashMap<u16, Arc<ServerConfig>>> =
+        SgxMutex::new(HashMap::new());
+}
+
+/// The TLS session of a connection.
+pub struct TlsStream {
+    // The session, which is only locked when the records are processed, but
+    // not when they are sent or received through the host
+    session: SgxMutex<TlsSession>,
+    // Serialize the receives of the records, so that they are processed in
+    // order
+    recv_lock: SgxMutex<()>,
+    // The records that are not sent yet, which also serializes the sends
+    pending_records: SgxMutex<Vec<u8>>,
+}
+
+struct TlsSession {
+    session: Box<dyn Session>,
+    // The decrypted data that is not received yet
+    plaintext: VecDeque<u8>,
+    // Whether the connection has been closed by the peer
+    is_eof: bool,
+}
+
+impl TlsStream {
+    /// Create a client session if the remote port is wrapped in TLS.
+    pub fn new_client(port: u16) -> Result<Option<Self>> {
+        let policy = match find_policy(port, |role| match role {
+            ConfigTlsRole::Client { .. } => true,
+            _ => false,
+        }) {
+            Some(policy) => policy,
+            None => return Ok(None),
+        };
+        match &policy.role {
+            ConfigTlsRole::Client { ca, server_name } => {
+                Self::new_client_with(ca, server_name).map(Some)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Create a client session that verifies the server by the CA.
+    pub fn new_client_with(ca: &str, server_name: &str) -> Result<Self> {
+        let config = client_config(ca)?;
+        let dns_name = webpki::DNSNameRef::try_from_ascii_str(server_name)
+            .map_err(|_| errno!(EINVAL, "invalid server name of TLS"))?;
+        let session = ClientSession::new(&config, dns_name);
+        Ok(Self::new(Box::new(session)))
+    }
+
+    /// Create a server session if the local port is wrapped in TLS.
+    pub fn new_server(port: u16) -> Result<Option<Self>> {
+        let policy = match find_policy(port, |role| match role {
+            ConfigTlsRole::Server { .. } => true,
+            _ => false,
+        }) {
+            Some(policy) => policy,
+            None => return Ok(None),
+        };
+        let config = server_config(policy)?;
+        let session = ServerSession::new(&config);
+        Ok(Some(Self::new(Box::new(session))))
+    }
+
+    fn new(session: Box<dyn Session>) -> Self {
+        let session = TlsSession {
+            session,
+            plaintext: VecDeque::new(),
+            is_eof: false,
+        };
+        Self {
+            session: SgxMutex::new(session),
+            recv_lock: SgxMutex::new(()),
+            pending_records: SgxMutex::new(Vec::new()),
+        }
+    }
+
+    /// Encrypt the data and send the records.
+    pub fn send(&self, socket: &HostSocket, bufs: &[&[u8]], flags: SendFlags) -> Result<usize> {
+        let recv_flags = if flags.contains(SendFlags::MSG_DONTWAIT) {
+            RecvFlags::MSG_DONTWAIT
+        } else {
+            RecvFlags::empty()
+        };
+        self.handshake(socket, flags, recv_flags)?;
+
+        let buf = bufs.concat();
+        let mut pending_records = self.pending_records.lock().unwrap();
+        // The records left by the last send go first
+        self.send_records(socket, &mut pending_records, flags)?;
+        let len = {
+            let mut session = self.session();
+            let len = session.session.write(&buf)?;
+            session.take_records(&mut pending_records)?;
+            len
+        };
+        // The records that cannot be sent at once are sent on the next
+        // operation of the connection, as the data has been taken
+        match self.send_records(socket, &mut pending_records, flags) {
+            Err(e) if e.errno() == EAGAIN => {}
+            res => res?,
+        }
+        Ok(len)
+    }
+
+    /// Receive the records and decrypt the data. Zero is returned if the
+    /// connection is closed by the peer.
+    pub fn recv(
+        &self,
+        socket: &HostSocket,
+        bufs: &mut [&mut [u8]],
+        flags: RecvFlags,
+    ) -> Result<usize> {
+        let send_flags = if flags.contains(RecvFlags::MSG_DONTWAIT) {
+            SendFlags::MSG_DONTWAIT
+        } else {
+            SendFlags::empty()
+        };
+        let _recv_lock = self.recv_lock.lock().unwrap();
+        self.handshake_locked(socket, send_flags, flags)?;
+
+        loop {
+            {
+                let mut session = self.session();
+                if !session.plaintext.is_empty() || session.is_eof {
+                    return Ok(session.copy_plaintext(bufs, flags.contains(RecvFlags::MSG_PEEK)));
+                }
+            }
+            self.recv_records(socket, flags)?;
+        }
+    }
+
+    /// The events of the connection given those of the host socket. Same as
+    /// an ordinary socket, it is readable only if there is decrypted data,
+    /// rather than records of the handshake or partial records, so the
+    /// records received are processed at once, without blocking.
+    pub fn poll(&self, socket: &HostSocket, host_events: IoEvents) -> IoEvents {
+        // The handshake is driven by the poll as well, e.g., for the servers
+        // that speak first
+        let flags = SendFlags::MSG_DONTWAIT | SendFlags::MSG_NOSIGNAL;
+        let _ = self.flush(socket, flags);
+        if host_events.contains(IoEvents::IN) {
+            // The readers blocked on the host are not waited for
+            if let Ok(_recv_lock) = self.recv_lock.try_lock() {
+                if self.recv_records(socket, RecvFlags::MSG_DONTWAIT).is_ok() {
+                    let _ = self.flush(socket, flags);
+                }
+            }
+        }
+
+        let mut events = host_events - IoEvents::IN;
+        let session = self.session();
+        if !session.plaintext.is_empty() || session.is_eof {
+            events |= IoEvents::IN;
+        }
+        events
+    }
+
+    /// The number of the bytes of the decrypted data, as FIONREAD.
+    pub fn bytes_to_read(&self) -> usize {
+        self.session().plaintext.len()
+    }
+
+    /// Send the close_notify alert to the peer.
+    pub fn close(&self, socket: &HostSocket) -> Result<()> {
+        let mut pending_records = self.pending_records.lock().unwrap();
+        {
+            let mut session = self.session();
+            session.session.send_close_notify();
+            session.take_records(&mut pending_records)?;
+        }
+        self.send_records(socket, &mut pending_records, SendFlags::MSG_NOSIGNAL)
+    }
+
+    fn handshake(
+        &self,
+        socket: &HostSocket,
+        send_flags: SendFlags,
+        recv_flags: RecvFlags,
+    ) -> Result<()> {
+        if !self.session().session.is_handshaking() {
+            return self.flush(socket, send_flags);
+        }
+        let _recv_lock = self.recv_lock.lock().unwrap();
+        self.handshake_locked(socket, send_flags, recv_flags)
+    }
+
+    // Do the handshake with the lock of the receives held
+    fn handshake_locked(
+        &self,
+        socket: &HostSocket,
+        send_flags: SendFlags,
+        recv_flags: RecvFlags,
+    ) -> Result<()> {
+        loop {
+            self.flush(socket, send_flags)?;
+            {
+                let session = self.session();
+                if !session.session.is_handshaking() {
+                    return Ok(());
+                }
+                if session.is_eof {
+                    return_errno!(ECONNRESET, "the connection is closed in the handshake");
+                }
+            }
+            self.recv_records(socket, recv_flags)?;
+        }
+    }
+
+    // Receive the records from the host without the session locked, and then
+    // process them. The lock of the receives must be held.
+    fn recv_records(&self, socket: &HostSocket, flags: RecvFlags) -> Result<()> {
+        let mut buf = vec![0; RECV_BUF_SIZE];
+        let (len, _, _, _) =
+            socket.do_host_recvmsg(&mut [&mut buf], flags & RecvFlags::MSG_DONTWAIT, None, None)?;
+
+        let mut session = self.session();
+        if len == 0 {
+            session.is_eof = true;
+            return Ok(());
+        }
+        let mut records = &buf[..len];
+        while !records.is_empty() {
+            if session.session.read_tls(&mut records)? == 0 {
+                return_errno!(ECONNABORTED, "the TLS records are not taken");
+            }
+            if let Err(e) = session.session.process_new_packets() {
+                warn!("TLS error: {:?}", e);
+                // Send the alert to the peer before failing
+                drop(session);
+                let _ = self.flush(socket, SendFlags::MSG_DONTWAIT | SendFlags::MSG_NOSIGNAL);
+                return_errno!(ECONNABORTED, "invalid TLS records");
+            }
+            session.read_plaintext()?;
+        }
+        Ok(())
+    }
+
+    // Send the records that the session wants to send, e.g., of the handshake
+    fn flush(&self, socket: &HostSocket, flags: SendFlags) -> Result<()> {
+        let mut pending_records = self.pending_records.lock().unwrap();
+        self.session().take_records(&mut pending_records)?;
+        self.send_records(socket, &mut pending_records, flags)
+    }
+
+    fn send_records(
+        &self,
+        socket: &HostSocket,
+        pending_records: &mut Vec<u8>,
+        flags: SendFlags,
+    ) -> Result<()> {
+        while !pending_records.is_empty() {
+            let len = socket.do_host_sendmsg(&[pending_records.as_slice()], flags, None, None)?;
+            pending_records.drain(..len);
+        }
+        Ok(())
+    }
+
+    fn session(&self) -> SgxMutexGuard<'_, TlsSession> {
+        self.session.lock().unwrap()
+    }
+}
+
+impl TlsSession {
+    // Take the records to send from the session
+    fn take_records(&mut self, records: &mut Vec<u8>) -> Result<()> {
+        while self.session.wants_write() {
+            self.session.write_tls(records)?;
+        }
+        Ok(())
+    }
+
+    // Move the decrypted data out of the session
+    fn read_plaintext(&mut self) -> Result<()> {
+        let mut buf = [0; 4096];
+        loop {
+            let len = self.session.read(&mut buf)?;
+            if len == 0 {
+                return Ok(());
+            }
+            self.plaintext.extend(&buf[..len]);
+        }
+    }
+
+    fn copy_plaintext(&mut self, bufs: &mut [&mut [u8]], is_peek: bool) -> usize {
+        let mut bytes_copied = 0;
+        for buf in bufs.iter_mut() {
+            let len = buf.len().min(self.plaintext.len() - bytes_copied);
+            for (dst, src) in buf[..len]
+                .iter_mut()
+                .zip(self.plaintext.iter().skip(bytes_copied))
+            {
+                *dst = *src;
+            }
+            bytes_copied += len;
+        }
+        if !is_peek {
+            self.plaintext.drain(..bytes_copied);
+        }
+        bytes_copied
+    }
+}
+
+impl Debug for TlsStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut debug = f.debug_struct("TlsStream");
+        if let Ok(session) = self.session.try_lock() {
+            debug
+                .field("is_handshaking", &session.session.is_handshaking())
+                .field("is_eof", &session.is_eof);
+        }
+        debug.finish()
+    }
+}
+
+fn find_policy<P: Fn(&ConfigTlsRole) -> bool>(
+    port: u16,
+    predicate: P,
+) -> Option<&'static ConfigTls> {
+    LIBOS_CONFIG
+        .network
+        .tls
+        .iter()
+        .find(|policy| policy.port == port && predicate(&policy.role))
+}
+
+fn client_config(ca: &str) -> Result<Arc<ClientConfig>> {
+    let mut configs = CLIENT_CONFIGS.lock().unwrap();
+    if let Some(config) = configs.get(ca) {
+        return Ok(config.clone());
+    }
+
+    let mut config = ClientConfig::new();
+    let (nr_added, _) = config
+        .root_store
+        .add_pem_file(&mut read_file(ca)?.as_slice())
+        .map_err(|_| errno!(EINVAL, "invalid CA of TLS"))?;
+    if nr_added == 0 {
+        return_errno!(EINVAL, "no CA of TLS");
+    }
+    let config = Arc::new(config);
+    configs.insert(ca.to_owned(), config.clone());
+    Ok(config)
+}
+
+fn server_config(policy: &ConfigTls) -> Result<Arc<ServerConfig>> {
+    let mut configs = SERVER_CONFIGS.lock().unwrap();
+    if let Some(config) = configs.get(&policy.port) {
+        return Ok(config.clone());
+    }
+
+    let (cert, key) = match &policy.role {
+        ConfigTlsRole::Server { cert, key } => (cert, key),
+        _ => unreachable!(),
+    };
+    let certs = pemfile::certs(&mut read_file(cert)?.as_slice())
+        .map_err(|_| errno!(EINVAL, "invalid cert of TLS"))?;
+    let key = {
+        let key_pem = read_file(key)?;
+        // Both PKCS#8 and PKCS#1 keys are accepted
+        let mut keys = pemfile::pkcs8_private_keys(&mut key_pem.as_slice())
+            .map_err(|_| errno!(EINVAL, "invalid key of TLS"))?;
+        if keys.is_empty() {
+            keys = pemfile::rsa_private_keys(&mut key_pem.as_slice())
+                .map_err(|_| errno!(EINVAL, "invalid key of TLS"))?;
+        }
+        keys.pop().ok_or_else(|| errno!(EINVAL, "no key of TLS"))?
+    };
+    let mut config = ServerConfig::new(NoClientAuth::new());
+    config
+        .set_single_cert(certs, key)
+        .map_err(|_| errno!(EINVAL, "the cert and the key of TLS do not match"))?;
+    let config = Arc::new(config);
+    configs.insert(policy.port, config.clone());
+    Ok(config)
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>> {
+    let inode = current!().fs().lock().unwrap().lookup_inode(path)?;
+    inode.read_as_vec()
+}
+
+/// Get the port of an IPv4 or IPv6 address, which is at the same offset.
+pub fn port_of_addr(addr: &SockAddr) -> Option<u16> {
+    let raw = addr.as_slice();
+    if raw.len() < 4 {
+        return None;
+    }
+    let family = u16::from_ne_bytes([raw[0], raw[1]]);
+    if family != AddressFamily::INET as u16 && family != AddressFamily::INET6 as u16 {
+        return None;
+    }
+    Some(u16::from_be_bytes([raw[2], raw[3]]))
+}
@@ -0,0 +1,114 @@
+//! The ioctls to query the network interfaces, i.e., SIOCGIFCONF, SIOCGIFADDR
+//! and SIOCGIFHWADDR, which are answered inside the enclave with the
+//! interfaces of the host that are visible according to the config.
+
+use super::netlink::host_interfaces;
+use super::*;
+use fs::{IfConf, IfReq, IoctlCmd};
+use util::mem_util::from_user;
+
+const IFNAMSIZ: usize = 16;
+
+/// Answer the ioctl to query the network interfaces.
+pub fn do_iface_ioctl(cmd: &mut IoctlCmd) -> Result<i32> {
+    match cmd {
+        IoctlCmd::SIOCGIFCONF(ifconf) => get_ifconf(ifconf),
+        IoctlCmd::SIOCGIFADDR(ifreq) => get_ifaddr(ifreq),
+        IoctlCmd::SIOCGIFHWADDR(ifreq) => get_ifhwaddr(ifreq),
+        _ => return_errno!(EINVAL, "not an ioctl to query the interfaces"),
+    }
+}
+
+// Same as Linux, only the IPv4 addresses are listed, each in a `struct ifreq`
+fn get_ifconf(ifconf: &mut IfConf) -> Result<i32> {
+    let (_, addrs) = host_interfaces()?;
+    let ifreqs: Vec<IfReq> = addrs
+        .iter()
+        .filter(|ifaddr| ifaddr.family == AddressFamily::INET)
+        .map(|ifaddr| {
+            let mut ifreq = new_ifreq(&ifaddr.name);
+            write_sockaddr_in(&mut ifreq, &ifaddr.addr);
+            ifreq
+        })
+        .collect();
+
+    let ifreq_size = std::mem::size_of::<IfReq>();
+    // Given a NULL buffer, the length of the buffer to hold all is returned
+    if ifconf.ifc_buf.is_null() {
+        ifconf.ifc_len = (ifreqs.len() * ifreq_size) as i32;
+        return Ok(0);
+    }
+    if ifconf.ifc_len < 0 {
+        return_errno!(EINVAL, "the length of the buffer is negative");
+    }
+
+    let count = (ifconf.ifc_len as usize / ifreq_size).min(ifreqs.len());
+    let buf = ifconf.ifc_buf as *mut IfReq;
+    from_user::check_mut_array(buf, count)?;
+    let buf = unsafe { std::slice::from_raw_parts_mut(buf, count) };
+    for (dst, src) in buf.iter_mut().zip(ifreqs.iter()) {
+        dst.ifr_name = src.ifr_name;
+        dst.ifr_union = src.ifr_union;
+    }
+    ifconf.ifc_len = (count * ifreq_size) as i32;
+    Ok(0)
+}
+
+fn get_ifaddr(ifreq: &mut IfReq) -> Result<i32> {
+    let name = ifreq_name(ifreq)?;
+    let (links, addrs) = host_interfaces()?;
+    if !links.iter().any(|link| link.name == name) {
+        return_errno!(ENODEV, "no such interface");
+    }
+    let ifaddr = addrs
+        .iter()
+        .find(|ifaddr| ifaddr.name == name && ifaddr.family == AddressFamily::INET)
+        .ok_or_else(|| errno!(EADDRNOTAVAIL, "the interface has no IPv4 address"))?;
+    write_sockaddr_in(ifreq, &ifaddr.addr);
+    Ok(0)
+}
+
+// The hardware address is in a `struct sockaddr`, whose family is the type
+// of the hardware, e.g., ARPHRD_ETHER
+fn get_ifhwaddr(ifreq: &mut IfReq) -> Result<i32> {
+    let name = ifreq_name(ifreq)?;
+    let (links, _) = host_interfaces()?;
+    let link = links
+        .iter()
+        .find(|link| link.name == name)
+        .ok_or_else(|| errno!(ENODEV, "no such interface"))?;
+
+    ifreq.ifr_union = [0; 24];
+    ifreq.ifr_union[..2].copy_from_slice(&link.hatype.to_ne_bytes());
+    let len = link.hwaddr.len().min(14);
+    ifreq.ifr_union[2..2 + len].copy_from_slice(&link.hwaddr[..len]);
+    Ok(0)
+}
+
+// Same as Linux, the name is truncated to fit in IFNAMSIZ with the NUL
+fn ifreq_name(ifreq: &IfReq) -> Result<String> {
+    let len = ifreq
+        .ifr_name
+        .iter()
+        .position(|&b| b == 0)
+        .unwrap_or(IFNAMSIZ - 1);
+    String::from_utf8(ifreq.ifr_name[..len].to_vec())
+        .map_err(|_| errno!(ENODEV, "no such interface"))
+}
+
+fn new_ifreq(name: &str) -> IfReq {
+    let mut ifr_name = [0; IFNAMSIZ];
+    let len = name.len().min(IFNAMSIZ - 1);
+    ifr_name[..len].copy_from_slice(&name.as_bytes()[..len]);
+    IfReq {
+        ifr_name,
+        ifr_union: [0; 24],
+    }
+}
+
+// The `struct sockaddr_in` of the IPv4 address
+fn write_sockaddr_in(ifreq: &mut IfReq, addr: &[u8]) {
+    ifreq.ifr_union = [0; 24];
+    ifreq.ifr_union[..2].copy_from_slice(&(libc::AF_INET as u16).to_ne_bytes());
+    ifreq.ifr_union[4..8].copy_from_slice(&addr[..4]);
+}
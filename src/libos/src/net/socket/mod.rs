@@ -1,21 +1,44 @@
 use super::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 mod address_family;
+mod dns;
+mod enclave;
 mod flags;
 mod host;
+mod iface_ioctl;
 mod iovs;
 mod msg;
+mod netlink;
 mod shutdown;
 mod socket_address;
 mod socket_type;
+mod sockopt;
+mod timeout;
 mod unix;
 
 pub use self::address_family::AddressFamily;
+pub use self::dns::{is_resolver_addr, write_resolv_conf, AsDnsSocket, DnsSocket};
+pub use self::enclave::{
+    do_poll_net, enclave_socket, is_enclave_addr, is_enclave_stack_enabled, AsEnclaveSocket,
+    EnclaveSocket,
+};
 pub use self::flags::{FileFlags, MsgHdrFlags, RecvFlags, SendFlags};
 pub use self::host::{HostSocket, HostSocketType};
+pub use self::iface_ioctl::do_iface_ioctl;
 pub use self::iovs::{Iovs, IovsMut, SliceAsLibcIovec};
-pub use self::msg::{msghdr, msghdr_mut, MsgHdr, MsgHdrMut};
+pub use self::msg::{mmsghdr, mmsghdr_mut, msghdr, msghdr_mut, MsgHdr, MsgHdrMut};
+pub use self::netlink::{netlink_socket, AsNetlinkSocket, NetlinkAddr, NETLINK_ROUTE};
 pub use self::shutdown::HowToShut;
 pub use self::socket_address::SockAddr;
 pub use self::socket_type::SocketType;
-pub use self::unix::{socketpair, unix_socket, AsUnixSocket, UnixAddr};
+pub use self::sockopt::{linger_t, SockOpt, SockOpts, SockOptsExt};
+pub use self::timeout::{sock_wait, SockTimeouts, SockTimeoutsExt};
+pub use self::unix::{socketpair, unix_socket, AsUnixDatagram, AsUnixSocket, UnixAddr};
+
+/// Allocate an inode number for a new socket, which identifies the socket
+/// in procfs, e.g., the inode column of `/proc/net/tcp`.
+pub fn alloc_socket_ino() -> usize {
+    static NEXT_SOCKET_INO: AtomicUsize = AtomicUsize::new(1);
+    NEXT_SOCKET_INO.fetch_add(1, Ordering::Relaxed)
+}
@@ -27,6 +27,22 @@ pub struct msghdr_mut {
     pub msg_flags: c_int,
 }
 
+/// C struct for a message of sendmmsg
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct mmsghdr {
+    pub msg_hdr: msghdr,
+    pub msg_len: u32,
+}
+
+/// C struct for a message of recvmmsg
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct mmsghdr_mut {
+    pub msg_hdr: msghdr_mut,
+    pub msg_len: u32,
+}
+
 /// MsgHdr is a memory-safe, immutable wrapper of msghdr
 pub struct MsgHdr<'a> {
     name: Option<&'a [u8]>,
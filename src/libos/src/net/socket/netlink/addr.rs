@@ -0,0 +1,62 @@
+use super::*;
+
+/// The address of a netlink socket, i.e., `struct sockaddr_nl`. The port ID
+/// of the kernel is zero.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct NetlinkAddr {
+    pub port_id: u32,
+    pub groups: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+struct sockaddr_nl {
+    nl_family: libc::sa_family_t,
+    nl_pad: u16,
+    nl_pid: u32,
+    nl_groups: u32,
+}
+
+impl NetlinkAddr {
+    /// The address of the kernel.
+    pub fn kernel() -> Self {
+        Self::default()
+    }
+
+    pub unsafe fn try_from_raw(
+        addr: *const libc::sockaddr,
+        addr_len: libc::socklen_t,
+    ) -> Result<Self> {
+        if (addr_len as usize) < std::mem::size_of::<sockaddr_nl>() {
+            return_errno!(EINVAL, "the address is too short");
+        }
+        let addr = &*(addr as *const sockaddr_nl);
+        if addr.nl_family != AddressFamily::NETLINK as libc::sa_family_t {
+            return_errno!(EINVAL, "not a netlink address");
+        }
+        Ok(Self {
+            port_id: addr.nl_pid,
+            groups: addr.nl_groups,
+        })
+    }
+
+    /// Copy the address to the slice, which is truncated if the slice is too
+    /// short.
+    pub fn copy_to_slice(&self, dst: &mut [u8]) {
+        let addr = sockaddr_nl {
+            nl_family: AddressFamily::NETLINK as libc::sa_family_t,
+            nl_pad: 0,
+            nl_pid: self.port_id,
+            nl_groups: self.groups,
+        };
+        let src =
+            unsafe { std::slice::from_raw_parts(&addr as *const _ as *const u8, Self::raw_len()) };
+        let len = dst.len().min(src.len());
+        dst[..len].copy_from_slice(&src[..len]);
+    }
+
+    pub fn raw_len() -> usize {
+        std::mem::size_of::<sockaddr_nl>()
+    }
+}
@@ -0,0 +1,144 @@
+//! The network interfaces of the host.
+//!
+//! The interfaces are provided by the untrusted host, so the malformed ones
+//! are dropped, and only the ones listed in the config are visible.
+
+use super::*;
+use crate::config::LIBOS_CONFIG;
+
+// The max number of the links and the addresses got from the host
+const MAX_IFADDRS: usize = 256;
+
+const IFNAMSIZ: usize = 16;
+const AF_PACKET: u16 = 17;
+
+// The flag of the interfaces that support broadcast
+pub const IFF_BROADCAST: u32 = 0x2;
+
+/// A network interface, i.e., a link.
+#[derive(Debug, Clone)]
+pub struct Link {
+    pub index: u32,
+    pub name: String,
+    pub flags: u32,
+    pub mtu: u32,
+    pub hatype: u16,
+    pub hwaddr: Vec<u8>,
+}
+
+/// An IPv4 or IPv6 address of a network interface.
+#[derive(Debug, Clone)]
+pub struct IfAddr {
+    pub index: u32,
+    pub name: String,
+    pub family: AddressFamily,
+    pub prefixlen: u8,
+    pub addr: Vec<u8>,
+    pub broadaddr: Option<Vec<u8>>,
+}
+
+/// Get the links and the addresses of the visible interfaces of the host.
+pub fn host_interfaces() -> Result<(Vec<Link>, Vec<IfAddr>)> {
+    let mut ifaddrs = vec![occlum_ifaddr::default(); MAX_IFADDRS];
+    let count = try_libc!({
+        let mut retval = 0;
+        let status = occlum_ocall_get_ifaddrs(&mut retval, ifaddrs.as_mut_ptr(), ifaddrs.len());
+        assert!(status == sgx_status_t::SGX_SUCCESS);
+        retval
+    }) as usize;
+    ifaddrs.truncate(count.min(MAX_IFADDRS));
+
+    let mut links = Vec::new();
+    let mut addrs = Vec::new();
+    for ifaddr in &ifaddrs {
+        let name = match ifaddr.name() {
+            Some(name) if is_visible_interface(&name) => name,
+            _ => continue,
+        };
+        if ifaddr.index == 0 || ifaddr.addr_len as usize > ifaddr.addr.len() {
+            warn!("invalid interface from the host: {}", name);
+            continue;
+        }
+        let addr = ifaddr.addr[..ifaddr.addr_len as usize].to_vec();
+
+        if ifaddr.family == AF_PACKET {
+            links.push(Link {
+                index: ifaddr.index,
+                name,
+                flags: ifaddr.flags,
+                mtu: ifaddr.mtu,
+                hatype: ifaddr.hatype,
+                hwaddr: addr,
+            });
+            continue;
+        }
+
+        let (family, addr_len) = match AddressFamily::try_from(ifaddr.family) {
+            Ok(AddressFamily::INET) => (AddressFamily::INET, 4),
+            Ok(AddressFamily::INET6) => (AddressFamily::INET6, 16),
+            _ => continue,
+        };
+        if addr.len() != addr_len || ifaddr.prefixlen as usize > addr_len * 8 {
+            warn!("invalid interface address from the host: {}", name);
+            continue;
+        }
+        let broadaddr = if family == AddressFamily::INET && ifaddr.flags & IFF_BROADCAST != 0 {
+            Some(ifaddr.broadaddr[..addr_len].to_vec())
+        } else {
+            None
+        };
+        addrs.push(IfAddr {
+            index: ifaddr.index,
+            name,
+            family,
+            prefixlen: ifaddr.prefixlen,
+            addr,
+            broadaddr,
+        });
+    }
+    Ok((links, addrs))
+}
+
+/// Whether the interface is visible to LibOS according to the config.
+pub fn is_visible_interface(name: &str) -> bool {
+    match &LIBOS_CONFIG.network.interfaces {
+        Some(interfaces) => interfaces.iter().any(|interface| interface == name),
+        None => true,
+    }
+}
+
+/// The `struct occlum_ifaddr` filled by the host.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+struct occlum_ifaddr {
+    name: [u8; IFNAMSIZ],
+    index: u32,
+    flags: u32,
+    mtu: u32,
+    family: u16,
+    hatype: u16,
+    prefixlen: u8,
+    addr_len: u8,
+    addr: [u8; 16],
+    broadaddr: [u8; 16],
+}
+
+impl occlum_ifaddr {
+    // The name must be a nonempty C string
+    fn name(&self) -> Option<String> {
+        let len = self.name.iter().position(|&b| b == 0)?;
+        if len == 0 {
+            return None;
+        }
+        String::from_utf8(self.name[..len].to_vec()).ok()
+    }
+}
+
+extern "C" {
+    fn occlum_ocall_get_ifaddrs(
+        retval: *mut i32,
+        ifaddrs: *mut occlum_ifaddr,
+        max_count: usize,
+    ) -> sgx_status_t;
+}
@@ -0,0 +1,49 @@
+//! Netlink sockets of NETLINK_ROUTE.
+//!
+//! The requests are answered inside the enclave, instead of by the netlink of
+//! the host. Only the requests to enumerate the network interfaces, i.e.,
+//! RTM_GETLINK and RTM_GETADDR, are supported, which are answered with the
+//! interfaces of the host that are visible according to the config.
+
+use super::*;
+use fs::File;
+
+mod addr;
+mod file;
+mod iface;
+mod route;
+mod socket;
+
+pub use self::addr::NetlinkAddr;
+pub(super) use self::iface::{host_interfaces, is_visible_interface};
+pub use self::socket::NetlinkSocket;
+
+/// The netlink protocol of routing and link configuration.
+pub const NETLINK_ROUTE: i32 = 0;
+
+pub fn netlink_socket(
+    socket_type: SocketType,
+    flags: FileFlags,
+    protocol: i32,
+) -> Result<Arc<dyn File>> {
+    if protocol != NETLINK_ROUTE {
+        return_errno!(EPROTONOSUPPORT, "protocol is not supported");
+    }
+
+    match socket_type {
+        SocketType::RAW | SocketType::DGRAM => Ok(Arc::new(NetlinkSocket::new(flags))),
+        _ => return_errno!(ESOCKTNOSUPPORT, "the socket type is not supported"),
+    }
+}
+
+pub trait AsNetlinkSocket {
+    fn as_netlink_socket(&self) -> Result<&NetlinkSocket>;
+}
+
+impl AsNetlinkSocket for FileRef {
+    fn as_netlink_socket(&self) -> Result<&NetlinkSocket> {
+        self.as_any()
+            .downcast_ref::<NetlinkSocket>()
+            .ok_or_else(|| errno!(EBADF, "not a netlink socket"))
+    }
+}
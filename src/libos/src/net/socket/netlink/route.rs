@@ -0,0 +1,302 @@
+//! The messages of NETLINK_ROUTE.
+
+use super::iface::{host_interfaces, IfAddr, Link, IFF_BROADCAST};
+use super::*;
+
+// The types of the messages
+const NLMSG_NOOP: u16 = 1;
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+const RTM_NEWLINK: u16 = 16;
+const RTM_GETLINK: u16 = 18;
+const RTM_NEWADDR: u16 = 20;
+const RTM_GETADDR: u16 = 22;
+
+// The flags of the messages
+const NLM_F_REQUEST: u16 = 0x1;
+const NLM_F_MULTI: u16 = 0x2;
+const NLM_F_ACK: u16 = 0x4;
+const NLM_F_DUMP: u16 = 0x300;
+
+// The attributes of the links
+const IFLA_ADDRESS: u16 = 1;
+const IFLA_BROADCAST: u16 = 2;
+const IFLA_IFNAME: u16 = 3;
+const IFLA_MTU: u16 = 4;
+
+// The attributes of the addresses
+const IFA_ADDRESS: u16 = 1;
+const IFA_LOCAL: u16 = 2;
+const IFA_LABEL: u16 = 3;
+const IFA_BROADCAST: u16 = 4;
+
+// The flag of the addresses that are not dynamic
+const IFA_F_PERMANENT: u8 = 0x80;
+
+// The scopes of the addresses
+const RT_SCOPE_UNIVERSE: u8 = 0;
+const RT_SCOPE_LINK: u8 = 253;
+const RT_SCOPE_HOST: u8 = 254;
+
+// All the messages and the attributes are aligned to 4 bytes
+const NLMSG_ALIGNTO: usize = 4;
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+struct nlmsghdr {
+    nlmsg_len: u32,
+    nlmsg_type: u16,
+    nlmsg_flags: u16,
+    nlmsg_seq: u32,
+    nlmsg_pid: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+struct nlmsgerr {
+    error: i32,
+    msg: nlmsghdr,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+struct ifinfomsg {
+    ifi_family: u8,
+    __ifi_pad: u8,
+    ifi_type: u16,
+    ifi_index: i32,
+    ifi_flags: u32,
+    ifi_change: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+struct ifaddrmsg {
+    ifa_family: u8,
+    ifa_prefixlen: u8,
+    ifa_flags: u8,
+    ifa_scope: u8,
+    ifa_index: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+struct rtattr {
+    rta_len: u16,
+    rta_type: u16,
+}
+
+/// Handle the requests in a datagram sent by the socket of the port ID,
+/// returning the messages to reply. The messages of a dump are returned as a
+/// group, so that they can be packed into fewer datagrams.
+pub fn handle_requests(buf: &[u8], port_id: u32) -> Vec<Vec<Vec<u8>>> {
+    let mut replies = Vec::new();
+    let mut offset = 0;
+    // Same as Linux, the malformed messages at the end are ignored
+    while buf.len() - offset >= std::mem::size_of::<nlmsghdr>() {
+        let hdr: nlmsghdr = read_struct(&buf[offset..]);
+        let msg_len = hdr.nlmsg_len as usize;
+        if msg_len < std::mem::size_of::<nlmsghdr>() || msg_len > buf.len() - offset {
+            break;
+        }
+        let payload = &buf[offset + std::mem::size_of::<nlmsghdr>()..offset + msg_len];
+        if hdr.nlmsg_flags & NLM_F_REQUEST != 0 {
+            let reply = handle_request(&hdr, payload, port_id);
+            if !reply.is_empty() {
+                replies.push(reply);
+            }
+        }
+        offset += align(msg_len).min(buf.len() - offset);
+    }
+    replies
+}
+
+fn handle_request(hdr: &nlmsghdr, payload: &[u8], port_id: u32) -> Vec<Vec<u8>> {
+    let is_dump = hdr.nlmsg_flags & NLM_F_DUMP == NLM_F_DUMP;
+    let result = match hdr.nlmsg_type {
+        NLMSG_NOOP | NLMSG_DONE | NLMSG_ERROR => return Vec::new(),
+        RTM_GETLINK if is_dump => dump_links(hdr, port_id),
+        RTM_GETLINK => get_link(hdr, payload, port_id),
+        RTM_GETADDR if is_dump => dump_addrs(hdr, payload, port_id),
+        _ => Err(errno!(EOPNOTSUPP, "the netlink request is not supported")),
+    };
+    match result {
+        Ok(mut msgs) => {
+            if hdr.nlmsg_flags & NLM_F_ACK != 0 {
+                msgs.push(new_error(hdr, 0, port_id));
+            }
+            msgs
+        }
+        Err(e) => vec![new_error(hdr, -(e.errno() as i32), port_id)],
+    }
+}
+
+fn dump_links(hdr: &nlmsghdr, port_id: u32) -> Result<Vec<Vec<u8>>> {
+    let (links, _) = host_interfaces()?;
+    let mut msgs: Vec<Vec<u8>> = links
+        .iter()
+        .map(|link| new_link(hdr, link, NLM_F_MULTI, port_id))
+        .collect();
+    msgs.push(new_done(hdr, port_id));
+    Ok(msgs)
+}
+
+fn get_link(hdr: &nlmsghdr, payload: &[u8], port_id: u32) -> Result<Vec<Vec<u8>>> {
+    if payload.len() < std::mem::size_of::<ifinfomsg>() {
+        return_errno!(EINVAL, "the request is too short");
+    }
+    let info: ifinfomsg = read_struct(payload);
+    let (links, _) = host_interfaces()?;
+    let link = links
+        .iter()
+        .find(|link| link.index as i32 == info.ifi_index)
+        .ok_or_else(|| errno!(ENODEV, "no such interface"))?;
+    Ok(vec![new_link(hdr, link, 0, port_id)])
+}
+
+fn dump_addrs(hdr: &nlmsghdr, payload: &[u8], port_id: u32) -> Result<Vec<Vec<u8>>> {
+    // The family is the first byte of either `struct rtgenmsg` or
+    // `struct ifaddrmsg`
+    let family = payload.first().copied().unwrap_or(0) as u16;
+    let (_, addrs) = host_interfaces()?;
+    let mut msgs: Vec<Vec<u8>> = addrs
+        .iter()
+        .filter(|addr| family == AddressFamily::UNSPEC as u16 || family == addr.family as u16)
+        .map(|addr| new_addr(hdr, addr, NLM_F_MULTI, port_id))
+        .collect();
+    msgs.push(new_done(hdr, port_id));
+    Ok(msgs)
+}
+
+fn new_link(req: &nlmsghdr, link: &Link, flags: u16, port_id: u32) -> Vec<u8> {
+    let mut builder = MsgBuilder::new(RTM_NEWLINK, flags, req.nlmsg_seq, port_id);
+    builder.push_struct(&ifinfomsg {
+        ifi_family: AddressFamily::UNSPEC as u8,
+        ifi_type: link.hatype,
+        ifi_index: link.index as i32,
+        ifi_flags: link.flags,
+        ..Default::default()
+    });
+    let mut name = link.name.as_bytes().to_vec();
+    name.push(0);
+    builder.push_attr(IFLA_IFNAME, &name);
+    if link.mtu > 0 {
+        builder.push_attr(IFLA_MTU, &link.mtu.to_ne_bytes());
+    }
+    if !link.hwaddr.is_empty() {
+        builder.push_attr(IFLA_ADDRESS, &link.hwaddr);
+        if link.flags & IFF_BROADCAST != 0 {
+            builder.push_attr(IFLA_BROADCAST, &vec![0xff; link.hwaddr.len()]);
+        }
+    }
+    builder.finish()
+}
+
+fn new_addr(req: &nlmsghdr, addr: &IfAddr, flags: u16, port_id: u32) -> Vec<u8> {
+    let mut builder = MsgBuilder::new(RTM_NEWADDR, flags, req.nlmsg_seq, port_id);
+    builder.push_struct(&ifaddrmsg {
+        ifa_family: addr.family as u8,
+        ifa_prefixlen: addr.prefixlen,
+        ifa_flags: IFA_F_PERMANENT,
+        ifa_scope: addr_scope(addr),
+        ifa_index: addr.index,
+    });
+    builder.push_attr(IFA_ADDRESS, &addr.addr);
+    if addr.family == AddressFamily::INET {
+        builder.push_attr(IFA_LOCAL, &addr.addr);
+        if let Some(broadaddr) = &addr.broadaddr {
+            builder.push_attr(IFA_BROADCAST, broadaddr);
+        }
+        let mut label = addr.name.as_bytes().to_vec();
+        label.push(0);
+        builder.push_attr(IFA_LABEL, &label);
+    }
+    builder.finish()
+}
+
+fn addr_scope(addr: &IfAddr) -> u8 {
+    match addr.family {
+        AddressFamily::INET if addr.addr[0] == 127 => RT_SCOPE_HOST,
+        AddressFamily::INET6 if addr.addr[..] == std::net::Ipv6Addr::LOCALHOST.octets() => {
+            RT_SCOPE_HOST
+        }
+        AddressFamily::INET6 if addr.addr[0] == 0xfe && addr.addr[1] & 0xc0 == 0x80 => {
+            RT_SCOPE_LINK
+        }
+        _ => RT_SCOPE_UNIVERSE,
+    }
+}
+
+fn new_done(req: &nlmsghdr, port_id: u32) -> Vec<u8> {
+    let mut builder = MsgBuilder::new(NLMSG_DONE, NLM_F_MULTI, req.nlmsg_seq, port_id);
+    builder.push_struct(&0i32);
+    builder.finish()
+}
+
+// An error message, or an acknowledgment if the error is zero
+fn new_error(req: &nlmsghdr, error: i32, port_id: u32) -> Vec<u8> {
+    let mut builder = MsgBuilder::new(NLMSG_ERROR, 0, req.nlmsg_seq, port_id);
+    builder.push_struct(&nlmsgerr { error, msg: *req });
+    builder.finish()
+}
+
+/// The builder of a message, which is the header followed by the payload.
+struct MsgBuilder {
+    buf: Vec<u8>,
+}
+
+impl MsgBuilder {
+    fn new(type_: u16, flags: u16, seq: u32, port_id: u32) -> Self {
+        let mut builder = Self { buf: Vec::new() };
+        builder.push_struct(&nlmsghdr {
+            nlmsg_len: 0,
+            nlmsg_type: type_,
+            nlmsg_flags: flags,
+            nlmsg_seq: seq,
+            nlmsg_pid: port_id,
+        });
+        builder
+    }
+
+    fn push_struct<T: Copy>(&mut self, val: &T) {
+        let bytes = unsafe {
+            std::slice::from_raw_parts(val as *const T as *const u8, std::mem::size_of::<T>())
+        };
+        self.push_bytes(bytes);
+    }
+
+    fn push_attr(&mut self, type_: u16, data: &[u8]) {
+        let attr = rtattr {
+            rta_len: (std::mem::size_of::<rtattr>() + data.len()) as u16,
+            rta_type: type_,
+        };
+        self.push_struct(&attr);
+        self.push_bytes(data);
+    }
+
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+        self.buf.resize(align(self.buf.len()), 0);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let len = self.buf.len() as u32;
+        self.buf[..4].copy_from_slice(&len.to_ne_bytes());
+        self.buf
+    }
+}
+
+fn align(len: usize) -> usize {
+    (len + NLMSG_ALIGNTO - 1) & !(NLMSG_ALIGNTO - 1)
+}
+
+// The caller should guarantee the buffer is long enough
+fn read_struct<T: Copy>(buf: &[u8]) -> T {
+    debug_assert!(buf.len() >= std::mem::size_of::<T>());
+    unsafe { std::ptr::read_unaligned(buf.as_ptr() as *const T) }
+}
@@ -0,0 +1,354 @@
+use super::route::handle_requests;
+use super::*;
+use events::{Waiter, WaiterQueue};
+use fs::{IoEvents, IoNotifier};
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Same as Linux, the messages of a dump are packed into datagrams of at most
+// a page, unless a message is larger than that
+const DUMP_DATAGRAM_SIZE: usize = 4096;
+// The max number of the datagrams queued in a socket
+const MAX_QUEUED_DATAGRAMS: usize = 512;
+
+lazy_static! {
+    // The port IDs of the bound netlink sockets
+    static ref PORT_IDS: SgxMutex<HashSet<u32>> = SgxMutex::new(HashSet::new());
+}
+
+/// A netlink socket of NETLINK_ROUTE, whose requests are answered by LibOS.
+/// The replies are queued in the socket as soon as the requests are sent.
+pub struct NetlinkSocket {
+    inner: SgxMutex<Inner>,
+    waiters: WaiterQueue,
+    notifier: IoNotifier,
+    nonblocking: AtomicBool,
+    ino: usize,
+}
+
+struct Inner {
+    port_id: Option<u32>,
+    replies: VecDeque<Vec<u8>>,
+    is_read_shutdown: bool,
+    is_write_shutdown: bool,
+    timeouts: SockTimeouts,
+}
+
+impl NetlinkSocket {
+    pub fn new(flags: FileFlags) -> Self {
+        let inner = Inner {
+            port_id: None,
+            replies: VecDeque::new(),
+            is_read_shutdown: false,
+            is_write_shutdown: false,
+            timeouts: SockTimeouts::default(),
+        };
+        Self {
+            inner: SgxMutex::new(inner),
+            waiters: WaiterQueue::new(),
+            notifier: IoNotifier::new(),
+            nonblocking: AtomicBool::new(flags.contains(FileFlags::SOCK_NONBLOCK)),
+            ino: alloc_socket_ino(),
+        }
+    }
+
+    pub fn ino(&self) -> usize {
+        self.ino
+    }
+
+    pub fn addr(&self) -> NetlinkAddr {
+        NetlinkAddr {
+            port_id: self.inner().port_id.unwrap_or(0),
+            groups: 0,
+        }
+    }
+
+    /// Bind the socket to the port ID of the address, or to a unique port ID
+    /// if it is zero. Multicast groups are not supported.
+    pub fn bind(&self, addr: &NetlinkAddr) -> Result<()> {
+        if addr.groups != 0 {
+            return_errno!(EPERM, "multicast groups are not supported");
+        }
+        let mut inner = self.inner();
+        match inner.port_id {
+            Some(port_id) if addr.port_id == 0 || addr.port_id == port_id => return Ok(()),
+            Some(_) => return_errno!(EINVAL, "the socket is already bound"),
+            None => {}
+        }
+        inner.port_id = Some(alloc_port_id(addr.port_id)?);
+        Ok(())
+    }
+
+    /// Only the kernel can be connected.
+    pub fn connect(&self, addr: &NetlinkAddr) -> Result<()> {
+        if addr.port_id != 0 || addr.groups != 0 {
+            return_errno!(ECONNREFUSED, "only the kernel can be connected");
+        }
+        self.bind(&NetlinkAddr::default())
+    }
+
+    pub fn peer_addr(&self) -> NetlinkAddr {
+        NetlinkAddr::kernel()
+    }
+
+    // The requests are answered at once, so sending never blocks
+    pub fn sendto(
+        &self,
+        buf: &[u8],
+        _flags: SendFlags,
+        addr: &Option<NetlinkAddr>,
+    ) -> Result<usize> {
+        self.send(&[buf], addr.as_ref())
+    }
+
+    // TODO: handle flags except MSG_DONTWAIT, MSG_PEEK and MSG_TRUNC
+    pub fn recvfrom(
+        &self,
+        buf: &mut [u8],
+        flags: RecvFlags,
+    ) -> Result<(usize, Option<NetlinkAddr>)> {
+        let (bytes_recvd, datagram_len) = self.recv(&mut [buf], flags)?;
+        let src = if datagram_len > 0 {
+            Some(NetlinkAddr::kernel())
+        } else {
+            None
+        };
+        Ok((Self::recvd_len(bytes_recvd, datagram_len, flags), src))
+    }
+
+    pub fn sendmsg(&self, msg: &MsgHdr, _flags: SendFlags) -> Result<usize> {
+        let addr = match msg.get_name() {
+            Some(name) if !name.is_empty() => Some(unsafe {
+                NetlinkAddr::try_from_raw(
+                    name.as_ptr() as *const libc::sockaddr,
+                    name.len() as u32,
+                )?
+            }),
+            _ => None,
+        };
+        self.send(msg.get_iovs().as_slices(), addr.as_ref())
+    }
+
+    pub fn recvmsg(&self, msg: &mut MsgHdrMut, flags: RecvFlags) -> Result<usize> {
+        let (bytes_recvd, datagram_len) = self.recv(msg.get_iovs_mut().as_slices_mut(), flags)?;
+        let name_len = match msg.get_name_mut() {
+            Some(name) if datagram_len > 0 => {
+                NetlinkAddr::kernel().copy_to_slice(name);
+                NetlinkAddr::raw_len().min(name.len())
+            }
+            _ => 0,
+        };
+        let mut msg_flags = MsgHdrFlags::empty();
+        if datagram_len > bytes_recvd {
+            msg_flags |= MsgHdrFlags::MSG_TRUNC;
+        }
+        msg.set_name_len(name_len)?;
+        msg.set_control_len(0)?;
+        msg.set_flags(msg_flags);
+        Ok(Self::recvd_len(bytes_recvd, datagram_len, flags))
+    }
+
+    pub fn shutdown(&self, how: HowToShut) -> Result<()> {
+        let mut inner = self.inner();
+        if how.to_shut_read() {
+            inner.is_read_shutdown = true;
+        }
+        if how.to_shut_write() {
+            inner.is_write_shutdown = true;
+        }
+        drop(inner);
+        self.waiters.dequeue_and_wake_all();
+        self.notifier.broadcast(&self.poll());
+        Ok(())
+    }
+
+    /// Send the requests to the kernel, whose replies are queued in the
+    /// socket.
+    pub(super) fn send(&self, bufs: &[&[u8]], addr: Option<&NetlinkAddr>) -> Result<usize> {
+        if let Some(addr) = addr {
+            if addr.port_id != 0 {
+                return_errno!(ECONNREFUSED, "only the kernel can be sent to");
+            }
+            if addr.groups != 0 {
+                return_errno!(EPERM, "multicast groups are not supported");
+            }
+        }
+        // Same as Linux, an unbound socket is bound on sending
+        self.bind(&NetlinkAddr::default())?;
+
+        let buf = bufs.concat();
+        let mut inner = self.inner();
+        if inner.is_write_shutdown {
+            return_errno!(EPIPE, "the socket has been shutdown for writing");
+        }
+        let port_id = inner.port_id.unwrap();
+        for reply in handle_requests(&buf, port_id) {
+            for datagram in pack_messages(reply) {
+                if inner.replies.len() >= MAX_QUEUED_DATAGRAMS {
+                    // Same as Linux, the replies are dropped if the socket
+                    // is full
+                    warn!("the replies of the netlink socket are dropped");
+                    break;
+                }
+                inner.replies.push_back(datagram);
+            }
+        }
+        drop(inner);
+        self.waiters.dequeue_and_wake_all();
+        self.notifier.broadcast(&IoEvents::IN);
+        Ok(buf.len())
+    }
+
+    /// Receive a datagram into the buffers, returning the number of the bytes
+    /// received and the length of the datagram, which is zero if the socket
+    /// has been shutdown for reading.
+    pub(super) fn recv(&self, bufs: &mut [&mut [u8]], flags: RecvFlags) -> Result<(usize, usize)> {
+        let nonblocking = self.nonblocking() || flags.contains(RecvFlags::MSG_DONTWAIT);
+        let mut timeout = self.inner().timeouts.recv;
+        let waiter = Waiter::new();
+        let datagram = loop {
+            let mut inner = self.inner();
+            let datagram = if flags.contains(RecvFlags::MSG_PEEK) {
+                inner.replies.front().cloned()
+            } else {
+                inner.replies.pop_front()
+            };
+            if let Some(datagram) = datagram {
+                break datagram;
+            }
+            if inner.is_read_shutdown {
+                return Ok((0, 0));
+            }
+            if nonblocking {
+                return_errno!(EAGAIN, "no replies are received");
+            }
+
+            // Enqueue before unlocking the socket, so that no wakeup is missed
+            self.waiters.reset_and_enqueue(&waiter);
+            drop(inner);
+            sock_wait(&waiter, timeout.as_mut())?;
+        };
+
+        // Same as Linux, the bytes that do not fit in the buffers are discarded
+        let mut bytes_recvd = 0;
+        for buf in bufs.iter_mut() {
+            let len = buf.len().min(datagram.len() - bytes_recvd);
+            buf[..len].copy_from_slice(&datagram[bytes_recvd..bytes_recvd + len]);
+            bytes_recvd += len;
+        }
+        Ok((bytes_recvd, datagram.len()))
+    }
+
+    // Same as Linux, the real length of a truncated datagram is returned given
+    // MSG_TRUNC
+    fn recvd_len(bytes_recvd: usize, datagram_len: usize, flags: RecvFlags) -> usize {
+        if flags.contains(RecvFlags::MSG_TRUNC) {
+            datagram_len
+        } else {
+            bytes_recvd
+        }
+    }
+
+    pub(super) fn bytes_to_read(&self) -> usize {
+        self.inner()
+            .replies
+            .front()
+            .map(|datagram| datagram.len())
+            .unwrap_or(0)
+    }
+
+    pub(super) fn poll(&self) -> IoEvents {
+        let inner = self.inner();
+        let mut events = IoEvents::empty();
+        if !inner.replies.is_empty() || inner.is_read_shutdown {
+            events |= IoEvents::IN;
+        }
+        if !inner.is_write_shutdown {
+            events |= IoEvents::OUT;
+        }
+        events
+    }
+
+    pub(super) fn notifier(&self) -> &IoNotifier {
+        &self.notifier
+    }
+
+    pub(super) fn nonblocking(&self) -> bool {
+        self.nonblocking.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn set_nonblocking(&self, nonblocking: bool) {
+        self.nonblocking.store(nonblocking, Ordering::Relaxed);
+    }
+
+    fn inner(&self) -> SgxMutexGuard<'_, Inner> {
+        self.inner.lock().unwrap()
+    }
+}
+
+// Pack the messages of a reply into as few datagrams as possible
+fn pack_messages(msgs: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+    let mut datagrams: Vec<Vec<u8>> = Vec::new();
+    for msg in msgs {
+        match datagrams.last_mut() {
+            Some(datagram) if datagram.len() + msg.len() <= DUMP_DATAGRAM_SIZE => {
+                datagram.extend_from_slice(&msg);
+            }
+            _ => datagrams.push(msg),
+        }
+    }
+    datagrams
+}
+
+// Same as Linux, the PID of the process is used as the port ID of its first
+// socket, and the others get the IDs counting down from -4096
+fn alloc_port_id(port_id: u32) -> Result<u32> {
+    let mut port_ids = PORT_IDS.lock().unwrap();
+    if port_id != 0 {
+        if !port_ids.insert(port_id) {
+            return_errno!(EADDRINUSE, "the port ID is in use");
+        }
+        return Ok(port_id);
+    }
+
+    let pid = current!().process().pid() as u32;
+    let port_id = if !port_ids.contains(&pid) {
+        pid
+    } else {
+        (1..)
+            .map(|i: u32| (-4096i32 as u32).wrapping_sub(i))
+            .find(|port_id| !port_ids.contains(port_id))
+            .unwrap()
+    };
+    port_ids.insert(port_id);
+    Ok(port_id)
+}
+
+// Sending never blocks, so only the receiving timeout takes effect
+impl SockTimeoutsExt for NetlinkSocket {
+    fn timeouts(&self) -> SockTimeouts {
+        self.inner().timeouts
+    }
+
+    fn set_timeouts(&self, timeouts: SockTimeouts) {
+        self.inner().timeouts = timeouts;
+    }
+}
+
+impl Debug for NetlinkSocket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NetlinkSocket")
+            .field("addr", &self.addr())
+            .field("nonblocking", &self.nonblocking())
+            .finish()
+    }
+}
+
+impl Drop for NetlinkSocket {
+    fn drop(&mut self) {
+        if let Some(port_id) = self.inner().port_id {
+            PORT_IDS.lock().unwrap().remove(&port_id);
+        }
+    }
+}
@@ -48,6 +48,12 @@ impl SockAddr {
                     return_errno!(EINVAL, "wrong ipv6 address length.");
                 }
             }
+            AddressFamily::VSOCK => {
+                // The size of struct sockaddr_vm
+                if addr_len < 16 {
+                    return_errno!(EINVAL, "short vsock address.");
+                }
+            }
             _ => warn!("address family not checked"),
         }
 
@@ -0,0 +1,297 @@
+//! The common socket options, which are described by a table, so that they
+//! are validated and subject to the policy of the config in one place.
+//!
+//! The options of host sockets are passed through to the host after they are
+//! validated, while those of the sockets handled inside the enclave are
+//! tracked by the sockets, so that getsockopt returns what setsockopt sets.
+//! The options not in the table are handled as before.
+
+use super::*;
+use crate::config::{ConfigSockOptPolicy, LIBOS_CONFIG};
+
+const SOL_SOCKET: i32 = 1;
+const IPPROTO_IP: i32 = 0;
+const IPPROTO_TCP: i32 = 6;
+
+/// The description of a socket option.
+#[derive(Debug)]
+pub struct SockOpt {
+    pub name: &'static str,
+    pub level: i32,
+    pub optname: i32,
+    kind: SockOptKind,
+    scope: SockOptScope,
+    // The value before the option is set, which is the same as Linux
+    default: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SockOptKind {
+    // An int, where any non-zero value is stored as 1
+    Bool,
+    // An int in the inclusive range
+    Int(i32, i32),
+    // The `struct linger`
+    Linger,
+}
+
+// The sockets that the option applies to
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SockOptScope {
+    Any,
+    Inet,
+    InetStream,
+}
+
+static SOCK_OPTS: &[SockOpt] = &[
+    SockOpt {
+        name: "SO_REUSEADDR",
+        level: SOL_SOCKET,
+        optname: 2,
+        kind: SockOptKind::Bool,
+        scope: SockOptScope::Any,
+        default: 0,
+    },
+    SockOpt {
+        name: "SO_KEEPALIVE",
+        level: SOL_SOCKET,
+        optname: 9,
+        kind: SockOptKind::Bool,
+        scope: SockOptScope::Any,
+        default: 0,
+    },
+    SockOpt {
+        name: "SO_LINGER",
+        level: SOL_SOCKET,
+        optname: 13,
+        kind: SockOptKind::Linger,
+        scope: SockOptScope::Any,
+        default: 0,
+    },
+    SockOpt {
+        name: "SO_REUSEPORT",
+        level: SOL_SOCKET,
+        optname: 15,
+        kind: SockOptKind::Bool,
+        scope: SockOptScope::Any,
+        default: 0,
+    },
+    SockOpt {
+        name: "IP_TOS",
+        level: IPPROTO_IP,
+        optname: 1,
+        kind: SockOptKind::Int(0, 255),
+        scope: SockOptScope::Inet,
+        default: 0,
+    },
+    SockOpt {
+        name: "TCP_NODELAY",
+        level: IPPROTO_TCP,
+        optname: 1,
+        kind: SockOptKind::Bool,
+        scope: SockOptScope::InetStream,
+        default: 0,
+    },
+    // Same as Linux, the keep-alive parameters are bounded
+    SockOpt {
+        name: "TCP_KEEPIDLE",
+        level: IPPROTO_TCP,
+        optname: 4,
+        kind: SockOptKind::Int(1, 32767),
+        scope: SockOptScope::InetStream,
+        default: 7200,
+    },
+    SockOpt {
+        name: "TCP_KEEPINTVL",
+        level: IPPROTO_TCP,
+        optname: 5,
+        kind: SockOptKind::Int(1, 32767),
+        scope: SockOptScope::InetStream,
+        default: 75,
+    },
+    SockOpt {
+        name: "TCP_KEEPCNT",
+        level: IPPROTO_TCP,
+        optname: 6,
+        kind: SockOptKind::Int(1, 127),
+        scope: SockOptScope::InetStream,
+        default: 9,
+    },
+];
+
+/// The `struct linger` of SO_LINGER.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+#[allow(non_camel_case_types)]
+pub struct linger_t {
+    pub l_onoff: i32,
+    pub l_linger: i32,
+}
+
+impl SockOpt {
+    /// Find the option in the table.
+    pub fn find(level: i32, optname: i32) -> Option<&'static SockOpt> {
+        SOCK_OPTS
+            .iter()
+            .find(|opt| opt.level == level && opt.optname == optname)
+    }
+
+    /// Whether the name is of an option in the table, as used by the config.
+    pub fn is_known_name(name: &str) -> bool {
+        SOCK_OPTS.iter().any(|opt| opt.name == name)
+    }
+
+    /// Check the option against the policy of the config. Same as an option
+    /// not supported, a denied option fails with ENOPROTOOPT, which most
+    /// applications tolerate.
+    pub fn check_policy(&self) -> Result<()> {
+        match LIBOS_CONFIG.network.socket_options.get(self.name) {
+            Some(ConfigSockOptPolicy::Deny) => {
+                return_errno!(ENOPROTOOPT, "the socket option is denied by the config")
+            }
+            Some(ConfigSockOptPolicy::Allow) | None => Ok(()),
+        }
+    }
+
+    /// Validate the value of setsockopt.
+    pub fn validate(&self, optval: &[u8]) -> Result<()> {
+        self.parse(optval).map(|_| ())
+    }
+
+    /// The length of the value of getsockopt.
+    pub fn len(&self) -> usize {
+        match self.kind {
+            SockOptKind::Bool | SockOptKind::Int(..) => std::mem::size_of::<i32>(),
+            SockOptKind::Linger => std::mem::size_of::<linger_t>(),
+        }
+    }
+
+    // Parse the value into the form kept by the sockets
+    fn parse(&self, optval: &[u8]) -> Result<Vec<u8>> {
+        if optval.len() < self.len() {
+            return_errno!(EINVAL, "the option length is too short");
+        }
+        let optval = &optval[..self.len()];
+        match self.kind {
+            SockOptKind::Bool => {
+                let val = read_i32(optval);
+                Ok(((val != 0) as i32).to_ne_bytes().to_vec())
+            }
+            SockOptKind::Int(min, max) => {
+                let val = read_i32(optval);
+                if val < min || val > max {
+                    return_errno!(EINVAL, "the option value is out of range");
+                }
+                Ok(optval.to_vec())
+            }
+            SockOptKind::Linger => {
+                let linger = unsafe { *(optval.as_ptr() as *const linger_t) };
+                // Same as Linux, a negative timeout is taken as the maximum
+                let linger = linger_t {
+                    l_onoff: (linger.l_onoff != 0) as i32,
+                    l_linger: if linger.l_linger < 0 {
+                        i32::max_value()
+                    } else {
+                        linger.l_linger
+                    },
+                };
+                let bytes = unsafe {
+                    std::slice::from_raw_parts(
+                        &linger as *const linger_t as *const u8,
+                        std::mem::size_of::<linger_t>(),
+                    )
+                };
+                Ok(bytes.to_vec())
+            }
+        }
+    }
+
+    fn default_value(&self) -> Vec<u8> {
+        let mut bytes = self.default.to_ne_bytes().to_vec();
+        bytes.resize(self.len(), 0);
+        bytes
+    }
+
+    fn applies_to(&self, domain: AddressFamily, socket_type: SocketType) -> bool {
+        match self.scope {
+            SockOptScope::Any => true,
+            SockOptScope::Inet => domain == AddressFamily::INET,
+            SockOptScope::InetStream => {
+                domain == AddressFamily::INET && socket_type == SocketType::STREAM
+            }
+        }
+    }
+}
+
+/// The values of the options in the table set on a socket handled inside the
+/// enclave.
+#[derive(Debug, Clone)]
+pub struct SockOpts {
+    domain: AddressFamily,
+    socket_type: SocketType,
+    values: HashMap<&'static str, Vec<u8>>,
+}
+
+impl SockOpts {
+    pub fn new(domain: AddressFamily, socket_type: SocketType) -> Self {
+        Self {
+            domain,
+            socket_type,
+            values: HashMap::new(),
+        }
+    }
+
+    pub fn set(&mut self, opt: &'static SockOpt, optval: &[u8]) -> Result<()> {
+        self.check_scope(opt)?;
+        let val = opt.parse(optval)?;
+        self.values.insert(opt.name, val);
+        Ok(())
+    }
+
+    pub fn get(&self, opt: &'static SockOpt) -> Result<Vec<u8>> {
+        self.check_scope(opt)?;
+        Ok(self
+            .values
+            .get(opt.name)
+            .cloned()
+            .unwrap_or_else(|| opt.default_value()))
+    }
+
+    /// The options that have been set, with their values.
+    pub fn options(&self) -> Vec<(&'static SockOpt, Vec<u8>)> {
+        SOCK_OPTS
+            .iter()
+            .filter_map(|opt| self.values.get(opt.name).map(|val| (opt, val.clone())))
+            .collect()
+    }
+
+    /// Get the value of SO_LINGER.
+    pub fn linger(&self) -> linger_t {
+        match self.values.get("SO_LINGER") {
+            Some(val) => unsafe { *(val.as_ptr() as *const linger_t) },
+            None => linger_t::default(),
+        }
+    }
+
+    fn check_scope(&self, opt: &SockOpt) -> Result<()> {
+        if opt.applies_to(self.domain, self.socket_type) {
+            return Ok(());
+        }
+        // Same as Linux, unix sockets support no option of other levels
+        if self.domain == AddressFamily::LOCAL {
+            return_errno!(EOPNOTSUPP, "the option is not supported by unix sockets");
+        }
+        return_errno!(ENOPROTOOPT, "the option does not apply to the socket")
+    }
+}
+
+/// The sockets that track the options in the table inside the enclave.
+pub trait SockOptsExt {
+    fn sock_opts(&self) -> &SgxMutex<SockOpts>;
+}
+
+fn read_i32(bytes: &[u8]) -> i32 {
+    let mut buf = [0; 4];
+    buf.copy_from_slice(&bytes[..4]);
+    i32::from_ne_bytes(buf)
+}
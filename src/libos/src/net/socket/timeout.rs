@@ -0,0 +1,88 @@
+//! The timeouts of the blocking operations of the sockets handled inside the
+//! enclave, i.e., SO_RCVTIMEO and SO_SNDTIMEO. The options of host sockets are
+//! handled by the host.
+
+use super::*;
+use events::Waiter;
+use std::time::Duration;
+use time::timeval_t;
+
+const SOL_SOCKET: i32 = 1;
+const SO_RCVTIMEO: i32 = 20;
+const SO_SNDTIMEO: i32 = 21;
+
+/// The timeouts of receiving and sending, where None means blocking
+/// indefinitely.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SockTimeouts {
+    pub recv: Option<Duration>,
+    pub send: Option<Duration>,
+}
+
+impl SockTimeouts {
+    pub fn is_timeout_option(level: i32, optname: i32) -> bool {
+        level == SOL_SOCKET && (optname == SO_RCVTIMEO || optname == SO_SNDTIMEO)
+    }
+
+    /// Set a timeout from the `struct timeval` of setsockopt. Same as Linux, a
+    /// zero timeout means blocking indefinitely, while a negative one means
+    /// not blocking at all.
+    pub fn set_option(&mut self, optname: i32, optval: &[u8]) -> Result<()> {
+        if optval.len() < std::mem::size_of::<timeval_t>() {
+            return_errno!(EINVAL, "the option length is too short");
+        }
+        let tv = unsafe { *(optval.as_ptr() as *const timeval_t) };
+        let timeout = if tv.sec() < 0 {
+            Some(Duration::from_secs(0))
+        } else {
+            if tv.validate().is_err() {
+                return_errno!(EDOM, "invalid timeout");
+            }
+            Some(tv.as_duration()).filter(|timeout| *timeout != Duration::from_secs(0))
+        };
+        match optname {
+            SO_RCVTIMEO => self.recv = timeout,
+            SO_SNDTIMEO => self.send = timeout,
+            _ => return_errno!(ENOPROTOOPT, "not a timeout option"),
+        }
+        Ok(())
+    }
+
+    /// Get a timeout as the `struct timeval` of getsockopt.
+    pub fn get_option(&self, optname: i32) -> Result<timeval_t> {
+        let timeout = match optname {
+            SO_RCVTIMEO => self.recv,
+            SO_SNDTIMEO => self.send,
+            _ => return_errno!(ENOPROTOOPT, "not a timeout option"),
+        };
+        Ok(timeout.unwrap_or_default().into())
+    }
+
+    /// The timeouts that have been set, as the level, the name and the value
+    /// of the options. Not blocking at all is taken as the shortest timeout,
+    /// since a zero timeout means blocking indefinitely.
+    pub fn options(&self) -> Vec<(i32, i32, timeval_t)> {
+        [(SO_RCVTIMEO, self.recv), (SO_SNDTIMEO, self.send)]
+            .iter()
+            .filter_map(|(optname, timeout)| {
+                let timeout = timeout.map(|timeout| timeout.max(Duration::from_micros(1)))?;
+                Some((SOL_SOCKET, *optname, timeout.into()))
+            })
+            .collect()
+    }
+}
+
+/// The sockets whose timeouts are handled inside the enclave.
+pub trait SockTimeoutsExt {
+    fn timeouts(&self) -> SockTimeouts;
+    fn set_timeouts(&self, timeouts: SockTimeouts);
+}
+
+/// Put the current thread to sleep as a blocking socket operation does, which
+/// fails with EAGAIN if the remaining timeout runs out.
+pub fn sock_wait(waiter: &Waiter, timeout: Option<&mut Duration>) -> Result<()> {
+    waiter.wait_mut(timeout).map_err(|e| match e.errno() {
+        Errno::ETIMEDOUT => errno!(EAGAIN, "timed out"),
+        _ => e,
+    })
+}
@@ -1,6 +1,7 @@
 use super::*;
 use std::path::{Path, PathBuf};
-use std::{cmp, mem, slice, str};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::{cmp, mem, slice};
 
 const MAX_PATH_LEN: usize = 108;
 const SUN_FAMILY_LEN: usize = mem::size_of::<libc::sa_family_t>();
@@ -11,7 +12,9 @@ lazy_static! {
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Addr {
     File(UnixPath),
-    Abstract(String),
+    /// A name in the abstract namespace, which is any bytes following the
+    /// leading null byte of sun_path, including null bytes.
+    Abstract(Vec<u8>),
 }
 
 impl Addr {
@@ -24,7 +27,7 @@ impl Addr {
     ) -> Result<Self> {
         let addr_len = addr_len as usize;
 
-        // TODO: support autobind to validate when addr_len == SUN_FAMILY_LEN
+        // The address of only the family is for autobind, see `Addr::autobind`
         if addr_len <= SUN_FAMILY_LEN {
             return_errno!(EINVAL, "the address is too short.");
         }
@@ -45,9 +48,7 @@ impl Addr {
             let path_slice =
                 slice::from_raw_parts(path_ptr as *const u8, addr_len - *SUN_PATH_OFFSET - 1);
 
-            Ok(Self::Abstract(
-                str::from_utf8(&path_slice).unwrap().to_string(),
-            ))
+            Ok(Self::Abstract(path_slice.to_vec()))
         } else {
             let path_cstr = CStr::from_ptr(sun_path.as_ptr());
             if path_cstr.to_bytes_with_nul().len() > MAX_PATH_LEN {
@@ -67,17 +68,45 @@ impl Addr {
         copied
     }
 
-    pub fn raw_len(&self) -> usize {
-        /// The '/0' at the end of Self::File counts
-        self.path_str().len()
-            + 1
-            + *SUN_PATH_OFFSET
+    /// Check whether the address is only the family, with which a socket is
+    /// autobound.
+    pub fn is_autobind_addr(addr_len: libc::socklen_t) -> bool {
+        addr_len as usize == SUN_FAMILY_LEN
     }
 
-    pub fn path_str(&self) -> &str {
+    /// Bind by `bind` to a unique name of five hex digits in the abstract
+    /// namespace, same as Linux does when binding to an address of only the
+    /// family.
+    pub fn autobind<F: Fn(&Addr) -> Result<()>>(bind: F) -> Result<()> {
+        const MAX_AUTOBIND_NAMES: u32 = 0x100000;
+        static NEXT_AUTOBIND_NAME: AtomicU32 = AtomicU32::new(0);
+
+        for _ in 0..MAX_AUTOBIND_NAMES {
+            let name = NEXT_AUTOBIND_NAME.fetch_add(1, Ordering::Relaxed) % MAX_AUTOBIND_NAMES;
+            let addr = Addr::Abstract(format!("{:05x}", name).into_bytes());
+            match bind(&addr) {
+                Err(e) if e.errno() == EADDRINUSE => continue,
+                res => return res,
+            }
+        }
+        return_errno!(ENOSPC, "no name is available for autobind");
+    }
+
+    /// The key of the address in its namespace, i.e., the absolute path of a
+    /// file or the abstract name.
+    pub fn key(&self) -> Vec<u8> {
         match self {
-            Self::File(unix_path) => &unix_path.path_str(),
-            Self::Abstract(path) => &path,
+            Self::File(unix_path) => unix_path.absolute().into_bytes(),
+            Self::Abstract(name) => name.clone(),
+        }
+    }
+
+    pub fn raw_len(&self) -> usize {
+        match self {
+            // The '/0' at the end of Self::File counts
+            Self::File(unix_path) => unix_path.path_str().len() + 1 + *SUN_PATH_OFFSET,
+            // The leading '/0' of Self::Abstract counts
+            Self::Abstract(name) => name.len() + 1 + *SUN_PATH_OFFSET,
         }
     }
 
@@ -96,11 +125,11 @@ impl Addr {
                     .copy_from_slice(unsafe { &*(path_str.as_bytes() as *const _ as *const [i8]) });
                 buf_len + *SUN_PATH_OFFSET + 1
             }
-            Self::Abstract(path_str) => {
+            Self::Abstract(name) => {
                 addr.sun_path[0] = 0;
-                let buf_len = path_str.len() + 1;
+                let buf_len = name.len() + 1;
                 addr.sun_path[1..buf_len]
-                    .copy_from_slice(unsafe { &*(path_str.as_bytes() as *const _ as *const [i8]) });
+                    .copy_from_slice(unsafe { &*(name.as_slice() as *const _ as *const [i8]) });
                 buf_len + *SUN_PATH_OFFSET
             }
         };
@@ -0,0 +1,204 @@
+//! The ancillary data of UNIX sockets, i.e., the control messages of sendmsg
+//! and recvmsg, of which only SCM_RIGHTS is supported.
+//!
+//! The files sent by SCM_RIGHTS are in transit along with the bytes sent by
+//! the same sendmsg, and are installed into the file table of the receiver
+//! when the first one of the bytes is received by recvmsg.
+
+use super::*;
+use std::mem::size_of;
+
+const SOL_SOCKET: i32 = 1;
+const SCM_RIGHTS: i32 = 1;
+// Same as Linux, the maximum number of files in a SCM_RIGHTS message
+const SCM_MAX_FD: usize = 253;
+
+/// The `struct cmsghdr` of Linux, which is followed by the data of the
+/// control message.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+#[allow(non_camel_case_types)]
+struct cmsghdr {
+    cmsg_len: usize,
+    cmsg_level: i32,
+    cmsg_type: i32,
+}
+
+const CMSG_HDR_LEN: usize = size_of::<cmsghdr>();
+
+fn cmsg_align(len: usize) -> usize {
+    align_up(len, size_of::<usize>())
+}
+
+/// Get the files of the SCM_RIGHTS messages in the control buffer of sendmsg.
+pub fn files_from_control(control: &[u8]) -> Result<Vec<FileRef>> {
+    let current = current!();
+    let mut files = Vec::new();
+    let mut offset = 0;
+    while offset + CMSG_HDR_LEN <= control.len() {
+        let hdr = unsafe { (control[offset..].as_ptr() as *const cmsghdr).read_unaligned() };
+        if hdr.cmsg_len < CMSG_HDR_LEN || hdr.cmsg_len > control.len() - offset {
+            return_errno!(EINVAL, "invalid length of the control message");
+        }
+        let data = &control[offset + CMSG_HDR_LEN..offset + hdr.cmsg_len];
+        match (hdr.cmsg_level, hdr.cmsg_type) {
+            (SOL_SOCKET, SCM_RIGHTS) => {
+                let nfds = data.len() / size_of::<FileDesc>();
+                if files.len() + nfds > SCM_MAX_FD {
+                    return_errno!(EINVAL, "too many files to send");
+                }
+                for fd_bytes in data.chunks_exact(size_of::<FileDesc>()) {
+                    let mut fd = [0; size_of::<FileDesc>()];
+                    fd.copy_from_slice(fd_bytes);
+                    files.push(current.file(FileDesc::from_ne_bytes(fd))?);
+                }
+            }
+            _ => return_errno!(EINVAL, "unsupported control message"),
+        }
+        offset += cmsg_align(hdr.cmsg_len);
+    }
+    Ok(files)
+}
+
+/// Install the received files into the file table, and write their fds as a
+/// SCM_RIGHTS message into the control buffer of recvmsg, returning the length
+/// of the control messages and the flags of the message.
+///
+/// Same as Linux, the files that cannot be installed or fit in the buffer are
+/// discarded, which is indicated by MSG_CTRUNC.
+pub fn files_to_control(
+    files: Vec<FileRef>,
+    control: Option<&mut [u8]>,
+    close_on_spawn: bool,
+) -> Result<(usize, MsgHdrFlags)> {
+    if files.is_empty() {
+        return Ok((0, MsgHdrFlags::empty()));
+    }
+    let control = match control {
+        Some(control) if control.len() >= CMSG_HDR_LEN + size_of::<FileDesc>() => control,
+        _ => return Ok((0, MsgHdrFlags::MSG_CTRUNC)),
+    };
+
+    let max_nfds = (control.len() - CMSG_HDR_LEN) / size_of::<FileDesc>();
+    let mut flags = MsgHdrFlags::empty();
+    if files.len() > max_nfds {
+        flags |= MsgHdrFlags::MSG_CTRUNC;
+    }
+    let current = current!();
+    let mut nfds = 0;
+    for file in files.into_iter().take(max_nfds) {
+        let fd = match current.add_file(file, close_on_spawn) {
+            Ok(fd) => fd,
+            Err(_) => {
+                flags |= MsgHdrFlags::MSG_CTRUNC;
+                break;
+            }
+        };
+        let fd_offset = CMSG_HDR_LEN + nfds * size_of::<FileDesc>();
+        control[fd_offset..fd_offset + size_of::<FileDesc>()].copy_from_slice(&fd.to_ne_bytes());
+        nfds += 1;
+    }
+    if nfds == 0 {
+        return Ok((0, flags));
+    }
+
+    let cmsg_len = CMSG_HDR_LEN + nfds * size_of::<FileDesc>();
+    let hdr = cmsghdr {
+        cmsg_len,
+        cmsg_level: SOL_SOCKET,
+        cmsg_type: SCM_RIGHTS,
+    };
+    unsafe {
+        (control.as_mut_ptr() as *mut cmsghdr).write_unaligned(hdr);
+    }
+    Ok((cmsg_align(cmsg_len).min(control.len()), flags))
+}
+
+/// The files in transit in one direction of a stream, each group of which is
+/// located by the position of the first byte sent along with it.
+pub struct FilesInTransit {
+    inner: SgxMutex<FilesInTransitInner>,
+}
+
+struct FilesInTransitInner {
+    // The numbers of the bytes written to and read from the stream
+    written: usize,
+    read: usize,
+    files: VecDeque<(usize, Vec<FileRef>)>,
+}
+
+impl FilesInTransit {
+    pub fn new() -> Self {
+        let inner = FilesInTransitInner {
+            written: 0,
+            read: 0,
+            files: VecDeque::new(),
+        };
+        Self {
+            inner: SgxMutex::new(inner),
+        }
+    }
+
+    /// Write the bytes by `write` along with the files, which are discarded
+    /// if the write fails.
+    pub fn write_with<F>(&self, files: Vec<FileRef>, write: F) -> Result<usize>
+    where
+        F: FnOnce() -> Result<usize>,
+    {
+        let pos = if files.is_empty() {
+            None
+        } else {
+            let mut inner = self.inner.lock().unwrap();
+            let pos = inner.written;
+            inner.files.push_back((pos, files));
+            Some(pos)
+        };
+
+        let res = write();
+
+        let mut inner = self.inner.lock().unwrap();
+        match res {
+            Ok(len) => inner.written += len,
+            Err(_) => {
+                if let Some(idx) = pos.and_then(|pos| inner.files.iter().rposition(|f| f.0 == pos))
+                {
+                    inner.files.remove(idx);
+                }
+            }
+        }
+        res
+    }
+
+    /// Read the bytes by `read` given the maximum number of bytes to read,
+    /// returning the files sent along with the bytes read.
+    ///
+    /// The bytes sent along with different groups of files are not read at
+    /// once, so that the files are received with the right bytes.
+    pub fn read_with<F>(&self, read: F) -> Result<(usize, Vec<FileRef>)>
+    where
+        F: FnOnce(Option<usize>) -> Result<usize>,
+    {
+        let max_len = {
+            let inner = self.inner.lock().unwrap();
+            inner
+                .files
+                .iter()
+                .map(|(pos, _)| *pos)
+                .find(|pos| *pos > inner.read)
+                .map(|pos| pos - inner.read)
+        };
+
+        let len = read(max_len)?;
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.read += len;
+        let mut files = Vec::new();
+        while let Some((pos, _)) = inner.files.front() {
+            if *pos >= inner.read {
+                break;
+            }
+            files.extend(inner.files.pop_front().unwrap().1);
+        }
+        Ok((len, files))
+    }
+}
@@ -0,0 +1,57 @@
+use super::receiver::Receiver;
+use super::*;
+use std::collections::btree_map::BTreeMap;
+use std::sync::Weak;
+
+lazy_static! {
+    pub(super) static ref ADDRESS_SPACE: AddressSpace = AddressSpace::new();
+}
+
+/// The addresses of the bound datagram sockets, each of which refers to the
+/// receiver of the socket.
+///
+/// The address space is separate from that of the stream sockets, so a
+/// datagram socket can never be connected by a stream socket, and vice versa.
+pub struct AddressSpace {
+    file: SgxMutex<BTreeMap<Vec<u8>, Weak<Receiver>>>,
+    abstr: SgxMutex<BTreeMap<Vec<u8>, Weak<Receiver>>>,
+}
+
+impl AddressSpace {
+    pub fn new() -> Self {
+        Self {
+            file: SgxMutex::new(BTreeMap::new()),
+            abstr: SgxMutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn add_receiver(&self, addr: &Addr, receiver: &Arc<Receiver>) -> Result<()> {
+        let key = addr.key();
+        let mut space = self.get_space(addr);
+        if space.contains_key(&key) {
+            return_errno!(EADDRINUSE, "the addr is already bound");
+        }
+        space.insert(key, Arc::downgrade(receiver));
+        Ok(())
+    }
+
+    pub fn get_receiver(&self, addr: &Addr) -> Result<Arc<Receiver>> {
+        let space = self.get_space(addr);
+        space
+            .get(&addr.key())
+            .and_then(|receiver| receiver.upgrade())
+            .ok_or_else(|| errno!(ECONNREFUSED, "no socket is bound to the address"))
+    }
+
+    pub fn remove_addr(&self, addr: &Addr) {
+        let mut space = self.get_space(addr);
+        space.remove(&addr.key());
+    }
+
+    fn get_space(&self, addr: &Addr) -> SgxMutexGuard<'_, BTreeMap<Vec<u8>, Weak<Receiver>>> {
+        match addr {
+            Addr::File(_) => self.file.lock().unwrap(),
+            Addr::Abstract(_) => self.abstr.lock().unwrap(),
+        }
+    }
+}
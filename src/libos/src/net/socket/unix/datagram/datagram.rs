@@ -0,0 +1,341 @@
+use super::address_space::ADDRESS_SPACE;
+use super::ancillary::{files_from_control, files_to_control};
+use super::receiver::{Message, Receiver};
+use super::*;
+use fs::IoEvents;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Weak;
+
+/// SOCK_DGRAM Unix socket. A datagram is sent to the receiver of the socket
+/// bound to the destination address, or that of the peer set by connect or
+/// socketpair, keeping the boundaries of the messages.
+pub struct Datagram {
+    inner: SgxMutex<Inner>,
+    receiver: Arc<Receiver>,
+    nonblocking: AtomicBool,
+    ino: usize,
+    sock_opts: SgxMutex<SockOpts>,
+}
+
+struct Inner {
+    addr: Option<Addr>,
+    peer: Option<Peer>,
+    is_write_shutdown: bool,
+    timeouts: SockTimeouts,
+}
+
+/// The peer of a connected datagram socket.
+#[derive(Clone)]
+struct Peer {
+    addr: Option<Addr>,
+    receiver: Weak<Receiver>,
+}
+
+impl Datagram {
+    pub fn new(flags: FileFlags) -> Self {
+        let inner = Inner {
+            addr: None,
+            peer: None,
+            is_write_shutdown: false,
+            timeouts: SockTimeouts::default(),
+        };
+        Self {
+            inner: SgxMutex::new(inner),
+            receiver: Arc::new(Receiver::new()),
+            nonblocking: AtomicBool::new(flags.contains(FileFlags::SOCK_NONBLOCK)),
+            ino: alloc_socket_ino(),
+            sock_opts: SgxMutex::new(SockOpts::new(AddressFamily::LOCAL, SocketType::DGRAM)),
+        }
+    }
+
+    pub fn socketpair(flags: FileFlags) -> Result<(Self, Self)> {
+        let socket_a = Self::new(flags);
+        let socket_b = Self::new(flags);
+        socket_a.inner().peer = Some(Peer {
+            addr: None,
+            receiver: Arc::downgrade(&socket_b.receiver),
+        });
+        socket_b.inner().peer = Some(Peer {
+            addr: None,
+            receiver: Arc::downgrade(&socket_a.receiver),
+        });
+        Ok((socket_a, socket_b))
+    }
+
+    pub fn ino(&self) -> usize {
+        self.ino
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.inner().peer.is_some()
+    }
+
+    pub fn addr(&self) -> Option<Addr> {
+        self.inner().addr.clone()
+    }
+
+    pub fn peer_addr(&self) -> Result<Addr> {
+        if let Some(peer) = &self.inner().peer {
+            if let Some(addr) = &peer.addr {
+                return Ok(addr.clone());
+            }
+        }
+        return_errno!(ENOTCONN, "the socket is not connected");
+    }
+
+    // TODO: create the corresponding file in the fs
+    pub fn bind(&self, addr: &Addr) -> Result<()> {
+        let mut inner = self.inner();
+        if inner.addr.is_some() {
+            return_errno!(EINVAL, "the socket is already bound");
+        }
+        ADDRESS_SPACE.add_receiver(addr, &self.receiver)?;
+        inner.addr = Some(addr.clone());
+        Ok(())
+    }
+
+    /// Bind the socket to a unique name in the abstract namespace.
+    pub fn autobind(&self) -> Result<()> {
+        Addr::autobind(|addr| self.bind(addr))
+    }
+
+    /// Set the default destination of the datagrams, which can be changed by
+    /// connecting again.
+    pub fn connect(&self, addr: &Addr) -> Result<()> {
+        debug!("connect to {:?}", addr);
+
+        let receiver = ADDRESS_SPACE.get_receiver(addr)?;
+        self.inner().peer = Some(Peer {
+            addr: Some(addr.clone()),
+            receiver: Arc::downgrade(&receiver),
+        });
+        Ok(())
+    }
+
+    // TODO: handle flags except MSG_DONTWAIT
+    pub fn sendto(&self, buf: &[u8], flags: SendFlags, addr: &Option<Addr>) -> Result<usize> {
+        self.send(&[buf], Vec::new(), addr.as_ref(), flags)
+    }
+
+    pub fn recvfrom(&self, buf: &mut [u8], flags: RecvFlags) -> Result<(usize, Option<Addr>)> {
+        let (bytes_recvd, msg) = match self.recv(&mut [buf], flags)? {
+            Some(res) => res,
+            None => return Ok((0, None)),
+        };
+        Ok((Self::recvd_len(bytes_recvd, &msg, flags), msg.src))
+    }
+
+    // TODO: handle flags except MSG_DONTWAIT
+    pub fn sendmsg(&self, msg: &MsgHdr, flags: SendFlags) -> Result<usize> {
+        let addr = match msg.get_name() {
+            Some(name) if !name.is_empty() => Some(unsafe {
+                Addr::try_from_raw(name.as_ptr() as *const libc::sockaddr, name.len() as u32)?
+            }),
+            _ => None,
+        };
+        let files = match msg.get_control() {
+            Some(control) => files_from_control(control)?,
+            None => Vec::new(),
+        };
+        self.send(msg.get_iovs().as_slices(), files, addr.as_ref(), flags)
+    }
+
+    pub fn recvmsg(&self, msg: &mut MsgHdrMut, flags: RecvFlags) -> Result<usize> {
+        let (bytes_recvd, recvd_msg) = match self.recv(msg.get_iovs_mut().as_slices_mut(), flags)? {
+            Some(res) => res,
+            None => {
+                msg.set_name_len(0)?;
+                msg.set_control_len(0)?;
+                msg.set_flags(MsgHdrFlags::empty());
+                return Ok(0);
+            }
+        };
+        let recvd_len = Self::recvd_len(bytes_recvd, &recvd_msg, flags);
+        let Message { data, src, files } = recvd_msg;
+
+        let name_len = match (&src, msg.get_name_mut()) {
+            (Some(src), Some(name)) => {
+                src.copy_to_slice(name);
+                src.raw_len().min(name.len())
+            }
+            _ => 0,
+        };
+        let close_on_spawn = flags.contains(RecvFlags::MSG_CMSG_CLOEXEC);
+        let (control_len, mut msg_flags) =
+            files_to_control(files, msg.get_control_mut(), close_on_spawn)?;
+        if data.len() > bytes_recvd {
+            msg_flags |= MsgHdrFlags::MSG_TRUNC;
+        }
+        msg.set_name_len(name_len)?;
+        msg.set_control_len(control_len)?;
+        msg.set_flags(msg_flags);
+        Ok(recvd_len)
+    }
+
+    pub fn getsockopt(&self, level: i32, optname: i32) -> Result<i32> {
+        match (level, optname) {
+            (libc::SOL_SOCKET, libc::SO_TYPE) => Ok(SocketType::DGRAM as i32),
+            (libc::SOL_SOCKET, libc::SO_ERROR) => Ok(0),
+            _ => return_errno!(ENOPROTOOPT, "the option is not supported"),
+        }
+    }
+
+    pub fn shutdown(&self, how: HowToShut) -> Result<()> {
+        if how.to_shut_read() {
+            self.receiver.shutdown();
+        }
+        if how.to_shut_write() {
+            self.inner().is_write_shutdown = true;
+        }
+        Ok(())
+    }
+
+    /// Send a datagram to the address, or to the peer if the address is None.
+    pub(super) fn send(
+        &self,
+        bufs: &[&[u8]],
+        files: Vec<FileRef>,
+        addr: Option<&Addr>,
+        flags: SendFlags,
+    ) -> Result<usize> {
+        let (src, receiver, timeout) = {
+            let inner = self.inner();
+            if inner.is_write_shutdown {
+                return flags.check_epipe(Err(errno!(
+                    EPIPE,
+                    "the socket has been shutdown for writing"
+                )));
+            }
+            let receiver = match (addr, &inner.peer) {
+                (Some(addr), _) => ADDRESS_SPACE.get_receiver(addr)?,
+                (None, Some(peer)) => peer
+                    .receiver
+                    .upgrade()
+                    .ok_or_else(|| errno!(ECONNREFUSED, "the peer has been closed"))?,
+                (None, None) => return_errno!(ENOTCONN, "the socket is not connected"),
+            };
+            (inner.addr.clone(), receiver, inner.timeouts.send)
+        };
+
+        let msg = Message {
+            data: bufs.concat(),
+            src,
+            files,
+        };
+        let len = msg.data.len();
+        let nonblocking = self.nonblocking() || flags.contains(SendFlags::MSG_DONTWAIT);
+        flags.check_epipe(receiver.push(msg, nonblocking, timeout))?;
+        Ok(len)
+    }
+
+    /// Receive a datagram into the buffers, returning the number of the bytes
+    /// received and the datagram, or None if the socket has been shutdown for
+    /// reading.
+    pub(super) fn recv(
+        &self,
+        bufs: &mut [&mut [u8]],
+        flags: RecvFlags,
+    ) -> Result<Option<(usize, Message)>> {
+        let nonblocking = self.nonblocking() || flags.contains(RecvFlags::MSG_DONTWAIT);
+        let timeout = self.inner().timeouts.recv;
+        let is_peek = flags.contains(RecvFlags::MSG_PEEK);
+        let msg = match self.receiver.pop(nonblocking, is_peek, timeout)? {
+            Some(msg) => msg,
+            None => return Ok(None),
+        };
+
+        // Same as Linux, the bytes that do not fit in the buffers are discarded
+        let mut bytes_recvd = 0;
+        for buf in bufs.iter_mut() {
+            let len = buf.len().min(msg.data.len() - bytes_recvd);
+            buf[..len].copy_from_slice(&msg.data[bytes_recvd..bytes_recvd + len]);
+            bytes_recvd += len;
+        }
+        Ok(Some((bytes_recvd, msg)))
+    }
+
+    // Same as Linux, the real length of a truncated datagram is returned given
+    // MSG_TRUNC
+    fn recvd_len(bytes_recvd: usize, msg: &Message, flags: RecvFlags) -> usize {
+        if flags.contains(RecvFlags::MSG_TRUNC) {
+            msg.data.len()
+        } else {
+            bytes_recvd
+        }
+    }
+
+    pub(super) fn bytes_to_read(&self) -> usize {
+        self.receiver.next_len()
+    }
+
+    pub(super) fn poll(&self) -> IoEvents {
+        let mut events = self.receiver.poll();
+        let inner = self.inner();
+        // An unconnected socket is always writable, as the destination is
+        // unknown
+        let writable = match &inner.peer {
+            Some(peer) => peer
+                .receiver
+                .upgrade()
+                .map(|receiver| receiver.has_space())
+                .unwrap_or(true),
+            None => true,
+        };
+        if writable && !inner.is_write_shutdown {
+            events |= IoEvents::OUT;
+        }
+        events
+    }
+
+    pub(super) fn receiver(&self) -> &Receiver {
+        &self.receiver
+    }
+
+    pub(super) fn nonblocking(&self) -> bool {
+        self.nonblocking.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn set_nonblocking(&self, nonblocking: bool) {
+        self.nonblocking.store(nonblocking, Ordering::Relaxed);
+    }
+
+    fn inner(&self) -> SgxMutexGuard<'_, Inner> {
+        self.inner.lock().unwrap()
+    }
+}
+
+impl SockOptsExt for Datagram {
+    fn sock_opts(&self) -> &SgxMutex<SockOpts> {
+        &self.sock_opts
+    }
+}
+
+impl SockTimeoutsExt for Datagram {
+    fn timeouts(&self) -> SockTimeouts {
+        self.inner().timeouts
+    }
+
+    fn set_timeouts(&self, timeouts: SockTimeouts) {
+        self.inner().timeouts = timeouts;
+    }
+}
+
+impl Debug for Datagram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Datagram")
+            .field("addr", &self.addr())
+            .field("nonblocking", &self.nonblocking())
+            .finish()
+    }
+}
+
+impl Drop for Datagram {
+    fn drop(&mut self) {
+        if let Some(addr) = &self.inner().addr {
+            ADDRESS_SPACE.remove_addr(addr);
+        }
+        // Wake the senders blocked on the receiver
+        self.receiver.shutdown();
+    }
+}
@@ -0,0 +1,8 @@
+use super::*;
+
+mod address_space;
+mod datagram;
+mod file;
+mod receiver;
+
+pub use datagram::Datagram;
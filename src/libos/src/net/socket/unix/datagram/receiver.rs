@@ -0,0 +1,156 @@
+use super::*;
+use events::{Waiter, WaiterQueue};
+use fs::{IoEvents, IoNotifier};
+use std::time::Duration;
+
+// The maximum number of the bytes queued in a receiver, same as the buffer of
+// a stream socket
+const RECV_BUF_SIZE: usize = 208 * 1024;
+// The maximum number of the datagrams queued in a receiver, same as the
+// default of /proc/sys/net/unix/max_dgram_qlen
+const MAX_DGRAM_QLEN: usize = 512;
+
+/// A datagram along with the address of its sender and the files sent by
+/// SCM_RIGHTS.
+#[derive(Clone)]
+pub struct Message {
+    pub data: Vec<u8>,
+    pub src: Option<Addr>,
+    pub files: Vec<FileRef>,
+}
+
+/// The queue of the datagrams received by a datagram socket. A sender pushes
+/// its datagrams into the receiver of the destination directly, and is blocked
+/// when the receiver is full.
+pub struct Receiver {
+    inner: SgxMutex<ReceiverInner>,
+    // The threads waiting for the datagrams or for the space of the receiver
+    waiters: WaiterQueue,
+    notifier: IoNotifier,
+}
+
+struct ReceiverInner {
+    msgs: VecDeque<Message>,
+    bytes: usize,
+    is_shutdown: bool,
+}
+
+impl Receiver {
+    pub fn new() -> Self {
+        let inner = ReceiverInner {
+            msgs: VecDeque::new(),
+            bytes: 0,
+            is_shutdown: false,
+        };
+        Self {
+            inner: SgxMutex::new(inner),
+            waiters: WaiterQueue::new(),
+            notifier: IoNotifier::new(),
+        }
+    }
+
+    pub fn push(&self, msg: Message, nonblocking: bool, timeout: Option<Duration>) -> Result<()> {
+        if msg.data.len() > RECV_BUF_SIZE {
+            return_errno!(EMSGSIZE, "the datagram is too large");
+        }
+
+        let mut timeout = timeout;
+        let waiter = Waiter::new();
+        loop {
+            let mut inner = self.inner.lock().unwrap();
+            if inner.is_shutdown {
+                return_errno!(EPIPE, "the receiver has been shutdown");
+            }
+            if inner.has_space_for(msg.data.len()) {
+                inner.bytes += msg.data.len();
+                inner.msgs.push_back(msg);
+                drop(inner);
+                self.waiters.dequeue_and_wake_all();
+                self.notifier.broadcast(&IoEvents::IN);
+                return Ok(());
+            }
+            if nonblocking {
+                return_errno!(EAGAIN, "the receiver is full");
+            }
+
+            // Enqueue before unlocking the receiver, so that no wakeup is missed
+            self.waiters.reset_and_enqueue(&waiter);
+            drop(inner);
+            sock_wait(&waiter, timeout.as_mut())?;
+        }
+    }
+
+    /// Pop a datagram, or return None if the receiver has been shutdown and
+    /// all the datagrams have been received. Given `is_peek`, a copy of the
+    /// datagram is returned, which is left in the receiver.
+    pub fn pop(
+        &self,
+        nonblocking: bool,
+        is_peek: bool,
+        timeout: Option<Duration>,
+    ) -> Result<Option<Message>> {
+        let mut timeout = timeout;
+        let waiter = Waiter::new();
+        loop {
+            let mut inner = self.inner.lock().unwrap();
+            if is_peek {
+                if let Some(msg) = inner.msgs.front() {
+                    return Ok(Some(msg.clone()));
+                }
+            } else if let Some(msg) = inner.msgs.pop_front() {
+                inner.bytes -= msg.data.len();
+                drop(inner);
+                self.waiters.dequeue_and_wake_all();
+                return Ok(Some(msg));
+            }
+            if inner.is_shutdown {
+                return Ok(None);
+            }
+            if nonblocking {
+                return_errno!(EAGAIN, "no datagram is received");
+            }
+
+            self.waiters.reset_and_enqueue(&waiter);
+            drop(inner);
+            sock_wait(&waiter, timeout.as_mut())?;
+        }
+    }
+
+    /// The length of the next datagram to receive.
+    pub fn next_len(&self) -> usize {
+        let inner = self.inner.lock().unwrap();
+        inner.msgs.front().map(|msg| msg.data.len()).unwrap_or(0)
+    }
+
+    pub fn has_space(&self) -> bool {
+        self.inner.lock().unwrap().has_space_for(0)
+    }
+
+    pub fn shutdown(&self) {
+        self.inner.lock().unwrap().is_shutdown = true;
+        self.waiters.dequeue_and_wake_all();
+        self.notifier.broadcast(&self.poll());
+    }
+
+    pub fn poll(&self) -> IoEvents {
+        let inner = self.inner.lock().unwrap();
+        let mut events = IoEvents::empty();
+        if !inner.msgs.is_empty() {
+            events |= IoEvents::IN;
+        }
+        if inner.is_shutdown {
+            events |= IoEvents::IN | IoEvents::RDHUP;
+        }
+        events
+    }
+
+    pub fn notifier(&self) -> &IoNotifier {
+        &self.notifier
+    }
+}
+
+impl ReceiverInner {
+    fn has_space_for(&self, len: usize) -> bool {
+        self.msgs.len() < MAX_DGRAM_QLEN && self.bytes + len <= RECV_BUF_SIZE
+    }
+}
@@ -1,22 +1,29 @@
 use self::addr::Addr;
 use super::*;
+use fs::File;
 
 mod addr;
+mod ancillary;
+mod datagram;
 mod stream;
 
 pub use self::addr::Addr as UnixAddr;
+pub use self::datagram::Datagram;
 pub use self::stream::Stream;
 
-//TODO: rewrite this file when a new kind of uds is added
-pub fn unix_socket(socket_type: SocketType, flags: FileFlags, protocol: i32) -> Result<Stream> {
+pub fn unix_socket(
+    socket_type: SocketType,
+    flags: FileFlags,
+    protocol: i32,
+) -> Result<Arc<dyn File>> {
     if protocol != 0 && protocol != AddressFamily::LOCAL as i32 {
         return_errno!(EPROTONOSUPPORT, "protocol is not supported");
     }
 
-    if socket_type == SocketType::STREAM {
-        Ok(Stream::new(flags))
-    } else {
-        return_errno!(ESOCKTNOSUPPORT, "only stream type is supported");
+    match socket_type {
+        SocketType::STREAM | SocketType::SEQPACKET => Ok(Arc::new(Stream::new(socket_type, flags))),
+        SocketType::DGRAM => Ok(Arc::new(Datagram::new(flags))),
+        _ => return_errno!(ESOCKTNOSUPPORT, "the socket type is not supported"),
     }
 }
 
@@ -24,15 +31,21 @@ pub fn socketpair(
     socket_type: SocketType,
     flags: FileFlags,
     protocol: i32,
-) -> Result<(Stream, Stream)> {
+) -> Result<(Arc<dyn File>, Arc<dyn File>)> {
     if protocol != 0 && protocol != AddressFamily::LOCAL as i32 {
         return_errno!(EPROTONOSUPPORT, "protocol is not supported");
     }
 
-    if socket_type == SocketType::STREAM {
-        Stream::socketpair(flags)
-    } else {
-        return_errno!(ESOCKTNOSUPPORT, "only stream type is supported");
+    match socket_type {
+        SocketType::STREAM | SocketType::SEQPACKET => {
+            let (socket_a, socket_b) = Stream::socketpair(socket_type, flags)?;
+            Ok((Arc::new(socket_a), Arc::new(socket_b)))
+        }
+        SocketType::DGRAM => {
+            let (socket_a, socket_b) = Datagram::socketpair(flags)?;
+            Ok((Arc::new(socket_a), Arc::new(socket_b)))
+        }
+        _ => return_errno!(ESOCKTNOSUPPORT, "the socket type is not supported"),
     }
 }
 
@@ -47,3 +60,15 @@ impl AsUnixSocket for FileRef {
             .ok_or_else(|| errno!(EBADF, "not a unix socket"))
     }
 }
+
+pub trait AsUnixDatagram {
+    fn as_unix_datagram(&self) -> Result<&Datagram>;
+}
+
+impl AsUnixDatagram for FileRef {
+    fn as_unix_datagram(&self) -> Result<&Datagram> {
+        self.as_any()
+            .downcast_ref::<Datagram>()
+            .ok_or_else(|| errno!(EBADF, "not a unix datagram socket"))
+    }
+}
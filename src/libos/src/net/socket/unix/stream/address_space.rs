@@ -8,8 +8,8 @@ lazy_static! {
 }
 
 pub struct AddressSpace {
-    file: SgxMutex<BTreeMap<String, Option<Arc<Listener>>>>,
-    abstr: SgxMutex<BTreeMap<String, Option<Arc<Listener>>>>,
+    file: SgxMutex<BTreeMap<Vec<u8>, Option<Arc<Listener>>>>,
+    abstr: SgxMutex<BTreeMap<Vec<u8>, Option<Arc<Listener>>>>,
 }
 
 impl AddressSpace {
@@ -21,7 +21,7 @@ impl AddressSpace {
     }
 
     pub fn add_binder(&self, addr: &Addr) -> Result<()> {
-        let key = Self::get_key(addr);
+        let key = addr.key();
         let mut space = self.get_space(addr);
         if space.contains_key(&key) {
             return_errno!(EADDRINUSE, "the addr is already bound");
@@ -31,13 +31,22 @@ impl AddressSpace {
         }
     }
 
-    pub fn add_listener(&self, addr: &Addr, capacity: usize, nonblocking: bool) -> Result<()> {
-        let key = Self::get_key(addr);
+    pub fn add_listener(
+        &self,
+        addr: &Addr,
+        capacity: usize,
+        nonblocking: bool,
+        socket_type: SocketType,
+    ) -> Result<()> {
+        let key = addr.key();
         let mut space = self.get_space(addr);
 
         if let Some(option) = space.get(&key) {
             if option.is_none() {
-                space.insert(key, Some(Arc::new(Listener::new(capacity, nonblocking)?)));
+                space.insert(
+                    key,
+                    Some(Arc::new(Listener::new(capacity, nonblocking, socket_type)?)),
+                );
                 Ok(())
             } else {
                 return_errno!(EINVAL, "the socket is already listened");
@@ -48,7 +57,7 @@ impl AddressSpace {
     }
 
     pub fn resize_listener(&self, addr: &Addr, capacity: usize) -> Result<()> {
-        let key = Self::get_key(addr);
+        let key = addr.key();
         let mut space = self.get_space(addr);
 
         if let Some(option) = space.get(&key) {
@@ -77,28 +86,24 @@ impl AddressSpace {
     }
 
     pub fn get_listener_ref(&self, addr: &Addr) -> Option<Arc<Listener>> {
-        let key = Self::get_key(addr);
+        let key = addr.key();
         let space = self.get_space(addr);
         space.get(&key).map(|x| x.clone()).flatten()
     }
 
     pub fn remove_addr(&self, addr: &Addr) {
-        let key = Self::get_key(addr);
+        let key = addr.key();
         let mut space = self.get_space(addr);
         space.remove(&key);
     }
 
-    fn get_space(&self, addr: &Addr) -> SgxMutexGuard<'_, BTreeMap<String, Option<Arc<Listener>>>> {
+    fn get_space(
+        &self,
+        addr: &Addr,
+    ) -> SgxMutexGuard<'_, BTreeMap<Vec<u8>, Option<Arc<Listener>>>> {
         match addr {
             Addr::File(unix_path) => self.file.lock().unwrap(),
             Addr::Abstract(path) => self.abstr.lock().unwrap(),
         }
     }
-
-    fn get_key(addr: &Addr) -> String {
-        match addr {
-            Addr::File(unix_path) => unix_path.absolute(),
-            Addr::Abstract(path) => addr.path_str().to_string(),
-        }
-    }
 }
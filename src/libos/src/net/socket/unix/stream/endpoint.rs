@@ -1,3 +1,4 @@
+use super::ancillary::FilesInTransit;
 use super::*;
 use events::{Event, EventFilter, Notifier, Observer};
 use fs::channel::{Channel, Consumer, Producer};
@@ -7,21 +8,40 @@ use std::sync::{Arc, Weak};
 
 pub type Endpoint = Arc<Inner>;
 
-/// Constructor of two connected Endpoints
-pub fn end_pair(nonblocking: bool) -> Result<(Endpoint, Endpoint)> {
+/// Constructor of two connected Endpoints, which keep the boundaries of the
+/// messages if `is_seqpacket`
+pub fn end_pair(nonblocking: bool, is_seqpacket: bool) -> Result<(Endpoint, Endpoint)> {
     let (pro_a, con_a) = Channel::new(DEFAULT_BUF_SIZE)?.split();
     let (pro_b, con_b) = Channel::new(DEFAULT_BUF_SIZE)?.split();
+    let files_a = Arc::new(FilesInTransit::new());
+    let files_b = Arc::new(FilesInTransit::new());
+    let (msgs_a, msgs_b) = if is_seqpacket {
+        (
+            Some(Arc::new(MsgBoundaries::new())),
+            Some(Arc::new(MsgBoundaries::new())),
+        )
+    } else {
+        (None, None)
+    };
 
     let mut end_a = Arc::new(Inner {
         addr: RwLock::new(None),
         reader: con_a,
         writer: pro_b,
+        incoming_files: files_a.clone(),
+        outgoing_files: files_b.clone(),
+        incoming_msgs: msgs_a.clone(),
+        outgoing_msgs: msgs_b.clone(),
         peer: Weak::default(),
     });
     let end_b = Arc::new(Inner {
         addr: RwLock::new(None),
         reader: con_b,
         writer: pro_a,
+        incoming_files: files_b,
+        outgoing_files: files_a,
+        incoming_msgs: msgs_b,
+        outgoing_msgs: msgs_a,
         peer: Arc::downgrade(&end_a),
     });
 
@@ -40,6 +60,14 @@ pub struct Inner {
     addr: RwLock<Option<Addr>>,
     reader: Consumer<u8>,
     writer: Producer<u8>,
+    // The files sent by SCM_RIGHTS along with the bytes of the reader and the
+    // writer, which are shared with the peer
+    incoming_files: Arc<FilesInTransit>,
+    outgoing_files: Arc<FilesInTransit>,
+    // The boundaries of the messages of the reader and the writer of a
+    // SOCK_SEQPACKET socket, which are shared with the peer
+    incoming_msgs: Option<Arc<MsgBoundaries>>,
+    outgoing_msgs: Option<Arc<MsgBoundaries>>,
     peer: Weak<Self>,
 }
 
@@ -61,26 +89,222 @@ impl Inner {
         self.writer.set_nonblocking(nonblocking);
     }
 
+    pub fn set_timeouts(&self, timeouts: &SockTimeouts) {
+        self.reader.set_timeout(timeouts.recv);
+        self.writer.set_timeout(timeouts.send);
+    }
+
     pub fn nonblocking(&self) -> bool {
         let cons_nonblocking = self.reader.is_nonblocking();
         let prod_nonblocking = self.writer.is_nonblocking();
         assert_eq!(cons_nonblocking, prod_nonblocking);
         cons_nonblocking
     }
+    // Same as Linux, the files sent along with the bytes read by read and readv
+    // are discarded
     pub fn read(&self, buf: &mut [u8]) -> Result<usize> {
-        self.reader.pop_slice(buf)
+        self.readv(&mut [buf])
     }
 
     pub fn write(&self, buf: &[u8]) -> Result<usize> {
-        self.writer.push_slice(buf)
+        self.writev(&[buf])
     }
 
     pub fn readv(&self, bufs: &mut [&mut [u8]]) -> Result<usize> {
-        self.reader.pop_slices(bufs)
+        let (len, _, _) = self.readv_with_files(bufs, RecvFlags::empty())?;
+        Ok(len)
     }
 
     pub fn writev(&self, bufs: &[&[u8]]) -> Result<usize> {
-        self.writer.push_slices(bufs)
+        self.writev_with_files(bufs, Vec::new(), SendFlags::empty())
+    }
+
+    /// Read the bytes and the files sent along with them by SCM_RIGHTS,
+    /// returning the number of the bytes read and that of the bytes consumed,
+    /// which is larger if a message is truncated.
+    ///
+    /// At most one message is read if the boundaries of the messages are kept.
+    /// Given MSG_PEEK, the bytes are copied without being consumed, and no
+    /// files are received. Given MSG_WAITALL, a stream is read until the
+    /// buffers are full, unless it reaches the end, an error or the files.
+    pub fn readv_with_files(
+        &self,
+        bufs: &mut [&mut [u8]],
+        flags: RecvFlags,
+    ) -> Result<(usize, usize, Vec<FileRef>)> {
+        let dontwait = flags.contains(RecvFlags::MSG_DONTWAIT);
+        if flags.contains(RecvFlags::MSG_PEEK) {
+            let (len, msg_len) = self.peek(bufs, dontwait)?;
+            return Ok((len, msg_len, Vec::new()));
+        }
+
+        if let Some(msgs) = &self.incoming_msgs {
+            let mut bytes_read = 0;
+            // The files are sent along with the first byte of a message, so
+            // they are always received with the message
+            let (msg_len, files) = self.incoming_files.read_with(|_| {
+                let (len, msg_len) = self.read_msg(msgs, bufs, dontwait)?;
+                bytes_read = len;
+                Ok(msg_len)
+            })?;
+            return Ok((bytes_read, msg_len, files));
+        }
+
+        let waitall = flags.contains(RecvFlags::MSG_WAITALL);
+        let capacity: usize = bufs.iter().map(|buf| buf.len()).sum();
+        let mut bytes_read = 0;
+        let mut files = Vec::new();
+        loop {
+            let res = self.incoming_files.read_with(|max_len| {
+                let mut bufs = skip_bufs(bufs, bytes_read);
+                match max_len {
+                    Some(max_len) => self
+                        .reader
+                        .pop_slices_dontwait(&mut limit_bufs(&mut bufs, max_len), dontwait),
+                    None => self.reader.pop_slices_dontwait(&mut bufs, dontwait),
+                }
+            });
+            let (len, new_files) = match res {
+                Ok(res) => res,
+                // Same as Linux, the bytes read so far are returned on errors,
+                // e.g., on timeout or being interrupted
+                Err(_) if bytes_read > 0 => break,
+                Err(e) => return Err(e),
+            };
+            bytes_read += len;
+            // Same as Linux, the reading stops at the files
+            let has_files = !new_files.is_empty();
+            files.extend(new_files);
+            if !waitall || len == 0 || has_files || bytes_read == capacity {
+                break;
+            }
+        }
+        Ok((bytes_read, bytes_read, files))
+    }
+
+    /// Write the bytes along with the files to send by SCM_RIGHTS.
+    ///
+    /// The bytes are written as one message if the boundaries of the messages
+    /// are kept.
+    pub fn writev_with_files(
+        &self,
+        bufs: &[&[u8]],
+        files: Vec<FileRef>,
+        flags: SendFlags,
+    ) -> Result<usize> {
+        let dontwait = flags.contains(SendFlags::MSG_DONTWAIT);
+        let res = self
+            .outgoing_files
+            .write_with(files, || match &self.outgoing_msgs {
+                Some(msgs) => self.write_msg(msgs, bufs, dontwait),
+                None => self.writer.push_slices_dontwait(bufs, dontwait),
+            });
+        flags.check_epipe(res)
+    }
+
+    // Copy the bytes without consuming them, returning the number of the bytes
+    // copied and that of the bytes in the message if the boundaries of the
+    // messages are kept.
+    fn peek(&self, bufs: &mut [&mut [u8]], dontwait: bool) -> Result<(usize, usize)> {
+        let capacity: usize = bufs.iter().map(|buf| buf.len()).sum();
+        let _read_guard = self
+            .incoming_msgs
+            .as_ref()
+            .map(|msgs| msgs.read_lock.lock().unwrap());
+        let msg_len = match &self.incoming_msgs {
+            Some(msgs) => loop {
+                if let Some(msg_len) = msgs.front() {
+                    break Some(msg_len);
+                }
+                if self.reader.peek_slice_dontwait(&mut [0], dontwait)? == 0 {
+                    return Ok((0, 0));
+                }
+            },
+            None => None,
+        };
+
+        let mut data = vec![0; msg_len.unwrap_or(capacity).min(capacity)];
+        let data_len = self.reader.peek_slice_dontwait(&mut data, dontwait)?;
+        let mut bytes_read = 0;
+        for buf in bufs.iter_mut() {
+            let len = buf.len().min(data_len - bytes_read);
+            buf[..len].copy_from_slice(&data[bytes_read..bytes_read + len]);
+            bytes_read += len;
+        }
+        Ok((bytes_read, msg_len.unwrap_or(bytes_read)))
+    }
+
+    // Read one message, returning the number of the bytes read and that of the
+    // bytes in the message, the rest of which are discarded.
+    fn read_msg(
+        &self,
+        msgs: &MsgBoundaries,
+        bufs: &mut [&mut [u8]],
+        dontwait: bool,
+    ) -> Result<(usize, usize)> {
+        let _read_guard = msgs.read_lock.lock().unwrap();
+        let msg_len = loop {
+            if let Some(msg_len) = msgs.front() {
+                break msg_len;
+            }
+            // The length of a message is recorded before its bytes are written,
+            // so wait for the bytes or the end of the stream
+            if self.reader.peek_slice_dontwait(&mut [0], dontwait)? == 0 {
+                return Ok((0, 0));
+            }
+        };
+        let nonblocking = dontwait || self.reader.is_nonblocking();
+        if nonblocking && self.reader.items_to_consume() < msg_len {
+            return_errno!(EAGAIN, "the message is not complete");
+        }
+
+        let mut data = vec![0; msg_len];
+        let mut data_len = 0;
+        while data_len < msg_len {
+            let len = self.reader.pop_slice(&mut data[data_len..])?;
+            if len == 0 {
+                break;
+            }
+            data_len += len;
+        }
+        msgs.pop_front();
+
+        let mut bytes_read = 0;
+        for buf in bufs.iter_mut() {
+            let len = buf.len().min(data_len - bytes_read);
+            buf[..len].copy_from_slice(&data[bytes_read..bytes_read + len]);
+            bytes_read += len;
+        }
+        Ok((bytes_read, data_len))
+    }
+
+    // Write the bytes as one message, which is never interleaved with other
+    // messages.
+    fn write_msg(&self, msgs: &MsgBoundaries, bufs: &[&[u8]], dontwait: bool) -> Result<usize> {
+        let data = bufs.concat();
+        if data.len() > DEFAULT_BUF_SIZE {
+            return_errno!(EMSGSIZE, "the message is too large");
+        }
+        let _write_guard = msgs.write_lock.lock().unwrap();
+        let nonblocking = dontwait || self.writer.is_nonblocking();
+        if nonblocking && self.writer.free_space() < data.len() {
+            return_errno!(EAGAIN, "no space for the message");
+        }
+
+        msgs.push_back(data.len());
+        let mut written = 0;
+        while written < data.len() {
+            match self.writer.push_slice(&data[written..]) {
+                Ok(len) => written += len,
+                Err(e) => {
+                    if written == 0 {
+                        msgs.pop_back();
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok(written)
     }
 
     pub fn bytes_to_read(&self) -> usize {
@@ -138,6 +362,71 @@ impl Inner {
     }
 }
 
+// Skip the first bytes of the buffers, which have been filled
+fn skip_bufs<'a>(bufs: &'a mut [&mut [u8]], skipped_len: usize) -> Vec<&'a mut [u8]> {
+    let mut remain = skipped_len;
+    let mut rest_bufs = Vec::new();
+    for buf in bufs.iter_mut() {
+        let len = buf.len().min(remain);
+        remain -= len;
+        if len < buf.len() {
+            rest_bufs.push(&mut buf[len..]);
+        }
+    }
+    rest_bufs
+}
+
+// Limit the total length of the buffers
+fn limit_bufs<'a>(bufs: &'a mut [&mut [u8]], max_len: usize) -> Vec<&'a mut [u8]> {
+    let mut remain = max_len;
+    let mut limited_bufs = Vec::new();
+    for buf in bufs.iter_mut() {
+        if remain == 0 {
+            break;
+        }
+        let len = buf.len().min(remain);
+        limited_bufs.push(&mut buf[..len]);
+        remain -= len;
+    }
+    limited_bufs
+}
+
+/// The lengths of the messages in one direction of a SOCK_SEQPACKET socket,
+/// which keep the boundaries of the messages in the channel of bytes.
+pub struct MsgBoundaries {
+    lens: SgxMutex<VecDeque<usize>>,
+    // Serialize the readers and the writers, so that a message is always read
+    // or written as a whole
+    read_lock: SgxMutex<()>,
+    write_lock: SgxMutex<()>,
+}
+
+impl MsgBoundaries {
+    pub fn new() -> Self {
+        Self {
+            lens: SgxMutex::new(VecDeque::new()),
+            read_lock: SgxMutex::new(()),
+            write_lock: SgxMutex::new(()),
+        }
+    }
+
+    fn front(&self) -> Option<usize> {
+        self.lens.lock().unwrap().front().cloned()
+    }
+
+    fn pop_front(&self) {
+        self.lens.lock().unwrap().pop_front();
+    }
+
+    fn push_back(&self, len: usize) {
+        self.lens.lock().unwrap().push_back(len);
+    }
+
+    fn pop_back(&self) {
+        self.lens.lock().unwrap().pop_back();
+    }
+}
+
 // TODO: Add SO_SNDBUF and SO_RCVBUF to set/getsockopt to dynamcally change the size.
 // This value is got from /proc/sys/net/core/rmem_max and wmem_max that are same on linux.
 pub const DEFAULT_BUF_SIZE: usize = 208 * 1024;
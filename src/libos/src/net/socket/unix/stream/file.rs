@@ -63,6 +63,9 @@ impl File for Stream {
                 }
                 _ => return_errno!(ENOTCONN, "unconnected socket"),
             },
+            IoctlCmd::SIOCGIFCONF(_) | IoctlCmd::SIOCGIFADDR(_) | IoctlCmd::SIOCGIFHWADDR(_) => {
+                do_iface_ioctl(cmd)
+            }
             _ => return_errno!(EINVAL, "unknown ioctl cmd for unix socket"),
         }
     }
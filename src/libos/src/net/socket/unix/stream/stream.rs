@@ -1,4 +1,5 @@
 use super::address_space::ADDRESS_SPACE;
+use super::ancillary::{files_from_control, files_to_control};
 use super::endpoint::{end_pair, Endpoint, RelayNotifier};
 use super::*;
 use events::{Event, EventFilter, Notifier, Observer};
@@ -7,32 +8,44 @@ use fs::IoEvents;
 use std::fmt;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-
-/// SOCK_STREAM Unix socket. It has three statuses: unconnected, listening and connected.  When a
-/// socket is created, it is in unconnected status.  It will transfer to listening after listen is
-/// called and connected after connect is called. A socket in connected status can be obtained
-/// through a listening socket calling accept. Listening and connected are ultimate statuses. They
-/// will not transfer to other statuses.
+use std::time::Duration;
+
+/// SOCK_STREAM or SOCK_SEQPACKET Unix socket. It has three statuses: unconnected, listening and
+/// connected.  When a socket is created, it is in unconnected status.  It will transfer to listening
+/// after listen is called and connected after connect is called. A socket in connected status can be
+/// obtained through a listening socket calling accept. Listening and connected are ultimate
+/// statuses. They will not transfer to other statuses.
+///
+/// A SOCK_SEQPACKET socket is the same as a SOCK_STREAM one, except that the boundaries of the
+/// messages are kept, and it can only connect to a listening SOCK_SEQPACKET socket.
 pub struct Stream {
     inner: SgxMutex<Status>,
+    socket_type: SocketType,
+    ino: usize,
+    timeouts: SgxMutex<SockTimeouts>,
+    sock_opts: SgxMutex<SockOpts>,
     // Use the internal notifier of RelayNotifier as the notifier of stream socket. It relays the
     // events of the endpoint, too.
     pub(super) notifier: Arc<RelayNotifier>,
 }
 
 impl Stream {
-    pub fn new(flags: FileFlags) -> Self {
+    pub fn new(socket_type: SocketType, flags: FileFlags) -> Self {
         Self {
             inner: SgxMutex::new(Status::Idle(Info::new(
                 flags.contains(FileFlags::SOCK_NONBLOCK),
             ))),
+            socket_type,
+            ino: alloc_socket_ino(),
+            timeouts: SgxMutex::new(SockTimeouts::default()),
+            sock_opts: SgxMutex::new(SockOpts::new(AddressFamily::LOCAL, socket_type)),
             notifier: Arc::new(RelayNotifier::new()),
         }
     }
 
-    pub fn socketpair(flags: FileFlags) -> Result<(Self, Self)> {
+    pub fn socketpair(socket_type: SocketType, flags: FileFlags) -> Result<(Self, Self)> {
         let nonblocking = flags.contains(FileFlags::SOCK_NONBLOCK);
-        let (end_a, end_b) = end_pair(nonblocking)?;
+        let (end_a, end_b) = end_pair(nonblocking, socket_type == SocketType::SEQPACKET)?;
         let notifier_a = Arc::new(RelayNotifier::new());
         let notifier_b = Arc::new(RelayNotifier::new());
         notifier_a.observe_endpoint(&end_a);
@@ -40,17 +53,47 @@ impl Stream {
 
         let socket_a = Self {
             inner: SgxMutex::new(Status::Connected(end_a)),
+            socket_type,
+            ino: alloc_socket_ino(),
+            timeouts: SgxMutex::new(SockTimeouts::default()),
+            sock_opts: SgxMutex::new(SockOpts::new(AddressFamily::LOCAL, socket_type)),
             notifier: notifier_a,
         };
 
         let socket_b = Self {
             inner: SgxMutex::new(Status::Connected(end_b)),
+            socket_type,
+            ino: alloc_socket_ino(),
+            timeouts: SgxMutex::new(SockTimeouts::default()),
+            sock_opts: SgxMutex::new(SockOpts::new(AddressFamily::LOCAL, socket_type)),
             notifier: notifier_b,
         };
 
         Ok((socket_a, socket_b))
     }
 
+    pub fn ino(&self) -> usize {
+        self.ino
+    }
+
+    pub fn socket_type(&self) -> SocketType {
+        self.socket_type
+    }
+
+    pub fn is_listening(&self) -> bool {
+        match &*self.inner() {
+            Status::Listening(_) => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        match &*self.inner() {
+            Status::Connected(_) => true,
+            _ => false,
+        }
+    }
+
     pub fn addr(&self) -> Option<Addr> {
         match &*self.inner() {
             Status::Idle(info) => info.addr().clone(),
@@ -94,6 +137,11 @@ impl Stream {
         Ok(())
     }
 
+    /// Bind the socket to a unique name in the abstract namespace.
+    pub fn autobind(&self) -> Result<()> {
+        Addr::autobind(|addr| self.bind(addr))
+    }
+
     pub fn listen(&self, backlog: i32) -> Result<()> {
         //TODO: restrict backlog accroding to /proc/sys/net/core/somaxconn
         if backlog < 0 {
@@ -105,7 +153,16 @@ impl Stream {
         match &*inner {
             Status::Idle(info) => {
                 if let Some(addr) = info.addr() {
-                    ADDRESS_SPACE.add_listener(addr, capacity, info.nonblocking())?;
+                    ADDRESS_SPACE.add_listener(
+                        addr,
+                        capacity,
+                        info.nonblocking(),
+                        self.socket_type,
+                    )?;
+                    ADDRESS_SPACE
+                        .get_listener_ref(addr)
+                        .unwrap()
+                        .set_timeout(self.timeouts().recv);
                     *inner = Status::Listening(addr.clone());
                 } else {
                     return_errno!(EINVAL, "the socket is not bound");
@@ -134,20 +191,35 @@ impl Stream {
                     }
                 }
 
-                let (end_self, end_incoming) = end_pair(info.nonblocking())?;
+                if let Some(listener) = ADDRESS_SPACE.get_listener_ref(addr) {
+                    if listener.socket_type() != self.socket_type {
+                        return_errno!(EPROTOTYPE, "the listening socket is of another type");
+                    }
+                }
+
+                let (end_self, end_incoming) = end_pair(
+                    info.nonblocking(),
+                    self.socket_type == SocketType::SEQPACKET,
+                )?;
                 end_incoming.set_addr(addr);
                 if let Some(self_addr) = self_addr_opt {
                     end_self.set_addr(self_addr);
                 }
 
+                // Same as Linux, a nonblocking socket fails with EAGAIN when
+                // the backlog is full, so that it can try again later
+                let nonblocking = info.nonblocking();
                 ADDRESS_SPACE
                     .push_incoming(addr, end_incoming)
                     .map_err(|e| match e.errno() {
-                        Errno::EAGAIN => errno!(ECONNREFUSED, "the backlog is full"),
+                        Errno::EAGAIN if !nonblocking => {
+                            errno!(ECONNREFUSED, "the backlog is full")
+                        }
                         _ => e,
                     })?;
 
                 self.notifier.observe_endpoint(&end_self);
+                end_self.set_timeouts(&self.timeouts());
 
                 *inner = Status::Connected(end_self);
                 Ok(())
@@ -163,6 +235,10 @@ impl Stream {
             Status::Listening(addr) => {
                 let endpoint = ADDRESS_SPACE.pop_incoming(&addr)?;
                 endpoint.set_nonblocking(flags.contains(FileFlags::SOCK_NONBLOCK));
+                // Same as Linux, the timeouts and the options are inherited from
+                // the listening socket
+                let timeouts = self.timeouts();
+                endpoint.set_timeouts(&timeouts);
                 let notifier = Arc::new(RelayNotifier::new());
                 notifier.observe_endpoint(&endpoint);
 
@@ -173,6 +249,10 @@ impl Stream {
                 Ok((
                     Self {
                         inner: SgxMutex::new(Status::Connected(endpoint)),
+                        socket_type: self.socket_type,
+                        ino: alloc_socket_ino(),
+                        timeouts: SgxMutex::new(timeouts),
+                        sock_opts: SgxMutex::new(self.sock_opts.lock().unwrap().clone()),
                         notifier: notifier,
                     },
                     peer_addr,
@@ -182,14 +262,28 @@ impl Stream {
         }
     }
 
-    // TODO: handle flags
     pub fn sendto(&self, buf: &[u8], flags: SendFlags, addr: &Option<Addr>) -> Result<usize> {
-        self.write(buf)
+        let status = (*self.inner()).clone();
+        match status {
+            Status::Connected(endpoint) => endpoint.writev_with_files(&[buf], Vec::new(), flags),
+            _ => return_errno!(ENOTCONN, "unconnected socket"),
+        }
     }
 
-    // TODO: handle flags
     pub fn recvfrom(&self, buf: &mut [u8], flags: RecvFlags) -> Result<(usize, Option<Addr>)> {
-        let data_len = self.read(buf)?;
+        let status = (*self.inner()).clone();
+        let endpoint = match status {
+            Status::Connected(endpoint) => endpoint,
+            _ => return_errno!(ENOTCONN, "unconnected socket"),
+        };
+        let (bytes_recvd, msg_len, _) = endpoint.readv_with_files(&mut [buf], flags)?;
+        // Same as Linux, the real length of a truncated message is returned
+        // given MSG_TRUNC
+        let data_len = if flags.contains(RecvFlags::MSG_TRUNC) {
+            msg_len
+        } else {
+            bytes_recvd
+        };
         let addr = self.peer_addr().ok();
 
         debug!("recvfrom {:?}", addr);
@@ -197,7 +291,55 @@ impl Stream {
         Ok((data_len, addr))
     }
 
+    pub fn sendmsg(&self, msg: &MsgHdr, flags: SendFlags) -> Result<usize> {
+        let files = match msg.get_control() {
+            Some(control) => files_from_control(control)?,
+            None => Vec::new(),
+        };
+        let status = (*self.inner()).clone();
+        match status {
+            Status::Connected(endpoint) => {
+                endpoint.writev_with_files(msg.get_iovs().as_slices(), files, flags)
+            }
+            _ => return_errno!(ENOTCONN, "unconnected socket"),
+        }
+    }
+
+    pub fn recvmsg(&self, msg: &mut MsgHdrMut, flags: RecvFlags) -> Result<usize> {
+        let status = (*self.inner()).clone();
+        let endpoint = match status {
+            Status::Connected(endpoint) => endpoint,
+            _ => return_errno!(ENOTCONN, "unconnected socket"),
+        };
+        let (bytes_recvd, msg_len, files) =
+            endpoint.readv_with_files(msg.get_iovs_mut().as_slices_mut(), flags)?;
+
+        let close_on_spawn = flags.contains(RecvFlags::MSG_CMSG_CLOEXEC);
+        let (control_len, mut msg_flags) =
+            files_to_control(files, msg.get_control_mut(), close_on_spawn)?;
+        if msg_len > bytes_recvd {
+            msg_flags |= MsgHdrFlags::MSG_TRUNC;
+        }
+        msg.set_name_len(0)?;
+        msg.set_control_len(control_len)?;
+        msg.set_flags(msg_flags);
+        if flags.contains(RecvFlags::MSG_TRUNC) {
+            Ok(msg_len)
+        } else {
+            Ok(bytes_recvd)
+        }
+    }
+
     /// perform shutdown on the socket.
+    pub fn getsockopt(&self, level: i32, optname: i32) -> Result<i32> {
+        match (level, optname) {
+            (libc::SOL_SOCKET, libc::SO_TYPE) => Ok(self.socket_type as i32),
+            // A connection is established at once, so no errors are pending
+            (libc::SOL_SOCKET, libc::SO_ERROR) => Ok(0),
+            _ => return_errno!(ENOPROTOOPT, "the option is not supported"),
+        }
+    }
+
     pub fn shutdown(&self, how: HowToShut) -> Result<()> {
         if let Status::Connected(ref end) = &*self.inner() {
             end.shutdown(how)
@@ -230,6 +372,31 @@ impl Stream {
     }
 }
 
+impl SockOptsExt for Stream {
+    fn sock_opts(&self) -> &SgxMutex<SockOpts> {
+        &self.sock_opts
+    }
+}
+
+impl SockTimeoutsExt for Stream {
+    fn timeouts(&self) -> SockTimeouts {
+        *self.timeouts.lock().unwrap()
+    }
+
+    fn set_timeouts(&self, timeouts: SockTimeouts) {
+        *self.timeouts.lock().unwrap() = timeouts;
+        match &*self.inner() {
+            Status::Idle(_) => {}
+            Status::Connected(endpoint) => endpoint.set_timeouts(&timeouts),
+            // Same as Linux, accept is bounded by the receiving timeout
+            Status::Listening(addr) => ADDRESS_SPACE
+                .get_listener_ref(&addr)
+                .unwrap()
+                .set_timeout(timeouts.recv),
+        }
+    }
+}
+
 impl Debug for Stream {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("Stream")
@@ -306,19 +473,25 @@ impl Info {
 /// ECONNREFUSED rather than block when the channel is full.
 pub struct Listener {
     channel: RwLock<Channel<Endpoint>>,
+    socket_type: SocketType,
 }
 
 impl Listener {
-    pub fn new(capacity: usize, nonblocking: bool) -> Result<Self> {
+    pub fn new(capacity: usize, nonblocking: bool, socket_type: SocketType) -> Result<Self> {
         let channel = Channel::new(capacity)?;
         channel.producer().set_nonblocking(true);
         channel.consumer().set_nonblocking(nonblocking);
 
         Ok(Self {
             channel: RwLock::new(channel),
+            socket_type,
         })
     }
 
+    pub fn socket_type(&self) -> SocketType {
+        self.socket_type
+    }
+
     pub fn capacity(&self) -> usize {
         let channel = self.channel.read().unwrap();
         channel.capacity()
@@ -337,6 +510,9 @@ impl Listener {
         new_channel
             .consumer()
             .set_nonblocking(channel.consumer().is_nonblocking());
+        new_channel
+            .consumer()
+            .set_timeout(channel.consumer().timeout());
 
         let remaining = channel.items_to_consume();
         for i in 0..std::cmp::min(remaining, capacity) {
@@ -371,6 +547,11 @@ impl Listener {
         channel.consumer().set_nonblocking(nonblocking);
     }
 
+    pub fn set_timeout(&self, timeout: Option<Duration>) {
+        let channel = self.channel.read().unwrap();
+        channel.consumer().set_timeout(timeout);
+    }
+
     pub fn shutdown(&self) {
         let channel = self.channel.read().unwrap();
         channel.shutdown();
@@ -4,11 +4,12 @@ use std::mem::MaybeUninit;
 use std::time::Duration;
 
 use super::io_multiplexing::{AsEpollFile, EpollCtl, EpollFile, EpollFlags, FdSetExt, PollFd};
-use fs::{CreationFlags, File, FileDesc, FileRef};
+use fs::{CreationFlags, File, FileDesc, FileRef, StatusFlags};
 use misc::resource_t;
 use process::Process;
+use signal::{sigset_t, wait_with_sigmask, SigSet};
 use std::convert::TryFrom;
-use time::timeval_t;
+use time::{do_clock_gettime, timespec_t, timeval_t, ClockID};
 use util::mem_util::from_user;
 
 pub fn do_socket(domain: c_int, socket_type: c_int, protocol: c_int) -> Result<isize> {
@@ -17,9 +18,15 @@ pub fn do_socket(domain: c_int, socket_type: c_int, protocol: c_int) -> Result<i
     let sock_type = SocketType::try_from(socket_type & (!file_flags.bits()))?;
 
     let file_ref: Arc<dyn File> = match sock_domain {
-        AddressFamily::LOCAL => {
-            let unix_socket = unix_socket(sock_type, file_flags, protocol)?;
-            Arc::new(unix_socket)
+        AddressFamily::LOCAL => unix_socket(sock_type, file_flags, protocol)?,
+        AddressFamily::NETLINK if protocol == NETLINK_ROUTE => {
+            netlink_socket(sock_type, file_flags, protocol)?
+        }
+        AddressFamily::INET
+            if is_enclave_stack_enabled()
+                && (sock_type == SocketType::STREAM || sock_type == SocketType::DGRAM) =>
+        {
+            enclave_socket(sock_type, file_flags, protocol)?
         }
         _ => {
             let socket = HostSocket::new(sock_domain, sock_type, file_flags, protocol)?;
@@ -28,7 +35,7 @@ pub fn do_socket(domain: c_int, socket_type: c_int, protocol: c_int) -> Result<i
     };
 
     let close_on_spawn = file_flags.contains(FileFlags::SOCK_CLOEXEC);
-    let fd = current!().add_file(file_ref, close_on_spawn);
+    let fd = current!().add_file(file_ref, close_on_spawn)?;
     Ok(fd as isize)
 }
 
@@ -39,14 +46,37 @@ pub fn do_bind(fd: c_int, addr: *const libc::sockaddr, addr_len: libc::socklen_t
     from_user::check_array(addr as *const u8, addr_len as usize)?;
 
     let file_ref = current!().file(fd as FileDesc)?;
+    let file_ref = divert_to_host(fd, file_ref, addr, addr_len, true)?;
     if let Ok(socket) = file_ref.as_host_socket() {
         let sock_addr = unsafe { SockAddr::try_from_raw(addr, addr_len)? };
         trace!("bind to addr: {:?}", sock_addr);
         socket.bind(&sock_addr)?;
     } else if let Ok(unix_socket) = file_ref.as_unix_socket() {
-        let unix_addr = unsafe { UnixAddr::try_from_raw(addr, addr_len)? };
-        trace!("bind to addr: {:?}", unix_addr);
-        unix_socket.bind(&unix_addr)?;
+        if UnixAddr::is_autobind_addr(addr_len) {
+            unix_socket.autobind()?;
+        } else {
+            let unix_addr = unsafe { UnixAddr::try_from_raw(addr, addr_len)? };
+            trace!("bind to addr: {:?}", unix_addr);
+            unix_socket.bind(&unix_addr)?;
+        }
+    } else if let Ok(unix_socket) = file_ref.as_unix_datagram() {
+        if UnixAddr::is_autobind_addr(addr_len) {
+            unix_socket.autobind()?;
+        } else {
+            let unix_addr = unsafe { UnixAddr::try_from_raw(addr, addr_len)? };
+            trace!("bind to addr: {:?}", unix_addr);
+            unix_socket.bind(&unix_addr)?;
+        }
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        let netlink_addr = unsafe { NetlinkAddr::try_from_raw(addr, addr_len)? };
+        trace!("bind to addr: {:?}", netlink_addr);
+        netlink_socket.bind(&netlink_addr)?;
+    } else if let Ok(enclave_socket) = file_ref.as_enclave_socket() {
+        let sock_addr = unsafe { SockAddr::try_from_raw(addr, addr_len)? };
+        trace!("bind to addr: {:?}", sock_addr);
+        enclave_socket.bind(&sock_addr)?;
+    } else if file_ref.as_dns_socket().is_ok() {
+        return_errno!(EINVAL, "the socket is already bound");
     } else {
         return_errno!(EBADF, "not a socket");
     }
@@ -60,6 +90,13 @@ pub fn do_listen(fd: c_int, backlog: c_int) -> Result<isize> {
         socket.listen(backlog)?;
     } else if let Ok(unix_socket) = file_ref.as_unix_socket() {
         unix_socket.listen(backlog)?;
+    } else if let Ok(enclave_socket) = file_ref.as_enclave_socket() {
+        enclave_socket.listen(backlog)?;
+    } else if file_ref.as_unix_datagram().is_ok()
+        || file_ref.as_netlink_socket().is_ok()
+        || file_ref.as_dns_socket().is_ok()
+    {
+        return_errno!(EOPNOTSUPP, "the socket does not support listen");
     } else {
         return_errno!(EBADF, "not a socket");
     }
@@ -81,6 +118,7 @@ pub fn do_connect(
     }
 
     let file_ref = current!().file(fd as FileDesc)?;
+    let file_ref = divert_to_host(fd, file_ref, addr, addr_len, false)?;
     if let Ok(socket) = file_ref.as_host_socket() {
         let addr_option = if addr_set {
             Some(unsafe { SockAddr::try_from_raw(addr, addr_len)? })
@@ -88,7 +126,11 @@ pub fn do_connect(
             None
         };
 
-        socket.connect(&addr_option)?;
+        if let Some(dns_file) = divert_to_resolver(fd, socket, &addr_option)? {
+            dns_file.as_dns_socket()?.connect(&addr_option)?;
+        } else {
+            socket.connect(&addr_option)?;
+        }
     } else if let Ok(unix_socket) = file_ref.as_unix_socket() {
         // TODO: support AF_UNSPEC address for datagram socket use
         let addr = if addr_set {
@@ -98,6 +140,39 @@ pub fn do_connect(
         };
 
         unix_socket.connect(&addr)?;
+    } else if let Ok(unix_socket) = file_ref.as_unix_datagram() {
+        // TODO: support AF_UNSPEC address to dissolve the association
+        let addr = if addr_set {
+            unsafe { UnixAddr::try_from_raw(addr, addr_len)? }
+        } else {
+            return_errno!(EINVAL, "invalid address");
+        };
+
+        unix_socket.connect(&addr)?;
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        let addr = if addr_set {
+            unsafe { NetlinkAddr::try_from_raw(addr, addr_len)? }
+        } else {
+            return_errno!(EINVAL, "invalid address");
+        };
+
+        netlink_socket.connect(&addr)?;
+    } else if let Ok(enclave_socket) = file_ref.as_enclave_socket() {
+        let addr_option = if addr_set {
+            Some(unsafe { SockAddr::try_from_raw(addr, addr_len)? })
+        } else {
+            None
+        };
+
+        enclave_socket.connect(&addr_option)?;
+    } else if let Ok(dns_socket) = file_ref.as_dns_socket() {
+        let addr_option = if addr_set {
+            Some(unsafe { SockAddr::try_from_raw(addr, addr_len)? })
+        } else {
+            None
+        };
+
+        dns_socket.connect(&addr_option)?;
     } else {
         return_errno!(EBADF, "not a socket");
     }
@@ -132,7 +207,7 @@ pub fn do_accept4(
     if let Ok(socket) = file_ref.as_host_socket() {
         let (new_socket_file, sock_addr_option) = socket.accept(file_flags)?;
         let new_file_ref: Arc<dyn File> = Arc::new(new_socket_file);
-        let new_fd = current!().add_file(new_file_ref, close_on_spawn);
+        let new_fd = current!().add_file(new_file_ref, close_on_spawn)?;
 
         if addr_set {
             if let Some(sock_addr) = sock_addr_option {
@@ -152,7 +227,7 @@ pub fn do_accept4(
     } else if let Ok(unix_socket) = file_ref.as_unix_socket() {
         let (new_socket_file, sock_addr_option) = unix_socket.accept(file_flags)?;
         let new_file_ref: Arc<dyn File> = Arc::new(new_socket_file);
-        let new_fd = current!().add_file(new_file_ref, close_on_spawn);
+        let new_fd = current!().add_file(new_file_ref, close_on_spawn)?;
 
         if addr_set {
             if let Some(sock_addr) = sock_addr_option {
@@ -169,6 +244,31 @@ pub fn do_accept4(
             }
         }
         Ok(new_fd as isize)
+    } else if let Ok(enclave_socket) = file_ref.as_enclave_socket() {
+        let (new_socket_file, sock_addr_option) = enclave_socket.accept(file_flags)?;
+        let new_file_ref: Arc<dyn File> = Arc::new(new_socket_file);
+        let new_fd = current!().add_file(new_file_ref, close_on_spawn)?;
+
+        if addr_set {
+            if let Some(sock_addr) = sock_addr_option {
+                let mut buf =
+                    unsafe { std::slice::from_raw_parts_mut(addr as *mut u8, *addr_len as usize) };
+                sock_addr.copy_to_slice(&mut buf);
+                unsafe {
+                    *addr_len = sock_addr.len() as u32;
+                }
+            } else {
+                unsafe {
+                    *addr_len = 0;
+                }
+            }
+        }
+        Ok(new_fd as isize)
+    } else if file_ref.as_unix_datagram().is_ok()
+        || file_ref.as_netlink_socket().is_ok()
+        || file_ref.as_dns_socket().is_ok()
+    {
+        return_errno!(EOPNOTSUPP, "the socket does not support accept");
     } else {
         return_errno!(EBADF, "not a socket");
     }
@@ -183,6 +283,14 @@ pub fn do_shutdown(fd: c_int, how: c_int) -> Result<isize> {
         socket.shutdown(how)?;
     } else if let Ok(unix_socket) = file_ref.as_unix_socket() {
         unix_socket.shutdown(how)?;
+    } else if let Ok(unix_socket) = file_ref.as_unix_datagram() {
+        unix_socket.shutdown(how)?;
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        netlink_socket.shutdown(how)?;
+    } else if let Ok(enclave_socket) = file_ref.as_enclave_socket() {
+        enclave_socket.shutdown(how)?;
+    } else if let Ok(dns_socket) = file_ref.as_dns_socket() {
+        dns_socket.shutdown(how)?;
     } else {
         return_errno!(EBADF, "not a host socket")
     }
@@ -201,19 +309,47 @@ pub fn do_setsockopt(
         "setsockopt: fd: {}, level: {}, optname: {}, optval: {:?}, optlen: {:?}",
         fd, level, optname, optval, optlen
     );
+    let optval = if optlen > 0 {
+        from_user::check_array(optval as *const u8, optlen as usize)?;
+        unsafe { std::slice::from_raw_parts(optval as *const u8, optlen as usize) }
+    } else {
+        &[]
+    };
+
     let file_ref = current!().file(fd as FileDesc)?;
+    // The timeouts of the sockets of the host are handled by the host
+    if SockTimeouts::is_timeout_option(level, optname) {
+        if let Some(socket) = as_sock_timeouts(&file_ref) {
+            let mut timeouts = socket.timeouts();
+            timeouts.set_option(optname, optval)?;
+            socket.set_timeouts(timeouts);
+            return Ok(0);
+        }
+    }
+    if let Some(opt) = SockOpt::find(level, optname) {
+        opt.check_policy()?;
+        if let Some(socket) = as_sock_opts(&file_ref) {
+            socket.sock_opts().lock().unwrap().set(opt, optval)?;
+            return Ok(0);
+        }
+        opt.validate(optval)?;
+    }
+
     if let Ok(socket) = file_ref.as_host_socket() {
-        let ret = try_libc!(libc::ocall::setsockopt(
-            socket.raw_host_fd() as i32,
-            level,
-            optname,
-            optval,
-            optlen
-        ));
-        Ok(ret as isize)
-    } else if let Ok(unix_socket) = file_ref.as_unix_socket() {
+        socket.setsockopt(level, optname, optval)?;
+        Ok(0)
+    } else if file_ref.as_unix_socket().is_ok() || file_ref.as_unix_datagram().is_ok() {
         warn!("setsockopt for unix socket is unimplemented");
         Ok(0)
+    } else if file_ref.as_netlink_socket().is_ok() {
+        warn!("setsockopt for netlink socket is unimplemented");
+        Ok(0)
+    } else if file_ref.as_enclave_socket().is_ok() {
+        warn!("setsockopt for enclave socket is unimplemented");
+        Ok(0)
+    } else if file_ref.as_dns_socket().is_ok() {
+        warn!("setsockopt for DNS socket is unimplemented");
+        Ok(0)
     } else {
         return_errno!(EBADF, "not a socket")
     }
@@ -231,6 +367,63 @@ pub fn do_getsockopt(
         fd, level, optname, optval, optlen
     );
     let file_ref = current!().file(fd as FileDesc)?;
+    if SockTimeouts::is_timeout_option(level, optname) {
+        if let Some(socket) = as_sock_timeouts(&file_ref) {
+            from_user::check_mut_ptr(optlen)?;
+            let len = unsafe { *optlen } as usize;
+            if len < std::mem::size_of::<timeval_t>() {
+                return_errno!(EINVAL, "the option length is too short");
+            }
+            from_user::check_mut_array(optval as *mut u8, len)?;
+            let val = socket.timeouts().get_option(optname)?;
+            unsafe {
+                *(optval as *mut timeval_t) = val;
+                *optlen = std::mem::size_of::<timeval_t>() as u32;
+            }
+            return Ok(0);
+        }
+    }
+    if let Some(opt) = SockOpt::find(level, optname) {
+        opt.check_policy()?;
+        if let Some(socket) = as_sock_opts(&file_ref) {
+            let val = socket.sock_opts().lock().unwrap().get(opt)?;
+            // Same as Linux, the value is truncated to the length given
+            from_user::check_mut_ptr(optlen)?;
+            let len = (unsafe { *optlen } as usize).min(val.len());
+            from_user::check_mut_array(optval as *mut u8, len)?;
+            unsafe {
+                std::ptr::copy_nonoverlapping(val.as_ptr(), optval as *mut u8, len);
+                *optlen = len as u32;
+            }
+            return Ok(0);
+        }
+    }
+    if file_ref.as_enclave_socket().is_ok()
+        || file_ref.as_unix_socket().is_ok()
+        || file_ref.as_unix_datagram().is_ok()
+        || file_ref.as_dns_socket().is_ok()
+    {
+        from_user::check_mut_ptr(optlen)?;
+        let len = unsafe { *optlen } as usize;
+        if len < std::mem::size_of::<i32>() {
+            return_errno!(EINVAL, "the option length is too short");
+        }
+        from_user::check_mut_array(optval as *mut u8, len)?;
+        let val = if let Ok(enclave_socket) = file_ref.as_enclave_socket() {
+            enclave_socket.getsockopt(level, optname)?
+        } else if let Ok(unix_socket) = file_ref.as_unix_socket() {
+            unix_socket.getsockopt(level, optname)?
+        } else if let Ok(unix_socket) = file_ref.as_unix_datagram() {
+            unix_socket.getsockopt(level, optname)?
+        } else {
+            file_ref.as_dns_socket()?.getsockopt(level, optname)?
+        };
+        unsafe {
+            *(optval as *mut i32) = val;
+            *optlen = std::mem::size_of::<i32>() as u32;
+        }
+        return Ok(0);
+    }
     let socket = file_ref.as_host_socket()?;
 
     let ret = try_libc!(libc::ocall::getsockopt(
@@ -266,12 +459,32 @@ pub fn do_getpeername(
         Ok(ret as isize)
     } else if let Ok(unix_socket) = file_ref.as_unix_socket() {
         let name = unix_socket.peer_addr()?;
-        let mut dst = unsafe {
-            std::slice::from_raw_parts_mut(addr as *mut _ as *mut u8, *addr_len as usize)
-        };
-        name.copy_to_slice(dst);
         unsafe {
-            *addr_len = name.raw_len() as u32;
+            copy_unix_addr_to_user(&name, addr, addr_len);
+        }
+        Ok(0)
+    } else if let Ok(unix_socket) = file_ref.as_unix_datagram() {
+        let name = unix_socket.peer_addr()?;
+        unsafe {
+            copy_unix_addr_to_user(&name, addr, addr_len);
+        }
+        Ok(0)
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        let name = netlink_socket.peer_addr();
+        unsafe {
+            copy_netlink_addr_to_user(&name, addr, addr_len);
+        }
+        Ok(0)
+    } else if let Ok(enclave_socket) = file_ref.as_enclave_socket() {
+        let name = enclave_socket.peer_addr()?;
+        unsafe {
+            copy_sock_addr_to_user(&name, addr, addr_len);
+        }
+        Ok(0)
+    } else if let Ok(dns_socket) = file_ref.as_dns_socket() {
+        let name = dns_socket.peer_addr()?;
+        unsafe {
+            copy_sock_addr_to_user(&name, addr, addr_len);
         }
         Ok(0)
     } else {
@@ -304,15 +517,14 @@ pub fn do_getsockname(
             addr_len
         ));
         Ok(ret as isize)
-    } else if let Ok(unix_socket) = file_ref.as_unix_socket() {
-        let name_opt = unix_socket.addr();
+    } else if file_ref.as_unix_socket().is_ok() || file_ref.as_unix_datagram().is_ok() {
+        let name_opt = match file_ref.as_unix_socket() {
+            Ok(unix_socket) => unix_socket.addr(),
+            Err(_) => file_ref.as_unix_datagram()?.addr(),
+        };
         if let Some(name) = name_opt {
-            let mut dst = unsafe {
-                std::slice::from_raw_parts_mut(addr as *mut _ as *mut u8, *addr_len as usize)
-            };
-            name.copy_to_slice(dst);
             unsafe {
-                *addr_len = name.raw_len() as u32;
+                copy_unix_addr_to_user(&name, addr, addr_len);
             }
         } else {
             unsafe {
@@ -321,6 +533,24 @@ pub fn do_getsockname(
             }
         }
         Ok(0)
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        let name = netlink_socket.addr();
+        unsafe {
+            copy_netlink_addr_to_user(&name, addr, addr_len);
+        }
+        Ok(0)
+    } else if let Ok(enclave_socket) = file_ref.as_enclave_socket() {
+        let name = enclave_socket.addr();
+        unsafe {
+            copy_sock_addr_to_user(&name, addr, addr_len);
+        }
+        Ok(0)
+    } else if let Ok(dns_socket) = file_ref.as_dns_socket() {
+        let name = dns_socket.addr();
+        unsafe {
+            copy_sock_addr_to_user(&name, addr, addr_len);
+        }
+        Ok(0)
     } else {
         return_errno!(EBADF, "not a socket");
     }
@@ -350,9 +580,11 @@ pub fn do_sendto(
         from_user::check_mut_array(addr as *mut u8, addr_len as usize)?;
     }
 
-    let send_flags = SendFlags::from_bits(flags).unwrap();
+    // Same as Linux, the unknown flags are ignored
+    let send_flags = SendFlags::from_bits_truncate(flags);
 
     let file_ref = current!().file(fd as FileDesc)?;
+    let file_ref = divert_to_host(fd, file_ref, addr, addr_len, false)?;
     if let Ok(socket) = file_ref.as_host_socket() {
         let addr_option = if addr_set {
             Some(unsafe { SockAddr::try_from_raw(addr, addr_len)? })
@@ -360,6 +592,12 @@ pub fn do_sendto(
             None
         };
 
+        if let Some(dns_file) = divert_to_resolver(fd, socket, &addr_option)? {
+            return dns_file
+                .as_dns_socket()?
+                .sendto(buf, send_flags, &addr_option)
+                .map(|u| u as isize);
+        }
         socket
             .sendto(buf, send_flags, &addr_option)
             .map(|u| u as isize)
@@ -373,6 +611,46 @@ pub fn do_sendto(
         unix_socket
             .sendto(buf, send_flags, &addr_option)
             .map(|u| u as isize)
+    } else if let Ok(unix_socket) = file_ref.as_unix_datagram() {
+        let addr_option = if addr_set {
+            Some(unsafe { UnixAddr::try_from_raw(addr, addr_len)? })
+        } else {
+            None
+        };
+
+        unix_socket
+            .sendto(buf, send_flags, &addr_option)
+            .map(|u| u as isize)
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        let addr_option = if addr_set {
+            Some(unsafe { NetlinkAddr::try_from_raw(addr, addr_len)? })
+        } else {
+            None
+        };
+
+        netlink_socket
+            .sendto(buf, send_flags, &addr_option)
+            .map(|u| u as isize)
+    } else if let Ok(enclave_socket) = file_ref.as_enclave_socket() {
+        let addr_option = if addr_set {
+            Some(unsafe { SockAddr::try_from_raw(addr, addr_len)? })
+        } else {
+            None
+        };
+
+        enclave_socket
+            .sendto(buf, send_flags, &addr_option)
+            .map(|u| u as isize)
+    } else if let Ok(dns_socket) = file_ref.as_dns_socket() {
+        let addr_option = if addr_set {
+            Some(unsafe { SockAddr::try_from_raw(addr, addr_len)? })
+        } else {
+            None
+        };
+
+        dns_socket
+            .sendto(buf, send_flags, &addr_option)
+            .map(|u| u as isize)
     } else {
         return_errno!(EBADF, "unsupported file type");
     }
@@ -393,10 +671,9 @@ pub fn do_recvfrom(
     from_user::check_array(base as *mut u8, len)?;
     let mut buf = unsafe { std::slice::from_raw_parts_mut(base as *mut u8, len as usize) };
 
-    // MSG_CTRUNC is a return flag but linux allows it to be set on input flags.
-    // We just ignore it.
-    let recv_flags = RecvFlags::from_bits(flags & !(MsgHdrFlags::MSG_CTRUNC.bits()))
-        .ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+    // Same as Linux, the unknown flags, e.g., MSG_CTRUNC, which is a return
+    // flag, are ignored
+    let recv_flags = RecvFlags::from_bits_truncate(flags);
 
     let addr_set: bool = !addr.is_null();
     if addr_set {
@@ -422,15 +699,57 @@ pub fn do_recvfrom(
             }
         }
         Ok(data_len as isize)
-    } else if let Ok(unix_socket) = file_ref.as_unix_socket() {
-        let (data_len, sock_addr_option) = unix_socket.recvfrom(buf, recv_flags)?;
+    } else if file_ref.as_unix_socket().is_ok() || file_ref.as_unix_datagram().is_ok() {
+        let (data_len, sock_addr_option) = match file_ref.as_unix_socket() {
+            Ok(unix_socket) => unix_socket.recvfrom(buf, recv_flags)?,
+            Err(_) => file_ref.as_unix_datagram()?.recvfrom(buf, recv_flags)?,
+        };
         if addr_set {
             if let Some(sock_addr) = sock_addr_option {
-                let mut buf =
-                    unsafe { std::slice::from_raw_parts_mut(addr as *mut u8, *addr_len as usize) };
-                sock_addr.copy_to_slice(&mut buf);
                 unsafe {
-                    *addr_len = sock_addr.raw_len() as u32;
+                    copy_unix_addr_to_user(&sock_addr, addr, addr_len);
+                }
+            } else {
+                unsafe {
+                    *addr_len = 0;
+                }
+            }
+        }
+        Ok(data_len as isize)
+    } else if let Ok(netlink_socket) = file_ref.as_netlink_socket() {
+        let (data_len, sock_addr_option) = netlink_socket.recvfrom(buf, recv_flags)?;
+        if addr_set {
+            if let Some(sock_addr) = sock_addr_option {
+                unsafe {
+                    copy_netlink_addr_to_user(&sock_addr, addr, addr_len);
+                }
+            } else {
+                unsafe {
+                    *addr_len = 0;
+                }
+            }
+        }
+        Ok(data_len as isize)
+    } else if let Ok(enclave_socket) = file_ref.as_enclave_socket() {
+        let (data_len, sock_addr_option) = enclave_socket.recvfrom(buf, recv_flags)?;
+        if addr_set {
+            if let Some(sock_addr) = sock_addr_option {
+                unsafe {
+                    copy_sock_addr_to_user(&sock_addr, addr, addr_len);
+                }
+            } else {
+                unsafe {
+                    *addr_len = 0;
+                }
+            }
+        }
+        Ok(data_len as isize)
+    } else if let Ok(dns_socket) = file_ref.as_dns_socket() {
+        let (data_len, sock_addr_option) = dns_socket.recvfrom(buf, recv_flags)?;
+        if addr_set {
+            if let Some(sock_addr) = sock_addr_option {
+                unsafe {
+                    copy_sock_addr_to_user(&sock_addr, addr, addr_len);
                 }
             } else {
                 unsafe {
@@ -464,9 +783,14 @@ pub fn do_socketpair(
         let (client_socket, server_socket) = socketpair(sock_type, file_flags, protocol as i32)?;
 
         let current = current!();
-        let mut files = current.files().lock().unwrap();
-        sock_pair[0] = files.put(Arc::new(client_socket), close_on_spawn);
-        sock_pair[1] = files.put(Arc::new(server_socket), close_on_spawn);
+        sock_pair[0] = current.add_file(client_socket, close_on_spawn)?;
+        sock_pair[1] = match current.add_file(server_socket, close_on_spawn) {
+            Ok(fd) => fd,
+            Err(e) => {
+                current.files().lock().unwrap().del(sock_pair[0])?;
+                return Err(e);
+            }
+        };
 
         debug!("socketpair: ({}, {})", sock_pair[0], sock_pair[1]);
         Ok(0)
@@ -493,11 +817,91 @@ pub fn do_sendmsg(fd: c_int, msg_ptr: *const msghdr, flags_c: c_int) -> Result<i
 
         let flags = SendFlags::from_bits_truncate(flags_c);
 
+        let addr_option = match msg.get_name() {
+            Some(name) if !name.is_empty() => Some(unsafe {
+                SockAddr::try_from_raw(name.as_ptr() as *const libc::sockaddr, name.len() as u32)?
+            }),
+            _ => None,
+        };
+        if let Some(dns_file) = divert_to_resolver(fd, socket, &addr_option)? {
+            return dns_file
+                .as_dns_socket()?
+                .sendmsg(&msg, flags)
+                .map(|bytes_sent| bytes_sent as isize);
+        }
         socket
             .sendmsg(&msg, flags)
             .map(|bytes_sent| bytes_sent as isize)
     } else if let Ok(socket) = file_ref.as_unix_socket() {
-        return_errno!(EBADF, "does not support unix socket")
+        let msg_c = {
+            from_user::check_ptr(msg_ptr)?;
+            let msg_c = unsafe { &*msg_ptr };
+            msg_c.check_member_ptrs()?;
+            msg_c
+        };
+        let msg = unsafe { MsgHdr::from_c(&msg_c)? };
+
+        let flags = SendFlags::from_bits_truncate(flags_c);
+
+        socket
+            .sendmsg(&msg, flags)
+            .map(|bytes_sent| bytes_sent as isize)
+    } else if let Ok(socket) = file_ref.as_unix_datagram() {
+        let msg_c = {
+            from_user::check_ptr(msg_ptr)?;
+            let msg_c = unsafe { &*msg_ptr };
+            msg_c.check_member_ptrs()?;
+            msg_c
+        };
+        let msg = unsafe { MsgHdr::from_c(&msg_c)? };
+
+        let flags = SendFlags::from_bits_truncate(flags_c);
+
+        socket
+            .sendmsg(&msg, flags)
+            .map(|bytes_sent| bytes_sent as isize)
+    } else if let Ok(socket) = file_ref.as_netlink_socket() {
+        let msg_c = {
+            from_user::check_ptr(msg_ptr)?;
+            let msg_c = unsafe { &*msg_ptr };
+            msg_c.check_member_ptrs()?;
+            msg_c
+        };
+        let msg = unsafe { MsgHdr::from_c(&msg_c)? };
+
+        let flags = SendFlags::from_bits_truncate(flags_c);
+
+        socket
+            .sendmsg(&msg, flags)
+            .map(|bytes_sent| bytes_sent as isize)
+    } else if let Ok(socket) = file_ref.as_enclave_socket() {
+        let msg_c = {
+            from_user::check_ptr(msg_ptr)?;
+            let msg_c = unsafe { &*msg_ptr };
+            msg_c.check_member_ptrs()?;
+            msg_c
+        };
+        let msg = unsafe { MsgHdr::from_c(&msg_c)? };
+
+        let flags = SendFlags::from_bits_truncate(flags_c);
+
+        socket
+            .sendmsg(&msg, flags)
+            .map(|bytes_sent| bytes_sent as isize)
+    } else if let Ok(socket) = file_ref.as_dns_socket() {
+        let msg_c = {
+            from_user::check_ptr(msg_ptr)?;
+            let msg_c = unsafe { &*msg_ptr };
+            msg_c.check_member_ptrs()?;
+            msg_c
+        };
+        let msg = unsafe { MsgHdr::from_c(&msg_c)? };
+
+        let flags = SendFlags::from_bits_truncate(flags_c);
+
+        socket
+            .sendmsg(&msg, flags)
+            .map(|bytes_sent| bytes_sent as isize)
     } else {
         return_errno!(EBADF, "not a socket")
     }
@@ -525,12 +929,368 @@ pub fn do_recvmsg(fd: c_int, msg_mut_ptr: *mut msghdr_mut, flags_c: c_int) -> Re
             .recvmsg(&mut msg_mut, flags)
             .map(|bytes_recvd| bytes_recvd as isize)
     } else if let Ok(socket) = file_ref.as_unix_socket() {
-        return_errno!(EBADF, "does not support unix socket")
+        let msg_mut_c = {
+            from_user::check_mut_ptr(msg_mut_ptr)?;
+            let msg_mut_c = unsafe { &mut *msg_mut_ptr };
+            msg_mut_c.check_member_ptrs()?;
+            msg_mut_c
+        };
+        let mut msg_mut = unsafe { MsgHdrMut::from_c(msg_mut_c)? };
+
+        let flags = RecvFlags::from_bits_truncate(flags_c);
+
+        socket
+            .recvmsg(&mut msg_mut, flags)
+            .map(|bytes_recvd| bytes_recvd as isize)
+    } else if let Ok(socket) = file_ref.as_unix_datagram() {
+        let msg_mut_c = {
+            from_user::check_mut_ptr(msg_mut_ptr)?;
+            let msg_mut_c = unsafe { &mut *msg_mut_ptr };
+            msg_mut_c.check_member_ptrs()?;
+            msg_mut_c
+        };
+        let mut msg_mut = unsafe { MsgHdrMut::from_c(msg_mut_c)? };
+
+        let flags = RecvFlags::from_bits_truncate(flags_c);
+
+        socket
+            .recvmsg(&mut msg_mut, flags)
+            .map(|bytes_recvd| bytes_recvd as isize)
+    } else if let Ok(socket) = file_ref.as_netlink_socket() {
+        let msg_mut_c = {
+            from_user::check_mut_ptr(msg_mut_ptr)?;
+            let msg_mut_c = unsafe { &mut *msg_mut_ptr };
+            msg_mut_c.check_member_ptrs()?;
+            msg_mut_c
+        };
+        let mut msg_mut = unsafe { MsgHdrMut::from_c(msg_mut_c)? };
+
+        let flags = RecvFlags::from_bits_truncate(flags_c);
+
+        socket
+            .recvmsg(&mut msg_mut, flags)
+            .map(|bytes_recvd| bytes_recvd as isize)
+    } else if let Ok(socket) = file_ref.as_enclave_socket() {
+        let msg_mut_c = {
+            from_user::check_mut_ptr(msg_mut_ptr)?;
+            let msg_mut_c = unsafe { &mut *msg_mut_ptr };
+            msg_mut_c.check_member_ptrs()?;
+            msg_mut_c
+        };
+        let mut msg_mut = unsafe { MsgHdrMut::from_c(msg_mut_c)? };
+
+        let flags = RecvFlags::from_bits_truncate(flags_c);
+
+        socket
+            .recvmsg(&mut msg_mut, flags)
+            .map(|bytes_recvd| bytes_recvd as isize)
+    } else if let Ok(socket) = file_ref.as_dns_socket() {
+        let msg_mut_c = {
+            from_user::check_mut_ptr(msg_mut_ptr)?;
+            let msg_mut_c = unsafe { &mut *msg_mut_ptr };
+            msg_mut_c.check_member_ptrs()?;
+            msg_mut_c
+        };
+        let mut msg_mut = unsafe { MsgHdrMut::from_c(msg_mut_c)? };
+
+        let flags = RecvFlags::from_bits_truncate(flags_c);
+
+        socket
+            .recvmsg(&mut msg_mut, flags)
+            .map(|bytes_recvd| bytes_recvd as isize)
     } else {
         return_errno!(EBADF, "not a socket")
     }
 }
 
+// Same as Linux, at most UIO_MAXIOV messages are sent or received by one
+// sendmmsg or recvmmsg
+const UIO_MAXIOV: usize = 1024;
+
+pub fn do_sendmmsg(fd: c_int, msgvec: *mut mmsghdr, vlen: u32, flags_c: c_int) -> Result<isize> {
+    debug!(
+        "sendmmsg: fd: {}, msgvec: {:?}, vlen: {}, flags: 0x{:x}",
+        fd, msgvec, vlen, flags_c
+    );
+    let vlen = (vlen as usize).min(UIO_MAXIOV);
+    if vlen == 0 {
+        return Ok(0);
+    }
+    from_user::check_mut_array(msgvec, vlen)?;
+    let msgvec = unsafe { std::slice::from_raw_parts_mut(msgvec, vlen) };
+
+    let file_ref = current!().file(fd as FileDesc)?;
+    let socket = match file_ref.as_host_socket() {
+        Ok(socket) => socket,
+        // The other sockets are handled inside the enclave, so sending the
+        // messages one by one costs no more OCalls
+        Err(_) => {
+            for (i, msg) in msgvec.iter_mut().enumerate() {
+                match do_sendmsg(fd, &msg.msg_hdr, flags_c) {
+                    Ok(bytes_sent) => msg.msg_len = bytes_sent as u32,
+                    Err(e) if i == 0 => return Err(e),
+                    Err(_) => return Ok(i as isize),
+                }
+            }
+            return Ok(vlen as isize);
+        }
+    };
+
+    let bytes_sent = {
+        let msgs = msgvec
+            .iter()
+            .map(|msg_c| {
+                msg_c.msg_hdr.check_member_ptrs()?;
+                unsafe { MsgHdr::from_c(&msg_c.msg_hdr) }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let flags = SendFlags::from_bits_truncate(flags_c);
+        socket.sendmmsg(&msgs, flags)?
+    };
+    for (msg_c, len) in msgvec.iter_mut().zip(bytes_sent.iter()) {
+        msg_c.msg_len = *len as u32;
+    }
+    Ok(bytes_sent.len() as isize)
+}
+
+pub fn do_recvmmsg(
+    fd: c_int,
+    msgvec: *mut mmsghdr_mut,
+    vlen: u32,
+    flags_c: c_int,
+    timeout: *const timespec_t,
+) -> Result<isize> {
+    debug!(
+        "recvmmsg: fd: {}, msgvec: {:?}, vlen: {}, flags: 0x{:x}, timeout: {:?}",
+        fd, msgvec, vlen, flags_c, timeout
+    );
+    let vlen = (vlen as usize).min(UIO_MAXIOV);
+    if vlen == 0 {
+        return Ok(0);
+    }
+    from_user::check_mut_array(msgvec, vlen)?;
+    let msgvec = unsafe { std::slice::from_raw_parts_mut(msgvec, vlen) };
+    let timeout = if timeout.is_null() {
+        None
+    } else {
+        from_user::check_ptr(timeout)?;
+        Some(timespec_t::from_raw_ptr(timeout)?)
+    };
+
+    let file_ref = current!().file(fd as FileDesc)?;
+    let socket = match file_ref.as_host_socket() {
+        Ok(socket) => socket,
+        // The other sockets are handled inside the enclave, so receiving the
+        // messages one by one costs no more OCalls
+        Err(_) => {
+            let deadline = match timeout {
+                Some(timeout) => Some(
+                    do_clock_gettime(ClockID::CLOCK_MONOTONIC)?.as_duration()
+                        + timeout.as_duration(),
+                ),
+                None => None,
+            };
+            let mut flags = flags_c & !RecvFlags::MSG_WAITFORONE.bits();
+            for (i, msg) in msgvec.iter_mut().enumerate() {
+                match do_recvmsg(fd, &mut msg.msg_hdr, flags) {
+                    Ok(bytes_recvd) => msg.msg_len = bytes_recvd as u32,
+                    Err(e) if i == 0 => return Err(e),
+                    Err(_) => return Ok(i as isize),
+                }
+                if flags_c & RecvFlags::MSG_WAITFORONE.bits() != 0 {
+                    flags |= RecvFlags::MSG_DONTWAIT.bits();
+                }
+                if let Some(deadline) = deadline {
+                    if do_clock_gettime(ClockID::CLOCK_MONOTONIC)?.as_duration() >= deadline {
+                        return Ok(i as isize + 1);
+                    }
+                }
+            }
+            return Ok(vlen as isize);
+        }
+    };
+
+    let bytes_recvd = {
+        let mut msgs = msgvec
+            .iter_mut()
+            .map(|msg_c| {
+                msg_c.msg_hdr.check_member_ptrs()?;
+                unsafe { MsgHdrMut::from_c(&mut msg_c.msg_hdr) }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let flags = RecvFlags::from_bits_truncate(flags_c);
+        socket.recvmmsg(&mut msgs, flags, timeout.as_ref())?
+    };
+    for (msg_c, len) in msgvec.iter_mut().zip(bytes_recvd.iter()) {
+        msg_c.msg_len = *len as u32;
+    }
+    Ok(bytes_recvd.len() as isize)
+}
+
+// Replace the UDP or TCP host socket with a DNS socket if it is going to talk
+// to the resolver of LibOS, returning the DNS socket that takes the place of
+// the fd. The DNS socket also takes the place of the host socket in the other
+// fds of all processes, e.g., the dup'd and the inherited ones.
+fn divert_to_resolver(
+    fd: c_int,
+    socket: &HostSocket,
+    addr: &Option<SockAddr>,
+) -> Result<Option<FileRef>> {
+    match addr {
+        Some(addr) if is_resolver_addr(addr) => {}
+        _ => return Ok(None),
+    }
+    let socket_type = socket.socket_type()?;
+    if socket_type != SocketType::DGRAM && socket_type != SocketType::STREAM {
+        return Ok(None);
+    }
+
+    let nonblocking = socket.status_flags()?.contains(StatusFlags::O_NONBLOCK);
+    let local_addr = socket.addr().unwrap_or_default();
+    let dns_file: FileRef = Arc::new(DnsSocket::new(local_addr, socket_type, nonblocking));
+    {
+        let current = current!();
+        let mut files = current.files().lock().unwrap();
+        *files.get_entry_mut(fd as FileDesc)?.get_file_mut() = dns_file.clone();
+    }
+    debug!("divert fd {} to the resolver", fd);
+
+    // The file tables are locked one by one, so no deadlock can happen
+    let mut file_tables: Vec<process::FileTableRef> = Vec::new();
+    for thread in process::table::get_all_threads() {
+        let file_table = thread.files();
+        if file_tables
+            .iter()
+            .any(|table| Arc::ptr_eq(table, file_table))
+        {
+            continue;
+        }
+        file_tables.push(file_table.clone());
+
+        let mut files = file_table.lock().unwrap();
+        for (other_fd, file_ref) in files.fds_and_files() {
+            let is_same_socket = file_ref
+                .as_host_socket()
+                .map_or(false, |other| std::ptr::eq(other, socket));
+            if is_same_socket {
+                *files.get_entry_mut(other_fd)?.get_file_mut() = dns_file.clone();
+            }
+        }
+    }
+    Ok(Some(dns_file))
+}
+
+// Divert a socket of the in-enclave stack to a socket of the host before it is
+// bound or connected, if the stack does not serve the address, e.g., one on
+// the loopback. The options set so far are applied to the socket of the host.
+fn divert_to_host(
+    fd: c_int,
+    file_ref: FileRef,
+    addr: *const libc::sockaddr,
+    addr_len: libc::socklen_t,
+    is_local: bool,
+) -> Result<FileRef> {
+    let socket = match file_ref.as_enclave_socket() {
+        Ok(socket) if !addr.is_null() && socket.is_unbound() => socket,
+        _ => return Ok(file_ref.clone()),
+    };
+    let sock_addr = unsafe { SockAddr::try_from_raw(addr, addr_len)? };
+    if is_enclave_addr(&sock_addr, is_local) {
+        return Ok(file_ref.clone());
+    }
+
+    let mut file_flags = FileFlags::empty();
+    if socket.nonblocking() {
+        file_flags |= FileFlags::SOCK_NONBLOCK;
+    }
+    let host_socket = HostSocket::new(AddressFamily::INET, socket.socket_type(), file_flags, 0)?;
+    for (opt, optval) in socket.sock_opts().lock().unwrap().options() {
+        host_socket.setsockopt(opt.level, opt.optname, &optval)?;
+    }
+    for (level, optname, optval) in socket.timeouts().options() {
+        let optval = unsafe {
+            std::slice::from_raw_parts(
+                &optval as *const timeval_t as *const u8,
+                std::mem::size_of::<timeval_t>(),
+            )
+        };
+        host_socket.setsockopt(level, optname, optval)?;
+    }
+
+    let host_file: FileRef = Arc::new(host_socket);
+    let current = current!();
+    let mut files = current.files().lock().unwrap();
+    *files.get_entry_mut(fd as FileDesc)?.get_file_mut() = host_file.clone();
+    debug!("divert fd {} to the host", fd);
+    Ok(host_file)
+}
+
+// Get the timeouts of the sockets implemented inside the enclave.
+fn as_sock_timeouts(file_ref: &FileRef) -> Option<&dyn SockTimeoutsExt> {
+    if let Ok(socket) = file_ref.as_unix_socket() {
+        Some(socket)
+    } else if let Ok(socket) = file_ref.as_unix_datagram() {
+        Some(socket)
+    } else if let Ok(socket) = file_ref.as_netlink_socket() {
+        Some(socket)
+    } else if let Ok(socket) = file_ref.as_enclave_socket() {
+        Some(socket)
+    } else if let Ok(socket) = file_ref.as_dns_socket() {
+        Some(socket)
+    } else {
+        None
+    }
+}
+
+// Get the options in the table tracked by the sockets implemented inside the
+// enclave.
+fn as_sock_opts(file_ref: &FileRef) -> Option<&dyn SockOptsExt> {
+    if let Ok(socket) = file_ref.as_unix_socket() {
+        Some(socket)
+    } else if let Ok(socket) = file_ref.as_unix_datagram() {
+        Some(socket)
+    } else if let Ok(socket) = file_ref.as_enclave_socket() {
+        Some(socket)
+    } else {
+        None
+    }
+}
+
+// Copy the address of a unix socket to the user buffer, whose length is
+// updated to that of the address. The caller should guarantee the pointers
+// are valid.
+unsafe fn copy_unix_addr_to_user(
+    name: &UnixAddr,
+    addr: *mut libc::sockaddr,
+    addr_len: *mut libc::socklen_t,
+) {
+    let dst = std::slice::from_raw_parts_mut(addr as *mut u8, *addr_len as usize);
+    name.copy_to_slice(dst);
+    *addr_len = name.raw_len() as u32;
+}
+
+unsafe fn copy_netlink_addr_to_user(
+    name: &NetlinkAddr,
+    addr: *mut libc::sockaddr,
+    addr_len: *mut libc::socklen_t,
+) {
+    let dst = std::slice::from_raw_parts_mut(addr as *mut u8, *addr_len as usize);
+    name.copy_to_slice(dst);
+    *addr_len = NetlinkAddr::raw_len() as u32;
+}
+
+// Copy the socket address to the user buffer, whose length is updated to that
+// of the address. The caller should guarantee the pointers are valid.
+unsafe fn copy_sock_addr_to_user(
+    name: &SockAddr,
+    addr: *mut libc::sockaddr,
+    addr_len: *mut libc::socklen_t,
+) {
+    let dst = std::slice::from_raw_parts_mut(addr as *mut u8, *addr_len as usize);
+    name.copy_to_slice(dst);
+    *addr_len = name.len() as u32;
+}
+
 #[allow(non_camel_case_types)]
 trait c_msghdr_ext {
     fn check_member_ptrs(&self) -> Result<()>;
@@ -598,6 +1358,84 @@ pub fn do_select(
     writefds: *mut libc::fd_set,
     exceptfds: *mut libc::fd_set,
     timeout: *mut timeval_t,
+) -> Result<isize> {
+    let mut timeout_c = if !timeout.is_null() {
+        from_user::check_ptr(timeout)?;
+        let timeval = unsafe { &mut *timeout };
+        timeval.validate()?;
+        Some(timeval)
+    } else {
+        None
+    };
+    let mut timeout = timeout_c.as_ref().map(|timeout_c| timeout_c.as_duration());
+
+    let ret = select_fds(nfds, readfds, writefds, exceptfds, timeout.as_mut(), None);
+
+    if let Some(timeout_c) = timeout_c {
+        *timeout_c = timeout.unwrap().into();
+    }
+
+    ret
+}
+
+/// The last argument of pselect6, which packs the signal mask and its size.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct sigset_argpack_t {
+    ss: *const sigset_t,
+    ss_len: usize,
+}
+
+pub fn do_pselect6(
+    nfds: c_int,
+    readfds: *mut libc::fd_set,
+    writefds: *mut libc::fd_set,
+    exceptfds: *mut libc::fd_set,
+    timeout: *mut timespec_t,
+    sigmask_argpack: *const sigset_argpack_t,
+) -> Result<isize> {
+    let mut timeout_c = if !timeout.is_null() {
+        from_user::check_mut_ptr(timeout)?;
+        let timespec = unsafe { &mut *timeout };
+        timespec.validate()?;
+        Some(timespec)
+    } else {
+        None
+    };
+    let mut timeout = timeout_c.as_ref().map(|timeout_c| timeout_c.as_duration());
+
+    let sigmask = if !sigmask_argpack.is_null() {
+        from_user::check_ptr(sigmask_argpack)?;
+        let argpack = unsafe { &*sigmask_argpack };
+        read_sigmask(argpack.ss, argpack.ss_len)?
+    } else {
+        None
+    };
+
+    let ret = select_fds(
+        nfds,
+        readfds,
+        writefds,
+        exceptfds,
+        timeout.as_mut(),
+        sigmask,
+    );
+
+    // Same as Linux, the remaining time is written back
+    if let Some(timeout_c) = timeout_c {
+        *timeout_c = timeout.unwrap().into();
+    }
+
+    ret
+}
+
+fn select_fds(
+    nfds: c_int,
+    readfds: *mut libc::fd_set,
+    writefds: *mut libc::fd_set,
+    exceptfds: *mut libc::fd_set,
+    timeout: Option<&mut Duration>,
+    sigmask: Option<SigSet>,
 ) -> Result<isize> {
     let nfds = {
         let soft_rlimit_nofile = current!()
@@ -615,16 +1453,6 @@ pub fn do_select(
         nfds as FileDesc
     };
 
-    let mut timeout_c = if !timeout.is_null() {
-        from_user::check_ptr(timeout)?;
-        let timeval = unsafe { &mut *timeout };
-        timeval.validate()?;
-        Some(timeval)
-    } else {
-        None
-    };
-    let mut timeout = timeout_c.as_ref().map(|timeout_c| timeout_c.as_duration());
-
     let readfds = if !readfds.is_null() {
         from_user::check_mut_ptr(readfds)?;
         Some(unsafe { &mut *readfds })
@@ -644,8 +1472,43 @@ pub fn do_select(
         None
     };
 
-    let ret = io_multiplexing::do_select(nfds, readfds, writefds, exceptfds, timeout.as_mut());
+    wait_with_sigmask(sigmask, || {
+        io_multiplexing::do_select(nfds, readfds, writefds, exceptfds, timeout)
+    })
+}
+
+pub fn do_poll(fds: *mut libc::pollfd, nfds: libc::nfds_t, timeout_ms: c_int) -> Result<isize> {
+    let mut timeout = if timeout_ms >= 0 {
+        Some(Duration::from_millis(timeout_ms as u64))
+    } else {
+        None
+    };
+
+    poll_fds(fds, nfds, timeout.as_mut(), None)
+}
+
+pub fn do_ppoll(
+    fds: *mut libc::pollfd,
+    nfds: libc::nfds_t,
+    timeout: *mut timespec_t,
+    sigmask: *const sigset_t,
+    sigset_size: usize,
+) -> Result<isize> {
+    let mut timeout_c = if !timeout.is_null() {
+        from_user::check_mut_ptr(timeout)?;
+        let timespec = unsafe { &mut *timeout };
+        timespec.validate()?;
+        Some(timespec)
+    } else {
+        None
+    };
+    let mut timeout = timeout_c.as_ref().map(|timeout_c| timeout_c.as_duration());
+
+    let sigmask = read_sigmask(sigmask, sigset_size)?;
 
+    let ret = poll_fds(fds, nfds, timeout.as_mut(), sigmask);
+
+    // Same as Linux, the remaining time is written back
     if let Some(timeout_c) = timeout_c {
         *timeout_c = timeout.unwrap().into();
     }
@@ -653,7 +1516,12 @@ pub fn do_select(
     ret
 }
 
-pub fn do_poll(fds: *mut libc::pollfd, nfds: libc::nfds_t, timeout_ms: c_int) -> Result<isize> {
+fn poll_fds(
+    fds: *mut libc::pollfd,
+    nfds: libc::nfds_t,
+    timeout: Option<&mut Duration>,
+    sigmask: Option<SigSet>,
+) -> Result<isize> {
     // It behaves like sleep when fds is null and nfds is zero.
     if !fds.is_null() || nfds != 0 {
         from_user::check_mut_array(fds, nfds as usize)?;
@@ -676,13 +1544,7 @@ pub fn do_poll(fds: *mut libc::pollfd, nfds: libc::nfds_t, timeout_ms: c_int) ->
         .map(|raw| PollFd::from_raw(raw))
         .collect();
 
-    let mut timeout = if timeout_ms >= 0 {
-        Some(Duration::from_millis(timeout_ms as u64))
-    } else {
-        None
-    };
-
-    let count = io_multiplexing::do_poll_new(&poll_fds, timeout.as_mut())?;
+    let count = wait_with_sigmask(sigmask, || io_multiplexing::do_poll_new(&poll_fds, timeout))?;
 
     for (raw_poll_fd, poll_fd) in raw_poll_fds.iter_mut().zip(poll_fds.iter()) {
         raw_poll_fd.revents = poll_fd.revents().get().to_raw() as i16;
@@ -690,6 +1552,19 @@ pub fn do_poll(fds: *mut libc::pollfd, nfds: libc::nfds_t, timeout_ms: c_int) ->
     Ok(count as isize)
 }
 
+// Read the signal mask given to ppoll, pselect or epoll_pwait, which is
+// optional
+fn read_sigmask(sigmask: *const sigset_t, sigset_size: usize) -> Result<Option<SigSet>> {
+    if sigmask.is_null() {
+        return Ok(None);
+    }
+    if sigset_size != std::mem::size_of::<sigset_t>() {
+        return_errno!(EINVAL, "the size of the signal mask is not correct");
+    }
+    from_user::check_ptr(sigmask)?;
+    Ok(Some(SigSet::from_c(unsafe { *sigmask })))
+}
+
 pub fn do_epoll_create(size: c_int) -> Result<isize> {
     if size <= 0 {
         return_errno!(EINVAL, "size is not positive");
@@ -706,7 +1581,7 @@ pub fn do_epoll_create1(raw_flags: c_int) -> Result<isize> {
         & CreationFlags::O_CLOEXEC;
     let epoll_file: Arc<EpollFile> = EpollFile::new();
     let close_on_spawn = flags.contains(CreationFlags::O_CLOEXEC);
-    let epfd = current!().add_file(epoll_file, close_on_spawn);
+    let epfd = current!().add_file(epoll_file, close_on_spawn)?;
     Ok(epfd as isize)
 }
 
@@ -801,12 +1676,9 @@ pub fn do_epoll_pwait(
     events: *mut libc::epoll_event,
     maxevents: c_int,
     timeout: c_int,
-    sigmask: *const usize, //TODO:add sigset_t
+    sigmask: *const sigset_t,
+    sigset_size: usize,
 ) -> Result<isize> {
-    if !sigmask.is_null() {
-        warn!("epoll_pwait cannot handle signal mask, yet");
-    } else {
-        debug!("epoll_wait");
-    }
-    do_epoll_wait(epfd, events, maxevents, timeout)
+    let sigmask = read_sigmask(sigmask, sigset_size)?;
+    wait_with_sigmask(sigmask, || do_epoll_wait(epfd, events, maxevents, timeout))
 }
@@ -20,6 +20,7 @@ pub fn do_clone(
     );
 
     check_clone_args(flags, user_rsp, ptid, ctid, new_tls)?;
+    crate::misc::check_nproc_limit(&current!())?;
 
     // Get thread entry, an implicit argument passed on the stack.
     //
@@ -35,6 +36,14 @@ pub fn do_clone(
         let vm = current.vm().clone();
         let task = {
             let user_stack_range = guess_user_stack_bound(&vm, user_rsp)?;
+            // A stack overflow of the new thread is caught by the guard region
+            // below its stack, instead of corrupting the memory there
+            if let Err(e) = vm.add_stack_guard(&user_stack_range) {
+                warn!(
+                    "failed to set up the stack guard of the new thread: {:?}",
+                    e
+                );
+            }
             let user_stack_base = user_stack_range.end();
             let user_stack_limit = user_stack_range.start();
             unsafe {
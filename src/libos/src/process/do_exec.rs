@@ -0,0 +1,88 @@
+use std::ffi::CString;
+
+use super::do_exit::exit_replaced_process;
+use super::do_fork::{has_suspended_parent, set_parent_retval};
+use crate::fs::{FsPath, RangeLockTable};
+use crate::prelude::*;
+use rcore_fs::vfs::FileType;
+
+/// Execute a program in place of the current process.
+///
+/// The memory of a process cannot be replaced in LibOS. So exec is supported
+/// only in a child created by fork or vfork, i.e., the common pattern of fork
+/// followed by exec, which is short-circuited into spawn: a new process is
+/// spawned from the program with the files and the working directory of the
+/// child, then the child exits and the suspended parent resumes from fork with
+/// the pid of the new process, which is adopted by the parent.
+///
+/// Same as Linux, the POSIX record locks of the child are kept by the new
+/// process, except the ones on the files that are closed on exec.
+pub fn do_exec(path: &str, argv: &[CString], envp: &[CString]) -> Result<()> {
+    let current = current!();
+    if !has_suspended_parent(&current) {
+        return_errno!(
+            ENOSYS,
+            "exec is only supported in the child of fork or vfork"
+        );
+    }
+
+    let new_pid = super::do_spawn::do_spawn(path, argv, envp, &[], &current)?;
+    let close_on_exec_files = current.files().lock().unwrap().close_on_spawn_files();
+    for file in close_on_exec_files {
+        file.release_advisory_locks();
+    }
+    RangeLockTable::transfer_all_locks(current.process().pid(), new_pid);
+    info!(
+        "Process execed: pid = {}, new pid = {}",
+        current.process().pid(),
+        new_pid
+    );
+
+    set_parent_retval(&current, new_pid);
+    exit_replaced_process();
+    Ok(())
+}
+
+bitflags! {
+    pub struct ExecveatFlags: i32 {
+        const AT_SYMLINK_NOFOLLOW = 0x100;
+        const AT_EMPTY_PATH = 0x1000;
+    }
+}
+
+/// Execute a program referred to by a path relative to a directory fd, or by
+/// an fd itself if the path is empty and AT_EMPTY_PATH is given, e.g., fexecve.
+///
+/// Same as Linux, the program referred to by an fd is executed through the path
+/// of `/dev/fd/N`, which also works for the files without a path, e.g., memfds
+/// and unlinked files. If the program is a script, `/dev/fd/N` is passed to the
+/// interpreter, so the fd must not be close-on-exec.
+pub fn do_execveat(
+    fs_path: &FsPath,
+    argv: &[CString],
+    envp: &[CString],
+    flags: ExecveatFlags,
+) -> Result<()> {
+    let path = match fs_path.as_fd() {
+        Some(fd) => {
+            // Make sure that the fd is valid
+            current!().file(fd)?;
+            format!("/dev/fd/{}", fd)
+        }
+        None => {
+            let path = fs_path.to_abs_path()?;
+            if flags.contains(ExecveatFlags::AT_SYMLINK_NOFOLLOW) {
+                let inode = current!()
+                    .fs()
+                    .lock()
+                    .unwrap()
+                    .lookup_inode_no_follow(&path)?;
+                if inode.metadata()?.type_ == FileType::SymLink {
+                    return_errno!(ELOOP, "the file is a symbolic link");
+                }
+            }
+            path
+        }
+    };
+    do_exec(&path, argv, envp)
+}
@@ -4,8 +4,10 @@ use std::intrinsics::atomic_store;
 use super::do_futex::futex_wake;
 use super::process::{Process, ProcessFilter};
 use super::{table, TermStatus, ThreadRef, ThreadStatus};
+use crate::fs::{IoEvents, RangeLockTable};
+use crate::ipc::exit_sem;
 use crate::prelude::*;
-use crate::signal::{KernelSignal, SigNum};
+use crate::signal::{ChildSignal, SigAction, SigActionFlags};
 
 pub fn do_exit_group(status: i32) {
     let term_status = TermStatus::Exited(status as u8);
@@ -55,12 +57,50 @@ fn exit_thread(term_status: TermStatus) {
     // If this thread is the last thread, then exit the process
     if num_remaining_threads == 0 {
         exit_process(&thread, term_status);
+        // Notify the pidfds of the process after all the locks are released
+        thread.process().exit_notifier().broadcast(&IoEvents::IN);
     }
 }
 
+/// Exit the current process, which is replaced by a new process spawned by
+/// it, e.g., a child of vfork that execs.
+///
+/// Unlike a normal exit, the parent is neither notified nor needs to reap the
+/// process. Instead, the children of the process are adopted by the parent.
+pub(super) fn exit_replaced_process() {
+    let thread = current!();
+    let term_status = TermStatus::Exited(0);
+    let num_remaining_threads = thread.exit(term_status);
+    debug_assert!(num_remaining_threads == 0);
+
+    let process = thread.process();
+    RangeLockTable::release_all_locks(process.pid());
+    exit_sem(process.pid());
+
+    // Deadlock note: always lock parent first, then child.
+    let parent = process.parent();
+    let mut parent_inner = parent.inner();
+    let mut process_inner = process.inner();
+    process_inner.exit(term_status, &parent, &mut parent_inner);
+    parent_inner.remove_zombie_child(process.pid());
+
+    let pid = process.pid();
+    table::del_thread(pid).expect("tid must be in the table");
+    table::del_process(pid).expect("pid must be in the table");
+    drop(process_inner);
+    drop(parent_inner);
+
+    process.exit_notifier().broadcast(&IoEvents::IN);
+}
+
 fn exit_process(thread: &ThreadRef, term_status: TermStatus) {
     let process = thread.process();
 
+    // The POSIX record locks of a process are released when it exits
+    RangeLockTable::release_all_locks(process.pid());
+    // And the operations of SEM_UNDO on System V semaphores are reverted
+    exit_sem(process.pid());
+
     // Deadlock note: always lock parent first, then child.
 
     // Lock the idle process since it may adopt new children.
@@ -106,11 +146,20 @@ fn exit_process(thread: &ThreadRef, term_status: TermStatus) {
     process_inner.exit(term_status, &idle_ref, &mut idle_inner);
 
     //Send SIGCHLD to parent
-    send_sigchld_to(&parent);
+    send_sigchld_to(&parent, &process, term_status);
+
+    // If the parent ignores SIGCHLD or sets SA_NOCLDWAIT for it, the child is
+    // reaped automatically instead of becoming a zombie.
+    let is_reaped_automatically = is_reaping_children_automatically(&parent);
+    if is_reaped_automatically {
+        let pid = process.pid();
+        table::del_thread(pid).expect("tid must be in the table");
+        table::del_process(pid).expect("pid must be in the table");
+        parent_inner.remove_zombie_child(pid);
+    }
 
     // Wake up the parent if it is waiting on this child
-    let waiting_children = parent_inner.waiting_children_mut().unwrap();
-    waiting_children.del_and_wake_one_waiter(|waiter_data| -> Option<pid_t> {
+    let is_waited = |waiter_data: &ProcessFilter| -> Option<pid_t> {
         match waiter_data {
             ProcessFilter::WithAnyPid => {}
             ProcessFilter::WithPid(required_pid) => {
@@ -125,11 +174,32 @@ fn exit_process(thread: &ThreadRef, term_status: TermStatus) {
             }
         }
         Some(process.pid())
-    });
+    };
+    let waiting_children = parent_inner.waiting_children_mut().unwrap();
+    if is_reaped_automatically {
+        // All waiters must check again whether they have any children left
+        waiting_children.del_and_wake_all_waiters(is_waited);
+    } else {
+        waiting_children.del_and_wake_one_waiter(is_waited);
+    }
+}
+
+fn is_reaping_children_automatically(parent: &Arc<Process>) -> bool {
+    match parent.sig_dispositions().read().unwrap().get(SIGCHLD) {
+        SigAction::Ign => true,
+        SigAction::User { flags, .. } => flags.contains(SigActionFlags::SA_NOCLDWAIT),
+        SigAction::Dfl => false,
+    }
 }
 
-fn send_sigchld_to(parent: &Arc<Process>) {
-    let signal = Box::new(KernelSignal::new(SigNum::from(SIGCHLD)));
+fn send_sigchld_to(parent: &Arc<Process>, child: &Arc<Process>, term_status: TermStatus) {
+    let cpu_time = child.usage().cpu_time();
+    let signal = Box::new(ChildSignal::new_exited(
+        child.pid(),
+        term_status,
+        cpu_time.utime(),
+        cpu_time.stime(),
+    ));
     let mut sig_queues = parent.sig_queues().write().unwrap();
     sig_queues.enqueue(signal);
 }
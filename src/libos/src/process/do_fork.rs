@@ -0,0 +1,204 @@
+use super::process::ProcessBuilder;
+use super::{current, table, ThreadRef, ThreadStatus};
+use crate::prelude::*;
+use crate::syscall::{CpuContext, FpRegs};
+use crate::vm::VMSnapshot;
+use std::ptr;
+
+/// Create a child process that is a copy of the current process, which is
+/// supported only for the pattern of fork followed by exec.
+///
+/// All processes of LibOS share one address space, so the child cannot run
+/// in a copy of the memory of the parent at the same addresses while the
+/// parent keeps running. Thus fork is emulated like vfork: the child continues
+/// from fork in the host thread of the parent, while the parent is suspended
+/// until the child execs or exits. Unlike vfork, the memory of the parent is
+/// saved before the child runs and restored after, so the changes of the child
+/// to the memory are invisible to the parent, except for the shared mappings.
+/// Exec after fork is short-circuited into spawn, see `do_exec`, after which
+/// the parent and the new process run concurrently.
+///
+/// Applications that keep both the parent and the child running, e.g.,
+/// pre-forking servers and the worker pools of Python multiprocessing, are
+/// not supported, as the parent does not run again until the child exits.
+/// They should spawn new processes instead. Fork of a multithreaded process
+/// fails with ENOSYS, as the memory written by the other threads of the
+/// parent while the child runs would be lost on restoring.
+pub fn do_fork(user_context: &CpuContext) -> Result<pid_t> {
+    let current = current!();
+    debug!("fork: pid: {}", current.process().pid());
+    if current.process().threads().len() > 1 {
+        return_errno!(ENOSYS, "fork of a multithreaded process is not supported");
+    }
+    let vm_snapshot = current.vm().snapshot()?;
+    fork_common(user_context, Some(vm_snapshot))
+}
+
+/// Create a child process that shares the memory with the current process,
+/// which is suspended until the child exits or execs.
+///
+/// This is the same as fork, except that the memory of the parent is not
+/// saved and restored, which is exactly the semantics of vfork. So vfork is
+/// also supported in a multithreaded process, whose other threads keep running.
+pub fn do_vfork(user_context: &CpuContext) -> Result<pid_t> {
+    debug!("vfork: pid: {}", current!().process().pid());
+    fork_common(user_context, None)
+}
+
+fn fork_common(user_context: &CpuContext, vm_snapshot: Option<VMSnapshot>) -> Result<pid_t> {
+    let current = current!();
+    let process = current.process().clone();
+    crate::misc::check_nproc_limit(&current)?;
+
+    let child_process = {
+        let task = current.task().fork();
+        let files = Arc::new(SgxMutex::new(current.files().lock().unwrap().clone()));
+        let fs = Arc::new(SgxMutex::new(current.fs().lock().unwrap().clone()));
+        let sched = Arc::new(SgxMutex::new(current.sched().lock().unwrap().clone()));
+        let rlimits = Arc::new(SgxMutex::new(current.rlimits().lock().unwrap().clone()));
+
+        ProcessBuilder::new()
+            .vm(current.vm().clone())
+            .exec_path(process.exec_path())
+            .argv(process.argv())
+            .envp(process.envp())
+            .parent(process.clone())
+            .task(task)
+            .sched(sched)
+            .rlimits(rlimits)
+            .fs(fs)
+            .files(files)
+            .name(current.name())
+            .build()?
+    };
+    let child_thread = child_process.main_thread().unwrap();
+    let child_pid = child_process.pid();
+
+    // The signal dispositions, the signal mask and the alternate signal stack
+    // are inherited by the child
+    *child_process.sig_dispositions().write().unwrap() =
+        *process.sig_dispositions().read().unwrap();
+    *child_thread.sig_mask().write().unwrap() = *current.sig_mask().read().unwrap();
+    *child_thread.sig_stack().lock().unwrap() = *current.sig_stack().lock().unwrap();
+
+    table::add_process(child_process.clone());
+    table::add_thread(child_thread.clone());
+    info!(
+        "Process forked: pid = {}, child pid = {}",
+        process.pid(),
+        child_pid
+    );
+
+    // The parent returns from fork with the pid of the child when it resumes.
+    // The floating point registers are restored on the return, as they may
+    // be modified by the child.
+    let mut parent_context = *user_context;
+    parent_context.rax = child_pid as u64;
+    parent_context.fpregs = Box::into_raw(Box::new(FpRegs::save()));
+    parent_context.fpregs_on_heap = 1;
+    SUSPENDED_PARENTS.with(|parents| {
+        parents.borrow_mut().push(SuspendedParent {
+            thread: current.clone(),
+            context: parent_context,
+            vm_snapshot,
+            child: child_thread.clone(),
+        })
+    });
+
+    // Continue as the child in the host thread of the parent
+    let host_tid = current.sched().lock().unwrap().host_tid().unwrap();
+    child_thread.start(host_tid);
+    unsafe {
+        child_thread.task().set_current();
+    }
+    current::set(child_thread);
+    Ok(0)
+}
+
+/// Resume the parent suspended by fork or vfork if the current thread is a
+/// child created by them that has exited.
+///
+/// The CPU context of the exited child is replaced by that of the parent.
+/// Return whether a parent is resumed.
+pub fn resume_fork_parent(user_context: &mut CpuContext) -> bool {
+    let current = current!();
+    if current.status() != ThreadStatus::Exited || !has_suspended_parent(&current) {
+        return false;
+    }
+    let SuspendedParent {
+        thread,
+        context,
+        vm_snapshot,
+        child,
+    } = SUSPENDED_PARENTS.with(|parents| parents.borrow_mut().pop().unwrap());
+    debug!("fork: resume pid: {}", thread.process().pid());
+
+    // The exited child may not be reaped by the parent for a while, so close
+    // its files now, e.g., the write ends of pipes
+    let child_files = child
+        .files()
+        .lock()
+        .unwrap()
+        .del_range(0, FileDesc::max_value());
+    drop(child_files);
+
+    // The child has run in the host thread of the parent, whose CPU time
+    // should not be accounted to the parent
+    let child_cpu_time = child.cpu_time().lock().unwrap().used();
+    thread.cpu_time().lock().unwrap().exclude(child_cpu_time);
+
+    if let Some(vm_snapshot) = vm_snapshot {
+        // The parent cannot resume with the memory of the child
+        thread
+            .vm()
+            .restore(vm_snapshot)
+            .expect("failed to restore the memory of the parent");
+    }
+    unsafe {
+        thread.task().set_current();
+    }
+    current::set(thread);
+
+    if user_context.fpregs != ptr::null_mut() && user_context.fpregs_on_heap == 1 {
+        drop(unsafe { Box::from_raw(user_context.fpregs as *mut FpRegs) });
+    }
+    *user_context = context;
+    true
+}
+
+/// Check whether the thread is a child created by fork or vfork, whose parent
+/// is suspended until it exits.
+pub(super) fn has_suspended_parent(child: &ThreadRef) -> bool {
+    SUSPENDED_PARENTS.with(|parents| {
+        parents
+            .borrow()
+            .last()
+            .map(|parent| Arc::ptr_eq(&parent.child, child))
+            .unwrap_or(false)
+    })
+}
+
+/// Change the return value of fork or vfork in the suspended parent, e.g.,
+/// to the pid of the process that replaces the child on exec.
+pub(super) fn set_parent_retval(child: &ThreadRef, retval: pid_t) {
+    SUSPENDED_PARENTS.with(|parents| {
+        if let Some(parent) = parents.borrow_mut().last_mut() {
+            debug_assert!(Arc::ptr_eq(&parent.child, child));
+            parent.context.rax = retval as u64;
+        }
+    })
+}
+
+/// A parent that is suspended by fork or vfork until its child exits.
+struct SuspendedParent {
+    thread: ThreadRef,
+    context: CpuContext,
+    // Only fork saves the memory of the parent
+    vm_snapshot: Option<VMSnapshot>,
+    child: ThreadRef,
+}
+
+thread_local! {
+    // The parents suspended by fork in this host thread, the innermost last
+    static SUSPENDED_PARENTS: RefCell<Vec<SuspendedParent>> = RefCell::new(Vec::new());
+}
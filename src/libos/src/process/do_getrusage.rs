@@ -0,0 +1,40 @@
+use super::rusage::rusage_t;
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum RusageWho {
+    RUSAGE_SELF = 0,
+    RUSAGE_CHILDREN = -1,
+    RUSAGE_THREAD = 1,
+}
+
+impl RusageWho {
+    pub fn from_i32(who: i32) -> Result<Self> {
+        Ok(match who {
+            0 => RusageWho::RUSAGE_SELF,
+            -1 => RusageWho::RUSAGE_CHILDREN,
+            1 => RusageWho::RUSAGE_THREAD,
+            _ => return_errno!(EINVAL, "invalid who for getrusage"),
+        })
+    }
+}
+
+/// Get the resource usage of the current process, its waited children, or the
+/// current thread.
+///
+/// The CPU time of a thread can only be measured by the host thread that
+/// executes it. So the CPU time of the other threads of the current process is
+/// the one when they last updated it, i.e., on their last getrusage.
+pub fn do_getrusage(who: RusageWho) -> Result<rusage_t> {
+    let current = current!();
+    let process = current.process();
+    let current_cpu_time = current.cpu_time().lock().unwrap().update();
+
+    let rusage = match who {
+        RusageWho::RUSAGE_SELF => rusage_t::new(process.cpu_time(), current.vm().get_max_rss()),
+        RusageWho::RUSAGE_CHILDREN => process.usage().children(),
+        RusageWho::RUSAGE_THREAD => rusage_t::new(current_cpu_time, current.vm().get_max_rss()),
+    };
+    Ok(rusage)
+}
@@ -0,0 +1,116 @@
+use std::sync::Weak;
+use std::time::Duration;
+
+use super::itimer::{itimerval_t, ITimerWhich};
+use super::rusage::CpuTime;
+use super::{Process, ProcessStatus, ThreadRef};
+use crate::prelude::*;
+use crate::signal::{KernelSignal, SigNum};
+use crate::time::timer_wheel::TimerCallback;
+use crate::time::{itimerspec_t, ClockID, Timer};
+
+pub fn do_getitimer(which: ITimerWhich) -> Result<itimerval_t> {
+    debug!("getitimer: which: {:?}", which);
+    let current = current!();
+    let process = current.process();
+    let curr_value = match which {
+        ITimerWhich::ITIMER_REAL => match process.itimers().real_timer() {
+            Some(timer) => itimerval_t::from(&timer.get()),
+            None => Default::default(),
+        },
+        _ => {
+            let cpu_time = update_process_cpu_time(&current);
+            process.itimers().cpu_timer(which, cpu_time)
+        }
+    };
+    Ok(curr_value)
+}
+
+/// Arm or disarm an interval timer of the current process, returning the old
+/// setting of the timer.
+pub fn do_setitimer(which: ITimerWhich, new_value: &itimerval_t) -> Result<itimerval_t> {
+    debug!("setitimer: which: {:?}, new_value: {:?}", which, new_value);
+    new_value.validate()?;
+
+    let current = current!();
+    let process = current.process();
+    let old_value = match which {
+        ITimerWhich::ITIMER_REAL => {
+            let mut itimers = process.itimers();
+            if itimers.real_timer().is_none() {
+                let callback = real_timer_callback(Arc::downgrade(process), which.signum());
+                itimers.set_real_timer(Timer::new(ClockID::CLOCK_MONOTONIC, callback)?);
+            }
+            let timer = itimers.real_timer().unwrap();
+            let old_value = timer.set(&itimerspec_t::from(new_value), false)?;
+            itimerval_t::from(&old_value)
+        }
+        _ => {
+            let cpu_time = update_process_cpu_time(&current);
+            process.itimers().set_cpu_timer(which, cpu_time, new_value)
+        }
+    };
+    Ok(old_value)
+}
+
+/// Arrange for SIGALRM to be sent to the current process after the given
+/// seconds, returning the seconds remaining until the previous alarm.
+pub fn do_alarm(seconds: u32) -> Result<u32> {
+    let new_value = itimerval_t::new(Duration::from_secs(seconds as u64), Duration::from_secs(0));
+    let old_value = do_setitimer(ITimerWhich::ITIMER_REAL, &new_value)?.value();
+    // Same as Linux, round the remaining time to the nearest second, but never
+    // report zero for an alarm that is still pending
+    let mut old_seconds = old_value.as_secs() as u32;
+    let old_micros = old_value.subsec_micros();
+    if (old_seconds == 0 && old_micros > 0) || old_micros >= 500_000 {
+        old_seconds += 1;
+    }
+    Ok(old_seconds)
+}
+
+/// Check whether a process has armed ITIMER_VIRTUAL or ITIMER_PROF.
+pub fn has_cpu_itimers(process: &Process) -> bool {
+    process.itimers().is_cpu_timer_armed()
+}
+
+/// Send signals to the current process if its ITIMER_VIRTUAL or ITIMER_PROF
+/// expires.
+///
+/// The CPU time of a thread can only be measured by the host thread that
+/// executes it. So the CPU timers are checked by the threads of the process
+/// themselves whenever they enter LibOS, e.g., on syscalls and interrupts.
+pub fn handle_cpu_itimers() {
+    let current = current!();
+    let process = current.process();
+    if !has_cpu_itimers(process) {
+        return;
+    }
+
+    let cpu_time = update_process_cpu_time(&current);
+    let signums = process.itimers().expire_cpu_timers(cpu_time);
+    for signum in signums {
+        send_signal_to(process, signum);
+    }
+}
+
+fn update_process_cpu_time(current: &ThreadRef) -> CpuTime {
+    current.cpu_time().lock().unwrap().update();
+    current.process().cpu_time()
+}
+
+fn real_timer_callback(process: Weak<Process>, signum: SigNum) -> TimerCallback {
+    Arc::new(move |_num_expirations| {
+        // The expirations are merged into one signal, like Linux does
+        if let Some(process) = process.upgrade() {
+            if process.status() != ProcessStatus::Zombie {
+                send_signal_to(&process, signum);
+            }
+        }
+    })
+}
+
+fn send_signal_to(process: &Process, signum: SigNum) {
+    let signal = Box::new(KernelSignal::new(signum));
+    let mut sig_queues = process.sig_queues().write().unwrap();
+    sig_queues.enqueue(signal);
+}
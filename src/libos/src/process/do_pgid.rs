@@ -0,0 +1,84 @@
+use super::{table, ProcessRef};
+use crate::prelude::*;
+
+/// Get the process group ID of a process, or of the current process if pid is 0.
+pub fn do_getpgid(pid: pid_t) -> Result<pid_t> {
+    let process = get_process_or_current(pid)?;
+    Ok(process.pgid())
+}
+
+/// Move a process, which is the current process or one of its children, to
+/// the process group.
+///
+/// The current process is used if pid is 0, and the process becomes the
+/// leader of a new process group if pgid is 0 or equals to its pid.
+pub fn do_setpgid(pid: pid_t, pgid: pid_t) -> Result<()> {
+    debug!("setpgid: pid: {}, pgid: {}", pid, pgid);
+
+    let current = current!().process().clone();
+    let process = get_process_or_current(pid)?;
+    let is_child = process.pid() != current.pid() && process.parent().pid() == current.pid();
+    if process.pid() != current.pid() && !is_child {
+        return_errno!(
+            ESRCH,
+            "the process is neither the current process nor its child"
+        );
+    }
+    let pgid = if pgid == 0 { process.pid() } else { pgid };
+
+    let _pgid_guard = PGID_LOCK.lock().unwrap();
+    if process.sid() != current.sid() {
+        return_errno!(EPERM, "the child is in a different session");
+    }
+    if process.sid() == process.pid() {
+        return_errno!(EPERM, "the process is a session leader");
+    }
+    // A process can only join a process group in its session
+    if pgid != process.pid() {
+        let is_group_in_session = table::get_all_processes()
+            .iter()
+            .any(|other| other.pgid() == pgid && other.sid() == process.sid());
+        if !is_group_in_session {
+            return_errno!(EPERM, "no such process group in the session");
+        }
+    }
+    process.set_pgid(pgid);
+    Ok(())
+}
+
+/// Get the session ID of a process, or of the current process if pid is 0.
+pub fn do_getsid(pid: pid_t) -> Result<pid_t> {
+    let process = get_process_or_current(pid)?;
+    Ok(process.sid())
+}
+
+/// Create a new session and a new process group, both of which are led by
+/// the current process.
+pub fn do_setsid() -> Result<pid_t> {
+    let current = current!().process().clone();
+    debug!("setsid: pid: {}", current.pid());
+
+    let _pgid_guard = PGID_LOCK.lock().unwrap();
+    let is_group_leader = table::get_all_processes()
+        .iter()
+        .any(|other| other.pgid() == current.pid());
+    if is_group_leader {
+        return_errno!(EPERM, "the process is a process group leader");
+    }
+    current.set_sid(current.pid());
+    current.set_pgid(current.pid());
+    Ok(current.sid())
+}
+
+fn get_process_or_current(pid: pid_t) -> Result<ProcessRef> {
+    if pid == 0 {
+        return Ok(current!().process().clone());
+    }
+    table::get_process(pid)
+}
+
+lazy_static! {
+    // Serialize the changes of process groups and sessions, so that the checks
+    // against other processes are not invalidated by concurrent changes
+    static ref PGID_LOCK: SgxMutex<()> = SgxMutex::new(());
+}
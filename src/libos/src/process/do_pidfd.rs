@@ -0,0 +1,35 @@
+use super::pid_file::PidFile;
+use super::table;
+use crate::prelude::*;
+
+bitflags! {
+    pub struct PidfdFlags: u32 {
+        /// Non-blocking, which is the same as O_NONBLOCK
+        const PIDFD_NONBLOCK = 0o4000;
+    }
+}
+
+impl PidfdFlags {
+    pub fn from_u32(raw: u32) -> Result<Self> {
+        Self::from_bits(raw).ok_or_else(|| errno!(EINVAL, "invalid pidfd flags"))
+    }
+}
+
+/// Open a pidfd that refers to the process of the given pid.
+///
+/// Same as Linux, the pid must refer to a process, not a thread, and the
+/// pidfd is always close-on-exec.
+pub fn do_pidfd_open(pid: pid_t, flags: PidfdFlags) -> Result<FileDesc> {
+    debug!("pidfd_open: pid: {}, flags: {:?}", pid, flags);
+
+    let process = table::get_process(pid).map_err(|e| {
+        if table::get_thread(pid).is_ok() {
+            errno!(EINVAL, "the pid refers to a thread that is not a process")
+        } else {
+            e
+        }
+    })?;
+    let is_nonblocking = flags.contains(PidfdFlags::PIDFD_NONBLOCK);
+    let file_ref: Arc<dyn File> = Arc::new(PidFile::new(process, is_nonblocking));
+    current!().add_file(file_ref, true)
+}
@@ -0,0 +1,72 @@
+use super::posix_timer::{sigevent_t, timer_t, PosixTimer, SigEvent, TIMER_ABSTIME};
+use crate::prelude::*;
+use crate::signal::{sigval_t, SIGALRM};
+use crate::time::{itimerspec_t, ClockID};
+
+/// Create a POSIX timer for the current process.
+///
+/// If sigevent is None, the timer notifies the process with SIGALRM, whose
+/// value is the ID of the timer.
+pub fn do_timer_create(clockid: ClockID, sigevent: Option<&sigevent_t>) -> Result<timer_t> {
+    debug!(
+        "timer_create: clockid: {:?}, sigevent: {:?}",
+        clockid, sigevent
+    );
+    match clockid {
+        ClockID::CLOCK_PROCESS_CPUTIME_ID | ClockID::CLOCK_THREAD_CPUTIME_ID => {
+            return_errno!(EOPNOTSUPP, "timers of CPU-time clocks are not supported")
+        }
+        _ => {}
+    }
+
+    let current = current!();
+    let process = current.process();
+    let mut posix_timers = process.posix_timers();
+    let id = posix_timers.alloc_id();
+    let sigevent = match sigevent {
+        Some(sigevent) => SigEvent::from_c(sigevent)?,
+        None => SigEvent::Signal {
+            signum: SIGALRM,
+            value: sigval_t::from(id),
+            tid: None,
+        },
+    };
+    let timer = PosixTimer::new(id, clockid, sigevent, process)?;
+    posix_timers.insert(id, timer);
+    Ok(id)
+}
+
+/// Arm or disarm a POSIX timer, returning the old setting of the timer.
+pub fn do_timer_settime(id: timer_t, flags: i32, new_value: &itimerspec_t) -> Result<itimerspec_t> {
+    debug!(
+        "timer_settime: id: {}, flags: {:?}, new_value: {:?}",
+        id, flags, new_value
+    );
+    let is_abs = flags & TIMER_ABSTIME != 0;
+    let current = current!();
+    let posix_timers = current.process().posix_timers();
+    posix_timers.get(id)?.set(new_value, is_abs)
+}
+
+pub fn do_timer_gettime(id: timer_t) -> Result<itimerspec_t> {
+    debug!("timer_gettime: id: {}", id);
+    let current = current!();
+    let posix_timers = current.process().posix_timers();
+    Ok(posix_timers.get(id)?.get())
+}
+
+pub fn do_timer_getoverrun(id: timer_t) -> Result<i32> {
+    debug!("timer_getoverrun: id: {}", id);
+    let current = current!();
+    let posix_timers = current.process().posix_timers();
+    Ok(posix_timers.get(id)?.overrun())
+}
+
+pub fn do_timer_delete(id: timer_t) -> Result<()> {
+    debug!("timer_delete: id: {}", id);
+    let current = current!();
+    let timer = current.process().posix_timers().remove(id)?;
+    // Disarm the timer without holding the lock of the timers
+    drop(timer);
+    Ok(())
+}
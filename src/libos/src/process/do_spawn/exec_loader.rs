@@ -1,6 +1,6 @@
 use super::super::elf_file::*;
 use super::ThreadRef;
-use crate::fs::{FileMode, INodeExt};
+use crate::fs::{AsINodeFile, AsMemFile, FileMode, INodeExt};
 use crate::prelude::*;
 use rcore_fs::vfs::INode;
 use std::ffi::CString;
@@ -9,81 +9,120 @@ use std::ffi::CString;
 ///
 /// If the file is an executable binary, then just load this file's header.
 /// If the file is an script text, then parse the shebang and load
-/// the interpreter header.
+/// the interpreter header. The interpreter can be a script as well, whose
+/// interpreter is loaded in turn.
+///
+/// For each script, argv is rewritten to `interpreter [optional-arg]
+/// script-path argv[1..]` as Linux does. Returns the path of the loaded ELF
+/// file, which is different from file_path if file_path leads to a script.
 pub fn load_exec_file_hdr_to_vec(
     file_path: &str,
+    argv: &mut Vec<CString>,
     current_ref: &ThreadRef,
-) -> Result<(Option<String>, Arc<dyn INode>, Vec<u8>, ElfHeader)> {
-    let (inode, file_buf, elf_hdr) = load_file_hdr_to_vec(&file_path, current_ref)?;
-    if elf_hdr.is_some() {
-        Ok((None, inode, file_buf, elf_hdr.unwrap()))
-    } else {
+) -> Result<(String, Arc<dyn INode>, Vec<u8>, ElfHeader)> {
+    let mut file_path = file_path.to_owned();
+    for _ in 0..=MAX_SCRIPT_NESTING {
+        let (inode, file_buf, elf_hdr) = load_file_hdr_to_vec(&file_path, current_ref)?;
+        if let Some(elf_hdr) = elf_hdr {
+            return Ok((file_path, inode, file_buf, elf_hdr));
+        }
         // loaded file is not Elf format, try script file
         if !is_script_file(&file_buf) {
             return_errno!(ENOEXEC, "unknown executable file format");
         }
-        // load interpreter
-        let interpreter_path = parse_script_interpreter(&file_buf)?;
+        let (interpreter_path, interpreter_arg) = parse_script_interpreter(&file_buf)?;
         if interpreter_path.starts_with("/host/") {
             return_errno!(
                 EACCES,
                 "libos doesn't support executing binaries from \"/host\" directory"
             );
         }
-        let (interp_inode, interp_buf, interp_hdr) =
-            load_file_hdr_to_vec(&interpreter_path, current_ref)?;
-        let interp_hdr = if interp_hdr.is_none() {
-            return_errno!(ENOEXEC, "scrip interpreter is not ELF format");
+
+        // argv[0] is replaced by the path of the script
+        let script_path = CString::new(file_path.as_str())?;
+        if argv.is_empty() {
+            argv.push(script_path);
         } else {
-            interp_hdr.unwrap()
-        };
-        Ok((Some(interpreter_path), interp_inode, interp_buf, interp_hdr))
+            argv[0] = script_path;
+        }
+        if let Some(interpreter_arg) = interpreter_arg {
+            argv.insert(0, CString::new(interpreter_arg)?);
+        }
+        argv.insert(0, CString::new(interpreter_path.as_str())?);
+        file_path = interpreter_path;
     }
+    return_errno!(ELOOP, "too many levels of script interpreters");
 }
 
+// The max levels of nested script interpreters, which is the same as Linux
+const MAX_SCRIPT_NESTING: usize = 4;
+
 fn is_script_file(file_buf: &Vec<u8>) -> bool {
     file_buf.starts_with(&[b'#', b'!'])
 }
 
-// TODO: Support parsing interpreter arguments. e.g. `/usr/bin/python -u`
-fn parse_script_interpreter(file_buf: &Vec<u8>) -> Result<String> {
-    let mut start = 2; // after '#', '!'
-    const MAX_LEN: usize = 127;
-
-    // skip whitespaced between shebang and interpreter
-    while (start < file_buf.len())
-        && (file_buf[start] == ' ' as u8 || file_buf[start] == '\t' as u8)
-    {
-        start += 1;
-    }
-
-    let end = file_buf
-        .iter()
-        .take(MAX_LEN)
-        .position(|&c| c == '\n' as u8)
-        .ok_or_else(|| errno!(EINVAL, "script parsing error"))?;
+/// Parse the shebang line into the interpreter path and an optional argument.
+///
+/// As Linux does, all the text after the interpreter path, excluding leading
+/// and trailing whitespaces, is treated as a single argument. E.g., the
+/// argument of `#!/usr/bin/env python3 -u` is `python3 -u`.
+fn parse_script_interpreter(file_buf: &Vec<u8>) -> Result<(String, Option<String>)> {
+    // The max length of the shebang line, which is the same as Linux
+    const MAX_LEN: usize = 256;
 
-    let interpreter = std::str::from_utf8(&file_buf[start..end])
+    let is_whitespace = |c: char| c == ' ' || c == '\t';
+    let buf = &file_buf[..file_buf.len().min(MAX_LEN)];
+    let (line, is_truncated) = match buf.iter().position(|&c| c == '\n' as u8) {
+        Some(end) => (&buf[2..end], false),
+        None => (&buf[2..], file_buf.len() > MAX_LEN),
+    };
+    let line = std::str::from_utf8(line)
         .map_err(|e| errno!(ENOEXEC, "failed to get the script interpreter"))?;
-    trace!("script file using interpreter: {:?}", interpreter);
-    Ok(interpreter.to_owned())
+    let line = line.trim_matches(is_whitespace);
+
+    let mut parts = line.splitn(2, is_whitespace);
+    let interpreter = parts.next().unwrap();
+    if interpreter.is_empty() {
+        return_errno!(ENOEXEC, "no script interpreter");
+    }
+    // The interpreter path must not be truncated, while the argument may be
+    if is_truncated && interpreter.len() == line.len() {
+        return_errno!(ENOEXEC, "the script interpreter is too long");
+    }
+    let interpreter_arg = parts
+        .next()
+        .map(|arg| arg.trim_start_matches(is_whitespace))
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| arg.to_owned());
+    trace!(
+        "script file using interpreter: {:?}, arg: {:?}",
+        interpreter,
+        interpreter_arg
+    );
+    Ok((interpreter.to_owned(), interpreter_arg))
 }
 
 pub fn load_file_hdr_to_vec(
     file_path: &str,
     current_ref: &ThreadRef,
 ) -> Result<(Arc<dyn INode>, Vec<u8>, Option<ElfHeader>)> {
-    let inode = current_ref
-        .fs()
-        .lock()
-        .unwrap()
-        .lookup_inode(file_path)
-        .map_err(|e| errno!(e.errno(), "cannot find the file"))?;
+    let inode = {
+        let fs = current_ref.fs().lock().unwrap();
+        fs.lookup_inode(file_path)
+            .or_else(|e| {
+                // The file may have no path to be looked up with, e.g., a
+                // memfd executed by fexecve through `/dev/fd/N`
+                fs.lookup_fd_magic_link(file_path)
+                    .and_then(|file_ref| get_inode_of_file(&file_ref))
+                    .ok_or(e)
+            })
+            .map_err(|e| errno!(e.errno(), "cannot find the file"))?
+    };
     let file_mode = {
         let info = inode.metadata()?;
         FileMode::from_bits_truncate(info.mode)
     };
-    if !file_mode.is_executable() {
+    if !inode.allow_exec()? {
         return_errno!(EACCES, "file is not executable");
     }
     if file_mode.has_set_uid() || file_mode.has_set_gid() {
@@ -105,3 +144,13 @@ pub fn load_file_hdr_to_vec(
         Ok((inode, file_buf, None))
     }
 }
+
+fn get_inode_of_file(file_ref: &FileRef) -> Option<Arc<dyn INode>> {
+    if let Ok(inode_file) = file_ref.as_inode_file() {
+        Some(inode_file.inode().clone())
+    } else if let Ok(mem_file) = file_ref.as_mem_file() {
+        Some(mem_file.inode().clone())
+    } else {
+        None
+    }
+}
@@ -9,10 +9,12 @@ use super::task::Task;
 use super::thread::ThreadName;
 use super::{table, task, ProcessRef, ThreadRef};
 use crate::fs::{
-    CreationFlags, File, FileDesc, FileTable, FsView, HostStdioFds, StdinFile, StdoutFile,
+    AsINodeFile, CreationFlags, File, FileDesc, FileTable, FsView, HostStdioFds, StdinFile,
+    StdoutFile, Terminal,
 };
 use crate::prelude::*;
 use crate::vm::ProcessVM;
+use rcore_fs::vfs::FileType;
 
 mod aux_vec;
 mod exec_loader;
@@ -100,22 +102,29 @@ fn new_process(
     host_stdio_fds: Option<&HostStdioFds>,
     current_ref: &ThreadRef,
 ) -> Result<ProcessRef> {
-    let mut argv = argv.clone().to_vec();
-    let (is_script, elf_inode, mut elf_buf, elf_header) =
-        load_exec_file_hdr_to_vec(file_path, current_ref)?;
+    crate::misc::check_nproc_limit(current_ref)?;
+
+    // The file actions are performed before the executable is loaded, as the
+    // path of the executable is relative to the working directory of the new
+    // process, which may be changed by the file actions
+    let mut fs = current_ref.fs().lock().unwrap().clone();
+    // The host terminal becomes the controlling terminal of the new session
+    // started by the first process
+    let terminal = host_stdio_fds.map(|_| Arc::new(Terminal::new()));
+    let files = init_files(
+        current_ref,
+        &mut fs,
+        file_actions,
+        host_stdio_fds,
+        terminal.as_ref(),
+    )?;
+    let file_path = &fs.convert_to_abs_path(file_path);
 
     // elf_path might be different from file_path because file_path could lead to a script text file.
     // And intepreter will be the loaded ELF.
-    let elf_path = if let Some(interpreter_path) = is_script {
-        if argv.len() == 0 {
-            return_errno!(EINVAL, "argv[0] not found");
-        }
-        argv.insert(0, CString::new(interpreter_path.as_str())?);
-        argv[1] = CString::new(file_path)?; // script file needs to be the full path
-        interpreter_path
-    } else {
-        file_path.to_string()
-    };
+    let mut argv = argv.clone().to_vec();
+    let (elf_path, elf_inode, mut elf_buf, elf_header) =
+        load_exec_file_hdr_to_vec(file_path, &mut argv, current_ref)?;
 
     let exec_elf_hdr = ElfFile::new(&elf_inode, &mut elf_buf, elf_header)
         .cause_err(|e| errno!(e.errno(), "invalid executable"))?;
@@ -181,11 +190,8 @@ fn new_process(
             }
         };
         let vm_ref = Arc::new(vm);
-        let files_ref = {
-            let files = init_files(current_ref, file_actions, host_stdio_fds)?;
-            Arc::new(SgxMutex::new(files))
-        };
-        let fs_ref = Arc::new(SgxMutex::new(current_ref.fs().lock().unwrap().clone()));
+        let files_ref = Arc::new(SgxMutex::new(files));
+        let fs_ref = Arc::new(SgxMutex::new(fs));
         let sched_ref = Arc::new(SgxMutex::new(current_ref.sched().lock().unwrap().clone()));
         let rlimit_ref = Arc::new(SgxMutex::new(current_ref.rlimits().lock().unwrap().clone()));
 
@@ -196,6 +202,8 @@ fn new_process(
         ProcessBuilder::new()
             .vm(vm_ref)
             .exec_path(&elf_path)
+            .argv(&argv)
+            .envp(envp)
             .parent(process_ref)
             .task(task)
             .sched(sched_ref)
@@ -208,6 +216,9 @@ fn new_process(
 
     table::add_process(new_process_ref.clone());
     table::add_thread(new_process_ref.main_thread().unwrap());
+    if let Some(terminal) = terminal {
+        terminal.set_session(new_process_ref.sid());
+    }
 
     info!(
         "Process created: elf = {}, pid = {}",
@@ -230,12 +241,20 @@ pub enum FileAction {
     },
     Dup2(FileDesc, FileDesc),
     Close(FileDesc),
+    /// Close all the file descriptors that are not less than `fd`.
+    CloseFrom(FileDesc),
+    /// Change the working directory to `path`.
+    Chdir(String),
+    /// Change the working directory to the directory opened as `fd`.
+    Fchdir(FileDesc),
 }
 
 fn init_files(
     current_ref: &ThreadRef,
+    fs: &mut FsView,
     file_actions: &[FileAction],
     host_stdio_fds: Option<&HostStdioFds>,
+    terminal: Option<&Arc<Terminal>>,
 ) -> Result<FileTable> {
     // Usually, we just inherit the file table from the current process
     let should_inherit_file_table = current_ref.process().pid() > 0;
@@ -251,12 +270,7 @@ fn init_files(
                     oflag,
                     fd,
                 } => {
-                    let file_ref =
-                        current_ref
-                            .fs()
-                            .lock()
-                            .unwrap()
-                            .open_file(path.as_str(), oflag, mode)?;
+                    let file_ref = fs.open_file(path.as_str(), oflag, mode)?;
                     let creation_flags = CreationFlags::from_bits_truncate(oflag);
                     cloned_file_table.put_at(fd, file_ref, creation_flags.must_close_on_spawn());
                 }
@@ -264,12 +278,31 @@ fn init_files(
                     let file = cloned_file_table.get(old_fd)?;
                     if old_fd != new_fd {
                         cloned_file_table.put_at(new_fd, file, false);
+                    } else {
+                        // Same as posix_spawn, dup2 to the same fd clears the
+                        // close-on-exec flag
+                        cloned_file_table
+                            .get_entry_mut(new_fd)?
+                            .set_close_on_spawn(false);
                     }
                 }
                 &FileAction::Close(fd) => {
                     // ignore error
                     cloned_file_table.del(fd);
                 }
+                &FileAction::CloseFrom(fd) => {
+                    cloned_file_table.del_range(fd, FileDesc::max_value());
+                }
+                &FileAction::Chdir(ref path) => {
+                    change_dir(fs, path)?;
+                }
+                &FileAction::Fchdir(fd) => {
+                    let file_ref = cloned_file_table.get(fd)?;
+                    let inode_file = file_ref
+                        .as_inode_file()
+                        .map_err(|_| errno!(ENOTDIR, "fd is not a directory"))?;
+                    change_dir(fs, inode_file.abs_path())?;
+                }
             }
         }
         // Exec: close fd with close_on_spawn
@@ -279,13 +312,18 @@ fn init_files(
 
     // But, for init process, we initialize file table for it
     let mut file_table = FileTable::new();
-    let stdin: Arc<dyn File> =
-        Arc::new(StdinFile::new(host_stdio_fds.unwrap().stdin_fd as FileDesc));
+    let terminal = terminal.unwrap();
+    let stdin: Arc<dyn File> = Arc::new(StdinFile::new(
+        host_stdio_fds.unwrap().stdin_fd as FileDesc,
+        terminal.clone(),
+    ));
     let stdout: Arc<dyn File> = Arc::new(StdoutFile::new(
         host_stdio_fds.unwrap().stdout_fd as FileDesc,
+        terminal.clone(),
     ));
     let stderr: Arc<dyn File> = Arc::new(StdoutFile::new(
         host_stdio_fds.unwrap().stderr_fd as FileDesc,
+        terminal.clone(),
     ));
 
     file_table.put(stdin, false);
@@ -294,6 +332,14 @@ fn init_files(
     Ok(file_table)
 }
 
+fn change_dir(fs: &mut FsView, path: &str) -> Result<()> {
+    let inode = fs.lookup_inode(path)?;
+    if inode.metadata()?.type_ != FileType::Dir {
+        return_errno!(ENOTDIR, "cwd must be directory");
+    }
+    fs.set_cwd(path)
+}
+
 fn init_auxvec(process_vm: &ProcessVM, exec_elf_file: &ElfFile) -> Result<AuxVec> {
     let mut auxvec = AuxVec::new();
     auxvec.set(AuxKey::AT_PAGESZ, 4096)?;
@@ -1,58 +1,116 @@
-use super::process::{ProcessFilter, ProcessInner};
+use super::process::{JobStatusChange, ProcessFilter, ProcessInner};
+use super::rusage::rusage_t;
 use super::wait::Waiter;
 use super::{table, ProcessRef, ProcessStatus};
 use crate::prelude::*;
 
-pub fn do_wait4(child_filter: &ProcessFilter) -> Result<(pid_t, i32)> {
-    // Lock the process early to ensure that we do not miss any changes in
-    // children processes
+/// Wait for a child to exit, returning its pid, exit status and the resource
+/// usage of the child and its waited children.
+///
+/// With WUNTRACED or WCONTINUED, a child that has been stopped or continued is
+/// returned, too, whose resource usage is not reported.
+///
+/// If WNOHANG is given and no child has exited yet, None is returned.
+pub fn do_wait4(
+    child_filter: &ProcessFilter,
+    options: WaitOptions,
+) -> Result<Option<(pid_t, i32, rusage_t)>> {
     let thread = current!();
     let process = thread.process();
-    // Lock order: always lock parent then child to avoid deadlock
-    let mut process_inner = process.inner();
+    loop {
+        // Lock the process early to ensure that we do not miss any changes in
+        // children processes
+        // Lock order: always lock parent then child to avoid deadlock
+        let mut process_inner = process.inner();
 
-    let unwaited_children = process_inner
-        .children()
-        .unwrap()
-        .iter()
-        .filter(|child| match child_filter {
-            ProcessFilter::WithAnyPid => true,
-            ProcessFilter::WithPid(required_pid) => child.pid() == *required_pid,
-            ProcessFilter::WithPgid(required_pgid) => child.pgid() == *required_pgid,
-        })
-        .collect::<Vec<&ProcessRef>>();
+        let unwaited_children = process_inner
+            .children()
+            .unwrap()
+            .iter()
+            .filter(|child| match child_filter {
+                ProcessFilter::WithAnyPid => true,
+                ProcessFilter::WithPid(required_pid) => child.pid() == *required_pid,
+                ProcessFilter::WithPgid(required_pgid) => child.pgid() == *required_pgid,
+            })
+            .collect::<Vec<&ProcessRef>>();
 
-    if unwaited_children.len() == 0 {
-        return_errno!(ECHILD, "Cannot find any unwaited children");
-    }
+        // Children that are reaped automatically are never waited, thus
+        // ECHILD is returned once all of them have exited.
+        if unwaited_children.len() == 0 {
+            return_errno!(ECHILD, "Cannot find any unwaited children");
+        }
+
+        // Return immediately if a child that we wait for has already exited
+        let zombie_child = unwaited_children
+            .iter()
+            .find(|child| child.status() == ProcessStatus::Zombie);
+        if let Some(zombie_child) = zombie_child {
+            let zombie_pid = zombie_child.pid();
+            let (exit_status, rusage) = free_zombie_child(process_inner, zombie_pid);
+            return Ok(Some((zombie_pid, exit_status, rusage)));
+        }
+
+        // Report a child that has been stopped or continued
+        let is_change_waited = |change: &JobStatusChange| match change {
+            JobStatusChange::Stopped(_) => options.contains(WaitOptions::WUNTRACED),
+            JobStatusChange::Continued => options.contains(WaitOptions::WCONTINUED),
+        };
+        let changed_child = unwaited_children.iter().find_map(|child| {
+            child
+                .inner()
+                .take_job_status_change(is_change_waited)
+                .map(|change| (child.pid(), change))
+        });
+        if let Some((pid, change)) = changed_child {
+            let status = change.as_u32() as i32;
+            return Ok(Some((pid, status, rusage_t::default())));
+        }
 
-    // Return immediately if a child that we wait for has already exited
-    let zombie_child = unwaited_children
-        .iter()
-        .find(|child| child.status() == ProcessStatus::Zombie);
-    if let Some(zombie_child) = zombie_child {
-        let zombie_pid = zombie_child.pid();
-        let exit_status = free_zombie_child(process_inner, zombie_pid);
-        return Ok((zombie_pid, exit_status));
+        if options.contains(WaitOptions::WNOHANG) {
+            return Ok(None);
+        }
+
+        let mut waiter = Waiter::new(child_filter);
+        process_inner
+            .waiting_children_mut()
+            .unwrap()
+            .add_waiter(&waiter);
+        // After adding the waiter, we can safely release the lock on the process inner
+        // without risking missing events from the process's children.
+        drop(process_inner);
+        // Wait until a child has interesting events. The child may have been
+        // reaped automatically, so check the children again after waking up.
+        waiter.sleep_until_woken_with_result();
     }
+}
 
-    let mut waiter = Waiter::new(child_filter);
-    process_inner
-        .waiting_children_mut()
-        .unwrap()
-        .add_waiter(&waiter);
-    // After adding the waiter, we can safely release the lock on the process inner
-    // without risking missing events from the process's children.
-    drop(process_inner);
-    // Wait until a child has interesting events
-    let zombie_pid = waiter.sleep_until_woken_with_result();
+bitflags! {
+    pub struct WaitOptions: u32 {
+        /// Return immediately if no child has exited
+        const WNOHANG = 1;
+        /// Also return if a child has stopped
+        const WUNTRACED = 2;
+        /// Also return if a stopped child has been resumed by SIGCONT
+        const WCONTINUED = 8;
+        /// Do not wait for the children of other threads in the same thread group
+        const __WNOTHREAD = 0x20000000;
+        /// Wait for all children, regardless of the type of them
+        const __WALL = 0x40000000;
+        /// Wait for clone children only
+        const __WCLONE = 0x80000000;
+    }
+}
 
-    let mut process_inner = process.inner();
-    let exit_status = free_zombie_child(process_inner, zombie_pid);
-    Ok((zombie_pid, exit_status))
+impl WaitOptions {
+    pub fn from_u32(bits: u32) -> Result<Self> {
+        Self::from_bits(bits).ok_or_else(|| errno!(EINVAL, "invalid wait options"))
+    }
 }
 
-fn free_zombie_child(mut parent_inner: SgxMutexGuard<ProcessInner>, zombie_pid: pid_t) -> i32 {
+fn free_zombie_child(
+    mut parent_inner: SgxMutexGuard<ProcessInner>,
+    zombie_pid: pid_t,
+) -> (i32, rusage_t) {
     // Remove zombie from the process and thread table
     table::del_thread(zombie_pid).expect("tid must be in the table");
     table::del_process(zombie_pid).expect("pid must be in the table");
@@ -60,6 +118,13 @@ fn free_zombie_child(mut parent_inner: SgxMutexGuard<ProcessInner>, zombie_pid:
     let zombie = parent_inner.remove_zombie_child(zombie_pid);
     debug_assert!(zombie.status() == ProcessStatus::Zombie);
 
+    // Account the resource usage of the zombie to the parent
+    let zombie_usage = zombie.usage();
+    current!().process().usage().add_waited_child(&zombie_usage);
+    let rusage = zombie_usage.exited_with_children();
+    drop(zombie_usage);
+
     let zombie_inner = zombie.inner();
-    zombie_inner.term_status().unwrap().as_u32() as i32
+    let exit_status = zombie_inner.term_status().unwrap().as_u32() as i32;
+    (exit_status, rusage)
 }
@@ -0,0 +1,219 @@
+use std::time::Duration;
+
+use super::rusage::CpuTime;
+use crate::prelude::*;
+use crate::signal::constants::*;
+use crate::signal::SigNum;
+use crate::time::{itimerspec_t, timeval_t, Timer};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum ITimerWhich {
+    ITIMER_REAL = 0,
+    ITIMER_VIRTUAL = 1,
+    ITIMER_PROF = 2,
+}
+
+impl ITimerWhich {
+    pub fn from_i32(which: i32) -> Result<Self> {
+        Ok(match which {
+            0 => ITimerWhich::ITIMER_REAL,
+            1 => ITimerWhich::ITIMER_VIRTUAL,
+            2 => ITimerWhich::ITIMER_PROF,
+            _ => return_errno!(EINVAL, "invalid which for getitimer/setitimer"),
+        })
+    }
+
+    /// The signal sent to the process when the timer expires.
+    pub fn signum(&self) -> SigNum {
+        match self {
+            ITimerWhich::ITIMER_REAL => SIGALRM,
+            ITimerWhich::ITIMER_VIRTUAL => SIGVTALRM,
+            ITimerWhich::ITIMER_PROF => SIGPROF,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct itimerval_t {
+    it_interval: timeval_t,
+    it_value: timeval_t,
+}
+
+impl itimerval_t {
+    pub fn new(value: Duration, interval: Duration) -> Self {
+        Self {
+            it_interval: interval.into(),
+            it_value: value.into(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        self.it_interval.validate()?;
+        self.it_value.validate()?;
+        Ok(())
+    }
+
+    pub fn value(&self) -> Duration {
+        self.it_value.as_duration()
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.it_interval.as_duration()
+    }
+}
+
+impl From<&itimerspec_t> for itimerval_t {
+    fn from(spec: &itimerspec_t) -> Self {
+        // Round up the value to microseconds so that an armed timer is never
+        // reported as a disarmed one
+        let value = spec.value().as_duration();
+        let value_micros = (value.as_nanos() + 999) / 1000;
+        Self::new(
+            Duration::from_micros(value_micros as u64),
+            spec.interval().as_duration(),
+        )
+    }
+}
+
+impl From<&itimerval_t> for itimerspec_t {
+    fn from(val: &itimerval_t) -> Self {
+        itimerspec_t::new(val.value(), val.interval())
+    }
+}
+
+/// The interval timers of a process.
+///
+/// The timer of ITIMER_REAL is a LibOS timer, which is created on first use,
+/// while the timers of ITIMER_VIRTUAL and ITIMER_PROF count down against the
+/// CPU time of the process, which is checked by the threads of the process
+/// themselves.
+#[derive(Debug, Default)]
+pub struct ITimers {
+    real: Option<Timer>,
+    virtual_: CpuITimer,
+    prof: CpuITimer,
+}
+
+impl ITimers {
+    pub fn real_timer(&self) -> Option<&Timer> {
+        self.real.as_ref()
+    }
+
+    pub fn set_real_timer(&mut self, timer: Timer) {
+        self.real = Some(timer);
+    }
+
+    /// Get the setting of ITIMER_VIRTUAL or ITIMER_PROF.
+    pub fn cpu_timer(&self, which: ITimerWhich, cpu_time: CpuTime) -> itimerval_t {
+        let timer = match which {
+            ITimerWhich::ITIMER_VIRTUAL => &self.virtual_,
+            ITimerWhich::ITIMER_PROF => &self.prof,
+            ITimerWhich::ITIMER_REAL => unreachable!(),
+        };
+        timer.get(cpu_clock_of(which, cpu_time))
+    }
+
+    /// Arm or disarm ITIMER_VIRTUAL or ITIMER_PROF, returning the old setting.
+    pub fn set_cpu_timer(
+        &mut self,
+        which: ITimerWhich,
+        cpu_time: CpuTime,
+        new_value: &itimerval_t,
+    ) -> itimerval_t {
+        let timer = match which {
+            ITimerWhich::ITIMER_VIRTUAL => &mut self.virtual_,
+            ITimerWhich::ITIMER_PROF => &mut self.prof,
+            ITimerWhich::ITIMER_REAL => unreachable!(),
+        };
+        let now = cpu_clock_of(which, cpu_time);
+        let old_value = timer.get(now);
+        timer.set(now, new_value.value(), new_value.interval());
+        old_value
+    }
+
+    pub fn is_cpu_timer_armed(&self) -> bool {
+        self.virtual_.is_armed() || self.prof.is_armed()
+    }
+
+    /// Check ITIMER_VIRTUAL and ITIMER_PROF against the CPU time of the
+    /// process, returning the signals of the expired timers.
+    pub fn expire_cpu_timers(&mut self, cpu_time: CpuTime) -> Vec<SigNum> {
+        let mut signums = Vec::new();
+        let virtual_clock = cpu_clock_of(ITimerWhich::ITIMER_VIRTUAL, cpu_time);
+        if self.virtual_.expire(virtual_clock) {
+            signums.push(ITimerWhich::ITIMER_VIRTUAL.signum());
+        }
+        let prof_clock = cpu_clock_of(ITimerWhich::ITIMER_PROF, cpu_time);
+        if self.prof.expire(prof_clock) {
+            signums.push(ITimerWhich::ITIMER_PROF.signum());
+        }
+        signums
+    }
+}
+
+/// Get the CPU time that a CPU timer counts down against.
+fn cpu_clock_of(which: ITimerWhich, cpu_time: CpuTime) -> Duration {
+    match which {
+        // ITIMER_VIRTUAL counts the time in user mode only
+        ITimerWhich::ITIMER_VIRTUAL => cpu_time.utime(),
+        ITimerWhich::ITIMER_PROF => cpu_time.utime() + cpu_time.stime(),
+        ITimerWhich::ITIMER_REAL => unreachable!(),
+    }
+}
+
+/// An interval timer that counts down against the CPU time.
+#[derive(Debug, Default, Clone, Copy)]
+struct CpuITimer {
+    // The CPU time when the timer expires
+    expires: Option<Duration>,
+    interval: Duration,
+}
+
+impl CpuITimer {
+    fn is_armed(&self) -> bool {
+        self.expires.is_some()
+    }
+
+    fn get(&self, now: Duration) -> itimerval_t {
+        let value = match self.expires {
+            // A timer that has expired but not been handled yet is about to
+            // fire, so it is reported as the smallest non-zero value
+            Some(expires) => expires
+                .checked_sub(now)
+                .filter(|value| *value > Duration::from_secs(0))
+                .unwrap_or(Duration::from_micros(1)),
+            None => Duration::from_secs(0),
+        };
+        itimerval_t::new(value, self.interval)
+    }
+
+    fn set(&mut self, now: Duration, value: Duration, interval: Duration) {
+        if value == Duration::from_secs(0) {
+            self.expires = None;
+        } else {
+            self.expires = Some(now + value);
+        }
+        self.interval = interval;
+    }
+
+    /// Check whether the timer expires, rearming it if it is periodic.
+    fn expire(&mut self, now: Duration) -> bool {
+        let expires = match self.expires {
+            Some(expires) if expires <= now => expires,
+            _ => return false,
+        };
+        if self.interval == Duration::from_secs(0) {
+            self.expires = None;
+        } else {
+            // The expirations that have been missed are merged into one
+            let interval_nanos = self.interval.as_nanos();
+            let num_expirations = (now - expires).as_nanos() / interval_nanos + 1;
+            self.expires =
+                Some(expires + Duration::from_nanos((interval_nanos * num_expirations) as u64));
+        }
+        true
+    }
+}
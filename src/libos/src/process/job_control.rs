@@ -0,0 +1,108 @@
+//! Job control, i.e., stopping and continuing processes by signals.
+//!
+//! A process is stopped when one of its threads delivers a stop signal whose
+//! action is the default one. The other threads of the process are stopped
+//! once they return from their current syscalls or are interrupted. A stopped
+//! process is continued by SIGCONT or killed by SIGKILL. The parent is
+//! notified of both changes by SIGCHLD and wait4.
+
+use super::process::{JobStatusChange, ProcessInner};
+use super::{ProcessRef, ThreadRef};
+use crate::events::Waiter;
+use crate::prelude::*;
+use crate::signal::{ChildSignal, SigAction, SigActionFlags, SigNum, SIGCHLD};
+
+/// Stop the process by the given signal.
+pub fn stop_process(process: &ProcessRef, signum: SigNum) {
+    // Lock order: always lock parent then child to avoid deadlock
+    let parent = process.parent();
+    let mut parent_inner = parent.inner();
+    if !process.inner().stop(signum) {
+        return;
+    }
+    debug!(
+        "Process stopped: pid = {}, signal = {:?}",
+        process.pid(),
+        signum
+    );
+    notify_parent(
+        &parent,
+        &mut parent_inner,
+        process,
+        JobStatusChange::Stopped(signum),
+    );
+}
+
+/// Continue the process if it is stopped.
+pub fn continue_process(process: &ProcessRef) {
+    // Lock order: always lock parent then child to avoid deadlock
+    let parent = process.parent();
+    let mut parent_inner = parent.inner();
+    if !process.inner().resume() {
+        return;
+    }
+    debug!("Process continued: pid = {}", process.pid());
+    process.stopped_threads().dequeue_and_wake_all();
+    notify_parent(
+        &parent,
+        &mut parent_inner,
+        process,
+        JobStatusChange::Continued,
+    );
+}
+
+/// Block the current thread until its process is continued or killed.
+pub fn wait_until_continued(thread: &ThreadRef) {
+    let process = thread.process();
+    let waiter = Waiter::new();
+    loop {
+        process.stopped_threads().reset_and_enqueue(&waiter);
+        if !process.is_stopped() {
+            break;
+        }
+        // The waiter is interrupted only if the process is killed
+        if waiter.wait(None).is_err() {
+            break;
+        }
+    }
+}
+
+fn notify_parent(
+    parent: &ProcessRef,
+    parent_inner: &mut SgxMutexGuard<ProcessInner>,
+    child: &ProcessRef,
+    change: JobStatusChange,
+) {
+    if parent.pid() == 0 {
+        return;
+    }
+
+    // Send SIGCHLD to the parent, unless it sets SA_NOCLDSTOP for the signal
+    let is_nocldstop = match parent.sig_dispositions().read().unwrap().get(SIGCHLD) {
+        SigAction::User { flags, .. } => flags.contains(SigActionFlags::SA_NOCLDSTOP),
+        _ => false,
+    };
+    if !is_nocldstop {
+        let cpu_time = child.cpu_time();
+        let signal = match change {
+            JobStatusChange::Stopped(signum) => {
+                ChildSignal::new_stopped(child.pid(), signum, cpu_time.utime(), cpu_time.stime())
+            }
+            JobStatusChange::Continued => {
+                ChildSignal::new_continued(child.pid(), cpu_time.utime(), cpu_time.stime())
+            }
+        };
+        parent
+            .sig_queues()
+            .write()
+            .unwrap()
+            .enqueue(Box::new(signal));
+    }
+
+    // Wake up the parent if it is waiting on the children, which checks
+    // whether it waits for this change by itself
+    parent_inner
+        .waiting_children_mut()
+        .unwrap()
+        .del_and_wake_all_waiters(|_| Some(child.pid()));
+}
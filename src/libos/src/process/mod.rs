@@ -21,9 +21,16 @@ use self::thread::{ThreadBuilder, ThreadId, ThreadInner};
 use self::wait::{WaitQueue, Waiter};
 
 pub use self::do_exit::handle_force_exit;
+pub use self::do_fork::resume_fork_parent;
 pub use self::do_futex::{futex_wait, futex_wake};
+pub use self::do_itimer::{handle_cpu_itimers, has_cpu_itimers};
 pub use self::do_spawn::do_spawn_without_exec;
+pub use self::itimer::itimerval_t;
+pub use self::job_control::{continue_process, stop_process, wait_until_continued};
+pub use self::pid_file::{AsPidFile, PidFile};
+pub use self::posix_timer::{sigevent_t, timer_t, SigEvent};
 pub use self::process::{Process, ProcessFilter, ProcessStatus, IDLE};
+pub use self::rusage::rusage_t;
 pub use self::syscalls::*;
 pub use self::task::Task;
 pub use self::term_status::{ForcedExitStatus, TermStatus};
@@ -31,14 +38,26 @@ pub use self::thread::{Thread, ThreadStatus};
 
 mod do_arch_prctl;
 mod do_clone;
+mod do_exec;
 mod do_exit;
+mod do_fork;
 mod do_futex;
 mod do_getpid;
+mod do_getrusage;
+mod do_itimer;
+mod do_pgid;
+mod do_pidfd;
+mod do_posix_timer;
 mod do_set_tid_address;
 mod do_spawn;
 mod do_wait4;
+mod itimer;
+mod job_control;
+mod pid_file;
+mod posix_timer;
 mod prctl;
 mod process;
+mod rusage;
 mod syscalls;
 mod term_status;
 mod thread;
@@ -0,0 +1,92 @@
+use std::any::Any;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::{ProcessRef, ProcessStatus};
+use crate::fs::{AccessMode, File, IoEvents, IoNotifier, StatusFlags};
+use crate::prelude::*;
+
+/// A file that refers to a process, i.e., a pidfd.
+///
+/// Unlike a pid, which may be reused after the process is reaped, a pidfd
+/// always refers to the same process. So signals can be sent via a pidfd
+/// without races. A pidfd becomes readable, as reported by poll, select,
+/// and epoll, once the process exits.
+pub struct PidFile {
+    process: ProcessRef,
+    is_nonblocking: AtomicBool,
+}
+
+impl PidFile {
+    pub fn new(process: ProcessRef, is_nonblocking: bool) -> Self {
+        Self {
+            process,
+            is_nonblocking: AtomicBool::new(is_nonblocking),
+        }
+    }
+
+    pub fn process(&self) -> &ProcessRef {
+        &self.process
+    }
+
+    fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Acquire)
+    }
+}
+
+impl File for PidFile {
+    fn access_mode(&self) -> Result<AccessMode> {
+        Ok(AccessMode::O_RDWR)
+    }
+
+    fn status_flags(&self) -> Result<StatusFlags> {
+        if self.is_nonblocking() {
+            Ok(StatusFlags::O_NONBLOCK)
+        } else {
+            Ok(StatusFlags::empty())
+        }
+    }
+
+    fn set_status_flags(&self, new_status_flags: StatusFlags) -> Result<()> {
+        let nonblocking = new_status_flags.contains(StatusFlags::O_NONBLOCK);
+        self.is_nonblocking.store(nonblocking, Ordering::Release);
+        Ok(())
+    }
+
+    fn poll_new(&self) -> IoEvents {
+        if self.process.status() == ProcessStatus::Zombie {
+            IoEvents::IN
+        } else {
+            IoEvents::empty()
+        }
+    }
+
+    fn notifier(&self) -> Option<&IoNotifier> {
+        Some(self.process.exit_notifier())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl fmt::Debug for PidFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PidFile")
+            .field("pid", &self.process.pid())
+            .field("is_nonblocking", &self.is_nonblocking())
+            .finish()
+    }
+}
+
+pub trait AsPidFile {
+    fn as_pid_file(&self) -> Result<&PidFile>;
+}
+
+impl AsPidFile for FileRef {
+    fn as_pid_file(&self) -> Result<&PidFile> {
+        self.as_any()
+            .downcast_ref::<PidFile>()
+            .ok_or_else(|| errno!(EBADF, "not a pidfd"))
+    }
+}
@@ -0,0 +1,223 @@
+use std::sync::Weak;
+
+use super::{Process, ProcessStatus, Thread, ThreadStatus};
+use crate::prelude::*;
+use crate::signal::{sigval_t, SigNum, TimerOverrun, TimerSignal};
+use crate::time::{itimerspec_t, ClockID, Timer};
+
+#[allow(non_camel_case_types)]
+pub type timer_t = i32;
+
+/// The flag of timer_settime that makes the value of the new setting an
+/// absolute time.
+pub const TIMER_ABSTIME: i32 = 1;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct sigevent_t {
+    pub sigev_value: sigval_t,
+    pub sigev_signo: i32,
+    pub sigev_notify: i32,
+    // The first member of the union, which is the tid of SIGEV_THREAD_ID
+    pub sigev_tid: i32,
+    _pad: [i32; 11],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum SigEvNotify {
+    SIGEV_SIGNAL = 0,
+    SIGEV_NONE = 1,
+    SIGEV_THREAD = 2,
+    SIGEV_THREAD_ID = 4,
+}
+
+impl SigEvNotify {
+    pub fn from_i32(notify: i32) -> Result<Self> {
+        Ok(match notify {
+            0 => SigEvNotify::SIGEV_SIGNAL,
+            1 => SigEvNotify::SIGEV_NONE,
+            2 => SigEvNotify::SIGEV_THREAD,
+            4 => SigEvNotify::SIGEV_THREAD_ID,
+            _ => return_errno!(EINVAL, "invalid sigev_notify"),
+        })
+    }
+}
+
+/// How the expirations of a timer are notified.
+#[derive(Debug, Clone, Copy)]
+pub enum SigEvent {
+    None,
+    /// Send a signal to the process, or to a thread of the process if the tid
+    /// is given.
+    Signal {
+        signum: SigNum,
+        value: sigval_t,
+        tid: Option<pid_t>,
+    },
+}
+
+// sigval_t is a plain value given by the user, which is never dereferenced
+unsafe impl Sync for SigEvent {}
+unsafe impl Send for SigEvent {}
+
+impl SigEvent {
+    pub fn from_c(sigevent: &sigevent_t) -> Result<Self> {
+        let notify = SigEvNotify::from_i32(sigevent.sigev_notify)?;
+        let tid = match notify {
+            SigEvNotify::SIGEV_NONE => return Ok(SigEvent::None),
+            SigEvNotify::SIGEV_SIGNAL => None,
+            SigEvNotify::SIGEV_THREAD_ID => Some(sigevent.sigev_tid),
+            // SIGEV_THREAD is implemented by libc on top of SIGEV_THREAD_ID
+            SigEvNotify::SIGEV_THREAD => return_errno!(EINVAL, "SIGEV_THREAD is handled by libc"),
+        };
+        if sigevent.sigev_signo < 0 || sigevent.sigev_signo > u8::MAX as i32 {
+            return_errno!(EINVAL, "invalid sigev_signo");
+        }
+        let signum = SigNum::from_u8(sigevent.sigev_signo as u8)?;
+        Ok(SigEvent::Signal {
+            signum,
+            value: sigevent.sigev_value,
+            tid,
+        })
+    }
+}
+
+/// A POSIX timer of a process.
+#[derive(Debug)]
+pub struct PosixTimer {
+    timer: Timer,
+    overrun: Arc<TimerOverrun>,
+}
+
+impl PosixTimer {
+    pub fn new(
+        id: timer_t,
+        clockid: ClockID,
+        sigevent: SigEvent,
+        process: &Arc<Process>,
+    ) -> Result<Self> {
+        let overrun = Arc::new(TimerOverrun::new());
+        let callback = {
+            let target_thread = match sigevent {
+                SigEvent::Signal { tid: Some(tid), .. } => {
+                    let thread = process
+                        .threads()
+                        .into_iter()
+                        .find(|thread| thread.tid() == tid)
+                        .ok_or_else(|| errno!(EINVAL, "the thread is not in the process"))?;
+                    Some(Arc::downgrade(&thread))
+                }
+                _ => None,
+            };
+            let process = Arc::downgrade(process);
+            let overrun = overrun.clone();
+            Arc::new(move |num_expirations| {
+                if let SigEvent::Signal { signum, value, .. } = sigevent {
+                    notify_by_signal(
+                        &process,
+                        target_thread.as_ref(),
+                        TimerSignalInfo { id, signum, value },
+                        &overrun,
+                        num_expirations,
+                    );
+                }
+            })
+        };
+        let timer = Timer::new(clockid, callback)?;
+        Ok(Self { timer, overrun })
+    }
+
+    /// Arm or disarm the timer, returning the old setting of the timer.
+    pub fn set(&self, new_value: &itimerspec_t, is_abs: bool) -> Result<itimerspec_t> {
+        let old_value = self.timer.set(new_value, is_abs)?;
+        self.overrun.reset();
+        Ok(old_value)
+    }
+
+    pub fn get(&self) -> itimerspec_t {
+        self.timer.get()
+    }
+
+    /// Get the overrun of the last delivered signal.
+    pub fn overrun(&self) -> i32 {
+        self.overrun.last()
+    }
+}
+
+/// The POSIX timers of a process.
+#[derive(Debug, Default)]
+pub struct PosixTimers {
+    timers: HashMap<timer_t, PosixTimer>,
+    next_id: timer_t,
+}
+
+impl PosixTimers {
+    /// Allocate an unused timer ID.
+    pub fn alloc_id(&mut self) -> timer_t {
+        loop {
+            let id = self.next_id;
+            self.next_id = self.next_id.checked_add(1).unwrap_or(0);
+            if !self.timers.contains_key(&id) {
+                return id;
+            }
+        }
+    }
+
+    pub fn insert(&mut self, id: timer_t, timer: PosixTimer) {
+        self.timers.insert(id, timer);
+    }
+
+    pub fn get(&self, id: timer_t) -> Result<&PosixTimer> {
+        self.timers
+            .get(&id)
+            .ok_or_else(|| errno!(EINVAL, "invalid timer ID"))
+    }
+
+    pub fn remove(&mut self, id: timer_t) -> Result<PosixTimer> {
+        self.timers
+            .remove(&id)
+            .ok_or_else(|| errno!(EINVAL, "invalid timer ID"))
+    }
+}
+
+#[derive(Clone, Copy)]
+struct TimerSignalInfo {
+    id: timer_t,
+    signum: SigNum,
+    value: sigval_t,
+}
+
+fn notify_by_signal(
+    process: &Weak<Process>,
+    target_thread: Option<&Weak<Thread>>,
+    info: TimerSignalInfo,
+    overrun: &Arc<TimerOverrun>,
+    num_expirations: u64,
+) {
+    let process = match process.upgrade() {
+        Some(process) if process.status() != ProcessStatus::Zombie => process,
+        _ => return,
+    };
+    if !overrun.on_expire(num_expirations) {
+        return;
+    }
+
+    let signal = Box::new(TimerSignal::new(
+        info.signum,
+        info.id,
+        info.value,
+        overrun.clone(),
+    ));
+    match target_thread {
+        Some(thread) => {
+            if let Some(thread) = thread.upgrade() {
+                if thread.status() != ThreadStatus::Exited {
+                    thread.sig_queues().write().unwrap().enqueue(signal);
+                }
+            }
+        }
+        None => process.sig_queues().write().unwrap().enqueue(signal),
+    }
+}
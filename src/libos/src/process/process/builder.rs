@@ -1,3 +1,6 @@
+use super::super::itimer::ITimers;
+use super::super::posix_timer::PosixTimers;
+use super::super::rusage::ProcessUsage;
 use super::super::task::Task;
 use super::super::thread::{ThreadBuilder, ThreadId, ThreadName};
 use super::super::{
@@ -5,8 +8,11 @@ use super::super::{
     SchedAgentRef,
 };
 use super::{Process, ProcessInner};
+use crate::events::WaiterQueue;
+use crate::fs::{AioContexts, IoNotifier};
 use crate::prelude::*;
 use crate::signal::{SigDispositions, SigQueues};
+use std::ffi::CString;
 
 #[derive(Debug)]
 pub struct ProcessBuilder {
@@ -16,6 +22,8 @@ pub struct ProcessBuilder {
     vm: Option<ProcessVMRef>,
     // Optional fields, which have reasonable default values
     exec_path: Option<String>,
+    argv: Option<Vec<CString>>,
+    envp: Option<Vec<CString>>,
     parent: Option<ProcessRef>,
     no_parent: bool,
 }
@@ -28,6 +36,8 @@ impl ProcessBuilder {
             thread_builder: Some(thread_builder),
             vm: None,
             exec_path: None,
+            argv: None,
+            envp: None,
             parent: None,
             no_parent: false,
         }
@@ -43,6 +53,16 @@ impl ProcessBuilder {
         self
     }
 
+    pub fn argv(mut self, argv: &[CString]) -> Self {
+        self.argv = Some(argv.to_vec());
+        self
+    }
+
+    pub fn envp(mut self, envp: &[CString]) -> Self {
+        self.envp = Some(envp.to_vec());
+        self
+    }
+
     pub fn parent(mut self, parent: ProcessRef) -> Self {
         self.parent = Some(parent);
         self
@@ -97,19 +117,46 @@ impl ProcessBuilder {
         // Build a new process
         let new_process = {
             let exec_path = self.exec_path.take().unwrap_or_default();
+            let argv = self.argv.take().unwrap_or_default();
+            let envp = self.envp.take().unwrap_or_default();
+            let start_time = crate::time::up_time::get().unwrap_or_default();
+            // A process inherits the process group and the session of its
+            // parent, except that the first process starts a new session
+            let (pgid, sid) = match self.parent.as_ref() {
+                Some(parent) if parent.pid() != 0 => (parent.pgid(), parent.sid()),
+                Some(_) => (pid, pid),
+                None => (0, 0),
+            };
             let parent = self.parent.take().map(|parent| RwLock::new(parent));
             let inner = SgxMutex::new(ProcessInner::new());
             let sig_dispositions = RwLock::new(SigDispositions::new());
             let sig_queues = RwLock::new(SigQueues::new());
             let forced_exit_status = ForcedExitStatus::new();
+            let stopped_threads = WaiterQueue::new();
+            let exit_notifier = IoNotifier::new();
+            let usage = SgxMutex::new(ProcessUsage::default());
+            let itimers = SgxMutex::new(ITimers::default());
+            let posix_timers = SgxMutex::new(PosixTimers::default());
+            let aio_contexts = SgxMutex::new(AioContexts::default());
             Arc::new(Process {
                 pid,
                 exec_path,
+                argv,
+                envp,
+                start_time,
                 parent,
+                pgid: RwLock::new(pgid),
+                sid: RwLock::new(sid),
                 inner,
                 sig_dispositions,
                 sig_queues,
                 forced_exit_status,
+                stopped_threads,
+                exit_notifier,
+                usage,
+                itimers,
+                posix_timers,
+                aio_contexts,
             })
         };
 
@@ -1,7 +1,14 @@
+use std::ffi::CString;
 use std::fmt;
+use std::time::Duration;
 
+use super::itimer::ITimers;
+use super::posix_timer::PosixTimers;
+use super::rusage::{CpuTime, ProcessUsage};
 use super::wait::WaitQueue;
 use super::{ForcedExitStatus, ProcessRef, TermStatus, ThreadRef};
+use crate::events::WaiterQueue;
+use crate::fs::{AioContexts, IoNotifier};
 use crate::prelude::*;
 use crate::signal::{SigDispositions, SigNum, SigQueues};
 
@@ -15,13 +22,29 @@ pub struct Process {
     // Immutable info
     pid: pid_t,
     exec_path: String,
+    argv: Vec<CString>,
+    envp: Vec<CString>,
+    start_time: Duration,
     // Mutable info
     parent: Option<RwLock<ProcessRef>>,
+    pgid: RwLock<pid_t>,
+    sid: RwLock<pid_t>,
     inner: SgxMutex<ProcessInner>,
     // Signal
     sig_dispositions: RwLock<SigDispositions>,
     sig_queues: RwLock<SigQueues>,
     forced_exit_status: ForcedExitStatus,
+    // The threads that are stopped by job control
+    stopped_threads: WaiterQueue,
+    // Broadcast when the process exits, which makes pidfds pollable
+    exit_notifier: IoNotifier,
+    // Resource usage
+    usage: SgxMutex<ProcessUsage>,
+    // Interval timers
+    itimers: SgxMutex<ITimers>,
+    posix_timers: SgxMutex<PosixTimers>,
+    // AIO contexts
+    aio_contexts: SgxMutex<AioContexts>,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -37,10 +60,22 @@ impl Process {
         self.pid
     }
 
-    /// Get process group ID
-    // TODO: implement process group
+    /// Get process group ID.
     pub fn pgid(&self) -> pid_t {
-        self.pid
+        *self.pgid.read().unwrap()
+    }
+
+    /// Get session ID.
+    pub fn sid(&self) -> pid_t {
+        *self.sid.read().unwrap()
+    }
+
+    pub(super) fn set_pgid(&self, pgid: pid_t) {
+        *self.pgid.write().unwrap() = pgid;
+    }
+
+    pub(super) fn set_sid(&self, sid: pid_t) {
+        *self.sid.write().unwrap() = sid;
     }
 
     /// Get the parent process.
@@ -99,6 +134,21 @@ impl Process {
         &self.exec_path
     }
 
+    /// Get the arguments given to the executable.
+    pub fn argv(&self) -> &[CString] {
+        &self.argv
+    }
+
+    /// Get the environment variables given to the executable.
+    pub fn envp(&self) -> &[CString] {
+        &self.envp
+    }
+
+    /// Get the time when the process was created, relative to the boot of LibOS.
+    pub fn start_time(&self) -> Duration {
+        self.start_time
+    }
+
     /// Get the signal queues for process-directed signals.
     pub fn sig_queues(&self) -> &RwLock<SigQueues> {
         &self.sig_queues
@@ -129,6 +179,52 @@ impl Process {
         self.forced_exit_status.force_exit(term_status);
     }
 
+    /// Check whether the process has been stopped by job control.
+    pub fn is_stopped(&self) -> bool {
+        self.status() == ProcessStatus::Stopped
+    }
+
+    /// Get the queue of the threads that are stopped until the process is
+    /// continued.
+    pub(super) fn stopped_threads(&self) -> &WaiterQueue {
+        &self.stopped_threads
+    }
+
+    /// Get the notifier that broadcasts `IoEvents::IN` when the process exits.
+    pub fn exit_notifier(&self) -> &IoNotifier {
+        &self.exit_notifier
+    }
+
+    /// Get the resource usage of the exited threads and the waited children.
+    pub(super) fn usage(&self) -> SgxMutexGuard<ProcessUsage> {
+        self.usage.lock().unwrap()
+    }
+
+    /// Get the CPU time used by the process.
+    ///
+    /// The CPU time of a live thread is the one when it last updated it.
+    pub(super) fn cpu_time(&self) -> CpuTime {
+        let exited_cpu_time = self.usage().cpu_time();
+        self.threads().iter().fold(exited_cpu_time, |sum, thread| {
+            sum + thread.cpu_time().lock().unwrap().used()
+        })
+    }
+
+    /// Get the interval timers.
+    pub(super) fn itimers(&self) -> SgxMutexGuard<ITimers> {
+        self.itimers.lock().unwrap()
+    }
+
+    /// Get the POSIX timers.
+    pub(super) fn posix_timers(&self) -> SgxMutexGuard<PosixTimers> {
+        self.posix_timers.lock().unwrap()
+    }
+
+    /// Get the AIO contexts.
+    pub fn aio_contexts(&self) -> SgxMutexGuard<AioContexts> {
+        self.aio_contexts.lock().unwrap()
+    }
+
     /// Get the internal representation of the process.
     ///
     /// For the purpose of encapsulation, this method is invisible to other subsystems.
@@ -140,6 +236,8 @@ impl Process {
 pub enum ProcessInner {
     Live {
         status: LiveStatus,
+        // The change of the status that has not been reported by wait4 yet
+        job_status_change: Option<JobStatusChange>,
         children: Vec<ProcessRef>,
         waiting_children: WaitQueue<ProcessFilter, pid_t>,
         threads: Vec<ThreadRef>,
@@ -153,6 +251,7 @@ impl ProcessInner {
     pub fn new() -> Self {
         Self::Live {
             status: LiveStatus::Running,
+            job_status_change: None,
             children: Vec::new(),
             waiting_children: WaitQueue::new(),
             threads: Vec::new(),
@@ -233,6 +332,57 @@ impl ProcessInner {
         children.swap_remove(zombie_i)
     }
 
+    /// Stop the process by a signal, returning whether the process is stopped
+    /// by this call.
+    pub fn stop(&mut self, signum: SigNum) -> bool {
+        match self {
+            Self::Live {
+                status,
+                job_status_change,
+                ..
+            } if *status == LiveStatus::Running => {
+                *status = LiveStatus::Stopped;
+                *job_status_change = Some(JobStatusChange::Stopped(signum));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Continue the process, returning whether the process was stopped.
+    pub fn resume(&mut self) -> bool {
+        match self {
+            Self::Live {
+                status,
+                job_status_change,
+                ..
+            } if *status == LiveStatus::Stopped => {
+                *status = LiveStatus::Running;
+                *job_status_change = Some(JobStatusChange::Continued);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Take the unreported change of the status if it is accepted by the filter.
+    pub fn take_job_status_change<F>(&mut self, filter: F) -> Option<JobStatusChange>
+    where
+        F: Fn(&JobStatusChange) -> bool,
+    {
+        match self {
+            Self::Live {
+                job_status_change, ..
+            } if job_status_change
+                .as_ref()
+                .map_or(false, |change| filter(change)) =>
+            {
+                job_status_change.take()
+            }
+            _ => None,
+        }
+    }
+
     /// Exit means two things: 1) transfer all children to a new parent; 2) update the status.
     ///
     /// A lock guard for the new parent process is passed so that the transfer can be done
@@ -243,8 +393,8 @@ impl ProcessInner {
         new_parent_ref: &ProcessRef,
         new_parent_inner: &mut SgxMutexGuard<ProcessInner>,
     ) {
-        // Check preconditions
-        debug_assert!(self.status() == ProcessStatus::Running);
+        // Check preconditions. A stopped process may exit if it is killed.
+        debug_assert!(self.status() != ProcessStatus::Zombie);
         debug_assert!(self.num_threads() == 0);
 
         // When this process exits, its children are adopted by the init process
@@ -294,6 +444,8 @@ impl fmt::Debug for Process {
             .field("pid", &self.pid())
             .field("exec_path", &self.exec_path())
             .field("ppid", &ppid)
+            .field("pgid", &self.pgid())
+            .field("sid", &self.sid())
             .field("inner", &self.inner())
             .finish()
     }
@@ -348,6 +500,24 @@ impl Into<ProcessStatus> for LiveStatus {
     }
 }
 
+/// A change of the status of a live process by job control, which is reported
+/// to its parent by wait4.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum JobStatusChange {
+    Stopped(SigNum),
+    Continued,
+}
+
+impl JobStatusChange {
+    /// Return as a 32-bit integer encoded as specified in wait(2) man page.
+    pub fn as_u32(&self) -> u32 {
+        match *self {
+            JobStatusChange::Stopped(signum) => ((signum.as_u8() as u32) << 8) | 0x7f,
+            JobStatusChange::Continued => 0xffff,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum ProcessFilter {
     WithAnyPid,
@@ -0,0 +1,181 @@
+use std::ops::{Add, AddAssign};
+use std::time::Duration;
+
+use crate::prelude::*;
+use crate::time::{do_thread_getrusage, timeval_t};
+
+/// The CPU time consumed in user mode and in kernel mode.
+///
+/// The CPU time of a LibOS thread is measured by the host thread that executes
+/// it. Since LibOS runs in the same mode as the user program, the time spent in
+/// LibOS is counted as user time, while the time spent in the host OS kernel
+/// (e.g., during OCalls) is counted as kernel time.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CpuTime {
+    utime: Duration,
+    stime: Duration,
+}
+
+impl CpuTime {
+    /// Get the CPU time consumed so far by the current host thread.
+    pub fn of_host_thread() -> Result<Self> {
+        let (utime, stime) = do_thread_getrusage()?;
+        Ok(Self {
+            utime: utime.as_duration(),
+            stime: stime.as_duration(),
+        })
+    }
+
+    pub fn utime(&self) -> Duration {
+        self.utime
+    }
+
+    pub fn stime(&self) -> Duration {
+        self.stime
+    }
+
+    fn saturating_sub(&self, other: &Self) -> Self {
+        Self {
+            utime: self.utime.checked_sub(other.utime).unwrap_or_default(),
+            stime: self.stime.checked_sub(other.stime).unwrap_or_default(),
+        }
+    }
+}
+
+impl Add for CpuTime {
+    type Output = CpuTime;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            utime: self.utime + other.utime,
+            stime: self.stime + other.stime,
+        }
+    }
+}
+
+impl AddAssign for CpuTime {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+/// The CPU time accounting of a thread.
+#[derive(Debug, Default)]
+pub struct ThreadCpuTime {
+    // The CPU time of the host thread when the accounting starts
+    start: CpuTime,
+    // The CPU time used by the thread at the last update
+    used: CpuTime,
+}
+
+impl ThreadCpuTime {
+    /// Start the accounting in the host thread that executes the thread.
+    pub fn start(&mut self) {
+        self.start = CpuTime::of_host_thread().unwrap_or_default();
+        self.used = Default::default();
+    }
+
+    /// Update and get the CPU time used by the thread.
+    ///
+    /// This must be called in the host thread that executes the thread.
+    pub fn update(&mut self) -> CpuTime {
+        if let Ok(now) = CpuTime::of_host_thread() {
+            self.used = now.saturating_sub(&self.start);
+        }
+        self.used
+    }
+
+    /// Get the CPU time used by the thread at the last update.
+    pub fn used(&self) -> CpuTime {
+        self.used
+    }
+
+    /// Exclude the CPU time used by another thread that has run in the same
+    /// host thread, e.g., the child of fork.
+    pub fn exclude(&mut self, other: CpuTime) {
+        self.start += other;
+    }
+}
+
+/// The resource usage of a process that outlives its threads.
+#[derive(Debug, Default)]
+pub struct ProcessUsage {
+    // The CPU time used by the exited threads
+    cpu_time: CpuTime,
+    // The maximum resident set size in bytes, which is recorded on exit
+    max_rss: usize,
+    // The resource usage of the waited children and their descendants
+    children_cpu_time: CpuTime,
+    children_max_rss: usize,
+}
+
+impl ProcessUsage {
+    pub fn cpu_time(&self) -> CpuTime {
+        self.cpu_time
+    }
+
+    pub fn add_exited_thread(&mut self, cpu_time: CpuTime) {
+        self.cpu_time += cpu_time;
+    }
+
+    pub fn set_max_rss(&mut self, max_rss: usize) {
+        self.max_rss = max_rss;
+    }
+
+    /// Get the resource usage of the waited children.
+    pub fn children(&self) -> rusage_t {
+        rusage_t::new(self.children_cpu_time, self.children_max_rss)
+    }
+
+    /// Get the resource usage of the exited process, including its waited
+    /// children.
+    pub fn exited_with_children(&self) -> rusage_t {
+        rusage_t::new(
+            self.cpu_time + self.children_cpu_time,
+            self.max_rss.max(self.children_max_rss),
+        )
+    }
+
+    /// Add the resource usage of a waited child.
+    pub fn add_waited_child(&mut self, child: &ProcessUsage) {
+        self.children_cpu_time += child.cpu_time + child.children_cpu_time;
+        self.children_max_rss = self
+            .children_max_rss
+            .max(child.max_rss)
+            .max(child.children_max_rss);
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct rusage_t {
+    ru_utime: timeval_t,
+    ru_stime: timeval_t,
+    // In kilobytes
+    ru_maxrss: i64,
+    ru_ixrss: i64,
+    ru_idrss: i64,
+    ru_isrss: i64,
+    ru_minflt: i64,
+    ru_majflt: i64,
+    ru_nswap: i64,
+    ru_inblock: i64,
+    ru_oublock: i64,
+    ru_msgsnd: i64,
+    ru_msgrcv: i64,
+    ru_nsignals: i64,
+    ru_nvcsw: i64,
+    ru_nivcsw: i64,
+}
+
+impl rusage_t {
+    pub fn new(cpu_time: CpuTime, max_rss: usize) -> Self {
+        Self {
+            ru_utime: cpu_time.utime().into(),
+            ru_stime: cpu_time.stime().into(),
+            ru_maxrss: (max_rss / 1024) as i64,
+            ..Default::default()
+        }
+    }
+}
@@ -1,11 +1,20 @@
 use super::do_arch_prctl::ArchPrctlCode;
 use super::do_clone::CloneFlags;
+use super::do_exec::ExecveatFlags;
 use super::do_futex::{FutexFlags, FutexOp, FutexTimeout};
+use super::do_getrusage::RusageWho;
+use super::do_pidfd::PidfdFlags;
 use super::do_spawn::FileAction;
+use super::do_wait4::WaitOptions;
+use super::itimer::{itimerval_t, ITimerWhich};
+use super::posix_timer::{sigevent_t, timer_t};
 use super::prctl::PrctlCmd;
 use super::process::ProcessFilter;
+use super::rusage::rusage_t;
+use crate::fs::FsPath;
 use crate::prelude::*;
-use crate::time::{timespec_t, ClockID};
+use crate::syscall::CpuContext;
+use crate::time::{clockid_t, itimerspec_t, timespec_t, ClockID};
 use crate::util::mem_util::from_user::*;
 use std::ptr::NonNull;
 
@@ -52,6 +61,8 @@ pub struct FdOp {
 const FDOP_CLOSE: u32 = 1;
 const FDOP_DUP2: u32 = 2;
 const FDOP_OPEN: u32 = 3;
+const FDOP_CHDIR: u32 = 4;
+const FDOP_FCHDIR: u32 = 5;
 
 fn clone_file_actions_safely(fdop_ptr: *const FdOp) -> Result<Vec<FileAction>> {
     let mut file_actions = Vec::new();
@@ -73,6 +84,12 @@ fn clone_file_actions_safely(fdop_ptr: *const FdOp) -> Result<Vec<FileAction>> {
                 oflag: fdop.oflag,
                 fd: fdop.fd,
             },
+            FDOP_CHDIR => FileAction::Chdir(
+                clone_cstring_safely(fdop.path)?
+                    .to_string_lossy()
+                    .into_owned(),
+            ),
+            FDOP_FCHDIR => FileAction::Fchdir(fdop.fd),
             _ => {
                 return_errno!(EINVAL, "Unknown file action command");
             }
@@ -139,6 +156,15 @@ impl SpawnAction {
                 oflag: unsafe { self.action.open_action.oflag },
                 fd: unsafe { self.action.open_action.fd },
             },
+            SPAWN_DO_CHDIR => FileAction::Chdir(
+                clone_cstring_safely(unsafe { self.action.chdir_action.path })?
+                    .to_string_lossy()
+                    .into_owned(),
+            ),
+            SPAWN_DO_FCHDIR => FileAction::Fchdir(unsafe { self.action.fchdir_action.fd }),
+            SPAWN_DO_CLOSEFROM => {
+                FileAction::CloseFrom(unsafe { self.action.closefrom_action.from })
+            }
             _ => return_errno!(EINVAL, "Unknown file action tag"),
         })
     }
@@ -148,12 +174,18 @@ impl SpawnAction {
 const SPAWN_DO_CLOSE: u32 = 0;
 const SPAWN_DO_DUP2: u32 = 1;
 const SPAWN_DO_OPEN: u32 = 2;
+const SPAWN_DO_CHDIR: u32 = 3;
+const SPAWN_DO_FCHDIR: u32 = 4;
+const SPAWN_DO_CLOSEFROM: u32 = 5;
 
 #[repr(C)]
 union Action {
     close_action: CloseAction,
     dup2_action: Dup2Action,
     open_action: OpenAction,
+    chdir_action: ChdirAction,
+    fchdir_action: FchdirAction,
+    closefrom_action: CloseFromAction,
 }
 
 #[repr(C)]
@@ -178,6 +210,24 @@ struct OpenAction {
     mode: u32,
 }
 
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct ChdirAction {
+    path: *const i8,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct FchdirAction {
+    fd: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct CloseFromAction {
+    from: u32,
+}
+
 fn clone_file_actions_from_fa_safely(fa_ptr: *const SpawnFileActions) -> Result<Vec<FileAction>> {
     let mut file_actions = Vec::new();
     if fa_ptr == std::ptr::null() {
@@ -240,6 +290,52 @@ pub fn do_clone(
     Ok(child_pid as isize)
 }
 
+pub fn do_fork(user_context: *mut CpuContext) -> Result<isize> {
+    let user_context = unsafe { &*user_context };
+    let child_pid = super::do_fork::do_fork(user_context)?;
+    Ok(child_pid as isize)
+}
+
+pub fn do_vfork(user_context: *mut CpuContext) -> Result<isize> {
+    let user_context = unsafe { &*user_context };
+    let child_pid = super::do_fork::do_vfork(user_context)?;
+    Ok(child_pid as isize)
+}
+
+pub fn do_execve(path: *const i8, argv: *const *const i8, envp: *const *const i8) -> Result<isize> {
+    let path = clone_cstring_safely(path)?.to_string_lossy().into_owned();
+    let argv = clone_cstrings_safely(argv)?;
+    let envp = clone_cstrings_safely(envp)?;
+    debug!(
+        "execve: path: {:?}, argv: {:?}, envp: {:?}",
+        path, argv, envp
+    );
+
+    super::do_exec::do_exec(&path, &argv, &envp)?;
+    Ok(0)
+}
+
+pub fn do_execveat(
+    dirfd: i32,
+    path: *const i8,
+    argv: *const *const i8,
+    envp: *const *const i8,
+    flags: i32,
+) -> Result<isize> {
+    let path = clone_cstring_safely(path)?.to_string_lossy().into_owned();
+    let argv = clone_cstrings_safely(argv)?;
+    let envp = clone_cstrings_safely(envp)?;
+    let flags = ExecveatFlags::from_bits(flags).ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+    debug!(
+        "execveat: dirfd: {}, path: {:?}, argv: {:?}, envp: {:?}, flags: {:?}",
+        dirfd, path, argv, envp, flags
+    );
+
+    let fs_path = FsPath::new(&path, dirfd, flags.contains(ExecveatFlags::AT_EMPTY_PATH))?;
+    super::do_exec::do_execveat(&fs_path, &argv, &envp, flags)?;
+    Ok(0)
+}
+
 pub fn do_futex(
     futex_addr: *const i32,
     futex_op: u32,
@@ -333,10 +429,19 @@ pub fn do_exit_group(status: i32) -> Result<isize> {
     Ok(0)
 }
 
-pub fn do_wait4(pid: i32, exit_status_ptr: *mut i32) -> Result<isize> {
+pub fn do_wait4(
+    pid: i32,
+    exit_status_ptr: *mut i32,
+    options: u32,
+    rusage_ptr: *mut rusage_t,
+) -> Result<isize> {
     if !exit_status_ptr.is_null() {
         check_mut_ptr(exit_status_ptr)?;
     }
+    if !rusage_ptr.is_null() {
+        check_mut_ptr(rusage_ptr)?;
+    }
+    let options = WaitOptions::from_u32(options)?;
 
     let child_process_filter = match pid {
         pid if pid < -1 => ProcessFilter::WithPgid((-pid) as pid_t),
@@ -348,18 +453,133 @@ pub fn do_wait4(pid: i32, exit_status_ptr: *mut i32) -> Result<isize> {
         pid if pid > 0 => ProcessFilter::WithPid(pid as pid_t),
         _ => unreachable!(),
     };
-    let mut exit_status = 0;
-    match super::do_wait4::do_wait4(&child_process_filter) {
-        Ok((pid, exit_status)) => {
-            if !exit_status_ptr.is_null() {
-                unsafe {
-                    *exit_status_ptr = exit_status;
-                }
-            }
-            Ok(pid as isize)
+    let (pid, exit_status, rusage) =
+        match super::do_wait4::do_wait4(&child_process_filter, options)? {
+            Some(result) => result,
+            // No child has exited yet with WNOHANG
+            None => return Ok(0),
+        };
+    if !exit_status_ptr.is_null() {
+        unsafe {
+            *exit_status_ptr = exit_status;
         }
-        Err(e) => Err(e),
     }
+    if !rusage_ptr.is_null() {
+        unsafe {
+            *rusage_ptr = rusage;
+        }
+    }
+    Ok(pid as isize)
+}
+
+pub fn do_getrusage(who: i32, rusage_ptr: *mut rusage_t) -> Result<isize> {
+    let rusage = {
+        check_mut_ptr(rusage_ptr)?;
+        unsafe { &mut *rusage_ptr }
+    };
+    let who = RusageWho::from_i32(who)?;
+    *rusage = super::do_getrusage::do_getrusage(who)?;
+    Ok(0)
+}
+
+pub fn do_getitimer(which: i32, curr_value_ptr: *mut itimerval_t) -> Result<isize> {
+    let curr_value = {
+        check_mut_ptr(curr_value_ptr)?;
+        unsafe { &mut *curr_value_ptr }
+    };
+    let which = ITimerWhich::from_i32(which)?;
+    *curr_value = super::do_itimer::do_getitimer(which)?;
+    Ok(0)
+}
+
+pub fn do_setitimer(
+    which: i32,
+    new_value_ptr: *const itimerval_t,
+    old_value_ptr: *mut itimerval_t,
+) -> Result<isize> {
+    let new_value = {
+        check_ptr(new_value_ptr)?;
+        unsafe { &*new_value_ptr }
+    };
+    let old_value = if !old_value_ptr.is_null() {
+        check_mut_ptr(old_value_ptr)?;
+        Some(unsafe { &mut *old_value_ptr })
+    } else {
+        None
+    };
+    let which = ITimerWhich::from_i32(which)?;
+    let old = super::do_itimer::do_setitimer(which, new_value)?;
+    if let Some(old_value) = old_value {
+        *old_value = old;
+    }
+    Ok(0)
+}
+
+pub fn do_alarm(seconds: u32) -> Result<isize> {
+    let remaining_seconds = super::do_itimer::do_alarm(seconds)?;
+    Ok(remaining_seconds as isize)
+}
+
+pub fn do_timer_create(
+    clockid: clockid_t,
+    sevp: *const sigevent_t,
+    timerid_ptr: *mut timer_t,
+) -> Result<isize> {
+    let sigevent = if !sevp.is_null() {
+        check_ptr(sevp)?;
+        Some(unsafe { &*sevp })
+    } else {
+        None
+    };
+    let timerid = {
+        check_mut_ptr(timerid_ptr)?;
+        unsafe { &mut *timerid_ptr }
+    };
+    let clockid = ClockID::from_raw(clockid)?;
+    *timerid = super::do_posix_timer::do_timer_create(clockid, sigevent)?;
+    Ok(0)
+}
+
+pub fn do_timer_settime(
+    timerid: timer_t,
+    flags: i32,
+    new_value_ptr: *const itimerspec_t,
+    old_value_ptr: *mut itimerspec_t,
+) -> Result<isize> {
+    let new_value = {
+        check_ptr(new_value_ptr)?;
+        unsafe { &*new_value_ptr }
+    };
+    let old_value = if !old_value_ptr.is_null() {
+        check_mut_ptr(old_value_ptr)?;
+        Some(unsafe { &mut *old_value_ptr })
+    } else {
+        None
+    };
+    let old = super::do_posix_timer::do_timer_settime(timerid, flags, new_value)?;
+    if let Some(old_value) = old_value {
+        *old_value = old;
+    }
+    Ok(0)
+}
+
+pub fn do_timer_gettime(timerid: timer_t, curr_value_ptr: *mut itimerspec_t) -> Result<isize> {
+    let curr_value = {
+        check_mut_ptr(curr_value_ptr)?;
+        unsafe { &mut *curr_value_ptr }
+    };
+    *curr_value = super::do_posix_timer::do_timer_gettime(timerid)?;
+    Ok(0)
+}
+
+pub fn do_timer_getoverrun(timerid: timer_t) -> Result<isize> {
+    let overrun = super::do_posix_timer::do_timer_getoverrun(timerid)?;
+    Ok(overrun as isize)
+}
+
+pub fn do_timer_delete(timerid: timer_t) -> Result<isize> {
+    super::do_posix_timer::do_timer_delete(timerid)?;
+    Ok(0)
 }
 
 pub fn do_getpid() -> Result<isize> {
@@ -377,11 +597,52 @@ pub fn do_getppid() -> Result<isize> {
     Ok(ppid as isize)
 }
 
-pub fn do_getpgid() -> Result<isize> {
-    let pgid = super::do_getpid::do_getpgid();
+pub fn do_getpgid(pid: i32) -> Result<isize> {
+    if pid < 0 {
+        return_errno!(ESRCH, "process with negative pid is not found");
+    }
+    let pgid = super::do_pgid::do_getpgid(pid as pid_t)?;
+    Ok(pgid as isize)
+}
+
+pub fn do_getpgrp() -> Result<isize> {
+    let pgid = super::do_pgid::do_getpgid(0)?;
     Ok(pgid as isize)
 }
 
+pub fn do_setpgid(pid: i32, pgid: i32) -> Result<isize> {
+    if pid < 0 {
+        return_errno!(ESRCH, "process with negative pid is not found");
+    }
+    if pgid < 0 {
+        return_errno!(EINVAL, "pgid must not be negative");
+    }
+    super::do_pgid::do_setpgid(pid as pid_t, pgid as pid_t)?;
+    Ok(0)
+}
+
+pub fn do_getsid(pid: i32) -> Result<isize> {
+    if pid < 0 {
+        return_errno!(ESRCH, "process with negative pid is not found");
+    }
+    let sid = super::do_pgid::do_getsid(pid as pid_t)?;
+    Ok(sid as isize)
+}
+
+pub fn do_setsid() -> Result<isize> {
+    let sid = super::do_pgid::do_setsid()?;
+    Ok(sid as isize)
+}
+
+pub fn do_pidfd_open(pid: i32, flags: u32) -> Result<isize> {
+    if pid <= 0 {
+        return_errno!(EINVAL, "pid must be positive");
+    }
+    let flags = PidfdFlags::from_u32(flags)?;
+    let fd = super::do_pidfd::do_pidfd_open(pid as pid_t, flags)?;
+    Ok(fd as isize)
+}
+
 // TODO: implement uid, gid, euid, egid
 
 pub fn do_getuid() -> Result<isize> {
@@ -50,6 +50,32 @@ impl Task {
         })
     }
 
+    /// Create a task that continues the execution of this task in the same
+    /// host thread, e.g., for the child of fork.
+    ///
+    /// The new task shares the kernel stack and the saved state of the host
+    /// thread with this task, so only one of them can be the current task.
+    pub(super) fn fork(&self) -> Task {
+        Task {
+            kernel_rsp: self.kernel_rsp,
+            kernel_stack_base: self.kernel_stack_base,
+            kernel_stack_limit: self.kernel_stack_limit,
+            kernel_fs: self.kernel_fs,
+            user_rsp: self.user_rsp,
+            user_stack_base: self.user_stack_base,
+            user_stack_limit: self.user_stack_limit,
+            user_fs: AtomicUsize::new(self.user_fs()),
+            user_entry_addr: self.user_entry_addr,
+            saved_state: self.saved_state,
+        }
+    }
+
+    /// Make this task the current task of the host thread, whose user fsbase
+    /// and kernel stack are used by the following syscalls.
+    pub(super) unsafe fn set_current(&self) {
+        __set_current_task(self as *const Task as *mut Task);
+    }
+
     pub(super) fn set_user_fs(&self, user_fs: usize) {
         self.user_fs.store(user_fs, Ordering::SeqCst);
     }
@@ -58,3 +84,7 @@ impl Task {
         self.user_fs.load(Ordering::SeqCst)
     }
 }
+
+extern "C" {
+    fn __set_current_task(task: *mut Task);
+}
@@ -2,7 +2,7 @@ use std::ptr::NonNull;
 
 use super::{
     FileTableRef, FsViewRef, ProcessRef, ProcessVM, ProcessVMRef, ResourceLimitsRef, SchedAgentRef,
-    SigQueues, SigSet, Task, Thread, ThreadId, ThreadInner, ThreadName, ThreadRef,
+    SigQueues, SigSet, Task, Thread, ThreadCpuTime, ThreadId, ThreadInner, ThreadName, ThreadRef,
 };
 use crate::events::HostEventFd;
 use crate::prelude::*;
@@ -111,12 +111,14 @@ impl ThreadBuilder {
         let sig_queues = RwLock::new(SigQueues::new());
         let sig_mask = RwLock::new(SigSet::new_empty());
         let sig_tmp_mask = RwLock::new(SigSet::new_empty());
+        let sig_saved_mask = RwLock::new(None);
         let sig_stack = SgxMutex::new(None);
         let profiler = if cfg!(feature = "syscall_timing") {
             SgxMutex::new(Some(ThreadProfiler::new()))
         } else {
             SgxMutex::new(None)
         };
+        let cpu_time = SgxMutex::new(ThreadCpuTime::default());
         let host_eventfd = Arc::new(HostEventFd::new()?);
 
         let new_thread = Arc::new(Thread {
@@ -134,8 +136,10 @@ impl ThreadBuilder {
             sig_queues,
             sig_mask,
             sig_tmp_mask,
+            sig_saved_mask,
             sig_stack,
             profiler,
+            cpu_time,
             host_eventfd,
         });
 
@@ -1,6 +1,7 @@
 use std::fmt;
 use std::ptr::NonNull;
 
+use super::rusage::ThreadCpuTime;
 use super::task::Task;
 use super::{
     FileTableRef, ForcedExitStatus, FsViewRef, ProcessRef, ProcessVM, ProcessVMRef,
@@ -8,6 +9,7 @@ use super::{
 };
 use crate::events::HostEventFd;
 use crate::fs::{EventCreationFlags, EventFile};
+use crate::misc::resource_t;
 use crate::net::THREAD_NOTIFIERS;
 use crate::prelude::*;
 use crate::signal::{SigQueues, SigSet, SigStack};
@@ -42,9 +44,12 @@ pub struct Thread {
     sig_queues: RwLock<SigQueues>,
     sig_mask: RwLock<SigSet>,
     sig_tmp_mask: RwLock<SigSet>,
+    sig_saved_mask: RwLock<Option<SigSet>>,
     sig_stack: SgxMutex<Option<SigStack>>,
     // System call timing
     profiler: SgxMutex<Option<ThreadProfiler>>,
+    // CPU time accounting
+    cpu_time: SgxMutex<ThreadCpuTime>,
     // Misc
     host_eventfd: Arc<HostEventFd>,
 }
@@ -103,6 +108,15 @@ impl Thread {
         &self.sig_tmp_mask
     }
 
+    /// Get the signal mask that is saved while a syscall, e.g., ppoll, waits
+    /// with a temporary signal mask.
+    ///
+    /// The saved mask is restored when the signals are delivered at the end
+    /// of the syscall.
+    pub fn sig_saved_mask(&self) -> &RwLock<Option<SigSet>> {
+        &self.sig_saved_mask
+    }
+
     /// Get the alternate signal stack.
     pub fn sig_stack(&self) -> &SgxMutex<Option<SigStack>> {
         &self.sig_stack
@@ -113,14 +127,37 @@ impl Thread {
         &self.profiler
     }
 
+    /// Get the CPU time accounting of the thread.
+    pub fn cpu_time(&self) -> &SgxMutex<ThreadCpuTime> {
+        &self.cpu_time
+    }
+
     /// Get a file from the file table.
     pub fn file(&self, fd: FileDesc) -> Result<FileRef> {
         self.files().lock().unwrap().get(fd)
     }
 
     /// Add a file to the file table.
-    pub fn add_file(&self, new_file: FileRef, close_on_spawn: bool) -> FileDesc {
-        self.files().lock().unwrap().put(new_file, close_on_spawn)
+    ///
+    /// The fd of the new file must be less than the limit of RLIMIT_NOFILE.
+    pub fn add_file(&self, new_file: FileRef, close_on_spawn: bool) -> Result<FileDesc> {
+        let max_fd = self.max_fds();
+        let mut files = self.files().lock().unwrap();
+        if files.lowest_free_fd() >= max_fd {
+            return_errno!(EMFILE, "too many open files");
+        }
+        Ok(files.put(new_file, close_on_spawn))
+    }
+
+    /// Get the soft limit of RLIMIT_NOFILE, i.e., the max fd plus one.
+    pub fn max_fds(&self) -> FileDesc {
+        let max_fds = self
+            .rlimits()
+            .lock()
+            .unwrap()
+            .get(resource_t::RLIMIT_NOFILE)
+            .get_cur();
+        max_fds.min(FileDesc::max_value() as u64) as FileDesc
     }
 
     pub fn fs(&self) -> &FsViewRef {
@@ -154,6 +191,7 @@ impl Thread {
     pub(super) fn start(&self, host_tid: pid_t) {
         self.sched().lock().unwrap().attach(host_tid);
         self.inner().start();
+        self.cpu_time().lock().unwrap().start();
 
         let eventfd = EventFile::new(
             0,
@@ -204,6 +242,15 @@ impl Thread {
             .expect("the thread must belong to the process");
         threads.swap_remove(thread_i);
 
+        // Account the resource usage of the thread to its owner process
+        let cpu_time = self.cpu_time().lock().unwrap().update();
+        let mut usage = self.process.usage();
+        usage.add_exited_thread(cpu_time);
+        if threads.len() == 0 {
+            usage.set_max_rss(self.vm().get_max_rss());
+        }
+        drop(usage);
+
         self.inner().exit(term_status);
 
         threads.len()
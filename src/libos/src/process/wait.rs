@@ -112,6 +112,34 @@ where
         set_event(del_waiter.thread);
         1
     }
+
+    pub fn del_and_wake_all_waiters<F>(&mut self, cond: F) -> usize
+    where
+        F: Fn(&D) -> Option<R>,
+    {
+        let mut num_woken = 0;
+        let mut waiter_i = 0;
+        while waiter_i < self.waiters.len() {
+            let is_woken = {
+                let mut waiter_inner = self.waiters[waiter_i].inner.lock().unwrap();
+                if let Some(waiter_result) = cond(&waiter_inner.data) {
+                    waiter_inner.is_woken = true;
+                    waiter_inner.result = Some(waiter_result);
+                    true
+                } else {
+                    false
+                }
+            };
+            if is_woken {
+                let del_waiter = self.waiters.swap_remove(waiter_i);
+                set_event(del_waiter.thread);
+                num_woken += 1;
+            } else {
+                waiter_i += 1;
+            }
+        }
+        num_woken
+    }
 }
 
 fn wait_event(thread: *const c_void) {
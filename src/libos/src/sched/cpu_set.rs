@@ -57,6 +57,15 @@ impl CpuSet {
         self.bits.count_ones()
     }
 
+    /// Returns the indexes of CPUs in set, in ascending order.
+    pub fn cpus(&self) -> Vec<usize> {
+        self.iter()
+            .enumerate()
+            .filter(|&(_, &b)| b)
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
     /// Returns the first index of CPUs in set.
     pub fn first_cpu_idx(&self) -> Option<usize> {
         self.iter().position(|&b| b == true)
@@ -145,7 +154,8 @@ lazy_static! {
     /// While `AVAIL_CPUSET` is likely to be equal to `CpuSet::new_full()`, this is not always the
     /// case.  For example, when the enclave is running on a container or a virtual machine on a public
     /// cloud platform, the container or vm is usually given access to a subset of the CPU cores on
-    /// the host machine.
+    /// the host machine. Furthermore, the CPU cores available to LibOS can be limited by
+    /// `resource_limits.cpu_set` in Occlum.json.
     ///
     /// Property: `AVAIL_CPUSET.empty() == false`.
     pub static ref AVAIL_CPUSET: CpuSet = {
@@ -162,6 +172,14 @@ lazy_static! {
         assert!(sgx_status == sgx_status_t::SGX_SUCCESS);
         CpuSet::clear_unused(&mut cpuset.bits);
         assert!(!cpuset.empty());
+        if let Some(cpus) = crate::config::LIBOS_CONFIG.resource_limits.cpu_set.as_ref() {
+            let mut configured_cpuset = CpuSet::new_empty();
+            for &cpu in cpus.iter().filter(|&&cpu| cpu < CpuSet::ncores()) {
+                configured_cpuset.bits.set(cpu, true);
+            }
+            cpuset.bits = cpuset.bits.clone() & configured_cpuset.bits;
+            assert!(!cpuset.empty(), "no CPU core in cpu_set of Occlum.json is available");
+        }
         cpuset
     };
 }
@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use super::do_sched_affinity::get_thread_by_tid;
+use super::priority::{SchedPolicy, SchedPriority};
+use crate::prelude::*;
+use crate::process::{table, ThreadRef};
+
+/// The time slice of SCHED_RR, which is the default one of Linux.
+const SCHED_RR_TIMESLICE: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum PrioWhich {
+    PRIO_PROCESS = 0,
+    PRIO_PGRP = 1,
+    PRIO_USER = 2,
+}
+
+impl PrioWhich {
+    pub fn from_i32(which: i32) -> Result<Self> {
+        Ok(match which {
+            0 => PrioWhich::PRIO_PROCESS,
+            1 => PrioWhich::PRIO_PGRP,
+            2 => PrioWhich::PRIO_USER,
+            _ => return_errno!(EINVAL, "invalid which for getpriority/setpriority"),
+        })
+    }
+}
+
+/// Get the lowest nice value (i.e., the highest priority) of the threads
+/// specified by which and who.
+pub fn do_getpriority(which: PrioWhich, who: i32) -> Result<i32> {
+    debug!("getpriority: which: {:?}, who: {}", which, who);
+    let threads = get_threads_by_which(which, who)?;
+    let nice = threads
+        .iter()
+        .map(|thread| thread.sched().lock().unwrap().priority().nice())
+        .min()
+        .unwrap();
+    Ok(nice)
+}
+
+/// Set the nice value of the threads specified by which and who.
+pub fn do_setpriority(which: PrioWhich, who: i32, nice: i32) -> Result<()> {
+    debug!(
+        "setpriority: which: {:?}, who: {}, nice: {}",
+        which, who, nice
+    );
+    let threads = get_threads_by_which(which, who)?;
+    for thread in threads {
+        let mut sched = thread.sched().lock().unwrap();
+        let mut priority = *sched.priority();
+        priority.set_nice(nice);
+        sched.set_priority(priority);
+    }
+    Ok(())
+}
+
+pub fn do_sched_getscheduler(tid: pid_t) -> Result<SchedPriority> {
+    debug!("sched_getscheduler: tid: {}", tid);
+    let thread = get_thread_by_tid(tid)?;
+    let priority = *thread.sched().lock().unwrap().priority();
+    Ok(priority)
+}
+
+pub fn do_sched_setscheduler(
+    tid: pid_t,
+    policy: SchedPolicy,
+    rt_priority: i32,
+    reset_on_fork: bool,
+) -> Result<()> {
+    debug!(
+        "sched_setscheduler: tid: {}, policy: {:?}, rt_priority: {}, reset_on_fork: {}",
+        tid, policy, rt_priority, reset_on_fork
+    );
+    let thread = get_thread_by_tid(tid)?;
+    let mut sched = thread.sched().lock().unwrap();
+    let mut priority = *sched.priority();
+    priority.set_policy(policy, rt_priority, reset_on_fork)?;
+    sched.set_priority(priority);
+    Ok(())
+}
+
+/// Set the static priority of a thread without changing its policy.
+pub fn do_sched_setparam(tid: pid_t, rt_priority: i32) -> Result<()> {
+    debug!("sched_setparam: tid: {}, rt_priority: {}", tid, rt_priority);
+    let thread = get_thread_by_tid(tid)?;
+    let mut sched = thread.sched().lock().unwrap();
+    let mut priority = *sched.priority();
+    priority.set_policy(priority.policy(), rt_priority, priority.reset_on_fork())?;
+    sched.set_priority(priority);
+    Ok(())
+}
+
+pub fn do_sched_rr_get_interval(tid: pid_t) -> Result<Duration> {
+    let priority = do_sched_getscheduler(tid)?;
+    let interval = match priority.policy() {
+        SchedPolicy::SCHED_RR => SCHED_RR_TIMESLICE,
+        // A SCHED_FIFO thread runs until it blocks or yields, and the time
+        // slice of the other policies is decided by the host OS
+        _ => Duration::from_secs(0),
+    };
+    Ok(interval)
+}
+
+fn get_threads_by_which(which: PrioWhich, who: i32) -> Result<Vec<ThreadRef>> {
+    let threads: Vec<ThreadRef> = match which {
+        PrioWhich::PRIO_PROCESS => {
+            return Ok(vec![get_thread_by_tid(who)?]);
+        }
+        PrioWhich::PRIO_PGRP => {
+            let pgid = if who == 0 {
+                current!().process().pgid()
+            } else {
+                who
+            };
+            table::get_all_processes()
+                .iter()
+                .filter(|process| process.pgid() == pgid)
+                .flat_map(|process| process.threads())
+                .collect()
+        }
+        // All LibOS processes are owned by root
+        PrioWhich::PRIO_USER if who == 0 => table::get_all_threads(),
+        PrioWhich::PRIO_USER => Vec::new(),
+    };
+    if threads.is_empty() {
+        return_errno!(ESRCH, "no threads are found");
+    }
+    Ok(threads)
+}
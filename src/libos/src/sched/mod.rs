@@ -2,10 +2,13 @@
 mod cpu_set;
 mod do_getcpu;
 mod do_sched_affinity;
+mod do_sched_priority;
 mod do_sched_yield;
+mod priority;
 mod sched_agent;
 mod syscalls;
 
-pub use cpu_set::NCORES;
+pub use cpu_set::{AVAIL_CPUSET, NCORES};
+pub use priority::sched_param_t;
 pub use sched_agent::SchedAgent;
 pub use syscalls::*;
@@ -0,0 +1,135 @@
+use crate::prelude::*;
+
+/// The scheduling policies of threads.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum SchedPolicy {
+    SCHED_NORMAL = 0,
+    SCHED_FIFO = 1,
+    SCHED_RR = 2,
+    SCHED_BATCH = 3,
+    SCHED_IDLE = 5,
+}
+
+impl SchedPolicy {
+    pub fn from_i32(policy: i32) -> Result<Self> {
+        Ok(match policy {
+            0 => SchedPolicy::SCHED_NORMAL,
+            1 => SchedPolicy::SCHED_FIFO,
+            2 => SchedPolicy::SCHED_RR,
+            3 => SchedPolicy::SCHED_BATCH,
+            5 => SchedPolicy::SCHED_IDLE,
+            _ => return_errno!(EINVAL, "invalid scheduling policy"),
+        })
+    }
+
+    pub fn is_realtime(&self) -> bool {
+        *self == SchedPolicy::SCHED_FIFO || *self == SchedPolicy::SCHED_RR
+    }
+
+    /// The min and max of the static priority of the policy.
+    pub fn priority_range(&self) -> (i32, i32) {
+        if self.is_realtime() {
+            (1, 99)
+        } else {
+            (0, 0)
+        }
+    }
+}
+
+/// The flag of sched_setscheduler that makes the children of a thread not
+/// inherit its privileged scheduler settings.
+pub const SCHED_RESET_ON_FORK: i32 = 0x40000000;
+
+pub const MIN_NICE: i32 = -20;
+pub const MAX_NICE: i32 = 19;
+
+/// The scheduling policy, the static priority and the nice value of a thread.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SchedPriority {
+    policy: SchedPolicy,
+    // Only meaningful for the real-time policies
+    rt_priority: i32,
+    // Only meaningful for the normal policies
+    nice: i32,
+    reset_on_fork: bool,
+}
+
+impl SchedPriority {
+    pub fn new() -> Self {
+        Self {
+            policy: SchedPolicy::SCHED_NORMAL,
+            rt_priority: 0,
+            nice: 0,
+            reset_on_fork: false,
+        }
+    }
+
+    pub fn policy(&self) -> SchedPolicy {
+        self.policy
+    }
+
+    pub fn rt_priority(&self) -> i32 {
+        self.rt_priority
+    }
+
+    pub fn nice(&self) -> i32 {
+        self.nice
+    }
+
+    pub fn reset_on_fork(&self) -> bool {
+        self.reset_on_fork
+    }
+
+    /// Set the nice value, which is silently clamped to [-20, 19] as Linux does.
+    pub fn set_nice(&mut self, nice: i32) {
+        self.nice = nice.max(MIN_NICE).min(MAX_NICE);
+    }
+
+    pub fn set_policy(
+        &mut self,
+        policy: SchedPolicy,
+        rt_priority: i32,
+        reset_on_fork: bool,
+    ) -> Result<()> {
+        let (min_priority, max_priority) = policy.priority_range();
+        if rt_priority < min_priority || rt_priority > max_priority {
+            return_errno!(EINVAL, "invalid priority for the scheduling policy");
+        }
+        self.policy = policy;
+        self.rt_priority = rt_priority;
+        self.reset_on_fork = reset_on_fork;
+        Ok(())
+    }
+
+    /// Get the scheduler settings inherited by a child.
+    ///
+    /// If SCHED_RESET_ON_FORK is set, the real-time policies are reset to
+    /// SCHED_NORMAL and a negative nice value is reset to 0.
+    pub fn for_child(&self) -> Self {
+        if !self.reset_on_fork {
+            return *self;
+        }
+        let mut child = *self;
+        if child.policy.is_realtime() {
+            child.policy = SchedPolicy::SCHED_NORMAL;
+            child.rt_priority = 0;
+        }
+        child.nice = child.nice.max(0);
+        child.reset_on_fork = false;
+        child
+    }
+}
+
+impl Default for SchedPriority {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct sched_param_t {
+    pub sched_priority: i32,
+}
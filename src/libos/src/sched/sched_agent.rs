@@ -2,8 +2,9 @@
 //!
 //! # Scheduler Settings
 //!
-//! Currently, the only scheduler setting that SchedAgent can access and update
-//! is the CPU affinity of a thread. Other settings will be added in the future.
+//! The scheduler settings that SchedAgent can access and update are the CPU
+//! affinity and the priority (i.e., the scheduling policy, the static priority
+//! and the nice value) of a thread.
 //!
 //! # The Two Modes: Attached vs Detached
 //!
@@ -20,8 +21,23 @@
 //! be applied to the host OS thread. Afterwards, all setting updates will be applied
 //! immediately to the host OS thread---until SchedAgent is detached from the
 //! host OS thread.
+//!
+//! # Pinning Host OS Threads
+//!
+//! The host OS threads are pinned to the CPU cores in the affinity of the
+//! threads, which are limited by `resource_limits.cpu_set` in Occlum.json. If
+//! `resource_limits.pin_cpu_affinity` is false, the affinity is only recorded
+//! and reported, while the host OS threads are not pinned.
+//!
+//! # Prioritizing Host OS Threads
+//!
+//! The priority of a thread is translated to that of its host OS thread. The
+//! host OS may refuse to raise the priority, e.g., to lower the nice value or to
+//! use the real-time policies without CAP_SYS_NICE. In this case, the priority
+//! is still recorded and reported, but the host OS thread is not prioritized.
 
 use super::cpu_set::{CpuSet, AVAIL_CPUSET};
+use super::priority::SchedPriority;
 use crate::prelude::*;
 use crate::util::dirty::Dirty;
 
@@ -48,30 +64,42 @@ impl Clone for SchedAgent {
     ///
     /// This implementation carefully handles the two points above.
     fn clone(&self) -> Self {
-        let mut affinity = Dirty::new(match self.inner() {
-            Inner::Detached { affinity } => affinity.as_ref().clone(),
-            Inner::Attached { affinity, .. } => affinity.clone(),
-        });
-        if affinity.as_ref().as_slice() != AVAIL_CPUSET.as_slice() {
+        let mut affinity = Dirty::new(self.affinity().clone());
+        if affinity.as_ref().as_slice() != AVAIL_CPUSET.as_slice() || is_cpu_set_configured() {
             affinity.set_dirty();
         }
+        let mut priority = Dirty::new(self.priority().for_child());
+        if *priority.as_ref() != SchedPriority::default() {
+            priority.set_dirty();
+        }
         Self {
-            inner: Some(Inner::Detached { affinity }),
+            inner: Some(Inner::Detached { affinity, priority }),
         }
     }
 }
 
 #[derive(Debug, Clone)]
 enum Inner {
-    Detached { affinity: Dirty<CpuSet> },
-    Attached { host_tid: pid_t, affinity: CpuSet },
+    Detached {
+        affinity: Dirty<CpuSet>,
+        priority: Dirty<SchedPriority>,
+    },
+    Attached {
+        host_tid: pid_t,
+        affinity: CpuSet,
+        priority: SchedPriority,
+    },
 }
 
 impl SchedAgent {
     pub fn new() -> Self {
         let inner = Some({
-            let affinity = Dirty::new(AVAIL_CPUSET.clone());
-            Inner::Detached { affinity }
+            let mut affinity = Dirty::new(AVAIL_CPUSET.clone());
+            if is_cpu_set_configured() {
+                affinity.set_dirty();
+            }
+            let priority = Dirty::new(SchedPriority::default());
+            Inner::Detached { affinity, priority }
         });
         Self { inner }
     }
@@ -85,7 +113,7 @@ impl SchedAgent {
 
     pub fn affinity(&self) -> &CpuSet {
         match self.inner() {
-            Inner::Detached { affinity } => affinity.as_ref(),
+            Inner::Detached { affinity, .. } => affinity.as_ref(),
             Inner::Attached { affinity, .. } => affinity,
         }
     }
@@ -101,10 +129,12 @@ impl SchedAgent {
             );
         }
         match self.inner_mut() {
-            Inner::Detached { affinity } => {
+            Inner::Detached { affinity, .. } => {
                 *affinity.as_mut() = new_affinity;
             }
-            Inner::Attached { host_tid, affinity } => {
+            Inner::Attached {
+                host_tid, affinity, ..
+            } => {
                 update_affinity(*host_tid, &new_affinity);
                 *affinity = new_affinity;
             }
@@ -112,16 +142,47 @@ impl SchedAgent {
         Ok(())
     }
 
+    pub fn priority(&self) -> &SchedPriority {
+        match self.inner() {
+            Inner::Detached { priority, .. } => priority.as_ref(),
+            Inner::Attached { priority, .. } => priority,
+        }
+    }
+
+    pub fn set_priority(&mut self, new_priority: SchedPriority) {
+        match self.inner_mut() {
+            Inner::Detached { priority, .. } => {
+                *priority.as_mut() = new_priority;
+            }
+            Inner::Attached {
+                host_tid, priority, ..
+            } => {
+                update_priority(*host_tid, &new_priority);
+                *priority = new_priority;
+            }
+        };
+    }
+
     pub fn attach(&mut self, host_tid: pid_t) {
         self.update_inner(|inner| match inner {
-            Inner::Detached { affinity } => {
+            Inner::Detached { affinity, priority } => {
                 let affinity = {
                     if affinity.dirty() {
                         update_affinity(host_tid, affinity.as_ref())
                     }
                     affinity.unwrap()
                 };
-                Inner::Attached { host_tid, affinity }
+                let priority = {
+                    if priority.dirty() {
+                        update_priority(host_tid, priority.as_ref())
+                    }
+                    priority.unwrap()
+                };
+                Inner::Attached {
+                    host_tid,
+                    affinity,
+                    priority,
+                }
             }
             Inner::Attached { .. } => panic!("cannot attach when the agent is already attached"),
         });
@@ -130,9 +191,12 @@ impl SchedAgent {
     pub fn detach(&mut self) {
         self.update_inner(|inner| match inner {
             Inner::Detached { .. } => panic!("cannot detach when the agent is already detached"),
-            Inner::Attached { affinity, .. } => {
+            Inner::Attached {
+                affinity, priority, ..
+            } => {
                 let affinity = Dirty::new(affinity);
-                Inner::Detached { affinity }
+                let priority = Dirty::new(priority);
+                Inner::Detached { affinity, priority }
             }
         });
     }
@@ -168,7 +232,19 @@ impl Default for SchedAgent {
     }
 }
 
+// The host OS threads must be pinned to the CPU cores configured in Occlum.json,
+// even if the affinity of the threads is not changed
+fn is_cpu_set_configured() -> bool {
+    crate::config::LIBOS_CONFIG
+        .resource_limits
+        .cpu_set
+        .is_some()
+}
+
 fn update_affinity(host_tid: pid_t, affinity: &CpuSet) {
+    if !crate::config::LIBOS_CONFIG.resource_limits.pin_cpu_affinity {
+        return;
+    }
     let mask = affinity.as_slice();
     let mut retval = 0;
     let sgx_status = unsafe {
@@ -179,6 +255,28 @@ fn update_affinity(host_tid: pid_t, affinity: &CpuSet) {
     assert!(retval == 0);
 }
 
+fn update_priority(host_tid: pid_t, priority: &SchedPriority) {
+    let mut retval = 0;
+    let sgx_status = unsafe {
+        occlum_ocall_sched_set_priority(
+            &mut retval,
+            host_tid as i32,
+            priority.policy() as i32,
+            priority.rt_priority(),
+            priority.nice(),
+        )
+    };
+    assert!(sgx_status == sgx_status_t::SGX_SUCCESS);
+    // The host OS may not permit raising the priority of the host thread
+    if retval < 0 {
+        warn!(
+            "failed to set the priority of host thread {}: errno = {}",
+            host_tid,
+            unsafe { libc::errno() }
+        );
+    }
+}
+
 extern "C" {
     fn occlum_ocall_sched_setaffinity(
         ret: *mut i32,
@@ -186,4 +284,12 @@ extern "C" {
         cpusetsize: size_t,
         mask: *const c_uchar,
     ) -> sgx_status_t;
+
+    fn occlum_ocall_sched_set_priority(
+        ret: *mut i32,
+        host_tid: i32,
+        policy: i32,
+        rt_priority: i32,
+        nice: i32,
+    ) -> sgx_status_t;
 }
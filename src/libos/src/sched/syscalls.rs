@@ -1,5 +1,8 @@
 use super::cpu_set::{CpuSet, AVAIL_CPUSET};
+use super::do_sched_priority::PrioWhich;
+use super::priority::{sched_param_t, SchedPolicy, SCHED_RESET_ON_FORK};
 use crate::prelude::*;
+use crate::time::timespec_t;
 use crate::util::mem_util::from_user::*;
 
 pub fn do_sched_yield() -> Result<isize> {
@@ -83,3 +86,103 @@ pub fn do_getcpu(cpu_ptr: *mut u32, node_ptr: *mut u32) -> Result<isize> {
     }
     Ok(0)
 }
+
+pub fn do_getpriority(which: i32, who: i32) -> Result<isize> {
+    let which = PrioWhich::from_i32(which)?;
+    let nice = super::do_sched_priority::do_getpriority(which, who)?;
+    // The raw syscall returns 20 - nice, which is always positive, to avoid
+    // being confused with errors. The libc converts it back to the nice value.
+    Ok((20 - nice) as isize)
+}
+
+pub fn do_setpriority(which: i32, who: i32, nice: i32) -> Result<isize> {
+    let which = PrioWhich::from_i32(which)?;
+    super::do_sched_priority::do_setpriority(which, who, nice)?;
+    Ok(0)
+}
+
+pub fn do_sched_getscheduler(pid: pid_t) -> Result<isize> {
+    check_pid(pid)?;
+    let priority = super::do_sched_priority::do_sched_getscheduler(pid)?;
+    let mut policy = priority.policy() as i32;
+    if priority.reset_on_fork() {
+        policy |= SCHED_RESET_ON_FORK;
+    }
+    Ok(policy as isize)
+}
+
+pub fn do_sched_setscheduler(
+    pid: pid_t,
+    policy: i32,
+    param_ptr: *const sched_param_t,
+) -> Result<isize> {
+    check_pid(pid)?;
+    let reset_on_fork = policy & SCHED_RESET_ON_FORK != 0;
+    let policy = SchedPolicy::from_i32(policy & !SCHED_RESET_ON_FORK)?;
+    let param = read_sched_param(param_ptr)?;
+    super::do_sched_priority::do_sched_setscheduler(
+        pid,
+        policy,
+        param.sched_priority,
+        reset_on_fork,
+    )?;
+    Ok(0)
+}
+
+pub fn do_sched_getparam(pid: pid_t, param_ptr: *mut sched_param_t) -> Result<isize> {
+    check_pid(pid)?;
+    if param_ptr.is_null() {
+        return_errno!(EINVAL, "param ptr must NOT be null");
+    }
+    check_mut_ptr(param_ptr)?;
+    let priority = super::do_sched_priority::do_sched_getscheduler(pid)?;
+    let param = sched_param_t {
+        sched_priority: priority.rt_priority(),
+    };
+    unsafe {
+        param_ptr.write(param);
+    }
+    Ok(0)
+}
+
+pub fn do_sched_setparam(pid: pid_t, param_ptr: *const sched_param_t) -> Result<isize> {
+    check_pid(pid)?;
+    let param = read_sched_param(param_ptr)?;
+    super::do_sched_priority::do_sched_setparam(pid, param.sched_priority)?;
+    Ok(0)
+}
+
+pub fn do_sched_get_priority_max(policy: i32) -> Result<isize> {
+    let (_, max_priority) = SchedPolicy::from_i32(policy)?.priority_range();
+    Ok(max_priority as isize)
+}
+
+pub fn do_sched_get_priority_min(policy: i32) -> Result<isize> {
+    let (min_priority, _) = SchedPolicy::from_i32(policy)?.priority_range();
+    Ok(min_priority as isize)
+}
+
+pub fn do_sched_rr_get_interval(pid: pid_t, interval_ptr: *mut timespec_t) -> Result<isize> {
+    check_pid(pid)?;
+    check_mut_ptr(interval_ptr)?;
+    let interval = super::do_sched_priority::do_sched_rr_get_interval(pid)?;
+    unsafe {
+        interval_ptr.write(interval.into());
+    }
+    Ok(0)
+}
+
+fn check_pid(pid: pid_t) -> Result<()> {
+    if pid < 0 {
+        return_errno!(EINVAL, "pid must NOT be negative");
+    }
+    Ok(())
+}
+
+fn read_sched_param(param_ptr: *const sched_param_t) -> Result<sched_param_t> {
+    if param_ptr.is_null() {
+        return_errno!(EINVAL, "param ptr must NOT be null");
+    }
+    check_ptr(param_ptr)?;
+    Ok(unsafe { *param_ptr })
+}
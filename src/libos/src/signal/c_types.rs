@@ -2,6 +2,7 @@
 
 use std::fmt;
 
+use super::constants::*;
 use super::SigNum;
 use crate::prelude::*;
 use crate::syscall::CpuContext;
@@ -275,9 +276,79 @@ pub const BUS_OBJERR: i32 = 3;
 pub const BUS_MCEERR_AR: i32 = 4;
 pub const BUS_MCEERR_AO: i32 = 5;
 
+pub const TRAP_BRKPT: i32 = 1;
+pub const TRAP_TRACE: i32 = 2;
+
 pub const CLD_EXITED: i32 = 1;
 pub const CLD_KILLED: i32 = 2;
 pub const CLD_DUMPED: i32 = 3;
 pub const CLD_TRAPPED: i32 = 4;
 pub const CLD_STOPPED: i32 = 5;
 pub const CLD_CONTINUED: i32 = 6;
+
+/// The structure read from a signalfd.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct signalfd_siginfo_t {
+    pub ssi_signo: u32,
+    pub ssi_errno: i32,
+    pub ssi_code: i32,
+    pub ssi_pid: u32,
+    pub ssi_uid: u32,
+    pub ssi_fd: i32,
+    pub ssi_tid: u32,
+    pub ssi_band: u32,
+    pub ssi_overrun: u32,
+    pub ssi_trapno: u32,
+    pub ssi_status: i32,
+    pub ssi_int: i32,
+    pub ssi_ptr: u64,
+    pub ssi_utime: u64,
+    pub ssi_stime: u64,
+    pub ssi_addr: u64,
+    pub ssi_addr_lsb: u16,
+    _pad2: u16,
+    pub ssi_syscall: i32,
+    pub ssi_call_addr: u64,
+    pub ssi_arch: u32,
+    _pad: [u8; 28],
+}
+
+impl From<&siginfo_t> for signalfd_siginfo_t {
+    fn from(info: &siginfo_t) -> Self {
+        let mut ssi: Self = unsafe { std::mem::zeroed() };
+        ssi.ssi_signo = info.si_signo as u32;
+        ssi.ssi_errno = info.si_errno;
+        ssi.ssi_code = info.si_code;
+
+        let signum = unsafe { SigNum::from_u8_unchecked(info.si_signo as u8) };
+        match signum {
+            SIGCHLD => {
+                ssi.ssi_pid = info.si_pid();
+                ssi.ssi_uid = info.si_uid();
+                ssi.ssi_status = info.si_status();
+                ssi.ssi_utime = info.si_utime() as u64;
+                ssi.ssi_stime = info.si_stime() as u64;
+            }
+            SIGSEGV | SIGBUS | SIGILL | SIGFPE | SIGTRAP => {
+                ssi.ssi_addr = info.si_addr() as u64;
+                ssi.ssi_addr_lsb = info.si_addr_lsb() as u16;
+            }
+            _ if info.si_code == SI_TIMER => {
+                ssi.ssi_tid = info.si_timerid() as u32;
+                ssi.ssi_overrun = info.si_overrune() as u32;
+                let value = info.si_value();
+                ssi.ssi_int = unsafe { value.sigval_int };
+                ssi.ssi_ptr = unsafe { value.sigval_ptr } as u64;
+            }
+            _ => {
+                ssi.ssi_pid = info.si_pid();
+                ssi.ssi_uid = info.si_uid();
+                let value = info.si_value();
+                ssi.ssi_int = unsafe { value.sigval_int };
+                ssi.ssi_ptr = unsafe { value.sigval_ptr } as u64;
+            }
+        }
+        ssi
+    }
+}
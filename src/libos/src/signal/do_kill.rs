@@ -1,27 +1,92 @@
+use super::c_types::{siginfo_t, SI_TKILL};
 use super::constants::*;
+use super::do_sigqueue::enqueue_signal;
 use super::signals::{KernelSignal, UserSignal, UserSignalKind};
-use super::{SigNum, Signal};
+use super::{SigNum, SigSet, Signal};
 use crate::prelude::*;
-use crate::process::{table, ProcessFilter, ProcessRef, ProcessStatus, ThreadRef, ThreadStatus};
+use crate::process::{
+    continue_process, table, ProcessFilter, ProcessRef, ProcessStatus, ThreadRef, ThreadStatus,
+};
 
-pub fn do_kill(filter: ProcessFilter, signum: SigNum) -> Result<()> {
+/// Send a signal to a process, a process group, or all processes.
+///
+/// Same as Linux, a signal sent to all processes, i.e., `kill(-1, sig)`, is
+/// not sent to the caller itself, nor to the init processes, which are the
+/// processes spawned directly by the host and whose parent is the idle process.
+/// If signum is None, only the existence and the permission are checked.
+pub fn do_kill(filter: ProcessFilter, signum: Option<SigNum>) -> Result<()> {
     debug!("do_kill: filter: {:?}, signum: {:?}", &filter, &signum);
 
-    let pid = current!().process().pid();
+    let current = current!();
+    let pid = current.process().pid();
     let uid = 0;
-    let processes = get_processes(&filter)?;
+    let processes = match filter {
+        ProcessFilter::WithAnyPid => {
+            let processes: Vec<ProcessRef> = get_processes(&filter)?
+                .into_iter()
+                .filter(|process| process.pid() != pid && !is_init_process(process))
+                .collect();
+            if processes.len() == 0 {
+                return_errno!(ESRCH, "no process to signal");
+            }
+            processes
+        }
+        _ => get_processes(&filter)?,
+    };
+
+    let mut num_permitted = 0;
     for process in processes {
-        if process.status() == ProcessStatus::Zombie {
+        if check_permission(&current, &process, signum).is_err() {
             continue;
         }
+        num_permitted += 1;
 
+        if process.status() == ProcessStatus::Zombie {
+            continue;
+        }
+        let signum = match signum {
+            Some(signum) => signum,
+            None => continue,
+        };
+        prepare_signal(&process, signum);
         let signal = Box::new(UserSignal::new(signum, UserSignalKind::Kill, pid, uid));
         let mut sig_queues = process.sig_queues().write().unwrap();
         sig_queues.enqueue(signal);
     }
+    // Same as Linux, the call succeeds if at least one process is signaled
+    if num_permitted == 0 {
+        return_errno!(EPERM, "no permission to signal the processes");
+    }
     Ok(())
 }
 
+/// Check whether the sender has the permission to send the signal to the
+/// receiver.
+///
+/// Same as Linux, the real or effective user ID of the sender must match the
+/// real or saved user ID of the receiver, unless the sender is privileged.
+/// SIGCONT can be sent to any process in the same session. Since all LibOS
+/// processes run as root for now, the check always passes.
+fn check_permission(
+    sender: &ThreadRef,
+    receiver: &ProcessRef,
+    signum: Option<SigNum>,
+) -> Result<()> {
+    let sender_uid = 0;
+    let receiver_uid = 0;
+    let is_privileged = sender_uid == 0;
+    let is_same_user = sender_uid == receiver_uid;
+    let is_same_session = sender.process().sid() == receiver.sid();
+    if !is_privileged && !is_same_user && !(signum == Some(SIGCONT) && is_same_session) {
+        return_errno!(EPERM, "no permission to signal the process");
+    }
+    Ok(())
+}
+
+fn is_init_process(process: &ProcessRef) -> bool {
+    process.pid() != 0 && process.parent().pid() == 0
+}
+
 /// Send a signal from the outside the enclave.
 ///
 /// Such a call must be performed very carefully. The obvious reason
@@ -62,7 +127,7 @@ fn get_processes(filter: &ProcessFilter) -> Result<Vec<ProcessRef>> {
                 .filter(|proc_ref| proc_ref.pgid() == *pgid)
                 .collect();
             if processes.len() == 0 {
-                return_errno!(EINVAL, "invalid pgid");
+                return_errno!(ESRCH, "no such process group");
             }
             processes
         }
@@ -87,6 +152,7 @@ pub fn do_tgkill(pid: Option<pid_t>, tid: pid_t, signum: SigNum) -> Result<()> {
         return Ok(());
     }
 
+    prepare_signal(thread.process(), signum);
     let signal = {
         let src_pid = current!().process().pid();
         let src_uid = 0;
@@ -101,3 +167,78 @@ pub fn do_tgkill(pid: Option<pid_t>, tid: pid_t, signum: SigNum) -> Result<()> {
     sig_queues.enqueue(signal);
     Ok(())
 }
+
+/// Send a signal to the process that a pidfd refers to.
+///
+/// If no siginfo is given, the signal is sent as if by kill. Otherwise, it is
+/// sent as if by rt_sigqueueinfo. If signum is None, only the existence of the
+/// process is checked.
+pub fn do_pidfd_send_signal(
+    process: &ProcessRef,
+    signum: Option<SigNum>,
+    info: Option<siginfo_t>,
+) -> Result<()> {
+    debug!(
+        "do_pidfd_send_signal: pid: {}, signum: {:?}",
+        process.pid(),
+        signum
+    );
+
+    if process.status() == ProcessStatus::Zombie {
+        return_errno!(ESRCH, "the process has exited");
+    }
+    let signum = match signum {
+        Some(signum) => signum,
+        None => return Ok(()),
+    };
+
+    let src_pid = current!().process().pid();
+    let src_uid = 0;
+    let kind = match info {
+        None => UserSignalKind::Kill,
+        Some(info) => {
+            // A process can not pretend to be the kernel or kill/tkill when
+            // sending signals to others
+            let code = info.si_code;
+            if (code >= 0 || code == SI_TKILL) && process.pid() != src_pid {
+                return_errno!(EPERM, "the si_code is not permitted");
+            }
+            UserSignalKind::Sigqueue {
+                code,
+                value: info.si_value(),
+            }
+        }
+    };
+    let signal = Box::new(UserSignal::new(signum, kind, src_pid, src_uid));
+    match process.leader_thread() {
+        Some(leader_thread) => enqueue_signal(Some(signal), process.sig_queues(), &leader_thread),
+        None => Ok(()),
+    }
+}
+
+/// Send a signal to all processes in the process group.
+pub fn kill_process_group(pgid: pid_t, signum: SigNum) -> Result<()> {
+    do_kill(ProcessFilter::WithPgid(pgid), Some(signum))
+}
+
+/// Handle the side effects of job-control signals on the process that they are
+/// sent to.
+///
+/// Same as Linux, SIGCONT continues the process and discards its pending stop
+/// signals, while a stop signal discards the pending SIGCONT. This is done when
+/// the signal is sent, regardless of whether it is blocked or ignored.
+pub(super) fn prepare_signal(process: &ProcessRef, signum: SigNum) {
+    let discarded = match signum {
+        SIGCONT => SigSet::new_empty() + SIGSTOP + SIGTSTP + SIGTTIN + SIGTTOU,
+        SIGSTOP | SIGTSTP | SIGTTIN | SIGTTOU => SigSet::new_empty() + SIGCONT,
+        _ => return,
+    };
+    process.sig_queues().write().unwrap().discard(&discarded);
+    for thread in process.threads() {
+        thread.sig_queues().write().unwrap().discard(&discarded);
+    }
+
+    if signum == SIGCONT {
+        continue_process(process);
+    }
+}
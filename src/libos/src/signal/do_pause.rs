@@ -0,0 +1,14 @@
+use crate::events::Waiter;
+use crate::prelude::*;
+
+/// Sleep until a signal that calls a user-registered handler or terminates the
+/// process is delivered.
+pub fn do_pause() -> Result<()> {
+    debug!("do_pause");
+
+    // No one wakes up the waiter. So it only returns when interrupted.
+    let waiter = Waiter::new();
+    loop {
+        waiter.wait(None)?;
+    }
+}
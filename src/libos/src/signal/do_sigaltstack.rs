@@ -1,3 +1,4 @@
+use super::c_types::stack_t;
 use super::sig_stack::{SigStack, SigStackFlags, MINSIGSTKSZ};
 use crate::prelude::*;
 use crate::syscall::CpuContext;
@@ -6,38 +7,52 @@ pub fn do_sigaltstack(new_ss: &Option<SigStack>, curr_user_ctxt: &CpuContext) ->
     debug!("do_sigaltstack: new_ss:{:?}", new_ss);
     let thread = current!();
     let mut sig_stack = thread.sig_stack().lock().unwrap();
-    let old_ss = if let Some(sig_stack) = *sig_stack {
-        // Deny to update the stack when we are on the stack
-        if new_ss.is_some() && sig_stack.contains(curr_user_ctxt.rsp as usize) {
-            return_errno!(EPERM, "thread is on signal stack currently");
-        }
-
-        // Retrieve the old signal stack information
-        let flags = if sig_stack.contains(curr_user_ctxt.rsp as usize) {
-            SigStackFlags::SS_ONSTACK
-        } else {
-            SigStackFlags::EMPTY
-        };
-        let mut ss: SigStack = Default::default();
-        ss.update(sig_stack.sp(), flags, sig_stack.size());
-        ss
-    } else {
-        Default::default()
-    };
+    let old_ss = get_sig_stack_info(&sig_stack, curr_user_ctxt.rsp as usize);
 
     // Set or update the signal stack if necessary
     if let Some(new_ss) = new_ss {
-        *sig_stack = if new_ss.flags() == SigStackFlags::SS_DISABLE {
+        // Deny to update the stack when we are on the stack
+        if old_ss.flags().contains(SigStackFlags::SS_ONSTACK) {
+            return_errno!(EPERM, "thread is on signal stack currently");
+        }
+        *sig_stack = if new_ss.flags().contains(SigStackFlags::SS_DISABLE) {
             None
         } else {
             if new_ss.size() < MINSIGSTKSZ {
                 return_errno!(ENOMEM, "the new alternate signal stack is too small");
             }
-            if (new_ss.flags() == SigStackFlags::SS_AUTODISARM) {
-                warn!("The SS_AUTODISARM flag is not supported yet");
-            }
-            Some(*new_ss)
+            // Only SS_AUTODISARM is kept, since SS_ONSTACK is decided by the
+            // stack pointer
+            let mut new_ss = *new_ss;
+            let flags = new_ss.flags() & SigStackFlags::SS_AUTODISARM;
+            new_ss.update(new_ss.sp(), flags, new_ss.size());
+            Some(new_ss)
         };
     }
     Ok(old_ss)
 }
+
+/// Get the information of the alternate signal stack, which is saved in the
+/// ucontext_t of a signal handler or returned by sigaltstack.
+pub(super) fn get_sig_stack_info(sig_stack: &Option<SigStack>, sp: usize) -> SigStack {
+    let mut info: SigStack = Default::default();
+    if let Some(stack) = sig_stack {
+        let mut flags = stack.flags() & SigStackFlags::SS_AUTODISARM;
+        if stack.is_on_stack(sp) {
+            flags |= SigStackFlags::SS_ONSTACK;
+        }
+        info.update(stack.sp(), flags, stack.size());
+    }
+    info
+}
+
+/// Restore the alternate signal stack saved in the ucontext_t of a signal
+/// handler, e.g., the one disarmed due to SS_AUTODISARM.
+///
+/// Same as Linux, the errors are ignored, e.g., when the signal handler
+/// returns to another signal handler that is running on the stack.
+pub(super) fn restore_sig_stack(uc_stack: &stack_t, curr_user_ctxt: &CpuContext) {
+    if let Ok(saved_ss) = SigStack::from_c(uc_stack) {
+        let _ = do_sigaltstack(&Some(saved_ss), curr_user_ctxt);
+    }
+}
@@ -0,0 +1,26 @@
+use super::{AsSignalFile, SigSet, SignalFile, SignalFileFlags};
+use crate::prelude::*;
+
+pub fn do_signalfd(fd: Option<FileDesc>, mask: SigSet, flags: SignalFileFlags) -> Result<FileDesc> {
+    debug!(
+        "do_signalfd: fd: {:?}, mask: {:?}, flags: {:?}",
+        fd, mask, flags
+    );
+
+    let thread = current!();
+    match fd {
+        // Create a new signal file
+        None => {
+            let signal_file: FileRef = Arc::new(SignalFile::new(mask, flags));
+            let close_on_spawn = flags.contains(SignalFileFlags::SFD_CLOEXEC);
+            thread.add_file(signal_file, close_on_spawn)
+        }
+        // Replace the mask of an existing signal file
+        Some(fd) => {
+            let file_ref = thread.file(fd)?;
+            let signal_file = file_ref.as_signal_file()?;
+            signal_file.set_mask(mask);
+            Ok(fd)
+        }
+    }
+}
@@ -1,5 +1,7 @@
-use super::SigSet;
+use super::constants::SIGKILL;
+use super::{SigAction, SigDefaultAction, SigSet};
 use crate::prelude::*;
+use crate::process::ThreadRef;
 
 pub fn do_sigpending() -> Result<SigSet> {
     debug!("do_sigpending");
@@ -11,3 +13,40 @@ pub fn do_sigpending() -> Result<SigSet> {
         & *thread.sig_mask().read().unwrap();
     Ok(pending)
 }
+
+/// Check whether a thread has pending signals that interrupt its blocking
+/// syscalls.
+///
+/// A signal interrupts the thread if it is not blocked by the thread and its
+/// delivery calls a user-registered handler, terminates the process or stops
+/// the process. A process that is forced to exit interrupts all its threads,
+/// too. While the process is stopped, only SIGKILL interrupts its threads.
+pub fn has_interrupting_signals(thread: &ThreadRef) -> bool {
+    let process = thread.process();
+    if process.is_forced_to_exit() {
+        return true;
+    }
+
+    let blocked = *thread.sig_mask().read().unwrap() | *thread.sig_tmp_mask().read().unwrap();
+    let pending = (thread.sig_queues().read().unwrap().pending()
+        | process.sig_queues().read().unwrap().pending())
+        & !blocked;
+    if pending.empty() {
+        return false;
+    }
+    if process.is_stopped() {
+        return pending.contains(SIGKILL);
+    }
+
+    let sig_dispositions = process.sig_dispositions().read().unwrap();
+    pending
+        .iter()
+        .any(|signum| match sig_dispositions.get(signum) {
+            SigAction::Ign => false,
+            SigAction::Dfl => match SigDefaultAction::from_signum(signum) {
+                SigDefaultAction::Term | SigDefaultAction::Core | SigDefaultAction::Stop => true,
+                _ => false,
+            },
+            SigAction::User { .. } => true,
+        })
+}
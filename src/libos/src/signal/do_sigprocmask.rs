@@ -41,6 +41,49 @@ pub fn do_rt_sigprocmask(
     Ok(())
 }
 
+/// Wait with the signal mask of the current thread replaced by the given one,
+/// e.g., in ppoll, pselect and epoll_pwait.
+///
+/// Since the signals are only delivered at the end of the syscall, the given
+/// mask takes effect atomically with the wait, avoiding the race between
+/// unblocking signals and starting to wait. Same as Linux, the original mask is
+/// restored right after the wait unless the wait is interrupted by signals. In
+/// that case, it is restored after the signals are delivered, or saved in the
+/// signal frame if a user-registered signal handler is called, so that the
+/// handler runs with the given mask and sigreturn restores the original one.
+pub fn wait_with_sigmask<T>(mask: Option<SigSet>, wait: impl FnOnce() -> Result<T>) -> Result<T> {
+    let mask = match mask {
+        Some(mask) => mask,
+        None => return wait(),
+    };
+
+    let thread = current!();
+    {
+        let mut sig_mask = thread.sig_mask().write().unwrap();
+        let mut saved_mask = thread.sig_saved_mask().write().unwrap();
+        if saved_mask.is_none() {
+            *saved_mask = Some(*sig_mask);
+        }
+        *sig_mask = mask;
+        // It is not possible to block SIGKILL or SIGSTOP
+        *sig_mask -= SIGKILL;
+        *sig_mask -= SIGSTOP;
+    }
+
+    let ret = wait();
+    let is_interrupted = match &ret {
+        Err(e) => e.errno() == EINTR,
+        Ok(_) => false,
+    };
+    if !is_interrupted {
+        let saved_mask = thread.sig_saved_mask().write().unwrap().take();
+        if let Some(saved_mask) = saved_mask {
+            *thread.sig_mask().write().unwrap() = saved_mask;
+        }
+    }
+    ret
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(u32)]
 pub enum MaskOp {
@@ -0,0 +1,92 @@
+use super::c_types::{sigval_t, SI_TKILL};
+use super::do_kill::prepare_signal;
+use super::signals::{UserSignal, UserSignalKind};
+use super::{SigNum, SigQueues, Signal};
+use crate::misc::resource_t;
+use crate::prelude::*;
+use crate::process::{table, ProcessStatus, ThreadRef, ThreadStatus};
+
+/// Send a signal with a value, which is delivered through siginfo_t, to a
+/// process, or to a thread of the process if tid is given.
+///
+/// Same as Linux, the signals sent by sigqueue are limited by the
+/// RLIMIT_SIGPENDING of the receiver. If signum is None, only the existence of
+/// the receiver is checked.
+pub fn do_sigqueue(
+    pid: pid_t,
+    tid: Option<pid_t>,
+    signum: Option<SigNum>,
+    code: i32,
+    value: sigval_t,
+) -> Result<()> {
+    debug!(
+        "do_sigqueue: pid: {}, tid: {:?}, signum: {:?}, code: {}",
+        pid, tid, signum, code
+    );
+
+    let src_pid = current!().process().pid();
+    // A process can not pretend to be the kernel or kill/tkill when sending
+    // signals to others
+    if (code >= 0 || code == SI_TKILL) && pid != src_pid {
+        return_errno!(EPERM, "the si_code is not permitted");
+    }
+
+    let signal = signum.map(|signum| {
+        let src_uid = 0;
+        Box::new(UserSignal::new(
+            signum,
+            UserSignalKind::Sigqueue { code, value },
+            src_pid,
+            src_uid,
+        ))
+    });
+    match tid {
+        Some(tid) => {
+            let thread = table::get_thread(tid)?;
+            if thread.process().pid() != pid {
+                return_errno!(ESRCH, "the thread does not belong to the process");
+            }
+            if thread.status() == ThreadStatus::Exited {
+                return Ok(());
+            }
+            enqueue_signal(signal, thread.sig_queues(), &thread)
+        }
+        None => {
+            let process = table::get_process(pid)?;
+            if process.status() == ProcessStatus::Zombie {
+                return Ok(());
+            }
+            match process.leader_thread() {
+                Some(leader_thread) => enqueue_signal(signal, process.sig_queues(), &leader_thread),
+                None => Ok(()),
+            }
+        }
+    }
+}
+
+pub(super) fn enqueue_signal(
+    signal: Option<Box<UserSignal>>,
+    sig_queues: &RwLock<SigQueues>,
+    receiver: &ThreadRef,
+) -> Result<()> {
+    let signal = match signal {
+        Some(signal) => signal,
+        None => return Ok(()),
+    };
+    prepare_signal(receiver.process(), signal.num());
+    let max_pending = receiver
+        .rlimits()
+        .lock()
+        .unwrap()
+        .get(resource_t::RLIMIT_SIGPENDING)
+        .get_cur();
+    let mut sig_queues = sig_queues.write().unwrap();
+    if sig_queues.len() as u64 >= max_pending {
+        return_errno!(
+            EAGAIN,
+            "the number of pending signals reaches RLIMIT_SIGPENDING"
+        );
+    }
+    sig_queues.enqueue(signal);
+    Ok(())
+}
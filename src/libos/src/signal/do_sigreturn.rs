@@ -1,11 +1,12 @@
 use super::c_types::{mcontext_t, siginfo_t, ucontext_t};
-use super::constants::SIGKILL;
-use super::sig_stack::SigStackFlags;
+use super::constants::{SIGKILL, SIGSEGV};
+use super::do_sigaltstack::{get_sig_stack_info, restore_sig_stack};
 use super::{SigAction, SigActionFlags, SigDefaultAction, SigSet, Signal};
 use crate::lazy_static::__Deref;
 use crate::prelude::*;
-use crate::process::{ProcessRef, TermStatus, ThreadRef};
-use crate::syscall::{CpuContext, FpRegs};
+use crate::process::{stop_process, wait_until_continued, ProcessRef, TermStatus, ThreadRef};
+use crate::syscall::{restart_syscall, CpuContext, FpRegs, SyscallNum};
+use crate::vm::VMRange;
 use aligned::{Aligned, A16};
 use core::arch::x86_64::{_fxrstor, _fxsave};
 use std::{ptr, slice};
@@ -30,6 +31,8 @@ pub fn do_rt_sigreturn(curr_user_ctxt: &mut CpuContext) -> Result<()> {
 
     // Restore sigmask
     *current!().sig_mask().write().unwrap() = SigSet::from_c(last_ucontext.uc_sigmask);
+    // Restore the alternate signal stack, which may be changed by the signal handler
+    restore_sig_stack(&last_ucontext.uc_stack, curr_user_ctxt);
     // Restore user context
     *curr_user_ctxt = last_ucontext.uc_mcontext.inner;
 
@@ -49,7 +52,7 @@ pub fn do_rt_sigreturn(curr_user_ctxt: &mut CpuContext) -> Result<()> {
 /// the per-thread or per-process signal queue, and 2) handling the signal
 /// according to the signal disposition.
 ///
-/// When handling a signal, one of the three actions below will be done:
+/// When handling a signal, one of the four actions below will be done:
 ///
 /// 1. Ignore the signal. This is the easy part.
 ///
@@ -59,16 +62,44 @@ pub fn do_rt_sigreturn(curr_user_ctxt: &mut CpuContext) -> Result<()> {
 /// will be modified so that the user-registered signal handler will be called
 /// upon returning to the user space when the current syscall is finished.
 ///
+/// 4. Stop the process. In this case, the current thread, as well as any other
+/// thread of the process that calls this function, is blocked until the process
+/// is continued or killed.
+///
+/// If the current syscall is interrupted by signals, i.e., it fails with EINTR and
+/// is given as `interrupted_syscall`, the syscall is restarted when no user-registered
+/// signal handler is called or the handler is installed with SA_RESTART. Otherwise,
+/// the syscall fails with EINTR.
+///
 /// **Requirement.** This must be called only once during the execution of a
 /// syscall and at a very late stage.
 ///
 /// **Post-condition.** The temporary signal mask of the current thread is cleared.
-pub fn deliver_signal(cpu_context: &mut CpuContext) {
+pub fn deliver_signal(cpu_context: &mut CpuContext, interrupted_syscall: Option<SyscallNum>) {
     let thread = current!();
     let process = thread.process();
 
-    if !process.is_forced_to_exit() {
-        do_deliver_signal(&thread, &process, cpu_context);
+    let mut interrupted_syscall = interrupted_syscall;
+    while !process.is_forced_to_exit() {
+        do_deliver_signal(&thread, &process, cpu_context, &mut interrupted_syscall);
+        if !process.is_stopped() {
+            break;
+        }
+        // Stop the thread until the process is continued or killed, after
+        // which the signals that arrive in the meantime are delivered
+        wait_until_continued(&thread);
+    }
+    if let Some(num) = interrupted_syscall {
+        if !process.is_forced_to_exit() {
+            restart_syscall(cpu_context, num);
+        }
+    }
+
+    // Restore the signal mask that is replaced during the syscall, unless it
+    // has been saved in the frame of a user-registered signal handler
+    let saved_mask = thread.sig_saved_mask().write().unwrap().take();
+    if let Some(saved_mask) = saved_mask {
+        *thread.sig_mask().write().unwrap() = saved_mask;
     }
 
     // Ensure the tmp signal mask is cleared before sysret
@@ -76,7 +107,12 @@ pub fn deliver_signal(cpu_context: &mut CpuContext) {
     *tmp_sig_mask = SigSet::new_empty();
 }
 
-fn do_deliver_signal(thread: &ThreadRef, process: &ProcessRef, cpu_context: &mut CpuContext) {
+fn do_deliver_signal(
+    thread: &ThreadRef,
+    process: &ProcessRef,
+    cpu_context: &mut CpuContext,
+    interrupted_syscall: &mut Option<SyscallNum>,
+) {
     loop {
         if process.sig_queues().read().unwrap().empty()
             && thread.sig_queues().read().unwrap().empty()
@@ -101,7 +137,8 @@ fn do_deliver_signal(thread: &ThreadRef, process: &ProcessRef, cpu_context: &mut
             signal_opt.unwrap()
         };
 
-        let continue_handling = handle_signal(signal, thread, process, cpu_context);
+        let continue_handling =
+            handle_signal(signal, thread, process, cpu_context, interrupted_syscall);
         if !continue_handling {
             break;
         }
@@ -109,7 +146,7 @@ fn do_deliver_signal(thread: &ThreadRef, process: &ProcessRef, cpu_context: &mut
 }
 
 /// Force delivering the given signal to the current thread, without checking the thread's
-/// signal mask.
+/// signal mask. A blocked or ignored signal is reset to its default action.
 ///
 /// **Post-condition.** The tmp signal mask of the current thread is all set. This avoids
 /// delivering two signals during one execution of a syscall.
@@ -120,7 +157,27 @@ pub fn force_signal(signal: Box<dyn Signal>, cpu_context: &mut CpuContext) {
     let thread = current!();
     let process = thread.process();
 
-    handle_signal(signal, &thread, &process, cpu_context);
+    // Same as Linux, a forced signal that is blocked or ignored takes the
+    // default action. Otherwise, the thread would fault again and again on
+    // returning to the faulting instruction.
+    let signum = signal.num();
+    {
+        let mut sig_mask = thread.sig_mask().write().unwrap();
+        let mut sig_dispositions = process.sig_dispositions().write().unwrap();
+        if sig_mask.contains(signum) || sig_dispositions.get(signum) == SigAction::Ign {
+            sig_dispositions.set(signum, SigAction::Dfl);
+            *sig_mask -= signum;
+        }
+    }
+
+    // A thread that keeps faulting in its signal handlers cannot make any
+    // progress, so kill the process instead of aborting LibOS
+    let is_sig_stack_full = PRE_UCONTEXTS.with(|ref_cell| ref_cell.borrow().full());
+    if is_sig_stack_full {
+        process.force_exit(TermStatus::Killed(signum));
+    } else {
+        handle_signal(signal, &thread, &process, cpu_context, &mut None);
+    }
 
     // Temporarily block all signals from being delivered until this syscall is
     // over. This ensures that the updated curr_cpu_ctxt will not be overriden
@@ -134,6 +191,7 @@ fn handle_signal(
     thread: &ThreadRef,
     process: &ProcessRef,
     cpu_context: &mut CpuContext,
+    interrupted_syscall: &mut Option<SyscallNum>,
 ) -> bool {
     let is_sig_stack_full = PRE_UCONTEXTS.with(|ref_cell| {
         let stack = ref_cell.borrow();
@@ -161,13 +219,11 @@ fn handle_signal(
                     false
                 }
                 SigDefaultAction::Stop => {
-                    warn!("SIGSTOP is unsupported");
-                    true
-                }
-                SigDefaultAction::Cont => {
-                    warn!("SIGCONT is unsupported");
-                    true
+                    stop_process(process, signal.num());
+                    false
                 }
+                // The process has been continued when the signal is sent
+                SigDefaultAction::Cont => true,
             }
         }
         SigAction::User {
@@ -176,6 +232,11 @@ fn handle_signal(
             restorer_addr,
             mask,
         } => {
+            if let Some(num) = interrupted_syscall.take() {
+                if flags.contains(SigActionFlags::SA_RESTART) {
+                    restart_syscall(cpu_context, num);
+                }
+            }
             let ret = handle_signals_by_user(
                 signal,
                 thread,
@@ -185,8 +246,11 @@ fn handle_signal(
                 mask,
                 cpu_context,
             );
+            // Same as Linux, the process is killed by SIGSEGV if the signal
+            // frame cannot be set up, e.g., the alternate stack is too small
             if let Err(_) = ret {
-                todo!("kill the process if any error");
+                let term_status = TermStatus::Killed(SIGSEGV);
+                process.force_exit(term_status);
             }
             false
         }
@@ -205,7 +269,13 @@ fn handle_signals_by_user(
 ) -> Result<()> {
     let old_sigmask = {
         let mut sigmask = thread.sig_mask().write().unwrap();
-        let old_sigmask = *sigmask;
+        // The signal mask replaced during the syscall is restored by sigreturn
+        let old_sigmask = thread
+            .sig_saved_mask()
+            .write()
+            .unwrap()
+            .take()
+            .unwrap_or(*sigmask);
         *sigmask = new_sigmask;
         if !flags.contains(SigActionFlags::SA_NODEFER) {
             // Block the current signal while executing the signal handler
@@ -215,29 +285,40 @@ fn handle_signals_by_user(
     };
 
     // Represent the user stack in a memory safe way
-    let mut user_stack = {
-        let get_stack_top = || -> usize {
-            if flags.contains(SigActionFlags::SA_ONSTACK) {
-                let thread = current!();
-                let sig_stack = thread.sig_stack().lock().unwrap();
-                if let Some(stack) = *sig_stack {
-                    if !stack.contains(curr_user_ctxt.rsp as usize) {
-                        let stack_top = stack.sp() + stack.size();
-                        return stack_top;
-                    }
+    let (mut user_stack, uc_stack) = {
+        let rsp = curr_user_ctxt.rsp as usize;
+        let mut sig_stack = thread.sig_stack().lock().unwrap();
+        // The alternate signal stack before the signal handler runs, which is
+        // restored when the signal handler returns
+        let uc_stack = get_sig_stack_info(&sig_stack, rsp);
+
+        // Switch to the alternate signal stack if we are not on it yet
+        let alt_stack = match *sig_stack {
+            Some(stack)
+                if flags.contains(SigActionFlags::SA_ONSTACK) && !stack.is_on_stack(rsp) =>
+            {
+                if stack.is_auto_disarm() {
+                    *sig_stack = None;
                 }
+                Some(stack)
             }
-            const BIG_ENOUGH_GAP: u64 = 1024;
-            let stack_top = (curr_user_ctxt.rsp - BIG_ENOUGH_GAP) as usize;
-            stack_top
+            _ => None,
         };
-        let stack_top = get_stack_top();
-        let stack_size = {
-            const BIG_ENOUGH_SIZE: u64 = 4096;
-            BIG_ENOUGH_SIZE as usize
+        let user_stack = match alt_stack {
+            Some(stack) => unsafe { Stack::new(stack.sp() + stack.size(), stack.size())? },
+            None => {
+                const BIG_ENOUGH_GAP: u64 = 1024;
+                const BIG_ENOUGH_SIZE: u64 = 4096;
+                let stack_top = (curr_user_ctxt.rsp - BIG_ENOUGH_GAP) as usize;
+                // The signal frame cannot be set up on an overflowed stack
+                let frame_range = VMRange::new(stack_top - BIG_ENOUGH_SIZE as usize, stack_top)?;
+                if thread.vm().overlaps_stack_guard(&frame_range) {
+                    return_errno!(EFAULT, "stack overflow");
+                }
+                unsafe { Stack::new(stack_top, BIG_ENOUGH_SIZE as usize)? }
+            }
         };
-        // TODO: validate the memory range of the stack
-        unsafe { Stack::new(stack_top, stack_size)? }
+        (user_stack, uc_stack)
     };
 
     // Prepare the user stack in four steps.
@@ -260,6 +341,8 @@ fn handle_signals_by_user(
         *ucontext = unsafe { std::mem::zeroed() };
         // Save the old sigmask
         ucontext.uc_sigmask = old_sigmask.to_c();
+        // Save the alternate signal stack
+        ucontext.uc_stack = uc_stack.to_c();
         // Save the user context
         ucontext.uc_mcontext.inner = *curr_user_ctxt;
 
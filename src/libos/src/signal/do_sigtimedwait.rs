@@ -130,7 +130,7 @@ impl Drop for PendingSigWaiter {
     }
 }
 
-fn dequeue_pending_signal(
+pub(super) fn dequeue_pending_signal(
     interest: &SigSet,
     thread: &ThreadRef,
     process: &ProcessRef,
@@ -2,26 +2,37 @@
 
 use crate::prelude::*;
 
-use sig_action::{SigAction, SigActionFlags, SigDefaultAction};
+use sig_action::SigDefaultAction;
 
-pub use self::c_types::{sigaction_t, siginfo_t, sigset_t, stack_t};
+pub use self::c_types::{sigaction_t, siginfo_t, sigset_t, sigval_t, stack_t, SI_MESGQ};
 pub use self::constants::*;
-pub use self::do_kill::do_kill_from_outside_enclave;
+pub use self::do_kill::{do_kill_from_outside_enclave, kill_process_group};
+pub use self::do_sigpending::has_interrupting_signals;
+pub use self::do_sigprocmask::wait_with_sigmask;
+pub use self::do_sigqueue::do_sigqueue;
 pub use self::do_sigreturn::{deliver_signal, force_signal};
+pub use self::sig_action::{SigAction, SigActionFlags};
 pub use self::sig_dispositions::SigDispositions;
 pub use self::sig_num::SigNum;
 pub use self::sig_queues::SigQueues;
 pub use self::sig_set::SigSet;
 pub use self::sig_stack::SigStack;
-pub use self::signals::{FaultSignal, KernelSignal, Signal, UserSignal, UserSignalKind};
+pub use self::signal_file::{AsSignalFile, SignalFile, SignalFileFlags};
+pub use self::signals::{
+    ChildSignal, FaultSignal, KernelSignal, Signal, TimerOverrun, TimerSignal, UserSignal,
+    UserSignalKind,
+};
 pub use self::syscalls::*;
 
 mod c_types;
 mod do_kill;
+mod do_pause;
 mod do_sigaction;
 mod do_sigaltstack;
+mod do_signalfd;
 mod do_sigpending;
 mod do_sigprocmask;
+mod do_sigqueue;
 mod do_sigreturn;
 mod do_sigtimedwait;
 mod sig_action;
@@ -30,6 +41,7 @@ mod sig_num;
 mod sig_queues;
 mod sig_set;
 mod sig_stack;
+mod signal_file;
 mod signals;
 mod syscalls;
 
@@ -1,7 +1,7 @@
 use std::fmt;
 
 use super::constants::*;
-use super::{SigAction, SigNum};
+use super::{SigAction, SigNum, SigSet};
 use crate::prelude::*;
 
 #[derive(Copy, Clone)]
@@ -27,6 +27,23 @@ impl SigDispositions {
         self.map[idx] = sa;
     }
 
+    /// Get the set of signals that are ignored.
+    pub fn ignored(&self) -> SigSet {
+        self.iter()
+            .filter(|(_, action)| **action == SigAction::Ign)
+            .fold(SigSet::new_empty(), |set, (signum, _)| set + signum)
+    }
+
+    /// Get the set of signals that are caught by user-given handlers.
+    pub fn caught(&self) -> SigSet {
+        self.iter()
+            .filter(|(_, action)| match action {
+                SigAction::User { .. } => true,
+                _ => false,
+            })
+            .fold(SigSet::new_empty(), |set, (signum, _)| set + signum)
+    }
+
     pub fn iter<'a>(&'a self) -> SigDispositionsIter<'a> {
         SigDispositionsIter::new(self)
     }
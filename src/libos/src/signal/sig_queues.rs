@@ -31,6 +31,11 @@ impl SigQueues {
         self.count == 0
     }
 
+    /// The number of pending signals.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
     pub fn enqueue(&mut self, signal: Box<dyn Signal>) {
         let signum = signal.num();
         if signum.is_std() {
@@ -126,6 +131,22 @@ impl SigQueues {
         None
     }
 
+    /// Discard the pending signals in the given set.
+    pub fn discard(&mut self, signums: &SigSet) {
+        for signum in signums.iter() {
+            if signum.is_std() {
+                if self.get_std_queue_mut(signum).take().is_some() {
+                    self.count -= 1;
+                }
+            } else {
+                let queue = self.get_rt_queue_mut(signum);
+                let num_discarded = queue.len();
+                queue.clear();
+                self.count -= num_discarded;
+            }
+        }
+    }
+
     pub fn notifier(&self) -> &Notifier<SigNum, SigSet> {
         &self.notifier
     }
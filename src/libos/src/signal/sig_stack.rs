@@ -3,26 +3,27 @@ use crate::prelude::*;
 
 pub const MINSIGSTKSZ: usize = 2048;
 
-#[allow(non_camel_case_types)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-#[repr(u32)]
-pub enum SigStackFlags {
-    /// The alternate signal stack is enabled and the process is not executing on it
-    EMPTY = 0,
-    /// The process is currently executing on the alternate signal stack
-    SS_ONSTACK = 1,
-    /// The alternate signal stack is currently disabled
-    SS_DISABLE = 2,
-    /// The alternate signal stack has been marked to be autodisarmed
-    SS_AUTODISARM = 1 << 31,
+bitflags! {
+    pub struct SigStackFlags: u32 {
+        /// The process is currently executing on the alternate signal stack
+        const SS_ONSTACK = 1;
+        /// The alternate signal stack is currently disabled
+        const SS_DISABLE = 2;
+        /// The alternate signal stack is disabled while a signal handler runs
+        /// on it, and is restored when the signal handler returns
+        const SS_AUTODISARM = 1 << 31;
+    }
 }
 
 impl SigStackFlags {
     pub fn from_u32(bits: u32) -> Result<Self> {
-        if bits > Self::SS_DISABLE as u32 && bits != Self::SS_AUTODISARM as u32 {
+        let flags = Self::from_bits(bits)
+            .ok_or_else(|| errno!(EINVAL, "invalid bits for sig stack flags"))?;
+        // SS_AUTODISARM can be combined with either SS_ONSTACK or SS_DISABLE
+        if flags.contains(Self::SS_ONSTACK | Self::SS_DISABLE) {
             return_errno!(EINVAL, "invalid bits for sig stack flags");
         }
-        Ok(unsafe { core::mem::transmute(bits as u32) })
+        Ok(flags)
     }
 }
 
@@ -45,7 +46,7 @@ impl SigStack {
     pub fn to_c(&self) -> stack_t {
         stack_t {
             ss_sp: self.sp as *mut c_void,
-            ss_flags: self.flags as i32,
+            ss_flags: self.flags.bits() as i32,
             ss_size: self.size,
         }
     }
@@ -71,6 +72,18 @@ impl SigStack {
     pub fn contains(&self, addr: usize) -> bool {
         addr >= self.sp && addr - self.sp < self.size
     }
+
+    pub fn is_auto_disarm(&self) -> bool {
+        self.flags.contains(SigStackFlags::SS_AUTODISARM)
+    }
+
+    /// Whether the thread is executing on the stack.
+    ///
+    /// Same as Linux, a thread is never considered to be on a stack with
+    /// SS_AUTODISARM, as the stack is disarmed when a signal handler runs on it.
+    pub fn is_on_stack(&self, sp: usize) -> bool {
+        !self.is_auto_disarm() && self.contains(sp)
+    }
 }
 
 impl Default for SigStack {
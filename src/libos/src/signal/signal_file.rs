@@ -0,0 +1,259 @@
+use std::any::Any;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Weak;
+
+use super::c_types::signalfd_siginfo_t;
+use super::constants::*;
+use super::do_sigtimedwait::dequeue_pending_signal;
+use super::{SigNum, SigSet};
+use crate::events::{Observer, Waiter, WaiterQueue};
+use crate::fs::{AccessMode, File, IoEvents, IoNotifier, StatusFlags};
+use crate::prelude::*;
+use crate::process::{ProcessRef, Thread};
+
+/// A file that allows consuming pending signals by reading it.
+///
+/// A `SignalFile` registers an observer to the signal queues of the process
+/// and the thread that creates it. Whenever an interesting signal is enqueued,
+/// the observer broadcasts an `IoEvents::IN` event via the I/O notifier of the
+/// file and wakes up the threads that are blocked on reading the file. This
+/// makes a signalfd pollable via poll, select, and epoll.
+pub struct SignalFile {
+    mask: RwLock<SigSet>,
+    is_nonblocking: AtomicBool,
+    process: ProcessRef,
+    thread: Weak<Thread>,
+    observer: Arc<SignalFileObserver>,
+}
+
+bitflags! {
+    pub struct SignalFileFlags: i32 {
+        /// Non-blocking
+        const SFD_NONBLOCK  = 1 << 11;
+        /// Close on exec
+        const SFD_CLOEXEC   = 1 << 19;
+    }
+}
+
+impl SignalFile {
+    pub fn new(mask: SigSet, flags: SignalFileFlags) -> Self {
+        let mask = RwLock::new(Self::sanitize_mask(mask));
+        let is_nonblocking = AtomicBool::new(flags.contains(SignalFileFlags::SFD_NONBLOCK));
+        let thread = current!();
+        let process = thread.process().clone();
+        let observer = Arc::new(SignalFileObserver {
+            waiters: WaiterQueue::new(),
+            notifier: IoNotifier::new(),
+        });
+
+        let new_self = Self {
+            mask,
+            is_nonblocking,
+            process,
+            thread: Arc::downgrade(&thread),
+            observer,
+        };
+        new_self.register_observer();
+        new_self
+    }
+
+    pub fn mask(&self) -> SigSet {
+        *self.mask.read().unwrap()
+    }
+
+    /// Replace the set of signals that the file is interested in.
+    pub fn set_mask(&self, mask: SigSet) {
+        self.unregister_observer();
+        *self.mask.write().unwrap() = Self::sanitize_mask(mask);
+        self.register_observer();
+
+        // Some pending signals may become interesting now
+        self.observer.notify();
+    }
+
+    fn sanitize_mask(mut mask: SigSet) -> SigSet {
+        // SIGKILL and SIGSTOP cannot be received via a signalfd; they are silently ignored
+        mask -= SIGKILL;
+        mask -= SIGSTOP;
+        mask
+    }
+
+    fn is_nonblocking(&self) -> bool {
+        self.is_nonblocking.load(Ordering::Acquire)
+    }
+
+    fn register_observer(&self) {
+        let mask = self.mask();
+        let weak_observer = Arc::downgrade(&self.observer) as Weak<dyn Observer<SigNum>>;
+        self.process
+            .sig_queues()
+            .read()
+            .unwrap()
+            .notifier()
+            .register(weak_observer.clone(), Some(mask), None);
+        if let Some(thread) = self.thread.upgrade() {
+            thread.sig_queues().read().unwrap().notifier().register(
+                weak_observer,
+                Some(mask),
+                None,
+            );
+        }
+    }
+
+    fn unregister_observer(&self) {
+        let weak_observer = Arc::downgrade(&self.observer) as Weak<dyn Observer<SigNum>>;
+        self.process
+            .sig_queues()
+            .read()
+            .unwrap()
+            .notifier()
+            .unregister(&weak_observer);
+        if let Some(thread) = self.thread.upgrade() {
+            thread
+                .sig_queues()
+                .read()
+                .unwrap()
+                .notifier()
+                .unregister(&weak_observer);
+        }
+    }
+
+    fn has_pending_signals(&self) -> bool {
+        let mask = self.mask();
+        let pending = {
+            let mut pending = self.process.sig_queues().read().unwrap().pending();
+            if let Some(thread) = self.thread.upgrade() {
+                pending |= thread.sig_queues().read().unwrap().pending();
+            }
+            pending
+        };
+        !(pending & mask).empty()
+    }
+}
+
+impl File for SignalFile {
+    fn read(&self, buf: &mut [u8]) -> Result<usize> {
+        const SSI_LEN: usize = std::mem::size_of::<signalfd_siginfo_t>();
+        let max_count = buf.len() / SSI_LEN;
+        if max_count == 0 {
+            return_errno!(EINVAL, "the buffer is too small");
+        }
+
+        // Signals are consumed from the queues of the reading thread and its process
+        let thread = current!();
+        let process = thread.process().clone();
+        let mask = self.mask();
+
+        let waiter = Waiter::new();
+        let mut count = 0;
+        loop {
+            self.observer.waiters.reset_and_enqueue(&waiter);
+
+            while count < max_count {
+                let signal = match dequeue_pending_signal(&mask, &thread, &process) {
+                    None => break,
+                    Some(signal) => signal,
+                };
+                let ssi = signalfd_siginfo_t::from(&signal.to_info());
+                let ssi_bytes =
+                    unsafe { std::slice::from_raw_parts(&ssi as *const _ as *const u8, SSI_LEN) };
+                buf[count * SSI_LEN..(count + 1) * SSI_LEN].copy_from_slice(ssi_bytes);
+                count += 1;
+            }
+            if count > 0 {
+                return Ok(count * SSI_LEN);
+            }
+
+            if self.is_nonblocking() {
+                return_errno!(EAGAIN, "no interesting, pending signal");
+            }
+            waiter.wait(None)?;
+        }
+    }
+
+    fn access_mode(&self) -> Result<AccessMode> {
+        Ok(AccessMode::O_RDONLY)
+    }
+
+    fn status_flags(&self) -> Result<StatusFlags> {
+        if self.is_nonblocking() {
+            Ok(StatusFlags::O_NONBLOCK)
+        } else {
+            Ok(StatusFlags::empty())
+        }
+    }
+
+    fn set_status_flags(&self, new_status_flags: StatusFlags) -> Result<()> {
+        let nonblocking = new_status_flags.contains(StatusFlags::O_NONBLOCK);
+        self.is_nonblocking.store(nonblocking, Ordering::Release);
+        if nonblocking {
+            // Wake all threads that are blocked on reading this file
+            self.observer.waiters.dequeue_and_wake_all();
+        }
+        Ok(())
+    }
+
+    fn poll_new(&self) -> IoEvents {
+        if self.has_pending_signals() {
+            IoEvents::IN
+        } else {
+            IoEvents::empty()
+        }
+    }
+
+    fn notifier(&self) -> Option<&IoNotifier> {
+        Some(&self.observer.notifier)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Drop for SignalFile {
+    fn drop(&mut self) {
+        self.unregister_observer();
+    }
+}
+
+impl fmt::Debug for SignalFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SignalFile")
+            .field("mask", &self.mask())
+            .field("is_nonblocking", &self.is_nonblocking())
+            .finish()
+    }
+}
+
+pub trait AsSignalFile {
+    fn as_signal_file(&self) -> Result<&SignalFile>;
+}
+
+impl AsSignalFile for FileRef {
+    fn as_signal_file(&self) -> Result<&SignalFile> {
+        self.as_any()
+            .downcast_ref::<SignalFile>()
+            .ok_or_else(|| errno!(EINVAL, "not a signal file"))
+    }
+}
+
+struct SignalFileObserver {
+    // All threads that are blocked on reading the signal file.
+    waiters: WaiterQueue,
+    // A notifier to broadcast events on the signal file.
+    notifier: IoNotifier,
+}
+
+impl SignalFileObserver {
+    fn notify(&self) {
+        self.notifier.broadcast(&IoEvents::IN);
+        self.waiters.dequeue_and_wake_all();
+    }
+}
+
+impl Observer<SigNum> for SignalFileObserver {
+    fn on_event(&self, _signum: &SigNum, _metadata: &Option<Weak<dyn Any + Send + Sync>>) {
+        self.notify();
+    }
+}
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use super::super::c_types::*;
+use super::super::constants::*;
+use super::super::{SigNum, Signal};
+use crate::prelude::*;
+use crate::process::TermStatus;
+use crate::time::clock_t;
+
+/// The SIGCHLD signal sent to the parent when a child changes its state.
+#[derive(Debug, Copy, Clone)]
+pub struct ChildSignal {
+    pid: pid_t,
+    uid: uid_t,
+    // One of CLD_EXITED, CLD_KILLED, CLD_DUMPED, CLD_STOPPED and CLD_CONTINUED
+    code: i32,
+    // The exit code for CLD_EXITED, or the signal number otherwise
+    status: i32,
+    utime: Duration,
+    stime: Duration,
+}
+
+impl ChildSignal {
+    /// Create the signal for a child that has terminated with the given status.
+    pub fn new_exited(
+        pid: pid_t,
+        term_status: TermStatus,
+        utime: Duration,
+        stime: Duration,
+    ) -> Self {
+        // TODO: report CLD_DUMPED once core dump is supported
+        let (code, status) = match term_status {
+            TermStatus::Exited(exit_code) => (CLD_EXITED, exit_code as i32),
+            TermStatus::Killed(signum) => (CLD_KILLED, signum.as_u8() as i32),
+        };
+        Self::new(pid, code, status, utime, stime)
+    }
+
+    /// Create the signal for a child that has been stopped by the given signal.
+    pub fn new_stopped(pid: pid_t, signum: SigNum, utime: Duration, stime: Duration) -> Self {
+        Self::new(pid, CLD_STOPPED, signum.as_u8() as i32, utime, stime)
+    }
+
+    /// Create the signal for a child that has been continued by SIGCONT.
+    pub fn new_continued(pid: pid_t, utime: Duration, stime: Duration) -> Self {
+        Self::new(pid, CLD_CONTINUED, SIGCONT.as_u8() as i32, utime, stime)
+    }
+
+    fn new(pid: pid_t, code: i32, status: i32, utime: Duration, stime: Duration) -> Self {
+        Self {
+            pid,
+            uid: 0,
+            code,
+            status,
+            utime,
+            stime,
+        }
+    }
+}
+
+impl Signal for ChildSignal {
+    fn num(&self) -> SigNum {
+        SIGCHLD
+    }
+
+    fn to_info(&self) -> siginfo_t {
+        let mut info = siginfo_t::new(SIGCHLD, self.code);
+        info.set_si_pid(self.pid);
+        info.set_si_uid(self.uid);
+        info.set_si_satus(self.status);
+        info.set_si_utime(to_clock_ticks(self.utime));
+        info.set_si_stime(to_clock_ticks(self.stime));
+        info
+    }
+}
+
+/// Convert a duration to clock ticks, whose frequency is USER_HZ (100) on Linux.
+fn to_clock_ticks(duration: Duration) -> clock_t {
+    const TICKS_PER_SEC: u128 = 100;
+    (duration.as_nanos() * TICKS_PER_SEC / 1_000_000_000) as clock_t
+}
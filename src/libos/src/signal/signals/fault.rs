@@ -2,6 +2,7 @@ use super::super::c_types::*;
 use super::super::constants::*;
 use super::super::{SigNum, Signal};
 use crate::prelude::*;
+use crate::syscall::FpRegs;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct FaultSignal {
@@ -11,31 +12,34 @@ pub struct FaultSignal {
 }
 
 impl FaultSignal {
-    pub fn new(info: &sgx_exception_info_t) -> Self {
-        // TODO: the current mapping from exceptinon to signal is only a first
-        // order approximation. The resulting signum or siginfo may not be
-        // idential to Linux's behavior.
+    /// Convert a hardware exception to a signal as Linux does.
+    ///
+    /// The floating-point registers at the time of the exception give the
+    /// cause of a floating-point exception.
+    pub fn new(info: &sgx_exception_info_t, fpregs: &FpRegs) -> Self {
         use sgx_exception_vector_t::*;
+        // The address of the faulting instruction
+        let ip = Some(info.cpu_context.rip);
         let (num, code, addr) = match info.exception_vector {
             // Divider exception
-            SGX_EXCEPTION_VECTOR_DE => (SIGFPE, FPE_INTDIV, None),
-            // Floating-point exception
-            SGX_EXCEPTION_VECTOR_MF |
+            SGX_EXCEPTION_VECTOR_DE => (SIGFPE, FPE_INTDIV, ip),
+            // x87 floating-point exception
+            SGX_EXCEPTION_VECTOR_MF => (SIGFPE, x87_fpe_code(fpregs), ip),
             // SIMD floating-point exception
-            SGX_EXCEPTION_VECTOR_XM => (SIGFPE, FPE_FLTDIV, None),
+            SGX_EXCEPTION_VECTOR_XM => (SIGFPE, simd_fpe_code(fpregs), ip),
             // Invalid opcode exception
-            SGX_EXCEPTION_VECTOR_UD |
-            // Debug exception: should not occur in enclave; treat is as #UD
-            SGX_EXCEPTION_VECTOR_DB |
-            // Break point exception: should not occur in enclave; treat is as #UD
-            SGX_EXCEPTION_VECTOR_BP => (SIGILL, ILL_ILLOPC, None),
+            SGX_EXCEPTION_VECTOR_UD => (SIGILL, ILL_ILLOPN, ip),
+            // Debug exception
+            SGX_EXCEPTION_VECTOR_DB => (SIGTRAP, TRAP_TRACE, ip),
+            // Break point exception
+            SGX_EXCEPTION_VECTOR_BP => (SIGTRAP, SI_KERNEL, None),
             // Bound range exception
-            SGX_EXCEPTION_VECTOR_BR => (SIGSEGV, SEGV_BNDERR, None),
+            SGX_EXCEPTION_VECTOR_BR => (SIGSEGV, SEGV_BNDERR, ip),
             // Alignment check exception
             SGX_EXCEPTION_VECTOR_AC => (SIGBUS, BUS_ADRALN, None),
             // Page fault exception
             SGX_EXCEPTION_VECTOR_PF => {
-                const PF_ERR_FLAG_PRESENT : u32 = 1u32 << 0;
+                const PF_ERR_FLAG_PRESENT: u32 = 1u32 << 0;
                 let code = if info.exinfo.errcd & PF_ERR_FLAG_PRESENT != 0 {
                     SEGV_ACCERR
                 } else {
@@ -43,10 +47,12 @@ impl FaultSignal {
                 };
                 let addr = Some(info.exinfo.maddr);
                 (SIGSEGV, code, addr)
-            },
+            }
             // General protection exception
-            SGX_EXCEPTION_VECTOR_PF => (SIGBUS, BUS_ADRERR, None),
-            _ => panic!("exception cannot be converted to signal"),
+            SGX_EXCEPTION_VECTOR_GP => (SIGSEGV, SI_KERNEL, None),
+            // Other exceptions, e.g., control protection exception
+            #[allow(unreachable_patterns)]
+            _ => (SIGSEGV, SI_KERNEL, None),
         };
         Self { num, code, addr }
     }
@@ -67,3 +73,52 @@ impl Signal for FaultSignal {
         info
     }
 }
+
+// The offsets of the registers in the fxsave area
+const FCW_OFFSET: usize = 0;
+const FSW_OFFSET: usize = 2;
+const MXCSR_OFFSET: usize = 24;
+
+/// Get the si_code of an x87 floating-point exception, which is given by the
+/// unmasked exceptions in the status word.
+fn x87_fpe_code(fpregs: &FpRegs) -> i32 {
+    let fxsave = fpregs.as_slice();
+    let cwd = u16::from_le_bytes([fxsave[FCW_OFFSET], fxsave[FCW_OFFSET + 1]]);
+    let swd = u16::from_le_bytes([fxsave[FSW_OFFSET], fxsave[FSW_OFFSET + 1]]);
+    fpe_code((swd & !cwd) as u32)
+}
+
+/// Get the si_code of a SIMD floating-point exception, which is given by the
+/// unmasked exceptions in MXCSR.
+fn simd_fpe_code(fpregs: &FpRegs) -> i32 {
+    let fxsave = fpregs.as_slice();
+    let mut mxcsr_bytes = [0u8; 4];
+    mxcsr_bytes.copy_from_slice(&fxsave[MXCSR_OFFSET..MXCSR_OFFSET + 4]);
+    let mxcsr = u32::from_le_bytes(mxcsr_bytes);
+    // The mask bits are 7 bits above the corresponding flag bits
+    fpe_code(!(mxcsr >> 7) & mxcsr)
+}
+
+fn fpe_code(unmasked_exceptions: u32) -> i32 {
+    const INVALID_OPERATION: u32 = 0x001;
+    const DENORMAL_OPERAND: u32 = 0x002;
+    const ZERO_DIVIDE: u32 = 0x004;
+    const OVERFLOW: u32 = 0x008;
+    const UNDERFLOW: u32 = 0x010;
+    const PRECISION: u32 = 0x020;
+
+    // Same as Linux, the exceptions are checked in the order of priority
+    if unmasked_exceptions & INVALID_OPERATION != 0 {
+        FPE_FLTINV
+    } else if unmasked_exceptions & ZERO_DIVIDE != 0 {
+        FPE_FLTDIV
+    } else if unmasked_exceptions & OVERFLOW != 0 {
+        FPE_FLTOVF
+    } else if unmasked_exceptions & (UNDERFLOW | DENORMAL_OPERAND) != 0 {
+        FPE_FLTUND
+    } else if unmasked_exceptions & PRECISION != 0 {
+        FPE_FLTRES
+    } else {
+        FPE_FLTINV
+    }
+}
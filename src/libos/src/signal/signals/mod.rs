@@ -1,10 +1,14 @@
 /// Implementation of signals generated from various sources.
+mod child;
 mod fault;
 mod kernel;
+mod timer;
 mod user;
 
+pub use self::child::ChildSignal;
 pub use self::fault::FaultSignal;
 pub use self::kernel::KernelSignal;
+pub use self::timer::{TimerOverrun, TimerSignal};
 pub use self::user::{UserSignal, UserSignalKind};
 
 use super::c_types::siginfo_t;
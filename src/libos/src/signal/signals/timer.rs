@@ -0,0 +1,115 @@
+use super::super::c_types::*;
+use super::super::{SigNum, Signal};
+use crate::prelude::*;
+
+/// A signal sent by a POSIX timer on its expiration.
+#[derive(Debug)]
+pub struct TimerSignal {
+    num: SigNum,
+    timer_id: i32,
+    value: sigval_t,
+    overrun: Arc<TimerOverrun>,
+}
+
+// sigval_t is a plain value given by the user, which is never dereferenced
+unsafe impl Sync for TimerSignal {}
+unsafe impl Send for TimerSignal {}
+
+impl TimerSignal {
+    pub fn new(num: SigNum, timer_id: i32, value: sigval_t, overrun: Arc<TimerOverrun>) -> Self {
+        Self {
+            num,
+            timer_id,
+            value,
+            overrun,
+        }
+    }
+}
+
+impl Signal for TimerSignal {
+    fn num(&self) -> SigNum {
+        self.num
+    }
+
+    fn to_info(&self) -> siginfo_t {
+        let mut info = siginfo_t::new(self.num, SI_TIMER);
+        info.set_si_timerid(self.timer_id);
+        info.set_si_overrune(self.overrun.pending());
+        info.set_si_value(self.value);
+        info
+    }
+}
+
+impl Drop for TimerSignal {
+    fn drop(&mut self) {
+        // A signal is dropped once it is delivered, ignored or discarded, after
+        // which the timer can send a new one
+        self.overrun.finish_signal();
+    }
+}
+
+/// The overrun of a POSIX timer.
+///
+/// Same as Linux, a timer has at most one signal queued at any time. The
+/// expirations that happen while the signal is queued are counted as the
+/// overrun, which is reported by the signal when it is delivered.
+#[derive(Debug, Default)]
+pub struct TimerOverrun {
+    inner: SgxMutex<TimerOverrunInner>,
+}
+
+#[derive(Debug, Default)]
+struct TimerOverrunInner {
+    is_signal_queued: bool,
+    // The overrun of the queued signal
+    pending: i32,
+    // The overrun of the last delivered signal
+    last: i32,
+}
+
+impl TimerOverrun {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Count the expirations of the timer, returning whether a new signal
+    /// should be sent.
+    pub fn on_expire(&self, num_expirations: u64) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        let should_send_signal = !inner.is_signal_queued;
+        let num_overruns = if should_send_signal {
+            num_expirations.saturating_sub(1)
+        } else {
+            num_expirations
+        };
+        // The overrun is capped at DELAYTIMER_MAX, i.e., i32::MAX
+        let num_overruns = num_overruns.min(i32::MAX as u64) as i32;
+        inner.pending = inner.pending.saturating_add(num_overruns);
+        inner.is_signal_queued = true;
+        should_send_signal
+    }
+
+    /// Get the overrun of the queued signal.
+    pub fn pending(&self) -> i32 {
+        self.inner.lock().unwrap().pending
+    }
+
+    /// Get the overrun of the last delivered signal.
+    pub fn last(&self) -> i32 {
+        self.inner.lock().unwrap().last
+    }
+
+    /// Reset the overruns, e.g., when the timer is rearmed.
+    pub fn reset(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.pending = 0;
+        inner.last = 0;
+    }
+
+    fn finish_signal(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.last = inner.pending;
+        inner.pending = 0;
+        inner.is_signal_queued = false;
+    }
+}
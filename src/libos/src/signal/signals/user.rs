@@ -15,7 +15,12 @@ pub struct UserSignal {
 pub enum UserSignalKind {
     Kill,
     Tkill,
-    Sigqueue(sigval_t),
+    /// Sent by sigqueue with a negative si_code given by the sender, which is
+    /// usually SI_QUEUE, and a value.
+    Sigqueue {
+        code: i32,
+        value: sigval_t,
+    },
 }
 
 unsafe impl Sync for UserSignalKind {}
@@ -53,14 +58,14 @@ impl Signal for UserSignal {
         let code = match self.kind {
             UserSignalKind::Kill => SI_USER,
             UserSignalKind::Tkill => SI_TKILL,
-            UserSignalKind::Sigqueue(_) => SI_QUEUE,
+            UserSignalKind::Sigqueue { code, .. } => code,
         };
 
         let mut info = siginfo_t::new(self.num, code);
         info.set_si_pid(self.pid);
         info.set_si_uid(self.uid);
-        if let UserSignalKind::Sigqueue(val) = self.kind {
-            info.set_si_value(val);
+        if let UserSignalKind::Sigqueue { value, .. } = self.kind {
+            info.set_si_value(value);
         }
 
         info
@@ -3,9 +3,11 @@ use std::time::Duration;
 use super::constants::*;
 use super::do_sigprocmask::MaskOp;
 use super::signals::FaultSignal;
-use super::{sigaction_t, siginfo_t, sigset_t, stack_t, SigAction, SigNum, SigSet, SigStack};
+use super::{
+    sigaction_t, siginfo_t, sigset_t, stack_t, SigAction, SigNum, SigSet, SigStack, SignalFileFlags,
+};
 use crate::prelude::*;
-use crate::process::ProcessFilter;
+use crate::process::{AsPidFile, ProcessFilter};
 use crate::syscall::CpuContext;
 use crate::time::timespec_t;
 use crate::util::mem_util::from_user;
@@ -53,6 +55,8 @@ pub fn do_rt_sigreturn(user_context: *mut CpuContext) -> Result<isize> {
 
 pub fn do_kill(pid: i32, sig: c_int) -> Result<isize> {
     let process_filter = match pid {
+        // The process group of -i32::MIN can not exist
+        i32::MIN => return_errno!(ESRCH, "no such process group"),
         pid if pid < -1 => ProcessFilter::WithPgid((-pid) as pid_t),
         -1 => ProcessFilter::WithAnyPid,
         0 => {
@@ -62,7 +66,7 @@ pub fn do_kill(pid: i32, sig: c_int) -> Result<isize> {
         pid if pid > 0 => ProcessFilter::WithPid(pid as pid_t),
         _ => unreachable!(),
     };
-    let signum = SigNum::from_u8(sig as u8)?;
+    let signum = get_optional_signum(sig)?;
     super::do_kill::do_kill(process_filter, signum)?;
     Ok(0)
 }
@@ -80,6 +84,66 @@ pub fn do_tgkill(pid: i32, tid: pid_t, sig: c_int) -> Result<isize> {
     Ok(0)
 }
 
+pub fn do_rt_sigqueueinfo(pid: pid_t, sig: c_int, info_ptr: *const siginfo_t) -> Result<isize> {
+    let (signum, info) = get_sigqueue_args(sig, info_ptr)?;
+    super::do_sigqueue::do_sigqueue(pid, None, signum, info.si_code, info.si_value())?;
+    Ok(0)
+}
+
+pub fn do_rt_tgsigqueueinfo(
+    pid: pid_t,
+    tid: pid_t,
+    sig: c_int,
+    info_ptr: *const siginfo_t,
+) -> Result<isize> {
+    if pid <= 0 || tid <= 0 {
+        return_errno!(EINVAL, "pid and tid must be positive");
+    }
+    let (signum, info) = get_sigqueue_args(sig, info_ptr)?;
+    super::do_sigqueue::do_sigqueue(pid, Some(tid), signum, info.si_code, info.si_value())?;
+    Ok(0)
+}
+
+pub fn do_pidfd_send_signal(
+    pidfd: FileDesc,
+    sig: c_int,
+    info_ptr: *const siginfo_t,
+    flags: u32,
+) -> Result<isize> {
+    if flags != 0 {
+        return_errno!(EINVAL, "flags must be zero");
+    }
+    let (signum, info) = if info_ptr.is_null() {
+        (get_optional_signum(sig)?, None)
+    } else {
+        let (signum, info) = get_sigqueue_args(sig, info_ptr)?;
+        (signum, Some(info))
+    };
+    let file_ref = current!().file(pidfd)?;
+    let pid_file = file_ref.as_pid_file()?;
+    super::do_kill::do_pidfd_send_signal(pid_file.process(), signum, info)?;
+    Ok(0)
+}
+
+fn get_sigqueue_args(
+    sig: c_int,
+    info_ptr: *const siginfo_t,
+) -> Result<(Option<SigNum>, siginfo_t)> {
+    let signum = get_optional_signum(sig)?;
+    from_user::check_ptr(info_ptr)?;
+    let info = unsafe { *info_ptr };
+    Ok((signum, info))
+}
+
+// The signal number 0 can be used to check the existence of the receiver
+fn get_optional_signum(sig: c_int) -> Result<Option<SigNum>> {
+    match sig {
+        0 => Ok(None),
+        sig if sig > 0 && sig <= u8::max_value() as c_int => Ok(Some(SigNum::from_u8(sig as u8)?)),
+        _ => return_errno!(EINVAL, "invalid signal number"),
+    }
+}
+
 pub fn do_rt_sigprocmask(
     how: c_int,
     set_ptr: *const sigset_t,
@@ -124,6 +188,11 @@ pub fn do_rt_sigpending(buf_ptr: *mut sigset_t, buf_size: usize) -> Result<isize
     Ok(0)
 }
 
+pub fn do_pause() -> Result<isize> {
+    super::do_pause::do_pause()?;
+    Ok(0)
+}
+
 pub fn do_sigaltstack(
     new_ss_c: *const stack_t,
     old_ss_c: *mut stack_t,
@@ -194,3 +263,31 @@ pub fn do_rt_sigtimedwait(
     *info = super::do_sigtimedwait::do_sigtimedwait(mask, timeout.as_ref())?;
     Ok(0)
 }
+
+pub fn do_signalfd(fd: c_int, mask_ptr: *const sigset_t, mask_size: usize) -> Result<isize> {
+    do_signalfd4(fd, mask_ptr, mask_size, 0)
+}
+
+pub fn do_signalfd4(
+    fd: c_int,
+    mask_ptr: *const sigset_t,
+    mask_size: usize,
+    flags: c_int,
+) -> Result<isize> {
+    let mask = {
+        if mask_size != std::mem::size_of::<sigset_t>() {
+            return_errno!(EINVAL, "mask size is not correct");
+        }
+        from_user::check_ptr(mask_ptr)?;
+        SigSet::from_c(unsafe { *mask_ptr })
+    };
+    let fd = match fd {
+        -1 => None,
+        fd if fd >= 0 => Some(fd as FileDesc),
+        _ => return_errno!(EBADF, "invalid fd"),
+    };
+    let flags = SignalFileFlags::from_bits(flags).ok_or_else(|| errno!(EINVAL, "invalid flags"))?;
+
+    let fd = super::do_signalfd::do_signalfd(fd, mask, flags)?;
+    Ok(fd as isize)
+}
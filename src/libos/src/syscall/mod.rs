@@ -16,41 +16,65 @@ use std::ffi::{CStr, CString};
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::mem::MaybeUninit;
 use std::ptr;
-use time::{clockid_t, timespec_t, timeval_t};
+use time::{clockid_t, itimerspec_t, timespec_t, timeval_t};
 use util::log::{self, LevelFilter};
 use util::mem_util::from_user::*;
 
 use crate::exception::do_handle_exception;
 use crate::fs::{
-    do_access, do_chdir, do_chmod, do_chown, do_close, do_dup, do_dup2, do_dup3, do_eventfd,
-    do_eventfd2, do_faccessat, do_fallocate, do_fchmod, do_fchmodat, do_fchown, do_fchownat,
-    do_fcntl, do_fdatasync, do_fstat, do_fstatat, do_fsync, do_ftruncate, do_getcwd, do_getdents,
-    do_getdents64, do_ioctl, do_lchown, do_link, do_linkat, do_lseek, do_lstat, do_mkdir,
-    do_mkdirat, do_mount_rootfs, do_open, do_openat, do_pipe, do_pipe2, do_pread, do_pwrite,
-    do_read, do_readlink, do_readlinkat, do_readv, do_rename, do_renameat, do_rmdir, do_sendfile,
-    do_stat, do_symlink, do_symlinkat, do_sync, do_truncate, do_unlink, do_unlinkat, do_write,
-    do_writev, iovec_t, File, FileDesc, FileRef, HostStdioFds, Stat,
+    aio_context_t, do_access, do_chdir, do_chmod, do_chown, do_close, do_close_range,
+    do_copy_file_range, do_dup, do_dup2, do_dup3, do_eventfd, do_eventfd2, do_faccessat,
+    do_fallocate, do_fchmod, do_fchmodat, do_fchown, do_fchownat, do_fcntl, do_fdatasync,
+    do_fgetxattr, do_flistxattr, do_flock, do_fremovexattr, do_fsetxattr, do_fstat, do_fstatat,
+    do_fstatfs, do_fsync, do_ftruncate, do_futimesat, do_getcwd, do_getdents, do_getdents64,
+    do_getxattr, do_inotify_add_watch, do_inotify_init, do_inotify_init1, do_inotify_rm_watch,
+    do_io_cancel, do_io_destroy, do_io_getevents, do_io_setup, do_io_submit, do_io_uring_enter,
+    do_io_uring_register, do_io_uring_setup, do_ioctl, do_lchown, do_lgetxattr, do_link, do_linkat,
+    do_listxattr, do_llistxattr, do_lremovexattr, do_lseek, do_lsetxattr, do_lstat,
+    do_memfd_create, do_mkdir, do_mkdirat, do_mount, do_mount_rootfs, do_open, do_openat,
+    do_openat2, do_pipe, do_pipe2, do_pread, do_pwrite, do_read, do_readlink, do_readlinkat,
+    do_readv, do_removexattr, do_rename, do_renameat, do_renameat2, do_rmdir, do_sendfile,
+    do_setxattr, do_splice, do_stat, do_statfs, do_symlink, do_symlinkat, do_sync, do_tee,
+    do_timerfd_create, do_timerfd_gettime, do_timerfd_settime, do_truncate, do_umask, do_umount,
+    do_unlink, do_unlinkat, do_utime, do_utimensat, do_utimes, do_vmsplice, do_write, do_writev,
+    io_event_t, io_uring_params_t, iocb_t, iovec_t, utimbuf_t, File, FileDesc, FileRef,
+    HostStdioFds, OpenHow, Stat, Statfs,
 };
 use crate::interrupt::{do_handle_interrupt, sgx_interrupt_info_t};
+use crate::ipc::{
+    do_mq_getsetattr, do_mq_notify, do_mq_open, do_mq_timedreceive, do_mq_timedsend, do_mq_unlink,
+    do_msgctl, do_msgget, do_msgrcv, do_msgsnd, do_semctl, do_semget, do_semop, do_semtimedop,
+    do_shmat, do_shmctl, do_shmdt, do_shmget, key_t, mq_attr_t, msqid_ds_t, sembuf_t, shmid_ds_t,
+};
 use crate::misc::{resource_t, rlimit_t, sysinfo_t, utsname_t};
 use crate::net::{
     do_accept, do_accept4, do_bind, do_connect, do_epoll_create, do_epoll_create1, do_epoll_ctl,
     do_epoll_pwait, do_epoll_wait, do_getpeername, do_getsockname, do_getsockopt, do_listen,
-    do_poll, do_recvfrom, do_recvmsg, do_select, do_sendmsg, do_sendto, do_setsockopt, do_shutdown,
-    do_socket, do_socketpair, msghdr, msghdr_mut,
+    do_poll, do_ppoll, do_pselect6, do_recvfrom, do_recvmmsg, do_recvmsg, do_select, do_sendmmsg,
+    do_sendmsg, do_sendto, do_setsockopt, do_shutdown, do_socket, do_socketpair, mmsghdr,
+    mmsghdr_mut, msghdr, msghdr_mut, sigset_argpack_t,
 };
 use crate::process::{
-    do_arch_prctl, do_clone, do_exit, do_exit_group, do_futex, do_getegid, do_geteuid, do_getgid,
-    do_getpgid, do_getpid, do_getppid, do_gettid, do_getuid, do_prctl, do_set_tid_address,
-    do_spawn_for_glibc, do_spawn_for_musl, do_wait4, pid_t, FdOp, SpawnFileActions, ThreadStatus,
+    do_alarm, do_arch_prctl, do_clone, do_execve, do_execveat, do_exit, do_exit_group, do_fork,
+    do_futex, do_getegid, do_geteuid, do_getgid, do_getitimer, do_getpgid, do_getpgrp, do_getpid,
+    do_getppid, do_getrusage, do_getsid, do_gettid, do_getuid, do_pidfd_open, do_prctl,
+    do_set_tid_address, do_setitimer, do_setpgid, do_setsid, do_spawn_for_glibc, do_spawn_for_musl,
+    do_timer_create, do_timer_delete, do_timer_getoverrun, do_timer_gettime, do_timer_settime,
+    do_vfork, do_wait4, itimerval_t, pid_t, rusage_t, sigevent_t, timer_t, FdOp, SpawnFileActions,
+    ThreadStatus,
+};
+use crate::sched::{
+    do_getcpu, do_getpriority, do_sched_get_priority_max, do_sched_get_priority_min,
+    do_sched_getaffinity, do_sched_getparam, do_sched_getscheduler, do_sched_rr_get_interval,
+    do_sched_setaffinity, do_sched_setparam, do_sched_setscheduler, do_sched_yield, do_setpriority,
+    sched_param_t,
 };
-use crate::sched::{do_getcpu, do_sched_getaffinity, do_sched_setaffinity, do_sched_yield};
 use crate::signal::{
-    do_kill, do_rt_sigaction, do_rt_sigpending, do_rt_sigprocmask, do_rt_sigreturn,
-    do_rt_sigtimedwait, do_sigaltstack, do_tgkill, do_tkill, sigaction_t, siginfo_t, sigset_t,
-    stack_t,
+    do_kill, do_pause, do_pidfd_send_signal, do_rt_sigaction, do_rt_sigpending, do_rt_sigprocmask,
+    do_rt_sigqueueinfo, do_rt_sigreturn, do_rt_sigtimedwait, do_rt_tgsigqueueinfo, do_sigaltstack,
+    do_signalfd, do_signalfd4, do_tgkill, do_tkill, sigaction_t, siginfo_t, sigset_t, stack_t,
 };
-use crate::vm::{MMapFlags, MRemapFlags, MSyncFlags, VMPerms};
+use crate::vm::{MAdvice, MLockAllFlags, MLockFlags, MMapFlags, MRemapFlags, MSyncFlags, VMPerms};
 use crate::{fs, process, std, vm};
 
 use super::*;
@@ -112,17 +136,17 @@ macro_rules! process_syscall_table_with_callback {
             (Mremap = 25) => do_mremap(old_addr: usize, old_size: usize, new_size: usize, flags: i32, new_addr: usize),
             (Msync = 26) => do_msync(addr: usize, size: usize, flags: u32),
             (Mincore = 27) => handle_unsupported(),
-            (Madvise = 28) => handle_unsupported(),
-            (Shmget = 29) => handle_unsupported(),
-            (Shmat = 30) => handle_unsupported(),
-            (Shmctl = 31) => handle_unsupported(),
+            (Madvise = 28) => do_madvise(addr: usize, size: usize, advice: u32),
+            (Shmget = 29) => do_shmget(key: key_t, size: usize, shmflg: i32),
+            (Shmat = 30) => do_shmat(shmid: i32, shmaddr: usize, shmflg: i32),
+            (Shmctl = 31) => do_shmctl(shmid: i32, cmd: i32, buf: *mut shmid_ds_t),
             (Dup = 32) => do_dup(old_fd: FileDesc),
             (Dup2 = 33) => do_dup2(old_fd: FileDesc, new_fd: FileDesc),
-            (Pause = 34) => handle_unsupported(),
+            (Pause = 34) => do_pause(),
             (Nanosleep = 35) => do_nanosleep(req_u: *const timespec_t, rem_u: *mut timespec_t),
-            (Getitimer = 36) => handle_unsupported(),
-            (Alarm = 37) => handle_unsupported(),
-            (Setitimer = 38) => handle_unsupported(),
+            (Getitimer = 36) => do_getitimer(which: i32, curr_value: *mut itimerval_t),
+            (Alarm = 37) => do_alarm(seconds: u32),
+            (Setitimer = 38) => do_setitimer(which: i32, new_value: *const itimerval_t, old_value: *mut itimerval_t),
             (Getpid = 39) => do_getpid(),
             (Sendfile = 40) => do_sendfile(out_fd: FileDesc, in_fd: FileDesc, offset_ptr: *mut off_t, count: usize),
             (Socket = 41) => do_socket(domain: c_int, socket_type: c_int, protocol: c_int),
@@ -141,23 +165,23 @@ macro_rules! process_syscall_table_with_callback {
             (Setsockopt = 54) => do_setsockopt(fd: c_int, level: c_int, optname: c_int, optval: *const c_void, optlen: libc::socklen_t),
             (Getsockopt = 55) => do_getsockopt(fd: c_int, level: c_int, optname: c_int, optval: *mut c_void, optlen: *mut libc::socklen_t),
             (Clone = 56) => do_clone(flags: u32, stack_addr: usize, ptid: *mut pid_t, ctid: *mut pid_t, new_tls: usize),
-            (Fork = 57) => handle_unsupported(),
-            (Vfork = 58) => handle_unsupported(),
-            (Execve = 59) => handle_unsupported(),
+            (Fork = 57) => do_fork(context: *mut CpuContext),
+            (Vfork = 58) => do_vfork(context: *mut CpuContext),
+            (Execve = 59) => do_execve(path: *const i8, argv: *const *const i8, envp: *const *const i8),
             (Exit = 60) => do_exit(exit_status: i32),
-            (Wait4 = 61) => do_wait4(pid: i32, _exit_status: *mut i32),
+            (Wait4 = 61) => do_wait4(pid: i32, _exit_status: *mut i32, options: u32, rusage: *mut rusage_t),
             (Kill = 62) => do_kill(pid: i32, sig: c_int),
             (Uname = 63) => do_uname(name: *mut utsname_t),
-            (Semget = 64) => handle_unsupported(),
-            (Semop = 65) => handle_unsupported(),
-            (Semctl = 66) => handle_unsupported(),
-            (Shmdt = 67) => handle_unsupported(),
-            (Msgget = 68) => handle_unsupported(),
-            (Msgsnd = 69) => handle_unsupported(),
-            (Msgrcv = 70) => handle_unsupported(),
-            (Msgctl = 71) => handle_unsupported(),
+            (Semget = 64) => do_semget(key: key_t, nsems: i32, semflg: i32),
+            (Semop = 65) => do_semop(semid: i32, sops: *const sembuf_t, nsops: usize),
+            (Semctl = 66) => do_semctl(semid: i32, semnum: i32, cmd: i32, arg: usize),
+            (Shmdt = 67) => do_shmdt(shmaddr: usize),
+            (Msgget = 68) => do_msgget(key: key_t, msgflg: i32),
+            (Msgsnd = 69) => do_msgsnd(msqid: i32, msgp: *const u8, msgsz: usize, msgflg: i32),
+            (Msgrcv = 70) => do_msgrcv(msqid: i32, msgp: *mut u8, msgsz: usize, msgtyp: i64, msgflg: i32),
+            (Msgctl = 71) => do_msgctl(msqid: i32, cmd: i32, buf: *mut msqid_ds_t),
             (Fcntl = 72) => do_fcntl(fd: FileDesc, cmd: u32, arg: u64),
-            (Flock = 73) => handle_unsupported(),
+            (Flock = 73) => do_flock(fd: FileDesc, operation: i32),
             (Fsync = 74) => do_fsync(fd: FileDesc),
             (Fdatasync = 75) => do_fdatasync(fd: FileDesc),
             (Truncate = 76) => do_truncate(path: *const i8, len: usize),
@@ -179,10 +203,10 @@ macro_rules! process_syscall_table_with_callback {
             (Chown = 92) => do_chown(path: *const i8, uid: u32, gid: u32),
             (Fchown = 93) => do_fchown(fd: FileDesc, uid: u32, gid: u32),
             (Lchown = 94) => do_lchown(path: *const i8, uid: u32, gid: u32),
-            (Umask = 95) => handle_unsupported(),
+            (Umask = 95) => do_umask(mask: u16),
             (Gettimeofday = 96) => do_gettimeofday(tv_u: *mut timeval_t),
-            (Getrlimit = 97) => handle_unsupported(),
-            (Getrusage = 98) => handle_unsupported(),
+            (Getrlimit = 97) => do_getrlimit(resource: u32, rlim: *mut rlimit_t),
+            (Getrusage = 98) => do_getrusage(who: i32, rusage: *mut rusage_t),
             (SysInfo = 99) => do_sysinfo(info: *mut sysinfo_t),
             (Times = 100) => handle_unsupported(),
             (Ptrace = 101) => handle_unsupported(),
@@ -193,10 +217,10 @@ macro_rules! process_syscall_table_with_callback {
             (Setgid = 106) => handle_unsupported(),
             (Geteuid = 107) => do_geteuid(),
             (Getegid = 108) => do_getegid(),
-            (Setpgid = 109) => handle_unsupported(),
+            (Setpgid = 109) => do_setpgid(pid: i32, pgid: i32),
             (Getppid = 110) => do_getppid(),
-            (Getpgrp = 111) => handle_unsupported(),
-            (Setsid = 112) => handle_unsupported(),
+            (Getpgrp = 111) => do_getpgrp(),
+            (Setsid = 112) => do_setsid(),
             (Setreuid = 113) => handle_unsupported(),
             (Setregid = 114) => handle_unsupported(),
             (Getgroups = 115) => handle_unsupported(),
@@ -205,38 +229,38 @@ macro_rules! process_syscall_table_with_callback {
             (Getresuid = 118) => handle_unsupported(),
             (Setresgid = 119) => handle_unsupported(),
             (Getresgid = 120) => handle_unsupported(),
-            (Getpgid = 121) => do_getpgid(),
+            (Getpgid = 121) => do_getpgid(pid: i32),
             (Setfsuid = 122) => handle_unsupported(),
             (Setfsgid = 123) => handle_unsupported(),
-            (Getsid = 124) => handle_unsupported(),
+            (Getsid = 124) => do_getsid(pid: i32),
             (Capget = 125) => handle_unsupported(),
             (Capset = 126) => handle_unsupported(),
             (RtSigpending = 127) => do_rt_sigpending(buf_ptr: *mut sigset_t, buf_size: usize),
             (RtSigtimedwait = 128) => do_rt_sigtimedwait(mask_ptr: *const sigset_t, info_ptr: *mut siginfo_t, timeout_ptr: *const timespec_t, mask_size: usize),
-            (RtSigqueueinfo = 129) => handle_unsupported(),
+            (RtSigqueueinfo = 129) => do_rt_sigqueueinfo(pid: pid_t, sig: c_int, info: *const siginfo_t),
             (RtSigsuspend = 130) => handle_unsupported(),
             (Sigaltstack = 131) => do_sigaltstack(ss: *const stack_t, old_ss: *mut stack_t, context: *const CpuContext),
-            (Utime = 132) => handle_unsupported(),
+            (Utime = 132) => do_utime(path: *const i8, times: *const utimbuf_t),
             (Mknod = 133) => handle_unsupported(),
             (Uselib = 134) => handle_unsupported(),
             (Personality = 135) => handle_unsupported(),
             (Ustat = 136) => handle_unsupported(),
-            (Statfs = 137) => handle_unsupported(),
-            (Fstatfs = 138) => handle_unsupported(),
+            (Statfs = 137) => do_statfs(path: *const i8, statfs_buf: *mut Statfs),
+            (Fstatfs = 138) => do_fstatfs(fd: FileDesc, statfs_buf: *mut Statfs),
             (SysFs = 139) => handle_unsupported(),
-            (Getpriority = 140) => handle_unsupported(),
-            (Setpriority = 141) => handle_unsupported(),
-            (SchedSetparam = 142) => handle_unsupported(),
-            (SchedGetparam = 143) => handle_unsupported(),
-            (SchedSetscheduler = 144) => handle_unsupported(),
-            (SchedGetscheduler = 145) => handle_unsupported(),
-            (SchedGetPriorityMax = 146) => handle_unsupported(),
-            (SchedGetPriorityMin = 147) => handle_unsupported(),
-            (SchedRrGetInterval = 148) => handle_unsupported(),
-            (Mlock = 149) => handle_unsupported(),
-            (Munlock = 150) => handle_unsupported(),
-            (Mlockall = 151) => handle_unsupported(),
-            (Munlockall = 152) => handle_unsupported(),
+            (Getpriority = 140) => do_getpriority(which: i32, who: i32),
+            (Setpriority = 141) => do_setpriority(which: i32, who: i32, nice: i32),
+            (SchedSetparam = 142) => do_sched_setparam(pid: pid_t, param: *const sched_param_t),
+            (SchedGetparam = 143) => do_sched_getparam(pid: pid_t, param: *mut sched_param_t),
+            (SchedSetscheduler = 144) => do_sched_setscheduler(pid: pid_t, policy: i32, param: *const sched_param_t),
+            (SchedGetscheduler = 145) => do_sched_getscheduler(pid: pid_t),
+            (SchedGetPriorityMax = 146) => do_sched_get_priority_max(policy: i32),
+            (SchedGetPriorityMin = 147) => do_sched_get_priority_min(policy: i32),
+            (SchedRrGetInterval = 148) => do_sched_rr_get_interval(pid: pid_t, interval: *mut timespec_t),
+            (Mlock = 149) => do_mlock(addr: usize, len: usize),
+            (Munlock = 150) => do_munlock(addr: usize, len: usize),
+            (Mlockall = 151) => do_mlockall(flags: u32),
+            (Munlockall = 152) => do_munlockall(),
             (Vhangup = 153) => handle_unsupported(),
             (ModifyLdt = 154) => handle_unsupported(),
             (PivotRoot = 155) => handle_unsupported(),
@@ -244,13 +268,13 @@ macro_rules! process_syscall_table_with_callback {
             (Prctl = 157) => do_prctl(option: i32, arg2: u64, arg3: u64, arg4: u64, arg5: u64),
             (ArchPrctl = 158) => do_arch_prctl(code: u32, addr: *mut usize),
             (Adjtimex = 159) => handle_unsupported(),
-            (Setrlimit = 160) => handle_unsupported(),
+            (Setrlimit = 160) => do_setrlimit(resource: u32, rlim: *const rlimit_t),
             (Chroot = 161) => handle_unsupported(),
             (Sync = 162) => do_sync(),
             (Acct = 163) => handle_unsupported(),
             (Settimeofday = 164) => handle_unsupported(),
-            (Mount = 165) => handle_unsupported(),
-            (Umount2 = 166) => handle_unsupported(),
+            (Mount = 165) => do_mount(source: *const i8, target: *const i8, fs_type: *const i8, flags: u32, data: *const u8),
+            (Umount2 = 166) => do_umount(target: *const i8, flags: u32),
             (Swapon = 167) => handle_unsupported(),
             (Swapoff = 168) => handle_unsupported(),
             (Reboot = 169) => handle_unsupported(),
@@ -272,29 +296,29 @@ macro_rules! process_syscall_table_with_callback {
             (Security = 185) => handle_unsupported(),
             (Gettid = 186) => do_gettid(),
             (Readahead = 187) => handle_unsupported(),
-            (Setxattr = 188) => handle_unsupported(),
-            (Lsetxattr = 189) => handle_unsupported(),
-            (Fsetxattr = 190) => handle_unsupported(),
-            (Getxattr = 191) => handle_unsupported(),
-            (Lgetxattr = 192) => handle_unsupported(),
-            (Fgetxattr = 193) => handle_unsupported(),
-            (Listxattr = 194) => handle_unsupported(),
-            (Llistxattr = 195) => handle_unsupported(),
-            (Flistxattr = 196) => handle_unsupported(),
-            (Removexattr = 197) => handle_unsupported(),
-            (Lremovexattr = 198) => handle_unsupported(),
-            (Fremovexattr = 199) => handle_unsupported(),
+            (Setxattr = 188) => do_setxattr(path: *const i8, name: *const i8, value: *const u8, size: usize, flags: i32),
+            (Lsetxattr = 189) => do_lsetxattr(path: *const i8, name: *const i8, value: *const u8, size: usize, flags: i32),
+            (Fsetxattr = 190) => do_fsetxattr(fd: FileDesc, name: *const i8, value: *const u8, size: usize, flags: i32),
+            (Getxattr = 191) => do_getxattr(path: *const i8, name: *const i8, value: *mut u8, size: usize),
+            (Lgetxattr = 192) => do_lgetxattr(path: *const i8, name: *const i8, value: *mut u8, size: usize),
+            (Fgetxattr = 193) => do_fgetxattr(fd: FileDesc, name: *const i8, value: *mut u8, size: usize),
+            (Listxattr = 194) => do_listxattr(path: *const i8, list: *mut u8, size: usize),
+            (Llistxattr = 195) => do_llistxattr(path: *const i8, list: *mut u8, size: usize),
+            (Flistxattr = 196) => do_flistxattr(fd: FileDesc, list: *mut u8, size: usize),
+            (Removexattr = 197) => do_removexattr(path: *const i8, name: *const i8),
+            (Lremovexattr = 198) => do_lremovexattr(path: *const i8, name: *const i8),
+            (Fremovexattr = 199) => do_fremovexattr(fd: FileDesc, name: *const i8),
             (Tkill = 200) => do_tkill(tid: pid_t, sig: c_int),
             (Time = 201) => handle_unsupported(),
             (Futex = 202) => do_futex(futex_addr: *const i32, futex_op: u32, futex_val: i32, timeout: u64, futex_new_addr: *const i32, bitset: u32),
             (SchedSetaffinity = 203) => do_sched_setaffinity(pid: pid_t, cpusize: size_t, buf: *const c_uchar),
             (SchedGetaffinity = 204) => do_sched_getaffinity(pid: pid_t, cpusize: size_t, buf: *mut c_uchar),
             (SetThreadArea = 205) => handle_unsupported(),
-            (IoSetup = 206) => handle_unsupported(),
-            (IoDestroy = 207) => handle_unsupported(),
-            (IoGetevents = 208) => handle_unsupported(),
-            (IoSubmit = 209) => handle_unsupported(),
-            (IoCancel = 210) => handle_unsupported(),
+            (IoSetup = 206) => do_io_setup(nr_events: u32, ctx_idp: *mut aio_context_t),
+            (IoDestroy = 207) => do_io_destroy(ctx_id: aio_context_t),
+            (IoGetevents = 208) => do_io_getevents(ctx_id: aio_context_t, min_nr: i64, nr: i64, events: *mut io_event_t, timeout: *const timespec_t),
+            (IoSubmit = 209) => do_io_submit(ctx_id: aio_context_t, nr: i64, iocbpp: *const *const iocb_t),
+            (IoCancel = 210) => do_io_cancel(ctx_id: aio_context_t, iocb: *const iocb_t, result: *mut io_event_t),
             (GetThreadArea = 211) => handle_unsupported(),
             (LookupDcookie = 212) => handle_unsupported(),
             (EpollCreate = 213) => do_epoll_create(size: c_int),
@@ -304,13 +328,13 @@ macro_rules! process_syscall_table_with_callback {
             (Getdents64 = 217) => do_getdents64(fd: FileDesc, buf: *mut u8, buf_size: usize),
             (SetTidAddress = 218) => do_set_tid_address(tidptr: *mut pid_t),
             (RestartSysCall = 219) => handle_unsupported(),
-            (Semtimedop = 220) => handle_unsupported(),
+            (Semtimedop = 220) => do_semtimedop(semid: i32, sops: *const sembuf_t, nsops: usize, timeout: *const timespec_t),
             (Fadvise64 = 221) => handle_unsupported(),
-            (TimerCreate = 222) => handle_unsupported(),
-            (TimerSettime = 223) => handle_unsupported(),
-            (TimerGettime = 224) => handle_unsupported(),
-            (TimerGetoverrun = 225) => handle_unsupported(),
-            (TimerDelete = 226) => handle_unsupported(),
+            (TimerCreate = 222) => do_timer_create(clockid: clockid_t, sevp: *const sigevent_t, timerid: *mut timer_t),
+            (TimerSettime = 223) => do_timer_settime(timerid: timer_t, flags: i32, new_value: *const itimerspec_t, old_value: *mut itimerspec_t),
+            (TimerGettime = 224) => do_timer_gettime(timerid: timer_t, curr_value: *mut itimerspec_t),
+            (TimerGetoverrun = 225) => do_timer_getoverrun(timerid: timer_t),
+            (TimerDelete = 226) => do_timer_delete(timerid: timer_t),
             (ClockSettime = 227) => handle_unsupported(),
             (ClockGettime = 228) => do_clock_gettime(clockid: clockid_t, ts_u: *mut timespec_t),
             (ClockGetres = 229) => do_clock_getres(clockid: clockid_t, res_u: *mut timespec_t),
@@ -319,17 +343,17 @@ macro_rules! process_syscall_table_with_callback {
             (EpollWait = 232) => do_epoll_wait(epfd: c_int, events: *mut libc::epoll_event, maxevents: c_int, timeout: c_int),
             (EpollCtl = 233) => do_epoll_ctl(epfd: c_int, op: c_int, fd: c_int, event: *const libc::epoll_event),
             (Tgkill = 234) => do_tgkill(pid: i32, tid: pid_t, sig: c_int),
-            (Utimes = 235) => handle_unsupported(),
+            (Utimes = 235) => do_utimes(path: *const i8, times: *const timeval_t),
             (Vserver = 236) => handle_unsupported(),
             (Mbind = 237) => handle_unsupported(),
             (SetMempolicy = 238) => handle_unsupported(),
             (GetMempolicy = 239) => handle_unsupported(),
-            (MqOpen = 240) => handle_unsupported(),
-            (MqUnlink = 241) => handle_unsupported(),
-            (MqTimedsend = 242) => handle_unsupported(),
-            (MqTimedreceive = 243) => handle_unsupported(),
-            (MqNotify = 244) => handle_unsupported(),
-            (MqGetsetattr = 245) => handle_unsupported(),
+            (MqOpen = 240) => do_mq_open(name: *const i8, oflag: i32, mode: u32, attr: *const mq_attr_t),
+            (MqUnlink = 241) => do_mq_unlink(name: *const i8),
+            (MqTimedsend = 242) => do_mq_timedsend(mqdes: FileDesc, msg_ptr: *const u8, msg_len: usize, msg_prio: u32, abs_timeout: *const timespec_t),
+            (MqTimedreceive = 243) => do_mq_timedreceive(mqdes: FileDesc, msg_ptr: *mut u8, msg_len: usize, msg_prio: *mut u32, abs_timeout: *const timespec_t),
+            (MqNotify = 244) => do_mq_notify(mqdes: FileDesc, sevp: *const sigevent_t),
+            (MqGetsetattr = 245) => do_mq_getsetattr(mqdes: FileDesc, newattr: *const mq_attr_t, oldattr: *mut mq_attr_t),
             (KexecLoad = 246) => handle_unsupported(),
             (Waitid = 247) => handle_unsupported(),
             (AddKey = 248) => handle_unsupported(),
@@ -337,15 +361,15 @@ macro_rules! process_syscall_table_with_callback {
             (Keyctl = 250) => handle_unsupported(),
             (IoprioSet = 251) => handle_unsupported(),
             (IoprioGet = 252) => handle_unsupported(),
-            (InotifyInit = 253) => handle_unsupported(),
-            (InotifyAddWatch = 254) => handle_unsupported(),
-            (InotifyRmWatch = 255) => handle_unsupported(),
+            (InotifyInit = 253) => do_inotify_init(),
+            (InotifyAddWatch = 254) => do_inotify_add_watch(fd: FileDesc, path: *const i8, mask: u32),
+            (InotifyRmWatch = 255) => do_inotify_rm_watch(fd: FileDesc, wd: i32),
             (MigratePages = 256) => handle_unsupported(),
             (Openat = 257) => do_openat(dirfd: i32, path: *const i8, flags: u32, mode: u32),
             (Mkdirat = 258) => do_mkdirat(dirfd: i32, path: *const i8, mode: usize),
             (Mknodat = 259) => handle_unsupported(),
             (Fchownat = 260) => do_fchownat(dirfd: i32, path: *const i8, uid: u32, gid: u32, flags: i32),
-            (Futimesat = 261) => handle_unsupported(),
+            (Futimesat = 261) => do_futimesat(dirfd: i32, path: *const i8, times: *const timeval_t),
             (Fstatat = 262) => do_fstatat(dirfd: i32, path: *const i8, stat_buf: *mut Stat, flags: u32),
             (Unlinkat = 263) => do_unlinkat(dirfd: i32, path: *const i8, flags: i32),
             (Renameat = 264) => do_renameat(olddirfd: i32, oldpath: *const i8, newdirfd: i32, newpath: *const i8),
@@ -354,36 +378,36 @@ macro_rules! process_syscall_table_with_callback {
             (Readlinkat = 267) => do_readlinkat(dirfd: i32, path: *const i8, buf: *mut u8, size: usize),
             (Fchmodat = 268) => do_fchmodat(dirfd: i32, path: *const i8, mode: u16),
             (Faccessat = 269) => do_faccessat(dirfd: i32, path: *const i8, mode: u32, flags: u32),
-            (Pselect6 = 270) => handle_unsupported(),
-            (Ppoll = 271) => handle_unsupported(),
+            (Pselect6 = 270) => do_pselect6(nfds: c_int, readfds: *mut libc::fd_set, writefds: *mut libc::fd_set, exceptfds: *mut libc::fd_set, timeout: *mut timespec_t, sigmask_argpack: *const sigset_argpack_t),
+            (Ppoll = 271) => do_ppoll(fds: *mut libc::pollfd, nfds: libc::nfds_t, timeout: *mut timespec_t, sigmask: *const sigset_t, sigset_size: usize),
             (Unshare = 272) => handle_unsupported(),
             (SetRobustList = 273) => handle_unsupported(),
             (GetRobustList = 274) => handle_unsupported(),
-            (Splice = 275) => handle_unsupported(),
-            (Tee = 276) => handle_unsupported(),
+            (Splice = 275) => do_splice(fd_in: FileDesc, off_in: *mut off_t, fd_out: FileDesc, off_out: *mut off_t, len: usize, flags: u32),
+            (Tee = 276) => do_tee(fd_in: FileDesc, fd_out: FileDesc, len: usize, flags: u32),
             (SyncFileRange = 277) => handle_unsupported(),
-            (Vmsplice = 278) => handle_unsupported(),
+            (Vmsplice = 278) => do_vmsplice(fd: FileDesc, iov: *mut iovec_t, count: usize, flags: u32),
             (MovePages = 279) => handle_unsupported(),
-            (Utimensat = 280) => handle_unsupported(),
-            (EpollPwait = 281) => do_epoll_pwait(epfd: c_int, events: *mut libc::epoll_event, maxevents: c_int, timeout: c_int, sigmask: *const usize),
-            (Signalfd = 282) => handle_unsupported(),
-            (TimerfdCreate = 283) => handle_unsupported(),
+            (Utimensat = 280) => do_utimensat(dirfd: i32, path: *const i8, times: *const timespec_t, flags: i32),
+            (EpollPwait = 281) => do_epoll_pwait(epfd: c_int, events: *mut libc::epoll_event, maxevents: c_int, timeout: c_int, sigmask: *const sigset_t, sigset_size: usize),
+            (Signalfd = 282) => do_signalfd(fd: c_int, mask_ptr: *const sigset_t, mask_size: usize),
+            (TimerfdCreate = 283) => do_timerfd_create(clockid: clockid_t, flags: i32),
             (Eventfd = 284) => do_eventfd(init_val: u32),
             (Fallocate = 285) => do_fallocate(fd: FileDesc, mode: u32, offset: off_t, len: off_t),
-            (TimerfdSettime = 286) => handle_unsupported(),
-            (TimerfdGettime = 287) => handle_unsupported(),
+            (TimerfdSettime = 286) => do_timerfd_settime(fd: FileDesc, flags: i32, new_value: *const itimerspec_t, old_value: *mut itimerspec_t),
+            (TimerfdGettime = 287) => do_timerfd_gettime(fd: FileDesc, curr_value: *mut itimerspec_t),
             (Accept4 = 288) => do_accept4(fd: c_int, addr: *mut libc::sockaddr, addr_len: *mut libc::socklen_t, flags: c_int),
-            (Signalfd4 = 289) => handle_unsupported(),
+            (Signalfd4 = 289) => do_signalfd4(fd: c_int, mask_ptr: *const sigset_t, mask_size: usize, flags: c_int),
             (Eventfd2 = 290) => do_eventfd2(init_val: u32, flags: i32),
             (EpollCreate1 = 291) => do_epoll_create1(flags: c_int),
             (Dup3 = 292) => do_dup3(old_fd: FileDesc, new_fd: FileDesc, flags: u32),
             (Pipe2 = 293) => do_pipe2(fds_u: *mut i32, flags: u32),
-            (InotifyInit1 = 294) => handle_unsupported(),
+            (InotifyInit1 = 294) => do_inotify_init1(flags: i32),
             (Preadv = 295) => handle_unsupported(),
             (Pwritev = 296) => handle_unsupported(),
-            (RtTgsigqueueinfo = 297) => handle_unsupported(),
+            (RtTgsigqueueinfo = 297) => do_rt_tgsigqueueinfo(pid: pid_t, tid: pid_t, sig: c_int, info: *const siginfo_t),
             (PerfEventOpen = 298) => handle_unsupported(),
-            (Recvmmsg = 299) => handle_unsupported(),
+            (Recvmmsg = 299) => do_recvmmsg(fd: c_int, msgvec: *mut mmsghdr_mut, vlen: u32, flags_c: c_int, timeout: *const timespec_t),
             (FanotifyInit = 300) => handle_unsupported(),
             (FanotifyMark = 301) => handle_unsupported(),
             (Prlimit64 = 302) => do_prlimit(pid: pid_t, resource: u32, new_limit: *const rlimit_t, old_limit: *mut rlimit_t),
@@ -391,7 +415,7 @@ macro_rules! process_syscall_table_with_callback {
             (OpenByHandleAt = 304) => handle_unsupported(),
             (ClockAdjtime = 305) => handle_unsupported(),
             (Syncfs = 306) => handle_unsupported(),
-            (Sendmmsg = 307) => handle_unsupported(),
+            (Sendmmsg = 307) => do_sendmmsg(fd: c_int, msgvec: *mut mmsghdr, vlen: u32, flags_c: c_int),
             (Setns = 308) => handle_unsupported(),
             (Getcpu = 309) => do_getcpu(cpu_ptr: *mut u32, node_ptr: *mut u32),
             (ProcessVmReadv = 310) => handle_unsupported(),
@@ -400,16 +424,42 @@ macro_rules! process_syscall_table_with_callback {
             (FinitModule = 313) => handle_unsupported(),
             (SchedSetattr = 314) => handle_unsupported(),
             (SchedGetattr = 315) => handle_unsupported(),
-            (Renameat2 = 316) => handle_unsupported(),
+            (Renameat2 = 316) => do_renameat2(olddirfd: i32, oldpath: *const i8, newdirfd: i32, newpath: *const i8, flags: u32),
             (Seccomp = 317) => handle_unsupported(),
             (Getrandom = 318) => handle_unsupported(),
-            (MemfdCreate = 319) => handle_unsupported(),
+            (MemfdCreate = 319) => do_memfd_create(name: *const i8, flags: u32),
             (KexecFileLoad = 320) => handle_unsupported(),
             (Bpf = 321) => handle_unsupported(),
-            (Execveat = 322) => handle_unsupported(),
+            (Execveat = 322) => do_execveat(dirfd: i32, path: *const i8, argv: *const *const i8, envp: *const *const i8, flags: i32),
             (Userfaultfd = 323) => handle_unsupported(),
             (Membarrier = 324) => handle_unsupported(),
-            (Mlock2 = 325) => handle_unsupported(),
+            (Mlock2 = 325) => do_mlock2(addr: usize, len: usize, flags: u32),
+            (CopyFileRange = 326) => do_copy_file_range(fd_in: FileDesc, off_in: *mut off_t, fd_out: FileDesc, off_out: *mut off_t, len: usize, flags: u32),
+            (Preadv2 = 327) => handle_unsupported(),
+            (Pwritev2 = 328) => handle_unsupported(),
+            (PkeyMprotect = 329) => handle_unsupported(),
+            (PkeyAlloc = 330) => handle_unsupported(),
+            (PkeyFree = 331) => handle_unsupported(),
+            (Statx = 332) => handle_unsupported(),
+            (IoPgetevents = 333) => handle_unsupported(),
+            (Rseq = 334) => handle_unsupported(),
+            (PidfdSendSignal = 424) => do_pidfd_send_signal(pidfd: FileDesc, sig: c_int, info_ptr: *const siginfo_t, flags: u32),
+            (IoUringSetup = 425) => do_io_uring_setup(entries: u32, params: *mut io_uring_params_t),
+            (IoUringEnter = 426) => do_io_uring_enter(fd: FileDesc, to_submit: u32, min_complete: u32, flags: u32, arg: *const c_void, argsz: usize),
+            (IoUringRegister = 427) => do_io_uring_register(fd: FileDesc, opcode: u32, arg: *const c_void, nr_args: u32),
+            (OpenTree = 428) => handle_unsupported(),
+            (MoveMount = 429) => handle_unsupported(),
+            (Fsopen = 430) => handle_unsupported(),
+            (Fsconfig = 431) => handle_unsupported(),
+            (Fsmount = 432) => handle_unsupported(),
+            (Fspick = 433) => handle_unsupported(),
+            (PidfdOpen = 434) => do_pidfd_open(pid: i32, flags: u32),
+            (Clone3 = 435) => handle_unsupported(),
+            (CloseRange = 436) => do_close_range(first: FileDesc, last: FileDesc, flags: u32),
+            (Openat2 = 437) => do_openat2(dirfd: i32, path: *const i8, how: *const OpenHow, size: usize),
+            (PidfdGetfd = 438) => handle_unsupported(),
+            (Faccessat2 = 439) => handle_unsupported(),
+            (ProcessMadvise = 440) => handle_unsupported(),
 
             // Occlum-specific system calls
             (SpawnGlibc = 359) => do_spawn_for_glibc(child_pid_ptr: *mut u32, path: *const i8, argv: *const *const i8, envp: *const *const i8, fa: *const SpawnFileActions),
@@ -623,9 +673,12 @@ fn do_syscall(user_context: &mut CpuContext) {
         trace!("{:?}", &syscall);
         let syscall_num = syscall.num;
 
-        // Pass user_context as an extra argument to two special syscalls that
-        // need to modify it
-        if syscall_num == SyscallNum::RtSigreturn {
+        // Pass user_context as an extra argument to the special syscalls that
+        // need to access it
+        if syscall_num == SyscallNum::RtSigreturn
+            || syscall_num == SyscallNum::Fork
+            || syscall_num == SyscallNum::Vfork
+        {
             syscall.args[0] = user_context as *mut _ as isize;
         } else if syscall_num == SyscallNum::HandleException {
             // syscall.args[0] == info
@@ -712,9 +765,56 @@ fn do_syscall(user_context: &mut CpuContext) {
         user_context.rax = retval as u64;
     }
 
-    crate::signal::deliver_signal(user_context);
+    crate::process::handle_cpu_itimers();
+
+    // A syscall that fails with EINTR is interrupted by signals, which may
+    // restart it after the signals are delivered
+    let interrupted_syscall = SyscallNum::try_from(num)
+        .ok()
+        .filter(|num| retval == -(EINTR as isize) && num.is_restartable());
+    crate::signal::deliver_signal(user_context, interrupted_syscall);
 
     crate::process::handle_force_exit();
+
+    // A forked child runs in the host thread of its parent, which resumes
+    // when the child exits
+    while crate::process::resume_fork_parent(user_context) {
+        crate::signal::deliver_signal(user_context, None);
+        crate::process::handle_force_exit();
+    }
+}
+
+impl SyscallNum {
+    /// Check whether the syscall can be restarted after being interrupted by
+    /// signals.
+    ///
+    /// Same as Linux, the syscalls that wait for I/O, locks or child processes
+    /// are restartable, while those that wait for a given time, e.g., nanosleep
+    /// and poll, are not.
+    fn is_restartable(&self) -> bool {
+        use SyscallNum::*;
+        match *self {
+            Read | Write | Pread64 | Pwrite64 | Readv | Writev | Open | Openat | Ioctl | Fcntl
+            | Flock | Accept | Accept4 | Sendto | Recvfrom | Sendmsg | Recvmsg | Sendfile
+            | Splice | Tee | CopyFileRange | Wait4 | Futex => true,
+            _ => false,
+        }
+    }
+}
+
+/// Make the user space execute the given syscall again when it returns to the
+/// CPU context of the syscall.
+///
+/// The arguments of the syscall are kept intact in the CPU context. So the
+/// syscall is restarted by jumping to the syscall entry with the syscall number
+/// in RAX and the return address in RCX.
+pub fn restart_syscall(user_context: &mut CpuContext, num: SyscallNum) {
+    extern "C" {
+        fn __occlum_syscall_linux_abi();
+    }
+    user_context.rax = num as u64;
+    user_context.rcx = user_context.rip;
+    user_context.rip = __occlum_syscall_linux_abi as usize as u64;
 }
 
 /// Return to the user space according to the given CPU context
@@ -795,6 +895,39 @@ fn do_mprotect(addr: usize, len: usize, perms: u32) -> Result<isize> {
     Ok(0)
 }
 
+fn do_madvise(addr: usize, size: usize, advice: u32) -> Result<isize> {
+    let advice = MAdvice::from_u32(advice)?;
+    vm::do_madvise(addr, size, advice)?;
+    Ok(0)
+}
+
+fn do_mlock(addr: usize, len: usize) -> Result<isize> {
+    vm::do_mlock(addr, len, MLockFlags::empty())?;
+    Ok(0)
+}
+
+fn do_mlock2(addr: usize, len: usize, flags: u32) -> Result<isize> {
+    let flags = MLockFlags::from_u32(flags)?;
+    vm::do_mlock(addr, len, flags)?;
+    Ok(0)
+}
+
+fn do_munlock(addr: usize, len: usize) -> Result<isize> {
+    vm::do_munlock(addr, len)?;
+    Ok(0)
+}
+
+fn do_mlockall(flags: u32) -> Result<isize> {
+    let flags = MLockAllFlags::from_u32(flags)?;
+    vm::do_mlockall(flags)?;
+    Ok(0)
+}
+
+fn do_munlockall() -> Result<isize> {
+    vm::do_munlockall()?;
+    Ok(0)
+}
+
 fn do_brk(new_brk_addr: usize) -> Result<isize> {
     let ret_brk_addr = vm::do_brk(new_brk_addr)?;
     Ok(ret_brk_addr as isize)
@@ -894,6 +1027,20 @@ fn do_prlimit(
     misc::do_prlimit(pid, resource, new_limit, old_limit).map(|_| 0)
 }
 
+fn do_getrlimit(resource: u32, rlim: *mut rlimit_t) -> Result<isize> {
+    let resource = resource_t::from_u32(resource)?;
+    check_mut_ptr(rlim)?;
+    let rlim = unsafe { &mut *rlim };
+    misc::do_getrlimit(resource, rlim).map(|_| 0)
+}
+
+fn do_setrlimit(resource: u32, rlim: *const rlimit_t) -> Result<isize> {
+    let resource = resource_t::from_u32(resource)?;
+    check_ptr(rlim)?;
+    let rlim = unsafe { &*rlim };
+    misc::do_setrlimit(resource, rlim).map(|_| 0)
+}
+
 fn handle_unsupported() -> Result<isize> {
     return_errno!(ENOSYS, "Unimplemented or unknown syscall")
 }
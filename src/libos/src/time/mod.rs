@@ -9,10 +9,13 @@ use std::{fmt, u64};
 use syscall::SyscallNum;
 
 mod profiler;
+pub mod timer;
 pub mod timer_slack;
+pub mod timer_wheel;
 pub mod up_time;
 
 pub use profiler::ThreadProfiler;
+pub use timer::Timer;
 pub use timer_slack::TIMERSLACK;
 
 #[allow(non_camel_case_types)]
@@ -48,6 +51,10 @@ impl timeval_t {
         }
     }
 
+    pub fn sec(&self) -> time_t {
+        self.sec
+    }
+
     pub fn as_duration(&self) -> Duration {
         Duration::new(self.sec as u64, (self.usec * 1_000) as u32)
     }
@@ -120,6 +127,43 @@ impl timespec_t {
     }
 }
 
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct itimerspec_t {
+    it_interval: timespec_t,
+    it_value: timespec_t,
+}
+
+impl itimerspec_t {
+    pub fn new(value: Duration, interval: Duration) -> Self {
+        Self {
+            it_interval: interval.into(),
+            it_value: value.into(),
+        }
+    }
+
+    pub fn from_raw_ptr(ptr: *const itimerspec_t) -> Result<itimerspec_t> {
+        let its = unsafe { *ptr };
+        its.validate()?;
+        Ok(its)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        self.it_interval.validate()?;
+        self.it_value.validate()?;
+        Ok(())
+    }
+
+    pub fn interval(&self) -> &timespec_t {
+        &self.it_interval
+    }
+
+    pub fn value(&self) -> &timespec_t {
+        &self.it_value
+    }
+}
+
 #[allow(non_camel_case_types)]
 pub type clockid_t = i32;
 
@@ -196,21 +240,29 @@ pub fn do_nanosleep(req: &timespec_t, rem: Option<&mut timespec_t>) -> Result<()
             rem: *mut timespec_t,
         ) -> sgx_status_t;
     }
-    unsafe {
+    let mut req = *req;
+    loop {
         let mut ret = 0;
         let mut u_rem: timespec_t = timespec_t { sec: 0, nsec: 0 };
-        let sgx_status = occlum_ocall_nanosleep(&mut ret, req, &mut u_rem);
-        assert!(sgx_status == sgx_status_t::SGX_SUCCESS);
-        assert!(ret == 0 || libc::errno() == Errno::EINTR as i32);
-        if ret != 0 {
-            assert!(u_rem.as_duration() <= req.as_duration() + (*TIMERSLACK).to_duration());
+        unsafe {
+            let sgx_status = occlum_ocall_nanosleep(&mut ret, &req, &mut u_rem);
+            assert!(sgx_status == sgx_status_t::SGX_SUCCESS);
+            assert!(ret == 0 || libc::errno() == Errno::EINTR as i32);
+        }
+        if ret == 0 {
+            return Ok(());
+        }
+        assert!(u_rem.as_duration() <= req.as_duration() + (*TIMERSLACK).to_duration());
+        // The host sleep may be interrupted by signals that are not for the
+        // LibOS thread, e.g., the blocked ones. Keep sleeping in that case.
+        if crate::signal::has_interrupting_signals(&current!()) {
             if let Some(rem) = rem {
                 *rem = u_rem;
             }
             return_errno!(EINTR, "sleep interrupted");
         }
+        req = u_rem;
     }
-    Ok(())
 }
 
 pub fn do_thread_getcpuclock() -> Result<timespec_t> {
@@ -229,6 +281,30 @@ pub fn do_thread_getcpuclock() -> Result<timespec_t> {
     Ok(tv)
 }
 
+/// Get the CPU time consumed by the current host thread in user mode and in
+/// kernel mode, respectively.
+pub fn do_thread_getrusage() -> Result<(timeval_t, timeval_t)> {
+    extern "C" {
+        fn occlum_ocall_thread_getrusage(
+            ret: *mut c_int,
+            utime: *mut timeval_t,
+            stime: *mut timeval_t,
+        ) -> sgx_status_t;
+    }
+
+    let mut utime: timeval_t = Default::default();
+    let mut stime: timeval_t = Default::default();
+    try_libc!({
+        let mut retval: i32 = 0;
+        let status = occlum_ocall_thread_getrusage(&mut retval, &mut utime, &mut stime);
+        assert!(status == sgx_status_t::SGX_SUCCESS);
+        retval
+    });
+    utime.validate()?;
+    stime.validate()?;
+    Ok((utime, stime))
+}
+
 pub fn do_rdtsc() -> (u32, u32) {
     extern "C" {
         fn occlum_ocall_rdtsc(low: *mut u32, high: *mut u32) -> sgx_status_t;
@@ -0,0 +1,114 @@
+//! The timers of LibOS.
+//!
+//! A `Timer` measures the time of a clock and notifies its owner through a
+//! callback when it expires. All kinds of timers inside LibOS, i.e., POSIX
+//! timers, timerfd and the ITIMER_REAL of processes, are built on it, and are
+//! driven by the same timer wheel.
+
+use super::timer_wheel::{self, TimerCallback, TimerId, TimerState};
+use super::*;
+
+pub struct Timer {
+    clockid: ClockID,
+    callback: TimerCallback,
+    // The timer in the timer wheel, if armed
+    wheel_timer: SgxMutex<Option<TimerId>>,
+}
+
+impl Timer {
+    /// Create a disarmed timer of the clock.
+    ///
+    /// The callback is given the number of expirations since its last
+    /// invocation.
+    pub fn new(clockid: ClockID, callback: TimerCallback) -> Result<Self> {
+        match clockid {
+            ClockID::CLOCK_REALTIME | ClockID::CLOCK_MONOTONIC | ClockID::CLOCK_BOOTTIME => {}
+            _ => return_errno!(EINVAL, "the clock is not supported by timers"),
+        }
+        Ok(Self {
+            clockid,
+            callback,
+            wheel_timer: SgxMutex::new(None),
+        })
+    }
+
+    pub fn clockid(&self) -> ClockID {
+        self.clockid
+    }
+
+    /// Arm or disarm the timer, returning the old setting of the timer.
+    ///
+    /// If is_abs is true, the value of the new setting is an absolute time of
+    /// the clock. Otherwise, it is relative to the current time.
+    pub fn set(&self, new_value: &itimerspec_t, is_abs: bool) -> Result<itimerspec_t> {
+        new_value.validate()?;
+
+        let mut wheel_timer = self.wheel_timer.lock().unwrap();
+        let old_state = wheel_timer.take().and_then(timer_wheel::cancel_timer);
+        let old_value = Self::state_to_itimerspec(old_state);
+
+        let value = new_value.value().as_duration();
+        if value == Duration::from_secs(0) {
+            return Ok(old_value);
+        }
+        let now = timer_wheel::now();
+        let deadline = if is_abs {
+            // Convert the time of the clock to that of the timer wheel. A
+            // deadline in the past makes the timer expire immediately.
+            let clock_now = do_clock_gettime(self.clockid)?.as_duration();
+            now + value.checked_sub(clock_now).unwrap_or_default()
+        } else {
+            now + value
+        };
+        let interval = new_value.interval().as_duration();
+        *wheel_timer = Some(timer_wheel::add_timer(
+            deadline,
+            interval,
+            self.callback.clone(),
+        ));
+        Ok(old_value)
+    }
+
+    /// Get the current setting of the timer, whose value is the time until
+    /// the next expiration.
+    pub fn get(&self) -> itimerspec_t {
+        let state = self
+            .wheel_timer
+            .lock()
+            .unwrap()
+            .and_then(timer_wheel::timer_state);
+        Self::state_to_itimerspec(state)
+    }
+
+    fn state_to_itimerspec(state: Option<TimerState>) -> itimerspec_t {
+        match state {
+            Some(TimerState { deadline, interval }) => {
+                // A timer that is about to expire is reported with the
+                // smallest value since a zero value means being disarmed
+                let value = deadline
+                    .checked_sub(timer_wheel::now())
+                    .filter(|value| *value > Duration::from_secs(0))
+                    .unwrap_or(Duration::from_nanos(1));
+                itimerspec_t::new(value, interval)
+            }
+            None => Default::default(),
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if let Some(wheel_timer) = self.wheel_timer.lock().unwrap().take() {
+            timer_wheel::cancel_timer(wheel_timer);
+        }
+    }
+}
+
+impl fmt::Debug for Timer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Timer")
+            .field("clockid", &self.clockid)
+            .field("wheel_timer", &*self.wheel_timer.lock().unwrap())
+            .finish()
+    }
+}
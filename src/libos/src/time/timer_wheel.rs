@@ -0,0 +1,191 @@
+//! A hashed timing wheel that drives the timers inside LibOS.
+//!
+//! Instead of arming alarms on the host, which can neither be trusted nor be
+//! shared by the processes inside LibOS, all LibOS timers are kept in a single
+//! wheel. The wheel is advanced by the periodic ticks of the interrupt thread
+//! of PAL (see `interrupt::broadcast_interrupts`), so the resolution of the
+//! timers is the interval between two ticks.
+
+use super::*;
+
+/// The number of slots in the wheel.
+const NUM_SLOTS: usize = 256;
+/// The time span covered by a slot.
+const SLOT_DURATION: Duration = Duration::from_millis(10);
+
+pub type TimerId = u64;
+
+/// The callback of a timer, which is given the number of expirations since the
+/// last time it was invoked.
+///
+/// The callback of an expired timer is invoked without holding the lock of
+/// the wheel, so it can add or cancel timers freely.
+pub type TimerCallback = Arc<dyn Fn(u64) + Send + Sync>;
+
+/// The state of an armed timer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimerState {
+    /// The expiration time, relative to the boot of LibOS
+    pub deadline: Duration,
+    /// The period of a periodic timer, or zero for a one-shot timer
+    pub interval: Duration,
+}
+
+lazy_static! {
+    static ref TIMER_WHEEL: SgxMutex<TimerWheel> = SgxMutex::new(TimerWheel::new());
+}
+
+/// Add a timer that expires at the deadline, relative to the boot of LibOS.
+///
+/// If the interval is not zero, the timer is rearmed periodically until it is
+/// canceled.
+pub fn add_timer(deadline: Duration, interval: Duration, callback: TimerCallback) -> TimerId {
+    TIMER_WHEEL
+        .lock()
+        .unwrap()
+        .add(TimerState { deadline, interval }, callback)
+}
+
+/// Cancel a timer, returning its state if it has not expired.
+pub fn cancel_timer(id: TimerId) -> Option<TimerState> {
+    TIMER_WHEEL.lock().unwrap().cancel(id)
+}
+
+/// Get the state of a timer if it has not expired.
+pub fn timer_state(id: TimerId) -> Option<TimerState> {
+    TIMER_WHEEL.lock().unwrap().state(id)
+}
+
+/// Get the current time of the wheel, i.e., the time since the boot of LibOS.
+pub fn now() -> Duration {
+    up_time::get().unwrap_or_default()
+}
+
+/// Advance the wheel to the current time and invoke the callbacks of the
+/// expired timers.
+pub fn tick() {
+    let now = now();
+    let expired_timers = TIMER_WHEEL.lock().unwrap().advance(now);
+    for (callback, num_expirations) in expired_timers {
+        callback(num_expirations);
+    }
+}
+
+struct TimerEntry {
+    id: TimerId,
+    state: TimerState,
+    callback: TimerCallback,
+}
+
+struct TimerWheel {
+    slots: Vec<Vec<TimerEntry>>,
+    // The tick number (i.e., the time in units of SLOT_DURATION) of the last advance
+    current_tick: u64,
+    next_id: TimerId,
+    // The slot of each armed timer
+    slot_of_timer: HashMap<TimerId, usize>,
+}
+
+impl TimerWheel {
+    fn new() -> Self {
+        let slots = (0..NUM_SLOTS).map(|_| Vec::new()).collect();
+        Self {
+            slots,
+            current_tick: tick_of(now()),
+            next_id: 1,
+            slot_of_timer: HashMap::new(),
+        }
+    }
+
+    fn add(&mut self, state: TimerState, callback: TimerCallback) -> TimerId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.insert(TimerEntry {
+            id,
+            state,
+            callback,
+        });
+        id
+    }
+
+    fn insert(&mut self, entry: TimerEntry) {
+        // A timer is put into the slot of the first tick that is no earlier
+        // than its deadline, so it must have expired when the tick is reached.
+        // A timer whose deadline has passed expires at the next tick.
+        let deadline_tick = round_up_tick_of(entry.state.deadline).max(self.current_tick + 1);
+        let slot_idx = (deadline_tick % NUM_SLOTS as u64) as usize;
+        self.slot_of_timer.insert(entry.id, slot_idx);
+        self.slots[slot_idx].push(entry);
+    }
+
+    fn cancel(&mut self, id: TimerId) -> Option<TimerState> {
+        let slot_idx = self.slot_of_timer.remove(&id)?;
+        let slot = &mut self.slots[slot_idx];
+        let entry_idx = slot.iter().position(|entry| entry.id == id)?;
+        Some(slot.swap_remove(entry_idx).state)
+    }
+
+    fn state(&self, id: TimerId) -> Option<TimerState> {
+        let slot_idx = self.slot_of_timer.get(&id)?;
+        self.slots[*slot_idx]
+            .iter()
+            .find(|entry| entry.id == id)
+            .map(|entry| entry.state)
+    }
+
+    /// Advance to the given time, returning the expired timers.
+    fn advance(&mut self, now: Duration) -> Vec<(TimerCallback, u64)> {
+        let now_tick = tick_of(now);
+        if now_tick <= self.current_tick {
+            return Vec::new();
+        }
+        // Each slot needs to be visited at most once no matter how long the
+        // time has elapsed since the last advance
+        let num_visited_slots = (now_tick - self.current_tick).min(NUM_SLOTS as u64);
+        let first_tick = now_tick + 1 - num_visited_slots;
+        self.current_tick = now_tick;
+
+        let mut expired_entries = Vec::new();
+        for tick in first_tick..=now_tick {
+            let slot = &mut self.slots[(tick % NUM_SLOTS as u64) as usize];
+            let mut entry_idx = 0;
+            while entry_idx < slot.len() {
+                if slot[entry_idx].state.deadline <= now {
+                    expired_entries.push(slot.swap_remove(entry_idx));
+                } else {
+                    entry_idx += 1;
+                }
+            }
+        }
+
+        expired_entries
+            .into_iter()
+            .map(|mut entry| {
+                let callback = entry.callback.clone();
+                if entry.state.interval > Duration::from_secs(0) {
+                    // The expirations that have been missed since the last
+                    // advance are reported at once
+                    let interval_nanos = entry.state.interval.as_nanos();
+                    let num_expirations =
+                        (now - entry.state.deadline).as_nanos() / interval_nanos + 1;
+                    entry.state.deadline +=
+                        Duration::from_nanos((interval_nanos * num_expirations) as u64);
+                    self.insert(entry);
+                    (callback, num_expirations as u64)
+                } else {
+                    self.slot_of_timer.remove(&entry.id);
+                    (callback, 1)
+                }
+            })
+            .collect()
+    }
+}
+
+fn tick_of(time: Duration) -> u64 {
+    (time.as_nanos() / SLOT_DURATION.as_nanos()) as u64
+}
+
+fn round_up_tick_of(time: Duration) -> u64 {
+    let slot_nanos = SLOT_DURATION.as_nanos();
+    ((time.as_nanos() + slot_nanos - 1) / slot_nanos) as u64
+}
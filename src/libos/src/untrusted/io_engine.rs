@@ -0,0 +1,315 @@
+//! An engine that offloads the blocking host syscalls to the host I/O threads.
+//!
+//! A blocking OCall occupies the host thread of a LibOS thread until the host
+//! syscall returns, during which the LibOS thread cannot be woken up inside
+//! the enclave. With the engine, a blocking host syscall is submitted as a
+//! request to a queue in the untrusted memory, which is served by a pool of
+//! host I/O threads. The LibOS thread then sleeps on its host eventfd, which
+//! is written either by the host I/O thread on completion or by the interrupt
+//! thread on signals. An interrupted request is cancelled by interrupting the
+//! host I/O thread that executes it.
+//!
+//! A request may also be submitted without waiting for it, e.g., by the Linux
+//! AIO and io_uring. The completion of such a request writes the host eventfd
+//! given at the submission, and its result is taken later by its `IoHandle`.
+//!
+//! The engine is enabled by setting `resource_limits.io_threads` in the config.
+//! All the buffers given to the requests must be in the untrusted memory.
+
+use super::*;
+use std::alloc::{AllocRef, Layout};
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicI32, AtomicU32, Ordering};
+use std::time::Duration;
+
+use crate::config::LIBOS_CONFIG;
+use crate::events::HostEventFd;
+use crate::sched::do_sched_yield;
+use crate::signal::has_interrupting_signals;
+use crate::syscall::SyscallNum;
+
+const QUEUE_SIZE: usize = 256;
+
+/// The interval to re-interrupt the host I/O thread that executes a cancelled
+/// request, in case that the signal arrives before the host syscall blocks.
+const CANCEL_INTERVAL: Duration = Duration::from_millis(10);
+
+lazy_static! {
+    static ref IO_ENGINE: Option<IoEngine> = {
+        let num_threads = LIBOS_CONFIG.resource_limits.io_threads;
+        if num_threads == 0 {
+            None
+        } else {
+            match IoEngine::new(num_threads) {
+                Ok(engine) => Some(engine),
+                Err(e) => {
+                    warn!("failed to start the host I/O threads: {:?}", e);
+                    None
+                }
+            }
+        }
+    };
+}
+
+/// Get the I/O engine if it is enabled.
+pub fn io_engine() -> Option<&'static IoEngine> {
+    IO_ENGINE.as_ref()
+}
+
+pub struct IoEngine {
+    queue: NonNull<occlum_io_queue>,
+    // Submissions are serialized, while the host I/O threads take the requests
+    // concurrently
+    submit_lock: SgxMutex<()>,
+}
+
+// Safety. The queue is only accessed with atomic operations.
+unsafe impl Send for IoEngine {}
+unsafe impl Sync for IoEngine {}
+
+impl IoEngine {
+    fn new(num_threads: usize) -> Result<Self> {
+        let queue = unsafe {
+            let ptr = UNTRUSTED_ALLOC.alloc(Layout::new::<occlum_io_queue>())?;
+            let queue = ptr.as_mut_ptr() as *mut occlum_io_queue;
+            std::ptr::write_bytes(queue, 0, 1);
+            NonNull::new(queue).unwrap()
+        };
+        try_libc!({
+            let mut retval = 0;
+            let status =
+                occlum_ocall_io_engine_start(&mut retval, queue.as_ptr(), num_threads as i32);
+            assert!(status == sgx_status_t::SGX_SUCCESS);
+            retval
+        });
+        Ok(Self {
+            queue,
+            submit_lock: SgxMutex::new(()),
+        })
+    }
+
+    /// Execute a host syscall with the host I/O threads.
+    ///
+    /// The current thread waits until the syscall is done or it is interrupted
+    /// by signals, in which case EINTR is returned.
+    ///
+    /// Safety. The pointers in the arguments must point to the untrusted memory
+    /// that remains valid until the method returns.
+    pub unsafe fn execute(&self, num: SyscallNum, args: [i64; 6]) -> Result<isize> {
+        let thread = current!();
+        let req = IoRequest::new(num, args, thread.host_eventfd())?;
+        loop {
+            self.push(&req);
+            match req.wait() {
+                // The host I/O thread is interrupted by a cancellation of
+                // another request, so try again
+                Err(e) if e.errno() == EINTR && !req.is_cancelled() => {
+                    req.reset();
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Submit a host syscall to the host I/O threads without waiting for it.
+    ///
+    /// The host eventfd is written once the syscall is done. If the returned
+    /// handle is dropped before that, the request is cancelled.
+    ///
+    /// Safety. The pointers in the arguments must point to the untrusted memory
+    /// that remains valid until the handle is dropped.
+    pub unsafe fn submit(
+        &self,
+        num: SyscallNum,
+        args: [i64; 6],
+        host_eventfd: &Arc<HostEventFd>,
+    ) -> Result<IoHandle> {
+        let req = IoRequest::new(num, args, host_eventfd)?;
+        self.push(&req);
+        Ok(IoHandle {
+            req,
+            host_eventfd: host_eventfd.clone(),
+        })
+    }
+
+    fn push(&self, req: &IoRequest) {
+        let queue = unsafe { &*self.queue.as_ptr() };
+        let _guard = self.submit_lock.lock().unwrap();
+        let tail = queue.tail.load(Ordering::SeqCst);
+        while tail.wrapping_sub(queue.head.load(Ordering::SeqCst)) as usize >= QUEUE_SIZE {
+            let _ = do_sched_yield();
+        }
+        unsafe {
+            let slot = &queue.reqs[tail as usize % QUEUE_SIZE] as *const _ as *mut _;
+            std::ptr::write_volatile(slot, req.ptr.as_ptr());
+        }
+        queue.tail.store(tail.wrapping_add(1), Ordering::SeqCst);
+
+        if queue.nr_sleeping.load(Ordering::SeqCst) > 0 {
+            unsafe {
+                let status = occlum_ocall_io_engine_wake(self.queue.as_ptr());
+                assert!(status == sgx_status_t::SGX_SUCCESS);
+            }
+        }
+    }
+}
+
+/// A host syscall submitted by `IoEngine::submit`.
+pub struct IoHandle {
+    req: IoRequest,
+    // Keep the host eventfd open until the request is done
+    host_eventfd: Arc<HostEventFd>,
+}
+
+impl IoHandle {
+    pub fn is_done(&self) -> bool {
+        self.req.is_done()
+    }
+
+    /// Get the result of the host syscall, or None if it is not done.
+    pub fn result(&self) -> Option<Result<isize>> {
+        if !self.is_done() {
+            return None;
+        }
+        Some(self.req.result())
+    }
+}
+
+impl Drop for IoHandle {
+    fn drop(&mut self) {
+        // The untrusted memory of the request must not be freed while the host
+        // I/O thread is executing it
+        while !self.req.is_done() {
+            self.req.cancel();
+            let _ = self.host_eventfd.poll(Some(&CANCEL_INTERVAL));
+        }
+    }
+}
+
+/// A host syscall request in the untrusted memory.
+struct IoRequest {
+    ptr: NonNull<occlum_io_request>,
+}
+
+impl IoRequest {
+    fn new(num: SyscallNum, args: [i64; 6], host_eventfd: &HostEventFd) -> Result<Self> {
+        let ptr = unsafe {
+            let ptr = UNTRUSTED_ALLOC.alloc(Layout::new::<occlum_io_request>())?;
+            let req = ptr.as_mut_ptr() as *mut occlum_io_request;
+            std::ptr::write(
+                req,
+                occlum_io_request {
+                    num: num as i64,
+                    args,
+                    ret: 0,
+                    host_eventfd: host_eventfd.host_fd() as i32,
+                    host_tid: AtomicI32::new(0),
+                    is_cancelled: AtomicI32::new(0),
+                    is_done: AtomicI32::new(0),
+                },
+            );
+            NonNull::new(req).unwrap()
+        };
+        Ok(Self { ptr })
+    }
+
+    fn inner(&self) -> &occlum_io_request {
+        unsafe { &*self.ptr.as_ptr() }
+    }
+
+    fn is_done(&self) -> bool {
+        self.inner().is_done.load(Ordering::Acquire) != 0
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.inner().is_cancelled.load(Ordering::SeqCst) != 0
+    }
+
+    fn reset(&self) {
+        self.inner().is_done.store(0, Ordering::SeqCst);
+    }
+
+    /// Cancel the request by interrupting the host I/O thread executing it.
+    ///
+    /// If the request has not been taken by any host I/O thread, the thread
+    /// that takes it later sees the cancellation and skips it.
+    fn cancel(&self) {
+        let inner = self.inner();
+        inner.is_cancelled.store(1, Ordering::SeqCst);
+        let host_tid = inner.host_tid.load(Ordering::SeqCst);
+        if host_tid != 0 {
+            let signum = 64; // real-time signal 64 is used to notify interrupts
+            unsafe {
+                let mut retval = 0;
+                let status = occlum_ocall_tkill(&mut retval, host_tid, signum);
+                assert!(status == sgx_status_t::SGX_SUCCESS);
+            }
+        }
+    }
+
+    fn wait(&self) -> Result<isize> {
+        let thread = current!();
+        while !self.is_done() {
+            // The interrupt thread writes the host eventfd if the current thread
+            // has interrupting signals
+            if has_interrupting_signals(&thread) {
+                self.cancel();
+                let _ = thread.host_eventfd().poll(Some(&CANCEL_INTERVAL));
+            } else {
+                let _ = thread.host_eventfd().poll(None);
+            }
+        }
+        self.result()
+    }
+
+    fn result(&self) -> Result<isize> {
+        debug_assert!(self.is_done());
+        let ret = unsafe { std::ptr::read_volatile(&self.inner().ret) };
+        if ret < 0 {
+            return_errno!(Errno::from(-ret as u32), "host syscall error");
+        }
+        Ok(ret as isize)
+    }
+}
+
+impl Drop for IoRequest {
+    fn drop(&mut self) {
+        // The request is freed only after it is done
+        debug_assert!(self.is_done());
+        unsafe {
+            UNTRUSTED_ALLOC.dealloc(self.ptr.cast(), Layout::new::<occlum_io_request>());
+        }
+    }
+}
+
+#[repr(C)]
+#[allow(non_camel_case_types)]
+struct occlum_io_request {
+    num: i64,
+    args: [i64; 6],
+    ret: i64,
+    host_eventfd: i32,
+    host_tid: AtomicI32,
+    is_cancelled: AtomicI32,
+    is_done: AtomicI32,
+}
+
+#[repr(C)]
+#[allow(non_camel_case_types)]
+struct occlum_io_queue {
+    head: AtomicU32,
+    tail: AtomicU32,
+    nr_sleeping: AtomicU32,
+    is_stopped: AtomicU32,
+    reqs: [*mut occlum_io_request; QUEUE_SIZE],
+}
+
+extern "C" {
+    fn occlum_ocall_io_engine_start(
+        ret: *mut i32,
+        queue: *mut occlum_io_queue,
+        num_threads: i32,
+    ) -> sgx_status_t;
+    fn occlum_ocall_io_engine_wake(queue: *mut occlum_io_queue) -> sgx_status_t;
+    fn occlum_ocall_tkill(ret: *mut i32, host_tid: pid_t, signum: i32) -> sgx_status_t;
+}
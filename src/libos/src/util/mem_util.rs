@@ -94,7 +94,12 @@ pub mod from_user {
         let ur_start = user_range.start();
         let ur_end = user_range.end();
         let addr_start = addr as usize;
-        addr_start >= ur_start && addr_start < ur_end && ur_end - addr_start >= len
+        if addr_start >= ur_start && addr_start < ur_end && ur_end - addr_start >= len {
+            return true;
+        }
+        // The shared memory, e.g., the named semaphores in `/dev/shm`, is
+        // mapped out of the process range
+        current.vm().is_inside_shm(addr_start, len)
     }
 }
 
@@ -0,0 +1,256 @@
+//! Enclave Dynamic Memory Management (EDMM) of SGX2.
+//!
+//! With EDMM, the user space is allocated out of the user region of the
+//! enclave with pages committed on demand: a page is committed (i.e., an EPC
+//! page is added to the enclave) by the page fault handler of LibOS when it is
+//! accessed for the first time. So the user space size is only a ceiling of
+//! the enclave memory used by LibOS processes, rather than an upfront
+//! reservation of EPC. Uncommitted pages are always filled with zeros once
+//! they are committed again, which makes releasing and zeroing memory cheap.
+//!
+//! The page fault handler only commits the pages that are not committed yet.
+//! A fault on a committed page, e.g., due to the page permissions, is passed
+//! on to the exception handler of LibOS, which converts it to a signal.
+
+use super::*;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+extern "C" {
+    static EDMM_supported: i32;
+
+    fn sgx_mm_alloc(
+        addr: *const c_void,
+        length: usize,
+        flags: i32,
+        handler: PageFaultHandler,
+        handler_private: *const c_void,
+        out_addr: *mut *mut c_void,
+    ) -> i32;
+    fn sgx_mm_dealloc(addr: *const c_void, length: usize) -> i32;
+    fn sgx_mm_commit(addr: *const c_void, length: usize) -> i32;
+    fn sgx_mm_uncommit(addr: *const c_void, length: usize) -> i32;
+    fn sgx_mm_modify_permissions(addr: *const c_void, length: usize, prot: i32) -> i32;
+}
+
+const SGX_EMA_COMMIT_ON_DEMAND: i32 = 0x4;
+const SGX_EMA_PAGE_TYPE_REG: i32 = 0x2 << 8;
+
+const SGX_MM_EXCEPTION_CONTINUE_SEARCH: i32 = 0;
+const SGX_MM_EXCEPTION_CONTINUE_EXECUTION: i32 = -1;
+
+#[repr(C)]
+#[allow(non_camel_case_types)]
+struct sgx_pfinfo {
+    maddr: u64,
+    errcd: u32,
+    reserved: u32,
+}
+
+type PageFaultHandler = extern "C" fn(*const sgx_pfinfo, *mut c_void) -> i32;
+
+lazy_static! {
+    static ref ON_DEMAND_REGIONS: SgxRwLock<Vec<Arc<OnDemandRegion>>> = SgxRwLock::new(Vec::new());
+}
+
+/// A range of memory whose pages are committed on demand.
+///
+/// The region keeps track of which pages are committed, so that the page
+/// fault handler can tell the faults on uncommitted pages from the others
+/// without taking any locks.
+struct OnDemandRegion {
+    start: AtomicUsize,
+    size: usize,
+    // One bit for each page, which is set if the page is committed
+    committed: Vec<AtomicU64>,
+}
+
+impl OnDemandRegion {
+    fn new(size: usize) -> Self {
+        let nr_pages = size / PAGE_SIZE;
+        let committed = (0..(nr_pages + 63) / 64)
+            .map(|_| AtomicU64::new(0))
+            .collect();
+        Self {
+            start: AtomicUsize::new(0),
+            size,
+            committed,
+        }
+    }
+
+    fn range(&self) -> VMRange {
+        let start = self.start.load(Ordering::Acquire);
+        unsafe { VMRange::from_unchecked(start, start + self.size) }
+    }
+
+    fn committed_size(&self, range: &VMRange) -> usize {
+        let range = match range.intersect(&self.range()) {
+            Some(range) => range,
+            None => return 0,
+        };
+        let start = self.start.load(Ordering::Acquire);
+        let end_idx = (range.end() - start) / PAGE_SIZE;
+        let mut page_idx = (range.start() - start) / PAGE_SIZE;
+        let mut nr_committed = 0;
+        // Count the committed pages word by word
+        while page_idx < end_idx {
+            let bit_idx = page_idx % 64;
+            let nr_bits = (64 - bit_idx).min(end_idx - page_idx);
+            let mask = if nr_bits == 64 {
+                !0
+            } else {
+                ((1u64 << nr_bits) - 1) << bit_idx
+            };
+            let word = self.committed[page_idx / 64].load(Ordering::Acquire);
+            nr_committed += (word & mask).count_ones() as usize;
+            page_idx += nr_bits;
+        }
+        nr_committed * PAGE_SIZE
+    }
+
+    fn is_committed(&self, page_addr: usize) -> bool {
+        let (word, bit) = self.locate(page_addr);
+        self.committed[word].load(Ordering::Acquire) & bit != 0
+    }
+
+    fn set_committed(&self, range: &VMRange, is_committed: bool) {
+        let range = match range.intersect(&self.range()) {
+            Some(range) => range,
+            None => return,
+        };
+        for page_addr in (range.start()..range.end()).step_by(PAGE_SIZE) {
+            let (word, bit) = self.locate(page_addr);
+            if is_committed {
+                self.committed[word].fetch_or(bit, Ordering::AcqRel);
+            } else {
+                self.committed[word].fetch_and(!bit, Ordering::AcqRel);
+            }
+        }
+    }
+
+    fn locate(&self, page_addr: usize) -> (usize, u64) {
+        let page_idx = (page_addr - self.start.load(Ordering::Acquire)) / PAGE_SIZE;
+        (page_idx / 64, 1 << (page_idx % 64))
+    }
+}
+
+// Commit the page on the first access to it. This is called by the SGX SDK for
+// every page fault in the on-demand regions, which may happen in LibOS as well
+// as in user programs, so no locks can be taken here.
+extern "C" fn handle_page_fault(pfinfo: *const sgx_pfinfo, private: *mut c_void) -> i32 {
+    let region = unsafe { &*(private as *const OnDemandRegion) };
+    let fault_addr = unsafe { (*pfinfo).maddr } as usize;
+    let page_addr = align_down(fault_addr, PAGE_SIZE);
+    if !region.range().contains(page_addr) || region.is_committed(page_addr) {
+        return SGX_MM_EXCEPTION_CONTINUE_SEARCH;
+    }
+
+    if unsafe { sgx_mm_commit(page_addr as *const c_void, PAGE_SIZE) } != 0 {
+        return SGX_MM_EXCEPTION_CONTINUE_SEARCH;
+    }
+    let page_range = unsafe { VMRange::from_unchecked(page_addr, page_addr + PAGE_SIZE) };
+    region.set_committed(&page_range, true);
+    SGX_MM_EXCEPTION_CONTINUE_EXECUTION
+}
+
+fn find_region(range: &VMRange) -> Option<Arc<OnDemandRegion>> {
+    ON_DEMAND_REGIONS
+        .read()
+        .unwrap()
+        .iter()
+        .find(|region| region.range().overlap_with(range))
+        .cloned()
+}
+
+/// Check whether EDMM is supported by the platform.
+pub fn is_supported() -> bool {
+    unsafe { EDMM_supported != 0 }
+}
+
+/// Allocate a range of memory whose pages are committed on demand.
+///
+/// The pages are readable and writable once they are committed.
+pub fn alloc_on_demand(size: usize) -> Result<VMRange> {
+    debug_assert!(is_supported());
+    let region = Arc::new(OnDemandRegion::new(size));
+    let mut addr: *mut c_void = std::ptr::null_mut();
+    let ret = unsafe {
+        sgx_mm_alloc(
+            std::ptr::null(),
+            size,
+            SGX_EMA_COMMIT_ON_DEMAND | SGX_EMA_PAGE_TYPE_REG,
+            handle_page_fault,
+            Arc::as_ptr(&region) as *const c_void,
+            &mut addr,
+        )
+    };
+    if ret != 0 || addr.is_null() {
+        return_errno!(ENOMEM, "run out of the user region");
+    }
+    let addr = addr as usize;
+    region.start.store(addr, Ordering::Release);
+    ON_DEMAND_REGIONS.write().unwrap().push(region);
+    Ok(unsafe { VMRange::from_unchecked(addr, addr + size) })
+}
+
+/// Free a range of memory allocated by `alloc_on_demand`.
+pub fn dealloc(range: &VMRange) {
+    let ret = unsafe { sgx_mm_dealloc(range.start() as *const c_void, range.size()) };
+    assert!(ret == 0);
+    // The page fault handler is never called for the range from now on
+    ON_DEMAND_REGIONS
+        .write()
+        .unwrap()
+        .retain(|region| region.range().start() != range.start());
+}
+
+/// Get the size of the memory in the range that is resident in the enclave.
+///
+/// The memory allocated with EDMM is resident once its pages are committed.
+/// Otherwise, the memory is reserved in the enclave, thus always resident.
+pub fn resident_size(range: &VMRange) -> usize {
+    if !is_supported() {
+        return range.size();
+    }
+    match find_region(range) {
+        Some(region) => region.committed_size(range),
+        None => range.size(),
+    }
+}
+
+/// Uncommit the pages in the range, releasing the EPC pages.
+///
+/// The pages are filled with zeros when they are accessed next time.
+pub fn uncommit(range: &VMRange) -> Result<()> {
+    // Mark the pages as uncommitted first, so that a fault on any of them is
+    // never mistaken for a fault on a committed page
+    if let Some(region) = find_region(range) {
+        region.set_committed(range, false);
+    }
+    let ret = unsafe { sgx_mm_uncommit(range.start() as *const c_void, range.size()) };
+    if ret != 0 {
+        return_errno!(EFAULT, "failed to uncommit pages");
+    }
+    Ok(())
+}
+
+/// Commit the pages in the range and extend their EPCM permissions.
+///
+/// The pages committed on demand are only readable and writable. Since the
+/// permissions of uncommitted pages can not be changed, the pages that need
+/// other permissions, e.g., executable or inaccessible ones, are committed
+/// eagerly.
+pub fn commit_with_perms(range: &VMRange, perms: VMPerms) -> Result<()> {
+    let addr = range.start() as *const c_void;
+    let size = range.size();
+    if unsafe { sgx_mm_commit(addr, size) } != 0 {
+        return_errno!(ENOMEM, "failed to commit pages");
+    }
+    if let Some(region) = find_region(range) {
+        region.set_committed(range, true);
+    }
+    let prot = (perms | VMPerms::DEFAULT).bits() as i32;
+    if unsafe { sgx_mm_modify_permissions(addr, size, prot) } != 0 {
+        return_errno!(EACCES, "failed to modify the permissions of pages");
+    }
+    Ok(())
+}
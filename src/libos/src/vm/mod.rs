@@ -1,9 +1,12 @@
 use super::*;
-use fs::{File, FileDesc, FileRef};
-use process::{Process, ProcessRef};
+use fs::{AsINodeFile, AsIoUring, AsMemFile, File, FileDesc, FileRef, FileSeals};
+use misc::resource_t;
+use process::{Process, ProcessRef, ThreadRef};
 use std::fmt;
 
+mod edmm;
 mod process_vm;
+mod shm_manager;
 mod user_space_vm;
 mod vm_area;
 mod vm_layout;
@@ -14,8 +17,13 @@ mod vm_range;
 use self::vm_layout::VMLayout;
 use self::vm_manager::{VMManager, VMMapOptionsBuilder};
 
-pub use self::process_vm::{MMapFlags, MRemapFlags, MSyncFlags, ProcessVM, ProcessVMBuilder};
+pub use self::process_vm::{
+    MAdvice, MLockAllFlags, MLockFlags, MMapFlags, MRemapFlags, MSyncFlags, ProcessVM,
+    ProcessVMBuilder, VMSnapshot, VMUsage,
+};
+pub use self::shm_manager::ShmSegment;
 pub use self::user_space_vm::USER_SPACE_VM_MANAGER;
+pub use self::vm_area::VMArea;
 pub use self::vm_perms::VMPerms;
 pub use self::vm_range::VMRange;
 
@@ -39,7 +47,13 @@ pub fn do_mmap(
         );
     }
 
-    current!().vm().mmap(addr, size, perms, flags, fd, offset)
+    let current = current!();
+    check_address_space_limit(&current, size)?;
+    if flags.contains(MMapFlags::MAP_LOCKED) || current.vm().is_future_locked() {
+        check_locked_memory_limit(&current, size)
+            .map_err(|_| errno!(EAGAIN, "the locked memory exceeds RLIMIT_MEMLOCK"))?;
+    }
+    current.vm().mmap(addr, size, perms, flags, fd, offset)
 }
 
 pub fn do_munmap(addr: usize, size: usize) -> Result<()> {
@@ -58,7 +72,76 @@ pub fn do_mremap(
         "mremap: old_addr: {:#x}, old_size: {:#x}, new_size: {:#x}, flags: {:?}",
         old_addr, old_size, new_size, flags
     );
-    current!().vm().mremap(old_addr, old_size, new_size, flags)
+    let current = current!();
+    check_address_space_limit(&current, new_size.saturating_sub(old_size))?;
+    if current.vm().is_future_locked() {
+        check_locked_memory_limit(&current, new_size.saturating_sub(old_size))
+            .map_err(|_| errno!(EAGAIN, "the locked memory exceeds RLIMIT_MEMLOCK"))?;
+    }
+    current.vm().mremap(old_addr, old_size, new_size, flags)
+}
+
+// The address space of a process cannot grow beyond the soft limit of RLIMIT_AS
+fn check_address_space_limit(current: &ThreadRef, extra_size: usize) -> Result<()> {
+    let max_size = current
+        .rlimits()
+        .lock()
+        .unwrap()
+        .get(resource_t::RLIMIT_AS)
+        .get_cur();
+    let new_size = current.vm().get_address_space_size() + align_up(extra_size, PAGE_SIZE);
+    if new_size as u64 > max_size {
+        return_errno!(ENOMEM, "the address space exceeds RLIMIT_AS");
+    }
+    Ok(())
+}
+
+pub fn do_mlock(addr: usize, size: usize, flags: MLockFlags) -> Result<()> {
+    debug!(
+        "mlock: addr: {:#x}, size: {:#x}, flags: {:?}",
+        addr, size, flags
+    );
+    let current = current!();
+    let max_locked_size = get_max_locked_size(&current);
+    current.vm().mlock(addr, size, max_locked_size)
+}
+
+pub fn do_munlock(addr: usize, size: usize) -> Result<()> {
+    debug!("munlock: addr: {:#x}, size: {:#x}", addr, size);
+    current!().vm().munlock(addr, size)
+}
+
+pub fn do_mlockall(flags: MLockAllFlags) -> Result<()> {
+    debug!("mlockall: flags: {:?}", flags);
+    let current = current!();
+    let max_locked_size = get_max_locked_size(&current);
+    current.vm().mlockall(flags, max_locked_size)
+}
+
+pub fn do_munlockall() -> Result<()> {
+    debug!("munlockall");
+    current!().vm().munlockall();
+    Ok(())
+}
+
+// The locked memory of a process cannot grow beyond the soft limit of
+// RLIMIT_MEMLOCK
+fn check_locked_memory_limit(current: &ThreadRef, extra_size: usize) -> Result<()> {
+    let new_size = current.vm().get_locked_size() + align_up(extra_size, PAGE_SIZE);
+    if new_size > get_max_locked_size(current) {
+        return_errno!(ENOMEM, "the locked memory exceeds RLIMIT_MEMLOCK");
+    }
+    Ok(())
+}
+
+fn get_max_locked_size(current: &ThreadRef) -> usize {
+    let max_size = current
+        .rlimits()
+        .lock()
+        .unwrap()
+        .get(resource_t::RLIMIT_MEMLOCK)
+        .get_cur();
+    max_size.min(usize::max_value() as u64) as usize
 }
 
 pub fn do_mprotect(addr: usize, size: usize, perms: VMPerms) -> Result<()> {
@@ -88,4 +171,12 @@ pub fn do_msync(addr: usize, size: usize, flags: MSyncFlags) -> Result<()> {
     current!().vm().msync(addr, size)
 }
 
+pub fn do_madvise(addr: usize, size: usize, advice: MAdvice) -> Result<()> {
+    debug!(
+        "madvise: addr: {:#x}, size: {:#x}, advice: {:?}",
+        addr, size, advice
+    );
+    current!().vm().madvise(addr, size, advice)
+}
+
 pub const PAGE_SIZE: usize = 4096;
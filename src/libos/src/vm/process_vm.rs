@@ -2,14 +2,25 @@ use super::*;
 
 use super::config;
 use super::process::elf_file::{ElfFile, ProgramHeaderExt};
+use super::shm_manager::ShmSegment;
 use super::user_space_vm::{UserSpaceVMManager, UserSpaceVMRange, USER_SPACE_VM_MANAGER};
 use super::vm_manager::{
     VMInitializer, VMManager, VMMapAddr, VMMapOptions, VMMapOptionsBuilder, VMRemapOptions,
 };
 use super::vm_perms::VMPerms;
+use crate::fs::INodeFile;
+use rcore_fs::vfs::{FileType, INode};
 use std::sync::atomic::{AtomicUsize, Ordering};
 
-// Used for heap and stack start address randomization.
+// The mappings of MAP_32BIT are placed in the first 2GB of the address space
+const MAP_32BIT_LIMIT: usize = 0x8000_0000;
+
+// The size of the guard region below the stack of the main thread, and below
+// the stack of any other thread whose guard region is not set up by the user
+const STACK_GUARD_SIZE: usize = 16 * 4096; // 64K
+
+// Used for the randomization of the gaps before the heap, the stack, the mmap
+// region and the ELFs other than the first one.
 const RANGE_FOR_RANDOMIZATION: usize = 256 * 4096; // 1M
 
 #[derive(Debug, Clone)]
@@ -45,11 +56,11 @@ impl<'a, 'b> ProcessVMBuilder<'a, 'b> {
         self
     }
 
-    // Generate a random address within [0, range]
-    // Note: This function doesn't gurantee alignment
+    // Generate a random page-aligned offset within [0, range), or zero if
+    // ASLR is disabled in the config
     fn get_randomize_offset(range: usize) -> usize {
-        if cfg!(debug_assertions) {
-            return range;
+        if !config::LIBOS_CONFIG.process.enable_aslr || range < PAGE_SIZE {
+            return 0;
         }
 
         use crate::util::random;
@@ -57,7 +68,29 @@ impl<'a, 'b> ProcessVMBuilder<'a, 'b> {
         let mut random_buf: [u8; 8] = [0u8; 8]; // same length as usize
         random::get_random(&mut random_buf).expect("failed to get random number");
         let random_num: usize = u64::from_le_bytes(random_buf) as usize;
-        random_num % range
+        align_down(random_num % range, PAGE_SIZE)
+    }
+
+    // The extra space needed by the random gaps placed before the ELFs, the
+    // heap, the stack and the mmap region, as well as the realignment of the
+    // ELFs after the gaps
+    fn get_randomization_size(elf_layouts: &[VMLayout], process_size: usize) -> usize {
+        if !config::LIBOS_CONFIG.process.enable_aslr {
+            return 0;
+        }
+        let elf_base_range = Self::get_elf_base_randomization_range(process_size);
+        let elf_gaps_size = elf_layouts
+            .iter()
+            .map(|elf_layout| elf_layout.align())
+            .sum::<usize>()
+            + RANGE_FOR_RANDOMIZATION * elf_layouts.len().saturating_sub(1);
+        // Gaps before the heap, the stack and the mmap region
+        let other_gaps_size = RANGE_FOR_RANDOMIZATION * 3;
+        elf_base_range + elf_gaps_size + other_gaps_size
+    }
+
+    fn get_elf_base_randomization_range(process_size: usize) -> usize {
+        align_down(process_size >> 3, PAGE_SIZE)
     }
 
     pub fn build(self) -> Result<ProcessVM> {
@@ -97,6 +130,7 @@ impl<'a, 'b> ProcessVMBuilder<'a, 'b> {
             VMLayout::new(heap_size, PAGE_SIZE)?,
             VMLayout::new(stack_size, PAGE_SIZE)?,
             VMLayout::new(mmap_size, PAGE_SIZE)?,
+            VMLayout::new(STACK_GUARD_SIZE, PAGE_SIZE)?,
         ];
         let process_layout = elf_layouts.iter().chain(other_layouts.iter()).fold(
             VMLayout::new_empty(),
@@ -111,7 +145,10 @@ impl<'a, 'b> ProcessVMBuilder<'a, 'b> {
         let process_range = {
             // TODO: ensure alignment through USER_SPACE_VM_MANAGER, not by
             // preserving extra space for alignment
-            USER_SPACE_VM_MANAGER.alloc(process_layout.align() + process_layout.size())?
+            let randomization_size =
+                Self::get_randomization_size(&elf_layouts, process_layout.size());
+            USER_SPACE_VM_MANAGER
+                .alloc(process_layout.align() + process_layout.size() + randomization_size)?
         };
         let process_base = process_range.range().start();
         // Use the vm_manager to manage the whole process VM (including mmap region)
@@ -120,14 +157,21 @@ impl<'a, 'b> ProcessVMBuilder<'a, 'b> {
         // VMManager will fill zeros (if necessary) on mmap.
 
         // Tracker to track the min_start for each part
-        let mut min_start =
-            process_base + Self::get_randomize_offset(process_range.range().size() >> 3);
+        let mut min_start = process_base
+            + Self::get_randomize_offset(Self::get_elf_base_randomization_range(
+                process_layout.size(),
+            ));
         // Init the memory for ELFs in the process
         let mut elf_ranges = Vec::with_capacity(2);
         elf_layouts
             .iter()
             .zip(self.elfs.iter())
-            .map(|(elf_layout, elf_file)| {
+            .enumerate()
+            .map(|(idx, (elf_layout, elf_file))| {
+                // Put a random gap between the executable and the interpreter
+                if idx > 0 {
+                    min_start += Self::get_randomize_offset(RANGE_FOR_RANDOMIZATION);
+                }
                 let desired_range = VMRange::new_with_layout(elf_layout, min_start);
                 let vm_option = VMMapOptionsBuilder::default()
                     .size(desired_range.size())
@@ -162,10 +206,25 @@ impl<'a, 'b> ProcessVMBuilder<'a, 'b> {
         let brk = AtomicUsize::new(heap_range.start());
         min_start = heap_range.end();
 
+        // Init the guard region below the stack in the process, which is
+        // never accessible so that a stack overflow is caught
+        let stack_guard_layout = &other_layouts[3];
+        let stack_guard_min_start = min_start + Self::get_randomize_offset(RANGE_FOR_RANDOMIZATION);
+        let stack_guard_range = VMRange::new_with_layout(stack_guard_layout, stack_guard_min_start);
+        let vm_option = VMMapOptionsBuilder::default()
+            .size(stack_guard_range.size())
+            .addr(VMMapAddr::Need(stack_guard_range.start()))
+            .perms(VMPerms::NONE)
+            .initializer(VMInitializer::DoNothing())
+            .build()?;
+        let stack_guard_start = vm_manager.mmap(vm_option)?;
+        debug_assert!(stack_guard_range.start == stack_guard_start);
+        trace!("stack guard range = {:?}", stack_guard_range);
+        min_start = stack_guard_range.end();
+
         // Init the stack memory in the process
         let stack_layout = &other_layouts[1];
-        let stack_min_start = min_start + Self::get_randomize_offset(RANGE_FOR_RANDOMIZATION);
-        let stack_range = VMRange::new_with_layout(stack_layout, stack_min_start);
+        let stack_range = VMRange::new_with_layout(stack_layout, min_start);
         let vm_option = VMMapOptionsBuilder::default()
             .size(stack_range.size())
             .addr(VMMapAddr::Need(stack_range.start()))
@@ -180,8 +239,11 @@ impl<'a, 'b> ProcessVMBuilder<'a, 'b> {
         debug_assert!(process_range.range().is_superset_of(&heap_range));
         debug_assert!(process_range.range().is_superset_of(&stack_range));
 
-        // Set mmap prefered start address
-        vm_manager.set_mmap_prefered_start_addr(min_start);
+        // Set mmap prefered start address, from which the shared libraries
+        // and other mmap-ed memory are allocated
+        let mmap_min_start = min_start + Self::get_randomize_offset(RANGE_FOR_RANDOMIZATION);
+        vm_manager.set_mmap_prefered_start_addr(mmap_min_start);
+        let max_rss = AtomicUsize::new(Self::resident_size(&vm_manager));
         let vm_manager = SgxMutex::new(vm_manager);
 
         Ok(ProcessVM {
@@ -191,6 +253,10 @@ impl<'a, 'b> ProcessVMBuilder<'a, 'b> {
             stack_range,
             brk,
             vm_manager,
+            shm_segments: Default::default(),
+            stack_guards: SgxMutex::new(vec![StackGuard::new(stack_guard_range, true)]),
+            locked_memory: Default::default(),
+            max_rss,
         })
     }
 
@@ -264,6 +330,14 @@ pub struct ProcessVM {
     heap_range: VMRange,
     stack_range: VMRange,
     brk: AtomicUsize,
+    // The shared memory segments mapped by the process, one for each mapping
+    shm_segments: SgxMutex<Vec<Arc<ShmSegment>>>,
+    // The guard regions below the stacks of threads
+    stack_guards: SgxMutex<Vec<StackGuard>>,
+    // The memory locked by mlock and mlockall
+    locked_memory: SgxMutex<LockedMemory>,
+    // The maximum resident set size in bytes
+    max_rss: AtomicUsize,
     // Memory safety notes: the process_range field must be the last one.
     //
     // Rust drops fields in the same order as they are declared. So by making
@@ -273,6 +347,94 @@ pub struct ProcessVM {
     process_range: UserSpaceVMRange,
 }
 
+/// The memory usage of a process in bytes
+#[derive(Debug, Default, Clone, Copy)]
+pub struct VMUsage {
+    /// The size of the mapped memory
+    pub size: usize,
+    /// The resident set size
+    pub rss: usize,
+    /// The proportional set size, in which each page shared by N processes
+    /// is counted as 1/N of a page
+    pub pss: usize,
+    /// The resident private memory that is anonymous
+    pub anon_rss: usize,
+    /// The resident private memory that is backed by files
+    pub file_rss: usize,
+    /// The resident memory shared with other processes
+    pub shared_rss: usize,
+    /// The size of the memory of ELFs
+    pub elf_size: usize,
+    /// The size of the stack of the main thread
+    pub stack_size: usize,
+    /// The size of the locked memory
+    pub locked_size: usize,
+}
+
+/// A guard region below the stack of a thread, which is not accessible
+#[derive(Debug, Clone, Copy)]
+struct StackGuard {
+    range: VMRange,
+    // Whether the region is mapped by LibOS rather than by the user
+    is_owned: bool,
+}
+
+impl StackGuard {
+    fn new(range: VMRange, is_owned: bool) -> Self {
+        Self { range, is_owned }
+    }
+}
+
+/// The memory locked by mlock and mlockall.
+///
+/// The memory of enclaves is never swapped out, so it is effectively locked
+/// all the time. The locked ranges are only tracked to account them against
+/// RLIMIT_MEMLOCK.
+#[derive(Debug, Default)]
+struct LockedMemory {
+    // The locked ranges, which are page aligned and disjoint
+    ranges: Vec<VMRange>,
+    // Whether the memory mapped in the future is locked (MCL_FUTURE)
+    lock_future: bool,
+}
+
+impl LockedMemory {
+    fn size(&self) -> usize {
+        self.ranges.iter().map(|range| range.size()).sum()
+    }
+
+    // The size of the part of the range that is not locked yet
+    fn unlocked_size_of(&self, range: &VMRange) -> usize {
+        let locked_size: usize = self
+            .ranges
+            .iter()
+            .filter_map(|locked_range| locked_range.intersect(range))
+            .map(|range| range.size())
+            .sum();
+        range.size() - locked_size
+    }
+
+    fn is_locked(&self, range: &VMRange) -> bool {
+        self.ranges
+            .iter()
+            .any(|locked_range| locked_range.overlap_with(range))
+    }
+
+    fn lock(&mut self, range: &VMRange) {
+        self.unlock(range);
+        self.ranges.push(*range);
+    }
+
+    fn unlock(&mut self, range: &VMRange) {
+        self.ranges = self
+            .ranges
+            .iter()
+            .flat_map(|locked_range| locked_range.subtract(range))
+            .filter(|locked_range| !locked_range.empty())
+            .collect();
+    }
+}
+
 impl Default for ProcessVM {
     fn default() -> ProcessVM {
         ProcessVM {
@@ -282,6 +444,10 @@ impl Default for ProcessVM {
             stack_range: Default::default(),
             brk: Default::default(),
             vm_manager: Default::default(),
+            shm_segments: Default::default(),
+            stack_guards: Default::default(),
+            locked_memory: Default::default(),
+            max_rss: Default::default(),
         }
     }
 }
@@ -315,6 +481,75 @@ impl ProcessVM {
         self.get_stack_range().start()
     }
 
+    /// Get the resident set size in bytes.
+    ///
+    /// Without EDMM, the memory of a process is committed in the enclave as
+    /// soon as it is mapped, so the resident set consists of all the mapped
+    /// memory. With EDMM, only the committed pages are resident.
+    pub fn get_rss(&self) -> usize {
+        self.get_usage().rss
+    }
+
+    /// Get the size of the mapped memory in bytes, including the shared memory.
+    pub fn get_mapped_size(&self) -> usize {
+        self.get_usage().size
+    }
+
+    /// Get the memory usage of the process.
+    pub fn get_usage(&self) -> VMUsage {
+        let mut usage = VMUsage::default();
+        for vma in self.get_vmas() {
+            let rss = vma.resident_size();
+            usage.size += vma.size();
+            usage.rss += rss;
+            usage.pss += rss;
+            if vma.file().is_some() {
+                usage.file_rss += rss;
+            } else {
+                usage.anon_rss += rss;
+            }
+        }
+        for segment in self.shm_segments.lock().unwrap().iter() {
+            // The pages of a segment are shared by all the processes that map it
+            let rss = segment.resident_size();
+            let nr_sharers = segment.nr_sharers();
+            usage.size += segment.range().size();
+            usage.rss += rss;
+            usage.pss += rss / nr_sharers;
+            usage.shared_rss += rss;
+        }
+        usage.elf_size = self.elf_ranges.iter().map(|range| range.size()).sum();
+        usage.stack_size = self.stack_range.size();
+        usage.locked_size = self.get_locked_size();
+        usage
+    }
+
+    /// Get the size of the address space that is limited by RLIMIT_AS, which
+    /// consists of the heap, the stack and the memory mappings, but not ELFs.
+    pub fn get_address_space_size(&self) -> usize {
+        let elf_size: usize = self.elf_ranges.iter().map(|range| range.size()).sum();
+        self.get_mapped_size() - elf_size
+    }
+
+    /// Get the maximum resident set size in bytes during the lifetime of the
+    /// process.
+    pub fn get_max_rss(&self) -> usize {
+        self.update_max_rss()
+    }
+
+    fn update_max_rss(&self) -> usize {
+        let rss = self.get_rss();
+        self.max_rss.fetch_max(rss, Ordering::SeqCst).max(rss)
+    }
+
+    fn resident_size(vm_manager: &VMManager) -> usize {
+        vm_manager
+            .vmas()
+            .iter()
+            .map(|vma| vma.resident_size())
+            .sum()
+    }
+
     pub fn get_brk(&self) -> usize {
         self.brk.load(Ordering::SeqCst)
     }
@@ -345,12 +580,29 @@ impl ProcessVM {
         fd: FileDesc,
         offset: usize,
     ) -> Result<usize> {
+        // A fixed shared mapping of a file at the address where the same pages
+        // of the file are already mapped by the process, e.g., to extend or
+        // replace a part of the mapping, shares the pages as well
+        if flags.contains(MMapFlags::MAP_FIXED) && flags.contains(MMapFlags::MAP_SHARED) {
+            if let Some(mmap_addr) = self.mmap_fixed_shm(addr, size, perms, fd, offset)? {
+                self.lock_new_mapping(mmap_addr, size, flags)?;
+                return Ok(mmap_addr);
+            }
+        }
         let addr_option = {
-            if flags.contains(MMapFlags::MAP_FIXED) {
+            if flags.contains(MMapFlags::MAP_FIXED_NOREPLACE) {
+                if !self.process_range.range().contains(addr) {
+                    return_errno!(ENOMEM, "Beyond valid memory range");
+                }
+                VMMapAddr::NoReplace(addr)
+            } else if flags.contains(MMapFlags::MAP_FIXED) {
                 if !self.process_range.range().contains(addr) {
                     return_errno!(EINVAL, "Beyond valid memory range");
                 }
                 VMMapAddr::Force(addr)
+            } else if flags.contains(MMapFlags::MAP_32BIT) {
+                // The hint is ignored, same as Linux
+                VMMapAddr::Below(MAP_32BIT_LIMIT)
             } else {
                 if addr == 0 {
                     VMMapAddr::Any
@@ -359,6 +611,39 @@ impl ProcessVM {
                 }
             }
         };
+        // The rings of an io_uring are shared by the LibOS and the process
+        if !flags.contains(MMapFlags::MAP_ANONYMOUS) {
+            let file_ref = current!().file(fd)?;
+            if let Ok(io_uring) = file_ref.as_io_uring() {
+                let segment = io_uring.mmap_segment(offset, size)?;
+                return self.attach_shm(segment);
+            }
+        }
+        // The shared mappings of regular files, e.g., the files of SEFS, hostfs
+        // and POSIX shared memory objects, share the pages of the file, so they
+        // are visible to all processes. The pages are out of the process range,
+        // so the mappings whose placement is constrained to the process range
+        // fall back to the write-back mappings below. They are loaded from and
+        // written back through the file, which is coherent with the shared
+        // pages, so the updates are visible to the others on msync and munmap.
+        if flags.contains(MMapFlags::MAP_SHARED)
+            && !flags.contains(MMapFlags::MAP_ANONYMOUS)
+            && !flags.intersects(
+                MMapFlags::MAP_FIXED | MMapFlags::MAP_FIXED_NOREPLACE | MMapFlags::MAP_32BIT,
+            )
+        {
+            let file_ref = current!().file(fd)?;
+            if let Ok(inode_file) = file_ref.as_inode_file() {
+                if inode_file.inode().metadata()?.type_ == FileType::File {
+                    if perms.can_write() && !inode_file.access_mode()?.writable() {
+                        return_errno!(EACCES, "the file is not opened for writing");
+                    }
+                    let mmap_addr = self.mmap_shm(inode_file, size, offset)?;
+                    self.lock_new_mapping(mmap_addr, size, flags)?;
+                    return Ok(mmap_addr);
+                }
+            }
+        }
         let initializer = {
             if flags.contains(MMapFlags::MAP_ANONYMOUS) {
                 VMInitializer::FillZeros()
@@ -373,6 +658,19 @@ impl ProcessVM {
         // Only shared, file-backed memory mappings have write-back files
         let writeback_file = if flags.contains(MMapFlags::MAP_SHARED) {
             if let VMInitializer::LoadFromFile { file, offset } = &initializer {
+                if perms.can_write() {
+                    if let Ok(inode_file) = file.as_inode_file() {
+                        if !inode_file.access_mode()?.writable() {
+                            return_errno!(EACCES, "the file is not opened for writing");
+                        }
+                    }
+                    if let Ok(mem_file) = file.as_mem_file() {
+                        let write_seals = FileSeals::F_SEAL_WRITE | FileSeals::F_SEAL_FUTURE_WRITE;
+                        if mem_file.seals().intersects(write_seals) {
+                            return_errno!(EPERM, "the file is sealed against writing");
+                        }
+                    }
+                }
                 Some((file.clone(), *offset))
             } else {
                 None
@@ -388,9 +686,127 @@ impl ProcessVM {
             .writeback_file(writeback_file)
             .build()?;
         let mmap_addr = self.vm_manager.lock().unwrap().mmap(mmap_options)?;
+        self.update_max_rss();
+        self.lock_new_mapping(mmap_addr, size, flags)?;
         Ok(mmap_addr)
     }
 
+    // Lock the new mapping if it is mapped with MAP_LOCKED or all the future
+    // mappings are locked
+    fn lock_new_mapping(&self, addr: usize, size: usize, flags: MMapFlags) -> Result<()> {
+        let mut locked_memory = self.locked_memory.lock().unwrap();
+        if flags.contains(MMapFlags::MAP_LOCKED) || locked_memory.lock_future {
+            locked_memory.lock(&VMRange::new_with_size(addr, align_up(size, PAGE_SIZE))?);
+        }
+        Ok(())
+    }
+
+    fn mmap_shm(&self, inode_file: &INodeFile, size: usize, offset: usize) -> Result<usize> {
+        if size == 0 {
+            return_errno!(EINVAL, "invalid size for mmap");
+        }
+        if offset % PAGE_SIZE != 0 {
+            return_errno!(EINVAL, "unaligned offset for mmap");
+        }
+        let segment = ShmSegment::attach(inode_file.inode(), inode_file.abs_path(), offset, size)?;
+        let mmap_addr = segment.range().start();
+        self.shm_segments.lock().unwrap().push(segment);
+        self.update_max_rss();
+        Ok(mmap_addr)
+    }
+
+    /// Map the pages of the file at the fixed address if the address is in a
+    /// shared mapping of the same file, where the page at the offset of the
+    /// file is mapped exactly.
+    ///
+    /// Return `None` if the address is in the process range, where the pages
+    /// can not be mapped.
+    fn mmap_fixed_shm(
+        &self,
+        addr: usize,
+        size: usize,
+        perms: VMPerms,
+        fd: FileDesc,
+        offset: usize,
+    ) -> Result<Option<usize>> {
+        if self.process_range.range().contains(addr) {
+            return Ok(None);
+        }
+        let file_ref = current!().file(fd)?;
+        let inode_file = file_ref.as_inode_file()?;
+        let is_same_page = self.shm_segments.lock().unwrap().iter().any(|segment| {
+            segment.is_backed_by(inode_file.inode()) && segment.file_offset_of(addr) == Some(offset)
+        });
+        if !is_same_page {
+            return_errno!(
+                EINVAL,
+                "the fixed address is not where the pages of the file are shared"
+            );
+        }
+        if perms.can_write() && !inode_file.access_mode()?.writable() {
+            return_errno!(EACCES, "the file is not opened for writing");
+        }
+        self.mmap_shm(inode_file, size, offset).map(Some)
+    }
+
+    /// Attach a System V shared memory segment to the process, returning its
+    /// address, which is the same in all the processes.
+    pub fn attach_shm(&self, segment: Arc<ShmSegment>) -> Result<usize> {
+        let attach_addr = segment.range().start();
+        let attach_size = segment.range().size();
+        self.shm_segments.lock().unwrap().push(segment);
+        self.update_max_rss();
+        self.lock_new_mapping(attach_addr, attach_size, MMapFlags::empty())?;
+        Ok(attach_addr)
+    }
+
+    /// Detach the System V shared memory segment attached at the address.
+    pub fn detach_shm(&self, addr: usize) -> Result<Arc<ShmSegment>> {
+        let segment = {
+            let mut shm_segments = self.shm_segments.lock().unwrap();
+            let idx = shm_segments
+                .iter()
+                .position(|segment| segment.is_anonymous() && segment.range().start() == addr)
+                .ok_or_else(|| {
+                    errno!(EINVAL, "no shared memory segment attached at the address")
+                })?;
+            shm_segments.remove(idx)
+        };
+        self.locked_memory.lock().unwrap().unlock(segment.range());
+        Ok(segment)
+    }
+
+    /// Get the shared memory segments attached to the process, sorted by address.
+    pub fn get_shm_segments(&self) -> Vec<Arc<ShmSegment>> {
+        let mut shm_segments = self.shm_segments.lock().unwrap().clone();
+        shm_segments.sort_by_key(|segment| segment.range().start());
+        shm_segments
+    }
+
+    /// Find the shared memory segment that contains the range.
+    fn find_shm_segment(&self, range: &VMRange) -> Option<Arc<ShmSegment>> {
+        self.shm_segments
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|segment| segment.range().is_superset_of(range))
+            .cloned()
+    }
+
+    /// Check whether the memory is inside a shared memory segment attached to
+    /// the process, which is out of the process range.
+    pub fn is_inside_shm(&self, addr: usize, len: usize) -> bool {
+        let end = match addr.checked_add(len) {
+            Some(end) => end,
+            None => return false,
+        };
+        self.shm_segments
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|segment| addr >= segment.range().start() && end <= segment.range().end())
+    }
+
     pub fn mremap(
         &self,
         old_addr: usize,
@@ -405,11 +821,126 @@ impl ProcessVM {
         }
 
         let mremap_option = VMRemapOptions::new(old_addr, old_size, new_size, flags)?;
-        self.vm_manager.lock().unwrap().mremap(&mremap_option)
+        let new_addr = self.vm_manager.lock().unwrap().mremap(&mremap_option)?;
+        self.update_max_rss();
+
+        // Same as Linux, the remapped memory is still locked if it was
+        let mut locked_memory = self.locked_memory.lock().unwrap();
+        let old_range = VMRange::new_with_size(old_addr, align_up(old_size, PAGE_SIZE))?;
+        let new_range = VMRange::new_with_size(new_addr, align_up(new_size, PAGE_SIZE))?;
+        if locked_memory.is_locked(&old_range) || locked_memory.lock_future {
+            locked_memory.unlock(&old_range);
+            locked_memory.lock(&new_range);
+        }
+        Ok(new_addr)
     }
 
     pub fn munmap(&self, addr: usize, size: usize) -> Result<()> {
-        self.vm_manager.lock().unwrap().munmap(addr, size)
+        // A shared memory segment is detached as a whole, even if only a part
+        // of it is unmapped
+        if size > 0 {
+            let munmap_range = VMRange::new_with_size(addr, align_up(size, PAGE_SIZE))?;
+            let mut shm_segments = self.shm_segments.lock().unwrap();
+            // The segment that starts at the address is preferred, as the
+            // segments of a file may overlap
+            if let Some(idx) = shm_segments
+                .iter()
+                .position(|segment| segment.range().start() == addr)
+                .or_else(|| {
+                    shm_segments
+                        .iter()
+                        .position(|segment| segment.range().overlap_with(&munmap_range))
+                })
+            {
+                shm_segments.remove(idx);
+                return Ok(());
+            }
+        }
+        let mut stack_guards = self.stack_guards.lock().unwrap();
+        let mut vm_manager = self.vm_manager.lock().unwrap();
+        vm_manager.munmap(addr, size)?;
+
+        let munmap_range = VMRange::new_with_size(addr, align_up(size, PAGE_SIZE))?;
+        self.locked_memory.lock().unwrap().unlock(&munmap_range);
+
+        // Forget the guard regions that are unmapped, and unmap the guard
+        // regions mapped by LibOS along with the stacks above them
+        stack_guards.retain(|guard| {
+            if guard.range.overlap_with(&munmap_range) {
+                return false;
+            }
+            if guard.is_owned && munmap_range.contains(guard.range.end()) {
+                let _ = vm_manager.munmap(guard.range.start(), guard.range.size());
+                return false;
+            }
+            true
+        });
+        Ok(())
+    }
+
+    /// Set up the guard region below the stack of a new thread.
+    ///
+    /// If the user has put an inaccessible region right below the stack, e.g.,
+    /// the guard page of a pthread stack, the region is used as the guard.
+    /// Otherwise, a guard region is mapped below the stack if the memory there
+    /// is free.
+    pub fn add_stack_guard(&self, stack_range: &VMRange) -> Result<()> {
+        let mut stack_guards = self.stack_guards.lock().unwrap();
+        if stack_guards
+            .iter()
+            .any(|guard| guard.range.end() == stack_range.start())
+        {
+            return Ok(());
+        }
+
+        let mut vm_manager = self.vm_manager.lock().unwrap();
+        let user_guard_range = vm_manager
+            .vmas()
+            .iter()
+            .find(|vma| vma.end() == stack_range.start() && vma.perms() == VMPerms::NONE)
+            .map(|vma| *vma.range());
+        let stack_guard = match user_guard_range {
+            Some(guard_range) => StackGuard::new(guard_range, false),
+            None => {
+                if stack_range.start() < vm_manager.range().start() + STACK_GUARD_SIZE {
+                    return_errno!(ENOMEM, "no room for the stack guard");
+                }
+                let guard_start = stack_range.start() - STACK_GUARD_SIZE;
+                let vm_option = VMMapOptionsBuilder::default()
+                    .size(STACK_GUARD_SIZE)
+                    .addr(VMMapAddr::Need(guard_start))
+                    .perms(VMPerms::NONE)
+                    .initializer(VMInitializer::DoNothing())
+                    .build()?;
+                vm_manager.mmap(vm_option)?;
+                let guard_range = VMRange::new_with_size(guard_start, STACK_GUARD_SIZE)?;
+                StackGuard::new(guard_range, true)
+            }
+        };
+        trace!("stack guard range = {:?}", stack_guard.range);
+        stack_guards.push(stack_guard);
+        Ok(())
+    }
+
+    /// Find the stack guard region that contains the address, which means
+    /// the stack below which the region lies overflows if the address is
+    /// accessed.
+    pub fn find_stack_guard(&self, addr: usize) -> Option<VMRange> {
+        self.stack_guards
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|guard| guard.range)
+            .find(|range| range.contains(addr))
+    }
+
+    /// Check whether the range overlaps with any stack guard region.
+    pub fn overlaps_stack_guard(&self, range: &VMRange) -> bool {
+        self.stack_guards
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|guard| guard.range.overlap_with(range))
     }
 
     pub fn mprotect(&self, addr: usize, size: usize, perms: VMPerms) -> Result<()> {
@@ -420,6 +951,12 @@ impl ProcessVM {
             align_up(size, PAGE_SIZE)
         };
         let protect_range = VMRange::new_with_size(addr, size)?;
+        if self.find_shm_segment(&protect_range).is_some() {
+            // The segment is shared by the processes, so its permissions are
+            // kept as readable and writable
+            warn!("Do not support mprotect shared memory yet");
+            return Ok(());
+        }
         if !self.process_range.range().is_superset_of(&protect_range) {
             return_errno!(ENOMEM, "invalid range");
         }
@@ -436,15 +973,165 @@ impl ProcessVM {
 
     pub fn msync(&self, addr: usize, size: usize) -> Result<()> {
         let sync_range = VMRange::new_with_size(addr, size)?;
+        if let Some(segment) = self.find_shm_segment(&sync_range) {
+            segment.flush();
+            return Ok(());
+        }
         let mut mmap_manager = self.vm_manager.lock().unwrap();
         mmap_manager.msync_by_range(&sync_range)
     }
 
+    pub fn madvise(&self, addr: usize, size: usize, advice: MAdvice) -> Result<()> {
+        if addr % PAGE_SIZE != 0 {
+            return_errno!(EINVAL, "unaligned address");
+        }
+        if size == 0 {
+            return Ok(());
+        }
+        let advise_range = VMRange::new_with_size(addr, align_up(size, PAGE_SIZE))?;
+        if self.find_shm_segment(&advise_range).is_some() {
+            // The content of a shared memory segment is kept in the segment
+            return Ok(());
+        }
+        if !self.process_range.range().is_superset_of(&advise_range) {
+            return_errno!(ENOMEM, "invalid range");
+        }
+
+        let mut mmap_manager = self.vm_manager.lock().unwrap();
+        let is_discarding = advice == MAdvice::MADV_DONTNEED || advice == MAdvice::MADV_FREE;
+        if is_discarding
+            && self
+                .elf_ranges
+                .iter()
+                .any(|elf_range| elf_range.overlap_with(&advise_range))
+        {
+            // The memory of ELFs can not be reloaded from the ELF files
+            warn!("Do not support discarding the memory of ELFs");
+            return mmap_manager.madvise(&advise_range, MAdvice::MADV_NORMAL);
+        }
+        mmap_manager.madvise(&advise_range, advice)
+    }
+
+    /// Lock the pages in the range, given the maximum size of locked memory.
+    pub fn mlock(&self, addr: usize, size: usize, max_locked_size: usize) -> Result<()> {
+        let lock_range = match self.get_mlock_range(addr, size)? {
+            Some(range) => range,
+            None => return Ok(()),
+        };
+        let mut locked_memory = self.locked_memory.lock().unwrap();
+        if locked_memory.size() + locked_memory.unlocked_size_of(&lock_range) > max_locked_size {
+            return_errno!(ENOMEM, "the locked memory exceeds RLIMIT_MEMLOCK");
+        }
+        locked_memory.lock(&lock_range);
+        Ok(())
+    }
+
+    pub fn munlock(&self, addr: usize, size: usize) -> Result<()> {
+        let unlock_range = match self.get_mlock_range(addr, size)? {
+            Some(range) => range,
+            None => return Ok(()),
+        };
+        self.locked_memory.lock().unwrap().unlock(&unlock_range);
+        Ok(())
+    }
+
+    /// Lock all the pages mapped currently or in the future, given the
+    /// maximum size of locked memory.
+    pub fn mlockall(&self, flags: MLockAllFlags, max_locked_size: usize) -> Result<()> {
+        if flags.contains(MLockAllFlags::MCL_CURRENT) {
+            let mut all_ranges: Vec<VMRange> = self
+                .vm_manager
+                .lock()
+                .unwrap()
+                .vmas()
+                .iter()
+                .filter(|vma| vma.size() > 0)
+                .map(|vma| *vma.range())
+                .collect();
+            all_ranges.extend(
+                self.shm_segments
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|segment| *segment.range()),
+            );
+            let all_size: usize = all_ranges.iter().map(|range| range.size()).sum();
+            if all_size > max_locked_size {
+                return_errno!(ENOMEM, "the locked memory exceeds RLIMIT_MEMLOCK");
+            }
+            self.locked_memory.lock().unwrap().ranges = all_ranges;
+        }
+        self.locked_memory.lock().unwrap().lock_future = flags.contains(MLockAllFlags::MCL_FUTURE);
+        Ok(())
+    }
+
+    pub fn munlockall(&self) {
+        let mut locked_memory = self.locked_memory.lock().unwrap();
+        locked_memory.ranges.clear();
+        locked_memory.lock_future = false;
+    }
+
+    /// Get the size of the locked memory in bytes.
+    pub fn get_locked_size(&self) -> usize {
+        self.locked_memory.lock().unwrap().size()
+    }
+
+    /// Whether the memory mapped in the future is locked, in which case its
+    /// size is also limited by RLIMIT_MEMLOCK.
+    pub fn is_future_locked(&self) -> bool {
+        self.locked_memory.lock().unwrap().lock_future
+    }
+
+    // Get the pages to be locked or unlocked, all of which must be mapped
+    fn get_mlock_range(&self, addr: usize, size: usize) -> Result<Option<VMRange>> {
+        if size == 0 {
+            return Ok(None);
+        }
+        let start = align_down(addr, PAGE_SIZE);
+        let end = align_up(
+            addr.checked_add(size)
+                .ok_or_else(|| errno!(ENOMEM, "invalid range"))?,
+            PAGE_SIZE,
+        );
+        let range = VMRange::new(start, end)?;
+        let is_mapped = self.find_shm_segment(&range).is_some()
+            || self.vm_manager.lock().unwrap().is_fully_mapped(&range);
+        if !is_mapped {
+            return_errno!(ENOMEM, "the range is not mapped entirely");
+        }
+        Ok(Some(range))
+    }
+
     pub fn msync_by_file(&self, sync_file: &FileRef) {
+        if let Ok(inode_file) = sync_file.as_inode_file() {
+            self.shm_segments
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|segment| segment.is_backed_by(inode_file.inode()))
+                .for_each(|segment| segment.flush());
+        }
         let mut mmap_manager = self.vm_manager.lock().unwrap();
         mmap_manager.msync_by_file(sync_file);
     }
 
+    pub fn has_shared_writable_mapping(&self, file: &FileRef) -> bool {
+        let mmap_manager = self.vm_manager.lock().unwrap();
+        mmap_manager.has_shared_writable_mapping(file)
+    }
+
+    /// Get a copy of all the VMAs of the process, sorted by address.
+    pub fn get_vmas(&self) -> Vec<VMArea> {
+        let mmap_manager = self.vm_manager.lock().unwrap();
+        mmap_manager
+            .vmas()
+            .iter()
+            // Skip the empty sentry VMAs
+            .filter(|vma| vma.size() > 0)
+            .cloned()
+            .collect()
+    }
+
     // Return: a copy of the found region
     pub fn find_mmap_region(&self, addr: usize) -> Result<VMRange> {
         self.vm_manager
@@ -453,6 +1140,78 @@ impl ProcessVM {
             .find_mmap_region(addr)
             .map(|range_ref| *range_ref)
     }
+
+    /// Save the memory of the process, which can be restored later.
+    ///
+    /// The shared mappings are not saved, so that the changes to them after
+    /// the snapshot is taken are kept after the snapshot is restored.
+    pub fn snapshot(&self) -> Result<VMSnapshot> {
+        let mmap_manager = self.vm_manager.lock().unwrap();
+        let vmas = mmap_manager.vmas().clone();
+        let content_size = vmas
+            .iter()
+            .filter(|vma| VMSnapshot::should_save(vma))
+            .map(|vma| vma.size())
+            .sum::<usize>()
+            .max(PAGE_SIZE);
+        // The memory of a process is too large to fit in the kernel heap
+        let content = USER_SPACE_VM_MANAGER.alloc(content_size)?;
+        let mut buf = unsafe { content.range().as_slice_mut() };
+        for vma in vmas.iter().filter(|vma| VMSnapshot::should_save(vma)) {
+            let (vma_buf, rest_buf) = buf.split_at_mut(vma.size());
+            vma_buf.copy_from_slice(unsafe { vma.as_slice() });
+            buf = rest_buf;
+        }
+        drop(mmap_manager);
+
+        Ok(VMSnapshot {
+            vmas,
+            brk: self.get_brk(),
+            shm_segments: self.shm_segments.lock().unwrap().clone(),
+            content,
+        })
+    }
+
+    /// Restore the memory of the process to the time when the snapshot was taken.
+    pub fn restore(&self, snapshot: VMSnapshot) -> Result<()> {
+        let VMSnapshot {
+            vmas,
+            brk,
+            shm_segments,
+            content,
+        } = snapshot;
+        let mut mmap_manager = self.vm_manager.lock().unwrap();
+        mmap_manager.replace_vmas(vmas, |vmas| {
+            let mut buf = unsafe { content.range().as_slice() };
+            for vma in vmas.iter().filter(|vma| VMSnapshot::should_save(vma)) {
+                let (vma_buf, rest_buf) = buf.split_at(vma.size());
+                unsafe { vma.as_slice_mut() }.copy_from_slice(vma_buf);
+                buf = rest_buf;
+            }
+        })?;
+        drop(mmap_manager);
+
+        self.brk.store(brk, Ordering::SeqCst);
+        *self.shm_segments.lock().unwrap() = shm_segments;
+        Ok(())
+    }
+}
+
+/// A copy of the memory of a process, see `ProcessVM::snapshot`.
+pub struct VMSnapshot {
+    vmas: Vec<VMArea>,
+    brk: usize,
+    shm_segments: Vec<Arc<ShmSegment>>,
+    // The content of the saved VMAs, one after another
+    content: UserSpaceVMRange,
+}
+
+impl VMSnapshot {
+    /// Whether the content of the VMA is saved. The VMAs that are not readable
+    /// cannot be accessed by LibOS either.
+    fn should_save(vma: &VMArea) -> bool {
+        vma.size() > 0 && vma.perms().can_read() && vma.writeback_file().is_none()
+    }
 }
 
 bitflags! {
@@ -464,6 +1223,7 @@ bitflags! {
         const MAP_TYPE            = 0xf;
         const MAP_FIXED           = 0x10;
         const MAP_ANONYMOUS       = 0x20;
+        const MAP_32BIT           = 0x40;
         const MAP_GROWSDOWN       = 0x100;
         const MAP_DENYWRITE       = 0x800;
         const MAP_EXECUTABLE      = 0x1000;
@@ -522,6 +1282,41 @@ impl Default for MRemapFlags {
     }
 }
 
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(u32)]
+pub enum MAdvice {
+    MADV_NORMAL = 0,
+    MADV_RANDOM = 1,
+    MADV_SEQUENTIAL = 2,
+    MADV_WILLNEED = 3,
+    MADV_DONTNEED = 4,
+    MADV_FREE = 8,
+    MADV_REMOVE = 9,
+    MADV_DONTFORK = 10,
+    MADV_DOFORK = 11,
+    MADV_MERGEABLE = 12,
+    MADV_UNMERGEABLE = 13,
+    MADV_HUGEPAGE = 14,
+    MADV_NOHUGEPAGE = 15,
+    MADV_DONTDUMP = 16,
+    MADV_DODUMP = 17,
+    MADV_WIPEONFORK = 18,
+    MADV_KEEPONFORK = 19,
+    MADV_COLD = 20,
+    MADV_PAGEOUT = 21,
+}
+
+impl MAdvice {
+    pub fn from_u32(raw: u32) -> Result<Self> {
+        let advice = match raw {
+            0..=4 | 8..=21 => unsafe { core::mem::transmute(raw) },
+            _ => return_errno!(EINVAL, "unknown advice"),
+        };
+        Ok(advice)
+    }
+}
+
 bitflags! {
     pub struct MSyncFlags : u32 {
         const MS_ASYNC      = 0x1;
@@ -530,6 +1325,37 @@ bitflags! {
     }
 }
 
+bitflags! {
+    pub struct MLockFlags : u32 {
+        const MLOCK_ONFAULT = 0x1;
+    }
+}
+
+impl MLockFlags {
+    pub fn from_u32(bits: u32) -> Result<Self> {
+        MLockFlags::from_bits(bits).ok_or_else(|| errno!(EINVAL, "containing unknown bits"))
+    }
+}
+
+bitflags! {
+    pub struct MLockAllFlags : u32 {
+        const MCL_CURRENT = 0x1;
+        const MCL_FUTURE  = 0x2;
+        const MCL_ONFAULT = 0x4;
+    }
+}
+
+impl MLockAllFlags {
+    pub fn from_u32(bits: u32) -> Result<Self> {
+        let flags = MLockAllFlags::from_bits(bits)
+            .ok_or_else(|| errno!(EINVAL, "containing unknown bits"))?;
+        if !flags.intersects(Self::MCL_CURRENT | Self::MCL_FUTURE) {
+            return_errno!(EINVAL, "must lock either current or future memory");
+        }
+        Ok(flags)
+    }
+}
+
 impl MSyncFlags {
     pub fn from_u32(bits: u32) -> Result<Self> {
         let flags =
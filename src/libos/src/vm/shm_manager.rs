@@ -0,0 +1,376 @@
+use super::*;
+
+use super::edmm;
+use super::user_space_vm::{UserSpaceVMRange, USER_SPACE_VM_MANAGER};
+use super::vm_manager::fill_zeros;
+use rcore_fs::vfs::INode;
+use std::ops::Range;
+use std::sync::Weak;
+
+/// A segment of memory shared by the MAP_SHARED mappings of a regular file,
+/// e.g., a file of SEFS or hostfs, or a POSIX shared memory object in
+/// `/dev/shm`, or a System V shared memory segment, which is backed by no file.
+///
+/// All the processes in an enclave share one address space, so a page of
+/// memory can not be mapped at more than one address. Thus the pages of a file
+/// are kept in one range of memory out of the range of any process, where
+/// the page of index `i` is always at the offset `i * PAGE_SIZE`, and a
+/// segment of a file is the part of the range that is mapped. The mappings
+/// of the same page, no matter at which offset the mappings start, share the
+/// page, and get the same address in all the processes.
+///
+/// A page of a file is loaded from the file when it is mapped for the first
+/// time. The pages are written back to the file on msync, fsync and when the
+/// segment is unmapped, including the ones that are unmapped when the
+/// processes exit. The loaded pages are also kept coherent with the reads and
+/// writes of the file, see `read_shared_pages` and `write_shared_pages`.
+pub struct ShmSegment {
+    pages: ShmPages,
+    range: VMRange,
+}
+
+enum ShmPages {
+    /// The memory of a segment that is backed by no file
+    Anonymous(UserSpaceVMRange),
+    /// The pages of a file, of which the segment maps the ones from the offset
+    File {
+        file_pages: Arc<ShmFilePages>,
+        offset: usize,
+        /// The path of the file that is mapped
+        abs_path: String,
+    },
+}
+
+/// The pages of a file that are shared by the segments of the file.
+struct ShmFilePages {
+    key: ShmKey,
+    inode: Arc<dyn INode>,
+    range: UserSpaceVMRange,
+    /// Whether each page is loaded from the file. The lock also serializes the
+    /// copies between the pages and the file.
+    loaded: SgxMutex<Vec<bool>>,
+}
+
+/// The pages of a file are identified by the device ID and the inode number.
+type ShmKey = (usize, usize);
+
+lazy_static! {
+    static ref SHM_FILE_PAGES: SgxMutex<HashMap<ShmKey, Weak<ShmFilePages>>> =
+        SgxMutex::new(HashMap::new());
+}
+
+impl ShmSegment {
+    /// Get a segment of the pages of the given region of the file.
+    ///
+    /// The pages are shared with the other segments of the file, which have
+    /// reserved the memory for the pages up to the end of the file when they
+    /// are created. So a segment beyond the reserved memory can not be created
+    /// until all the segments of the file are unmapped.
+    pub fn attach(
+        inode: &Arc<dyn INode>,
+        abs_path: &str,
+        offset: usize,
+        size: usize,
+    ) -> Result<Arc<Self>> {
+        if offset % PAGE_SIZE != 0 {
+            return_errno!(EINVAL, "unaligned offset of shared memory");
+        }
+        let size = align_up(size, PAGE_SIZE);
+        let end = offset
+            .checked_add(size)
+            .ok_or_else(|| errno!(EINVAL, "the region of the file overflows"))?;
+        let metadata = inode.metadata()?;
+        let key = (metadata.dev, metadata.inode);
+
+        let file_pages = {
+            let mut all_file_pages = SHM_FILE_PAGES.lock().unwrap();
+            match all_file_pages.get(&key).and_then(|pages| pages.upgrade()) {
+                Some(file_pages) => file_pages,
+                None => {
+                    // Reserve the memory for the whole file if possible, so that
+                    // the other regions of the file can be mapped later
+                    let file_pages = ShmFilePages::new(key, inode, max(end, metadata.size))
+                        .or_else(|_| ShmFilePages::new(key, inode, end))?;
+                    let file_pages = Arc::new(file_pages);
+                    all_file_pages.insert(key, Arc::downgrade(&file_pages));
+                    file_pages
+                }
+            }
+        };
+        if end > file_pages.range().size() {
+            return_errno!(
+                ENOMEM,
+                "the region is beyond the pages of the file mapped by others"
+            );
+        }
+        file_pages.load(offset, size)?;
+
+        let range = VMRange::new_with_size(file_pages.range().start() + offset, size)?;
+        Ok(Arc::new(Self {
+            pages: ShmPages::File {
+                file_pages,
+                offset,
+                abs_path: abs_path.to_owned(),
+            },
+            range,
+        }))
+    }
+
+    /// Create a segment filled with zeros that is backed by no file.
+    pub fn new_anonymous(size: usize) -> Result<Arc<Self>> {
+        let size = align_up(size, PAGE_SIZE);
+        let user_range = USER_SPACE_VM_MANAGER.alloc(size)?;
+        fill_zeros(unsafe { user_range.range().as_slice_mut() })?;
+        Ok(Arc::new(Self {
+            range: *user_range.range(),
+            pages: ShmPages::Anonymous(user_range),
+        }))
+    }
+
+    pub fn range(&self) -> &VMRange {
+        &self.range
+    }
+
+    /// Get the path of the file, the device ID, the inode number and the
+    /// offset in the file of the segment.
+    pub fn file_info(&self) -> Option<(&str, usize, usize, usize)> {
+        match &self.pages {
+            ShmPages::File {
+                file_pages,
+                offset,
+                abs_path,
+            } => Some((abs_path, file_pages.key.0, file_pages.key.1, *offset)),
+            ShmPages::Anonymous(_) => None,
+        }
+    }
+
+    /// Get the offset in the file of the given address of the segment.
+    pub fn file_offset_of(&self, addr: usize) -> Option<usize> {
+        match &self.pages {
+            ShmPages::File { offset, .. } if self.range.contains(addr) => {
+                Some(offset + (addr - self.range.start()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the size of the memory of the segment that is resident in the
+    /// enclave.
+    pub fn resident_size(&self) -> usize {
+        edmm::resident_size(self.range())
+    }
+
+    /// Get the number of the mappings that share the pages of the segment.
+    pub fn nr_sharers(self: &Arc<Self>) -> usize {
+        match &self.pages {
+            ShmPages::Anonymous(_) => Arc::strong_count(self),
+            ShmPages::File { file_pages, .. } => Arc::strong_count(file_pages),
+        }
+    }
+
+    /// Check whether the segment is backed by no file.
+    pub fn is_anonymous(&self) -> bool {
+        match &self.pages {
+            ShmPages::Anonymous(_) => true,
+            ShmPages::File { .. } => false,
+        }
+    }
+
+    /// Check whether the segment is a region of the given file.
+    pub fn is_backed_by(&self, inode: &Arc<dyn INode>) -> bool {
+        let key = match &self.pages {
+            ShmPages::File { file_pages, .. } => file_pages.key,
+            ShmPages::Anonymous(_) => return false,
+        };
+        match inode.metadata() {
+            Ok(metadata) => metadata.dev == key.0 && metadata.inode == key.1,
+            Err(_) => false,
+        }
+    }
+
+    /// Write the content of the segment back to the file.
+    ///
+    /// The file is not extended, so the part of the segment beyond the end of
+    /// the file is discarded, which is not accessible on Linux either.
+    pub fn flush(&self) {
+        if let ShmPages::File {
+            file_pages, offset, ..
+        } = &self.pages
+        {
+            file_pages.flush(*offset, self.range.size());
+        }
+    }
+
+    /// Copy the content of the pages of the file that are mapped into the
+    /// buffer, which is just read from the file at the offset.
+    ///
+    /// The pages may be written through the mappings since they are loaded,
+    /// so they are more up-to-date than the file.
+    pub fn read_shared_pages(inode: &Arc<dyn INode>, offset: usize, buf: &mut [u8]) {
+        if let Some(file_pages) = ShmFilePages::of(inode) {
+            file_pages.for_each_loaded(offset, buf.len(), |pages_buf, buf_range| {
+                buf[buf_range].copy_from_slice(pages_buf);
+            });
+        }
+    }
+
+    /// Copy the buffer that is just written to the file at the offset into
+    /// the pages of the file that are mapped.
+    pub fn write_shared_pages(inode: &Arc<dyn INode>, offset: usize, buf: &[u8]) {
+        if let Some(file_pages) = ShmFilePages::of(inode) {
+            file_pages.for_each_loaded(offset, buf.len(), |pages_buf, buf_range| {
+                pages_buf.copy_from_slice(&buf[buf_range]);
+            });
+        }
+    }
+
+    /// Zero the pages of the file that are mapped beyond the end of the file,
+    /// which is just resized. The data beyond the end is discarded when the
+    /// file is shrunk, and the file is extended with zeros.
+    pub fn resize_shared_pages(inode: &Arc<dyn INode>, old_size: usize, new_size: usize) {
+        if let Some(file_pages) = ShmFilePages::of(inode) {
+            let start = min(old_size, new_size);
+            let len = file_pages.range().size().saturating_sub(start);
+            file_pages.for_each_loaded(start, len, |pages_buf, _| {
+                for b in pages_buf {
+                    *b = 0;
+                }
+            });
+        }
+    }
+}
+
+impl Drop for ShmSegment {
+    fn drop(&mut self) {
+        // The pages are written back before they are released, so that the
+        // pages of the file mapped again are loaded with the up-to-date content
+        self.flush();
+    }
+}
+
+impl Debug for ShmSegment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let offset = match &self.pages {
+            ShmPages::File { offset, .. } => Some(*offset),
+            ShmPages::Anonymous(_) => None,
+        };
+        f.debug_struct("ShmSegment")
+            .field("offset", &offset)
+            .field("range", self.range())
+            .finish()
+    }
+}
+
+impl ShmFilePages {
+    fn new(key: ShmKey, inode: &Arc<dyn INode>, size: usize) -> Result<Self> {
+        let size = align_up(size, PAGE_SIZE);
+        let range = USER_SPACE_VM_MANAGER.alloc(size)?;
+        Ok(Self {
+            key,
+            inode: inode.clone(),
+            range,
+            loaded: SgxMutex::new(vec![false; size / PAGE_SIZE]),
+        })
+    }
+
+    /// Get the pages of the file if it is mapped.
+    fn of(inode: &Arc<dyn INode>) -> Option<Arc<Self>> {
+        let all_file_pages = SHM_FILE_PAGES.lock().unwrap();
+        if all_file_pages.is_empty() {
+            return None;
+        }
+        let metadata = inode.metadata().ok()?;
+        all_file_pages
+            .get(&(metadata.dev, metadata.inode))
+            .and_then(|file_pages| file_pages.upgrade())
+    }
+
+    fn range(&self) -> &VMRange {
+        self.range.range()
+    }
+
+    /// Load the pages of the region that are not loaded yet from the file.
+    fn load(&self, offset: usize, size: usize) -> Result<()> {
+        let mut loaded = self.loaded.lock().unwrap();
+        for page_idx in offset / PAGE_SIZE..(offset + size) / PAGE_SIZE {
+            if loaded[page_idx] {
+                continue;
+            }
+            let page_offset = page_idx * PAGE_SIZE;
+            let page_buf = unsafe {
+                VMRange::from_unchecked(
+                    self.range().start() + page_offset,
+                    self.range().start() + page_offset + PAGE_SIZE,
+                )
+                .as_slice_mut()
+            };
+            let len = self.inode.read_at(page_offset, page_buf)?;
+            for b in &mut page_buf[len..] {
+                *b = 0;
+            }
+            loaded[page_idx] = true;
+        }
+        Ok(())
+    }
+
+    /// Write the loaded pages of the region back to the file.
+    fn flush(&self, offset: usize, size: usize) {
+        let file_size = match self.inode.metadata() {
+            Ok(metadata) => metadata.size,
+            Err(_) => return,
+        };
+        if file_size <= offset {
+            return;
+        }
+        let len = min(size, file_size - offset);
+        let mut pending = Vec::new();
+        self.for_each_loaded(offset, len, |pages_buf, buf_range| {
+            pending.push((offset + buf_range.start, pages_buf.to_vec()));
+        });
+        for (offset, buf) in pending {
+            if let Err(e) = self.inode.write_at(offset, &buf) {
+                warn!("failed to write back the shared memory: {:?}", e);
+            }
+        }
+    }
+
+    /// Call the closure on the parts of the loaded pages in the region of the
+    /// file, along with the range of each part relative to the region.
+    fn for_each_loaded(
+        &self,
+        offset: usize,
+        len: usize,
+        mut f: impl FnMut(&mut [u8], Range<usize>),
+    ) {
+        let loaded = self.loaded.lock().unwrap();
+        let end = min(offset.saturating_add(len), self.range().size());
+        let mut pos = offset;
+        while pos < end {
+            let part_end = min(align_down(pos, PAGE_SIZE) + PAGE_SIZE, end);
+            if loaded[pos / PAGE_SIZE] {
+                let pages_buf = unsafe {
+                    VMRange::from_unchecked(
+                        self.range().start() + pos,
+                        self.range().start() + part_end,
+                    )
+                    .as_slice_mut()
+                };
+                f(pages_buf, pos - offset..part_end - offset);
+            }
+            pos = part_end;
+        }
+    }
+}
+
+impl Drop for ShmFilePages {
+    fn drop(&mut self) {
+        let mut all_file_pages = SHM_FILE_PAGES.lock().unwrap();
+        let is_stale = all_file_pages
+            .get(&self.key)
+            .map(|file_pages| file_pages.strong_count() == 0)
+            .unwrap_or(false);
+        if is_stale {
+            all_file_pages.remove(&self.key);
+        }
+    }
+}
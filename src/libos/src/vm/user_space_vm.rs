@@ -1,3 +1,4 @@
+use super::edmm;
 use super::*;
 use config::LIBOS_CONFIG;
 
@@ -17,21 +18,38 @@ impl UserSpaceVMManager {
     }
 
     pub fn alloc(&self, size: usize) -> Result<UserSpaceVMRange> {
-        let vm_range = unsafe {
-            let ptr = sgx_alloc_rsrv_mem(size);
-            let perm = MemPerm::READ | MemPerm::WRITE;
-            if ptr.is_null() {
-                return_errno!(ENOMEM, "run out of reserved memory");
-            }
-            // Change the page permission to RW (default)
-            assert!(sgx_tprotect_rsrv_mem(ptr, size, perm.bits()) == sgx_status_t::SGX_SUCCESS);
+        let mut free_size = self.free_size.lock().unwrap();
+        // The user space size is a ceiling of the memory committed on demand
+        // with EDMM, or the size of the reserved memory without EDMM
+        if size > *free_size {
+            return_errno!(ENOMEM, "run out of reserved memory");
+        }
 
-            let addr = ptr as usize;
-            debug!("allocated rsrv addr is 0x{:x}, len is 0x{:x}", addr, size);
-            VMRange::from_unchecked(addr, addr + size)
+        let vm_range = if edmm::is_supported() {
+            let vm_range = edmm::alloc_on_demand(size)?;
+            debug!(
+                "allocated on-demand addr is 0x{:x}, len is 0x{:x}",
+                vm_range.start(),
+                size
+            );
+            vm_range
+        } else {
+            unsafe {
+                let ptr = sgx_alloc_rsrv_mem(size);
+                let perm = MemPerm::READ | MemPerm::WRITE;
+                if ptr.is_null() {
+                    return_errno!(ENOMEM, "run out of reserved memory");
+                }
+                // Change the page permission to RW (default)
+                assert!(sgx_tprotect_rsrv_mem(ptr, size, perm.bits()) == sgx_status_t::SGX_SUCCESS);
+
+                let addr = ptr as usize;
+                debug!("allocated rsrv addr is 0x{:x}, len is 0x{:x}", addr, size);
+                VMRange::from_unchecked(addr, addr + size)
+            }
         };
 
-        *self.free_size.lock().unwrap() -= size;
+        *free_size -= size;
         Ok(UserSpaceVMRange::new(vm_range))
     }
 
@@ -121,6 +139,10 @@ impl Drop for UserSpaceVMRange {
 
         USER_SPACE_VM_MANAGER.add_free_size(self);
 
-        assert!(unsafe { sgx_free_rsrv_mem(addr, size) == 0 });
+        if edmm::is_supported() {
+            edmm::dealloc(&self.vm_range);
+        } else {
+            assert!(unsafe { sgx_free_rsrv_mem(addr, size) == 0 });
+        }
     }
 }
@@ -8,22 +8,32 @@ use super::*;
 pub struct VMArea {
     range: VMRange,
     perms: VMPerms,
-    writeback_file: Option<(FileRef, usize)>,
+    // The file mapped by the VMA and the file offset of the start of the VMA
+    file: Option<(FileRef, usize)>,
+    // Whether the VMA is a shared mapping of the file, whose content is
+    // written back to the file
+    is_shared: bool,
 }
 
 impl VMArea {
-    pub fn new(range: VMRange, perms: VMPerms, writeback_file: Option<(FileRef, usize)>) -> Self {
+    pub fn new(
+        range: VMRange,
+        perms: VMPerms,
+        file: Option<(FileRef, usize)>,
+        is_shared: bool,
+    ) -> Self {
         Self {
             range,
             perms,
-            writeback_file,
+            is_shared: is_shared && file.is_some(),
+            file,
         }
     }
 
-    /// Create a new VMArea object that inherits the write-back file (if any), but has
+    /// Create a new VMArea object that inherits the file (if any), but has
     /// a new range and permissions.
     pub fn inherits_file_from(vma: &VMArea, new_range: VMRange, new_perms: VMPerms) -> Self {
-        let new_writeback_file = vma.writeback_file.as_ref().map(|(file, file_offset)| {
+        let new_file = vma.file.as_ref().map(|(file, file_offset)| {
             let new_file = file.clone();
 
             let new_file_offset = if vma.start() < new_range.start() {
@@ -36,7 +46,7 @@ impl VMArea {
             };
             (new_file, new_file_offset)
         });
-        Self::new(new_range, new_perms, new_writeback_file)
+        Self::new(new_range, new_perms, new_file, vma.is_shared)
     }
 
     pub fn perms(&self) -> VMPerms {
@@ -47,8 +57,29 @@ impl VMArea {
         &self.range
     }
 
-    pub fn writeback_file(&self) -> &Option<(FileRef, usize)> {
-        &self.writeback_file
+    /// Get the size of the memory of the VMA that is resident in the enclave.
+    pub fn resident_size(&self) -> usize {
+        edmm::resident_size(&self.range)
+    }
+
+    /// Get the file mapped by the VMA and the file offset of its start, which
+    /// is either a shared or a private mapping of the file.
+    pub fn file(&self) -> Option<&(FileRef, usize)> {
+        self.file.as_ref()
+    }
+
+    /// Get the file that the content of the VMA is written back to, i.e., the
+    /// file of a shared mapping.
+    pub fn writeback_file(&self) -> Option<&(FileRef, usize)> {
+        if self.is_shared {
+            self.file.as_ref()
+        } else {
+            None
+        }
+    }
+
+    pub fn is_shared(&self) -> bool {
+        self.is_shared
     }
 
     pub fn set_perms(&mut self, new_perms: VMPerms) {
@@ -84,9 +115,9 @@ impl VMArea {
         let old_start = self.start();
         self.range.set_start(new_start);
 
-        // If the updates to the VMA needs to write back to a file, then the
-        // file offset must be adjusted according to the new start address.
-        if let Some((_, offset)) = self.writeback_file.as_mut() {
+        // If the VMA maps a file, then the file offset must be adjusted
+        // according to the new start address.
+        if let Some((_, offset)) = self.file.as_mut() {
             if old_start < new_start {
                 *offset += new_start - old_start;
             } else {
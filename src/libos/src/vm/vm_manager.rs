@@ -1,5 +1,6 @@
 use super::*;
 
+use super::edmm;
 use super::vm_area::VMArea;
 use super::vm_perms::VMPerms;
 
@@ -7,8 +8,20 @@ use super::vm_perms::VMPerms;
 pub enum VMInitializer {
     DoNothing(),
     FillZeros(),
-    CopyFrom { range: VMRange },
-    LoadFromFile { file: FileRef, offset: usize },
+    CopyFrom {
+        range: VMRange,
+    },
+    LoadFromFile {
+        file: FileRef,
+        offset: usize,
+    },
+    // For file-backed mremap that moves and grows the mapping: the old content
+    // is copied and the rest is loaded from the file
+    CopyOldAndReadNew {
+        old_range: VMRange,
+        file: FileRef,
+        offset: usize,
+    },
 }
 
 impl Default for VMInitializer {
@@ -24,38 +37,73 @@ impl VMInitializer {
                 // Do nothing
             }
             VMInitializer::FillZeros() => {
-                for b in buf {
-                    *b = 0;
-                }
+                fill_zeros(buf)?;
             }
             VMInitializer::CopyFrom { range } => {
                 let src_slice = unsafe { range.as_slice() };
                 let copy_len = min(buf.len(), src_slice.len());
                 buf[..copy_len].copy_from_slice(&src_slice[..copy_len]);
-                for b in &mut buf[copy_len..] {
-                    *b = 0;
-                }
+                fill_zeros(&mut buf[copy_len..])?;
             }
             VMInitializer::LoadFromFile { file, offset } => {
                 // TODO: make sure that read_at does not move file cursor
                 let len = file
                     .read_at(*offset, buf)
                     .cause_err(|_| errno!(EIO, "failed to init memory from file"))?;
-                for b in &mut buf[len..] {
-                    *b = 0;
-                }
+                fill_zeros(&mut buf[len..])?;
+            }
+            VMInitializer::CopyOldAndReadNew {
+                old_range,
+                file,
+                offset,
+            } => {
+                let src_slice = unsafe { old_range.as_slice() };
+                let copy_len = min(buf.len(), src_slice.len());
+                buf[..copy_len].copy_from_slice(&src_slice[..copy_len]);
+
+                // The offset is the file offset of the old range
+                let new_buf = &mut buf[copy_len..];
+                let len = file
+                    .read_at(*offset + copy_len, new_buf)
+                    .cause_err(|_| errno!(EIO, "failed to init memory from file"))?;
+                fill_zeros(&mut new_buf[len..])?;
             }
         }
         Ok(())
     }
 }
 
+// With EDMM, uncommitted pages are filled with zeros when they are committed
+// on demand, which saves both time and EPC. So only the partial pages at both
+// ends of the buffer are filled with zeros eagerly.
+pub(super) fn fill_zeros(buf: &mut [u8]) -> Result<()> {
+    let start = buf.as_ptr() as usize;
+    let end = start + buf.len();
+    let pages_start = align_up(start, PAGE_SIZE);
+    let pages_end = align_down(end, PAGE_SIZE);
+    if !edmm::is_supported() || pages_start >= pages_end {
+        for b in buf {
+            *b = 0;
+        }
+        return Ok(());
+    }
+
+    let (head, rest) = buf.split_at_mut(pages_start - start);
+    let (_, tail) = rest.split_at_mut(pages_end - pages_start);
+    for b in head.iter_mut().chain(tail.iter_mut()) {
+        *b = 0;
+    }
+    edmm::uncommit(&VMRange::new(pages_start, pages_end)?)
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum VMMapAddr {
-    Any,          // Free to choose any address
-    Hint(usize),  // Prefer the address, but can use other address
-    Need(usize),  // Need to use the address, otherwise report error
-    Force(usize), // Force using the address by munmap first
+    Any,              // Free to choose any address
+    Hint(usize),      // Prefer the address, but can use other address
+    Need(usize),      // Need to use the address, otherwise report error
+    Force(usize),     // Force using the address by munmap first
+    NoReplace(usize), // Need to use the address, but report EEXIST if it is in use
+    Below(usize),     // Free to choose any address, as long as the range ends below it
 }
 
 impl Default for VMMapAddr {
@@ -103,11 +151,12 @@ impl VMMapOptionsBuilder {
             match addr {
                 // TODO: check addr + size overflow
                 VMMapAddr::Any => VMMapAddr::Any,
+                VMMapAddr::Below(limit) => VMMapAddr::Below(limit),
                 VMMapAddr::Hint(addr) => {
                     let addr = align_down(addr, PAGE_SIZE);
                     VMMapAddr::Hint(addr)
                 }
-                VMMapAddr::Need(addr_) | VMMapAddr::Force(addr_) => {
+                VMMapAddr::Need(addr_) | VMMapAddr::Force(addr_) | VMMapAddr::NoReplace(addr_) => {
                     if addr_ % align != 0 {
                         return_errno!(EINVAL, "unaligned addr for fixed mmap");
                     }
@@ -268,12 +317,12 @@ impl VMManager {
             let start_sentry = {
                 let range = VMRange::new_empty(start)?;
                 let perms = VMPerms::empty();
-                VMArea::new(range, perms, None)
+                VMArea::new(range, perms, None, false)
             };
             let end_sentry = {
                 let range = VMRange::new_empty(end)?;
                 let perms = VMPerms::empty();
-                VMArea::new(range, perms, None)
+                VMArea::new(range, perms, None, false)
             };
             vec![start_sentry, end_sentry]
         };
@@ -305,13 +354,33 @@ impl VMManager {
         if let VMMapAddr::Force(addr) = addr {
             self.munmap(addr, size)?;
         }
+        if let VMMapAddr::NoReplace(addr) = addr {
+            let request_range = VMRange::new_with_size(addr, size)?;
+            if !self.range.is_superset_of(&request_range) {
+                return_errno!(ENOMEM, "not enough memory for fixed mmap");
+            }
+            if !self.is_free_range(&request_range) {
+                return_errno!(EEXIST, "the range overlaps with existing mappings");
+            }
+        }
 
         // Allocate a new range for this mmap request
         let (insert_idx, free_range) = self.find_free_range(size, addr)?;
         let new_range = self.alloc_range_from(size, addr, &free_range);
         let new_addr = new_range.start();
-        let writeback_file = options.writeback_file.take();
-        let new_vma = VMArea::new(new_range, *options.perms(), writeback_file);
+        // A private mapping of a file is initialized from the file, while a
+        // shared one is also written back to the file
+        let (file, is_shared) = match options.writeback_file.take() {
+            Some(writeback_file) => (Some(writeback_file), true),
+            None => match &options.initializer {
+                VMInitializer::LoadFromFile { file, offset }
+                | VMInitializer::CopyOldAndReadNew { file, offset, .. } => {
+                    (Some((file.clone(), *offset)), false)
+                }
+                _ => (None, false),
+            },
+        };
+        let new_vma = VMArea::new(new_range, *options.perms(), file, is_shared);
 
         // Initialize the memory of the new range
         unsafe {
@@ -320,7 +389,7 @@ impl VMManager {
         }
         // Set memory permissions
         if !options.perms.is_default() {
-            Self::apply_perms(&new_vma, new_vma.perms());
+            Self::apply_perms(&new_vma, new_vma.perms())?;
         }
 
         // After initializing, we can safely insert the new VMA
@@ -374,9 +443,12 @@ impl VMManager {
 
                 // Reset memory permissions
                 if !&intersection_vma.perms().is_default() {
-                    Self::apply_perms(&intersection_vma, VMPerms::default());
+                    Self::apply_perms(&intersection_vma, VMPerms::default()).expect("never fail");
                 }
 
+                // Release the EPC pages of the unmapped memory
+                Self::release_pages(&intersection_vma);
+
                 vma.subtract(&intersection_vma)
             })
             .collect();
@@ -405,15 +477,20 @@ impl VMManager {
             SizeType::Growing
         };
 
-        // Get the memory permissions of the old range
-        let perms = {
+        // Get the memory permissions and the file of the old range
+        let (perms, file, is_shared) = {
             // The old range must be contained in one VMA
             let idx = self
                 .find_containing_vma_idx(&old_range)
                 .ok_or_else(|| errno!(EFAULT, "invalid range"))?;
             let containing_vma = &self.vmas[idx];
-            containing_vma.perms()
+            // The file offset of the old range, instead of the containing VMA
+            let file = containing_vma
+                .file()
+                .map(|(file, offset)| (file.clone(), offset + (old_addr - containing_vma.start())));
+            (containing_vma.perms(), file, containing_vma.is_shared())
         };
+        let writeback_file = if is_shared { file.clone() } else { None };
 
         // Implement mremap as one optional mmap followed by one optional munmap.
         //
@@ -422,52 +499,77 @@ impl VMManager {
         // As some combinations result in the same mmap and munmap operations, the following code
         // only needs to match four patterns of (MRemapFlags, SizeType) and treat each case
         // accordingly.
+        //
+        // A file-backed mapping keeps being backed by the file after it is grown or moved. So
+        // the grown part is loaded from the file and can be merged with the old part. Only
+        // the shared mappings are written back to the file.
+
+        // The options to grow the old range in place, i.e., to map the range right after it
+        let grow_in_place_options = || -> Result<VMMapOptions> {
+            let grown_range = VMRange::new_with_size(old_range.end(), new_size - old_size)?;
+            let (initializer, grown_writeback_file) = match file.as_ref() {
+                Some((file, offset)) => {
+                    let grown_offset = offset + old_size;
+                    let initializer = VMInitializer::LoadFromFile {
+                        file: file.clone(),
+                        offset: grown_offset,
+                    };
+                    let grown_writeback_file = if is_shared {
+                        Some((file.clone(), grown_offset))
+                    } else {
+                        None
+                    };
+                    (initializer, grown_writeback_file)
+                }
+                None => (VMInitializer::FillZeros(), None),
+            };
+            VMMapOptionsBuilder::default()
+                .size(grown_range.size())
+                .addr(VMMapAddr::Need(grown_range.start()))
+                .perms(perms)
+                .initializer(initializer)
+                .writeback_file(grown_writeback_file)
+                .build()
+        };
+        // The options to move the old range to a new range
+        let move_options = |addr: VMMapAddr| -> Result<VMMapOptions> {
+            let initializer = match file.as_ref() {
+                Some((file, offset)) => VMInitializer::CopyOldAndReadNew {
+                    old_range,
+                    file: file.clone(),
+                    offset: *offset,
+                },
+                None => VMInitializer::CopyFrom { range: old_range },
+            };
+            VMMapOptionsBuilder::default()
+                .size(new_size)
+                .addr(addr)
+                .perms(perms)
+                .initializer(initializer)
+                .writeback_file(writeback_file.clone())
+                .build()
+        };
 
         // Determine whether need to do mmap. And when possible, determine the returned address
-        // TODO: should fill zeros even when extending a file-backed mapping?
         let (need_mmap, mut ret_addr) = match (flags, size_type) {
             (MRemapFlags::None, SizeType::Growing) => {
-                let mmap_opts = VMMapOptionsBuilder::default()
-                    .size(new_size - old_size)
-                    .addr(VMMapAddr::Need(old_range.end()))
-                    .perms(perms)
-                    .initializer(VMInitializer::FillZeros())
-                    .build()?;
                 let ret_addr = Some(old_addr);
-                (Some(mmap_opts), ret_addr)
+                (Some(grow_in_place_options()?), ret_addr)
             }
             (MRemapFlags::MayMove, SizeType::Growing) => {
                 let prefered_new_range =
                     VMRange::new_with_size(old_addr + old_size, new_size - old_size)?;
                 if self.is_free_range(&prefered_new_range) {
-                    let mmap_ops = VMMapOptionsBuilder::default()
-                        .size(prefered_new_range.size())
-                        .addr(VMMapAddr::Need(prefered_new_range.start()))
-                        .perms(perms)
-                        .initializer(VMInitializer::FillZeros())
-                        .build()?;
-                    (Some(mmap_ops), Some(old_addr))
+                    (Some(grow_in_place_options()?), Some(old_addr))
                 } else {
-                    let mmap_ops = VMMapOptionsBuilder::default()
-                        .size(new_size)
-                        .addr(VMMapAddr::Any)
-                        .perms(perms)
-                        .initializer(VMInitializer::CopyFrom { range: old_range })
-                        .build()?;
                     // Cannot determine the returned address for now, which can only be obtained after calling mmap
                     let ret_addr = None;
-                    (Some(mmap_ops), ret_addr)
+                    (Some(move_options(VMMapAddr::Any)?), ret_addr)
                 }
             }
             (MRemapFlags::FixedAddr(new_addr), _) => {
-                let mmap_opts = VMMapOptionsBuilder::default()
-                    .size(new_size)
-                    .addr(VMMapAddr::Force(new_addr))
-                    .perms(perms)
-                    .initializer(VMInitializer::CopyFrom { range: old_range })
-                    .build()?;
                 let ret_addr = Some(new_addr);
-                (Some(mmap_opts), ret_addr)
+                (Some(move_options(VMMapAddr::Force(new_addr))?), ret_addr)
             }
             _ => (None, Some(old_addr)),
         };
@@ -529,27 +631,27 @@ impl VMManager {
             return Ok(());
         }
 
+        // Apply the new permissions before the VMAs are changed, so that the
+        // VMAs are kept intact on failure
+        Self::apply_perms(&protect_range, new_perms)?;
+
         let same_start = protect_range.start() == containing_vma.start();
         let same_end = protect_range.end() == containing_vma.end();
         let containing_vma = &mut self.vmas[containing_idx];
         match (same_start, same_end) {
             (true, true) => {
                 containing_vma.set_perms(new_perms);
-
-                Self::apply_perms(containing_vma, containing_vma.perms());
             }
             (false, true) => {
                 containing_vma.set_end(protect_range.start());
 
                 let new_vma = VMArea::inherits_file_from(containing_vma, protect_range, new_perms);
-                Self::apply_perms(&new_vma, new_vma.perms());
                 self.insert_new_vma(containing_idx + 1, new_vma);
             }
             (true, false) => {
                 containing_vma.set_start(protect_range.end());
 
                 let new_vma = VMArea::inherits_file_from(containing_vma, protect_range, new_perms);
-                Self::apply_perms(&new_vma, new_vma.perms());
                 self.insert_new_vma(containing_idx, new_vma);
             }
             (false, false) => {
@@ -566,7 +668,6 @@ impl VMManager {
 
                 // New VMA
                 let new_vma = VMArea::inherits_file_from(containing_vma, protect_range, new_perms);
-                Self::apply_perms(&new_vma, new_vma.perms());
 
                 // Another new VMA
                 let new_vma2 = {
@@ -583,6 +684,62 @@ impl VMManager {
         Ok(())
     }
 
+    /// Give advice about the use of the memory in the given range.
+    ///
+    /// The range must be mapped entirely. MADV_DONTNEED and MADV_FREE discard
+    /// the memory: the anonymous memory is filled with zeros, while the
+    /// shared, file-backed memory is flushed to the file, whose content is
+    /// seen on the next access. Since the enclave memory is committed as long
+    /// as the process VM exists, the memory is not returned to the system. The
+    /// other advices are only hints, which are accepted without any effect.
+    pub fn madvise(&mut self, advise_range: &VMRange, advice: MAdvice) -> Result<()> {
+        if !self.is_fully_mapped(advise_range) {
+            return_errno!(ENOMEM, "the range is not mapped entirely");
+        }
+        let vmas: Vec<VMArea> = self
+            .vmas
+            .iter()
+            .filter(|vma| vma.size() > 0)
+            .filter_map(|vma| vma.intersect(advise_range))
+            .collect();
+
+        match advice {
+            MAdvice::MADV_DONTNEED => {}
+            MAdvice::MADV_FREE => {
+                // Same as Linux, only private, anonymous memory can be freed
+                if vmas.iter().any(|vma| vma.file().is_some()) {
+                    return_errno!(EINVAL, "the range is not anonymous");
+                }
+            }
+            _ => return Ok(()),
+        }
+        for vma in &vmas {
+            if vma.writeback_file().is_some() {
+                Self::flush_file_vma(vma);
+                continue;
+            }
+            // The pages with other permissions are kept committed, see
+            // `apply_perms`
+            if edmm::is_supported() && vma.perms().is_default() {
+                Self::release_pages(vma);
+                continue;
+            }
+            // The memory must be writable to be filled with zeros
+            if !vma.perms().is_default() {
+                Self::apply_perms(vma, VMPerms::default()).expect("never fail");
+            }
+            unsafe {
+                for b in vma.as_slice_mut() {
+                    *b = 0;
+                }
+            }
+            if !vma.perms().is_default() {
+                Self::apply_perms(vma, vma.perms())?;
+            }
+        }
+        Ok(())
+    }
+
     /// Sync all shared, file-backed memory mappings in the given range by flushing the
     /// memory content to its underlying file.
     pub fn msync_by_range(&mut self, sync_range: &VMRange) -> Result<()> {
@@ -610,6 +767,44 @@ impl VMManager {
         }
     }
 
+    /// Check whether there are any shared, writable memory mappings of the given file.
+    pub fn has_shared_writable_mapping(&self, file: &FileRef) -> bool {
+        self.vmas.iter().any(|vma| match vma.writeback_file() {
+            Some((vma_file, _)) => Arc::ptr_eq(vma_file, file) && vma.perms().can_write(),
+            None => false,
+        })
+    }
+
+    /// Replace all the VMAs with the given ones, e.g., the VMAs saved before fork.
+    ///
+    /// The file-backed VMAs are flushed before they are replaced. The memory
+    /// is readable and writable when `init_fn` is called to initialize the
+    /// new VMAs, after which the permissions of the new VMAs take effect.
+    pub fn replace_vmas<F: FnOnce(&[VMArea])>(
+        &mut self,
+        new_vmas: Vec<VMArea>,
+        init_fn: F,
+    ) -> Result<()> {
+        for vma in &self.vmas {
+            Self::flush_file_vma(vma);
+            if vma.size() == 0 || vma.perms() == VMPerms::default() {
+                continue;
+            }
+            Self::apply_perms(vma, VMPerms::default()).expect("never fail");
+        }
+
+        self.vmas = new_vmas;
+        init_fn(&self.vmas);
+
+        for vma in &self.vmas {
+            if vma.size() == 0 || vma.perms() == VMPerms::default() {
+                continue;
+            }
+            Self::apply_perms(vma, vma.perms())?;
+        }
+        Ok(())
+    }
+
     /// Flush a file-backed VMA to its file. This has no effect on anonymous VMA.
     fn flush_file_vma(vma: &VMArea) {
         Self::flush_file_vma_with_cond(vma, |_| true)
@@ -617,7 +812,7 @@ impl VMManager {
 
     /// Same as flush_vma, except that an extra condition on the file needs to satisfy.
     fn flush_file_vma_with_cond<F: Fn(&FileRef) -> bool>(vma: &VMArea, cond_fn: F) {
-        let (file, file_offset) = match vma.writeback_file().as_ref() {
+        let (file, file_offset) = match vma.writeback_file() {
             None => return,
             Some((file_and_offset)) => file_and_offset,
         };
@@ -634,6 +829,18 @@ impl VMManager {
         file.write_at(*file_offset, unsafe { vma.as_slice() });
     }
 
+    /// Check whether every page in the range is mapped.
+    pub fn is_fully_mapped(&self, range: &VMRange) -> bool {
+        let mapped_size: usize = self
+            .vmas
+            .iter()
+            .filter(|vma| vma.size() > 0)
+            .filter_map(|vma| vma.range().intersect(range))
+            .map(|range| range.size())
+            .sum();
+        mapped_size == range.size()
+    }
+
     pub fn find_mmap_region(&self, addr: usize) -> Result<&VMRange> {
         self.vmas
             .iter()
@@ -697,6 +904,13 @@ impl VMManager {
             match addr {
                 // Want a minimal free_range
                 VMMapAddr::Any => {}
+                // Want a minimal free_range that is below the limit
+                VMMapAddr::Below(limit) => {
+                    if free_range.start() >= limit || limit - free_range.start() < size {
+                        continue;
+                    }
+                    free_range.end = free_range.end().min(limit);
+                }
                 // Prefer to have free_range.start == addr
                 VMMapAddr::Hint(addr) => {
                     if free_range.contains(addr) {
@@ -708,7 +922,7 @@ impl VMManager {
                     }
                 }
                 // Must have free_range.start == addr
-                VMMapAddr::Need(addr) | VMMapAddr::Force(addr) => {
+                VMMapAddr::Need(addr) | VMMapAddr::Force(addr) | VMMapAddr::NoReplace(addr) => {
                     if free_range.start() > addr {
                         return_errno!(ENOMEM, "not enough memory for fixed mmap");
                     }
@@ -755,7 +969,7 @@ impl VMManager {
 
         let mut new_range = *free_range;
 
-        if let VMMapAddr::Need(addr) = addr {
+        if let VMMapAddr::Need(addr) | VMMapAddr::NoReplace(addr) = addr {
             debug_assert!(addr == new_range.start());
         }
         if let VMMapAddr::Force(addr) = addr {
@@ -821,11 +1035,12 @@ impl VMManager {
             return false;
         }
 
-        // If the two VMAs have write-back files, the files must be the same and
-        // the two file regions must be continuous.
-        let left_writeback_file = left.writeback_file();
-        let right_writeback_file = right.writeback_file();
-        match (left_writeback_file, right_writeback_file) {
+        // If the two VMAs map files, the files and the sharing modes must be
+        // the same and the two file regions must be continuous.
+        if left.is_shared() != right.is_shared() {
+            return false;
+        }
+        match (left.file(), right.file()) {
             (None, None) => true,
             (Some(_), None) => false,
             (None, Some(_)) => false,
@@ -837,7 +1052,27 @@ impl VMManager {
         }
     }
 
-    fn apply_perms(protect_range: &VMRange, perms: VMPerms) {
+    /// Release the EPC pages of the memory with EDMM, after which the memory
+    /// is filled with zeros on the next access. This has no effect without EDMM.
+    fn release_pages(range: &VMRange) {
+        if !edmm::is_supported() {
+            return;
+        }
+        if let Err(e) = edmm::uncommit(range) {
+            warn!("failed to release the pages of {:?}: {:?}", range, e);
+        }
+    }
+
+    fn apply_perms(protect_range: &VMRange, perms: VMPerms) -> Result<()> {
+        // The pages committed on demand are only readable and writable, and a
+        // fault on a page whose permissions are restricted by the page table
+        // must not be taken as a fault on an uncommitted page. So the pages
+        // with other permissions are committed eagerly, which may fail. The
+        // default permissions are always applied successfully.
+        if !perms.is_default() && edmm::is_supported() {
+            edmm::commit_with_perms(protect_range, perms)?;
+        }
+
         extern "C" {
             pub fn occlum_ocall_mprotect(
                 retval: *mut i32,
@@ -855,17 +1090,20 @@ impl VMManager {
             let sgx_status = occlum_ocall_mprotect(&mut retval, addr, len, prot);
             assert!(sgx_status == sgx_status_t::SGX_SUCCESS && retval == 0);
         }
+        Ok(())
     }
 }
 
 impl Drop for VMManager {
     fn drop(&mut self) {
-        // Ensure that memory permissions are recovered
+        // Ensure that memory permissions are recovered, and the file-backed
+        // VMAs are flushed, e.g., when the process exits without munmap
         for vma in &self.vmas {
+            Self::flush_file_vma(vma);
             if vma.size() == 0 || vma.perms() == VMPerms::default() {
                 continue;
             }
-            Self::apply_perms(vma, VMPerms::default());
+            Self::apply_perms(vma, VMPerms::default()).expect("never fail");
         }
     }
 }
@@ -5,6 +5,63 @@
 #include <sys/select.h>         // import fd_set
 #include <sys/time.h>           // import struct timeval
 #include <sys/uio.h>            // import struct iovec
+#include <sys/statfs.h>         // import struct statfs
 #include <occlum_pal_api.h>     // import occlum_stdio_fds
+#include <stdint.h>
+
+// A network interface or an address of it on the host, which is used to
+// answer the netlink requests inside the enclave
+struct occlum_ifaddr {
+    char name[16];              // The name of the interface
+    uint32_t index;             // The index of the interface
+    uint32_t flags;             // The flags of the interface, e.g., IFF_UP
+    uint32_t mtu;               // The MTU of the interface, only for a link
+    uint16_t family;            // AF_PACKET for a link, AF_INET or AF_INET6 for an address
+    uint16_t hatype;            // The hardware type of a link, e.g., ARPHRD_ETHER
+    uint8_t prefixlen;          // The prefix length of an address
+    uint8_t addr_len;           // The length of the address or the hardware address
+    uint8_t addr[16];
+    uint8_t broadaddr[16];      // The broadcast address given IFF_BROADCAST
+};
+
+// A message of sendmmsg or recvmmsg, whose name, control and data buffers are
+// in the untrusted memory. The iovecs of the messages are consecutive in the
+// iovec array of the batch.
+struct occlum_mmsghdr {
+    void *msg_name;
+    uint32_t msg_namelen;       // The length of the name, updated by recvmmsg
+    size_t msg_iovlen;          // The number of the iovecs of the message
+    void *msg_control;
+    size_t msg_controllen;      // The length of the control, updated by recvmmsg
+    int msg_flags;              // The flags of the message, updated by recvmmsg
+    uint32_t msg_len;           // The number of the bytes sent or received
+};
+
+// A host syscall that is offloaded to the host I/O threads. The request is
+// submitted by a LibOS thread and completed by one of the host I/O threads,
+// which then writes the host eventfd given by LibOS, e.g., the one of the
+// LibOS thread waiting for the request.
+struct occlum_io_request {
+    int64_t num;                    // The syscall number
+    int64_t args[6];                // The syscall arguments, pointing to untrusted memory
+    int64_t ret;                    // The return value, or -errno on failure
+    int32_t host_eventfd;           // The host eventfd to notify the completion
+    volatile int32_t host_tid;      // The host I/O thread executing the request
+    volatile int32_t is_cancelled;  // Set by LibOS to cancel the request
+    volatile int32_t is_done;       // Set by the host I/O thread on completion
+};
+
+#define OCCLUM_IO_QUEUE_SIZE    256
+
+// A single-producer-multiple-consumer ring of the pending I/O requests, which
+// is shared by LibOS and the host I/O threads. LibOS serializes submissions,
+// while the host I/O threads take requests by advancing the head atomically.
+struct occlum_io_queue {
+    volatile uint32_t head;
+    volatile uint32_t tail;
+    volatile uint32_t nr_sleeping;  // The number of the idle host I/O threads
+    volatile uint32_t is_stopped;
+    struct occlum_io_request *reqs[OCCLUM_IO_QUEUE_SIZE];
+};
 
 #endif /* __OCCLUM_EDL_TYPES__ */
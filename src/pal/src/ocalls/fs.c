@@ -1,36 +1,21 @@
 #include "ocalls.h"
 #include <errno.h>
-#include <net/if.h>
+#include <fcntl.h>
 #include <unistd.h>
 #include <sys/ioctl.h>
+#include <sys/stat.h>
+#include <sys/statfs.h>
 
 void occlum_ocall_sync(void) {
     sync();
 }
 
-int occlum_ocall_ioctl_repack(int fd, int request, char *buf, int len, int *recv_len) {
-    int ret = 0;
-
-    switch (request) {
-        case SIOCGIFCONF:
-            if (recv_len == NULL) {
-                errno = EINVAL;
-                return -1;
-            }
-
-            struct ifconf config = { .ifc_len = len, .ifc_buf = buf };
-            ret = ioctl(fd, SIOCGIFCONF, &config);
-            if (ret == 0) {
-                *recv_len = config.ifc_len;
-            }
-            break;
-
-        default:
-            errno = EINVAL;
-            return -1;
-    }
+int occlum_ocall_statfs(const char *path, struct statfs *buf) {
+    return statfs(path, buf);
+}
 
-    return ret;
+int occlum_ocall_utimensat(const char *path, const struct timespec *times) {
+    return utimensat(AT_FDCWD, path, times, 0);
 }
 
 int occlum_ocall_ioctl(int fd, int request, void *arg, size_t len) {
@@ -0,0 +1,10 @@
+#include "ocalls.h"
+#include "../pal_io_engine.h"
+
+int occlum_ocall_io_engine_start(struct occlum_io_queue *queue, int num_threads) {
+    return pal_io_engine_start(queue, num_threads);
+}
+
+void occlum_ocall_io_engine_wake(struct occlum_io_queue *queue) {
+    pal_io_engine_wake(queue);
+}
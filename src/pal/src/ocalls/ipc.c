@@ -0,0 +1,106 @@
+#include "ocalls.h"
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <unistd.h>
+#include <sys/mman.h>
+#include <sys/stat.h>
+
+// The untrusted shared memory of the cross-enclave IPC channels
+#define IPC_SHM_DIR     "/dev/shm"
+#define IPC_SHM_PREFIX  "occlum_ipc."
+
+static int ipc_shm_path(char *path, size_t len, const char *name, const char *suffix) {
+    int ret = snprintf(path, len, IPC_SHM_DIR "/" IPC_SHM_PREFIX "%s%s", name, suffix);
+    if (ret < 0 || ret >= len) {
+        errno = ENAMETOOLONG;
+        return -1;
+    }
+    return 0;
+}
+
+// Create the shared memory of the name, or open it if it has been created by
+// another enclave, whose size must be the same. The new shared memory is
+// linked to the name only after it is fully initialized, so that it is never
+// seen with a wrong size.
+void *occlum_ocall_ipc_shm_open(const char *name, size_t size, int *is_creator) {
+    char path[PATH_MAX];
+    char tmp_path[PATH_MAX];
+    char suffix[32];
+    if (ipc_shm_path(path, sizeof(path), name, "") < 0) {
+        return NULL;
+    }
+    snprintf(suffix, sizeof(suffix), ".%d.%d", getpid(), GETTID());
+    if (ipc_shm_path(tmp_path, sizeof(tmp_path), name, suffix) < 0) {
+        return NULL;
+    }
+
+    int fd = open(tmp_path, O_RDWR | O_CREAT | O_EXCL | O_CLOEXEC, 0600);
+    if (fd < 0) {
+        return NULL;
+    }
+    if (ftruncate(fd, size) < 0) {
+        goto on_tmp_error;
+    }
+    if (link(tmp_path, path) == 0) {
+        *is_creator = 1;
+    } else if (errno == EEXIST) {
+        close(fd);
+        unlink(tmp_path);
+        fd = open(path, O_RDWR | O_CLOEXEC);
+        if (fd < 0) {
+            return NULL;
+        }
+        struct stat stat_buf;
+        if (fstat(fd, &stat_buf) < 0) {
+            goto on_error;
+        }
+        if (stat_buf.st_size != size) {
+            errno = EINVAL;
+            goto on_error;
+        }
+        *is_creator = 0;
+    } else {
+        goto on_tmp_error;
+    }
+    unlink(tmp_path);
+
+    void *addr = mmap(NULL, size, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    if (addr == MAP_FAILED) {
+        if (*is_creator) {
+            unlink(path);
+        }
+        goto on_error;
+    }
+    close(fd);
+    return addr;
+
+on_tmp_error:
+    unlink(tmp_path);
+on_error: {
+        int saved_errno = errno;
+        close(fd);
+        errno = saved_errno;
+    }
+    return NULL;
+}
+
+int occlum_ocall_ipc_shm_unlink(const char *name) {
+    char path[PATH_MAX];
+    if (ipc_shm_path(path, sizeof(path), name, "") < 0) {
+        return -1;
+    }
+    return unlink(path);
+}
+
+int occlum_ocall_ipc_shm_close(void *addr, size_t size) {
+    return munmap(addr, size);
+}
+
+int occlum_ocall_ipc_futex_wait(int *addr, int val, const struct timespec *timeout) {
+    return FUTEX_WAIT_TIMEOUT(addr, val, timeout);
+}
+
+int occlum_ocall_ipc_futex_wake(int *addr) {
+    return FUTEX_WAKE_ALL(addr);
+}
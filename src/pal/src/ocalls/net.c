@@ -1,11 +1,24 @@
+#define _GNU_SOURCE
 #include <sys/time.h>
 #include <sys/types.h>
 #include <sys/select.h>
 #include <sys/socket.h>
+#include <sys/ioctl.h>
 #include <errno.h>
+#include <fcntl.h>
+#include <ifaddrs.h>
+#include <unistd.h>
+#include <linux/if_tun.h>
+#include <linux/vm_sockets.h>
+#include <net/if.h>
+#include <netinet/in.h>
+#include <netpacket/packet.h>
 #include <stdio.h>
 #include <stddef.h>
+#include <stdlib.h>
+#include <string.h>
 #include "ocalls.h"
+#include "../pal_net_thread.h"
 
 ssize_t occlum_ocall_sendmsg(int sockfd,
                              const void *msg_name,
@@ -27,6 +40,18 @@ ssize_t occlum_ocall_sendmsg(int sockfd,
     return sendmsg(sockfd, &msg, flags);
 }
 
+ssize_t occlum_ocall_sendmsg_switchless(int sockfd,
+                                        const void *msg_name,
+                                        socklen_t msg_namelen,
+                                        const struct iovec *msg_iov,
+                                        size_t msg_iovlen,
+                                        const void *msg_control,
+                                        size_t msg_controllen,
+                                        int flags) {
+    return occlum_ocall_sendmsg(sockfd, msg_name, msg_namelen, msg_iov, msg_iovlen,
+                                msg_control, msg_controllen, flags);
+}
+
 ssize_t occlum_ocall_recvmsg(int sockfd,
                              void *msg_name,
                              socklen_t msg_namelen,
@@ -56,6 +81,79 @@ ssize_t occlum_ocall_recvmsg(int sockfd,
     return ret;
 }
 
+// Convert the messages of the batch to struct mmsghdr, returning NULL if the
+// iovecs of the messages are out of the iovec array
+static struct mmsghdr *to_mmsghdrs(struct occlum_mmsghdr *msgvec,
+                                   unsigned int vlen,
+                                   struct iovec *iovs,
+                                   size_t iovlen) {
+    struct mmsghdr *msgs = calloc(vlen, sizeof(struct mmsghdr));
+    if (msgs == NULL) {
+        errno = ENOMEM;
+        return NULL;
+    }
+
+    size_t iov_pos = 0;
+    for (unsigned int i = 0; i < vlen; i++) {
+        if (msgvec[i].msg_iovlen > iovlen - iov_pos) {
+            free(msgs);
+            errno = EINVAL;
+            return NULL;
+        }
+        struct msghdr *msg = &msgs[i].msg_hdr;
+        msg->msg_name = msgvec[i].msg_name;
+        msg->msg_namelen = msgvec[i].msg_namelen;
+        msg->msg_iov = &iovs[iov_pos];
+        msg->msg_iovlen = msgvec[i].msg_iovlen;
+        msg->msg_control = msgvec[i].msg_control;
+        msg->msg_controllen = msgvec[i].msg_controllen;
+        iov_pos += msgvec[i].msg_iovlen;
+    }
+    return msgs;
+}
+
+int occlum_ocall_sendmmsg(int sockfd,
+                          struct occlum_mmsghdr *msgvec,
+                          unsigned int vlen,
+                          const struct iovec *iovs,
+                          size_t iovlen,
+                          int flags) {
+    struct mmsghdr *msgs = to_mmsghdrs(msgvec, vlen, (struct iovec *) iovs, iovlen);
+    if (msgs == NULL) {
+        return -1;
+    }
+
+    int ret = sendmmsg(sockfd, msgs, vlen, flags);
+    for (int i = 0; i < ret; i++) {
+        msgvec[i].msg_len = msgs[i].msg_len;
+    }
+    free(msgs);
+    return ret;
+}
+
+int occlum_ocall_recvmmsg(int sockfd,
+                          struct occlum_mmsghdr *msgvec,
+                          unsigned int vlen,
+                          struct iovec *iovs,
+                          size_t iovlen,
+                          int flags,
+                          struct timespec *timeout) {
+    struct mmsghdr *msgs = to_mmsghdrs(msgvec, vlen, iovs, iovlen);
+    if (msgs == NULL) {
+        return -1;
+    }
+
+    int ret = recvmmsg(sockfd, msgs, vlen, flags, timeout);
+    for (int i = 0; i < ret; i++) {
+        msgvec[i].msg_namelen = msgs[i].msg_hdr.msg_namelen;
+        msgvec[i].msg_controllen = msgs[i].msg_hdr.msg_controllen;
+        msgvec[i].msg_flags = msgs[i].msg_hdr.msg_flags;
+        msgvec[i].msg_len = msgs[i].msg_len;
+    }
+    free(msgs);
+    return ret;
+}
+
 int occlum_ocall_poll(struct pollfd *fds,
                       nfds_t nfds,
                       struct timeval *timeout,
@@ -88,3 +186,133 @@ int occlum_ocall_poll(struct pollfd *fds,
     errno = saved_errno;
     return ret;
 }
+
+static uint8_t netmask_to_prefixlen(const uint8_t *netmask, size_t len) {
+    uint8_t prefixlen = 0;
+    for (size_t i = 0; i < len; i++) {
+        prefixlen += __builtin_popcount(netmask[i]);
+    }
+    return prefixlen;
+}
+
+static uint32_t get_mtu(const char *name) {
+    int fd = socket(AF_INET, SOCK_DGRAM | SOCK_CLOEXEC, 0);
+    if (fd < 0) {
+        return 0;
+    }
+    struct ifreq ifr;
+    memset(&ifr, 0, sizeof(ifr));
+    strncpy(ifr.ifr_name, name, IFNAMSIZ - 1);
+    uint32_t mtu = ioctl(fd, SIOCGIFMTU, &ifr) < 0 ? 0 : ifr.ifr_mtu;
+    close(fd);
+    return mtu;
+}
+
+// Get the links and the addresses of the interfaces on the host, returning
+// the number of the entries filled, which are at most max_count
+int occlum_ocall_get_ifaddrs(struct occlum_ifaddr *ifaddrs, size_t max_count) {
+    struct ifaddrs *ifaddr_list;
+    if (getifaddrs(&ifaddr_list) < 0) {
+        return -1;
+    }
+
+    size_t count = 0;
+    for (struct ifaddrs *ifa = ifaddr_list; ifa != NULL && count < max_count;
+            ifa = ifa->ifa_next) {
+        if (ifa->ifa_addr == NULL) {
+            continue;
+        }
+        struct occlum_ifaddr *entry = &ifaddrs[count];
+        memset(entry, 0, sizeof(*entry));
+        strncpy(entry->name, ifa->ifa_name, sizeof(entry->name) - 1);
+        entry->index = if_nametoindex(ifa->ifa_name);
+        entry->flags = ifa->ifa_flags;
+        entry->family = ifa->ifa_addr->sa_family;
+
+        switch (entry->family) {
+            case AF_PACKET: {
+                struct sockaddr_ll *ll = (struct sockaddr_ll *)ifa->ifa_addr;
+                entry->index = ll->sll_ifindex;
+                entry->hatype = ll->sll_hatype;
+                entry->addr_len = ll->sll_halen < sizeof(entry->addr) ?
+                                  ll->sll_halen : sizeof(entry->addr);
+                memcpy(entry->addr, ll->sll_addr, entry->addr_len);
+                entry->mtu = get_mtu(ifa->ifa_name);
+                break;
+            }
+            case AF_INET: {
+                struct sockaddr_in *in = (struct sockaddr_in *)ifa->ifa_addr;
+                entry->addr_len = sizeof(in->sin_addr);
+                memcpy(entry->addr, &in->sin_addr, entry->addr_len);
+                if (ifa->ifa_netmask != NULL) {
+                    struct sockaddr_in *mask = (struct sockaddr_in *)ifa->ifa_netmask;
+                    entry->prefixlen = netmask_to_prefixlen((uint8_t *)&mask->sin_addr,
+                                                            sizeof(mask->sin_addr));
+                }
+                if ((ifa->ifa_flags & IFF_BROADCAST) && ifa->ifa_broadaddr != NULL) {
+                    struct sockaddr_in *brd = (struct sockaddr_in *)ifa->ifa_broadaddr;
+                    memcpy(entry->broadaddr, &brd->sin_addr, sizeof(brd->sin_addr));
+                }
+                break;
+            }
+            case AF_INET6: {
+                struct sockaddr_in6 *in6 = (struct sockaddr_in6 *)ifa->ifa_addr;
+                entry->addr_len = sizeof(in6->sin6_addr);
+                memcpy(entry->addr, &in6->sin6_addr, entry->addr_len);
+                if (ifa->ifa_netmask != NULL) {
+                    struct sockaddr_in6 *mask = (struct sockaddr_in6 *)ifa->ifa_netmask;
+                    entry->prefixlen = netmask_to_prefixlen((uint8_t *)&mask->sin6_addr,
+                                                            sizeof(mask->sin6_addr));
+                }
+                break;
+            }
+            default:
+                continue;
+        }
+        count++;
+    }
+
+    freeifaddrs(ifaddr_list);
+    return count;
+}
+
+int occlum_ocall_vsock_get_local_cid(uint32_t *cid) {
+    int fd = open("/dev/vsock", O_RDONLY | O_CLOEXEC);
+    if (fd < 0) {
+        return -1;
+    }
+
+    int ret = ioctl(fd, IOCTL_VM_SOCKETS_GET_LOCAL_CID, cid);
+    int saved_errno = errno;
+    close(fd);
+    // restore the errno of ioctl
+    errno = saved_errno;
+    return ret;
+}
+
+int occlum_ocall_tap_open(const char *name) {
+    int fd = open("/dev/net/tun", O_RDWR | O_NONBLOCK | O_CLOEXEC);
+    if (fd < 0) {
+        return -1;
+    }
+
+    struct ifreq ifr;
+    memset(&ifr, 0, sizeof(ifr));
+    ifr.ifr_flags = IFF_TAP | IFF_NO_PI;
+    strncpy(ifr.ifr_name, name, IFNAMSIZ - 1);
+    if (ioctl(fd, TUNSETIFF, &ifr) < 0) {
+        int saved_errno = errno;
+        close(fd);
+        // restore the errno of ioctl
+        errno = saved_errno;
+        return -1;
+    }
+    // The frames wake up the net thread, which drives the stack
+    if (pal_net_thread_add_tap(fd) < 0) {
+        int saved_errno = errno;
+        close(fd);
+        errno = saved_errno;
+        return -1;
+    }
+    return fd;
+}
@@ -1,5 +1,6 @@
 #define _GNU_SOURCE
 #include <sched.h>
+#include <sys/resource.h>
 #include <dirent.h>
 #include <unistd.h>
 #include "ocalls.h"
@@ -19,6 +20,16 @@ int occlum_ocall_sched_setaffinity(int host_tid, size_t cpusize,
     return syscall(__NR_sched_setaffinity, host_tid, cpusize, buf);
 }
 
+int occlum_ocall_sched_set_priority(int host_tid, int policy, int rt_priority,
+                                    int nice) {
+    struct sched_param param = { .sched_priority = rt_priority };
+    if (sched_setscheduler(host_tid, policy, &param) < 0) {
+        return -1;
+    }
+    // The nice value is ignored by the real-time policies
+    return setpriority(PRIO_PROCESS, host_tid, nice);
+}
+
 /* In the Linux implementation, sched_yield() always succeeds */
 void occlum_ocall_sched_yield(void) {
     sched_yield();
@@ -1,5 +1,7 @@
+#define _GNU_SOURCE
 #include <pthread.h>
 #include <sys/time.h>
+#include <sys/resource.h>
 #include <sys/prctl.h>
 #include "ocalls.h"
 
@@ -30,6 +32,18 @@ int occlum_ocall_thread_getcpuclock(struct timespec *tp) {
     return clock_gettime(thread_clock_id, tp);
 }
 
+int occlum_ocall_thread_getrusage(struct timeval *utime, struct timeval *stime) {
+    struct rusage usage;
+    int ret = getrusage(RUSAGE_THREAD, &usage);
+    if (ret < 0) {
+        return -1;
+    }
+
+    *utime = usage.ru_utime;
+    *stime = usage.ru_stime;
+    return 0;
+}
+
 void occlum_ocall_rdtsc(uint32_t *low, uint32_t *high) {
     uint64_t rax, rdx;
     asm volatile("rdtsc" : "=a"(rax), "=d"(rdx));
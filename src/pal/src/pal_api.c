@@ -3,10 +3,13 @@
 #include "pal_enclave.h"
 #include "pal_error.h"
 #include "pal_interrupt_thread.h"
+#include "pal_io_engine.h"
 #include "pal_log.h"
 #include "pal_sig_handler.h"
 #include "pal_syscall.h"
 #include "pal_thread_counter.h"
+#include "pal_net_thread.h"
+#include "pal_writeback_thread.h"
 #include "errno2str.h"
 #include <linux/limits.h>
 
@@ -112,6 +115,16 @@ int occlum_pal_init(const struct occlum_pal_attr *attr) {
         goto on_destroy_enclave;
     }
 
+    if (pal_writeback_thread_start() < 0) {
+        PAL_ERROR("Failed to start the writeback thread: %s", errno2str(errno));
+        goto on_destroy_enclave;
+    }
+
+    if (pal_net_thread_start() < 0) {
+        PAL_ERROR("Failed to start the net thread: %s", errno2str(errno));
+        goto on_destroy_enclave;
+    }
+
     if (pal_run_init_process() < 0) {
         PAL_ERROR("Failed to run the init process: %s", errno2str(errno));
         goto on_destroy_enclave;
@@ -227,11 +240,29 @@ int occlum_pal_destroy(void) {
 
     int ret = 0;
 
+    // Stop the writeback thread and the net thread first, which issue ECalls
+    // by themselves
+    if (pal_writeback_thread_stop() < 0) {
+        ret = -1;
+        PAL_WARN("Cannot stop the writeback thread: %s", errno2str(errno));
+    }
+
+    if (pal_net_thread_stop() < 0) {
+        ret = -1;
+        PAL_WARN("Cannot stop the net thread: %s", errno2str(errno));
+    }
+
     if (pal_interrupt_thread_stop() < 0) {
         ret = -1;
         PAL_WARN("Cannot stop the interrupt thread: %s", errno2str(errno));
     }
 
+    // The host I/O threads are only started on demand
+    if (pal_io_engine_stop() < 0 && errno != ENOENT) {
+        ret = -1;
+        PAL_WARN("Cannot stop the host I/O threads: %s", errno2str(errno));
+    }
+
     if (pal_destroy_enclave() < 0) {
         ret = -1;
         PAL_WARN("Cannot destroy the enclave");
@@ -18,6 +18,7 @@
 #include <sgx_eid.h>
 #include <sgx_error.h>
 #include <sgx_urts.h>
+#include <sgx_uswitchless.h>
 
 #include "pal_enclave.h"
 #include "pal_error.h"
@@ -26,6 +27,7 @@
 #define MAX_PATH            FILENAME_MAX
 #define TOKEN_FILENAME      "enclave.token"
 #define ENCLAVE_FILENAME    "libocclum-libos.signed.so"
+#define PAL_CONF_FILENAME   "pal.conf"
 
 static sgx_enclave_id_t global_eid = SGX_INVALID_ENCLAVE_ID;
 
@@ -43,6 +45,26 @@ static int get_enclave_debug_flag() {
     return 1;
 }
 
+/* Get the number of the untrusted workers for switchless OCalls according to
+ * "resource_limits.switchless_threads" of Occlum.json, which is written to the
+ * PAL config file at build time. Switchless OCalls are disabled by default. */
+static int get_switchless_threads(const char *instance_dir) {
+    char pal_conf_path[MAX_PATH + 1] = {0};
+    snprintf(pal_conf_path, sizeof(pal_conf_path), "%s/build/%s", instance_dir,
+             PAL_CONF_FILENAME);
+    FILE *fp = fopen(pal_conf_path, "r");
+    if (fp == NULL) {
+        return 0;
+    }
+    int num_threads = 0;
+    if (fscanf(fp, "switchless_threads=%d", &num_threads) != 1 || num_threads < 0) {
+        PAL_WARN("Invalid number of switchless threads in \"%s\"", pal_conf_path);
+        num_threads = 0;
+    }
+    fclose(fp);
+    return num_threads;
+}
+
 static const char *get_enclave_absolute_path(const char *instance_dir) {
     static char enclave_path[MAX_PATH + 1] = {0};
     strncat(enclave_path, instance_dir, MAX_PATH);
@@ -102,8 +124,22 @@ int pal_init_enclave(const char *instance_dir) {
     /* Debug Support: set 2nd parameter to 1 */
     const char *enclave_path = get_enclave_absolute_path(instance_dir);
     int sgx_debug_flag = get_enclave_debug_flag();
-    ret = sgx_create_enclave(enclave_path, sgx_debug_flag, &token, &updated, &global_eid,
-                             NULL);
+    int switchless_threads = get_switchless_threads(instance_dir);
+    if (switchless_threads > 0) {
+        /* The OCalls marked as switchless are executed by the untrusted workers,
+         * which poll the requests in the shared memory. A switchless OCall falls
+         * back to a regular one if no worker takes it after some retries. */
+        sgx_uswitchless_config_t us_config = SGX_USWITCHLESS_CONFIG_INITIALIZER;
+        us_config.num_uworkers = switchless_threads;
+        const void *enclave_ex_p[32] = {0};
+        enclave_ex_p[SGX_CREATE_ENCLAVE_EX_SWITCHLESS_BIT_IDX] = (const void *)&us_config;
+        ret = sgx_create_enclave_ex(enclave_path, sgx_debug_flag, &token, &updated,
+                                    &global_eid, NULL, SGX_CREATE_ENCLAVE_EX_SWITCHLESS,
+                                    enclave_ex_p);
+    } else {
+        ret = sgx_create_enclave(enclave_path, sgx_debug_flag, &token, &updated, &global_eid,
+                                 NULL);
+    }
     if (ret != SGX_SUCCESS) {
         const char *sgx_err_msg = pal_get_sgx_error_msg(ret);
         PAL_ERROR("Failed to create enclave with error code 0x%x: %s", ret, sgx_err_msg);
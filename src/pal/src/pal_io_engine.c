@@ -0,0 +1,122 @@
+#include <pthread.h>
+#include <signal.h>
+#include <stdbool.h>
+#include <stdlib.h>
+#include <errno.h>
+#include "pal_io_engine.h"
+#include "pal_log.h"
+#include "pal_sig_handler.h"
+#include "pal_syscall.h"
+#include "errno2str.h"
+
+#define MAX_NUM_IO_THREADS  64
+
+static struct occlum_io_queue *io_queue = NULL;
+static pthread_t io_threads[MAX_NUM_IO_THREADS];
+static int num_io_threads = 0;
+
+static void do_request(struct occlum_io_request *req) {
+    __atomic_store_n(&req->host_tid, GETTID(), __ATOMIC_SEQ_CST);
+    if (__atomic_load_n(&req->is_cancelled, __ATOMIC_SEQ_CST)) {
+        req->ret = -EINTR;
+    } else {
+        long ret = syscall(req->num, req->args[0], req->args[1], req->args[2],
+                           req->args[3], req->args[4], req->args[5]);
+        req->ret = ret < 0 ? -errno : ret;
+    }
+
+    // The request may be freed by LibOS once it is done, so the eventfd must
+    // be read from the request in advance
+    int host_eventfd = req->host_eventfd;
+    __atomic_store_n(&req->host_tid, 0, __ATOMIC_SEQ_CST);
+    __atomic_store_n(&req->is_done, 1, __ATOMIC_RELEASE);
+    uint64_t val = 1;
+    write(host_eventfd, &val, sizeof(val));
+}
+
+static void *thread_func(void *_data) {
+    struct occlum_io_queue *queue = io_queue;
+
+    while (!__atomic_load_n(&queue->is_stopped, __ATOMIC_ACQUIRE)) {
+        uint32_t head = __atomic_load_n(&queue->head, __ATOMIC_ACQUIRE);
+        uint32_t tail = __atomic_load_n(&queue->tail, __ATOMIC_ACQUIRE);
+        if (head == tail) {
+            // Sleep until LibOS submits new requests. The futex returns
+            // immediately if the tail has been changed.
+            __atomic_fetch_add(&queue->nr_sleeping, 1, __ATOMIC_SEQ_CST);
+            if (__atomic_load_n(&queue->tail, __ATOMIC_SEQ_CST) == tail &&
+                    !__atomic_load_n(&queue->is_stopped, __ATOMIC_SEQ_CST)) {
+                (void)FUTEX_WAIT_TIMEOUT((int *)&queue->tail, tail, NULL);
+            }
+            __atomic_fetch_sub(&queue->nr_sleeping, 1, __ATOMIC_SEQ_CST);
+            continue;
+        }
+
+        // The slot must be read before taking it, after which LibOS may reuse it
+        struct occlum_io_request *req = queue->reqs[head % OCCLUM_IO_QUEUE_SIZE];
+        if (!__atomic_compare_exchange_n(&queue->head, &head, head + 1, false,
+                                         __ATOMIC_SEQ_CST, __ATOMIC_SEQ_CST)) {
+            continue;
+        }
+        do_request(req);
+    }
+    return NULL;
+}
+
+int pal_io_engine_start(struct occlum_io_queue *queue, int num_threads) {
+    if (io_queue != NULL) {
+        errno = EEXIST;
+        PAL_ERROR("The host I/O threads are already running: %s", errno2str(errno));
+        return -1;
+    }
+    if (num_threads <= 0 || num_threads > MAX_NUM_IO_THREADS) {
+        errno = EINVAL;
+        PAL_ERROR("Invalid number of the host I/O threads: %d", num_threads);
+        return -1;
+    }
+
+    io_queue = queue;
+    for (int i = 0; i < num_threads; i++) {
+        int ret = pthread_create(&io_threads[i], NULL, thread_func, NULL);
+        if (ret) {
+            pal_io_engine_stop();
+
+            errno = ret;
+            PAL_ERROR("Failed to start the host I/O threads: %s", errno2str(errno));
+            return -1;
+        }
+        num_io_threads++;
+    }
+    return 0;
+}
+
+void pal_io_engine_wake(struct occlum_io_queue *queue) {
+    (void)FUTEX_WAKE_ONE((int *)&queue->tail);
+}
+
+int pal_io_engine_stop(void) {
+    if (io_queue == NULL) {
+        errno = ENOENT;
+        return -1;
+    }
+
+    // Interrupt the requests in progress and the idle threads
+    __atomic_store_n(&io_queue->is_stopped, 1, __ATOMIC_SEQ_CST);
+    (void)FUTEX_WAKE_ALL((int *)&io_queue->tail);
+    for (int i = 0; i < num_io_threads; i++) {
+        pthread_kill(io_threads[i], SIGRT_INTERRUPT);
+    }
+
+    int ret = 0;
+    for (int i = 0; i < num_io_threads; i++) {
+        int err = pthread_join(io_threads[i], NULL);
+        if (err) {
+            errno = err;
+            PAL_ERROR("Failed to free the host I/O thread: %s", errno2str(errno));
+            ret = -1;
+        }
+    }
+    num_io_threads = 0;
+    io_queue = NULL;
+    return ret;
+}
@@ -0,0 +1,20 @@
+#ifndef __PAL_IO_ENGINE_H__
+#define __PAL_IO_ENGINE_H__
+
+#include "Enclave_u.h"
+
+// The host I/O threads, which execute the blocking host syscalls offloaded by
+// LibOS. The requests are taken from a queue in the untrusted memory that is
+// shared with LibOS. Once a request is done, the host eventfd of the request
+// is written to wake up the LibOS thread waiting for it.
+
+// Start the host I/O threads that serve the queue
+int pal_io_engine_start(struct occlum_io_queue *queue, int num_threads);
+
+// Wake up the idle host I/O threads to handle the new requests
+void pal_io_engine_wake(struct occlum_io_queue *queue);
+
+// Stop the host I/O threads if they are running
+int pal_io_engine_stop(void);
+
+#endif /* __PAL_IO_ENGINE_H__ */
@@ -0,0 +1,124 @@
+#include <errno.h>
+#include <poll.h>
+#include <pthread.h>
+#include <string.h>
+#include <time.h>
+#include "Enclave_u.h"
+#include "pal_enclave.h"
+#include "pal_error.h"
+#include "pal_log.h"
+#include "pal_net_thread.h"
+#include "errno2str.h"
+
+#define MS  (1000*1000L) // 1ms = 1,000,000ns
+
+// The max time to wait before the next round, e.g., when the stack is not
+// running yet
+#define MAX_WAIT_MS     100
+#define MAX_NUM_TAPS    16
+
+// The net thread drives the in-enclave TCP/IP stack, which waits for the
+// frames from the TAP devices and the timers of the stack. Same as the
+// writeback thread, it is not counted by the thread counter.
+static pthread_t thread;
+static volatile int is_running = 0;
+
+static pthread_mutex_t taps_lock = PTHREAD_MUTEX_INITIALIZER;
+static struct pollfd taps[MAX_NUM_TAPS];
+static int num_taps = 0;
+
+static void wait_for_taps(int timeout_ms) {
+    struct pollfd fds[MAX_NUM_TAPS];
+
+    pthread_mutex_lock(&taps_lock);
+    int nfds = num_taps;
+    memcpy(fds, taps, sizeof(struct pollfd) * nfds);
+    pthread_mutex_unlock(&taps_lock);
+
+    if (nfds == 0) {
+        struct timespec timeout = { .tv_sec = 0, .tv_nsec = timeout_ms * MS };
+        nanosleep(&timeout, NULL);
+        return;
+    }
+    // The frames are read by the stack, so the errors are ignored here
+    poll(fds, nfds, timeout_ms);
+}
+
+static void *thread_func(void *_data) {
+    sgx_enclave_id_t eid = pal_get_enclave_id();
+
+    while (is_running) {
+        int ecall_ret = 0;
+        sgx_status_t ecall_status = occlum_ecall_poll_net(eid, &ecall_ret);
+        if (ecall_status != SGX_SUCCESS) {
+            const char *sgx_err = pal_get_sgx_error_msg(ecall_status);
+            PAL_ERROR("Failed to do ECall: occlum_ecall_poll_net with error code 0x%x: %s",
+                      ecall_status, sgx_err);
+            exit(EXIT_FAILURE);
+        }
+        // The stack is not running until the first socket of it is created
+        if (ecall_ret < 0 && ecall_ret != -ENOENT) {
+            PAL_WARN("Unexpected error from occlum_ecall_poll_net: %s", errno2str(-ecall_ret));
+        }
+
+        int timeout_ms = MAX_WAIT_MS;
+        if (ecall_ret >= 0 && ecall_ret < MAX_WAIT_MS) {
+            timeout_ms = ecall_ret;
+        }
+        wait_for_taps(timeout_ms);
+    }
+
+    return NULL;
+}
+
+int pal_net_thread_add_tap(int fd) {
+    pthread_mutex_lock(&taps_lock);
+    if (num_taps == MAX_NUM_TAPS) {
+        pthread_mutex_unlock(&taps_lock);
+        errno = ENOSPC;
+        return -1;
+    }
+    taps[num_taps].fd = fd;
+    taps[num_taps].events = POLLIN;
+    num_taps++;
+    pthread_mutex_unlock(&taps_lock);
+    return 0;
+}
+
+int pal_net_thread_start(void) {
+    if (is_running) {
+        errno = EEXIST;
+        PAL_ERROR("The net thread is already running: %s", errno2str(errno));
+        return -1;
+    }
+
+    is_running = 1;
+
+    int ret = 0;
+    if ((ret = pthread_create(&thread, NULL, thread_func, NULL))) {
+        is_running = 0;
+
+        errno = ret;
+        PAL_ERROR("Failed to start the net thread: %s", errno2str(errno));
+        return -1;
+    }
+    return 0;
+}
+
+int pal_net_thread_stop(void) {
+    if (!is_running) {
+        errno = ENOENT;
+        return -1;
+    }
+
+    is_running = 0;
+
+    int ret = 0;
+    if ((ret = pthread_join(thread, NULL))) {
+        errno = ret;
+        PAL_ERROR("Failed to free the net thread: %s", errno2str(errno));
+        return -1;
+    }
+
+    return 0;
+}
@@ -0,0 +1,12 @@
+#ifndef __PAL_NET_THREAD_H__
+#define __PAL_NET_THREAD_H__
+
+int pal_net_thread_start(void);
+
+int pal_net_thread_stop(void);
+
+// Add a TAP device of the in-enclave TCP/IP stack, whose frames wake up the
+// net thread
+int pal_net_thread_add_tap(int fd);
+
+#endif /* __PAL_NET_THREAD_H__ */
@@ -3,11 +3,19 @@
 #include <signal.h>
 #include <string.h>
 
-// Signal 64 is used to notify interrupts
-#define SIGRT_INTERRUPT     64
+static void handle_interrupt_signal(int signum) {
+    // Do nothing. The signal is only to interrupt the blocking syscalls of
+    // the thread, which then check the pending signals inside the enclave.
+}
 
 int pal_register_sig_handlers(void) {
-    if (signal(SIGRT_INTERRUPT, SIG_IGN) == SIG_ERR) {
+    // Without SA_RESTART, a blocking syscall interrupted by the signal returns
+    // EINTR instead of being restarted by the host kernel
+    struct sigaction action;
+    memset(&action, 0, sizeof(action));
+    action.sa_handler = handle_interrupt_signal;
+    sigemptyset(&action.sa_mask);
+    if (sigaction(SIGRT_INTERRUPT, &action, NULL) < 0) {
         PAL_ERROR("Failed to regiter the SIG64 handler");
         return -1;
     }
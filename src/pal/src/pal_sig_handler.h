@@ -1,6 +1,9 @@
 #ifndef __PAL_SIG_HANDLER_H__
 #define __PAL_SIG_HANDLER_H__
 
+// Signal 64 is used to notify interrupts
+#define SIGRT_INTERRUPT     64
+
 // Register signal handlers for PAL.
 //
 // Currently, there is only one signal number that needs to be covered: signal
@@ -9,7 +12,9 @@
 // registered by Intel SGX SDK. So we are ok in this case. But for a
 // simulation-mode enclave, there is no signal handler registered by Intel SGX
 // SDK. Without a signal handler, the delivery of the signal will kill the
-// process. This crash can be prevented by this API.
+// process. This crash can be prevented by this API. Besides, the signal
+// interrupts the blocking host syscalls of the thread that receives it, so
+// that signals can be delivered to LibOS threads blocked in OCalls.
 int pal_register_sig_handlers(void);
 
 #endif /* __PAL_SIG_HANDLER_H__ */
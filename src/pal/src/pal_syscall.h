@@ -2,6 +2,7 @@
 #define __PAL_SYSCALL_H__
 
 #define _GNU_SOURCE
+#include <limits.h>
 #include <linux/futex.h>
 #include <sys/time.h>
 #include <sys/types.h>
@@ -12,6 +13,7 @@
 #define TGKILL(tgid, tid, signum)       ((int)syscall(__NR_tgkill, (tgid), (tid), (signum)))
 #define FUTEX_WAIT_TIMEOUT(addr, val, timeout)  ((int)syscall(__NR_futex, (addr), FUTEX_WAIT, (val), (timeout)))
 #define FUTEX_WAKE_ONE(addr)                ((int)syscall(__NR_futex, (addr), FUTEX_WAKE, 1))
+#define FUTEX_WAKE_ALL(addr)                ((int)syscall(__NR_futex, (addr), FUTEX_WAKE, INT_MAX))
 #define RAW_PPOLL(fds, nfds, timeout)   ((int)syscall(__NR_ppoll, (fds), (nfds), (timeout), NULL, 0))
 
 #endif /* __PAL_SYSCALL_H__ */
@@ -0,0 +1,77 @@
+#include <pthread.h>
+#include <time.h>
+#include "Enclave_u.h"
+#include "pal_enclave.h"
+#include "pal_error.h"
+#include "pal_log.h"
+#include "pal_writeback_thread.h"
+#include "errno2str.h"
+
+#define MS  (1000*1000L) // 1ms = 1,000,000ns
+
+// The writeback thread is not counted by the thread counter, which is waited
+// by the interrupt thread to exit. Instead, it runs until being stopped.
+static pthread_t thread;
+static volatile int is_running = 0;
+
+static void *thread_func(void *_data) {
+    sgx_enclave_id_t eid = pal_get_enclave_id();
+
+    while (is_running) {
+        int ecall_ret = 0;
+        sgx_status_t ecall_status = occlum_ecall_writeback(eid, &ecall_ret);
+        if (ecall_status != SGX_SUCCESS) {
+            const char *sgx_err = pal_get_sgx_error_msg(ecall_status);
+            PAL_ERROR("Failed to do ECall: occlum_ecall_writeback with error code 0x%x: %s",
+                      ecall_status, sgx_err);
+            exit(EXIT_FAILURE);
+        }
+        // A failed writeback is retried in the next round
+        if (ecall_ret < 0) {
+            PAL_WARN("Unexpected error from occlum_ecall_writeback: %s", errno2str(-ecall_ret));
+        }
+
+        struct timespec timeout = { .tv_sec = 0, .tv_nsec = 100 * MS };
+        nanosleep(&timeout, NULL);
+    }
+
+    return NULL;
+}
+
+int pal_writeback_thread_start(void) {
+    if (is_running) {
+        errno = EEXIST;
+        PAL_ERROR("The writeback thread is already running: %s", errno2str(errno));
+        return -1;
+    }
+
+    is_running = 1;
+
+    int ret = 0;
+    if ((ret = pthread_create(&thread, NULL, thread_func, NULL))) {
+        is_running = 0;
+
+        errno = ret;
+        PAL_ERROR("Failed to start the writeback thread: %s", errno2str(errno));
+        return -1;
+    }
+    return 0;
+}
+
+int pal_writeback_thread_stop(void) {
+    if (!is_running) {
+        errno = ENOENT;
+        return -1;
+    }
+
+    is_running = 0;
+
+    int ret = 0;
+    if ((ret = pthread_join(thread, NULL))) {
+        errno = ret;
+        PAL_ERROR("Failed to free the writeback thread: %s", errno2str(errno));
+        return -1;
+    }
+
+    return 0;
+}
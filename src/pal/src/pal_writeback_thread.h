@@ -0,0 +1,8 @@
+#ifndef __PAL_WRITEBACK_H__
+#define __PAL_WRITEBACK_H__
+
+int pal_writeback_thread_start(void);
+
+int pal_writeback_thread_stop(void);
+
+#endif /* __PAL_WRITEBACK_H__ */
@@ -0,0 +1,261 @@
+#include <sys/stat.h>
+#include <sys/xattr.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdint.h>
+#include <string.h>
+#include <unistd.h>
+#include "test_fs.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define FILE_PATH           "/root/test_acl_file.txt"
+#define DIR_PATH            "/root/test_acl_dir"
+#define INHERIT_FILE_PATH   DIR_PATH "/file.txt"
+#define INHERIT_DIR_PATH    DIR_PATH "/dir"
+
+#define ACL_ACCESS          "system.posix_acl_access"
+#define ACL_DEFAULT         "system.posix_acl_default"
+
+// The binary format of ACLs in extended attributes, same as Linux
+#define ACL_XATTR_VERSION   0x0002
+#define ACL_USER_OBJ        0x01
+#define ACL_USER            0x02
+#define ACL_GROUP_OBJ       0x04
+#define ACL_MASK            0x10
+#define ACL_OTHER           0x20
+#define ACL_UNDEFINED_ID    ((uint32_t)-1)
+
+typedef struct {
+    uint16_t tag;
+    uint16_t perm;
+    uint32_t id;
+} acl_entry_t;
+
+typedef struct {
+    uint32_t version;
+    acl_entry_t entries[8];
+} acl_t;
+
+#define ACL_SIZE(num_entries) (sizeof(uint32_t) + (num_entries) * sizeof(acl_entry_t))
+
+static int create_file(const char *file_path, mode_t mode) {
+    int fd = open(file_path, O_RDWR | O_CREAT | O_TRUNC, mode);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a file");
+    }
+    close(fd);
+    return 0;
+}
+
+static int check_mode(const char *path, mode_t expected_mode) {
+    struct stat stat_buf;
+    if (stat(path, &stat_buf) < 0) {
+        THROW_ERROR("failed to stat the file");
+    }
+    if ((stat_buf.st_mode & 0777) != expected_mode) {
+        THROW_ERROR("the permission bits are not as expected");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_set_access_acl() {
+    acl_t acl = {
+        .version = ACL_XATTR_VERSION,
+        .entries = {
+            { ACL_USER_OBJ, 6, ACL_UNDEFINED_ID },
+            { ACL_USER, 4, 1000 },
+            { ACL_GROUP_OBJ, 4, ACL_UNDEFINED_ID },
+            { ACL_MASK, 4, ACL_UNDEFINED_ID },
+            { ACL_OTHER, 0, ACL_UNDEFINED_ID },
+        },
+    };
+    if (create_file(FILE_PATH, 0666) < 0) {
+        return -1;
+    }
+    if (setxattr(FILE_PATH, ACL_ACCESS, &acl, ACL_SIZE(5), 0) < 0) {
+        THROW_ERROR("failed to set the access ACL");
+    }
+    // The permission bits are updated with the ACL
+    if (check_mode(FILE_PATH, 0640) < 0) {
+        return -1;
+    }
+    if (access(FILE_PATH, R_OK | W_OK) < 0) {
+        THROW_ERROR("the owner should be able to read and write");
+    }
+    if (access(FILE_PATH, X_OK) == 0 || errno != EACCES) {
+        THROW_ERROR("the owner should not be able to execute");
+    }
+
+    // The mask entry of the ACL is updated with the group bits by chmod
+    if (chmod(FILE_PATH, 0600) < 0) {
+        THROW_ERROR("failed to chmod");
+    }
+    acl_t new_acl;
+    if (getxattr(FILE_PATH, ACL_ACCESS, &new_acl, sizeof(new_acl)) != ACL_SIZE(5)) {
+        THROW_ERROR("failed to get the access ACL");
+    }
+    if (new_acl.entries[3].tag != ACL_MASK || new_acl.entries[3].perm != 0 ||
+            new_acl.entries[2].perm != 4) {
+        THROW_ERROR("the mask entry should be updated by chmod");
+    }
+    return 0;
+}
+
+static int test_set_equiv_access_acl() {
+    acl_t acl = {
+        .version = ACL_XATTR_VERSION,
+        .entries = {
+            { ACL_USER_OBJ, 7, ACL_UNDEFINED_ID },
+            { ACL_GROUP_OBJ, 5, ACL_UNDEFINED_ID },
+            { ACL_OTHER, 1, ACL_UNDEFINED_ID },
+        },
+    };
+    if (setxattr(FILE_PATH, ACL_ACCESS, &acl, ACL_SIZE(3), 0) < 0) {
+        THROW_ERROR("failed to set the access ACL");
+    }
+    if (check_mode(FILE_PATH, 0751) < 0) {
+        return -1;
+    }
+    // An ACL equivalent to the permission bits is not stored
+    if (getxattr(FILE_PATH, ACL_ACCESS, NULL, 0) >= 0 || errno != ENODATA) {
+        THROW_ERROR("the equivalent ACL should not be stored");
+    }
+    return 0;
+}
+
+static int test_set_invalid_acl() {
+    acl_t acl = {
+        .version = ACL_XATTR_VERSION,
+        .entries = {
+            { ACL_USER_OBJ, 6, ACL_UNDEFINED_ID },
+            { ACL_USER, 4, 1000 },
+            { ACL_GROUP_OBJ, 4, ACL_UNDEFINED_ID },
+            { ACL_OTHER, 0, ACL_UNDEFINED_ID },
+        },
+    };
+    // A named entry requires a mask entry
+    if (setxattr(FILE_PATH, ACL_ACCESS, &acl, ACL_SIZE(4), 0) == 0 || errno != EINVAL) {
+        THROW_ERROR("setting an ACL without the mask entry should fail");
+    }
+    // The entries must be sorted
+    acl.entries[3] = (acl_entry_t) { ACL_MASK, 4, ACL_UNDEFINED_ID };
+    acl.entries[4] = acl.entries[0];
+    acl.entries[0] = (acl_entry_t) { ACL_OTHER, 0, ACL_UNDEFINED_ID };
+    if (setxattr(FILE_PATH, ACL_ACCESS, &acl, ACL_SIZE(5), 0) == 0 || errno != EINVAL) {
+        THROW_ERROR("setting an unsorted ACL should fail");
+    }
+    if (setxattr(FILE_PATH, ACL_ACCESS, &acl, ACL_SIZE(4) - 1, 0) == 0 || errno != EINVAL) {
+        THROW_ERROR("setting an ACL of invalid size should fail");
+    }
+    // Only directories have default ACLs
+    if (setxattr(FILE_PATH, ACL_DEFAULT, &acl, ACL_SIZE(3), 0) == 0 || errno != EACCES) {
+        THROW_ERROR("setting a default ACL on a file should fail");
+    }
+    if (setxattr(FILE_PATH, "system.unknown", "", 0, 0) == 0 || errno != EOPNOTSUPP) {
+        THROW_ERROR("setting an unknown system attribute should fail");
+    }
+    return 0;
+}
+
+static int test_inherit_default_acl() {
+    acl_t acl = {
+        .version = ACL_XATTR_VERSION,
+        .entries = {
+            { ACL_USER_OBJ, 7, ACL_UNDEFINED_ID },
+            { ACL_USER, 7, 1000 },
+            { ACL_GROUP_OBJ, 5, ACL_UNDEFINED_ID },
+            { ACL_MASK, 7, ACL_UNDEFINED_ID },
+            { ACL_OTHER, 0, ACL_UNDEFINED_ID },
+        },
+    };
+    if (mkdir(DIR_PATH, 0777) < 0) {
+        THROW_ERROR("failed to create the dir");
+    }
+    if (setxattr(DIR_PATH, ACL_DEFAULT, &acl, ACL_SIZE(5), 0) < 0) {
+        THROW_ERROR("failed to set the default ACL");
+    }
+
+    // The inherited ACL is restricted by the mode given at creation
+    if (create_file(INHERIT_FILE_PATH, 0644) < 0) {
+        return -1;
+    }
+    if (check_mode(INHERIT_FILE_PATH, 0640) < 0) {
+        return -1;
+    }
+    acl_t file_acl;
+    if (getxattr(INHERIT_FILE_PATH, ACL_ACCESS, &file_acl, sizeof(file_acl)) != ACL_SIZE(5)) {
+        THROW_ERROR("the file should inherit the default ACL");
+    }
+    if (file_acl.entries[1].perm != 7 || file_acl.entries[3].perm != 4) {
+        THROW_ERROR("the inherited ACL is not as expected");
+    }
+
+    // A new dir inherits the default ACL as both its access and default ACLs
+    if (mkdir(INHERIT_DIR_PATH, 0755) < 0) {
+        THROW_ERROR("failed to create the dir");
+    }
+    if (check_mode(INHERIT_DIR_PATH, 0750) < 0) {
+        return -1;
+    }
+    acl_t dir_acl;
+    if (getxattr(INHERIT_DIR_PATH, ACL_DEFAULT, &dir_acl, sizeof(dir_acl)) != ACL_SIZE(5) ||
+            memcmp(&dir_acl, &acl, ACL_SIZE(5)) != 0) {
+        THROW_ERROR("the dir should inherit the default ACL");
+    }
+    if (getxattr(INHERIT_DIR_PATH, ACL_ACCESS, NULL, 0) != ACL_SIZE(5)) {
+        THROW_ERROR("the dir should have an access ACL");
+    }
+    return 0;
+}
+
+static int test_umask() {
+    const char *file_path = "/root/test_acl_umask.txt";
+    if (umask(022) != 0) {
+        THROW_ERROR("the default umask should be 0");
+    }
+    if (create_file(file_path, 0666) < 0) {
+        return -1;
+    }
+    if (umask(0) != 022) {
+        THROW_ERROR("umask should return the previous mask");
+    }
+    if (check_mode(file_path, 0644) < 0) {
+        return -1;
+    }
+    if (unlink(file_path) < 0) {
+        THROW_ERROR("failed to unlink the file");
+    }
+    return 0;
+}
+
+static int test_cleanup() {
+    if (unlink(INHERIT_FILE_PATH) < 0 || rmdir(INHERIT_DIR_PATH) < 0 ||
+            rmdir(DIR_PATH) < 0 || unlink(FILE_PATH) < 0) {
+        THROW_ERROR("failed to remove the files");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_set_access_acl),
+    TEST_CASE(test_set_equiv_access_acl),
+    TEST_CASE(test_set_invalid_acl),
+    TEST_CASE(test_inherit_default_acl),
+    TEST_CASE(test_umask),
+    TEST_CASE(test_cleanup),
+};
+
+int main(int argc, const char *argv[]) {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,269 @@
+#include <sys/syscall.h>
+#include <sys/eventfd.h>
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+#include <linux/aio_abi.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdint.h>
+#include <time.h>
+#include <unistd.h>
+#include <string.h>
+
+#include "test.h"
+
+#define FILE_PATH   "/root/test_aio.txt"
+#define MSG         "hello, aio"
+#define MSG_LEN     (sizeof(MSG) - 1)
+
+// ============================================================================
+// Helper function
+// ============================================================================
+static int io_setup(unsigned int nr_events, aio_context_t *ctx) {
+    return syscall(__NR_io_setup, nr_events, ctx);
+}
+
+static int io_destroy(aio_context_t ctx) {
+    return syscall(__NR_io_destroy, ctx);
+}
+
+static int io_submit(aio_context_t ctx, long nr, struct iocb **iocbpp) {
+    return syscall(__NR_io_submit, ctx, nr, iocbpp);
+}
+
+static int io_getevents(aio_context_t ctx, long min_nr, long nr, struct io_event *events,
+                        struct timespec *timeout) {
+    return syscall(__NR_io_getevents, ctx, min_nr, nr, events, timeout);
+}
+
+static void prep_iocb(struct iocb *iocb, int opcode, int fd, void *buf, size_t len,
+                      off_t offset) {
+    memset(iocb, 0, sizeof(*iocb));
+    iocb->aio_lio_opcode = opcode;
+    iocb->aio_fildes = fd;
+    iocb->aio_buf = (uint64_t)(uintptr_t)buf;
+    iocb->aio_nbytes = len;
+    iocb->aio_offset = offset;
+    iocb->aio_data = opcode + 1;
+}
+
+static int check_event(struct io_event *event, struct iocb *iocb, long long res) {
+    if (event->obj != (uint64_t)(uintptr_t)iocb || event->data != iocb->aio_data ||
+            event->res != res) {
+        THROW_ERROR("the event is not the expected one");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases for Linux AIO
+// ============================================================================
+static int test_aio_write_read() {
+    aio_context_t ctx = 0;
+    if (io_setup(8, &ctx) < 0) {
+        THROW_ERROR("failed to set up the AIO context");
+    }
+    int fd = open(FILE_PATH, O_RDWR | O_CREAT | O_TRUNC, 0666);
+    if (fd < 0) {
+        io_destroy(ctx);
+        THROW_ERROR("failed to open the file");
+    }
+
+    int ret = -1;
+    struct iocb iocb;
+    struct iocb *iocbs[1] = { &iocb };
+    struct io_event event;
+    char buf[32] = {0};
+
+    prep_iocb(&iocb, IOCB_CMD_PWRITE, fd, MSG, MSG_LEN, 4);
+    if (io_submit(ctx, 1, iocbs) != 1 || io_getevents(ctx, 1, 1, &event, NULL) != 1 ||
+            check_event(&event, &iocb, MSG_LEN) < 0) {
+        printf("\t\tERROR: failed to write the file by AIO\n");
+        goto out;
+    }
+    prep_iocb(&iocb, IOCB_CMD_PREAD, fd, buf, sizeof(buf), 4);
+    if (io_submit(ctx, 1, iocbs) != 1 || io_getevents(ctx, 1, 1, &event, NULL) != 1 ||
+            check_event(&event, &iocb, MSG_LEN) < 0 || strcmp(buf, MSG) != 0) {
+        printf("\t\tERROR: failed to read the file by AIO\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    close(fd);
+    unlink(FILE_PATH);
+    io_destroy(ctx);
+    return ret;
+}
+
+static int test_aio_batch_and_resfd() {
+    aio_context_t ctx = 0;
+    if (io_setup(8, &ctx) < 0) {
+        THROW_ERROR("failed to set up the AIO context");
+    }
+    int fd = open(FILE_PATH, O_RDWR | O_CREAT | O_TRUNC, 0666);
+    int efd = eventfd(0, 0);
+    if (fd < 0 || efd < 0) {
+        io_destroy(ctx);
+        THROW_ERROR("failed to open the file or the eventfd");
+    }
+
+    int ret = -1;
+    struct iocb write_iocb, fsync_iocb;
+    struct iocb *iocbs[2] = { &write_iocb, &fsync_iocb };
+    struct io_event events[2];
+    prep_iocb(&write_iocb, IOCB_CMD_PWRITE, fd, MSG, MSG_LEN, 0);
+    prep_iocb(&fsync_iocb, IOCB_CMD_FSYNC, fd, NULL, 0, 0);
+    write_iocb.aio_flags = fsync_iocb.aio_flags = IOCB_FLAG_RESFD;
+    write_iocb.aio_resfd = fsync_iocb.aio_resfd = efd;
+    if (io_submit(ctx, 2, iocbs) != 2) {
+        printf("\t\tERROR: failed to submit the requests\n");
+        goto out;
+    }
+    // The eventfd is signaled once for each completed request
+    uint64_t count = 0;
+    if (read(efd, &count, sizeof(count)) != sizeof(count) || count != 2) {
+        printf("\t\tERROR: the eventfd is not signaled\n");
+        goto out;
+    }
+    if (io_getevents(ctx, 2, 2, events, NULL) != 2 ||
+            check_event(&events[0], &write_iocb, MSG_LEN) < 0 ||
+            check_event(&events[1], &fsync_iocb, 0) < 0) {
+        goto out;
+    }
+    ret = 0;
+out:
+    close(efd);
+    close(fd);
+    unlink(FILE_PATH);
+    io_destroy(ctx);
+    return ret;
+}
+
+static int connect_udp_sockets(int *sock, int *peer) {
+    struct sockaddr_in addr = {
+        .sin_family = AF_INET,
+        .sin_addr.s_addr = htonl(INADDR_LOOPBACK),
+        .sin_port = 0,
+    };
+    socklen_t addr_len = sizeof(addr);
+    *sock = socket(AF_INET, SOCK_DGRAM, 0);
+    *peer = socket(AF_INET, SOCK_DGRAM, 0);
+    if (*sock < 0 || *peer < 0) {
+        THROW_ERROR("failed to create the sockets");
+    }
+    if (bind(*sock, (struct sockaddr *)&addr, sizeof(addr)) < 0 ||
+            getsockname(*sock, (struct sockaddr *)&addr, &addr_len) < 0 ||
+            connect(*peer, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        close(*sock);
+        close(*peer);
+        THROW_ERROR("failed to connect the sockets");
+    }
+    return 0;
+}
+
+static int test_aio_read_socket_async() {
+    aio_context_t ctx = 0;
+    if (io_setup(8, &ctx) < 0) {
+        THROW_ERROR("failed to set up the AIO context");
+    }
+    int sock, peer;
+    if (connect_udp_sockets(&sock, &peer) < 0) {
+        io_destroy(ctx);
+        return -1;
+    }
+
+    int ret = -1;
+    struct iocb iocb;
+    struct iocb *iocbs[1] = { &iocb };
+    struct io_event event;
+    struct timespec timeout = { .tv_sec = 0, .tv_nsec = 10 * 1000 * 1000 };
+    char buf[32] = {0};
+
+    // The read is in progress until the data arrives, without blocking the submission
+    prep_iocb(&iocb, IOCB_CMD_PREAD, sock, buf, sizeof(buf), 0);
+    if (io_submit(ctx, 1, iocbs) != 1) {
+        printf("\t\tERROR: failed to submit the read of the socket\n");
+        goto out;
+    }
+    if (io_getevents(ctx, 1, 1, &event, &timeout) != 0) {
+        printf("\t\tERROR: the read of the socket is done without data\n");
+        goto out;
+    }
+    if (send(peer, MSG, MSG_LEN, 0) != MSG_LEN) {
+        printf("\t\tERROR: failed to send the data\n");
+        goto out;
+    }
+    if (io_getevents(ctx, 1, 1, &event, NULL) != 1 ||
+            check_event(&event, &iocb, MSG_LEN) < 0 || strcmp(buf, MSG) != 0) {
+        printf("\t\tERROR: failed to read the socket by AIO\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    io_destroy(ctx);
+    close(sock);
+    close(peer);
+    return ret;
+}
+
+static int test_aio_getevents_timeout() {
+    aio_context_t ctx = 0;
+    if (io_setup(1, &ctx) < 0) {
+        THROW_ERROR("failed to set up the AIO context");
+    }
+    struct io_event event;
+    struct timespec timeout = { .tv_sec = 0, .tv_nsec = 10 * 1000 * 1000 };
+    int ret = io_getevents(ctx, 1, 1, &event, &timeout);
+    io_destroy(ctx);
+    if (ret != 0) {
+        THROW_ERROR("io_getevents should return no event on timeout");
+    }
+    return 0;
+}
+
+static int test_aio_invalid() {
+    aio_context_t ctx = 0;
+    if (io_setup(0, &ctx) >= 0 || errno != EINVAL) {
+        THROW_ERROR("io_setup should fail with EINVAL given zero events");
+    }
+    if (io_setup(1, &ctx) < 0) {
+        THROW_ERROR("failed to set up the AIO context");
+    }
+
+    int ret = -1;
+    struct iocb iocb;
+    struct iocb *iocbs[1] = { &iocb };
+    prep_iocb(&iocb, IOCB_CMD_PREAD, -1, NULL, 0, 0);
+    if (io_submit(ctx, 1, iocbs) >= 0 || errno != EBADF) {
+        printf("\t\tERROR: io_submit should fail with EBADF\n");
+        goto out;
+    }
+    prep_iocb(&iocb, 0xff, STDOUT_FILENO, NULL, 0, 0);
+    if (io_submit(ctx, 1, iocbs) >= 0 || errno != EINVAL) {
+        printf("\t\tERROR: io_submit should fail with EINVAL\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    io_destroy(ctx);
+    if (ret == 0 && (io_destroy(ctx) >= 0 || errno != EINVAL)) {
+        THROW_ERROR("io_destroy should fail with EINVAL given a destroyed context");
+    }
+    return ret;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+static test_case_t test_cases[] = {
+    TEST_CASE(test_aio_write_read),
+    TEST_CASE(test_aio_batch_and_resfd),
+    TEST_CASE(test_aio_read_socket_async),
+    TEST_CASE(test_aio_getevents_timeout),
+    TEST_CASE(test_aio_invalid),
+};
+
+int main() {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,120 @@
+#include <sys/mman.h>
+#include <sys/wait.h>
+#include <spawn.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define CHILD_PROG          "/bin/aslr"
+#define NR_CHILDREN         4
+
+// The addresses reported by a child process
+typedef struct {
+    uintptr_t code;
+    uintptr_t heap;
+    uintptr_t stack;
+    uintptr_t mmap;
+} layout_t;
+
+static int child_report_layout() {
+    int stack_var = 0;
+    layout_t layout;
+    layout.code = (uintptr_t)&child_report_layout;
+    layout.heap = (uintptr_t)sbrk(0);
+    layout.stack = (uintptr_t)&stack_var;
+    void *buf = mmap(NULL, 4096, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (buf == MAP_FAILED) {
+        return -1;
+    }
+    layout.mmap = (uintptr_t)buf;
+    munmap(buf, 4096);
+
+    if (write(STDOUT_FILENO, &layout, sizeof(layout)) != sizeof(layout)) {
+        return -1;
+    }
+    return 0;
+}
+
+static int get_child_layout(layout_t *layout) {
+    int pipe_fds[2];
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+
+    posix_spawn_file_actions_t file_actions;
+    posix_spawn_file_actions_init(&file_actions);
+    posix_spawn_file_actions_adddup2(&file_actions, pipe_fds[1], STDOUT_FILENO);
+    posix_spawn_file_actions_addclose(&file_actions, pipe_fds[0]);
+
+    int child_pid;
+    const char *child_argv[3] = { CHILD_PROG, "report_layout", NULL };
+    int ret = posix_spawn(&child_pid, CHILD_PROG, &file_actions, NULL,
+                          (char *const *)child_argv, NULL);
+    posix_spawn_file_actions_destroy(&file_actions);
+    close(pipe_fds[1]);
+    if (ret != 0) {
+        close(pipe_fds[0]);
+        THROW_ERROR("failed to spawn a child process");
+    }
+
+    ssize_t len = read(pipe_fds[0], layout, sizeof(*layout));
+    close(pipe_fds[0]);
+
+    int status;
+    if (waitpid(child_pid, &status, 0) < 0) {
+        THROW_ERROR("failed to wait for the child process");
+    }
+    if (len != sizeof(*layout) || !WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        THROW_ERROR("failed to get the layout of the child process");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_randomized_layout() {
+    layout_t layouts[NR_CHILDREN];
+    for (int i = 0; i < NR_CHILDREN; i++) {
+        if (get_child_layout(&layouts[i]) < 0) {
+            THROW_ERROR("failed to get the layout of child %d", i);
+        }
+    }
+
+    // The chance that all the children share the same address of any part is
+    // negligible if the layout is randomized
+    int code_differs = 0, heap_differs = 0, stack_differs = 0, mmap_differs = 0;
+    for (int i = 1; i < NR_CHILDREN; i++) {
+        code_differs |= layouts[i].code != layouts[0].code;
+        heap_differs |= layouts[i].heap != layouts[0].heap;
+        stack_differs |= layouts[i].stack != layouts[0].stack;
+        mmap_differs |= layouts[i].mmap != layouts[0].mmap;
+    }
+    if (!code_differs || !heap_differs || !stack_differs || !mmap_differs) {
+        THROW_ERROR("the layout of processes is not randomized");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_randomized_layout),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 1 && strcmp(argv[1], "report_layout") == 0) {
+        return child_report_layout() < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
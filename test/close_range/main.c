@@ -0,0 +1,127 @@
+#include <sys/syscall.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#ifndef SYS_close_range
+#define SYS_close_range         436
+#endif
+
+#define CLOSE_RANGE_UNSHARE     (1U << 1)
+#define CLOSE_RANGE_CLOEXEC     (1U << 2)
+
+#define NUM_FDS                 4
+
+static int close_range(unsigned int first, unsigned int last, unsigned int flags) {
+    return syscall(SYS_close_range, first, last, flags);
+}
+
+static int open_files(int *fds) {
+    for (int i = 0; i < NUM_FDS; i++) {
+        fds[i] = open("/dev/null", O_RDONLY);
+        if (fds[i] < 0) {
+            THROW_ERROR("failed to open /dev/null");
+        }
+    }
+    return 0;
+}
+
+static int is_open(int fd) {
+    return fcntl(fd, F_GETFD) >= 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_close_range() {
+    int fds[NUM_FDS];
+    if (open_files(fds) < 0) {
+        return -1;
+    }
+    // The fds are allocated in increasing order
+    if (close_range(fds[1], fds[NUM_FDS - 1], 0) < 0) {
+        THROW_ERROR("failed to close the range");
+    }
+    if (!is_open(fds[0])) {
+        THROW_ERROR("the fd out of the range should not be closed");
+    }
+    for (int i = 1; i < NUM_FDS; i++) {
+        if (is_open(fds[i]) || errno != EBADF) {
+            THROW_ERROR("the fd in the range should be closed");
+        }
+    }
+    close(fds[0]);
+    return 0;
+}
+
+static int test_close_range_to_max() {
+    int fds[NUM_FDS];
+    if (open_files(fds) < 0) {
+        return -1;
+    }
+    // Closing the fds beyond the fd table is fine
+    if (close_range(fds[0], ~0U, 0) < 0) {
+        THROW_ERROR("failed to close the range");
+    }
+    for (int i = 0; i < NUM_FDS; i++) {
+        if (is_open(fds[i])) {
+            THROW_ERROR("the fd in the range should be closed");
+        }
+    }
+    if (!is_open(STDOUT_FILENO)) {
+        THROW_ERROR("the stdout should not be closed");
+    }
+    return 0;
+}
+
+static int test_close_range_cloexec() {
+    int fds[NUM_FDS];
+    if (open_files(fds) < 0) {
+        return -1;
+    }
+    if (close_range(fds[0], fds[NUM_FDS - 1], CLOSE_RANGE_CLOEXEC) < 0) {
+        THROW_ERROR("failed to set the range to be close-on-exec");
+    }
+    for (int i = 0; i < NUM_FDS; i++) {
+        int fd_flags = fcntl(fds[i], F_GETFD);
+        if (fd_flags < 0) {
+            THROW_ERROR("the fd should not be closed");
+        }
+        if (!(fd_flags & FD_CLOEXEC)) {
+            THROW_ERROR("the fd should be close-on-exec");
+        }
+        close(fds[i]);
+    }
+    return 0;
+}
+
+static int test_close_range_with_invalid_args() {
+    if (close_range(10, 9, 0) == 0 || errno != EINVAL) {
+        THROW_ERROR("close_range with first > last should fail");
+    }
+    if (close_range(10, 20, 1U << 0) == 0 || errno != EINVAL) {
+        THROW_ERROR("close_range with invalid flags should fail");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_close_range),
+    TEST_CASE(test_close_range_to_max),
+    TEST_CASE(test_close_range_cloexec),
+    TEST_CASE(test_close_range_with_invalid_args),
+};
+
+int main(int argc, const char *argv[]) {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,148 @@
+#define _GNU_SOURCE
+#include <sys/stat.h>
+#include <sys/syscall.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+#ifndef __NR_copy_file_range
+#define __NR_copy_file_range 326
+#endif
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+static const char *in_path = "/root/test_copy_file_range_in.txt";
+static const char *out_path = "/root/test_copy_file_range_out.txt";
+static const char *msg = "Hello, copy_file_range!";
+
+static ssize_t copy_range(int fd_in, off_t *off_in, int fd_out, off_t *off_out, size_t len,
+                          unsigned int flags) {
+    return syscall(__NR_copy_file_range, fd_in, off_in, fd_out, off_out, len, flags);
+}
+
+static int open_files(int *in_fd, int *out_fd) {
+    int fd = open(in_path, O_WRONLY | O_CREAT | O_TRUNC, 00666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create the input file");
+    }
+    if (write(fd, msg, strlen(msg)) != strlen(msg)) {
+        close(fd);
+        THROW_ERROR("failed to write the input file");
+    }
+    close(fd);
+
+    *in_fd = open(in_path, O_RDONLY);
+    *out_fd = open(out_path, O_RDWR | O_CREAT | O_TRUNC, 00666);
+    if (*in_fd < 0 || *out_fd < 0) {
+        THROW_ERROR("failed to open files");
+    }
+    return 0;
+}
+
+static int close_and_remove_files(int in_fd, int out_fd) {
+    close_files(2, in_fd, out_fd);
+    if (unlink(in_path) < 0 || unlink(out_path) < 0) {
+        THROW_ERROR("failed to remove files");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+int test_copy_with_file_offsets() {
+    int in_fd, out_fd;
+    if (open_files(&in_fd, &out_fd) < 0) {
+        return -1;
+    }
+
+    size_t len = strlen(msg);
+    // Copy more than the length of the input file
+    if (copy_range(in_fd, NULL, out_fd, NULL, len + 100, 0) != len) {
+        THROW_ERROR("failed to copy the file range");
+    }
+    if (lseek(in_fd, 0, SEEK_CUR) != len || lseek(out_fd, 0, SEEK_CUR) != len) {
+        THROW_ERROR("the file offsets are not updated");
+    }
+
+    char buf[64] = {0};
+    if (pread(out_fd, buf, sizeof(buf), 0) != len || strcmp(buf, msg) != 0) {
+        THROW_ERROR("the content of the output file is wrong");
+    }
+
+    return close_and_remove_files(in_fd, out_fd);
+}
+
+int test_copy_with_given_offsets() {
+    int in_fd, out_fd;
+    if (open_files(&in_fd, &out_fd) < 0) {
+        return -1;
+    }
+
+    off_t in_offset = 7;
+    off_t out_offset = 3;
+    if (copy_range(in_fd, &in_offset, out_fd, &out_offset, 5, 0) != 5) {
+        THROW_ERROR("failed to copy the file range");
+    }
+    if (in_offset != 12 || out_offset != 8) {
+        THROW_ERROR("the given offsets are not updated");
+    }
+    if (lseek(in_fd, 0, SEEK_CUR) != 0 || lseek(out_fd, 0, SEEK_CUR) != 0) {
+        THROW_ERROR("the file offsets should not be changed");
+    }
+
+    char buf[64] = {0};
+    if (pread(out_fd, buf, sizeof(buf), 3) != 5 || strncmp(buf, msg + 7, 5) != 0) {
+        THROW_ERROR("the content of the output file is wrong");
+    }
+
+    return close_and_remove_files(in_fd, out_fd);
+}
+
+int test_copy_with_invalid_args() {
+    int in_fd, out_fd;
+    if (open_files(&in_fd, &out_fd) < 0) {
+        return -1;
+    }
+
+    if (copy_range(in_fd, NULL, out_fd, NULL, 1, 1) >= 0 || errno != EINVAL) {
+        THROW_ERROR("non-zero flags should be rejected");
+    }
+    if (copy_range(out_fd, NULL, in_fd, NULL, 1, 0) >= 0 || errno != EBADF) {
+        THROW_ERROR("copying to a read-only file should fail");
+    }
+    off_t in_offset = 0;
+    off_t out_offset = 2;
+    if (copy_range(out_fd, &in_offset, out_fd, &out_offset, 5, 0) >= 0 || errno != EINVAL) {
+        THROW_ERROR("copying overlapping ranges of the same file should fail");
+    }
+    int dir_fd = open("/root", O_RDONLY | O_DIRECTORY);
+    if (copy_range(dir_fd, NULL, out_fd, NULL, 1, 0) >= 0 || errno != EISDIR) {
+        close(dir_fd);
+        THROW_ERROR("copying from a directory should fail");
+    }
+    close(dir_fd);
+
+    return close_and_remove_files(in_fd, out_fd);
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_copy_with_file_offsets),
+    TEST_CASE(test_copy_with_given_offsets),
+    TEST_CASE(test_copy_with_invalid_args),
+};
+
+int main() {
+    int ret = test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+    return ret;
+}
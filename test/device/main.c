@@ -4,6 +4,8 @@
 #include <poll.h>
 #include <unistd.h>
 #include <stdio.h>
+#include <string.h>
+#include <limits.h>
 #include "test.h"
 
 // ============================================================================
@@ -115,6 +117,84 @@ int test_dev_arandom() {
     return 0;
 }
 
+int test_dev_tty() {
+    int fd = open("/dev/tty", O_RDWR);
+    if (fd < 0) {
+        THROW_ERROR("failed to open /dev/tty");
+    }
+    struct stat stat_buf;
+    if (fstat(fd, &stat_buf) < 0 || !S_ISCHR(stat_buf.st_mode)) {
+        close(fd);
+        THROW_ERROR("/dev/tty should be a character device");
+    }
+    close(fd);
+    return 0;
+}
+
+int test_dev_stdio_links() {
+    const char *links[][2] = {
+        { "/dev/fd", "/proc/self/fd" },
+        { "/dev/stdin", "/proc/self/fd/0" },
+        { "/dev/stdout", "/proc/self/fd/1" },
+        { "/dev/stderr", "/proc/self/fd/2" },
+    };
+    for (int i = 0; i < ARRAY_SIZE(links); i++) {
+        char buf[PATH_MAX] = { 0 };
+        if (readlink(links[i][0], buf, sizeof(buf) - 1) < 0) {
+            THROW_ERROR("failed to readlink %s", links[i][0]);
+        }
+        if (strcmp(buf, links[i][1]) != 0) {
+            THROW_ERROR("the target of %s is wrong", links[i][0]);
+        }
+    }
+    return 0;
+}
+
+int test_dev_stdin_redirect() {
+    const char *msg = "from the pipe";
+    char buf[32] = { 0 };
+    int pipe_fds[2];
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+    if (write(pipe_fds[1], msg, strlen(msg)) != strlen(msg)) {
+        THROW_ERROR("failed to write the pipe");
+    }
+
+    // Opening /dev/stdin refers to the file that the stdin is redirected to
+    int saved_stdin = dup(STDIN_FILENO);
+    if (saved_stdin < 0 || dup2(pipe_fds[0], STDIN_FILENO) < 0) {
+        THROW_ERROR("failed to redirect the stdin");
+    }
+    int fd = open("/dev/stdin", O_RDONLY);
+    dup2(saved_stdin, STDIN_FILENO);
+    close(saved_stdin);
+    if (fd < 0) {
+        THROW_ERROR("failed to open /dev/stdin");
+    }
+    if (read(fd, buf, sizeof(buf)) != strlen(msg) || strcmp(buf, msg) != 0) {
+        THROW_ERROR("failed to read from /dev/stdin");
+    }
+    close(fd);
+
+    // Opening /dev/fd/N refers to the file of the fd
+    char fd_path[32];
+    snprintf(fd_path, sizeof(fd_path), "/dev/fd/%d", pipe_fds[1]);
+    fd = open(fd_path, O_WRONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open %s", fd_path);
+    }
+    memset(buf, 0, sizeof(buf));
+    if (write(fd, msg, strlen(msg)) != strlen(msg) ||
+            read(pipe_fds[0], buf, sizeof(buf)) != strlen(msg) || strcmp(buf, msg) != 0) {
+        THROW_ERROR("failed to write through %s", fd_path);
+    }
+    close(fd);
+    close(pipe_fds[0]);
+    close(pipe_fds[1]);
+    return 0;
+}
+
 // ============================================================================
 // Test suite
 // ============================================================================
@@ -127,6 +207,9 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_dev_urandom_fstat),
     TEST_CASE(test_dev_urandom_poll),
     TEST_CASE(test_dev_arandom),
+    TEST_CASE(test_dev_tty),
+    TEST_CASE(test_dev_stdio_links),
+    TEST_CASE(test_dev_stdin_redirect),
 };
 
 int main() {
@@ -0,0 +1,525 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <netdb.h>
+#include <pthread.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include <arpa/inet.h>
+#include <netinet/in.h>
+#include <sys/socket.h>
+
+#include "test.h"
+
+// The upstream server of the resolver in test/Occlum.json, which is served by
+// this test
+#define UPSTREAM_PORT           5353
+// The virtual address of the resolver of LibOS
+#define RESOLVER_ADDR           "127.0.0.53"
+#define RESOLVER_PORT           53
+
+#define HEADER_SIZE             12
+#define FLAG_TC                 0x0200
+#define RCODE_MASK              0x000f
+#define RCODE_FORMERR           1
+#define RCODE_NXDOMAIN          3
+#define TYPE_A                  1
+#define CLASS_IN                1
+
+// The names served by the upstream server
+#define NAME                    "www.occlum.test"
+#define BIG_NAME                "big.occlum.test"
+#define NUM_BIG_ANSWERS         40
+#define SHORT_TTL_NAME          "ttl.occlum.test"
+#define SHORT_TTL               2
+#define EVICT_NAME_SUFFIX       ".evict.occlum.test"
+#define DEFAULT_TTL             300
+
+// The max number of the cached replies of the resolver
+#define MAX_CACHE_ENTRIES       1024
+
+#define MAX_MSG_SIZE            4096
+
+static volatile int num_upstream_queries;
+
+// ============================================================================
+// The upstream server
+// ============================================================================
+
+static uint16_t read_u16(const uint8_t *msg, int offset) {
+    return (uint16_t)(msg[offset] << 8 | msg[offset + 1]);
+}
+
+static void write_u16(uint8_t *msg, int offset, uint16_t val) {
+    msg[offset] = val >> 8;
+    msg[offset + 1] = val & 0xff;
+}
+
+static void write_u32(uint8_t *msg, int offset, uint32_t val) {
+    write_u16(msg, offset, val >> 16);
+    write_u16(msg, offset + 2, val & 0xffff);
+}
+
+static uint32_t read_u32(const uint8_t *msg, int offset) {
+    return (uint32_t)read_u16(msg, offset) << 16 | read_u16(msg, offset + 2);
+}
+
+// Decode the name of the question, returning the offset of the end of the
+// question, or -1 if the question is malformed
+static int parse_question(const uint8_t *msg, int msg_len, char *name, size_t name_size) {
+    int offset = HEADER_SIZE;
+    size_t name_len = 0;
+    while (offset < msg_len && msg[offset] != 0) {
+        int label_len = msg[offset];
+        if (offset + 1 + label_len > msg_len || name_len + label_len + 1 >= name_size) {
+            return -1;
+        }
+        if (name_len > 0) {
+            name[name_len++] = '.';
+        }
+        memcpy(name + name_len, msg + offset + 1, label_len);
+        name_len += label_len;
+        offset += 1 + label_len;
+    }
+    name[name_len] = '\0';
+    offset += 5;
+    return offset <= msg_len ? offset : -1;
+}
+
+static int ends_with(const char *str, const char *suffix) {
+    size_t str_len = strlen(str), suffix_len = strlen(suffix);
+    return str_len >= suffix_len && strcmp(str + str_len - suffix_len, suffix) == 0;
+}
+
+// Answer the A queries of the served names, whose addresses are 10.0.x.y
+static int build_reply(const uint8_t *query, int query_len, uint8_t *reply) {
+    char name[256];
+    int question_end = parse_question(query, query_len, name, sizeof(name));
+    if (question_end < 0) {
+        return -1;
+    }
+    uint16_t qtype = read_u16(query, question_end - 4);
+
+    int num_answers = 1;
+    uint32_t ttl = DEFAULT_TTL;
+    uint32_t addr = 0x0a000001;
+    if (strcmp(name, BIG_NAME) == 0) {
+        num_answers = NUM_BIG_ANSWERS;
+        addr = 0x0a000100;
+    } else if (strcmp(name, SHORT_TTL_NAME) == 0) {
+        ttl = SHORT_TTL;
+        addr = 0x0a000002;
+    } else if (ends_with(name, EVICT_NAME_SUFFIX)) {
+        addr = 0x0a000201;
+    } else if (strcmp(name, NAME) != 0) {
+        num_answers = -1;
+    }
+
+    memcpy(reply, query, question_end);
+    uint16_t flags = 0x8180 | (num_answers < 0 ? RCODE_NXDOMAIN : 0);
+    write_u16(reply, 2, flags);
+    if (num_answers < 0 || qtype != TYPE_A) {
+        num_answers = 0;
+    }
+    write_u16(reply, 6, num_answers);
+    write_u16(reply, 8, 0);
+    write_u16(reply, 10, 0);
+
+    int offset = question_end;
+    for (int i = 0; i < num_answers; i++) {
+        // The name is a pointer to that of the question
+        write_u16(reply, offset, 0xc000 | HEADER_SIZE);
+        write_u16(reply, offset + 2, TYPE_A);
+        write_u16(reply, offset + 4, CLASS_IN);
+        write_u32(reply, offset + 6, ttl);
+        write_u16(reply, offset + 10, 4);
+        write_u32(reply, offset + 12, addr + i);
+        offset += 16;
+    }
+    return offset;
+}
+
+static void *upstream_server(void *arg) {
+    int fd = *(int *)arg;
+    uint8_t query[MAX_MSG_SIZE], reply[MAX_MSG_SIZE];
+    while (1) {
+        struct sockaddr_in src;
+        socklen_t src_len = sizeof(src);
+        int query_len = recvfrom(fd, query, sizeof(query), 0, (struct sockaddr *)&src,
+                                 &src_len);
+        if (query_len < HEADER_SIZE) {
+            continue;
+        }
+        __sync_fetch_and_add(&num_upstream_queries, 1);
+        int reply_len = build_reply(query, query_len, reply);
+        if (reply_len > 0) {
+            sendto(fd, reply, reply_len, 0, (struct sockaddr *)&src, src_len);
+        }
+    }
+    return NULL;
+}
+
+static int start_upstream_server(void) {
+    static int fd;
+    fd = socket(AF_INET, SOCK_DGRAM, 0);
+    if (fd < 0) {
+        THROW_ERROR("failed to create the socket of the upstream server");
+    }
+    struct sockaddr_in addr = {
+        .sin_family = AF_INET,
+        .sin_port = htons(UPSTREAM_PORT),
+        .sin_addr.s_addr = htonl(INADDR_LOOPBACK),
+    };
+    if (bind(fd, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        close(fd);
+        THROW_ERROR("failed to bind the socket of the upstream server");
+    }
+    pthread_t thread;
+    if (pthread_create(&thread, NULL, upstream_server, &fd) != 0) {
+        close(fd);
+        THROW_ERROR("failed to create the thread of the upstream server");
+    }
+    pthread_detach(thread);
+    return 0;
+}
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+static int build_query(uint8_t *query, uint16_t id, const char *name) {
+    memset(query, 0, HEADER_SIZE);
+    write_u16(query, 0, id);
+    // Recursion desired
+    write_u16(query, 2, 0x0100);
+    write_u16(query, 4, 1);
+
+    int offset = HEADER_SIZE;
+    const char *label = name;
+    while (*label != '\0') {
+        const char *dot = strchr(label, '.');
+        int label_len = dot ? dot - label : strlen(label);
+        query[offset] = label_len;
+        memcpy(query + offset + 1, label, label_len);
+        offset += 1 + label_len;
+        label += label_len + (dot ? 1 : 0);
+    }
+    query[offset++] = 0;
+    write_u16(query, offset, TYPE_A);
+    write_u16(query, offset + 2, CLASS_IN);
+    return offset + 4;
+}
+
+static int connect_to_resolver(int type) {
+    int fd = socket(AF_INET, type, 0);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a socket");
+    }
+    struct sockaddr_in addr = {
+        .sin_family = AF_INET,
+        .sin_port = htons(RESOLVER_PORT),
+    };
+    inet_pton(AF_INET, RESOLVER_ADDR, &addr.sin_addr);
+    if (connect(fd, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        close(fd);
+        THROW_ERROR("failed to connect to the resolver");
+    }
+    return fd;
+}
+
+// Send the query over UDP, returning the length of the reply. The replies
+// are queued as soon as the queries are sent.
+static int query_over_udp(const uint8_t *query, int query_len, uint8_t *reply) {
+    int fd = connect_to_resolver(SOCK_DGRAM);
+    if (fd < 0) {
+        return -1;
+    }
+    if (send(fd, query, query_len, 0) != query_len) {
+        close(fd);
+        THROW_ERROR("failed to send the query");
+    }
+    int reply_len = recv(fd, reply, MAX_MSG_SIZE, MSG_DONTWAIT);
+    close(fd);
+    return reply_len;
+}
+
+static int resolve_over_udp(const char *name, uint8_t *reply) {
+    uint8_t query[MAX_MSG_SIZE];
+    int query_len = build_query(query, 0x1234, name);
+    int reply_len = query_over_udp(query, query_len, reply);
+    if (reply_len < HEADER_SIZE || read_u16(reply, 0) != 0x1234) {
+        THROW_ERROR("failed to receive the reply of %s", name);
+    }
+    return reply_len;
+}
+
+static int recv_exact(int fd, uint8_t *buf, int len) {
+    int bytes_recvd = 0;
+    while (bytes_recvd < len) {
+        int ret = recv(fd, buf + bytes_recvd, len - bytes_recvd, 0);
+        if (ret <= 0) {
+            THROW_ERROR("failed to receive over TCP");
+        }
+        bytes_recvd += ret;
+    }
+    return 0;
+}
+
+// The TTL of the first answer of the reply
+static uint32_t first_ttl(const uint8_t *reply, int reply_len) {
+    char name[256];
+    int question_end = parse_question(reply, reply_len, name, sizeof(name));
+    return read_u32(reply, question_end + 6);
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+int test_getaddrinfo() {
+    struct addrinfo hints = { .ai_family = AF_INET, .ai_socktype = SOCK_DGRAM };
+    struct addrinfo *result;
+    int ret = getaddrinfo(NAME, NULL, &hints, &result);
+    if (ret != 0) {
+        THROW_ERROR("failed to resolve %s: %s", NAME, gai_strerror(ret));
+    }
+    struct sockaddr_in *addr = (struct sockaddr_in *)result->ai_addr;
+    if (ntohl(addr->sin_addr.s_addr) != 0x0a000001) {
+        freeaddrinfo(result);
+        THROW_ERROR("the address of %s is wrong", NAME);
+    }
+    freeaddrinfo(result);
+    return 0;
+}
+
+int test_getaddrinfo_from_cache() {
+    int num_queries = num_upstream_queries;
+    if (test_getaddrinfo() < 0) {
+        return -1;
+    }
+    if (num_upstream_queries != num_queries) {
+        THROW_ERROR("the cached reply is not used");
+    }
+    return 0;
+}
+
+int test_getaddrinfo_nxdomain() {
+    struct addrinfo hints = { .ai_family = AF_INET, .ai_socktype = SOCK_DGRAM };
+    struct addrinfo *result;
+    int ret = getaddrinfo("none.occlum.test", NULL, &hints, &result);
+    if (ret != EAI_NONAME) {
+        if (ret == 0) {
+            freeaddrinfo(result);
+        }
+        THROW_ERROR("an unknown name should not be resolved");
+    }
+    return 0;
+}
+
+int test_malformed_queries() {
+    uint8_t query[MAX_MSG_SIZE], reply[MAX_MSG_SIZE];
+
+    // A query shorter than the header is not replied
+    memset(query, 0, HEADER_SIZE);
+    if (query_over_udp(query, HEADER_SIZE - 1, reply) != -1 || errno != EAGAIN) {
+        THROW_ERROR("a too short query should not be replied");
+    }
+
+    // A query of more than one question is replied with FORMERR
+    int query_len = build_query(query, 0x4321, NAME);
+    write_u16(query, 4, 2);
+    int reply_len = query_over_udp(query, query_len, reply);
+    if (reply_len < HEADER_SIZE || read_u16(reply, 0) != 0x4321 ||
+            (read_u16(reply, 2) & RCODE_MASK) != RCODE_FORMERR) {
+        THROW_ERROR("a query of two questions should be replied with FORMERR");
+    }
+
+    // A query whose name is truncated is replied with FORMERR
+    build_query(query, 0x4321, NAME);
+    reply_len = query_over_udp(query, HEADER_SIZE + 3, reply);
+    if (reply_len < HEADER_SIZE || (read_u16(reply, 2) & RCODE_MASK) != RCODE_FORMERR) {
+        THROW_ERROR("a query of a truncated name should be replied with FORMERR");
+    }
+    return 0;
+}
+
+int test_truncated_reply_over_udp() {
+    uint8_t reply[MAX_MSG_SIZE];
+    int reply_len = resolve_over_udp(BIG_NAME, reply);
+    if (reply_len < 0) {
+        return -1;
+    }
+    // The reply that does not fit in 512 bytes only has the question
+    if (!(read_u16(reply, 2) & FLAG_TC) || read_u16(reply, 6) != 0 || reply_len > 512) {
+        THROW_ERROR("the reply of %s should be truncated", BIG_NAME);
+    }
+    return 0;
+}
+
+int test_query_over_tcp() {
+    int fd = connect_to_resolver(SOCK_STREAM);
+    if (fd < 0) {
+        return -1;
+    }
+
+    // The length prefix and the query are sent separately
+    uint8_t query[MAX_MSG_SIZE], reply[MAX_MSG_SIZE];
+    int query_len = build_query(query, 0x5678, BIG_NAME);
+    uint8_t len_buf[2];
+    write_u16(len_buf, 0, query_len);
+    if (send(fd, len_buf, 2, 0) != 2 || send(fd, query, query_len, 0) != query_len) {
+        close(fd);
+        THROW_ERROR("failed to send the query over TCP");
+    }
+
+    // The reply is never truncated over TCP
+    if (recv_exact(fd, len_buf, 2) < 0) {
+        close(fd);
+        return -1;
+    }
+    int reply_len = read_u16(len_buf, 0);
+    if (reply_len > MAX_MSG_SIZE || recv_exact(fd, reply, reply_len) < 0) {
+        close(fd);
+        THROW_ERROR("failed to receive the reply over TCP");
+    }
+    close(fd);
+    if (read_u16(reply, 0) != 0x5678 || (read_u16(reply, 2) & FLAG_TC) ||
+            read_u16(reply, 6) != NUM_BIG_ANSWERS) {
+        THROW_ERROR("the reply of %s over TCP is wrong", BIG_NAME);
+    }
+    return 0;
+}
+
+int test_dup_before_connect() {
+    int fd = socket(AF_INET, SOCK_DGRAM, 0);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a socket");
+    }
+    int dup_fd = dup(fd);
+    if (dup_fd < 0) {
+        close(fd);
+        THROW_ERROR("failed to dup the socket");
+    }
+    struct sockaddr_in addr = {
+        .sin_family = AF_INET,
+        .sin_port = htons(RESOLVER_PORT),
+    };
+    inet_pton(AF_INET, RESOLVER_ADDR, &addr.sin_addr);
+    if (connect(fd, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        close_files(2, fd, dup_fd);
+        THROW_ERROR("failed to connect to the resolver");
+    }
+
+    // The dup'd fd also talks to the resolver
+    uint8_t query[MAX_MSG_SIZE], reply[MAX_MSG_SIZE];
+    int query_len = build_query(query, 0x2468, NAME);
+    if (send(dup_fd, query, query_len, 0) != query_len) {
+        close_files(2, fd, dup_fd);
+        THROW_ERROR("failed to send the query by the dup'd fd");
+    }
+    int reply_len = recv(fd, reply, sizeof(reply), MSG_DONTWAIT);
+    close_files(2, fd, dup_fd);
+    if (reply_len < HEADER_SIZE || read_u16(reply, 0) != 0x2468 || read_u16(reply, 6) != 1) {
+        THROW_ERROR("the query sent by the dup'd fd is not answered by the resolver");
+    }
+    return 0;
+}
+
+int test_ttl_of_cached_reply() {
+    uint8_t reply[MAX_MSG_SIZE];
+    int num_queries = num_upstream_queries;
+    int reply_len = resolve_over_udp(SHORT_TTL_NAME, reply);
+    if (reply_len < 0) {
+        return -1;
+    }
+    if (num_upstream_queries != num_queries + 1 || first_ttl(reply, reply_len) != SHORT_TTL) {
+        THROW_ERROR("the reply of %s is not from the upstream server", SHORT_TTL_NAME);
+    }
+
+    // The TTL of the cached reply is decreased by the time elapsed
+    sleep(1);
+    reply_len = resolve_over_udp(SHORT_TTL_NAME, reply);
+    if (reply_len < 0) {
+        return -1;
+    }
+    if (num_upstream_queries != num_queries + 1 || first_ttl(reply, reply_len) >= SHORT_TTL) {
+        THROW_ERROR("the reply of %s is not from the cache", SHORT_TTL_NAME);
+    }
+
+    // The expired reply is evicted
+    sleep(SHORT_TTL);
+    if (resolve_over_udp(SHORT_TTL_NAME, reply) < 0) {
+        return -1;
+    }
+    if (num_upstream_queries != num_queries + 2) {
+        THROW_ERROR("the expired reply of %s is used", SHORT_TTL_NAME);
+    }
+    return 0;
+}
+
+#define NUM_EVICT_NAMES         (MAX_CACHE_ENTRIES + 100)
+
+static int resolve_evict_names(void) {
+    uint8_t reply[MAX_MSG_SIZE];
+    for (int i = 0; i < NUM_EVICT_NAMES; i++) {
+        char name[64];
+        snprintf(name, sizeof(name), "e%d" EVICT_NAME_SUFFIX, i);
+        if (resolve_over_udp(name, reply) < 0) {
+            return -1;
+        }
+        if ((read_u16(reply, 2) & RCODE_MASK) != 0 || read_u16(reply, 6) != 1) {
+            THROW_ERROR("the reply of %s is wrong", name);
+        }
+    }
+    return 0;
+}
+
+int test_cache_eviction() {
+    int num_queries = num_upstream_queries;
+    if (resolve_evict_names() < 0) {
+        return -1;
+    }
+    if (num_upstream_queries - num_queries != NUM_EVICT_NAMES) {
+        THROW_ERROR("the names should be resolved by the upstream server");
+    }
+
+    // The cache cannot hold all the replies, so some of them are evicted and
+    // resolved by the upstream server again
+    num_queries = num_upstream_queries;
+    if (resolve_evict_names() < 0) {
+        return -1;
+    }
+    int num_evicted = num_upstream_queries - num_queries;
+    if (num_evicted < NUM_EVICT_NAMES - MAX_CACHE_ENTRIES) {
+        THROW_ERROR("the cache holds more than %d replies", MAX_CACHE_ENTRIES);
+    }
+    if (num_evicted == NUM_EVICT_NAMES) {
+        THROW_ERROR("no replies are cached");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_getaddrinfo),
+    TEST_CASE(test_getaddrinfo_from_cache),
+    TEST_CASE(test_getaddrinfo_nxdomain),
+    TEST_CASE(test_malformed_queries),
+    TEST_CASE(test_truncated_reply_over_udp),
+    TEST_CASE(test_query_over_tcp),
+    TEST_CASE(test_dup_before_connect),
+    TEST_CASE(test_ttl_of_cached_reply),
+    TEST_CASE(test_cache_eviction),
+};
+
+int main() {
+    if (start_upstream_server() < 0) {
+        return -1;
+    }
+    int ret = test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+    return ret;
+}
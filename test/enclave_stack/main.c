@@ -0,0 +1,102 @@
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+#include <errno.h>
+#include <unistd.h>
+#include <stdio.h>
+
+#include "test.h"
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+// The IPv4 TCP and UDP sockets are provided by either the host or the
+// in-enclave stack, depending on the config, which behave the same here
+
+int test_protocol_mismatch() {
+    int sock = socket(AF_INET, SOCK_STREAM, IPPROTO_UDP);
+    if (sock >= 0) {
+        close(sock);
+        THROW_ERROR("creating a TCP socket of UDP should fail");
+    }
+    if (errno != EPROTONOSUPPORT) {
+        THROW_ERROR("errno is wrong");
+    }
+    return 0;
+}
+
+int test_socket_type() {
+    int types[] = {SOCK_STREAM, SOCK_DGRAM};
+    for (int i = 0; i < ARRAY_SIZE(types); i++) {
+        int sock = socket(AF_INET, types[i], 0);
+        if (sock < 0) {
+            THROW_ERROR("failed to create a socket");
+        }
+
+        int type = 0;
+        socklen_t len = sizeof(type);
+        int ret = getsockopt(sock, SOL_SOCKET, SO_TYPE, &type, &len);
+        close(sock);
+        if (ret < 0 || len != sizeof(type) || type != types[i]) {
+            THROW_ERROR("the socket type is wrong");
+        }
+    }
+    return 0;
+}
+
+int test_bind_ephemeral_port() {
+    int sock = socket(AF_INET, SOCK_DGRAM, 0);
+    if (sock < 0) {
+        THROW_ERROR("failed to create a socket");
+    }
+
+    struct sockaddr_in addr = {
+        .sin_family = AF_INET,
+        .sin_port = 0,
+        .sin_addr.s_addr = htonl(INADDR_ANY),
+    };
+    if (bind(sock, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        close(sock);
+        THROW_ERROR("failed to bind the socket");
+    }
+
+    socklen_t len = sizeof(addr);
+    int ret = getsockname(sock, (struct sockaddr *)&addr, &len);
+    close(sock);
+    if (ret < 0 || len != sizeof(addr) || addr.sin_port == 0) {
+        THROW_ERROR("the socket is not bound to an ephemeral port");
+    }
+    return 0;
+}
+
+int test_unconnected_peer() {
+    int sock = socket(AF_INET, SOCK_STREAM, 0);
+    if (sock < 0) {
+        THROW_ERROR("failed to create a socket");
+    }
+
+    struct sockaddr_in addr;
+    socklen_t len = sizeof(addr);
+    int ret = getpeername(sock, (struct sockaddr *)&addr, &len);
+    close(sock);
+    if (ret == 0 || errno != ENOTCONN) {
+        THROW_ERROR("getting the peer of an unconnected socket should fail");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_protocol_mismatch),
+    TEST_CASE(test_socket_type),
+    TEST_CASE(test_bind_ephemeral_port),
+    TEST_CASE(test_unconnected_peer),
+};
+
+int main() {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
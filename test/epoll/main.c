@@ -0,0 +1,298 @@
+#include <sys/epoll.h>
+#include <sys/eventfd.h>
+#include <errno.h>
+#include <pthread.h>
+#include <unistd.h>
+#include <stdio.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+static int pipe_fds[2];
+static int ep_fd;
+
+static int setup_epoll(uint32_t events) {
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+    ep_fd = epoll_create1(0);
+    if (ep_fd < 0) {
+        close_files(2, pipe_fds[0], pipe_fds[1]);
+        THROW_ERROR("failed to create an epoll file");
+    }
+
+    struct epoll_event event = { .events = events, .data.fd = pipe_fds[0] };
+    if (epoll_ctl(ep_fd, EPOLL_CTL_ADD, pipe_fds[0], &event) < 0) {
+        close_files(3, pipe_fds[0], pipe_fds[1], ep_fd);
+        THROW_ERROR("failed to add the pipe to the epoll file");
+    }
+    return 0;
+}
+
+static void teardown_epoll(void) {
+    close_files(3, pipe_fds[0], pipe_fds[1], ep_fd);
+}
+
+static int write_one_byte(void) {
+    char c = 'a';
+    if (write(pipe_fds[1], &c, 1) != 1) {
+        THROW_ERROR("failed to write the pipe");
+    }
+    return 0;
+}
+
+static int epoll_wait_nonblock(void) {
+    struct epoll_event event;
+    return epoll_wait(ep_fd, &event, 1, 0);
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+int test_level_trigger() {
+    if (setup_epoll(EPOLLIN) < 0 || write_one_byte() < 0) {
+        return -1;
+    }
+
+    // Level-triggered entries are reported as long as the file is ready
+    for (int i = 0; i < 2; i++) {
+        if (epoll_wait_nonblock() != 1) {
+            teardown_epoll();
+            THROW_ERROR("level-triggered event is not reported");
+        }
+    }
+
+    teardown_epoll();
+    return 0;
+}
+
+int test_edge_trigger() {
+    if (setup_epoll(EPOLLIN | EPOLLET) < 0 || write_one_byte() < 0) {
+        return -1;
+    }
+
+    if (epoll_wait_nonblock() != 1) {
+        teardown_epoll();
+        THROW_ERROR("edge-triggered event is not reported");
+    }
+    // No new event happens, so nothing should be reported
+    if (epoll_wait_nonblock() != 0) {
+        teardown_epoll();
+        THROW_ERROR("edge-triggered event is reported twice");
+    }
+    // A new edge
+    if (write_one_byte() < 0 || epoll_wait_nonblock() != 1) {
+        teardown_epoll();
+        THROW_ERROR("new edge-triggered event is not reported");
+    }
+
+    teardown_epoll();
+    return 0;
+}
+
+int test_one_shot() {
+    if (setup_epoll(EPOLLIN | EPOLLONESHOT) < 0 || write_one_byte() < 0) {
+        return -1;
+    }
+
+    if (epoll_wait_nonblock() != 1) {
+        teardown_epoll();
+        THROW_ERROR("one-shot event is not reported");
+    }
+    // The entry is disabled until being re-armed
+    if (write_one_byte() < 0 || epoll_wait_nonblock() != 0) {
+        teardown_epoll();
+        THROW_ERROR("disabled one-shot entry is reported");
+    }
+
+    struct epoll_event event = { .events = EPOLLIN | EPOLLONESHOT, .data.fd = pipe_fds[0] };
+    if (epoll_ctl(ep_fd, EPOLL_CTL_MOD, pipe_fds[0], &event) < 0) {
+        teardown_epoll();
+        THROW_ERROR("failed to re-arm the one-shot entry");
+    }
+    if (epoll_wait_nonblock() != 1) {
+        teardown_epoll();
+        THROW_ERROR("re-armed one-shot event is not reported");
+    }
+
+    teardown_epoll();
+    return 0;
+}
+
+int test_exclusive() {
+    if (setup_epoll(EPOLLIN | EPOLLEXCLUSIVE) < 0 || write_one_byte() < 0) {
+        return -1;
+    }
+
+    if (epoll_wait_nonblock() != 1) {
+        teardown_epoll();
+        THROW_ERROR("exclusive event is not reported");
+    }
+
+    // An exclusive entry cannot be modified
+    struct epoll_event event = { .events = EPOLLIN, .data.fd = pipe_fds[0] };
+    if (epoll_ctl(ep_fd, EPOLL_CTL_MOD, pipe_fds[0], &event) == 0 || errno != EINVAL) {
+        teardown_epoll();
+        THROW_ERROR("modifying an exclusive entry should fail");
+    }
+
+    teardown_epoll();
+    return 0;
+}
+
+int test_exclusive_with_invalid_flags() {
+    int fds[2];
+    if (pipe(fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+    int epfd = epoll_create1(0);
+    if (epfd < 0) {
+        close_files(2, fds[0], fds[1]);
+        THROW_ERROR("failed to create an epoll file");
+    }
+
+    struct epoll_event event = { .events = EPOLLIN | EPOLLEXCLUSIVE | EPOLLONESHOT };
+    if (epoll_ctl(epfd, EPOLL_CTL_ADD, fds[0], &event) == 0 || errno != EINVAL) {
+        close_files(3, fds[0], fds[1], epfd);
+        THROW_ERROR("EPOLLEXCLUSIVE with EPOLLONESHOT should fail");
+    }
+
+    close_files(3, fds[0], fds[1], epfd);
+    return 0;
+}
+
+#define NUM_EXCLUSIVE_WAITERS 4
+
+static volatile int num_started_waiters;
+
+static void *exclusive_waiter(void *arg) {
+    int epfd = *(int *)arg;
+    struct epoll_event event;
+    __sync_fetch_and_add(&num_started_waiters, 1);
+    // The waiters that are not woken up time out without any events
+    long ret = epoll_wait(epfd, &event, 1, 1000);
+    return (void *)ret;
+}
+
+int test_exclusive_across_epoll_files() {
+    int epfds[NUM_EXCLUSIVE_WAITERS];
+    pthread_t threads[NUM_EXCLUSIVE_WAITERS];
+    int ret = -1;
+
+    int exclusive_event_fd = eventfd(0, 0);
+    if (exclusive_event_fd < 0) {
+        THROW_ERROR("failed to create an eventfd");
+    }
+    num_started_waiters = 0;
+    for (int i = 0; i < NUM_EXCLUSIVE_WAITERS; i++) {
+        epfds[i] = epoll_create1(0);
+        struct epoll_event event = { .events = EPOLLIN | EPOLLEXCLUSIVE };
+        if (epfds[i] < 0 ||
+                epoll_ctl(epfds[i], EPOLL_CTL_ADD, exclusive_event_fd, &event) < 0) {
+            THROW_ERROR("failed to add the eventfd to an epoll file exclusively");
+        }
+    }
+    for (int i = 0; i < NUM_EXCLUSIVE_WAITERS; i++) {
+        if (pthread_create(&threads[i], NULL, exclusive_waiter, &epfds[i]) != 0) {
+            THROW_ERROR("failed to create a thread");
+        }
+    }
+    while (num_started_waiters < NUM_EXCLUSIVE_WAITERS) {
+        usleep(1000);
+    }
+    // Give the waiters some time to block in epoll_wait
+    usleep(200 * 1000);
+
+    uint64_t val = 1;
+    if (write(exclusive_event_fd, &val, sizeof(val)) != sizeof(val)) {
+        printf("\t\tERROR: failed to write the eventfd\n");
+    }
+
+    // Only one of the epoll files that have added the eventfd exclusively
+    // is woken up
+    int num_woken_waiters = 0;
+    for (int i = 0; i < NUM_EXCLUSIVE_WAITERS; i++) {
+        void *thread_ret;
+        pthread_join(threads[i], &thread_ret);
+        if ((long)thread_ret == 1) {
+            num_woken_waiters++;
+        }
+    }
+    if (num_woken_waiters == 1) {
+        ret = 0;
+    } else {
+        printf("\t\tERROR: %d epoll files are woken up\n", num_woken_waiters);
+    }
+
+    for (int i = 0; i < NUM_EXCLUSIVE_WAITERS; i++) {
+        close(epfds[i]);
+    }
+    close(exclusive_event_fd);
+    return ret;
+}
+
+#define NUM_IDLE_PIPES 64
+
+int test_many_idle_files() {
+    int fds[NUM_IDLE_PIPES][2];
+    int epfd = epoll_create1(0);
+    if (epfd < 0) {
+        THROW_ERROR("failed to create an epoll file");
+    }
+    for (int i = 0; i < NUM_IDLE_PIPES; i++) {
+        if (pipe(fds[i]) < 0) {
+            THROW_ERROR("failed to create a pipe");
+        }
+        struct epoll_event event = { .events = EPOLLIN, .data.u32 = i };
+        if (epoll_ctl(epfd, EPOLL_CTL_ADD, fds[i][0], &event) < 0) {
+            THROW_ERROR("failed to add the pipe to the epoll file");
+        }
+    }
+
+    // Only the ready one out of all the interesting files is reported
+    int ret = -1;
+    const int ready_idx = NUM_IDLE_PIPES / 2;
+    struct epoll_event events[NUM_IDLE_PIPES];
+    if (epoll_wait(epfd, events, NUM_IDLE_PIPES, 0) != 0) {
+        printf("\t\tERROR: idle files are reported\n");
+        goto out;
+    }
+    if (write(fds[ready_idx][1], "a", 1) != 1) {
+        printf("\t\tERROR: failed to write the pipe\n");
+        goto out;
+    }
+    if (epoll_wait(epfd, events, NUM_IDLE_PIPES, -1) != 1 || events[0].data.u32 != ready_idx ||
+            events[0].events != EPOLLIN) {
+        printf("\t\tERROR: the ready file is not reported\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    for (int i = 0; i < NUM_IDLE_PIPES; i++) {
+        close_files(2, fds[i][0], fds[i][1]);
+    }
+    close(epfd);
+    return ret;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_level_trigger),
+    TEST_CASE(test_edge_trigger),
+    TEST_CASE(test_one_shot),
+    TEST_CASE(test_exclusive),
+    TEST_CASE(test_exclusive_with_invalid_flags),
+    TEST_CASE(test_exclusive_across_epoll_files),
+    TEST_CASE(test_many_idle_files),
+};
+
+int main() {
+    int ret = test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+    return ret;
+}
@@ -9,6 +9,7 @@
 #include <poll.h>
 #include <pthread.h>
 #include <stdlib.h>
+#include <stdint.h>
 #include <stdio.h>
 #include <spawn.h>
 #include <string.h>
@@ -67,6 +68,70 @@ int test_create_with_flags() {
     return 0;
 }
 
+int test_semaphore() {
+    uint64_t val = 3;
+    int event_fd = eventfd(0, EFD_SEMAPHORE | EFD_NONBLOCK);
+    if (event_fd < 0) {
+        THROW_ERROR("failed to create an eventfd");
+    }
+
+    if (write(event_fd, &val, sizeof(val)) != sizeof(val)) {
+        close(event_fd);
+        THROW_ERROR("failed to write the eventfd");
+    }
+    // Each read decreases the counter by one and returns one
+    for (int i = 0; i < 3; i++) {
+        val = 0;
+        if (read(event_fd, &val, sizeof(val)) != sizeof(val) || val != 1) {
+            close(event_fd);
+            THROW_ERROR("semaphore read failed");
+        }
+    }
+    if (read(event_fd, &val, sizeof(val)) != -1 || errno != EAGAIN) {
+        close(event_fd);
+        THROW_ERROR("read a zero counter should fail with EAGAIN");
+    }
+
+    close(event_fd);
+    return 0;
+}
+
+int test_nonblock_and_invalid_args() {
+    uint64_t val = 0;
+    int event_fd = eventfd(0, EFD_NONBLOCK);
+    if (event_fd < 0) {
+        THROW_ERROR("failed to create an eventfd");
+    }
+
+    if (read(event_fd, &val, sizeof(val)) != -1 || errno != EAGAIN) {
+        close(event_fd);
+        THROW_ERROR("read a zero counter should fail with EAGAIN");
+    }
+    if (read(event_fd, &val, sizeof(val) - 1) != -1 || errno != EINVAL) {
+        close(event_fd);
+        THROW_ERROR("read with a small buffer should fail with EINVAL");
+    }
+    val = UINT64_MAX;
+    if (write(event_fd, &val, sizeof(val)) != -1 || errno != EINVAL) {
+        close(event_fd);
+        THROW_ERROR("write 0xffffffffffffffff should fail with EINVAL");
+    }
+    // The counter cannot exceed 0xfffffffffffffffe
+    val = UINT64_MAX - 1;
+    if (write(event_fd, &val, sizeof(val)) != sizeof(val)) {
+        close(event_fd);
+        THROW_ERROR("failed to write the eventfd");
+    }
+    val = 1;
+    if (write(event_fd, &val, sizeof(val)) != -1 || errno != EAGAIN) {
+        close(event_fd);
+        THROW_ERROR("write an overflowing value should fail with EAGAIN");
+    }
+
+    close(event_fd);
+    return 0;
+}
+
 struct thread_arg {
     pthread_t tid;
     int fd;
@@ -250,6 +315,8 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_fcntl_get_flags),
     TEST_CASE(test_fcntl_set_flags),
     TEST_CASE(test_create_with_flags),
+    TEST_CASE(test_semaphore),
+    TEST_CASE(test_nonblock_and_invalid_args),
     TEST_CASE(test_read_write),
     TEST_CASE(test_epoll_with_socket),
     TEST_CASE(test_poll_with_socket),
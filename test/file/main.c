@@ -1,3 +1,4 @@
+#define _GNU_SOURCE
 #include <sys/stat.h>
 #include <sys/uio.h>
 #include <errno.h>
@@ -5,6 +6,15 @@
 #include <stdlib.h>
 #include "test_fs.h"
 
+#ifndef FALLOC_FL_KEEP_SIZE
+#define FALLOC_FL_KEEP_SIZE         0x01
+#define FALLOC_FL_PUNCH_HOLE        0x02
+#endif
+#ifndef FALLOC_FL_ZERO_RANGE
+#define FALLOC_FL_COLLAPSE_RANGE    0x08
+#define FALLOC_FL_ZERO_RANGE        0x10
+#endif
+
 // ============================================================================
 // Helper function
 // ============================================================================
@@ -203,6 +213,298 @@ static int __test_posix_fallocate(const char *file_path) {
     return 0;
 }
 
+static int check_file_content(int fd, off_t offset, const char *expected, size_t len) {
+    char buf[64];
+    if (len > sizeof(buf)) {
+        THROW_ERROR("the buffer is too small");
+    }
+    if (pread(fd, buf, len, offset) != len || memcmp(buf, expected, len) != 0) {
+        THROW_ERROR("the content of the file is wrong");
+    }
+    return 0;
+}
+
+static int __test_fallocate_modes(const char *file_path) {
+    int fd = open(file_path, O_RDWR);
+    if (fd < 0) {
+        THROW_ERROR("failed to open a file to read/write");
+    }
+    const char *data = "0123456789abcdef";
+    size_t data_len = strlen(data);
+    if (write(fd, data, data_len) != data_len) {
+        THROW_ERROR("failed to write the file");
+    }
+    struct stat stat_buf;
+
+    // Preallocating with FALLOC_FL_KEEP_SIZE does not change the file size
+    if (fallocate(fd, FALLOC_FL_KEEP_SIZE, 0, 1024) < 0) {
+        THROW_ERROR("failed to call fallocate with FALLOC_FL_KEEP_SIZE");
+    }
+    if (fstat(fd, &stat_buf) < 0 || stat_buf.st_size != data_len) {
+        THROW_ERROR("the file size should not be changed");
+    }
+
+    // Punching a hole zeros the range without changing the file size
+    if (fallocate(fd, FALLOC_FL_PUNCH_HOLE | FALLOC_FL_KEEP_SIZE, 2, 4) < 0) {
+        THROW_ERROR("failed to punch a hole");
+    }
+    if (check_file_content(fd, 0, "01\0\0\0\06789abcdef", data_len) < 0) {
+        return -1;
+    }
+    if (fstat(fd, &stat_buf) < 0 || stat_buf.st_size != data_len) {
+        THROW_ERROR("the file size should not be changed after punching a hole");
+    }
+
+    // Zeroing a range beyond the end of the file extends the file
+    if (fallocate(fd, FALLOC_FL_ZERO_RANGE, 12, 8) < 0) {
+        THROW_ERROR("failed to zero a range");
+    }
+    if (check_file_content(fd, 8, "89ab\0\0\0\0\0\0\0\0", 12) < 0) {
+        return -1;
+    }
+    if (fstat(fd, &stat_buf) < 0 || stat_buf.st_size != 20) {
+        THROW_ERROR("the file should be extended after zeroing a range");
+    }
+
+    // Invalid or unsupported modes
+    if (fallocate(fd, FALLOC_FL_PUNCH_HOLE, 0, 4) == 0 || errno != EOPNOTSUPP) {
+        THROW_ERROR("punching a hole without FALLOC_FL_KEEP_SIZE should fail");
+    }
+    if (fallocate(fd, FALLOC_FL_COLLAPSE_RANGE, 0, 4) == 0 || errno != EOPNOTSUPP) {
+        THROW_ERROR("collapsing a range should not be supported");
+    }
+    close(fd);
+
+    fd = open(file_path, O_RDONLY);
+    if (fallocate(fd, 0, 0, 4) == 0 || errno != EBADF) {
+        THROW_ERROR("fallocate on a read-only file should fail");
+    }
+    close(fd);
+    return 0;
+}
+
+static int __test_direct_io(const char *file_path) {
+    const size_t block_size = 512;
+    int fd = open(file_path, O_RDWR | O_DIRECT);
+    if (fd < 0) {
+        THROW_ERROR("failed to open a file with O_DIRECT");
+    }
+    int flags = fcntl(fd, F_GETFL);
+    if (flags < 0 || (flags & O_DIRECT) == 0) {
+        THROW_ERROR("O_DIRECT should be set in the status flags");
+    }
+
+    char *buf = NULL;
+    if (posix_memalign((void **)&buf, block_size, block_size * 2) != 0) {
+        THROW_ERROR("failed to allocate an aligned buffer");
+    }
+    memset(buf, 'a', block_size * 2);
+
+    // Aligned I/O succeeds
+    if (pwrite(fd, buf, block_size * 2, 0) != block_size * 2) {
+        THROW_ERROR("failed to write the file with aligned direct I/O");
+    }
+    memset(buf, 0, block_size * 2);
+    if (pread(fd, buf, block_size, block_size) != block_size || buf[0] != 'a') {
+        THROW_ERROR("failed to read the file with aligned direct I/O");
+    }
+
+    // Unaligned I/O fails with EINVAL
+    if (pwrite(fd, buf + 1, block_size, 0) >= 0 || errno != EINVAL) {
+        THROW_ERROR("direct I/O with an unaligned buffer should fail");
+    }
+    if (pread(fd, buf, block_size, 1) >= 0 || errno != EINVAL) {
+        THROW_ERROR("direct I/O with an unaligned offset should fail");
+    }
+    if (read(fd, buf, block_size - 1) >= 0 || errno != EINVAL) {
+        THROW_ERROR("direct I/O with an unaligned length should fail");
+    }
+
+    // Unaligned I/O succeeds after O_DIRECT is cleared
+    if (fcntl(fd, F_SETFL, flags & ~O_DIRECT) < 0) {
+        THROW_ERROR("failed to clear O_DIRECT");
+    }
+    if (pread(fd, buf + 1, block_size - 1, 1) != block_size - 1) {
+        THROW_ERROR("failed to read the file without O_DIRECT");
+    }
+    free(buf);
+    close(fd);
+    return 0;
+}
+
+static int read_page_cache_stat(const char *name, unsigned long *value) {
+    char stats[512] = { 0 };
+    int fd = open("/proc/sefs_page_cache", O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the stats of the page cache");
+    }
+    if (read(fd, stats, sizeof(stats) - 1) < 0) {
+        close(fd);
+        THROW_ERROR("failed to read the stats of the page cache");
+    }
+    close(fd);
+
+    char pattern[64];
+    snprintf(pattern, sizeof(pattern), "%s: %%lu", name);
+    char *value_str = strstr(stats, name);
+    if (value_str == NULL || sscanf(value_str, pattern, value) != 1) {
+        THROW_ERROR("the format of the stats is wrong");
+    }
+    return 0;
+}
+
+static int __test_page_cache(const char *file_path) {
+    const size_t page_size = 4096;
+    const size_t len = page_size * 3;
+    char *write_buf = malloc(len);
+    char *read_buf = malloc(len);
+    if (write_buf == NULL || read_buf == NULL) {
+        THROW_ERROR("failed to allocate the buffers");
+    }
+    for (size_t i = 0; i < len; i++) {
+        write_buf[i] = 'a' + i % 26;
+    }
+
+    int fd = open(file_path, O_RDWR);
+    if (fd < 0) {
+        THROW_ERROR("failed to open a file to read/write");
+    }
+    // Unaligned writes and reads across the page boundaries
+    if (pwrite(fd, write_buf, len, 100) != len) {
+        THROW_ERROR("failed to write the file");
+    }
+    unsigned long hits_before, hits_after;
+    if (read_page_cache_stat("hits", &hits_before) < 0) {
+        return -1;
+    }
+    for (int i = 0; i < 2; i++) {
+        memset(read_buf, 0, len);
+        if (pread(fd, read_buf, len, 100) != len || memcmp(read_buf, write_buf, len) != 0) {
+            THROW_ERROR("the data read is not the data written");
+        }
+    }
+    if (read_page_cache_stat("hits", &hits_after) < 0) {
+        return -1;
+    }
+    if (hits_after <= hits_before) {
+        THROW_ERROR("the repeated reads should hit the page cache");
+    }
+
+    // The data is persistent after the file is synced and reopened
+    if (fsync(fd) < 0) {
+        THROW_ERROR("failed to sync the file");
+    }
+    close(fd);
+    fd = open(file_path, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to reopen the file");
+    }
+    memset(read_buf, 0, len);
+    if (pread(fd, read_buf, len, 100) != len || memcmp(read_buf, write_buf, len) != 0) {
+        THROW_ERROR("the data read after reopening is not the data written");
+    }
+    close(fd);
+    free(write_buf);
+    free(read_buf);
+    return 0;
+}
+
+static int __test_direct_io_bypassing_page_cache(const char *file_path) {
+    const size_t block_size = 512;
+    char *buf = NULL;
+    if (posix_memalign((void **)&buf, block_size, block_size) != 0) {
+        THROW_ERROR("failed to allocate an aligned buffer");
+    }
+    int fd = open(file_path, O_RDWR);
+    int direct_fd = open(file_path, O_RDWR | O_DIRECT);
+    if (fd < 0 || direct_fd < 0) {
+        THROW_ERROR("failed to open the file");
+    }
+
+    // Direct I/O reads the data buffered in the page cache, but never hits the cache
+    memset(buf, 'a', block_size);
+    if (pwrite(fd, buf, block_size, 0) != block_size) {
+        THROW_ERROR("failed to write the file");
+    }
+    unsigned long hits_before, hits_after;
+    if (read_page_cache_stat("hits", &hits_before) < 0) {
+        return -1;
+    }
+    for (int i = 0; i < 2; i++) {
+        memset(buf, 0, block_size);
+        if (pread(direct_fd, buf, block_size, 0) != block_size || buf[0] != 'a' ||
+                buf[block_size - 1] != 'a') {
+            THROW_ERROR("direct I/O should read the data written through the page cache");
+        }
+    }
+    if (read_page_cache_stat("hits", &hits_after) < 0) {
+        return -1;
+    }
+    if (hits_after != hits_before) {
+        THROW_ERROR("direct I/O should bypass the page cache");
+    }
+
+    // The pages cached before direct I/O are not stale
+    if (pread(fd, buf, block_size, 0) != block_size) {
+        THROW_ERROR("failed to read the file");
+    }
+    memset(buf, 'b', block_size);
+    if (pwrite(direct_fd, buf, block_size, 0) != block_size) {
+        THROW_ERROR("failed to write the file with direct I/O");
+    }
+    memset(buf, 0, block_size);
+    if (pread(fd, buf, block_size, 0) != block_size || buf[0] != 'b' ||
+            buf[block_size - 1] != 'b') {
+        THROW_ERROR("the data written by direct I/O should be read through the page cache");
+    }
+
+    close(direct_fd);
+    close(fd);
+    free(buf);
+    return 0;
+}
+
+static int __test_fsync_fdatasync(const char *file_path) {
+    const char *write_str = "Hello World\n";
+    int fd = open(file_path, O_WRONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open a file to write");
+    }
+
+    // Each sync writes back the dirty pages of the file
+    int (*sync_funcs[])(int) = { fsync, fdatasync };
+    for (int i = 0; i < ARRAY_SIZE(sync_funcs); i++) {
+        unsigned long writebacks_before, writebacks_after;
+        if (read_page_cache_stat("writebacks", &writebacks_before) < 0) {
+            return -1;
+        }
+        if (write(fd, write_str, strlen(write_str)) <= 0) {
+            THROW_ERROR("failed to write to the file");
+        }
+        if (sync_funcs[i](fd) < 0) {
+            THROW_ERROR("failed to sync the file");
+        }
+        if (read_page_cache_stat("writebacks", &writebacks_after) < 0) {
+            return -1;
+        }
+        if (writebacks_after <= writebacks_before) {
+            THROW_ERROR("the dirty pages of the file are not written back");
+        }
+    }
+    close(fd);
+
+    // Syncing a clean file is allowed
+    fd = open(file_path, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open a file to read");
+    }
+    if (fsync(fd) < 0 || fdatasync(fd) < 0) {
+        THROW_ERROR("failed to sync the clean file");
+    }
+    close(fd);
+    return 0;
+}
+
 typedef int(*test_file_func_t)(const char *);
 
 static int test_file_framework(test_file_func_t fn) {
@@ -240,6 +542,26 @@ static int test_posix_fallocate() {
     return test_file_framework(__test_posix_fallocate);
 }
 
+static int test_fallocate_modes() {
+    return test_file_framework(__test_fallocate_modes);
+}
+
+static int test_direct_io() {
+    return test_file_framework(__test_direct_io);
+}
+
+static int test_page_cache() {
+    return test_file_framework(__test_page_cache);
+}
+
+static int test_direct_io_bypassing_page_cache() {
+    return test_file_framework(__test_direct_io_bypassing_page_cache);
+}
+
+static int test_fsync_fdatasync() {
+    return test_file_framework(__test_fsync_fdatasync);
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -250,6 +572,11 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_writev_readv),
     TEST_CASE(test_lseek),
     TEST_CASE(test_posix_fallocate),
+    TEST_CASE(test_fallocate_modes),
+    TEST_CASE(test_direct_io),
+    TEST_CASE(test_page_cache),
+    TEST_CASE(test_direct_io_bypassing_page_cache),
+    TEST_CASE(test_fsync_fdatasync),
 };
 
 int main(int argc, const char *argv[]) {
@@ -0,0 +1,361 @@
+#include <sys/file.h>
+#include <sys/wait.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <spawn.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define FILE_PATH       "/root/test_flock_file.txt"
+#define CLOEXEC_PATH    "/root/test_flock_cloexec_file.txt"
+#define CHILD_PROG      "/bin/flock"
+
+static int set_lock(int fd, int cmd, short type, off_t start, off_t len) {
+    struct flock fl = {
+        .l_type = type,
+        .l_whence = SEEK_SET,
+        .l_start = start,
+        .l_len = len,
+    };
+    return fcntl(fd, cmd, &fl);
+}
+
+static int spawn_child(const char *test_name, int *child_pid) {
+    const char *child_argv[3] = { CHILD_PROG, test_name, NULL };
+    if (posix_spawn(child_pid, CHILD_PROG, NULL, NULL,
+                    (char *const *)child_argv, NULL) != 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+    return 0;
+}
+
+static int wait_child(int child_pid) {
+    int status = 0;
+    if (wait4(child_pid, &status, 0, NULL) < 0) {
+        THROW_ERROR("failed to wait4 the child process");
+    }
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        THROW_ERROR("the child process failed");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Child processes
+// ============================================================================
+
+// The parent holds a write lock on [0, 100)
+static int child_test_conflict(void) {
+    int fd = open(FILE_PATH, O_RDWR);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the file");
+    }
+    if (set_lock(fd, F_SETLK, F_RDLCK, 50, 10) == 0 || errno != EAGAIN) {
+        THROW_ERROR("placing a conflicting lock should fail");
+    }
+    if (set_lock(fd, F_SETLK, F_WRLCK, 100, 10) < 0) {
+        THROW_ERROR("placing a non-conflicting lock should succeed");
+    }
+
+    struct flock fl = { .l_type = F_WRLCK, .l_whence = SEEK_SET, .l_start = 0, .l_len = 0 };
+    if (fcntl(fd, F_GETLK, &fl) < 0) {
+        THROW_ERROR("failed to call getlk");
+    }
+    if (fl.l_type != F_WRLCK || fl.l_start != 0 || fl.l_len != 100 ||
+            fl.l_pid != getppid()) {
+        THROW_ERROR("getlk should report the lock of the parent");
+    }
+
+    // A BSD lock is independent of the POSIX record locks
+    if (flock(fd, LOCK_EX | LOCK_NB) < 0) {
+        THROW_ERROR("failed to place a BSD lock");
+    }
+    close(fd);
+    return 0;
+}
+
+// The parent has released its locks
+static int child_test_released(void) {
+    int fd = open(FILE_PATH, O_RDWR);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the file");
+    }
+    if (set_lock(fd, F_SETLK, F_WRLCK, 0, 0) < 0) {
+        THROW_ERROR("failed to lock the whole file");
+    }
+    close(fd);
+    return 0;
+}
+
+// The parent holds a write lock on [0, 1)
+static int child_test_deadlock(void) {
+    int fd = open(FILE_PATH, O_RDWR);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the file");
+    }
+    if (set_lock(fd, F_SETLK, F_WRLCK, 1, 1) < 0) {
+        THROW_ERROR("failed to lock [1, 2)");
+    }
+    // Block until the parent releases its lock
+    if (set_lock(fd, F_SETLKW, F_WRLCK, 0, 1) < 0) {
+        THROW_ERROR("failed to wait for the lock on [0, 1)");
+    }
+    close(fd);
+    return 0;
+}
+
+// The lock on FILE_PATH is kept across exec, while the lock on CLOEXEC_PATH
+// is released when the file is closed on exec
+static int child_test_exec(void) {
+    int child_pid;
+    if (spawn_child("exec_check", &child_pid) < 0 || wait_child(child_pid) < 0) {
+        return -1;
+    }
+    return 0;
+}
+
+// The parent has execed with the locks of the process before exec
+static int child_test_exec_check(void) {
+    int fd = open(FILE_PATH, O_RDWR);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the file");
+    }
+    struct flock fl = { .l_type = F_WRLCK, .l_whence = SEEK_SET, .l_start = 0, .l_len = 0 };
+    if (fcntl(fd, F_GETLK, &fl) < 0) {
+        THROW_ERROR("failed to call getlk");
+    }
+    if (fl.l_type != F_WRLCK || fl.l_pid != getppid()) {
+        THROW_ERROR("the lock should be kept across exec");
+    }
+    close(fd);
+
+    int cloexec_fd = open(CLOEXEC_PATH, O_RDWR);
+    if (cloexec_fd < 0) {
+        THROW_ERROR("failed to open the file");
+    }
+    if (set_lock(cloexec_fd, F_SETLK, F_WRLCK, 0, 0) < 0) {
+        THROW_ERROR("the lock should be released on exec");
+    }
+    close(cloexec_fd);
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_posix_lock_conflict() {
+    int fd = open(FILE_PATH, O_RDWR | O_CREAT | O_TRUNC, 0666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create the file");
+    }
+    if (set_lock(fd, F_SETLK, F_WRLCK, 0, 100) < 0) {
+        THROW_ERROR("failed to lock [0, 100)");
+    }
+    // Locks of the same process never conflict
+    if (set_lock(fd, F_SETLK, F_RDLCK, 50, 10) < 0) {
+        THROW_ERROR("failed to convert a part of the lock");
+    }
+    if (set_lock(fd, F_SETLK, F_WRLCK, 50, 10) < 0) {
+        THROW_ERROR("failed to convert the part back");
+    }
+
+    int child_pid;
+    if (spawn_child("conflict", &child_pid) < 0 || wait_child(child_pid) < 0) {
+        return -1;
+    }
+    close(fd);
+    return 0;
+}
+
+static int test_posix_lock_release_on_close() {
+    int fd = open(FILE_PATH, O_RDWR);
+    int another_fd = open(FILE_PATH, O_RDWR);
+    if (fd < 0 || another_fd < 0) {
+        THROW_ERROR("failed to open the file");
+    }
+    if (set_lock(fd, F_SETLK, F_WRLCK, 0, 0) < 0) {
+        THROW_ERROR("failed to lock the whole file");
+    }
+    // Closing any fd of the file releases the locks of the process
+    close(another_fd);
+
+    int child_pid;
+    if (spawn_child("released", &child_pid) < 0 || wait_child(child_pid) < 0) {
+        return -1;
+    }
+    close(fd);
+    return 0;
+}
+
+static int test_posix_lock_release_on_dup2() {
+    int fd = open(FILE_PATH, O_RDWR);
+    int another_fd = open(FILE_PATH, O_RDWR);
+    if (fd < 0 || another_fd < 0) {
+        THROW_ERROR("failed to open the file");
+    }
+    if (set_lock(fd, F_SETLK, F_WRLCK, 0, 0) < 0) {
+        THROW_ERROR("failed to lock the whole file");
+    }
+    // Replacing an fd of the file closes it, which releases the locks
+    if (dup2(fd, another_fd) < 0) {
+        THROW_ERROR("failed to dup2");
+    }
+
+    int child_pid;
+    if (spawn_child("released", &child_pid) < 0 || wait_child(child_pid) < 0) {
+        return -1;
+    }
+    close(another_fd);
+    close(fd);
+    return 0;
+}
+
+static int test_posix_lock_across_exec() {
+    int child_pid = fork();
+    if (child_pid < 0) {
+        THROW_ERROR("failed to fork");
+    }
+    if (child_pid == 0) {
+        int fd = open(FILE_PATH, O_RDWR);
+        int cloexec_fd = open(CLOEXEC_PATH, O_RDWR | O_CREAT | O_TRUNC | O_CLOEXEC, 0666);
+        if (fd < 0 || cloexec_fd < 0) {
+            _exit(EXIT_FAILURE);
+        }
+        if (set_lock(fd, F_SETLK, F_WRLCK, 0, 0) < 0 ||
+                set_lock(cloexec_fd, F_SETLK, F_WRLCK, 0, 0) < 0) {
+            _exit(EXIT_FAILURE);
+        }
+        const char *child_argv[3] = { CHILD_PROG, "exec", NULL };
+        execv(CHILD_PROG, (char *const *)child_argv);
+        _exit(EXIT_FAILURE);
+    }
+    return wait_child(child_pid);
+}
+
+static int test_posix_lock_deadlock() {
+    int fd = open(FILE_PATH, O_RDWR);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the file");
+    }
+    if (set_lock(fd, F_SETLK, F_WRLCK, 0, 1) < 0) {
+        THROW_ERROR("failed to lock [0, 1)");
+    }
+
+    int child_pid;
+    if (spawn_child("deadlock", &child_pid) < 0) {
+        return -1;
+    }
+    // Wait until the child holds [1, 2) and blocks on [0, 1)
+    struct flock fl;
+    do {
+        usleep(10 * 1000);
+        fl = (struct flock) { .l_type = F_WRLCK, .l_whence = SEEK_SET, .l_start = 1, .l_len = 1 };
+        if (fcntl(fd, F_GETLK, &fl) < 0) {
+            THROW_ERROR("failed to call getlk");
+        }
+    } while (fl.l_type == F_UNLCK);
+    usleep(100 * 1000);
+
+    if (set_lock(fd, F_SETLKW, F_WRLCK, 1, 1) == 0 || errno != EDEADLK) {
+        THROW_ERROR("waiting for the lock of the child should cause a deadlock");
+    }
+    if (set_lock(fd, F_SETLK, F_UNLCK, 0, 1) < 0) {
+        THROW_ERROR("failed to unlock [0, 1)");
+    }
+    if (wait_child(child_pid) < 0) {
+        return -1;
+    }
+    close(fd);
+    return 0;
+}
+
+static int test_flock() {
+    int fd = open(FILE_PATH, O_RDWR);
+    int another_fd = open(FILE_PATH, O_RDONLY);
+    if (fd < 0 || another_fd < 0) {
+        THROW_ERROR("failed to open the file");
+    }
+    if (flock(fd, LOCK_SH) < 0 || flock(another_fd, LOCK_SH | LOCK_NB) < 0) {
+        THROW_ERROR("failed to place shared locks");
+    }
+    // Locks of different open files conflict, even in the same process
+    if (flock(fd, LOCK_EX | LOCK_NB) == 0 || errno != EWOULDBLOCK) {
+        THROW_ERROR("converting to an exclusive lock should fail");
+    }
+    if (flock(another_fd, LOCK_UN) < 0) {
+        THROW_ERROR("failed to unlock");
+    }
+    if (flock(fd, LOCK_EX | LOCK_NB) < 0) {
+        THROW_ERROR("failed to convert to an exclusive lock");
+    }
+
+    // The lock is shared by duplicated fds and released on the last close
+    int dup_fd = dup(fd);
+    close(fd);
+    if (flock(another_fd, LOCK_SH | LOCK_NB) == 0 || errno != EWOULDBLOCK) {
+        THROW_ERROR("the lock should be held through the duplicated fd");
+    }
+    close(dup_fd);
+    if (flock(another_fd, LOCK_EX | LOCK_NB) < 0) {
+        THROW_ERROR("the lock should be released after the last close");
+    }
+
+    if (flock(another_fd, LOCK_SH | LOCK_EX) == 0 || errno != EINVAL) {
+        THROW_ERROR("invalid operations should fail");
+    }
+    close(another_fd);
+    return 0;
+}
+
+static int test_cleanup() {
+    if (unlink(FILE_PATH) < 0) {
+        THROW_ERROR("failed to unlink the file");
+    }
+    if (unlink(CLOEXEC_PATH) < 0) {
+        THROW_ERROR("failed to unlink the file");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_posix_lock_conflict),
+    TEST_CASE(test_posix_lock_release_on_close),
+    TEST_CASE(test_posix_lock_release_on_dup2),
+    TEST_CASE(test_posix_lock_across_exec),
+    TEST_CASE(test_posix_lock_deadlock),
+    TEST_CASE(test_flock),
+    TEST_CASE(test_cleanup),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 1) {
+        if (strcmp(argv[1], "conflict") == 0) {
+            return child_test_conflict() < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+        }
+        if (strcmp(argv[1], "released") == 0) {
+            return child_test_released() < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+        }
+        if (strcmp(argv[1], "deadlock") == 0) {
+            return child_test_deadlock() < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+        }
+        if (strcmp(argv[1], "exec") == 0) {
+            return child_test_exec() < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+        }
+        if (strcmp(argv[1], "exec_check") == 0) {
+            return child_test_exec_check() < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+        }
+        return EXIT_FAILURE;
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
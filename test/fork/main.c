@@ -0,0 +1,352 @@
+#define _GNU_SOURCE
+#include <sys/mman.h>
+#include <sys/sendfile.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <pthread.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <syscall.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define CHILD_EXIT_CODE     42
+#define PIPE_MSG            "Hello from the child"
+#define SHM_NAME            "/test_fork_shm"
+#define SHM_SIZE            4096
+#define CHILD_PROG          "/bin/fork"
+#define EXEC_EXIT_CODE      24
+
+static int wait_child(pid_t child_pid, int expected_exit_code) {
+    int status = 0;
+    if (waitpid(child_pid, &status, 0) != child_pid) {
+        THROW_ERROR("failed to wait for the child process");
+    }
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != expected_exit_code) {
+        THROW_ERROR("the exit status of the child process is not as expected");
+    }
+    return 0;
+}
+
+// The parent resumes before the child exits, so the child can wait for the
+// message of the parent from the pipe
+static int exec_child(pid_t vfork_pid, int read_fd) {
+    char buf[sizeof(PIPE_MSG)] = { 0 };
+    // The new process replaces the child of vfork with a different pid
+    if (getpid() == vfork_pid) {
+        return EXIT_FAILURE;
+    }
+    if (read(read_fd, buf, sizeof(buf)) != sizeof(buf) || strcmp(buf, PIPE_MSG) != 0) {
+        return EXIT_FAILURE;
+    }
+    return EXEC_EXIT_CODE;
+}
+
+static int exec_by_path(char *const argv[]) {
+    return execve(CHILD_PROG, argv, NULL);
+}
+
+static int exec_by_fd(char *const argv[]) {
+    int fd = open(CHILD_PROG, O_PATH);
+    if (fd < 0) {
+        return -1;
+    }
+    return fexecve(fd, argv, NULL);
+}
+
+// Execute the copy of the program in a memfd, which has no path
+static int exec_by_memfd(char *const argv[]) {
+    int fd = open(CHILD_PROG, O_RDONLY);
+    if (fd < 0) {
+        return -1;
+    }
+    struct stat stat_buf;
+    if (fstat(fd, &stat_buf) < 0) {
+        return -1;
+    }
+    int mem_fd = memfd_create("fork", 0);
+    if (mem_fd < 0) {
+        return -1;
+    }
+    if (sendfile(mem_fd, fd, NULL, stat_buf.st_size) != stat_buf.st_size) {
+        return -1;
+    }
+    close(fd);
+    return fexecve(mem_fd, argv, NULL);
+}
+
+static int fork_and_exec(pid_t (*fork_fn)(void), int (*exec_fn)(char *const argv[])) {
+    int pipe_fds[2];
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+
+    pid_t child_pid = fork_fn();
+    if (child_pid < 0) {
+        THROW_ERROR("failed to fork");
+    }
+    if (child_pid == 0) {
+        char pid_arg[16];
+        char fd_arg[16];
+        snprintf(pid_arg, sizeof(pid_arg), "%d", getpid());
+        snprintf(fd_arg, sizeof(fd_arg), "%d", pipe_fds[0]);
+        char *child_argv[] = { CHILD_PROG, "exec_child", pid_arg, fd_arg, NULL };
+        exec_fn(child_argv);
+        _exit(EXIT_FAILURE);
+    }
+    close(pipe_fds[0]);
+
+    if (write(pipe_fds[1], PIPE_MSG, sizeof(PIPE_MSG)) != sizeof(PIPE_MSG)) {
+        THROW_ERROR("failed to write the message to the child");
+    }
+    close(pipe_fds[1]);
+    return wait_child(child_pid, EXEC_EXIT_CODE);
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_fork_and_wait() {
+    pid_t parent_pid = getpid();
+    pid_t child_pid = fork();
+    if (child_pid < 0) {
+        THROW_ERROR("failed to fork");
+    }
+    if (child_pid == 0) {
+        if (getppid() != parent_pid || getpid() == parent_pid) {
+            _exit(EXIT_FAILURE);
+        }
+        _exit(CHILD_EXIT_CODE);
+    }
+    return wait_child(child_pid, CHILD_EXIT_CODE);
+}
+
+static int test_fork_copy_memory() {
+    static int global_value = 1;
+    int *heap_value = malloc(sizeof(int));
+    if (heap_value == NULL) {
+        THROW_ERROR("failed to malloc");
+    }
+    *heap_value = 2;
+
+    pid_t child_pid = fork();
+    if (child_pid < 0) {
+        THROW_ERROR("failed to fork");
+    }
+    if (child_pid == 0) {
+        // The child sees the memory of the parent
+        if (global_value != 1 || *heap_value != 2) {
+            _exit(EXIT_FAILURE);
+        }
+        global_value = 10;
+        *heap_value = 20;
+        _exit(EXIT_SUCCESS);
+    }
+    if (wait_child(child_pid, EXIT_SUCCESS) < 0) {
+        return -1;
+    }
+    // The changes of the child are invisible to the parent
+    if (global_value != 1 || *heap_value != 2) {
+        THROW_ERROR("the memory of the parent is changed by the child");
+    }
+    free(heap_value);
+    return 0;
+}
+
+static int test_fork_inherit_fds() {
+    int pipe_fds[2];
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+
+    pid_t child_pid = fork();
+    if (child_pid < 0) {
+        THROW_ERROR("failed to fork");
+    }
+    if (child_pid == 0) {
+        close(pipe_fds[0]);
+        if (write(pipe_fds[1], PIPE_MSG, sizeof(PIPE_MSG)) != sizeof(PIPE_MSG)) {
+            _exit(EXIT_FAILURE);
+        }
+        _exit(EXIT_SUCCESS);
+    }
+    close(pipe_fds[1]);
+
+    char buf[sizeof(PIPE_MSG)] = { 0 };
+    if (read(pipe_fds[0], buf, sizeof(buf)) != sizeof(buf) || strcmp(buf, PIPE_MSG) != 0) {
+        THROW_ERROR("failed to read the message of the child");
+    }
+    // All the write ends of the pipe are closed
+    if (read(pipe_fds[0], buf, sizeof(buf)) != 0) {
+        THROW_ERROR("the pipe should be at the end of file");
+    }
+    close(pipe_fds[0]);
+    return wait_child(child_pid, EXIT_SUCCESS);
+}
+
+static int test_fork_inherit_signal_dispositions() {
+    if (signal(SIGUSR1, SIG_IGN) == SIG_ERR) {
+        THROW_ERROR("failed to ignore SIGUSR1");
+    }
+
+    pid_t child_pid = fork();
+    if (child_pid < 0) {
+        THROW_ERROR("failed to fork");
+    }
+    if (child_pid == 0) {
+        // The child would be killed if SIGUSR1 were not ignored
+        raise(SIGUSR1);
+        _exit(EXIT_SUCCESS);
+    }
+    int ret = wait_child(child_pid, EXIT_SUCCESS);
+    signal(SIGUSR1, SIG_DFL);
+    return ret;
+}
+
+static int test_fork_shared_mapping() {
+    int fd = shm_open(SHM_NAME, O_RDWR | O_CREAT, 0600);
+    if (fd < 0) {
+        THROW_ERROR("failed to create the shared memory object");
+    }
+    if (ftruncate(fd, SHM_SIZE) < 0) {
+        THROW_ERROR("failed to set the size of the shared memory object");
+    }
+    char *buf = mmap(NULL, SHM_SIZE, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    if (buf == MAP_FAILED) {
+        THROW_ERROR("failed to mmap the shared memory object");
+    }
+    close(fd);
+
+    pid_t child_pid = fork();
+    if (child_pid < 0) {
+        THROW_ERROR("failed to fork");
+    }
+    if (child_pid == 0) {
+        strcpy(buf, PIPE_MSG);
+        _exit(EXIT_SUCCESS);
+    }
+    if (wait_child(child_pid, EXIT_SUCCESS) < 0) {
+        return -1;
+    }
+    // The changes of the child to the shared mapping are visible
+    if (strcmp(buf, PIPE_MSG) != 0) {
+        THROW_ERROR("the write of the child to the shared mapping is not visible");
+    }
+    munmap(buf, SHM_SIZE);
+    shm_unlink(SHM_NAME);
+    return 0;
+}
+
+static int test_vfork_and_exec() {
+    return fork_and_exec(vfork, exec_by_path);
+}
+
+static int test_fork_and_exec() {
+    return fork_and_exec(fork, exec_by_path);
+}
+
+static int test_vfork_and_fexecve() {
+    return fork_and_exec(vfork, exec_by_fd);
+}
+
+static int test_fork_and_fexecve_memfd() {
+    return fork_and_exec(fork, exec_by_memfd);
+}
+
+static void *wait_for_pipe(void *arg) {
+    char buf;
+    int read_fd = *(int *)arg;
+    read(read_fd, &buf, sizeof(buf));
+    return NULL;
+}
+
+// The other thread keeps running while the child of vfork runs, and fork is
+// not supported at all
+static int test_vfork_in_multithreaded_process() {
+    int pipe_fds[2];
+    pthread_t thread;
+    int ret = -1;
+
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+    if (pthread_create(&thread, NULL, wait_for_pipe, &pipe_fds[0]) != 0) {
+        THROW_ERROR("failed to create a thread");
+    }
+
+    if (fork() != -1 || errno != ENOSYS) {
+        printf("\t\tERROR: fork of a multithreaded process should fail\n");
+        goto out;
+    }
+    if (fork_and_exec(vfork, exec_by_path) < 0) {
+        printf("\t\tERROR: failed to vfork in a multithreaded process\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    write(pipe_fds[1], "", 1);
+    pthread_join(thread, NULL);
+    close(pipe_fds[0]);
+    close(pipe_fds[1]);
+    return ret;
+}
+
+static int test_execveat_symlink_nofollow() {
+    const char *link_path = "/root/test_fork_symlink";
+    unlink(link_path);
+    if (symlink(CHILD_PROG, link_path) < 0) {
+        THROW_ERROR("failed to create a symlink");
+    }
+    char *child_argv[] = { CHILD_PROG, NULL };
+    int ret = syscall(__NR_execveat, AT_FDCWD, link_path, child_argv, NULL,
+                      AT_SYMLINK_NOFOLLOW);
+    int exec_errno = errno;
+    unlink(link_path);
+    if (ret == 0 || exec_errno != ELOOP) {
+        THROW_ERROR("executing a symlink with AT_SYMLINK_NOFOLLOW should fail");
+    }
+    return 0;
+}
+
+static int test_exec_without_fork() {
+    char *child_argv[] = { CHILD_PROG, NULL };
+    if (execve(CHILD_PROG, child_argv, NULL) == 0 || errno != ENOSYS) {
+        THROW_ERROR("exec should fail outside the child of fork");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_fork_and_wait),
+    TEST_CASE(test_fork_copy_memory),
+    TEST_CASE(test_fork_inherit_fds),
+    TEST_CASE(test_fork_inherit_signal_dispositions),
+    TEST_CASE(test_fork_shared_mapping),
+    TEST_CASE(test_vfork_and_exec),
+    TEST_CASE(test_fork_and_exec),
+    TEST_CASE(test_vfork_and_fexecve),
+    TEST_CASE(test_fork_and_fexecve_memfd),
+    TEST_CASE(test_vfork_in_multithreaded_process),
+    TEST_CASE(test_execveat_symlink_nofollow),
+    TEST_CASE(test_exec_without_fork),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 3 && strcmp(argv[1], "exec_child") == 0) {
+        return exec_child(atoi(argv[2]), atoi(argv[3]));
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
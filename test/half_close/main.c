@@ -0,0 +1,184 @@
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+#include <errno.h>
+#include <signal.h>
+#include <unistd.h>
+#include <string.h>
+
+#include "test.h"
+
+#define MSG         "hello"
+#define MSG_LEN     (sizeof(MSG) - 1)
+
+// ============================================================================
+// Helper function
+// ============================================================================
+static int check_read(int fd, const char *expected, size_t expected_len) {
+    char buf[32] = {0};
+    ssize_t len = read(fd, buf, sizeof(buf));
+    if (len != (ssize_t)expected_len || memcmp(buf, expected, expected_len) != 0) {
+        THROW_ERROR("the data read is not the expected one");
+    }
+    return 0;
+}
+
+static int check_write_epipe(int fd) {
+    if (write(fd, MSG, MSG_LEN) >= 0 || errno != EPIPE) {
+        THROW_ERROR("write should fail with EPIPE");
+    }
+    return 0;
+}
+
+static int connect_tcp_pair(int *client, int *server) {
+    struct sockaddr_in addr = {0};
+    socklen_t addr_len = sizeof(addr);
+    addr.sin_family = AF_INET;
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+
+    int listener = socket(AF_INET, SOCK_STREAM, 0);
+    if (listener < 0) {
+        THROW_ERROR("failed to create a TCP socket");
+    }
+    if (bind(listener, (struct sockaddr *)&addr, sizeof(addr)) < 0 ||
+            getsockname(listener, (struct sockaddr *)&addr, &addr_len) < 0 ||
+            listen(listener, 1) < 0) {
+        close(listener);
+        THROW_ERROR("failed to listen on the socket");
+    }
+
+    *client = socket(AF_INET, SOCK_STREAM, 0);
+    if (*client < 0 || connect(*client, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        close(listener);
+        THROW_ERROR("failed to connect to the listening socket");
+    }
+    *server = accept(listener, NULL, NULL);
+    close(listener);
+    if (*server < 0) {
+        close(*client);
+        THROW_ERROR("failed to accept the connection");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases for half-close and SO_LINGER
+// ============================================================================
+static int test_unix_shutdown_write() {
+    int socks[2];
+    if (socketpair(AF_UNIX, SOCK_STREAM, 0, socks) < 0) {
+        THROW_ERROR("failed to create a unix socket pair");
+    }
+
+    int ret = -1;
+    if (write(socks[0], MSG, MSG_LEN) != MSG_LEN || shutdown(socks[0], SHUT_WR) < 0) {
+        printf("\t\tERROR: failed to write and shutdown the socket\n");
+        goto out;
+    }
+    // The data written before the shutdown are read before the end
+    if (check_read(socks[1], MSG, MSG_LEN) < 0 || check_read(socks[1], "", 0) < 0 ||
+            check_write_epipe(socks[0]) < 0) {
+        goto out;
+    }
+    // The other direction is still open
+    if (write(socks[1], MSG, MSG_LEN) != MSG_LEN || check_read(socks[0], MSG, MSG_LEN) < 0) {
+        printf("\t\tERROR: the other direction should be open\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    close(socks[0]);
+    close(socks[1]);
+    return ret;
+}
+
+static int test_unix_shutdown_read() {
+    int socks[2];
+    if (socketpair(AF_UNIX, SOCK_STREAM, 0, socks) < 0) {
+        THROW_ERROR("failed to create a unix socket pair");
+    }
+
+    int ret = -1;
+    if (write(socks[1], MSG, MSG_LEN) != MSG_LEN || shutdown(socks[0], SHUT_RD) < 0) {
+        printf("\t\tERROR: failed to write and shutdown the socket\n");
+        goto out;
+    }
+    // The data received before the shutdown can still be read
+    if (check_read(socks[0], MSG, MSG_LEN) < 0 || check_read(socks[0], "", 0) < 0 ||
+            check_write_epipe(socks[1]) < 0) {
+        goto out;
+    }
+    ret = 0;
+out:
+    close(socks[0]);
+    close(socks[1]);
+    return ret;
+}
+
+static int test_tcp_shutdown_write() {
+    int client, server;
+    if (connect_tcp_pair(&client, &server) < 0) {
+        return -1;
+    }
+
+    // The client sends a request and closes its side as an HTTP/1.0 client
+    // does, and then receives the response
+    int ret = -1;
+    if (write(client, MSG, MSG_LEN) != MSG_LEN || shutdown(client, SHUT_WR) < 0) {
+        printf("\t\tERROR: failed to write and shutdown the socket\n");
+        goto out;
+    }
+    if (check_read(server, MSG, MSG_LEN) < 0 || check_read(server, "", 0) < 0) {
+        goto out;
+    }
+    if (write(server, MSG, MSG_LEN) != MSG_LEN || check_read(client, MSG, MSG_LEN) < 0 ||
+            check_write_epipe(client) < 0) {
+        goto out;
+    }
+    ret = 0;
+out:
+    close(client);
+    close(server);
+    return ret;
+}
+
+static int test_tcp_linger_zero() {
+    int client, server;
+    if (connect_tcp_pair(&client, &server) < 0) {
+        return -1;
+    }
+
+    // The connection is reset on close with a zero linger timeout
+    struct linger linger = { .l_onoff = 1, .l_linger = 0 };
+    if (setsockopt(client, SOL_SOCKET, SO_LINGER, &linger, sizeof(linger)) < 0) {
+        close(client);
+        close(server);
+        THROW_ERROR("failed to set SO_LINGER");
+    }
+    close(client);
+
+    char buf[32];
+    ssize_t len = read(server, buf, sizeof(buf));
+    int err = errno;
+    close(server);
+    if (len >= 0 || err != ECONNRESET) {
+        THROW_ERROR("read should fail with ECONNRESET");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+static test_case_t test_cases[] = {
+    TEST_CASE(test_unix_shutdown_write),
+    TEST_CASE(test_unix_shutdown_read),
+    TEST_CASE(test_tcp_shutdown_write),
+    TEST_CASE(test_tcp_linger_zero),
+};
+
+int main() {
+    // Writing to a socket shutdown for writing fails with EPIPE instead
+    signal(SIGPIPE, SIG_IGN);
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
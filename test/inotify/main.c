@@ -0,0 +1,244 @@
+#include <sys/inotify.h>
+#include <sys/stat.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#ifndef IN_MASK_CREATE
+#define IN_MASK_CREATE  0x10000000
+#endif
+
+#define EVENT_BUF_LEN   (16 * (sizeof(struct inotify_event) + NAME_MAX + 1))
+
+static const char *watched_dir = "/root/test_inotify_dir";
+
+static int read_one_event(int ifd, char *buf, struct inotify_event **event) {
+    ssize_t len = read(ifd, buf, EVENT_BUF_LEN);
+    if (len < (ssize_t)sizeof(struct inotify_event)) {
+        THROW_ERROR("failed to read an inotify event");
+    }
+    *event = (struct inotify_event *)buf;
+    return 0;
+}
+
+static int check_event(int ifd, int wd, uint32_t mask, const char *name) {
+    char buf[EVENT_BUF_LEN] __attribute__((aligned(__alignof__(struct inotify_event))));
+    struct inotify_event *event;
+    if (read_one_event(ifd, buf, &event) < 0) {
+        return -1;
+    }
+    if (event->wd != wd || event->mask != mask) {
+        THROW_ERROR("unexpected event: wd = %d, mask = 0x%x", event->wd, event->mask);
+    }
+    if (name != NULL && (event->len == 0 || strcmp(event->name, name) != 0)) {
+        THROW_ERROR("unexpected name in the event");
+    }
+    return 0;
+}
+
+static int setup_watch(int *ifd, int *wd, uint32_t mask) {
+    if (mkdir(watched_dir, 00775) < 0) {
+        THROW_ERROR("failed to create the watched dir");
+    }
+    *ifd = inotify_init1(IN_NONBLOCK);
+    if (*ifd < 0) {
+        THROW_ERROR("failed to create an inotify file");
+    }
+    *wd = inotify_add_watch(*ifd, watched_dir, mask);
+    if (*wd < 0) {
+        close(*ifd);
+        THROW_ERROR("failed to add a watch");
+    }
+    return 0;
+}
+
+static int teardown_watch(int ifd) {
+    close(ifd);
+    if (rmdir(watched_dir) < 0) {
+        THROW_ERROR("failed to remove the watched dir");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+int test_create_modify_delete() {
+    int ifd, wd;
+    if (setup_watch(&ifd, &wd, IN_CREATE | IN_MODIFY | IN_DELETE) < 0) {
+        return -1;
+    }
+
+    const char *file_path = "/root/test_inotify_dir/file.txt";
+    int fd = open(file_path, O_WRONLY | O_CREAT, 00666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a file");
+    }
+    if (check_event(ifd, wd, IN_CREATE, "file.txt") < 0) {
+        return -1;
+    }
+
+    if (write(fd, "hello", 5) != 5) {
+        THROW_ERROR("failed to write the file");
+    }
+    close(fd);
+    if (check_event(ifd, wd, IN_MODIFY, "file.txt") < 0) {
+        return -1;
+    }
+
+    if (unlink(file_path) < 0) {
+        THROW_ERROR("failed to unlink the file");
+    }
+    if (check_event(ifd, wd, IN_DELETE, "file.txt") < 0) {
+        return -1;
+    }
+
+    return teardown_watch(ifd);
+}
+
+int test_move() {
+    int ifd, wd;
+    if (setup_watch(&ifd, &wd, IN_MOVED_FROM | IN_MOVED_TO) < 0) {
+        return -1;
+    }
+
+    const char *old_path = "/root/test_inotify_dir/old.txt";
+    const char *new_path = "/root/test_inotify_dir/new.txt";
+    int fd = open(old_path, O_WRONLY | O_CREAT, 00666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a file");
+    }
+    close(fd);
+    if (rename(old_path, new_path) < 0) {
+        THROW_ERROR("failed to rename the file");
+    }
+
+    char buf[EVENT_BUF_LEN] __attribute__((aligned(__alignof__(struct inotify_event))));
+    ssize_t len = read(ifd, buf, sizeof(buf));
+    if (len <= 0) {
+        THROW_ERROR("failed to read the inotify file");
+    }
+    struct inotify_event *from = (struct inotify_event *)buf;
+    struct inotify_event *to = (struct inotify_event *)(buf + sizeof(*from) + from->len);
+    if ((char *)to + sizeof(*to) > buf + len) {
+        THROW_ERROR("the pair of move events is incomplete");
+    }
+    if (from->mask != IN_MOVED_FROM || strcmp(from->name, "old.txt") != 0 ||
+            to->mask != IN_MOVED_TO || strcmp(to->name, "new.txt") != 0) {
+        THROW_ERROR("unexpected move events");
+    }
+    if (from->cookie == 0 || from->cookie != to->cookie) {
+        THROW_ERROR("the cookies of the move events do not match");
+    }
+
+    if (unlink(new_path) < 0) {
+        THROW_ERROR("failed to unlink the file");
+    }
+    return teardown_watch(ifd);
+}
+
+int test_modify_through_stale_path() {
+    int ifd, wd;
+    if (setup_watch(&ifd, &wd, IN_MODIFY) < 0) {
+        return -1;
+    }
+
+    // The file is replaced by another file of the same name after it is opened
+    const char *file_path = "/root/test_inotify_dir/file.txt";
+    int fd = open(file_path, O_WRONLY | O_CREAT, 00666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a file");
+    }
+    if (unlink(file_path) < 0) {
+        THROW_ERROR("failed to unlink the file");
+    }
+    int another_fd = open(file_path, O_WRONLY | O_CREAT, 00666);
+    if (another_fd < 0) {
+        THROW_ERROR("failed to create another file");
+    }
+    close(another_fd);
+
+    // The unlinked file is no longer an entry of the watched dir
+    if (write(fd, "hello", 5) != 5) {
+        THROW_ERROR("failed to write the unlinked file");
+    }
+    close(fd);
+    char buf[EVENT_BUF_LEN] __attribute__((aligned(__alignof__(struct inotify_event))));
+    if (read(ifd, buf, sizeof(buf)) >= 0 || errno != EAGAIN) {
+        THROW_ERROR("writing the unlinked file should not be reported to the dir");
+    }
+
+    if (unlink(file_path) < 0) {
+        THROW_ERROR("failed to unlink the file");
+    }
+    return teardown_watch(ifd);
+}
+
+int test_rm_watch() {
+    int ifd, wd;
+    if (setup_watch(&ifd, &wd, IN_CREATE) < 0) {
+        return -1;
+    }
+
+    if (inotify_rm_watch(ifd, wd) < 0) {
+        THROW_ERROR("failed to remove the watch");
+    }
+    if (check_event(ifd, wd, IN_IGNORED, NULL) < 0) {
+        return -1;
+    }
+    if (inotify_rm_watch(ifd, wd) == 0 || errno != EINVAL) {
+        THROW_ERROR("removing a removed watch should fail with EINVAL");
+    }
+
+    return teardown_watch(ifd);
+}
+
+int test_nonblock_and_invalid_args() {
+    int ifd, wd;
+    if (setup_watch(&ifd, &wd, IN_CREATE) < 0) {
+        return -1;
+    }
+
+    char buf[EVENT_BUF_LEN];
+    if (read(ifd, buf, sizeof(buf)) >= 0 || errno != EAGAIN) {
+        THROW_ERROR("reading an empty inotify file should fail with EAGAIN");
+    }
+    if (inotify_add_watch(ifd, watched_dir, 0) >= 0 || errno != EINVAL) {
+        THROW_ERROR("adding a watch without events should fail with EINVAL");
+    }
+    if (inotify_add_watch(ifd, watched_dir, IN_CREATE | IN_MASK_CREATE) >= 0 ||
+            errno != EEXIST) {
+        THROW_ERROR("IN_MASK_CREATE on a watched inode should fail with EEXIST");
+    }
+    if (inotify_init1(~(IN_NONBLOCK | IN_CLOEXEC)) >= 0 || errno != EINVAL) {
+        THROW_ERROR("invalid flags should be rejected");
+    }
+
+    return teardown_watch(ifd);
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_create_modify_delete),
+    TEST_CASE(test_move),
+    TEST_CASE(test_modify_through_stale_path),
+    TEST_CASE(test_rm_watch),
+    TEST_CASE(test_nonblock_and_invalid_args),
+};
+
+int main() {
+    int ret = test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+    return ret;
+}
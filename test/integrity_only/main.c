@@ -0,0 +1,103 @@
+#include <sys/stat.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+// The integrity-only SEFS are mounted by the Makefile, which tampers with the
+// file of the second one on the host
+#define INTACT_DIR          "/integrity_only/intact"
+#define TAMPERED_DIR        "/integrity_only/tampered"
+#define FILE_CONTENT        "intact content"
+#define TAMPERED_CONTENT    "tampered value"
+
+static int read_file(const char *path, char *buf, size_t buf_len) {
+    int fd = open(path, O_RDONLY);
+    if (fd < 0) {
+        return -1;
+    }
+    ssize_t len = read(fd, buf, buf_len - 1);
+    close(fd);
+    if (len < 0) {
+        return -1;
+    }
+    buf[len] = '\0';
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+int test_read_intact_file() {
+    char buf[64];
+    if (read_file(INTACT_DIR "/file", buf, sizeof(buf)) < 0) {
+        THROW_ERROR("failed to read the file");
+    }
+    if (strcmp(buf, FILE_CONTENT) != 0) {
+        THROW_ERROR("the content of the file is wrong");
+    }
+    return 0;
+}
+
+int test_read_tampered_file() {
+    char buf[64];
+    if (read_file(TAMPERED_DIR "/file", buf, sizeof(buf)) == 0) {
+        if (strcmp(buf, TAMPERED_CONTENT) == 0) {
+            THROW_ERROR("the tampered content is read");
+        }
+        THROW_ERROR("the tampered file is read");
+    }
+    return 0;
+}
+
+int test_update_rejected() {
+    const char *file_path = INTACT_DIR "/file";
+
+    // The file may be opened for writing, but cannot be written
+    int fd = open(file_path, O_WRONLY);
+    if (fd < 0 && errno != EROFS) {
+        THROW_ERROR("failed to open the file");
+    }
+    if (fd >= 0) {
+        int ret = write(fd, TAMPERED_CONTENT, strlen(TAMPERED_CONTENT));
+        int err = errno;
+        close(fd);
+        if (ret >= 0 || err != EROFS) {
+            THROW_ERROR("the write should fail with EROFS");
+        }
+    }
+    if (truncate(file_path, 0) == 0 || errno != EROFS) {
+        THROW_ERROR("the truncate should fail with EROFS");
+    }
+    if (creat(INTACT_DIR "/new_file", 0666) >= 0 || errno != EROFS) {
+        THROW_ERROR("the creat should fail with EROFS");
+    }
+    if (mkdir(INTACT_DIR "/new_dir", 0755) == 0 || errno != EROFS) {
+        THROW_ERROR("the mkdir should fail with EROFS");
+    }
+    if (unlink(file_path) == 0 || errno != EROFS) {
+        THROW_ERROR("the unlink should fail with EROFS");
+    }
+    // The file is intact after all the updates are rejected
+    return test_read_intact_file();
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_read_intact_file),
+    TEST_CASE(test_read_tampered_file),
+    TEST_CASE(test_update_rejected),
+};
+
+int main() {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
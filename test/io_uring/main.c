@@ -0,0 +1,385 @@
+#include <sys/mman.h>
+#include <sys/socket.h>
+#include <sys/syscall.h>
+#include <sys/uio.h>
+#include <linux/io_uring.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdint.h>
+#include <time.h>
+#include <unistd.h>
+#include <string.h>
+
+#include "test.h"
+
+#define FILE_PATH   "/root/test_io_uring.txt"
+#define MSG         "hello, io_uring"
+#define MSG_LEN     (sizeof(MSG) - 1)
+#define ENTRIES     8
+
+// ============================================================================
+// Helper function
+// ============================================================================
+struct ring {
+    int fd;
+    unsigned *sq_tail;
+    unsigned *sq_mask;
+    unsigned *sq_array;
+    struct io_uring_sqe *sqes;
+    unsigned *cq_head;
+    unsigned *cq_tail;
+    unsigned *cq_mask;
+    struct io_uring_cqe *cqes;
+    void *ring_ptr;
+    size_t ring_size;
+    size_t sqes_size;
+};
+
+static int ring_init(struct ring *ring) {
+    struct io_uring_params params;
+    memset(&params, 0, sizeof(params));
+    ring->fd = syscall(__NR_io_uring_setup, ENTRIES, &params);
+    if (ring->fd < 0) {
+        THROW_ERROR("failed to set up the io_uring");
+    }
+    if (params.sq_entries != ENTRIES || params.cq_entries != 2 * ENTRIES ||
+            !(params.features & IORING_FEAT_SINGLE_MMAP)) {
+        close(ring->fd);
+        THROW_ERROR("the params of the io_uring are not expected");
+    }
+
+    size_t sq_size = params.sq_off.array + params.sq_entries * sizeof(unsigned);
+    size_t cq_size = params.cq_off.cqes + params.cq_entries * sizeof(struct io_uring_cqe);
+    ring->ring_size = sq_size > cq_size ? sq_size : cq_size;
+    ring->sqes_size = params.sq_entries * sizeof(struct io_uring_sqe);
+    char *ptr = mmap(NULL, ring->ring_size, PROT_READ | PROT_WRITE, MAP_SHARED,
+                     ring->fd, IORING_OFF_SQ_RING);
+    ring->sqes = mmap(NULL, ring->sqes_size, PROT_READ | PROT_WRITE, MAP_SHARED,
+                      ring->fd, IORING_OFF_SQES);
+    if (ptr == MAP_FAILED || ring->sqes == MAP_FAILED) {
+        close(ring->fd);
+        THROW_ERROR("failed to mmap the io_uring");
+    }
+    ring->ring_ptr = ptr;
+    ring->sq_tail = (unsigned *)(ptr + params.sq_off.tail);
+    ring->sq_mask = (unsigned *)(ptr + params.sq_off.ring_mask);
+    ring->sq_array = (unsigned *)(ptr + params.sq_off.array);
+    ring->cq_head = (unsigned *)(ptr + params.cq_off.head);
+    ring->cq_tail = (unsigned *)(ptr + params.cq_off.tail);
+    ring->cq_mask = (unsigned *)(ptr + params.cq_off.ring_mask);
+    ring->cqes = (struct io_uring_cqe *)(ptr + params.cq_off.cqes);
+    return 0;
+}
+
+static void ring_exit(struct ring *ring) {
+    munmap(ring->ring_ptr, ring->ring_size);
+    munmap(ring->sqes, ring->sqes_size);
+    close(ring->fd);
+}
+
+static struct io_uring_sqe *get_sqe(struct ring *ring, int opcode, int fd, uint64_t user_data) {
+    unsigned tail = *ring->sq_tail;
+    unsigned idx = tail & *ring->sq_mask;
+    struct io_uring_sqe *sqe = &ring->sqes[idx];
+    memset(sqe, 0, sizeof(*sqe));
+    sqe->opcode = opcode;
+    sqe->fd = fd;
+    sqe->user_data = user_data;
+    ring->sq_array[idx] = idx;
+    __atomic_store_n(ring->sq_tail, tail + 1, __ATOMIC_RELEASE);
+    return sqe;
+}
+
+static int ring_enter(struct ring *ring, unsigned to_submit, unsigned min_complete) {
+    unsigned flags = min_complete > 0 ? IORING_ENTER_GETEVENTS : 0;
+    return syscall(__NR_io_uring_enter, ring->fd, to_submit, min_complete, flags, NULL, 0);
+}
+
+static int pop_cqe(struct ring *ring, struct io_uring_cqe *cqe) {
+    unsigned head = *ring->cq_head;
+    if (head == __atomic_load_n(ring->cq_tail, __ATOMIC_ACQUIRE)) {
+        return -1;
+    }
+    *cqe = ring->cqes[head & *ring->cq_mask];
+    __atomic_store_n(ring->cq_head, head + 1, __ATOMIC_RELEASE);
+    return 0;
+}
+
+static int check_cqe(struct ring *ring, uint64_t user_data, int res) {
+    struct io_uring_cqe cqe;
+    if (pop_cqe(ring, &cqe) < 0) {
+        THROW_ERROR("no CQE is posted");
+    }
+    if (cqe.user_data != user_data || cqe.res != res) {
+        THROW_ERROR("the CQE is not the expected one");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases for io_uring
+// ============================================================================
+static int test_io_uring_nop() {
+    struct ring ring;
+    if (ring_init(&ring) < 0) {
+        return -1;
+    }
+    int ret = -1;
+    get_sqe(&ring, IORING_OP_NOP, -1, 1);
+    get_sqe(&ring, IORING_OP_NOP, -1, 2);
+    if (ring_enter(&ring, 2, 2) != 2 || check_cqe(&ring, 1, 0) < 0 ||
+            check_cqe(&ring, 2, 0) < 0) {
+        printf("\t\tERROR: failed to do NOP\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    ring_exit(&ring);
+    return ret;
+}
+
+static int test_io_uring_writev_readv() {
+    struct ring ring;
+    if (ring_init(&ring) < 0) {
+        return -1;
+    }
+    int fd = open(FILE_PATH, O_RDWR | O_CREAT | O_TRUNC, 0666);
+    if (fd < 0) {
+        ring_exit(&ring);
+        THROW_ERROR("failed to open the file");
+    }
+
+    int ret = -1;
+    char buf[32] = {0};
+    struct iovec write_iov = { .iov_base = MSG, .iov_len = MSG_LEN };
+    struct iovec read_iov = { .iov_base = buf, .iov_len = sizeof(buf) };
+    struct io_uring_sqe *sqe = get_sqe(&ring, IORING_OP_WRITEV, fd, 1);
+    sqe->addr = (uint64_t)(uintptr_t)&write_iov;
+    sqe->len = 1;
+    // The read is linked to the write, so it starts after the write is done
+    sqe->flags = IOSQE_IO_LINK;
+    sqe = get_sqe(&ring, IORING_OP_READV, fd, 2);
+    sqe->addr = (uint64_t)(uintptr_t)&read_iov;
+    sqe->len = 1;
+    if (ring_enter(&ring, 2, 2) != 2 || check_cqe(&ring, 1, MSG_LEN) < 0 ||
+            check_cqe(&ring, 2, MSG_LEN) < 0 || strcmp(buf, MSG) != 0) {
+        printf("\t\tERROR: failed to write and read the file\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    close(fd);
+    unlink(FILE_PATH);
+    ring_exit(&ring);
+    return ret;
+}
+
+static int test_io_uring_link_cancelled() {
+    struct ring ring;
+    if (ring_init(&ring) < 0) {
+        return -1;
+    }
+    int ret = -1;
+    char buf[8];
+    struct iovec iov = { .iov_base = buf, .iov_len = sizeof(buf) };
+    struct io_uring_sqe *sqe = get_sqe(&ring, IORING_OP_READV, -1, 1);
+    sqe->addr = (uint64_t)(uintptr_t)&iov;
+    sqe->len = 1;
+    sqe->flags = IOSQE_IO_LINK;
+    get_sqe(&ring, IORING_OP_NOP, -1, 2);
+    if (ring_enter(&ring, 2, 2) != 2 || check_cqe(&ring, 1, -EBADF) < 0 ||
+            check_cqe(&ring, 2, -ECANCELED) < 0) {
+        printf("\t\tERROR: the failed request should cancel the linked one\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    ring_exit(&ring);
+    return ret;
+}
+
+static int test_io_uring_pending_read() {
+    struct ring ring;
+    if (ring_init(&ring) < 0) {
+        return -1;
+    }
+    int pipe_fds[2];
+    if (pipe(pipe_fds) < 0) {
+        ring_exit(&ring);
+        THROW_ERROR("failed to create a pipe");
+    }
+
+    int ret = -1;
+    char buf[32] = {0};
+    struct iovec iov = { .iov_base = buf, .iov_len = sizeof(buf) };
+    struct io_uring_sqe *sqe = get_sqe(&ring, IORING_OP_READV, pipe_fds[0], 1);
+    sqe->addr = (uint64_t)(uintptr_t)&iov;
+    sqe->len = 1;
+    get_sqe(&ring, IORING_OP_NOP, -1, 2);
+    // The read of the empty pipe does not block the NOP after it
+    if (ring_enter(&ring, 2, 1) != 2 || check_cqe(&ring, 2, 0) < 0) {
+        printf("\t\tERROR: the read of the empty pipe should be pending\n");
+        goto out;
+    }
+    if (write(pipe_fds[1], MSG, MSG_LEN) != MSG_LEN || ring_enter(&ring, 0, 1) != 0 ||
+            check_cqe(&ring, 1, MSG_LEN) < 0 || strcmp(buf, MSG) != 0) {
+        printf("\t\tERROR: the pending read should be done\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    close(pipe_fds[0]);
+    close(pipe_fds[1]);
+    ring_exit(&ring);
+    return ret;
+}
+
+static int connect_udp_sockets(int *sock, int *peer) {
+    struct sockaddr_in addr = {
+        .sin_family = AF_INET,
+        .sin_addr.s_addr = htonl(INADDR_LOOPBACK),
+        .sin_port = 0,
+    };
+    socklen_t addr_len = sizeof(addr);
+    *sock = socket(AF_INET, SOCK_DGRAM, 0);
+    *peer = socket(AF_INET, SOCK_DGRAM, 0);
+    if (*sock < 0 || *peer < 0) {
+        THROW_ERROR("failed to create the sockets");
+    }
+    if (bind(*sock, (struct sockaddr *)&addr, sizeof(addr)) < 0 ||
+            getsockname(*sock, (struct sockaddr *)&addr, &addr_len) < 0 ||
+            connect(*peer, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        close(*sock);
+        close(*peer);
+        THROW_ERROR("failed to connect the sockets");
+    }
+    return 0;
+}
+
+static int test_io_uring_recv_host_socket() {
+    struct ring ring;
+    if (ring_init(&ring) < 0) {
+        return -1;
+    }
+    int sock, peer;
+    if (connect_udp_sockets(&sock, &peer) < 0) {
+        ring_exit(&ring);
+        return -1;
+    }
+
+    int ret = -1;
+    char buf[32] = {0};
+    struct io_uring_sqe *sqe = get_sqe(&ring, IORING_OP_RECV, sock, 1);
+    sqe->addr = (uint64_t)(uintptr_t)buf;
+    sqe->len = sizeof(buf);
+    get_sqe(&ring, IORING_OP_NOP, -1, 2);
+    // The receive is in progress until the data arrives, without blocking the NOP
+    if (ring_enter(&ring, 2, 1) != 2 || check_cqe(&ring, 2, 0) < 0) {
+        printf("\t\tERROR: the receive of the host socket should be in progress\n");
+        goto out;
+    }
+    if (send(peer, MSG, MSG_LEN, 0) != MSG_LEN || ring_enter(&ring, 0, 1) != 0 ||
+            check_cqe(&ring, 1, MSG_LEN) < 0 || strcmp(buf, MSG) != 0) {
+        printf("\t\tERROR: the receive of the host socket should be done\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    ring_exit(&ring);
+    close(sock);
+    close(peer);
+    return ret;
+}
+
+static int test_io_uring_timeout() {
+    struct ring ring;
+    if (ring_init(&ring) < 0) {
+        return -1;
+    }
+    int ret = -1;
+    struct __kernel_timespec ts = { .tv_sec = 0, .tv_nsec = 10 * 1000 * 1000 };
+    struct io_uring_sqe *sqe = get_sqe(&ring, IORING_OP_TIMEOUT, -1, 1);
+    sqe->addr = (uint64_t)(uintptr_t)&ts;
+    sqe->len = 1;
+    if (ring_enter(&ring, 1, 1) != 1 || check_cqe(&ring, 1, -ETIME) < 0) {
+        printf("\t\tERROR: the timeout should expire\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    ring_exit(&ring);
+    return ret;
+}
+
+static int test_io_uring_fixed_files() {
+    struct ring ring;
+    if (ring_init(&ring) < 0) {
+        return -1;
+    }
+    int fd = open(FILE_PATH, O_RDWR | O_CREAT | O_TRUNC, 0666);
+    if (fd < 0 || write(fd, MSG, MSG_LEN) != MSG_LEN) {
+        ring_exit(&ring);
+        THROW_ERROR("failed to prepare the file");
+    }
+
+    int ret = -1;
+    if (syscall(__NR_io_uring_register, ring.fd, IORING_REGISTER_FILES, &fd, 1) < 0) {
+        printf("\t\tERROR: failed to register the file\n");
+        goto out;
+    }
+    char buf[32] = {0};
+    struct iovec iov = { .iov_base = buf, .iov_len = sizeof(buf) };
+    struct io_uring_sqe *sqe = get_sqe(&ring, IORING_OP_READV, 0, 1);
+    sqe->addr = (uint64_t)(uintptr_t)&iov;
+    sqe->len = 1;
+    sqe->off = 0;
+    sqe->flags = IOSQE_FIXED_FILE;
+    if (ring_enter(&ring, 1, 1) != 1 || check_cqe(&ring, 1, MSG_LEN) < 0 ||
+            strcmp(buf, MSG) != 0) {
+        printf("\t\tERROR: failed to read the fixed file\n");
+        goto out;
+    }
+    if (syscall(__NR_io_uring_register, ring.fd, IORING_UNREGISTER_FILES, NULL, 0) < 0) {
+        printf("\t\tERROR: failed to unregister the files\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    close(fd);
+    unlink(FILE_PATH);
+    ring_exit(&ring);
+    return ret;
+}
+
+static int test_io_uring_invalid() {
+    struct io_uring_params params;
+    memset(&params, 0, sizeof(params));
+    if (syscall(__NR_io_uring_setup, 0, &params) >= 0 || errno != EINVAL) {
+        THROW_ERROR("io_uring_setup should fail with EINVAL given zero entries");
+    }
+    if (syscall(__NR_io_uring_enter, STDOUT_FILENO, 0, 0, 0, NULL, 0) >= 0 ||
+            errno != EOPNOTSUPP) {
+        THROW_ERROR("io_uring_enter should fail with EOPNOTSUPP given a non-io_uring fd");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+static test_case_t test_cases[] = {
+    TEST_CASE(test_io_uring_nop),
+    TEST_CASE(test_io_uring_writev_readv),
+    TEST_CASE(test_io_uring_link_cancelled),
+    TEST_CASE(test_io_uring_pending_read),
+    TEST_CASE(test_io_uring_recv_host_socket),
+    TEST_CASE(test_io_uring_timeout),
+    TEST_CASE(test_io_uring_fixed_files),
+    TEST_CASE(test_io_uring_invalid),
+};
+
+int main() {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -1,4 +1,7 @@
 #include <net/if.h>
+#include <net/if_arp.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
 #include <sys/socket.h>
 #include <sys/types.h>
 #include <sys/ioctl.h>
@@ -227,6 +230,75 @@ static int do_SGXIOC_CREATE_AND_VERIFY_REPORT(int sgx_fd) {
     return 0;
 }
 
+static int read_enclave_identity(const char *name, char *buf, size_t buf_size) {
+    char path[64];
+    snprintf(path, sizeof(path), "/proc/sgx/%s", name);
+    int fd = open(path, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the identity of the enclave");
+    }
+    ssize_t len = read(fd, buf, buf_size - 1);
+    close(fd);
+    if (len <= 0 || buf[len - 1] != '\n') {
+        THROW_ERROR("failed to read the identity of the enclave");
+    }
+    buf[len - 1] = '\0';
+    return 0;
+}
+
+static int check_hex_identity(const char *name, const uint8_t *expected, size_t size) {
+    char buf[128];
+    if (read_enclave_identity(name, buf, sizeof(buf)) < 0) {
+        return -1;
+    }
+    if (strlen(buf) != size * 2) {
+        THROW_ERROR("the length of the identity is wrong");
+    }
+    for (size_t i = 0; i < size; i++) {
+        char hex[3];
+        snprintf(hex, sizeof(hex), "%02x", expected[i]);
+        if (strncmp(buf + i * 2, hex, 2) != 0) {
+            THROW_ERROR("the identity is not the same as the report");
+        }
+    }
+    return 0;
+}
+
+static int check_int_identity(const char *name, long expected) {
+    char buf[32];
+    if (read_enclave_identity(name, buf, sizeof(buf)) < 0) {
+        return -1;
+    }
+    if (strtol(buf, NULL, 10) != expected) {
+        THROW_ERROR("the identity is not the same as the report");
+    }
+    return 0;
+}
+
+static int do_read_enclave_identity(int sgx_fd) {
+    sgx_report_t report;
+    sgxioc_create_report_arg_t arg = {
+        .target_info = NULL,
+        .report_data = NULL,
+        .report = &report
+    };
+    if (ioctl(sgx_fd, SGXIOC_CREATE_REPORT, &arg) < 0) {
+        THROW_ERROR("failed to create report");
+    }
+
+    // The identity in /proc/sgx is the same as the one in the report
+    sgx_report_body_t *body = &report.body;
+    int is_debug = (body->attributes.flags & SGX_FLAGS_DEBUG) != 0;
+    if (check_hex_identity("mr_enclave", body->mr_enclave.m, sizeof(body->mr_enclave.m)) < 0 ||
+            check_hex_identity("mr_signer", body->mr_signer.m, sizeof(body->mr_signer.m)) < 0 ||
+            check_int_identity("isv_prod_id", body->isv_prod_id) < 0 ||
+            check_int_identity("isv_svn", body->isv_svn) < 0 ||
+            check_int_identity("debug", is_debug) < 0) {
+        return -1;
+    }
+    return 0;
+}
+
 #ifndef OCCLUM_DISABLE_DCAP
 #define REPORT_BODY_OFFSET 48
 static int generate_and_verify_dcap_quote(int sgx_fd) {
@@ -365,6 +437,10 @@ int test_sgx_ioctl_SGXIOC_CREATE_AND_VERIFY_REPORT(void) {
     return do_sgx_ioctl_test(do_SGXIOC_CREATE_AND_VERIFY_REPORT);
 }
 
+int test_read_enclave_identity(void) {
+    return do_sgx_ioctl_test(do_read_enclave_identity);
+}
+
 #define CONFIG_SIZE  512
 int test_ioctl_SIOCGIFCONF(void) {
     struct ifreq *req;
@@ -457,6 +533,107 @@ int test_ioctl_FIONBIO(void) {
     return 0;
 }
 
+int test_ioctl_FIONBIO_unix_socket(void) {
+    int socks[2];
+    if (socketpair(AF_UNIX, SOCK_STREAM, 0, socks) < 0) {
+        THROW_ERROR("failed to create a unix socket pair");
+    }
+
+    int ret = -1;
+    int on = 1, off = 0;
+    if (ioctl(socks[0], FIONBIO, &on) < 0 || !(fcntl(socks[0], F_GETFL) & O_NONBLOCK)) {
+        printf("\t\tERROR: failed to set the nonblocking mode by FIONBIO\n");
+        goto out;
+    }
+    char buf[8];
+    if (read(socks[0], buf, sizeof(buf)) >= 0 || errno != EAGAIN) {
+        printf("\t\tERROR: read should fail with EAGAIN in the nonblocking mode\n");
+        goto out;
+    }
+    if (ioctl(socks[0], FIONBIO, &off) < 0 || (fcntl(socks[0], F_GETFL) & O_NONBLOCK)) {
+        printf("\t\tERROR: failed to clear the nonblocking mode by FIONBIO\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    close(socks[0]);
+    close(socks[1]);
+    return ret;
+}
+
+static int check_FIONREAD(int read_fd, int write_fd) {
+    const char msg[] = "hello";
+    int nread = -1;
+    if (ioctl(read_fd, FIONREAD, &nread) < 0 || nread != 0) {
+        THROW_ERROR("FIONREAD should be zero before any write");
+    }
+    if (write(write_fd, msg, sizeof(msg)) != sizeof(msg)) {
+        THROW_ERROR("failed to write");
+    }
+    if (ioctl(read_fd, FIONREAD, &nread) < 0 || nread != sizeof(msg)) {
+        THROW_ERROR("FIONREAD should be the number of bytes written");
+    }
+    return 0;
+}
+
+int test_ioctl_FIONREAD(void) {
+    int fds[2];
+    if (pipe(fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+    int ret = check_FIONREAD(fds[0], fds[1]);
+    close(fds[0]);
+    close(fds[1]);
+    if (ret < 0) {
+        return -1;
+    }
+
+    if (socketpair(AF_UNIX, SOCK_STREAM, 0, fds) < 0) {
+        THROW_ERROR("failed to create a unix socket pair");
+    }
+    ret = check_FIONREAD(fds[0], fds[1]);
+    close(fds[0]);
+    close(fds[1]);
+    return ret;
+}
+
+int test_ioctl_SIOCGIFADDR_and_SIOCGIFHWADDR(void) {
+    // The interfaces can be queried by sockets of any kind
+    int sock = socket(AF_UNIX, SOCK_DGRAM, 0);
+    if (sock < 0) {
+        THROW_ERROR("failed to create a unix socket");
+    }
+
+    int ret = -1;
+    struct ifreq req;
+    memset(&req, 0, sizeof(req));
+    strncpy(req.ifr_name, "lo", IFNAMSIZ - 1);
+    if (ioctl(sock, SIOCGIFADDR, &req) < 0) {
+        printf("\t\tERROR: failed to get the address of lo\n");
+        goto out;
+    }
+    struct sockaddr_in *addr = (struct sockaddr_in *)&req.ifr_addr;
+    if (addr->sin_family != AF_INET || addr->sin_addr.s_addr != htonl(INADDR_LOOPBACK)) {
+        printf("\t\tERROR: the address of lo is not the loopback address\n");
+        goto out;
+    }
+    if (ioctl(sock, SIOCGIFHWADDR, &req) < 0 ||
+            req.ifr_hwaddr.sa_family != ARPHRD_LOOPBACK) {
+        printf("\t\tERROR: failed to get the hardware address of lo\n");
+        goto out;
+    }
+
+    strncpy(req.ifr_name, "no_such_if", IFNAMSIZ - 1);
+    if (ioctl(sock, SIOCGIFADDR, &req) == 0 || errno != ENODEV) {
+        printf("\t\tERROR: querying an unknown interface should fail with ENODEV\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    close(sock);
+    return ret;
+}
+
 // ============================================================================
 // Test suite
 // ============================================================================
@@ -468,11 +645,15 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_sgx_ioctl_SGXIOC_GEN_EPID_QUOTE),
     TEST_CASE(test_sgx_ioctl_SGXIOC_SELF_TARGET),
     TEST_CASE(test_sgx_ioctl_SGXIOC_CREATE_AND_VERIFY_REPORT),
+    TEST_CASE(test_read_enclave_identity),
 #ifndef OCCLUM_DISABLE_DCAP
     TEST_CASE(test_sgx_ioctl_SGXIOC_GENERATE_AND_VERIFY_DCAP_QUOTE),
 #endif
     TEST_CASE(test_ioctl_SIOCGIFCONF),
     TEST_CASE(test_ioctl_FIONBIO),
+    TEST_CASE(test_ioctl_FIONBIO_unix_socket),
+    TEST_CASE(test_ioctl_FIONREAD),
+    TEST_CASE(test_ioctl_SIOCGIFADDR_and_SIOCGIFHWADDR),
 };
 
 int main() {
@@ -0,0 +1,224 @@
+#include <sys/resource.h>
+#include <sys/time.h>
+#include <errno.h>
+#include <signal.h>
+#include <string.h>
+#include <time.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define TIMEOUT_MS          2000
+#define TIMER_MS            100
+
+static volatile sig_atomic_t num_signals = 0;
+
+static void handle_timer_signal(int signum) {
+    num_signals++;
+}
+
+static int install_handler(int signum, struct sigaction *old_action) {
+    struct sigaction new_action;
+    memset(&new_action, 0, sizeof(new_action));
+    new_action.sa_handler = handle_timer_signal;
+    if (sigaction(signum, &new_action, old_action) < 0) {
+        THROW_ERROR("failed to set the signal handler");
+    }
+    num_signals = 0;
+    return 0;
+}
+
+static long timeval_to_ms(const struct timeval *tv) {
+    return tv->tv_sec * 1000 + tv->tv_usec / 1000;
+}
+
+static long cpu_time_ms(void) {
+    struct rusage usage;
+    if (getrusage(RUSAGE_SELF, &usage) < 0) {
+        return -1;
+    }
+    return timeval_to_ms(&usage.ru_utime) + timeval_to_ms(&usage.ru_stime);
+}
+
+// Sleep until the given number of signals are received or timeout
+static int wait_for_signals(int expected_signals) {
+    struct timespec interval = { .tv_sec = 0, .tv_nsec = 10 * 1000 * 1000 };
+    for (int ms = 0; ms < TIMEOUT_MS && num_signals < expected_signals; ms += 10) {
+        nanosleep(&interval, NULL);
+    }
+    return num_signals >= expected_signals ? 0 : -1;
+}
+
+// Consume the CPU until a signal is received or timeout
+static int busy_loop_for_signal(void) {
+    long end_ms = cpu_time_ms() + TIMEOUT_MS;
+    volatile unsigned long counter = 0;
+    while (num_signals == 0 && cpu_time_ms() < end_ms) {
+        for (int i = 0; i < 1000000; i++) {
+            counter++;
+        }
+    }
+    return num_signals > 0 ? 0 : -1;
+}
+
+static int disarm_timer(int which) {
+    struct itimerval zero_value;
+    memset(&zero_value, 0, sizeof(zero_value));
+    return setitimer(which, &zero_value, NULL);
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_alarm() {
+    struct sigaction old_action;
+    if (install_handler(SIGALRM, &old_action) < 0) {
+        return -1;
+    }
+    if (alarm(1) != 0) {
+        THROW_ERROR("there should be no previous alarm");
+    }
+    int ret = wait_for_signals(1);
+    sigaction(SIGALRM, &old_action, NULL);
+    if (ret < 0) {
+        THROW_ERROR("SIGALRM is not received");
+    }
+    return 0;
+}
+
+static int test_alarm_remaining_seconds() {
+    if (alarm(10) != 0) {
+        THROW_ERROR("there should be no previous alarm");
+    }
+    // The remaining time is rounded to the nearest second
+    unsigned int remaining = alarm(0);
+    if (remaining != 10) {
+        THROW_ERROR("the remaining seconds of the alarm is not as expected");
+    }
+    if (alarm(0) != 0) {
+        THROW_ERROR("the alarm should have been canceled");
+    }
+    return 0;
+}
+
+static int test_getitimer_and_setitimer() {
+    struct itimerval new_value = {
+        .it_interval = { .tv_sec = 1, .tv_usec = 0 },
+        .it_value = { .tv_sec = 10, .tv_usec = 0 },
+    };
+    struct itimerval curr_value, old_value;
+    if (setitimer(ITIMER_REAL, &new_value, NULL) < 0) {
+        THROW_ERROR("failed to setitimer");
+    }
+    if (getitimer(ITIMER_REAL, &curr_value) < 0) {
+        THROW_ERROR("failed to getitimer");
+    }
+    if (curr_value.it_value.tv_sec > 10 || curr_value.it_value.tv_sec < 9 ||
+            curr_value.it_interval.tv_sec != 1 || curr_value.it_interval.tv_usec != 0) {
+        THROW_ERROR("the current value of the timer is not as expected");
+    }
+
+    struct itimerval zero_value;
+    memset(&zero_value, 0, sizeof(zero_value));
+    if (setitimer(ITIMER_REAL, &zero_value, &old_value) < 0) {
+        THROW_ERROR("failed to disarm the timer");
+    }
+    if (old_value.it_value.tv_sec > 10 || old_value.it_value.tv_sec < 9 ||
+            old_value.it_interval.tv_sec != 1) {
+        THROW_ERROR("the old value of the timer is not as expected");
+    }
+    if (getitimer(ITIMER_REAL, &curr_value) < 0) {
+        THROW_ERROR("failed to getitimer");
+    }
+    if (curr_value.it_value.tv_sec != 0 || curr_value.it_value.tv_usec != 0) {
+        THROW_ERROR("the timer should have been disarmed");
+    }
+    return 0;
+}
+
+static int test_periodic_itimer_real() {
+    struct sigaction old_action;
+    if (install_handler(SIGALRM, &old_action) < 0) {
+        return -1;
+    }
+    struct itimerval new_value = {
+        .it_interval = { .tv_sec = 0, .tv_usec = TIMER_MS * 1000 },
+        .it_value = { .tv_sec = 0, .tv_usec = TIMER_MS * 1000 },
+    };
+    if (setitimer(ITIMER_REAL, &new_value, NULL) < 0) {
+        THROW_ERROR("failed to setitimer");
+    }
+    int ret = wait_for_signals(3);
+    disarm_timer(ITIMER_REAL);
+    sigaction(SIGALRM, &old_action, NULL);
+    if (ret < 0) {
+        THROW_ERROR("SIGALRM is not received periodically");
+    }
+    return 0;
+}
+
+static int test_cpu_itimer(int which, int signum) {
+    struct sigaction old_action;
+    if (install_handler(signum, &old_action) < 0) {
+        return -1;
+    }
+    struct itimerval new_value = {
+        .it_interval = { .tv_sec = 0, .tv_usec = 0 },
+        .it_value = { .tv_sec = 0, .tv_usec = TIMER_MS * 1000 },
+    };
+    if (setitimer(which, &new_value, NULL) < 0) {
+        THROW_ERROR("failed to setitimer");
+    }
+    int ret = busy_loop_for_signal();
+    disarm_timer(which);
+    sigaction(signum, &old_action, NULL);
+    if (ret < 0) {
+        THROW_ERROR("the signal of the CPU timer is not received");
+    }
+    return 0;
+}
+
+static int test_itimer_virtual() {
+    return test_cpu_itimer(ITIMER_VIRTUAL, SIGVTALRM);
+}
+
+static int test_itimer_prof() {
+    return test_cpu_itimer(ITIMER_PROF, SIGPROF);
+}
+
+static int test_setitimer_with_invalid_args() {
+    struct itimerval new_value = {
+        .it_interval = { .tv_sec = 0, .tv_usec = 0 },
+        .it_value = { .tv_sec = 1, .tv_usec = 0 },
+    };
+    if (setitimer(100, &new_value, NULL) == 0 || errno != EINVAL) {
+        THROW_ERROR("setitimer with an invalid which should fail");
+    }
+    new_value.it_value.tv_usec = 1000000;
+    if (setitimer(ITIMER_REAL, &new_value, NULL) == 0 || errno != EINVAL) {
+        THROW_ERROR("setitimer with an invalid timeval should fail");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_alarm),
+    TEST_CASE(test_alarm_remaining_seconds),
+    TEST_CASE(test_getitimer_and_setitimer),
+    TEST_CASE(test_periodic_itimer_real),
+    TEST_CASE(test_itimer_virtual),
+    TEST_CASE(test_itimer_prof),
+    TEST_CASE(test_setitimer_with_invalid_args),
+};
+
+int main(int argc, const char *argv[]) {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
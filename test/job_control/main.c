@@ -0,0 +1,277 @@
+#include <sys/wait.h>
+#include <errno.h>
+#include <signal.h>
+#include <spawn.h>
+#include <stdlib.h>
+#include <string.h>
+#include <termios.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define CHILD_PROG          "/bin/job_control"
+#define NONEXISTENT_PID     99999
+
+static int spawn_child(const char *cmd, pid_t *child_pid) {
+    const char *child_argv[3] = { CHILD_PROG, cmd, NULL };
+    if (posix_spawn(child_pid, CHILD_PROG, NULL, NULL,
+                    (char *const *)child_argv, NULL) != 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+    return 0;
+}
+
+static int wait_for_exit(pid_t child_pid) {
+    int status = 0;
+    if (waitpid(child_pid, &status, 0) != child_pid) {
+        THROW_ERROR("failed to wait for the child");
+    }
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        THROW_ERROR("the child failed");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Child processes
+// ============================================================================
+
+// Wait until killed by a signal
+static int child_wait_for_signal() {
+    for (int i = 0; i < 10; i++) {
+        sleep(1);
+    }
+    return -1;
+}
+
+// Read the terminal in a background process group, which stops the process
+static int child_read_in_background() {
+    if (setpgid(0, 0) < 0) {
+        return -1;
+    }
+    char buf[1];
+    read(STDIN_FILENO, buf, sizeof(buf));
+    return -1;
+}
+
+static int child_access_terminal_in_background() {
+    if (setpgid(0, 0) < 0) {
+        return -1;
+    }
+    // The terminal is controlled by the session, but not in the foreground
+    if (tcgetpgrp(STDIN_FILENO) == getpgrp() || tcgetsid(STDIN_FILENO) != getsid(0)) {
+        return -1;
+    }
+
+    // Reading the terminal fails if SIGTTIN is ignored
+    signal(SIGTTIN, SIG_IGN);
+    char buf[1];
+    if (read(STDIN_FILENO, buf, sizeof(buf)) != -1 || errno != EIO) {
+        return -1;
+    }
+
+    // Changing the foreground process group is allowed if SIGTTOU is ignored
+    signal(SIGTTOU, SIG_IGN);
+    if (tcsetpgrp(STDIN_FILENO, NONEXISTENT_PID) == 0 || errno != EPERM) {
+        return -1;
+    }
+    pid_t fg_pgid = tcgetpgrp(STDIN_FILENO);
+    if (tcsetpgrp(STDIN_FILENO, getpgrp()) < 0 || tcgetpgrp(STDIN_FILENO) != getpgrp()) {
+        return -1;
+    }
+    // Now the process can read the terminal. Give the terminal back.
+    if (tcsetpgrp(STDIN_FILENO, fg_pgid) < 0) {
+        return -1;
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_stop_and_continue() {
+    pid_t child_pid = 0;
+    if (spawn_child("wait_for_signal", &child_pid) < 0) {
+        return -1;
+    }
+
+    int status = 0;
+    if (kill(child_pid, SIGSTOP) < 0) {
+        THROW_ERROR("failed to stop the child");
+    }
+    if (waitpid(child_pid, &status, WUNTRACED) != child_pid) {
+        THROW_ERROR("failed to wait for the stopped child");
+    }
+    if (!WIFSTOPPED(status) || WSTOPSIG(status) != SIGSTOP) {
+        THROW_ERROR("the child is not stopped");
+    }
+    // The stop is reported only once
+    if (waitpid(child_pid, &status, WUNTRACED | WNOHANG) != 0) {
+        THROW_ERROR("the stopped child should be reported only once");
+    }
+
+    if (kill(child_pid, SIGCONT) < 0) {
+        THROW_ERROR("failed to continue the child");
+    }
+    if (waitpid(child_pid, &status, WCONTINUED) != child_pid) {
+        THROW_ERROR("failed to wait for the continued child");
+    }
+    if (!WIFCONTINUED(status)) {
+        THROW_ERROR("the child is not continued");
+    }
+
+    // A stopped child can be killed
+    if (kill(child_pid, SIGTSTP) < 0) {
+        THROW_ERROR("failed to stop the child");
+    }
+    if (waitpid(child_pid, &status, WUNTRACED) != child_pid ||
+            !WIFSTOPPED(status) || WSTOPSIG(status) != SIGTSTP) {
+        THROW_ERROR("the child is not stopped by SIGTSTP");
+    }
+    if (kill(child_pid, SIGKILL) < 0) {
+        THROW_ERROR("failed to kill the child");
+    }
+    if (waitpid(child_pid, &status, 0) != child_pid) {
+        THROW_ERROR("failed to wait for the killed child");
+    }
+    if (!WIFSIGNALED(status) || WTERMSIG(status) != SIGKILL) {
+        THROW_ERROR("the child is not killed");
+    }
+    return 0;
+}
+
+static volatile int g_sigchld_codes[4];
+static volatile int g_num_sigchld = 0;
+
+static void handle_sigchld(int num, siginfo_t *info, void *context) {
+    if (g_num_sigchld < ARRAY_SIZE(g_sigchld_codes)) {
+        g_sigchld_codes[g_num_sigchld] = info->si_code;
+    }
+    g_num_sigchld++;
+}
+
+static int stop_continue_and_kill(int sa_flags) {
+    struct sigaction new_action, old_action;
+    memset(&new_action, 0, sizeof(new_action));
+    new_action.sa_sigaction = handle_sigchld;
+    new_action.sa_flags = SA_SIGINFO | sa_flags;
+    if (sigaction(SIGCHLD, &new_action, &old_action) < 0) {
+        THROW_ERROR("registering new signal handler failed");
+    }
+    g_num_sigchld = 0;
+
+    pid_t child_pid = 0;
+    if (spawn_child("wait_for_signal", &child_pid) < 0) {
+        return -1;
+    }
+    int status = 0;
+    if (kill(child_pid, SIGSTOP) < 0 ||
+            waitpid(child_pid, &status, WUNTRACED) != child_pid) {
+        THROW_ERROR("failed to stop the child");
+    }
+    if (kill(child_pid, SIGCONT) < 0 ||
+            waitpid(child_pid, &status, WCONTINUED) != child_pid) {
+        THROW_ERROR("failed to continue the child");
+    }
+    if (kill(child_pid, SIGKILL) < 0 || waitpid(child_pid, &status, 0) != child_pid) {
+        THROW_ERROR("failed to kill the child");
+    }
+
+    if (sigaction(SIGCHLD, &old_action, NULL) < 0) {
+        THROW_ERROR("restoring old signal handler failed");
+    }
+    return 0;
+}
+
+static int test_sigchld_on_stop_and_continue() {
+    if (stop_continue_and_kill(0) < 0) {
+        return -1;
+    }
+    if (g_num_sigchld != 3 ||
+            g_sigchld_codes[0] != CLD_STOPPED ||
+            g_sigchld_codes[1] != CLD_CONTINUED ||
+            g_sigchld_codes[2] != CLD_KILLED) {
+        THROW_ERROR("the SIGCHLD signals are not as expected");
+    }
+    return 0;
+}
+
+static int test_sa_nocldstop() {
+    if (stop_continue_and_kill(SA_NOCLDSTOP) < 0) {
+        return -1;
+    }
+    if (g_num_sigchld != 1 || g_sigchld_codes[0] != CLD_KILLED) {
+        THROW_ERROR("SIGCHLD should be sent only on termination");
+    }
+    return 0;
+}
+
+static int test_terminal_foreground_group() {
+    if (tcgetpgrp(STDIN_FILENO) != getpgrp()) {
+        THROW_ERROR("the process group is not in the foreground");
+    }
+    if (tcgetsid(STDIN_FILENO) != getsid(0)) {
+        THROW_ERROR("the terminal does not control the session");
+    }
+
+    pid_t child_pid = 0;
+    if (spawn_child("access_terminal_in_background", &child_pid) < 0) {
+        return -1;
+    }
+    if (wait_for_exit(child_pid) < 0) {
+        return -1;
+    }
+    if (tcgetpgrp(STDIN_FILENO) != getpgrp()) {
+        THROW_ERROR("the terminal is not given back");
+    }
+    return 0;
+}
+
+static int test_sigttin() {
+    pid_t child_pid = 0;
+    if (spawn_child("read_in_background", &child_pid) < 0) {
+        return -1;
+    }
+    int status = 0;
+    if (waitpid(child_pid, &status, WUNTRACED) != child_pid) {
+        THROW_ERROR("failed to wait for the child");
+    }
+    if (!WIFSTOPPED(status) || WSTOPSIG(status) != SIGTTIN) {
+        THROW_ERROR("the child is not stopped by SIGTTIN");
+    }
+    if (kill(child_pid, SIGKILL) < 0 || waitpid(child_pid, &status, 0) != child_pid) {
+        THROW_ERROR("failed to kill the child");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_stop_and_continue),
+    TEST_CASE(test_sigchld_on_stop_and_continue),
+    TEST_CASE(test_sa_nocldstop),
+    TEST_CASE(test_terminal_foreground_group),
+    TEST_CASE(test_sigttin),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 1) {
+        int ret = -1;
+        if (strcmp(argv[1], "wait_for_signal") == 0) {
+            ret = child_wait_for_signal();
+        } else if (strcmp(argv[1], "read_in_background") == 0) {
+            ret = child_read_in_background();
+        } else if (strcmp(argv[1], "access_terminal_in_background") == 0) {
+            ret = child_access_terminal_in_background();
+        }
+        return ret < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
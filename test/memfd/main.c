@@ -0,0 +1,283 @@
+#define _GNU_SOURCE
+#include <sys/mman.h>
+#include <sys/stat.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <spawn.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+#ifndef MFD_CLOEXEC
+#define MFD_CLOEXEC         0x0001U
+#define MFD_ALLOW_SEALING   0x0002U
+#endif
+
+#ifndef F_ADD_SEALS
+#define F_ADD_SEALS         1033
+#define F_GET_SEALS         1034
+#define F_SEAL_SEAL         0x0001
+#define F_SEAL_SHRINK       0x0002
+#define F_SEAL_GROW         0x0004
+#define F_SEAL_WRITE        0x0008
+#endif
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define CHILD_PROG          "/bin/memfd"
+
+static int create_memfd(const char *name, unsigned int flags) {
+    return syscall(__NR_memfd_create, name, flags);
+}
+
+// ============================================================================
+// Child process
+// ============================================================================
+
+// Map the memfd shared and writable until the parent tells the child to exit
+static int child_map_memfd(int fd, int ready_fd, int exit_fd) {
+    void *addr = mmap(NULL, 4096, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    if (addr == MAP_FAILED) {
+        THROW_ERROR("failed to mmap the memfd");
+    }
+    char c = 0;
+    if (write(ready_fd, &c, 1) != 1) {
+        THROW_ERROR("failed to notify the parent");
+    }
+    if (read(exit_fd, &c, 1) != 1) {
+        THROW_ERROR("failed to wait for the parent");
+    }
+    munmap(addr, 4096);
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+int test_read_write() {
+    int fd = create_memfd("test_read_write", MFD_CLOEXEC);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a memfd");
+    }
+
+    const char *msg = "Hello, memfd!";
+    if (write(fd, msg, strlen(msg)) != strlen(msg)) {
+        close(fd);
+        THROW_ERROR("failed to write the memfd");
+    }
+    char buf[32] = {0};
+    if (pread(fd, buf, sizeof(buf), 0) != strlen(msg) || strcmp(buf, msg) != 0) {
+        close(fd);
+        THROW_ERROR("failed to read the memfd");
+    }
+
+    struct stat stat_buf;
+    if (fstat(fd, &stat_buf) < 0 || stat_buf.st_size != strlen(msg)) {
+        close(fd);
+        THROW_ERROR("the size of the memfd is wrong");
+    }
+    if (fcntl(fd, F_GETFD) != FD_CLOEXEC) {
+        close(fd);
+        THROW_ERROR("the close-on-exec flag is not set");
+    }
+
+    close(fd);
+    return 0;
+}
+
+int test_mmap() {
+    int fd = create_memfd("test_mmap", 0);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a memfd");
+    }
+    size_t len = 4096;
+    if (ftruncate(fd, len) < 0) {
+        close(fd);
+        THROW_ERROR("failed to truncate the memfd");
+    }
+
+    char *addr = mmap(NULL, len, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    if (addr == MAP_FAILED) {
+        close(fd);
+        THROW_ERROR("failed to mmap the memfd");
+    }
+    const char *msg = "shared data";
+    strcpy(addr, msg);
+    if (msync(addr, len, MS_SYNC) < 0) {
+        close(fd);
+        THROW_ERROR("failed to msync");
+    }
+    munmap(addr, len);
+
+    char buf[32] = {0};
+    if (pread(fd, buf, strlen(msg), 0) != strlen(msg) || strcmp(buf, msg) != 0) {
+        close(fd);
+        THROW_ERROR("the content is not written back to the memfd");
+    }
+
+    close(fd);
+    return 0;
+}
+
+int test_seals() {
+    int fd = create_memfd("test_seals", MFD_ALLOW_SEALING);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a memfd");
+    }
+    if (fcntl(fd, F_GET_SEALS) != 0) {
+        close(fd);
+        THROW_ERROR("a new memfd should have no seals");
+    }
+    if (ftruncate(fd, 4096) < 0) {
+        close(fd);
+        THROW_ERROR("failed to truncate the memfd");
+    }
+
+    if (fcntl(fd, F_ADD_SEALS, F_SEAL_SHRINK | F_SEAL_GROW) < 0) {
+        close(fd);
+        THROW_ERROR("failed to add seals");
+    }
+    if (ftruncate(fd, 1024) == 0 || errno != EPERM) {
+        close(fd);
+        THROW_ERROR("shrinking a sealed memfd should fail");
+    }
+    if (ftruncate(fd, 8192) == 0 || errno != EPERM) {
+        close(fd);
+        THROW_ERROR("growing a sealed memfd should fail");
+    }
+    if (pwrite(fd, "a", 1, 4096) >= 0 || errno != EPERM) {
+        close(fd);
+        THROW_ERROR("writing beyond the end of a sealed memfd should fail");
+    }
+    if (pwrite(fd, "a", 1, 0) != 1) {
+        close(fd);
+        THROW_ERROR("writing inside a memfd sealed against resizing should succeed");
+    }
+
+    if (fcntl(fd, F_ADD_SEALS, F_SEAL_WRITE | F_SEAL_SEAL) < 0) {
+        close(fd);
+        THROW_ERROR("failed to add seals");
+    }
+    if (pwrite(fd, "a", 1, 0) >= 0 || errno != EPERM) {
+        close(fd);
+        THROW_ERROR("writing a write-sealed memfd should fail");
+    }
+    void *addr = mmap(NULL, 4096, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    if (addr != MAP_FAILED || errno != EPERM) {
+        close(fd);
+        THROW_ERROR("a shared, writable mapping of a write-sealed memfd should fail");
+    }
+    if (fcntl(fd, F_ADD_SEALS, F_SEAL_SHRINK) == 0 || errno != EPERM) {
+        close(fd);
+        THROW_ERROR("adding seals to a memfd sealed with F_SEAL_SEAL should fail");
+    }
+    int seals = F_SEAL_SHRINK | F_SEAL_GROW | F_SEAL_WRITE | F_SEAL_SEAL;
+    if (fcntl(fd, F_GET_SEALS) != seals) {
+        close(fd);
+        THROW_ERROR("the seals are wrong");
+    }
+
+    close(fd);
+    return 0;
+}
+
+int test_seal_write_with_mapping_in_child() {
+    int fd = create_memfd("test_seal_write_with_mapping_in_child", MFD_ALLOW_SEALING);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a memfd");
+    }
+    if (ftruncate(fd, 4096) < 0) {
+        THROW_ERROR("failed to set the size of the memfd");
+    }
+    int ready_pipe[2], exit_pipe[2];
+    if (pipe(ready_pipe) < 0 || pipe(exit_pipe) < 0) {
+        THROW_ERROR("failed to create pipes");
+    }
+
+    char fd_str[3][16];
+    snprintf(fd_str[0], sizeof(fd_str[0]), "%d", fd);
+    snprintf(fd_str[1], sizeof(fd_str[1]), "%d", ready_pipe[1]);
+    snprintf(fd_str[2], sizeof(fd_str[2]), "%d", exit_pipe[0]);
+    const char *child_argv[] = { CHILD_PROG, fd_str[0], fd_str[1], fd_str[2], NULL };
+    int child_pid;
+    if (posix_spawn(&child_pid, CHILD_PROG, NULL, NULL, (char *const *)child_argv,
+                    NULL) != 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+    char c = 0;
+    if (read(ready_pipe[0], &c, 1) != 1) {
+        THROW_ERROR("failed to wait for the child");
+    }
+
+    // The shared, writable mapping of the child prevents the file from being write-sealed
+    if (fcntl(fd, F_ADD_SEALS, F_SEAL_WRITE) == 0 || errno != EBUSY) {
+        THROW_ERROR("write-sealing a memfd mapped by the child should fail");
+    }
+
+    int status = 0;
+    if (write(exit_pipe[1], &c, 1) != 1 || wait4(child_pid, &status, 0, NULL) < 0) {
+        THROW_ERROR("failed to wait for the child to exit");
+    }
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        THROW_ERROR("the child process failed");
+    }
+    if (fcntl(fd, F_ADD_SEALS, F_SEAL_WRITE) < 0) {
+        THROW_ERROR("write-sealing a memfd without mappings should succeed");
+    }
+
+    close(ready_pipe[0]);
+    close(ready_pipe[1]);
+    close(exit_pipe[0]);
+    close(exit_pipe[1]);
+    close(fd);
+    return 0;
+}
+
+int test_sealing_not_allowed() {
+    int fd = create_memfd("test_sealing_not_allowed", 0);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a memfd");
+    }
+    if (fcntl(fd, F_GET_SEALS) != F_SEAL_SEAL) {
+        close(fd);
+        THROW_ERROR("a memfd without MFD_ALLOW_SEALING should be sealed with F_SEAL_SEAL");
+    }
+    if (fcntl(fd, F_ADD_SEALS, F_SEAL_WRITE) == 0 || errno != EPERM) {
+        close(fd);
+        THROW_ERROR("adding seals should fail");
+    }
+    close(fd);
+
+    if (create_memfd("test_invalid_flags", 0x100) >= 0 || errno != EINVAL) {
+        THROW_ERROR("invalid flags should be rejected");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_read_write),
+    TEST_CASE(test_mmap),
+    TEST_CASE(test_seals),
+    TEST_CASE(test_seal_write_with_mapping_in_child),
+    TEST_CASE(test_sealing_not_allowed),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 3) {
+        int ret = child_map_memfd(atoi(argv[1]), atoi(argv[2]), atoi(argv[3]));
+        return ret < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+    }
+    int ret = test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+    return ret;
+}
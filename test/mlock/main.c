@@ -0,0 +1,191 @@
+#include <sys/mman.h>
+#include <sys/resource.h>
+#include <sys/syscall.h>
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define PAGE_SIZE           4096
+#define BUF_SIZE            (4 * PAGE_SIZE)
+
+#ifndef MLOCK_ONFAULT
+#define MLOCK_ONFAULT       0x01
+#endif
+#ifndef SYS_mlock2
+#define SYS_mlock2          325
+#endif
+
+// Get the size of locked memory in kB from /proc/self/status
+static long get_locked_kb() {
+    FILE *file = fopen("/proc/self/status", "r");
+    if (file == NULL) {
+        return -1;
+    }
+    char line[256];
+    long locked_kb = -1;
+    while (fgets(line, sizeof(line), file) != NULL) {
+        if (sscanf(line, "VmLck: %ld kB", &locked_kb) == 1) {
+            break;
+        }
+    }
+    fclose(file);
+    return locked_kb;
+}
+
+static void *map_buf(size_t size) {
+    void *buf = mmap(NULL, size, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    return buf == MAP_FAILED ? NULL : buf;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_mlock_and_munlock() {
+    char *buf = map_buf(BUF_SIZE);
+    if (buf == NULL) {
+        THROW_ERROR("failed to mmap");
+    }
+
+    // The unaligned range is rounded to pages
+    if (mlock(buf + 1, PAGE_SIZE) < 0) {
+        THROW_ERROR("failed to mlock");
+    }
+    if (get_locked_kb() != 2 * PAGE_SIZE / 1024) {
+        THROW_ERROR("unexpected size of locked memory");
+    }
+    // Locking the locked pages again is not accounted twice
+    if (mlock(buf, BUF_SIZE) < 0) {
+        THROW_ERROR("failed to mlock");
+    }
+    if (get_locked_kb() != BUF_SIZE / 1024) {
+        THROW_ERROR("unexpected size of locked memory");
+    }
+    if (munlock(buf, PAGE_SIZE) < 0) {
+        THROW_ERROR("failed to munlock");
+    }
+    if (get_locked_kb() != (BUF_SIZE - PAGE_SIZE) / 1024) {
+        THROW_ERROR("unexpected size of locked memory");
+    }
+    // The unmapped pages are unlocked
+    munmap(buf, BUF_SIZE);
+    if (get_locked_kb() != 0) {
+        THROW_ERROR("unmapped memory is still locked");
+    }
+    return 0;
+}
+
+static int test_mlock2() {
+    char *buf = map_buf(BUF_SIZE);
+    if (buf == NULL) {
+        THROW_ERROR("failed to mmap");
+    }
+    if (syscall(SYS_mlock2, buf, BUF_SIZE, MLOCK_ONFAULT) < 0) {
+        THROW_ERROR("failed to mlock2");
+    }
+    if (syscall(SYS_mlock2, buf, BUF_SIZE, 0x100) == 0 || errno != EINVAL) {
+        THROW_ERROR("mlock2 with invalid flags should fail with EINVAL");
+    }
+    munmap(buf, BUF_SIZE);
+    return 0;
+}
+
+static int test_mlock_unmapped_memory() {
+    char *buf = map_buf(BUF_SIZE);
+    if (buf == NULL) {
+        THROW_ERROR("failed to mmap");
+    }
+    munmap(buf + PAGE_SIZE, PAGE_SIZE);
+    if (mlock(buf, BUF_SIZE) == 0 || errno != ENOMEM) {
+        THROW_ERROR("mlock on unmapped memory should fail with ENOMEM");
+    }
+    munmap(buf, BUF_SIZE);
+    return 0;
+}
+
+static int test_mlock_beyond_rlimit() {
+    struct rlimit old_rlim, new_rlim;
+    if (getrlimit(RLIMIT_MEMLOCK, &old_rlim) < 0) {
+        THROW_ERROR("failed to getrlimit");
+    }
+    new_rlim.rlim_cur = 2 * PAGE_SIZE;
+    new_rlim.rlim_max = old_rlim.rlim_max;
+    if (setrlimit(RLIMIT_MEMLOCK, &new_rlim) < 0) {
+        THROW_ERROR("failed to setrlimit");
+    }
+
+    int ret = 0;
+    char *buf = map_buf(BUF_SIZE);
+    if (buf == NULL) {
+        ret = -1;
+        printf("failed to mmap\n");
+    } else if (mlock(buf, 2 * PAGE_SIZE) < 0) {
+        ret = -1;
+        printf("failed to mlock within RLIMIT_MEMLOCK\n");
+    } else if (mlock(buf, BUF_SIZE) == 0 || errno != ENOMEM) {
+        ret = -1;
+        printf("mlock beyond RLIMIT_MEMLOCK should fail with ENOMEM\n");
+    }
+    if (buf != NULL) {
+        munmap(buf, BUF_SIZE);
+    }
+
+    setrlimit(RLIMIT_MEMLOCK, &old_rlim);
+    if (ret < 0) {
+        THROW_ERROR("failed to check RLIMIT_MEMLOCK");
+    }
+    return 0;
+}
+
+static int test_mlockall_and_munlockall() {
+    if (mlockall(0) == 0 || errno != EINVAL) {
+        THROW_ERROR("mlockall without flags should fail with EINVAL");
+    }
+    if (mlockall(MCL_CURRENT | MCL_FUTURE) < 0) {
+        THROW_ERROR("failed to mlockall");
+    }
+    long locked_kb = get_locked_kb();
+    if (locked_kb <= 0) {
+        THROW_ERROR("the current memory is not locked");
+    }
+    // The new mapping is locked as well
+    char *buf = map_buf(BUF_SIZE);
+    if (buf == NULL) {
+        THROW_ERROR("failed to mmap");
+    }
+    if (get_locked_kb() != locked_kb + BUF_SIZE / 1024) {
+        THROW_ERROR("the new mapping is not locked");
+    }
+    munmap(buf, BUF_SIZE);
+
+    if (munlockall() < 0) {
+        THROW_ERROR("failed to munlockall");
+    }
+    if (get_locked_kb() != 0) {
+        THROW_ERROR("the memory is still locked");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_mlock_and_munlock),
+    TEST_CASE(test_mlock2),
+    TEST_CASE(test_mlock_unmapped_memory),
+    TEST_CASE(test_mlock_beyond_rlimit),
+    TEST_CASE(test_mlockall_and_munlockall),
+};
+
+int main(int argc, const char *argv[]) {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -252,6 +252,41 @@ int test_anonymous_mmap_with_zero_len() {
     return 0;
 }
 
+int test_anonymous_mmap_reusing_dirty_memory() {
+    size_t len = MAX_MMAP_USED_MEMORY;
+    int prot = PROT_READ | PROT_WRITE;
+    int flags = MAP_PRIVATE | MAP_ANONYMOUS;
+    char *buf = mmap(NULL, len, prot, flags, -1, 0);
+    if (buf == MAP_FAILED) {
+        THROW_ERROR("mmap failed");
+    }
+    memset(buf, 0xff, len);
+    if (munmap(buf, len) < 0) {
+        THROW_ERROR("munmap failed");
+    }
+
+    // The memory that is used before must be filled with zeros
+    char *new_buf = mmap(buf, len, prot, flags, -1, 0);
+    if (new_buf != buf) {
+        THROW_ERROR("mmap with the hint of the unmapped memory failed");
+    }
+    // Touch the pages sparsely first
+    for (size_t offset = 0; offset < len; offset += 16 * PAGE_SIZE) {
+        if (new_buf[offset] != 0) {
+            THROW_ERROR("the buffer is not initialized to zeros");
+        }
+        new_buf[offset] = 1;
+        new_buf[offset] = 0;
+    }
+    if (check_bytes_in_buf(new_buf, len, 0) < 0) {
+        THROW_ERROR("the buffer is not initialized to zeros");
+    }
+    if (munmap(new_buf, len) < 0) {
+        THROW_ERROR("munmap failed");
+    }
+    return 0;
+}
+
 int test_anonymous_mmap_with_non_page_aligned_len() {
     int len = PAGE_SIZE + 17; // length need not to be page aligned!
     int prot = PROT_READ | PROT_WRITE;
@@ -487,6 +522,125 @@ int test_shared_file_mmap_flushing_with_fsync(void) {
     return 0;
 }
 
+int test_shared_file_mmap_sharing_pages() {
+    const char *file_path = "/root/mmap_shared_file.data";
+    int fd = open(file_path, O_CREAT | O_TRUNC | O_RDWR, 0644);
+    if (fd < 0) {
+        THROW_ERROR("file creation failed");
+    }
+    if (fill_file_with_repeated_bytes(fd, PAGE_SIZE, 0) < 0) {
+        THROW_ERROR("file init failed");
+    }
+
+    // Two shared mappings of the same file see the updates of each other
+    char *buf1 = mmap(NULL, PAGE_SIZE, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    char *buf2 = mmap(NULL, PAGE_SIZE, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    if (buf1 == MAP_FAILED || buf2 == MAP_FAILED) {
+        THROW_ERROR("mmap failed");
+    }
+    int byte_val = 0x5a;
+    memset(buf1, byte_val, PAGE_SIZE);
+    if (check_bytes_in_buf(buf2, PAGE_SIZE, byte_val) < 0) {
+        THROW_ERROR("the update is not visible to the other mapping");
+    }
+
+    // The updates are flushed by fsync, while the file is still mapped
+    if (fsync(fd) < 0) {
+        THROW_ERROR("fsync failed");
+    }
+    int read_fd = open(file_path, O_RDONLY);
+    if (read_fd < 0) {
+        THROW_ERROR("file open failed");
+    }
+    if (check_file_with_repeated_bytes(read_fd, PAGE_SIZE, byte_val) < 0) {
+        THROW_ERROR("unexpected file content");
+    }
+    close(read_fd);
+
+    if (munmap(buf1, PAGE_SIZE) < 0 || munmap(buf2, PAGE_SIZE) < 0) {
+        THROW_ERROR("munmap failed");
+    }
+    close(fd);
+    unlink(file_path);
+    return 0;
+}
+
+int test_shared_file_mmap_with_overlapping_offsets() {
+    const char *file_path = "/root/mmap_shared_file.data";
+    int fd = open(file_path, O_CREAT | O_TRUNC | O_RDWR, 0644);
+    if (fd < 0) {
+        THROW_ERROR("file creation failed");
+    }
+    if (fill_file_with_repeated_bytes(fd, 3 * PAGE_SIZE, 0) < 0) {
+        THROW_ERROR("file init failed");
+    }
+
+    // The mappings starting at different offsets share the overlapped page
+    char *buf1 = mmap(NULL, 2 * PAGE_SIZE, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    char *buf2 = mmap(NULL, 2 * PAGE_SIZE, PROT_READ | PROT_WRITE, MAP_SHARED, fd,
+                      PAGE_SIZE);
+    if (buf1 == MAP_FAILED || buf2 == MAP_FAILED) {
+        THROW_ERROR("mmap failed");
+    }
+    int byte_val = 0x6b;
+    memset(buf2, byte_val, PAGE_SIZE);
+    if (check_bytes_in_buf(buf1 + PAGE_SIZE, PAGE_SIZE, byte_val) < 0) {
+        THROW_ERROR("the update is not visible to the overlapped mapping");
+    }
+    if (check_bytes_in_buf(buf1, PAGE_SIZE, 0) < 0) {
+        THROW_ERROR("the page not overlapped is changed");
+    }
+
+    if (munmap(buf1, 2 * PAGE_SIZE) < 0 || munmap(buf2, 2 * PAGE_SIZE) < 0) {
+        THROW_ERROR("munmap failed");
+    }
+    close(fd);
+    unlink(file_path);
+    return 0;
+}
+
+int test_shared_file_mmap_coherent_with_read_write() {
+    const char *file_path = "/root/mmap_shared_file.data";
+    int fd = open(file_path, O_CREAT | O_TRUNC | O_RDWR, 0644);
+    if (fd < 0) {
+        THROW_ERROR("file creation failed");
+    }
+    if (fill_file_with_repeated_bytes(fd, PAGE_SIZE, 0) < 0) {
+        THROW_ERROR("file init failed");
+    }
+    char *buf = mmap(NULL, PAGE_SIZE, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    if (buf == MAP_FAILED) {
+        THROW_ERROR("mmap failed");
+    }
+
+    // The data written to the file is visible to the mapping
+    char write_buf[PAGE_SIZE];
+    memset(write_buf, 0x3c, PAGE_SIZE);
+    if (pwrite(fd, write_buf, PAGE_SIZE, 0) != PAGE_SIZE) {
+        THROW_ERROR("pwrite failed");
+    }
+    if (check_bytes_in_buf(buf, PAGE_SIZE, 0x3c) < 0) {
+        THROW_ERROR("the written data is not visible to the mapping");
+    }
+
+    // The data written to the mapping is visible to the reads without msync
+    memset(buf, 0x4d, PAGE_SIZE);
+    char read_buf[PAGE_SIZE];
+    if (pread(fd, read_buf, PAGE_SIZE, 0) != PAGE_SIZE) {
+        THROW_ERROR("pread failed");
+    }
+    if (check_bytes_in_buf(read_buf, PAGE_SIZE, 0x4d) < 0) {
+        THROW_ERROR("the data of the mapping is not visible to the reads");
+    }
+
+    if (munmap(buf, PAGE_SIZE) < 0) {
+        THROW_ERROR("munmap failed");
+    }
+    close(fd);
+    unlink(file_path);
+    return 0;
+}
+
 // ============================================================================
 // Test cases for fixed mmap
 // ============================================================================
@@ -568,6 +722,116 @@ int test_fixed_mmap_with_non_page_aligned_addr() {
     return 0;
 }
 
+#ifndef MAP_FIXED_NOREPLACE
+#define MAP_FIXED_NOREPLACE     0x100000
+#endif
+
+int test_fixed_noreplace_mmap() {
+    size_t len = 2 * PAGE_SIZE;
+    int prot = PROT_READ | PROT_WRITE;
+    int flags = MAP_PRIVATE | MAP_ANONYMOUS | MAP_FIXED_NOREPLACE;
+    void *addr = mmap((void *)HINT_BEGIN, len, prot, flags, -1, 0);
+    if (addr != (void *)HINT_BEGIN) {
+        THROW_ERROR("fixed mmap without replacement failed on free memory");
+    }
+
+    // The mapping overlapping with the existing one is not replaced
+    void *overlap_addr = (char *)HINT_BEGIN + PAGE_SIZE;
+    if (mmap(overlap_addr, len, prot, flags, -1, 0) != MAP_FAILED || errno != EEXIST) {
+        THROW_ERROR("fixed mmap without replacement should fail with EEXIST");
+    }
+    if (check_bytes_in_buf(addr, len, 0) < 0) {
+        THROW_ERROR("the existing mapping is clobbered");
+    }
+
+    if (munmap(addr, len) < 0) {
+        THROW_ERROR("munmap failed");
+    }
+    return 0;
+}
+
+int test_fixed_shared_file_mmap() {
+    const char *file_path = "/root/mmap_shared_file.data";
+    int fd = open(file_path, O_CREAT | O_TRUNC | O_RDWR, 0644);
+    if (fd < 0) {
+        THROW_ERROR("file creation failed");
+    }
+    if (fill_file_with_repeated_bytes(fd, 2 * PAGE_SIZE, 0) < 0) {
+        THROW_ERROR("file init failed");
+    }
+    int prot = PROT_READ | PROT_WRITE;
+    char *buf = mmap(NULL, 2 * PAGE_SIZE, prot, MAP_SHARED, fd, 0);
+    if (buf == MAP_FAILED) {
+        THROW_ERROR("mmap failed");
+    }
+
+    // Map a page of the file again at the address where it is mapped
+    char *fixed_buf = mmap(buf + PAGE_SIZE, PAGE_SIZE, prot, MAP_SHARED | MAP_FIXED, fd,
+                           PAGE_SIZE);
+    if (fixed_buf != buf + PAGE_SIZE) {
+        THROW_ERROR("fixed mmap of the shared pages failed");
+    }
+    memset(fixed_buf, 0x7e, PAGE_SIZE);
+    if (check_bytes_in_buf(buf + PAGE_SIZE, PAGE_SIZE, 0x7e) < 0) {
+        THROW_ERROR("the update is not visible to the other mapping");
+    }
+    if (munmap(fixed_buf, PAGE_SIZE) < 0) {
+        THROW_ERROR("munmap failed");
+    }
+
+    // Map the file at a fixed address in a reserved range, whose updates are
+    // written back to the file and the shared pages on msync
+    char *reserved = mmap(NULL, PAGE_SIZE, PROT_NONE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (reserved == MAP_FAILED) {
+        THROW_ERROR("mmap failed");
+    }
+    fixed_buf = mmap(reserved, PAGE_SIZE, prot, MAP_SHARED | MAP_FIXED, fd, 0);
+    if (fixed_buf != reserved) {
+        THROW_ERROR("fixed mmap in the reserved range failed");
+    }
+    memset(fixed_buf, 0x2f, PAGE_SIZE);
+    if (msync(fixed_buf, PAGE_SIZE, MS_SYNC) < 0) {
+        THROW_ERROR("msync failed");
+    }
+    if (check_bytes_in_buf(buf, PAGE_SIZE, 0x2f) < 0) {
+        THROW_ERROR("the update is not visible to the shared pages");
+    }
+    if (lseek(fd, 0, SEEK_SET) < 0) {
+        THROW_ERROR("lseek failed");
+    }
+    if (check_file_with_repeated_bytes(fd, PAGE_SIZE, 0x2f) < 0) {
+        THROW_ERROR("unexpected file content");
+    }
+
+    if (munmap(fixed_buf, PAGE_SIZE) < 0 || munmap(buf, 2 * PAGE_SIZE) < 0) {
+        THROW_ERROR("munmap failed");
+    }
+    close(fd);
+    unlink(file_path);
+    return 0;
+}
+
+int test_32bit_mmap() {
+    size_t len = PAGE_SIZE;
+    int prot = PROT_READ | PROT_WRITE;
+    int flags = MAP_PRIVATE | MAP_ANONYMOUS | MAP_32BIT;
+    void *addr = mmap(NULL, len, prot, flags, -1, 0);
+    if (addr == MAP_FAILED) {
+        // There may be no memory in the first 2GB of the address space
+        if (errno != ENOMEM) {
+            THROW_ERROR("32-bit mmap should fail with ENOMEM");
+        }
+        return 0;
+    }
+    if ((size_t)addr + len > 0x80000000UL) {
+        THROW_ERROR("32-bit mmap is not in the first 2GB");
+    }
+    if (munmap(addr, len) < 0) {
+        THROW_ERROR("munmap failed");
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test cases for munmap
 // ============================================================================
@@ -841,6 +1105,202 @@ int test_mremap_with_fixed_addr() {
     return 0;
 }
 
+int test_mremap_in_place() {
+    int prot = PROT_READ | PROT_WRITE;
+    int flags = MAP_PRIVATE | MAP_ANONYMOUS;
+
+    // Reserve four pages and free the last two so that the first two can grow
+    size_t len = PAGE_SIZE * 2;
+    char *buf = mmap(NULL, len * 2, prot, flags, -1, 0);
+    if (buf == MAP_FAILED) {
+        THROW_ERROR("mmap failed");
+    }
+    if (munmap(buf + len, len) < 0) {
+        THROW_ERROR("munmap failed");
+    }
+    memset(buf, 'a', len);
+
+    char *new_buf = mremap(buf, len, len * 2, 0);
+    if (new_buf != buf) {
+        THROW_ERROR("mremap should grow the buffer in place");
+    }
+    if (check_bytes_in_buf(new_buf, len, 'a') < 0 ||
+            check_bytes_in_buf(new_buf + len, len, 0) < 0) {
+        THROW_ERROR("the grown buffer is not correct");
+    }
+
+    // The buffer can not grow in place without MREMAP_MAYMOVE if the next
+    // pages are occupied
+    char *next_buf = mmap(new_buf + len * 2, PAGE_SIZE, prot, flags | MAP_FIXED, -1, 0);
+    if (next_buf != new_buf + len * 2) {
+        THROW_ERROR("fixed mmap failed");
+    }
+    if (mremap(new_buf, len * 2, len * 3, 0) != MAP_FAILED || errno != ENOMEM) {
+        THROW_ERROR("mremap should fail if the buffer can not grow in place");
+    }
+
+    if (munmap(new_buf, len * 2) < 0 || munmap(next_buf, PAGE_SIZE) < 0) {
+        THROW_ERROR("munmap failed");
+    }
+    return 0;
+}
+
+int test_mremap_shared_file_mmap() {
+    const char *file_path = "/root/mremap_file.data";
+    int fd = open(file_path, O_CREAT | O_TRUNC | O_RDWR, 0644);
+    if (fd < 0) {
+        THROW_ERROR("file creation failed");
+    }
+    size_t len = PAGE_SIZE;
+    if (fill_file_with_repeated_bytes(fd, len * 2, 0xab) < 0) {
+        THROW_ERROR("file init failed");
+    }
+
+    // Occupy the page after the mapping so that it must be moved to grow
+    char *buf = mmap(NULL, len * 2, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    if (buf == MAP_FAILED) {
+        THROW_ERROR("mmap failed");
+    }
+    char *next_buf = mmap(buf + len, len, PROT_READ | PROT_WRITE,
+                          MAP_PRIVATE | MAP_ANONYMOUS | MAP_FIXED, -1, 0);
+    if (next_buf != buf + len) {
+        THROW_ERROR("fixed mmap failed");
+    }
+    memset(buf, 0xcd, len);
+
+    char *new_buf = mremap(buf, len, len * 2, MREMAP_MAYMOVE);
+    if (new_buf == MAP_FAILED) {
+        THROW_ERROR("mremap failed");
+    }
+    if (new_buf == buf) {
+        THROW_ERROR("the mapping should be moved");
+    }
+    // The old part keeps its content, while the grown part is loaded from the file
+    if (check_bytes_in_buf(new_buf, len, 0xcd) < 0 ||
+            check_bytes_in_buf(new_buf + len, len, 0xab) < 0) {
+        THROW_ERROR("the moved mapping is not correct");
+    }
+
+    // The moved mapping is still backed by the file
+    memset(new_buf, 0xef, len * 2);
+    if (munmap(new_buf, len * 2) < 0 || munmap(next_buf, len) < 0) {
+        THROW_ERROR("munmap failed");
+    }
+    if (lseek(fd, 0, SEEK_SET) < 0) {
+        THROW_ERROR("lseek failed");
+    }
+    if (check_file_with_repeated_bytes(fd, len * 2, 0xef) < 0) {
+        THROW_ERROR("unexpected file content");
+    }
+
+    close(fd);
+    unlink(file_path);
+    return 0;
+}
+
+// ============================================================================
+// Test cases for madvise
+// ============================================================================
+
+int test_madvise_dontneed() {
+    int prot = PROT_READ | PROT_WRITE;
+    int flags = MAP_PRIVATE | MAP_ANONYMOUS;
+
+    size_t len = PAGE_SIZE * 4;
+    char *buf = mmap(NULL, len, prot, flags, -1, 0);
+    if (buf == MAP_FAILED) {
+        THROW_ERROR("mmap failed");
+    }
+    memset(buf, 'a', len);
+
+    // The discarded anonymous memory is filled with zeros on the next access
+    if (madvise(buf + PAGE_SIZE, PAGE_SIZE * 2, MADV_DONTNEED) < 0) {
+        THROW_ERROR("madvise with MADV_DONTNEED failed");
+    }
+    if (check_bytes_in_buf(buf, PAGE_SIZE, 'a') < 0 ||
+            check_bytes_in_buf(buf + PAGE_SIZE, PAGE_SIZE * 2, 0) < 0 ||
+            check_bytes_in_buf(buf + PAGE_SIZE * 3, PAGE_SIZE, 'a') < 0) {
+        THROW_ERROR("the discarded memory is not correct");
+    }
+
+    // The content of MADV_FREE'd memory is undefined until it is written
+    if (madvise(buf, len, MADV_FREE) < 0) {
+        THROW_ERROR("madvise with MADV_FREE failed");
+    }
+    memset(buf, 'b', len);
+    if (check_bytes_in_buf(buf, len, 'b') < 0) {
+        THROW_ERROR("the freed memory can not be reused");
+    }
+
+    if (munmap(buf, len) < 0) {
+        THROW_ERROR("munmap failed");
+    }
+    return 0;
+}
+
+int test_madvise_hints() {
+    int prot = PROT_READ | PROT_WRITE;
+    int flags = MAP_PRIVATE | MAP_ANONYMOUS;
+
+    size_t len = PAGE_SIZE * 4;
+    char *buf = mmap(NULL, len, prot, flags, -1, 0);
+    if (buf == MAP_FAILED) {
+        THROW_ERROR("mmap failed");
+    }
+    memset(buf, 'a', len);
+
+    // The hints have no effects on the content
+    int advices[] = { MADV_NORMAL, MADV_RANDOM, MADV_SEQUENTIAL, MADV_WILLNEED,
+                      MADV_HUGEPAGE, MADV_NOHUGEPAGE, MADV_DONTFORK, MADV_DOFORK
+                    };
+    for (int i = 0; i < ARRAY_SIZE(advices); i++) {
+        if (madvise(buf, len, advices[i]) < 0) {
+            THROW_ERROR("madvise with a hint failed");
+        }
+    }
+    if (check_bytes_in_buf(buf, len, 'a') < 0) {
+        THROW_ERROR("the memory is changed by hints");
+    }
+
+    if (munmap(buf, len) < 0) {
+        THROW_ERROR("munmap failed");
+    }
+    return 0;
+}
+
+int test_madvise_with_invalid_args() {
+    int prot = PROT_READ | PROT_WRITE;
+    int flags = MAP_PRIVATE | MAP_ANONYMOUS;
+
+    size_t len = PAGE_SIZE * 2;
+    char *buf = mmap(NULL, len, prot, flags, -1, 0);
+    if (buf == MAP_FAILED) {
+        THROW_ERROR("mmap failed");
+    }
+
+    if (madvise(buf + 1, PAGE_SIZE, MADV_DONTNEED) == 0 || errno != EINVAL) {
+        THROW_ERROR("madvise with an unaligned address should fail");
+    }
+    if (madvise(buf, len, 12345) == 0 || errno != EINVAL) {
+        THROW_ERROR("madvise with an unknown advice should fail");
+    }
+    if (madvise(buf, 0, MADV_DONTNEED) < 0) {
+        THROW_ERROR("madvise with zero length should succeed");
+    }
+
+    if (munmap(buf + PAGE_SIZE, PAGE_SIZE) < 0) {
+        THROW_ERROR("munmap failed");
+    }
+    if (madvise(buf, len, MADV_DONTNEED) == 0 || errno != ENOMEM) {
+        THROW_ERROR("madvise with an unmapped range should fail");
+    }
+
+    if (munmap(buf, PAGE_SIZE) < 0) {
+        THROW_ERROR("munmap failed");
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test cases for mprotect
 // ============================================================================
@@ -1091,6 +1551,36 @@ int test_mprotect_with_non_page_aligned_size() {
     return 0;
 }
 
+int test_mprotect_executable_pages() {
+    size_t len = PAGE_SIZE * 2;
+    int flags = MAP_PRIVATE | MAP_ANONYMOUS;
+    char *buf = mmap(NULL, len, PROT_READ | PROT_WRITE, flags, -1, 0);
+    if (buf == MAP_FAILED) {
+        THROW_ERROR("mmap failed");
+    }
+
+    // Only the second page is touched, and the first one may be uncommitted
+    // with EDMM when the permissions are changed
+    char *code = buf + PAGE_SIZE;
+    code[0] = 0xc3; // ret
+    if (mprotect(buf, len, PROT_READ | PROT_EXEC) < 0) {
+        THROW_ERROR("mprotect failed");
+    }
+    ((void (*)(void))code)();
+    if (check_bytes_in_buf(buf, PAGE_SIZE, 0) < 0) {
+        THROW_ERROR("the untouched page is not filled with zeros");
+    }
+
+    if (mprotect(buf, len, PROT_READ | PROT_WRITE) < 0) {
+        THROW_ERROR("mprotect failed");
+    }
+    memset(buf, 0xff, len);
+    if (munmap(buf, len) < 0) {
+        THROW_ERROR("munmap failed");
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -1100,6 +1590,7 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_anonymous_mmap_randomly),
     TEST_CASE(test_anonymous_mmap_randomly_with_good_hints),
     TEST_CASE(test_anonymous_mmap_with_bad_hints),
+    TEST_CASE(test_anonymous_mmap_reusing_dirty_memory),
     TEST_CASE(test_anonymous_mmap_with_zero_len),
     TEST_CASE(test_anonymous_mmap_with_non_page_aligned_len),
     TEST_CASE(test_private_file_mmap),
@@ -1110,9 +1601,15 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_shared_file_mmap_flushing_with_munmap),
     TEST_CASE(test_shared_file_mmap_flushing_with_fdatasync),
     TEST_CASE(test_shared_file_mmap_flushing_with_fsync),
+    TEST_CASE(test_shared_file_mmap_sharing_pages),
+    TEST_CASE(test_shared_file_mmap_with_overlapping_offsets),
+    TEST_CASE(test_shared_file_mmap_coherent_with_read_write),
     TEST_CASE(test_fixed_mmap_that_does_not_override_any_mmaping),
     TEST_CASE(test_fixed_mmap_that_overrides_existing_mmaping),
     TEST_CASE(test_fixed_mmap_with_non_page_aligned_addr),
+    TEST_CASE(test_fixed_noreplace_mmap),
+    TEST_CASE(test_fixed_shared_file_mmap),
+    TEST_CASE(test_32bit_mmap),
     TEST_CASE(test_munmap_whose_range_is_a_subset_of_a_mmap_region),
     TEST_CASE(test_munmap_whose_range_is_a_superset_of_a_mmap_region),
     TEST_CASE(test_munmap_whose_range_intersects_with_a_mmap_region),
@@ -1124,6 +1621,11 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_mremap),
     TEST_CASE(test_mremap_subrange),
     TEST_CASE(test_mremap_with_fixed_addr),
+    TEST_CASE(test_mremap_in_place),
+    TEST_CASE(test_mremap_shared_file_mmap),
+    TEST_CASE(test_madvise_dontneed),
+    TEST_CASE(test_madvise_hints),
+    TEST_CASE(test_madvise_with_invalid_args),
     TEST_CASE(test_mprotect_once),
     TEST_CASE(test_mprotect_twice),
     TEST_CASE(test_mprotect_triple),
@@ -1131,6 +1633,7 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_mprotect_with_invalid_addr),
     TEST_CASE(test_mprotect_with_invalid_prot),
     TEST_CASE(test_mprotect_with_non_page_aligned_size),
+    TEST_CASE(test_mprotect_executable_pages),
 };
 
 int main() {
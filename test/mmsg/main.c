@@ -0,0 +1,185 @@
+#define _GNU_SOURCE
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+#include <errno.h>
+#include <unistd.h>
+#include <string.h>
+
+#include "test.h"
+
+#define NR_MSGS     4
+#define MSG_SIZE    16
+
+// ============================================================================
+// Helper function
+// ============================================================================
+static int create_udp_socket(struct sockaddr_in *addr) {
+    int sock = socket(AF_INET, SOCK_DGRAM, 0);
+    if (sock < 0) {
+        THROW_ERROR("failed to create a UDP socket");
+    }
+
+    socklen_t addr_len = sizeof(*addr);
+    memset(addr, 0, sizeof(*addr));
+    addr->sin_family = AF_INET;
+    addr->sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    if (bind(sock, (struct sockaddr *)addr, sizeof(*addr)) < 0 ||
+            getsockname(sock, (struct sockaddr *)addr, &addr_len) < 0) {
+        close(sock);
+        THROW_ERROR("failed to bind the socket");
+    }
+    return sock;
+}
+
+static int send_msgs(int sock, struct sockaddr_in *dst, int nr_msgs) {
+    char bufs[NR_MSGS][MSG_SIZE];
+    struct iovec iovs[NR_MSGS];
+    struct mmsghdr msgs[NR_MSGS];
+    memset(msgs, 0, sizeof(msgs));
+    for (int i = 0; i < nr_msgs; i++) {
+        memset(bufs[i], 'a' + i, MSG_SIZE);
+        iovs[i].iov_base = bufs[i];
+        iovs[i].iov_len = MSG_SIZE;
+        msgs[i].msg_hdr.msg_name = dst;
+        msgs[i].msg_hdr.msg_namelen = dst == NULL ? 0 : sizeof(*dst);
+        msgs[i].msg_hdr.msg_iov = &iovs[i];
+        msgs[i].msg_hdr.msg_iovlen = 1;
+    }
+
+    int ret = sendmmsg(sock, msgs, nr_msgs, 0);
+    if (ret != nr_msgs) {
+        THROW_ERROR("failed to send the messages");
+    }
+    for (int i = 0; i < nr_msgs; i++) {
+        if (msgs[i].msg_len != MSG_SIZE) {
+            THROW_ERROR("the length of a message sent is wrong");
+        }
+    }
+    return 0;
+}
+
+static int recv_and_check_msgs(int sock, struct sockaddr_in *src, int nr_msgs,
+                               int flags) {
+    char bufs[NR_MSGS][MSG_SIZE];
+    struct iovec iovs[NR_MSGS];
+    struct sockaddr_in addrs[NR_MSGS];
+    struct mmsghdr msgs[NR_MSGS];
+    memset(msgs, 0, sizeof(msgs));
+    for (int i = 0; i < NR_MSGS; i++) {
+        iovs[i].iov_base = bufs[i];
+        iovs[i].iov_len = MSG_SIZE;
+        msgs[i].msg_hdr.msg_name = &addrs[i];
+        msgs[i].msg_hdr.msg_namelen = sizeof(addrs[i]);
+        msgs[i].msg_hdr.msg_iov = &iovs[i];
+        msgs[i].msg_hdr.msg_iovlen = 1;
+    }
+
+    int ret = recvmmsg(sock, msgs, NR_MSGS, flags, NULL);
+    if (ret != nr_msgs) {
+        THROW_ERROR("failed to receive the messages");
+    }
+    for (int i = 0; i < nr_msgs; i++) {
+        if (msgs[i].msg_len != MSG_SIZE) {
+            THROW_ERROR("the length of a message received is wrong");
+        }
+        for (int j = 0; j < MSG_SIZE; j++) {
+            if (bufs[i][j] != 'a' + i) {
+                THROW_ERROR("the content of a message received is wrong");
+            }
+        }
+        if (src != NULL && (msgs[i].msg_hdr.msg_namelen != sizeof(*src) ||
+                            addrs[i].sin_port != src->sin_port)) {
+            THROW_ERROR("the source of a message received is wrong");
+        }
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+int test_udp_sendmmsg_and_recvmmsg() {
+    struct sockaddr_in sender_addr, receiver_addr;
+    int sender = create_udp_socket(&sender_addr);
+    int receiver = create_udp_socket(&receiver_addr);
+    if (sender < 0 || receiver < 0) {
+        THROW_ERROR("failed to create the sockets");
+    }
+
+    int ret = send_msgs(sender, &receiver_addr, NR_MSGS);
+    if (ret == 0) {
+        ret = recv_and_check_msgs(receiver, &sender_addr, NR_MSGS, 0);
+    }
+    close(sender);
+    close(receiver);
+    return ret;
+}
+
+int test_recvmmsg_waitforone() {
+    struct sockaddr_in sender_addr, receiver_addr;
+    int sender = create_udp_socket(&sender_addr);
+    int receiver = create_udp_socket(&receiver_addr);
+    if (sender < 0 || receiver < 0) {
+        THROW_ERROR("failed to create the sockets");
+    }
+
+    // Only the messages available are received given MSG_WAITFORONE
+    int ret = send_msgs(sender, &receiver_addr, NR_MSGS / 2);
+    if (ret == 0) {
+        ret = recv_and_check_msgs(receiver, &sender_addr, NR_MSGS / 2, MSG_WAITFORONE);
+    }
+    close(sender);
+    close(receiver);
+    return ret;
+}
+
+int test_recvmmsg_nonblocking() {
+    struct sockaddr_in addr;
+    int sock = create_udp_socket(&addr);
+    if (sock < 0) {
+        THROW_ERROR("failed to create the socket");
+    }
+
+    char buf[MSG_SIZE];
+    struct iovec iov = { .iov_base = buf, .iov_len = sizeof(buf) };
+    struct mmsghdr msg;
+    memset(&msg, 0, sizeof(msg));
+    msg.msg_hdr.msg_iov = &iov;
+    msg.msg_hdr.msg_iovlen = 1;
+    int ret = recvmmsg(sock, &msg, 1, MSG_DONTWAIT, NULL);
+    close(sock);
+    if (ret >= 0 || errno != EAGAIN) {
+        THROW_ERROR("receiving nothing without blocking should fail with EAGAIN");
+    }
+    return 0;
+}
+
+int test_unix_sendmmsg_and_recvmmsg() {
+    int socks[2];
+    if (socketpair(AF_UNIX, SOCK_DGRAM, 0, socks) < 0) {
+        THROW_ERROR("failed to create a unix socket pair");
+    }
+
+    int ret = send_msgs(socks[0], NULL, NR_MSGS);
+    if (ret == 0) {
+        ret = recv_and_check_msgs(socks[1], NULL, NR_MSGS, 0);
+    }
+    close(socks[0]);
+    close(socks[1]);
+    return ret;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+static test_case_t test_cases[] = {
+    TEST_CASE(test_udp_sendmmsg_and_recvmmsg),
+    TEST_CASE(test_recvmmsg_waitforone),
+    TEST_CASE(test_recvmmsg_nonblocking),
+    TEST_CASE(test_unix_sendmmsg_and_recvmmsg),
+};
+
+int main() {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
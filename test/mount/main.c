@@ -0,0 +1,287 @@
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <sys/statfs.h>
+#include <sys/statvfs.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <string.h>
+#include <unistd.h>
+#include "test_fs.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define RAMFS_MAGIC         0x858458f6
+#define TMPFS_MAGIC         0x01021994
+
+static int create_file_with_content(const char *path, const char *msg) {
+    int fd = open(path, O_WRONLY | O_CREAT | O_TRUNC, 0666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a file");
+    }
+    if (write(fd, msg, strlen(msg)) != strlen(msg)) {
+        THROW_ERROR("failed to write to the file");
+    }
+    close(fd);
+    return 0;
+}
+
+static int __test_mount_memory_fs(const char *fs_type, long expected_magic) {
+    const char *mnt_dir = "/root/test_mount_dir";
+    const char *file_path = "/root/test_mount_dir/test_file.txt";
+    const char *msg = "Hello from the runtime mount\n";
+    int ret = -1;
+
+    if (mkdir(mnt_dir, 0755) < 0) {
+        THROW_ERROR("failed to create the mount point");
+    }
+    if (mount("none", mnt_dir, fs_type, 0, NULL) < 0) {
+        printf("\t\tERROR: failed to mount %s\n", fs_type);
+        goto out;
+    }
+    struct statfs statfs_buf;
+    if (statfs(mnt_dir, &statfs_buf) < 0 || statfs_buf.f_type != expected_magic) {
+        printf("\t\tERROR: statfs should report the mounted file system\n");
+        goto out_umount;
+    }
+    if (create_file_with_content(file_path, msg) < 0 ||
+            fs_check_file_content(file_path, msg) < 0) {
+        goto out_umount;
+    }
+    if (mount("none", mnt_dir, fs_type, 0, NULL) == 0 || errno != EBUSY) {
+        printf("\t\tERROR: mounting on a mount point should fail\n");
+        goto out_umount;
+    }
+    ret = 0;
+
+out_umount:
+    if (umount2(mnt_dir, 0) < 0) {
+        printf("\t\tERROR: failed to umount %s\n", fs_type);
+        ret = -1;
+    }
+    if (ret == 0 && access(file_path, F_OK) == 0) {
+        printf("\t\tERROR: the file should disappear after umount\n");
+        ret = -1;
+    }
+out:
+    rmdir(mnt_dir);
+    return ret;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_mount_ramfs() {
+    return __test_mount_memory_fs("ramfs", RAMFS_MAGIC);
+}
+
+static int test_mount_tmpfs() {
+    return __test_mount_memory_fs("tmpfs", TMPFS_MAGIC);
+}
+
+static int test_mount_hostfs() {
+    // The source is relative to the instance dir, which is mounted at /host
+    const char *host_dir = "/host/test_mount_hostfs";
+    const char *host_file_path = "/host/test_mount_hostfs/test_file.txt";
+    const char *mnt_dir = "/root/test_mount_hostfs";
+    const char *file_path = "/root/test_mount_hostfs/test_file.txt";
+    const char *msg = "Hello from the host\n";
+    int ret = -1;
+
+    if (mkdir(host_dir, 0755) < 0 || mkdir(mnt_dir, 0755) < 0) {
+        THROW_ERROR("failed to create the dirs");
+    }
+    if (create_file_with_content(host_file_path, msg) < 0) {
+        goto out;
+    }
+    if (mount("test_mount_hostfs", mnt_dir, "hostfs", 0, NULL) < 0) {
+        printf("\t\tERROR: failed to mount hostfs\n");
+        goto out;
+    }
+    if (fs_check_file_content(file_path, msg) < 0) {
+        umount2(mnt_dir, 0);
+        goto out;
+    }
+    if (umount2(mnt_dir, MNT_DETACH) < 0) {
+        printf("\t\tERROR: failed to umount hostfs\n");
+        goto out;
+    }
+    ret = 0;
+
+out:
+    unlink(host_file_path);
+    rmdir(host_dir);
+    rmdir(mnt_dir);
+    return ret;
+}
+
+static int test_bind_mount() {
+    const char *src_dir = "/root/test_bind_src";
+    const char *mnt_dir = "/root/test_bind_dst";
+    const char *src_file_path = "/root/test_bind_src/src_file.txt";
+    const char *file_path = "/root/test_bind_dst/src_file.txt";
+    const char *new_src_file_path = "/root/test_bind_src/new_file.txt";
+    const char *new_file_path = "/root/test_bind_dst/new_file.txt";
+    const char *msg = "Hello from the bind mount\n";
+    int ret = -1;
+
+    if (mkdir(src_dir, 0755) < 0 || mkdir(mnt_dir, 0755) < 0) {
+        THROW_ERROR("failed to create the dirs");
+    }
+    if (create_file_with_content(src_file_path, msg) < 0) {
+        goto out;
+    }
+    if (mount(src_dir, mnt_dir, NULL, MS_BIND, NULL) < 0) {
+        printf("\t\tERROR: failed to bind mount\n");
+        goto out;
+    }
+    if (fs_check_file_content(file_path, msg) < 0) {
+        umount2(mnt_dir, 0);
+        goto out;
+    }
+    // The updates through the bind mount are visible at the source dir
+    if (create_file_with_content(new_file_path, msg) < 0 ||
+            fs_check_file_content(new_src_file_path, msg) < 0) {
+        umount2(mnt_dir, 0);
+        goto out;
+    }
+    if (umount2(mnt_dir, 0) < 0) {
+        printf("\t\tERROR: failed to umount the bind mount\n");
+        goto out;
+    }
+    if (access(file_path, F_OK) == 0) {
+        printf("\t\tERROR: the file should disappear after umount\n");
+        goto out;
+    }
+    ret = 0;
+
+out:
+    unlink(new_src_file_path);
+    unlink(src_file_path);
+    rmdir(src_dir);
+    rmdir(mnt_dir);
+    return ret;
+}
+
+static int test_bind_mount_read_only() {
+    const char *src_dir = "/root/test_bind_ro_src";
+    const char *mnt_dir = "/root/test_bind_ro_dst";
+    const char *src_file_path = "/root/test_bind_ro_src/src_file.txt";
+    const char *file_path = "/root/test_bind_ro_dst/src_file.txt";
+    const char *new_file_path = "/root/test_bind_ro_dst/new_file.txt";
+    const char *msg = "Hello from the read-only bind mount\n";
+    int ret = -1;
+
+    if (mkdir(src_dir, 0755) < 0 || mkdir(mnt_dir, 0755) < 0) {
+        THROW_ERROR("failed to create the dirs");
+    }
+    if (create_file_with_content(src_file_path, msg) < 0) {
+        goto out;
+    }
+    if (mount(src_dir, mnt_dir, NULL, MS_BIND | MS_RDONLY, NULL) < 0) {
+        printf("\t\tERROR: failed to bind mount\n");
+        goto out;
+    }
+    if (fs_check_file_content(file_path, msg) < 0) {
+        goto out_umount;
+    }
+    if (open(file_path, O_WRONLY) >= 0 || errno != EROFS) {
+        printf("\t\tERROR: opening a file to write should fail\n");
+        goto out_umount;
+    }
+    if (creat(new_file_path, 0666) >= 0 || errno != EROFS) {
+        printf("\t\tERROR: creating a file should fail\n");
+        goto out_umount;
+    }
+    if (unlink(file_path) == 0 || errno != EROFS) {
+        printf("\t\tERROR: unlinking a file should fail\n");
+        goto out_umount;
+    }
+    struct statfs statfs_buf;
+    if (statfs(mnt_dir, &statfs_buf) < 0 || !(statfs_buf.f_flags & ST_RDONLY)) {
+        printf("\t\tERROR: statfs should report the read-only mount\n");
+        goto out_umount;
+    }
+    // Make the bind mount writable by remounting
+    if (mount(NULL, mnt_dir, NULL, MS_REMOUNT | MS_BIND, NULL) < 0) {
+        printf("\t\tERROR: failed to remount the bind mount\n");
+        goto out_umount;
+    }
+    if (create_file_with_content(new_file_path, msg) < 0 || unlink(new_file_path) < 0) {
+        printf("\t\tERROR: the bind mount should be writable after remount\n");
+        goto out_umount;
+    }
+    ret = 0;
+
+out_umount:
+    if (umount2(mnt_dir, 0) < 0) {
+        printf("\t\tERROR: failed to umount the bind mount\n");
+        ret = -1;
+    }
+out:
+    unlink(src_file_path);
+    rmdir(src_dir);
+    rmdir(mnt_dir);
+    return ret;
+}
+
+static int test_mount_with_invalid_args() {
+    const char *mnt_dir = "/root/test_mount_invalid";
+    int ret = -1;
+
+    if (mkdir(mnt_dir, 0755) < 0) {
+        THROW_ERROR("failed to create the mount point");
+    }
+    if (mount("none", mnt_dir, "nonexistent_fs", 0, NULL) == 0 || errno != ENODEV) {
+        printf("\t\tERROR: mounting an unknown file system should fail\n");
+        goto out;
+    }
+    if (mount("none", mnt_dir, "ramfs", MS_REMOUNT, NULL) == 0 || errno != EINVAL) {
+        printf("\t\tERROR: remounting should fail\n");
+        goto out;
+    }
+    if (mount("/", mnt_dir, "hostfs", 0, NULL) == 0 || errno != EPERM) {
+        printf("\t\tERROR: mounting a host dir that is not allowed should fail\n");
+        goto out;
+    }
+    if (mount("none", "/root/nonexistent", "ramfs", 0, NULL) == 0 || errno != ENOENT) {
+        printf("\t\tERROR: mounting on a nonexistent dir should fail\n");
+        goto out;
+    }
+    if (mount("/root/nonexistent", mnt_dir, NULL, MS_BIND, NULL) == 0 || errno != ENOENT) {
+        printf("\t\tERROR: bind mounting a nonexistent dir should fail\n");
+        goto out;
+    }
+    if (umount2(mnt_dir, 0) == 0 || errno != EINVAL) {
+        printf("\t\tERROR: unmounting a dir that is not a mount point should fail\n");
+        goto out;
+    }
+    if (umount2("/proc", 0) == 0 || errno != EPERM) {
+        printf("\t\tERROR: unmounting a file system in the config should fail\n");
+        goto out;
+    }
+    ret = 0;
+
+out:
+    rmdir(mnt_dir);
+    return ret;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_mount_ramfs),
+    TEST_CASE(test_mount_tmpfs),
+    TEST_CASE(test_mount_hostfs),
+    TEST_CASE(test_bind_mount),
+    TEST_CASE(test_bind_mount_read_only),
+    TEST_CASE(test_mount_with_invalid_args),
+};
+
+int main(int argc, const char *argv[]) {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
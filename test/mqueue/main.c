@@ -0,0 +1,274 @@
+#define _GNU_SOURCE
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <mqueue.h>
+#include <signal.h>
+#include <spawn.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <time.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define MQ_NAME         "/test_mqueue"
+#define MQ_FILE         "/dev/mqueue/test_mqueue"
+#define MQ_MAXMSG       4
+#define MQ_MSGSIZE      64
+#define CHILD_PROG      "/bin/mqueue"
+
+static mqd_t open_new_mqueue(int flags) {
+    struct mq_attr attr = {
+        .mq_maxmsg = MQ_MAXMSG,
+        .mq_msgsize = MQ_MSGSIZE,
+    };
+    return mq_open(MQ_NAME, O_CREAT | O_EXCL | O_RDWR | flags, 0600, &attr);
+}
+
+static int spawn_child(const char *test_name, int *child_pid) {
+    const char *child_argv[3] = { CHILD_PROG, test_name, NULL };
+    if (posix_spawn(child_pid, CHILD_PROG, NULL, NULL,
+                    (char *const *)child_argv, NULL) != 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+    return 0;
+}
+
+static int wait_child(int child_pid) {
+    int status = 0;
+    int ret;
+    // The notification may interrupt the waiting
+    while ((ret = wait4(child_pid, &status, 0, NULL)) < 0 && errno == EINTR) {
+    }
+    if (ret < 0) {
+        THROW_ERROR("failed to wait4 the child process");
+    }
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        THROW_ERROR("the child process failed");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Child processes
+// ============================================================================
+
+static int child_test_send(void) {
+    mqd_t mqd = mq_open(MQ_NAME, O_WRONLY);
+    if (mqd == (mqd_t) -1) {
+        THROW_ERROR("failed to open the queue");
+    }
+    const char *msg = "hello from child";
+    if (mq_send(mqd, msg, strlen(msg) + 1, 0) < 0) {
+        THROW_ERROR("failed to send the message");
+    }
+    mq_close(mqd);
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_open_and_unlink() {
+    mqd_t mqd = open_new_mqueue(0);
+    if (mqd == (mqd_t) -1) {
+        THROW_ERROR("failed to create the queue");
+    }
+    if (open_new_mqueue(0) != (mqd_t) -1 || errno != EEXIST) {
+        THROW_ERROR("the queue is created twice");
+    }
+    mq_close(mqd);
+
+    if (mq_unlink(MQ_NAME) < 0) {
+        THROW_ERROR("failed to unlink the queue");
+    }
+    if (mq_open(MQ_NAME, O_RDWR) != (mqd_t) -1 || errno != ENOENT) {
+        THROW_ERROR("the queue is not unlinked");
+    }
+    if (mq_unlink(MQ_NAME) == 0 || errno != ENOENT) {
+        THROW_ERROR("the queue is unlinked twice");
+    }
+    return 0;
+}
+
+static int test_send_and_receive_by_priority() {
+    mqd_t mqd = open_new_mqueue(0);
+    if (mqd == (mqd_t) -1) {
+        THROW_ERROR("failed to create the queue");
+    }
+
+    const char *msgs[] = { "low", "high", "middle", "high2" };
+    unsigned int prios[] = { 1, 10, 5, 10 };
+    for (int i = 0; i < 4; i++) {
+        if (mq_send(mqd, msgs[i], strlen(msgs[i]) + 1, prios[i]) < 0) {
+            THROW_ERROR("failed to send the message");
+        }
+    }
+
+    // Messages of higher priorities come first, in FIFO order for the same priority
+    const char *expected_msgs[] = { "high", "high2", "middle", "low" };
+    unsigned int expected_prios[] = { 10, 10, 5, 1 };
+    char buf[MQ_MSGSIZE];
+    for (int i = 0; i < 4; i++) {
+        unsigned int prio = 0;
+        ssize_t len = mq_receive(mqd, buf, sizeof(buf), &prio);
+        if (len != strlen(expected_msgs[i]) + 1 || strcmp(buf, expected_msgs[i]) != 0 ||
+                prio != expected_prios[i]) {
+            THROW_ERROR("the message is not received in the order of priorities");
+        }
+    }
+
+    mq_close(mqd);
+    mq_unlink(MQ_NAME);
+    return 0;
+}
+
+static int test_nonblocking_and_timeout() {
+    mqd_t mqd = open_new_mqueue(O_NONBLOCK);
+    if (mqd == (mqd_t) -1) {
+        THROW_ERROR("failed to create the queue");
+    }
+
+    char buf[MQ_MSGSIZE] = { 0 };
+    if (mq_receive(mqd, buf, sizeof(buf), NULL) != -1 || errno != EAGAIN) {
+        THROW_ERROR("receiving from an empty queue does not fail with EAGAIN");
+    }
+    for (int i = 0; i < MQ_MAXMSG; i++) {
+        if (mq_send(mqd, buf, 1, 0) < 0) {
+            THROW_ERROR("failed to send the message");
+        }
+    }
+    if (mq_send(mqd, buf, 1, 0) != -1 || errno != EAGAIN) {
+        THROW_ERROR("sending to a full queue does not fail with EAGAIN");
+    }
+    if (mq_send(mqd, buf, MQ_MSGSIZE + 1, 0) != -1 || errno != EMSGSIZE) {
+        THROW_ERROR("sending a too large message does not fail with EMSGSIZE");
+    }
+
+    // Clear O_NONBLOCK and wait for a timeout
+    struct mq_attr attr = { .mq_flags = 0 };
+    struct mq_attr old_attr;
+    if (mq_setattr(mqd, &attr, &old_attr) < 0) {
+        THROW_ERROR("failed to set the attributes");
+    }
+    if (!(old_attr.mq_flags & O_NONBLOCK) || old_attr.mq_maxmsg != MQ_MAXMSG ||
+            old_attr.mq_msgsize != MQ_MSGSIZE || old_attr.mq_curmsgs != MQ_MAXMSG) {
+        THROW_ERROR("the attributes are wrong");
+    }
+    struct timespec abs_timeout;
+    clock_gettime(CLOCK_REALTIME, &abs_timeout);
+    abs_timeout.tv_nsec += 100 * 1000 * 1000;
+    if (abs_timeout.tv_nsec >= 1000 * 1000 * 1000) {
+        abs_timeout.tv_sec += 1;
+        abs_timeout.tv_nsec -= 1000 * 1000 * 1000;
+    }
+    if (mq_timedsend(mqd, buf, 1, 0, &abs_timeout) != -1 || errno != ETIMEDOUT) {
+        THROW_ERROR("sending to a full queue does not time out");
+    }
+
+    mq_close(mqd);
+    mq_unlink(MQ_NAME);
+    return 0;
+}
+
+static volatile sig_atomic_t notified = 0;
+
+static void notify_handler(int signum, siginfo_t *info, void *ucontext) {
+    if (info->si_code == SI_MESGQ && info->si_value.sival_int == 42) {
+        notified = 1;
+    }
+}
+
+static int test_notify_by_signal() {
+    mqd_t mqd = open_new_mqueue(0);
+    if (mqd == (mqd_t) -1) {
+        THROW_ERROR("failed to create the queue");
+    }
+
+    struct sigaction sa = { 0 };
+    sa.sa_sigaction = notify_handler;
+    sa.sa_flags = SA_SIGINFO;
+    if (sigaction(SIGUSR1, &sa, NULL) < 0) {
+        THROW_ERROR("failed to set the signal handler");
+    }
+    struct sigevent sev = { 0 };
+    sev.sigev_notify = SIGEV_SIGNAL;
+    sev.sigev_signo = SIGUSR1;
+    sev.sigev_value.sival_int = 42;
+    if (mq_notify(mqd, &sev) < 0) {
+        THROW_ERROR("failed to register the notification");
+    }
+    if (mq_notify(mqd, &sev) != -1 || errno != EBUSY) {
+        THROW_ERROR("the notification is registered twice");
+    }
+
+    int fd = open(MQ_FILE, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the file of the queue");
+    }
+    char status[128] = { 0 };
+    if (read(fd, status, sizeof(status) - 1) < 0 || strstr(status, "SIGNO:10") == NULL) {
+        THROW_ERROR("the status of the queue is wrong");
+    }
+    close(fd);
+
+    int child_pid;
+    if (spawn_child("send", &child_pid) < 0 || wait_child(child_pid) < 0) {
+        THROW_ERROR("the child fails to send the message");
+    }
+    for (int i = 0; i < 100 && !notified; i++) {
+        usleep(10 * 1000);
+    }
+    if (!notified) {
+        THROW_ERROR("the process is not notified");
+    }
+
+    // The notification is removed after it is sent
+    if (mq_notify(mqd, &sev) < 0) {
+        THROW_ERROR("failed to register the notification again");
+    }
+    if (mq_notify(mqd, NULL) < 0) {
+        THROW_ERROR("failed to unregister the notification");
+    }
+
+    fd = open(MQ_FILE, O_RDONLY);
+    memset(status, 0, sizeof(status));
+    if (fd < 0 || read(fd, status, sizeof(status) - 1) < 0 ||
+            strncmp(status, "QSIZE:17 ", 9) != 0) {
+        THROW_ERROR("the size of the queue is wrong");
+    }
+    close(fd);
+
+    mq_close(mqd);
+    mq_unlink(MQ_NAME);
+    signal(SIGUSR1, SIG_DFL);
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_open_and_unlink),
+    TEST_CASE(test_send_and_receive_by_priority),
+    TEST_CASE(test_nonblocking_and_timeout),
+    TEST_CASE(test_notify_by_signal),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 1) {
+        if (strcmp(argv[1], "send") == 0) {
+            return child_test_send() < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+        }
+        return EXIT_FAILURE;
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,227 @@
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+#include <errno.h>
+#include <pthread.h>
+#include <signal.h>
+#include <unistd.h>
+#include <string.h>
+
+#include "test.h"
+
+#define MSG         "hello"
+#define MSG_LEN     (sizeof(MSG) - 1)
+
+static volatile int sigpipe_count = 0;
+
+// ============================================================================
+// Helper function
+// ============================================================================
+static void sigpipe_handler(int signum) {
+    sigpipe_count++;
+}
+
+static int connect_tcp_pair(int *client, int *server) {
+    struct sockaddr_in addr = {0};
+    socklen_t addr_len = sizeof(addr);
+    addr.sin_family = AF_INET;
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+
+    int listener = socket(AF_INET, SOCK_STREAM, 0);
+    if (listener < 0) {
+        THROW_ERROR("failed to create a TCP socket");
+    }
+    if (bind(listener, (struct sockaddr *)&addr, sizeof(addr)) < 0 ||
+            getsockname(listener, (struct sockaddr *)&addr, &addr_len) < 0 ||
+            listen(listener, 1) < 0) {
+        close(listener);
+        THROW_ERROR("failed to listen on the socket");
+    }
+
+    *client = socket(AF_INET, SOCK_STREAM, 0);
+    if (*client < 0 || connect(*client, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        close(listener);
+        THROW_ERROR("failed to connect to the listening socket");
+    }
+    *server = accept(listener, NULL, NULL);
+    close(listener);
+    if (*server < 0) {
+        close(*client);
+        THROW_ERROR("failed to accept the connection");
+    }
+    return 0;
+}
+
+// Receive with MSG_PEEK and then without it, which get the same data
+static int check_peek(int sock) {
+    char buf[32] = {0};
+    if (recv(sock, buf, sizeof(buf), MSG_PEEK) != MSG_LEN || memcmp(buf, MSG, MSG_LEN) != 0) {
+        THROW_ERROR("failed to peek the data");
+    }
+    memset(buf, 0, sizeof(buf));
+    if (recv(sock, buf, sizeof(buf), 0) != MSG_LEN || memcmp(buf, MSG, MSG_LEN) != 0) {
+        THROW_ERROR("the data received is not the one peeked");
+    }
+    return 0;
+}
+
+static int check_dontwait(int sock) {
+    char buf[32];
+    if (recv(sock, buf, sizeof(buf), MSG_DONTWAIT) >= 0 || errno != EAGAIN) {
+        THROW_ERROR("recv should fail with EAGAIN given MSG_DONTWAIT");
+    }
+    return 0;
+}
+
+static void *write_slowly(void *arg) {
+    int sock = *(int *)arg;
+    for (int i = 0; i < MSG_LEN; i++) {
+        usleep(10 * 1000);
+        write(sock, &MSG[i], 1);
+    }
+    return NULL;
+}
+
+// The data written byte by byte is received as a whole given MSG_WAITALL
+static int check_waitall(int reader, int writer) {
+    pthread_t thread;
+    if (pthread_create(&thread, NULL, write_slowly, &writer) != 0) {
+        THROW_ERROR("failed to create a thread");
+    }
+
+    char buf[MSG_LEN] = {0};
+    ssize_t len = recv(reader, buf, sizeof(buf), MSG_WAITALL);
+    pthread_join(thread, NULL);
+    if (len != MSG_LEN || memcmp(buf, MSG, MSG_LEN) != 0) {
+        THROW_ERROR("failed to receive all the data given MSG_WAITALL");
+    }
+    return 0;
+}
+
+static int check_nosignal(int sock) {
+    int count = sigpipe_count;
+    if (send(sock, MSG, MSG_LEN, MSG_NOSIGNAL) >= 0 || errno != EPIPE) {
+        THROW_ERROR("send should fail with EPIPE");
+    }
+    if (sigpipe_count != count) {
+        THROW_ERROR("SIGPIPE should not be raised given MSG_NOSIGNAL");
+    }
+    if (send(sock, MSG, MSG_LEN, 0) >= 0 || errno != EPIPE) {
+        THROW_ERROR("send should fail with EPIPE");
+    }
+    if (sigpipe_count != count + 1) {
+        THROW_ERROR("SIGPIPE should be raised without MSG_NOSIGNAL");
+    }
+    return 0;
+}
+
+static int check_stream_flags(int sock, int peer) {
+    if (check_dontwait(sock) < 0) {
+        return -1;
+    }
+    if (write(peer, MSG, MSG_LEN) != MSG_LEN) {
+        THROW_ERROR("failed to write");
+    }
+    if (check_peek(sock) < 0 || check_waitall(sock, peer) < 0) {
+        return -1;
+    }
+    if (shutdown(sock, SHUT_WR) < 0) {
+        THROW_ERROR("failed to shutdown the socket");
+    }
+    return check_nosignal(sock);
+}
+
+// ============================================================================
+// Test cases for the flags of send and recv
+// ============================================================================
+static int test_unix_stream_flags() {
+    int socks[2];
+    if (socketpair(AF_UNIX, SOCK_STREAM, 0, socks) < 0) {
+        THROW_ERROR("failed to create a unix socket pair");
+    }
+    int ret = check_stream_flags(socks[0], socks[1]);
+    close(socks[0]);
+    close(socks[1]);
+    return ret;
+}
+
+static int test_tcp_flags() {
+    int client, server;
+    if (connect_tcp_pair(&client, &server) < 0) {
+        return -1;
+    }
+    int ret = check_stream_flags(client, server);
+    close(client);
+    close(server);
+    return ret;
+}
+
+static int test_tcp_trunc() {
+    int client, server;
+    if (connect_tcp_pair(&client, &server) < 0) {
+        return -1;
+    }
+
+    // The data is discarded given MSG_TRUNC
+    int ret = -1;
+    char buf[32] = {0};
+    if (write(client, MSG, MSG_LEN) != MSG_LEN ||
+            recv(server, buf, MSG_LEN, MSG_TRUNC | MSG_WAITALL) != MSG_LEN) {
+        printf("\t\tERROR: failed to receive with MSG_TRUNC\n");
+        goto out;
+    }
+    if (buf[0] != 0 || check_dontwait(server) < 0) {
+        printf("\t\tERROR: the data should be discarded\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    close(client);
+    close(server);
+    return ret;
+}
+
+static int test_unix_datagram_flags() {
+    int socks[2];
+    if (socketpair(AF_UNIX, SOCK_DGRAM, 0, socks) < 0) {
+        THROW_ERROR("failed to create a unix socket pair");
+    }
+
+    int ret = -1;
+    char buf[2];
+    if (check_dontwait(socks[0]) < 0) {
+        goto out;
+    }
+    if (write(socks[1], MSG, MSG_LEN) != MSG_LEN || check_peek(socks[0]) < 0) {
+        goto out;
+    }
+    // The real length of the truncated datagram is returned given MSG_TRUNC
+    if (write(socks[1], MSG, MSG_LEN) != MSG_LEN ||
+            recv(socks[0], buf, sizeof(buf), MSG_TRUNC) != MSG_LEN) {
+        printf("\t\tERROR: failed to receive the length of the datagram\n");
+        goto out;
+    }
+    if (shutdown(socks[0], SHUT_WR) < 0 || check_nosignal(socks[0]) < 0) {
+        goto out;
+    }
+    ret = 0;
+out:
+    close(socks[0]);
+    close(socks[1]);
+    return ret;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+static test_case_t test_cases[] = {
+    TEST_CASE(test_unix_stream_flags),
+    TEST_CASE(test_tcp_flags),
+    TEST_CASE(test_tcp_trunc),
+    TEST_CASE(test_unix_datagram_flags),
+};
+
+int main() {
+    signal(SIGPIPE, sigpipe_handler);
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,159 @@
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+#include <errno.h>
+#include <unistd.h>
+#include <string.h>
+
+#include "test.h"
+
+#define MULTICAST_GROUP     "239.255.0.1"
+#define MULTICAST_PORT      53535
+
+// ============================================================================
+// Helper function
+// ============================================================================
+static int create_bound_socket() {
+    int sock = socket(AF_INET, SOCK_DGRAM, 0);
+    if (sock < 0) {
+        THROW_ERROR("failed to create a UDP socket");
+    }
+
+    int reuse = 1;
+    struct sockaddr_in addr = {
+        .sin_family = AF_INET,
+        .sin_port = htons(MULTICAST_PORT),
+        .sin_addr.s_addr = htonl(INADDR_ANY),
+    };
+    if (setsockopt(sock, SOL_SOCKET, SO_REUSEADDR, &reuse, sizeof(reuse)) < 0 ||
+            bind(sock, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        close(sock);
+        THROW_ERROR("failed to bind the socket");
+    }
+    return sock;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+int test_multicast_ttl_and_loop() {
+    int sock = socket(AF_INET, SOCK_DGRAM, 0);
+    if (sock < 0) {
+        THROW_ERROR("failed to create a UDP socket");
+    }
+
+    int ttl = 4, loop = 0;
+    if (setsockopt(sock, IPPROTO_IP, IP_MULTICAST_TTL, &ttl, sizeof(ttl)) < 0 ||
+            setsockopt(sock, IPPROTO_IP, IP_MULTICAST_LOOP, &loop, sizeof(loop)) < 0) {
+        close(sock);
+        THROW_ERROR("failed to set the options");
+    }
+
+    ttl = 0;
+    loop = 1;
+    socklen_t len = sizeof(ttl);
+    if (getsockopt(sock, IPPROTO_IP, IP_MULTICAST_TTL, &ttl, &len) < 0 || ttl != 4) {
+        close(sock);
+        THROW_ERROR("failed to get IP_MULTICAST_TTL");
+    }
+    len = sizeof(loop);
+    if (getsockopt(sock, IPPROTO_IP, IP_MULTICAST_LOOP, &loop, &len) < 0 || loop != 0) {
+        close(sock);
+        THROW_ERROR("failed to get IP_MULTICAST_LOOP");
+    }
+
+    close(sock);
+    return 0;
+}
+
+int test_multicast_if() {
+    int sock = socket(AF_INET, SOCK_DGRAM, 0);
+    if (sock < 0) {
+        THROW_ERROR("failed to create a UDP socket");
+    }
+
+    struct in_addr ifaddr = { .s_addr = htonl(INADDR_LOOPBACK) };
+    if (setsockopt(sock, IPPROTO_IP, IP_MULTICAST_IF, &ifaddr, sizeof(ifaddr)) < 0) {
+        close(sock);
+        THROW_ERROR("failed to set IP_MULTICAST_IF");
+    }
+
+    ifaddr.s_addr = 0;
+    socklen_t len = sizeof(ifaddr);
+    if (getsockopt(sock, IPPROTO_IP, IP_MULTICAST_IF, &ifaddr, &len) < 0 ||
+            ifaddr.s_addr != htonl(INADDR_LOOPBACK)) {
+        close(sock);
+        THROW_ERROR("failed to get IP_MULTICAST_IF");
+    }
+
+    close(sock);
+    return 0;
+}
+
+int test_add_and_drop_membership() {
+    int sock = create_bound_socket();
+    if (sock < 0) {
+        THROW_ERROR("failed to create a socket");
+    }
+
+    struct ip_mreq mreq;
+    mreq.imr_multiaddr.s_addr = inet_addr(MULTICAST_GROUP);
+    mreq.imr_interface.s_addr = htonl(INADDR_LOOPBACK);
+    if (setsockopt(sock, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq, sizeof(mreq)) < 0) {
+        close(sock);
+        THROW_ERROR("failed to join the group");
+    }
+
+    // The group has been joined
+    if (setsockopt(sock, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq, sizeof(mreq)) == 0 ||
+            errno != EADDRINUSE) {
+        close(sock);
+        THROW_ERROR("joining the group twice should fail");
+    }
+
+    if (setsockopt(sock, IPPROTO_IP, IP_DROP_MEMBERSHIP, &mreq, sizeof(mreq)) < 0) {
+        close(sock);
+        THROW_ERROR("failed to leave the group");
+    }
+
+    // The group has been left
+    if (setsockopt(sock, IPPROTO_IP, IP_DROP_MEMBERSHIP, &mreq, sizeof(mreq)) == 0 ||
+            errno != EADDRNOTAVAIL) {
+        close(sock);
+        THROW_ERROR("leaving the group twice should fail");
+    }
+
+    close(sock);
+    return 0;
+}
+
+int test_add_membership_with_invalid_group() {
+    int sock = socket(AF_INET, SOCK_DGRAM, 0);
+    if (sock < 0) {
+        THROW_ERROR("failed to create a UDP socket");
+    }
+
+    struct ip_mreq mreq;
+    mreq.imr_multiaddr.s_addr = htonl(INADDR_LOOPBACK);
+    mreq.imr_interface.s_addr = htonl(INADDR_ANY);
+    int ret = setsockopt(sock, IPPROTO_IP, IP_ADD_MEMBERSHIP, &mreq, sizeof(mreq));
+    close(sock);
+    if (ret == 0 || errno != EINVAL) {
+        THROW_ERROR("joining a unicast address should fail");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+static test_case_t test_cases[] = {
+    TEST_CASE(test_multicast_ttl_and_loop),
+    TEST_CASE(test_multicast_if),
+    TEST_CASE(test_add_and_drop_membership),
+    TEST_CASE(test_add_membership_with_invalid_group),
+};
+
+int main() {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
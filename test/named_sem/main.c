@@ -0,0 +1,170 @@
+#define _GNU_SOURCE
+#include <sys/wait.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <semaphore.h>
+#include <spawn.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <time.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define SEM_NAME        "/test_named_sem"
+#define SEM_FILE        "/dev/shm/sem.test_named_sem"
+#define CHILD_PROG      "/bin/named_sem"
+
+static int spawn_child(const char *test_name, int *child_pid) {
+    const char *child_argv[3] = { CHILD_PROG, test_name, NULL };
+    if (posix_spawn(child_pid, CHILD_PROG, NULL, NULL,
+                    (char *const *)child_argv, NULL) != 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+    return 0;
+}
+
+static int wait_child(int child_pid) {
+    int status = 0;
+    if (wait4(child_pid, &status, 0, NULL) < 0) {
+        THROW_ERROR("failed to wait4 the child process");
+    }
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        THROW_ERROR("the child process failed");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Child processes
+// ============================================================================
+
+// The parent is blocked until the child posts the semaphore
+static int child_test_post(void) {
+    sem_t *sem = sem_open(SEM_NAME, 0);
+    if (sem == SEM_FAILED) {
+        THROW_ERROR("failed to open the semaphore");
+    }
+    usleep(100 * 1000);
+    if (sem_post(sem) < 0) {
+        THROW_ERROR("failed to post the semaphore");
+    }
+    sem_close(sem);
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_open_and_unlink() {
+    sem_t *sem = sem_open(SEM_NAME, O_CREAT | O_EXCL, 0600, 2);
+    if (sem == SEM_FAILED) {
+        THROW_ERROR("failed to create the semaphore");
+    }
+    if (sem_open(SEM_NAME, O_CREAT | O_EXCL, 0600, 0) != SEM_FAILED || errno != EEXIST) {
+        THROW_ERROR("the semaphore is created twice");
+    }
+    // The semaphore is a file in /dev/shm
+    if (access(SEM_FILE, F_OK) < 0) {
+        THROW_ERROR("the file of the semaphore does not exist");
+    }
+
+    int val = -1;
+    if (sem_getvalue(sem, &val) < 0 || val != 2) {
+        THROW_ERROR("the initial value is wrong");
+    }
+    if (sem_trywait(sem) < 0 || sem_trywait(sem) < 0) {
+        THROW_ERROR("failed to decrease the semaphore");
+    }
+    if (sem_trywait(sem) != -1 || errno != EAGAIN) {
+        THROW_ERROR("decreasing the semaphore of zero does not fail with EAGAIN");
+    }
+    sem_close(sem);
+
+    // The value persists after the semaphore is closed
+    sem = sem_open(SEM_NAME, 0);
+    if (sem == SEM_FAILED) {
+        THROW_ERROR("failed to open the semaphore again");
+    }
+    if (sem_getvalue(sem, &val) < 0 || val != 0) {
+        THROW_ERROR("the value is not kept");
+    }
+    sem_close(sem);
+
+    if (sem_unlink(SEM_NAME) < 0) {
+        THROW_ERROR("failed to unlink the semaphore");
+    }
+    if (sem_open(SEM_NAME, 0) != SEM_FAILED || errno != ENOENT) {
+        THROW_ERROR("the semaphore is not unlinked");
+    }
+    return 0;
+}
+
+static int test_timedwait() {
+    sem_t *sem = sem_open(SEM_NAME, O_CREAT | O_EXCL, 0600, 0);
+    if (sem == SEM_FAILED) {
+        THROW_ERROR("failed to create the semaphore");
+    }
+
+    struct timespec abs_timeout;
+    clock_gettime(CLOCK_REALTIME, &abs_timeout);
+    abs_timeout.tv_nsec += 100 * 1000 * 1000;
+    if (abs_timeout.tv_nsec >= 1000 * 1000 * 1000) {
+        abs_timeout.tv_sec += 1;
+        abs_timeout.tv_nsec -= 1000 * 1000 * 1000;
+    }
+    if (sem_timedwait(sem, &abs_timeout) != -1 || errno != ETIMEDOUT) {
+        THROW_ERROR("waiting for the semaphore does not time out");
+    }
+
+    sem_close(sem);
+    sem_unlink(SEM_NAME);
+    return 0;
+}
+
+static int test_synchronize_processes() {
+    sem_t *sem = sem_open(SEM_NAME, O_CREAT | O_EXCL, 0600, 0);
+    if (sem == SEM_FAILED) {
+        THROW_ERROR("failed to create the semaphore");
+    }
+
+    int child_pid;
+    if (spawn_child("post", &child_pid) < 0) {
+        THROW_ERROR("failed to spawn the child");
+    }
+    if (sem_wait(sem) < 0) {
+        THROW_ERROR("failed to wait for the semaphore");
+    }
+    if (wait_child(child_pid) < 0) {
+        THROW_ERROR("the child failed");
+    }
+
+    sem_close(sem);
+    sem_unlink(SEM_NAME);
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_open_and_unlink),
+    TEST_CASE(test_timedwait),
+    TEST_CASE(test_synchronize_processes),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 1) {
+        if (strcmp(argv[1], "post") == 0) {
+            return child_test_post() < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+        }
+        return EXIT_FAILURE;
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,221 @@
+#define _GNU_SOURCE
+#include <sys/socket.h>
+#include <linux/netlink.h>
+#include <linux/rtnetlink.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+#include <net/if.h>
+#include <ifaddrs.h>
+#include <errno.h>
+#include <unistd.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "test.h"
+
+#define RECV_BUF_SIZE   8192
+
+// ============================================================================
+// Helper function
+// ============================================================================
+struct link_request {
+    struct nlmsghdr nlh;
+    struct ifinfomsg ifi;
+};
+
+static int send_getlink_request(int sock, int flags, int ifindex, unsigned int seq) {
+    struct link_request req;
+    memset(&req, 0, sizeof(req));
+    req.nlh.nlmsg_len = sizeof(req);
+    req.nlh.nlmsg_type = RTM_GETLINK;
+    req.nlh.nlmsg_flags = NLM_F_REQUEST | flags;
+    req.nlh.nlmsg_seq = seq;
+    req.ifi.ifi_family = AF_UNSPEC;
+    req.ifi.ifi_index = ifindex;
+
+    struct sockaddr_nl kernel = { .nl_family = AF_NETLINK };
+    if (sendto(sock, &req, sizeof(req), 0, (struct sockaddr *)&kernel,
+               sizeof(kernel)) != sizeof(req)) {
+        THROW_ERROR("failed to send the request");
+    }
+    return 0;
+}
+
+// Find the index of the loopback interface in the RTM_GETLINK dump
+static int dump_links(int sock, int *lo_index) {
+    char buf[RECV_BUF_SIZE];
+    unsigned int seq = 1;
+    int done = 0;
+    *lo_index = 0;
+
+    if (send_getlink_request(sock, NLM_F_DUMP, 0, seq) < 0) {
+        THROW_ERROR("failed to request the dump");
+    }
+
+    while (!done) {
+        struct sockaddr_nl src;
+        socklen_t src_len = sizeof(src);
+        int len = recvfrom(sock, buf, sizeof(buf), 0, (struct sockaddr *)&src, &src_len);
+        if (len <= 0) {
+            THROW_ERROR("failed to receive the dump");
+        }
+        if (src_len != sizeof(src) || src.nl_family != AF_NETLINK || src.nl_pid != 0) {
+            THROW_ERROR("the dump is not from the kernel");
+        }
+
+        struct nlmsghdr *nlh;
+        for (nlh = (struct nlmsghdr *)buf; NLMSG_OK(nlh, len); nlh = NLMSG_NEXT(nlh, len)) {
+            if (nlh->nlmsg_seq != seq) {
+                THROW_ERROR("the sequence number is wrong");
+            }
+            if (nlh->nlmsg_type == NLMSG_DONE) {
+                done = 1;
+                break;
+            }
+            if (nlh->nlmsg_type != RTM_NEWLINK || !(nlh->nlmsg_flags & NLM_F_MULTI)) {
+                THROW_ERROR("unexpected message in the dump");
+            }
+
+            struct ifinfomsg *ifi = NLMSG_DATA(nlh);
+            int attr_len = IFLA_PAYLOAD(nlh);
+            struct rtattr *rta;
+            for (rta = IFLA_RTA(ifi); RTA_OK(rta, attr_len); rta = RTA_NEXT(rta, attr_len)) {
+                if (rta->rta_type == IFLA_IFNAME && strcmp(RTA_DATA(rta), "lo") == 0) {
+                    *lo_index = ifi->ifi_index;
+                }
+            }
+        }
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+int test_getlink_dump() {
+    int sock = socket(AF_NETLINK, SOCK_RAW | SOCK_CLOEXEC, NETLINK_ROUTE);
+    if (sock < 0) {
+        THROW_ERROR("failed to create a netlink socket");
+    }
+
+    int lo_index;
+    if (dump_links(sock, &lo_index) < 0) {
+        close(sock);
+        THROW_ERROR("failed to dump the links");
+    }
+    close(sock);
+
+    if (lo_index == 0 || lo_index != if_nametoindex("lo")) {
+        THROW_ERROR("failed to find the loopback interface");
+    }
+    return 0;
+}
+
+int test_getlink_by_index() {
+    int sock = socket(AF_NETLINK, SOCK_DGRAM, NETLINK_ROUTE);
+    if (sock < 0) {
+        THROW_ERROR("failed to create a netlink socket");
+    }
+
+    char buf[RECV_BUF_SIZE];
+    int lo_index = if_nametoindex("lo");
+    if (send_getlink_request(sock, 0, lo_index, 2) < 0 ||
+            recv(sock, buf, sizeof(buf), 0) <= 0) {
+        close(sock);
+        THROW_ERROR("failed to get the link");
+    }
+    struct nlmsghdr *nlh = (struct nlmsghdr *)buf;
+    struct ifinfomsg *ifi = NLMSG_DATA(nlh);
+    if (nlh->nlmsg_type != RTM_NEWLINK || ifi->ifi_index != lo_index ||
+            !(ifi->ifi_flags & IFF_LOOPBACK)) {
+        close(sock);
+        THROW_ERROR("the link is wrong");
+    }
+
+    // The link of an unknown index does not exist
+    if (send_getlink_request(sock, 0, 0x7fffffff, 3) < 0 ||
+            recv(sock, buf, sizeof(buf), 0) <= 0) {
+        close(sock);
+        THROW_ERROR("failed to get the error");
+    }
+    struct nlmsgerr *err = NLMSG_DATA(nlh);
+    if (nlh->nlmsg_type != NLMSG_ERROR || err->error != -ENODEV) {
+        close(sock);
+        THROW_ERROR("the error is wrong");
+    }
+
+    close(sock);
+    return 0;
+}
+
+int test_getsockname() {
+    int sock = socket(AF_NETLINK, SOCK_RAW, NETLINK_ROUTE);
+    if (sock < 0) {
+        THROW_ERROR("failed to create a netlink socket");
+    }
+
+    struct sockaddr_nl addr = { .nl_family = AF_NETLINK };
+    if (bind(sock, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        close(sock);
+        THROW_ERROR("failed to bind the socket");
+    }
+    socklen_t addr_len = sizeof(addr);
+    if (getsockname(sock, (struct sockaddr *)&addr, &addr_len) < 0 ||
+            addr_len != sizeof(addr) || addr.nl_family != AF_NETLINK ||
+            addr.nl_pid == 0) {
+        close(sock);
+        THROW_ERROR("failed to get the bound address");
+    }
+
+    // The port ID is in use
+    int sock2 = socket(AF_NETLINK, SOCK_RAW, NETLINK_ROUTE);
+    if (sock2 < 0) {
+        close(sock);
+        THROW_ERROR("failed to create a netlink socket");
+    }
+    int ret = bind(sock2, (struct sockaddr *)&addr, sizeof(addr));
+    close(sock2);
+    close(sock);
+    if (ret == 0 || errno != EADDRINUSE) {
+        THROW_ERROR("binding to the port ID in use should fail");
+    }
+    return 0;
+}
+
+int test_getifaddrs() {
+    struct ifaddrs *ifaddrs;
+    if (getifaddrs(&ifaddrs) < 0) {
+        THROW_ERROR("getifaddrs failed");
+    }
+
+    int found = 0;
+    for (struct ifaddrs *ifa = ifaddrs; ifa != NULL; ifa = ifa->ifa_next) {
+        if (strcmp(ifa->ifa_name, "lo") == 0 && ifa->ifa_addr != NULL &&
+                ifa->ifa_addr->sa_family == AF_INET) {
+            struct sockaddr_in *sin = (struct sockaddr_in *)ifa->ifa_addr;
+            if (sin->sin_addr.s_addr == htonl(INADDR_LOOPBACK)) {
+                found = 1;
+            }
+        }
+    }
+    freeifaddrs(ifaddrs);
+
+    if (!found) {
+        THROW_ERROR("failed to find the address of the loopback interface");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+static test_case_t test_cases[] = {
+    TEST_CASE(test_getlink_dump),
+    TEST_CASE(test_getlink_by_index),
+    TEST_CASE(test_getsockname),
+    TEST_CASE(test_getifaddrs),
+};
+
+int main() {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,254 @@
+#define _GNU_SOURCE
+#include <sys/socket.h>
+#include <sys/epoll.h>
+#include <sys/un.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <poll.h>
+#include <unistd.h>
+#include <string.h>
+
+#include "test.h"
+
+#define TIMEOUT_MS  3000
+#define UNIX_PATH   "/tmp/nonblock_connect.sock"
+
+// ============================================================================
+// Helper function
+// ============================================================================
+static int create_tcp_listener(struct sockaddr_in *addr) {
+    int sock = socket(AF_INET, SOCK_STREAM, 0);
+    if (sock < 0) {
+        THROW_ERROR("failed to create a TCP socket");
+    }
+
+    socklen_t addr_len = sizeof(*addr);
+    memset(addr, 0, sizeof(*addr));
+    addr->sin_family = AF_INET;
+    addr->sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    if (bind(sock, (struct sockaddr *)addr, sizeof(*addr)) < 0 ||
+            getsockname(sock, (struct sockaddr *)addr, &addr_len) < 0 ||
+            listen(sock, 4) < 0) {
+        close(sock);
+        THROW_ERROR("failed to listen on the socket");
+    }
+    return sock;
+}
+
+static int get_so_error(int sock) {
+    int error = -1;
+    socklen_t len = sizeof(error);
+    if (getsockopt(sock, SOL_SOCKET, SO_ERROR, &error, &len) < 0) {
+        THROW_ERROR("failed to get SO_ERROR");
+    }
+    return error;
+}
+
+// Wait until the connection in progress completes, as reported by EPOLLOUT
+static int wait_for_connect(int sock) {
+    int epfd = epoll_create1(0);
+    if (epfd < 0) {
+        THROW_ERROR("failed to create an epoll file");
+    }
+
+    int ret = -1;
+    struct epoll_event event = { .events = EPOLLOUT, .data.fd = sock };
+    if (epoll_ctl(epfd, EPOLL_CTL_ADD, sock, &event) < 0) {
+        printf("\t\tERROR: failed to add the socket to the epoll file\n");
+        goto out;
+    }
+    if (epoll_wait(epfd, &event, 1, TIMEOUT_MS) != 1 || !(event.events & EPOLLOUT)) {
+        printf("\t\tERROR: the connection is not completed in time\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    close(epfd);
+    return ret;
+}
+
+static int check_accepted_flags(int fd) {
+    int status_flags = fcntl(fd, F_GETFL);
+    int fd_flags = fcntl(fd, F_GETFD);
+    if (status_flags < 0 || fd_flags < 0) {
+        THROW_ERROR("failed to get the flags of the accepted socket");
+    }
+    if (!(status_flags & O_NONBLOCK) || !(fd_flags & FD_CLOEXEC)) {
+        THROW_ERROR("the flags of accept4 are not set on the accepted socket");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases for nonblocking connect and accept4
+// ============================================================================
+static int test_tcp_nonblocking_connect() {
+    struct sockaddr_in addr;
+    int listener = create_tcp_listener(&addr);
+    if (listener < 0) {
+        return -1;
+    }
+
+    int ret = -1;
+    int accepted = -1;
+    int sock = socket(AF_INET, SOCK_STREAM | SOCK_NONBLOCK, 0);
+    if (sock < 0) {
+        printf("\t\tERROR: failed to create a TCP socket\n");
+        goto out;
+    }
+    if (connect(sock, (struct sockaddr *)&addr, sizeof(addr)) < 0 && errno != EINPROGRESS) {
+        printf("\t\tERROR: failed to start the connection\n");
+        goto out;
+    }
+    if (wait_for_connect(sock) < 0 || get_so_error(sock) != 0) {
+        printf("\t\tERROR: the connection is not established\n");
+        goto out;
+    }
+    // The connection is established
+    if (connect(sock, (struct sockaddr *)&addr, sizeof(addr)) == 0 || errno != EISCONN) {
+        printf("\t\tERROR: connect should fail with EISCONN\n");
+        goto out;
+    }
+
+    accepted = accept4(listener, NULL, NULL, SOCK_NONBLOCK | SOCK_CLOEXEC);
+    if (accepted < 0 || check_accepted_flags(accepted) < 0) {
+        printf("\t\tERROR: failed to accept the connection\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    if (accepted >= 0) {
+        close(accepted);
+    }
+    if (sock >= 0) {
+        close(sock);
+    }
+    close(listener);
+    return ret;
+}
+
+static int test_tcp_nonblocking_connect_refused() {
+    // Get a port that no one listens on
+    struct sockaddr_in addr;
+    int listener = create_tcp_listener(&addr);
+    if (listener < 0) {
+        return -1;
+    }
+    close(listener);
+
+    int sock = socket(AF_INET, SOCK_STREAM | SOCK_NONBLOCK, 0);
+    if (sock < 0) {
+        THROW_ERROR("failed to create a TCP socket");
+    }
+
+    int ret = -1;
+    if (connect(sock, (struct sockaddr *)&addr, sizeof(addr)) == 0) {
+        printf("\t\tERROR: the connection should be refused\n");
+        goto out;
+    }
+    if (errno == EINPROGRESS) {
+        struct pollfd pfd = { .fd = sock, .events = POLLOUT };
+        if (poll(&pfd, 1, TIMEOUT_MS) != 1 || !(pfd.revents & POLLERR)) {
+            printf("\t\tERROR: the failure is not reported by poll\n");
+            goto out;
+        }
+        if (get_so_error(sock) != ECONNREFUSED) {
+            printf("\t\tERROR: SO_ERROR should be ECONNREFUSED\n");
+            goto out;
+        }
+        // The error is cleared once it is got
+        if (get_so_error(sock) != 0) {
+            printf("\t\tERROR: SO_ERROR is not cleared\n");
+            goto out;
+        }
+    } else if (errno != ECONNREFUSED) {
+        printf("\t\tERROR: connect should fail with ECONNREFUSED\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    close(sock);
+    return ret;
+}
+
+static int test_unix_nonblocking_connect() {
+    struct sockaddr_un addr = { .sun_family = AF_UNIX };
+    strncpy(addr.sun_path, UNIX_PATH, sizeof(addr.sun_path) - 1);
+    unlink(UNIX_PATH);
+
+    int listener = socket(AF_UNIX, SOCK_STREAM, 0);
+    if (listener < 0) {
+        THROW_ERROR("failed to create a unix socket");
+    }
+
+    int ret = -1;
+    int accepted = -1;
+    int sock = -1;
+    if (bind(listener, (struct sockaddr *)&addr, sizeof(addr)) < 0 ||
+            listen(listener, 4) < 0) {
+        printf("\t\tERROR: failed to listen on the unix socket\n");
+        goto out;
+    }
+    sock = socket(AF_UNIX, SOCK_STREAM | SOCK_NONBLOCK, 0);
+    if (sock < 0) {
+        printf("\t\tERROR: failed to create a unix socket\n");
+        goto out;
+    }
+    if (connect(sock, (struct sockaddr *)&addr, sizeof(addr)) < 0 && errno != EINPROGRESS) {
+        printf("\t\tERROR: failed to connect the unix socket\n");
+        goto out;
+    }
+    if (wait_for_connect(sock) < 0 || get_so_error(sock) != 0) {
+        printf("\t\tERROR: the connection is not established\n");
+        goto out;
+    }
+
+    accepted = accept4(listener, NULL, NULL, SOCK_NONBLOCK | SOCK_CLOEXEC);
+    if (accepted < 0 || check_accepted_flags(accepted) < 0) {
+        printf("\t\tERROR: failed to accept the connection\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    if (accepted >= 0) {
+        close(accepted);
+    }
+    if (sock >= 0) {
+        close(sock);
+    }
+    close(listener);
+    unlink(UNIX_PATH);
+    return ret;
+}
+
+static int test_accept4_invalid_flags() {
+    struct sockaddr_in addr;
+    int listener = create_tcp_listener(&addr);
+    if (listener < 0) {
+        return -1;
+    }
+
+    int ret = accept4(listener, NULL, NULL, O_APPEND);
+    int err = errno;
+    close(listener);
+    if (ret >= 0 || err != EINVAL) {
+        THROW_ERROR("accept4 should fail with EINVAL for invalid flags");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+static test_case_t test_cases[] = {
+    TEST_CASE(test_tcp_nonblocking_connect),
+    TEST_CASE(test_tcp_nonblocking_connect_refused),
+    TEST_CASE(test_unix_nonblocking_connect),
+    TEST_CASE(test_accept4_invalid_flags),
+};
+
+int main() {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,209 @@
+#include <sys/ioctl.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <pthread.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// The interface of /dev/occlum_ipc
+// ============================================================================
+
+struct occlum_ipc_connect_arg {
+    const char *name;   // Input
+    int flags;          // Input: O_NONBLOCK and O_CLOEXEC
+    int fd;             // Output
+};
+
+#define OCCLUM_IPC_CONNECT  _IOWR('i', 0, struct occlum_ipc_connect_arg)
+
+#define OCCLUM_IPC_MSG_MAX  (64 * 1024 - 4 - 16)
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+static int ipc_connect(const char *name, int flags) {
+    int dev_fd = open("/dev/occlum_ipc", O_RDONLY);
+    if (dev_fd < 0) {
+        return -1;
+    }
+    struct occlum_ipc_connect_arg arg = {
+        .name = name,
+        .flags = flags,
+        .fd = -1,
+    };
+    int ret = ioctl(dev_fd, OCCLUM_IPC_CONNECT, &arg);
+    close(dev_fd);
+    return ret < 0 ? -1 : arg.fd;
+}
+
+struct peer_arg {
+    const char *name;
+    int fd;
+};
+
+static void *connect_peer(void *_arg) {
+    struct peer_arg *arg = _arg;
+    arg->fd = ipc_connect(arg->name, 0);
+    return NULL;
+}
+
+// Connect to the channel of the name from both sides, which are the current
+// thread and a new thread, within the same enclave
+static int connect_pair(const char *name, int fds[2]) {
+    struct peer_arg arg = { .name = name, .fd = -1 };
+    pthread_t thread;
+    if (pthread_create(&thread, NULL, connect_peer, &arg) != 0) {
+        THROW_ERROR("failed to create the thread");
+    }
+    fds[0] = ipc_connect(name, 0);
+    pthread_join(thread, NULL);
+    if (fds[0] < 0 || arg.fd < 0) {
+        THROW_ERROR("failed to connect the channel");
+    }
+    fds[1] = arg.fd;
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+int test_invalid_name(void) {
+    const char *names[] = { "", "a/b", "../a" };
+    for (int i = 0; i < sizeof(names) / sizeof(names[0]); i++) {
+        if (ipc_connect(names[i], 0) >= 0 || errno != EINVAL) {
+            THROW_ERROR("the invalid name is accepted");
+        }
+    }
+    return 0;
+}
+
+int test_send_and_recv(void) {
+    int fds[2];
+    if (connect_pair("test_send_and_recv", fds) < 0) {
+        THROW_ERROR("failed to connect");
+    }
+
+    const char *msgs[] = { "hello", "occlum", "ipc" };
+    for (int i = 0; i < 3; i++) {
+        if (write(fds[i % 2], msgs[i], strlen(msgs[i])) != strlen(msgs[i])) {
+            THROW_ERROR("failed to send the message");
+        }
+    }
+    // The boundaries of the messages are preserved
+    char buf[64];
+    for (int i = 0; i < 3; i++) {
+        int len = read(fds[1 - i % 2], buf, sizeof(buf));
+        if (len != strlen(msgs[i]) || memcmp(buf, msgs[i], len) != 0) {
+            THROW_ERROR("the message is wrong");
+        }
+    }
+
+    close(fds[0]);
+    close(fds[1]);
+    return 0;
+}
+
+int test_msg_size(void) {
+    int fds[2];
+    if (connect_pair("test_msg_size", fds) < 0) {
+        THROW_ERROR("failed to connect");
+    }
+
+    static char buf[OCCLUM_IPC_MSG_MAX + 1];
+    memset(buf, 'a', sizeof(buf));
+    if (write(fds[0], buf, OCCLUM_IPC_MSG_MAX + 1) >= 0 || errno != EMSGSIZE) {
+        THROW_ERROR("the message should be too large");
+    }
+    if (write(fds[0], buf, OCCLUM_IPC_MSG_MAX) != OCCLUM_IPC_MSG_MAX) {
+        THROW_ERROR("failed to send the largest message");
+    }
+    // The extra bytes are discarded
+    if (read(fds[1], buf, 10) != 10) {
+        THROW_ERROR("failed to recv the truncated message");
+    }
+
+    close(fds[0]);
+    close(fds[1]);
+    return 0;
+}
+
+int test_nonblocking(void) {
+    int fds[2];
+    if (connect_pair("test_nonblocking", fds) < 0) {
+        THROW_ERROR("failed to connect");
+    }
+
+    if (fcntl(fds[1], F_SETFL, O_NONBLOCK) < 0) {
+        THROW_ERROR("failed to set O_NONBLOCK");
+    }
+    char buf[16];
+    if (read(fds[1], buf, sizeof(buf)) >= 0 || errno != EAGAIN) {
+        THROW_ERROR("read should return EAGAIN");
+    }
+
+    close(fds[0]);
+    close(fds[1]);
+    return 0;
+}
+
+int test_close_by_peer(void) {
+    int fds[2];
+    if (connect_pair("test_close_by_peer", fds) < 0) {
+        THROW_ERROR("failed to connect");
+    }
+
+    if (write(fds[0], "bye", 3) != 3) {
+        THROW_ERROR("failed to send the message");
+    }
+    close(fds[0]);
+
+    // The messages sent before closing can still be received
+    char buf[16];
+    if (read(fds[1], buf, sizeof(buf)) != 3) {
+        THROW_ERROR("failed to recv the message");
+    }
+    if (read(fds[1], buf, sizeof(buf)) != 0) {
+        THROW_ERROR("read should return EOF");
+    }
+    if (write(fds[1], "hi", 2) >= 0 || errno != EPIPE) {
+        THROW_ERROR("write should return EPIPE");
+    }
+
+    close(fds[1]);
+    return 0;
+}
+
+int test_reuse_name(void) {
+    for (int i = 0; i < 2; i++) {
+        int fds[2];
+        if (connect_pair("test_reuse_name", fds) < 0) {
+            THROW_ERROR("failed to connect");
+        }
+        close(fds[0]);
+        close(fds[1]);
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_invalid_name),
+    TEST_CASE(test_send_and_recv),
+    TEST_CASE(test_msg_size),
+    TEST_CASE(test_nonblocking),
+    TEST_CASE(test_close_by_peer),
+    TEST_CASE(test_reuse_name),
+};
+
+int main(void) {
+    int ret = test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+    return ret;
+}
@@ -1,5 +1,10 @@
+#define _GNU_SOURCE
+#include <sys/stat.h>
+#include <sys/syscall.h>
 #include <fcntl.h>
 #include <errno.h>
+#include <stdint.h>
+#include <string.h>
 #include "test_fs.h"
 
 // ============================================================================
@@ -138,6 +143,171 @@ static int test_openat_with_dirfd() {
     return test_open_framework(__test_openat_with_dirfd);
 }
 
+// ============================================================================
+// Test cases for O_TMPFILE
+// ============================================================================
+
+static int test_open_tmpfile() {
+    const char *dir_path = "/root";
+    const char *file_path = "/root/test_filesystem_tmpfile.txt";
+    const char *write_str = "Hello World\n";
+    char read_buf[32] = { 0 };
+
+    int fd = open(dir_path, O_TMPFILE | O_RDWR, 00600);
+    if (fd < 0) {
+        THROW_ERROR("failed to open an unnamed temporary file");
+    }
+    if (write(fd, write_str, strlen(write_str)) != strlen(write_str)) {
+        THROW_ERROR("failed to write to the temporary file");
+    }
+    if (linkat(fd, "", AT_FDCWD, file_path, AT_EMPTY_PATH) < 0) {
+        THROW_ERROR("failed to link the temporary file");
+    }
+    close(fd);
+
+    fd = open(file_path, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the linked file");
+    }
+    if (read(fd, read_buf, sizeof(read_buf)) != strlen(write_str) ||
+            strcmp(read_buf, write_str) != 0) {
+        THROW_ERROR("the content of the linked file is not as expected");
+    }
+    close(fd);
+    return remove_file(file_path);
+}
+
+static int test_open_tmpfile_with_invalid_flags() {
+    const char *file_path = "/root/test_filesystem_tmpfile.txt";
+
+    int fd = open("/root", O_TMPFILE | O_RDONLY, 00600);
+    if (!(fd < 0 && errno == EINVAL)) {
+        THROW_ERROR("O_TMPFILE without write access should return EINVAL");
+    }
+    if (__test_open(file_path, O_RDONLY | O_CREAT | O_TRUNC, 00666) < 0) {
+        return -1;
+    }
+    fd = open(file_path, O_TMPFILE | O_RDWR, 00600);
+    if (!(fd < 0 && errno == ENOTDIR)) {
+        THROW_ERROR("O_TMPFILE on a file should return ENOTDIR");
+    }
+    if (remove_file(file_path) < 0) {
+        return -1;
+    }
+
+    // A temporary file opened with O_EXCL can never be linked
+    fd = open("/root", O_TMPFILE | O_EXCL | O_RDWR, 00600);
+    if (fd < 0) {
+        THROW_ERROR("failed to open an unnamed temporary file");
+    }
+    if (!(linkat(fd, "", AT_FDCWD, file_path, AT_EMPTY_PATH) < 0 && errno == ENOENT)) {
+        THROW_ERROR("linking a temporary file opened with O_EXCL should fail");
+    }
+    close(fd);
+    return 0;
+}
+
+// ============================================================================
+// Test cases for openat2
+// ============================================================================
+
+#ifndef SYS_openat2
+#define SYS_openat2 437
+#endif
+
+#define RESOLVE_NO_XDEV         0x01
+#define RESOLVE_NO_MAGICLINKS   0x02
+#define RESOLVE_NO_SYMLINKS     0x04
+#define RESOLVE_BENEATH         0x08
+#define RESOLVE_IN_ROOT         0x10
+
+struct open_how {
+    uint64_t flags;
+    uint64_t mode;
+    uint64_t resolve;
+};
+
+static int openat2(int dirfd, const char *path, uint64_t flags, uint64_t resolve) {
+    struct open_how how = { .flags = flags, .mode = 0, .resolve = resolve };
+    return syscall(SYS_openat2, dirfd, path, &how, sizeof(how));
+}
+
+#define OPENAT2_DIR         "/root/test_openat2_dir"
+#define OPENAT2_FILE        OPENAT2_DIR "/file.txt"
+#define OPENAT2_LINK        OPENAT2_DIR "/link"
+#define OPENAT2_ABS_LINK    OPENAT2_DIR "/abs_link"
+
+static int test_openat2() {
+    if (mkdir(OPENAT2_DIR, 00775) < 0) {
+        THROW_ERROR("failed to mkdir");
+    }
+    if (__test_open(OPENAT2_FILE, O_RDONLY | O_CREAT | O_TRUNC, 00666) < 0) {
+        return -1;
+    }
+    if (symlink("file.txt", OPENAT2_LINK) < 0 || symlink("/root", OPENAT2_ABS_LINK) < 0) {
+        THROW_ERROR("failed to create symlinks");
+    }
+    int dirfd = open(OPENAT2_DIR, O_RDONLY | O_DIRECTORY);
+    if (dirfd < 0) {
+        THROW_ERROR("failed to open the dir");
+    }
+
+    int fd = openat2(dirfd, "link", O_RDONLY, RESOLVE_BENEATH);
+    if (fd < 0) {
+        THROW_ERROR("failed to open a symlink inside the dir");
+    }
+    close(fd);
+    fd = openat2(dirfd, "../test_openat2_dir/file.txt", O_RDONLY, 0);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the file without restrictions");
+    }
+    close(fd);
+
+    if (!(openat2(dirfd, "../test_openat2_dir/file.txt", O_RDONLY, RESOLVE_BENEATH) < 0 &&
+            errno == EXDEV)) {
+        THROW_ERROR("\"..\" should not escape from the dir with RESOLVE_BENEATH");
+    }
+    if (!(openat2(dirfd, OPENAT2_FILE, O_RDONLY, RESOLVE_BENEATH) < 0 && errno == EXDEV)) {
+        THROW_ERROR("absolute paths should be rejected with RESOLVE_BENEATH");
+    }
+    if (!(openat2(dirfd, "abs_link", O_RDONLY, RESOLVE_BENEATH) < 0 && errno == EXDEV)) {
+        THROW_ERROR("absolute symlinks should be rejected with RESOLVE_BENEATH");
+    }
+    if (!(openat2(dirfd, "link", O_RDONLY, RESOLVE_NO_SYMLINKS) < 0 && errno == ELOOP)) {
+        THROW_ERROR("symlinks should be rejected with RESOLVE_NO_SYMLINKS");
+    }
+    if (!(openat2(dirfd, "/proc/self/exe", O_RDONLY, RESOLVE_NO_MAGICLINKS) < 0 &&
+            errno == ELOOP)) {
+        THROW_ERROR("magic links should be rejected with RESOLVE_NO_MAGICLINKS");
+    }
+    if (!(openat2(dirfd, "/proc/self", O_RDONLY | O_DIRECTORY, RESOLVE_NO_XDEV) < 0 &&
+            errno == EXDEV)) {
+        THROW_ERROR("mount points should not be crossed with RESOLVE_NO_XDEV");
+    }
+
+    // With RESOLVE_IN_ROOT, the dir is treated as the root
+    fd = openat2(dirfd, "/../../file.txt", O_RDONLY, RESOLVE_IN_ROOT);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the file with RESOLVE_IN_ROOT");
+    }
+    close(fd);
+
+    if (!(openat2(dirfd, "file.txt", O_RDONLY, RESOLVE_BENEATH | RESOLVE_IN_ROOT) < 0 &&
+            errno == EINVAL)) {
+        THROW_ERROR("RESOLVE_BENEATH and RESOLVE_IN_ROOT should be exclusive");
+    }
+    if (!(openat2(dirfd, "file.txt", O_RDONLY, 1ULL << 63) < 0 && errno == EINVAL)) {
+        THROW_ERROR("unknown resolve flags should be rejected");
+    }
+
+    close(dirfd);
+    if (unlink(OPENAT2_LINK) < 0 || unlink(OPENAT2_ABS_LINK) < 0 ||
+            remove_file(OPENAT2_FILE) < 0 || rmdir(OPENAT2_DIR) < 0) {
+        THROW_ERROR("failed to remove the files");
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -148,6 +318,9 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_open_dir_with_write_flags),
     TEST_CASE(test_openat_with_abs_path),
     TEST_CASE(test_openat_with_dirfd),
+    TEST_CASE(test_open_tmpfile),
+    TEST_CASE(test_open_tmpfile_with_invalid_flags),
+    TEST_CASE(test_openat2),
 };
 
 int main(int argc, const char *argv[]) {
@@ -0,0 +1,217 @@
+#include <sys/wait.h>
+#include <errno.h>
+#include <limits.h>
+#include <signal.h>
+#include <spawn.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define CHILD_PROG          "/bin/pgid"
+#define NONEXISTENT_PID     99999
+
+static int spawn_child(const char *cmd, pid_t *child_pid) {
+    const char *child_argv[3] = { CHILD_PROG, cmd, NULL };
+    if (posix_spawn(child_pid, CHILD_PROG, NULL, NULL,
+                    (char *const *)child_argv, NULL) != 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Child processes
+// ============================================================================
+
+// Wait until killed by a signal
+static int child_wait_for_signal() {
+    for (int i = 0; i < 10; i++) {
+        sleep(1);
+    }
+    return -1;
+}
+
+static int child_setsid() {
+    pid_t pid = getpid();
+    if (setsid() != pid) {
+        return -1;
+    }
+    if (getsid(0) != pid || getpgid(0) != pid || getpgrp() != pid) {
+        return -1;
+    }
+    // A session leader cannot change its process group
+    if (setpgid(0, 0) == 0 || errno != EPERM) {
+        return -1;
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_getpgid_and_getsid() {
+    if (getpgid(0) != getpgrp() || getpgid(getpid()) != getpgrp()) {
+        THROW_ERROR("the process group ID is not as expected");
+    }
+    if (getsid(0) <= 0 || getsid(getpid()) != getsid(0)) {
+        THROW_ERROR("the session ID is not as expected");
+    }
+    if (getpgid(NONEXISTENT_PID) >= 0 || errno != ESRCH) {
+        THROW_ERROR("getpgid of a nonexistent process should fail");
+    }
+    if (getsid(NONEXISTENT_PID) >= 0 || errno != ESRCH) {
+        THROW_ERROR("getsid of a nonexistent process should fail");
+    }
+    return 0;
+}
+
+static int test_setpgid_invalid() {
+    if (setpgid(NONEXISTENT_PID, 0) == 0 || errno != ESRCH) {
+        THROW_ERROR("setpgid of a nonexistent process should fail");
+    }
+    if (setpgid(0, NONEXISTENT_PID) == 0 || errno != EPERM) {
+        THROW_ERROR("joining a nonexistent process group should fail");
+    }
+    if (setpgid(0, -1) == 0 || errno != EINVAL) {
+        THROW_ERROR("setpgid with a negative pgid should fail");
+    }
+    return 0;
+}
+
+static int test_setsid() {
+    // The child is not a process group leader, so it can create a new session
+    pid_t child_pid = 0;
+    if (spawn_child("setsid", &child_pid) < 0) {
+        return -1;
+    }
+    int status = 0;
+    if (waitpid(child_pid, &status, 0) != child_pid) {
+        THROW_ERROR("failed to wait for the child");
+    }
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        THROW_ERROR("the child failed to create a new session");
+    }
+    return 0;
+}
+
+static int test_kill_process_group() {
+    pid_t child_pids[2] = { 0 };
+    for (int i = 0; i < 2; i++) {
+        if (spawn_child("wait_for_signal", &child_pids[i]) < 0) {
+            return -1;
+        }
+        if (getpgid(child_pids[i]) != getpgrp()) {
+            THROW_ERROR("the child is not in the process group of the parent");
+        }
+    }
+
+    // Move the children to a new process group led by the first child
+    pid_t pgid = child_pids[0];
+    if (setpgid(child_pids[0], 0) < 0 || setpgid(child_pids[1], pgid) < 0) {
+        THROW_ERROR("failed to move the children to a new process group");
+    }
+    if (getpgid(child_pids[0]) != pgid || getpgid(child_pids[1]) != pgid) {
+        THROW_ERROR("the process group of the children is not as expected");
+    }
+
+    if (killpg(pgid, SIGTERM) < 0) {
+        THROW_ERROR("failed to kill the process group");
+    }
+    // Wait for the children in the process group
+    for (int i = 0; i < 2; i++) {
+        int status = 0;
+        pid_t pid = waitpid(-pgid, &status, 0);
+        if (pid != child_pids[0] && pid != child_pids[1]) {
+            THROW_ERROR("failed to wait for the process group");
+        }
+        if (!WIFSIGNALED(status) || WTERMSIG(status) != SIGTERM) {
+            THROW_ERROR("the child is not killed by the signal");
+        }
+    }
+
+    if (killpg(pgid, SIGTERM) == 0 || errno != ESRCH) {
+        THROW_ERROR("killing an empty process group should fail");
+    }
+    return 0;
+}
+
+static int test_kill_with_signal_zero() {
+    // Check the existence of the process group of the caller
+    if (kill(0, 0) < 0) {
+        THROW_ERROR("the process group of the caller should exist");
+    }
+    if (kill(-getpgrp(), 0) < 0) {
+        THROW_ERROR("the process group should exist");
+    }
+    if (kill(-NONEXISTENT_PID, 0) == 0 || errno != ESRCH) {
+        THROW_ERROR("a nonexistent process group should not exist");
+    }
+    if (kill(INT_MIN, 0) == 0 || errno != ESRCH) {
+        THROW_ERROR("the process group of INT_MIN should not exist");
+    }
+    if (kill(0, -1) == 0 || errno != EINVAL) {
+        THROW_ERROR("an invalid signal number should be rejected");
+    }
+    return 0;
+}
+
+static int test_kill_all_processes() {
+    pid_t child_pids[2] = { 0 };
+    for (int i = 0; i < 2; i++) {
+        if (spawn_child("wait_for_signal", &child_pids[i]) < 0) {
+            return -1;
+        }
+    }
+
+    // The caller itself is not signaled
+    if (kill(-1, SIGTERM) < 0) {
+        THROW_ERROR("failed to kill all processes");
+    }
+    for (int i = 0; i < 2; i++) {
+        int status = 0;
+        if (waitpid(child_pids[i], &status, 0) != child_pids[i]) {
+            THROW_ERROR("failed to wait for the child");
+        }
+        if (!WIFSIGNALED(status) || WTERMSIG(status) != SIGTERM) {
+            THROW_ERROR("the child is not killed by the signal");
+        }
+    }
+
+    // No process other than the caller is left
+    if (kill(-1, SIGTERM) == 0 || errno != ESRCH) {
+        THROW_ERROR("killing all processes should fail if there are none");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_getpgid_and_getsid),
+    TEST_CASE(test_setpgid_invalid),
+    TEST_CASE(test_setsid),
+    TEST_CASE(test_kill_process_group),
+    TEST_CASE(test_kill_with_signal_zero),
+    TEST_CASE(test_kill_all_processes),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 1) {
+        int ret = -1;
+        if (strcmp(argv[1], "wait_for_signal") == 0) {
+            ret = child_wait_for_signal();
+        } else if (strcmp(argv[1], "setsid") == 0) {
+            ret = child_setsid();
+        }
+        return ret < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
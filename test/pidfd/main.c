@@ -0,0 +1,239 @@
+#include <sys/epoll.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <poll.h>
+#include <signal.h>
+#include <spawn.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#ifndef SYS_pidfd_open
+#define SYS_pidfd_open          434
+#endif
+#ifndef SYS_pidfd_send_signal
+#define SYS_pidfd_send_signal   424
+#endif
+#ifndef PIDFD_NONBLOCK
+#define PIDFD_NONBLOCK          O_NONBLOCK
+#endif
+
+#define CHILD_PROG          "/bin/pidfd"
+#define NONEXISTENT_PID     99999
+
+static int pidfd_open(pid_t pid, unsigned int flags) {
+    return syscall(SYS_pidfd_open, pid, flags);
+}
+
+static int pidfd_send_signal(int pidfd, int sig, siginfo_t *info, unsigned int flags) {
+    return syscall(SYS_pidfd_send_signal, pidfd, sig, info, flags);
+}
+
+static int spawn_child(const char *cmd, pid_t *child_pid) {
+    const char *child_argv[3] = { CHILD_PROG, cmd, NULL };
+    if (posix_spawn(child_pid, CHILD_PROG, NULL, NULL,
+                    (char *const *)child_argv, NULL) != 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Child processes
+// ============================================================================
+
+static int child_exit_after_sleep() {
+    usleep(200 * 1000);
+    return 0;
+}
+
+// Wait until killed by a signal
+static int child_wait_for_signal() {
+    for (int i = 0; i < 10; i++) {
+        sleep(1);
+    }
+    return -1;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_pidfd_open() {
+    int pidfd = pidfd_open(getpid(), 0);
+    if (pidfd < 0) {
+        THROW_ERROR("failed to open a pidfd");
+    }
+    if ((fcntl(pidfd, F_GETFD) & FD_CLOEXEC) == 0) {
+        THROW_ERROR("a pidfd must be close-on-exec");
+    }
+    close(pidfd);
+
+    pidfd = pidfd_open(getpid(), PIDFD_NONBLOCK);
+    if (pidfd < 0) {
+        THROW_ERROR("failed to open a non-blocking pidfd");
+    }
+    if ((fcntl(pidfd, F_GETFL) & O_NONBLOCK) == 0) {
+        THROW_ERROR("the pidfd should be non-blocking");
+    }
+    close(pidfd);
+    return 0;
+}
+
+static int test_pidfd_open_with_invalid_args() {
+    if (pidfd_open(getpid(), O_CLOEXEC) >= 0 || errno != EINVAL) {
+        THROW_ERROR("invalid flags should be rejected");
+    }
+    if (pidfd_open(0, 0) >= 0 || errno != EINVAL) {
+        THROW_ERROR("a non-positive pid should be rejected");
+    }
+    if (pidfd_open(NONEXISTENT_PID, 0) >= 0 || errno != ESRCH) {
+        THROW_ERROR("a nonexistent pid should be rejected");
+    }
+    return 0;
+}
+
+static int test_poll_pidfd_until_exit() {
+    pid_t child_pid;
+    if (spawn_child("exit_after_sleep", &child_pid) < 0) {
+        THROW_ERROR("failed to spawn a child");
+    }
+    int pidfd = pidfd_open(child_pid, 0);
+    if (pidfd < 0) {
+        THROW_ERROR("failed to open a pidfd");
+    }
+
+    struct pollfd pfd = { .fd = pidfd, .events = POLLIN };
+    if (poll(&pfd, 1, 0) != 0) {
+        THROW_ERROR("the pidfd should not be readable before the child exits");
+    }
+    if (poll(&pfd, 1, 5000) != 1 || (pfd.revents & POLLIN) == 0) {
+        THROW_ERROR("the pidfd should be readable after the child exits");
+    }
+
+    // The pidfd stays readable until it is closed, even if the child is reaped
+    int status = 0;
+    if (waitpid(child_pid, &status, 0) != child_pid ||
+            !WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        THROW_ERROR("failed to wait for the child");
+    }
+    if (poll(&pfd, 1, 0) != 1 || (pfd.revents & POLLIN) == 0) {
+        THROW_ERROR("the pidfd should be readable after the child is reaped");
+    }
+    close(pidfd);
+    return 0;
+}
+
+static int test_epoll_pidfd_until_exit() {
+    pid_t child_pid;
+    if (spawn_child("exit_after_sleep", &child_pid) < 0) {
+        THROW_ERROR("failed to spawn a child");
+    }
+    int pidfd = pidfd_open(child_pid, 0);
+    if (pidfd < 0) {
+        THROW_ERROR("failed to open a pidfd");
+    }
+
+    int epfd = epoll_create1(0);
+    if (epfd < 0) {
+        THROW_ERROR("failed to create an epoll file");
+    }
+    struct epoll_event event = { .events = EPOLLIN, .data.fd = pidfd };
+    if (epoll_ctl(epfd, EPOLL_CTL_ADD, pidfd, &event) < 0) {
+        THROW_ERROR("failed to add the pidfd to epoll");
+    }
+    struct epoll_event ready_event;
+    if (epoll_wait(epfd, &ready_event, 1, 5000) != 1 ||
+            ready_event.data.fd != pidfd || (ready_event.events & EPOLLIN) == 0) {
+        THROW_ERROR("the pidfd should be ready after the child exits");
+    }
+
+    if (waitpid(child_pid, NULL, 0) != child_pid) {
+        THROW_ERROR("failed to wait for the child");
+    }
+    close(epfd);
+    close(pidfd);
+    return 0;
+}
+
+static int test_pidfd_send_signal() {
+    pid_t child_pid;
+    if (spawn_child("wait_for_signal", &child_pid) < 0) {
+        THROW_ERROR("failed to spawn a child");
+    }
+    int pidfd = pidfd_open(child_pid, 0);
+    if (pidfd < 0) {
+        THROW_ERROR("failed to open a pidfd");
+    }
+
+    // Signal number 0 checks the existence of the process
+    if (pidfd_send_signal(pidfd, 0, NULL, 0) < 0) {
+        THROW_ERROR("the child should exist");
+    }
+    if (pidfd_send_signal(pidfd, SIGKILL, NULL, 1) >= 0 || errno != EINVAL) {
+        THROW_ERROR("non-zero flags should be rejected");
+    }
+    if (pidfd_send_signal(pidfd, SIGKILL, NULL, 0) < 0) {
+        THROW_ERROR("failed to send SIGKILL via the pidfd");
+    }
+
+    int status = 0;
+    if (waitpid(child_pid, &status, 0) != child_pid) {
+        THROW_ERROR("failed to wait for the child");
+    }
+    if (!WIFSIGNALED(status) || WTERMSIG(status) != SIGKILL) {
+        THROW_ERROR("the child should be killed by SIGKILL");
+    }
+
+    // The pidfd still refers to the exited child, not to a reused pid
+    if (pidfd_send_signal(pidfd, SIGKILL, NULL, 0) >= 0 || errno != ESRCH) {
+        THROW_ERROR("signaling an exited process should fail");
+    }
+    close(pidfd);
+    return 0;
+}
+
+static int test_pidfd_send_signal_with_invalid_fd() {
+    int fd = open("/dev/null", O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open /dev/null");
+    }
+    if (pidfd_send_signal(fd, 0, NULL, 0) >= 0 || errno != EBADF) {
+        THROW_ERROR("a non-pidfd should be rejected");
+    }
+    close(fd);
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_pidfd_open),
+    TEST_CASE(test_pidfd_open_with_invalid_args),
+    TEST_CASE(test_poll_pidfd_until_exit),
+    TEST_CASE(test_epoll_pidfd_until_exit),
+    TEST_CASE(test_pidfd_send_signal),
+    TEST_CASE(test_pidfd_send_signal_with_invalid_fd),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 1) {
+        int ret = -1;
+        if (strcmp(argv[1], "exit_after_sleep") == 0) {
+            ret = child_exit_after_sleep();
+        } else if (strcmp(argv[1], "wait_for_signal") == 0) {
+            ret = child_wait_for_signal();
+        }
+        return ret < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
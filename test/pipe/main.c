@@ -75,6 +75,54 @@ int test_create_with_flags() {
     return 0;
 }
 
+int test_fcntl_pipe_size() {
+    int pipe_fds[2];
+    if (pipe2(pipe_fds, O_NONBLOCK) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+
+    int size = fcntl(pipe_fds[0], F_GETPIPE_SZ);
+    if (size <= 0 || fcntl(pipe_fds[1], F_GETPIPE_SZ) != size) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("failed to get the pipe size");
+    }
+
+    // The size is rounded up to a power of two pages
+    if (fcntl(pipe_fds[1], F_SETPIPE_SZ, 4096 * 3) != 4096 * 4 ||
+            fcntl(pipe_fds[0], F_GETPIPE_SZ) != 4096 * 4) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("failed to set the pipe size");
+    }
+
+    // The data in the pipe is kept, and the pipe cannot be shrunk below it
+    char buf[4096 * 2] = {0};
+    if (write(pipe_fds[1], buf, sizeof(buf)) != sizeof(buf)) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("failed to write the pipe");
+    }
+    if (fcntl(pipe_fds[0], F_SETPIPE_SZ, 4096) >= 0 || errno != EBUSY) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("the pipe should not be shrunk below its data");
+    }
+    if (fcntl(pipe_fds[0], F_SETPIPE_SZ, 4096 * 16) != 4096 * 16) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("failed to set the pipe size");
+    }
+    if (read(pipe_fds[0], buf, sizeof(buf)) != sizeof(buf)) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("failed to read the data written before resizing");
+    }
+
+    // Only pipes have sizes
+    if (fcntl(STDIN_FILENO, F_GETPIPE_SZ) >= 0) {
+        free_pipe(pipe_fds);
+        THROW_ERROR("the size of a non-pipe file should not be got");
+    }
+
+    free_pipe(pipe_fds);
+    return 0;
+}
+
 int test_select_timeout() {
     fd_set rfds;
 
@@ -318,6 +366,7 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_fcntl_get_flags),
     TEST_CASE(test_fcntl_set_flags),
     TEST_CASE(test_create_with_flags),
+    TEST_CASE(test_fcntl_pipe_size),
     TEST_CASE(test_select_timeout),
     TEST_CASE(test_poll_timeout),
     TEST_CASE(test_epoll_timeout),
@@ -0,0 +1,387 @@
+#include <sys/syscall.h>
+#include <errno.h>
+#include <pthread.h>
+#include <sched.h>
+#include <signal.h>
+#include <string.h>
+#include <time.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define TIMEOUT_MS          2000
+#define TIMER_MS            100
+#define TIMER_VALUE         0x1234
+
+static volatile sig_atomic_t num_signals = 0;
+static volatile int last_si_code = 0;
+static volatile int last_si_value = 0;
+static volatile int max_si_overrun = 0;
+static volatile pid_t last_receiver_tid = 0;
+
+static void handle_timer_signal(int signum, siginfo_t *info, void *ucontext) {
+    last_si_code = info->si_code;
+    last_si_value = info->si_value.sival_int;
+    if (info->si_overrun > max_si_overrun) {
+        max_si_overrun = info->si_overrun;
+    }
+    last_receiver_tid = syscall(SYS_gettid);
+    num_signals++;
+}
+
+static int install_handler(int signum, struct sigaction *old_action) {
+    struct sigaction new_action;
+    memset(&new_action, 0, sizeof(new_action));
+    new_action.sa_sigaction = handle_timer_signal;
+    new_action.sa_flags = SA_SIGINFO;
+    if (sigaction(signum, &new_action, old_action) < 0) {
+        THROW_ERROR("failed to set the signal handler");
+    }
+    num_signals = 0;
+    return 0;
+}
+
+// Sleep until the given number of signals are received or timeout
+static int wait_for_signals(int expected_signals) {
+    struct timespec interval = { .tv_sec = 0, .tv_nsec = 10 * 1000 * 1000 };
+    for (int ms = 0; ms < TIMEOUT_MS && num_signals < expected_signals; ms += 10) {
+        nanosleep(&interval, NULL);
+    }
+    return num_signals >= expected_signals ? 0 : -1;
+}
+
+static long timespec_to_ms(const struct timespec *ts) {
+    return ts->tv_sec * 1000 + ts->tv_nsec / (1000 * 1000);
+}
+
+static void set_timespec_ms(struct timespec *ts, long ms) {
+    ts->tv_sec = ms / 1000;
+    ts->tv_nsec = (ms % 1000) * 1000 * 1000;
+}
+
+static int create_signal_timer(int signum, int value, timer_t *timerid) {
+    struct sigevent sev;
+    memset(&sev, 0, sizeof(sev));
+    sev.sigev_notify = SIGEV_SIGNAL;
+    sev.sigev_signo = signum;
+    sev.sigev_value.sival_int = value;
+    if (timer_create(CLOCK_MONOTONIC, &sev, timerid) < 0) {
+        THROW_ERROR("failed to create a timer");
+    }
+    return 0;
+}
+
+// The kernel's sigevent, which is used to create timers with SIGEV_THREAD_ID
+// since not every libc exposes the tid field.
+struct kernel_sigevent {
+    union sigval sigev_value;
+    int sigev_signo;
+    int sigev_notify;
+    int sigev_tid;
+    int pad[11];
+};
+
+#ifndef SIGEV_THREAD_ID
+#define SIGEV_THREAD_ID     4
+#endif
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_sigev_signal() {
+    struct sigaction old_action;
+    if (install_handler(SIGUSR1, &old_action) < 0) {
+        return -1;
+    }
+
+    timer_t timerid;
+    if (create_signal_timer(SIGUSR1, TIMER_VALUE, &timerid) < 0) {
+        return -1;
+    }
+    struct itimerspec new_value;
+    memset(&new_value, 0, sizeof(new_value));
+    set_timespec_ms(&new_value.it_value, TIMER_MS);
+    if (timer_settime(timerid, 0, &new_value, NULL) < 0) {
+        THROW_ERROR("failed to arm the timer");
+    }
+    if (wait_for_signals(1) < 0) {
+        THROW_ERROR("the timer signal is not received");
+    }
+    if (last_si_code != SI_TIMER || last_si_value != TIMER_VALUE) {
+        THROW_ERROR("the siginfo of the timer signal is wrong");
+    }
+
+    // A one-shot timer is disarmed after it expires
+    struct itimerspec curr_value;
+    if (timer_gettime(timerid, &curr_value) < 0) {
+        THROW_ERROR("failed to get the timer");
+    }
+    if (curr_value.it_value.tv_sec != 0 || curr_value.it_value.tv_nsec != 0) {
+        THROW_ERROR("the expired timer should be disarmed");
+    }
+
+    timer_delete(timerid);
+    sigaction(SIGUSR1, &old_action, NULL);
+    return 0;
+}
+
+static int test_sigev_none_and_gettime() {
+    struct sigevent sev;
+    memset(&sev, 0, sizeof(sev));
+    sev.sigev_notify = SIGEV_NONE;
+    timer_t timerid;
+    if (timer_create(CLOCK_REALTIME, &sev, &timerid) < 0) {
+        THROW_ERROR("failed to create a timer");
+    }
+
+    struct itimerspec new_value, old_value, curr_value;
+    set_timespec_ms(&new_value.it_value, 10 * 1000);
+    set_timespec_ms(&new_value.it_interval, 1000);
+    if (timer_settime(timerid, 0, &new_value, &old_value) < 0) {
+        THROW_ERROR("failed to arm the timer");
+    }
+    if (old_value.it_value.tv_sec != 0 || old_value.it_value.tv_nsec != 0) {
+        THROW_ERROR("a new timer should be disarmed");
+    }
+    if (timer_gettime(timerid, &curr_value) < 0) {
+        THROW_ERROR("failed to get the timer");
+    }
+    long value_ms = timespec_to_ms(&curr_value.it_value);
+    if (value_ms <= 0 || value_ms > 10 * 1000 ||
+            timespec_to_ms(&curr_value.it_interval) != 1000) {
+        THROW_ERROR("the setting of the timer is wrong");
+    }
+
+    // Disarm the timer
+    memset(&new_value, 0, sizeof(new_value));
+    if (timer_settime(timerid, 0, &new_value, &old_value) < 0) {
+        THROW_ERROR("failed to disarm the timer");
+    }
+    if (timespec_to_ms(&old_value.it_interval) != 1000) {
+        THROW_ERROR("the old setting of the timer is wrong");
+    }
+    if (timer_gettime(timerid, &curr_value) < 0) {
+        THROW_ERROR("failed to get the timer");
+    }
+    if (curr_value.it_value.tv_sec != 0 || curr_value.it_value.tv_nsec != 0) {
+        THROW_ERROR("the timer should be disarmed");
+    }
+
+    timer_delete(timerid);
+    return 0;
+}
+
+static int test_periodic_timer_and_overrun() {
+    struct sigaction old_action;
+    if (install_handler(SIGUSR2, &old_action) < 0) {
+        return -1;
+    }
+
+    // Block the signal so that the expirations overrun
+    sigset_t mask, old_mask;
+    sigemptyset(&mask);
+    sigaddset(&mask, SIGUSR2);
+    sigprocmask(SIG_BLOCK, &mask, &old_mask);
+    max_si_overrun = 0;
+
+    timer_t timerid;
+    if (create_signal_timer(SIGUSR2, TIMER_VALUE, &timerid) < 0) {
+        return -1;
+    }
+    struct itimerspec new_value;
+    set_timespec_ms(&new_value.it_value, 10);
+    set_timespec_ms(&new_value.it_interval, 10);
+    if (timer_settime(timerid, 0, &new_value, NULL) < 0) {
+        THROW_ERROR("failed to arm the timer");
+    }
+    struct timespec sleep_time = { .tv_sec = 0, .tv_nsec = 200 * 1000 * 1000 };
+    nanosleep(&sleep_time, NULL);
+
+    // Only one signal is queued for all the expirations
+    sigprocmask(SIG_SETMASK, &old_mask, NULL);
+    if (wait_for_signals(1) < 0) {
+        THROW_ERROR("the timer signal is not received");
+    }
+    if (max_si_overrun <= 0) {
+        THROW_ERROR("the overrun of the timer signal is wrong");
+    }
+    if (timer_getoverrun(timerid) < 0) {
+        THROW_ERROR("failed to get the overrun");
+    }
+
+    timer_delete(timerid);
+    sigaction(SIGUSR2, &old_action, NULL);
+    return 0;
+}
+
+static int test_timer_abstime() {
+    struct sigaction old_action;
+    if (install_handler(SIGUSR1, &old_action) < 0) {
+        return -1;
+    }
+
+    timer_t timerid;
+    if (create_signal_timer(SIGUSR1, TIMER_VALUE, &timerid) < 0) {
+        return -1;
+    }
+    struct itimerspec new_value;
+    memset(&new_value, 0, sizeof(new_value));
+    clock_gettime(CLOCK_MONOTONIC, &new_value.it_value);
+    new_value.it_value.tv_nsec += TIMER_MS * 1000 * 1000;
+    if (new_value.it_value.tv_nsec >= 1000 * 1000 * 1000) {
+        new_value.it_value.tv_sec += 1;
+        new_value.it_value.tv_nsec -= 1000 * 1000 * 1000;
+    }
+    if (timer_settime(timerid, TIMER_ABSTIME, &new_value, NULL) < 0) {
+        THROW_ERROR("failed to arm the timer with an absolute time");
+    }
+    if (wait_for_signals(1) < 0) {
+        THROW_ERROR("the timer signal is not received");
+    }
+
+    timer_delete(timerid);
+    sigaction(SIGUSR1, &old_action, NULL);
+    return 0;
+}
+
+static pid_t target_tid = 0;
+static volatile int should_thread_exit = 0;
+
+static void *thread_func(void *arg) {
+    target_tid = syscall(SYS_gettid);
+    while (!should_thread_exit) {
+        struct timespec interval = { .tv_sec = 0, .tv_nsec = 10 * 1000 * 1000 };
+        nanosleep(&interval, NULL);
+    }
+    return NULL;
+}
+
+static int test_sigev_thread_id() {
+    struct sigaction old_action;
+    if (install_handler(SIGUSR1, &old_action) < 0) {
+        return -1;
+    }
+    should_thread_exit = 0;
+    target_tid = 0;
+    pthread_t thread;
+    if (pthread_create(&thread, NULL, thread_func, NULL) < 0) {
+        THROW_ERROR("failed to create a thread");
+    }
+    while (target_tid == 0) {
+        sched_yield();
+    }
+    // Block the signal in the main thread, which is not the target
+    sigset_t mask, old_mask;
+    sigemptyset(&mask);
+    sigaddset(&mask, SIGUSR1);
+    sigprocmask(SIG_BLOCK, &mask, &old_mask);
+
+    struct kernel_sigevent sev;
+    memset(&sev, 0, sizeof(sev));
+    sev.sigev_notify = SIGEV_THREAD_ID;
+    sev.sigev_signo = SIGUSR1;
+    sev.sigev_value.sival_int = TIMER_VALUE;
+    sev.sigev_tid = target_tid;
+    int timerid;
+    if (syscall(SYS_timer_create, CLOCK_MONOTONIC, &sev, &timerid) < 0) {
+        THROW_ERROR("failed to create a timer with SIGEV_THREAD_ID");
+    }
+    struct itimerspec new_value;
+    memset(&new_value, 0, sizeof(new_value));
+    set_timespec_ms(&new_value.it_value, TIMER_MS);
+    if (syscall(SYS_timer_settime, timerid, 0, &new_value, NULL) < 0) {
+        THROW_ERROR("failed to arm the timer");
+    }
+    if (wait_for_signals(1) < 0) {
+        THROW_ERROR("the timer signal is not received");
+    }
+    if (last_receiver_tid != target_tid) {
+        THROW_ERROR("the timer signal is received by a wrong thread");
+    }
+
+    should_thread_exit = 1;
+    pthread_join(thread, NULL);
+    syscall(SYS_timer_delete, timerid);
+    sigprocmask(SIG_SETMASK, &old_mask, NULL);
+    sigaction(SIGUSR1, &old_action, NULL);
+    return 0;
+}
+
+static int test_timer_delete() {
+    timer_t timerid;
+    if (create_signal_timer(SIGUSR1, TIMER_VALUE, &timerid) < 0) {
+        return -1;
+    }
+    if (timer_delete(timerid) < 0) {
+        THROW_ERROR("failed to delete the timer");
+    }
+    struct itimerspec curr_value;
+    if (timer_gettime(timerid, &curr_value) == 0 || errno != EINVAL) {
+        THROW_ERROR("getting a deleted timer should fail");
+    }
+    if (timer_delete(timerid) == 0 || errno != EINVAL) {
+        THROW_ERROR("deleting a deleted timer should fail");
+    }
+    return 0;
+}
+
+static int test_timer_create_with_invalid_args() {
+    struct sigevent sev;
+    memset(&sev, 0, sizeof(sev));
+    sev.sigev_notify = SIGEV_SIGNAL;
+    sev.sigev_signo = 100;
+    timer_t timerid;
+    if (timer_create(CLOCK_MONOTONIC, &sev, &timerid) == 0 || errno != EINVAL) {
+        THROW_ERROR("creating a timer with an invalid signal should fail");
+    }
+
+    sev.sigev_signo = SIGUSR1;
+    if (timer_create(100, &sev, &timerid) == 0 || errno != EINVAL) {
+        THROW_ERROR("creating a timer with an invalid clock should fail");
+    }
+
+    struct kernel_sigevent kernel_sev;
+    memset(&kernel_sev, 0, sizeof(kernel_sev));
+    kernel_sev.sigev_notify = SIGEV_THREAD_ID;
+    kernel_sev.sigev_signo = SIGUSR1;
+    kernel_sev.sigev_tid = -1;
+    int kernel_timerid;
+    if (syscall(SYS_timer_create, CLOCK_MONOTONIC, &kernel_sev, &kernel_timerid) == 0 ||
+            errno != EINVAL) {
+        THROW_ERROR("creating a timer for an invalid thread should fail");
+    }
+
+    if (create_signal_timer(SIGUSR1, TIMER_VALUE, &timerid) < 0) {
+        return -1;
+    }
+    struct itimerspec new_value;
+    memset(&new_value, 0, sizeof(new_value));
+    new_value.it_value.tv_nsec = 1000 * 1000 * 1000;
+    if (timer_settime(timerid, 0, &new_value, NULL) == 0 || errno != EINVAL) {
+        THROW_ERROR("arming a timer with an invalid timespec should fail");
+    }
+    timer_delete(timerid);
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_sigev_signal),
+    TEST_CASE(test_sigev_none_and_gettime),
+    TEST_CASE(test_periodic_timer_and_overrun),
+    TEST_CASE(test_timer_abstime),
+    TEST_CASE(test_sigev_thread_id),
+    TEST_CASE(test_timer_delete),
+    TEST_CASE(test_timer_create_with_invalid_args),
+};
+
+int main(int argc, const char *argv[]) {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,185 @@
+#define _GNU_SOURCE
+#include <sys/select.h>
+#include <sys/syscall.h>
+#include <errno.h>
+#include <poll.h>
+#include <signal.h>
+#include <string.h>
+#include <unistd.h>
+
+#include "test.h"
+
+// ============================================================================
+// Helper function
+// ============================================================================
+static volatile int handled_signum = 0;
+
+static void handle_sigusr1(int signum) {
+    handled_signum = signum;
+}
+
+// Install the handler of SIGUSR1 and make a SIGUSR1 pending while blocked
+static int raise_blocked_sigusr1(sigset_t *old_mask) {
+    struct sigaction sa;
+    memset(&sa, 0, sizeof(sa));
+    sa.sa_handler = handle_sigusr1;
+    if (sigaction(SIGUSR1, &sa, NULL) < 0) {
+        THROW_ERROR("failed to install the signal handler");
+    }
+
+    sigset_t mask;
+    sigemptyset(&mask);
+    sigaddset(&mask, SIGUSR1);
+    if (sigprocmask(SIG_BLOCK, &mask, old_mask) < 0) {
+        THROW_ERROR("failed to block SIGUSR1");
+    }
+    handled_signum = 0;
+    if (raise(SIGUSR1) < 0 || handled_signum != 0) {
+        THROW_ERROR("failed to raise the blocked SIGUSR1");
+    }
+    return 0;
+}
+
+// Check SIGUSR1 is still blocked and then unblock it
+static int check_and_unblock_sigusr1(sigset_t *old_mask) {
+    sigset_t mask;
+    if (sigprocmask(SIG_SETMASK, NULL, &mask) < 0 || !sigismember(&mask, SIGUSR1)) {
+        THROW_ERROR("the signal mask is not restored");
+    }
+    if (sigprocmask(SIG_SETMASK, old_mask, NULL) < 0) {
+        THROW_ERROR("failed to restore the signal mask");
+    }
+    if (handled_signum != SIGUSR1) {
+        THROW_ERROR("SIGUSR1 is not handled");
+    }
+    signal(SIGUSR1, SIG_DFL);
+    return 0;
+}
+
+// ============================================================================
+// Test cases for ppoll and pselect
+// ============================================================================
+static int test_ppoll_interrupted_by_unblocked_signal() {
+    int pipe_fds[2];
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+
+    sigset_t old_mask;
+    if (raise_blocked_sigusr1(&old_mask) < 0) {
+        return -1;
+    }
+
+    // The pending SIGUSR1 is unblocked only during the wait of ppoll
+    sigset_t wait_mask;
+    sigemptyset(&wait_mask);
+    struct pollfd pfd = { .fd = pipe_fds[0], .events = POLLIN };
+    int ret = ppoll(&pfd, 1, NULL, &wait_mask);
+    if (ret != -1 || errno != EINTR) {
+        THROW_ERROR("ppoll should be interrupted by the unblocked signal");
+    }
+
+    close(pipe_fds[0]);
+    close(pipe_fds[1]);
+    return check_and_unblock_sigusr1(&old_mask);
+}
+
+static int test_ppoll_ready_before_signal() {
+    int pipe_fds[2];
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+    if (write(pipe_fds[1], "a", 1) != 1) {
+        THROW_ERROR("failed to write the pipe");
+    }
+
+    sigset_t old_mask;
+    if (raise_blocked_sigusr1(&old_mask) < 0) {
+        return -1;
+    }
+
+    // Same as Linux, the signal stays pending if ppoll is not interrupted
+    sigset_t wait_mask, pending;
+    sigemptyset(&wait_mask);
+    struct pollfd pfd = { .fd = pipe_fds[0], .events = POLLIN };
+    int ret = ppoll(&pfd, 1, NULL, &wait_mask);
+    if (ret != 1 || pfd.revents != POLLIN) {
+        THROW_ERROR("ppoll should return the ready file");
+    }
+    if (handled_signum != 0 || sigpending(&pending) < 0 || !sigismember(&pending, SIGUSR1)) {
+        THROW_ERROR("SIGUSR1 should be still pending");
+    }
+
+    close(pipe_fds[0]);
+    close(pipe_fds[1]);
+    return check_and_unblock_sigusr1(&old_mask);
+}
+
+static int test_ppoll_timeout() {
+    struct timespec timeout = { .tv_sec = 0, .tv_nsec = 10 * 1000 * 1000 };
+    sigset_t wait_mask;
+    sigfillset(&wait_mask);
+    int ret = ppoll(NULL, 0, &timeout, &wait_mask);
+    if (ret != 0) {
+        THROW_ERROR("ppoll should time out");
+    }
+
+    sigset_t mask;
+    if (sigprocmask(SIG_SETMASK, NULL, &mask) < 0 || sigismember(&mask, SIGUSR1)) {
+        THROW_ERROR("the signal mask is not restored");
+    }
+    return 0;
+}
+
+static int test_pselect_interrupted_by_unblocked_signal() {
+    int pipe_fds[2];
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+
+    sigset_t old_mask;
+    if (raise_blocked_sigusr1(&old_mask) < 0) {
+        return -1;
+    }
+
+    sigset_t wait_mask;
+    sigemptyset(&wait_mask);
+    fd_set readfds;
+    FD_ZERO(&readfds);
+    FD_SET(pipe_fds[0], &readfds);
+    struct timespec timeout = { .tv_sec = 1, .tv_nsec = 0 };
+    int ret = pselect(pipe_fds[0] + 1, &readfds, NULL, NULL, &timeout, &wait_mask);
+    if (ret != -1 || errno != EINTR) {
+        THROW_ERROR("pselect should be interrupted by the unblocked signal");
+    }
+
+    close(pipe_fds[0]);
+    close(pipe_fds[1]);
+    return check_and_unblock_sigusr1(&old_mask);
+}
+
+static int test_ppoll_invalid_sigset_size() {
+    sigset_t wait_mask;
+    sigemptyset(&wait_mask);
+    struct timespec timeout = { .tv_sec = 0, .tv_nsec = 0 };
+    int ret = syscall(__NR_ppoll, NULL, 0, &timeout, &wait_mask, 4);
+    if (ret != -1 || errno != EINVAL) {
+        THROW_ERROR("ppoll should fail with EINVAL given a wrong size of sigset");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+static test_case_t test_cases[] = {
+    TEST_CASE(test_ppoll_interrupted_by_unblocked_signal),
+    TEST_CASE(test_ppoll_ready_before_signal),
+    TEST_CASE(test_ppoll_timeout),
+    TEST_CASE(test_pselect_interrupted_by_unblocked_signal),
+    TEST_CASE(test_ppoll_invalid_sigset_size),
+};
+
+int main() {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
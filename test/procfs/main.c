@@ -1,15 +1,28 @@
 #include <sys/types.h>
+#include <sys/mman.h>
+#include <sys/syscall.h>
+#include <sys/socket.h>
+#include <sys/un.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+#include <dirent.h>
 #include <fcntl.h>
 #include <limits.h>
+#include <pthread.h>
+#include <stdio.h>
 #include <stdlib.h>
 #include <errno.h>
+#include <unistd.h>
 #include "test_fs.h"
 
 // ============================================================================
 // Helper variable and function
 // ============================================================================
 
+#define PAGE_SIZE 4096
+
 const char **g_argv;
+extern char **environ;
 
 static int test_readlink_from_procfs(const char *proc_inode, char *buf, int buf_size,
                                      const char *expected_target) {
@@ -67,58 +80,627 @@ static int test_readlink_from_proc_self_cwd() {
 }
 
 static int test_read_from_proc_self_cmdline() {
-    char absolute_path[PATH_MAX] = { 0 };
+    char cmdline_buf[PATH_MAX] = { 0 };
+    char expected_cmdline[PATH_MAX] = { 0 };
     const char *proc_cmdline = "/proc/self/cmdline";
 
-    int n = snprintf(absolute_path, sizeof(absolute_path), "/bin/%s", *g_argv);
-    if (n < 0) {
-        THROW_ERROR("failed to call snprintf");
+    // The arguments are separated by null bytes
+    int expected_len = 0;
+    for (const char **arg = g_argv; *arg != NULL; arg++) {
+        int arg_len = strlen(*arg) + 1;
+        memcpy(expected_cmdline + expected_len, *arg, arg_len);
+        expected_len += arg_len;
+    }
+    int fd = open(proc_cmdline, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open file: %s", proc_cmdline);
     }
-    if (fs_check_file_content(proc_cmdline, absolute_path) < 0) {
+    int len = read(fd, cmdline_buf, sizeof(cmdline_buf));
+    close(fd);
+    if (len != expected_len || memcmp(cmdline_buf, expected_cmdline, len) != 0) {
         THROW_ERROR("failed to check result in %s", proc_cmdline);
     }
 
     return 0;
 }
 
+static int test_read_from_proc_self_environ() {
+    char environ_buf[4096] = { 0 };
+    const char *proc_environ = "/proc/self/environ";
+
+    int fd = open(proc_environ, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open file: %s", proc_environ);
+    }
+    int len = read(fd, environ_buf, sizeof(environ_buf) - 1);
+    close(fd);
+    if (len < 0) {
+        THROW_ERROR("failed to read the environ");
+    }
+    if (environ[0] != NULL && strcmp(environ_buf, environ[0]) != 0) {
+        THROW_ERROR("failed to check result in %s", proc_environ);
+    }
+
+    return 0;
+}
+
+static int test_read_from_proc_self_status() {
+    char status_buf[4096] = { 0 };
+    char expected_pid[64] = { 0 };
+    const char *proc_status = "/proc/self/status";
+
+    int fd = open(proc_status, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open file: %s", proc_status);
+    }
+    if (read(fd, status_buf, sizeof(status_buf) - 1) < 0) {
+        THROW_ERROR("failed to read the status");
+    }
+    close(fd);
+
+    snprintf(expected_pid, sizeof(expected_pid), "\nPid:\t%d\n", getpid());
+    if (strstr(status_buf, expected_pid) == NULL) {
+        THROW_ERROR("the pid in %s is wrong", proc_status);
+    }
+    snprintf(expected_pid, sizeof(expected_pid), "\nPPid:\t%d\n", getppid());
+    if (strstr(status_buf, expected_pid) == NULL) {
+        THROW_ERROR("the parent pid in %s is wrong", proc_status);
+    }
+    if (strstr(status_buf, "\nThreads:\t1\n") == NULL) {
+        THROW_ERROR("the number of threads in %s is wrong", proc_status);
+    }
+
+    return 0;
+}
+
+static int test_read_from_proc_self_stat() {
+    char stat_buf[1024] = { 0 };
+    const char *proc_stat = "/proc/self/stat";
+    int pid, ppid, num_threads;
+    char state;
+
+    int fd = open(proc_stat, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open file: %s", proc_stat);
+    }
+    if (read(fd, stat_buf, sizeof(stat_buf) - 1) < 0) {
+        THROW_ERROR("failed to read the stat");
+    }
+    close(fd);
+
+    // The command name may contain spaces, so parse the fields after it
+    char *comm_end = strrchr(stat_buf, ')');
+    if (sscanf(stat_buf, "%d", &pid) != 1 || comm_end == NULL ||
+            sscanf(comm_end + 1, " %c %d %*d %*d %*d %*d %*u %*u %*u %*u %*u %*u %*u %*d %*d "
+                   "%*d %*d %d", &state, &ppid, &num_threads) != 3) {
+        THROW_ERROR("the format of %s is wrong", proc_stat);
+    }
+    if (pid != getpid() || ppid != getppid() || state != 'R' || num_threads != 1) {
+        THROW_ERROR("failed to check result in %s", proc_stat);
+    }
+
+    return 0;
+}
+
 static int test_read_from_proc_meminfo() {
     char meminfo[1024] = { 0 };
     const char *proc_meminfo = "/proc/meminfo";
+    unsigned long mem_total, mem_free;
 
     int fd = open(proc_meminfo, O_RDONLY);
     if (fd < 0) {
         THROW_ERROR("failed to open file: %s", proc_meminfo);
     }
-    if (read(fd, meminfo, sizeof(meminfo)) < 0) {
+    if (read(fd, meminfo, sizeof(meminfo) - 1) < 0) {
         THROW_ERROR("failed to read the meminfo");
     }
     close(fd);
 
+    if (sscanf(meminfo, "MemTotal: %lu kB\nMemFree: %lu kB", &mem_total, &mem_free) != 2) {
+        THROW_ERROR("the format of meminfo is wrong");
+    }
+    if (mem_total == 0 || mem_free > mem_total) {
+        THROW_ERROR("the memory size in meminfo is wrong");
+    }
+    if (strstr(meminfo, "SwapTotal:") == NULL) {
+        THROW_ERROR("the swap size should be in meminfo");
+    }
+
     return 0;
 }
 
 static int test_read_from_proc_cpuinfo() {
     char cpuinfo[1024] = { 0 };
     const char *proc_cpuinfo = "/proc/cpuinfo";
-    int len;
+    const char *processor_key = "processor\t:";
+    int len, num_processors = 0;
 
+    // Count the processors, whose key may be split between two reads
     int fd = open(proc_cpuinfo, O_RDONLY);
     if (fd < 0) {
         THROW_ERROR("failed to open file: %s", proc_cpuinfo);
     }
-    do {
-        len = read(fd, cpuinfo, sizeof(cpuinfo));
-        if (len < 0) {
-            THROW_ERROR("failed to read the cpuinfo");
-        } else if (len < sizeof(cpuinfo)) {
-            break;
+    int key_len = strlen(processor_key);
+    int remain_len = 0;
+    while ((len = read(fd, cpuinfo + remain_len, sizeof(cpuinfo) - 1 - remain_len)) > 0) {
+        cpuinfo[remain_len + len] = '\0';
+        for (char *p = cpuinfo; (p = strstr(p, processor_key)) != NULL; p += key_len) {
+            num_processors++;
+        }
+        remain_len = MIN(remain_len + len, key_len - 1);
+        memmove(cpuinfo, cpuinfo + strlen(cpuinfo) - remain_len, remain_len);
+    }
+    close(fd);
+    if (len < 0) {
+        THROW_ERROR("failed to read the cpuinfo");
+    }
+
+    if (num_processors != sysconf(_SC_NPROCESSORS_ONLN)) {
+        THROW_ERROR("the number of processors in cpuinfo is wrong");
+    }
+
+    return 0;
+}
+
+static int check_proc_self_maps(const char *maps, unsigned long addr, const char *perms,
+                                const char *path) {
+    const char *line = maps;
+    while (line != NULL && *line != '\0') {
+        unsigned long start, end;
+        char line_perms[5] = { 0 };
+        if (sscanf(line, "%lx-%lx %4s", &start, &end, line_perms) != 3) {
+            THROW_ERROR("the format of maps is wrong");
         }
-    } while (len == sizeof(cpuinfo));
+        if (start <= addr && addr < end) {
+            if (strncmp(line_perms, perms, strlen(perms)) != 0) {
+                THROW_ERROR("the permissions of the mapping are wrong");
+            }
+            const char *line_end = strchr(line, '\n');
+            size_t path_len = path != NULL ? strlen(path) : 0;
+            if (path != NULL && (line_end == NULL || line_end - line < path_len ||
+                                 strncmp(line_end - path_len, path, path_len) != 0)) {
+                THROW_ERROR("the path of the mapping is wrong");
+            }
+            return 0;
+        }
+        line = strchr(line, '\n');
+        if (line != NULL) {
+            line++;
+        }
+    }
+    THROW_ERROR("failed to find the mapping of the address");
+}
+
+static int test_read_from_proc_self_maps() {
+    char maps[4096] = { 0 };
+    const char *proc_maps = "/proc/self/maps";
+    int local_var = 0;
+
+    int fd = open(proc_maps, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open file: %s", proc_maps);
+    }
+    if (read(fd, maps, sizeof(maps) - 1) <= 0) {
+        THROW_ERROR("failed to read the maps");
+    }
+    close(fd);
+
+    if (check_proc_self_maps(maps, (unsigned long)&local_var, "rw", "[stack]") < 0) {
+        THROW_ERROR("failed to check the mapping of the stack");
+    }
+    if (check_proc_self_maps(maps, (unsigned long)test_read_from_proc_self_maps, "r",
+                             NULL) < 0) {
+        THROW_ERROR("failed to check the mapping of the code");
+    }
+    if (strstr(maps, "[stack]") == NULL) {
+        THROW_ERROR("the stack should be in the maps");
+    }
+    return 0;
+}
+
+static int test_read_file_mappings_from_proc_self_maps() {
+    const char *file_path = "/root/test_procfs_maps.data";
+    int fd = open(file_path, O_CREAT | O_TRUNC | O_RDWR, 0644);
+    if (fd < 0) {
+        THROW_ERROR("failed to create file: %s", file_path);
+    }
+    if (ftruncate(fd, 2 * PAGE_SIZE) < 0) {
+        THROW_ERROR("failed to truncate the file");
+    }
+    char *private_buf = mmap(NULL, PAGE_SIZE, PROT_READ, MAP_PRIVATE, fd, 0);
+    char *shared_buf = mmap(NULL, PAGE_SIZE, PROT_READ | PROT_WRITE, MAP_SHARED, fd,
+                            PAGE_SIZE);
+    char *heap_buf = malloc(16);
+    if (private_buf == MAP_FAILED || shared_buf == MAP_FAILED || heap_buf == NULL) {
+        THROW_ERROR("failed to map the file");
+    }
+
+    char maps[16384] = { 0 };
+    int maps_fd = open("/proc/self/maps", O_RDONLY);
+    if (maps_fd < 0) {
+        THROW_ERROR("failed to open the maps");
+    }
+    size_t len = 0;
+    ssize_t n;
+    while ((n = read(maps_fd, maps + len, sizeof(maps) - 1 - len)) > 0) {
+        len += n;
+    }
+    close(maps_fd);
+
+    if (check_proc_self_maps(maps, (unsigned long)private_buf, "r--p", file_path) < 0) {
+        THROW_ERROR("failed to check the private mapping of the file");
+    }
+    if (check_proc_self_maps(maps, (unsigned long)shared_buf, "rw-s", file_path) < 0) {
+        THROW_ERROR("failed to check the shared mapping of the file");
+    }
+    if (strstr(maps, "[heap]") == NULL) {
+        THROW_ERROR("the heap should be in the maps");
+    }
+
+    free(heap_buf);
+    munmap(private_buf, PAGE_SIZE);
+    munmap(shared_buf, PAGE_SIZE);
     close(fd);
+    unlink(file_path);
+    return 0;
+}
 
+static int read_proc_self_statm(unsigned long *size, unsigned long *resident) {
+    char statm_buf[256] = { 0 };
+    const char *proc_statm = "/proc/self/statm";
+
+    int fd = open(proc_statm, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open file: %s", proc_statm);
+    }
+    if (read(fd, statm_buf, sizeof(statm_buf) - 1) <= 0) {
+        THROW_ERROR("failed to read the statm");
+    }
+    close(fd);
+
+    unsigned long shared, text, lib, data, dt;
+    if (sscanf(statm_buf, "%lu %lu %lu %lu %lu %lu %lu", size, resident, &shared,
+               &text, &lib, &data, &dt) != 7) {
+        THROW_ERROR("the format of %s is wrong", proc_statm);
+    }
+    if (*resident > *size || text > *size) {
+        THROW_ERROR("the sizes in %s are inconsistent", proc_statm);
+    }
     return 0;
 }
 
+static int test_read_from_proc_self_statm() {
+    unsigned long size, resident, new_size, new_resident;
+    if (read_proc_self_statm(&size, &resident) < 0) {
+        THROW_ERROR("failed to read statm");
+    }
+
+    // The new mapping is accounted once it is touched
+    size_t len = 64 * PAGE_SIZE;
+    char *buf = mmap(NULL, len, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (buf == MAP_FAILED) {
+        THROW_ERROR("failed to mmap");
+    }
+    memset(buf, 1, len);
+    if (read_proc_self_statm(&new_size, &new_resident) < 0) {
+        THROW_ERROR("failed to read statm");
+    }
+    munmap(buf, len);
+    if (new_size < size + len / PAGE_SIZE || new_resident < resident + len / PAGE_SIZE) {
+        THROW_ERROR("the new mapping is not accounted");
+    }
+    return 0;
+}
+
+static int test_read_from_proc_self_smaps_rollup() {
+    char smaps_buf[2048] = { 0 };
+    const char *proc_smaps_rollup = "/proc/self/smaps_rollup";
+
+    int fd = open(proc_smaps_rollup, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open file: %s", proc_smaps_rollup);
+    }
+    if (read(fd, smaps_buf, sizeof(smaps_buf) - 1) <= 0) {
+        THROW_ERROR("failed to read the smaps_rollup");
+    }
+    close(fd);
+
+    if (strstr(smaps_buf, "[rollup]\n") == NULL) {
+        THROW_ERROR("the header of %s is wrong", proc_smaps_rollup);
+    }
+    char *rss_line = strstr(smaps_buf, "\nRss:");
+    char *pss_line = strstr(smaps_buf, "\nPss:");
+    unsigned long rss_kb, pss_kb;
+    if (rss_line == NULL || pss_line == NULL ||
+            sscanf(rss_line, "\nRss: %lu kB", &rss_kb) != 1 ||
+            sscanf(pss_line, "\nPss: %lu kB", &pss_kb) != 1) {
+        THROW_ERROR("the format of %s is wrong", proc_smaps_rollup);
+    }
+    if (rss_kb == 0 || pss_kb > rss_kb) {
+        THROW_ERROR("the sizes in %s are wrong", proc_smaps_rollup);
+    }
+    return 0;
+}
+
+static int test_readlink_from_proc_self_fd() {
+    char link_buf[PATH_MAX] = { 0 };
+    char fd_path[64];
+    int pipe_fds[2];
+
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+    snprintf(fd_path, sizeof(fd_path), "/proc/self/fd/%d", pipe_fds[0]);
+    if (readlink(fd_path, link_buf, sizeof(link_buf) - 1) < 0) {
+        THROW_ERROR("failed to readlink from %s", fd_path);
+    }
+    if (strncmp(link_buf, "pipe:[", 6) != 0) {
+        THROW_ERROR("the link of the pipe is wrong");
+    }
+
+    // Both ends of the pipe refer to the same inode
+    char writer_link_buf[PATH_MAX] = { 0 };
+    snprintf(fd_path, sizeof(fd_path), "/proc/self/fd/%d", pipe_fds[1]);
+    if (readlink(fd_path, writer_link_buf, sizeof(writer_link_buf) - 1) < 0) {
+        THROW_ERROR("failed to readlink from %s", fd_path);
+    }
+    if (strcmp(link_buf, writer_link_buf) != 0) {
+        THROW_ERROR("the ends of the pipe should have the same link");
+    }
+
+    // Opening the link refers to the same pipe
+    int writer_fd = open(fd_path, O_WRONLY);
+    if (writer_fd < 0) {
+        THROW_ERROR("failed to open %s", fd_path);
+    }
+    const char *msg = "hello";
+    char read_buf[8] = { 0 };
+    if (write(writer_fd, msg, strlen(msg)) != strlen(msg) ||
+            read(pipe_fds[0], read_buf, sizeof(read_buf)) != strlen(msg) ||
+            strcmp(read_buf, msg) != 0) {
+        THROW_ERROR("failed to communicate through the reopened pipe");
+    }
+    close(writer_fd);
+    close(pipe_fds[0]);
+    close(pipe_fds[1]);
+    return 0;
+}
+
+static int test_read_proc_self_fd_dir() {
+    const char *file_path = "/root/test_procfs_fd.txt";
+    int fd = open(file_path, O_RDWR | O_CREAT | O_TRUNC, 0666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a file");
+    }
+
+    DIR *dir = opendir("/proc/self/fd");
+    if (dir == NULL) {
+        THROW_ERROR("failed to open /proc/self/fd");
+    }
+    char fd_name[16];
+    snprintf(fd_name, sizeof(fd_name), "%d", fd);
+    int found_fd = 0, found_stdin = 0;
+    struct dirent *entry;
+    while ((entry = readdir(dir)) != NULL) {
+        if (strcmp(entry->d_name, fd_name) == 0) {
+            found_fd = 1;
+        } else if (strcmp(entry->d_name, "0") == 0) {
+            found_stdin = 1;
+        }
+    }
+    closedir(dir);
+    if (!found_fd || !found_stdin) {
+        THROW_ERROR("the fds are not listed in /proc/self/fd");
+    }
+
+    char link_buf[PATH_MAX] = { 0 };
+    char fd_path[64];
+    snprintf(fd_path, sizeof(fd_path), "/proc/self/fd/%d", fd);
+    if (test_readlink_from_procfs(fd_path, link_buf, sizeof(link_buf), file_path) < 0) {
+        return -1;
+    }
+    close(fd);
+    if (unlink(file_path) < 0) {
+        THROW_ERROR("failed to unlink the file");
+    }
+    return 0;
+}
+
+static pid_t g_child_tid;
+static pthread_mutex_t g_task_mutex = PTHREAD_MUTEX_INITIALIZER;
+static pthread_cond_t g_task_cond = PTHREAD_COND_INITIALIZER;
+static int g_task_ready, g_task_done;
+
+static void *task_thread_func(void *arg) {
+    pthread_mutex_lock(&g_task_mutex);
+    g_child_tid = syscall(SYS_gettid);
+    g_task_ready = 1;
+    pthread_cond_broadcast(&g_task_cond);
+    while (!g_task_done) {
+        pthread_cond_wait(&g_task_cond, &g_task_mutex);
+    }
+    pthread_mutex_unlock(&g_task_mutex);
+    return NULL;
+}
+
+static int check_proc_self_task() {
+    // Both the main thread and the child thread are listed
+    DIR *dir = opendir("/proc/self/task");
+    if (dir == NULL) {
+        THROW_ERROR("failed to open /proc/self/task");
+    }
+    int num_tasks = 0, found_main = 0, found_child = 0;
+    struct dirent *entry;
+    while ((entry = readdir(dir)) != NULL) {
+        if (entry->d_name[0] == '.') {
+            continue;
+        }
+        pid_t tid = atoi(entry->d_name);
+        if (tid == getpid()) {
+            found_main = 1;
+        } else if (tid == g_child_tid) {
+            found_child = 1;
+        }
+        num_tasks++;
+    }
+    closedir(dir);
+    if (num_tasks != 2 || !found_main || !found_child) {
+        THROW_ERROR("the threads are not listed in /proc/self/task");
+    }
+
+    char path[64];
+    char buf[1024] = { 0 };
+    snprintf(path, sizeof(path), "/proc/self/task/%d/stat", g_child_tid);
+    int fd = open(path, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open %s", path);
+    }
+    int len = read(fd, buf, sizeof(buf) - 1);
+    close(fd);
+    if (len <= 0 || atoi(buf) != g_child_tid) {
+        THROW_ERROR("the tid in the stat of the thread is wrong");
+    }
+
+    snprintf(path, sizeof(path), "/proc/self/task/%d/status", g_child_tid);
+    fd = open(path, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open %s", path);
+    }
+    memset(buf, 0, sizeof(buf));
+    len = read(fd, buf, sizeof(buf) - 1);
+    close(fd);
+    char expected[64];
+    snprintf(expected, sizeof(expected), "Tgid:\t%d\n", getpid());
+    if (len <= 0 || strstr(buf, expected) == NULL) {
+        THROW_ERROR("the tgid in the status of the thread is wrong");
+    }
+    snprintf(expected, sizeof(expected), "Pid:\t%d\n", g_child_tid);
+    if (strstr(buf, expected) == NULL) {
+        THROW_ERROR("the pid in the status of the thread is wrong");
+    }
+    return 0;
+}
+
+static int test_read_from_proc_self_task() {
+    pthread_t thread;
+    if (pthread_create(&thread, NULL, task_thread_func, NULL) != 0) {
+        THROW_ERROR("failed to create a thread");
+    }
+    pthread_mutex_lock(&g_task_mutex);
+    while (!g_task_ready) {
+        pthread_cond_wait(&g_task_cond, &g_task_mutex);
+    }
+    pthread_mutex_unlock(&g_task_mutex);
+
+    int ret = check_proc_self_task();
+
+    pthread_mutex_lock(&g_task_mutex);
+    g_task_done = 1;
+    pthread_cond_broadcast(&g_task_cond);
+    pthread_mutex_unlock(&g_task_mutex);
+    pthread_join(thread, NULL);
+    return ret;
+}
+
+static int read_proc_file(const char *path, char *buf, size_t buf_size) {
+    int fd = open(path, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open file: %s", path);
+    }
+    memset(buf, 0, buf_size);
+    if (read(fd, buf, buf_size - 1) <= 0) {
+        close(fd);
+        THROW_ERROR("failed to read file: %s", path);
+    }
+    close(fd);
+    return 0;
+}
+
+static int test_read_from_proc_net_tcp() {
+    char tcp_table[4096];
+    char expected_entry[64];
+    struct sockaddr_in addr = { 0 };
+    socklen_t addr_len = sizeof(addr);
+    int ret = -1;
+
+    int sock = socket(AF_INET, SOCK_STREAM, 0);
+    if (sock < 0) {
+        THROW_ERROR("failed to create a socket");
+    }
+    addr.sin_family = AF_INET;
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    addr.sin_port = 0;
+    if (bind(sock, (struct sockaddr *)&addr, sizeof(addr)) < 0 || listen(sock, 1) < 0 ||
+            getsockname(sock, (struct sockaddr *)&addr, &addr_len) < 0) {
+        close(sock);
+        THROW_ERROR("failed to listen on the socket");
+    }
+
+    // The listening socket is in the state of TCP_LISTEN, i.e., 0A
+    snprintf(expected_entry, sizeof(expected_entry), "0100007F:%04X 00000000:0000 0A",
+             ntohs(addr.sin_port));
+    if (read_proc_file("/proc/net/tcp", tcp_table, sizeof(tcp_table)) < 0) {
+        goto out;
+    }
+    if (strncmp(tcp_table, "  sl  local_address", 19) != 0) {
+        printf("the header of /proc/net/tcp is wrong\n");
+        goto out;
+    }
+    if (strstr(tcp_table, expected_entry) == NULL) {
+        printf("the listening socket is not in /proc/net/tcp\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    close(sock);
+    return ret;
+}
+
+static int test_read_from_proc_net_unix() {
+    char unix_table[4096];
+    const char *sock_path = "/tmp/test_procfs_unix.sock";
+    struct sockaddr_un addr = { 0 };
+    int ret = -1;
+
+    int sock = socket(AF_UNIX, SOCK_STREAM, 0);
+    if (sock < 0) {
+        THROW_ERROR("failed to create a socket");
+    }
+    addr.sun_family = AF_UNIX;
+    strncpy(addr.sun_path, sock_path, sizeof(addr.sun_path) - 1);
+    if (bind(sock, (struct sockaddr *)&addr, sizeof(addr)) < 0 || listen(sock, 1) < 0) {
+        close(sock);
+        THROW_ERROR("failed to listen on the socket");
+    }
+
+    if (read_proc_file("/proc/net/unix", unix_table, sizeof(unix_table)) < 0) {
+        goto out;
+    }
+    if (strncmp(unix_table, "Num       RefCount", 18) != 0) {
+        printf("the header of /proc/net/unix is wrong\n");
+        goto out;
+    }
+    // The listening socket has the flag of __SO_ACCEPTCON
+    const char *entry = strstr(unix_table, sock_path);
+    if (entry == NULL) {
+        printf("the listening socket is not in /proc/net/unix\n");
+        goto out;
+    }
+    const char *line = entry;
+    while (line > unix_table && *(line - 1) != '\n') {
+        line--;
+    }
+    if (strstr(line, " 00010000 0001 01 ") == NULL || strstr(line, " 00010000 0001 01 ") > entry) {
+        printf("the flags or the state of the socket is wrong\n");
+        goto out;
+    }
+    ret = 0;
+out:
+    close(sock);
+    unlink(sock_path);
+    return ret;
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -127,8 +709,20 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_readlink_from_proc_self_exe),
     TEST_CASE(test_readlink_from_proc_self_cwd),
     TEST_CASE(test_read_from_proc_self_cmdline),
+    TEST_CASE(test_read_from_proc_self_environ),
+    TEST_CASE(test_read_from_proc_self_status),
+    TEST_CASE(test_read_from_proc_self_stat),
     TEST_CASE(test_read_from_proc_meminfo),
     TEST_CASE(test_read_from_proc_cpuinfo),
+    TEST_CASE(test_read_from_proc_self_maps),
+    TEST_CASE(test_read_file_mappings_from_proc_self_maps),
+    TEST_CASE(test_read_from_proc_self_statm),
+    TEST_CASE(test_read_from_proc_self_smaps_rollup),
+    TEST_CASE(test_readlink_from_proc_self_fd),
+    TEST_CASE(test_read_proc_self_fd_dir),
+    TEST_CASE(test_read_from_proc_self_task),
+    TEST_CASE(test_read_from_proc_net_tcp),
+    TEST_CASE(test_read_from_proc_net_unix),
 };
 
 int main(int argc, const char *argv[]) {
@@ -0,0 +1,68 @@
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <errno.h>
+#include <unistd.h>
+
+#include "test.h"
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+// The sockets are denied as raw_sockets and ping_sockets are not set in
+// Occlum.json
+
+int test_raw_socket_denied() {
+    int sock = socket(AF_INET, SOCK_RAW, IPPROTO_ICMP);
+    if (sock >= 0) {
+        close(sock);
+        THROW_ERROR("raw socket should be denied");
+    }
+    if (errno != EPERM) {
+        THROW_ERROR("the error of the raw socket is wrong");
+    }
+
+    sock = socket(AF_INET6, SOCK_RAW, IPPROTO_ICMPV6);
+    if (sock >= 0) {
+        close(sock);
+        THROW_ERROR("raw socket should be denied");
+    }
+    if (errno != EPERM) {
+        THROW_ERROR("the error of the raw socket is wrong");
+    }
+    return 0;
+}
+
+int test_ping_socket_denied() {
+    int sock = socket(AF_INET, SOCK_DGRAM, IPPROTO_ICMP);
+    if (sock >= 0) {
+        close(sock);
+        THROW_ERROR("ping socket should be denied");
+    }
+    if (errno != EACCES) {
+        THROW_ERROR("the error of the ping socket is wrong");
+    }
+    return 0;
+}
+
+int test_udp_socket_allowed() {
+    int sock = socket(AF_INET, SOCK_DGRAM, 0);
+    if (sock < 0) {
+        THROW_ERROR("failed to create a UDP socket");
+    }
+    close(sock);
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+static test_case_t test_cases[] = {
+    TEST_CASE(test_raw_socket_denied),
+    TEST_CASE(test_ping_socket_denied),
+    TEST_CASE(test_udp_socket_allowed),
+};
+
+int main() {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
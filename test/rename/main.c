@@ -1,4 +1,5 @@
 #include <sys/stat.h>
+#include <sys/syscall.h>
 #include <errno.h>
 #include <fcntl.h>
 #include <stdbool.h>
@@ -9,6 +10,19 @@
 // ============================================================================
 
 #define WRITE_MSG "Hello World"
+#define ANOTHER_MSG "Goodbye World"
+
+#ifndef RENAME_NOREPLACE
+#define RENAME_NOREPLACE    (1 << 0)
+#endif
+#ifndef RENAME_EXCHANGE
+#define RENAME_EXCHANGE     (1 << 1)
+#endif
+
+static int renameat2(int olddirfd, const char *oldpath, int newdirfd,
+                     const char *newpath, unsigned int flags) {
+    return syscall(SYS_renameat2, olddirfd, oldpath, newdirfd, newpath, flags);
+}
 
 static int create_file_with_content(const char *file_path, const char *msg) {
     int fd;
@@ -178,6 +192,87 @@ static int test_rename_dir() {
     return 0;
 }
 
+static int test_rename_noreplace() {
+    const char *old_path = "/root/test_old_file.txt";
+    const char *new_path = "/root/test_new_file.txt";
+
+    if (create_file_with_content(old_path, WRITE_MSG) < 0 ||
+            create_file_with_content(new_path, ANOTHER_MSG) < 0) {
+        THROW_ERROR("failed to create files");
+    }
+    if (!(renameat2(AT_FDCWD, old_path, AT_FDCWD, new_path, RENAME_NOREPLACE) < 0 &&
+            errno == EEXIST)) {
+        THROW_ERROR("renaming to an existing path with RENAME_NOREPLACE should fail");
+    }
+    if (fs_check_file_content(new_path, ANOTHER_MSG) < 0) {
+        THROW_ERROR("the existing file should not be replaced");
+    }
+    if (unlink(new_path) < 0) {
+        THROW_ERROR("failed to remove the new file");
+    }
+    if (renameat2(AT_FDCWD, old_path, AT_FDCWD, new_path, RENAME_NOREPLACE) < 0) {
+        THROW_ERROR("failed to rename with RENAME_NOREPLACE");
+    }
+    if (fs_check_file_content(new_path, WRITE_MSG) < 0) {
+        THROW_ERROR("failed to check file content");
+    }
+    if (unlink(new_path) < 0) {
+        THROW_ERROR("failed to remove the new file");
+    }
+    return 0;
+}
+
+static int test_rename_exchange() {
+    const char *old_path = "/root/test_old_file.txt";
+    const char *new_dir = "/root/test_new_dir";
+    const char *new_path = "/root/test_new_dir/test_new_file.txt";
+
+    if (mkdir(new_dir, 00775) < 0) {
+        THROW_ERROR("failed to mkdir new dir");
+    }
+    if (create_file_with_content(old_path, WRITE_MSG) < 0) {
+        THROW_ERROR("failed to create the old file");
+    }
+    if (!(renameat2(AT_FDCWD, old_path, AT_FDCWD, new_path, RENAME_EXCHANGE) < 0 &&
+            errno == ENOENT)) {
+        THROW_ERROR("exchanging with a non-existent path should fail");
+    }
+    if (create_file_with_content(new_path, ANOTHER_MSG) < 0) {
+        THROW_ERROR("failed to create the new file");
+    }
+    if (renameat2(AT_FDCWD, old_path, AT_FDCWD, new_path, RENAME_EXCHANGE) < 0) {
+        THROW_ERROR("failed to exchange the files");
+    }
+    if (fs_check_file_content(old_path, ANOTHER_MSG) < 0 ||
+            fs_check_file_content(new_path, WRITE_MSG) < 0) {
+        THROW_ERROR("the files are not exchanged");
+    }
+
+    // Exchange a file with a dir
+    if (unlink(new_path) < 0) {
+        THROW_ERROR("failed to remove the new file");
+    }
+    if (renameat2(AT_FDCWD, old_path, AT_FDCWD, new_dir, RENAME_EXCHANGE) < 0) {
+        THROW_ERROR("failed to exchange the file and the dir");
+    }
+    struct stat stat_buf;
+    if (stat(old_path, &stat_buf) < 0 || !S_ISDIR(stat_buf.st_mode)) {
+        THROW_ERROR("the old path should be the dir");
+    }
+    if (fs_check_file_content(new_dir, ANOTHER_MSG) < 0) {
+        THROW_ERROR("the new path should be the file");
+    }
+
+    if (!(renameat2(AT_FDCWD, old_path, AT_FDCWD, new_dir,
+                    RENAME_EXCHANGE | RENAME_NOREPLACE) < 0 && errno == EINVAL)) {
+        THROW_ERROR("RENAME_EXCHANGE with RENAME_NOREPLACE should fail");
+    }
+    if (rmdir(old_path) < 0 || unlink(new_dir) < 0) {
+        THROW_ERROR("failed to remove the files");
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -188,6 +283,8 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_rename_with_target_exist),
     TEST_CASE(test_renameat),
     TEST_CASE(test_rename_dir),
+    TEST_CASE(test_rename_noreplace),
+    TEST_CASE(test_rename_exchange),
 };
 
 int main(int argc, const char *argv[]) {
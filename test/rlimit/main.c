@@ -1,15 +1,164 @@
+#include <sys/mman.h>
 #include <sys/resource.h>
+#include <errno.h>
+#include <fcntl.h>
 #include <stdio.h>
+#include <unistd.h>
+#include "test.h"
 
-int main(int argc, const char *argv[]) {
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define MAX_OPEN_FILES      16
+#define MMAP_SIZE           (4 * 1024 * 1024)
+#define PAGE_SIZE           4096
+
+static int set_soft_limit(int resource, rlim_t soft_limit, struct rlimit *old_rlim) {
+    if (getrlimit(resource, old_rlim) < 0) {
+        THROW_ERROR("failed to getrlimit");
+    }
+    struct rlimit new_rlim = { soft_limit, old_rlim->rlim_max };
+    if (setrlimit(resource, &new_rlim) < 0) {
+        THROW_ERROR("failed to setrlimit");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_getrlimit_and_setrlimit() {
     struct rlimit rlim;
     if (getrlimit(RLIMIT_AS, &rlim) < 0) {
-        printf("ERROR: getrlimit failed\n");
-        return -1;
+        THROW_ERROR("failed to getrlimit");
     }
     if (setrlimit(RLIMIT_AS, &rlim) < 0) {
-        printf("ERROR: getrlimit failed\n");
+        THROW_ERROR("failed to setrlimit");
+    }
+    if (getrlimit(RLIMIT_NOFILE, &rlim) < 0 || rlim.rlim_cur == 0) {
+        THROW_ERROR("failed to getrlimit of RLIMIT_NOFILE");
+    }
+    return 0;
+}
+
+static int test_raise_hard_limit() {
+    struct rlimit rlim;
+    if (getrlimit(RLIMIT_NPROC, &rlim) < 0) {
+        THROW_ERROR("failed to getrlimit");
+    }
+    // Lowering the hard limit is permitted, but raising it is not
+    struct rlimit new_rlim = { 100, 100 };
+    if (setrlimit(RLIMIT_NPROC, &new_rlim) < 0) {
+        THROW_ERROR("failed to lower the hard limit");
+    }
+    if (setrlimit(RLIMIT_NPROC, &rlim) == 0 || errno != EPERM) {
+        THROW_ERROR("raising the hard limit should fail");
+    }
+    return 0;
+}
+
+static int test_nofile_limit() {
+    struct rlimit old_rlim;
+    if (set_soft_limit(RLIMIT_NOFILE, MAX_OPEN_FILES, &old_rlim) < 0) {
+        return -1;
+    }
+
+    int ret = -1;
+    int fds[MAX_OPEN_FILES] = { 0 };
+    int num_fds = 0;
+    // Open files until the fds run out
+    while (num_fds < MAX_OPEN_FILES) {
+        int fd = open("/dev/null", O_RDONLY);
+        if (fd < 0) {
+            break;
+        }
+        fds[num_fds++] = fd;
+    }
+    if (num_fds == MAX_OPEN_FILES || errno != EMFILE) {
+        printf("\t\tERROR: open should fail with EMFILE\n");
+        goto out;
+    }
+    if (dup(fds[0]) >= 0 || errno != EMFILE) {
+        printf("\t\tERROR: dup should fail with EMFILE\n");
+        goto out;
+    }
+    if (dup2(fds[0], MAX_OPEN_FILES) >= 0 || errno != EBADF) {
+        printf("\t\tERROR: dup2 should fail with EBADF\n");
+        goto out;
+    }
+    if (fcntl(fds[0], F_DUPFD, MAX_OPEN_FILES) >= 0 || errno != EINVAL) {
+        printf("\t\tERROR: fcntl F_DUPFD should fail with EINVAL\n");
+        goto out;
+    }
+    // An fd is available again after closing a file
+    close(fds[--num_fds]);
+    int fd = open("/dev/null", O_RDONLY);
+    if (fd < 0) {
+        printf("\t\tERROR: failed to open a file after closing one\n");
+        goto out;
+    }
+    fds[num_fds++] = fd;
+    ret = 0;
+out:
+    for (int i = 0; i < num_fds; i++) {
+        close(fds[i]);
+    }
+    setrlimit(RLIMIT_NOFILE, &old_rlim);
+    return ret;
+}
+
+static int test_as_limit() {
+    // Make sure that some memory has been mapped by mmap
+    char *page = mmap(NULL, PAGE_SIZE, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1,
+                      0);
+    if (page == MAP_FAILED) {
+        THROW_ERROR("failed to mmap");
+    }
+
+    // The address space consists of the heap, the stack and the mmap region,
+    // so there is not enough space for the new mapping
+    struct rlimit data_rlim, stack_rlim, old_rlim;
+    if (getrlimit(RLIMIT_DATA, &data_rlim) < 0 || getrlimit(RLIMIT_STACK, &stack_rlim) < 0) {
+        THROW_ERROR("failed to getrlimit");
+    }
+    rlim_t as_limit = data_rlim.rlim_cur + stack_rlim.rlim_cur + MMAP_SIZE;
+    if (set_soft_limit(RLIMIT_AS, as_limit, &old_rlim) < 0) {
         return -1;
     }
+    char *buf = mmap(NULL, MMAP_SIZE, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1,
+                     0);
+    int mmap_errno = errno;
+    if (buf != MAP_FAILED) {
+        munmap(buf, MMAP_SIZE);
+    }
+    setrlimit(RLIMIT_AS, &old_rlim);
+    munmap(page, PAGE_SIZE);
+    if (buf != MAP_FAILED || mmap_errno != ENOMEM) {
+        THROW_ERROR("mmap should fail with ENOMEM");
+    }
+
+    // The address space can grow again after the limit is restored
+    buf = mmap(NULL, MMAP_SIZE, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (buf == MAP_FAILED) {
+        THROW_ERROR("failed to mmap after restoring the limit");
+    }
+    munmap(buf, MMAP_SIZE);
     return 0;
 }
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_getrlimit_and_setrlimit),
+    TEST_CASE(test_raise_hard_limit),
+    TEST_CASE(test_nofile_limit),
+    TEST_CASE(test_as_limit),
+};
+
+int main(int argc, const char *argv[]) {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
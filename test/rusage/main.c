@@ -0,0 +1,154 @@
+#define _GNU_SOURCE
+#include <sys/mman.h>
+#include <sys/resource.h>
+#include <sys/time.h>
+#include <sys/wait.h>
+#include <errno.h>
+#include <spawn.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define CHILD_PROG          "/bin/rusage"
+#define BUSY_LOOP_MS        100
+#define MMAP_SIZE           (16 * 1024 * 1024)
+
+static long timeval_to_ms(const struct timeval *tv) {
+    return tv->tv_sec * 1000 + tv->tv_usec / 1000;
+}
+
+static long cpu_time_ms(const struct rusage *usage) {
+    return timeval_to_ms(&usage->ru_utime) + timeval_to_ms(&usage->ru_stime);
+}
+
+// Consume the CPU until the CPU time of the thread increases by the given time
+static int busy_loop(long ms) {
+    struct rusage usage;
+    if (getrusage(RUSAGE_THREAD, &usage) < 0) {
+        return -1;
+    }
+    long end_ms = cpu_time_ms(&usage) + ms;
+    volatile unsigned long counter = 0;
+    do {
+        for (int i = 0; i < 1000000; i++) {
+            counter++;
+        }
+        if (getrusage(RUSAGE_THREAD, &usage) < 0) {
+            return -1;
+        }
+    } while (cpu_time_ms(&usage) < end_ms);
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_getrusage_self() {
+    struct rusage usage_before, usage_after;
+    if (getrusage(RUSAGE_SELF, &usage_before) < 0) {
+        THROW_ERROR("failed to getrusage");
+    }
+    if (busy_loop(BUSY_LOOP_MS) < 0) {
+        THROW_ERROR("failed to consume the CPU");
+    }
+    if (getrusage(RUSAGE_SELF, &usage_after) < 0) {
+        THROW_ERROR("failed to getrusage");
+    }
+    if (cpu_time_ms(&usage_after) - cpu_time_ms(&usage_before) < BUSY_LOOP_MS) {
+        THROW_ERROR("the CPU time of the process is not accounted");
+    }
+    if (usage_after.ru_maxrss <= 0) {
+        THROW_ERROR("the maximum resident set size is not accounted");
+    }
+    return 0;
+}
+
+static int test_getrusage_maxrss() {
+    struct rusage usage_before, usage_after;
+    if (getrusage(RUSAGE_SELF, &usage_before) < 0) {
+        THROW_ERROR("failed to getrusage");
+    }
+    char *buf = mmap(NULL, MMAP_SIZE, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1,
+                     0);
+    if (buf == MAP_FAILED) {
+        THROW_ERROR("failed to mmap");
+    }
+    memset(buf, 1, MMAP_SIZE);
+    munmap(buf, MMAP_SIZE);
+    if (getrusage(RUSAGE_SELF, &usage_after) < 0) {
+        THROW_ERROR("failed to getrusage");
+    }
+    // The maximum resident set size is in kilobytes and never decreases
+    if (usage_after.ru_maxrss - usage_before.ru_maxrss < MMAP_SIZE / 1024) {
+        THROW_ERROR("the maximum resident set size is not increased by mmap");
+    }
+    return 0;
+}
+
+static int test_wait4_rusage() {
+    struct rusage children_before, children_after, child_usage;
+    if (getrusage(RUSAGE_CHILDREN, &children_before) < 0) {
+        THROW_ERROR("failed to getrusage of children");
+    }
+
+    pid_t child_pid = 0;
+    const char *child_argv[3] = { CHILD_PROG, "busy_loop", NULL };
+    if (posix_spawn(&child_pid, CHILD_PROG, NULL, NULL, (char *const *)child_argv,
+                    NULL) != 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+    int status = 0;
+    if (wait4(child_pid, &status, 0, &child_usage) != child_pid) {
+        THROW_ERROR("failed to wait4 the child process");
+    }
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        THROW_ERROR("the child process failed");
+    }
+    if (cpu_time_ms(&child_usage) < BUSY_LOOP_MS || child_usage.ru_maxrss <= 0) {
+        THROW_ERROR("the resource usage of the child is not as expected");
+    }
+
+    if (getrusage(RUSAGE_CHILDREN, &children_after) < 0) {
+        THROW_ERROR("failed to getrusage of children");
+    }
+    if (cpu_time_ms(&children_after) - cpu_time_ms(&children_before) <
+            cpu_time_ms(&child_usage)) {
+        THROW_ERROR("the CPU time of the waited child is not accounted");
+    }
+    if (children_after.ru_maxrss < child_usage.ru_maxrss) {
+        THROW_ERROR("the maximum resident set size of the child is not accounted");
+    }
+    return 0;
+}
+
+static int test_getrusage_invalid_who() {
+    struct rusage usage;
+    if (getrusage(100, &usage) == 0 || errno != EINVAL) {
+        THROW_ERROR("getrusage with an invalid who should fail");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_getrusage_self),
+    TEST_CASE(test_getrusage_maxrss),
+    TEST_CASE(test_wait4_rusage),
+    TEST_CASE(test_getrusage_invalid_who),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 1 && strcmp(argv[1], "busy_loop") == 0) {
+        return busy_loop(BUSY_LOOP_MS) < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
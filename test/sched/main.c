@@ -7,6 +7,7 @@
 #include <sched.h>
 #include <errno.h>
 #include <spawn.h>
+#include <sys/resource.h>
 #include <sys/syscall.h>
 #include <sys/wait.h>
 #include "test.h"
@@ -244,6 +245,32 @@ static int test_sched_setaffinity_with_null_buffer() {
 // Test cases for sched_yield
 // ============================================================================
 
+static int test_sched_setaffinity_with_unavailable_cpu() {
+    cpu_set_t mask;
+    CPU_ZERO(&mask);
+    // Only the available CPU cores, which are reported by sched_getaffinity,
+    // can be set
+    if (sched_getaffinity(0, sizeof(cpu_set_t), &mask) < 0) {
+        THROW_ERROR("failed to call sched_getaffinity");
+    }
+    int unavailable_cpu = -1;
+    for (int i = 0; i < CPU_SETSIZE; i++) {
+        if (!CPU_ISSET(i, &mask)) {
+            unavailable_cpu = i;
+            break;
+        }
+    }
+    if (unavailable_cpu < 0) {
+        return 0;
+    }
+    CPU_ZERO(&mask);
+    CPU_SET(unavailable_cpu, &mask);
+    if (sched_setaffinity(0, sizeof(cpu_set_t), &mask) == 0 || errno != EINVAL) {
+        THROW_ERROR("setting an unavailable CPU core should fail");
+    }
+    return 0;
+}
+
 static int test_sched_yield() {
     // In the Linux implementation, sched_yield() always succeeds.
     if (sched_yield() < 0) {
@@ -256,6 +283,88 @@ static int test_sched_yield() {
 // Test cases for getcpu
 // ============================================================================
 
+static int test_getpriority_and_setpriority() {
+    errno = 0;
+    if (getpriority(PRIO_PROCESS, 0) != 0 || errno != 0) {
+        THROW_ERROR("the default nice value should be 0");
+    }
+    if (setpriority(PRIO_PROCESS, 0, 5) < 0) {
+        THROW_ERROR("failed to call setpriority");
+    }
+    if (getpriority(PRIO_PROCESS, getpid()) != 5) {
+        THROW_ERROR("the nice value is not set");
+    }
+    // The nice value is clamped to [-20, 19]
+    if (setpriority(PRIO_PGRP, 0, -100) < 0) {
+        THROW_ERROR("failed to call setpriority with PRIO_PGRP");
+    }
+    if (getpriority(PRIO_PROCESS, 0) != -20) {
+        THROW_ERROR("the nice value is not clamped");
+    }
+    if (setpriority(PRIO_PROCESS, 0, 0) < 0) {
+        THROW_ERROR("failed to restore the nice value");
+    }
+    if (getpriority(100, 0) != -1 || errno != EINVAL) {
+        THROW_ERROR("getpriority with an invalid which should fail");
+    }
+    return 0;
+}
+
+static int test_sched_get_priority_range() {
+    if (sched_get_priority_min(SCHED_FIFO) != 1 || sched_get_priority_max(SCHED_FIFO) != 99) {
+        THROW_ERROR("the priority range of SCHED_FIFO is wrong");
+    }
+    if (sched_get_priority_min(SCHED_OTHER) != 0 || sched_get_priority_max(SCHED_OTHER) != 0) {
+        THROW_ERROR("the priority range of SCHED_OTHER is wrong");
+    }
+    if (sched_get_priority_max(100) != -1 || errno != EINVAL) {
+        THROW_ERROR("getting the priority of an invalid policy should fail");
+    }
+    return 0;
+}
+
+// The libc (e.g., musl) may not support sched_setscheduler and its friends,
+// so the explicit syscalls are used
+static int test_sched_setscheduler() {
+    if (syscall(__NR_sched_getscheduler, 0) != SCHED_OTHER) {
+        THROW_ERROR("the default policy should be SCHED_OTHER");
+    }
+
+    struct sched_param param = { .sched_priority = 10 };
+    if (syscall(__NR_sched_setscheduler, 0, SCHED_RR, &param) < 0) {
+        THROW_ERROR("failed to call sched_setscheduler");
+    }
+    if (syscall(__NR_sched_getscheduler, 0) != SCHED_RR) {
+        THROW_ERROR("the policy is not set");
+    }
+    param.sched_priority = 20;
+    if (syscall(__NR_sched_setparam, 0, &param) < 0) {
+        THROW_ERROR("failed to call sched_setparam");
+    }
+    param.sched_priority = 0;
+    if (syscall(__NR_sched_getparam, 0, &param) < 0 || param.sched_priority != 20) {
+        THROW_ERROR("the priority is not set");
+    }
+    struct timespec interval;
+    if (syscall(__NR_sched_rr_get_interval, 0, &interval) < 0 ||
+            (interval.tv_sec == 0 && interval.tv_nsec == 0)) {
+        THROW_ERROR("the time slice of SCHED_RR should not be zero");
+    }
+
+    // SCHED_OTHER only accepts priority 0
+    if (syscall(__NR_sched_setscheduler, 0, SCHED_OTHER, &param) == 0 || errno != EINVAL) {
+        THROW_ERROR("setting an invalid priority should fail");
+    }
+    param.sched_priority = 0;
+    if (syscall(__NR_sched_setscheduler, 0, SCHED_OTHER, &param) < 0) {
+        THROW_ERROR("failed to restore the policy");
+    }
+    if (syscall(__NR_sched_setscheduler, -1, SCHED_OTHER, &param) == 0 || errno != EINVAL) {
+        THROW_ERROR("sched_setscheduler with a negative pid should fail");
+    }
+    return 0;
+}
+
 static int test_getcpu() {
     int cpu, node;
     if (syscall(__NR_getcpu, &cpu, &node, NULL) < 0) {
@@ -317,7 +426,11 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_sched_setaffinity_with_zero_cpusetsize),
     TEST_CASE(test_sched_getaffinity_with_null_buffer),
     TEST_CASE(test_sched_setaffinity_with_null_buffer),
+    TEST_CASE(test_sched_setaffinity_with_unavailable_cpu),
     TEST_CASE(test_sched_yield),
+    TEST_CASE(test_getpriority_and_setpriority),
+    TEST_CASE(test_sched_get_priority_range),
+    TEST_CASE(test_sched_setscheduler),
     TEST_CASE(test_sched_xetaffinity_children_inheritance),
     TEST_CASE(test_getcpu),
     TEST_CASE(test_getcpu_after_setaffinity),
@@ -0,0 +1,15 @@
+#!/bin/bash
+# Usage: crash_and_recover.sh <occlum> exec /bin/sefs_journal
+#
+# Crash the writes to SEFS by killing the Occlum server in the middle of them,
+# and then check the SEFS recovered from the journal after the server is
+# restarted.
+OCCLUM=$1
+
+"$@" write &
+sleep 5
+pkill -9 -f occlum_exec_server
+wait
+
+"$OCCLUM" start || exit 1
+"$@"
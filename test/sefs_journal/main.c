@@ -0,0 +1,162 @@
+#include <sys/stat.h>
+#include <dirent.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include "test_fs.h"
+
+// The writes are crashed by killing the Occlum server in the middle of them,
+// and then the SEFS recovered from the journal is checked after the server is
+// restarted. See crash_and_recover.sh.
+
+#define TEST_DIR        "/root/test_sefs_journal"
+#define PROGRESS_PATH   TEST_DIR "/progress"
+#define MSG_FMT         "the content of the file %d"
+#define PATH_FMT        TEST_DIR "/file_%d"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+static int write_and_sync(int fd, const char *buf, size_t len) {
+    if (pwrite(fd, buf, len, 0) != len || ftruncate(fd, len) < 0 || fsync(fd) < 0) {
+        THROW_ERROR("failed to write and sync the file");
+    }
+    return 0;
+}
+
+static int create_file(int idx) {
+    char path[PATH_MAX];
+    char msg[64];
+    snprintf(path, sizeof(path), PATH_FMT, idx);
+    snprintf(msg, sizeof(msg), MSG_FMT, idx);
+
+    int fd = open(path, O_WRONLY | O_CREAT | O_TRUNC, 0666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create the file");
+    }
+    // Write more than the message to truncate the file afterwards
+    char buf[256];
+    memset(buf, 'x', sizeof(buf));
+    if (write(fd, buf, sizeof(buf)) != sizeof(buf) ||
+            write_and_sync(fd, msg, strlen(msg)) < 0) {
+        close(fd);
+        THROW_ERROR("failed to write the file");
+    }
+    close(fd);
+    return 0;
+}
+
+// Keep writing the files until being killed. The progress is updated only
+// after a file is synced, so the files before the progress must survive.
+static int write_files() {
+    if (mkdir(TEST_DIR, 0777) < 0 && errno != EEXIST) {
+        THROW_ERROR("failed to create the test directory");
+    }
+    int fd = open(PROGRESS_PATH, O_WRONLY | O_CREAT | O_TRUNC, 0666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create the progress file");
+    }
+    for (int idx = 0; ; idx++) {
+        if (create_file(idx) < 0) {
+            close(fd);
+            return -1;
+        }
+        char progress[16];
+        int len = snprintf(progress, sizeof(progress), "%d", idx + 1);
+        if (write_and_sync(fd, progress, len) < 0) {
+            close(fd);
+            return -1;
+        }
+        // Unlink the previous files to free the inodes in the metadata
+        if (idx >= 8) {
+            char path[PATH_MAX];
+            snprintf(path, sizeof(path), PATH_FMT, idx - 8);
+            if (unlink(path) < 0) {
+                close(fd);
+                THROW_ERROR("failed to unlink the file");
+            }
+        }
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_recovered_files() {
+    char progress[16] = {0};
+    int fd = open(PROGRESS_PATH, O_RDONLY);
+    if (fd < 0 || read(fd, progress, sizeof(progress) - 1) <= 0) {
+        THROW_ERROR("failed to read the progress of the writes");
+    }
+    close(fd);
+
+    int nr_files = atoi(progress);
+    if (nr_files <= 0) {
+        THROW_ERROR("no file is written before the crash");
+    }
+    int first = nr_files > 8 ? nr_files - 8 : 0;
+    for (int idx = first; idx < nr_files; idx++) {
+        char path[PATH_MAX];
+        char msg[64];
+        snprintf(path, sizeof(path), PATH_FMT, idx);
+        snprintf(msg, sizeof(msg), MSG_FMT, idx);
+        if (fs_check_file_content(path, msg) < 0) {
+            THROW_ERROR("the synced file is not recovered");
+        }
+    }
+    return 0;
+}
+
+static int test_write_after_recovery() {
+    if (create_file(-1) < 0) {
+        THROW_ERROR("failed to write the recovered SEFS");
+    }
+    return 0;
+}
+
+static int test_cleanup() {
+    DIR *dir = opendir(TEST_DIR);
+    if (dir == NULL) {
+        THROW_ERROR("failed to open the test directory");
+    }
+    struct dirent *entry;
+    char path[PATH_MAX];
+    while ((entry = readdir(dir)) != NULL) {
+        if (strcmp(entry->d_name, ".") == 0 || strcmp(entry->d_name, "..") == 0) {
+            continue;
+        }
+        snprintf(path, sizeof(path), TEST_DIR "/%s", entry->d_name);
+        if (unlink(path) < 0) {
+            closedir(dir);
+            THROW_ERROR("failed to unlink the file");
+        }
+    }
+    closedir(dir);
+    if (rmdir(TEST_DIR) < 0) {
+        THROW_ERROR("failed to remove the test directory");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_recovered_files),
+    TEST_CASE(test_write_after_recovery),
+    TEST_CASE(test_cleanup),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 1 && strcmp(argv[1], "write") == 0) {
+        return write_files();
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,205 @@
+#include <sys/ioctl.h>
+#include <sys/stat.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include "test_fs.h"
+
+// The writable layer of the root file system is re-encrypted with the new key,
+// and then the files are checked after the Occlum instance is rebuilt with the
+// new key, i.e., the file system is reopened. See rekey_and_reopen.sh.
+
+#define TEST_DIR            "/root/test_sefs_rekey"
+#define PATH_FMT            TEST_DIR "/file_%d"
+#define AFTER_REKEY_PATH    TEST_DIR "/file_after_rekey"
+#define AFTER_REKEY_MSG     "the content written after the re-encryption"
+#define NR_FILES            8
+// Larger than the chunks in which the files are re-encrypted
+#define FILE_LEN            (200 * 1024)
+
+// The same as the new key in rekey_and_reopen.sh
+static const uint8_t new_key[16] = {
+    0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77,
+    0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff,
+};
+
+struct sefs_rekey_arg {
+    uint8_t new_key[16];
+};
+
+struct sefs_rekey_progress {
+    uint32_t is_running;
+    uint32_t total_files;
+    uint32_t done_files;
+};
+
+#define SEFS_IOC_REKEY              _IOW('f', 0, struct sefs_rekey_arg)
+#define SEFS_IOC_GET_REKEY_PROGRESS _IOR('f', 1, struct sefs_rekey_progress)
+
+static char file_buf[FILE_LEN];
+// The file opened before the re-encryption
+static int opened_fd = -1;
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+static void fill_file_buf(int idx) {
+    for (int i = 0; i < FILE_LEN; i++) {
+        file_buf[i] = (char)(i * 13 + idx);
+    }
+}
+
+static int check_file(int fd, int idx) {
+    static char read_buf[FILE_LEN];
+
+    fill_file_buf(idx);
+    if (pread(fd, read_buf, FILE_LEN, 0) != FILE_LEN ||
+            memcmp(read_buf, file_buf, FILE_LEN) != 0) {
+        THROW_ERROR("the content of the file %d is wrong", idx);
+    }
+    return 0;
+}
+
+static int check_files() {
+    char path[PATH_MAX];
+
+    for (int idx = 0; idx < NR_FILES; idx++) {
+        snprintf(path, sizeof(path), PATH_FMT, idx);
+        int fd = open(path, O_RDONLY);
+        if (fd < 0) {
+            THROW_ERROR("failed to open the file %d", idx);
+        }
+        int ret = check_file(fd, idx);
+        close(fd);
+        if (ret < 0) {
+            return -1;
+        }
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases before the file system is reopened
+// ============================================================================
+
+static int test_write_files() {
+    char path[PATH_MAX];
+
+    if (mkdir(TEST_DIR, 0777) < 0 && errno != EEXIST) {
+        THROW_ERROR("failed to create the test directory");
+    }
+    for (int idx = 0; idx < NR_FILES; idx++) {
+        snprintf(path, sizeof(path), PATH_FMT, idx);
+        int fd = open(path, O_RDWR | O_CREAT | O_TRUNC, 0666);
+        if (fd < 0) {
+            THROW_ERROR("failed to create the file %d", idx);
+        }
+        fill_file_buf(idx);
+        if (write(fd, file_buf, FILE_LEN) != FILE_LEN) {
+            close(fd);
+            THROW_ERROR("failed to write the file %d", idx);
+        }
+        // Keep the first file opened across the re-encryption
+        if (idx == 0) {
+            opened_fd = fd;
+        } else {
+            close(fd);
+        }
+    }
+    return 0;
+}
+
+static int test_rekey() {
+    struct sefs_rekey_arg arg;
+    struct sefs_rekey_progress progress;
+
+    int fd = open("/dev/sefs", O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open /dev/sefs");
+    }
+    memcpy(arg.new_key, new_key, sizeof(new_key));
+    if (ioctl(fd, SEFS_IOC_REKEY, &arg) < 0) {
+        close(fd);
+        THROW_ERROR("failed to re-encrypt the writable layer");
+    }
+    if (ioctl(fd, SEFS_IOC_GET_REKEY_PROGRESS, &progress) < 0) {
+        close(fd);
+        THROW_ERROR("failed to get the progress of the re-encryption");
+    }
+    close(fd);
+    if (progress.is_running || progress.total_files < NR_FILES ||
+            progress.done_files != progress.total_files) {
+        THROW_ERROR("the progress of the re-encryption is wrong");
+    }
+    return 0;
+}
+
+static int test_resume_after_rekey() {
+    // The file opened before the re-encryption is still usable
+    if (check_file(opened_fd, 0) < 0) {
+        THROW_ERROR("failed to read the file opened before the re-encryption");
+    }
+    fill_file_buf(0);
+    if (pwrite(opened_fd, file_buf, FILE_LEN, 0) != FILE_LEN) {
+        THROW_ERROR("failed to write the file opened before the re-encryption");
+    }
+    close(opened_fd);
+
+    if (check_files() < 0) {
+        THROW_ERROR("failed to read the files after the re-encryption");
+    }
+    int fd = open(AFTER_REKEY_PATH, O_WRONLY | O_CREAT | O_TRUNC, 0666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create the file after the re-encryption");
+    }
+    if (write(fd, AFTER_REKEY_MSG, strlen(AFTER_REKEY_MSG)) != strlen(AFTER_REKEY_MSG)) {
+        close(fd);
+        THROW_ERROR("failed to write the file after the re-encryption");
+    }
+    close(fd);
+    return 0;
+}
+
+// ============================================================================
+// Test cases after the file system is reopened with the new key
+// ============================================================================
+
+static int test_read_old_files() {
+    if (check_files() < 0) {
+        THROW_ERROR("failed to read the files written before the re-encryption");
+    }
+    return 0;
+}
+
+static int test_read_new_file() {
+    if (fs_check_file_content(AFTER_REKEY_PATH, AFTER_REKEY_MSG) < 0) {
+        THROW_ERROR("failed to read the file written after the re-encryption");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t rekey_test_cases[] = {
+    TEST_CASE(test_write_files),
+    TEST_CASE(test_rekey),
+    TEST_CASE(test_resume_after_rekey),
+};
+
+static test_case_t reopen_test_cases[] = {
+    TEST_CASE(test_read_old_files),
+    TEST_CASE(test_read_new_file),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 1 && strcmp(argv[1], "rekey") == 0) {
+        return test_suite_run(rekey_test_cases, ARRAY_SIZE(rekey_test_cases));
+    }
+    return test_suite_run(reopen_test_cases, ARRAY_SIZE(reopen_test_cases));
+}
@@ -0,0 +1,30 @@
+#!/bin/bash
+# Usage: rekey_and_reopen.sh <occlum> exec /bin/sefs_rekey
+#
+# Re-encrypt the writable layer of the root file system with the new key in a
+# new Occlum instance, and then check the files after the instance is rebuilt
+# with the new key, but keeps the re-encrypted writable layer.
+OCCLUM=$1
+TEST_BIN=$3
+INSTANCE_DIR="$PWD/sefs_rekey_instance"
+# The new key is the same as in main.c
+OLD_KEY="0f-1e-2d-3c-4b-5a-69-78-87-96-a5-b4-c3-d2-e1-f0"
+NEW_KEY="00-11-22-33-44-55-66-77-88-99-aa-bb-cc-dd-ee-ff"
+
+rm -rf "$INSTANCE_DIR"
+"$OCCLUM" new "$INSTANCE_DIR" > /dev/null || exit 1
+mkdir -p "$INSTANCE_DIR/image/bin"
+cp "image$TEST_BIN" "$INSTANCE_DIR/image$TEST_BIN" || exit 1
+cd "$INSTANCE_DIR"
+printf "$OLD_KEY" > old_key
+printf "$NEW_KEY" > new_key
+
+"$OCCLUM" build --image-key old_key > /dev/null || exit 1
+"$OCCLUM" run "$TEST_BIN" rekey || exit 1
+
+# Rebuilding removes the writable layer, so keep it aside
+mv run/mount/__ROOT writable_layer
+"$OCCLUM" build --image-key new_key > /dev/null || exit 1
+rm -rf run/mount/__ROOT
+mv writable_layer run/mount/__ROOT
+"$OCCLUM" run "$TEST_BIN"
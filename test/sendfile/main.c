@@ -0,0 +1,189 @@
+#include <sys/sendfile.h>
+#include <sys/socket.h>
+#include <sys/stat.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define DATA_LEN    (256 * 1024 + 123)
+
+static const char *in_path = "/root/test_sendfile_in.txt";
+static const char *out_path = "/root/test_sendfile_out.txt";
+
+static char *data_buf;
+
+static int create_in_file() {
+    data_buf = malloc(DATA_LEN);
+    if (data_buf == NULL) {
+        THROW_ERROR("failed to allocate the data buffer");
+    }
+    for (int i = 0; i < DATA_LEN; i++) {
+        data_buf[i] = (char)(i % 251);
+    }
+
+    int fd = open(in_path, O_WRONLY | O_CREAT | O_TRUNC, 00666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create the input file");
+    }
+    if (write(fd, data_buf, DATA_LEN) != DATA_LEN) {
+        close(fd);
+        THROW_ERROR("failed to write the input file");
+    }
+    close(fd);
+    return 0;
+}
+
+static int check_out_file(size_t offset, size_t len) {
+    char *buf = malloc(len);
+    if (buf == NULL) {
+        THROW_ERROR("failed to allocate a buffer");
+    }
+    int fd = open(out_path, O_RDONLY);
+    if (fd < 0) {
+        free(buf);
+        THROW_ERROR("failed to open the output file");
+    }
+    if (read(fd, buf, len) != len || memcmp(buf, data_buf + offset, len) != 0) {
+        close(fd);
+        free(buf);
+        THROW_ERROR("the content of the output file is wrong");
+    }
+    close(fd);
+    free(buf);
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+int test_sendfile_with_file_offset() {
+    int in_fd = open(in_path, O_RDONLY);
+    int out_fd = open(out_path, O_WRONLY | O_CREAT | O_TRUNC, 00666);
+    if (in_fd < 0 || out_fd < 0) {
+        THROW_ERROR("failed to open files");
+    }
+
+    if (sendfile(out_fd, in_fd, NULL, DATA_LEN) != DATA_LEN) {
+        close_files(2, in_fd, out_fd);
+        THROW_ERROR("failed to sendfile");
+    }
+    if (lseek(in_fd, 0, SEEK_CUR) != DATA_LEN) {
+        close_files(2, in_fd, out_fd);
+        THROW_ERROR("the file offset of the input file is not updated");
+    }
+    close_files(2, in_fd, out_fd);
+
+    return check_out_file(0, DATA_LEN);
+}
+
+int test_sendfile_with_given_offset() {
+    int in_fd = open(in_path, O_RDONLY);
+    int out_fd = open(out_path, O_WRONLY | O_CREAT | O_TRUNC, 00666);
+    if (in_fd < 0 || out_fd < 0) {
+        THROW_ERROR("failed to open files");
+    }
+
+    off_t offset = 100;
+    size_t count = DATA_LEN; // More than the remaining bytes
+    ssize_t len = sendfile(out_fd, in_fd, &offset, count);
+    if (len != DATA_LEN - 100 || offset != DATA_LEN) {
+        close_files(2, in_fd, out_fd);
+        THROW_ERROR("failed to sendfile with an offset");
+    }
+    if (lseek(in_fd, 0, SEEK_CUR) != 0) {
+        close_files(2, in_fd, out_fd);
+        THROW_ERROR("the file offset of the input file should not be changed");
+    }
+    close_files(2, in_fd, out_fd);
+
+    return check_out_file(100, DATA_LEN - 100);
+}
+
+int test_sendfile_to_socket() {
+    int socks[2];
+    if (socketpair(AF_UNIX, SOCK_STREAM, 0, socks) < 0) {
+        THROW_ERROR("failed to create a socket pair");
+    }
+    int in_fd = open(in_path, O_RDONLY);
+    if (in_fd < 0) {
+        close_files(2, socks[0], socks[1]);
+        THROW_ERROR("failed to open the input file");
+    }
+
+    size_t count = 4096;
+    char buf[4096];
+    if (sendfile(socks[0], in_fd, NULL, count) != count ||
+            read(socks[1], buf, count) != count ||
+            memcmp(buf, data_buf, count) != 0) {
+        close_files(3, in_fd, socks[0], socks[1]);
+        THROW_ERROR("failed to sendfile to a socket");
+    }
+
+    close_files(3, in_fd, socks[0], socks[1]);
+    return 0;
+}
+
+int test_sendfile_with_invalid_args() {
+    int in_fd = open(in_path, O_RDONLY);
+    int out_fd = open(out_path, O_WRONLY | O_APPEND);
+    if (in_fd < 0 || out_fd < 0) {
+        THROW_ERROR("failed to open files");
+    }
+
+    if (sendfile(out_fd, in_fd, NULL, 1) >= 0 || errno != EINVAL) {
+        close_files(2, in_fd, out_fd);
+        THROW_ERROR("sendfile to a file opened with O_APPEND should fail");
+    }
+    off_t offset = -1;
+    if (sendfile(in_fd, in_fd, &offset, 1) >= 0 || errno != EBADF) {
+        close_files(2, in_fd, out_fd);
+        THROW_ERROR("sendfile to a read-only file should fail");
+    }
+    close(out_fd);
+
+    out_fd = open(out_path, O_WRONLY);
+    if (sendfile(out_fd, in_fd, &offset, 1) >= 0 || errno != EINVAL) {
+        close_files(2, in_fd, out_fd);
+        THROW_ERROR("sendfile with a negative offset should fail");
+    }
+
+    close_files(2, in_fd, out_fd);
+    return 0;
+}
+
+int test_cleanup() {
+    free(data_buf);
+    if (unlink(in_path) < 0 || unlink(out_path) < 0) {
+        THROW_ERROR("failed to remove files");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_sendfile_with_file_offset),
+    TEST_CASE(test_sendfile_with_given_offset),
+    TEST_CASE(test_sendfile_to_socket),
+    TEST_CASE(test_sendfile_with_invalid_args),
+    TEST_CASE(test_cleanup),
+};
+
+int main() {
+    if (create_in_file() < 0) {
+        return -1;
+    }
+    int ret = test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+    return ret;
+}
@@ -0,0 +1,138 @@
+#include <sys/wait.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <spawn.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define INTERPRETER         "/bin/shebang"
+#define INTERPRETER_ARG     "interp-arg"
+#define SCRIPT_PATH         "/root/shebang_script.sh"
+#define NESTED_SCRIPT_PATH  "/root/shebang_nested_script.sh"
+#define INVALID_SCRIPT_PATH "/root/shebang_invalid_script.sh"
+#define LOOP_SCRIPT_PATH    "/root/shebang_loop_script.sh"
+
+static int create_script(const char *path, const char *content) {
+    int fd = open(path, O_WRONLY | O_CREAT | O_TRUNC, 0755);
+    if (fd < 0) {
+        THROW_ERROR("failed to create the script");
+    }
+    size_t len = strlen(content);
+    if (write(fd, content, len) != len) {
+        close(fd);
+        THROW_ERROR("failed to write the script");
+    }
+    close(fd);
+    return 0;
+}
+
+// Spawn the script with the expected argc of the interpreter as the only
+// argument, and wait for the interpreter to check its arguments
+static int spawn_script_and_wait(const char *path, int expected_argc) {
+    char argc_str[8] = { 0 };
+    snprintf(argc_str, sizeof(argc_str), "%d", expected_argc);
+    const char *child_argv[3] = { "script", argc_str, NULL };
+    pid_t child_pid = 0;
+    int ret = posix_spawn(&child_pid, path, NULL, NULL, (char *const *)child_argv, NULL);
+    if (ret != 0) {
+        errno = ret;
+        return -1;
+    }
+    int status = 0;
+    if (waitpid(child_pid, &status, 0) != child_pid) {
+        THROW_ERROR("failed to wait for the child");
+    }
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        THROW_ERROR("the arguments of the interpreter are not as expected");
+    }
+    return 0;
+}
+
+// ============================================================================
+// The interpreter
+// ============================================================================
+
+// The arguments are `INTERPRETER INTERPRETER_ARG SCRIPT_PATH [NESTED_SCRIPT_PATH]
+// expected_argc`
+static int check_interpreter_args(int argc, const char *argv[]) {
+    if (argc != atoi(argv[argc - 1])) {
+        return -1;
+    }
+    if (strcmp(argv[0], INTERPRETER) != 0 || strcmp(argv[2], SCRIPT_PATH) != 0) {
+        return -1;
+    }
+    if (argc == 5 && strcmp(argv[3], NESTED_SCRIPT_PATH) != 0) {
+        return -1;
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_script_with_interpreter_arg() {
+    // The whitespaces around the interpreter and its argument are ignored
+    if (create_script(SCRIPT_PATH, "#! " INTERPRETER "  " INTERPRETER_ARG " \n") < 0) {
+        return -1;
+    }
+    if (spawn_script_and_wait(SCRIPT_PATH, 4) < 0) {
+        THROW_ERROR("failed to execute the script");
+    }
+    return 0;
+}
+
+static int test_nested_script() {
+    if (create_script(NESTED_SCRIPT_PATH, "#!" SCRIPT_PATH "\n") < 0) {
+        return -1;
+    }
+    if (spawn_script_and_wait(NESTED_SCRIPT_PATH, 5) < 0) {
+        THROW_ERROR("failed to execute the nested script");
+    }
+    return 0;
+}
+
+static int test_script_without_interpreter() {
+    if (create_script(INVALID_SCRIPT_PATH, "#!  \n") < 0) {
+        return -1;
+    }
+    if (spawn_script_and_wait(INVALID_SCRIPT_PATH, 0) == 0 || errno != ENOEXEC) {
+        THROW_ERROR("executing a script without an interpreter should fail");
+    }
+    return 0;
+}
+
+static int test_script_interpreter_loop() {
+    if (create_script(LOOP_SCRIPT_PATH, "#!" LOOP_SCRIPT_PATH "\n") < 0) {
+        return -1;
+    }
+    if (spawn_script_and_wait(LOOP_SCRIPT_PATH, 0) == 0 || errno != ELOOP) {
+        THROW_ERROR("executing a script interpreted by itself should fail");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_script_with_interpreter_arg),
+    TEST_CASE(test_nested_script),
+    TEST_CASE(test_script_without_interpreter),
+    TEST_CASE(test_script_interpreter_loop),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 1 && strcmp(argv[1], INTERPRETER_ARG) == 0) {
+        return check_interpreter_args(argc, argv) < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,198 @@
+#include <sys/mman.h>
+#include <sys/stat.h>
+#include <sys/vfs.h>
+#include <sys/wait.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <spawn.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define SHM_NAME        "/test_shm"
+#define SHM_PATH        "/dev/shm/test_shm"
+#define SHM_SIZE        8192
+#define CHILD_PROG      "/bin/shm"
+
+#define PARENT_MSG      "Hello from the parent"
+#define CHILD_MSG       "Hello from the child"
+// The child writes its message to the second page
+#define CHILD_MSG_OFFSET 4096
+
+#define TMPFS_MAGIC     0x01021994
+
+static int spawn_child(const char *test_name, int *child_pid) {
+    const char *child_argv[3] = { CHILD_PROG, test_name, NULL };
+    if (posix_spawn(child_pid, CHILD_PROG, NULL, NULL,
+                    (char *const *)child_argv, NULL) != 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+    return 0;
+}
+
+static int wait_child(int child_pid) {
+    int status = 0;
+    if (wait4(child_pid, &status, 0, NULL) < 0) {
+        THROW_ERROR("failed to wait4 the child process");
+    }
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        THROW_ERROR("the child process failed");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Child processes
+// ============================================================================
+
+// The parent has mapped the shared memory object and written its message
+static int child_test_mmap_shared() {
+    int fd = shm_open(SHM_NAME, O_RDWR, 0);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the shared memory object");
+    }
+    char *buf = mmap(NULL, SHM_SIZE, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    if (buf == MAP_FAILED) {
+        THROW_ERROR("failed to mmap the shared memory object");
+    }
+    close(fd);
+
+    if (strcmp(buf, PARENT_MSG) != 0) {
+        THROW_ERROR("the message of the parent is not visible");
+    }
+    strcpy(buf + CHILD_MSG_OFFSET, CHILD_MSG);
+    if (munmap(buf, SHM_SIZE) < 0) {
+        THROW_ERROR("failed to munmap the shared memory object");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_shm_open() {
+    int fd = shm_open(SHM_NAME, O_RDWR | O_CREAT | O_EXCL, 0600);
+    if (fd < 0) {
+        THROW_ERROR("failed to create the shared memory object");
+    }
+    if (shm_open(SHM_NAME, O_RDWR | O_CREAT | O_EXCL, 0600) >= 0 || errno != EEXIST) {
+        THROW_ERROR("creating an existing shared memory object should fail");
+    }
+    if (ftruncate(fd, SHM_SIZE) < 0) {
+        THROW_ERROR("failed to set the size of the shared memory object");
+    }
+    close(fd);
+
+    // The shared memory object is a file in /dev/shm
+    struct stat stat_buf;
+    if (stat(SHM_PATH, &stat_buf) < 0) {
+        THROW_ERROR("failed to stat the shared memory object");
+    }
+    if (!S_ISREG(stat_buf.st_mode) || stat_buf.st_size != SHM_SIZE) {
+        THROW_ERROR("the shared memory object is not as expected");
+    }
+    return 0;
+}
+
+static int test_mmap_shared_across_processes() {
+    int fd = shm_open(SHM_NAME, O_RDWR, 0);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the shared memory object");
+    }
+    char *buf = mmap(NULL, SHM_SIZE, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    if (buf == MAP_FAILED) {
+        THROW_ERROR("failed to mmap the shared memory object");
+    }
+    strcpy(buf, PARENT_MSG);
+
+    int child_pid = 0;
+    if (spawn_child("mmap_shared", &child_pid) < 0 || wait_child(child_pid) < 0) {
+        return -1;
+    }
+    // The write of the child is visible through the mapping of the parent
+    if (strcmp(buf + CHILD_MSG_OFFSET, CHILD_MSG) != 0) {
+        THROW_ERROR("the message of the child is not visible");
+    }
+    if (munmap(buf, SHM_SIZE) < 0) {
+        THROW_ERROR("failed to munmap the shared memory object");
+    }
+
+    // The content is written back to the object after unmapping
+    char read_buf[sizeof(CHILD_MSG)] = { 0 };
+    if (pread(fd, read_buf, sizeof(read_buf), CHILD_MSG_OFFSET) != sizeof(read_buf) ||
+            strcmp(read_buf, CHILD_MSG) != 0) {
+        THROW_ERROR("the content of the shared memory object is not as expected");
+    }
+    close(fd);
+    return 0;
+}
+
+static int test_statfs_dev_shm() {
+    struct statfs statfs_buf;
+    if (statfs("/dev/shm", &statfs_buf) < 0) {
+        THROW_ERROR("failed to statfs /dev/shm");
+    }
+    if (statfs_buf.f_type != TMPFS_MAGIC || statfs_buf.f_blocks == 0 ||
+            statfs_buf.f_bfree > statfs_buf.f_blocks) {
+        THROW_ERROR("the statistics of /dev/shm are not as expected");
+    }
+    return 0;
+}
+
+static int test_size_limit() {
+    const char *name = "/test_shm_size_limit";
+    int fd = shm_open(name, O_RDWR | O_CREAT, 0600);
+    if (fd < 0) {
+        THROW_ERROR("failed to create the shared memory object");
+    }
+    // The size of /dev/shm is limited
+    if (ftruncate(fd, 1L << 30) == 0 || errno != ENOSPC) {
+        THROW_ERROR("growing beyond the size limit should fail");
+    }
+    close(fd);
+    if (shm_unlink(name) < 0) {
+        THROW_ERROR("failed to unlink the shared memory object");
+    }
+    return 0;
+}
+
+static int test_shm_unlink() {
+    if (shm_unlink(SHM_NAME) < 0) {
+        THROW_ERROR("failed to unlink the shared memory object");
+    }
+    if (shm_open(SHM_NAME, O_RDWR, 0) >= 0 || errno != ENOENT) {
+        THROW_ERROR("the unlinked shared memory object should not exist");
+    }
+    if (shm_unlink(SHM_NAME) == 0 || errno != ENOENT) {
+        THROW_ERROR("unlinking a nonexistent shared memory object should fail");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_shm_open),
+    TEST_CASE(test_mmap_shared_across_processes),
+    TEST_CASE(test_statfs_dev_shm),
+    TEST_CASE(test_size_limit),
+    TEST_CASE(test_shm_unlink),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 1) {
+        if (strcmp(argv[1], "mmap_shared") == 0) {
+            return child_test_mmap_shared() < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+        }
+        return EXIT_FAILURE;
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
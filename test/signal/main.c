@@ -2,6 +2,8 @@
 #include <sys/types.h>
 #include <sys/stat.h>
 #include <sys/mman.h>
+#include <sys/resource.h>
+#include <sys/time.h>
 #include <sys/wait.h>
 #include <unistd.h>
 #include <ucontext.h>
@@ -14,6 +16,7 @@
 #include <fcntl.h>
 #include <signal.h>
 #include <pthread.h>
+#include <sched.h>
 #include <errno.h>
 #include <time.h>
 #include "test.h"
@@ -320,6 +323,90 @@ int test_handle_sigsegv() {
     return 0;
 }
 
+// ============================================================================
+// Test the siginfo of hardware exceptions
+// ============================================================================
+
+static volatile int g_fault_si_code;
+static void *volatile g_fault_si_addr;
+static volatile greg_t g_fault_rip;
+
+static void handle_fault_and_record_info(int num, siginfo_t *info, void *_context) {
+    ucontext_t *ucontext = _context;
+    mcontext_t *mcontext = &ucontext->uc_mcontext;
+    g_fault_si_code = info->si_code;
+    g_fault_si_addr = info->si_addr;
+    g_fault_rip = mcontext->gregs[REG_RIP];
+    // The faulty instruction should be `idiv %esi` (f7 fe) or `mov (%rdi),%eax` (8b 07)
+    mcontext->gregs[REG_RIP] += 2;
+}
+
+int test_fault_siginfo() {
+    struct sigaction new_action, old_fpe_action, old_segv_action;
+    memset(&new_action, 0, sizeof(struct sigaction));
+    new_action.sa_sigaction = handle_fault_and_record_info;
+    new_action.sa_flags = SA_SIGINFO;
+    if (sigaction(SIGFPE, &new_action, &old_fpe_action) < 0 ||
+            sigaction(SIGSEGV, &new_action, &old_segv_action) < 0) {
+        THROW_ERROR("registering new signal handler failed");
+    }
+
+    // The address of a divide-by-zero exception is the faulting instruction
+    volatile int c = div_maybe_zero(1, 0);
+    (void)c;
+    if (g_fault_si_code != FPE_INTDIV || g_fault_si_addr != (void *)g_fault_rip) {
+        THROW_ERROR("the siginfo of SIGFPE is wrong");
+    }
+
+    // The address of a page fault is the accessed memory
+    int *bad_addr = (int *)0x10;
+    volatile int val = read_maybe_null(bad_addr);
+    (void)val;
+    if (g_fault_si_code != SEGV_MAPERR || g_fault_si_addr != (void *)bad_addr) {
+        THROW_ERROR("the siginfo of SIGSEGV is wrong");
+    }
+
+    if (sigaction(SIGFPE, &old_fpe_action, NULL) < 0 ||
+            sigaction(SIGSEGV, &old_segv_action, NULL) < 0) {
+        THROW_ERROR("restoring old signal handler failed");
+    }
+    return 0;
+}
+
+// A fault signal that is blocked or ignored kills the process
+int test_blocked_or_ignored_fault_signal() {
+    char *child_args[] = {"blocked", "ignored"};
+    for (int i = 0; i < ARRAY_SIZE(child_args); i++) {
+        pid_t child_pid;
+        char *child_argv[] = {"signal", "faulting_child", child_args[i], NULL};
+        int status;
+        if (posix_spawn(&child_pid, "/bin/signal", NULL, NULL, child_argv, NULL) < 0) {
+            THROW_ERROR("failed to spawn a child process\n");
+        }
+        if (wait4(child_pid, &status, 0, NULL) < 0) {
+            THROW_ERROR("failed to wait4 the child process\n");
+        }
+        if (!WIFSIGNALED(status) || WTERMSIG(status) != SIGSEGV) {
+            THROW_ERROR("child process is expected to be killed by SIGSEGV\n");
+        }
+    }
+    return 0;
+}
+
+static int faulting_child(const char *how) {
+    if (strcmp(how, "blocked") == 0) {
+        sigset_t mask;
+        sigemptyset(&mask);
+        sigaddset(&mask, SIGSEGV);
+        sigprocmask(SIG_BLOCK, &mask, NULL);
+    } else {
+        signal(SIGSEGV, SIG_IGN);
+    }
+    volatile int val = read_maybe_null(NULL);
+    (void)val;
+    return 0;
+}
+
 // ============================================================================
 // Test handle signal on alternate signal stack
 // ============================================================================
@@ -388,6 +475,69 @@ int test_sigaltstack() {
     return 0;
 }
 
+#ifndef SS_AUTODISARM
+#define SS_AUTODISARM   (1U << 31)
+#endif
+
+static char g_autodisarm_stack[SIGSTKSZ];
+static volatile int g_autodisarm_ok = 0;
+
+static void handle_sigusr1_with_autodisarm(int num, siginfo_t *info, void *context) {
+    // The handler runs on the alternate signal stack, which is disarmed
+    char on_stack;
+    stack_t ss;
+    if (sigaltstack(NULL, &ss) < 0 || ss.ss_flags != SS_DISABLE) {
+        return;
+    }
+    if (&on_stack < g_autodisarm_stack || &on_stack >= g_autodisarm_stack + SIGSTKSZ) {
+        return;
+    }
+    // The alternate signal stack before the signal is saved in the ucontext
+    ucontext_t *ucontext = (ucontext_t *)context;
+    if (ucontext->uc_stack.ss_sp != g_autodisarm_stack ||
+            ucontext->uc_stack.ss_flags != (int)SS_AUTODISARM) {
+        return;
+    }
+    g_autodisarm_ok = 1;
+}
+
+int test_sigaltstack_autodisarm() {
+    stack_t new_ss = {
+        .ss_size = SIGSTKSZ,
+        .ss_sp = g_autodisarm_stack,
+        .ss_flags = SS_AUTODISARM,
+    };
+    stack_t old_ss;
+    if (sigaltstack(&new_ss, &old_ss) < 0) {
+        THROW_ERROR("failed to call sigaltstack");
+    }
+
+    struct sigaction new_action, old_action;
+    memset(&new_action, 0, sizeof(struct sigaction));
+    new_action.sa_sigaction = handle_sigusr1_with_autodisarm;
+    new_action.sa_flags = SA_SIGINFO | SA_ONSTACK;
+    if (sigaction(SIGUSR1, &new_action, &old_action) < 0) {
+        THROW_ERROR("registering new signal handler failed");
+    }
+    raise(SIGUSR1);
+    if (sigaction(SIGUSR1, &old_action, NULL) < 0) {
+        THROW_ERROR("restoring old signal handler failed");
+    }
+    if (!g_autodisarm_ok) {
+        THROW_ERROR("the alternate signal stack is not disarmed in the signal handler");
+    }
+
+    // The alternate signal stack is restored after the signal handler returns
+    stack_t actual_ss;
+    if (sigaltstack(&old_ss, &actual_ss) < 0) {
+        THROW_ERROR("failed to call sigaltstack");
+    }
+    if (actual_ss.ss_sp != g_autodisarm_stack || actual_ss.ss_flags != (int)SS_AUTODISARM) {
+        THROW_ERROR("the alternate signal stack is not restored");
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test SIGCHLD signal
 // ============================================================================
@@ -416,6 +566,135 @@ int test_sigchld() {
     return 0;
 }
 
+#define EXITED_CHILD_STATUS 42
+
+int exited_child() {
+    return EXITED_CHILD_STATUS;
+}
+
+static int spawn_exited_child(pid_t *child_pid) {
+    char *child_argv[] = {"signal", "exited_child", NULL};
+    if (posix_spawn(child_pid, "/bin/signal", NULL, NULL, child_argv, NULL) != 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+    return 0;
+}
+
+static volatile siginfo_t g_sigchld_info;
+static volatile int g_num_sigchld = 0;
+
+static void handle_sigchld_with_info(int num, siginfo_t *info, void *context) {
+    g_sigchld_info = *info;
+    g_num_sigchld++;
+}
+
+int test_sigchld_siginfo() {
+    struct sigaction new_action, old_action;
+    memset(&new_action, 0, sizeof(new_action));
+    new_action.sa_sigaction = handle_sigchld_with_info;
+    new_action.sa_flags = SA_SIGINFO;
+    if (sigaction(SIGCHLD, &new_action, &old_action) < 0) {
+        THROW_ERROR("registering new signal handler failed");
+    }
+    g_num_sigchld = 0;
+
+    pid_t child_pid;
+    if (spawn_exited_child(&child_pid) < 0) {
+        return -1;
+    }
+    // Poll the child with WNOHANG until it exits
+    int status = 0;
+    int ret;
+    while ((ret = waitpid(child_pid, &status, WNOHANG)) == 0) {
+        sched_yield();
+    }
+    if (ret != child_pid) {
+        THROW_ERROR("failed to wait for the child");
+    }
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != EXITED_CHILD_STATUS) {
+        THROW_ERROR("the exit status of the child is wrong");
+    }
+    if (waitpid(child_pid, NULL, WNOHANG) != -1 || errno != ECHILD) {
+        THROW_ERROR("the waited child should have been freed");
+    }
+
+    if (g_num_sigchld != 1) {
+        THROW_ERROR("the SIGCHLD handler is not called");
+    }
+    if (g_sigchld_info.si_signo != SIGCHLD ||
+            g_sigchld_info.si_code != CLD_EXITED ||
+            g_sigchld_info.si_pid != child_pid ||
+            g_sigchld_info.si_status != EXITED_CHILD_STATUS) {
+        THROW_ERROR("the siginfo of SIGCHLD is wrong");
+    }
+
+    if (sigaction(SIGCHLD, &old_action, NULL) < 0) {
+        THROW_ERROR("restoring old signal handler failed");
+    }
+    return 0;
+}
+
+static int check_children_reaped_automatically(void) {
+    pid_t child_pids[3];
+    for (int i = 0; i < ARRAY_SIZE(child_pids); i++) {
+        if (spawn_exited_child(&child_pids[i]) < 0) {
+            return -1;
+        }
+    }
+    // Wait blocks until all children have exited, then fails with ECHILD
+    if (wait(NULL) != -1 || errno != ECHILD) {
+        THROW_ERROR("the children should be reaped automatically");
+    }
+    for (int i = 0; i < ARRAY_SIZE(child_pids); i++) {
+        if (kill(child_pids[i], 0) != -1 || errno != ESRCH) {
+            THROW_ERROR("the child should not become a zombie");
+        }
+    }
+    return 0;
+}
+
+int test_sigchld_ignored() {
+    struct sigaction new_action, old_action;
+    memset(&new_action, 0, sizeof(new_action));
+    new_action.sa_handler = SIG_IGN;
+    if (sigaction(SIGCHLD, &new_action, &old_action) < 0) {
+        THROW_ERROR("registering new signal handler failed");
+    }
+
+    if (check_children_reaped_automatically() < 0) {
+        return -1;
+    }
+
+    if (sigaction(SIGCHLD, &old_action, NULL) < 0) {
+        THROW_ERROR("restoring old signal handler failed");
+    }
+    return 0;
+}
+
+int test_sa_nocldwait() {
+    struct sigaction new_action, old_action;
+    memset(&new_action, 0, sizeof(new_action));
+    new_action.sa_sigaction = handle_sigchld_with_info;
+    new_action.sa_flags = SA_SIGINFO | SA_NOCLDWAIT;
+    if (sigaction(SIGCHLD, &new_action, &old_action) < 0) {
+        THROW_ERROR("registering new signal handler failed");
+    }
+    g_num_sigchld = 0;
+
+    if (check_children_reaped_automatically() < 0) {
+        return -1;
+    }
+    // SIGCHLD is still sent to the parent
+    if (g_num_sigchld == 0) {
+        THROW_ERROR("the SIGCHLD handler is not called");
+    }
+
+    if (sigaction(SIGCHLD, &old_action, NULL) < 0) {
+        THROW_ERROR("restoring old signal handler failed");
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test sigtimedwait syscall
 // ============================================================================
@@ -511,6 +790,232 @@ int test_sigtimedwait() {
     return 0;
 }
 
+int test_sigqueue() {
+    int ret;
+    siginfo_t info;
+    sigset_t new_mask, old_mask;
+    // The real-time signals are queued and delivered from the lowest-numbered
+    // one, and the signals of the same number are delivered in order
+    int signums[] = { SIGRTMIN + 1, SIGRTMIN, SIGRTMIN };
+    int values[] = { 1, 2, 3 };
+    int expected_order[] = { 1, 2, 0 };
+    int num_signals = ARRAY_SIZE(signums);
+
+    sigemptyset(&new_mask);
+    sigaddset(&new_mask, SIGRTMIN);
+    sigaddset(&new_mask, SIGRTMIN + 1);
+    if ((ret = sigprocmask(SIG_BLOCK, &new_mask, &old_mask)) < 0) {
+        THROW_ERROR("sigprocmask failed unexpectedly");
+    }
+
+    // The signal number 0 only checks the existence of the receiver
+    union sigval value = { .sival_int = 0 };
+    if (sigqueue(getpid(), 0, value) < 0) {
+        THROW_ERROR("sigqueue with signal 0 failed unexpectedly");
+    }
+    for (int i = 0; i < num_signals; i++) {
+        value.sival_int = values[i];
+        if (sigqueue(getpid(), signums[i], value) < 0) {
+            THROW_ERROR("sigqueue failed unexpectedly");
+        }
+    }
+    for (int i = 0; i < num_signals; i++) {
+        int expected = expected_order[i];
+        if ((ret = sigtimedwait(&new_mask, &info, NULL)) < 0) {
+            THROW_ERROR("sigtimedwait failed unexpectedly");
+        }
+        if (info.si_signo != signums[expected] || info.si_code != SI_QUEUE ||
+                info.si_pid != getpid() || info.si_value.sival_int != values[expected]) {
+            THROW_ERROR("the queued signal is not as expected");
+        }
+    }
+
+    if ((ret = sigprocmask(SIG_SETMASK, &old_mask, NULL)) < 0) {
+        THROW_ERROR("sigprocmask failed unexpectedly");
+    }
+    return 0;
+}
+
+int test_sigqueue_with_rlimit() {
+    int ret;
+    siginfo_t info;
+    sigset_t new_mask, old_mask;
+    struct rlimit old_rlim, new_rlim;
+
+    sigemptyset(&new_mask);
+    sigaddset(&new_mask, SIGRTMIN);
+    if ((ret = sigprocmask(SIG_BLOCK, &new_mask, &old_mask)) < 0) {
+        THROW_ERROR("sigprocmask failed unexpectedly");
+    }
+    if (getrlimit(RLIMIT_SIGPENDING, &old_rlim) < 0) {
+        THROW_ERROR("getrlimit failed unexpectedly");
+    }
+    new_rlim.rlim_cur = 1;
+    new_rlim.rlim_max = old_rlim.rlim_max;
+    if (setrlimit(RLIMIT_SIGPENDING, &new_rlim) < 0) {
+        THROW_ERROR("setrlimit failed unexpectedly");
+    }
+
+    union sigval value = { .sival_int = 0 };
+    if (sigqueue(getpid(), SIGRTMIN, value) < 0) {
+        THROW_ERROR("sigqueue failed unexpectedly");
+    }
+    ret = sigqueue(getpid(), SIGRTMIN, value);
+    int sigqueue_errno = errno;
+    setrlimit(RLIMIT_SIGPENDING, &old_rlim);
+    if (ret == 0 || sigqueue_errno != EAGAIN) {
+        THROW_ERROR("sigqueue should fail when RLIMIT_SIGPENDING is reached");
+    }
+
+    if ((ret = sigtimedwait(&new_mask, &info, NULL)) < 0 || info.si_signo != SIGRTMIN) {
+        THROW_ERROR("sigtimedwait should return the SIGRTMIN");
+    }
+    if ((ret = sigprocmask(SIG_SETMASK, &old_mask, NULL)) < 0) {
+        THROW_ERROR("sigprocmask failed unexpectedly");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test interrupting blocking syscalls by signals
+// ============================================================================
+
+static volatile int g_num_interrupting_signals = 0;
+
+static void handle_interrupting_signal(int num) {
+    g_num_interrupting_signals++;
+}
+
+struct blocking_read_args {
+    int fd;
+    ssize_t ret;
+    int errno_;
+    volatile int is_started;
+};
+
+static void *blocking_read_thread(void *_args) {
+    struct blocking_read_args *args = _args;
+    char buf[1];
+    args->is_started = 1;
+    args->ret = read(args->fd, buf, sizeof(buf));
+    args->errno_ = errno;
+    return NULL;
+}
+
+// Interrupt a thread blocked in reading a pipe, returning the result of the read
+static int interrupt_blocking_read(int sa_flags, struct blocking_read_args *args) {
+    struct sigaction new_action, old_action;
+    memset(&new_action, 0, sizeof(new_action));
+    new_action.sa_handler = handle_interrupting_signal;
+    new_action.sa_flags = sa_flags;
+    if (sigaction(SIGUSR1, &new_action, &old_action) < 0) {
+        THROW_ERROR("registering new signal handler failed");
+    }
+    g_num_interrupting_signals = 0;
+
+    int pipe_fds[2];
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+    memset(args, 0, sizeof(*args));
+    args->fd = pipe_fds[0];
+    pthread_t thread;
+    if (pthread_create(&thread, NULL, blocking_read_thread, args) < 0) {
+        THROW_ERROR("failed to create a thread");
+    }
+    while (!args->is_started) {
+        sched_yield();
+    }
+    // Give the thread some time to block
+    struct timespec interval = { .tv_sec = 0, .tv_nsec = 100 * 1000 * 1000 };
+    nanosleep(&interval, NULL);
+
+    pthread_kill(thread, SIGUSR1);
+    for (int i = 0; i < 100 && g_num_interrupting_signals == 0; i++) {
+        nanosleep(&interval, NULL);
+    }
+    // Unblock the restarted read, if any
+    if (write(pipe_fds[1], "a", 1) != 1) {
+        THROW_ERROR("failed to write the pipe");
+    }
+    pthread_join(thread, NULL);
+
+    close(pipe_fds[0]);
+    close(pipe_fds[1]);
+    if (sigaction(SIGUSR1, &old_action, NULL) < 0) {
+        THROW_ERROR("restoring old signal handler failed");
+    }
+    if (g_num_interrupting_signals != 1) {
+        THROW_ERROR("the signal handler is not called");
+    }
+    return 0;
+}
+
+int test_interrupt_blocking_read() {
+    struct blocking_read_args args;
+    if (interrupt_blocking_read(0, &args) < 0) {
+        return -1;
+    }
+    if (args.ret != -1 || args.errno_ != EINTR) {
+        THROW_ERROR("the interrupted read should fail with EINTR");
+    }
+    return 0;
+}
+
+int test_restart_blocking_read() {
+    struct blocking_read_args args;
+    if (interrupt_blocking_read(SA_RESTART, &args) < 0) {
+        return -1;
+    }
+    if (args.ret != 1) {
+        THROW_ERROR("the interrupted read should be restarted");
+    }
+    return 0;
+}
+
+int test_interrupt_nanosleep_and_pause() {
+    struct sigaction new_action, old_action;
+    memset(&new_action, 0, sizeof(new_action));
+    new_action.sa_handler = handle_interrupting_signal;
+    // Sleep is never restarted, even with SA_RESTART
+    new_action.sa_flags = SA_RESTART;
+    if (sigaction(SIGALRM, &new_action, &old_action) < 0) {
+        THROW_ERROR("registering new signal handler failed");
+    }
+    g_num_interrupting_signals = 0;
+
+    struct itimerval timer_value = {
+        .it_interval = { .tv_sec = 0, .tv_usec = 0 },
+        .it_value = { .tv_sec = 0, .tv_usec = 100 * 1000 },
+    };
+    if (setitimer(ITIMER_REAL, &timer_value, NULL) < 0) {
+        THROW_ERROR("failed to arm the timer");
+    }
+    struct timespec req = { .tv_sec = 10, .tv_nsec = 0 };
+    struct timespec rem = { .tv_sec = 0, .tv_nsec = 0 };
+    if (nanosleep(&req, &rem) == 0 || errno != EINTR) {
+        THROW_ERROR("nanosleep should be interrupted");
+    }
+    if (g_num_interrupting_signals != 1 || rem.tv_sec == 0 || rem.tv_sec > req.tv_sec) {
+        THROW_ERROR("the remaining time of the interrupted nanosleep is wrong");
+    }
+
+    if (setitimer(ITIMER_REAL, &timer_value, NULL) < 0) {
+        THROW_ERROR("failed to arm the timer");
+    }
+    if (pause() != -1 || errno != EINTR) {
+        THROW_ERROR("pause should be interrupted");
+    }
+    if (g_num_interrupting_signals != 2) {
+        THROW_ERROR("the signal handler is not called");
+    }
+
+    if (sigaction(SIGALRM, &old_action, NULL) < 0) {
+        THROW_ERROR("restoring old signal handler failed");
+    }
+    return 0;
+}
+
 // ============================================================================
 // Test suite main
 // ============================================================================
@@ -522,9 +1027,20 @@ static test_case_t test_cases[] = {
     TEST_CASE(test_kill),
     TEST_CASE(test_handle_sigfpe),
     TEST_CASE(test_handle_sigsegv),
+    TEST_CASE(test_fault_siginfo),
+    TEST_CASE(test_blocked_or_ignored_fault_signal),
     TEST_CASE(test_sigaltstack),
+    TEST_CASE(test_sigaltstack_autodisarm),
     TEST_CASE(test_sigchld),
+    TEST_CASE(test_sigchld_siginfo),
+    TEST_CASE(test_sigchld_ignored),
+    TEST_CASE(test_sa_nocldwait),
     TEST_CASE(test_sigtimedwait),
+    TEST_CASE(test_sigqueue),
+    TEST_CASE(test_sigqueue_with_rlimit),
+    TEST_CASE(test_interrupt_blocking_read),
+    TEST_CASE(test_restart_blocking_read),
+    TEST_CASE(test_interrupt_nanosleep_and_pause),
 };
 
 int main(int argc, const char *argv[]) {
@@ -534,6 +1050,10 @@ int main(int argc, const char *argv[]) {
             return aborted_child();
         } else if (strcmp(cmd, "killed_child") == 0) {
             return killed_child();
+        } else if (strcmp(cmd, "exited_child") == 0) {
+            return exited_child();
+        } else if (strcmp(cmd, "faulting_child") == 0 && argc > 2) {
+            return faulting_child(argv[2]);
         } else {
             fprintf(stderr, "ERROR: unknown command: %s\n", cmd);
             return EXIT_FAILURE;
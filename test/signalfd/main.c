@@ -0,0 +1,185 @@
+#include <sys/epoll.h>
+#include <sys/signalfd.h>
+#include <sys/syscall.h>
+#include <errno.h>
+#include <poll.h>
+#include <signal.h>
+#include <stdio.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+static int block_signal(int signum, sigset_t *mask) {
+    sigemptyset(mask);
+    sigaddset(mask, signum);
+    if (sigprocmask(SIG_BLOCK, mask, NULL) < 0) {
+        THROW_ERROR("failed to block the signal");
+    }
+    return 0;
+}
+
+static int unblock_signal(sigset_t *mask) {
+    if (sigprocmask(SIG_UNBLOCK, mask, NULL) < 0) {
+        THROW_ERROR("failed to unblock the signal");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+int test_read_signal() {
+    sigset_t mask;
+    if (block_signal(SIGUSR1, &mask) < 0) {
+        return -1;
+    }
+
+    int sfd = signalfd(-1, &mask, 0);
+    if (sfd < 0) {
+        THROW_ERROR("failed to create a signalfd");
+    }
+
+    if (kill(getpid(), SIGUSR1) < 0) {
+        close(sfd);
+        THROW_ERROR("failed to send the signal");
+    }
+
+    struct signalfd_siginfo ssi;
+    if (read(sfd, &ssi, sizeof(ssi)) != sizeof(ssi)) {
+        close(sfd);
+        THROW_ERROR("failed to read the signalfd");
+    }
+    if (ssi.ssi_signo != SIGUSR1 || ssi.ssi_pid != getpid()) {
+        close(sfd);
+        THROW_ERROR("the signal info is wrong");
+    }
+
+    close(sfd);
+    return unblock_signal(&mask);
+}
+
+int test_nonblock_read() {
+    sigset_t mask;
+    if (block_signal(SIGUSR2, &mask) < 0) {
+        return -1;
+    }
+
+    int sfd = signalfd(-1, &mask, SFD_NONBLOCK | SFD_CLOEXEC);
+    if (sfd < 0) {
+        THROW_ERROR("failed to create a signalfd");
+    }
+
+    struct signalfd_siginfo ssi;
+    if (read(sfd, &ssi, sizeof(ssi)) != -1 || errno != EAGAIN) {
+        close(sfd);
+        THROW_ERROR("read without pending signals should fail with EAGAIN");
+    }
+    if (read(sfd, &ssi, sizeof(ssi) - 1) != -1 || errno != EINVAL) {
+        close(sfd);
+        THROW_ERROR("read with a small buffer should fail with EINVAL");
+    }
+
+    close(sfd);
+    return unblock_signal(&mask);
+}
+
+int test_modify_mask() {
+    sigset_t mask;
+    if (block_signal(SIGUSR1, &mask) < 0) {
+        return -1;
+    }
+
+    sigset_t empty_mask;
+    sigemptyset(&empty_mask);
+    int sfd = signalfd(-1, &empty_mask, SFD_NONBLOCK);
+    if (sfd < 0) {
+        THROW_ERROR("failed to create a signalfd");
+    }
+    if (signalfd(sfd, &mask, 0) != sfd) {
+        close(sfd);
+        THROW_ERROR("failed to modify the mask of the signalfd");
+    }
+
+    if (kill(getpid(), SIGUSR1) < 0) {
+        close(sfd);
+        THROW_ERROR("failed to send the signal");
+    }
+
+    struct signalfd_siginfo ssi;
+    if (read(sfd, &ssi, sizeof(ssi)) != sizeof(ssi) || ssi.ssi_signo != SIGUSR1) {
+        close(sfd);
+        THROW_ERROR("failed to read the signal with the modified mask");
+    }
+
+    close(sfd);
+    return unblock_signal(&mask);
+}
+
+int test_poll_and_epoll() {
+    sigset_t mask;
+    if (block_signal(SIGUSR1, &mask) < 0) {
+        return -1;
+    }
+
+    int sfd = signalfd(-1, &mask, SFD_NONBLOCK);
+    int ep_fd = epoll_create1(0);
+    if (sfd < 0 || ep_fd < 0) {
+        THROW_ERROR("failed to create files");
+    }
+
+    struct epoll_event event = { .events = EPOLLIN, .data.fd = sfd };
+    if (epoll_ctl(ep_fd, EPOLL_CTL_ADD, sfd, &event) < 0) {
+        close_files(2, sfd, ep_fd);
+        THROW_ERROR("failed to add the signalfd to the epoll file");
+    }
+
+    struct pollfd pollfds[] = { { .fd = sfd, .events = POLLIN, .revents = 0 } };
+    if (poll(pollfds, 1, 0) != 0) {
+        close_files(2, sfd, ep_fd);
+        THROW_ERROR("the signalfd should not be readable");
+    }
+
+    if (kill(getpid(), SIGUSR1) < 0) {
+        close_files(2, sfd, ep_fd);
+        THROW_ERROR("failed to send the signal");
+    }
+
+    if (poll(pollfds, 1, 1000) != 1 || (pollfds[0].revents & POLLIN) == 0) {
+        close_files(2, sfd, ep_fd);
+        THROW_ERROR("the signalfd should be readable");
+    }
+    struct epoll_event ready_event;
+    if (epoll_wait(ep_fd, &ready_event, 1, 1000) != 1 || ready_event.data.fd != sfd) {
+        close_files(2, sfd, ep_fd);
+        THROW_ERROR("the pending signal is not reported by epoll");
+    }
+
+    struct signalfd_siginfo ssi;
+    if (read(sfd, &ssi, sizeof(ssi)) != sizeof(ssi)) {
+        close_files(2, sfd, ep_fd);
+        THROW_ERROR("failed to read the signalfd");
+    }
+
+    close_files(2, sfd, ep_fd);
+    return unblock_signal(&mask);
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_read_signal),
+    TEST_CASE(test_nonblock_read),
+    TEST_CASE(test_modify_mask),
+    TEST_CASE(test_poll_and_epoll),
+};
+
+int main() {
+    int ret = test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+    return ret;
+}
@@ -0,0 +1,147 @@
+#include <sys/socket.h>
+#include <sys/time.h>
+#include <errno.h>
+#include <unistd.h>
+#include <string.h>
+
+#include "test.h"
+
+#define TIMEOUT_MS  200
+
+// ============================================================================
+// Helper function
+// ============================================================================
+static long elapsed_ms(struct timeval *start) {
+    struct timeval end;
+    gettimeofday(&end, NULL);
+    return (end.tv_sec - start->tv_sec) * 1000 + (end.tv_usec - start->tv_usec) / 1000;
+}
+
+static int set_timeout(int sock, int optname) {
+    struct timeval timeout = { .tv_sec = 0, .tv_usec = TIMEOUT_MS * 1000 };
+    if (setsockopt(sock, SOL_SOCKET, optname, &timeout, sizeof(timeout)) < 0) {
+        THROW_ERROR("failed to set the timeout");
+    }
+
+    struct timeval val;
+    socklen_t len = sizeof(val);
+    if (getsockopt(sock, SOL_SOCKET, optname, &val, &len) < 0) {
+        THROW_ERROR("failed to get the timeout");
+    }
+    if (len != sizeof(val) || val.tv_sec != timeout.tv_sec ||
+            val.tv_usec != timeout.tv_usec) {
+        THROW_ERROR("the timeout got is not the one set");
+    }
+    return 0;
+}
+
+static int check_recv_timeout(int sock) {
+    if (set_timeout(sock, SO_RCVTIMEO) < 0) {
+        return -1;
+    }
+
+    char buf[16];
+    struct timeval start;
+    gettimeofday(&start, NULL);
+    if (recv(sock, buf, sizeof(buf), 0) >= 0 || errno != EAGAIN) {
+        THROW_ERROR("recv should fail with EAGAIN after the timeout");
+    }
+    if (elapsed_ms(&start) < TIMEOUT_MS) {
+        THROW_ERROR("recv returned before the timeout");
+    }
+    return 0;
+}
+
+static int check_send_timeout(int sock) {
+    if (set_timeout(sock, SO_SNDTIMEO) < 0) {
+        return -1;
+    }
+
+    // Fill the buffer of the connection
+    char buf[4096];
+    memset(buf, 'a', sizeof(buf));
+    while (send(sock, buf, sizeof(buf), MSG_DONTWAIT) > 0) {
+    }
+    if (errno != EAGAIN) {
+        THROW_ERROR("failed to fill the buffer");
+    }
+
+    struct timeval start;
+    gettimeofday(&start, NULL);
+    if (send(sock, buf, 1, 0) >= 0 || errno != EAGAIN) {
+        THROW_ERROR("send should fail with EAGAIN after the timeout");
+    }
+    if (elapsed_ms(&start) < TIMEOUT_MS) {
+        THROW_ERROR("send returned before the timeout");
+    }
+    return 0;
+}
+
+static int test_recv_timeout_of_socketpair(int type) {
+    int socks[2];
+    if (socketpair(AF_UNIX, type, 0, socks) < 0) {
+        THROW_ERROR("failed to create a unix socket pair");
+    }
+
+    int ret = check_recv_timeout(socks[0]);
+    close(socks[0]);
+    close(socks[1]);
+    return ret;
+}
+
+// ============================================================================
+// Test cases for the timeouts
+// ============================================================================
+static int test_stream_recv_timeout() {
+    return test_recv_timeout_of_socketpair(SOCK_STREAM);
+}
+
+static int test_datagram_recv_timeout() {
+    return test_recv_timeout_of_socketpair(SOCK_DGRAM);
+}
+
+static int test_stream_send_timeout() {
+    int socks[2];
+    if (socketpair(AF_UNIX, SOCK_STREAM, 0, socks) < 0) {
+        THROW_ERROR("failed to create a unix socket pair");
+    }
+
+    int ret = check_send_timeout(socks[0]);
+    close(socks[0]);
+    close(socks[1]);
+    return ret;
+}
+
+static int test_invalid_timeout() {
+    int socks[2];
+    if (socketpair(AF_UNIX, SOCK_STREAM, 0, socks) < 0) {
+        THROW_ERROR("failed to create a unix socket pair");
+    }
+
+    int ret = 0;
+    struct timeval timeout = { .tv_sec = 0, .tv_usec = 1000000 };
+    if (setsockopt(socks[0], SOL_SOCKET, SO_RCVTIMEO, &timeout, sizeof(timeout)) >= 0 ||
+            errno != EDOM) {
+        ret = -1;
+    }
+    close(socks[0]);
+    close(socks[1]);
+    if (ret < 0) {
+        THROW_ERROR("setsockopt should fail with EDOM for an invalid timeout");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+static test_case_t test_cases[] = {
+    TEST_CASE(test_stream_recv_timeout),
+    TEST_CASE(test_datagram_recv_timeout),
+    TEST_CASE(test_stream_send_timeout),
+    TEST_CASE(test_invalid_timeout),
+};
+
+int main() {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
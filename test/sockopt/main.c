@@ -0,0 +1,124 @@
+#include <sys/socket.h>
+#include <netinet/in.h>
+#include <netinet/ip.h>
+#include <netinet/tcp.h>
+#include <errno.h>
+#include <unistd.h>
+#include <string.h>
+
+#include "test.h"
+
+// ============================================================================
+// Helper function
+// ============================================================================
+static int set_and_check_int(int sock, int level, int optname, int val, int expected) {
+    if (setsockopt(sock, level, optname, &val, sizeof(val)) < 0) {
+        THROW_ERROR("failed to set the option");
+    }
+
+    int got = -1;
+    socklen_t len = sizeof(got);
+    if (getsockopt(sock, level, optname, &got, &len) < 0) {
+        THROW_ERROR("failed to get the option");
+    }
+    if (len != sizeof(got) || got != expected) {
+        THROW_ERROR("the option got is not the one set");
+    }
+    return 0;
+}
+
+static int check_invalid_int(int sock, int level, int optname, int val, int expected_errno) {
+    if (setsockopt(sock, level, optname, &val, sizeof(val)) >= 0 ||
+            errno != expected_errno) {
+        THROW_ERROR("setsockopt should fail with the expected errno");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases for the socket options
+// ============================================================================
+static int test_unix_socket_options() {
+    int socks[2];
+    if (socketpair(AF_UNIX, SOCK_STREAM, 0, socks) < 0) {
+        THROW_ERROR("failed to create a unix socket pair");
+    }
+
+    int ret = -1;
+    struct linger linger = { .l_onoff = 1, .l_linger = 5 };
+    struct linger linger_got;
+    socklen_t len = sizeof(linger_got);
+    if (set_and_check_int(socks[0], SOL_SOCKET, SO_KEEPALIVE, 5, 1) < 0 ||
+            set_and_check_int(socks[0], SOL_SOCKET, SO_REUSEADDR, 1, 1) < 0) {
+        goto out;
+    }
+    if (setsockopt(socks[0], SOL_SOCKET, SO_LINGER, &linger, sizeof(linger)) < 0 ||
+            getsockopt(socks[0], SOL_SOCKET, SO_LINGER, &linger_got, &len) < 0 ||
+            len != sizeof(linger_got) || linger_got.l_onoff != 1 || linger_got.l_linger != 5) {
+        printf("\t\tERROR: failed to set and get SO_LINGER\n");
+        goto out;
+    }
+    // The options of TCP do not apply to unix sockets
+    if (check_invalid_int(socks[0], IPPROTO_TCP, TCP_NODELAY, 1, EOPNOTSUPP) < 0) {
+        goto out;
+    }
+    ret = 0;
+out:
+    close(socks[0]);
+    close(socks[1]);
+    return ret;
+}
+
+static int test_tcp_socket_options() {
+    int sock = socket(AF_INET, SOCK_STREAM, 0);
+    if (sock < 0) {
+        THROW_ERROR("failed to create a TCP socket");
+    }
+
+    int ret = -1;
+    if (set_and_check_int(sock, IPPROTO_TCP, TCP_NODELAY, 1, 1) < 0 ||
+            set_and_check_int(sock, IPPROTO_TCP, TCP_KEEPIDLE, 60, 60) < 0 ||
+            set_and_check_int(sock, IPPROTO_TCP, TCP_KEEPINTVL, 10, 10) < 0 ||
+            set_and_check_int(sock, IPPROTO_TCP, TCP_KEEPCNT, 3, 3) < 0 ||
+            set_and_check_int(sock, IPPROTO_IP, IP_TOS, IPTOS_LOWDELAY, IPTOS_LOWDELAY) < 0 ||
+            set_and_check_int(sock, SOL_SOCKET, SO_REUSEPORT, 1, 1) < 0) {
+        goto out;
+    }
+    if (check_invalid_int(sock, IPPROTO_TCP, TCP_KEEPCNT, 0, EINVAL) < 0 ||
+            check_invalid_int(sock, IPPROTO_TCP, TCP_KEEPIDLE, 32768, EINVAL) < 0) {
+        goto out;
+    }
+    ret = 0;
+out:
+    close(sock);
+    return ret;
+}
+
+static int test_short_option_length() {
+    int sock = socket(AF_INET, SOCK_STREAM, 0);
+    if (sock < 0) {
+        THROW_ERROR("failed to create a TCP socket");
+    }
+
+    char val = 1;
+    int ret = setsockopt(sock, IPPROTO_TCP, TCP_NODELAY, &val, sizeof(val));
+    int err = errno;
+    close(sock);
+    if (ret >= 0 || err != EINVAL) {
+        THROW_ERROR("setsockopt should fail with EINVAL for a short option");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+static test_case_t test_cases[] = {
+    TEST_CASE(test_unix_socket_options),
+    TEST_CASE(test_tcp_socket_options),
+    TEST_CASE(test_short_option_length),
+};
+
+int main() {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
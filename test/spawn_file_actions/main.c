@@ -0,0 +1,201 @@
+#define _GNU_SOURCE
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <spawn.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define CHILD_PROG          "/bin/spawn_file_actions"
+#define CHILD_MSG           "Hello from the child"
+#define OUTPUT_FILE         "/root/spawn_file_actions.txt"
+
+// Spawn the child with the file actions and wait for it to succeed
+static int spawn_and_wait(const char *path, posix_spawn_file_actions_t *file_actions,
+                          const char *cmd, const char *arg) {
+    const char *child_argv[4] = { CHILD_PROG, cmd, arg, NULL };
+    int child_pid = 0;
+    if (posix_spawn(&child_pid, path, file_actions, NULL,
+                    (char *const *)child_argv, NULL) != 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+    int status = 0;
+    if (wait4(child_pid, &status, 0, NULL) < 0) {
+        THROW_ERROR("failed to wait4 the child process");
+    }
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        THROW_ERROR("the child process failed");
+    }
+    return 0;
+}
+
+static int read_msg(int fd) {
+    char buf[sizeof(CHILD_MSG)] = { 0 };
+    if (read(fd, buf, sizeof(buf)) != sizeof(buf) || strcmp(buf, CHILD_MSG) != 0) {
+        THROW_ERROR("failed to read the message of the child");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Child processes
+// ============================================================================
+
+static int child_write_fd(const char *fd_str) {
+    int fd = atoi(fd_str);
+    if (write(fd, CHILD_MSG, sizeof(CHILD_MSG)) != sizeof(CHILD_MSG)) {
+        return -1;
+    }
+    return 0;
+}
+
+static int child_check_cwd(const char *expected_cwd) {
+    char cwd[128] = { 0 };
+    if (getcwd(cwd, sizeof(cwd)) == NULL || strcmp(cwd, expected_cwd) != 0) {
+        return -1;
+    }
+    return 0;
+}
+
+static int child_check_closed(const char *fd_str) {
+    int fd = atoi(fd_str);
+    if (fcntl(fd, F_GETFD) >= 0 || errno != EBADF) {
+        return -1;
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_open_action() {
+    const int child_fd = 10;
+    char fd_str[16];
+    snprintf(fd_str, sizeof(fd_str), "%d", child_fd);
+
+    posix_spawn_file_actions_t file_actions;
+    posix_spawn_file_actions_init(&file_actions);
+    posix_spawn_file_actions_addopen(&file_actions, child_fd, OUTPUT_FILE,
+                                     O_WRONLY | O_CREAT | O_TRUNC, 0640);
+    int ret = spawn_and_wait(CHILD_PROG, &file_actions, "write_fd", fd_str);
+    posix_spawn_file_actions_destroy(&file_actions);
+    if (ret < 0) {
+        return -1;
+    }
+
+    int fd = open(OUTPUT_FILE, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("the file is not created by the open action");
+    }
+    ret = read_msg(fd);
+    close(fd);
+    unlink(OUTPUT_FILE);
+    return ret;
+}
+
+static int test_dup2_chain() {
+    int pipe_fds[2];
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+
+    // pipe_fds[1] -> 20 -> 21, then 20 is closed
+    posix_spawn_file_actions_t file_actions;
+    posix_spawn_file_actions_init(&file_actions);
+    posix_spawn_file_actions_adddup2(&file_actions, pipe_fds[1], 20);
+    posix_spawn_file_actions_adddup2(&file_actions, 20, 21);
+    posix_spawn_file_actions_addclose(&file_actions, 20);
+    int ret = spawn_and_wait(CHILD_PROG, &file_actions, "write_fd", "21");
+    if (ret == 0) {
+        ret = spawn_and_wait(CHILD_PROG, &file_actions, "check_closed", "20");
+    }
+    posix_spawn_file_actions_destroy(&file_actions);
+    if (ret == 0) {
+        ret = read_msg(pipe_fds[0]);
+    }
+    close(pipe_fds[0]);
+    close(pipe_fds[1]);
+    return ret;
+}
+
+static int test_dup2_same_fd() {
+    int pipe_fds[2];
+    if (pipe2(pipe_fds, O_CLOEXEC) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+    char fd_str[16];
+    snprintf(fd_str, sizeof(fd_str), "%d", pipe_fds[1]);
+
+    // Dup2 to the same fd clears the close-on-exec flag
+    posix_spawn_file_actions_t file_actions;
+    posix_spawn_file_actions_init(&file_actions);
+    posix_spawn_file_actions_adddup2(&file_actions, pipe_fds[1], pipe_fds[1]);
+    int ret = spawn_and_wait(CHILD_PROG, &file_actions, "write_fd", fd_str);
+    posix_spawn_file_actions_destroy(&file_actions);
+    if (ret == 0) {
+        ret = read_msg(pipe_fds[0]);
+    }
+    close(pipe_fds[0]);
+    close(pipe_fds[1]);
+    return ret;
+}
+
+static int test_chdir_action() {
+    posix_spawn_file_actions_t file_actions;
+    posix_spawn_file_actions_init(&file_actions);
+    posix_spawn_file_actions_addchdir_np(&file_actions, "/bin");
+    // The path of the executable is relative to the new working directory
+    int ret = spawn_and_wait("spawn_file_actions", &file_actions, "check_cwd", "/bin");
+    posix_spawn_file_actions_destroy(&file_actions);
+    return ret;
+}
+
+static int test_fchdir_action() {
+    int dir_fd = open("/bin", O_RDONLY | O_DIRECTORY);
+    if (dir_fd < 0) {
+        THROW_ERROR("failed to open the directory");
+    }
+    posix_spawn_file_actions_t file_actions;
+    posix_spawn_file_actions_init(&file_actions);
+    posix_spawn_file_actions_addfchdir_np(&file_actions, dir_fd);
+    int ret = spawn_and_wait(CHILD_PROG, &file_actions, "check_cwd", "/bin");
+    posix_spawn_file_actions_destroy(&file_actions);
+    close(dir_fd);
+    return ret;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_open_action),
+    TEST_CASE(test_dup2_chain),
+    TEST_CASE(test_dup2_same_fd),
+    TEST_CASE(test_chdir_action),
+    TEST_CASE(test_fchdir_action),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 2) {
+        int ret = -1;
+        if (strcmp(argv[1], "write_fd") == 0) {
+            ret = child_write_fd(argv[2]);
+        } else if (strcmp(argv[1], "check_cwd") == 0) {
+            ret = child_check_cwd(argv[2]);
+        } else if (strcmp(argv[1], "check_closed") == 0) {
+            ret = child_check_closed(argv[2]);
+        }
+        return ret < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,197 @@
+#define _GNU_SOURCE
+#include <sys/uio.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+static const char *in_path = "/root/test_splice_in.txt";
+static const char *out_path = "/root/test_splice_out.txt";
+static const char *msg = "Hello, splice! This message goes through a pipe.";
+
+static int create_in_file() {
+    int fd = open(in_path, O_WRONLY | O_CREAT | O_TRUNC, 00666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create the input file");
+    }
+    if (write(fd, msg, strlen(msg)) != strlen(msg)) {
+        close(fd);
+        THROW_ERROR("failed to write the input file");
+    }
+    close(fd);
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+int test_splice_file_to_file() {
+    if (create_in_file() < 0) {
+        return -1;
+    }
+    int pipe_fds[2];
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+    int in_fd = open(in_path, O_RDONLY);
+    int out_fd = open(out_path, O_WRONLY | O_CREAT | O_TRUNC, 00666);
+    if (in_fd < 0 || out_fd < 0) {
+        THROW_ERROR("failed to open files");
+    }
+
+    size_t len = strlen(msg);
+    loff_t in_offset = 7;
+    if (splice(in_fd, &in_offset, pipe_fds[1], NULL, len, 0) != len - 7 ||
+            in_offset != len) {
+        close_files(4, in_fd, out_fd, pipe_fds[0], pipe_fds[1]);
+        THROW_ERROR("failed to splice from the file to the pipe");
+    }
+    if (lseek(in_fd, 0, SEEK_CUR) != 0) {
+        close_files(4, in_fd, out_fd, pipe_fds[0], pipe_fds[1]);
+        THROW_ERROR("the file offset should not be changed");
+    }
+    if (splice(pipe_fds[0], NULL, out_fd, NULL, len, 0) != len - 7) {
+        close_files(4, in_fd, out_fd, pipe_fds[0], pipe_fds[1]);
+        THROW_ERROR("failed to splice from the pipe to the file");
+    }
+    close_files(4, in_fd, out_fd, pipe_fds[0], pipe_fds[1]);
+
+    char buf[128] = {0};
+    int fd = open(out_path, O_RDONLY);
+    if (fd < 0 || read(fd, buf, sizeof(buf)) != len - 7 || strcmp(buf, msg + 7) != 0) {
+        THROW_ERROR("the content of the output file is wrong");
+    }
+    close(fd);
+
+    if (unlink(in_path) < 0 || unlink(out_path) < 0) {
+        THROW_ERROR("failed to remove files");
+    }
+    return 0;
+}
+
+int test_tee() {
+    int pipe1[2], pipe2[2];
+    if (pipe(pipe1) < 0 || pipe(pipe2) < 0) {
+        THROW_ERROR("failed to create pipes");
+    }
+    size_t len = strlen(msg);
+    if (write(pipe1[1], msg, len) != len) {
+        close_files(4, pipe1[0], pipe1[1], pipe2[0], pipe2[1]);
+        THROW_ERROR("failed to write the pipe");
+    }
+
+    if (tee(pipe1[0], pipe2[1], len, 0) != len) {
+        close_files(4, pipe1[0], pipe1[1], pipe2[0], pipe2[1]);
+        THROW_ERROR("failed to tee");
+    }
+
+    // The data is duplicated, so both pipes have a copy of the data
+    char buf1[128] = {0};
+    char buf2[128] = {0};
+    if (read(pipe1[0], buf1, sizeof(buf1)) != len || strcmp(buf1, msg) != 0 ||
+            read(pipe2[0], buf2, sizeof(buf2)) != len || strcmp(buf2, msg) != 0) {
+        close_files(4, pipe1[0], pipe1[1], pipe2[0], pipe2[1]);
+        THROW_ERROR("the data is not duplicated correctly");
+    }
+
+    if (tee(pipe1[0], pipe1[1], len, 0) >= 0 || errno != EINVAL) {
+        close_files(4, pipe1[0], pipe1[1], pipe2[0], pipe2[1]);
+        THROW_ERROR("tee on the same pipe should fail");
+    }
+    if (tee(pipe1[0], pipe2[1], len, SPLICE_F_NONBLOCK) >= 0 || errno != EAGAIN) {
+        close_files(4, pipe1[0], pipe1[1], pipe2[0], pipe2[1]);
+        THROW_ERROR("non-blocking tee on an empty pipe should fail with EAGAIN");
+    }
+
+    close_files(4, pipe1[0], pipe1[1], pipe2[0], pipe2[1]);
+    return 0;
+}
+
+int test_vmsplice() {
+    int pipe_fds[2];
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+
+    char part1[] = "Hello, ";
+    char part2[] = "vmsplice!";
+    struct iovec iov[2] = {
+        { .iov_base = part1, .iov_len = strlen(part1) },
+        { .iov_base = part2, .iov_len = strlen(part2) },
+    };
+    size_t len = strlen(part1) + strlen(part2);
+    if (vmsplice(pipe_fds[1], iov, 2, 0) != len) {
+        close_files(2, pipe_fds[0], pipe_fds[1]);
+        THROW_ERROR("failed to vmsplice to the pipe");
+    }
+
+    char buf[32] = {0};
+    struct iovec read_iov = { .iov_base = buf, .iov_len = sizeof(buf) };
+    if (vmsplice(pipe_fds[0], &read_iov, 1, 0) != len ||
+            strcmp(buf, "Hello, vmsplice!") != 0) {
+        close_files(2, pipe_fds[0], pipe_fds[1]);
+        THROW_ERROR("failed to vmsplice from the pipe");
+    }
+
+    close_files(2, pipe_fds[0], pipe_fds[1]);
+    return 0;
+}
+
+int test_splice_with_invalid_args() {
+    if (create_in_file() < 0) {
+        return -1;
+    }
+    int pipe_fds[2];
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+    int in_fd = open(in_path, O_RDONLY);
+    int out_fd = open(out_path, O_WRONLY | O_CREAT | O_TRUNC, 00666);
+    if (in_fd < 0 || out_fd < 0) {
+        THROW_ERROR("failed to open files");
+    }
+
+    if (splice(in_fd, NULL, out_fd, NULL, 1, 0) >= 0 || errno != EINVAL) {
+        close_files(4, in_fd, out_fd, pipe_fds[0], pipe_fds[1]);
+        THROW_ERROR("splice without a pipe should fail");
+    }
+    loff_t offset = 0;
+    if (splice(pipe_fds[0], &offset, out_fd, NULL, 1, 0) >= 0 || errno != ESPIPE) {
+        close_files(4, in_fd, out_fd, pipe_fds[0], pipe_fds[1]);
+        THROW_ERROR("splice with an offset for a pipe should fail");
+    }
+    if (splice(pipe_fds[0], NULL, out_fd, NULL, 1, SPLICE_F_NONBLOCK) >= 0 ||
+            errno != EAGAIN) {
+        close_files(4, in_fd, out_fd, pipe_fds[0], pipe_fds[1]);
+        THROW_ERROR("non-blocking splice from an empty pipe should fail with EAGAIN");
+    }
+
+    close_files(4, in_fd, out_fd, pipe_fds[0], pipe_fds[1]);
+    if (unlink(in_path) < 0 || unlink(out_path) < 0) {
+        THROW_ERROR("failed to remove files");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_splice_file_to_file),
+    TEST_CASE(test_tee),
+    TEST_CASE(test_vmsplice),
+    TEST_CASE(test_splice_with_invalid_args),
+};
+
+int main() {
+    int ret = test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+    return ret;
+}
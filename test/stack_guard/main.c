@@ -0,0 +1,146 @@
+#include <sys/wait.h>
+#include <pthread.h>
+#include <signal.h>
+#include <spawn.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define CHILD_PROG          "/bin/stack_guard"
+#define THREAD_STACK_SIZE   (64 * 1024)
+
+// Recurse until the stack overflows
+static int recurse(volatile char *prev_frame) {
+    volatile char frame[1024];
+    frame[0] = prev_frame != NULL ? prev_frame[0] + 1 : 0;
+    return recurse(frame) + frame[sizeof(frame) - 1];
+}
+
+static int spawn_child_and_wait(const char *cmd, int *status) {
+    int child_pid;
+    const char *child_argv[3] = { CHILD_PROG, cmd, NULL };
+    if (posix_spawn(&child_pid, CHILD_PROG, NULL, NULL,
+                    (char *const *)child_argv, NULL) != 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+    if (waitpid(child_pid, status, 0) < 0) {
+        THROW_ERROR("failed to wait for the child process");
+    }
+    return 0;
+}
+
+static int check_killed_by_sigsegv(const char *cmd) {
+    int status;
+    if (spawn_child_and_wait(cmd, &status) < 0) {
+        THROW_ERROR("failed to run the child process");
+    }
+    if (!WIFSIGNALED(status) || WTERMSIG(status) != SIGSEGV) {
+        THROW_ERROR("the child process is not killed by SIGSEGV");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Child processes
+// ============================================================================
+
+static int child_overflow_main_stack() {
+    recurse(NULL);
+    return -1;
+}
+
+static void *overflow_thread_func(void *arg) {
+    recurse(NULL);
+    return NULL;
+}
+
+static int child_overflow_thread_stack() {
+    pthread_attr_t attr;
+    pthread_t thread;
+    pthread_attr_init(&attr);
+    pthread_attr_setstacksize(&attr, THREAD_STACK_SIZE);
+    if (pthread_create(&thread, &attr, overflow_thread_func, NULL) != 0) {
+        return -1;
+    }
+    pthread_join(thread, NULL);
+    return -1;
+}
+
+static void handle_sigsegv(int num, siginfo_t *info, void *context) {
+    // The handler can only run on the alternate stack as the stack overflows
+    _exit(info->si_addr != NULL ? EXIT_SUCCESS : EXIT_FAILURE);
+}
+
+static int child_overflow_with_handler() {
+    static char alt_stack[SIGSTKSZ];
+    stack_t ss = {
+        .ss_sp = alt_stack,
+        .ss_size = sizeof(alt_stack),
+        .ss_flags = 0,
+    };
+    if (sigaltstack(&ss, NULL) < 0) {
+        return -1;
+    }
+    struct sigaction sa;
+    memset(&sa, 0, sizeof(sa));
+    sa.sa_sigaction = handle_sigsegv;
+    sa.sa_flags = SA_SIGINFO | SA_ONSTACK;
+    if (sigaction(SIGSEGV, &sa, NULL) < 0) {
+        return -1;
+    }
+    recurse(NULL);
+    return -1;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_overflow_main_stack() {
+    return check_killed_by_sigsegv("overflow_main_stack");
+}
+
+static int test_overflow_thread_stack() {
+    return check_killed_by_sigsegv("overflow_thread_stack");
+}
+
+static int test_overflow_with_handler_on_alt_stack() {
+    int status;
+    if (spawn_child_and_wait("overflow_with_handler", &status) < 0) {
+        THROW_ERROR("failed to run the child process");
+    }
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != EXIT_SUCCESS) {
+        THROW_ERROR("the stack overflow is not handled by the signal handler");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_overflow_main_stack),
+    TEST_CASE(test_overflow_thread_stack),
+    TEST_CASE(test_overflow_with_handler_on_alt_stack),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 1) {
+        int ret = -1;
+        if (strcmp(argv[1], "overflow_main_stack") == 0) {
+            ret = child_overflow_main_stack();
+        } else if (strcmp(argv[1], "overflow_thread_stack") == 0) {
+            ret = child_overflow_thread_stack();
+        } else if (strcmp(argv[1], "overflow_with_handler") == 0) {
+            ret = child_overflow_with_handler();
+        }
+        return ret < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
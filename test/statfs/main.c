@@ -0,0 +1,138 @@
+#include <sys/statfs.h>
+#include <sys/eventfd.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <string.h>
+#include <unistd.h>
+#include "test_fs.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define SEFS_MAGIC          0x53454653
+#define UNIONFS_MAGIC       0x554e494f
+#define TMPFS_MAGIC         0x01021994
+#define PROC_SUPER_MAGIC    0x9fa0
+
+static int check_statfs(const struct statfs *statfs_buf, long expected_type) {
+    if (statfs_buf->f_type != expected_type) {
+        THROW_ERROR("the type of the file system is not as expected");
+    }
+    if (statfs_buf->f_bsize <= 0 || statfs_buf->f_namelen != 255) {
+        THROW_ERROR("the block size or the name length is invalid");
+    }
+    if (statfs_buf->f_bfree > statfs_buf->f_blocks ||
+            statfs_buf->f_bavail > statfs_buf->f_blocks ||
+            statfs_buf->f_ffree > statfs_buf->f_files) {
+        THROW_ERROR("the free blocks or inodes are more than the total");
+    }
+    return 0;
+}
+
+static int check_disk_statfs(const char *path, long expected_type) {
+    struct statfs statfs_buf;
+    if (statfs(path, &statfs_buf) < 0) {
+        THROW_ERROR("failed to statfs");
+    }
+    if (check_statfs(&statfs_buf, expected_type) < 0) {
+        return -1;
+    }
+    if (statfs_buf.f_blocks == 0 || statfs_buf.f_files == 0) {
+        THROW_ERROR("the file system should report its real size");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_statfs_sefs() {
+    if (check_disk_statfs("/root", UNIONFS_MAGIC) < 0) {
+        return -1;
+    }
+    return check_disk_statfs("/tmp/../tmp/.", SEFS_MAGIC);
+}
+
+static int test_statfs_hostfs() {
+    struct statfs statfs_buf;
+    if (statfs("/host", &statfs_buf) < 0) {
+        THROW_ERROR("failed to statfs");
+    }
+    // The type of the file system is reported by the host
+    if (statfs_buf.f_blocks == 0 || statfs_buf.f_bfree > statfs_buf.f_blocks) {
+        THROW_ERROR("the host file system should report its real size");
+    }
+    return 0;
+}
+
+static int test_statfs_pseudo_fs() {
+    struct statfs statfs_buf;
+    if (statfs("/proc/self", &statfs_buf) < 0) {
+        THROW_ERROR("failed to statfs");
+    }
+    if (check_statfs(&statfs_buf, PROC_SUPER_MAGIC) < 0) {
+        return -1;
+    }
+    if (statfs("/dev/null", &statfs_buf) < 0) {
+        THROW_ERROR("failed to statfs");
+    }
+    return check_statfs(&statfs_buf, TMPFS_MAGIC);
+}
+
+static int test_fstatfs() {
+    const char *file_path = "/root/test_fstatfs.txt";
+    int fd = open(file_path, O_RDWR | O_CREAT | O_TRUNC, 0666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a file");
+    }
+    struct statfs statfs_buf, fstatfs_buf;
+    if (statfs(file_path, &statfs_buf) < 0 || fstatfs(fd, &fstatfs_buf) < 0) {
+        THROW_ERROR("failed to statfs");
+    }
+    if (statfs_buf.f_type != fstatfs_buf.f_type ||
+            statfs_buf.f_blocks != fstatfs_buf.f_blocks) {
+        THROW_ERROR("fstatfs should report the same file system as statfs");
+    }
+    close(fd);
+    if (unlink(file_path) < 0) {
+        THROW_ERROR("failed to unlink the file");
+    }
+    return 0;
+}
+
+static int test_statfs_with_invalid_args() {
+    struct statfs statfs_buf;
+    if (statfs("/root/nonexistent", &statfs_buf) == 0 || errno != ENOENT) {
+        THROW_ERROR("statfs on a nonexistent file should fail");
+    }
+    if (fstatfs(-1, &statfs_buf) == 0 || errno != EBADF) {
+        THROW_ERROR("fstatfs on an invalid fd should fail");
+    }
+    int fd = eventfd(0, 0);
+    if (fd < 0) {
+        THROW_ERROR("failed to create an eventfd");
+    }
+    if (fstatfs(fd, &statfs_buf) < 0) {
+        THROW_ERROR("fstatfs on an eventfd should succeed");
+    }
+    close(fd);
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_statfs_sefs),
+    TEST_CASE(test_statfs_hostfs),
+    TEST_CASE(test_statfs_pseudo_fs),
+    TEST_CASE(test_fstatfs),
+    TEST_CASE(test_statfs_with_invalid_args),
+};
+
+int main(int argc, const char *argv[]) {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
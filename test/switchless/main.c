@@ -0,0 +1,200 @@
+#define _GNU_SOURCE
+#include <sys/socket.h>
+#include <sys/time.h>
+#include <netinet/in.h>
+#include <arpa/inet.h>
+#include <errno.h>
+#include <stdio.h>
+#include <time.h>
+#include <unistd.h>
+#include <string.h>
+
+#include "test.h"
+
+// The messages of at most this length are sent with the switchless OCall,
+// which is enabled by "switchless_threads" of test/Occlum.json
+#define SWITCHLESS_SEND_MAX_LEN     4096
+
+#define MAX_MSG_LEN                 (SWITCHLESS_SEND_MAX_LEN * 2)
+#define NR_LATENCY_ROUNDS           10000
+
+static int sender;
+static int receiver;
+static struct sockaddr_in receiver_addr;
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+static int create_udp_socket(struct sockaddr_in *addr) {
+    int sock = socket(AF_INET, SOCK_DGRAM, 0);
+    if (sock < 0) {
+        THROW_ERROR("failed to create a UDP socket");
+    }
+
+    socklen_t addr_len = sizeof(*addr);
+    memset(addr, 0, sizeof(*addr));
+    addr->sin_family = AF_INET;
+    addr->sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    if (bind(sock, (struct sockaddr *)addr, sizeof(*addr)) < 0 ||
+            getsockname(sock, (struct sockaddr *)addr, &addr_len) < 0) {
+        close(sock);
+        THROW_ERROR("failed to bind the socket");
+    }
+    return sock;
+}
+
+static int setup_sockets(void) {
+    struct sockaddr_in sender_addr;
+    sender = create_udp_socket(&sender_addr);
+    if (sender < 0) {
+        return -1;
+    }
+    receiver = create_udp_socket(&receiver_addr);
+    if (receiver < 0) {
+        close(sender);
+        return -1;
+    }
+    return 0;
+}
+
+static void fill_msg(char *buf, size_t len, int seed) {
+    for (size_t i = 0; i < len; i++) {
+        buf[i] = (char)(i * 31 + seed);
+    }
+}
+
+// Send a message of the length with sendmsg, whose data is split into
+// nr_iovs iovs, and check that it is received intact
+static int send_and_check(size_t len, int nr_iovs) {
+    static char send_buf[MAX_MSG_LEN];
+    static char recv_buf[MAX_MSG_LEN + 1];
+    fill_msg(send_buf, len, (int)len + nr_iovs);
+
+    struct iovec iovs[4];
+    size_t offset = 0;
+    for (int i = 0; i < nr_iovs; i++) {
+        size_t iov_len = i == nr_iovs - 1 ? len - offset : len / nr_iovs;
+        iovs[i].iov_base = send_buf + offset;
+        iovs[i].iov_len = iov_len;
+        offset += iov_len;
+    }
+    struct msghdr msg = {
+        .msg_name = &receiver_addr,
+        .msg_namelen = sizeof(receiver_addr),
+        .msg_iov = iovs,
+        .msg_iovlen = nr_iovs,
+    };
+    ssize_t ret = sendmsg(sender, &msg, 0);
+    if (ret != (ssize_t)len) {
+        THROW_ERROR("failed to send a message of %zu bytes in %d iovs", len, nr_iovs);
+    }
+
+    ret = recv(receiver, recv_buf, sizeof(recv_buf), 0);
+    if (ret != (ssize_t)len || memcmp(send_buf, recv_buf, len) != 0) {
+        THROW_ERROR("the message of %zu bytes in %d iovs is corrupted", len, nr_iovs);
+    }
+    return 0;
+}
+
+static double elapsed_us(struct timespec *start, struct timespec *end) {
+    return (end->tv_sec - start->tv_sec) * 1e6 + (end->tv_nsec - start->tv_nsec) / 1e3;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+int test_sendmsg_around_switchless_boundary() {
+    // The lengths right below, at and right above the max length of the
+    // switchless sends, i.e., via the switchless and the regular OCalls
+    const size_t lens[] = {
+        1,
+        SWITCHLESS_SEND_MAX_LEN - 1,
+        SWITCHLESS_SEND_MAX_LEN,
+        SWITCHLESS_SEND_MAX_LEN + 1,
+        MAX_MSG_LEN,
+    };
+    for (int i = 0; i < ARRAY_SIZE(lens); i++) {
+        for (int nr_iovs = 1; nr_iovs <= 4; nr_iovs++) {
+            if (send_and_check(lens[i], nr_iovs) < 0) {
+                return -1;
+            }
+        }
+    }
+    return 0;
+}
+
+int test_sendmsg_empty() {
+    struct iovec iov = { .iov_base = NULL, .iov_len = 0 };
+    struct msghdr msg = {
+        .msg_name = &receiver_addr,
+        .msg_namelen = sizeof(receiver_addr),
+        .msg_iov = &iov,
+        .msg_iovlen = 1,
+    };
+    if (sendmsg(sender, &msg, 0) != 0) {
+        THROW_ERROR("failed to send an empty message");
+    }
+    char buf[1];
+    if (recv(receiver, buf, sizeof(buf), 0) != 0) {
+        THROW_ERROR("failed to receive the empty message");
+    }
+    return 0;
+}
+
+int test_clock_gettime() {
+    struct timespec prev, now;
+    if (clock_gettime(CLOCK_MONOTONIC, &prev) < 0) {
+        THROW_ERROR("failed to get the time");
+    }
+    for (int i = 0; i < NR_LATENCY_ROUNDS; i++) {
+        if (clock_gettime(CLOCK_MONOTONIC, &now) < 0) {
+            THROW_ERROR("failed to get the time");
+        }
+        if (elapsed_us(&prev, &now) < 0) {
+            THROW_ERROR("the monotonic clock goes backwards");
+        }
+        prev = now;
+    }
+    return 0;
+}
+
+// Report the latencies of the sends via the switchless and the regular
+// OCalls, which are not checked as they depend on the platform
+int test_sendmsg_latency() {
+    const size_t lens[] = { SWITCHLESS_SEND_MAX_LEN, SWITCHLESS_SEND_MAX_LEN + 1 };
+    for (int i = 0; i < ARRAY_SIZE(lens); i++) {
+        struct timespec start, end;
+        clock_gettime(CLOCK_MONOTONIC, &start);
+        for (int round = 0; round < NR_LATENCY_ROUNDS / 10; round++) {
+            if (send_and_check(lens[i], 1) < 0) {
+                return -1;
+            }
+        }
+        clock_gettime(CLOCK_MONOTONIC, &end);
+        printf("\t\tsendmsg and recv of %zu bytes: %.2f us\n", lens[i],
+               elapsed_us(&start, &end) / (NR_LATENCY_ROUNDS / 10));
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_sendmsg_around_switchless_boundary),
+    TEST_CASE(test_sendmsg_empty),
+    TEST_CASE(test_clock_gettime),
+    TEST_CASE(test_sendmsg_latency),
+};
+
+int main() {
+    if (setup_sockets() < 0) {
+        return -1;
+    }
+    int ret = test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+    close_files(2, sender, receiver);
+    return ret;
+}
@@ -0,0 +1,238 @@
+#define _GNU_SOURCE
+#include <sys/types.h>
+#include <sys/stat.h>
+#include <sys/vfs.h>
+#include <dirent.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <sched.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include "test_fs.h"
+
+// ============================================================================
+// Helper function
+// ============================================================================
+
+#define CPU_DIR         "/sys/devices/system/cpu"
+#define SYSFS_MAGIC     0x62656572
+
+static int read_sysfs_file(const char *path, char *buf, size_t buf_size) {
+    int fd = open(path, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open %s", path);
+    }
+    memset(buf, 0, buf_size);
+    ssize_t len = read(fd, buf, buf_size - 1);
+    close(fd);
+    if (len <= 0) {
+        THROW_ERROR("failed to read %s", path);
+    }
+    return 0;
+}
+
+static int check_sysfs_file(const char *path, const char *expected) {
+    char buf[128];
+    if (read_sysfs_file(path, buf, sizeof(buf)) < 0) {
+        return -1;
+    }
+    if (strcmp(buf, expected) != 0) {
+        THROW_ERROR("the content of %s is not as expected", path);
+    }
+    return 0;
+}
+
+// The CPUs available to the enclave, which are limited by the cpu_set in
+// Occlum.json
+static int get_available_cpus(cpu_set_t *cpus) {
+    CPU_ZERO(cpus);
+    if (sched_getaffinity(0, sizeof(*cpus), cpus) < 0) {
+        THROW_ERROR("failed to get the CPU affinity");
+    }
+    if (CPU_COUNT(cpus) <= 0) {
+        THROW_ERROR("no CPU is available");
+    }
+    return 0;
+}
+
+// The list format of the CPUs, e.g., "0-3,6"
+static int get_all_cpus_list(char *buf, size_t buf_size) {
+    cpu_set_t cpus;
+    if (get_available_cpus(&cpus) < 0) {
+        return -1;
+    }
+    size_t len = 0;
+    buf[0] = '\0';
+    for (int cpu = 0; cpu < CPU_SETSIZE; cpu++) {
+        if (!CPU_ISSET(cpu, &cpus) || (cpu > 0 && CPU_ISSET(cpu - 1, &cpus))) {
+            continue;
+        }
+        int last = cpu;
+        while (last + 1 < CPU_SETSIZE && CPU_ISSET(last + 1, &cpus)) {
+            last++;
+        }
+        const char *sep = len > 0 ? "," : "";
+        if (last == cpu) {
+            len += snprintf(buf + len, buf_size - len, "%s%d", sep, cpu);
+        } else {
+            len += snprintf(buf + len, buf_size - len, "%s%d-%d", sep, cpu, last);
+        }
+        if (len >= buf_size) {
+            THROW_ERROR("the buffer is too small");
+        }
+    }
+    snprintf(buf + len, buf_size - len, "\n");
+    return 0;
+}
+
+// ============================================================================
+// Test cases for sysfs
+// ============================================================================
+
+static int test_read_cpu_online() {
+    char expected[256];
+    if (get_all_cpus_list(expected, sizeof(expected)) < 0) {
+        return -1;
+    }
+    if (check_sysfs_file(CPU_DIR "/online", expected) < 0 ||
+            check_sysfs_file(CPU_DIR "/possible", expected) < 0 ||
+            check_sysfs_file(CPU_DIR "/present", expected) < 0) {
+        return -1;
+    }
+    return 0;
+}
+
+static int test_read_cpu_topology() {
+    cpu_set_t cpus;
+    char all_cpus_list[256];
+    if (get_available_cpus(&cpus) < 0 ||
+            get_all_cpus_list(all_cpus_list, sizeof(all_cpus_list)) < 0) {
+        return -1;
+    }
+    for (int cpu = 0; cpu < CPU_SETSIZE; cpu++) {
+        char path[PATH_MAX];
+        char expected[32];
+
+        // No CPUs other than the ones the enclave is able to use
+        if (!CPU_ISSET(cpu, &cpus)) {
+            struct stat stat_buf;
+            snprintf(path, sizeof(path), CPU_DIR "/cpu%d", cpu);
+            if (stat(path, &stat_buf) == 0 || errno != ENOENT) {
+                THROW_ERROR("the CPU not available should not exist");
+            }
+            continue;
+        }
+
+        snprintf(path, sizeof(path), CPU_DIR "/cpu%d/topology/core_id", cpu);
+        snprintf(expected, sizeof(expected), "%d\n", cpu);
+        if (check_sysfs_file(path, expected) < 0) {
+            return -1;
+        }
+        snprintf(path, sizeof(path), CPU_DIR "/cpu%d/topology/thread_siblings_list", cpu);
+        if (check_sysfs_file(path, expected) < 0) {
+            return -1;
+        }
+        snprintf(path, sizeof(path), CPU_DIR "/cpu%d/topology/physical_package_id", cpu);
+        if (check_sysfs_file(path, "0\n") < 0) {
+            return -1;
+        }
+        snprintf(path, sizeof(path), CPU_DIR "/cpu%d/topology/core_siblings_list", cpu);
+        if (check_sysfs_file(path, all_cpus_list) < 0) {
+            return -1;
+        }
+    }
+    return 0;
+}
+
+static int test_read_cpu_cache() {
+    cpu_set_t cpus;
+    if (get_available_cpus(&cpus) < 0) {
+        return -1;
+    }
+    int cpu = 0;
+    while (!CPU_ISSET(cpu, &cpus)) {
+        cpu++;
+    }
+    char cache_dir[PATH_MAX];
+    snprintf(cache_dir, sizeof(cache_dir), CPU_DIR "/cpu%d/cache", cpu);
+    DIR *dir = opendir(cache_dir);
+    if (dir == NULL) {
+        THROW_ERROR("failed to open the cache dir");
+    }
+    int nindexes = 0;
+    struct dirent *entry;
+    while ((entry = readdir(dir)) != NULL) {
+        if (strncmp(entry->d_name, "index", 5) != 0) {
+            continue;
+        }
+        char path[PATH_MAX];
+        char buf[128];
+        snprintf(path, sizeof(path), "%s/%s/level", cache_dir, entry->d_name);
+        if (read_sysfs_file(path, buf, sizeof(buf)) < 0) {
+            closedir(dir);
+            return -1;
+        }
+        int level = atoi(buf);
+        if (level < 1 || level > 4) {
+            closedir(dir);
+            THROW_ERROR("the cache level is invalid");
+        }
+        snprintf(path, sizeof(path), "%s/%s/size", cache_dir, entry->d_name);
+        if (read_sysfs_file(path, buf, sizeof(buf)) < 0) {
+            closedir(dir);
+            return -1;
+        }
+        if (atoi(buf) <= 0 || strchr(buf, 'K') == NULL) {
+            closedir(dir);
+            THROW_ERROR("the cache size is invalid");
+        }
+        nindexes++;
+    }
+    closedir(dir);
+    if (nindexes == 0) {
+        THROW_ERROR("no cache is found");
+    }
+    return 0;
+}
+
+static int test_statfs_sysfs() {
+    struct statfs statfs_buf;
+    if (statfs(CPU_DIR, &statfs_buf) < 0) {
+        THROW_ERROR("failed to statfs sysfs");
+    }
+    if (statfs_buf.f_type != SYSFS_MAGIC) {
+        THROW_ERROR("the type of sysfs is wrong");
+    }
+    return 0;
+}
+
+static int test_write_sysfs() {
+    int fd = open(CPU_DIR "/online", O_WRONLY);
+    if (fd < 0) {
+        return 0;
+    }
+    ssize_t len = write(fd, "0", 1);
+    close(fd);
+    if (len >= 0) {
+        THROW_ERROR("sysfs should be read-only");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_read_cpu_online),
+    TEST_CASE(test_read_cpu_topology),
+    TEST_CASE(test_read_cpu_cache),
+    TEST_CASE(test_statfs_sysfs),
+    TEST_CASE(test_write_sysfs),
+};
+
+int main(int argc, const char *argv[]) {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,224 @@
+#define _GNU_SOURCE
+#include <sys/ipc.h>
+#include <sys/msg.h>
+#include <sys/wait.h>
+#include <errno.h>
+#include <spawn.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define MSG_KEY         0x4d53
+#define MSG_SIZE        64
+#define CHILD_PROG      "/bin/sysv_msg"
+
+struct test_msgbuf {
+    long mtype;
+    char mtext[MSG_SIZE];
+};
+
+static int send_msg(int msqid, long mtype, const char *text, int flags) {
+    struct test_msgbuf msg = { .mtype = mtype };
+    strncpy(msg.mtext, text, MSG_SIZE - 1);
+    return msgsnd(msqid, &msg, strlen(text) + 1, flags);
+}
+
+// Receive a message and check its type and text
+static int recv_msg(int msqid, long msgtyp, int flags, long expected_mtype,
+                    const char *expected_text) {
+    struct test_msgbuf msg = { 0 };
+    ssize_t len = msgrcv(msqid, &msg, MSG_SIZE, msgtyp, flags);
+    if (len != strlen(expected_text) + 1) {
+        THROW_ERROR("the length of the message is wrong");
+    }
+    if (msg.mtype != expected_mtype || strcmp(msg.mtext, expected_text) != 0) {
+        THROW_ERROR("the message is wrong");
+    }
+    return 0;
+}
+
+static int spawn_child(const char *test_name, int msqid, int *child_pid) {
+    char msqid_buf[16];
+    snprintf(msqid_buf, sizeof(msqid_buf), "%d", msqid);
+    const char *child_argv[4] = { CHILD_PROG, test_name, msqid_buf, NULL };
+    if (posix_spawn(child_pid, CHILD_PROG, NULL, NULL,
+                    (char *const *)child_argv, NULL) != 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+    return 0;
+}
+
+static int wait_child(int child_pid) {
+    int status = 0;
+    if (wait4(child_pid, &status, 0, NULL) < 0) {
+        THROW_ERROR("failed to wait4 the child process");
+    }
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        THROW_ERROR("the child process failed");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Child processes
+// ============================================================================
+
+// The parent is blocked until the child sends a message of the type 7
+static int child_test_wake_up_blocked_msgrcv(int msqid) {
+    usleep(100 * 1000);
+    if (send_msg(msqid, 3, "ignored", 0) < 0 || send_msg(msqid, 7, "wanted", 0) < 0) {
+        THROW_ERROR("failed to send the messages");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_msgget() {
+    int msqid = msgget(MSG_KEY, IPC_CREAT | IPC_EXCL | 0600);
+    if (msqid < 0) {
+        THROW_ERROR("failed to create the queue");
+    }
+    if (msgget(MSG_KEY, IPC_CREAT | IPC_EXCL | 0600) != -1 || errno != EEXIST) {
+        THROW_ERROR("the queue is created twice");
+    }
+    if (msgget(MSG_KEY, 0) != msqid) {
+        THROW_ERROR("failed to get the queue by the key");
+    }
+    if (msgctl(msqid, IPC_RMID, NULL) < 0) {
+        THROW_ERROR("failed to remove the queue");
+    }
+    if (msgget(MSG_KEY, 0) != -1 || errno != ENOENT) {
+        THROW_ERROR("the queue is not removed");
+    }
+    return 0;
+}
+
+static int test_receive_by_type() {
+    int msqid = msgget(IPC_PRIVATE, 0600);
+    if (msqid < 0) {
+        THROW_ERROR("failed to create the queue");
+    }
+    if (send_msg(msqid, 3, "three", 0) < 0 || send_msg(msqid, 1, "one", 0) < 0 ||
+            send_msg(msqid, 2, "two", 0) < 0 || send_msg(msqid, 3, "three again", 0) < 0 ||
+            send_msg(msqid, 5, "five", 0) < 0) {
+        THROW_ERROR("failed to send the messages");
+    }
+
+    // The first message of the type
+    if (recv_msg(msqid, 3, 0, 3, "three") < 0) {
+        THROW_ERROR("failed to receive the message of the type");
+    }
+    // The first message of any other type
+    if (recv_msg(msqid, 1, MSG_EXCEPT, 2, "two") < 0) {
+        THROW_ERROR("failed to receive the message of another type");
+    }
+    // The message of the lowest type not larger than 4
+    if (recv_msg(msqid, -4, 0, 1, "one") < 0) {
+        THROW_ERROR("failed to receive the message of the lowest type");
+    }
+    // The first message
+    if (recv_msg(msqid, 0, 0, 3, "three again") < 0) {
+        THROW_ERROR("failed to receive the first message");
+    }
+    struct test_msgbuf msg;
+    if (msgrcv(msqid, &msg, MSG_SIZE, 4, IPC_NOWAIT) != -1 || errno != ENOMSG) {
+        THROW_ERROR("receiving a message of a missing type does not fail with ENOMSG");
+    }
+    if (msgrcv(msqid, &msg, 2, 5, 0) != -1 || errno != E2BIG) {
+        THROW_ERROR("receiving a message into a small buffer does not fail with E2BIG");
+    }
+    if (msgrcv(msqid, &msg, 2, 5, MSG_NOERROR) != 2 || strncmp(msg.mtext, "fi", 2) != 0) {
+        THROW_ERROR("the message is not truncated with MSG_NOERROR");
+    }
+
+    msgctl(msqid, IPC_RMID, NULL);
+    return 0;
+}
+
+static int test_msgctl() {
+    int msqid = msgget(IPC_PRIVATE, 0600);
+    if (msqid < 0) {
+        THROW_ERROR("failed to create the queue");
+    }
+    if (send_msg(msqid, 1, "hello", 0) < 0) {
+        THROW_ERROR("failed to send the message");
+    }
+
+    struct msqid_ds ds;
+    if (msgctl(msqid, IPC_STAT, &ds) < 0) {
+        THROW_ERROR("failed to get the status of the queue");
+    }
+    if (ds.msg_qnum != 1 || ds.msg_lspid != getpid() || (ds.msg_perm.mode & 0777) != 0600) {
+        THROW_ERROR("the status of the queue is wrong");
+    }
+
+    // A queue of no more space blocks the senders
+    ds.msg_qbytes = 8;
+    if (msgctl(msqid, IPC_SET, &ds) < 0) {
+        THROW_ERROR("failed to set the status of the queue");
+    }
+    if (send_msg(msqid, 1, "world", IPC_NOWAIT) != -1 || errno != EAGAIN) {
+        THROW_ERROR("sending to a full queue does not fail with EAGAIN");
+    }
+
+    msgctl(msqid, IPC_RMID, NULL);
+    if (send_msg(msqid, 1, "world", 0) != -1 || errno != EINVAL) {
+        THROW_ERROR("the queue is not removed");
+    }
+    return 0;
+}
+
+static int test_wake_up_blocked_msgrcv() {
+    int msqid = msgget(IPC_PRIVATE, 0600);
+    if (msqid < 0) {
+        THROW_ERROR("failed to create the queue");
+    }
+
+    int child_pid;
+    if (spawn_child("wake_up_blocked_msgrcv", msqid, &child_pid) < 0) {
+        THROW_ERROR("failed to spawn the child");
+    }
+    if (recv_msg(msqid, 7, 0, 7, "wanted") < 0) {
+        THROW_ERROR("failed to receive the message from the child");
+    }
+    if (wait_child(child_pid) < 0) {
+        THROW_ERROR("the child failed");
+    }
+    if (recv_msg(msqid, 0, IPC_NOWAIT, 3, "ignored") < 0) {
+        THROW_ERROR("the other message is lost");
+    }
+
+    msgctl(msqid, IPC_RMID, NULL);
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_msgget),
+    TEST_CASE(test_receive_by_type),
+    TEST_CASE(test_msgctl),
+    TEST_CASE(test_wake_up_blocked_msgrcv),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 2) {
+        int msqid = atoi(argv[2]);
+        if (strcmp(argv[1], "wake_up_blocked_msgrcv") == 0) {
+            return child_test_wake_up_blocked_msgrcv(msqid) < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+        }
+        return EXIT_FAILURE;
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,273 @@
+#define _GNU_SOURCE
+#include <sys/ipc.h>
+#include <sys/sem.h>
+#include <sys/wait.h>
+#include <errno.h>
+#include <spawn.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <time.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define SEM_KEY         0x5345
+#define NSEMS           3
+#define CHILD_PROG      "/bin/sysv_sem"
+
+union semun {
+    int val;
+    struct semid_ds *buf;
+    unsigned short *array;
+};
+
+static int spawn_child(const char *test_name, int semid, int *child_pid) {
+    char semid_buf[16];
+    snprintf(semid_buf, sizeof(semid_buf), "%d", semid);
+    const char *child_argv[4] = { CHILD_PROG, test_name, semid_buf, NULL };
+    if (posix_spawn(child_pid, CHILD_PROG, NULL, NULL,
+                    (char *const *)child_argv, NULL) != 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+    return 0;
+}
+
+static int wait_child(int child_pid) {
+    int status = 0;
+    if (wait4(child_pid, &status, 0, NULL) < 0) {
+        THROW_ERROR("failed to wait4 the child process");
+    }
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        THROW_ERROR("the child process failed");
+    }
+    return 0;
+}
+
+static int get_sem_set() {
+    int semid = semget(SEM_KEY, 0, 0);
+    if (semid < 0) {
+        THROW_ERROR("failed to get the semaphore set");
+    }
+    return semid;
+}
+
+// ============================================================================
+// Child processes
+// ============================================================================
+
+// The parent is blocked until the child increases the first semaphore
+static int child_test_wake_up_blocked_semop(int semid) {
+    usleep(100 * 1000);
+    struct sembuf sop = { .sem_num = 0, .sem_op = 1, .sem_flg = 0 };
+    if (semop(semid, &sop, 1) < 0) {
+        THROW_ERROR("failed to increase the semaphore");
+    }
+    return 0;
+}
+
+// The operation of SEM_UNDO is reverted when the child exits
+static int child_test_sem_undo(int semid) {
+    struct sembuf sop = { .sem_num = 1, .sem_op = -1, .sem_flg = SEM_UNDO };
+    if (semop(semid, &sop, 1) < 0) {
+        THROW_ERROR("failed to decrease the semaphore");
+    }
+    if (semctl(semid, 1, GETVAL) != 0) {
+        THROW_ERROR("the semaphore is not decreased");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_semget() {
+    int semid = semget(SEM_KEY, NSEMS, IPC_CREAT | IPC_EXCL | 0600);
+    if (semid < 0) {
+        THROW_ERROR("failed to create the semaphore set");
+    }
+    if (semget(SEM_KEY, NSEMS, IPC_CREAT | IPC_EXCL | 0600) >= 0 || errno != EEXIST) {
+        THROW_ERROR("creating an existing semaphore set should fail");
+    }
+    if (semget(SEM_KEY, NSEMS + 1, 0) >= 0 || errno != EINVAL) {
+        THROW_ERROR("getting more semaphores than the set has should fail");
+    }
+    if (semget(SEM_KEY, 0, 0) != semid) {
+        THROW_ERROR("failed to get the existing semaphore set");
+    }
+    if (semget(SEM_KEY + 1, NSEMS, 0) >= 0 || errno != ENOENT) {
+        THROW_ERROR("getting a nonexistent semaphore set should fail");
+    }
+
+    struct semid_ds ds;
+    union semun arg = { .buf = &ds };
+    if (semctl(semid, 0, IPC_STAT, arg) < 0) {
+        THROW_ERROR("failed to stat the semaphore set");
+    }
+    if (ds.sem_nsems != NSEMS || (ds.sem_perm.mode & 0777) != 0600) {
+        THROW_ERROR("the attributes of the semaphore set are not as expected");
+    }
+    return 0;
+}
+
+static int test_setval_and_getval() {
+    int semid = get_sem_set();
+    if (semid < 0) {
+        return -1;
+    }
+    union semun arg = { .val = 5 };
+    if (semctl(semid, 2, SETVAL, arg) < 0) {
+        THROW_ERROR("failed to set the value of the semaphore");
+    }
+    if (semctl(semid, 2, GETVAL) != 5 || semctl(semid, 2, GETPID) != getpid()) {
+        THROW_ERROR("the semaphore is not as expected");
+    }
+    if (semctl(semid, NSEMS, GETVAL) >= 0 || errno != EINVAL) {
+        THROW_ERROR("getting an invalid semaphore should fail");
+    }
+
+    unsigned short vals[NSEMS] = { 0, 1, 2 };
+    arg.array = vals;
+    if (semctl(semid, 0, SETALL, arg) < 0) {
+        THROW_ERROR("failed to set the values of the semaphores");
+    }
+    memset(vals, 0, sizeof(vals));
+    if (semctl(semid, 0, GETALL, arg) < 0) {
+        THROW_ERROR("failed to get the values of the semaphores");
+    }
+    if (vals[0] != 0 || vals[1] != 1 || vals[2] != 2) {
+        THROW_ERROR("the values of the semaphores are not as expected");
+    }
+    return 0;
+}
+
+static int test_semop() {
+    int semid = get_sem_set();
+    if (semid < 0) {
+        return -1;
+    }
+    // The operations are performed atomically
+    struct sembuf sops[2] = {
+        { .sem_num = 2, .sem_op = -2, .sem_flg = 0 },
+        { .sem_num = 0, .sem_op = -1, .sem_flg = IPC_NOWAIT },
+    };
+    if (semop(semid, sops, 2) == 0 || errno != EAGAIN) {
+        THROW_ERROR("the operations should block");
+    }
+    if (semctl(semid, 2, GETVAL) != 2) {
+        THROW_ERROR("the operations are not performed atomically");
+    }
+
+    sops[1].sem_op = 0;
+    if (semop(semid, sops, 2) < 0) {
+        THROW_ERROR("failed to perform the operations");
+    }
+    if (semctl(semid, 2, GETVAL) != 0) {
+        THROW_ERROR("the semaphore is not decreased");
+    }
+
+    struct sembuf sop = { .sem_num = NSEMS, .sem_op = 1, .sem_flg = 0 };
+    if (semop(semid, &sop, 1) == 0 || errno != EFBIG) {
+        THROW_ERROR("operating on an invalid semaphore should fail");
+    }
+    return 0;
+}
+
+static int test_semtimedop_timeout() {
+    int semid = get_sem_set();
+    if (semid < 0) {
+        return -1;
+    }
+    struct sembuf sop = { .sem_num = 0, .sem_op = -1, .sem_flg = 0 };
+    struct timespec timeout = { .tv_sec = 0, .tv_nsec = 50 * 1000 * 1000 };
+    if (semtimedop(semid, &sop, 1, &timeout) == 0 || errno != EAGAIN) {
+        THROW_ERROR("the operation should time out");
+    }
+    return 0;
+}
+
+static int test_wake_up_blocked_semop() {
+    int semid = get_sem_set();
+    if (semid < 0) {
+        return -1;
+    }
+    int child_pid = 0;
+    if (spawn_child("wake_up_blocked_semop", semid, &child_pid) < 0) {
+        return -1;
+    }
+    struct sembuf sop = { .sem_num = 0, .sem_op = -1, .sem_flg = 0 };
+    if (semop(semid, &sop, 1) < 0) {
+        THROW_ERROR("failed to decrease the semaphore");
+    }
+    if (wait_child(child_pid) < 0) {
+        return -1;
+    }
+    if (semctl(semid, 0, GETVAL) != 0) {
+        THROW_ERROR("the semaphore is not as expected");
+    }
+    return 0;
+}
+
+static int test_sem_undo() {
+    int semid = get_sem_set();
+    if (semid < 0) {
+        return -1;
+    }
+    int child_pid = 0;
+    if (spawn_child("sem_undo", semid, &child_pid) < 0 || wait_child(child_pid) < 0) {
+        return -1;
+    }
+    if (semctl(semid, 1, GETVAL) != 1) {
+        THROW_ERROR("the operation of SEM_UNDO is not reverted");
+    }
+    return 0;
+}
+
+static int test_ipc_rmid() {
+    int semid = get_sem_set();
+    if (semid < 0) {
+        return -1;
+    }
+    if (semctl(semid, 0, IPC_RMID) < 0) {
+        THROW_ERROR("failed to remove the semaphore set");
+    }
+    if (semctl(semid, 0, GETVAL) >= 0 || errno != EINVAL) {
+        THROW_ERROR("the removed semaphore set should not exist");
+    }
+    if (semget(SEM_KEY, 0, 0) >= 0 || errno != ENOENT) {
+        THROW_ERROR("the key of the removed semaphore set should not exist");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_semget),
+    TEST_CASE(test_setval_and_getval),
+    TEST_CASE(test_semop),
+    TEST_CASE(test_semtimedop_timeout),
+    TEST_CASE(test_wake_up_blocked_semop),
+    TEST_CASE(test_sem_undo),
+    TEST_CASE(test_ipc_rmid),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 2) {
+        int semid = atoi(argv[2]);
+        if (strcmp(argv[1], "wake_up_blocked_semop") == 0) {
+            return child_test_wake_up_blocked_semop(semid) < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+        }
+        if (strcmp(argv[1], "sem_undo") == 0) {
+            return child_test_sem_undo(semid) < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+        }
+        return EXIT_FAILURE;
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
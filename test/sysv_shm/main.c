@@ -0,0 +1,251 @@
+#include <sys/ipc.h>
+#include <sys/shm.h>
+#include <sys/wait.h>
+#include <errno.h>
+#include <spawn.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define SHM_KEY         0x5353
+#define SHM_SIZE        8192
+#define CHILD_PROG      "/bin/sysv_shm"
+
+#define PARENT_MSG      "Hello from the parent"
+#define CHILD_MSG       "Hello from the child"
+// The child writes its message to the second page
+#define CHILD_MSG_OFFSET 4096
+
+static int spawn_child(const char *test_name, int shmid, int *child_pid) {
+    char shmid_buf[16];
+    snprintf(shmid_buf, sizeof(shmid_buf), "%d", shmid);
+    const char *child_argv[4] = { CHILD_PROG, test_name, shmid_buf, NULL };
+    if (posix_spawn(child_pid, CHILD_PROG, NULL, NULL,
+                    (char *const *)child_argv, NULL) != 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+    return 0;
+}
+
+static int wait_child(int child_pid) {
+    int status = 0;
+    if (wait4(child_pid, &status, 0, NULL) < 0) {
+        THROW_ERROR("failed to wait4 the child process");
+    }
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 0) {
+        THROW_ERROR("the child process failed");
+    }
+    return 0;
+}
+
+static int get_nattch(int shmid) {
+    struct shmid_ds ds;
+    if (shmctl(shmid, IPC_STAT, &ds) < 0) {
+        return -1;
+    }
+    return (int)ds.shm_nattch;
+}
+
+// ============================================================================
+// Child processes
+// ============================================================================
+
+// The parent has attached the segment and written its message
+static int child_test_shared_across_processes(int shmid) {
+    // The segment is also found by its key
+    if (shmget(SHM_KEY, SHM_SIZE, 0) != shmid) {
+        THROW_ERROR("failed to get the segment by its key");
+    }
+    char *buf = shmat(shmid, NULL, 0);
+    if (buf == (void *) -1) {
+        THROW_ERROR("failed to attach the segment");
+    }
+    if (strcmp(buf, PARENT_MSG) != 0) {
+        THROW_ERROR("the message of the parent is not visible");
+    }
+    if (get_nattch(shmid) != 2) {
+        THROW_ERROR("the number of attachments is not as expected");
+    }
+    strcpy(buf + CHILD_MSG_OFFSET, CHILD_MSG);
+    // The segment is detached when the child exits
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_shmget() {
+    int shmid = shmget(SHM_KEY, SHM_SIZE, IPC_CREAT | IPC_EXCL | 0600);
+    if (shmid < 0) {
+        THROW_ERROR("failed to create the segment");
+    }
+    if (shmget(SHM_KEY, SHM_SIZE, IPC_CREAT | IPC_EXCL | 0600) >= 0 || errno != EEXIST) {
+        THROW_ERROR("creating an existing segment should fail");
+    }
+    if (shmget(SHM_KEY, SHM_SIZE * 2, 0) >= 0 || errno != EINVAL) {
+        THROW_ERROR("getting a segment larger than the existing one should fail");
+    }
+    if (shmget(SHM_KEY, SHM_SIZE / 2, 0) != shmid) {
+        THROW_ERROR("failed to get the existing segment");
+    }
+    if (shmget(SHM_KEY + 1, SHM_SIZE, 0) >= 0 || errno != ENOENT) {
+        THROW_ERROR("getting a nonexistent segment should fail");
+    }
+
+    // A new segment is created for IPC_PRIVATE every time
+    int private_shmid = shmget(IPC_PRIVATE, SHM_SIZE, 0600);
+    if (private_shmid < 0 || private_shmid == shmid ||
+            shmget(IPC_PRIVATE, SHM_SIZE, 0600) == private_shmid) {
+        THROW_ERROR("failed to create private segments");
+    }
+    return 0;
+}
+
+static int test_ipc_stat() {
+    int shmid = shmget(SHM_KEY, 0, 0);
+    if (shmid < 0) {
+        THROW_ERROR("failed to get the segment");
+    }
+    struct shmid_ds ds;
+    if (shmctl(shmid, IPC_STAT, &ds) < 0) {
+        THROW_ERROR("failed to stat the segment");
+    }
+    if ((ds.shm_perm.mode & 0777) != 0600 ||
+            ds.shm_segsz != SHM_SIZE || ds.shm_nattch != 0 ||
+            ds.shm_cpid != getpid()) {
+        THROW_ERROR("the attributes of the segment are not as expected");
+    }
+
+    ds.shm_perm.mode = 0644;
+    if (shmctl(shmid, IPC_SET, &ds) < 0) {
+        THROW_ERROR("failed to set the attributes of the segment");
+    }
+    if (shmctl(shmid, IPC_STAT, &ds) < 0 || (ds.shm_perm.mode & 0777) != 0644) {
+        THROW_ERROR("the permission of the segment is not changed");
+    }
+    if (shmctl(shmid + 1, IPC_STAT, &ds) >= 0 || errno != EINVAL) {
+        THROW_ERROR("stating an invalid segment should fail");
+    }
+    return 0;
+}
+
+static int test_shared_across_processes() {
+    int shmid = shmget(SHM_KEY, 0, 0);
+    if (shmid < 0) {
+        THROW_ERROR("failed to get the segment");
+    }
+    char *buf = shmat(shmid, NULL, 0);
+    if (buf == (void *) -1) {
+        THROW_ERROR("failed to attach the segment");
+    }
+    // The segment is filled with zeros initially
+    for (int i = 0; i < SHM_SIZE; i++) {
+        if (buf[i] != 0) {
+            THROW_ERROR("the segment is not filled with zeros");
+        }
+    }
+    strcpy(buf, PARENT_MSG);
+    if (get_nattch(shmid) != 1) {
+        THROW_ERROR("the number of attachments is not as expected");
+    }
+
+    int child_pid = 0;
+    if (spawn_child("shared_across_processes", shmid, &child_pid) < 0 ||
+            wait_child(child_pid) < 0) {
+        return -1;
+    }
+    // The write of the child is visible through the attachment of the parent
+    if (strcmp(buf + CHILD_MSG_OFFSET, CHILD_MSG) != 0) {
+        THROW_ERROR("the message of the child is not visible");
+    }
+    if (get_nattch(shmid) != 1) {
+        THROW_ERROR("the segment is not detached when the child exits");
+    }
+
+    if (shmdt(buf) < 0) {
+        THROW_ERROR("failed to detach the segment");
+    }
+    if (get_nattch(shmid) != 0) {
+        THROW_ERROR("the number of attachments is not as expected");
+    }
+    if (shmdt(buf) == 0 || errno != EINVAL) {
+        THROW_ERROR("detaching a detached segment should fail");
+    }
+
+    // The content is kept after the segment is detached by all the processes
+    buf = shmat(shmid, NULL, SHM_RDONLY);
+    if (buf == (void *) -1) {
+        THROW_ERROR("failed to attach the segment again");
+    }
+    if (strcmp(buf, PARENT_MSG) != 0 || strcmp(buf + CHILD_MSG_OFFSET, CHILD_MSG) != 0) {
+        THROW_ERROR("the content of the segment is lost");
+    }
+    if (shmdt(buf) < 0) {
+        THROW_ERROR("failed to detach the segment");
+    }
+    return 0;
+}
+
+static int test_ipc_rmid() {
+    int shmid = shmget(SHM_KEY, 0, 0);
+    if (shmid < 0) {
+        THROW_ERROR("failed to get the segment");
+    }
+    char *buf = shmat(shmid, NULL, 0);
+    if (buf == (void *) -1) {
+        THROW_ERROR("failed to attach the segment");
+    }
+    if (shmctl(shmid, IPC_RMID, NULL) < 0) {
+        THROW_ERROR("failed to remove the segment");
+    }
+
+    // The removed segment is still accessible until it is detached
+    if (strcmp(buf, PARENT_MSG) != 0) {
+        THROW_ERROR("the removed segment is not accessible");
+    }
+    struct shmid_ds ds;
+    if (shmctl(shmid, IPC_STAT, &ds) < 0 || ds.shm_nattch != 1) {
+        THROW_ERROR("failed to stat the removed segment");
+    }
+    // But the key refers to a new segment
+    if (shmget(SHM_KEY, SHM_SIZE, 0) >= 0 || errno != ENOENT) {
+        THROW_ERROR("the key of the removed segment should not exist");
+    }
+
+    if (shmdt(buf) < 0) {
+        THROW_ERROR("failed to detach the segment");
+    }
+    if (shmctl(shmid, IPC_STAT, &ds) >= 0 || errno != EINVAL) {
+        THROW_ERROR("the removed segment should be destroyed");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_shmget),
+    TEST_CASE(test_ipc_stat),
+    TEST_CASE(test_shared_across_processes),
+    TEST_CASE(test_ipc_rmid),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 2) {
+        int shmid = atoi(argv[2]);
+        if (strcmp(argv[1], "shared_across_processes") == 0) {
+            return child_test_shared_across_processes(shmid) < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+        }
+        return EXIT_FAILURE;
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
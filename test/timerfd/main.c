@@ -0,0 +1,275 @@
+#include <sys/epoll.h>
+#include <sys/timerfd.h>
+#include <errno.h>
+#include <poll.h>
+#include <stdint.h>
+#include <time.h>
+#include <unistd.h>
+#include "test.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define NS_PER_MS   (1000 * 1000)
+
+static int arm_timer(int timer_fd, long value_ms, long interval_ms) {
+    struct itimerspec new_value = {
+        .it_interval = { .tv_sec = 0, .tv_nsec = interval_ms * NS_PER_MS },
+        .it_value = { .tv_sec = 0, .tv_nsec = value_ms * NS_PER_MS },
+    };
+    return timerfd_settime(timer_fd, 0, &new_value, NULL);
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+int test_create_with_invalid_args() {
+    if (timerfd_create(-1, 0) != -1 || errno != EINVAL) {
+        THROW_ERROR("create with an invalid clockid should fail");
+    }
+    if (timerfd_create(CLOCK_MONOTONIC, -1) != -1 || errno != EINVAL) {
+        THROW_ERROR("create with invalid flags should fail");
+    }
+    return 0;
+}
+
+int test_settime_and_gettime() {
+    int timer_fd = timerfd_create(CLOCK_MONOTONIC, 0);
+    if (timer_fd < 0) {
+        THROW_ERROR("failed to create a timerfd");
+    }
+
+    if (arm_timer(timer_fd, 500, 100) < 0) {
+        close(timer_fd);
+        THROW_ERROR("failed to arm the timer");
+    }
+
+    struct itimerspec curr_value;
+    if (timerfd_gettime(timer_fd, &curr_value) < 0) {
+        close(timer_fd);
+        THROW_ERROR("failed to get the time of the timer");
+    }
+    if (curr_value.it_interval.tv_nsec != 100 * NS_PER_MS ||
+            curr_value.it_value.tv_nsec > 500 * NS_PER_MS ||
+            (curr_value.it_value.tv_sec == 0 && curr_value.it_value.tv_nsec == 0)) {
+        close(timer_fd);
+        THROW_ERROR("the time of the timer is wrong");
+    }
+
+    // Disarm the timer
+    struct itimerspec old_value;
+    struct itimerspec zero_value = { 0 };
+    if (timerfd_settime(timer_fd, 0, &zero_value, &old_value) < 0) {
+        close(timer_fd);
+        THROW_ERROR("failed to disarm the timer");
+    }
+    if (old_value.it_interval.tv_nsec != 100 * NS_PER_MS) {
+        close(timer_fd);
+        THROW_ERROR("the old value of the timer is wrong");
+    }
+
+    close(timer_fd);
+    return 0;
+}
+
+int test_settime_with_bad_old_value() {
+    int timer_fd = timerfd_create(CLOCK_MONOTONIC, 0);
+    if (timer_fd < 0) {
+        THROW_ERROR("failed to create a timerfd");
+    }
+    if (arm_timer(timer_fd, 500, 100) < 0) {
+        close(timer_fd);
+        THROW_ERROR("failed to arm the timer");
+    }
+
+    // The timer is not changed if the old value cannot be written
+    struct itimerspec zero_value = { 0 };
+    if (timerfd_settime(timer_fd, 0, &zero_value, (struct itimerspec *)1) != -1 ||
+            errno != EFAULT) {
+        close(timer_fd);
+        THROW_ERROR("settime with a bad old value should fail with EFAULT");
+    }
+    struct itimerspec curr_value;
+    if (timerfd_gettime(timer_fd, &curr_value) < 0) {
+        close(timer_fd);
+        THROW_ERROR("failed to get the time of the timer");
+    }
+    if (curr_value.it_interval.tv_nsec != 100 * NS_PER_MS) {
+        close(timer_fd);
+        THROW_ERROR("the timer should not be changed");
+    }
+
+    close(timer_fd);
+    return 0;
+}
+
+int test_settime_with_invalid_args() {
+    int timer_fd = timerfd_create(CLOCK_REALTIME, 0);
+    if (timer_fd < 0) {
+        THROW_ERROR("failed to create a timerfd");
+    }
+
+    // The discontinuous changes of the realtime clock are not supported
+    struct itimerspec new_value = { .it_value = { .tv_sec = 1 } };
+    if (timerfd_settime(timer_fd, TFD_TIMER_ABSTIME | TFD_TIMER_CANCEL_ON_SET, &new_value,
+                        NULL) != -1 || errno != EINVAL) {
+        close(timer_fd);
+        THROW_ERROR("settime with TFD_TIMER_CANCEL_ON_SET should fail with EINVAL");
+    }
+    close(timer_fd);
+
+    // A file that is not a timerfd
+    int pipe_fds[2];
+    if (pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create a pipe");
+    }
+    struct itimerspec curr_value;
+    if (timerfd_settime(pipe_fds[0], 0, &new_value, NULL) != -1 || errno != EINVAL) {
+        close_files(2, pipe_fds[0], pipe_fds[1]);
+        THROW_ERROR("settime on a pipe should fail with EINVAL");
+    }
+    if (timerfd_gettime(pipe_fds[0], &curr_value) != -1 || errno != EINVAL) {
+        close_files(2, pipe_fds[0], pipe_fds[1]);
+        THROW_ERROR("gettime on a pipe should fail with EINVAL");
+    }
+    close_files(2, pipe_fds[0], pipe_fds[1]);
+    return 0;
+}
+
+int test_read_fast_expiration() {
+    int timer_fd = timerfd_create(CLOCK_MONOTONIC, 0);
+    if (timer_fd < 0) {
+        THROW_ERROR("failed to create a timerfd");
+    }
+
+    // The timer expires right after it is armed, and the expiration must not be
+    // discarded as one of the old setting
+    for (int i = 0; i < 100; i++) {
+        struct itimerspec new_value = { .it_value = { .tv_nsec = 1 } };
+        if (timerfd_settime(timer_fd, 0, &new_value, NULL) < 0) {
+            close(timer_fd);
+            THROW_ERROR("failed to arm the timer");
+        }
+        uint64_t expirations = 0;
+        if (read(timer_fd, &expirations, sizeof(expirations)) != sizeof(expirations) ||
+                expirations != 1) {
+            close(timer_fd);
+            THROW_ERROR("failed to read the expiration");
+        }
+    }
+
+    close(timer_fd);
+    return 0;
+}
+
+int test_read_expirations() {
+    int timer_fd = timerfd_create(CLOCK_MONOTONIC, 0);
+    if (timer_fd < 0) {
+        THROW_ERROR("failed to create a timerfd");
+    }
+
+    if (arm_timer(timer_fd, 10, 10) < 0) {
+        close(timer_fd);
+        THROW_ERROR("failed to arm the timer");
+    }
+
+    uint64_t expirations = 0;
+    if (read(timer_fd, &expirations, sizeof(expirations)) != sizeof(expirations) ||
+            expirations == 0) {
+        close(timer_fd);
+        THROW_ERROR("failed to read the expirations");
+    }
+
+    close(timer_fd);
+    return 0;
+}
+
+int test_nonblock_read() {
+    int timer_fd = timerfd_create(CLOCK_MONOTONIC, TFD_NONBLOCK);
+    if (timer_fd < 0) {
+        THROW_ERROR("failed to create a timerfd");
+    }
+
+    uint64_t expirations = 0;
+    if (read(timer_fd, &expirations, sizeof(expirations)) != -1 || errno != EAGAIN) {
+        close(timer_fd);
+        THROW_ERROR("read a disarmed timer should fail with EAGAIN");
+    }
+
+    close(timer_fd);
+    return 0;
+}
+
+int test_poll() {
+    int timer_fd = timerfd_create(CLOCK_MONOTONIC, 0);
+    if (timer_fd < 0) {
+        THROW_ERROR("failed to create a timerfd");
+    }
+
+    if (arm_timer(timer_fd, 10, 0) < 0) {
+        close(timer_fd);
+        THROW_ERROR("failed to arm the timer");
+    }
+
+    struct pollfd pollfds[] = {
+        { .fd = timer_fd, .events = POLLIN, .revents = 0 },
+    };
+    if (poll(pollfds, 1, 1000) != 1 || (pollfds[0].revents & POLLIN) == 0) {
+        close(timer_fd);
+        THROW_ERROR("the timer is not readable after expiration");
+    }
+
+    close(timer_fd);
+    return 0;
+}
+
+int test_epoll() {
+    int timer_fd = timerfd_create(CLOCK_MONOTONIC, TFD_NONBLOCK);
+    int ep_fd = epoll_create1(0);
+    if (timer_fd < 0 || ep_fd < 0) {
+        THROW_ERROR("failed to create files");
+    }
+
+    struct epoll_event event = { .events = EPOLLIN, .data.fd = timer_fd };
+    if (epoll_ctl(ep_fd, EPOLL_CTL_ADD, timer_fd, &event) < 0) {
+        close_files(2, timer_fd, ep_fd);
+        THROW_ERROR("failed to add the timerfd to the epoll file");
+    }
+
+    if (arm_timer(timer_fd, 10, 0) < 0) {
+        close_files(2, timer_fd, ep_fd);
+        THROW_ERROR("failed to arm the timer");
+    }
+
+    struct epoll_event ready_event;
+    if (epoll_wait(ep_fd, &ready_event, 1, 1000) != 1 || ready_event.data.fd != timer_fd) {
+        close_files(2, timer_fd, ep_fd);
+        THROW_ERROR("the timer expiration is not reported by epoll");
+    }
+
+    close_files(2, timer_fd, ep_fd);
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_create_with_invalid_args),
+    TEST_CASE(test_settime_and_gettime),
+    TEST_CASE(test_settime_with_bad_old_value),
+    TEST_CASE(test_settime_with_invalid_args),
+    TEST_CASE(test_read_fast_expiration),
+    TEST_CASE(test_read_expirations),
+    TEST_CASE(test_nonblock_read),
+    TEST_CASE(test_poll),
+    TEST_CASE(test_epoll),
+};
+
+int main() {
+    int ret = test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+    return ret;
+}
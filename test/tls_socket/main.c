@@ -0,0 +1,288 @@
+#define _GNU_SOURCE
+#include <errno.h>
+#include <poll.h>
+#include <pthread.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include <arpa/inet.h>
+#include <netinet/in.h>
+#include <sys/ioctl.h>
+#include <sys/socket.h>
+
+#include "test.h"
+
+// The ports of the TLS policies in test/Occlum.json. The connections of
+// TLS_PORT are TLS on both sides, while only the clients of
+// TLS_CLIENT_ONLY_PORT are TLS.
+#define TLS_PORT                8443
+#define TLS_CLIENT_ONLY_PORT    8444
+
+#define MSG                     "msg over the TLS connection"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+static int listen_on(int port) {
+    int listen_fd = socket(AF_INET, SOCK_STREAM, 0);
+    if (listen_fd < 0) {
+        THROW_ERROR("create socket error");
+    }
+    int reuse = 1;
+    if (setsockopt(listen_fd, SOL_SOCKET, SO_REUSEADDR, &reuse, sizeof(reuse)) < 0) {
+        close(listen_fd);
+        THROW_ERROR("setsockopt port to reuse failed");
+    }
+
+    struct sockaddr_in addr;
+    memset(&addr, 0, sizeof(addr));
+    addr.sin_family = AF_INET;
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    addr.sin_port = htons(port);
+    if (bind(listen_fd, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        close(listen_fd);
+        THROW_ERROR("bind socket failed");
+    }
+    if (listen(listen_fd, 1) < 0) {
+        close(listen_fd);
+        THROW_ERROR("listen socket error");
+    }
+    return listen_fd;
+}
+
+static int connect_to(int port) {
+    int fd = socket(AF_INET, SOCK_STREAM, 0);
+    if (fd < 0) {
+        THROW_ERROR("create socket error");
+    }
+
+    struct sockaddr_in addr;
+    memset(&addr, 0, sizeof(addr));
+    addr.sin_family = AF_INET;
+    addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+    addr.sin_port = htons(port);
+    if (connect(fd, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        close(fd);
+        THROW_ERROR("connect socket failed");
+    }
+    return fd;
+}
+
+// Accept a connection and echo the data received until the peer closes it
+static void *echo_server(void *arg) {
+    int listen_fd = *(int *)arg;
+    char buf[64];
+
+    int fd = accept(listen_fd, NULL, NULL);
+    if (fd < 0) {
+        printf("ERROR: accept socket error\n");
+        return (void *) -1;
+    }
+    while (1) {
+        ssize_t len = recv(fd, buf, sizeof(buf), 0);
+        if (len <= 0) {
+            break;
+        }
+        if (send(fd, buf, len, 0) != len) {
+            printf("ERROR: send failed\n");
+            break;
+        }
+    }
+    close(fd);
+    return NULL;
+}
+
+static int start_echo_server(pthread_t *thread, int *listen_fd) {
+    *listen_fd = listen_on(TLS_PORT);
+    if (*listen_fd < 0) {
+        THROW_ERROR("failed to listen");
+    }
+    if (pthread_create(thread, NULL, echo_server, listen_fd) != 0) {
+        close(*listen_fd);
+        THROW_ERROR("failed to create the server thread");
+    }
+    return 0;
+}
+
+static int stop_echo_server(pthread_t thread, int listen_fd) {
+    void *ret = NULL;
+    pthread_join(thread, &ret);
+    close(listen_fd);
+    if (ret != NULL) {
+        THROW_ERROR("the server failed");
+    }
+    return 0;
+}
+
+static int recv_all(int fd, char *buf, size_t len) {
+    size_t bytes_recvd = 0;
+    while (bytes_recvd < len) {
+        ssize_t ret = recv(fd, buf + bytes_recvd, len - bytes_recvd, 0);
+        if (ret <= 0) {
+            THROW_ERROR("recv failed");
+        }
+        bytes_recvd += ret;
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+int test_round_trip() {
+    pthread_t thread;
+    int listen_fd;
+    char buf[sizeof(MSG)] = {0};
+    int ret = -1;
+
+    if (start_echo_server(&thread, &listen_fd) < 0) {
+        THROW_ERROR("failed to start the server");
+    }
+    int fd = connect_to(TLS_PORT);
+    if (fd < 0) {
+        goto out;
+    }
+    if (send(fd, MSG, sizeof(MSG), 0) != sizeof(MSG)) {
+        printf("ERROR: send failed\n");
+        goto out_close;
+    }
+    if (recv_all(fd, buf, sizeof(MSG)) < 0) {
+        goto out_close;
+    }
+    if (strcmp(buf, MSG) != 0) {
+        printf("ERROR: msg mismatch\n");
+        goto out_close;
+    }
+    ret = 0;
+out_close:
+    close(fd);
+out:
+    if (stop_echo_server(thread, listen_fd) < 0) {
+        ret = -1;
+    }
+    if (ret < 0) {
+        THROW_ERROR("failed to do a round trip");
+    }
+    return 0;
+}
+
+int test_readiness_of_decrypted_data() {
+    pthread_t thread;
+    int listen_fd;
+    char buf[sizeof(MSG)] = {0};
+    int nbytes = 0;
+    int ret = -1;
+
+    if (start_echo_server(&thread, &listen_fd) < 0) {
+        THROW_ERROR("failed to start the server");
+    }
+    int fd = connect_to(TLS_PORT);
+    if (fd < 0) {
+        goto out;
+    }
+
+    // The records of the handshake do not make the connection readable
+    struct pollfd pfd = { .fd = fd, .events = POLLIN };
+    if (poll(&pfd, 1, 200) != 0) {
+        printf("ERROR: readable without data\n");
+        goto out_close;
+    }
+    if (ioctl(fd, FIONREAD, &nbytes) < 0 || nbytes != 0) {
+        printf("ERROR: FIONREAD without data\n");
+        goto out_close;
+    }
+
+    if (send(fd, MSG, sizeof(MSG), 0) != sizeof(MSG)) {
+        printf("ERROR: send failed\n");
+        goto out_close;
+    }
+    if (poll(&pfd, 1, 5000) != 1 || !(pfd.revents & POLLIN)) {
+        printf("ERROR: not readable with data\n");
+        goto out_close;
+    }
+    if (ioctl(fd, FIONREAD, &nbytes) < 0 || nbytes <= 0 || nbytes > sizeof(MSG)) {
+        printf("ERROR: FIONREAD is not the length of the decrypted data\n");
+        goto out_close;
+    }
+    // The decrypted data can be peeked
+    if (recv(fd, buf, nbytes, MSG_PEEK) != nbytes || strncmp(buf, MSG, nbytes) != 0) {
+        printf("ERROR: recv with MSG_PEEK failed\n");
+        goto out_close;
+    }
+    if (recv_all(fd, buf, sizeof(MSG)) < 0 || strcmp(buf, MSG) != 0) {
+        printf("ERROR: msg mismatch\n");
+        goto out_close;
+    }
+    ret = 0;
+out_close:
+    close(fd);
+out:
+    if (stop_echo_server(thread, listen_fd) < 0) {
+        ret = -1;
+    }
+    if (ret < 0) {
+        THROW_ERROR("wrong readiness of the TLS connection");
+    }
+    return 0;
+}
+
+int test_records_on_the_wire() {
+    char buf[1024];
+    int ret = -1;
+
+    int listen_fd = listen_on(TLS_CLIENT_ONLY_PORT);
+    if (listen_fd < 0) {
+        THROW_ERROR("failed to listen");
+    }
+    int fd = connect_to(TLS_CLIENT_ONLY_PORT);
+    if (fd < 0) {
+        close(listen_fd);
+        THROW_ERROR("failed to connect");
+    }
+    int server_fd = accept(listen_fd, NULL, NULL);
+    if (server_fd < 0) {
+        goto out;
+    }
+
+    // The plain server never answers the handshake, so the data is not sent
+    if (send(fd, MSG, sizeof(MSG), MSG_DONTWAIT) >= 0 || errno != EAGAIN) {
+        printf("ERROR: data sent before the handshake\n");
+        goto out_close;
+    }
+    ssize_t len = recv(server_fd, buf, sizeof(buf), 0);
+    if (len <= 0) {
+        printf("ERROR: recv failed\n");
+        goto out_close;
+    }
+    // The first record is the ClientHello of the handshake
+    if (buf[0] != 0x16 || memmem(buf, len, MSG, sizeof(MSG) - 1) != NULL) {
+        printf("ERROR: not a TLS record\n");
+        goto out_close;
+    }
+    ret = 0;
+out_close:
+    close(server_fd);
+out:
+    close(fd);
+    close(listen_fd);
+    if (ret < 0) {
+        THROW_ERROR("the records on the wire are wrong");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_round_trip),
+    TEST_CASE(test_readiness_of_decrypted_data),
+    TEST_CASE(test_records_on_the_wire),
+};
+
+int main() {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,220 @@
+#include <sys/stat.h>
+#include <dirent.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdbool.h>
+#include "test_fs.h"
+
+// ============================================================================
+// Helper function
+// ============================================================================
+
+// The files are in the read-only layer, see the Makefile
+#define LOWER_DIR       "/unionfs"
+#define LOWER_FILE      LOWER_DIR "/file"
+#define LOWER_SUBDIR    LOWER_DIR "/dir"
+#define LOWER_SUBFILE   LOWER_SUBDIR "/file"
+#define LOWER_MSG       "lower"
+#define UPPER_MSG       "upper"
+
+static int create_file_with_content(const char *file_path, const char *msg) {
+    int fd = open(file_path, O_WRONLY | O_CREAT | O_TRUNC, 00666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a file");
+    }
+    if (write(fd, msg, strlen(msg)) != strlen(msg)) {
+        close(fd);
+        THROW_ERROR("failed to write to the file");
+    }
+    close(fd);
+    return 0;
+}
+
+static bool dir_has_entry(const char *dir_path, const char *name) {
+    struct dirent *dp;
+    bool found = false;
+
+    DIR *dirp = opendir(dir_path);
+    if (dirp == NULL) {
+        return false;
+    }
+    while ((dp = readdir(dirp)) != NULL) {
+        if (strcmp(dp->d_name, name) == 0) {
+            found = true;
+        }
+    }
+    closedir(dirp);
+    return found;
+}
+
+static bool dir_has_whiteouts(const char *dir_path) {
+    struct dirent *dp;
+    bool found = false;
+
+    DIR *dirp = opendir(dir_path);
+    if (dirp == NULL) {
+        return false;
+    }
+    while ((dp = readdir(dirp)) != NULL) {
+        if (strncmp(dp->d_name, ".wh.", 4) == 0) {
+            found = true;
+        }
+    }
+    closedir(dirp);
+    return found;
+}
+
+static bool path_exists(const char *path) {
+    struct stat stat_buf;
+    return stat(path, &stat_buf) == 0;
+}
+
+// ============================================================================
+// Test cases for whiteouts and opaque directories
+// ============================================================================
+
+static int test_copy_up_on_write() {
+    struct stat stat_buf;
+
+    int fd = open(LOWER_FILE, O_WRONLY | O_APPEND);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the file of the lower layer");
+    }
+    if (write(fd, UPPER_MSG, strlen(UPPER_MSG)) != strlen(UPPER_MSG)) {
+        close(fd);
+        THROW_ERROR("failed to write the file of the lower layer");
+    }
+    close(fd);
+
+    if (fs_check_file_content(LOWER_FILE, LOWER_MSG UPPER_MSG) < 0) {
+        THROW_ERROR("the content of the file copied up is not kept");
+    }
+    if (chmod(LOWER_FILE, 0640) < 0 || stat(LOWER_FILE, &stat_buf) < 0) {
+        THROW_ERROR("failed to chmod the file copied up");
+    }
+    if ((stat_buf.st_mode & 0777) != 0640) {
+        THROW_ERROR("the mode of the file copied up is not updated");
+    }
+
+    if (create_file_with_content(LOWER_FILE, LOWER_MSG) < 0) {
+        THROW_ERROR("failed to restore the file");
+    }
+    return 0;
+}
+
+static int test_remove_lower_file() {
+    if (unlink(LOWER_FILE) < 0) {
+        THROW_ERROR("failed to remove the file of the lower layer");
+    }
+    if (path_exists(LOWER_FILE) || errno != ENOENT) {
+        THROW_ERROR("the removed file still exists");
+    }
+    if (dir_has_entry(LOWER_DIR, "file")) {
+        THROW_ERROR("the removed file is still listed");
+    }
+    if (dir_has_whiteouts(LOWER_DIR)) {
+        THROW_ERROR("the whiteouts should be hidden");
+    }
+
+    // The file created in place of the removed one is a new file
+    if (create_file_with_content(LOWER_FILE, UPPER_MSG) < 0) {
+        THROW_ERROR("failed to create the file again");
+    }
+    if (fs_check_file_content(LOWER_FILE, UPPER_MSG) < 0) {
+        THROW_ERROR("the content of the lower layer shows up");
+    }
+    if (!dir_has_entry(LOWER_DIR, "file")) {
+        THROW_ERROR("the file created again is not listed");
+    }
+
+    if (create_file_with_content(LOWER_FILE, LOWER_MSG) < 0) {
+        THROW_ERROR("failed to restore the file");
+    }
+    return 0;
+}
+
+static int test_remove_lower_dir() {
+    int ret = rmdir(LOWER_SUBDIR);
+    if (!(ret < 0 && errno == ENOTEMPTY)) {
+        THROW_ERROR("rmdir on the non-empty dir of the lower layer should fail");
+    }
+    if (unlink(LOWER_SUBFILE) < 0) {
+        THROW_ERROR("failed to remove the file in the dir of the lower layer");
+    }
+    if (rmdir(LOWER_SUBDIR) < 0) {
+        THROW_ERROR("failed to remove the empty dir of the lower layer");
+    }
+    if (path_exists(LOWER_SUBDIR) || dir_has_entry(LOWER_DIR, "dir")) {
+        THROW_ERROR("the removed dir still exists");
+    }
+
+    // The dir created in place of the removed one is opaque
+    if (mkdir(LOWER_SUBDIR, 00775) < 0) {
+        THROW_ERROR("failed to create the dir again");
+    }
+    if (path_exists(LOWER_SUBFILE) || dir_has_entry(LOWER_SUBDIR, "file")) {
+        THROW_ERROR("the entries of the removed dir show up");
+    }
+    if (dir_has_whiteouts(LOWER_SUBDIR)) {
+        THROW_ERROR("the opaque marker should be hidden");
+    }
+
+    if (create_file_with_content(LOWER_SUBFILE, LOWER_MSG) < 0) {
+        THROW_ERROR("failed to restore the file in the dir");
+    }
+    return 0;
+}
+
+static int test_rename_lower_file() {
+    const char *new_path = LOWER_DIR "/renamed_file";
+
+    if (rename(LOWER_FILE, new_path) < 0) {
+        THROW_ERROR("failed to rename the file of the lower layer");
+    }
+    if (path_exists(LOWER_FILE) || dir_has_entry(LOWER_DIR, "file")) {
+        THROW_ERROR("the old path of the renamed file still exists");
+    }
+    if (fs_check_file_content(new_path, LOWER_MSG) < 0) {
+        THROW_ERROR("the content of the renamed file is not kept");
+    }
+    if (rename(new_path, LOWER_FILE) < 0) {
+        THROW_ERROR("failed to rename the file back");
+    }
+    if (fs_check_file_content(LOWER_FILE, LOWER_MSG) < 0) {
+        THROW_ERROR("the content of the file renamed back is not kept");
+    }
+    return 0;
+}
+
+static int test_rename_lower_dir() {
+    int ret = rename(LOWER_SUBDIR, LOWER_DIR "/renamed_dir");
+    if (!(ret < 0 && errno == EXDEV)) {
+        THROW_ERROR("renaming the merged dir should fail with EXDEV");
+    }
+    return 0;
+}
+
+static int test_create_whiteout_name() {
+    int fd = open(LOWER_DIR "/.wh.file", O_WRONLY | O_CREAT, 00666);
+    if (!(fd < 0 && errno == EINVAL)) {
+        THROW_ERROR("creating a file with the name of whiteouts should fail");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_copy_up_on_write),
+    TEST_CASE(test_rename_lower_file),
+    TEST_CASE(test_rename_lower_dir),
+    TEST_CASE(test_remove_lower_file),
+    TEST_CASE(test_remove_lower_dir),
+    TEST_CASE(test_create_whiteout_name),
+};
+
+int main(int argc, const char *argv[]) {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,55 @@
+#!/bin/bash
+# Usage: check_invalid_layers.sh <occlum> exec /bin/unionfs_layers
+#
+# Check that the invalid layers of the root UnionFS are rejected when the
+# Occlum.json of the test is converted as `occlum build` does, and then run the
+# test with the valid layers.
+GEN_INTERNAL_CONF="$(dirname "$1")/gen_internal_conf"
+ROOT_LAYERS='(.mount[] | select(.target == "/" and .type == "unionfs") | .options.layers)'
+USER_FS_MAC="00-11-22-33-44-55-66-77-88-99-aa-bb-cc-dd-ee-ff"
+
+TMP_DIR=$(mktemp -d)
+trap 'rm -rf "$TMP_DIR"' EXIT
+
+# Convert the Occlum.json after applying the jq filter to it, which fails if
+# the mount configuration is rejected
+gen_user_conf() {
+    jq "$1" Occlum.json > "$TMP_DIR/Occlum.json" || exit 1
+    "$GEN_INTERNAL_CONF" --user_json "$TMP_DIR/Occlum.json" gen_user_conf \
+        --user_fs_mac "$USER_FS_MAC" \
+        --sdk_xml "$TMP_DIR/Enclave.xml" \
+        --pal_conf "$TMP_DIR/pal.conf" \
+        --output_user_json "$TMP_DIR/Occlum.json.out" > "$TMP_DIR/log" 2>&1
+    [ -e "$TMP_DIR/Occlum.json.out" ] && rm -f "$TMP_DIR/Occlum.json.out"
+}
+
+check_invalid_layers() {
+    if gen_user_conf "$2"; then
+        echo "ERROR: the layers with $1 are accepted"
+        exit 1
+    fi
+    if ! grep -q "Mount configuration invalid" "$TMP_DIR/log"; then
+        echo "ERROR: the layers with $1 are not rejected as invalid"
+        exit 1
+    fi
+}
+
+if ! gen_user_conf "."; then
+    echo "ERROR: the valid layers are rejected"
+    exit 1
+fi
+
+check_invalid_layers "a missing role" "del($ROOT_LAYERS[0].options.role)"
+check_invalid_layers "an unknown role" "$ROOT_LAYERS[0].options.role = \"base\""
+check_invalid_layers "a non-SEFS layer" "$ROOT_LAYERS[0].type = \"hostfs\""
+check_invalid_layers "no container layer" "del($ROOT_LAYERS[-1])"
+check_invalid_layers "two container layers" "$ROOT_LAYERS += [$ROOT_LAYERS[-1]]"
+check_invalid_layers "no image layer" \
+    "$ROOT_LAYERS |= map(select(.options.role == \"container\"))"
+check_invalid_layers "an image layer without a MAC" "del($ROOT_LAYERS[0].options.MAC)"
+check_invalid_layers "a container layer with a MAC" \
+    "$ROOT_LAYERS[-1].options.MAC = \"$USER_FS_MAC\""
+check_invalid_layers "two image layers with the empty MAC" \
+    "$ROOT_LAYERS[0].options.MAC = \"\""
+
+"$@"
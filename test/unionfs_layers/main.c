@@ -0,0 +1,96 @@
+#include <sys/stat.h>
+#include <dirent.h>
+#include <fcntl.h>
+#include <string.h>
+#include <unistd.h>
+#include "test_fs.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+// The root UnionFS has the base layer, the image and the container layers
+// from the bottom to the top, see the Makefile
+#define TEST_DIR        "/unionfs_layers"
+#define BASE_FILE       TEST_DIR "/base_file"
+#define SHADOWED_FILE   TEST_DIR "/shadowed_file"
+#define COPIED_FILE     TEST_DIR "/copied_file"
+
+static int count_dir_entries(const char *dir_path, const char *name) {
+    struct dirent *dp;
+    int count = 0;
+
+    DIR *dirp = opendir(dir_path);
+    if (dirp == NULL) {
+        return -1;
+    }
+    while ((dp = readdir(dirp)) != NULL) {
+        if (strcmp(dp->d_name, name) == 0) {
+            count++;
+        }
+    }
+    closedir(dirp);
+    return count;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+int test_read_base_layer() {
+    if (fs_check_file_content(BASE_FILE, "base") < 0) {
+        THROW_ERROR("failed to read the file of the base layer");
+    }
+    return 0;
+}
+
+int test_upper_layer_shadows_lower() {
+    if (fs_check_file_content(SHADOWED_FILE, "image") < 0) {
+        THROW_ERROR("the file of the image layer does not shadow the base layer");
+    }
+    return 0;
+}
+
+int test_readdir_merged_layers() {
+    const char *names[] = { "base_file", "shadowed_file", "copied_file" };
+    for (int i = 0; i < ARRAY_SIZE(names); i++) {
+        if (count_dir_entries(TEST_DIR, names[i]) != 1) {
+            THROW_ERROR("the entry of %s is not listed once", names[i]);
+        }
+    }
+    return 0;
+}
+
+int test_copy_up_from_base_layer() {
+    const char *msg = "upper";
+
+    int fd = open(COPIED_FILE, O_WRONLY | O_TRUNC);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the file of the base layer");
+    }
+    if (write(fd, msg, strlen(msg)) != strlen(msg)) {
+        close(fd);
+        THROW_ERROR("failed to write the file of the base layer");
+    }
+    close(fd);
+    if (fs_check_file_content(COPIED_FILE, msg) < 0) {
+        THROW_ERROR("the file is not copied up to the container layer");
+    }
+    // The other file of the base layer is intact
+    return test_read_base_layer();
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_read_base_layer),
+    TEST_CASE(test_upper_layer_shadows_lower),
+    TEST_CASE(test_readdir_merged_layers),
+    TEST_CASE(test_copy_up_from_base_layer),
+};
+
+int main() {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -2,7 +2,10 @@
 #include <sys/wait.h>
 #include <sys/socket.h>
 #include <sys/un.h>
+#include <sys/ioctl.h>
 #include <poll.h>
+#include <errno.h>
+#include <stddef.h>
 #include <unistd.h>
 #include <stdlib.h>
 #include <stdio.h>
@@ -245,14 +248,422 @@ int test_getname() {
     return 0;
 }
 
+// The abstract name may contain null bytes
+#define ABSTRACT_NAME "\0occlum\0abstract"
+
+int test_abstract_socket() {
+    struct sockaddr_un addr = {0};
+    addr.sun_family = AF_UNIX;
+    memcpy(addr.sun_path, ABSTRACT_NAME, sizeof(ABSTRACT_NAME) - 1);
+    socklen_t addr_len = offsetof(struct sockaddr_un, sun_path) + sizeof(ABSTRACT_NAME) - 1;
+
+    int listen_fd = socket(AF_UNIX, SOCK_STREAM, 0);
+    if (listen_fd < 0) {
+        THROW_ERROR("failed to create a unix socket");
+    }
+    if (bind(listen_fd, (struct sockaddr *)&addr, addr_len) < 0 || listen(listen_fd, 5) < 0) {
+        THROW_ERROR("failed to bind and listen on the abstract address");
+    }
+    // No file is created for an abstract address
+    if (access("occlum", F_OK) == 0) {
+        THROW_ERROR("a file is created for the abstract address");
+    }
+
+    struct sockaddr_un ret_addr = {0};
+    socklen_t ret_addr_len = sizeof(ret_addr);
+    if (getsockname(listen_fd, (struct sockaddr *)&ret_addr, &ret_addr_len) < 0 ||
+            ret_addr_len != addr_len ||
+            memcmp(ret_addr.sun_path, ABSTRACT_NAME, sizeof(ABSTRACT_NAME) - 1) != 0) {
+        THROW_ERROR("the abstract address is wrong");
+    }
+
+    // A prefix of the name is a different address
+    int client_fd = socket(AF_UNIX, SOCK_STREAM, 0);
+    if (connect(client_fd, (struct sockaddr *)&addr, addr_len - 1) == 0 ||
+            errno != ECONNREFUSED) {
+        THROW_ERROR("connecting to a prefix of the name does not fail");
+    }
+    if (connect(client_fd, (struct sockaddr *)&addr, addr_len) < 0) {
+        THROW_ERROR("failed to connect to the abstract address");
+    }
+    int accepted_fd = accept(listen_fd, NULL, NULL);
+    if (accepted_fd < 0) {
+        THROW_ERROR("failed to accept");
+    }
+    if (verify_connection(client_fd, accepted_fd) < 0) {
+        THROW_ERROR("failed to communicate");
+    }
+
+    close(accepted_fd);
+    close(client_fd);
+    close(listen_fd);
+
+    // The name can be bound again after the socket is closed
+    listen_fd = socket(AF_UNIX, SOCK_STREAM, 0);
+    if (bind(listen_fd, (struct sockaddr *)&addr, addr_len) < 0) {
+        THROW_ERROR("failed to bind the abstract address again");
+    }
+    close(listen_fd);
+    return 0;
+}
+
+int test_autobind() {
+    int sock = socket(AF_UNIX, SOCK_STREAM, 0);
+    if (sock < 0) {
+        THROW_ERROR("failed to create a unix socket");
+    }
+    struct sockaddr_un addr = { .sun_family = AF_UNIX };
+    if (bind(sock, (struct sockaddr *)&addr, sizeof(sa_family_t)) < 0) {
+        THROW_ERROR("failed to autobind");
+    }
+
+    // The name is five hex digits in the abstract namespace
+    struct sockaddr_un ret_addr = {0};
+    socklen_t ret_addr_len = sizeof(ret_addr);
+    if (getsockname(sock, (struct sockaddr *)&ret_addr, &ret_addr_len) < 0) {
+        THROW_ERROR("failed to getsockname");
+    }
+    if (ret_addr_len != offsetof(struct sockaddr_un, sun_path) + 6 ||
+            ret_addr.sun_path[0] != '\0') {
+        THROW_ERROR("the autobound address is wrong");
+    }
+
+    if (bind(sock, (struct sockaddr *)&addr, sizeof(sa_family_t)) == 0 || errno != EINVAL) {
+        THROW_ERROR("the socket is bound twice");
+    }
+    close(sock);
+    return 0;
+}
+
+#define FD_MSG "fd"
+
+int send_fd(int sock, int fd) {
+    char buf[CMSG_SPACE(sizeof(int))] = {0};
+    struct iovec iov = { .iov_base = FD_MSG, .iov_len = sizeof(FD_MSG) };
+    struct msghdr msg = {
+        .msg_iov = &iov,
+        .msg_iovlen = 1,
+        .msg_control = buf,
+        .msg_controllen = sizeof(buf),
+    };
+    struct cmsghdr *cmsg = CMSG_FIRSTHDR(&msg);
+    cmsg->cmsg_level = SOL_SOCKET;
+    cmsg->cmsg_type = SCM_RIGHTS;
+    cmsg->cmsg_len = CMSG_LEN(sizeof(int));
+    memcpy(CMSG_DATA(cmsg), &fd, sizeof(int));
+
+    if (sendmsg(sock, &msg, 0) != sizeof(FD_MSG)) {
+        THROW_ERROR("failed to send the fd");
+    }
+    return 0;
+}
+
+int recv_fd(int sock) {
+    char data[sizeof(FD_MSG)] = {0};
+    char buf[CMSG_SPACE(sizeof(int))] = {0};
+    struct iovec iov = { .iov_base = data, .iov_len = sizeof(data) };
+    struct msghdr msg = {
+        .msg_iov = &iov,
+        .msg_iovlen = 1,
+        .msg_control = buf,
+        .msg_controllen = sizeof(buf),
+    };
+    if (recvmsg(sock, &msg, 0) != sizeof(FD_MSG) || strcmp(data, FD_MSG) != 0) {
+        THROW_ERROR("failed to receive the data along with the fd");
+    }
+
+    struct cmsghdr *cmsg = CMSG_FIRSTHDR(&msg);
+    if (cmsg == NULL || cmsg->cmsg_level != SOL_SOCKET || cmsg->cmsg_type != SCM_RIGHTS ||
+            cmsg->cmsg_len != CMSG_LEN(sizeof(int)) || (msg.msg_flags & MSG_CTRUNC)) {
+        THROW_ERROR("no fd is received");
+    }
+    int fd;
+    memcpy(&fd, CMSG_DATA(cmsg), sizeof(int));
+    return fd;
+}
+
+// Check the received fd is the write end of the pipe
+int verify_pipe_fd(int read_fd, int received_fd) {
+    if (write(received_fd, ECHO_MSG, sizeof(ECHO_MSG)) != sizeof(ECHO_MSG)) {
+        THROW_ERROR("failed to write to the received fd");
+    }
+    char buf[sizeof(ECHO_MSG)] = {0};
+    if (read(read_fd, buf, sizeof(buf)) != sizeof(ECHO_MSG) ||
+            strcmp(buf, ECHO_MSG) != 0) {
+        THROW_ERROR("the received fd is not the pipe");
+    }
+    return 0;
+}
+
+int test_scm_rights() {
+    int socks[2], pipe_fds[2];
+    if (socketpair(AF_UNIX, SOCK_STREAM, 0, socks) < 0 || pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create the socketpair and the pipe");
+    }
+
+    if (send_fd(socks[0], pipe_fds[1]) < 0) {
+        THROW_ERROR("failed to send the fd");
+    }
+    // The file is kept alive by the message in transit
+    close(pipe_fds[1]);
+    int received_fd = recv_fd(socks[1]);
+    if (received_fd < 0) {
+        THROW_ERROR("failed to receive the fd");
+    }
+    if (verify_pipe_fd(pipe_fds[0], received_fd) < 0) {
+        THROW_ERROR("failed to verify the received fd");
+    }
+    close(received_fd);
+
+    // The fd is discarded if the control buffer is too small
+    if (send_fd(socks[0], pipe_fds[0]) < 0) {
+        THROW_ERROR("failed to send the fd");
+    }
+    char data[sizeof(FD_MSG)] = {0};
+    struct iovec iov = { .iov_base = data, .iov_len = sizeof(data) };
+    struct msghdr msg = { .msg_iov = &iov, .msg_iovlen = 1 };
+    if (recvmsg(socks[1], &msg, 0) != sizeof(FD_MSG) || !(msg.msg_flags & MSG_CTRUNC) ||
+            msg.msg_controllen != 0) {
+        THROW_ERROR("the control message is not truncated");
+    }
+
+    close(pipe_fds[0]);
+    close(socks[0]);
+    close(socks[1]);
+    return 0;
+}
+
+// The child receives the fd from the socket at stdin and writes to it
+int child_recv_fd() {
+    int received_fd = recv_fd(STDIN_FILENO);
+    if (received_fd < 0) {
+        THROW_ERROR("failed to receive the fd");
+    }
+    if (write(received_fd, ECHO_MSG, sizeof(ECHO_MSG)) != sizeof(ECHO_MSG)) {
+        THROW_ERROR("failed to write to the received fd");
+    }
+    return 0;
+}
+
+int test_scm_rights_inter_process() {
+    int socks[2], pipe_fds[2];
+    if (socketpair(AF_UNIX, SOCK_STREAM, 0, socks) < 0 || pipe(pipe_fds) < 0) {
+        THROW_ERROR("failed to create the socketpair and the pipe");
+    }
+
+    const char *child_prog = "/bin/unix_socket";
+    const char *child_argv[3] = { child_prog, "recv_fd", NULL };
+    int child_pid;
+    posix_spawn_file_actions_t file_actions;
+    posix_spawn_file_actions_init(&file_actions);
+    posix_spawn_file_actions_adddup2(&file_actions, socks[1], STDIN_FILENO);
+    posix_spawn_file_actions_addclose(&file_actions, socks[0]);
+    posix_spawn_file_actions_addclose(&file_actions, pipe_fds[0]);
+    posix_spawn_file_actions_addclose(&file_actions, pipe_fds[1]);
+    if (posix_spawn(&child_pid, child_prog, &file_actions,
+                    NULL, (char *const *)child_argv, NULL) < 0) {
+        THROW_ERROR("failed to spawn a child process");
+    }
+
+    if (send_fd(socks[0], pipe_fds[1]) < 0) {
+        THROW_ERROR("failed to send the fd");
+    }
+    close(pipe_fds[1]);
+    char buf[sizeof(ECHO_MSG)] = {0};
+    if (read(pipe_fds[0], buf, sizeof(buf)) != sizeof(ECHO_MSG) ||
+            strcmp(buf, ECHO_MSG) != 0) {
+        THROW_ERROR("the child fails to write to the received fd");
+    }
+
+    int status = 0;
+    if (wait4(child_pid, &status, 0, NULL) < 0 || !WIFEXITED(status) ||
+            WEXITSTATUS(status) != 0) {
+        THROW_ERROR("the child process failed");
+    }
+    close(pipe_fds[0]);
+    close(socks[0]);
+    close(socks[1]);
+    return 0;
+}
+
+#define DGRAM_MSG_1 "first datagram"
+#define DGRAM_MSG_2 "second"
+
+int bind_abstract(int sock, const char *name, struct sockaddr_un *addr, socklen_t *addr_len) {
+    memset(addr, 0, sizeof(*addr));
+    addr->sun_family = AF_UNIX;
+    strcpy(addr->sun_path + 1, name);
+    *addr_len = offsetof(struct sockaddr_un, sun_path) + 1 + strlen(name);
+    return bind(sock, (struct sockaddr *)addr, *addr_len);
+}
+
+int test_dgram_socket() {
+    struct sockaddr_un recv_addr, send_addr;
+    socklen_t recv_addr_len, send_addr_len;
+    int recv_sock = socket(AF_UNIX, SOCK_DGRAM, 0);
+    int send_sock = socket(AF_UNIX, SOCK_DGRAM, 0);
+    if (recv_sock < 0 || send_sock < 0) {
+        THROW_ERROR("failed to create datagram sockets");
+    }
+    if (bind_abstract(recv_sock, "dgram_recv", &recv_addr, &recv_addr_len) < 0 ||
+            bind_abstract(send_sock, "dgram_send", &send_addr, &send_addr_len) < 0) {
+        THROW_ERROR("failed to bind datagram sockets");
+    }
+    // A datagram socket cannot listen
+    if (listen(recv_sock, 5) == 0 || errno != EOPNOTSUPP) {
+        THROW_ERROR("listen on a datagram socket does not fail");
+    }
+
+    if (sendto(send_sock, DGRAM_MSG_1, sizeof(DGRAM_MSG_1), 0,
+               (struct sockaddr *)&recv_addr, recv_addr_len) != sizeof(DGRAM_MSG_1) ||
+            sendto(send_sock, DGRAM_MSG_2, sizeof(DGRAM_MSG_2), 0,
+                   (struct sockaddr *)&recv_addr, recv_addr_len) != sizeof(DGRAM_MSG_2)) {
+        THROW_ERROR("failed to send datagrams");
+    }
+
+    // The boundaries of the datagrams are kept, and the sender is known
+    char buf[64] = {0};
+    struct sockaddr_un src_addr = {0};
+    socklen_t src_addr_len = sizeof(src_addr);
+    if (recvfrom(recv_sock, buf, sizeof(buf), 0, (struct sockaddr *)&src_addr,
+                 &src_addr_len) != sizeof(DGRAM_MSG_1) || strcmp(buf, DGRAM_MSG_1) != 0) {
+        THROW_ERROR("failed to receive the first datagram");
+    }
+    if (src_addr_len != send_addr_len ||
+            memcmp(&src_addr, &send_addr, send_addr_len) != 0) {
+        THROW_ERROR("the address of the sender is wrong");
+    }
+
+    // The rest of a truncated datagram is discarded, and its real length is
+    // returned given MSG_TRUNC
+    memset(buf, 0, sizeof(buf));
+    if (recv(recv_sock, buf, 3, MSG_TRUNC) != sizeof(DGRAM_MSG_2) ||
+            strncmp(buf, DGRAM_MSG_2, 3) != 0) {
+        THROW_ERROR("failed to receive the truncated datagram");
+    }
+    if (recv(recv_sock, buf, sizeof(buf), MSG_DONTWAIT) >= 0 || errno != EAGAIN) {
+        THROW_ERROR("the rest of the truncated datagram is not discarded");
+    }
+
+    // Sending to an address that no socket is bound to fails
+    close(recv_sock);
+    if (sendto(send_sock, DGRAM_MSG_1, sizeof(DGRAM_MSG_1), 0,
+               (struct sockaddr *)&recv_addr, recv_addr_len) == 0 || errno != ECONNREFUSED) {
+        THROW_ERROR("sending to a closed socket does not fail");
+    }
+    close(send_sock);
+    return 0;
+}
+
+int test_dgram_socketpair() {
+    int socks[2];
+    if (socketpair(AF_UNIX, SOCK_DGRAM, 0, socks) < 0) {
+        THROW_ERROR("failed to create a datagram socketpair");
+    }
+    if (write(socks[0], DGRAM_MSG_1, sizeof(DGRAM_MSG_1)) != sizeof(DGRAM_MSG_1) ||
+            write(socks[0], DGRAM_MSG_2, sizeof(DGRAM_MSG_2)) != sizeof(DGRAM_MSG_2)) {
+        THROW_ERROR("failed to write datagrams");
+    }
+
+    int nbytes = 0;
+    if (ioctl(socks[1], FIONREAD, &nbytes) < 0 || nbytes != sizeof(DGRAM_MSG_1)) {
+        THROW_ERROR("FIONREAD does not return the length of the next datagram");
+    }
+    char buf[64] = {0};
+    if (read(socks[1], buf, sizeof(buf)) != sizeof(DGRAM_MSG_1) ||
+            strcmp(buf, DGRAM_MSG_1) != 0 ||
+            read(socks[1], buf, sizeof(buf)) != sizeof(DGRAM_MSG_2) ||
+            strcmp(buf, DGRAM_MSG_2) != 0) {
+        THROW_ERROR("failed to read the datagrams");
+    }
+
+    // The peer is gone after it is closed
+    close(socks[1]);
+    if (write(socks[0], DGRAM_MSG_1, sizeof(DGRAM_MSG_1)) >= 0 || errno != ECONNREFUSED) {
+        THROW_ERROR("writing to a closed peer does not fail");
+    }
+    close(socks[0]);
+    return 0;
+}
+
+int test_seqpacket_socket() {
+    struct sockaddr_un addr;
+    socklen_t addr_len;
+    int listen_fd = socket(AF_UNIX, SOCK_SEQPACKET, 0);
+    if (listen_fd < 0) {
+        THROW_ERROR("failed to create a seqpacket socket");
+    }
+    if (bind_abstract(listen_fd, "seqpacket", &addr, &addr_len) < 0 || listen(listen_fd, 5) < 0) {
+        THROW_ERROR("failed to bind and listen");
+    }
+
+    // A stream socket cannot connect to a seqpacket socket
+    int stream_fd = socket(AF_UNIX, SOCK_STREAM, 0);
+    if (connect(stream_fd, (struct sockaddr *)&addr, addr_len) == 0 || errno != EPROTOTYPE) {
+        THROW_ERROR("connecting to a socket of another type does not fail");
+    }
+    close(stream_fd);
+
+    int client_fd = socket(AF_UNIX, SOCK_SEQPACKET, 0);
+    if (connect(client_fd, (struct sockaddr *)&addr, addr_len) < 0) {
+        THROW_ERROR("failed to connect");
+    }
+    int accepted_fd = accept(listen_fd, NULL, NULL);
+    if (accepted_fd < 0) {
+        THROW_ERROR("failed to accept");
+    }
+
+    if (write(client_fd, DGRAM_MSG_1, sizeof(DGRAM_MSG_1)) != sizeof(DGRAM_MSG_1) ||
+            write(client_fd, DGRAM_MSG_2, sizeof(DGRAM_MSG_2)) != sizeof(DGRAM_MSG_2) ||
+            write(client_fd, DGRAM_MSG_1, sizeof(DGRAM_MSG_1)) != sizeof(DGRAM_MSG_1)) {
+        THROW_ERROR("failed to write messages");
+    }
+
+    // Each read returns one message at most
+    char buf[64] = {0};
+    if (read(accepted_fd, buf, sizeof(buf)) != sizeof(DGRAM_MSG_1) ||
+            strcmp(buf, DGRAM_MSG_1) != 0 ||
+            read(accepted_fd, buf, sizeof(buf)) != sizeof(DGRAM_MSG_2) ||
+            strcmp(buf, DGRAM_MSG_2) != 0) {
+        THROW_ERROR("the boundaries of the messages are not kept");
+    }
+
+    // A truncated message is indicated by MSG_TRUNC
+    memset(buf, 0, sizeof(buf));
+    struct iovec iov = { .iov_base = buf, .iov_len = 5 };
+    struct msghdr msg = { .msg_iov = &iov, .msg_iovlen = 1 };
+    if (recvmsg(accepted_fd, &msg, 0) != 5 || !(msg.msg_flags & MSG_TRUNC) ||
+            strncmp(buf, DGRAM_MSG_1, 5) != 0) {
+        THROW_ERROR("failed to receive the truncated message");
+    }
+
+    close(client_fd);
+    if (read(accepted_fd, buf, sizeof(buf)) != 0) {
+        THROW_ERROR("the end of the connection is not read");
+    }
+    close(accepted_fd);
+    close(listen_fd);
+    return 0;
+}
+
 static test_case_t test_cases[] = {
     TEST_CASE(test_unix_socket_inter_process),
     TEST_CASE(test_socketpair_inter_process),
     TEST_CASE(test_multiple_socketpairs),
     TEST_CASE(test_poll),
     TEST_CASE(test_getname),
+    TEST_CASE(test_scm_rights),
+    TEST_CASE(test_scm_rights_inter_process),
+    TEST_CASE(test_abstract_socket),
+    TEST_CASE(test_autobind),
+    TEST_CASE(test_dgram_socket),
+    TEST_CASE(test_dgram_socketpair),
+    TEST_CASE(test_seqpacket_socket),
 };
 
 int main(int argc, const char *argv[]) {
+    if (argc > 1 && strcmp(argv[1], "recv_fd") == 0) {
+        return child_recv_fd() < 0 ? EXIT_FAILURE : EXIT_SUCCESS;
+    }
     return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
 }
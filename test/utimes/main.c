@@ -0,0 +1,173 @@
+#define _GNU_SOURCE
+#include <sys/stat.h>
+#include <sys/time.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <time.h>
+#include <unistd.h>
+#include <utime.h>
+#include "test_fs.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define FILE_PATH   "/root/test_utimes.txt"
+
+static int create_file(const char *file_path) {
+    int fd = open(file_path, O_RDWR | O_CREAT | O_TRUNC, 0666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a file");
+    }
+    close(fd);
+    return 0;
+}
+
+static int timespec_equal(const struct timespec *a, const struct timespec *b) {
+    return a->tv_sec == b->tv_sec && a->tv_nsec == b->tv_nsec;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_utimensat() {
+    struct timespec times[2] = {
+        { .tv_sec = 1000, .tv_nsec = 123456789 },
+        { .tv_sec = 2000, .tv_nsec = 987654321 },
+    };
+    if (create_file(FILE_PATH) < 0) {
+        return -1;
+    }
+    if (utimensat(AT_FDCWD, FILE_PATH, times, 0) < 0) {
+        THROW_ERROR("failed to set the timestamps");
+    }
+    struct stat stat_buf;
+    if (stat(FILE_PATH, &stat_buf) < 0) {
+        THROW_ERROR("failed to stat the file");
+    }
+    // The timestamps are in nanosecond precision
+    if (!timespec_equal(&stat_buf.st_atim, &times[0]) ||
+            !timespec_equal(&stat_buf.st_mtim, &times[1])) {
+        THROW_ERROR("the timestamps are not as expected");
+    }
+    return 0;
+}
+
+static int test_utimensat_with_utime_omit() {
+    struct timespec times[2] = {
+        { .tv_sec = 3000, .tv_nsec = 0 },
+        { .tv_sec = 0, .tv_nsec = UTIME_OMIT },
+    };
+    struct stat old_stat_buf, stat_buf;
+    if (stat(FILE_PATH, &old_stat_buf) < 0) {
+        THROW_ERROR("failed to stat the file");
+    }
+    if (utimensat(AT_FDCWD, FILE_PATH, times, 0) < 0) {
+        THROW_ERROR("failed to set the timestamps");
+    }
+    if (stat(FILE_PATH, &stat_buf) < 0) {
+        THROW_ERROR("failed to stat the file");
+    }
+    if (!timespec_equal(&stat_buf.st_atim, &times[0]) ||
+            !timespec_equal(&stat_buf.st_mtim, &old_stat_buf.st_mtim)) {
+        THROW_ERROR("the modification time should be left unchanged");
+    }
+    return 0;
+}
+
+static int test_futimens_with_utime_now() {
+    struct timespec times[2] = {
+        { .tv_sec = 0, .tv_nsec = UTIME_NOW },
+        { .tv_sec = 0, .tv_nsec = UTIME_NOW },
+    };
+    int fd = open(FILE_PATH, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the file");
+    }
+    time_t before = time(NULL);
+    if (futimens(fd, times) < 0) {
+        THROW_ERROR("failed to set the timestamps");
+    }
+    struct stat stat_buf;
+    if (fstat(fd, &stat_buf) < 0) {
+        THROW_ERROR("failed to stat the file");
+    }
+    close(fd);
+    if (stat_buf.st_atim.tv_sec < before || stat_buf.st_mtim.tv_sec < before ||
+            stat_buf.st_ctim.tv_sec < before) {
+        THROW_ERROR("the timestamps should be set to the current time");
+    }
+    return 0;
+}
+
+static int test_utimes() {
+    struct timeval times[2] = {
+        { .tv_sec = 4000, .tv_usec = 1 },
+        { .tv_sec = 5000, .tv_usec = 2 },
+    };
+    if (utimes(FILE_PATH, times) < 0) {
+        THROW_ERROR("failed to set the timestamps");
+    }
+    struct stat stat_buf;
+    if (stat(FILE_PATH, &stat_buf) < 0) {
+        THROW_ERROR("failed to stat the file");
+    }
+    if (stat_buf.st_atim.tv_sec != 4000 || stat_buf.st_atim.tv_nsec != 1000 ||
+            stat_buf.st_mtim.tv_sec != 5000 || stat_buf.st_mtim.tv_nsec != 2000) {
+        THROW_ERROR("the timestamps are not as expected");
+    }
+
+    struct utimbuf buf = { .actime = 6000, .modtime = 7000 };
+    if (utime(FILE_PATH, &buf) < 0) {
+        THROW_ERROR("failed to set the timestamps");
+    }
+    if (stat(FILE_PATH, &stat_buf) < 0) {
+        THROW_ERROR("failed to stat the file");
+    }
+    if (stat_buf.st_atime != 6000 || stat_buf.st_mtime != 7000) {
+        THROW_ERROR("the timestamps are not as expected");
+    }
+    return 0;
+}
+
+static int test_utimensat_with_invalid_args() {
+    struct timespec times[2] = {
+        { .tv_sec = 0, .tv_nsec = 1000000000 },
+        { .tv_sec = 0, .tv_nsec = 0 },
+    };
+    if (utimensat(AT_FDCWD, FILE_PATH, times, 0) == 0 || errno != EINVAL) {
+        THROW_ERROR("setting an invalid timestamp should fail");
+    }
+    if (utimensat(AT_FDCWD, FILE_PATH, NULL, 0x1) == 0 || errno != EINVAL) {
+        THROW_ERROR("setting the timestamps with invalid flags should fail");
+    }
+    if (utimensat(AT_FDCWD, "/root/non_existent_file", NULL, 0) == 0 || errno != ENOENT) {
+        THROW_ERROR("setting the timestamps of a non-existent file should fail");
+    }
+    return 0;
+}
+
+static int test_cleanup() {
+    if (unlink(FILE_PATH) < 0) {
+        THROW_ERROR("failed to unlink the file");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_utimensat),
+    TEST_CASE(test_utimensat_with_utime_omit),
+    TEST_CASE(test_futimens_with_utime_now),
+    TEST_CASE(test_utimes),
+    TEST_CASE(test_utimensat_with_invalid_args),
+    TEST_CASE(test_cleanup),
+};
+
+int main(int argc, const char *argv[]) {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
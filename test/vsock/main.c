@@ -0,0 +1,82 @@
+#include <sys/socket.h>
+#include <sys/ioctl.h>
+#include <linux/vm_sockets.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <unistd.h>
+#include <stdio.h>
+
+#include "test.h"
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+// The vsock of the host may be unavailable, in which case the tests are skipped
+
+int test_get_local_cid() {
+    int fd = open("/dev/vsock", O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open /dev/vsock");
+    }
+
+    unsigned int cid = VMADDR_CID_ANY;
+    int ret = ioctl(fd, IOCTL_VM_SOCKETS_GET_LOCAL_CID, &cid);
+    close(fd);
+    if (ret < 0) {
+        printf("vsock is unavailable on the host, skipped\n");
+        return 0;
+    }
+    if (cid == VMADDR_CID_ANY) {
+        THROW_ERROR("the local CID is wrong");
+    }
+    return 0;
+}
+
+int test_bind_with_short_addr() {
+    int sock = socket(AF_VSOCK, SOCK_STREAM, 0);
+    if (sock < 0) {
+        if (errno == EAFNOSUPPORT) {
+            printf("vsock is unavailable on the host, skipped\n");
+            return 0;
+        }
+        THROW_ERROR("failed to create a vsock socket");
+    }
+
+    struct sockaddr_vm addr = {
+        .svm_family = AF_VSOCK,
+        .svm_cid = VMADDR_CID_ANY,
+        .svm_port = VMADDR_PORT_ANY,
+    };
+    int ret = bind(sock, (struct sockaddr *)&addr, sizeof(addr) - 4);
+    if (ret == 0 || errno != EINVAL) {
+        close(sock);
+        THROW_ERROR("binding to a short address should fail");
+    }
+
+    if (bind(sock, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+        close(sock);
+        THROW_ERROR("failed to bind the socket");
+    }
+    socklen_t addr_len = sizeof(addr);
+    if (getsockname(sock, (struct sockaddr *)&addr, &addr_len) < 0 ||
+            addr.svm_family != AF_VSOCK || addr.svm_port == VMADDR_PORT_ANY) {
+        close(sock);
+        THROW_ERROR("failed to get the bound address");
+    }
+
+    close(sock);
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+static test_case_t test_cases[] = {
+    TEST_CASE(test_get_local_cid),
+    TEST_CASE(test_bind_with_short_addr),
+};
+
+int main() {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
@@ -0,0 +1,16 @@
+#!/bin/bash
+# Usage: crash_after_writeback.sh <occlum> exec /bin/writeback
+#
+# Write a file without syncing it, and kill the Occlum server after the dirty
+# data should have been written back by the background thread, i.e., after
+# the writeback interval (5 seconds by default) elapses twice, and then check
+# the file after the server is restarted.
+OCCLUM=$1
+
+"$@" write &
+sleep 12
+pkill -9 -f occlum_exec_server
+wait
+
+"$OCCLUM" start || exit 1
+"$@"
@@ -0,0 +1,103 @@
+#include <sys/stat.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include "test.h"
+
+// The file is written without being synced, and the writer is killed along
+// with the Occlum server after the background writeback, and then the file is
+// checked after the server is restarted. See crash_after_writeback.sh.
+
+#define FILE_PATH       "/root/test_writeback_file"
+// Less than half of the page cache in test/Occlum.json, so the dirty pages
+// are written back periodically rather than on memory pressure
+#define FILE_LEN        (1024 * 1024)
+#define CHUNK_LEN       4096
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+static void fill_chunk(char *buf, int chunk_idx) {
+    for (int i = 0; i < CHUNK_LEN; i++) {
+        buf[i] = (char)(chunk_idx * 7 + i);
+    }
+}
+
+// Write the file and wait to be killed. The process must not exit, which
+// syncs the file systems.
+static int write_file() {
+    char buf[CHUNK_LEN];
+
+    int fd = open(FILE_PATH, O_WRONLY | O_CREAT | O_TRUNC, 0666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create the file");
+    }
+    for (int idx = 0; idx < FILE_LEN / CHUNK_LEN; idx++) {
+        fill_chunk(buf, idx);
+        if (write(fd, buf, CHUNK_LEN) != CHUNK_LEN) {
+            close(fd);
+            THROW_ERROR("failed to write the file");
+        }
+    }
+    printf("The file is written without being synced\n");
+    fflush(stdout);
+    while (1) {
+        sleep(60);
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_written_back_file() {
+    char expected[CHUNK_LEN];
+    char buf[CHUNK_LEN];
+    struct stat stat_buf;
+
+    if (stat(FILE_PATH, &stat_buf) < 0) {
+        THROW_ERROR("the file is not written back");
+    }
+    if (stat_buf.st_size != FILE_LEN) {
+        THROW_ERROR("the length of the file is not written back");
+    }
+    int fd = open(FILE_PATH, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the file");
+    }
+    for (int idx = 0; idx < FILE_LEN / CHUNK_LEN; idx++) {
+        fill_chunk(expected, idx);
+        if (read(fd, buf, CHUNK_LEN) != CHUNK_LEN || memcmp(buf, expected, CHUNK_LEN) != 0) {
+            close(fd);
+            THROW_ERROR("the data of the file is not written back");
+        }
+    }
+    close(fd);
+    return 0;
+}
+
+static int test_cleanup() {
+    if (unlink(FILE_PATH) < 0) {
+        THROW_ERROR("failed to unlink the file");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_written_back_file),
+    TEST_CASE(test_cleanup),
+};
+
+int main(int argc, const char *argv[]) {
+    if (argc > 1 && strcmp(argv[1], "write") == 0) {
+        return write_file();
+    }
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
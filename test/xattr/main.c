@@ -0,0 +1,238 @@
+#include <sys/stat.h>
+#include <sys/xattr.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <string.h>
+#include <unistd.h>
+#include "test_fs.h"
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+#define FILE_PATH   "/root/test_xattr_file.txt"
+
+static int create_file(const char *file_path) {
+    int fd = open(file_path, O_RDWR | O_CREAT | O_TRUNC, 0666);
+    if (fd < 0) {
+        THROW_ERROR("failed to create a file");
+    }
+    close(fd);
+    return 0;
+}
+
+static int check_xattr(const char *name, const char *expected_value) {
+    char value[64] = {0};
+    ssize_t expected_len = strlen(expected_value);
+    if (getxattr(FILE_PATH, name, NULL, 0) != expected_len) {
+        THROW_ERROR("failed to get the size of the value");
+    }
+    if (getxattr(FILE_PATH, name, value, sizeof(value)) != expected_len ||
+            memcmp(value, expected_value, expected_len) != 0) {
+        THROW_ERROR("the value is not as expected");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test cases
+// ============================================================================
+
+static int test_set_and_get() {
+    const char *name = "user.test";
+    if (create_file(FILE_PATH) < 0) {
+        return -1;
+    }
+    if (getxattr(FILE_PATH, name, NULL, 0) >= 0 || errno != ENODATA) {
+        THROW_ERROR("getting a non-existent attribute should fail");
+    }
+
+    if (setxattr(FILE_PATH, name, "hello", 5, XATTR_CREATE) < 0) {
+        THROW_ERROR("failed to create the attribute");
+    }
+    if (check_xattr(name, "hello") < 0) {
+        return -1;
+    }
+    if (setxattr(FILE_PATH, name, "world", 5, XATTR_CREATE) == 0 || errno != EEXIST) {
+        THROW_ERROR("creating an existing attribute should fail");
+    }
+    if (setxattr(FILE_PATH, "user.none", "world", 5, XATTR_REPLACE) == 0 ||
+            errno != ENODATA) {
+        THROW_ERROR("replacing a non-existent attribute should fail");
+    }
+    if (setxattr(FILE_PATH, name, "hello world", 11, XATTR_REPLACE) < 0) {
+        THROW_ERROR("failed to replace the attribute");
+    }
+    if (check_xattr(name, "hello world") < 0) {
+        return -1;
+    }
+
+    char small_buf[4];
+    if (getxattr(FILE_PATH, name, small_buf, sizeof(small_buf)) >= 0 || errno != ERANGE) {
+        THROW_ERROR("getting the value with a small buffer should fail");
+    }
+    return 0;
+}
+
+static int test_fd_variants() {
+    int fd = open(FILE_PATH, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the file");
+    }
+    if (fsetxattr(fd, "trusted.test", "", 0, 0) < 0) {
+        THROW_ERROR("failed to set an empty attribute through the fd");
+    }
+    if (fgetxattr(fd, "trusted.test", NULL, 0) != 0) {
+        THROW_ERROR("the value should be empty");
+    }
+    if (fremovexattr(fd, "trusted.test") < 0) {
+        THROW_ERROR("failed to remove the attribute through the fd");
+    }
+    close(fd);
+    return 0;
+}
+
+static int test_list() {
+    if (setxattr(FILE_PATH, "user.another", "1", 1, 0) < 0) {
+        THROW_ERROR("failed to set the attribute");
+    }
+    const char expected_list[] = "user.test\0user.another";
+    char list[64] = {0};
+    if (listxattr(FILE_PATH, NULL, 0) != sizeof(expected_list)) {
+        THROW_ERROR("failed to get the size of the list");
+    }
+    if (listxattr(FILE_PATH, list, sizeof(list)) != sizeof(expected_list) ||
+            memcmp(list, expected_list, sizeof(expected_list)) != 0) {
+        THROW_ERROR("the list is not as expected");
+    }
+    if (listxattr(FILE_PATH, list, 4) >= 0 || errno != ERANGE) {
+        THROW_ERROR("getting the list with a small buffer should fail");
+    }
+    return 0;
+}
+
+static int test_remove() {
+    if (removexattr(FILE_PATH, "user.another") < 0) {
+        THROW_ERROR("failed to remove the attribute");
+    }
+    if (removexattr(FILE_PATH, "user.another") == 0 || errno != ENODATA) {
+        THROW_ERROR("removing a non-existent attribute should fail");
+    }
+    if (listxattr(FILE_PATH, NULL, 0) != strlen("user.test") + 1) {
+        THROW_ERROR("the attribute should be removed from the list");
+    }
+    return 0;
+}
+
+static int test_invalid_names() {
+    if (setxattr(FILE_PATH, "unknown.test", "1", 1, 0) == 0 || errno != EOPNOTSUPP) {
+        THROW_ERROR("an unknown namespace should not be supported");
+    }
+    if (setxattr(FILE_PATH, "", "1", 1, 0) == 0 || errno != ERANGE) {
+        THROW_ERROR("an empty name should be invalid");
+    }
+    if (setxattr(FILE_PATH, "user.test", "1", 1, XATTR_CREATE | 0x4) == 0 ||
+            errno != EINVAL) {
+        THROW_ERROR("invalid flags should fail");
+    }
+    return 0;
+}
+
+static int test_permissions() {
+    const char *name = "user.test";
+    if (setxattr(FILE_PATH, name, "1", 1, 0) < 0) {
+        THROW_ERROR("failed to set the attribute");
+    }
+
+    // User attributes are accessed with the permissions of the file
+    if (chmod(FILE_PATH, 0444) < 0) {
+        THROW_ERROR("failed to chmod");
+    }
+    if (setxattr(FILE_PATH, name, "2", 1, 0) == 0 || errno != EACCES) {
+        THROW_ERROR("setting an attribute of a read-only file should fail");
+    }
+    if (removexattr(FILE_PATH, name) == 0 || errno != EACCES) {
+        THROW_ERROR("removing an attribute of a read-only file should fail");
+    }
+    if (check_xattr(name, "1") < 0) {
+        return -1;
+    }
+
+    if (chmod(FILE_PATH, 0222) < 0) {
+        THROW_ERROR("failed to chmod");
+    }
+    if (getxattr(FILE_PATH, name, NULL, 0) >= 0 || errno != EACCES) {
+        THROW_ERROR("getting an attribute of a write-only file should fail");
+    }
+
+    if (chmod(FILE_PATH, 0666) < 0) {
+        THROW_ERROR("failed to chmod");
+    }
+    if (removexattr(FILE_PATH, name) < 0) {
+        THROW_ERROR("failed to remove the attribute");
+    }
+    return 0;
+}
+
+static int test_kept_until_released() {
+    char value[8] = {0};
+    if (setxattr(FILE_PATH, "user.test", "open", 4, 0) < 0) {
+        THROW_ERROR("failed to set the attribute");
+    }
+    int fd = open(FILE_PATH, O_RDONLY);
+    if (fd < 0) {
+        THROW_ERROR("failed to open the file");
+    }
+    if (unlink(FILE_PATH) < 0) {
+        close(fd);
+        THROW_ERROR("failed to unlink the file");
+    }
+
+    // The attributes of an unlinked file are kept until it is closed
+    if (fgetxattr(fd, "user.test", value, sizeof(value)) != 4 ||
+            memcmp(value, "open", 4) != 0) {
+        close(fd);
+        THROW_ERROR("the attribute of an open file is dropped by unlink");
+    }
+    close(fd);
+
+    if (create_file(FILE_PATH) < 0) {
+        return -1;
+    }
+    return 0;
+}
+
+static int test_removed_with_file() {
+    if (unlink(FILE_PATH) < 0) {
+        THROW_ERROR("failed to unlink the file");
+    }
+    if (create_file(FILE_PATH) < 0) {
+        return -1;
+    }
+    if (listxattr(FILE_PATH, NULL, 0) != 0) {
+        THROW_ERROR("a new file should have no attributes");
+    }
+    if (unlink(FILE_PATH) < 0) {
+        THROW_ERROR("failed to unlink the file");
+    }
+    return 0;
+}
+
+// ============================================================================
+// Test suite main
+// ============================================================================
+
+static test_case_t test_cases[] = {
+    TEST_CASE(test_set_and_get),
+    TEST_CASE(test_fd_variants),
+    TEST_CASE(test_list),
+    TEST_CASE(test_remove),
+    TEST_CASE(test_invalid_names),
+    TEST_CASE(test_permissions),
+    TEST_CASE(test_kept_until_released),
+    TEST_CASE(test_removed_with_file),
+};
+
+int main(int argc, const char *argv[]) {
+    return test_suite_run(test_cases, ARRAY_SIZE(test_cases));
+}
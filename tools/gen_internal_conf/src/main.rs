@@ -14,6 +14,11 @@ use std::fs::File;
 use std::io::Write;
 use std::path::Path;
 
+// The size of the reserved memory committed when the enclave is loaded on a
+// platform with EDMM, on which the reserved memory is not used by the user space
+// and is committed on demand up to the max size
+const RESERVED_MEM_INIT_SIZE: u64 = 0x1000;
+
 fn main() {
     env_logger::init();
 
@@ -68,6 +73,18 @@ fn main() {
                             Err(e) => Err(e.to_string()),
                         })
                         .takes_value(true),
+                )
+                // Output: config file used by PAL before creating the enclave
+                .arg(
+                    Arg::with_name("pal_conf")
+                        .long("pal_conf")
+                        .value_name("output pal config")
+                        .required(true)
+                        .validator(|f| match File::create(f) {
+                            Ok(_e) => Ok(()),
+                            Err(e) => Err(e.to_string()),
+                        })
+                        .takes_value(true),
                 ),
         )
         .subcommand(
@@ -125,6 +142,9 @@ fn main() {
             enclave_config_file_path
         );
 
+        let pal_config_file_path = sub_matches.value_of("pal_conf").unwrap();
+        debug!("PAL config file name {:?}", pal_config_file_path);
+
         // get the kernel stack size
         let stack_max_size =
             parse_memory_size(&occlum_config.resource_limits.kernel_space_stack_size);
@@ -171,14 +191,30 @@ fn main() {
             },
             MiscSelect: "0".to_string(),
             MiscMask: "0xFFFFFFFF".to_string(),
+            // Without EDMM, the user space is allocated out of the reserved
+            // memory, whose max size is committed when the enclave is loaded
             ReservedMemMaxSize: user_space_size.unwrap() as u64,
-            ReservedMemMinSize: user_space_size.unwrap() as u64,
-            ReservedMemInitSize: user_space_size.unwrap() as u64,
+            ReservedMemMinSize: RESERVED_MEM_INIT_SIZE,
+            ReservedMemInitSize: RESERVED_MEM_INIT_SIZE,
             ReservedMemExecutable: 1,
+            // With EDMM, the user space is allocated out of the user region,
+            // whose pages are committed on demand
+            UserRegionSize: user_space_size.unwrap() as u64,
         };
         let enclave_config = serde_xml_rs::to_string(&sgx_enclave_configuration).unwrap();
         debug!("The enclave config:{:?}", enclave_config);
 
+        // Generate the PAL configuration, which takes effect before the enclave
+        // is created, e.g., the untrusted workers of the switchless OCalls
+        let pal_config = format!(
+            "switchless_threads={}\n",
+            occlum_config
+                .resource_limits
+                .switchless_threads
+                .unwrap_or(0)
+        );
+        debug!("The PAL config:{:?}", pal_config);
+
         // Generate user Occlum.json - "output_user_json"
         let user_mount_config = {
             let user_mount_config =
@@ -192,15 +228,24 @@ fn main() {
         let user_occlum_json_config = InternalOcclumJson {
             resource_limits: InternalResourceLimits {
                 user_space_size: occlum_config.resource_limits.user_space_size.to_string(),
+                cpu_set: occlum_config.resource_limits.cpu_set.clone(),
+                pin_cpu_affinity: occlum_config.resource_limits.pin_cpu_affinity,
+                io_threads: occlum_config.resource_limits.io_threads,
+                page_cache_size: occlum_config.resource_limits.page_cache_size.clone(),
             },
             process: OcclumProcess {
                 default_stack_size: occlum_config.process.default_stack_size,
                 default_heap_size: occlum_config.process.default_heap_size,
                 default_mmap_size: occlum_config.process.default_mmap_size,
+                default_max_open_files: occlum_config.process.default_max_open_files,
+                default_max_processes: occlum_config.process.default_max_processes,
+                enable_aslr: occlum_config.process.enable_aslr,
             },
             entry_points: occlum_config.entry_points,
             env: occlum_config.env,
             mount: serde_json::to_value(user_mount_config).unwrap(),
+            network: occlum_config.network,
+            fs: occlum_config.fs,
         };
         let user_occlum_json_str = serde_json::to_string_pretty(&user_occlum_json_config).unwrap();
         debug!("The user Occlum.json config:\n{:?}", user_occlum_json_str);
@@ -212,6 +257,12 @@ fn main() {
             .write_all(enclave_config.as_bytes())
             .expect("Failed to update the Enclave configuration file.");
 
+        let mut pal_config_file = File::create(pal_config_file_path)
+            .expect("Could not open the target PAL configuration file.");
+        pal_config_file
+            .write_all(pal_config.as_bytes())
+            .expect("Failed to update the PAL configuration file.");
+
         let mut user_occlum_json = File::create(occlum_user_json_file_path)
             .expect("Could not open the output user Occlum.json file.");
         user_occlum_json
@@ -231,15 +282,24 @@ fn main() {
         let sys_occlum_json_config = InternalOcclumJson {
             resource_limits: InternalResourceLimits {
                 user_space_size: occlum_config.resource_limits.user_space_size.to_string(),
+                cpu_set: occlum_config.resource_limits.cpu_set.clone(),
+                pin_cpu_affinity: occlum_config.resource_limits.pin_cpu_affinity,
+                io_threads: occlum_config.resource_limits.io_threads,
+                page_cache_size: occlum_config.resource_limits.page_cache_size.clone(),
             },
             process: OcclumProcess {
                 default_stack_size: occlum_config.process.default_stack_size,
                 default_heap_size: occlum_config.process.default_heap_size,
                 default_mmap_size: occlum_config.process.default_mmap_size,
+                default_max_open_files: occlum_config.process.default_max_open_files,
+                default_max_processes: occlum_config.process.default_max_processes,
+                enable_aslr: occlum_config.process.enable_aslr,
             },
             entry_points: json!(["/bin"]),
             env: occlum_config.env,
             mount: gen_sys_mount_config(occlum_conf_init_fs_mac.to_string()),
+            network: occlum_config.network,
+            fs: occlum_config.fs,
         };
 
         // Update the output file
@@ -298,18 +358,47 @@ fn gen_user_mount_config(
     if root_mc.options.layers.is_none() {
         return Err("the root UnionFS must be given layers");
     }
-    let mut root_image_sefs_mc = root_mc
-        .options
-        .layers
-        .as_mut()
-        .unwrap()
+    let layers = root_mc.options.layers.as_mut().unwrap();
+    if layers
+        .iter()
+        .any(|m| m.target != String::from("/") || m.type_ != String::from("sefs"))
+    {
+        return Err("the layers of the root UnionFS must be SEFS");
+    }
+    let nr_container_layers = layers
+        .iter()
+        .filter(|m| m.options.role.as_deref() == Some("container"))
+        .count();
+    let nr_image_layers = layers
+        .iter()
+        .filter(|m| m.options.role.as_deref() == Some("image"))
+        .count();
+    if nr_container_layers + nr_image_layers != layers.len() {
+        return Err("the roles of the layers must be \"image\" or \"container\"");
+    }
+    if nr_container_layers != 1 {
+        return Err("the root UnionFS must have only one container layer");
+    }
+    if nr_image_layers == 0 {
+        return Err("the root UnionFS must have at least one image layer");
+    }
+    if layers
+        .iter()
+        .any(|m| (m.options.role.as_deref() == Some("image")) != m.options.mac.is_some())
+    {
+        return Err("the image layers must be given the MACs, while the container layer must not");
+    }
+    // The image SEFS built from the image dir is given an empty MAC, while the
+    // other image SEFS layers, e.g., the shared base images, are given theirs
+    let mut root_image_sefs_mcs = layers
         .iter_mut()
-        .find(|m| {
-            m.target == String::from("/")
-                && m.type_ == String::from("sefs")
-                && m.options.mac.is_some()
-        })
-        .ok_or("the image SEFS in layers is not valid")?;
+        .filter(|m| m.options.mac.as_ref().map_or(false, |mac| mac.is_empty()));
+    let root_image_sefs_mc = root_image_sefs_mcs
+        .next()
+        .ok_or("the image layer built from the image dir is not given")?;
+    if root_image_sefs_mcs.next().is_some() {
+        return Err("only one image layer can be given the empty MAC");
+    }
     root_image_sefs_mc.options.mac = Some(occlum_conf_user_fs_mac);
 
     debug!("user Occlum.json mount config:\n{:?}", user_mount_config);
@@ -329,13 +418,17 @@ fn gen_sys_mount_config(occlum_conf_init_fs_mac: String) -> serde_json::Value {
                             "type": "sefs",
                             "source": "./build/initfs/__ROOT",
                             "options": {
+                                "role": "image",
                                 "MAC": ""
                             }
                         },
                         {
                             "target": "/",
                             "type": "sefs",
-                            "source": "./run/initfs/__ROOT"
+                            "source": "./run/initfs/__ROOT",
+                            "options": {
+                                "role": "container"
+                            }
                         }
                     ]
                 }
@@ -344,6 +437,10 @@ fn gen_sys_mount_config(occlum_conf_init_fs_mac: String) -> serde_json::Value {
                 "target": "/proc",
                 "type": "procfs"
             },
+            {
+                "target": "/sys",
+                "type": "sysfs"
+            },
             {
                 "target": "/dev",
                 "type": "devfs"
@@ -368,6 +465,10 @@ struct OcclumConfiguration {
     env: serde_json::Value,
     metadata: OcclumMetadata,
     mount: Vec<OcclumMount>,
+    #[serde(default)]
+    network: Option<serde_json::Value>,
+    #[serde(default)]
+    fs: Option<serde_json::Value>,
 }
 
 #[derive(Debug, PartialEq, Deserialize)]
@@ -376,6 +477,16 @@ struct OcclumResourceLimits {
     kernel_space_heap_size: String,
     kernel_space_stack_size: String,
     user_space_size: String,
+    #[serde(default)]
+    cpu_set: Option<String>,
+    #[serde(default)]
+    pin_cpu_affinity: Option<bool>,
+    #[serde(default)]
+    io_threads: Option<u32>,
+    #[serde(default)]
+    page_cache_size: Option<String>,
+    #[serde(default)]
+    switchless_threads: Option<u32>,
 }
 
 #[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
@@ -383,6 +494,12 @@ struct OcclumProcess {
     default_stack_size: String,
     default_heap_size: String,
     default_mmap_size: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    default_max_open_files: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    default_max_processes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    enable_aslr: Option<bool>,
 }
 
 #[derive(Debug, PartialEq, Deserialize)]
@@ -412,6 +529,10 @@ struct OcclumMountOptions {
     pub layers: Option<Vec<OcclumMount>>,
     #[serde(default, skip_serializing_if = "is_false")]
     pub temporary: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub integrity_only: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
 }
 
 #[inline]
@@ -443,11 +564,20 @@ struct EnclaveConfiguration {
     ReservedMemMinSize: u64,
     ReservedMemInitSize: u64,
     ReservedMemExecutable: u32,
+    UserRegionSize: u64,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize)]
 struct InternalResourceLimits {
     user_space_size: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpu_set: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pin_cpu_affinity: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    io_threads: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page_cache_size: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize)]
@@ -457,4 +587,8 @@ struct InternalOcclumJson {
     entry_points: serde_json::Value,
     env: serde_json::Value,
     mount: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    network: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fs: Option<serde_json::Value>,
 }
@@ -120,9 +120,11 @@ $(instance_dir)/build/Occlum.json.protected: $(instance_dir)/build/Occlum.json
 		LD_LIBRARY_PATH="$(SGX_SDK)/sdk_libs" "$(occlum_dir)/build/bin/occlum-protect-integrity" protect Occlum.json ;
 
 $(instance_dir)/build/Enclave.xml:
+$(instance_dir)/build/pal.conf:
 $(instance_dir)/build/Occlum.json: $(SECURE_IMAGE) $(SECURE_IMAGE_MAC) $(JSON_CONF) | $(instance_dir)/build/lib
 	@$(occlum_dir)/build/bin/gen_internal_conf --user_json "$(JSON_CONF)" gen_user_conf \
 		--user_fs_mac "`cat $(SECURE_IMAGE_MAC)`" --sdk_xml "$(instance_dir)/build/Enclave.xml"  \
+		--pal_conf "$(instance_dir)/build/pal.conf" \
 		--output_user_json $(instance_dir)/build/Occlum.json
 
 # If image dir not exist, just use the secure Occlum FS image